@@ -0,0 +1,38 @@
+#ifndef NEWSBOAT_ADDFEED_H_
+#define NEWSBOAT_ADDFEED_H_
+
+#include <string>
+
+namespace newsboat {
+
+class ConfigContainer;
+class FileUrlReader;
+
+/// \brief Subscribing to a feed from outside the UI: from `--add-feed`, or
+/// from another `newsboat` through the control socket.
+namespace addfeed {
+
+enum class Outcome { ADDED, ALREADY_SUBSCRIBED, FAILED };
+
+struct Result {
+	Outcome outcome;
+	/// The URL that was written to the urls file (or that was found in it
+	/// already). Empty if the input wasn't a URL.
+	std::string feed_url;
+	/// Message for the user.
+	std::string message;
+};
+
+/// Adds the feed at `url` to the end of the urls file that `urls` reads.
+///
+/// `url` may also use the `feed:` scheme. The feed is fetched first, to
+/// make sure it is one; if `url` points to a web page instead, the first
+/// feed that the page links to is added. Nothing is written if the feed is
+/// in the file already.
+Result add(const std::string& url, FileUrlReader& urls, ConfigContainer* cfg);
+
+} // namespace addfeed
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_ADDFEED_H_ */
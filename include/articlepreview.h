@@ -0,0 +1,67 @@
+#ifndef NEWSBOAT_ARTICLEPREVIEW_H_
+#define NEWSBOAT_ARTICLEPREVIEW_H_
+
+#include <cstddef>
+#include <functional>
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// \brief Caches and debounces the preview shown next to the article list.
+///
+/// The view asks for a preview with request() whenever the selection
+/// changes, and renders the item returned by poll() once the selection
+/// settles.
+class ArticlePreview {
+public:
+	/// \brief Creates a preview that waits for \a debounce_ms before
+	/// rendering, and caches at most \a max_entries previews taking
+	/// \a max_bytes in total.
+	ArticlePreview(unsigned int debounce_ms,
+		std::size_t max_entries,
+		std::size_t max_bytes);
+	~ArticlePreview();
+
+	void request(const std::string& guid);
+	nonstd::optional<std::string> poll();
+
+	/// \brief Milliseconds until poll() returns something, or -1 if
+	/// nothing is pending.
+	int time_left_ms();
+
+	/// \brief Makes the next request go through even if it's for the item
+	/// that's shown already, e.g. because the pane was resized.
+	void reset();
+
+	/// \brief Changes the limits of the cache, evicting previews if
+	/// necessary.
+	void set_cache_limits(std::size_t max_entries, std::size_t max_bytes);
+
+	/// \brief Returns lines of the preview of article \a guid, fitted into
+	/// a pane of the given size and quoted for STFL richtext.
+	///
+	/// \a render is only called if the preview isn't cached yet; it should
+	/// return the article rendered into plain text. The preview stays in
+	/// the cache at least until another one is requested.
+	std::vector<std::string> lines(const std::string& guid,
+		unsigned int width,
+		unsigned int height,
+		std::function<std::string()> render);
+
+	/// \brief Forgets all cached previews.
+	void clear_cache();
+
+private:
+	ArticlePreview(const ArticlePreview&) = delete;
+	ArticlePreview& operator=(const ArticlePreview&) = delete;
+
+	void* rs_cache = nullptr;
+	void* rs_debouncer = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_ARTICLEPREVIEW_H_ */
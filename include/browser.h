@@ -0,0 +1,39 @@
+#ifndef NEWSBOAT_BROWSER_H_
+#define NEWSBOAT_BROWSER_H_
+
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+namespace browser {
+
+/// Command that opens \a url in \a browser (the value of the `browser`
+/// setting). `%u` is replaced with the URL; without it, the URL is appended.
+std::string cmdline(const std::string& browser, const std::string& url);
+
+} // namespace browser
+
+/// Browsers started in the background, without taking over the terminal.
+class DetachedBrowsers {
+public:
+	DetachedBrowsers();
+	~DetachedBrowsers();
+	DetachedBrowsers(const DetachedBrowsers&) = delete;
+	DetachedBrowsers& operator=(const DetachedBrowsers&) = delete;
+
+	/// Runs \a cmdline and returns right away. Returns false if the browser
+	/// couldn't be started; whether it exited successfully is found out
+	/// later, see take_failures().
+	bool launch(const std::string& url, const std::string& cmdline);
+
+	/// Messages about browsers that failed since the last call.
+	std::vector<std::string> take_failures();
+
+private:
+	void* rs_object = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_BROWSER_H_ */
@@ -1,11 +1,13 @@
 #ifndef NEWSBOAT_CACHE_H_
 #define NEWSBOAT_CACHE_H_
 
+#include <functional>
 #include <memory>
 #include <mutex>
 #include <sqlite3.h>
 #include <unordered_set>
 
+#include "3rd-party/optional.hpp"
 #include "configcontainer.h"
 
 namespace newsboat {
@@ -39,12 +41,80 @@ const SchemaVersion unknown_version = {0, 0};
 
 using schema_patches = std::map<SchemaVersion, std::vector<std::string>>;
 
+/// A migration that rewrites every row of a table. It runs in chunks of rows,
+/// each in a transaction of its own, so that it can be resumed if it gets
+/// interrupted.
+struct ChunkedMigration {
+	/// Table whose highest `id` estimates the number of rows to migrate.
+	std::string table;
+	/// Statement that migrates the rows whose `id` is greater than its first
+	/// argument, and at most its second one. Running it twice on the same
+	/// rows must do no harm.
+	std::string statement;
+};
+
+using chunked_migrations =
+	std::map<SchemaVersion, std::vector<ChunkedMigration>>;
+
+struct CacheMergeSummary {
+	/// Articles copied from the other cache.
+	unsigned int added;
+	/// Articles whose read state or flags changed.
+	unsigned int merged;
+	/// Articles that are read in one cache and unread in the other.
+	unsigned int conflicts;
+};
+
+struct CacheRecoveryTable {
+	std::string name;
+	unsigned int recovered;
+	/// Unknown if the damaged file couldn't even tell how many rows the
+	/// table had.
+	nonstd::optional<unsigned int> lost;
+};
+
+struct CacheRecoverySummary {
+	/// One entry per table, in the order in which they were copied.
+	std::vector<CacheRecoveryTable> tables;
+	/// Where the damaged file was kept.
+	std::string backup_file;
+};
+
 class Cache {
 public:
-	Cache(const std::string& cachefile, ConfigContainer* c);
+	/// `migration_progress` gets messages like "migrating cache: 42%" while
+	/// the schema of an old cache is migrated.
+	Cache(const std::string& cachefile, ConfigContainer* c,
+		const std::function<void(const std::string&)>& migration_progress = {});
 	~Cache();
+
+	/// Opens the cache file read-only, so that it can be queried while
+	/// another instance of Newsboat is using it. Nothing is migrated or
+	/// cleaned up, so the file has to be in the current format already. If
+	/// the other instance is writing to the file, this waits a few seconds
+	/// for it to finish.
+	static std::unique_ptr<Cache> open_read_only(const std::string& cachefile,
+		ConfigContainer* c);
+	/// URLs of all the feeds stored in the cache.
+	std::vector<std::string> get_feed_urls();
 	void externalize_rssfeed(std::shared_ptr<RssFeed> feed,
 		bool reset_unread);
+
+	/// \brief Stores a page of a feed's first sync along with the \a progress
+	/// of the sync, in a single transaction.
+	///
+	/// Either both the articles and the progress end up in the cache, or
+	/// neither does, so an interrupted sync can always continue from the
+	/// stored progress.
+	void store_sync_page(std::shared_ptr<RssFeed> page,
+		bool reset_unread,
+		const std::string& progress);
+	void set_sync_progress(const std::string& feedurl,
+		const std::string& progress);
+	/// \brief Progress of the feed's first sync, or nothing if it never
+	/// started.
+	nonstd::optional<std::string> fetch_sync_progress(
+		const std::string& feedurl);
 	std::shared_ptr<RssFeed> internalize_rssfeed(std::string rssurl,
 		RssIgnores* ign);
 	void update_rssitem_unread_and_enqueued(std::shared_ptr<RssItem> item,
@@ -52,6 +122,10 @@ public:
 	void update_rssitem_unread_and_enqueued(RssItem* item,
 		const std::string& feedurl);
 	void cleanup_cache(std::vector<std::shared_ptr<RssFeed>> feeds);
+	/// Removes what cleanup_cache() removes, even if `cleanup-on-quit` is
+	/// off, and compacts the cache. Unlike cleanup_cache(), leaves the
+	/// cache usable.
+	void cleanup_and_vacuum(std::vector<std::shared_ptr<RssFeed>> feeds);
 	void do_vacuum();
 	std::vector<std::shared_ptr<RssItem>> search_for_items(
 			const std::string& querystr,
@@ -62,6 +136,13 @@ public:
 	void mark_all_read(const std::string& feedurl = "");
 	void mark_all_read(std::shared_ptr<RssFeed> feed);
 	void update_rssitem_flags(RssItem* item);
+	void update_rssitem_note(RssItem* item);
+	/// \brief Stores the read and deleted states and the flags of \a items
+	/// in a single transaction.
+	///
+	/// Either all of them are stored, or, if one fails, none is.
+	void update_rssitem_states(
+		const std::vector<std::shared_ptr<RssItem>>& items);
 	void fetch_lastmodified(const std::string& uri,
 		time_t& t,
 		std::string& etag);
@@ -75,13 +156,50 @@ public:
 	std::vector<std::string> get_read_item_guids();
 	void fetch_descriptions(RssFeed* feed);
 	std::string fetch_description(const RssItem& item);
+	/// Copies the articles of another cache file that are missing from this
+	/// one, and merges the read states and flags of the rest. The other file
+	/// is opened read-only.
+	CacheMergeSummary merge_from(const std::string& other_cachefile,
+		bool prefer_read);
+
+	/// Runs SQLite's integrity check on the cache file, and returns the
+	/// problems it found. The file is opened read-only; it's healthy if
+	/// nothing is returned.
+	static std::vector<std::string> check_integrity(
+		const std::string& cachefile);
+	/// Copies every row that can still be read from a damaged cache file
+	/// into a fresh one, table by table, calling `progress` after each
+	/// table. The fresh file then replaces the damaged one, which is kept
+	/// under the name `<cachefile>.corrupt`.
+	///
+	/// Does nothing and returns nothing if the file passes the integrity
+	/// check, unless `force` is true.
+	static nonstd::optional<CacheRecoverySummary> recover(
+		const std::string& cachefile,
+		ConfigContainer* cfg,
+		bool force,
+		const std::function<void(const CacheRecoveryTable&)>& progress);
 
 private:
+	Cache(sqlite3* handle, ConfigContainer* c);
+
 	SchemaVersion get_schema_version();
-	void populate_tables();
+	void check_min_reader_version(const SchemaVersion& version);
+	void populate_tables(
+		const std::function<void(const std::string&)>& migration_progress);
+	void run_chunked_migrations(void* plan,
+		const SchemaVersion& version,
+		const std::vector<size_t>& migrations,
+		const std::function<void(const std::string&)>& migration_progress);
+	/// Counts the words of articles that were stored without a word count.
+	void fill_in_article_lengths();
 	void set_pragmas();
 	void delete_item(const std::shared_ptr<RssItem>& item);
 	void clean_old_articles();
+	void remove_unused_entries_unlocked(
+		const std::vector<std::shared_ptr<RssFeed>>& feeds);
+	void externalize_rssfeed_unlocked(std::shared_ptr<RssFeed> feed,
+		bool reset_unread);
 	void update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 		const std::string& feedurl,
 		bool reset_unread);
@@ -108,6 +226,8 @@ private:
 
 	sqlite3* db;
 	ConfigContainer* cfg;
+	/// The file was opened with `open_read_only()`.
+	bool read_only;
 	std::mutex mtx;
 };
 
@@ -0,0 +1,35 @@
+#ifndef NEWSBOAT_CACHEQUERY_H_
+#define NEWSBOAT_CACHEQUERY_H_
+
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+class Matchable;
+
+/// \brief Articles that `--query-cache` prints, in the format chosen with
+/// `--format`.
+class QueryResult {
+public:
+	/// \a fields are the attributes of the articles to print, in order.
+	QueryResult(const std::vector<std::string>& fields, bool json);
+	~QueryResult();
+
+	QueryResult(const QueryResult&) = delete;
+	QueryResult& operator=(const QueryResult&) = delete;
+
+	/// Adds the values of the fields of \a article.
+	void add_article(const Matchable& article);
+
+	/// All the articles added so far, ending in a newline.
+	std::string render() const;
+
+private:
+	std::vector<std::string> fields;
+	void* rs_result;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_CACHEQUERY_H_ */
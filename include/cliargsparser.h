@@ -2,6 +2,7 @@
 #define NEWSBOAT_CLIARGSPARSER_H_
 
 #include <string>
+#include <utility>
 #include <vector>
 
 #include "3rd-party/optional.hpp"
@@ -19,9 +20,14 @@ public:
 	bool do_import() const;
 
 	bool do_export() const;
+	bool export_opml2() const;
 
 	bool do_vacuum() const;
 
+	/// If `cleanup()` is `true`, Newsboat should do the cache cleanup that
+	/// normally happens on quit, compact the cache, and exit.
+	bool cleanup() const;
+
 	std::string importfile() const;
 
 	/// If non-null, Newsboat should import read articles info from this
@@ -73,10 +79,85 @@ public:
 	/// \note The parser does not check if the passed commands are valid.
 	nonstd::optional<std::vector<std::string>> cmds_to_execute() const;
 
+	/// Config options set with `-o key=value`, in the order in which they
+	/// were given. They should be applied after the config file is read, so
+	/// that they win.
+	///
+	/// \note The parser does not check if the keys are valid options.
+	std::vector<std::pair<std::string, std::string>> config_overrides() const;
+
 	nonstd::optional<std::string> log_file() const;
 
 	nonstd::optional<Level> log_level() const;
 
+	/// If non-null, Newsboat should use config, urls, cache etc. files of
+	/// this profile.
+	nonstd::optional<std::string> profile() const;
+
+	/// If `list_profiles()` is `true`, the creator of `CliArgsParser` should
+	/// print the names of existing profiles and exit.
+	bool list_profiles() const;
+
+	/// If `json_errors()` is `true`, errors in non-interactive modes should
+	/// be printed to stderr as JSON objects (see `clierror::format()`).
+	///
+	/// \note Usage errors are then already formatted in `display_msg()`, and
+	/// `should_print_usage()` is `false` for them.
+	bool json_errors() const;
+
+	/// If non-null, Newsboat should merge the articles and read states of
+	/// this cache file into its own cache, and exit.
+	nonstd::optional<std::string> merge_cache_file() const;
+
+	/// Whether an article that's read in one cache but unread in the other
+	/// should end up read (`--merge-prefer read`, the default) or unread.
+	bool merge_prefer_read() const;
+
+	/// If `recover_cache()` is `true`, Newsboat should salvage what it can
+	/// from a damaged cache file into a fresh one, and exit.
+	bool recover_cache() const;
+
+	/// If `force()` is `true`, the cache should be recovered even if it
+	/// passes the integrity check.
+	bool force() const;
+
+	/// If `import_from_newsbeuter()` is `true`, Newsboat should copy
+	/// Newsbeuter's config and data into its own directories, unless it
+	/// already has some, and exit.
+	bool import_from_newsbeuter() const;
+
+	/// If `dry_run_migration()` is `true`, Newsboat should print what
+	/// `import_from_newsbeuter()` would copy, without copying anything, and
+	/// exit.
+	bool dry_run_migration() const;
+
+	/// If `convert_to_utf8()` is `true`, Newsboat should rewrite the config
+	/// and urls files that aren't UTF-8 in UTF-8, and exit.
+	bool convert_to_utf8() const;
+
+	/// If `plain_output()` is `true`, non-interactive modes should print the
+	/// same output to a terminal as they do to a pipe.
+	bool plain_output() const;
+
+	/// If non-null, Newsboat should add this feed to the urls file and exit.
+	/// If another instance is running, it's asked to do that instead.
+	nonstd::optional<std::string> add_feed() const;
+
+	/// If non-null, a URL was given without an option. With
+	/// `single-instance` enabled, it's handled like `add_feed()`.
+	nonstd::optional<std::string> url_argument() const;
+
+	/// If non-null, it's a filter expression: Newsboat should print the
+	/// articles of the cache that match it, and exit.
+	nonstd::optional<std::string> query_cache() const;
+
+	/// Attributes of the articles that `query_cache()` prints, in order.
+	std::vector<std::string> query_fields() const;
+
+	/// Whether `query_cache()` prints JSON (`--format json`) or
+	/// tab-separated values (the default).
+	bool query_format_json() const;
+
 	/// Returns the pointer to the Rust object.
 	///
 	/// This is only meant to be used in situations when one wants to pass
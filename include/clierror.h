@@ -0,0 +1,45 @@
+#ifndef NEWSBOAT_CLIERROR_H_
+#define NEWSBOAT_CLIERROR_H_
+
+#include <cstdint>
+#include <string>
+
+namespace newsboat {
+
+/// \brief What stopped a non-interactive mode (import, export, `-x`,
+/// vacuum etc.)
+///
+/// Each category has its own exit code, so that scripts can tell them apart.
+///
+/// \note The values have to match `ErrorCategory` in
+/// rust/libnewsboat/src/clierror.rs.
+enum class ErrorCategory : std::uint8_t {
+	OTHER = 0,
+	USAGE = 1,
+	CONFIG = 2,
+	URLS_FILE = 3,
+	CACHE = 4,
+	NETWORK = 5,
+};
+
+namespace clierror {
+
+/// Exit code that Newsboat returns when it stops because of an error of
+/// this category.
+int exit_code(ErrorCategory category);
+
+/// Formats an error for printing to stderr: either as a human-readable
+/// message, or (if `json` is `true`) as a single-line JSON object. `file`
+/// and `line` should be empty and zero, respectively, if the location of the
+/// error is unknown.
+std::string format(ErrorCategory category,
+	const std::string& message,
+	bool json,
+	const std::string& file = {},
+	unsigned int line = 0);
+
+} // namespace clierror
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_CLIERROR_H_ */
@@ -0,0 +1,43 @@
+#ifndef NEWSBOAT_CLIOUTPUT_H_
+#define NEWSBOAT_CLIOUTPUT_H_
+
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+/// \brief What `-x reload` and `-x print-unread` print to stdout.
+///
+/// Output that goes to a pipe or a file stays the way scripts expect it.
+/// In a terminal, it's aligned in columns and has more detail, and errors are
+/// colored unless `NO_COLOR` is set.
+class CliReport {
+public:
+	/// Lays the report out for stdout, or for a pipe if \a force_plain is
+	/// true (see `--plain`).
+	explicit CliReport(bool force_plain);
+	~CliReport();
+	CliReport(const CliReport&) = delete;
+	CliReport& operator=(const CliReport&) = delete;
+
+	/// Records that the feed titled \a title has \a unread unread articles.
+	void add_feed(const std::string& title, unsigned int unread);
+
+	/// Records that the feed at \a url couldn't be reloaded because of
+	/// \a message.
+	void add_failure(const std::string& url, const std::string& message);
+
+	/// Lines that `print-unread` prints, given \a total unread articles.
+	std::vector<std::string> unread_summary(unsigned int total) const;
+
+	/// Lines that `reload` prints after the report of the reload hooks.
+	/// Empty unless the output goes to a terminal.
+	std::vector<std::string> failure_report() const;
+
+private:
+	void* rs_report = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_CLIOUTPUT_H_ */
@@ -21,22 +21,28 @@ struct TextStyle {
 	std::vector<std::string> attributes;
 };
 
+/// Styles configured with `color`. The arguments are validated, and the
+/// styles stored, by the Rust side (see
+/// `rust/libnewsboat/src/colormanager.rs`).
 class ColorManager : public ConfigActionHandler {
 public:
 	ColorManager();
 	~ColorManager() override;
+	ColorManager(const ColorManager&) = delete;
+	ColorManager& operator=(const ColorManager&) = delete;
 	void register_commands(ConfigParser& cfgparser);
 	void handle_action(const std::string& action,
 		const std::vector<std::string>& params) override;
 	void dump_config(std::vector<std::string>& config_output) const override;
 	void apply_colors(Stfl::Form& form) const;
-	std::map<std::string, TextStyle> get_styles() const
-	{
-		return element_styles;
-	}
+	/// Configured elements and their styles, with colors spelled the way
+	/// the config does, e.g. "color208" or "#ff8000".
+	std::map<std::string, TextStyle> get_styles() const;
 
 private:
-	std::map<std::string, TextStyle> element_styles;
+	std::vector<std::string> elements() const;
+
+	void* rs_object = nullptr;
 };
 
 } // namespace newsboat
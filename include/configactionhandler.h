@@ -13,6 +13,11 @@ public:
 		const std::string& params);
 
 	virtual void dump_config(std::vector<std::string>& config_output) const = 0;
+
+	/// Called by ConfigParser before handle_action() with the file and line
+	/// of the command. \a file is empty if the command doesn't come from
+	/// a file.
+	virtual void set_origin(const std::string& file, unsigned int line);
 	ConfigActionHandler() {}
 	virtual ~ConfigActionHandler() {}
 
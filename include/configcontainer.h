@@ -6,6 +6,7 @@
 #include <string>
 #include <vector>
 
+#include "3rd-party/optional.hpp"
 #include "configparser.h"
 
 namespace newsboat {
@@ -21,7 +22,16 @@ enum class FeedSortMethod {
 	LAST_UPDATED
 };
 
-enum class ArtSortMethod { TITLE, FLAGS, AUTHOR, LINK, GUID, DATE, RANDOM };
+enum class ArtSortMethod {
+	TITLE,
+	FLAGS,
+	AUTHOR,
+	LINK,
+	GUID,
+	DATE,
+	WORDS,
+	RANDOM
+};
 
 enum class SortDirection { ASC, DESC };
 
@@ -63,21 +73,44 @@ public:
 	void handle_action(const std::string& action,
 		const std::vector<std::string>& params) override;
 	void dump_config(std::vector<std::string>& config_output) const override;
+	void set_origin(const std::string& file, unsigned int line) override;
 
 	bool get_configvalue_as_bool(const std::string& key) const;
 	int get_configvalue_as_int(const std::string& key) const;
 	std::string get_configvalue(const std::string& key) const;
 	void set_configvalue(const std::string& key, const std::string& value);
+	/// Sets \a key to \a value if the value passes the checks that apply to
+	/// the config file. Otherwise, returns the internationalized reason why
+	/// it was refused, and keeps the previous value.
+	nonstd::optional<std::string> try_set_configvalue(const std::string& key,
+		const std::string& value);
+	/// Returns the internationalized error about \a key not being an
+	/// option, suggesting options with similar names, or nothing if it is one.
+	nonstd::optional<std::string> check_option_exists(
+		const std::string& key) const;
 	void reset_to_default(const std::string& key);
 	void toggle(const std::string& key);
 	std::vector<std::string> get_suggestions(const std::string& fragment) const;
+	/// Describes the type, default and current values of \a key, and the
+	/// file and line of the config command that last set it. Returns
+	/// nullopt if there's no such option.
+	nonstd::optional<std::string> describe_option(const std::string& key) const;
 	FeedSortStrategy get_feed_sort_strategy() const;
 	ArticleSortStrategy get_article_sort_strategy() const;
 
 	static const std::string PARTIAL_FILE_SUFFIX;
 
 private:
+	static nonstd::optional<std::string> validate(const std::string& key,
+		const std::string& value);
+
 	std::map<std::string, ConfigData> config_data;
+	/// "file:line" of the config command that last set each option. Options
+	/// that were set in some other way since then aren't in here.
+	std::map<std::string, std::string> origins;
+	/// File and line of the config command that's being handled.
+	std::string origin_file;
+	unsigned int origin_line = 0;
 	mutable std::recursive_mutex config_data_mtx;
 };
 
@@ -10,6 +10,15 @@ class ConfigException : public std::exception {
 public:
 	explicit ConfigException(const std::string& errmsg)
 		: msg(errmsg)
+		, line_(0)
+	{
+	}
+	ConfigException(const std::string& errmsg,
+		const std::string& file,
+		unsigned int line)
+		: msg(errmsg)
+		, file_(file)
+		, line_(line)
 	{
 	}
 	~ConfigException() throw() override {}
@@ -18,8 +27,22 @@ public:
 		return msg.c_str();
 	}
 
+	/// File in which the error was found; empty if unknown.
+	const std::string& file() const
+	{
+		return file_;
+	}
+
+	/// Line of `file()` at which the error was found; zero if unknown.
+	unsigned int line() const
+	{
+		return line_;
+	}
+
 private:
 	std::string msg;
+	std::string file_;
+	unsigned int line_;
 };
 
 } // namespace newsboat
@@ -3,6 +3,8 @@
 
 #include <functional>
 #include <map>
+#include <string>
+#include <vector>
 
 #include "configactionhandler.h"
 
@@ -32,12 +34,43 @@ public:
 	void parse_line(const std::string& line, const std::string& location);
 	static std::string evaluate_backticks(std::string token);
 
+	/// \brief Warnings about files that weren't UTF-8, one per file, about
+	/// commands that were ignored because of `ignore-unknown-directives`, one
+	/// per command, and about the options that were ignored because of
+	/// `tolerate-unknown-options`, all in one.
+	std::vector<std::string> get_warnings() const;
+
+	/// \brief Parsed files that weren't UTF-8, and were read as ISO-8859-1 or
+	/// Windows-1252 instead.
+	std::vector<std::string> get_non_utf8_files() const;
+
 private:
+	ConfigParser(const ConfigParser&) = delete;
+	ConfigParser& operator=(const ConfigParser&) = delete;
+
+	struct UnknownDirective {
+		std::string name;
+		std::string first_location;
+		unsigned int count;
+	};
+
+	void handle_unknown_directive(const std::string& cmd,
+		const std::string& location);
+
 	static std::string evaluate_cmd(const std::string& cmd);
 	std::vector<std::vector<std::string>> parsed_content;
 	std::map<std::string, std::reference_wrapper<ConfigActionHandler>>
 		action_handlers;
 	std::vector<std::string> included_files;
+	bool ignore_unknown_directives;
+	std::vector<UnknownDirective> unknown_directives;
+	bool tolerate_unknown_options;
+	void* rs_unknown_options;
+	std::vector<std::string> encoding_warnings;
+	std::vector<std::string> non_utf8_files;
+	/// File and line of the command that's being parsed.
+	std::string origin_file;
+	unsigned int origin_line;
 };
 
 } // namespace newsboat
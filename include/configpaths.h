@@ -2,7 +2,9 @@
 #define NEWSBOAT_CONFIGPATHS_H_
 
 #include <string>
+#include <vector>
 
+#include "3rd-party/optional.hpp"
 #include "cliargsparser.h"
 
 namespace newsboat {
@@ -27,6 +29,9 @@ public:
 	std::string error_message() const;
 
 	/// Initializes paths to config, cache etc. from CLI arguments.
+	///
+	/// If a profile is specified, all paths point into the profile's
+	/// subdirectories, except for those that were explicitly specified too.
 	void process_args(const CliArgsParser& args);
 
 	/// If user didn't specify paths to configs on the command line, and the
@@ -35,11 +40,20 @@ public:
 	/// true if copied something, false otherwise.
 	bool try_migrate_from_newsbeuter();
 
+	/// Describes what `try_migrate_from_newsbeuter()` would copy, one line
+	/// per directory and file, without copying anything. Returns nothing if
+	/// there is nothing to migrate.
+	nonstd::optional<std::string> describe_migration_plan() const;
+
 	/// Creates Newsboat's dotdir or XDG config & data dirs (depending on what
 	/// was configured during initialization, when processing CLI args, and if
 	/// migration found anything).
 	bool create_dirs() const;
 
+	/// Directory that holds the cache and other data files of the current
+	/// profile.
+	std::string data_dir() const;
+
 	/// Path to the URLs file.
 	std::string url_file() const;
 
@@ -71,6 +85,16 @@ public:
 
 	/// Path to the file with command-line history.
 	std::string cmdline_file() const;
+
+	/// Path to the snapshot of the feedlist that speeds up the next start.
+	std::string feedlist_snapshot_file() const;
+
+	/// Path to the overlay that changes of the urls file go into if
+	/// `urls-file-copy-on-write` is set.
+	std::string url_overlay_file() const;
+
+	/// Names of the existing profiles, in alphabetical order.
+	std::vector<std::string> profiles() const;
 };
 } // namespace newsboat
 
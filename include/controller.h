@@ -2,10 +2,14 @@
 #define NEWSBOAT_CONTROLLER_H_
 
 #include <libxml/tree.h>
+#include <thread>
 
+#include "3rd-party/optional.hpp"
 #include "cache.h"
+#include "clierror.h"
 #include "colormanager.h"
 #include "configcontainer.h"
+#include "controlsocket.h"
 #include "feedcontainer.h"
 #include "filtercontainer.h"
 #include "fslock.h"
@@ -13,6 +17,11 @@
 #include "queuemanager.h"
 #include "regexmanager.h"
 #include "reloader.h"
+#include "emptyfeedguard.h"
+#include "feedicons.h"
+#include "feedoptions.h"
+#include "feedschedule.h"
+#include "reloadhooks.h"
 #include "remoteapi.h"
 #include "rssignores.h"
 #include "urlreader.h"
@@ -21,6 +30,7 @@ namespace newsboat {
 
 class CliArgsParser;
 class ConfigPaths;
+class FileUrlReader;
 class View;
 
 class CurlHandle;
@@ -43,6 +53,10 @@ public:
 	void update_feedlist();
 	void update_visible_feeds();
 	void mark_all_read(unsigned int pos);
+	/// Blocks until the feeds are loaded, if the feedlist was shown from
+	/// the snapshot of the last run and still has placeholders without
+	/// articles.
+	void wait_for_feedlist();
 	void mark_article_read(const std::string& guid, bool read);
 	void mark_all_read(const std::string& feedurl);
 	void mark_all_read(std::shared_ptr<RssFeed> feed)
@@ -57,6 +71,31 @@ public:
 
 	void reload_urls_file();
 	void edit_urls_file();
+	/// Lets the user edit the note on `item` in `$VISUAL` or `$EDITOR`, and
+	/// stores the result. Returns `false` if the note couldn't be edited.
+	bool edit_note_in_editor(std::shared_ptr<RssItem> item);
+	/// Adds `url` to the urls file, and the feed to the feed list. Returns
+	/// `false` and sets `error` if that's not possible.
+	bool subscribe(const std::string& url, std::string& error);
+	/// Add `tag` to, or remove it from, the feed `url` in the urls file, and
+	/// update the feed list. Return `false` and set `error` if that's not
+	/// possible.
+	bool add_tag(const std::string& url,
+		const std::string& tag,
+		std::string& error);
+	bool remove_tag(const std::string& url,
+		const std::string& tag,
+		std::string& error);
+	/// Renames `old_tag` to `new_tag` on every feed that has it, and sets
+	/// `renamed` to the number of those feeds. Returns `false` and sets
+	/// `error` if that's not possible.
+	bool rename_tag(const std::string& old_tag,
+		const std::string& new_tag,
+		std::size_t& renamed,
+		std::string& error);
+	/// Number of feeds in the urls file that have `tag`, or 0 if the
+	/// feeds don't come from a local urls file.
+	std::size_t count_feeds_with_tag(const std::string& tag);
 
 	FeedContainer* get_feedcontainer()
 	{
@@ -67,14 +106,27 @@ public:
 		const std::string& filename);
 	void write_item(std::shared_ptr<RssItem> item, std::ostream& ostr);
 	std::string write_temporary_item(std::shared_ptr<RssItem> item);
+	/// Creates an empty temporary file whose name starts with `prefix`, and
+	/// returns its name, or an empty string on failure.
+	std::string create_temporary_file(const std::string& prefix);
 
 	void update_config();
 
 	void load_configfile(const std::string& filename);
 
-	void dump_config(const std::string& filename) const;
+	/// Writes the current configuration to `filename`. Returns the error
+	/// message if the file couldn't be written.
+	nonstd::optional<std::string> dump_config(const std::string& filename)
+	const;
 
 	void update_flags(std::shared_ptr<RssItem> item);
+	void update_note(std::shared_ptr<RssItem> item);
+	/// Tells the remote API about \a items, whose read states or flags were
+	/// changed together and stored already.
+	void sync_article_states(
+		const std::vector<std::shared_ptr<RssItem>>& items,
+		bool read_changed,
+		bool flags_changed);
 
 	Reloader* get_reloader()
 	{
@@ -91,6 +143,31 @@ public:
 		return &ign;
 	}
 
+	ReloadHooks& get_reload_hooks()
+	{
+		return reload_hooks;
+	}
+
+	const FeedOptions& get_feed_options() const
+	{
+		return feed_options;
+	}
+
+	EmptyFeedGuard& get_empty_feed_guard()
+	{
+		return empty_feed_guard;
+	}
+
+	FeedIcons& get_feed_icons()
+	{
+		return feed_icons;
+	}
+
+	FeedScheduler& get_feed_scheduler()
+	{
+		return feed_scheduler;
+	}
+
 	RemoteApi* get_api()
 	{
 		return api;
@@ -112,20 +189,80 @@ public:
 	}
 
 private:
-	void import_opml(const std::string& opmlFile, const std::string& urlFile);
-	void export_opml();
+	/// The urls file whose tags can be edited, or nullptr and sets `error`
+	/// if the feeds don't come from one.
+	FileUrlReader* tags_urls_file(std::string& error);
+
+	/// The overlay that changes of the urls file go into, or an empty string
+	/// if `urls-file-copy-on-write` isn't set.
+	std::string url_overlay_file() const;
+
+	bool import_opml(const std::string& opmlFile, const std::string& urlFile);
+	void export_opml(bool version2);
 	void rec_find_rss_outlines(xmlNode* node, std::string tag);
 	int execute_commands(const std::vector<std::string>& cmds);
 
-	void import_read_information(const std::string& readinfofile);
-	void export_read_information(const std::string& readinfofile);
+	/// Handles `--add-feed`, and URLs given without an option. If another
+	/// instance holds the lock (\a running_elsewhere), it's asked to add the
+	/// feed through its control socket; otherwise, the urls file is changed
+	/// directly. Returns the exit code.
+	int add_feed_from_cli(const CliArgsParser& args,
+		bool running_elsewhere,
+		pid_t pid);
+
+	/// Lets other `newsboat` processes send commands to this one.
+	void start_control_socket();
+	ControlSocket::Response handle_control_command(const std::string& command,
+		const std::string& argument);
+	bool all_feeds_failed_to_download();
+
+	/// Reads the feedlist snapshot if it's current, puts placeholders for
+	/// its feeds into the feed container, and returns the snapshot for
+	/// load_feeds_in_background(). Returns nullptr, changing nothing, if
+	/// there is no current snapshot.
+	void* load_feedlist_snapshot();
+	/// Replaces the placeholders with the feeds from the cache, and frees
+	/// \a snapshot. Runs in `feedlist_loader`.
+	void load_feeds_in_background(void* snapshot);
+	/// Writes the current feedlist to the snapshot for the next start.
+	void save_feedlist_snapshot();
+
+	bool import_read_information(const std::string& readinfofile);
+	bool export_read_information(const std::string& readinfofile);
+
+	/// Salvages what can still be read from a damaged cache file (see
+	/// `Cache::recover()`), printing the progress, and returns the exit code.
+	int recover_cache(bool force);
+
+	/// Prints the \a fields of the cached articles that match \a filter,
+	/// and returns the exit code. The cache is opened read-only, so this
+	/// works while another instance is running.
+	int query_cache(const std::string& filter,
+		const std::vector<std::string>& fields,
+		bool json);
+
+	/// Prints an error that stops a non-interactive mode to stderr (as
+	/// JSON if the user asked for that), and returns the exit code for it.
+	int report_error(ErrorCategory category,
+		const std::string& message,
+		const std::string& file = {},
+		unsigned int line = 0);
 
 	View* v;
 	UrlReader* urlcfg;
 	Cache* rsscache;
 	bool refresh_on_start;
+	bool json_errors;
+	/// Print the output of `-x` the way it looks in a pipe, even in a
+	/// terminal.
+	bool plain_output;
 	ConfigContainer cfg;
 	RssIgnores ign;
+	ReloadHooks reload_hooks;
+	FeedOptions feed_options;
+	EmptyFeedGuard empty_feed_guard;
+	FeedIcons feed_icons;
+	FeedScheduler feed_scheduler;
 	FeedContainer feedcontainer;
 	FilterContainer filters;
 
@@ -135,11 +272,14 @@ private:
 	RemoteApi* api;
 
 	std::unique_ptr<FsLock> fslock;
+	ControlSocket control_socket;
 
 	ConfigPaths& configpaths;
 
 	std::unique_ptr<Reloader> reloader;
 
+	std::thread feedlist_loader;
+
 	QueueManager queueManager;
 };
 
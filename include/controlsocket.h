@@ -0,0 +1,69 @@
+#ifndef NEWSBOAT_CONTROLSOCKET_H_
+#define NEWSBOAT_CONTROLSOCKET_H_
+
+#include <functional>
+#include <string>
+#include <thread>
+
+namespace newsboat {
+
+/// \brief The Unix socket through which other `newsboat` processes send
+/// commands to the running instance.
+///
+/// The instance listens on a background thread, and hands each valid
+/// command to the handler; invalid ones are answered by the socket itself.
+/// The only command so far is `add-feed <url>`.
+class ControlSocket {
+public:
+	struct Response {
+		/// Whether the command was carried out.
+		bool ok;
+		/// Message to show to the user who sent the command.
+		std::string message;
+	};
+
+	/// Carries out \a command with \a argument. Called on the socket's
+	/// thread.
+	using Handler = std::function<Response(const std::string& command,
+			const std::string& argument)>;
+
+	enum class SendStatus {
+		/// The instance answered; see `Response::ok`.
+		RESPONDED,
+		/// Nothing listens on the socket.
+		NO_INSTANCE,
+		/// The instance couldn't be reached for another reason.
+		FAILED,
+	};
+
+	ControlSocket() = default;
+	~ControlSocket();
+	ControlSocket(const ControlSocket&) = delete;
+	ControlSocket& operator=(const ControlSocket&) = delete;
+
+	/// Starts listening on \a path. Returns `false` and sets \a error if
+	/// that's not possible.
+	bool start(const std::string& path, Handler handler, std::string& error);
+
+	/// Stops listening and removes the socket. Does nothing if the socket
+	/// isn't running.
+	void stop();
+
+	/// The socket of the instance that holds \a lock_file.
+	static std::string path_for_lock_file(const std::string& lock_file);
+
+	/// Asks the instance that listens on \a path to add the feed at \a url.
+	/// `response.message` is set to the instance's answer, or to the reason
+	/// why there's none.
+	static SendStatus send_add_feed(const std::string& path,
+		const std::string& url,
+		Response& response);
+
+private:
+	void* rs_server = nullptr;
+	std::thread thread;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_CONTROLSOCKET_H_ */
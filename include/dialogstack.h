@@ -0,0 +1,40 @@
+#ifndef NEWSBOAT_DIALOGSTACK_H_
+#define NEWSBOAT_DIALOGSTACK_H_
+
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+/// Which dialogs are open, which dialog each of them was opened from, and in
+/// which order. Dialogs are identified by the address of their FormAction.
+class DialogStack {
+public:
+	DialogStack();
+	~DialogStack();
+	DialogStack(const DialogStack&) = delete;
+	DialogStack& operator=(const DialogStack&) = delete;
+
+	/// Records that `dialog` was opened while `parent` was the current
+	/// dialog. `parent` can be nullptr.
+	void push(const void* dialog, const std::string& kind,
+		const std::string& name, const void* parent);
+	/// Forgets `dialog`. Returns false if there was no such dialog.
+	bool remove(const void* dialog);
+
+	/// Labels of the dialogs leading to `dialog`, e.g. "Feeds > Planet
+	/// Rust > Article".
+	std::string breadcrumb(const void* dialog);
+	/// Dialogs to close, in order, to get from `dialog` back to the feed
+	/// list.
+	std::vector<const void*> unwind_to_feedlist(const void* dialog);
+	/// All dialogs except the feed list, the newest one first.
+	std::vector<const void*> close_all();
+
+private:
+	void* rs_stack = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_DIALOGSTACK_H_ */
@@ -1,9 +1,6 @@
 #ifndef NEWSBOAT_DIRBROWSERFORMACTION_H
 #define NEWSBOAT_DIRBROWSERFORMACTION_H
 
-#include <sys/stat.h>
-#include <grp.h>
-
 #include "configcontainer.h"
 #include "listformatter.h"
 #include "listwidget.h"
@@ -36,16 +33,8 @@ private:
 		std::vector<std::string>* args = nullptr) override;
 	void update_title(const std::string& working_directory);
 
-	void add_directory(ListFormatter& listfmt,
-		std::vector<std::string>& id_at_position,
-		std::string dirname);
-	std::string get_rwx(unsigned short val);
 	std::vector<std::string> id_at_position;
 
-	std::string get_owner(uid_t uid);
-	std::string get_group(gid_t gid);
-	std::string get_formatted_dirname(std::string dirname, char ftype, mode_t mode);
-
 	std::string cwd;
 	std::string dir;
 
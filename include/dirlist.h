@@ -0,0 +1,58 @@
+#ifndef NEWSBOAT_DIRLIST_H_
+#define NEWSBOAT_DIRLIST_H_
+
+#include <string>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+class ConfigContainer;
+
+/// \brief Listing of a directory, as shown by the file and directory
+/// browsers.
+///
+/// How the entries are filtered and sorted is controlled by the
+/// `filebrowser-show-hidden`, `filebrowser-dirs-first` and
+/// `filebrowser-sort-order` settings. Unless the directory is the root one,
+/// the first entry is `..`.
+class DirList {
+public:
+	/// \brief Lists \a path; if \a directories_only is set, only directories
+	/// and symlinks to them are listed.
+	DirList(const std::string& path, ConfigContainer& cfg,
+		bool directories_only);
+	~DirList();
+
+	unsigned int size() const;
+
+	/// \brief The line to show for the entry at \a index, in the style of
+	/// `ls -l`.
+	std::string row(unsigned int index) const;
+
+	std::string name(unsigned int index) const;
+
+	/// \brief The character `ls -l` shows for the kind of the entry at
+	/// \a index, except that symlinks are shown as what they point to: 'd'
+	/// if that's a directory, '-' otherwise.
+	char type(unsigned int index) const;
+
+	/// \brief Why the directory couldn't be (fully) read, if it couldn't.
+	nonstd::optional<std::string> error() const;
+
+	/// \brief Completes the path typed into a browser's input field, the way
+	/// a shell would.
+	static std::string complete_path(const std::string& input,
+		ConfigContainer& cfg,
+		bool directories_only);
+
+private:
+	DirList(const DirList&) = delete;
+	DirList& operator=(const DirList&) = delete;
+
+	void* rs_list = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_DIRLIST_H_ */
@@ -9,13 +9,16 @@ namespace newsboat {
 
 class DownloadThread {
 public:
-	DownloadThread(Reloader& r, const std::vector<int>& idxs = {});
+	DownloadThread(Reloader& r,
+		const std::vector<int>& idxs = {},
+		bool scheduled = false);
 	virtual ~DownloadThread();
 	void operator()();
 
 private:
 	Reloader& reloader;
 	std::vector<int> indexes;
+	bool scheduled;
 };
 
 } // namespace newsboat
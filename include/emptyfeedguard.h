@@ -0,0 +1,51 @@
+#ifndef NEWSBOAT_EMPTYFEEDGUARD_H_
+#define NEWSBOAT_EMPTYFEEDGUARD_H_
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+#include "configactionhandler.h"
+
+namespace newsboat {
+
+/// \brief Keeps cached articles when a feed that used to have them
+/// suddenly comes back empty.
+///
+/// Such a fetch is ignored until it happened `empty-feed-accept-after`
+/// times in a row. Handles `accept-empty-feed`, which lists feeds that may
+/// legitimately be empty. check() can be called from multiple reload
+/// threads at once.
+class EmptyFeedGuard : public ConfigActionHandler {
+public:
+	EmptyFeedGuard();
+	~EmptyFeedGuard() override;
+	void handle_action(const std::string& action,
+		const std::vector<std::string>& params) override;
+	void dump_config(std::vector<std::string>& config_output) const override;
+
+	/// \brief Empty fetches are only suspicious if the cache holds more
+	/// than \a min_cached_items articles of the feed, and are accepted once
+	/// they happened \a accept_after times in a row.
+	void set_thresholds(unsigned int min_cached_items,
+		unsigned int accept_after);
+
+	/// \brief Checks a fetch of the feed at \a url that brought
+	/// \a fetched_items articles, while the cache holds \a cached_items.
+	///
+	/// Returns a warning if the fetched feed should be ignored, in which
+	/// case the cached articles should be kept as they are.
+	nonstd::optional<std::string> check(const std::string& url,
+		unsigned int fetched_items,
+		unsigned int cached_items);
+
+private:
+	EmptyFeedGuard(const EmptyFeedGuard&) = delete;
+	EmptyFeedGuard& operator=(const EmptyFeedGuard&) = delete;
+
+	void* rs_guard = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_EMPTYFEEDGUARD_H_ */
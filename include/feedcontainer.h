@@ -40,6 +40,10 @@ public:
 	unsigned int unread_item_count() const;
 
 	void replace_feed(unsigned int pos, std::shared_ptr<RssFeed> feed);
+	/// Puts \a feed where \a oldfeed is. Returns false, leaving the feeds
+	/// alone, if \a oldfeed was already replaced or removed.
+	bool replace_feed(const std::shared_ptr<RssFeed>& oldfeed,
+		std::shared_ptr<RssFeed> feed);
 
 private:
 	std::vector<std::shared_ptr<RssFeed>> feeds;
@@ -0,0 +1,40 @@
+#ifndef NEWSBOAT_FEEDICONS_H_
+#define NEWSBOAT_FEEDICONS_H_
+
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "configactionhandler.h"
+
+namespace newsboat {
+
+class RssFeed;
+
+/// \brief Glyphs of the feed icon column (`%I` in `feedlist-format`).
+///
+/// Handles `feedlist-icon`, which sets the glyph of a category of feeds
+/// (error, query, podcast, plain). Glyphs are at most two columns wide, and
+/// are padded so that the column stays aligned.
+class FeedIcons : public ConfigActionHandler {
+public:
+	FeedIcons();
+	~FeedIcons() override;
+	void handle_action(const std::string& action,
+		const std::vector<std::string>& params) override;
+	void dump_config(std::vector<std::string>& config_output) const override;
+
+	/// \brief Glyph of the category \a feed belongs to, padded to the width
+	/// of the column.
+	std::string glyph(std::shared_ptr<RssFeed> feed) const;
+
+private:
+	FeedIcons(const FeedIcons&) = delete;
+	FeedIcons& operator=(const FeedIcons&) = delete;
+
+	void* rs_icons = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_FEEDICONS_H_ */
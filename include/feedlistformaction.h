@@ -1,19 +1,36 @@
 #ifndef NEWSBOAT_FEEDLISTFORMACTION_H_
 #define NEWSBOAT_FEEDLISTFORMACTION_H_
 
+#include <cstdint>
+
 #include "3rd-party/optional.hpp"
 
 #include "configcontainer.h"
 #include "history.h"
 #include "listformaction.h"
+#include "listposition.h"
 #include "matcher.h"
 #include "regexmanager.h"
+#include "scopedcounts.h"
 #include "view.h"
 
 namespace newsboat {
 
 typedef std::pair<std::shared_ptr<RssFeed>, unsigned int> FeedPtrPosPair;
 
+/// Outcome of FeedListFormAction::jump_to_feed_with_unread().
+enum class FeedJump : std::uint8_t {
+	// These numbers MUST match constants in
+	// rust/libnewsboat-ffi/src/feednavigation.rs
+	JUMPED = 0,
+	/// The current feed is the only one with unread articles.
+	ONLY_CURRENT = 1,
+	/// Feeds with unread articles are only in the other direction, and
+	/// `wrap-scroll` is off.
+	NONE_IN_DIRECTION = 2,
+	NO_UNREAD = 3,
+};
+
 class FeedListFormAction : public ListFormAction {
 public:
 	FeedListFormAction(View*,
@@ -35,12 +52,20 @@ public:
 		return "feedlist";
 	}
 	std::string title() override;
+	void add_to_memory_report(MemoryReport& report) override;
 
 	bool jump_to_next_unread_feed(unsigned int& feedpos);
 	bool jump_to_previous_unread_feed(unsigned int& feedpos);
 	bool jump_to_next_feed(unsigned int& feedpos);
 	bool jump_to_previous_feed(unsigned int& feedpos);
 	bool jump_to_random_unread_feed(unsigned int& feedpos);
+	/// Selects the first visible feed with unread articles if \a first is
+	/// true, and the last one otherwise. Returns false if there is none.
+	bool jump_to_unread_end(bool first);
+	/// Selects the closest visible feed with unread articles below (or, if
+	/// \a forward is false, above) the selected one. \a feedpos is only set
+	/// if the result is FeedJump::JUMPED.
+	FeedJump jump_to_feed_with_unread(bool forward, unsigned int& feedpos);
 
 	void handle_cmdline(const std::string& cmd) override;
 
@@ -64,11 +89,26 @@ private:
 
 	void save_filterpos();
 
+	/// Reloads the feeds that carry \a tag, or shows an error if there are
+	/// none.
+	void reload_tag(const std::string& tag);
+
 	void op_end_setfilter();
 	void op_start_search();
+	/// Adds the tag that was asked for to the selected feed, or removes it.
+	void op_end_edit_tag(bool add);
+	/// Renames the tag that was asked for, once the user confirmed it.
+	void op_end_rename_tag();
 
 	void handle_cmdline_num(unsigned int idx);
 
+	/// Goes to the position given by the arguments of `goto-percent`.
+	/// Returns false and shows an error if they're wrong.
+	bool goto_percent(const std::vector<std::string>& arguments);
+
+	/// Says on the status line where the cursor landed.
+	void show_position();
+
 	void set_pos();
 
 	std::string get_title(std::shared_ptr<RssFeed> feed);
@@ -81,6 +121,11 @@ private:
 	bool zero_feedpos;
 	std::vector<FeedPtrPosPair> visible_feeds;
 	std::string tag;
+	/// Feeds with the current tag, for `%m` and `%r` in the title.
+	ScopedCounts tag_counts;
+	/// Visible feeds with unread articles, for `first-unread` and
+	/// `last-unread`.
+	UnreadIndex unread_index;
 
 	Matcher matcher;
 	bool apply_filter;
@@ -0,0 +1,47 @@
+#ifndef NEWSBOAT_FEEDOPTIONS_H_
+#define NEWSBOAT_FEEDOPTIONS_H_
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+#include "configactionhandler.h"
+
+namespace newsboat {
+
+/// \brief Per-feed overrides for how feeds are fetched.
+///
+/// Handles `feedopts <rssurl> <option> <value> ...`, where the options are
+/// `http-header "Name: value"`, which can be repeated, and `user-agent`.
+/// Headers are validated when they're set, so that they can't smuggle other
+/// headers into requests.
+class FeedOptions : public ConfigActionHandler {
+public:
+	FeedOptions();
+	~FeedOptions() override;
+	void handle_action(const std::string& action,
+		const std::vector<std::string>& params) override;
+	void dump_config(std::vector<std::string>& config_output) const override;
+
+	/// \brief The user agent to use for the feed at \a url instead of the
+	/// global one, if any.
+	nonstd::optional<std::string> user_agent(const std::string& url) const;
+
+	/// \brief Extra headers for requests for the feed at \a url, as
+	/// "Name: value" lines.
+	std::vector<std::string> http_headers(const std::string& url) const;
+
+	/// \brief Same as http_headers(), but with the values hidden, for logs.
+	std::vector<std::string> redacted_http_headers(
+		const std::string& url) const;
+
+private:
+	FeedOptions(const FeedOptions&) = delete;
+	FeedOptions& operator=(const FeedOptions&) = delete;
+
+	void* rs_options = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_FEEDOPTIONS_H_ */
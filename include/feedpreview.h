@@ -0,0 +1,33 @@
+#ifndef NEWSBOAT_FEEDPREVIEW_H_
+#define NEWSBOAT_FEEDPREVIEW_H_
+
+#include <memory>
+#include <string>
+
+namespace newsboat {
+
+class ConfigContainer;
+class RssFeed;
+
+/// \brief Fetching feeds that the user isn't subscribed to, to look at them
+/// before subscribing.
+namespace feedpreview {
+
+/// Fetches and parses the feed at `url`. If `url` points to a web page
+/// rather than a feed, the first feed that the page links to is fetched
+/// instead; the returned feed's `rssurl()` is the URL that was actually
+/// used.
+///
+/// The feed isn't stored in the cache: `is_cached()` is `false` for it, and
+/// it's gone as soon as the last pointer to it is.
+///
+/// Returns a null pointer and sets `error` if there is no feed at `url`.
+std::shared_ptr<RssFeed> fetch(const std::string& url,
+	ConfigContainer* cfg,
+	std::string& error);
+
+} // namespace feedpreview
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_FEEDPREVIEW_H_ */
@@ -0,0 +1,56 @@
+#ifndef NEWSBOAT_FEEDSCHEDULE_H_
+#define NEWSBOAT_FEEDSCHEDULE_H_
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+#include "configactionhandler.h"
+
+namespace newsboat {
+
+namespace feedschedule {
+
+/// \brief Turns the text of a feed's `<ttl>` element, and of the `<hour>`
+/// and `<day>` elements inside `<skipHours>` and `<skipDays>`, into a string
+/// that RssFeed and the cache keep. Nonsensical values are dropped.
+std::string from_elements(const std::string& ttl,
+	const std::vector<std::string>& skip_hours,
+	const std::vector<std::string>& skip_days);
+
+} // namespace feedschedule
+
+/// \brief Decides whether a scheduled reload should fetch a feed, based on
+/// what the feed said about it (see feedschedule::from_elements()).
+///
+/// Handles `ignore-feed-schedule`, which lists feeds whose schedule isn't
+/// honored. fetched() and postpone_reason() can be called from multiple
+/// reload threads at once.
+class FeedScheduler : public ConfigActionHandler {
+public:
+	FeedScheduler();
+	~FeedScheduler() override;
+	void handle_action(const std::string& action,
+		const std::vector<std::string>& params) override;
+	void dump_config(std::vector<std::string>& config_output) const override;
+
+	/// \brief Records that the feed at \a url was fetched just now.
+	void fetched(const std::string& url);
+
+	/// \brief Returns why the feed at \a url with \a schedule shouldn't be
+	/// fetched now, or nullopt if it should. A \a manual reload always
+	/// fetches the feed.
+	nonstd::optional<std::string> postpone_reason(const std::string& url,
+		const std::string& schedule,
+		bool manual);
+
+private:
+	FeedScheduler(const FeedScheduler&) = delete;
+	FeedScheduler& operator=(const FeedScheduler&) = delete;
+
+	void* rs_scheduler = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_FEEDSCHEDULE_H_ */
@@ -1,26 +1,92 @@
 #ifndef NEWSBOAT_FILEURLREADER_H_
 #define NEWSBOAT_FILEURLREADER_H_
 
+#include <cstddef>
 #include <string>
 
+#include "3rd-party/optional.hpp"
 #include "urlreader.h"
 
 namespace newsboat {
 
 class FileUrlReader : public UrlReader {
 public:
-	explicit FileUrlReader(const std::string& file = "");
+	/// \brief Reads the urls file \a file. If \a overlay_file isn't empty,
+	/// it's merged into the urls file, and changes go into it instead (see
+	/// `urls-file-copy-on-write`).
+	explicit FileUrlReader(const std::string& file = "",
+		const std::string& overlay_file = "");
 
+	/// \brief Re-reads the file.
+	///
+	/// If the file can't be parsed, e.g. because a quote isn't closed, the
+	/// URLs and tags that were read before are kept, and get_error() tells
+	/// what's wrong.
 	void reload() override;
 	std::string get_source() override;
 
+	/// \brief Why the last reload() couldn't parse the file, or nullopt if
+	/// it could.
+	nonstd::optional<std::string> get_error() const;
+
+	/// \brief The line of the file that get_error() is about, starting at 1.
+	unsigned int get_error_line() const;
+
 	/// \brief Write URLs back to the input file.
 	///
-	/// This method is used after importing feeds from OPML.
-	void write_config();
+	/// This method is used after importing feeds from OPML. Returns the error
+	/// message if the file couldn't be written; it's left alone in that case.
+	nonstd::optional<std::string> write_config();
+
+	/// \brief Adds a URL at the end of the file, leaving the rest of it as
+	/// is.
+	///
+	/// Returns the error message if the file couldn't be written, e.g.
+	/// because it's read-only.
+	nonstd::optional<std::string> append_url(const std::string& url);
+
+	/// \brief Adds `tag` to the feed `url`, changing only its line.
+	///
+	/// The tags are re-read afterwards. Returns the error message if the feed
+	/// isn't in the file, already has the tag, or the file couldn't be written.
+	nonstd::optional<std::string> add_tag(const std::string& url,
+		const std::string& tag);
+
+	/// \brief Removes `tag` from the feed `url`, like add_tag() adds it.
+	nonstd::optional<std::string> remove_tag(const std::string& url,
+		const std::string& tag);
+
+	/// \brief Renames `old_tag` to `new_tag` on every feed that has it.
+	///
+	/// `renamed` is set to the number of feeds that had `old_tag`. Returns the
+	/// error message if none had it, or the file couldn't be written.
+	nonstd::optional<std::string> rename_tag(const std::string& old_tag,
+		const std::string& new_tag,
+		std::size_t& renamed);
+
+	/// \brief Number of feeds in the file that have `tag`.
+	std::size_t count_feeds_with_tag(const std::string& tag);
 
 private:
+	/// The contents of the file, with the overlay merged in, or nullopt if
+	/// the file couldn't be read.
+	nonstd::optional<std::string> read_contents();
+
+	/// Writes \a contents to the file, or to the overlay if there is one.
+	/// Returns the error message if that failed.
+	nonstd::optional<std::string> write_contents(const std::string& contents);
+
+	/// Applies `edit` to the contents of the file, and writes the result
+	/// back if it returned one. `edit` is a function from the libnewsboat
+	/// FFI: it returns the new contents, or nullptr and sets its error.
+	template<typename Edit>
+	nonstd::optional<std::string> edit_file(Edit edit);
+
 	const std::string filename;
+	const std::string overlay_filename;
+
+	nonstd::optional<std::string> error;
+	unsigned int error_line = 0;
 };
 
 }
@@ -0,0 +1,66 @@
+#ifndef NEWSBOAT_FILTERBUILDER_H_
+#define NEWSBOAT_FILTERBUILDER_H_
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// State of the filter builder dialog, which puts a filter expression
+/// together from conditions picked from lists. The lists, prompts and
+/// validation messages come from the Rust side (see
+/// `rust/libnewsboat/src/filterbuilder.rs`).
+class FilterBuilder {
+public:
+	// These numbers MUST match constants in
+	// rust/libnewsboat-ffi/src/filterbuilder.rs
+	enum class Stage {
+		ATTRIBUTE = 0,
+		OPERATOR = 1,
+		VALUE = 2,
+		NEXT = 3,
+		DONE = 4,
+	};
+
+	/// \a context is "article" for the article list, "feed" for the feed
+	/// list.
+	explicit FilterBuilder(const std::string& context);
+	~FilterBuilder();
+	FilterBuilder(const FilterBuilder&) = delete;
+	FilterBuilder& operator=(const FilterBuilder&) = delete;
+
+	Stage stage() const;
+
+	/// Lines of the list that the user picks from at the current stage.
+	/// They aren't quoted for STFL.
+	std::vector<std::string> items() const;
+
+	/// Heading of the dialog, followed by the expression so far.
+	std::string title() const;
+
+	/// Act on the item at \a index of items().
+	void select(unsigned int index);
+
+	/// Prompt for the value, or an empty string outside of Stage::VALUE.
+	std::string prompt() const;
+
+	/// Check the value and add the condition. If the value is wrong,
+	/// returns an explanation and stays in Stage::VALUE.
+	nonstd::optional<std::string> set_value(const std::string& value);
+
+	/// Go back to picking an operator.
+	void cancel_value();
+
+	/// The filter expression, or nonstd::nullopt if there are no conditions
+	/// yet.
+	nonstd::optional<std::string> expression() const;
+
+private:
+	void* rs_object = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_FILTERBUILDER_H_ */
@@ -0,0 +1,62 @@
+#ifndef NEWSBOAT_FILTERBUILDERFORMACTION_H_
+#define NEWSBOAT_FILTERBUILDERFORMACTION_H_
+
+#include <memory>
+
+#include "filterbuilder.h"
+#include "formaction.h"
+#include "listwidget.h"
+
+namespace newsboat {
+
+/// The filter builder dialog. All it does is show what FilterBuilder tells it
+/// to, and pass the user's choices back.
+class FilterBuilderFormAction : public FormAction {
+public:
+	FilterBuilderFormAction(View*, std::string formstr, ConfigContainer* cfg);
+	~FilterBuilderFormAction() override;
+	void prepare() override;
+	void init() override;
+	KeyMapHintEntry* get_keymap_hint() override;
+	std::string id() const override
+	{
+		return "filterselection";
+	}
+	std::string title() override;
+
+	void finished_qna(Operation op) override;
+
+	/// \a context is "article" or "feed", see FilterBuilder.
+	void set_context(const std::string& ctx)
+	{
+		context = ctx;
+	}
+
+	/// The filter the user applied, or an empty string if the dialog was
+	/// cancelled.
+	std::string get_expression()
+	{
+		return expression;
+	}
+
+private:
+	bool process_operation(Operation op,
+		bool automatic = false,
+		std::vector<std::string>* args = nullptr) override;
+	void start_value_qna();
+
+	bool quit;
+	std::string context;
+	std::string expression;
+	std::unique_ptr<FilterBuilder> builder;
+	/// Whether the user is typing a value.
+	bool entering_value;
+	/// The value typed last, so that it can be corrected if it's wrong.
+	std::string last_value;
+
+	ListWidget items_list;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_FILTERBUILDERFORMACTION_H_ */
@@ -3,6 +3,7 @@
 
 #include <map>
 #include <string>
+#include <vector>
 
 namespace newsboat {
 
@@ -11,8 +12,22 @@ public:
 	FmtStrFormatter();
 	~FmtStrFormatter();
 	void register_fmt(char f, const std::string& value);
+
+	/// Makes values that are cut to the width of their specifier (like
+	/// `%-60t`) end with \a marker, unless it doesn't fit. Empty by default,
+	/// which cuts values without a marker.
+	void set_truncation_marker(const std::string& marker);
 	std::string do_format(const std::string& fmt, unsigned int width = 0);
 
+	/// Like do_format(), but the value of \a key continues on up to
+	/// \a max_rows - 1 further rows, indented to where it starts on the
+	/// first one, instead of being cut off at \a width. Only a top-level
+	/// specifier without padding wraps.
+	std::vector<std::string> do_format_wrapped(const std::string& fmt,
+		unsigned int width,
+		char key,
+		unsigned int max_rows);
+
 private:
 	void* rs_fmt = nullptr;
 };
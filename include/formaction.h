@@ -1,6 +1,7 @@
 #ifndef NEWSBOAT_FORMACTION_H_
 #define NEWSBOAT_FORMACTION_H_
 
+#include <map>
 #include <memory>
 #include <string>
 #include <vector>
@@ -12,6 +13,7 @@
 namespace newsboat {
 
 class ConfigContainer;
+class MemoryReport;
 class RssFeed;
 class View;
 
@@ -72,15 +74,33 @@ public:
 
 	virtual std::string title() = 0;
 
+	/// \brief How long (in milliseconds) the main loop should wait for the
+	/// next event before calling prepare() again.
+	///
+	/// FormActions that have work to do shortly after the user stops
+	/// pressing keys return a shorter timeout.
+	virtual int get_event_timeout()
+	{
+		return 60000;
+	}
+
 	virtual std::vector<std::string> get_suggestions(
 		const std::string& fragment);
 
+	/// Adds this dialog and the articles it shows to \a report. Dialogs
+	/// without articles leave the report alone.
+	virtual void add_to_memory_report(MemoryReport& /* report */) {}
+
 	static void load_histories(const std::string& searchfile,
 		const std::string& cmdlinefile);
 	static void save_histories(const std::string& searchfile,
 		const std::string& cmdlinefile,
 		unsigned int limit);
 
+	/// Asks for the arguments of the parameterized macro bound to \a key,
+	/// one at a time, and runs the macro once all of them are known.
+	void start_macro_argument_qna(const std::string& key);
+
 	std::string bookmark(const std::string& url,
 		const std::string& title,
 		const std::string& description,
@@ -99,6 +119,11 @@ protected:
 	void open_unread_items_in_browser(std::shared_ptr<RssFeed> feed,
 		bool markread);
 
+	/// Tokenizes a commandline with KeyMap::tokenize_cmdline(). If that
+	/// fails, shows the error in the status line and returns nullopt.
+	nonstd::optional<std::vector<std::string>> tokenize_cmdline(
+			const std::string& cmdline);
+
 	View* v;
 	ConfigContainer* cfg;
 	Stfl::Form f;
@@ -113,12 +138,29 @@ protected:
 
 private:
 	std::string prepare_keymap_hint(KeyMapHintEntry* hints);
+	/// Returns false if the command was refused, e.g. because `set` was
+	/// given an invalid value.
+	bool handle_parsed_cmdline(std::vector<std::string> tokens);
+	/// Runs \a cmdline if it's a `run` command, which takes a whole
+	/// operation sequence and thus can't be tokenized like other commands.
+	/// Returns false if it's some other command.
+	bool handle_run_cmdline(const std::string& cmdline);
+	void edit_macro(const std::string& key);
+	void describe_key(const std::string& key, const std::string& context);
+	/// Describes the option or operation \a name, or the key after `key:`.
+	void describe(const std::string& name);
+	void ask_next_macro_argument();
 	void start_next_question();
 
 	std::vector<QnaPair> qna_prompts;
 	Operation finish_operation;
 	History* qna_history;
 	std::shared_ptr<FormAction> parent_formaction;
+
+	std::string macro_key;
+	std::vector<std::string> macro_arguments;
+	/// Input history of each argument slot of parameterized macros.
+	static std::map<unsigned int, History> macro_argument_histories;
 };
 
 } // namespace newsboat
@@ -0,0 +1,37 @@
+#ifndef NEWSBOAT_HELPDATA_H_
+#define NEWSBOAT_HELPDATA_H_
+
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+class KeyMap;
+
+/// Contents of the help dialog opened from a single context: the operations
+/// of that context and the generic ones, grouped into sections. The rows are
+/// filtered and formatted by the Rust side (see
+/// `rust/libnewsboat/src/help.rs`).
+class HelpData {
+public:
+	HelpData(KeyMap& keys, const std::string& context);
+	~HelpData();
+	HelpData(const HelpData&) = delete;
+	HelpData& operator=(const HelpData&) = delete;
+
+	/// Only show rows whose key, operation or description contain \a filter,
+	/// ignoring case.
+	void set_filter(const std::string& filter);
+
+	/// Lines of the dialog, quoted for STFL and at most \a width columns
+	/// wide (zero means there's no limit). Occurrences of the filter are
+	/// wrapped in `<hl>` tags.
+	std::vector<std::string> lines(unsigned int width) const;
+
+private:
+	void* rs_object = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_HELPDATA_H_ */
@@ -1,7 +1,10 @@
 #ifndef NEWSBOAT_HELPFORMACTION_H_
 #define NEWSBOAT_HELPFORMACTION_H_
 
+#include <memory>
+
 #include "formaction.h"
+#include "helpdata.h"
 #include "textviewwidget.h"
 
 namespace newsboat {
@@ -28,9 +31,14 @@ private:
 		std::vector<std::string>* args = nullptr) override;
 	std::string make_colorstring(const std::vector<std::string>& colors);
 	bool quit;
-	bool apply_search;
-	std::string searchphrase;
+	/// Whether the user is typing a filter.
+	bool filtering;
+	std::string filter;
+	/// Filter to go back to if the user cancels typing one.
+	std::string filter_before_edit;
 	std::string context;
+	/// Built the first time the dialog is drawn for the context.
+	std::unique_ptr<HelpData> help_data;
 	TextviewWidget textview;
 };
 
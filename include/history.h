@@ -3,6 +3,8 @@
 
 #include <string>
 
+#include "3rd-party/optional.hpp"
+
 namespace newsboat {
 
 class History {
@@ -12,6 +14,11 @@ public:
 	void add_line(const std::string& line);
 	std::string previous_line();
 	std::string next_line();
+	/// The first line that starts with `prefix`, going back in history from
+	/// the current position like previous_line() does. Doesn't change the
+	/// position.
+	nonstd::optional<std::string> search_prefix(const std::string& prefix)
+	const;
 	void load_from_file(const std::string& file);
 	void save_to_file(const std::string& file, unsigned int limit);
 
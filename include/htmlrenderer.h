@@ -1,11 +1,14 @@
 #ifndef NEWSBOAT_HTMLRENDERER_H_
 #define NEWSBOAT_HTMLRENDERER_H_
 
+#include <cstddef>
 #include <istream>
 #include <map>
+#include <sstream>
 #include <string>
 #include <vector>
 
+#include "tagsouppullparser.h"
 #include "textformatter.h"
 
 namespace newsboat {
@@ -55,7 +58,16 @@ typedef std::pair<std::string, LinkType> LinkPair;
 
 class HtmlRenderer {
 public:
-	explicit HtmlRenderer(bool raw = false);
+	/// \brief How deep blockquotes and lists are nested before further
+	/// levels stop being indented, unless "html-max-nesting" says otherwise.
+	static const unsigned int DEFAULT_MAX_NESTING = 16;
+
+	/// \brief Renders HTML; \a max_nesting is the number of nested
+	/// blockquotes, list items and term descriptions that get indented.
+	/// Deeper ones are rendered at the indentation of the deepest allowed
+	/// level, after a "[nesting truncated]" marker.
+	explicit HtmlRenderer(bool raw = false,
+		unsigned int max_nesting = DEFAULT_MAX_NESTING);
 	void render(const std::string& source,
 		std::vector<std::pair<LineType, std::string>>& lines,
 		std::vector<LinkPair>& links,
@@ -109,8 +121,77 @@ public:
 		std::vector<TableRow> rows;
 	};
 
+	/// \brief A rendering that's in progress, see render_some().
+	class State {
+	public:
+		State(const std::string& source, const std::string& url);
+
+		/// Output so far. Lines can be added to or links seeded before
+		/// the first call to render_some(), just like with render().
+		std::vector<std::pair<LineType, std::string>> lines;
+		std::vector<LinkPair> links;
+
+		bool is_finished() const
+		{
+			return finished;
+		}
+
+		/// Number of elements that were nested too deep to be indented.
+		unsigned int get_truncated_nestings() const
+		{
+			return truncated_nestings;
+		}
+
+	private:
+		friend class HtmlRenderer;
+
+		State(const State&) = delete;
+		State& operator=(const State&) = delete;
+
+		std::istringstream input;
+		TagSoupPullParser xpp;
+		std::string url;
+		bool finished = false;
+
+		unsigned int image_count = 0;
+		unsigned int video_count = 0;
+		unsigned int audio_count = 0;
+		unsigned int source_count = 0;
+		std::string curline;
+		int indent_level = 0;
+		/// Blockquotes, list items and term descriptions that are open,
+		/// including those nested too deep to be indented.
+		unsigned int nesting_depth = 0;
+		unsigned int truncated_nestings = 0;
+		std::vector<HtmlTag> list_elements_stack;
+		bool inside_pre = false;
+		bool itunes_hack = false;
+		size_t inside_script = 0;
+		size_t inside_style = 0;
+		bool inside_video = false;
+		bool inside_audio = false;
+		std::vector<unsigned int> ol_counts;
+		std::vector<char> ol_types;
+		HtmlTag current_tag = HtmlTag::A;
+		int link_num = -1;
+		std::vector<Table> tables;
+	};
+
+	/// \brief Renders at most \a max_nodes more HTML nodes of \a state.
+	///
+	/// Returns true once the whole document is rendered; the output is then
+	/// the same as render() would have produced. Lines only show up in
+	/// \a state once they're complete, and the list of links is only
+	/// added at the end.
+	bool render_some(State& state, std::size_t max_nodes);
+
 private:
 	void prepare_new_line(std::string& line, int indent_level);
+	/// Opens an element that indents its contents by \a indent levels,
+	/// unless it's nested deeper than max_nesting.
+	void enter_nesting(State& state, int indent);
+	/// Closes an element that was opened with enter_nesting().
+	void leave_nesting(State& state, int indent);
 	bool line_is_nonempty(const std::string& line);
 	unsigned int add_link(std::vector<LinkPair>& links,
 		const std::string& link,
@@ -138,6 +219,7 @@ private:
 		const std::string& url, const std::string& media_url,
 		unsigned int media_count, LinkType type);
 	bool raw_;
+	unsigned int max_nesting;
 };
 
 } // namespace newsboat
@@ -0,0 +1,81 @@
+#ifndef NEWSBOAT_INITIALSYNC_H_
+#define NEWSBOAT_INITIALSYNC_H_
+
+#include <chrono>
+#include <cstdint>
+#include <functional>
+#include <string>
+
+#include "3rd-party/optional.hpp"
+#include "rss/feed.h"
+
+namespace newsboat {
+
+/// \brief First sync of a feed from a remote API, which fetches the
+/// articles that are too old to be part of a regular fetch.
+///
+/// The articles are fetched page by page, newest first. Each page is stored
+/// along with the progress of the sync, so an interrupted sync continues
+/// from the last stored page.
+class InitialSync {
+public:
+	/// \brief Fetches up to `limit` articles, newest first, that are older
+	/// than the article `before_id` (if any). Returns nothing if the request
+	/// failed. The articles' GUIDs are their IDs.
+	using FetchPage = std::function<nonstd::optional<rsspp::Feed>(
+			unsigned int limit,
+			nonstd::optional<std::int64_t> before_id)>;
+
+	/// \brief Stores the articles of a page along with the progress, in a
+	/// single transaction.
+	using StorePage = std::function<void(const rsspp::Feed& page,
+			const std::string& progress)>;
+
+	using Sleep = std::function<void(std::chrono::milliseconds)>;
+
+	/// \brief Continues the sync from \a progress, which is what was stored
+	/// along with a page, or starts a new one if \a progress is empty.
+	///
+	/// A page is tried \a attempts times before the sync gives up, with
+	/// growing pauses in between. Pages are \a page_delay apart.
+	InitialSync(unsigned int page_size,
+		unsigned int attempts,
+		std::chrono::milliseconds page_delay,
+		const std::string& progress);
+	~InitialSync();
+
+	/// \brief Fetches and stores pages until the sync is complete, a page
+	/// couldn't be fetched after all attempts, or \a should_stop returns
+	/// true.
+	///
+	/// \a report is called with a status message after each page. Returns
+	/// true if the sync is complete.
+	bool run(const FetchPage& fetch,
+		const StorePage& store,
+		const std::function<bool()>& should_stop,
+		const std::function<void(const std::string&)>& report,
+		const Sleep& sleep = default_sleep);
+
+	bool is_complete() const;
+
+	/// \brief Progress to store when a sync starts.
+	static std::string initial_progress();
+
+	/// \brief Checks if the sync that got as far as \a progress is complete.
+	static bool is_complete(const std::string& progress);
+
+	/// \brief Internationalized summary of the progress.
+	std::string status() const;
+
+private:
+	InitialSync(const InitialSync&) = delete;
+	InitialSync& operator=(const InitialSync&) = delete;
+
+	static void default_sleep(std::chrono::milliseconds duration);
+
+	void* rs_sync = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_INITIALSYNC_H_ */
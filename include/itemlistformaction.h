@@ -5,10 +5,16 @@
 
 #include "3rd-party/optional.hpp"
 
+#include "articlepreview.h"
 #include "history.h"
 #include "listformaction.h"
 #include "listformatter.h"
+#include "listposition.h"
+#include "markreadundo.h"
 #include "regexmanager.h"
+#include "renderjob.h"
+#include "scopedcounts.h"
+#include "selection.h"
 #include "view.h"
 
 namespace newsboat {
@@ -42,11 +48,18 @@ public:
 		return "articlelist";
 	}
 	std::string title() override;
+	void add_to_memory_report(MemoryReport& report) override;
 
 	std::shared_ptr<RssFeed> get_feed()
 	{
 		return feed;
 	}
+
+	/// \brief Articles of this feed that the article view rendered.
+	RenderCache& get_render_cache()
+	{
+		return render_cache;
+	}
 	void set_pos(unsigned int p)
 	{
 		pos = p;
@@ -59,6 +72,8 @@ public:
 	bool jump_to_next_item(bool start_with_first);
 	bool jump_to_previous_item(bool start_with_last);
 	bool jump_to_random_unread_item();
+	bool jump_to_first_unread_item();
+	bool jump_to_last_unread_item();
 
 	void handle_cmdline(const std::string& cmd) override;
 
@@ -77,6 +92,8 @@ public:
 
 	void recalculate_form() override;
 
+	int get_event_timeout() override;
+
 private:
 	void register_format_styles();
 
@@ -96,14 +113,33 @@ private:
 
 	void qna_end_setfilter();
 	void qna_end_editflags();
+	void qna_end_editnote();
 	void qna_start_search();
 
 	void handle_cmdline_num(unsigned int idx);
 
+	/// Goes to the position given by the arguments of `goto-percent`.
+	/// Returns false and shows an error if they're wrong.
+	bool goto_percent(const std::vector<std::string>& arguments);
+
+	/// Moves to the first unread article if \a first is true, and to the
+	/// last one otherwise. Returns false if there is none.
+	bool jump_to_unread_end(bool first);
+
+	/// Says on the status line where the cursor landed.
+	void show_position();
+
 	std::string gen_flags(std::shared_ptr<RssItem> item);
 
 	void prepare_set_filterpos();
 
+	/// Shows or hides the preview pane according to the settings, and
+	/// renders the selected article into it once the selection settles.
+	void update_preview();
+
+	/// Updates the counts for \a item after its state changed.
+	void update_filter_counts(const std::shared_ptr<RssItem>& item);
+
 	void invalidate_everything()
 	{
 		invalidation_mode = InvalidationMode::COMPLETE;
@@ -119,16 +155,59 @@ private:
 		invalidated_itempos.push_back(invalidated_pos);
 	}
 
-	std::string item2formatted_line(const ItemPtrPosPair& item,
+	/// Formats an article for the list. The title takes up to \a max_rows
+	/// rows, so the result can have more than one. \a marker is put in
+	/// front of the first row, and shows whether the article is selected.
+	std::vector<std::string> item2formatted_lines(const ItemPtrPosPair& item,
 		const unsigned int width,
 		const std::string& itemlist_format,
-		const std::string& datetime_format);
+		const std::string& datetime_format,
+		unsigned int max_rows,
+		const std::string& marker);
+
+	/// Stores the states of \a items after an operation changed them, and
+	/// shows \a message. Returns whether the states could be stored.
+	bool store_item_states(const std::vector<std::shared_ptr<RssItem>>& items,
+		bool read_changed,
+		bool flags_changed,
+		const std::string& message);
+	/// `mark-above-read`, or `mark-below-read` if \a below is set, for the
+	/// article at \a itempos.
+	void mark_beside_read(unsigned int itempos, bool below);
+	/// `undo-mark-read`. Returns false if there's nothing to undo.
+	bool undo_mark_read();
+
+	/// The selected articles that are shown, in order.
+	std::vector<std::shared_ptr<RssItem>> selected_items();
+	/// Stores the states of the selected \a items after a batch operation
+	/// changed them, shows \a message, and clears the selection.
+	void store_selection(const std::vector<std::shared_ptr<RssItem>>& items,
+		bool read_changed,
+		bool flags_changed,
+		const std::string& message);
+	/// `toggle-article-read` for the selection; \a state is the argument
+	/// of the operation, if any.
+	void toggle_read_of_selection(const std::string& state);
+	/// `delete-article` for the selection.
+	void delete_selection();
+	/// Enqueues the podcasts of the selection, or of the article at
+	/// \a itempos if there is none.
+	void enqueue_selection(unsigned int itempos);
 
 	unsigned int pos;
 	std::shared_ptr<RssFeed> feed;
 	bool apply_filter;
 	Matcher matcher;
 	std::vector<ItemPtrPosPair> visible_items;
+	/// Articles that match the filter, whether they're shown or not, for
+	/// `%m` and `%r` in the title.
+	ScopedCounts filter_counts;
+	/// Articles that batch operations apply to.
+	Selection selection;
+	/// Unread articles that are shown, for `first-unread` and `last-unread`.
+	UnreadIndex unread_index;
+	/// Articles that `mark-above-read` and `mark-below-read` marked read.
+	MarkReadUndo mark_read_undo;
 	bool show_searchresult;
 	std::string search_phrase;
 
@@ -149,6 +228,9 @@ private:
 	std::vector<unsigned int> invalidated_itempos;
 
 	ListFormatter listfmt;
+	ArticlePreview preview;
+	RenderCache render_cache;
+	std::pair<unsigned int, unsigned int> old_preview_size;
 	Cache* rsscache;
 	FilterContainer& filters;
 
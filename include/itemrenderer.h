@@ -6,6 +6,7 @@
 #include <vector>
 
 #include "htmlrenderer.h"
+#include "renderjob.h"
 #include "textformatter.h"
 
 namespace newsboat {
@@ -27,6 +28,34 @@ std::string to_plain_text(
 	ConfigContainer& cfg,
 	std::shared_ptr<RssItem> item);
 
+/// \brief Returns the RssItem as it's sent to a command by `pipe-to`.
+///
+/// The text is rendered like in to_plain_text(), but it's always UTF-8,
+/// and it starts with a fixed header block (Title, Author, Date and Link,
+/// followed by an empty line) that scripts can parse.
+std::string to_piped_text(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item);
+
+/// \brief Returns the same header block as to_piped_text(), followed by the
+/// RssItem's original HTML (for `pipe-to --raw-html`).
+std::string to_piped_html(std::shared_ptr<RssItem> item);
+
+/// \brief Splits the `--raw-html` flag off a `pipe-to` command.
+///
+/// Returns the command to run, and sets \a raw_html to whether the flag was
+/// given.
+std::string split_pipe_command(const std::string& input, bool& raw_html);
+
+/// \brief Returns plain-text representation of the RssItem for the preview
+/// pane.
+///
+/// Like to_plain_text(), but lines aren't wrapped: the preview does that
+/// itself, at the width of the pane.
+std::string to_preview_text(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item);
+
 /// \brief Returns RssItem as STFL list.
 ///
 /// `html-renderer` settings controls what tool is used to render HTML. \a
@@ -44,6 +73,29 @@ std::pair<std::string, size_t> to_stfl_list(
 	const std::string& location,
 	std::vector<LinkPair>& links);
 
+/// \brief Starts rendering the RssItem's body for the article view, a unit
+/// of work at a time.
+///
+/// \a links are put in front of the links found in the article. Returns
+/// nullptr if `html-renderer` is an external program, which can't be
+/// interrupted; to_stfl_list() has to be used then.
+std::unique_ptr<RenderJob> start_rendering(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item,
+	std::vector<LinkPair> links,
+	unsigned int screen_height);
+
+/// \brief Returns RssItem as STFL list, like to_stfl_list(), but with a body
+/// that was rendered already, by a RenderJob or into a RenderCache.
+std::pair<std::string, size_t> rendered_to_stfl_list(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item,
+	const std::vector<std::pair<LineType, std::string>>& body,
+	unsigned int text_width,
+	unsigned int window_width,
+	RegexManager* rxman,
+	const std::string& location);
+
 /// \brief Returns RssItem's text source as STFL list.
 ///
 /// \a text_width dictates where text is wrapped. \a window_width dictates
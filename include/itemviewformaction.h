@@ -3,7 +3,9 @@
 
 #include "formaction.h"
 #include "htmlrenderer.h"
+#include "markreadpolicy.h"
 #include "regexmanager.h"
+#include "renderjob.h"
 #include "textformatter.h"
 #include "textviewwidget.h"
 
@@ -24,6 +26,7 @@ public:
 	~ItemViewFormAction() override;
 	void prepare() override;
 	void init() override;
+	int get_event_timeout() override;
 	void set_guid(const std::string& guid_)
 	{
 		guid = guid_;
@@ -41,6 +44,7 @@ public:
 		return "article";
 	}
 	std::string title() override;
+	void add_to_memory_report(MemoryReport& report) override;
 
 	void finished_qna(Operation op) override;
 
@@ -68,8 +72,26 @@ private:
 	void render_source(std::vector<std::pair<LineType, std::string>>& lines,
 		std::string source);
 
+	unsigned int get_text_width();
+
+	/// \brief Shows the article from the cache, or starts rendering it.
+	void render_article();
+	/// \brief Renders the next unit of the article, and shows what's ready.
+	void continue_rendering();
+	void cancel_rendering();
+	/// \brief Shows the header followed by \a body. \a more_to_come adds
+	/// an indicator that the rest of the article is still being rendered.
+	void show_article(const std::vector<std::pair<LineType, std::string>>&
+		body,
+		bool more_to_come);
+
 	void do_search();
 
+	/// \brief Marks the article read if `mark-read-on` says that \a trigger
+	/// should do so.
+	void mark_read_on(MarkReadPolicy::Trigger trigger);
+	void mark_item_read();
+
 	std::string guid;
 	std::shared_ptr<RssFeed> feed;
 	std::shared_ptr<RssItem> item;
@@ -82,6 +104,9 @@ private:
 	bool in_search;
 	Cache* rsscache;
 	TextviewWidget textview;
+	MarkReadPolicy mark_read_policy;
+	DwellTimer dwell_timer;
+	std::unique_ptr<RenderJob> render_job;
 };
 
 } // namespace newsboat
@@ -2,10 +2,12 @@
 #define NEWSBOAT_KEYMAP_H_
 
 #include <map>
+#include <set>
 #include <string>
 #include <utility>
 #include <vector>
 
+#include "3rd-party/optional.hpp"
 #include "configparser.h"
 
 // in configuration: bind-key <key> <operation>
@@ -23,6 +25,9 @@ enum { KM_FEEDLIST = 1 << 0,
 	KM_DIRBROWSER = 1 << 10,
 	KM_SYSKEYS = 1 << 11,
 	KM_INTERNAL = 1 << 12,
+	/// Not a context: the operation can take a while, so View shows that
+	/// it's running (see LongOperation).
+	KM_LONG = 1 << 13,
 	KM_NEWSBOAT = KM_FEEDLIST | KM_FILEBROWSER | KM_HELP | KM_ARTICLELIST |
 		KM_ARTICLE | KM_TAGSELECT | KM_FILTERSELECT | KM_URLVIEW |
 		KM_DIALOGS | KM_DIRBROWSER,
@@ -31,6 +36,8 @@ enum { KM_FEEDLIST = 1 << 0,
 
 namespace newsboat {
 
+class ConfigContainer;
+
 enum Operation {
 	OP_NIL = 0,
 	// general and newsboat-specific operations:
@@ -39,9 +46,14 @@ enum Operation {
 	OP_HARDQUIT,
 	OP_RELOAD,
 	OP_RELOADALL,
+	OP_RELOADTAG,
+	OP_CANCEL_RELOAD,
 	OP_MARKFEEDREAD,
 	OP_MARKALLFEEDSREAD,
 	OP_MARKALLABOVEASREAD,
+	OP_MARKABOVEREAD,
+	OP_MARKBELOWREAD,
+	OP_UNDOMARKREAD,
 	OP_OPEN,
 	OP_SWITCH_FOCUS,
 	OP_SAVE,
@@ -54,13 +66,18 @@ enum Operation {
 	OP_OPENBROWSER_AND_MARK,
 	OP_OPENALLUNREADINBROWSER,
 	OP_OPENALLUNREADINBROWSER_AND_MARK,
+	OP_OPENINBROWSER_NONINTERACTIVELY,
 	OP_HELP,
 	OP_TOGGLESOURCEVIEW,
 	OP_TOGGLEITEMREAD,
 	OP_TOGGLESHOWREAD,
+	OP_TOGGLE_PREVIEW,
 	OP_SHOWURLS,
 	OP_CLEARTAG,
 	OP_SETTAG,
+	OP_ADD_TAG,
+	OP_REMOVE_TAG,
+	OP_RENAME_TAG,
 	OP_SEARCH,
 	OP_GOTO_URL,
 	OP_ENQUEUE,
@@ -69,26 +86,45 @@ enum Operation {
 	OP_SETFILTER,
 	OP_CLEARFILTER,
 	OP_SELECTFILTER,
+	OP_BUILDFILTER,
 	OP_RELOADURLS,
 	OP_BOOKMARK,
 	OP_EDITFLAGS,
+	OP_EDITNOTE,
+	OP_EDITNOTE_IN_EDITOR,
 	OP_NEXTUNREADFEED,
 	OP_PREVUNREADFEED,
+	OP_NEXTFEEDWITHUNREAD,
+	OP_PREVFEEDWITHUNREAD,
 	OP_NEXTFEED,
 	OP_PREVFEED,
 	OP_MACROPREFIX,
+	OP_EDIT_MACRO,
+	OP_DESCRIBE_KEY,
+	OP_DESCRIBE,
 	OP_DELETE,
 	OP_DELETE_ALL,
 	OP_PURGE_DELETED,
 	OP_EDIT_URLS,
+	OP_PREVIEW_FEED,
+	OP_SUBSCRIBE,
 	OP_CLOSEDIALOG,
 	OP_VIEWDIALOGS,
 	OP_NEXTDIALOG,
 	OP_PREVDIALOG,
+	OP_POP_TO_FEEDLIST,
+	OP_CLOSE_ALL_DIALOGS,
 	OP_PIPE_TO,
 	OP_RANDOMUNREAD,
 	OP_SORT,
 	OP_REVSORT,
+	OP_TOGGLE_SELECT,
+	OP_SELECT_RANGE,
+	OP_FIRST_UNREAD,
+	OP_LAST_UNREAD,
+	OP_GOTO_PERCENT,
+	OP_DEFAULT_ACTION_PRIMARY,
+	OP_DEFAULT_ACTION_SECONDARY,
 	OP_NB_MAX,
 
 	// podboat-specific operations:
@@ -121,11 +157,19 @@ enum Operation {
 	OP_INT_END_SETFILTER,
 	OP_INT_BM_END,
 	OP_INT_EDITFLAGS_END,
+	OP_INT_EDITNOTE_END,
 	OP_INT_START_SEARCH,
+	OP_INT_END_PREVIEW_FEED,
+	OP_INT_END_ADD_TAG,
+	OP_INT_END_REMOVE_TAG,
+	OP_INT_END_RENAME_TAG,
+	OP_INT_END_FILTER_VALUE,
 
 	OP_INT_GOTO_URL,
+	OP_INT_GOTO_PERCENT,
 
 	OP_INT_END_QUESTION,
+	OP_INT_END_MACRO_ARGUMENT,
 	OP_INT_CANCEL_QNA,
 	OP_INT_QNA_NEXTHIST,
 	OP_INT_QNA_PREVHIST,
@@ -156,17 +200,37 @@ enum Operation {
 	OP_CMD_START_9,
 };
 
+/// Section of the help dialog in which an operation is listed.
+// Numbers here MUST match constants in rust/libnewsboat-ffi/src/help.rs
+enum class OpCategory {
+	NAVIGATION = 0,
+	FEEDS = 1,
+	ARTICLES = 2,
+	MACROS = 3,
+	OTHER = 4,
+};
+
 struct KeyMapDesc {
 	std::string key;
 	std::string cmd;
 	std::string desc;
 	std::string ctx;
 	unsigned short flags;
+	OpCategory category;
+};
+
+/// When an operation of a macro runs, depending on how it's connected to the
+/// previous one.
+enum class MacroCondition {
+	ALWAYS, ///< `;`, or the first operation
+	IF_SUCCEEDED, ///< `&&`
+	IF_FAILED, ///< `||`
 };
 
 struct MacroCmd {
 	Operation op;
 	std::vector<std::string> args;
+	MacroCondition condition = MacroCondition::ALWAYS;
 };
 
 class KeyMap : public ConfigActionHandler {
@@ -179,27 +243,136 @@ public:
 	void unset_key(const std::string& key, const std::string& context);
 	void unset_all_keys(const std::string& context);
 	Operation get_opcode(const std::string& opstr);
+	std::string getopname(Operation op) const;
+
+	/// Whether \a op is expected to take a while, see KM_LONG.
+	bool is_long_operation(Operation op) const;
 	Operation get_operation(const std::string& keycode,
 		const std::string& context);
+
+	/// If \a op is `default-action-primary` or `default-action-secondary`,
+	/// returns the operation it stands for in \a context (OP_NIL if none);
+	/// otherwise returns \a op.
+	Operation resolve_default_action(Operation op, const std::string& context);
+
+	/// Makes the default actions stand for the operations set with options
+	/// like `articlelist-primary-action`. Throws ConfigException if one of
+	/// them doesn't work in its context, after applying the others.
+	void apply_default_actions(const ConfigContainer& cfg);
 	std::vector<MacroCmd> get_macro(const std::string& key);
+
+	/// Number of arguments taken by the macro bound to \a key; zero for
+	/// macros defined with `macro` rather than `macro-with-args`.
+	unsigned int get_macro_parameter_count(const std::string& key) const;
+
+	/// Returns the macro bound to \a key, with \a arguments substituted
+	/// for its `%1`..`%9` and `%0` placeholders.
+	std::vector<MacroCmd> get_macro(const std::string& key,
+		const std::vector<std::string>& arguments);
+
+	/// Returns the prompt for the next argument of a macro that takes
+	/// \a count arguments, \a supplied of which are already known, or
+	/// nullopt if none are missing.
+	static nonstd::optional<std::string> next_macro_argument_prompt(
+		unsigned int count,
+		unsigned int supplied);
 	char get_key(const std::string& keycode);
 	std::vector<std::string> get_keys(Operation op, const std::string& context);
 	void handle_action(const std::string& action,
 		const std::string& params) override;
+	void set_origin(const std::string& file, unsigned int line) override;
 	void dump_config(std::vector<std::string>& config_output) const override;
+
+	/// Warnings about bindings and macros from the config that replaced
+	/// ones defined elsewhere, naming both places, and about ones that will
+	/// never trigger because their operations don't work where they're
+	/// used.
+	std::vector<std::string> get_warnings() const;
+
+	/// Describes the operation bound to \a key in \a context, and the file
+	/// and line of the command that bound it. Throws
+	/// ConfigHandlerException if \a key isn't a key name.
+	std::string describe_key(const std::string& key,
+		const std::string& context) const;
+
+	/// Describes what operation \a name does, the contexts it works in,
+	/// the arguments it takes and the keys it's bound to. Returns nullopt
+	/// if there's no such operation.
+	nonstd::optional<std::string> describe_operation(const std::string& name);
+
+	/// Names of the operations that can be bound to keys and used in
+	/// macros.
+	std::vector<std::string> get_operation_names() const;
 	std::vector<KeyMapDesc> get_keymap_descriptions(std::string context);
 
 	std::vector<MacroCmd> parse_operation_sequence(const std::string& line);
 	std::vector<MacroCmd> get_startup_operation_sequence();
 
+	/// Returns a `run` commandline that executes the macro bound to
+	/// \a key. Returns nullopt and puts a message listing the defined
+	/// macros into `error` if there's no such macro.
+	nonstd::optional<std::string> get_macro_cmdline(const std::string& key,
+		std::string& error) const;
+
+	/// If \a line is a `run` command, returns the operation sequence it
+	/// runs. Throws ConfigHandlerException if the sequence contains an
+	/// invalid operation, just like parse_operation_sequence().
+	nonstd::optional<std::vector<MacroCmd>> parse_run_cmdline(
+			const std::string& line);
+
+	/// Splits a line entered into the internal commandline into tokens,
+	/// using the same quoting rules as macros. Returns nullopt and puts
+	/// a message (with the offending column highlighted) into `error` if
+	/// the line couldn't be parsed.
+	static nonstd::optional<std::vector<std::string>> tokenize_cmdline(
+			const std::string& line,
+			std::string& error);
+
 private:
-	bool is_valid_context(const std::string& context);
+	KeyMap(const KeyMap&) = delete;
+	KeyMap& operator=(const KeyMap&) = delete;
+
 	unsigned short get_flag_from_context(const std::string& context);
 	std::map<std::string, Operation> get_internal_operations() const;
-	std::string getopname(Operation op) const;
+	std::vector<MacroCmd> to_macro_cmds(
+		const std::vector<std::vector<std::string>>& tokenized);
+	/// Converts \a cmds into an operation sequence owned by Rust, which
+	/// the caller has to free.
+	void* to_operation_sequence(const std::vector<MacroCmd>& cmds) const;
+
+	/// Returns the canonical spelling of the key name used in a config
+	/// command. Warns (once per spelling) if \a key is written in some
+	/// other way, and throws ConfigHandlerException if it's not a key name
+	/// at all.
+	std::string canonicalize_key_name(const std::string& key);
+
+	/// Records that the command being handled bound \a key in \a context.
+	void record_binding(const std::string& key,
+		const std::string& context,
+		Operation op);
+	void record_macro(const std::string& key,
+		const std::vector<MacroCmd>& cmds);
+	/// Appends a comment with the origin to a line of dump_config() output.
+	void append_origin(std::string& configline,
+		const nonstd::optional<std::string>& origin) const;
+	nonstd::optional<std::string> binding_origin(const std::string& context,
+		const std::string& key) const;
+	nonstd::optional<std::string> macro_origin(const std::string& key) const;
+
 	std::map<std::string, std::map<std::string, Operation>> keymap_;
 	std::map<std::string, std::vector<MacroCmd>> macros_;
+	/// Number of arguments of each macro defined with `macro-with-args`.
+	std::map<std::string, unsigned int> macro_parameters_;
+	/// Descriptions given after `--` in the definitions of macros.
+	std::map<std::string, std::string> macro_descriptions_;
 	std::vector<MacroCmd> startup_operations_sequence;
+	std::set<std::string> warned_key_names;
+	/// File and line of the config command that's being handled.
+	std::string origin_file;
+	unsigned int origin_line = 0;
+	std::vector<std::string> warnings;
+	void* rs_origins = nullptr;
+	void* rs_default_actions = nullptr;
 };
 
 } // namespace newsboat
@@ -2,6 +2,7 @@
 #define NEWSBOAT_LISTFORMATTER_H_
 
 #include <climits>
+#include <cstdint>
 #include <string>
 #include <utility>
 #include <vector>
@@ -17,18 +18,27 @@ public:
 	void add_line(const std::string& text);
 	void set_line(const unsigned int itempos,
 		const std::string& text);
+
+	/// Adds an item that's shown on several rows.
+	void add_lines(const std::vector<std::string>& rows);
+	void set_lines(const unsigned int itempos,
+		const std::vector<std::string>& rows);
 	void clear()
 	{
-		lines.clear();
+		items.clear();
 	}
 	std::string format_list() const;
-	unsigned int get_lines_count() const
-	{
-		return lines.size();
-	}
+
+	/// Number of rows, which is more than the number of items if some of
+	/// them take up several rows.
+	unsigned int get_lines_count() const;
+
+	/// Number of rows of each item.
+	std::vector<std::uint32_t> get_row_counts() const;
 
 private:
-	std::vector<std::string> lines;
+	/// Rows of each item.
+	std::vector<std::vector<std::string>> items;
 	RegexManager* rxman;
 	std::string location;
 };
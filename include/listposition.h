@@ -0,0 +1,61 @@
+#ifndef NEWSBOAT_LISTPOSITION_H_
+#define NEWSBOAT_LISTPOSITION_H_
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// \brief Positions of the unread entries of a list, for `first-unread`,
+/// `last-unread`, `mark-above-read` and `mark-below-read`.
+///
+/// Entries are updated one by one when their state changes, so the jumps
+/// don't have to look at every entry of the list.
+class UnreadIndex {
+public:
+	UnreadIndex();
+	~UnreadIndex();
+	UnreadIndex(const UnreadIndex&) = delete;
+	UnreadIndex& operator=(const UnreadIndex&) = delete;
+
+	/// Sets the entries of the list, in the order in which they're shown:
+	/// their \a keys, and whether each of them is \a unread.
+	void reset(const std::vector<std::string>& keys,
+		const std::vector<bool>& unread);
+
+	/// Records whether the entry \a key is unread now.
+	void update(const std::string& key, bool unread);
+
+	/// Position of the first unread entry, if there is one.
+	nonstd::optional<unsigned int> first() const;
+
+	/// Position of the last unread entry, if there is one.
+	nonstd::optional<unsigned int> last() const;
+
+	/// Positions of the unread entries above \a cursor, or below it if
+	/// \a below is set, in the order in which they're shown. The entry at
+	/// \a cursor itself is never included.
+	std::vector<std::size_t> unread_beside(std::size_t cursor,
+		bool below) const;
+
+private:
+	void* rs_index = nullptr;
+};
+
+/// \brief Position that is \a percent of the way through a list of \a size
+/// entries, or nothing if the list is empty.
+nonstd::optional<unsigned int> percent_position(unsigned int percent,
+	unsigned int size);
+
+/// \brief Parses the arguments of `goto-percent`. On failure, returns nothing
+/// and sets \a error to the message.
+nonstd::optional<unsigned int> parse_percent(
+	const std::vector<std::string>& arguments,
+	std::string& error);
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_LISTPOSITION_H_ */
@@ -14,6 +14,7 @@ class ListWidget {
 public:
 	ListWidget(const std::string& list_name, Stfl::Form& form,
 		std::uint32_t scrolloff);
+	~ListWidget();
 	void stfl_replace_list(std::uint32_t number_of_lines, std::string stfl);
 	void stfl_replace_lines(const ListFormatter& listfmt);
 
@@ -24,21 +25,32 @@ public:
 	void move_page_up(bool wrap_scroll);
 	void move_page_down(bool wrap_scroll);
 
+	/// Index of the item under the cursor. If some items take up several
+	/// rows, that's not the same as the row of the cursor.
 	std::uint32_t get_position();
 	void set_position(std::uint32_t pos);
 
 	std::uint32_t get_width();
 	std::uint32_t get_height();
 private:
+	ListWidget(const ListWidget&) = delete;
+	ListWidget& operator=(const ListWidget&) = delete;
+
 	std::uint32_t get_scroll_offset();
 	void set_scroll_offset(std::uint32_t pos);
 
-	void update_scroll_offset(std::uint32_t pos);
+	/// Scrolls so that the rows from \a first_row to \a last_row are
+	/// visible, along with the context lines around them.
+	void update_scroll_offset(std::uint32_t first_row, std::uint32_t last_row);
+
+	std::uint32_t get_item_count();
 
 	const std::string list_name;
 	Stfl::Form& form;
 	std::uint32_t num_lines;
 	std::uint32_t num_context_lines;
+	/// Which rows belong to which item.
+	void* rs_row_groups = nullptr;
 };
 
 } // namespace newsboat
@@ -1,6 +1,10 @@
 #ifndef NEWSBOAT_LOGGER_H_
 #define NEWSBOAT_LOGGER_H_
 
+#include <string>
+#include <utility>
+#include <vector>
+
 #include "config.h"
 #include "strprintf.h"
 
@@ -9,8 +13,8 @@ enum class Level;
 }
 
 extern "C" {
-	uint64_t rs_get_loglevel();
-	void rs_log(newsboat::Level level, const char* message);
+	bool rs_log_enabled(const char* module, newsboat::Level level);
+	void rs_log(const char* module, newsboat::Level level, const char* message);
 }
 
 namespace newsboat {
@@ -22,13 +26,24 @@ namespace Logger {
 void set_logfile(const std::string& logfile);
 void set_user_error_logfile(const std::string& logfile);
 void set_loglevel(Level l);
+/// Gives each module a level that's used instead of the one from
+/// set_loglevel(). A module is named after its source file, e.g. "reloader".
+void set_module_levels(const std::vector<std::pair<std::string, Level>>&
+	levels);
+/// Logfiles that are about to grow beyond `bytes` are rotated; zero means
+/// never.
+void set_max_size(uint64_t bytes);
+/// Number of rotated logfiles to keep next to each log.
+void set_keep_files(std::size_t n);
 
+/// Logs a message from `module`, which is the source file it comes from (see
+/// the LOG macro). The message is only formatted if it's going to be written.
 template<typename... Args>
-void log(Level l, const std::string& format, Args... args)
+void log(const char* module, Level l, const std::string& format,
+	Args... args)
 {
-	if (l == Level::USERERROR
-		|| static_cast<uint64_t>(l) <= rs_get_loglevel()) {
-		rs_log(l, strprintf::fmt(format, args...).c_str());
+	if (rs_log_enabled(module, l)) {
+		rs_log(module, l, strprintf::fmt(format, args...).c_str());
 	}
 }
 };
@@ -41,9 +56,9 @@ void log(Level l, const std::string& format, Args... args)
 	do {        \
 	} while (0)
 #else
-#define LOG(x, ...)                                        \
-	do {                                               \
-		newsboat::Logger::log(x, __VA_ARGS__); \
+#define LOG(x, ...)                                                \
+	do {                                                       \
+		newsboat::Logger::log(__FILE__, x, __VA_ARGS__); \
 	} while (0)
 #endif
 
@@ -0,0 +1,37 @@
+#ifndef NEWSBOAT_LONGOPERATION_H_
+#define NEWSBOAT_LONGOPERATION_H_
+
+#include <string>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// \brief Feedback for an operation that's expected to take a while (see
+/// KM_LONG).
+///
+/// While the operation runs, status() describes it for the status line. Once
+/// it's done, finish() writes it to the log, unless it turned out to be fast.
+class LongOperation {
+public:
+	explicit LongOperation(const std::string& name);
+	~LongOperation();
+	LongOperation(const LongOperation&) = delete;
+	LongOperation& operator=(const LongOperation&) = delete;
+
+	/// A spinner followed by the name of the operation.
+	std::string status() const;
+
+	/// Ends the operation. Returns a summary to notify the user with if it
+	/// took longer than \a notify_threshold_s seconds (0 turns
+	/// notifications off). Does nothing if called again.
+	nonstd::optional<std::string> finish(bool succeeded,
+		unsigned int notify_threshold_s);
+
+private:
+	void* rs_object = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_LONGOPERATION_H_ */
@@ -0,0 +1,80 @@
+#ifndef NEWSBOAT_MARKREADPOLICY_H_
+#define NEWSBOAT_MARKREADPOLICY_H_
+
+#include <cstdint>
+#include <string>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// \brief Decides when the article that's being read becomes read, as
+/// configured by `mark-read-on`.
+class MarkReadPolicy {
+public:
+	/// Must be kept in sync with `libnewsboat::markread::Trigger`.
+	enum class Trigger : std::uint8_t { OPEN = 0, DWELL, SCROLL_END, NEXT };
+
+	struct Context {
+		/// \brief How long the article has been shown, in milliseconds.
+		std::uint64_t dwelled_ms = 0;
+		/// \brief Whether the last line of the article is on the screen.
+		bool at_end = false;
+	};
+
+	/// \brief Returns the reason why \a value is not a valid
+	/// `mark-read-on`, or nothing if it's valid.
+	static nonstd::optional<std::string> validate(const std::string& value);
+
+	/// \brief Creates the default policy, which marks articles read when
+	/// they're opened.
+	MarkReadPolicy();
+	~MarkReadPolicy();
+
+	/// \brief Replaces the policy with the one described by \a value.
+	///
+	/// Returns false, and keeps the policy as it was, if \a value is
+	/// invalid.
+	bool set(const std::string& value);
+
+	/// \brief Milliseconds an article has to be shown to become read, or -1
+	/// if `dwell` is disabled.
+	std::int64_t dwell_ms();
+
+	bool should_mark_read(Trigger trigger, const Context& context);
+
+private:
+	MarkReadPolicy(const MarkReadPolicy&) = delete;
+	MarkReadPolicy& operator=(const MarkReadPolicy&) = delete;
+
+	void* rs_policy = nullptr;
+};
+
+/// \brief Measures how long the current article has been shown.
+class DwellTimer {
+public:
+	DwellTimer();
+	~DwellTimer();
+
+	/// \brief Starts measuring anew. A negative \a threshold_ms means that
+	/// the article doesn't become read after some time.
+	void start(std::int64_t threshold_ms);
+	void cancel();
+
+	/// \brief Milliseconds since start(), or 0 if the timer isn't running.
+	std::uint64_t elapsed_ms();
+
+	/// \brief Milliseconds until the threshold is reached, or -1 if the
+	/// timer isn't running or has no threshold.
+	std::int64_t time_left_ms();
+
+private:
+	DwellTimer(const DwellTimer&) = delete;
+	DwellTimer& operator=(const DwellTimer&) = delete;
+
+	void* rs_timer = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_MARKREADPOLICY_H_ */
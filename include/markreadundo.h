@@ -0,0 +1,38 @@
+#ifndef NEWSBOAT_MARKREADUNDO_H_
+#define NEWSBOAT_MARKREADUNDO_H_
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// \brief Undo for `mark-above-read` and `mark-below-read`.
+///
+/// Each of them records the GUIDs of the articles it marked read;
+/// `undo-mark-read` makes the most recently recorded ones unread again.
+class MarkReadUndo {
+public:
+	MarkReadUndo();
+	~MarkReadUndo();
+	MarkReadUndo(const MarkReadUndo&) = delete;
+	MarkReadUndo& operator=(const MarkReadUndo&) = delete;
+
+	/// Records that the articles \a guids were marked read. Empty records
+	/// are ignored.
+	void record(const std::vector<std::string>& guids);
+
+	/// GUIDs of the articles that the most recent operation marked read,
+	/// which is forgotten, or nothing if there's nothing left to undo.
+	nonstd::optional<std::vector<std::string>> undo();
+
+	void clear();
+
+private:
+	void* rs_undo = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_MARKREADUNDO_H_ */
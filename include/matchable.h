@@ -1,6 +1,7 @@
 #ifndef NEWSBOAT_MATCHABLE_H_
 #define NEWSBOAT_MATCHABLE_H_
 
+#include <ctime>
 #include <string>
 
 #include "3rd-party/optional.hpp"
@@ -14,6 +15,15 @@ public:
 	virtual nonstd::optional<std::string> attribute_value(const std::string& attr)
 	const =
 		0;
+
+	/// Returns the date attribute \a attr as seconds since the Unix epoch,
+	/// or nonstd::nullopt if there is no such attribute. Relative dates in
+	/// filters, like `date > "-7 days"`, are compared against this.
+	virtual nonstd::optional<time_t> date_timestamp(
+		const std::string& /* attr */) const
+	{
+		return nonstd::nullopt;
+	}
 };
 
 } // namespace newsboat
@@ -1,39 +1,33 @@
 #ifndef NEWSBOAT_MATCHER_H_
 #define NEWSBOAT_MATCHER_H_
 
-#include "FilterParser.h"
+#include <string>
 
 namespace newsboat {
 
 class Matchable;
 
+/// Checks if a filter expression is true for a feed or an article. Parsing
+/// and matching happen on the Rust side (see
+/// `rust/libnewsboat/src/matcher.rs`).
 class Matcher {
 public:
-	Matcher();
+	Matcher() = default;
 	explicit Matcher(const std::string& expr);
+	~Matcher();
+	Matcher(const Matcher&) = delete;
+	Matcher& operator=(const Matcher&) = delete;
+
 	bool parse(const std::string& expr);
+
+	/// Throws MatcherException if \a item lacks an attribute that the
+	/// expression uses, or if a regular expression in it is invalid.
 	bool matches(Matchable* item);
 	std::string get_parse_error();
 	std::string get_expression();
 
-	/// Convert numerical prefix of the string to an `int`.
-	///
-	/// Return 0 if there is no numeric prefix. On underflow, return `int`'s
-	/// minimum. On overflow, return `int`'s maximum.
-	// This is made public so it can be tested. DON'T USE OUTSIDE OF MATCHER.
-	static int string_to_num(const std::string& number);
-
 private:
-	bool matches_r(expression* e, Matchable* item);
-
-	bool matchop_lt(expression* e, Matchable* item);
-	bool matchop_gt(expression* e, Matchable* item);
-	bool matchop_rxeq(expression* e, Matchable* item);
-	bool matchop_cont(expression* e, Matchable* item);
-	bool matchop_eq(expression* e, Matchable* item);
-	bool matchop_between(expression* e, Matchable* item);
-
-	FilterParser p;
+	void* rs_object = nullptr;
 	std::string errmsg;
 	std::string exp;
 };
@@ -0,0 +1,42 @@
+#ifndef NEWSBOAT_MEMORYREPORT_H_
+#define NEWSBOAT_MEMORYREPORT_H_
+
+#include <memory>
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+class RssItem;
+
+/// \brief How much memory the items of the open dialogs take.
+///
+/// Dialogs share their items through `std::shared_ptr`, so an article that
+/// shows up in a feed, a query feed and a search exists only once. The report
+/// counts it towards each of these dialogs, but only once towards the total.
+class MemoryReport {
+public:
+	MemoryReport();
+	~MemoryReport();
+	MemoryReport(const MemoryReport&) = delete;
+	MemoryReport& operator=(const MemoryReport&) = delete;
+
+	/// Starts a new dialog. The items added after this belong to it.
+	void add_dialog(const std::string& title);
+
+	/// Records that the last dialog shows \a item.
+	void add_item(const std::shared_ptr<RssItem>& item);
+
+	/// Number of items in all dialogs, counting each item once.
+	unsigned int distinct_items() const;
+
+	/// One line per dialog, and one with the total.
+	std::vector<std::string> lines() const;
+
+private:
+	void* rs_report = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_MEMORYREPORT_H_ */
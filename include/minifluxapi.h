@@ -1,7 +1,10 @@
 #ifndef NEWSBOAT_MINIFLUXAPI_H_
 #define NEWSBOAT_MINIFLUXAPI_H_
 
+#include <cstdint>
+
 #include "3rd-party/json.hpp"
+#include "3rd-party/optional.hpp"
 #include "remoteapi.h"
 #include "rss/feed.h"
 #include "utils.h"
@@ -23,12 +26,21 @@ public:
 		const std::string& guid) override;
 	void add_custom_headers(curl_slist**) override;
 	rsspp::Feed fetch_feed(const std::string& id, CURL* cached_handle);
+	/// \brief Fetches up to \a limit entries of the feed, newest first,
+	/// that are older than the entry \a before_id (if any).
+	///
+	/// Returns nothing if the request failed.
+	nonstd::optional<rsspp::Feed> fetch_feed_page(const std::string& id,
+		unsigned int limit,
+		nonstd::optional<std::int64_t> before_id,
+		CURL* cached_handle);
 
 private:
 	virtual nlohmann::json run_op(const std::string& path,
 		const nlohmann::json& req_data,
 		const HTTPMethod method = HTTPMethod::GET,
 		CURL* cached_handle = nullptr);
+	void fill_items(rsspp::Feed& feed, const nlohmann::json& entries);
 	TaggedFeedUrl feed_from_json(const nlohmann::json& jfeed,
 		const std::vector<std::string>& tags);
 	bool flag_changed(const std::string& oldflags,
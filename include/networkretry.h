@@ -0,0 +1,58 @@
+#ifndef NEWSBOAT_NETWORKRETRY_H_
+#define NEWSBOAT_NETWORKRETRY_H_
+
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+/// \brief Reloads feeds that failed with network errors again once the network
+/// is back, e.g. after the computer resumed from suspend.
+///
+/// If at least `network-retry-threshold` percent of the feeds of a reload fail
+/// because their servers couldn't be reached, the network is probed with
+/// exponentially growing spacing until a probe succeeds. Only the feeds that
+/// failed with network errors are reloaded then. All methods can be called from
+/// multiple threads at once.
+class NetworkRetry {
+public:
+	NetworkRetry();
+	~NetworkRetry();
+	NetworkRetry(const NetworkRetry&) = delete;
+	NetworkRetry& operator=(const NetworkRetry&) = delete;
+
+	/// Starts counting the feeds of a reload.
+	void start_cycle();
+
+	/// Records that the feed at \a url was reloaded.
+	void feed_succeeded(const std::string& url);
+
+	/// Records that the feed at \a url failed to reload. \a curl_code is the
+	/// curl error that caused it, or 0 if the failure didn't come from curl.
+	void feed_failed(const std::string& url, unsigned int curl_code);
+
+	/// Ends the reload, and schedules a probe if enough of its feeds failed
+	/// with network errors. Returns true if a probe is scheduled.
+	bool cycle_finished(bool enabled, unsigned int threshold_percent);
+
+	/// Seconds until the next probe, or nullopt if no probe is scheduled.
+	nonstd::optional<unsigned int> seconds_until_probe() const;
+
+	/// URL to send the probe to, or nullopt if no probe is due yet. If
+	/// \a configured_url is empty, one of the failed feeds is probed.
+	nonstd::optional<std::string> probe_target(
+		const std::string& configured_url);
+
+	/// Records the outcome of a probe, and returns the URLs of the feeds to
+	/// reload (which is none if the probe failed).
+	std::vector<std::string> probe_finished(bool succeeded);
+
+private:
+	void* rs_retry = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_NETWORKRETRY_H_ */
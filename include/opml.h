@@ -1,7 +1,7 @@
 #ifndef NEWSBOAT_OPML_H_
 #define NEWSBOAT_OPML_H_
 
-#include <libxml/tree.h>
+#include <string>
 
 #include "feedcontainer.h"
 #include "fileurlreader.h"
@@ -9,7 +9,9 @@
 namespace newsboat {
 
 namespace opml {
-xmlDocPtr generate(const FeedContainer& feedcontainer);
+/// Returns an OPML document that lists the feeds, in OPML 2.0 if `version2`
+/// is `true` and 1.0 otherwise.
+std::string generate(const FeedContainer& feedcontainer, bool version2 = false);
 bool import(
 	const std::string& filename,
 	FileUrlReader& urlcfg);
@@ -1,16 +1,23 @@
 #ifndef NEWSBOAT_RELOADER_H_
 #define NEWSBOAT_RELOADER_H_
 
+#include <atomic>
+#include <condition_variable>
 #include <mutex>
+#include <set>
+#include <string>
 #include <vector>
 
 #include "configcontainer.h"
+#include "networkretry.h"
+#include "reloadprogress.h"
 
 namespace newsboat {
 
 class Cache;
 class Controller;
 class CurlHandle;
+class RssFeed;
 
 /// \brief Updates feeds (fetches, parses, puts results into Controller).
 class Reloader {
@@ -23,8 +30,11 @@ public:
 	/// \brief Starts a thread that will reload feeds with specified
 	/// indexes.
 	///
-	/// If \a indexes is empty, all feeds will be reloaded.
-	void start_reload_all_thread(const std::vector<int>& indexes = {});
+	/// If \a indexes is empty, all feeds will be reloaded. A \a scheduled
+	/// reload skips feeds that asked not to be fetched right now (see
+	/// FeedScheduler); otherwise, the user asked for the reload.
+	void start_reload_all_thread(const std::vector<int>& indexes = {},
+		bool scheduled = false);
 
 	void unlock_reload_mutex()
 	{
@@ -40,40 +50,67 @@ public:
 	/// of the screen) if \a unattended is false. All network requests are
 	/// made through \a easyhandle, unless it's nullptr, in which case
 	/// method creates a temporary handle that is destroyed when method
-	/// completes.
+	/// completes. If \a scheduled is true, the feed is skipped if its
+	/// schedule says so.
 	// TODO: check that the value passed via "max" is always obtained from
 	// feedcontainer, then move that request into the method and drop the
 	// parameter.
 	void reload(unsigned int pos,
 		unsigned int max = 0,
 		bool unattended = false,
-		CurlHandle* easyhandle = nullptr);
+		CurlHandle* easyhandle = nullptr,
+		bool scheduled = false);
 
 	/// \brief Reloads all feeds, spawning threads as necessary.
 	///
 	/// Only updates status bar if \a unattended is false. The number of
 	/// threads spawned is controlled by the user via reload-threads
-	/// setting.
-	void reload_all(bool unattended = false);
+	/// setting. See reload() for \a scheduled.
+	void reload_all(bool unattended = false, bool scheduled = false);
 
 	/// \brief Reloads all feeds with given indexes in feedlist.
 	///
-	/// Only updates status bar if \a unattended is false.
+	/// Only updates status bar if \a unattended is false. See reload() for
+	/// \a scheduled.
 	void reload_indexes(const std::vector<int>& indexes,
-		bool unattended = false);
+		bool unattended = false,
+		bool scheduled = false);
 
 	/// \brief Reloads feeds occupying positions from \a start to \a end in
 	/// feedlist.
 	///
-	/// Only updates status bar if \a unattended is false.
+	/// Only updates status bar if \a unattended is false. See reload() for
+	/// \a scheduled.
 	void reload_range(unsigned int start,
 		unsigned int end,
 		unsigned int size,
-		bool unattended = false);
+		bool unattended = false,
+		bool scheduled = false);
+
+	/// \brief Progress of the reload that's currently running.
+	///
+	/// Calling ReloadProgress::cancel() on it stops the reload.
+	ReloadProgress& get_progress()
+	{
+		return progress;
+	}
+
+	/// \brief Feeds that failed with network errors, and when to check if
+	/// the network is back. See spawn_reloadthread().
+	NetworkRetry& get_network_retry()
+	{
+		return network_retry;
+	}
+
+	/// \brief Feeds that failed to reload in the last reload that finished.
+	std::vector<ReloadProgress::Failure> last_failures() const;
+
+	/// \brief Stops the first syncs that run in the background, and waits
+	/// until they stored what they fetched.
+	void stop_initial_syncs();
 
-private:
 	/// \brief Notify in various ways that there are new unread feeds or
-	/// articles.
+	/// articles, or that a long operation finished.
 	///
 	/// The type of notification is based on "notify-screen", "notify-xterm",
 	/// "notify-beep" and "notify-program" settings chosen in Newsboat's config
@@ -83,6 +120,8 @@ private:
 	/// notification will contain \a msg passed.
 	void notify(const std::string& msg);
 
+private:
+
 	void notify_reload_finished(unsigned int unread_feeds_before,
 		unsigned int unread_articles_before);
 
@@ -90,10 +129,38 @@ private:
 	Cache* rsscache;
 	ConfigContainer* cfg;
 	std::mutex reload_mutex;
+	ReloadProgress progress;
+	NetworkRetry network_retry;
+	mutable std::mutex last_failures_mutex;
+	std::vector<ReloadProgress::Failure> failures_of_last_reload;
+	/// True while reload_all() or reload_indexes() runs. Otherwise, each
+	/// call to reload() is a reload cycle of its own, as far as hooks are
+	/// concerned.
+	std::atomic<bool> in_reload_cycle;
 
 	std::string prepare_message(unsigned int pos, unsigned int max);
-
-
+	void notify_reload_cancelled();
+
+	/// Number of items in \a newfeed that aren't in \a oldfeed.
+	static unsigned int count_new_items(RssFeed& oldfeed, RssFeed& newfeed);
+	void log_hook_report();
+	/// Logs the feeds that failed to reload in this cycle, and keeps them
+	/// for last_failures().
+	void log_failure_report();
+	/// Ends a cycle for network_retry, and logs if a probe was scheduled.
+	void finish_network_retry_cycle();
+
+	/// Starts the first sync of the feed at \a url in a thread of its own,
+	/// unless it's complete or running already. \a had_articles tells if
+	/// the cache held articles of the feed before it was reloaded.
+	void start_initial_sync(const std::string& url, bool had_articles);
+	void run_initial_sync(const std::string& url);
+
+	std::mutex initial_syncs_mutex;
+	std::condition_variable initial_sync_finished;
+	/// Feeds whose first sync is running.
+	std::set<std::string> initial_syncs;
+	std::atomic<bool> initial_syncs_stopped;
 };
 
 } // namespace newsboat
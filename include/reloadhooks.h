@@ -0,0 +1,70 @@
+#ifndef NEWSBOAT_RELOADHOOKS_H_
+#define NEWSBOAT_RELOADHOOKS_H_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+#include "configactionhandler.h"
+
+namespace newsboat {
+
+/// \brief Result of fetching a feed. Post-reload hooks get it as "ok",
+/// "error" or "not-modified".
+///
+/// \note The numeric values are also used by the Rust side (see
+/// `rust/libnewsboat/src/reloadhooks.rs`); keep them in sync.
+enum class ReloadOutcome : std::uint8_t {
+	FETCHED = 0,
+	FAILED = 1,
+	NOT_MODIFIED = 2,
+};
+
+/// \brief Commands that are run before and after particular feeds are
+/// fetched.
+///
+/// Handles `pre-reload-command` and `post-reload-command`. Each hook runs at
+/// most once per reload cycle, which begins with start_cycle(). The run_*
+/// methods can be called from multiple reload threads at once.
+class ReloadHooks : public ConfigActionHandler {
+public:
+	ReloadHooks();
+	~ReloadHooks() override;
+	void handle_action(const std::string& action,
+		const std::vector<std::string>& params) override;
+	void dump_config(std::vector<std::string>& config_output) const override;
+
+	/// \brief Hooks that run longer than this are killed.
+	void set_timeout(unsigned int seconds);
+
+	/// \brief Forgets which hooks ran during the previous cycle.
+	void start_cycle();
+
+	/// \brief Runs the pre-reload hook of the feed at \a url.
+	///
+	/// Returns an error message if the hook failed, in which case the feed
+	/// shouldn't be fetched.
+	nonstd::optional<std::string> run_pre_reload(const std::string& url);
+
+	/// \brief Runs the post-reload hook of the feed at \a url, passing it
+	/// the outcome and the number of new items.
+	///
+	/// Returns an error message if the hook failed.
+	nonstd::optional<std::string> run_post_reload(const std::string& url,
+		ReloadOutcome outcome,
+		unsigned int new_items);
+
+	/// \brief One line per hook that ran during the current cycle.
+	std::vector<std::string> report() const;
+
+private:
+	ReloadHooks(const ReloadHooks&) = delete;
+	ReloadHooks& operator=(const ReloadHooks&) = delete;
+
+	void* rs_hooks = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_RELOADHOOKS_H_ */
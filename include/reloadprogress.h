@@ -0,0 +1,79 @@
+#ifndef NEWSBOAT_RELOADPROGRESS_H_
+#define NEWSBOAT_RELOADPROGRESS_H_
+
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+/// \brief Progress of a reload, shared between the fetching threads and the
+/// UI thread.
+///
+/// All methods are safe to call from multiple threads at once.
+class ReloadProgress {
+public:
+	struct Snapshot {
+		unsigned int done = 0;
+		unsigned int total = 0;
+		bool cancelled = false;
+		/// Value for `%P`; empty if no reload is running.
+		std::string percent_text;
+		/// Value for `%C`; empty if no reload is running.
+		std::string current_text;
+	};
+
+	/// A feed that couldn't be reloaded.
+	struct Failure {
+		std::string url;
+		std::string message;
+	};
+
+	/// \brief Counts a feed as done when it goes out of scope, however the
+	/// reload of the feed ended.
+	class FeedGuard {
+	public:
+		explicit FeedGuard(ReloadProgress& progress)
+			: progress(progress)
+		{
+		}
+		~FeedGuard()
+		{
+			progress.feed_finished();
+		}
+		FeedGuard(const FeedGuard&) = delete;
+		FeedGuard& operator=(const FeedGuard&) = delete;
+
+	private:
+		ReloadProgress& progress;
+	};
+
+	ReloadProgress();
+	~ReloadProgress();
+
+	void start(unsigned int total);
+	void feed_started(const std::string& title);
+	void feed_finished();
+	void finish();
+
+	/// \brief Asks the fetchers to stop. Does nothing if no reload is
+	/// running.
+	void cancel();
+	bool is_cancelled() const;
+
+	Snapshot snapshot() const;
+
+	void feed_failed(const std::string& url, const std::string& message);
+	/// Feeds that couldn't be reloaded since the last start(), in the
+	/// order they failed. Empty once the reload is finish()ed.
+	std::vector<Failure> failures() const;
+
+private:
+	ReloadProgress(const ReloadProgress&) = delete;
+	ReloadProgress& operator=(const ReloadProgress&) = delete;
+
+	void* rs_progress = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_RELOADPROGRESS_H_ */
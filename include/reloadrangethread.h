@@ -13,13 +13,15 @@ public:
 		unsigned int start,
 		unsigned int end,
 		unsigned int size,
-		bool unattended);
+		bool unattended,
+		bool scheduled);
 	void operator()();
 
 private:
 	Reloader& reloader;
 	unsigned int start, end, size;
 	bool unattended;
+	bool scheduled;
 };
 
 } // namespace newsboat
@@ -0,0 +1,35 @@
+#ifndef NEWSBOAT_RELOADTAG_H_
+#define NEWSBOAT_RELOADTAG_H_
+
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+
+namespace newsboat {
+
+class RssFeed;
+
+/// \brief The feeds that `reload-tag <tag>` (or `reload-all <tag>`) reloads.
+///
+/// Such a reload is a scheduled one: feeds whose schedule says they shouldn't
+/// be fetched right now are skipped.
+struct TagScope {
+	/// Picks the feeds among \a feeds that carry \a tag. If there are none,
+	/// returns nothing and sets \a error to a message that suggests similar
+	/// tags.
+	static nonstd::optional<TagScope> select(const std::string& tag,
+		const std::vector<std::shared_ptr<RssFeed>>& feeds,
+		std::string& error);
+
+	/// Positions of the feeds to reload in \a feeds.
+	std::vector<int> indexes;
+
+	/// Notes the tag and the number of feeds it covers.
+	std::string summary;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_RELOADTAG_H_ */
@@ -15,6 +15,14 @@ public:
 	void operator()();
 
 private:
+	/// Sleeps until the next reload is due, waking up in between to check
+	/// if the network is back after a reload failed because of it.
+	void wait_for_next_reload();
+
+	/// Sends the probe that NetworkRetry asks for, if one is due, and
+	/// reloads the feeds that failed if it succeeds.
+	void probe_network();
+
 	Controller* ctrl;
 	time_t oldtime;
 	time_t waittime_sec;
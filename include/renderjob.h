@@ -0,0 +1,104 @@
+#ifndef NEWSBOAT_RENDERJOB_H_
+#define NEWSBOAT_RENDERJOB_H_
+
+#include <cstddef>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "htmlrenderer.h"
+#include "textformatter.h"
+
+namespace newsboat {
+
+/// \brief Renders an article's HTML a unit of work at a time.
+///
+/// The article view calls render_unit() between key presses, so that a huge
+/// article doesn't freeze the UI: the first screenful is shown as soon as
+/// it's ready, and the rest follows. Navigating away cancels the job.
+class RenderJob {
+public:
+	/// \brief What to do after a unit of work.
+	enum class Step {
+		/// Render another unit; there's nothing new to show yet.
+		CONTINUE,
+		/// Enough is rendered to fill the screen: show it, then render
+		/// the rest.
+		SHOW_FIRST_SCREEN,
+		/// The article is rendered completely: show it.
+		FINISHED,
+		/// The job was cancelled: show nothing.
+		CANCELLED
+	};
+
+	/// \brief Starts rendering \a source, resolving relative links against
+	/// \a url, for a screen that's \a screen_height lines high.
+	///
+	/// \a links are put in front of the links found in the article, as
+	/// with HtmlRenderer::render(). See HtmlRenderer::HtmlRenderer() for
+	/// \a max_nesting.
+	RenderJob(const std::string& source,
+		const std::string& url,
+		std::vector<LinkPair> links,
+		unsigned int screen_height,
+		unsigned int max_nesting = HtmlRenderer::DEFAULT_MAX_NESTING);
+	~RenderJob();
+
+	/// \brief Renders a bounded number of HTML nodes.
+	Step render_unit();
+
+	/// \brief Makes the next render_unit() stop the job.
+	void cancel();
+
+	/// \brief Whether render_unit() should be called again.
+	bool should_continue() const;
+
+	/// \brief Lines rendered so far.
+	const std::vector<std::pair<LineType, std::string>>& lines() const;
+
+	/// \brief Links found so far.
+	const std::vector<LinkPair>& links() const;
+
+private:
+	friend class RenderCache;
+
+	RenderJob(const RenderJob&) = delete;
+	RenderJob& operator=(const RenderJob&) = delete;
+
+	HtmlRenderer renderer;
+	HtmlRenderer::State state;
+	void* rs_job = nullptr;
+};
+
+/// \brief Keeps articles that RenderJob rendered, so that going back to them
+/// doesn't render them again.
+class RenderCache {
+public:
+	RenderCache(std::size_t max_entries, std::size_t max_bytes);
+	~RenderCache();
+
+	void set_limits(std::size_t max_entries, std::size_t max_bytes);
+
+	/// \brief Fills \a lines and \a links with the rendered article \a guid.
+	/// Returns false if it isn't cached.
+	bool get(const std::string& guid,
+		std::vector<std::pair<LineType, std::string>>& lines,
+		std::vector<LinkPair>& links);
+
+	/// \brief Caches what \a job rendered, but only if it rendered the
+	/// whole article. Returns whether it did.
+	bool store(const std::string& guid, const RenderJob& job);
+
+	/// \brief Forgets all cached articles.
+	void clear();
+
+private:
+	RenderCache(const RenderCache&) = delete;
+	RenderCache& operator=(const RenderCache&) = delete;
+
+	void* rs_cache = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_RENDERJOB_H_ */
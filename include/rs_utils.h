@@ -1,6 +1,7 @@
 #ifndef NEWSBOAT_RS_UTILS_H_
 #define NEWSBOAT_RS_UTILS_H_
 
+#include <cstddef>
 #include <cstdint>
 
 #include "3rd-party/optional.hpp"
@@ -25,6 +26,8 @@ unsigned int rs_to_u(const char* str, const unsigned int default_value);
 
 char* rs_absolute_url(const char* base_url, const char* link);
 
+char* rs_absolute_url_without_fragment(const char* base_url, const char* link);
+
 char* rs_resolve_tilde(const char* str);
 
 char* rs_resolve_relative(const char* reference, const char* fname);
@@ -82,6 +85,10 @@ char* rs_substr_with_width_stfl(const char* str, size_t max_width);
 
 char* rs_remove_soft_hyphens(const char* str);
 
+char* rs_strip_bidi_controls(const char* str);
+
+char* rs_format_urlview_line(size_t number, const char* url, bool strip_bidi);
+
 bool rs_is_valid_podcast_type(const char* mimetype);
 
 std::int64_t rs_podcast_mime_to_link_type(const char* mimetype, bool* success);
@@ -102,8 +109,31 @@ unsigned int rs_gentabs(const char* path);
 
 int rs_mkdir_parents(const char* path, const std::uint32_t mode);
 
+char* rs_atomic_write(const char* path,
+	const char* contents,
+	std::size_t contents_len,
+	const std::uint32_t mode);
+
+char* rs_atomic_write_urls_file(const char* path,
+	const char* contents,
+	std::size_t contents_len);
+
+char* rs_file_encoding_decode(const char* path,
+	const char* data,
+	std::size_t length,
+	const char* locale_codeset,
+	char** warning);
+
+char* rs_file_encoding_convert(const char* path,
+	const char* locale_codeset,
+	bool* converted);
+
 char* rs_strip_comments(const char* line);
 
+char* rs_unescape_quoted(const char* input);
+
+char* rs_escape_quoted(const char* input);
+
 FilterUrl rs_extract_filter(const char* line);
 
 #ifdef __cplusplus
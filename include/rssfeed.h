@@ -113,16 +113,41 @@ public:
 	unsigned int unread_item_count() const;
 	unsigned int total_item_count() const
 	{
+		if (placeholder) {
+			return placeholder_total;
+		}
 		return items_.size();
 	}
 
+	/// Makes this a stand-in for a feed that wasn't loaded from the cache
+	/// yet: it has no items, yet claims to have \a unread and \a total of
+	/// them, as the feedlist snapshot says.
+	void set_placeholder_counts(unsigned int unread, unsigned int total)
+	{
+		placeholder = true;
+		placeholder_unread = unread;
+		placeholder_total = total;
+	}
+	bool is_placeholder() const
+	{
+		return placeholder;
+	}
+
 	void set_tags(const std::vector<std::string>& tags);
 	bool matches_tag(const std::string& tag);
 	std::string get_tags() const;
+	/// All tags of the feed, including the ones that set its title or hide
+	/// it.
+	const std::vector<std::string>& get_tag_list() const
+	{
+		return tags_;
+	}
 	std::string get_firsttag();
 
 	nonstd::optional<std::string> attribute_value(const std::string& attr) const
 	override;
+	nonstd::optional<time_t> date_timestamp(const std::string& attr) const
+	override;
 
 	void update_items(std::vector<std::shared_ptr<RssFeed>> feeds);
 
@@ -141,6 +166,13 @@ public:
 		return search_feed;
 	}
 
+	/// Whether the feed and its items are stored in the cache. Previews of
+	/// feeds that the user isn't subscribed to only live in memory.
+	bool is_cached() const
+	{
+		return ch != nullptr;
+	}
+
 	void set_search_feed(bool b)
 	{
 		search_feed = b;
@@ -160,6 +192,17 @@ public:
 		return is_rtl_;
 	}
 
+	/// What the feed said about when to fetch it, as returned by
+	/// feedschedule::from_elements().
+	void set_schedule(const std::string& schedule)
+	{
+		schedule_ = schedule;
+	}
+	std::string schedule() const
+	{
+		return schedule_;
+	}
+
 	void set_index(unsigned int i)
 	{
 		idx = i;
@@ -187,6 +230,11 @@ public:
 		std::lock_guard<std::mutex> guard(status_mutex_);
 		status_ = DlStatus::TO_BE_DOWNLOADED;
 	}
+	DlStatus get_dl_status()
+	{
+		std::lock_guard<std::mutex> guard(status_mutex_);
+		return status_;
+	}
 	void set_status(DlStatus st)
 	{
 		std::lock_guard<std::mutex> guard(status_mutex_);
@@ -217,12 +265,17 @@ private:
 
 	bool search_feed;
 	bool is_rtl_;
+	std::string schedule_;
 	unsigned int idx;
 	unsigned int order;
 	std::mutex items_guid_map_mutex;
 
 	DlStatus status_;
 	std::mutex status_mutex_;
+
+	bool placeholder;
+	unsigned int placeholder_unread;
+	unsigned int placeholder_total;
 };
 
 } // namespace newsboat
@@ -1,6 +1,8 @@
 #ifndef NEWSBOAT_RSSITEM_H_
 #define NEWSBOAT_RSSITEM_H_
 
+#include <cstddef>
+#include <cstdint>
 #include <memory>
 #include <mutex>
 #include <string>
@@ -50,6 +52,10 @@ public:
 	}
 	void set_size(unsigned int size);
 
+	/// Approximate amount of memory the item takes, in bytes: the object
+	/// itself plus the text it holds.
+	std::size_t byte_size() const;
+
 	std::string length() const;
 	std::string pubDate() const;
 
@@ -125,8 +131,22 @@ public:
 	void update_flags();
 	void sort_flags();
 
+	/// The user's free-text note on the article, or an empty string.
+	const std::string& note() const
+	{
+		return note_;
+	}
+	void set_note(const std::string& note)
+	{
+		note_ = note;
+	}
+	/// Stores the note in the cache.
+	void update_note();
+
 	nonstd::optional<std::string> attribute_value(const std::string& attr) const
 	override;
+	nonstd::optional<time_t> date_timestamp(const std::string& attr) const
+	override;
 
 	void set_feedptr(std::shared_ptr<RssFeed> ptr);
 	void set_feedptr(const std::weak_ptr<RssFeed>& ptr);
@@ -144,6 +164,34 @@ public:
 		deleted_ = b;
 	}
 
+	/// \brief Whether the publisher edited the article since it was last
+	/// marked read.
+	bool updated() const
+	{
+		return updated_;
+	}
+	void set_updated(bool b)
+	{
+		updated_ = b;
+	}
+
+	/// Number of words in the article, as counted by the cache when the
+	/// article was stored.
+	uint64_t word_count() const
+	{
+		return word_count_;
+	}
+	/// Minutes it takes to read the article at the `reading-speed`.
+	uint64_t reading_minutes() const
+	{
+		return reading_minutes_;
+	}
+	void set_word_count(uint64_t words, uint64_t reading_minutes)
+	{
+		word_count_ = words;
+		reading_minutes_ = reading_minutes;
+	}
+
 	void set_index(unsigned int i)
 	{
 		idx = i;
@@ -188,14 +236,18 @@ private:
 	std::string enclosure_type_;
 	std::string flags_;
 	std::string oldflags_;
+	std::string note_;
 	std::weak_ptr<RssFeed> feedptr_;
 	std::string base;
 	unsigned int idx;
 	unsigned int size_;
+	uint64_t word_count_;
+	uint64_t reading_minutes_;
 	time_t pubDate_;
 	bool unread_;
 	bool enqueued_;
 	bool deleted_;
+	bool updated_;
 	bool override_unread_;
 
 	mutable std::mutex description_mutex;
@@ -16,6 +16,7 @@ namespace newsboat {
 class Cache;
 class ConfigContainer;
 class CurlHandle;
+class FeedOptions;
 class RssFeed;
 class RssIgnores;
 class RssItem;
@@ -29,6 +30,9 @@ public:
 		RemoteApi* a = 0);
 	~RssParser();
 	std::shared_ptr<RssFeed> parse();
+	/// \brief Turns a page of a feed, fetched from a remote API, into a feed
+	/// that holds just that page's articles.
+	std::shared_ptr<RssFeed> parse_page(const rsspp::Feed& page);
 	bool check_and_update_lastmodified();
 
 	void set_easyhandle(CurlHandle* h)
@@ -36,6 +40,12 @@ public:
 		easyhandle = h;
 	}
 
+	/// \brief Per-feed user agents and headers to apply to HTTP downloads.
+	void set_feed_options(const FeedOptions* o)
+	{
+		feed_options = o;
+	}
+
 private:
 	void replace_newline_characters(std::string& str);
 	std::string render_xhtml_title(const std::string& title,
@@ -50,6 +60,7 @@ private:
 		const std::string& uri);
 	void parse_file(const std::string& file);
 
+	std::shared_ptr<RssFeed> build_feed();
 	void fill_feed_fields(std::shared_ptr<RssFeed> feed);
 	void fill_feed_items(std::shared_ptr<RssFeed> feed);
 
@@ -89,6 +100,7 @@ private:
 	bool is_miniflux;
 
 	CurlHandle* easyhandle;
+	const FeedOptions* feed_options;
 };
 
 } // namespace newsboat
@@ -0,0 +1,42 @@
+#ifndef NEWSBOAT_SCOPEDCOUNTS_H_
+#define NEWSBOAT_SCOPEDCOUNTS_H_
+
+#include <string>
+
+namespace newsboat {
+
+/// \brief Article and unread counts for the members of a scope, e.g. the
+/// articles that match the current filter, or the feeds with the current tag.
+///
+/// Members are updated one by one when their state changes, so the counts stay
+/// right without re-evaluating the scope for everything.
+class ScopedCounts {
+public:
+	ScopedCounts();
+	~ScopedCounts();
+	ScopedCounts(const ScopedCounts&) = delete;
+	ScopedCounts& operator=(const ScopedCounts&) = delete;
+
+	/// Removes all members.
+	void clear();
+
+	/// Records that the member \a key has \a unread unread articles, and
+	/// whether it's (still) in the scope.
+	void update(const std::string& key, bool in_scope, unsigned int unread);
+
+	/// Number of members.
+	unsigned int total() const;
+
+	/// Number of unread articles of all members.
+	unsigned int unread() const;
+
+	/// Number of members that have unread articles.
+	unsigned int unread_members() const;
+
+private:
+	void* rs_counts = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_SCOPEDCOUNTS_H_ */
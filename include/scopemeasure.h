@@ -2,12 +2,19 @@
 #define NEWSBOAT_SCOPEMEASURE_H_
 
 #include <string>
+#include <vector>
+
+#include "3rd-party/optional.hpp"
+#include "logger.h"
 
 namespace newsboat {
 
 class ScopeMeasure {
 public:
 	ScopeMeasure(const std::string& func);
+	/// Only logs, at `level`, if at least `threshold_ms` elapsed.
+	ScopeMeasure(const std::string& func, unsigned int threshold_ms,
+		Level level);
 	~ScopeMeasure();
 	void stopover(const std::string& son = "");
 
@@ -15,6 +22,27 @@ private:
 	void* rs_object = nullptr;
 };
 
+/// Times each operation of a macro or a key binding, and says which one was
+/// the slowest if they took longer than `threshold_ms` together. A disabled
+/// timer does nothing at all.
+class OperationTimer {
+public:
+	OperationTimer(bool enabled, unsigned int threshold_ms);
+	~OperationTimer();
+	OperationTimer(const OperationTimer&) = delete;
+	OperationTimer& operator=(const OperationTimer&) = delete;
+
+	void start_step();
+	/// Writes the time since start_step() to the log.
+	void finish_step(const std::string& name,
+		const std::vector<std::string>& args);
+	/// A message for the status line, if the sequence was slow.
+	nonstd::optional<std::string> slow_sequence_hint();
+
+private:
+	void* rs_object = nullptr;
+};
+
 } // namespace newsboat
 
 #endif /* NEWSBOAT_SCOPEMEASURE_H_ */
@@ -0,0 +1,59 @@
+#ifndef NEWSBOAT_SELECTION_H_
+#define NEWSBOAT_SELECTION_H_
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+namespace newsboat {
+
+/// \brief Articles of the article list that are selected for a batch
+/// operation.
+///
+/// Articles are remembered by their GUID, so the selection survives
+/// re-sorting the list. A range anchored with select_range() follows the
+/// cursor until select_range() is called again.
+class Selection {
+public:
+	Selection();
+	~Selection();
+	Selection(const Selection&) = delete;
+	Selection& operator=(const Selection&) = delete;
+
+	/// Sets the GUIDs of the articles in the list, in the order in which
+	/// they're shown.
+	void set_order(const std::vector<std::string>& guids);
+
+	/// Selects or unselects the article at \a cursor. Returns whether it's
+	/// selected now.
+	bool toggle(std::size_t cursor);
+
+	/// Anchors a range at \a cursor and returns `false`, or, if one was
+	/// anchored already, adds it to the selection, sets \a count to the
+	/// number of selected articles and returns `true`.
+	bool select_range(std::size_t cursor, std::size_t& count);
+
+	bool is_anchored() const;
+
+	/// Whether there are selected articles in the list.
+	bool is_active() const;
+
+	/// Positions of the selected articles, with the cursor at \a cursor.
+	std::vector<std::size_t> selected_positions(std::size_t cursor) const;
+
+	/// What's shown in front of the article at \a position: an empty string
+	/// if there's no selection, and a marker column otherwise.
+	std::string marker(std::size_t position, std::size_t cursor) const;
+
+	/// Whether the markers have to be drawn again since the last call.
+	bool needs_redraw(std::size_t cursor);
+
+	void clear();
+
+private:
+	void* rs_selection = nullptr;
+};
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_SELECTION_H_ */
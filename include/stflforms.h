@@ -0,0 +1,25 @@
+#ifndef NEWSBOAT_STFLFORMS_H_
+#define NEWSBOAT_STFLFORMS_H_
+
+#include <string>
+
+namespace newsboat {
+
+/// \brief STFL descriptions of the dialogs whose labels are translated.
+///
+/// The forms are built by libnewsboat, which quotes every value, so
+/// translations may contain any characters.
+namespace stflforms {
+
+std::string help();
+
+std::string urlview();
+
+/// Used by both the file and the directory browser.
+std::string filebrowser();
+
+} // namespace stflforms
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_STFLFORMS_H_ */
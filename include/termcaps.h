@@ -0,0 +1,54 @@
+#ifndef NEWSBOAT_TERMCAPS_H_
+#define NEWSBOAT_TERMCAPS_H_
+
+#include <cstdint>
+#include <string>
+
+namespace newsboat {
+
+/// \brief How many colors the terminal can show.
+///
+/// \note The numeric values are also used by the Rust side (see
+/// `rust/libnewsboat/src/termcaps.rs`); keep them in sync.
+enum class ColorSupport : std::uint8_t {
+	NONE = 0,
+	COLORS_8 = 1,
+	COLORS_256 = 2,
+	TRUECOLOR = 3,
+};
+
+/// \brief Rendering decisions for terminals that lack colors or space.
+namespace termcaps {
+
+/// \brief Looks up the terminal's colors in terminfo and the `COLORTERM`
+/// environment variable.
+ColorSupport detect_color_support();
+
+/// \brief Sets the support level that degrade_style() maps colors down to.
+///
+/// Until this is called, styles are left unchanged, except for RGB colors.
+void set_color_support(ColorSupport support);
+
+/// \brief Rewrites an STFL style like "fg=color208,attr=bold" or
+/// "fg=#ff8000" so that it only uses colors the terminal can show.
+std::string degrade_style(const std::string& style);
+
+/// \brief Returns the format to use for a title that's \a width columns
+/// wide: \a configured (the value of \a option), or a short template if
+/// the title is too narrow for it.
+std::string title_format(const std::string& option,
+	const std::string& configured,
+	unsigned int width);
+
+/// \brief Whether both the article list and the preview pane fit into the
+/// given area, if the preview takes \a percent of it.
+bool preview_pane_fits(unsigned int total_width,
+	unsigned int total_height,
+	unsigned int percent,
+	bool beside);
+
+} // namespace termcaps
+
+} // namespace newsboat
+
+#endif /* NEWSBOAT_TERMCAPS_H_ */
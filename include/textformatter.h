@@ -44,8 +44,16 @@ public:
 		lines.clear();
 	}
 
+	/// Enables hyphenation of English words with patterns. Soft hyphens
+	/// found in the text are used as break opportunities regardless.
+	void set_hyphenation(bool enabled)
+	{
+		hyphenation = enabled;
+	}
+
 private:
 	std::vector<std::pair<LineType, std::string>> lines;
+	bool hyphenation;
 };
 
 } // namespace newsboat
@@ -30,6 +30,8 @@ enum class HTTPMethod {
 };
 
 std::string strip_comments(const std::string& line);
+std::string unescape_quoted(const std::string& input);
+std::string escape_quoted(const std::string& input);
 std::vector<std::string> tokenize(const std::string& str,
 	std::string delimiters = " \r\n\t");
 std::vector<std::string> tokenize_spaced(const std::string& str,
@@ -53,6 +55,10 @@ std::string convert_text(const std::string& text,
 /// nl_langinfo(CODESET)).
 std::string utf8_to_locale(const std::string& text);
 
+/// Converts input string from the locale's encoding (as detected by
+/// nl_langinfo(CODESET)) to UTF-8.
+std::string locale_to_utf8(const std::string& text);
+
 std::string get_command_output(const std::string& cmd);
 void extract_filter(const std::string& line,
 	std::string& filter,
@@ -82,6 +88,11 @@ std::wstring clean_nonprintable_characters(std::wstring text);
 std::string absolute_url(const std::string& url,
 	const std::string& link);
 
+/// Like `absolute_url()`, but drops the fragment (the part after `#`) of the
+/// result, which is of no use when the URL is downloaded.
+std::string absolute_url_without_fragment(const std::string& url,
+	const std::string& link);
+
 std::string get_useragent(ConfigContainer* cfgcont);
 
 size_t strwidth(const std::string& s);
@@ -143,6 +154,31 @@ unsigned int gentabs(const std::string& str);
 int mkdir_parents(const std::string& pathname,
 	mode_t mode = 0755);
 
+/// Replaces the contents of the file at `path` with `contents`, via
+/// a temporary file that is renamed over it. Returns the error message if the
+/// file couldn't be written.
+nonstd::optional<std::string> atomic_write(const std::string& path,
+	const std::string& contents,
+	mode_t mode = 0600);
+
+/// Like atomic_write(), but leaves the file alone if `contents` isn't a valid
+/// urls file.
+nonstd::optional<std::string> atomic_write_urls_file(const std::string& path,
+	const std::string& contents);
+
+/// Reads the file at `path` and converts it to UTF-8. Lines that aren't valid
+/// UTF-8 are read as ISO-8859-1 (or Windows-1252, if that's the locale's
+/// codeset), and `warning` is set to a message naming the file; otherwise,
+/// it's left empty. Returns nullopt if the file couldn't be opened.
+nonstd::optional<std::string> read_text_file(const std::string& path,
+	std::string& warning);
+
+/// Rewrites the file at `path` in UTF-8, decoding it like read_text_file()
+/// does, via atomic_write(). `converted` is set to `false` if the file already
+/// was UTF-8. Returns the error message if the file couldn't be converted.
+nonstd::optional<std::string> convert_file_to_utf8(const std::string& path,
+	bool& converted);
+
 std::string make_title(const std::string& url);
 
 nonstd::optional<std::uint8_t> run_interactively(const std::string& command,
@@ -154,6 +190,16 @@ int strnaturalcmp(const std::string& a, const std::string& b);
 
 void remove_soft_hyphens(std::string& text);
 
+/// Removes Unicode bidi control characters (RLO, LRI, PDF etc.), which can
+/// make text display in a different order than it's stored in.
+std::string strip_bidi_controls(const std::string& text);
+
+/// Formats a line of the URL view. Bidi controls in the URL are removed if
+/// `strip_bidi` is true, and visibly escaped otherwise. Possibly deceptive URLs
+/// are marked with an exclamation mark after their number.
+std::string format_urlview_line(unsigned int number, const std::string& url,
+	bool strip_bidi);
+
 bool is_valid_podcast_type(const std::string& mimetype);
 
 nonstd::optional<LinkType> podcast_mime_to_link_type(const std::string&
@@ -9,15 +9,18 @@
 
 #include "3rd-party/optional.hpp"
 
+#include "browser.h"
 #include "colormanager.h"
 #include "configcontainer.h"
 #include "controller.h"
+#include "dialogstack.h"
 #include "dirbrowserformaction.h"
 #include "feedlistformaction.h"
 #include "filebrowserformaction.h"
 #include "htmlrenderer.h"
 #include "keymap.h"
 #include "regexmanager.h"
+#include "scopemeasure.h"
 #include "stflpp.h"
 
 namespace newsboat {
@@ -70,6 +73,9 @@ public:
 	void push_itemlist(unsigned int pos);
 	std::shared_ptr<ItemListFormAction> push_itemlist(std::shared_ptr<RssFeed>
 		feed);
+	/// Fetches the feed at `url` and shows its articles without storing
+	/// them in the cache (see `feedpreview::fetch()`).
+	void push_feed_preview(const std::string& url);
 	void push_itemview(std::shared_ptr<RssFeed> f,
 		const std::string& guid,
 		const std::string& searchphrase = "");
@@ -86,9 +92,20 @@ public:
 	std::string select_tag();
 	std::string select_filter(
 		const std::vector<FilterNameExprPair>& filters);
-
-	nonstd::optional<std::uint8_t> open_in_browser(const std::string& url);
+	std::string build_filter(const std::string& context);
+
+	/// \brief Opens \a url in the browser, and returns its exit code, or
+	/// nullopt if it couldn't be started.
+	///
+	/// If \a interactive is false, the browser runs in the background
+	/// instead of taking over the terminal. The exit code is then 0 as soon
+	/// as it started; failures are shown in the status line later on.
+	nonstd::optional<std::uint8_t> open_in_browser(const std::string& url,
+		bool interactive = true);
 	void open_in_pager(const std::string& filename);
+	/// Shows \a text in the status line if it's a single line, and in the
+	/// pager otherwise.
+	void show_text(const std::string& text);
 
 	std::string get_filename_suggestion(const std::string& s);
 
@@ -105,6 +122,14 @@ public:
 
 	bool get_next_unread_feed(ItemListFormAction& itemlist);
 	bool get_prev_unread_feed(ItemListFormAction& itemlist);
+	/// \brief Opens the next (or, if \a forward is false, previous) feed
+	/// with unread articles, at its first unread article.
+	///
+	/// The feed is shown in \a itemlist, or in a new article list if
+	/// \a itemlist is nullptr. Returns the reason why there's no such feed,
+	/// or nullopt if it was opened.
+	nonstd::optional<std::string> open_feed_with_unread(bool forward,
+		ItemListFormAction* itemlist = nullptr);
 	bool get_next_feed(ItemListFormAction& itemlist);
 	bool get_prev_feed(ItemListFormAction& itemlist);
 
@@ -120,6 +145,10 @@ public:
 
 	std::vector<std::pair<unsigned int, std::string>> get_formaction_names();
 
+	/// Shows how many articles each open dialog holds, and roughly how much
+	/// memory they take.
+	void show_memory_report();
+
 	std::shared_ptr<FormAction> get_current_formaction();
 
 	std::shared_ptr<FormAction> get_formaction(unsigned int idx) const
@@ -130,6 +159,16 @@ public:
 	void goto_next_dialog();
 	void goto_prev_dialog();
 
+	/// Labels of the dialogs leading to `fa`, starting with the feed list,
+	/// e.g. "Feeds > Planet Rust > Article".
+	std::string dialog_breadcrumb(const FormAction* fa);
+	/// Closes the current dialog, then the one it was opened from, and so
+	/// on until the feed list is reached. Dialogs opened from elsewhere
+	/// stay open.
+	void pop_to_feedlist();
+	/// Closes every dialog except the feed list.
+	void close_all_dialogs();
+
 	void apply_colors_to_all_formactions();
 
 	void inside_qna(bool f);
@@ -139,9 +178,12 @@ public:
 
 	static void ctrl_c_action(int sig);
 
-protected:
+	/// Runs operations one after another, like a macro. Returns false if
+	/// one of them failed, in which case the rest isn't run.
 	bool run_commands(const std::vector<MacroCmd>& commands);
 
+protected:
+
 	void apply_colors(std::shared_ptr<FormAction> fa);
 
 	void handle_cmdline_completion(std::shared_ptr<FormAction> fa);
@@ -151,6 +193,21 @@ protected:
 	void delete_word(std::shared_ptr<FormAction> fa);
 	bool handle_qna_event(const std::string& event, std::shared_ptr<FormAction> fa);
 
+	void push_formaction(std::shared_ptr<FormAction> fa,
+		const std::string& name = "");
+	void close_dialogs(const std::vector<const void*>& dialogs);
+
+	void show_slow_sequence_hint(OperationTimer& timer);
+	/// Runs \a op in \a fa, showing that it's running if it can take a
+	/// while (see KeyMap::is_long_operation()).
+	bool process_op(std::shared_ptr<FormAction> fa,
+		Operation op,
+		bool automatic = false,
+		std::vector<std::string>* args = nullptr);
+	/// Puts messages about background browsers that failed into the status
+	/// line.
+	void show_detached_browser_failures();
+
 	Controller* ctrl;
 
 	ConfigContainer* cfg;
@@ -160,6 +217,12 @@ protected:
 	std::vector<std::shared_ptr<FormAction>> formaction_stack;
 	unsigned int current_formaction;
 	std::shared_ptr<FeedListFormAction> feedlist_form;
+	DialogStack dialog_stack;
+	unsigned int modal_depth;
+	/// Set when a browser or a pager ran; the time spent there is the
+	/// user's, so the operation that ran it isn't called slow.
+	bool ran_interactive_program;
+	DetachedBrowsers detached_browsers;
 
 	std::vector<std::string> tags;
 
@@ -44,6 +44,7 @@ void print_usage(const std::string& argv0, const std::string& config_path,
 
 	static const std::vector<arg> args = {
 		{'e', "export-to-opml", "", _s("export OPML feed to stdout")},
+		{'\0', "export-to-opml2", "", _s("export OPML 2.0 feed to stdout")},
 		{'r', "refresh-on-start", "", _s("refresh feeds on start")},
 		{'i', "import-from-opml", _s("<file>"), _s("import OPML file")},
 		{
@@ -64,7 +65,14 @@ void print_usage(const std::string& argv0, const std::string& config_path,
 			_s("<configfile>"),
 			_s("read configuration from <configfile>")
 		},
+		{
+			'o',
+			"option",
+			_s("<key>=<value>"),
+			_s("set config option <key> to <value>, overriding the config file")
+		},
 		{'X', "vacuum", "", _s("compact the cache")},
+		{'\0', "cleanup", "", _s("clean up and compact the cache like on quit, and exit")},
 		{
 			'x',
 			"execute",
@@ -99,14 +107,70 @@ void print_usage(const std::string& argv0, const std::string& config_path,
 			_s("<file>"),
 			_s("import list of read articles from <file>")
 		},
+		{
+			'\0',
+			"profile",
+			_s("<name>"),
+			_s("use config, urls and cache files of profile <name>")
+		},
+		{'\0', "list-profiles", "", _s("list existing profiles")},
+		{
+			'\0',
+			"merge-cache",
+			_s("<file>"),
+			_s("merge articles and read states from cache <file>")
+		},
+		{
+			'\0',
+			"merge-prefer",
+			_s("<state>"),
+			_s("keep articles `read' (default) or `unread' if caches disagree")
+		},
+		{'\0', "recover-cache", "", _s("salvage what can be read from a damaged cache")},
+		{'\0', "force", "", _s("with --recover-cache, recover even a healthy cache")},
+		{'\0', "import-from-newsbeuter", "", _s("copy Newsbeuter's config and data, if Newsboat has none")},
+		{'\0', "dry-run-migration", "", _s("print what --import-from-newsbeuter would copy, and exit")},
+		{'\0', "convert-to-utf8", "", _s("rewrite config and urls files that aren't UTF-8 in UTF-8")},
+		{
+			'\0',
+			"add-feed",
+			_s("<url>"),
+			_s("subscribe to <url>, through the running instance if there is one")
+		},
+		{'\0', "plain", "", _s("print -x output for pipes even in a terminal")},
+		{
+			'\0',
+			"query-cache",
+			_s("<filter>"),
+			_s("print the cached articles that match <filter>, and exit")
+		},
+		{
+			'\0',
+			"fields",
+			_s("<list>"),
+			_s("with --query-cache, attributes to print (default: title,link,date)")
+		},
+		{
+			'\0',
+			"format",
+			_s("<format>"),
+			_s("with --query-cache, print `tsv' (default) or `json'")
+		},
+		{
+			'\0',
+			"error-format",
+			_s("<format>"),
+			_s("print errors as `text' (default) or `json'")
+		},
 		{'h', "help", "", _s("this help")}
 	};
 
 	std::stringstream ss;
 	for (const auto& a : args) {
-		std::string longcolumn("-");
-		longcolumn += a.name;
-		longcolumn += ", --" + a.longname;
+		// Options without a short name are aligned with the long names of
+		// the rest
+		std::string longcolumn(a.name != '\0' ? std::string("-") + a.name + ", " : "    ");
+		longcolumn += "--" + a.longname;
 		longcolumn += a.params.size() > 0 ? "=" + a.params : "";
 		ss << "\t" << longcolumn;
 		for (unsigned int j = 0; j < utils::gentabs(longcolumn); j++) {
@@ -232,6 +296,9 @@ int main(int argc, char* argv[])
 	configpaths.process_args(args);
 
 	if (args.should_print_usage()) {
+		if (!args.display_msg().empty()) {
+			std::cerr << args.display_msg() << std::endl;
+		}
 		print_usage(args.program_name(), configpaths.config_file(),
 			configpaths.url_file(), configpaths.cache_file());
 		if (args.return_code().has_value()) {
@@ -240,6 +307,11 @@ int main(int argc, char* argv[])
 	} else if (args.show_version()) {
 		print_version(args.program_name(), args.show_version());
 		return EXIT_SUCCESS;
+	} else if (args.list_profiles()) {
+		for (const auto& profile : configpaths.profiles()) {
+			std::cout << profile << std::endl;
+		}
+		return EXIT_SUCCESS;
 	}
 
 	int ret;
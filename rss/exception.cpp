@@ -2,8 +2,9 @@
 
 namespace rsspp {
 
-Exception::Exception(const std::string& errmsg)
+Exception::Exception(const std::string& errmsg, unsigned int curl_code)
 	: emsg(errmsg)
+	, code(curl_code)
 {
 }
 
@@ -14,4 +15,9 @@ const char* Exception::what() const throw()
 	return emsg.c_str();
 }
 
+unsigned int Exception::curl_code() const
+{
+	return code;
+}
+
 } // namespace rsspp
@@ -8,12 +8,18 @@ namespace rsspp {
 
 class Exception : public std::exception {
 public:
-	explicit Exception(const std::string& errmsg = "");
+	explicit Exception(const std::string& errmsg = "",
+		unsigned int curl_code = 0);
 	~Exception() throw() override;
 	const char* what() const throw() override;
 
+	/// The curl error that caused this exception, or 0 if it wasn't caused
+	/// by curl.
+	unsigned int curl_code() const;
+
 private:
 	std::string emsg;
+	unsigned int code;
 };
 
 } // namespace rsspp
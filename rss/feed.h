@@ -43,6 +43,12 @@ public:
 	std::string dc_creator;
 	std::string pubDate;
 
+	/// Text of `<ttl>`, and of the `<hour>` and `<day>` elements in
+	/// `<skipHours>` and `<skipDays>`. Only RSS 0.9x and 2.0 have them.
+	std::string ttl;
+	std::vector<std::string> skip_hours;
+	std::vector<std::string> skip_days;
+
 	std::vector<Item> items;
 };
 
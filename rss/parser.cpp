@@ -1,6 +1,7 @@
 #include "parser.h"
 
 #include <cinttypes>
+#include <cstdint>
 #include <cstring>
 #include <curl/curl.h>
 #include <libxml/parser.h>
@@ -13,11 +14,25 @@
 #include "rssparser.h"
 #include "rssparserfactory.h"
 #include "rsspp_uris.h"
+#include "ruststring.h"
 #include "strprintf.h"
 #include "utils.h"
 
 using namespace newsboat;
 
+extern "C" {
+	void* rs_download_watch_new(std::uint32_t timeout,
+		std::uint32_t low_speed_limit,
+		std::uint32_t low_speed_time);
+
+	void rs_download_watch_free(void* watch);
+
+	char* rs_download_watch_progress(void* watch, std::uint64_t received);
+
+	char* rs_download_check_length(std::int64_t expected,
+		std::uint64_t received);
+}
+
 static size_t my_write_data(void* buffer, size_t size, size_t nmemb,
 	void* userp)
 {
@@ -33,8 +48,12 @@ Parser::Parser(unsigned int timeout,
 	const std::string& proxy,
 	const std::string& proxy_auth,
 	curl_proxytype proxy_type,
-	const bool ssl_verify)
+	const bool ssl_verify,
+	unsigned int low_speed_limit,
+	unsigned int low_speed_time)
 	: to(timeout)
+	, low_speed_limit(low_speed_limit)
+	, low_speed_time(low_speed_time)
 	, ua(user_agent)
 	, prx(proxy)
 	, prxauth(proxy_auth)
@@ -100,6 +119,55 @@ static size_t handle_headers(void* ptr, size_t size, size_t nmemb, void* data)
 	return size * nmemb;
 }
 
+/// Watches a download from curl's progress callback, and remembers why it
+/// was aborted.
+struct DownloadProgress {
+	void* rs_watch;
+	std::string error;
+
+	DownloadProgress(unsigned int timeout,
+		unsigned int low_speed_limit,
+		unsigned int low_speed_time)
+		: rs_watch(rs_download_watch_new(
+				  timeout, low_speed_limit, low_speed_time))
+	{
+	}
+
+	~DownloadProgress()
+	{
+		rs_download_watch_free(rs_watch);
+	}
+
+	DownloadProgress(const DownloadProgress&) = delete;
+	DownloadProgress& operator=(const DownloadProgress&) = delete;
+};
+
+static int check_progress(void* clientp,
+	double /* dltotal */,
+	double dlnow,
+	double /* ultotal */,
+	double /* ulnow */)
+{
+	DownloadProgress* progress = static_cast<DownloadProgress*>(clientp);
+	progress->error = RustString(rs_download_watch_progress(
+				progress->rs_watch,
+				static_cast<std::uint64_t>(dlnow)));
+	return progress->error.empty() ? 0 : 1;
+}
+
+/// Returns an error message if the server announced more bytes than it sent.
+static std::string check_length(CURL* easyhandle)
+{
+	double expected = -1;
+	double received = 0;
+	curl_easy_getinfo(
+		easyhandle, CURLINFO_CONTENT_LENGTH_DOWNLOAD, &expected);
+	curl_easy_getinfo(easyhandle, CURLINFO_SIZE_DOWNLOAD, &received);
+	return RustString(rs_download_check_length(
+				static_cast<std::int64_t>(expected),
+				static_cast<std::uint64_t>(received)));
+}
+
 Feed Parser::parse_url(const std::string& url,
 	time_t lastmodified,
 	const std::string& etag,
@@ -126,6 +194,9 @@ Feed Parser::parse_url(const std::string& url,
 	if (api) {
 		api->add_custom_headers(&custom_headers);
 	}
+	for (const auto& header : extra_headers) {
+		custom_headers = curl_slist_append(custom_headers, header.c_str());
+	}
 	curl_easy_setopt(easyhandle, CURLOPT_URL, url.c_str());
 	curl_easy_setopt(easyhandle, CURLOPT_SSL_VERIFYPEER, verify_ssl);
 	curl_easy_setopt(easyhandle, CURLOPT_WRITEFUNCTION, my_write_data);
@@ -141,9 +212,12 @@ Feed Parser::parse_url(const std::string& url,
 		curl_easy_setopt(
 			easyhandle, CURLOPT_COOKIEJAR, cookie_cache.c_str());
 	}
-	if (to != 0) {
-		curl_easy_setopt(easyhandle, CURLOPT_TIMEOUT, to);
-	}
+	// The timeout and the low-speed limit are enforced by the progress
+	// callback rather than by curl, so that each has its own error message.
+	DownloadProgress progress(to, low_speed_limit, low_speed_time);
+	curl_easy_setopt(easyhandle, CURLOPT_NOPROGRESS, 0);
+	curl_easy_setopt(easyhandle, CURLOPT_PROGRESSFUNCTION, check_progress);
+	curl_easy_setopt(easyhandle, CURLOPT_PROGRESSDATA, &progress);
 
 	if (!prx.empty()) {
 		curl_easy_setopt(easyhandle, CURLOPT_PROXY, prx.c_str());
@@ -208,6 +282,16 @@ Feed Parser::parse_url(const std::string& url,
 	CURLcode infoOk =
 		curl_easy_getinfo(easyhandle, CURLINFO_RESPONSE_CODE, &status);
 
+	// Only a complete response is worth parsing. curl notices some
+	// truncated responses itself, but says nothing about how much is
+	// missing. Other responses, like 304, may announce a length without
+	// having a body.
+	std::string truncated;
+	if (ret == CURLE_PARTIAL_FILE
+		|| (ret == CURLE_OK && infoOk == CURLE_OK && status == 200)) {
+		truncated = check_length(easyhandle);
+	}
+
 	curl_easy_reset(easyhandle);
 	if (cookie_cache != "") {
 		curl_easy_setopt(
@@ -226,7 +310,11 @@ Feed Parser::parse_url(const std::string& url,
 			ret,
 			curl_easy_strerror(ret));
 		std::string msg;
-		if (ret == CURLE_HTTP_RETURNED_ERROR && infoOk == CURLE_OK) {
+		if (ret == CURLE_ABORTED_BY_CALLBACK && !progress.error.empty()) {
+			msg = progress.error;
+		} else if (ret == CURLE_PARTIAL_FILE && !truncated.empty()) {
+			msg = truncated;
+		} else if (ret == CURLE_HTTP_RETURNED_ERROR && infoOk == CURLE_OK) {
 			msg = strprintf::fmt(
 					"%s %" PRIi64,
 					curl_easy_strerror(ret),
@@ -237,7 +325,15 @@ Feed Parser::parse_url(const std::string& url,
 		} else {
 			msg = curl_easy_strerror(ret);
 		}
-		throw Exception(msg);
+		throw Exception(msg, ret);
+	}
+
+	if (!truncated.empty()) {
+		LOG(Level::ERROR,
+			"rsspp::Parser::parse_url: %s: %s",
+			url,
+			truncated);
+		throw Exception(truncated);
 	}
 
 	LOG(Level::INFO,
@@ -4,6 +4,7 @@
 #include <curl/curl.h>
 #include <libxml/parser.h>
 #include <string>
+#include <vector>
 
 #include "remoteapi.h"
 #include "feed.h"
@@ -17,7 +18,9 @@ public:
 		const std::string& proxy = "",
 		const std::string& proxy_auth = "",
 		curl_proxytype proxy_type = CURLPROXY_HTTP,
-		const bool ssl_verify = true);
+		const bool ssl_verify = true,
+		unsigned int low_speed_limit = 0,
+		unsigned int low_speed_time = 0);
 	~Parser();
 	Feed parse_url(const std::string& url,
 		time_t lastmodified = 0,
@@ -25,6 +28,12 @@ public:
 		newsboat::RemoteApi* api = 0,
 		const std::string& cookie_cache = "",
 		CURL* ehandle = 0);
+	/// \brief Headers, as "Name: value" lines, to send with every request
+	/// besides the ones the parser adds itself.
+	void set_custom_headers(const std::vector<std::string>& headers)
+	{
+		extra_headers = headers;
+	}
 	Feed parse_buffer(const std::string& buffer,
 		const std::string& url = "");
 	Feed parse_file(const std::string& filename);
@@ -43,11 +52,14 @@ public:
 private:
 	Feed parse_xmlnode(xmlNode* node);
 	unsigned int to;
+	unsigned int low_speed_limit;
+	unsigned int low_speed_time;
 	const std::string ua;
 	const std::string prx;
 	const std::string prxauth;
 	curl_proxytype prxtype;
 	const bool verify_ssl;
+	std::vector<std::string> extra_headers;
 	xmlDocPtr doc;
 	time_t lm;
 	std::string et;
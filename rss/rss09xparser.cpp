@@ -46,6 +46,22 @@ void Rss09xParser::parse_feed(Feed& f, xmlNode* rootNode)
 			f.language = get_content(node);
 		} else if (node_is(node, "managingEditor", ns)) {
 			f.managingeditor = get_content(node);
+		} else if (node_is(node, "ttl", ns)) {
+			f.ttl = get_content(node);
+		} else if (node_is(node, "skipHours", ns)) {
+			for (xmlNode* hour = node->children; hour != nullptr;
+				hour = hour->next) {
+				if (node_is(hour, "hour", ns)) {
+					f.skip_hours.push_back(get_content(hour));
+				}
+			}
+		} else if (node_is(node, "skipDays", ns)) {
+			for (xmlNode* day = node->children; day != nullptr;
+				day = day->next) {
+				if (node_is(day, "day", ns)) {
+					f.skip_days.push_back(get_content(day));
+				}
+			}
 		} else if (node_is(node, "item", ns)) {
 			f.items.push_back(parse_item(node));
 		}
@@ -0,0 +1,180 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::articlepreview::{Debouncer, PaneSize, PreviewCache, SystemClock};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+use std::time::Duration;
+
+type SystemDebouncer = Debouncer<SystemClock>;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+fn join_lines(lines: &[String]) -> *mut c_char {
+    // Preview lines have all control characters removed, so they contain neither newlines nor
+    // NUL bytes. Thus, `unwrap` won't panic.
+    CString::new(lines.join("\n")).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_preview_cache_new(max_entries: usize, max_bytes: usize) -> *mut c_void {
+    abort_on_panic(|| {
+        Box::into_raw(Box::new(PreviewCache::new(max_entries, max_bytes))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_free(cache: *mut c_void) {
+    abort_on_panic(|| {
+        if cache.is_null() {
+            return;
+        }
+        drop(Box::from_raw(cache as *mut PreviewCache));
+    })
+}
+
+unsafe fn with_cache<F, T>(cache: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&PreviewCache) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!cache.is_null());
+        // The cache locks internally, so it's enough to borrow it.
+        let cache = &*(cache as *const PreviewCache);
+        action(cache)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_set_limits(
+    cache: *mut c_void,
+    max_entries: usize,
+    max_bytes: usize,
+) {
+    with_cache(cache, |cache| cache.set_limits(max_entries, max_bytes))
+}
+
+/// Returns cached preview lines, joined with newlines, or a null pointer if there are none.
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_get(
+    cache: *mut c_void,
+    item: *const c_char,
+    width: usize,
+    height: usize,
+) -> *mut c_char {
+    let item = to_string(item);
+    with_cache(cache, move |cache| {
+        match cache.get(&item, PaneSize { width, height }) {
+            Some(lines) => join_lines(&lines),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// Fits `text` into the pane and caches the result. Returns the lines joined with newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_insert(
+    cache: *mut c_void,
+    item: *const c_char,
+    width: usize,
+    height: usize,
+    text: *const c_char,
+) -> *mut c_char {
+    let item = to_string(item);
+    let text = to_string(text);
+    with_cache(cache, move |cache| {
+        join_lines(&cache.insert(&item, PaneSize { width, height }, &text))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_pin(
+    cache: *mut c_void,
+    item: *const c_char,
+    width: usize,
+    height: usize,
+) {
+    let item = to_string(item);
+    with_cache(cache, move |cache| {
+        cache.pin(&item, PaneSize { width, height })
+    })
+}
+
+/// Hit/miss/eviction counters and the size of the cache, as a line for the log.
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_stats(cache: *mut c_void) -> *mut c_char {
+    // The description consists of numbers and ASCII words. Thus, `unwrap` won't panic.
+    with_cache(cache, |cache| {
+        CString::new(cache.stats().describe()).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_preview_cache_clear(cache: *mut c_void) {
+    with_cache(cache, |cache| cache.clear())
+}
+
+#[no_mangle]
+pub extern "C" fn rs_debouncer_new(delay_ms: u64) -> *mut c_void {
+    abort_on_panic(|| {
+        let debouncer = SystemDebouncer::new(Duration::from_millis(delay_ms), SystemClock);
+        Box::into_raw(Box::new(debouncer)) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_debouncer_free(debouncer: *mut c_void) {
+    abort_on_panic(|| {
+        if debouncer.is_null() {
+            return;
+        }
+        drop(Box::from_raw(debouncer as *mut SystemDebouncer));
+    })
+}
+
+unsafe fn with_debouncer<F, T>(debouncer: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut SystemDebouncer) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!debouncer.is_null());
+        let mut debouncer = Box::from_raw(debouncer as *mut SystemDebouncer);
+        let result = action(&mut debouncer);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(debouncer);
+        result
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_debouncer_request(debouncer: *mut c_void, item: *const c_char) {
+    let item = to_string(item);
+    with_debouncer(debouncer, move |debouncer| debouncer.request(&item))
+}
+
+/// Returns the item that should be rendered now, or a null pointer if there's none.
+#[no_mangle]
+pub unsafe extern "C" fn rs_debouncer_poll(debouncer: *mut c_void) -> *mut c_char {
+    with_debouncer(debouncer, |debouncer| match debouncer.poll() {
+        // The item came from C, so it doesn't contain NUL bytes. Thus, `unwrap` won't panic.
+        Some(item) => CString::new(item).unwrap().into_raw(),
+        None => ptr::null_mut(),
+    })
+}
+
+/// Milliseconds until the pending item becomes ready, or -1 if nothing is pending.
+#[no_mangle]
+pub unsafe extern "C" fn rs_debouncer_time_left_ms(debouncer: *mut c_void) -> i64 {
+    with_debouncer(debouncer, |debouncer| match debouncer.time_left() {
+        Some(left) => left.as_millis() as i64,
+        None => -1,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_debouncer_reset(debouncer: *mut c_void) {
+    with_debouncer(debouncer, |debouncer| debouncer.reset())
+}
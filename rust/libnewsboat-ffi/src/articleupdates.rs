@@ -0,0 +1,28 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::articleupdates;
+use std::ffi::CStr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_decide_article_update(
+    stored_title: *const c_char,
+    stored_content: *const c_char,
+    new_title: *const c_char,
+    new_content: *const c_char,
+    mark_unread: bool,
+) -> u8 {
+    abort_on_panic(|| {
+        articleupdates::decide(
+            &to_string(stored_title),
+            &to_string(stored_content),
+            &to_string(new_title),
+            &to_string(new_content),
+            mark_unread,
+        ) as u8
+    })
+}
@@ -0,0 +1,69 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::browser::{self, DetachedBrowsers};
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_browser_cmdline(
+    browser: *const c_char,
+    url: *const c_char,
+) -> *mut c_char {
+    let browser = to_string(browser);
+    let url = to_string(url);
+    abort_on_panic(move || {
+        // The command is made of `browser` and `url`, which came from C strings, so it doesn't
+        // contain NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(browser::browser_cmdline(&browser, &url))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_detached_browsers_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(DetachedBrowsers::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_detached_browsers_free(browsers: *mut c_void) {
+    abort_on_panic(|| {
+        if browsers.is_null() {
+            return;
+        }
+        drop(Box::from_raw(browsers as *mut DetachedBrowsers));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_detached_browsers_launch(
+    browsers: *mut c_void,
+    url: *const c_char,
+    cmdline: *const c_char,
+) -> bool {
+    let url = to_string(url);
+    let cmdline = to_string(cmdline);
+    abort_on_panic(move || {
+        assert!(!browsers.is_null());
+        let browsers = &*(browsers as *const DetachedBrowsers);
+        browsers.launch(&url, &cmdline)
+    })
+}
+
+/// Messages about the browsers that failed since the last call, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_detached_browsers_take_failures(browsers: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!browsers.is_null());
+        let browsers = &*(browsers as *const DetachedBrowsers);
+        // The messages are made of our own text and URLs that came from C strings. Thus, `unwrap`
+        // won't panic.
+        CString::new(browsers.take_failures().join("\n"))
+            .unwrap()
+            .into_raw()
+    })
+}
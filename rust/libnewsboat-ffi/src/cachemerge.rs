@@ -0,0 +1,151 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::cachemerge::{Article, CacheMerge, Decision, ReadPreference};
+use std::ffi::{CStr, CString};
+use std::mem;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn to_article(
+    guid: *const c_char,
+    feedurl: *const c_char,
+    link: *const c_char,
+    title: *const c_char,
+    content: *const c_char,
+    unread: bool,
+    flags: *const c_char,
+    note: *const c_char,
+) -> Article {
+    Article {
+        guid: to_string(guid),
+        feedurl: to_string(feedurl),
+        link: to_string(link),
+        title: to_string(title),
+        content: to_string(content),
+        unread,
+        flags: to_string(flags),
+        note: to_string(note),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_cache_merge_new(prefer_read: bool) -> *mut c_void {
+    abort_on_panic(|| {
+        let preference = if prefer_read {
+            ReadPreference::Read
+        } else {
+            ReadPreference::Unread
+        };
+        Box::into_raw(Box::new(CacheMerge::new(preference))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cache_merge_free(merge: *mut c_void) {
+    abort_on_panic(|| {
+        if merge.is_null() {
+            return;
+        }
+        drop(Box::from_raw(merge as *mut CacheMerge));
+    })
+}
+
+unsafe fn with_merge<F, T>(merge: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut CacheMerge) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!merge.is_null());
+        let mut merge = Box::from_raw(merge as *mut CacheMerge);
+        let result = action(&mut merge);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(merge);
+        result
+    })
+}
+
+/// Registers an article that is in the destination cache.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn rs_cache_merge_add_existing(
+    merge: *mut c_void,
+    guid: *const c_char,
+    feedurl: *const c_char,
+    link: *const c_char,
+    title: *const c_char,
+    content: *const c_char,
+    unread: bool,
+    flags: *const c_char,
+    note: *const c_char,
+) {
+    let article = to_article(guid, feedurl, link, title, content, unread, flags, note);
+    with_merge(merge, move |merge| merge.add_existing(&article))
+}
+
+/// Decides what to do with an article from the other cache. Returns 0 if nothing should change,
+/// 1 if the article should be copied, and 2 if the article stored under `*guid_out` should get
+/// the state in `*unread_out`, `*flags_out` and `*note_out`. The strings put into `*guid_out`,
+/// `*flags_out` and `*note_out` have to be freed with `rs_cstring_free`.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn rs_cache_merge_decide(
+    merge: *mut c_void,
+    guid: *const c_char,
+    feedurl: *const c_char,
+    link: *const c_char,
+    title: *const c_char,
+    content: *const c_char,
+    unread: bool,
+    flags: *const c_char,
+    note: *const c_char,
+    guid_out: *mut *mut c_char,
+    unread_out: *mut bool,
+    flags_out: *mut *mut c_char,
+    note_out: *mut *mut c_char,
+) -> u8 {
+    assert!(!guid_out.is_null());
+    assert!(!unread_out.is_null());
+    assert!(!flags_out.is_null());
+    assert!(!note_out.is_null());
+    let article = to_article(guid, feedurl, link, title, content, unread, flags, note);
+    let decision = with_merge(merge, move |merge| merge.decide(&article));
+    abort_on_panic(|| match decision {
+        Decision::Keep => 0,
+        Decision::Add => 1,
+        Decision::Update {
+            guid,
+            unread,
+            flags,
+            note,
+        } => {
+            // The GUID came from C, so it contains no NUL bytes, and `unwrap` won't panic.
+            *guid_out = CString::new(guid).unwrap().into_raw();
+            *unread_out = unread;
+            // Flags consist of ASCII letters only, so `unwrap` won't panic.
+            *flags_out = CString::new(flags).unwrap().into_raw();
+            // Notes are combined from ones that came from C. Thus, `unwrap` won't panic.
+            *note_out = CString::new(note).unwrap().into_raw();
+            2
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cache_merge_summary(
+    merge: *mut c_void,
+    added: *mut usize,
+    merged: *mut usize,
+    conflicts: *mut usize,
+) {
+    assert!(!added.is_null());
+    assert!(!merged.is_null());
+    assert!(!conflicts.is_null());
+    let summary = with_merge(merge, |merge| merge.summary());
+    *added = summary.added;
+    *merged = summary.merged;
+    *conflicts = summary.conflicts;
+}
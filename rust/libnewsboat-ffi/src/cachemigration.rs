@@ -0,0 +1,122 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::cachemigration::{self, Chunk, MigrationPlan, Version};
+use std::ffi::CString;
+use std::ptr;
+
+#[no_mangle]
+pub extern "C" fn rs_migration_plan_new() -> *mut c_void {
+    abort_on_panic(|| {
+        Box::into_raw(Box::new(MigrationPlan::new(cachemigration::CHUNK_ROWS))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_migration_plan_free(plan: *mut c_void) {
+    abort_on_panic(|| {
+        if plan.is_null() {
+            return;
+        }
+        drop(Box::from_raw(plan as *mut MigrationPlan));
+    })
+}
+
+/// Adds a migration of the rows up to `last_row`, which was already done up to `done_up_to`.
+/// Returns its index.
+#[no_mangle]
+pub unsafe extern "C" fn rs_migration_plan_add(
+    plan: *mut c_void,
+    last_row: u64,
+    done_up_to: u64,
+) -> usize {
+    abort_on_panic(|| {
+        assert!(!plan.is_null());
+        let plan = &mut *(plan as *mut MigrationPlan);
+        plan.add(last_row, done_up_to)
+    })
+}
+
+/// Stores the next chunk of the migration at `index` into `after` and `up_to`. Returns `false` if
+/// the migration is done.
+#[no_mangle]
+pub unsafe extern "C" fn rs_migration_plan_next_chunk(
+    plan: *mut c_void,
+    index: usize,
+    after: *mut u64,
+    up_to: *mut u64,
+) -> bool {
+    abort_on_panic(|| {
+        assert!(!plan.is_null());
+        assert!(!after.is_null());
+        assert!(!up_to.is_null());
+        let plan = &*(plan as *const MigrationPlan);
+        match plan.next_chunk(index) {
+            Some(chunk) => {
+                *after = chunk.after;
+                *up_to = chunk.up_to;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_migration_plan_chunk_done(
+    plan: *mut c_void,
+    index: usize,
+    after: u64,
+    up_to: u64,
+) {
+    abort_on_panic(|| {
+        assert!(!plan.is_null());
+        let plan = &mut *(plan as *mut MigrationPlan);
+        plan.chunk_done(index, Chunk { after, up_to });
+    })
+}
+
+/// Returns the progress message if the percentage changed since the last one, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rs_migration_plan_progress(plan: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!plan.is_null());
+        let plan = &mut *(plan as *mut MigrationPlan);
+        match plan.progress() {
+            // The message is a translated text and a number. Thus, `unwrap` won't panic.
+            Some(message) => CString::new(message).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// Stores the version to record as the oldest one that may open a cache this binary migrated.
+#[no_mangle]
+pub unsafe extern "C" fn rs_cache_min_reader_version(major: *mut u32, minor: *mut u32) {
+    abort_on_panic(|| {
+        assert!(!major.is_null());
+        assert!(!minor.is_null());
+        let current = Version::current();
+        *major = current.major;
+        *minor = current.minor;
+    })
+}
+
+/// Returns the message to show if this binary may not open a cache that was last migrated by
+/// `min_reader_major`.`min_reader_minor`, or null if it may.
+#[no_mangle]
+pub extern "C" fn rs_check_reader_version(
+    min_reader_major: u32,
+    min_reader_minor: u32,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let min_reader = Version {
+            major: min_reader_major,
+            minor: min_reader_minor,
+        };
+        match cachemigration::check_reader_version(min_reader, Version::current()) {
+            Ok(()) => ptr::null_mut(),
+            // The message is a translated text and version numbers. Thus, `unwrap` won't panic.
+            Err(message) => CString::new(message).unwrap().into_raw(),
+        }
+    })
+}
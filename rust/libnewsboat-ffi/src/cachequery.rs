@@ -0,0 +1,77 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::cachequery::{OutputFormat, QueryResult};
+use std::ffi::{CStr, CString};
+use std::mem;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_query_result_new(
+    json: bool,
+    fields: *const *const c_char,
+    field_count: usize,
+) -> *mut c_void {
+    let fields = (0..field_count)
+        .map(|i| to_string(*fields.add(i)))
+        .collect::<Vec<_>>();
+    abort_on_panic(move || {
+        let format = if json {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Tsv
+        };
+        Box::into_raw(Box::new(QueryResult::new(format, fields))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_query_result_free(result: *mut c_void) {
+    abort_on_panic(|| {
+        if result.is_null() {
+            return;
+        }
+        drop(Box::from_raw(result as *mut QueryResult));
+    })
+}
+
+/// Adds an article. `values` holds one value per field; a null pointer means the article doesn't
+/// have the attribute.
+#[no_mangle]
+pub unsafe extern "C" fn rs_query_result_add_row(
+    result: *mut c_void,
+    values: *const *const c_char,
+    value_count: usize,
+) {
+    let values = (0..value_count)
+        .map(|i| {
+            let value = *values.add(i);
+            if value.is_null() {
+                None
+            } else {
+                Some(to_string(value))
+            }
+        })
+        .collect::<Vec<_>>();
+    abort_on_panic(move || {
+        assert!(!result.is_null());
+        let mut result = Box::from_raw(result as *mut QueryResult);
+        result.add_row(values);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(result);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_query_result_render(result: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!result.is_null());
+        let result = &*(result as *mut QueryResult);
+        // The output is made of field names and values that came from C, and of the text that
+        // separates them. Thus, it doesn't contain NUL bytes, and `unwrap` won't panic.
+        CString::new(result.render()).unwrap().into_raw()
+    })
+}
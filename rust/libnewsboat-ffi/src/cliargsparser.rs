@@ -1,6 +1,9 @@
 use crate::abort_on_panic;
 use libc::{c_char, c_void};
+use libnewsboat::cachemerge::ReadPreference;
+use libnewsboat::cachequery::OutputFormat;
 use libnewsboat::cliargsparser::CliArgsParser;
+use libnewsboat::clierror::ErrorFormat;
 use libnewsboat::logger::Level;
 use std::ffi::{CStr, CString};
 use std::mem;
@@ -93,11 +96,21 @@ pub unsafe extern "C" fn rs_cliargsparser_do_export(object: *mut c_void) -> bool
     with_cliargsparser(object, |o| o.do_export, false)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_export_opml2(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.export_opml2, false)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_cliargsparser_do_vacuum(object: *mut c_void) -> bool {
     with_cliargsparser(object, |o| o.do_vacuum, false)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_cleanup(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.cleanup, false)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_cliargsparser_program_name(object: *mut c_void) -> *mut c_char {
     with_cliargsparser_str(object, |o| &o.program_name)
@@ -241,6 +254,27 @@ pub unsafe extern "C" fn rs_cliargsparser_cmd_to_execute_n(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_config_overrides_count(object: *mut c_void) -> usize {
+    with_cliargsparser(object, |o| o.config_overrides.len(), 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_config_override_key_n(
+    object: *mut c_void,
+    n: usize,
+) -> *mut c_char {
+    with_cliargsparser_str(object, |o| &o.config_overrides[n].0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_config_override_value_n(
+    object: *mut c_void,
+    n: usize,
+) -> *mut c_char {
+    with_cliargsparser_str(object, |o| &o.config_overrides[n].1)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_cliargsparser_set_log_file(object: *mut c_void) -> bool {
     with_cliargsparser(object, |o| o.log_file.is_some(), false)
@@ -251,6 +285,125 @@ pub unsafe extern "C" fn rs_cliargsparser_log_file(object: *mut c_void) -> *mut
     with_cliargsparser_opt_pathbuf(object, |o| &o.log_file)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_set_add_feed(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.add_feed.is_some(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_add_feed(object: *mut c_void) -> *mut c_char {
+    with_cliargsparser_str(object, |o| o.add_feed.as_ref().map_or("", String::as_str))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_set_url_argument(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.url_argument.is_some(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_url_argument(object: *mut c_void) -> *mut c_char {
+    with_cliargsparser_str(object, |o| {
+        o.url_argument.as_ref().map_or("", String::as_str)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_set_profile(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.profile.is_some(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_profile(object: *mut c_void) -> *mut c_char {
+    with_cliargsparser_str(object, |o| o.profile.as_ref().map_or("", String::as_str))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_list_profiles(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.list_profiles, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_set_merge_cache_file(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.merge_cache_file.is_some(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_merge_cache_file(object: *mut c_void) -> *mut c_char {
+    with_cliargsparser_opt_pathbuf(object, |o| &o.merge_cache_file)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_merge_prefer_read(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.merge_prefer == ReadPreference::Read, true)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_recover_cache(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.recover_cache, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_force(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.force, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_import_from_newsbeuter(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.import_from_newsbeuter, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_dry_run_migration(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.dry_run_migration, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_convert_to_utf8(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.convert_to_utf8, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_plain_output(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.plain_output, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_set_query_cache(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.query_cache.is_some(), false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_query_cache(object: *mut c_void) -> *mut c_char {
+    with_cliargsparser_str(object, |o| {
+        o.query_cache.as_ref().map_or("", String::as_str)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_query_fields_count(object: *mut c_void) -> usize {
+    with_cliargsparser(object, |o| o.query_fields.len(), 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_query_field_n(
+    object: *mut c_void,
+    n: usize,
+) -> *mut c_char {
+    with_cliargsparser_str(object, move |o| {
+        o.query_fields.get(n).map_or("", String::as_str)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_query_format_json(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.query_format == OutputFormat::Json, false)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cliargsparser_json_errors(object: *mut c_void) -> bool {
+    with_cliargsparser(object, |o| o.error_format == ErrorFormat::Json, false)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_cliargsparser_set_log_level(object: *mut c_void) -> bool {
     with_cliargsparser(object, |o| o.log_level.is_some(), false)
@@ -0,0 +1,39 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_int};
+use libnewsboat::clierror::{CliError, ErrorCategory, ErrorFormat};
+use std::ffi::{CStr, CString};
+
+#[no_mangle]
+pub extern "C" fn rs_cli_error_exit_code(category: u8) -> c_int {
+    abort_on_panic(|| ErrorCategory::from_u8(category).exit_code())
+}
+
+/// Formats an error for printing to stderr. `file` can be null, and `line` can be zero if the
+/// location of the error is unknown.
+#[no_mangle]
+pub unsafe extern "C" fn rs_cli_error_format(
+    category: u8,
+    message: *const c_char,
+    file: *const c_char,
+    line: u32,
+    json: bool,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let message = CStr::from_ptr(message).to_string_lossy();
+        let mut error = CliError::new(ErrorCategory::from_u8(category), &message);
+        if !file.is_null() {
+            error.file = Some(CStr::from_ptr(file).to_string_lossy().into_owned());
+        }
+        if line > 0 {
+            error.line = Some(line);
+        }
+        let format = if json {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Text
+        };
+        // The result consists of strings that came from C (and thus don't contain NUL bytes), and
+        // of our own text, which doesn't contain NUL bytes either. Thus, `unwrap` won't panic.
+        CString::new(error.format(format)).unwrap().into_raw()
+    })
+}
@@ -0,0 +1,87 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::clioutput::{CliReport, OutputStyle};
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Joins `lines` with newlines, for C to split them again.
+fn join_lines(lines: Vec<String>) -> *mut c_char {
+    // Titles, URLs and messages came from C as C strings, and the rest is our own text. Thus,
+    // there are no NUL bytes, and `unwrap` won't panic.
+    CString::new(lines.join("\n")).unwrap().into_raw()
+}
+
+/// Creates a report that's laid out for our stdout, or for a pipe if `force_plain` is `true`.
+#[no_mangle]
+pub extern "C" fn rs_cli_report_new(force_plain: bool) -> *mut c_void {
+    abort_on_panic(|| {
+        let style = OutputStyle::for_stdout(force_plain);
+        Box::into_raw(Box::new(CliReport::new(style))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cli_report_free(report: *mut c_void) {
+    abort_on_panic(|| {
+        if report.is_null() {
+            return;
+        }
+        drop(Box::from_raw(report as *mut CliReport));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cli_report_add_feed(
+    report: *mut c_void,
+    title: *const c_char,
+    unread: u32,
+) {
+    let title = to_string(title);
+    abort_on_panic(move || {
+        assert!(!report.is_null());
+        let report = &mut *(report as *mut CliReport);
+        report.add_feed(&title, unread);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_cli_report_add_failure(
+    report: *mut c_void,
+    url: *const c_char,
+    message: *const c_char,
+) {
+    let url = to_string(url);
+    let message = to_string(message);
+    abort_on_panic(move || {
+        assert!(!report.is_null());
+        let report = &mut *(report as *mut CliReport);
+        report.add_failure(&url, &message);
+    })
+}
+
+/// Returns the lines of the summary, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_cli_report_unread_summary(
+    report: *mut c_void,
+    total: u32,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!report.is_null());
+        let report = &*(report as *const CliReport);
+        join_lines(report.unread_summary(total))
+    })
+}
+
+/// Returns the lines of the report, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_cli_report_failure_report(report: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!report.is_null());
+        let report = &*(report as *const CliReport);
+        join_lines(report.failure_report())
+    })
+}
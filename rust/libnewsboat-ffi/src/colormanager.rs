@@ -0,0 +1,119 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::colormanager::ColorManager;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+
+#[no_mangle]
+pub extern "C" fn rs_color_manager_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(ColorManager::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_color_manager_free(manager: *mut c_void) {
+    abort_on_panic(|| {
+        if manager.is_null() {
+            return;
+        }
+        drop(Box::from_raw(manager as *mut ColorManager));
+    })
+}
+
+unsafe fn with_manager<F, T>(manager: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut ColorManager) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!manager.is_null());
+        let mut manager = Box::from_raw(manager as *mut ColorManager);
+        let result = action(&mut manager);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(manager);
+        result
+    })
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Handles the arguments of a `color` command. Returns the error message, or a null pointer if
+/// the command was fine.
+#[no_mangle]
+pub unsafe extern "C" fn rs_color_manager_handle_color(
+    manager: *mut c_void,
+    params: *const *const c_char,
+    params_count: usize,
+) -> *mut c_char {
+    let params = (0..params_count)
+        .map(|i| to_string(*params.add(i)))
+        .collect::<Vec<_>>();
+    with_manager(manager, move |m| match m.handle_color(&params) {
+        Ok(()) => ptr::null_mut(),
+        // The message is made of our own text and the arguments, which came from C++ as C
+        // strings. Thus, `unwrap` won't panic.
+        Err(error) => CString::new(error.to_message()).unwrap().into_raw(),
+    })
+}
+
+/// Names of the configured elements, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_color_manager_elements(manager: *mut c_void) -> *mut c_char {
+    with_manager(manager, |m| {
+        let elements = m.styles().map(|(element, _)| element).collect::<Vec<_>>();
+        // Element names are our own constants. Thus, `unwrap` won't panic.
+        CString::new(elements.join("\n")).unwrap().into_raw()
+    })
+}
+
+/// Writes the colors of `element` into `fg` and `bg`, and returns its attributes, separated by
+/// newlines. Returns a null pointer (and leaves `fg` and `bg` alone) if `element` isn't
+/// configured.
+#[no_mangle]
+pub unsafe extern "C" fn rs_color_manager_style(
+    manager: *mut c_void,
+    element: *const c_char,
+    fg: *mut *mut c_char,
+    bg: *mut *mut c_char,
+) -> *mut c_char {
+    let element = to_string(element);
+    with_manager(manager, move |m| match m.style(&element) {
+        Some(style) => {
+            assert!(!fg.is_null() && !bg.is_null());
+            // Colors are our own names and numbers. Thus, `unwrap` won't panic.
+            *fg = CString::new(style.fg.to_string()).unwrap().into_raw();
+            *bg = CString::new(style.bg.to_string()).unwrap().into_raw();
+            // Attributes are our own constants. Thus, `unwrap` won't panic.
+            CString::new(style.attributes.join("\n"))
+                .unwrap()
+                .into_raw()
+        }
+        None => ptr::null_mut(),
+    })
+}
+
+/// Style of `element` in STFL's syntax, before it's degraded for the terminal. Returns a null
+/// pointer if `element` isn't configured.
+#[no_mangle]
+pub unsafe extern "C" fn rs_color_manager_stfl_style(
+    manager: *mut c_void,
+    element: *const c_char,
+) -> *mut c_char {
+    let element = to_string(element);
+    with_manager(manager, move |m| match m.style(&element) {
+        // The style is made of our own names and numbers. Thus, `unwrap` won't panic.
+        Some(style) => CString::new(style.to_stfl()).unwrap().into_raw(),
+        None => ptr::null_mut(),
+    })
+}
+
+/// `color` commands that recreate the configured styles, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_color_manager_dump_config(manager: *mut c_void) -> *mut c_char {
+    with_manager(manager, |m| {
+        // The commands are made of our own names and numbers. Thus, `unwrap` won't panic.
+        CString::new(m.dump_config().join("\n")).unwrap().into_raw()
+    })
+}
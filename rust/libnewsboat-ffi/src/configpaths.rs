@@ -95,6 +95,24 @@ pub unsafe extern "C" fn rs_configpaths_try_migrate_from_newsbeuter(object: *mut
     with_configpaths(object, |o| o.try_migrate_from_newsbeuter(), false)
 }
 
+/// Describes what `rs_configpaths_try_migrate_from_newsbeuter` would copy. Returns a null pointer
+/// if there's nothing to migrate.
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_describe_migration_plan(
+    object: *mut c_void,
+) -> *mut c_char {
+    with_configpaths(
+        object,
+        |o| match o.plan_migration() {
+            // The description is made of translated texts and paths, neither of which contain NUL
+            // bytes, so `unwrap` won't panic.
+            Some(plan) => CString::new(plan.describe()).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        },
+        ptr::null_mut(),
+    )
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_configpaths_create_dirs(object: *mut c_void) -> bool {
     with_configpaths(object, |o| o.create_dirs(), false)
@@ -105,6 +123,11 @@ pub unsafe extern "C" fn rs_configpaths_url_file(object: *mut c_void) -> *mut c_
     with_configpaths_path(object, |o| o.url_file())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_data_dir(object: *mut c_void) -> *mut c_char {
+    with_configpaths_string(object, |o| o.data_dir().to_string_lossy().into_owned())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_configpaths_cache_file(object: *mut c_void) -> *mut c_char {
     with_configpaths_path(object, |o| o.cache_file())
@@ -154,3 +177,32 @@ pub unsafe extern "C" fn rs_configpaths_search_file(object: *mut c_void) -> *mut
 pub unsafe extern "C" fn rs_configpaths_cmdline_file(object: *mut c_void) -> *mut c_char {
     with_configpaths_path(object, |o| o.cmdline_file())
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_feedlist_snapshot_file(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.feedlist_snapshot_file())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_url_overlay_file(object: *mut c_void) -> *mut c_char {
+    with_configpaths_path(object, |o| o.url_overlay_file())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_profiles_count(object: *mut c_void) -> usize {
+    with_configpaths(object, |o| o.profiles().len(), 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_configpaths_profile_n(object: *mut c_void, n: usize) -> *mut c_char {
+    with_configpaths(
+        object,
+        |o| match o.profiles().get(n) {
+            // Profile names come from directory names, which can't contain null bytes. Thus,
+            // `unwrap` won't panic.
+            Some(profile) => CString::new(profile.clone()).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        },
+        ptr::null_mut(),
+    )
+}
@@ -0,0 +1,29 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::configvalidation;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Returns the reason why `value` is not a valid value of `option`, or a null pointer if it's
+/// valid.
+#[no_mangle]
+pub unsafe extern "C" fn rs_config_validate(
+    option: *const c_char,
+    value: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let option = to_string(option);
+        let value = to_string(value);
+        match configvalidation::validate(&option, &value) {
+            Ok(()) => ptr::null_mut(),
+            // The message consists of a translated text and parts of `value`, which came from C.
+            // Thus, it doesn't contain NUL bytes, and `unwrap` won't panic.
+            Err(message) => CString::new(message).unwrap().into_raw(),
+        }
+    })
+}
@@ -0,0 +1,194 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::controlsocket::{self, Command, Connection, ControlServer, Response, SendError};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_socket_path(lock_file: *const c_char) -> *mut c_char {
+    let lock_file = to_string(lock_file);
+    abort_on_panic(move || {
+        let path = controlsocket::socket_path(Path::new(&lock_file));
+        // The path was built from a string that came from C. Thus, it doesn't contain NUL bytes,
+        // and `unwrap` won't panic.
+        CString::new(path.to_string_lossy().into_owned())
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Returns the normalized URL, or a null pointer and sets `error` if `url` isn't a feed URL.
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_socket_normalize_feed_url(
+    url: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let url = to_string(url);
+    abort_on_panic(move || {
+        assert!(!error.is_null());
+        *error = ptr::null_mut();
+        match controlsocket::normalize_feed_url(&url) {
+            // The URL is a part of a string that came from C. Thus, it doesn't contain NUL bytes,
+            // and `unwrap` won't panic.
+            Ok(url) => CString::new(url).unwrap().into_raw(),
+            Err(message) => {
+                // The message is a translated text and a string that came from C. Thus, it
+                // doesn't contain NUL bytes, and `unwrap` won't panic.
+                *error = CString::new(message).unwrap().into_raw();
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Sends an `add-feed` command for `url` to the instance that listens on `path`. Returns 0 if
+/// the instance answered, 1 if there is no instance listening, and 2 if something else went wrong.
+///
+/// If the instance answered, `ok` is set to whether it carried out the command, and `message` to
+/// its message. Otherwise, `message` is set to the description of the problem.
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_socket_send_add_feed(
+    path: *const c_char,
+    url: *const c_char,
+    ok: *mut bool,
+    message: *mut *mut c_char,
+) -> u8 {
+    let path = to_string(path);
+    let url = to_string(url);
+    abort_on_panic(move || {
+        assert!(!ok.is_null());
+        assert!(!message.is_null());
+        *ok = false;
+        let (status, text) = match Command::parse(&format!("{} {}", controlsocket::ADD_FEED, url)) {
+            Ok(command) => match controlsocket::send(Path::new(&path), &command) {
+                Ok(response) => {
+                    *ok = response.ok;
+                    (0, response.message)
+                }
+                Err(SendError::NoInstance) => (1, SendError::NoInstance.to_message()),
+                Err(error) => (2, error.to_message()),
+            },
+            Err(error) => (2, error),
+        };
+        // The text is a translated message, a system error message, or a response that was read
+        // with `read_line`, which doesn't accept NUL bytes in UTF-8. Thus, `unwrap` won't panic.
+        *message = CString::new(text).unwrap().into_raw();
+        status
+    })
+}
+
+/// Returns a `ControlServer` that listens on `path`, or a null pointer and sets `error` if that
+/// isn't possible.
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_server_bind(
+    path: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    let path = to_string(path);
+    abort_on_panic(move || {
+        assert!(!error.is_null());
+        *error = ptr::null_mut();
+        match ControlServer::bind(Path::new(&path)) {
+            Ok(server) => Box::into_raw(Box::new(server)) as *mut c_void,
+            Err(e) => {
+                // The message is a translated text or a system error message. Thus, it doesn't
+                // contain NUL bytes, and `unwrap` won't panic.
+                *error = CString::new(e.to_string()).unwrap().into_raw();
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_server_free(server: *mut c_void) {
+    abort_on_panic(|| {
+        if server.is_null() {
+            return;
+        }
+        drop(Box::from_raw(server as *mut ControlServer));
+    })
+}
+
+/// Blocks until another `newsboat` connects. Returns the connection, which has to be freed with
+/// `rs_control_connection_free`, or a null pointer once `rs_control_server_shut_down` was called.
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_server_accept(server: *mut c_void) -> *mut c_void {
+    abort_on_panic(|| {
+        assert!(!server.is_null());
+        let server = &*(server as *const ControlServer);
+        match server.accept() {
+            Some(connection) => Box::into_raw(Box::new(connection)) as *mut c_void,
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_server_shut_down(server: *mut c_void) {
+    abort_on_panic(|| {
+        assert!(!server.is_null());
+        let server = &*(server as *const ControlServer);
+        server.shut_down();
+    })
+}
+
+/// Reads the command. Returns its name and sets `argument`, or returns a null pointer if the
+/// command was invalid (in which case the error was sent back already).
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_connection_read_command(
+    connection: *mut c_void,
+    argument: *mut *mut c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!connection.is_null());
+        assert!(!argument.is_null());
+        *argument = ptr::null_mut();
+        let connection = &mut *(connection as *mut Connection);
+        match connection.read_command() {
+            Some(command) => {
+                // The argument was read with `read_line`, which doesn't accept NUL bytes in UTF-8.
+                // Thus, `unwrap` won't panic.
+                *argument = CString::new(command.argument()).unwrap().into_raw();
+                // The name is a fixed string. Thus, `unwrap` won't panic.
+                CString::new(command.name()).unwrap().into_raw()
+            }
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_connection_respond(
+    connection: *mut c_void,
+    ok: bool,
+    message: *const c_char,
+) {
+    let message = to_string(message);
+    abort_on_panic(move || {
+        assert!(!connection.is_null());
+        let connection = &mut *(connection as *mut Connection);
+        let response = if ok {
+            Response::ok(&message)
+        } else {
+            Response::error(&message)
+        };
+        connection.respond(&response);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_control_connection_free(connection: *mut c_void) {
+    abort_on_panic(|| {
+        if connection.is_null() {
+            return;
+        }
+        drop(Box::from_raw(connection as *mut Connection));
+    })
+}
@@ -0,0 +1,142 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::defaultactions::{self, DefaultActions, Slot};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Splits a newline-separated list of contexts.
+fn to_contexts(input: &str) -> Vec<String> {
+    input
+        .split('\n')
+        .filter(|context| !context.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn to_slot(secondary: bool) -> Slot {
+    if secondary {
+        Slot::Secondary
+    } else {
+        Slot::Primary
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_default_actions_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(DefaultActions::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_default_actions_free(actions: *mut c_void) {
+    abort_on_panic(|| {
+        if actions.is_null() {
+            return;
+        }
+        drop(Box::from_raw(actions as *mut DefaultActions));
+    })
+}
+
+/// `contexts` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_default_actions_register_operation(
+    actions: *mut c_void,
+    operation: *const c_char,
+    contexts: *const c_char,
+) {
+    let operation = to_string(operation);
+    let contexts = to_string(contexts);
+    abort_on_panic(move || {
+        assert!(!actions.is_null());
+        let actions = &mut *(actions as *mut DefaultActions);
+        actions.register_operation(&operation, &to_contexts(&contexts));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_default_actions_set_builtin(
+    actions: *mut c_void,
+    context: *const c_char,
+    secondary: bool,
+    operation: *const c_char,
+) {
+    let context = to_string(context);
+    let operation = to_string(operation);
+    abort_on_panic(move || {
+        assert!(!actions.is_null());
+        let actions = &mut *(actions as *mut DefaultActions);
+        actions.set_builtin(&context, to_slot(secondary), &operation);
+    })
+}
+
+/// Returns an error message if `operation` can't be used in `context`, or a null pointer if it
+/// was set.
+#[no_mangle]
+pub unsafe extern "C" fn rs_default_actions_configure(
+    actions: *mut c_void,
+    context: *const c_char,
+    secondary: bool,
+    operation: *const c_char,
+) -> *mut c_char {
+    let context = to_string(context);
+    let operation = to_string(operation);
+    abort_on_panic(move || {
+        assert!(!actions.is_null());
+        let actions = &mut *(actions as *mut DefaultActions);
+        match actions.configure(&context, to_slot(secondary), &operation) {
+            Ok(()) => ptr::null_mut(),
+            // The message consists of a translated text and strings that came from C. Thus, it
+            // doesn't contain NUL bytes, and `unwrap` won't panic.
+            Err(message) => CString::new(message).unwrap().into_raw(),
+        }
+    })
+}
+
+/// Returns the operation that the slot stands for in `context`, or a null pointer if none.
+#[no_mangle]
+pub unsafe extern "C" fn rs_default_actions_resolve(
+    actions: *mut c_void,
+    context: *const c_char,
+    secondary: bool,
+) -> *mut c_char {
+    let context = to_string(context);
+    abort_on_panic(move || {
+        assert!(!actions.is_null());
+        let actions = &*(actions as *const DefaultActions);
+        match actions.resolve(&context, to_slot(secondary)) {
+            // The operation came from C. Thus, it doesn't contain NUL bytes, and `unwrap` won't
+            // panic.
+            Some(operation) => CString::new(operation).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_default_actions_option_name(
+    context: *const c_char,
+    secondary: bool,
+) -> *mut c_char {
+    let context = to_string(context);
+    abort_on_panic(move || {
+        // The context came from C. Thus, it doesn't contain NUL bytes, and `unwrap` won't panic.
+        CString::new(to_slot(secondary).option_name(&context))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Returns the contexts whose default actions can be configured, as a newline-separated list.
+#[no_mangle]
+pub extern "C" fn rs_default_actions_configurable_contexts() -> *mut c_char {
+    abort_on_panic(|| {
+        // The contexts are literals without NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(defaultactions::CONFIGURABLE_CONTEXTS.join("\n"))
+            .unwrap()
+            .into_raw()
+    })
+}
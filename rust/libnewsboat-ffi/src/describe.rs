@@ -0,0 +1,114 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::describe::{self, OperationDescription, OptionDescription};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Splits a newline-separated list.
+fn to_list(input: &str) -> Vec<String> {
+    input
+        .split('\n')
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn to_c_string(text: String) -> *mut c_char {
+    // The text consists of translated messages and strings that came from C. Thus, it doesn't
+    // contain NUL bytes, and `unwrap` won't panic.
+    CString::new(text).unwrap().into_raw()
+}
+
+/// Returns the key that `argument` names, or a null pointer if it doesn't name one.
+#[no_mangle]
+pub unsafe extern "C" fn rs_describe_key_argument(argument: *const c_char) -> *mut c_char {
+    let argument = to_string(argument);
+    abort_on_panic(move || match describe::key_argument(&argument) {
+        Some(key) => to_c_string(key.to_string()),
+        None => ptr::null_mut(),
+    })
+}
+
+/// `values` is a newline-separated list. An empty `origin` means that no config command set the
+/// option. Returns the lines of the description, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_describe_option(
+    name: *const c_char,
+    kind: *const c_char,
+    values: *const c_char,
+    default_value: *const c_char,
+    value: *const c_char,
+    origin: *const c_char,
+) -> *mut c_char {
+    let origin = to_string(origin);
+    let description = OptionDescription {
+        name: to_string(name),
+        kind: to_string(kind),
+        values: to_list(&to_string(values)),
+        default_value: to_string(default_value),
+        value: to_string(value),
+        origin: if origin.is_empty() {
+            None
+        } else {
+            Some(origin)
+        },
+    };
+    abort_on_panic(move || to_c_string(description.lines().join("\n")))
+}
+
+/// `contexts`, `binding_contexts` and `binding_keys` are newline-separated lists; the last two
+/// have the same length, and together list the keys bound to the operation. Returns the lines of
+/// the description, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_describe_operation(
+    name: *const c_char,
+    description: *const c_char,
+    contexts: *const c_char,
+    binding_contexts: *const c_char,
+    binding_keys: *const c_char,
+) -> *mut c_char {
+    let binding_contexts = to_list(&to_string(binding_contexts));
+    let binding_keys = to_list(&to_string(binding_keys));
+    let description = OperationDescription {
+        name: to_string(name),
+        description: to_string(description),
+        contexts: to_list(&to_string(contexts)),
+        bindings: binding_contexts.into_iter().zip(binding_keys).collect(),
+    };
+    abort_on_panic(move || to_c_string(description.lines().join("\n")))
+}
+
+/// `options` and `operations` are newline-separated lists.
+#[no_mangle]
+pub unsafe extern "C" fn rs_describe_unknown_name(
+    name: *const c_char,
+    options: *const c_char,
+    operations: *const c_char,
+) -> *mut c_char {
+    let name = to_string(name);
+    let options = to_list(&to_string(options));
+    let operations = to_list(&to_string(operations));
+    abort_on_panic(move || {
+        to_c_string(describe::unknown_name_message(&name, &options, &operations))
+    })
+}
+
+/// `options` and `operations` are newline-separated lists. Returns a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_describe_completions(
+    fragment: *const c_char,
+    options: *const c_char,
+    operations: *const c_char,
+) -> *mut c_char {
+    let fragment = to_string(fragment);
+    let options = to_list(&to_string(options));
+    let operations = to_list(&to_string(operations));
+    abort_on_panic(move || {
+        to_c_string(describe::completions(&fragment, &options, &operations).join("\n"))
+    })
+}
@@ -0,0 +1,105 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::dialogstack::DialogStack;
+use std::ffi::{CStr, CString};
+use std::mem;
+
+#[no_mangle]
+pub extern "C" fn rs_dialog_stack_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(DialogStack::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_free(stack: *mut c_void) {
+    abort_on_panic(|| {
+        if stack.is_null() {
+            return;
+        }
+        drop(Box::from_raw(stack as *mut DialogStack));
+    })
+}
+
+unsafe fn with_stack<F, T>(stack: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut DialogStack) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!stack.is_null());
+        let mut stack = Box::from_raw(stack as *mut DialogStack);
+        let result = action(&mut stack);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(stack);
+        result
+    })
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Copies `handles` into the array `out`, which has room for `rs_dialog_stack_len()` handles.
+unsafe fn write_handles(handles: Vec<usize>, out: *mut usize) -> usize {
+    assert!(!out.is_null());
+    for (i, handle) in handles.iter().enumerate() {
+        *out.add(i) = *handle;
+    }
+    handles.len()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_push(
+    stack: *mut c_void,
+    handle: usize,
+    kind: *const c_char,
+    name: *const c_char,
+    parent: usize,
+    has_parent: bool,
+) {
+    let kind = to_string(kind);
+    let name = to_string(name);
+    let parent = if has_parent { Some(parent) } else { None };
+    with_stack(stack, move |s| s.push(handle, &kind, &name, parent))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_remove(stack: *mut c_void, handle: usize) -> bool {
+    with_stack(stack, |s| s.remove(handle))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_len(stack: *mut c_void) -> usize {
+    with_stack(stack, |s| s.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_breadcrumb(
+    stack: *mut c_void,
+    handle: usize,
+) -> *mut c_char {
+    with_stack(stack, |s| {
+        // The labels are either names passed in as C strings, or translations, neither of which
+        // contain NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(s.breadcrumb(handle)).unwrap().into_raw()
+    })
+}
+
+/// Writes the handles of the dialogs to close on the way from `handle` to the feed list into
+/// `out`, and returns how many there are.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_unwind_to_feedlist(
+    stack: *mut c_void,
+    handle: usize,
+    out: *mut usize,
+) -> usize {
+    let handles = with_stack(stack, |s| s.unwind_to_feedlist(handle));
+    write_handles(handles, out)
+}
+
+/// Writes the handles of all dialogs except the feed list into `out`, and returns how many there
+/// are.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dialog_stack_close_all(stack: *mut c_void, out: *mut usize) -> usize {
+    let handles = with_stack(stack, |s| s.close_all());
+    write_handles(handles, out)
+}
@@ -0,0 +1,129 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_uint, c_void};
+use libnewsboat::dirlist::{self, DirEntry, DirList, DirListOptions, SortKey};
+use std::ffi::{CStr, CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn entry<'a>(list: *mut c_void, index: c_uint) -> &'a DirEntry {
+    assert!(!list.is_null());
+    let list = &*(list as *const DirList);
+    &list.entries()[index as usize]
+}
+
+/// `sort_order` is the value of `filebrowser-sort-order`; unknown values sort by name.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_read(
+    path: *const c_char,
+    show_hidden: bool,
+    dirs_first: bool,
+    sort_order: *const c_char,
+    directories_only: bool,
+) -> *mut c_void {
+    assert!(!path.is_null());
+    // Paths are bytes, not necessarily UTF-8
+    let path = Path::new(OsStr::from_bytes(CStr::from_ptr(path).to_bytes()));
+    let sort_order = to_string(sort_order);
+    abort_on_panic(move || {
+        let options = DirListOptions {
+            show_hidden,
+            dirs_first,
+            sort_key: SortKey::from_name(&sort_order).unwrap_or(SortKey::Name),
+            directories_only,
+        };
+        Box::into_raw(Box::new(DirList::read(path, &options))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_free(list: *mut c_void) {
+    abort_on_panic(|| {
+        if list.is_null() {
+            return;
+        }
+        drop(Box::from_raw(list as *mut DirList));
+    })
+}
+
+/// Returns why the directory couldn't be (fully) read, or a null pointer if it could.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_error(list: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!list.is_null());
+        let list = &*(list as *const DirList);
+        match list.error() {
+            // Error descriptions come from gettext or the OS, and don't contain NUL bytes. Thus,
+            // `unwrap` won't panic.
+            Some(error) => CString::new(error).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_count(list: *mut c_void) -> c_uint {
+    abort_on_panic(|| {
+        assert!(!list.is_null());
+        let list = &*(list as *const DirList);
+        list.entries().len() as c_uint
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_row(list: *mut c_void, index: c_uint) -> *mut c_char {
+    abort_on_panic(|| {
+        // File names, user and group names, and error descriptions can't contain NUL bytes.
+        // Thus, `unwrap` won't panic.
+        CString::new(entry(list, index).row()).unwrap().into_raw()
+    })
+}
+
+/// Returns the name of the entry at `index`, byte for byte.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_name(list: *mut c_void, index: c_uint) -> *mut c_char {
+    abort_on_panic(|| {
+        // File names can't contain NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(entry(list, index).name.as_bytes())
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Returns the character `ls -l` shows for the kind of the entry at `index`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_type_char(list: *mut c_void, index: c_uint) -> c_char {
+    abort_on_panic(|| entry(list, index).kind.type_char() as c_char)
+}
+
+/// Returns `true` if the entry at `index` is a directory, or a symlink that ends up at one.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_leads_to_directory(list: *mut c_void, index: c_uint) -> bool {
+    abort_on_panic(|| entry(list, index).leads_to_directory)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dir_list_complete_path(
+    input: *const c_char,
+    show_hidden: bool,
+    directories_only: bool,
+) -> *mut c_char {
+    let input = to_string(input);
+    abort_on_panic(move || {
+        let options = DirListOptions {
+            show_hidden,
+            directories_only,
+            ..DirListOptions::default()
+        };
+        // The input came from C, and completion only adds file names and slashes to it. Thus, it
+        // doesn't contain NUL bytes, and `unwrap` won't panic.
+        CString::new(dirlist::complete_path(&input, &options))
+            .unwrap()
+            .into_raw()
+    })
+}
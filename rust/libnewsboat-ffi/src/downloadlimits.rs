@@ -0,0 +1,64 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::downloadlimits::{self, DownloadError, DownloadWatch, Limits};
+use std::ffi::CString;
+use std::ptr;
+use std::time::Instant;
+
+fn to_c_error(result: Result<(), DownloadError>) -> *mut c_char {
+    match result {
+        Ok(()) => ptr::null_mut(),
+        // The message is a translated text and numbers. Thus, `unwrap` won't panic.
+        Err(e) => CString::new(e.to_message()).unwrap().into_raw(),
+    }
+}
+
+/// Starts watching a download. The timeout and the low-speed time are in seconds.
+#[no_mangle]
+pub extern "C" fn rs_download_watch_new(
+    timeout: u32,
+    low_speed_limit: u32,
+    low_speed_time: u32,
+) -> *mut c_void {
+    abort_on_panic(|| {
+        let limits = Limits::from_config(timeout, low_speed_limit, low_speed_time);
+        Box::into_raw(Box::new(DownloadWatch::new(limits, Instant::now()))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_download_watch_free(watch: *mut c_void) {
+    abort_on_panic(|| {
+        if watch.is_null() {
+            return;
+        }
+        drop(Box::from_raw(watch as *mut DownloadWatch));
+    })
+}
+
+/// Returns a null pointer if the download can go on, or the reason to abort it.
+#[no_mangle]
+pub unsafe extern "C" fn rs_download_watch_progress(
+    watch: *mut c_void,
+    received: u64,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!watch.is_null());
+        let watch = &mut *(watch as *mut DownloadWatch);
+        to_c_error(watch.progress(received, Instant::now()))
+    })
+}
+
+/// Returns a null pointer if `received` matches the `expected` length, or an error message. A
+/// negative `expected` means that the length is unknown.
+#[no_mangle]
+pub extern "C" fn rs_download_check_length(expected: i64, received: u64) -> *mut c_char {
+    abort_on_panic(|| {
+        let expected = if expected < 0 {
+            None
+        } else {
+            Some(expected as u64)
+        };
+        to_c_error(downloadlimits::check_length(expected, received))
+    })
+}
@@ -0,0 +1,87 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::emptyfeedguard::{EmptyFeedGuard, Verdict};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+#[no_mangle]
+pub extern "C" fn rs_empty_feed_guard_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(EmptyFeedGuard::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_empty_feed_guard_free(guard: *mut c_void) {
+    abort_on_panic(|| {
+        if guard.is_null() {
+            return;
+        }
+        drop(Box::from_raw(guard as *mut EmptyFeedGuard));
+    })
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_empty_feed_guard_set_thresholds(
+    guard: *mut c_void,
+    min_cached_items: usize,
+    accept_after: u32,
+) {
+    abort_on_panic(|| {
+        assert!(!guard.is_null());
+        // Thresholds are only changed while the config is (re)loaded, when no reload threads run.
+        let guard = &mut *(guard as *mut EmptyFeedGuard);
+        guard.set_thresholds(min_cached_items, accept_after);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_empty_feed_guard_force_accept(guard: *mut c_void, url: *const c_char) {
+    abort_on_panic(|| {
+        assert!(!guard.is_null());
+        // Feeds are only configured while the config is parsed, before any reload threads start.
+        let guard = &mut *(guard as *mut EmptyFeedGuard);
+        guard.force_accept(&to_string(url));
+    })
+}
+
+/// Returns a warning if the fetched feed should be ignored, or a null pointer if it should replace
+/// the cached one.
+#[no_mangle]
+pub unsafe extern "C" fn rs_empty_feed_guard_check(
+    guard: *mut c_void,
+    url: *const c_char,
+    fetched_items: usize,
+    cached_items: usize,
+) -> *mut c_char {
+    let url = to_string(url);
+    abort_on_panic(move || {
+        assert!(!guard.is_null());
+        // Reload threads check feeds at the same time, so we only ever borrow this object.
+        let guard = &*(guard as *const EmptyFeedGuard);
+        match guard.check(&url, fetched_items, cached_items) {
+            Verdict::Accept => ptr::null_mut(),
+            // The warning is our own text. Thus, `unwrap` won't panic.
+            verdict => CString::new(verdict.warning().unwrap_or_default())
+                .unwrap()
+                .into_raw(),
+        }
+    })
+}
+
+/// URLs of feeds that are allowed to be empty, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_empty_feed_guard_forced_urls(guard: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!guard.is_null());
+        let guard = &*(guard as *const EmptyFeedGuard);
+        // URLs came from C++ as C strings, so they don't contain NUL bytes. Thus, `unwrap` won't
+        // panic.
+        CString::new(guard.forced_urls().join("\n"))
+            .unwrap()
+            .into_raw()
+    })
+}
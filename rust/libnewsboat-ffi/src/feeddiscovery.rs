@@ -0,0 +1,28 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::feeddiscovery::discover_feeds;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Returns the first feed that the page links to, or a null pointer if there is none.
+#[no_mangle]
+pub unsafe extern "C" fn rs_discover_feed(
+    html: *const c_char,
+    page_url: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let html = to_string(html);
+        let page_url = to_string(page_url);
+        match discover_feeds(&html, &page_url).into_iter().next() {
+            // The URL was built from strings that came from C, so it contains no NUL bytes, and
+            // `unwrap` won't panic.
+            Some(url) => CString::new(url).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
@@ -0,0 +1,85 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::feedicons::{classify, FeedFacts, FeedIcons};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_feed_icons_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(FeedIcons::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_icons_free(icons: *mut c_void) {
+    abort_on_panic(|| {
+        if icons.is_null() {
+            return;
+        }
+        drop(Box::from_raw(icons as *mut FeedIcons));
+    })
+}
+
+/// Returns the reason why the glyph was refused, or a null pointer if it was set.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_icons_set_glyph(
+    icons: *mut c_void,
+    category: *const c_char,
+    glyph: *const c_char,
+) -> *mut c_char {
+    let category = to_string(category);
+    let glyph = to_string(glyph);
+    abort_on_panic(move || {
+        assert!(!icons.is_null());
+        // Glyphs are only configured while the config is parsed.
+        let icons = &mut *(icons as *mut FeedIcons);
+        match icons.set_glyph(&category, &glyph) {
+            Ok(()) => ptr::null_mut(),
+            // The message consists of a translated text and the arguments, which came from C.
+            // Thus, it doesn't contain NUL bytes, and `unwrap` won't panic.
+            Err(message) => CString::new(message).unwrap().into_raw(),
+        }
+    })
+}
+
+/// Glyph for a feed with the given properties, padded to the width of the icon column.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_icons_glyph(
+    icons: *mut c_void,
+    is_query: bool,
+    has_error: bool,
+    items: usize,
+    items_with_enclosures: usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!icons.is_null());
+        let icons = &*(icons as *const FeedIcons);
+        let category = classify(&FeedFacts {
+            is_query,
+            has_error,
+            items,
+            items_with_enclosures,
+        });
+        // Glyphs are either our own defaults or came from C++ as C strings. Thus, `unwrap` won't
+        // panic.
+        CString::new(icons.glyph(category)).unwrap().into_raw()
+    })
+}
+
+/// `feedlist-icon` commands, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_icons_dump_config(icons: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!icons.is_null());
+        let icons = &*(icons as *const FeedIcons);
+        // Glyphs are either our own defaults or came from C++ as C strings. Thus, `unwrap` won't
+        // panic.
+        CString::new(icons.dump_config().join("\n"))
+            .unwrap()
+            .into_raw()
+    })
+}
@@ -0,0 +1,171 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_uint, c_void};
+use libnewsboat::feedlistsnapshot::{FeedListSnapshot, SnapshotFeed};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Splits a newline-separated list.
+fn to_list(input: &str) -> Vec<String> {
+    input
+        .split('\n')
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+unsafe fn to_feed(
+    url: *const c_char,
+    title: *const c_char,
+    tags: *const c_char,
+    unread: c_uint,
+    total: c_uint,
+) -> SnapshotFeed {
+    SnapshotFeed {
+        url: to_string(url),
+        title: to_string(title),
+        tags: to_list(&to_string(tags)),
+        unread,
+        total,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_feedlist_snapshot_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(FeedListSnapshot::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_free(snapshot: *mut c_void) {
+    abort_on_panic(|| {
+        if snapshot.is_null() {
+            return;
+        }
+        drop(Box::from_raw(snapshot as *mut FeedListSnapshot));
+    })
+}
+
+/// `tags` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_add_feed(
+    snapshot: *mut c_void,
+    url: *const c_char,
+    title: *const c_char,
+    tags: *const c_char,
+    unread: c_uint,
+    total: c_uint,
+) {
+    let feed = to_feed(url, title, tags, unread, total);
+    abort_on_panic(move || {
+        assert!(!snapshot.is_null());
+        let snapshot = &mut *(snapshot as *mut FeedListSnapshot);
+        snapshot.add_feed(feed);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_save(
+    snapshot: *mut c_void,
+    path: *const c_char,
+    urls_file: *const c_char,
+    cache_file: *const c_char,
+) -> bool {
+    let path = to_string(path);
+    let urls_file = to_string(urls_file);
+    let cache_file = to_string(cache_file);
+    abort_on_panic(move || {
+        assert!(!snapshot.is_null());
+        let snapshot = &mut *(snapshot as *mut FeedListSnapshot);
+        snapshot
+            .save(
+                Path::new(&path),
+                Path::new(&urls_file),
+                Path::new(&cache_file),
+            )
+            .is_ok()
+    })
+}
+
+/// `urls` is a newline-separated list. Returns a null pointer if there is no current snapshot.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_load_if_current(
+    path: *const c_char,
+    urls_file: *const c_char,
+    cache_file: *const c_char,
+    urls: *const c_char,
+) -> *mut c_void {
+    let path = to_string(path);
+    let urls_file = to_string(urls_file);
+    let cache_file = to_string(cache_file);
+    let urls = to_string(urls);
+    abort_on_panic(move || {
+        match FeedListSnapshot::load_if_current(
+            Path::new(&path),
+            Path::new(&urls_file),
+            Path::new(&cache_file),
+            &to_list(&urls),
+        ) {
+            Some(snapshot) => Box::into_raw(Box::new(snapshot)) as *mut c_void,
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_feed_count(snapshot: *mut c_void) -> c_uint {
+    abort_on_panic(|| {
+        assert!(!snapshot.is_null());
+        let snapshot = &*(snapshot as *const FeedListSnapshot);
+        snapshot.feeds().len() as c_uint
+    })
+}
+
+/// Stores the feed at `index` into the output parameters. The strings have to be freed with
+/// `rs_cstring_free`; `tags` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_get_feed(
+    snapshot: *mut c_void,
+    index: c_uint,
+    url: *mut *mut c_char,
+    title: *mut *mut c_char,
+    tags: *mut *mut c_char,
+    unread: *mut c_uint,
+    total: *mut c_uint,
+) {
+    abort_on_panic(|| {
+        assert!(!snapshot.is_null());
+        let snapshot = &*(snapshot as *const FeedListSnapshot);
+        let feed = &snapshot.feeds()[index as usize];
+        // The strings came from C, or from a snapshot file, which is refused if it contains NUL
+        // bytes. Thus, `unwrap` won't panic.
+        let to_cstring = |text: &str| CString::new(text).unwrap().into_raw();
+        *url = to_cstring(&feed.url);
+        *title = to_cstring(&feed.title);
+        *tags = to_cstring(&feed.tags.join("\n"));
+        *unread = feed.unread;
+        *total = feed.total;
+    })
+}
+
+/// Returns `true` if the snapshot showed the feed differently.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feedlist_snapshot_reconcile(
+    snapshot: *mut c_void,
+    url: *const c_char,
+    title: *const c_char,
+    tags: *const c_char,
+    unread: c_uint,
+    total: c_uint,
+) -> bool {
+    let feed = to_feed(url, title, tags, unread, total);
+    abort_on_panic(move || {
+        assert!(!snapshot.is_null());
+        let snapshot = &mut *(snapshot as *mut FeedListSnapshot);
+        snapshot.reconcile(feed)
+    })
+}
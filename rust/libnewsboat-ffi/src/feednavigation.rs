@@ -0,0 +1,46 @@
+use crate::abort_on_panic;
+use libnewsboat::feednavigation::{feed_with_unread, Direction, Jump};
+use std::slice;
+
+// These constants MUST match numbers in `enum class FeedJump`, see include/feedlistformaction.h
+const JUMPED: u8 = 0;
+const ONLY_CURRENT: u8 = 1;
+const NONE_IN_DIRECTION: u8 = 2;
+const NO_UNREAD: u8 = 3;
+
+/// Stores the index of the feed to jump to in `index` if the result is `JUMPED`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_with_unread(
+    unread_counts: *const u32,
+    count: usize,
+    current: usize,
+    has_current: bool,
+    forward: bool,
+    wrap: bool,
+    index: *mut usize,
+) -> u8 {
+    abort_on_panic(|| {
+        assert!(!index.is_null());
+        let unread_counts = if count == 0 {
+            &[]
+        } else {
+            assert!(!unread_counts.is_null());
+            slice::from_raw_parts(unread_counts, count)
+        };
+        let current = if has_current { Some(current) } else { None };
+        let direction = if forward {
+            Direction::Next
+        } else {
+            Direction::Previous
+        };
+        match feed_with_unread(unread_counts, current, direction, wrap) {
+            Jump::To(target) => {
+                *index = target;
+                JUMPED
+            }
+            Jump::OnlyCurrent => ONLY_CURRENT,
+            Jump::NoneInDirection => NONE_IN_DIRECTION,
+            Jump::NoUnread => NO_UNREAD,
+        }
+    })
+}
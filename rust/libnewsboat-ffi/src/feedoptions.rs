@@ -0,0 +1,106 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::feedoptions::FeedOptions;
+use std::ffi::{CStr, CString};
+use std::panic::UnwindSafe;
+use std::ptr;
+
+#[no_mangle]
+pub extern "C" fn rs_feed_options_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(FeedOptions::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_options_free(options: *mut c_void) {
+    abort_on_panic(|| {
+        if options.is_null() {
+            return;
+        }
+        drop(Box::from_raw(options as *mut FeedOptions));
+    })
+}
+
+unsafe fn with_options<F, T>(options: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&FeedOptions) -> T + UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!options.is_null());
+        // Reload threads look options up at the same time, so we only ever borrow this object.
+        let options = &*(options as *const FeedOptions);
+        action(options)
+    })
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Joins lines with newlines. The lines came from C++ as C strings, so they don't contain null
+/// bytes. Thus, `unwrap` won't panic.
+fn join_lines(lines: Vec<String>) -> *mut c_char {
+    CString::new(lines.join("\n")).unwrap().into_raw()
+}
+
+/// Returns an error message, or null if the option was set.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_options_set(
+    options: *mut c_void,
+    url: *const c_char,
+    option: *const c_char,
+    value: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!options.is_null());
+        // Options are only set while the config is parsed, before any reload threads start.
+        let options = &mut *(options as *mut FeedOptions);
+        match options.set_option(&to_string(url), &to_string(option), &to_string(value)) {
+            Ok(()) => ptr::null_mut(),
+            // The message is made of our own text and the option's name, which came from C++ as
+            // a C string. Thus, `unwrap` won't panic.
+            Err(error) => CString::new(error.to_message()).unwrap().into_raw(),
+        }
+    })
+}
+
+/// Returns the user agent for the feed at `url`, or null if the global one should be used.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_options_user_agent(
+    options: *mut c_void,
+    url: *const c_char,
+) -> *mut c_char {
+    let url = to_string(url);
+    with_options(options, move |o| match o.user_agent(&url) {
+        // The user agent came from C++ as a C string, and was checked for null bytes. Thus,
+        // `unwrap` won't panic.
+        Some(user_agent) => CString::new(user_agent).unwrap().into_raw(),
+        None => ptr::null_mut(),
+    })
+}
+
+/// Headers for the feed at `url`, separated by newlines (which the headers can't contain).
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_options_headers(
+    options: *mut c_void,
+    url: *const c_char,
+) -> *mut c_char {
+    let url = to_string(url);
+    with_options(options, move |o| join_lines(o.header_lines(&url)))
+}
+
+/// Same as `rs_feed_options_headers`, but with the values hidden, for logs.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_options_redacted_headers(
+    options: *mut c_void,
+    url: *const c_char,
+) -> *mut c_char {
+    let url = to_string(url);
+    with_options(options, move |o| join_lines(o.redacted_header_lines(&url)))
+}
+
+/// Config lines, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_options_dump_config(options: *mut c_void) -> *mut c_char {
+    with_options(options, |o| join_lines(o.dump_config()))
+}
@@ -0,0 +1,110 @@
+use crate::abort_on_panic;
+use chrono::Utc;
+use libc::{c_char, c_void};
+use libnewsboat::feedschedule::{FeedSchedule, FeedScheduler};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn to_strings(input: *const *const c_char, count: usize) -> Vec<String> {
+    (0..count).map(|i| to_string(*input.add(i))).collect()
+}
+
+/// Turns the text of the `<ttl>` element, and of the `<hour>` and `<day>` elements inside
+/// `<skipHours>` and `<skipDays>`, into a string that can be stored in the cache.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_schedule_from_elements(
+    ttl: *const c_char,
+    hours: *const *const c_char,
+    hours_count: usize,
+    days: *const *const c_char,
+    days_count: usize,
+) -> *mut c_char {
+    let ttl = to_string(ttl);
+    let hours = to_strings(hours, hours_count);
+    let days = to_strings(days, days_count);
+    abort_on_panic(move || {
+        let schedule = FeedSchedule::from_elements(&ttl, &hours, &days);
+        // The serialized schedule is made of numbers and day names. Thus, `unwrap` won't panic.
+        CString::new(schedule.serialize()).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_feed_scheduler_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(FeedScheduler::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_scheduler_free(scheduler: *mut c_void) {
+    abort_on_panic(|| {
+        if scheduler.is_null() {
+            return;
+        }
+        drop(Box::from_raw(scheduler as *mut FeedScheduler));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_scheduler_ignore_schedule(
+    scheduler: *mut c_void,
+    url: *const c_char,
+) {
+    abort_on_panic(|| {
+        assert!(!scheduler.is_null());
+        // Feeds are only configured while the config is parsed, before any reload threads start.
+        let scheduler = &mut *(scheduler as *mut FeedScheduler);
+        scheduler.ignore_schedule(&to_string(url));
+    })
+}
+
+/// URLs of feeds whose schedule is ignored, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_scheduler_ignored_urls(scheduler: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!scheduler.is_null());
+        let scheduler = &*(scheduler as *const FeedScheduler);
+        // URLs came from C++ as C strings, so they don't contain NUL bytes. Thus, `unwrap` won't
+        // panic.
+        CString::new(scheduler.ignored_urls().join("\n"))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_scheduler_fetched(scheduler: *mut c_void, url: *const c_char) {
+    let url = to_string(url);
+    abort_on_panic(move || {
+        assert!(!scheduler.is_null());
+        // Reload threads record fetches at the same time, so we only ever borrow this object.
+        let scheduler = &*(scheduler as *const FeedScheduler);
+        scheduler.fetched(&url, Utc::now());
+    })
+}
+
+/// Returns why the feed at `url` shouldn't be fetched now, or a null pointer if it should be.
+/// `schedule` is what `rs_feed_schedule_from_elements()` returned for the feed.
+#[no_mangle]
+pub unsafe extern "C" fn rs_feed_scheduler_postpone_reason(
+    scheduler: *mut c_void,
+    url: *const c_char,
+    schedule: *const c_char,
+    manual: bool,
+) -> *mut c_char {
+    let url = to_string(url);
+    let schedule = FeedSchedule::deserialize(&to_string(schedule));
+    abort_on_panic(move || {
+        assert!(!scheduler.is_null());
+        let scheduler = &*(scheduler as *const FeedScheduler);
+        match scheduler.postpone_reason(&url, &schedule, Utc::now(), manual) {
+            // The reason is our own text. Thus, `unwrap` won't panic.
+            Some(reason) => CString::new(reason).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
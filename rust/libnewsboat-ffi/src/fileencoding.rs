@@ -0,0 +1,75 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::fileencoding::{self, Encoding};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Converts the `length` bytes at `data`, which were read from the file at `path`, to UTF-8. Sets
+/// `warning` to the warning to show if the file wasn't UTF-8, or to a null pointer otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn rs_file_encoding_decode(
+    path: *const c_char,
+    data: *const u8,
+    length: usize,
+    locale_codeset: *const c_char,
+    warning: *mut *mut c_char,
+) -> *mut c_char {
+    let path = to_string(path);
+    let locale_codeset = to_string(locale_codeset);
+    abort_on_panic(move || {
+        assert!(!warning.is_null());
+        let bytes = if length == 0 {
+            &[]
+        } else {
+            assert!(!data.is_null());
+            slice::from_raw_parts(data, length)
+        };
+        let decoded = fileencoding::decode(bytes, &locale_codeset);
+        *warning = match decoded.encoding {
+            Encoding::Utf8 => ptr::null_mut(),
+            // The warning consists of a translated text, a path that came from C, and the name of
+            // an encoding. Thus, it doesn't contain NUL bytes, and `unwrap` won't panic.
+            encoding => CString::new(fileencoding::warning(&path, encoding))
+                .unwrap()
+                .into_raw(),
+        };
+        // NUL bytes are replaced first. Thus, `unwrap` won't panic.
+        CString::new(decoded.text.replace('\0', "\u{fffd}"))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Rewrites the file at `path` in UTF-8. Returns the error message if that failed, or a null
+/// pointer otherwise; `converted` is set to `false` if the file already was UTF-8.
+#[no_mangle]
+pub unsafe extern "C" fn rs_file_encoding_convert(
+    path: *const c_char,
+    locale_codeset: *const c_char,
+    converted: *mut bool,
+) -> *mut c_char {
+    let path = to_string(path);
+    let locale_codeset = to_string(locale_codeset);
+    abort_on_panic(move || {
+        assert!(!converted.is_null());
+        match fileencoding::convert_file(Path::new(&path), &locale_codeset) {
+            Ok(encoding) => {
+                *converted = encoding.is_some();
+                ptr::null_mut()
+            }
+            Err(error) => {
+                *converted = false;
+                // The error message is a translated text or a system error message. Thus, it
+                // doesn't contain NUL bytes, and `unwrap` won't panic.
+                CString::new(error.to_string()).unwrap().into_raw()
+            }
+        }
+    })
+}
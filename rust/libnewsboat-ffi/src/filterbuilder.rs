@@ -0,0 +1,114 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::filterbuilder::{FilterBuilder, Stage};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+
+// These constants MUST match numbers in `enum class FilterBuilder::Stage`, see
+// include/filterbuilder.h
+const ATTRIBUTE: u8 = 0;
+const OPERATOR: u8 = 1;
+const VALUE: u8 = 2;
+const NEXT: u8 = 3;
+const DONE: u8 = 4;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_new(context: *const c_char) -> *mut c_void {
+    let context = to_string(context);
+    abort_on_panic(move || Box::into_raw(Box::new(FilterBuilder::new(&context))) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_free(builder: *mut c_void) {
+    abort_on_panic(|| {
+        if builder.is_null() {
+            return;
+        }
+        drop(Box::from_raw(builder as *mut FilterBuilder));
+    })
+}
+
+unsafe fn with_builder<F, T>(builder: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut FilterBuilder) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!builder.is_null());
+        let mut builder = Box::from_raw(builder as *mut FilterBuilder);
+        let result = action(&mut builder);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(builder);
+        result
+    })
+}
+
+fn into_c_string(string: String) -> *mut c_char {
+    // The strings are made of our own text and of strings that came from C++ as C strings.
+    // Thus, `unwrap` won't panic.
+    CString::new(string).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_stage(builder: *mut c_void) -> u8 {
+    with_builder(builder, |b| match b.stage() {
+        Stage::Attribute => ATTRIBUTE,
+        Stage::Operator => OPERATOR,
+        Stage::Value => VALUE,
+        Stage::Next => NEXT,
+        Stage::Done => DONE,
+    })
+}
+
+/// Items of the list, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_items(builder: *mut c_void) -> *mut c_char {
+    with_builder(builder, |b| into_c_string(b.items().join("\n")))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_title(builder: *mut c_void) -> *mut c_char {
+    with_builder(builder, |b| into_c_string(b.title()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_select(builder: *mut c_void, index: usize) {
+    with_builder(builder, |b| b.select(index))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_prompt(builder: *mut c_void) -> *mut c_char {
+    with_builder(builder, |b| into_c_string(b.prompt()))
+}
+
+/// Returns the description of the problem with the value, or null if it was accepted.
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_set_value(
+    builder: *mut c_void,
+    value: *const c_char,
+) -> *mut c_char {
+    let value = to_string(value);
+    with_builder(builder, move |b| match b.set_value(&value) {
+        Ok(()) => ptr::null_mut(),
+        Err(message) => into_c_string(message),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_cancel_value(builder: *mut c_void) {
+    with_builder(builder, |b| b.cancel_value())
+}
+
+/// Returns the filter expression, or null if no condition was added yet.
+#[no_mangle]
+pub unsafe extern "C" fn rs_filter_builder_expression(builder: *mut c_void) -> *mut c_char {
+    with_builder(builder, |b| match b.expression() {
+        Some(expression) => into_c_string(expression.to_string()),
+        None => ptr::null_mut(),
+    })
+}
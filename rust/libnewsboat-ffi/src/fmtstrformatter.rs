@@ -46,6 +46,28 @@ pub unsafe extern "C" fn rs_fmtstrformatter_register_fmt(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_fmtstrformatter_set_truncation_marker(
+    fmt: *mut c_void,
+    marker: *const c_char,
+) {
+    abort_on_panic(|| {
+        let mut fmt = {
+            assert!(!fmt.is_null());
+            Box::from_raw(fmt as *mut FmtStrFormatter)
+        };
+        let marker = {
+            assert!(!marker.is_null());
+            CStr::from_ptr(marker)
+        }
+        .to_string_lossy();
+        fmt.set_truncation_marker(&marker);
+
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(fmt);
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_fmtstrformatter_do_format(
     fmt: *mut c_void,
@@ -72,3 +94,42 @@ pub unsafe extern "C" fn rs_fmtstrformatter_do_format(
         result
     })
 }
+
+/// Returns the rows joined with newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_fmtstrformatter_do_format_wrapped(
+    fmt: *mut c_void,
+    format: *const c_char,
+    width: u32,
+    key: c_char,
+    max_rows: usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let fmt = {
+            assert!(!fmt.is_null());
+            Box::from_raw(fmt as *mut FmtStrFormatter)
+        };
+        let format = {
+            assert!(!format.is_null());
+            CStr::from_ptr(format)
+        }
+        .to_str()
+        .expect("format contained invalid UTF-8");
+        // Keys are ASCII, so it's safe to cast c_char (i8) to u8 (0-127 map to the same bits).
+        // From there, it's safe to cast to Rust's char.
+        let key = key as u8 as char;
+        let rows = fmt
+            .do_format_wrapped(format, width, key, max_rows)
+            .iter()
+            // A newline in a value would look like an extra row
+            .map(|row| row.replace('\n', " "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let result = CString::new(rows).unwrap().into_raw();
+
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(fmt);
+
+        result
+    })
+}
@@ -1,5 +1,5 @@
 use crate::abort_on_panic;
-use libnewsboat::fslock::FsLock;
+use libnewsboat::fslock::{FsLock, LockError};
 use std::ffi::CStr;
 
 #[no_mangle]
@@ -17,6 +17,8 @@ pub unsafe extern "C" fn rs_fslock_free(ptr: *mut FsLock) {
     })
 }
 
+/// Returns `true` if the lock was taken. Otherwise, sets `pid` to the PID of the process that holds
+/// the lock, or to 0 if it's unknown or locking failed for some other reason.
 #[no_mangle]
 pub unsafe extern "C" fn rs_fslock_try_lock(
     ptr: *mut FsLock,
@@ -34,6 +36,16 @@ pub unsafe extern "C" fn rs_fslock_try_lock(
         };
         let pid = { &mut *pid };
         let new_lock_filepath = new_lock_filepath.to_string_lossy().into_owned();
-        fslock.try_lock(new_lock_filepath.as_ref(), pid)
+        match fslock.try_lock(new_lock_filepath.as_ref()) {
+            Ok(()) => true,
+            Err(LockError::AlreadyLocked { pid: holder }) => {
+                *pid = holder.unwrap_or(0);
+                false
+            }
+            Err(LockError::Io(_)) => {
+                *pid = 0;
+                false
+            }
+        }
     })
 }
@@ -0,0 +1,91 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::help::{Category, Help, HelpRow};
+use std::ffi::{CStr, CString};
+use std::mem;
+
+// These constants MUST match numbers in `enum class OpCategory`, see include/keymap.h
+const NAVIGATION: u8 = 0;
+const FEEDS: u8 = 1;
+const ARTICLES: u8 = 2;
+const MACROS: u8 = 3;
+
+fn to_category(category: u8) -> Category {
+    match category {
+        NAVIGATION => Category::Navigation,
+        FEEDS => Category::Feeds,
+        ARTICLES => Category::Articles,
+        MACROS => Category::Macros,
+        _ => Category::Other,
+    }
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_help_new(context: *const c_char) -> *mut c_void {
+    let context = to_string(context);
+    abort_on_panic(move || Box::into_raw(Box::new(Help::new(&context))) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_help_free(help: *mut c_void) {
+    abort_on_panic(|| {
+        if help.is_null() {
+            return;
+        }
+        drop(Box::from_raw(help as *mut Help));
+    })
+}
+
+unsafe fn with_help<F, T>(help: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut Help) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!help.is_null());
+        let mut help = Box::from_raw(help as *mut Help);
+        let result = action(&mut help);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(help);
+        result
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_help_add_row(
+    help: *mut c_void,
+    context: *const c_char,
+    key: *const c_char,
+    operation: *const c_char,
+    description: *const c_char,
+    category: u8,
+) {
+    let context = to_string(context);
+    let row = HelpRow {
+        key: to_string(key),
+        operation: to_string(operation),
+        description: to_string(description),
+        category: to_category(category),
+    };
+    with_help(help, move |h| h.add_row(&context, row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_help_set_filter(help: *mut c_void, filter: *const c_char) {
+    let filter = to_string(filter);
+    with_help(help, move |h| h.set_filter(&filter))
+}
+
+/// Lines of the dialog, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_help_lines(help: *mut c_void, width: usize) -> *mut c_char {
+    with_help(help, |h| {
+        // The lines are made of our own text and of strings that came from C++ as C strings.
+        // Thus, `unwrap` won't panic.
+        CString::new(h.lines(width).join("\n")).unwrap().into_raw()
+    })
+}
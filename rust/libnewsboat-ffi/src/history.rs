@@ -3,6 +3,7 @@ use libc::{c_char, c_void};
 use libnewsboat::history::History;
 use std::ffi::{CStr, CString};
 use std::mem;
+use std::ptr;
 
 #[no_mangle]
 pub extern "C" fn rs_history_new() -> *mut c_void {
@@ -86,6 +87,32 @@ pub unsafe extern "C" fn rs_history_next_line(hst: *mut c_void) -> *mut c_char {
     })
 }
 
+/// Returns a null pointer if no line starts with `prefix`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_history_search_prefix(
+    hst: *mut c_void,
+    prefix: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let hst = {
+            assert!(!hst.is_null());
+            &*(hst as *mut History)
+        };
+        let prefix = {
+            assert!(!prefix.is_null());
+            CStr::from_ptr(prefix)
+        }
+        .to_string_lossy();
+
+        match hst.search_prefix(&prefix) {
+            // Panic here can't happen because the line came from `rs_history_add_line` or from
+            // a file, whose lines are split at newlines and can't contain null bytes either.
+            Some(line) => CString::new(line).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_history_load_from_file(hst: *mut c_void, file: *const c_char) {
     abort_on_panic(|| {
@@ -116,7 +143,7 @@ pub unsafe extern "C" fn rs_history_save_to_file(
     limit: usize,
 ) {
     abort_on_panic(|| {
-        let hst = {
+        let mut hst = {
             assert!(!hst.is_null());
             Box::from_raw(hst as *mut History)
         };
@@ -1,5 +1,8 @@
 use crate::abort_on_panic;
+use libc::c_char;
 use libnewsboat::human_panic;
+use std::ffi::CStr;
+use std::path::Path;
 
 #[no_mangle]
 pub extern "C" fn rs_setup_human_panic() {
@@ -7,3 +10,16 @@ pub extern "C" fn rs_setup_human_panic() {
         human_panic::setup();
     })
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_human_panic_set_report_dir(dir: *const c_char) {
+    abort_on_panic(|| {
+        let dir = {
+            assert!(!dir.is_null());
+            CStr::from_ptr(dir)
+        }
+        .to_string_lossy()
+        .into_owned();
+        human_panic::set_report_dir(Path::new(&dir));
+    })
+}
@@ -0,0 +1,32 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::hyphenation;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+/// Splits `word` so that the first part (with a hyphen appended) fits into `max_width` columns.
+///
+/// Returns the first part and stores the second into `tail`. If `word` can't be split, returns
+/// a null pointer and leaves `tail` untouched.
+#[no_mangle]
+pub unsafe extern "C" fn rs_hyphenate(
+    word: *const c_char,
+    max_width: usize,
+    use_patterns: bool,
+    tail: *mut *mut c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let word = CStr::from_ptr(word).to_string_lossy();
+        match hyphenation::hyphenate(&word, max_width, use_patterns) {
+            // Both parts came from `word`, which came from C and thus doesn't contain NUL bytes.
+            // Thus, `unwrap` won't panic.
+            Some((head, rest)) => {
+                if !tail.is_null() {
+                    *tail = CString::new(rest).unwrap().into_raw();
+                }
+                CString::new(head).unwrap().into_raw()
+            }
+            None => ptr::null_mut(),
+        }
+    })
+}
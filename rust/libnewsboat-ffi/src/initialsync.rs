@@ -0,0 +1,158 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::initialsync::{InitialSync, Progress, RetryPolicy};
+use libnewsboat::{
+    log,
+    logger::{self, Level},
+};
+use std::ffi::{CStr, CString};
+use std::slice;
+use std::time::Duration;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Continues the sync from `progress`, which is what `rs_initial_sync_page_fetched` returned
+/// earlier, or an empty string to start from scratch.
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_new(
+    page_size: usize,
+    attempts: u32,
+    page_delay_ms: u64,
+    progress: *const c_char,
+) -> *mut c_void {
+    let progress = to_string(progress);
+    abort_on_panic(move || {
+        let progress = Progress::parse(&progress).unwrap_or_else(|| {
+            log!(
+                "initialsync",
+                Level::Warn,
+                "rs_initial_sync_new: can't parse progress `{}', starting over",
+                progress
+            );
+            Progress::default()
+        });
+        let policy = RetryPolicy {
+            attempts,
+            page_delay: Duration::from_millis(page_delay_ms),
+        };
+        Box::into_raw(Box::new(InitialSync::new(page_size, policy, progress))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_free(sync: *mut c_void) {
+    abort_on_panic(|| {
+        if sync.is_null() {
+            return;
+        }
+        drop(Box::from_raw(sync as *mut InitialSync));
+    })
+}
+
+/// Returns `false` if the sync is complete. Otherwise, fills in the page to fetch next; if
+/// `has_before_id` is `false`, the page starts with the newest article.
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_next_request(
+    sync: *mut c_void,
+    limit: *mut usize,
+    has_before_id: *mut bool,
+    before_id: *mut i64,
+) -> bool {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        assert!(!limit.is_null());
+        assert!(!has_before_id.is_null());
+        assert!(!before_id.is_null());
+        let sync = &*(sync as *const InitialSync);
+        match sync.next_request() {
+            None => false,
+            Some(request) => {
+                *limit = request.limit;
+                *has_before_id = request.before_id.is_some();
+                *before_id = request.before_id.unwrap_or(0);
+                true
+            }
+        }
+    })
+}
+
+/// Records a fetched page with `count` articles, whose IDs are in `ids`. Returns the progress to
+/// store along with the articles.
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_page_fetched(
+    sync: *mut c_void,
+    ids: *const i64,
+    count: usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        let sync = &mut *(sync as *mut InitialSync);
+        let ids = if count == 0 {
+            &[]
+        } else {
+            assert!(!ids.is_null());
+            slice::from_raw_parts(ids, count)
+        };
+        let progress = sync.page_fetched(ids).serialize();
+        // The progress consists of words and numbers. Thus, it doesn't contain NUL bytes, and
+        // `unwrap` won't panic.
+        CString::new(progress).unwrap().into_raw()
+    })
+}
+
+/// Returns how many milliseconds to wait before trying the page again, or -1 if the sync should
+/// give up for now.
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_page_failed(sync: *mut c_void) -> i64 {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        let sync = &mut *(sync as *mut InitialSync);
+        sync.page_failed()
+            .map_or(-1, |delay| delay.as_millis() as i64)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_page_delay_ms(sync: *mut c_void) -> u64 {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        let sync = &*(sync as *const InitialSync);
+        sync.page_delay().as_millis() as u64
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_is_complete(sync: *mut c_void) -> bool {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        let sync = &*(sync as *const InitialSync);
+        sync.is_complete()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_progress(sync: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        let sync = &*(sync as *const InitialSync);
+        // The progress consists of words and numbers. Thus, it doesn't contain NUL bytes, and
+        // `unwrap` won't panic.
+        CString::new(sync.progress().serialize())
+            .unwrap()
+            .into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_initial_sync_status(sync: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!sync.is_null());
+        let sync = &*(sync as *const InitialSync);
+        // The status consists of a translated text and numbers. Thus, it doesn't contain NUL
+        // bytes, and `unwrap` won't panic.
+        CString::new(sync.status()).unwrap().into_raw()
+    })
+}
@@ -0,0 +1,41 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::keycombination;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+/// Parses a key name and returns its canonical spelling.
+///
+/// If `name` isn't spelled canonically, a warning for the user is stored into `warning`;
+/// otherwise, `warning` is left untouched. If `name` isn't a valid key name at all, returns
+/// a null pointer and stores an error message into `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_parse_key_name(
+    name: *const c_char,
+    warning: *mut *mut c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let name = CStr::from_ptr(name);
+        let name = name.to_string_lossy();
+
+        // All the strings below consist of `name`, which came from C and thus doesn't contain
+        // NUL bytes, and of our own text. Thus, `unwrap` won't panic.
+        match keycombination::parse_key_name(&name) {
+            Ok(key) => {
+                if !warning.is_null() {
+                    if let Some(message) = key.warning(&name) {
+                        *warning = CString::new(message).unwrap().into_raw();
+                    }
+                }
+                CString::new(key.canonical).unwrap().into_raw()
+            }
+            Err(e) => {
+                if !error.is_null() {
+                    *error = CString::new(e.to_message()).unwrap().into_raw();
+                }
+                ptr::null_mut()
+            }
+        }
+    })
+}
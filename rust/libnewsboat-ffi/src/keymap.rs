@@ -0,0 +1,360 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::keymap;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+
+type Operations = Vec<Vec<String>>;
+
+/// If tokenization fails, returns a null pointer, stores the one-based column at which it failed
+/// into `column`, and a description of the problem into `error`.
+///
+/// If `strict` is set, quotes have to be closed (see `keymap::tokenize_operation_sequence_strict`).
+#[no_mangle]
+pub unsafe extern "C" fn rs_tokenize_operation_sequence(
+    input: *const c_char,
+    strict: bool,
+    column: *mut usize,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        let input = input.to_string_lossy();
+
+        let result = if strict {
+            keymap::tokenize_operation_sequence_strict(&input)
+        } else {
+            keymap::tokenize_operation_sequence_verbose(&input)
+        };
+        match result {
+            Ok(operations) => Box::into_raw(Box::new(operations)) as *mut c_void,
+            Err(e) => {
+                if !column.is_null() {
+                    *column = e.column;
+                }
+                if !error.is_null() {
+                    // The message consists of our own text and a part of the input, which came
+                    // from C and thus has no NUL bytes. Thus, `unwrap` won't panic.
+                    *error = CString::new(e.to_message()).unwrap().into_raw();
+                }
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Like `rs_tokenize_operation_sequence`, but for the definition of a macro. Its description, if
+/// it has one, is stored into `description`; otherwise, `description` is set to null.
+#[no_mangle]
+pub unsafe extern "C" fn rs_tokenize_macro_definition(
+    input: *const c_char,
+    strict: bool,
+    description: *mut *mut c_char,
+    column: *mut usize,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        let input = input.to_string_lossy();
+
+        assert!(!description.is_null());
+        *description = ptr::null_mut();
+        let result = if strict {
+            keymap::tokenize_macro_definition_strict(&input)
+        } else {
+            keymap::tokenize_macro_definition(&input)
+        };
+        match result {
+            Ok((operations, text)) => {
+                if let Some(text) = text {
+                    // The description is a part of the input, which came from C and thus has no
+                    // NUL bytes. Thus, `unwrap` won't panic.
+                    *description = CString::new(text).unwrap().into_raw();
+                }
+                Box::into_raw(Box::new(operations)) as *mut c_void
+            }
+            Err(e) => {
+                if !column.is_null() {
+                    *column = e.column;
+                }
+                if !error.is_null() {
+                    // The message consists of our own text and a part of the input, which came
+                    // from C and thus has no NUL bytes. Thus, `unwrap` won't panic.
+                    *error = CString::new(e.to_message()).unwrap().into_raw();
+                }
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Tokenizes a commandline. Returns an operation sequence that contains at most one operation.
+///
+/// If tokenization fails, returns a null pointer and stores an error message into `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_tokenize_cmdline(
+    input: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        let input = input.to_string_lossy();
+
+        match keymap::tokenize_cmdline(&input) {
+            Ok(tokens) => {
+                let mut operations: Operations = Vec::new();
+                if !tokens.is_empty() {
+                    operations.push(tokens);
+                }
+                Box::into_raw(Box::new(operations)) as *mut c_void
+            }
+            Err(e) => {
+                if !error.is_null() {
+                    // The message consists of the input, which came from C and thus doesn't
+                    // contain NUL bytes, and of our own text. Thus, `unwrap` won't panic.
+                    *error = CString::new(e.to_message(&input)).unwrap().into_raw();
+                }
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_free(operations: *mut c_void) {
+    abort_on_panic(|| {
+        if operations.is_null() {
+            return;
+        }
+        drop(Box::from_raw(operations as *mut Operations));
+    })
+}
+
+unsafe fn with_operations<F, T>(operations: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&Operations) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!operations.is_null());
+        let operations = Box::from_raw(operations as *mut Operations);
+        let result = action(&operations);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(operations);
+        result
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_count(operations: *mut c_void) -> usize {
+    with_operations(operations, |ops| ops.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_tokens_count(
+    operations: *mut c_void,
+    operation: usize,
+) -> usize {
+    with_operations(operations, |ops| ops[operation].len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_token(
+    operations: *mut c_void,
+    operation: usize,
+    token: usize,
+) -> *mut c_char {
+    with_operations(operations, |ops| {
+        // Tokens are made from the input, which is a C string, and escape sequences, none of
+        // which produce a NUL byte. Thus, `unwrap` won't panic.
+        CString::new(ops[operation][token].clone())
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Tokenizes a commandline that starts with `run`. Returns an operation sequence, or a null
+/// pointer if the line is some other command.
+#[no_mangle]
+pub unsafe extern "C" fn rs_tokenize_run_cmdline(input: *const c_char) -> *mut c_void {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        let input = input.to_string_lossy();
+
+        match keymap::tokenize_run_cmdline(&input) {
+            Some(operations) => Box::into_raw(Box::new(operations)) as *mut c_void,
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_operation_sequence_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(Operations::new())) as *mut c_void)
+}
+
+/// Appends a token to the last operation of the sequence. If `starts_operation` is true, the token
+/// is the name of a new operation instead.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_push_token(
+    operations: *mut c_void,
+    token: *const c_char,
+    starts_operation: bool,
+) {
+    abort_on_panic(|| {
+        assert!(!operations.is_null());
+        let operations = &mut *(operations as *mut Operations);
+        let token = CStr::from_ptr(token).to_string_lossy().into_owned();
+        match operations.last_mut() {
+            Some(tokens) if !starts_operation => tokens.push(token),
+            _ => operations.push(vec![token]),
+        }
+    })
+}
+
+/// Returns a `run` commandline that executes the operation sequence.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_to_run_cmdline(
+    operations: *mut c_void,
+) -> *mut c_char {
+    with_operations(operations, |ops| {
+        // Tokens came from C strings, and quoting doesn't add NUL bytes. Thus, `unwrap` won't
+        // panic.
+        CString::new(keymap::run_cmdline(ops)).unwrap().into_raw()
+    })
+}
+
+/// Returns the operation sequence the way it would be written in a macro.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_serialize(operations: *mut c_void) -> *mut c_char {
+    with_operations(operations, |ops| {
+        // Tokens came from C strings, and quoting doesn't add NUL bytes. Thus, `unwrap` won't
+        // panic.
+        CString::new(keymap::quote_operation_sequence(ops))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Stores the number of arguments taken by a parameterized macro into `count`. If the macro uses
+/// its parameters incorrectly, returns false and stores an error message into `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_macro_parameter_count(
+    operations: *mut c_void,
+    count: *mut usize,
+    error: *mut *mut c_char,
+) -> bool {
+    with_operations(operations, |ops| match keymap::macro_parameter_count(ops) {
+        Ok(c) => {
+            if !count.is_null() {
+                *count = c;
+            }
+            true
+        }
+        Err(message) => {
+            if !error.is_null() {
+                // The message is our own text. Thus, `unwrap` won't panic.
+                *error = CString::new(message).unwrap().into_raw();
+            }
+            false
+        }
+    })
+}
+
+/// Returns false and stores an error message into `error` if an operation of the sequence was
+/// given the wrong arguments.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_check_arguments(
+    operations: *mut c_void,
+    error: *mut *mut c_char,
+) -> bool {
+    with_operations(operations, |ops| {
+        match keymap::check_operation_arguments(ops) {
+            Ok(()) => true,
+            Err(message) => {
+                if !error.is_null() {
+                    // The message is a translated text and a token that came from C. Thus, `unwrap`
+                    // won't panic.
+                    *error = CString::new(message).unwrap().into_raw();
+                }
+                false
+            }
+        }
+    })
+}
+
+/// Returns a new operation sequence in which placeholders are replaced with `arguments`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_sequence_substitute_macro_arguments(
+    operations: *mut c_void,
+    arguments: *const *const c_char,
+    arguments_count: usize,
+) -> *mut c_void {
+    let arguments = (0..arguments_count)
+        .map(|i| {
+            let argument = *arguments.add(i);
+            assert!(!argument.is_null());
+            CStr::from_ptr(argument).to_string_lossy().into_owned()
+        })
+        .collect::<Vec<_>>();
+    with_operations(operations, move |ops| {
+        let substituted = keymap::substitute_macro_arguments(ops, &arguments);
+        Box::into_raw(Box::new(substituted)) as *mut c_void
+    })
+}
+
+/// Returns the prompt for the next argument of a parameterized macro, or a null pointer if all
+/// the arguments are known.
+#[no_mangle]
+pub extern "C" fn rs_next_macro_argument_prompt(count: usize, supplied: usize) -> *mut c_char {
+    abort_on_panic(
+        || match keymap::next_macro_argument_prompt(count, supplied) {
+            // The prompt is our own text. Thus, `unwrap` won't panic.
+            Some(prompt) => CString::new(prompt).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        },
+    )
+}
+
+/// Parses the parameters of `bind-key`. Returns the operation sequence, and stores the key into
+/// `key` and the newline-separated list of dialogs into `contexts`; the list is empty if the
+/// binding applies everywhere.
+///
+/// If parsing fails, returns a null pointer and stores an error message into `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_parse_binding(
+    input: *const c_char,
+    key: *mut *mut c_char,
+    contexts: *mut *mut c_char,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        let input = input.to_string_lossy();
+
+        assert!(!key.is_null());
+        assert!(!contexts.is_null());
+        match keymap::parse_binding(&input) {
+            Ok(binding) => {
+                let mut list = String::new();
+                for context in binding.contexts.unwrap_or_default() {
+                    list.push_str(&context);
+                    list.push('\n');
+                }
+                // The key is a part of the input, which came from C and thus has no NUL bytes,
+                // and the dialogs are our own names. Thus, `unwrap` won't panic.
+                *key = CString::new(binding.key).unwrap().into_raw();
+                *contexts = CString::new(list).unwrap().into_raw();
+                Box::into_raw(Box::new(binding.operations)) as *mut c_void
+            }
+            Err(e) => {
+                if !error.is_null() {
+                    // The message consists of our own text and parts of the input, which came
+                    // from C and thus doesn't contain NUL bytes. Thus, `unwrap` won't panic.
+                    *error = CString::new(e.to_message()).unwrap().into_raw();
+                }
+                ptr::null_mut()
+            }
+        }
+    })
+}
@@ -0,0 +1,213 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::keyorigins::{KeyOrigins, Origin};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Splits a newline-separated list of contexts.
+fn to_contexts(input: &str) -> Vec<String> {
+    input
+        .split('\n')
+        .filter(|context| !context.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn to_c_string(text: Option<String>) -> *mut c_char {
+    match text {
+        // The text consists of a translated message and strings that came from C. Thus, it
+        // doesn't contain NUL bytes, and `unwrap` won't panic.
+        Some(text) => CString::new(text).unwrap().into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_key_origins_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(KeyOrigins::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_free(origins: *mut c_void) {
+    abort_on_panic(|| {
+        if origins.is_null() {
+            return;
+        }
+        drop(Box::from_raw(origins as *mut KeyOrigins));
+    })
+}
+
+/// `contexts` is a newline-separated list. Returns a warning if the binding overrides one that
+/// was defined elsewhere, or a null pointer otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_bind(
+    origins: *mut c_void,
+    contexts: *const c_char,
+    key: *const c_char,
+    operation: *const c_char,
+    file: *const c_char,
+    line: u32,
+) -> *mut c_char {
+    let contexts = to_string(contexts);
+    let key = to_string(key);
+    let operation = to_string(operation);
+    let file = to_string(file);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &mut *(origins as *mut KeyOrigins);
+        let origin = Origin { file, line };
+        to_c_string(origins.bind(&to_contexts(&contexts), &key, &operation, &origin))
+    })
+}
+
+/// `contexts` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_unbind(
+    origins: *mut c_void,
+    contexts: *const c_char,
+    key: *const c_char,
+) {
+    let contexts = to_string(contexts);
+    let key = to_string(key);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &mut *(origins as *mut KeyOrigins);
+        origins.unbind(&to_contexts(&contexts), &key);
+    })
+}
+
+/// `contexts` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_unbind_all(origins: *mut c_void, contexts: *const c_char) {
+    let contexts = to_string(contexts);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &mut *(origins as *mut KeyOrigins);
+        origins.unbind_all(&to_contexts(&contexts));
+    })
+}
+
+/// Returns where `key` was bound in `context`, as "file:line", or a null pointer if it's not
+/// known.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_binding(
+    origins: *mut c_void,
+    context: *const c_char,
+    key: *const c_char,
+) -> *mut c_char {
+    let context = to_string(context);
+    let key = to_string(key);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &*(origins as *const KeyOrigins);
+        to_c_string(
+            origins
+                .binding_origin(&context, &key)
+                .map(|origin| origin.to_string()),
+        )
+    })
+}
+
+/// `operations` is a newline-separated list. Returns a warning if the macro overrides one that
+/// was defined elsewhere, or a null pointer otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_define_macro(
+    origins: *mut c_void,
+    key: *const c_char,
+    operations: *const c_char,
+    file: *const c_char,
+    line: u32,
+) -> *mut c_char {
+    let key = to_string(key);
+    let operations = to_string(operations);
+    let file = to_string(file);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &mut *(origins as *mut KeyOrigins);
+        to_c_string(origins.define_macro(&key, &to_contexts(&operations), &Origin { file, line }))
+    })
+}
+
+/// Returns where the macro for `key` was defined, as "file:line", or a null pointer if it's not
+/// known.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_macro(
+    origins: *mut c_void,
+    key: *const c_char,
+) -> *mut c_char {
+    let key = to_string(key);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &*(origins as *const KeyOrigins);
+        to_c_string(origins.macro_origin(&key).map(|origin| origin.to_string()))
+    })
+}
+
+/// An empty `operation` means that the key isn't bound in `context`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_describe(
+    origins: *mut c_void,
+    context: *const c_char,
+    key: *const c_char,
+    operation: *const c_char,
+    has_macro: bool,
+) -> *mut c_char {
+    let context = to_string(context);
+    let key = to_string(key);
+    let operation = to_string(operation);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &*(origins as *const KeyOrigins);
+        let operation = if operation.is_empty() {
+            None
+        } else {
+            Some(operation.as_str())
+        };
+        to_c_string(Some(origins.describe(&context, &key, operation, has_macro)))
+    })
+}
+
+/// `contexts` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_register_operation(
+    origins: *mut c_void,
+    operation: *const c_char,
+    contexts: *const c_char,
+) {
+    let operation = to_string(operation);
+    let contexts = to_string(contexts);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &mut *(origins as *mut KeyOrigins);
+        origins.register_operation(&operation, &to_contexts(&contexts));
+    })
+}
+
+/// `contexts` is a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_set_program_contexts(
+    origins: *mut c_void,
+    contexts: *const c_char,
+) {
+    let contexts = to_string(contexts);
+    abort_on_panic(move || {
+        assert!(!origins.is_null());
+        let origins = &mut *(origins as *mut KeyOrigins);
+        origins.set_program_contexts(&to_contexts(&contexts));
+    })
+}
+
+/// Returns the warnings as a newline-separated list.
+#[no_mangle]
+pub unsafe extern "C" fn rs_key_origins_context_warnings(origins: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!origins.is_null());
+        let origins = &*(origins as *const KeyOrigins);
+        to_c_string(Some(origins.context_warnings().join("\n")))
+    })
+}
@@ -10,15 +10,67 @@ use std::ffi::CString;
 use std::panic::{catch_unwind, UnwindSafe};
 use std::process::abort;
 
+pub mod articlepreview;
+pub mod articleupdates;
+pub mod browser;
+pub mod cachemerge;
+pub mod cachemigration;
+pub mod cachequery;
 pub mod cliargsparser;
+pub mod clierror;
+pub mod clioutput;
+pub mod colormanager;
 pub mod configpaths;
+pub mod configvalidation;
+pub mod controlsocket;
+pub mod defaultactions;
+pub mod describe;
+pub mod dialogstack;
+pub mod dirlist;
+pub mod downloadlimits;
+pub mod emptyfeedguard;
+pub mod feeddiscovery;
+pub mod feedicons;
+pub mod feedlistsnapshot;
+pub mod feednavigation;
+pub mod feedoptions;
+pub mod feedschedule;
+pub mod fileencoding;
+pub mod filterbuilder;
 pub mod fmtstrformatter;
 pub mod fslock;
+pub mod help;
 pub mod history;
 pub mod human_panic;
+pub mod hyphenation;
+pub mod initialsync;
+pub mod keycombination;
+pub mod keymap;
+pub mod keyorigins;
+pub mod listposition;
 pub mod logger;
+pub mod longoperation;
+pub mod markread;
+pub mod markreadundo;
+pub mod matcher;
 pub mod matchererror;
+pub mod memoryreport;
+pub mod networkretry;
+pub mod opml;
+pub mod pipedarticle;
+pub mod readingtime;
+pub mod reloadhooks;
+pub mod reloadprogress;
+pub mod reloadtag;
+pub mod renderjob;
+pub mod rowgroups;
+pub mod scopedcounts;
 pub mod scopemeasure;
+pub mod selection;
+pub mod stflbuilder;
+pub mod termcaps;
+pub mod unknownoptions;
+pub mod urlsfile;
 pub mod utils;
 
 /// Runs a Rust function, and if it panics, calls abort(); otherwise returns what function
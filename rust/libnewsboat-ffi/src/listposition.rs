@@ -0,0 +1,160 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::listposition::{self, Side, UnreadIndex};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn to_strings(input: *const *const c_char, count: usize) -> Vec<String> {
+    (0..count).map(|i| to_string(*input.add(i))).collect()
+}
+
+/// Stores `value` into `output` and returns `true`, or returns `false` if there is none.
+unsafe fn to_c_position(value: Option<usize>, output: *mut usize) -> bool {
+    assert!(!output.is_null());
+    match value {
+        Some(value) => {
+            *output = value;
+            true
+        }
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_unread_index_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(UnreadIndex::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_unread_index_free(index: *mut c_void) {
+    abort_on_panic(|| {
+        if index.is_null() {
+            return;
+        }
+        drop(Box::from_raw(index as *mut UnreadIndex));
+    })
+}
+
+/// Sets the `count` entries of the list: their `keys`, and whether each one is `unread`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_unread_index_reset(
+    index: *mut c_void,
+    keys: *const *const c_char,
+    unread: *const bool,
+    count: usize,
+) {
+    let keys = to_strings(keys, count);
+    let unread = if count > 0 {
+        assert!(!unread.is_null());
+        std::slice::from_raw_parts(unread, count).to_vec()
+    } else {
+        Vec::new()
+    };
+    abort_on_panic(move || {
+        assert!(!index.is_null());
+        let index = &mut *(index as *mut UnreadIndex);
+        index.reset(keys.into_iter().zip(unread));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_unread_index_update(
+    index: *mut c_void,
+    key: *const c_char,
+    unread: bool,
+) {
+    let key = to_string(key);
+    abort_on_panic(move || {
+        assert!(!index.is_null());
+        let index = &mut *(index as *mut UnreadIndex);
+        index.update(&key, unread);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_unread_index_first(index: *mut c_void, position: *mut usize) -> bool {
+    abort_on_panic(|| {
+        assert!(!index.is_null());
+        let index = &*(index as *const UnreadIndex);
+        to_c_position(index.first(), position)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_unread_index_last(index: *mut c_void, position: *mut usize) -> bool {
+    abort_on_panic(|| {
+        assert!(!index.is_null());
+        let index = &*(index as *const UnreadIndex);
+        to_c_position(index.last(), position)
+    })
+}
+
+/// Stores the positions of the unread entries above `cursor` (or below it, if `below` is set)
+/// into `positions`, up to `capacity` of them. Returns how many there are.
+#[no_mangle]
+pub unsafe extern "C" fn rs_unread_index_unread_beside(
+    index: *mut c_void,
+    cursor: usize,
+    below: bool,
+    positions: *mut usize,
+    capacity: usize,
+) -> usize {
+    abort_on_panic(|| {
+        assert!(!index.is_null());
+        let index = &*(index as *const UnreadIndex);
+        let side = if below { Side::Below } else { Side::Above };
+        let unread = index.unread_beside(cursor, side);
+        if capacity > 0 {
+            assert!(!positions.is_null());
+            for (i, &position) in unread.iter().take(capacity).enumerate() {
+                *positions.add(i) = position;
+            }
+        }
+        unread.len()
+    })
+}
+
+/// Stores the position `percent` of the way through a list of `len` entries into `position`.
+/// Returns `false` if the list is empty.
+#[no_mangle]
+pub unsafe extern "C" fn rs_percent_position(
+    percent: u32,
+    len: usize,
+    position: *mut usize,
+) -> bool {
+    abort_on_panic(|| to_c_position(listposition::percent_position(percent, len), position))
+}
+
+/// Parses the arguments of `goto-percent` into `percent`. On failure, returns `false` and sets
+/// `error` to the message.
+#[no_mangle]
+pub unsafe extern "C" fn rs_parse_percent(
+    arguments: *const *const c_char,
+    count: usize,
+    percent: *mut u32,
+    error: *mut *mut c_char,
+) -> bool {
+    let arguments = to_strings(arguments, count);
+    abort_on_panic(move || {
+        assert!(!percent.is_null());
+        assert!(!error.is_null());
+        *error = ptr::null_mut();
+        match listposition::parse_percent(&arguments) {
+            Ok(value) => {
+                *percent = value;
+                true
+            }
+            Err(message) => {
+                // The message is a translated text and an argument that came from C. Thus,
+                // `unwrap` won't panic.
+                *error = CString::new(message).unwrap().into_raw();
+                false
+            }
+        }
+    })
+}
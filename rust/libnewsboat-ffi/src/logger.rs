@@ -3,11 +3,25 @@ use libc::c_char;
 use libnewsboat::logger;
 use std::ffi::CStr;
 
+/// Whether a message at `level` from `module` (the `__FILE__` of C++ code) would be logged.
 #[no_mangle]
-pub unsafe extern "C" fn rs_log(level: logger::Level, message: *const c_char) {
+pub unsafe extern "C" fn rs_log_enabled(module: *const c_char, level: logger::Level) -> bool {
     abort_on_panic(|| {
+        let module = CStr::from_ptr(module).to_string_lossy();
+        logger::get_instance().is_enabled(&module, level)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_log(
+    module: *const c_char,
+    level: logger::Level,
+    message: *const c_char,
+) {
+    abort_on_panic(|| {
+        let module = CStr::from_ptr(module).to_string_lossy();
         let message = CStr::from_ptr(message);
-        logger::get_instance().log_raw(level, message.to_bytes());
+        logger::get_instance().log_raw(&module, level, message.to_bytes());
     })
 }
 
@@ -18,6 +32,31 @@ pub unsafe extern "C" fn rs_set_loglevel(level: logger::Level) {
     })
 }
 
+/// Gives each of the `count` modules in `modules` the level at the same position in `levels`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_set_module_levels(
+    modules: *const *const c_char,
+    levels: *const logger::Level,
+    count: usize,
+) {
+    let modules = (0..count)
+        .map(|i| {
+            CStr::from_ptr(*modules.add(i))
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    let levels = (0..count).map(|i| *levels.add(i)).collect::<Vec<_>>();
+    abort_on_panic(move || {
+        let module_levels = modules
+            .iter()
+            .map(String::as_str)
+            .zip(levels)
+            .collect::<Vec<_>>();
+        logger::get_instance().set_module_levels(&module_levels);
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_set_logfile(logfile: *const c_char) {
     abort_on_panic(|| {
@@ -41,6 +80,15 @@ pub unsafe extern "C" fn rs_set_user_error_logfile(user_error_logfile: *const c_
 }
 
 #[no_mangle]
-pub extern "C" fn rs_get_loglevel() -> u64 {
-    abort_on_panic(|| logger::get_instance().get_loglevel() as u64)
+pub extern "C" fn rs_set_log_max_size(bytes: u64) {
+    abort_on_panic(|| {
+        logger::get_instance().set_max_size(bytes);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_set_log_keep_files(n: usize) {
+    abort_on_panic(|| {
+        logger::get_instance().set_keep_files(n);
+    })
 }
@@ -0,0 +1,65 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::longoperation::{should_notify, LongOperation};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+use std::time::Duration;
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_long_operation_start(name: *const c_char) -> *mut c_void {
+    abort_on_panic(|| {
+        assert!(!name.is_null());
+        let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+        Box::into_raw(Box::new(LongOperation::start(&name))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_long_operation_free(operation: *mut c_void) {
+    abort_on_panic(|| {
+        if operation.is_null() {
+            return;
+        }
+        drop(Box::from_raw(operation as *mut LongOperation));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_long_operation_status(operation: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!operation.is_null());
+        let operation = Box::from_raw(operation as *mut LongOperation);
+        // The name came from a C string, so it contains no NUL bytes. Thus, `unwrap` won't panic.
+        let result = CString::new(operation.status()).unwrap().into_raw();
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(operation);
+        result
+    })
+}
+
+/// Ends the operation and frees the object. Returns a summary if the user should be notified
+/// that it finished, and a null pointer otherwise. `notify_threshold_s` of zero turns
+/// notifications off.
+#[no_mangle]
+pub unsafe extern "C" fn rs_long_operation_finish(
+    operation: *mut c_void,
+    succeeded: bool,
+    notify_threshold_s: u64,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!operation.is_null());
+        let operation = Box::from_raw(operation as *mut LongOperation);
+        let threshold = match notify_threshold_s {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        };
+        match operation.finish(succeeded) {
+            // Newsboat doesn't know if the terminal is focused
+            Some(record) if should_notify(&record, threshold, None) => {
+                CString::new(record.summary()).unwrap().into_raw()
+            }
+            _ => ptr::null_mut(),
+        }
+    })
+}
@@ -0,0 +1,164 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::articlepreview::SystemClock;
+use libnewsboat::markread::{Context, DwellTimer, MarkReadPolicy, Trigger};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+use std::time::Duration;
+
+type SystemDwellTimer = DwellTimer<SystemClock>;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Returns the reason why `value` is not a valid `mark-read-on`, or a null pointer if it's valid.
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_policy_validate(value: *const c_char) -> *mut c_char {
+    abort_on_panic(|| {
+        let value = to_string(value);
+        match MarkReadPolicy::parse(&value) {
+            Ok(_) => ptr::null_mut(),
+            // The message consists of a translated text and parts of `value`, which came from C.
+            // Thus, it doesn't contain NUL bytes, and `unwrap` won't panic.
+            Err(message) => CString::new(message).unwrap().into_raw(),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_mark_read_policy_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(MarkReadPolicy::default())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_policy_free(policy: *mut c_void) {
+    abort_on_panic(|| {
+        if policy.is_null() {
+            return;
+        }
+        drop(Box::from_raw(policy as *mut MarkReadPolicy));
+    })
+}
+
+unsafe fn with_policy<F, T>(policy: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut MarkReadPolicy) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!policy.is_null());
+        let mut policy = Box::from_raw(policy as *mut MarkReadPolicy);
+        let result = action(&mut policy);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(policy);
+        result
+    })
+}
+
+/// Replaces the policy with the one described by `value`. Returns `false`, and keeps the policy
+/// as it was, if `value` is invalid.
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_policy_set(
+    policy: *mut c_void,
+    value: *const c_char,
+) -> bool {
+    let value = to_string(value);
+    with_policy(policy, move |policy| match MarkReadPolicy::parse(&value) {
+        Ok(parsed) => {
+            *policy = parsed;
+            true
+        }
+        Err(_) => false,
+    })
+}
+
+/// Milliseconds an article has to be shown to become read, or -1 if `dwell` is disabled.
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_policy_dwell_ms(policy: *mut c_void) -> i64 {
+    with_policy(policy, |policy| match policy.dwell() {
+        Some(dwell) => dwell.as_millis() as i64,
+        None => -1,
+    })
+}
+
+/// `trigger` is one of the values of `libnewsboat::markread::Trigger`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_policy_should_mark_read(
+    policy: *mut c_void,
+    trigger: u8,
+    dwelled_ms: u64,
+    at_end: bool,
+) -> bool {
+    with_policy(policy, |policy| {
+        let trigger = Trigger::from_u8(trigger).expect("unknown mark-read trigger");
+        let context = Context {
+            dwelled: Duration::from_millis(dwelled_ms),
+            at_end,
+        };
+        policy.should_mark_read(trigger, &context)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dwell_timer_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(SystemDwellTimer::new(SystemClock))) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dwell_timer_free(timer: *mut c_void) {
+    abort_on_panic(|| {
+        if timer.is_null() {
+            return;
+        }
+        drop(Box::from_raw(timer as *mut SystemDwellTimer));
+    })
+}
+
+unsafe fn with_timer<F, T>(timer: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut SystemDwellTimer) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!timer.is_null());
+        let mut timer = Box::from_raw(timer as *mut SystemDwellTimer);
+        let result = action(&mut timer);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(timer);
+        result
+    })
+}
+
+/// Starts the timer; a negative `threshold_ms` means there is no threshold.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dwell_timer_start(timer: *mut c_void, threshold_ms: i64) {
+    with_timer(timer, |timer| {
+        let threshold = if threshold_ms < 0 {
+            None
+        } else {
+            Some(Duration::from_millis(threshold_ms as u64))
+        };
+        timer.start(threshold)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dwell_timer_cancel(timer: *mut c_void) {
+    with_timer(timer, |timer| timer.cancel())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dwell_timer_elapsed_ms(timer: *mut c_void) -> u64 {
+    with_timer(timer, |timer| timer.elapsed().as_millis() as u64)
+}
+
+/// Milliseconds until the threshold is reached, or -1 if the timer isn't running or has no
+/// threshold.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dwell_timer_time_left_ms(timer: *mut c_void) -> i64 {
+    with_timer(timer, |timer| match timer.time_left() {
+        Some(left) => left.as_millis() as i64,
+        None => -1,
+    })
+}
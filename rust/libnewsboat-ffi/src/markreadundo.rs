@@ -0,0 +1,96 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::markreadundo::MarkReadUndo;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn to_strings(input: *const *const c_char, count: usize) -> Vec<String> {
+    (0..count).map(|i| to_string(*input.add(i))).collect()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_mark_read_undo_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(MarkReadUndo::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_free(undo: *mut c_void) {
+    abort_on_panic(|| {
+        if undo.is_null() {
+            return;
+        }
+        drop(Box::from_raw(undo as *mut MarkReadUndo));
+    })
+}
+
+/// Records that the `count` articles `keys` were marked read.
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_record(
+    undo: *mut c_void,
+    keys: *const *const c_char,
+    count: usize,
+) {
+    let keys = to_strings(keys, count);
+    abort_on_panic(move || {
+        assert!(!undo.is_null());
+        let undo = &mut *(undo as *mut MarkReadUndo);
+        undo.record(keys);
+    })
+}
+
+/// Returns the keys to make unread again, which have to be freed with
+/// `rs_mark_read_undo_keys_free`, or a null pointer if there's nothing to undo.
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_undo(undo: *mut c_void) -> *mut c_void {
+    abort_on_panic(|| {
+        assert!(!undo.is_null());
+        let undo = &mut *(undo as *mut MarkReadUndo);
+        match undo.undo() {
+            Some(keys) => Box::into_raw(Box::new(keys)) as *mut c_void,
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_clear(undo: *mut c_void) {
+    abort_on_panic(|| {
+        assert!(!undo.is_null());
+        let undo = &mut *(undo as *mut MarkReadUndo);
+        undo.clear();
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_keys_free(keys: *mut c_void) {
+    abort_on_panic(|| {
+        if keys.is_null() {
+            return;
+        }
+        drop(Box::from_raw(keys as *mut Vec<String>));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_keys_count(keys: *mut c_void) -> usize {
+    abort_on_panic(|| {
+        assert!(!keys.is_null());
+        (&*(keys as *const Vec<String>)).len()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_mark_read_undo_key(keys: *mut c_void, index: usize) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!keys.is_null());
+        let keys = &*(keys as *const Vec<String>);
+        // The keys came from C++ as C strings, so they don't contain NUL bytes. Thus, `unwrap`
+        // won't panic.
+        CString::new(keys[index].clone()).unwrap().into_raw()
+    })
+}
@@ -0,0 +1,120 @@
+use crate::abort_on_panic;
+use crate::matchererror::{matcher_error_to_ffi, MatcherErrorFfi};
+use libc::{c_char, c_void};
+use libnewsboat::matchable::Matchable;
+use libnewsboat::matcher::Matcher;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+/// Returns the value of the attribute `attr` of the C++ `Matchable` behind `item`, or null if
+/// there is no such attribute. The value stays valid until the next call.
+type AttributeValue = unsafe extern "C" fn(item: *mut c_void, attr: *const c_char) -> *const c_char;
+
+/// Writes the date attribute `attr` of the C++ `Matchable` behind `item` into `timestamp`, and
+/// returns `false` if there is no such attribute.
+type DateTimestamp =
+    unsafe extern "C" fn(item: *mut c_void, attr: *const c_char, timestamp: *mut i64) -> bool;
+
+/// A C++ `Matchable`, seen through the callbacks that `Matcher::matches` passes in.
+struct CppMatchable {
+    item: *mut c_void,
+    attribute_value: AttributeValue,
+    date_timestamp: DateTimestamp,
+}
+
+fn attribute_name(attr: &str) -> CString {
+    // Attribute names come from the filter parser, which only accepts alphanumerics and a few
+    // punctuation characters. Thus, `unwrap` won't panic.
+    CString::new(attr).unwrap()
+}
+
+impl Matchable for CppMatchable {
+    fn attribute_value(&self, attr: &str) -> Option<String> {
+        let attr = attribute_name(attr);
+        unsafe {
+            let value = (self.attribute_value)(self.item, attr.as_ptr());
+            if value.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(value).to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    fn date_timestamp(&self, attr: &str) -> Option<i64> {
+        let attr = attribute_name(attr);
+        let mut timestamp = 0;
+        if unsafe { (self.date_timestamp)(self.item, attr.as_ptr(), &mut timestamp) } {
+            Some(timestamp)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the matcher, or null with the parse error in `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_matcher_parse(
+    expr: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    assert!(!expr.is_null());
+    assert!(!error.is_null());
+    let expr = CStr::from_ptr(expr).to_string_lossy().into_owned();
+    abort_on_panic(move || match Matcher::parse(&expr) {
+        Ok(matcher) => {
+            *error = ptr::null_mut();
+            Box::into_raw(Box::new(matcher)) as *mut c_void
+        }
+        Err(message) => {
+            // The message is our own text and bits of `expr`, which came from C++ as a C string.
+            // Thus, `unwrap` won't panic.
+            *error = CString::new(message).unwrap().into_raw();
+            ptr::null_mut()
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_matcher_free(matcher: *mut c_void) {
+    abort_on_panic(|| {
+        if matcher.is_null() {
+            return;
+        }
+        drop(Box::from_raw(matcher as *mut Matcher));
+    })
+}
+
+/// Checks `item` against the filter. Returns `false` and fills in `error` if the filter can't be
+/// checked, e.g. because `item` lacks an attribute that the filter uses.
+#[no_mangle]
+pub unsafe extern "C" fn rs_matcher_matches(
+    matcher: *mut c_void,
+    item: *mut c_void,
+    attribute_value: AttributeValue,
+    date_timestamp: DateTimestamp,
+    matched: *mut bool,
+    error: *mut MatcherErrorFfi,
+) -> bool {
+    abort_on_panic(|| {
+        assert!(!matcher.is_null());
+        assert!(!matched.is_null());
+        assert!(!error.is_null());
+        let matcher = &*(matcher as *const Matcher);
+        let item = CppMatchable {
+            item,
+            attribute_value,
+            date_timestamp,
+        };
+        match matcher.matches(&item) {
+            Ok(result) => {
+                *matched = result;
+                true
+            }
+            Err(e) => {
+                *error = matcher_error_to_ffi(e);
+                false
+            }
+        }
+    })
+}
@@ -0,0 +1,68 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::memoryreport::MemoryReport;
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_memory_report_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(MemoryReport::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_memory_report_free(report: *mut c_void) {
+    abort_on_panic(|| {
+        if report.is_null() {
+            return;
+        }
+        drop(Box::from_raw(report as *mut MemoryReport));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_memory_report_add_dialog(report: *mut c_void, title: *const c_char) {
+    let title = to_string(title);
+    abort_on_panic(move || {
+        assert!(!report.is_null());
+        let report = &mut *(report as *mut MemoryReport);
+        report.add_dialog(&title);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_memory_report_add_item(
+    report: *mut c_void,
+    address: usize,
+    bytes: u64,
+) {
+    abort_on_panic(|| {
+        assert!(!report.is_null());
+        let report = &mut *(report as *mut MemoryReport);
+        report.add_item(address, bytes as usize);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_memory_report_distinct_items(report: *mut c_void) -> u64 {
+    abort_on_panic(|| {
+        assert!(!report.is_null());
+        let report = &*(report as *const MemoryReport);
+        report.distinct_items() as u64
+    })
+}
+
+/// Returns the lines of the report, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_memory_report_lines(report: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!report.is_null());
+        let report = &*(report as *const MemoryReport);
+        // Dialog titles came from C as C strings, and the rest is our own text. Thus, there are no
+        // NUL bytes, and `unwrap` won't panic.
+        CString::new(report.lines().join("\n")).unwrap().into_raw()
+    })
+}
@@ -0,0 +1,130 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::networkretry::{FailureKind, NetworkRetry, RetryPolicy};
+use std::ffi::{CStr, CString};
+use std::ptr;
+use std::time::Instant;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_network_retry_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(NetworkRetry::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_free(retry: *mut c_void) {
+    abort_on_panic(|| {
+        if retry.is_null() {
+            return;
+        }
+        drop(Box::from_raw(retry as *mut NetworkRetry));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_start_cycle(retry: *mut c_void) {
+    abort_on_panic(|| {
+        assert!(!retry.is_null());
+        let retry = &*(retry as *const NetworkRetry);
+        retry.start_cycle();
+    })
+}
+
+/// Records the outcome of reloading the feed at `url`. `curl_code` is only looked at if `failed`
+/// is `true`; it's 0 if the failure didn't come from curl.
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_feed_finished(
+    retry: *mut c_void,
+    url: *const c_char,
+    failed: bool,
+    curl_code: u32,
+) {
+    let url = to_string(url);
+    abort_on_panic(move || {
+        assert!(!retry.is_null());
+        let retry = &*(retry as *const NetworkRetry);
+        let failure = if failed {
+            Some(FailureKind::from_curl_code(curl_code))
+        } else {
+            None
+        };
+        retry.feed_finished(&url, failure);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_cycle_finished(
+    retry: *mut c_void,
+    enabled: bool,
+    threshold_percent: u32,
+) -> bool {
+    abort_on_panic(|| {
+        assert!(!retry.is_null());
+        let retry = &*(retry as *const NetworkRetry);
+        let policy = RetryPolicy {
+            enabled,
+            threshold_percent,
+        };
+        retry.cycle_finished(policy, Instant::now())
+    })
+}
+
+/// Returns `true` and stores the number of seconds until the next probe in `seconds`, rounded up,
+/// if a probe is scheduled. Otherwise, returns `false` and leaves `seconds` alone.
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_seconds_until_probe(
+    retry: *mut c_void,
+    seconds: *mut u64,
+) -> bool {
+    abort_on_panic(|| {
+        assert!(!retry.is_null());
+        assert!(!seconds.is_null());
+        let retry = &*(retry as *const NetworkRetry);
+        match retry.time_until_probe(Instant::now()) {
+            Some(duration) => {
+                let rounding = if duration.subsec_nanos() > 0 { 1 } else { 0 };
+                *seconds = duration.as_secs() + rounding;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Returns the URL to probe, or a null pointer if no probe is due.
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_probe_target(
+    retry: *mut c_void,
+    configured_url: *const c_char,
+) -> *mut c_char {
+    let configured_url = to_string(configured_url);
+    abort_on_panic(move || {
+        assert!(!retry.is_null());
+        let retry = &*(retry as *const NetworkRetry);
+        match retry.probe_target(&configured_url, Instant::now()) {
+            // URLs came from C as C strings, so they contain no NUL bytes, and `unwrap` won't
+            // panic.
+            Some(url) => CString::new(url).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// Returns the URLs of the feeds to reload, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_network_retry_probe_finished(
+    retry: *mut c_void,
+    succeeded: bool,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!retry.is_null());
+        let retry = &*(retry as *const NetworkRetry);
+        let urls = retry.probe_finished(succeeded, Instant::now());
+        // URLs came from C as C strings, so they contain no NUL bytes, and `unwrap` won't panic.
+        CString::new(urls.join("\n")).unwrap().into_raw()
+    })
+}
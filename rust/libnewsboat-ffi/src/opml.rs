@@ -0,0 +1,122 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::opml::{self, OpmlFeed, OpmlVersion};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Returns the feeds listed in the OPML document `content`, which have to be freed with
+/// `rs_opml_feeds_free`. On failure, returns a null pointer and sets `error` to the message.
+#[no_mangle]
+pub unsafe extern "C" fn rs_opml_parse(
+    content: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    let content = to_string(content);
+    abort_on_panic(move || {
+        assert!(!error.is_null());
+        *error = ptr::null_mut();
+        match opml::parse(&content) {
+            Ok(feeds) => Box::into_raw(Box::new(feeds)) as *mut c_void,
+            Err(e) => {
+                // The message is a translated text and tag names that came from C. Thus, it
+                // contains no NUL bytes, and `unwrap` won't panic.
+                *error = CString::new(e.to_message()).unwrap().into_raw();
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Returns an empty list of feeds, to be filled with `rs_opml_feeds_add` and passed to
+/// `rs_opml_generate`.
+#[no_mangle]
+pub extern "C" fn rs_opml_feeds_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(Vec::<OpmlFeed>::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_opml_feeds_free(feeds: *mut c_void) {
+    abort_on_panic(|| {
+        if feeds.is_null() {
+            return;
+        }
+        drop(Box::from_raw(feeds as *mut Vec<OpmlFeed>));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_opml_feeds_count(feeds: *mut c_void) -> usize {
+    abort_on_panic(|| {
+        assert!(!feeds.is_null());
+        let feeds = &*(feeds as *const Vec<OpmlFeed>);
+        feeds.len()
+    })
+}
+
+/// Stores the feed at `index` into the output parameters: the URL as it's written in the urls
+/// file, and a newline-separated list of tags. The strings have to be freed with
+/// `rs_cstring_free`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_opml_feeds_get(
+    feeds: *mut c_void,
+    index: usize,
+    url: *mut *mut c_char,
+    tags: *mut *mut c_char,
+) {
+    abort_on_panic(|| {
+        assert!(!feeds.is_null());
+        let feeds = &*(feeds as *const Vec<OpmlFeed>);
+        let feed = &feeds[index];
+        // The strings came from C, and references to NUL are kept as they are. Thus, `unwrap`
+        // won't panic.
+        *url = CString::new(feed.newsboat_url()).unwrap().into_raw();
+        *tags = CString::new(feed.folders.join("\n")).unwrap().into_raw();
+    })
+}
+
+/// Adds a feed without folders. Empty `html_url` and `title` are left out of the document.
+#[no_mangle]
+pub unsafe extern "C" fn rs_opml_feeds_add(
+    feeds: *mut c_void,
+    xml_url: *const c_char,
+    html_url: *const c_char,
+    title: *const c_char,
+) {
+    let xml_url = to_string(xml_url);
+    let html_url = Some(to_string(html_url)).filter(|html_url| !html_url.is_empty());
+    let title = Some(to_string(title)).filter(|title| !title.is_empty());
+    abort_on_panic(move || {
+        assert!(!feeds.is_null());
+        let feeds = &mut *(feeds as *mut Vec<OpmlFeed>);
+        feeds.push(OpmlFeed {
+            xml_url,
+            html_url,
+            title,
+            ..OpmlFeed::default()
+        });
+    })
+}
+
+/// Returns an OPML document that lists `feeds`, in version 2.0 if `version2` is `true` and 1.0
+/// otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn rs_opml_generate(feeds: *mut c_void, version2: bool) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!feeds.is_null());
+        let feeds = &*(feeds as *const Vec<OpmlFeed>);
+        let version = if version2 {
+            OpmlVersion::V2
+        } else {
+            OpmlVersion::V1
+        };
+        // The document is made of strings that came from C. Thus, `unwrap` won't panic.
+        CString::new(opml::generate(feeds, version))
+            .unwrap()
+            .into_raw()
+    })
+}
@@ -0,0 +1,76 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::pipedarticle::{self, Header};
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn to_header(
+    title: *const c_char,
+    author: *const c_char,
+    date: *const c_char,
+    link: *const c_char,
+) -> Header {
+    Header {
+        title: to_string(title),
+        author: to_string(author),
+        date: to_string(date),
+        link: to_string(link),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_piped_article_plain_text(
+    title: *const c_char,
+    author: *const c_char,
+    date: *const c_char,
+    link: *const c_char,
+    text: *const c_char,
+) -> *mut c_char {
+    let header = to_header(title, author, date, link);
+    let text = to_string(text);
+    abort_on_panic(move || {
+        let result = pipedarticle::plain_text(&header, &text);
+        // The result consists of strings that came from C and fixed labels. Thus, it doesn't
+        // contain NUL bytes, and `unwrap` won't panic.
+        CString::new(result).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_piped_article_raw_html(
+    title: *const c_char,
+    author: *const c_char,
+    date: *const c_char,
+    link: *const c_char,
+    html: *const c_char,
+) -> *mut c_char {
+    let header = to_header(title, author, date, link);
+    let html = to_string(html);
+    abort_on_panic(move || {
+        let result = pipedarticle::raw_html(&header, &html);
+        // The result consists of strings that came from C and fixed labels. Thus, it doesn't
+        // contain NUL bytes, and `unwrap` won't panic.
+        CString::new(result).unwrap().into_raw()
+    })
+}
+
+/// Returns the command to run, and sets `raw_html` to whether the `--raw-html` flag was given.
+#[no_mangle]
+pub unsafe extern "C" fn rs_piped_article_parse_command(
+    input: *const c_char,
+    raw_html: *mut bool,
+) -> *mut c_char {
+    let input = to_string(input);
+    abort_on_panic(move || {
+        assert!(!raw_html.is_null());
+        let (flag, command) = pipedarticle::parse_command(&input);
+        *raw_html = flag;
+        // The command is a part of a string that came from C. Thus, it doesn't contain NUL bytes,
+        // and `unwrap` won't panic.
+        CString::new(command).unwrap().into_raw()
+    })
+}
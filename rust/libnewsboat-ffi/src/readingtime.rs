@@ -0,0 +1,39 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::readingtime::{self, ArticleLength};
+use std::ffi::CStr;
+
+/// Measures `content`, reusing `previous_words` if `previous_hash` is the hash of the same
+/// content. Stores the results in `hash` and `words`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_article_length_measure(
+    content: *const c_char,
+    has_previous: bool,
+    previous_hash: u64,
+    previous_words: u64,
+    hash: *mut u64,
+    words: *mut u64,
+) {
+    abort_on_panic(|| {
+        assert!(!content.is_null());
+        assert!(!hash.is_null());
+        assert!(!words.is_null());
+        let content = CStr::from_ptr(content).to_string_lossy();
+        let previous = if has_previous {
+            Some(ArticleLength {
+                content_hash: previous_hash,
+                words: previous_words,
+            })
+        } else {
+            None
+        };
+        let length = ArticleLength::measure(&content, previous);
+        *hash = length.content_hash;
+        *words = length.words;
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_reading_minutes(words: u64, words_per_minute: u32) -> u64 {
+    abort_on_panic(|| readingtime::reading_minutes(words, words_per_minute))
+}
@@ -0,0 +1,136 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::reloadhooks::{HookExecution, HookKind, ReloadHooks, ReloadOutcome};
+use std::ffi::{CStr, CString};
+use std::panic::UnwindSafe;
+use std::ptr;
+use std::time::Duration;
+
+#[no_mangle]
+pub extern "C" fn rs_reload_hooks_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(ReloadHooks::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_free(hooks: *mut c_void) {
+    abort_on_panic(|| {
+        if hooks.is_null() {
+            return;
+        }
+        drop(Box::from_raw(hooks as *mut ReloadHooks));
+    })
+}
+
+unsafe fn with_hooks<F, T>(hooks: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&ReloadHooks) -> T + UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!hooks.is_null());
+        // Reload threads run hooks at the same time, so we only ever borrow this object.
+        let hooks = &*(hooks as *const ReloadHooks);
+        action(hooks)
+    })
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+fn kind_from_u8(kind: u8) -> HookKind {
+    match kind {
+        1 => HookKind::PostReload,
+        _ => HookKind::PreReload,
+    }
+}
+
+/// Converts the result of a hook run into an error message for C++: null if there was nothing to
+/// run or the hook succeeded.
+fn error_message(execution: Option<HookExecution>) -> *mut c_char {
+    match execution {
+        // The message is made of the command's stderr, which was lossily converted and had
+        // its null bytes replaced. Thus, `unwrap` won't panic.
+        Some(run) if !run.succeeded() => CString::new(run.error_message().replace('\0', ""))
+            .unwrap()
+            .into_raw(),
+        _ => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_set_command(
+    hooks: *mut c_void,
+    kind: u8,
+    url: *const c_char,
+    command: *const c_char,
+) {
+    abort_on_panic(|| {
+        assert!(!hooks.is_null());
+        // Hooks are only configured while the config is parsed, before any reload threads start.
+        let hooks = &mut *(hooks as *mut ReloadHooks);
+        hooks.set_command(kind_from_u8(kind), &to_string(url), &to_string(command));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_set_timeout(hooks: *mut c_void, seconds: u32) {
+    abort_on_panic(|| {
+        assert!(!hooks.is_null());
+        let hooks = &mut *(hooks as *mut ReloadHooks);
+        hooks.set_timeout(Duration::from_secs(u64::from(seconds)));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_start_cycle(hooks: *mut c_void) {
+    with_hooks(hooks, |h| h.start_cycle())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_run_pre_reload(
+    hooks: *mut c_void,
+    url: *const c_char,
+) -> *mut c_char {
+    let url = to_string(url);
+    with_hooks(hooks, move |h| error_message(h.run_pre_reload(&url)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_run_post_reload(
+    hooks: *mut c_void,
+    url: *const c_char,
+    outcome: u8,
+    new_items: u32,
+) -> *mut c_char {
+    let url = to_string(url);
+    with_hooks(hooks, move |h| {
+        error_message(h.run_post_reload(&url, ReloadOutcome::from_u8(outcome), new_items))
+    })
+}
+
+/// Lines of the report, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_report(hooks: *mut c_void) -> *mut c_char {
+    with_hooks(hooks, |h| {
+        let report = h
+            .report()
+            .iter()
+            .map(HookExecution::describe)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .replace('\0', "");
+        // Null bytes were removed above. Thus, `unwrap` won't panic.
+        CString::new(report).unwrap().into_raw()
+    })
+}
+
+/// Config lines, separated by newlines.
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_hooks_dump_config(hooks: *mut c_void) -> *mut c_char {
+    with_hooks(hooks, |h| {
+        // URLs and commands came from C++ as C strings, so they don't contain null bytes. Thus,
+        // `unwrap` won't panic.
+        CString::new(h.dump_config().join("\n")).unwrap().into_raw()
+    })
+}
@@ -0,0 +1,212 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::reloadprogress::{FeedFailure, ReloadProgress, ReloadProgressSnapshot};
+use std::ffi::{CStr, CString};
+use std::panic::UnwindSafe;
+
+#[no_mangle]
+pub extern "C" fn rs_reload_progress_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(ReloadProgress::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_free(progress: *mut c_void) {
+    abort_on_panic(|| {
+        if progress.is_null() {
+            return;
+        }
+        drop(Box::from_raw(progress as *mut ReloadProgress));
+    })
+}
+
+unsafe fn with_progress<F, T>(progress: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&ReloadProgress) -> T + UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!progress.is_null());
+        // Fetcher threads and the UI thread use this object at the same time, so we only ever
+        // borrow it. Re-boxing it like the other wrappers do would create aliasing Boxes.
+        let progress = &*(progress as *const ReloadProgress);
+        action(progress)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_start(progress: *mut c_void, total: u32) {
+    with_progress(progress, |p| p.start(total))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_feed_started(
+    progress: *mut c_void,
+    title: *const c_char,
+) {
+    let title = {
+        assert!(!title.is_null());
+        CStr::from_ptr(title)
+    }
+    .to_string_lossy()
+    .into_owned();
+    with_progress(progress, move |p| p.feed_started(&title))
+}
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_feed_finished(progress: *mut c_void) {
+    with_progress(progress, |p| p.feed_finished())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_finish(progress: *mut c_void) {
+    with_progress(progress, |p| p.finish())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_cancel(progress: *mut c_void) {
+    with_progress(progress, |p| p.cancel())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_is_cancelled(progress: *mut c_void) -> bool {
+    with_progress(progress, |p| p.is_cancelled())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot(progress: *mut c_void) -> *mut c_void {
+    with_progress(progress, |p| {
+        Box::into_raw(Box::new(p.snapshot())) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot_free(snapshot: *mut c_void) {
+    abort_on_panic(|| {
+        if snapshot.is_null() {
+            return;
+        }
+        drop(Box::from_raw(snapshot as *mut ReloadProgressSnapshot));
+    })
+}
+
+unsafe fn with_snapshot<F, T>(snapshot: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&ReloadProgressSnapshot) -> T + UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!snapshot.is_null());
+        let snapshot = &*(snapshot as *const ReloadProgressSnapshot);
+        action(snapshot)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot_done(snapshot: *mut c_void) -> u32 {
+    with_snapshot(snapshot, |s| s.done)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot_total(snapshot: *mut c_void) -> u32 {
+    with_snapshot(snapshot, |s| s.total)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot_cancelled(snapshot: *mut c_void) -> bool {
+    with_snapshot(snapshot, |s| s.cancelled)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot_percent_text(
+    snapshot: *mut c_void,
+) -> *mut c_char {
+    // The text consists of digits only. Thus, `unwrap` won't panic.
+    with_snapshot(snapshot, |s| {
+        CString::new(s.percent_text()).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_snapshot_current_text(
+    snapshot: *mut c_void,
+) -> *mut c_char {
+    // The title came from C++ as a C string, so it doesn't contain null bytes. Thus, `unwrap`
+    // won't panic.
+    with_snapshot(snapshot, |s| {
+        CString::new(s.current_text()).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_feed_failed(
+    progress: *mut c_void,
+    url: *const c_char,
+    message: *const c_char,
+) {
+    let url = to_string(url);
+    let message = to_string(message);
+    with_progress(progress, move |p| p.feed_failed(&url, &message))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_failures(progress: *mut c_void) -> *mut c_void {
+    with_progress(progress, |p| {
+        Box::into_raw(Box::new(p.failures())) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_failures_free(failures: *mut c_void) {
+    abort_on_panic(|| {
+        if failures.is_null() {
+            return;
+        }
+        drop(Box::from_raw(failures as *mut Vec<FeedFailure>));
+    })
+}
+
+unsafe fn with_failure<F, T>(failures: *mut c_void, index: usize, action: F) -> T
+where
+    F: FnOnce(&FeedFailure) -> T + UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!failures.is_null());
+        let failures = &*(failures as *const Vec<FeedFailure>);
+        action(&failures[index])
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_failures_count(failures: *mut c_void) -> usize {
+    abort_on_panic(|| {
+        assert!(!failures.is_null());
+        (&*(failures as *const Vec<FeedFailure>)).len()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_failure_url(
+    failures: *mut c_void,
+    index: usize,
+) -> *mut c_char {
+    // The URL came from C++ as a C string, so it doesn't contain null bytes. Thus, `unwrap`
+    // won't panic.
+    with_failure(failures, index, |f| {
+        CString::new(f.url.clone()).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_reload_progress_failure_message(
+    failures: *mut c_void,
+    index: usize,
+) -> *mut c_char {
+    // The message came from C++ as a C string, so it doesn't contain null bytes. Thus, `unwrap`
+    // won't panic.
+    with_failure(failures, index, |f| {
+        CString::new(f.message.clone()).unwrap().into_raw()
+    })
+}
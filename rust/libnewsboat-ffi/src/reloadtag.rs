@@ -0,0 +1,90 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::reloadtag::TagScope;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Picks the feeds that carry `tag`. The tags of the `feed_count` feeds are laid out one after
+/// another in `tags`, and `tag_counts` holds the number of tags of each feed.
+///
+/// Returns a null pointer and sets `error` if no feed carries the tag.
+#[no_mangle]
+pub unsafe extern "C" fn rs_tag_scope_select(
+    tag: *const c_char,
+    tags: *const *const c_char,
+    tag_counts: *const usize,
+    feed_count: usize,
+    error: *mut *mut c_char,
+) -> *mut c_void {
+    let tag = to_string(tag);
+    let mut feed_tags = Vec::with_capacity(feed_count);
+    let mut next_tag = 0;
+    for feed in 0..feed_count {
+        let count = *tag_counts.add(feed);
+        feed_tags.push(
+            (next_tag..next_tag + count)
+                .map(|i| to_string(*tags.add(i)))
+                .collect::<Vec<_>>(),
+        );
+        next_tag += count;
+    }
+    abort_on_panic(move || {
+        assert!(!error.is_null());
+        *error = ptr::null_mut();
+        match TagScope::select(&tag, &feed_tags) {
+            Ok(scope) => Box::into_raw(Box::new(scope)) as *mut c_void,
+            Err(unknown) => {
+                // The message is a translated text and a string that came from C. Thus, it
+                // doesn't contain NUL bytes, and `unwrap` won't panic.
+                *error = CString::new(unknown.message()).unwrap().into_raw();
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_tag_scope_free(scope: *mut c_void) {
+    abort_on_panic(|| {
+        if scope.is_null() {
+            return;
+        }
+        drop(Box::from_raw(scope as *mut TagScope));
+    })
+}
+
+/// Number of feeds that carry the tag.
+#[no_mangle]
+pub unsafe extern "C" fn rs_tag_scope_len(scope: *mut c_void) -> usize {
+    abort_on_panic(|| {
+        assert!(!scope.is_null());
+        let scope = &*(scope as *mut TagScope);
+        scope.indexes().len()
+    })
+}
+
+/// Position in the feed list of the `n`-th feed that carries the tag.
+#[no_mangle]
+pub unsafe extern "C" fn rs_tag_scope_index(scope: *mut c_void, n: usize) -> usize {
+    abort_on_panic(|| {
+        assert!(!scope.is_null());
+        let scope = &*(scope as *mut TagScope);
+        scope.indexes()[n]
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_tag_scope_summary(scope: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!scope.is_null());
+        let scope = &*(scope as *mut TagScope);
+        // The summary is a translated text and a tag that came from C. Thus, it doesn't contain
+        // NUL bytes, and `unwrap` won't panic.
+        CString::new(scope.summary()).unwrap().into_raw()
+    })
+}
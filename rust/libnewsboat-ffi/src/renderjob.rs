@@ -0,0 +1,252 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::renderjob::{RenderCache, RenderJob, RenderedArticle, Step, NODES_PER_UNIT};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn c_string(input: &str) -> *mut c_char {
+    // Lines and links of a rendered article came from C, so they don't contain NUL bytes. Thus,
+    // `unwrap` won't panic.
+    CString::new(input).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_render_job_nodes_per_unit() -> usize {
+    NODES_PER_UNIT
+}
+
+#[no_mangle]
+pub extern "C" fn rs_render_job_new(screen_height: usize) -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(RenderJob::new(screen_height))) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_job_free(job: *mut c_void) {
+    abort_on_panic(|| {
+        if job.is_null() {
+            return;
+        }
+        drop(Box::from_raw(job as *mut RenderJob));
+    })
+}
+
+unsafe fn with_job<F, T>(job: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut RenderJob) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!job.is_null());
+        let mut job = Box::from_raw(job as *mut RenderJob);
+        let result = action(&mut job);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(job);
+        result
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_job_cancel(job: *mut c_void) {
+    with_job(job, |job| job.cancel())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_job_should_continue(job: *mut c_void) -> bool {
+    with_job(job, |job| job.should_continue())
+}
+
+/// Returns 0 to continue, 1 to show the first screen, 2 if the job finished, and 3 if it was
+/// cancelled.
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_job_unit_done(
+    job: *mut c_void,
+    lines: usize,
+    finished: bool,
+) -> u8 {
+    with_job(job, move |job| match job.unit_done(lines, finished) {
+        Step::Continue => 0,
+        Step::ShowFirstScreen => 1,
+        Step::Finished => 2,
+        Step::Cancelled => 3,
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_rendered_article_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(RenderedArticle::default())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_free(article: *mut c_void) {
+    abort_on_panic(|| {
+        if article.is_null() {
+            return;
+        }
+        drop(Box::from_raw(article as *mut RenderedArticle));
+    })
+}
+
+unsafe fn with_article<F, T>(article: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut RenderedArticle) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!article.is_null());
+        let mut article = Box::from_raw(article as *mut RenderedArticle);
+        let result = action(&mut article);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(article);
+        result
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_add_line(
+    article: *mut c_void,
+    line_type: u8,
+    line: *const c_char,
+) {
+    let line = to_string(line);
+    with_article(article, move |article| {
+        article.lines.push((line_type, line))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_add_link(
+    article: *mut c_void,
+    url: *const c_char,
+    link_type: u8,
+) {
+    let url = to_string(url);
+    with_article(article, move |article| article.links.push((url, link_type)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_line_count(article: *mut c_void) -> usize {
+    with_article(article, |article| article.lines.len())
+}
+
+/// Returns line `index` and sets `line_type` to its type.
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_line(
+    article: *mut c_void,
+    index: usize,
+    line_type: *mut u8,
+) -> *mut c_char {
+    with_article(article, move |article| {
+        assert!(!line_type.is_null());
+        let (kind, line) = &article.lines[index];
+        *line_type = *kind;
+        c_string(line)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_link_count(article: *mut c_void) -> usize {
+    with_article(article, |article| article.links.len())
+}
+
+/// Returns the URL of link `index` and sets `link_type` to its type.
+#[no_mangle]
+pub unsafe extern "C" fn rs_rendered_article_link(
+    article: *mut c_void,
+    index: usize,
+    link_type: *mut u8,
+) -> *mut c_char {
+    with_article(article, move |article| {
+        assert!(!link_type.is_null());
+        let (url, kind) = &article.links[index];
+        *link_type = *kind;
+        c_string(url)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_render_cache_new(max_entries: usize, max_bytes: usize) -> *mut c_void {
+    abort_on_panic(|| {
+        Box::into_raw(Box::new(RenderCache::new(max_entries, max_bytes))) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_cache_free(cache: *mut c_void) {
+    abort_on_panic(|| {
+        if cache.is_null() {
+            return;
+        }
+        drop(Box::from_raw(cache as *mut RenderCache));
+    })
+}
+
+unsafe fn with_cache<F, T>(cache: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&RenderCache) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!cache.is_null());
+        // The cache locks internally, so it's enough to borrow it.
+        let cache = &*(cache as *const RenderCache);
+        action(cache)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_cache_set_limits(
+    cache: *mut c_void,
+    max_entries: usize,
+    max_bytes: usize,
+) {
+    with_cache(cache, |cache| cache.set_limits(max_entries, max_bytes))
+}
+
+/// Returns a copy of the cached article, which has to be freed with `rs_rendered_article_free`,
+/// or a null pointer if it's not cached.
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_cache_get(
+    cache: *mut c_void,
+    guid: *const c_char,
+) -> *mut c_void {
+    let guid = to_string(guid);
+    with_cache(cache, move |cache| match cache.get(&guid) {
+        Some(article) => Box::into_raw(Box::new(article)) as *mut c_void,
+        None => ptr::null_mut(),
+    })
+}
+
+/// Caches a copy of `article` if `job` rendered it completely. Returns whether it did.
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_cache_store(
+    cache: *mut c_void,
+    guid: *const c_char,
+    job: *mut c_void,
+    article: *mut c_void,
+) -> bool {
+    let guid = to_string(guid);
+    abort_on_panic(move || {
+        assert!(!job.is_null());
+        assert!(!article.is_null());
+        let job = &*(job as *const RenderJob);
+        let article = (*(article as *const RenderedArticle)).clone();
+        with_cache(cache, move |cache| cache.store(&guid, job, article))
+    })
+}
+
+/// Hit/miss/eviction counters and the size of the cache, as a line for the log.
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_cache_stats(cache: *mut c_void) -> *mut c_char {
+    // The description consists of numbers and ASCII words. Thus, `unwrap` won't panic.
+    with_cache(cache, |cache| {
+        CString::new(cache.stats().describe()).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_render_cache_clear(cache: *mut c_void) {
+    with_cache(cache, |cache| cache.clear())
+}
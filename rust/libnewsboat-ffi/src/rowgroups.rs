@@ -0,0 +1,107 @@
+use crate::abort_on_panic;
+use libc::c_void;
+use libnewsboat::rowgroups::RowGroups;
+use std::slice;
+
+#[no_mangle]
+pub extern "C" fn rs_row_groups_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(RowGroups::default())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_free(groups: *mut c_void) {
+    abort_on_panic(|| {
+        if groups.is_null() {
+            return;
+        }
+        drop(Box::from_raw(groups as *mut RowGroups));
+    })
+}
+
+/// Replaces the groups with `count` items, which take up `row_counts` rows each.
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_set(
+    groups: *mut c_void,
+    row_counts: *const u32,
+    count: usize,
+) {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &mut *(groups as *mut RowGroups);
+        let row_counts = if count == 0 {
+            &[]
+        } else {
+            assert!(!row_counts.is_null());
+            slice::from_raw_parts(row_counts, count)
+        };
+        *groups = RowGroups::new(row_counts);
+    })
+}
+
+/// Replaces the groups with `items` items of one row each.
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_set_uniform(groups: *mut c_void, items: u32) {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &mut *(groups as *mut RowGroups);
+        *groups = RowGroups::uniform(items);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_item_count(groups: *mut c_void) -> u32 {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &*(groups as *const RowGroups);
+        groups.item_count()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_first_row(groups: *mut c_void, item: u32) -> u32 {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &*(groups as *const RowGroups);
+        groups.first_row(item)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_last_row(groups: *mut c_void, item: u32) -> u32 {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &*(groups as *const RowGroups);
+        groups.last_row(item)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_item_at_row(groups: *mut c_void, row: u32) -> u32 {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &*(groups as *const RowGroups);
+        groups.item_at_row(row)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_page_down(
+    groups: *mut c_void,
+    item: u32,
+    height: u32,
+) -> u32 {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &*(groups as *const RowGroups);
+        groups.page_down(item, height)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_row_groups_page_up(groups: *mut c_void, item: u32, height: u32) -> u32 {
+    abort_on_panic(|| {
+        assert!(!groups.is_null());
+        let groups = &*(groups as *const RowGroups);
+        groups.page_up(item, height)
+    })
+}
@@ -0,0 +1,75 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::scopedcounts::ScopedCounts;
+use std::ffi::CStr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_scoped_counts_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(ScopedCounts::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_scoped_counts_free(counts: *mut c_void) {
+    abort_on_panic(|| {
+        if counts.is_null() {
+            return;
+        }
+        drop(Box::from_raw(counts as *mut ScopedCounts));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_scoped_counts_clear(counts: *mut c_void) {
+    abort_on_panic(|| {
+        assert!(!counts.is_null());
+        let counts = &mut *(counts as *mut ScopedCounts);
+        counts.clear();
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_scoped_counts_update(
+    counts: *mut c_void,
+    key: *const c_char,
+    in_scope: bool,
+    unread: u32,
+) {
+    let key = to_string(key);
+    abort_on_panic(move || {
+        assert!(!counts.is_null());
+        let counts = &mut *(counts as *mut ScopedCounts);
+        counts.update(&key, in_scope, unread);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_scoped_counts_total(counts: *mut c_void) -> u64 {
+    abort_on_panic(|| {
+        assert!(!counts.is_null());
+        let counts = &*(counts as *const ScopedCounts);
+        counts.total() as u64
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_scoped_counts_unread(counts: *mut c_void) -> u64 {
+    abort_on_panic(|| {
+        assert!(!counts.is_null());
+        let counts = &*(counts as *const ScopedCounts);
+        counts.unread()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_scoped_counts_unread_members(counts: *mut c_void) -> u64 {
+    abort_on_panic(|| {
+        assert!(!counts.is_null());
+        let counts = &*(counts as *const ScopedCounts);
+        counts.unread_members() as u64
+    })
+}
@@ -1,8 +1,11 @@
 use crate::abort_on_panic;
 use libc::{c_char, c_void};
-use libnewsboat::scopemeasure::ScopeMeasure;
-use std::ffi::CStr;
+use libnewsboat::logger::Level;
+use libnewsboat::scopemeasure::{OperationTimer, ScopeMeasure};
+use std::ffi::{CStr, CString};
 use std::mem;
+use std::ptr;
+use std::time::Duration;
 
 #[no_mangle]
 pub unsafe extern "C" fn create_rs_scopemeasure(scope_name: *const c_char) -> *mut c_void {
@@ -12,6 +15,22 @@ pub unsafe extern "C" fn create_rs_scopemeasure(scope_name: *const c_char) -> *m
     })
 }
 
+/// Like `create_rs_scopemeasure`, but only logs at `level` if at least `threshold_ms` elapsed.
+#[no_mangle]
+pub unsafe extern "C" fn create_rs_scopemeasure_with_threshold(
+    scope_name: *const c_char,
+    threshold_ms: u64,
+    level: Level,
+) -> *mut c_void {
+    abort_on_panic(|| {
+        let scope_name = CStr::from_ptr(scope_name).to_string_lossy().into_owned();
+        let measure = ScopeMeasure::new(scope_name)
+            .threshold(Duration::from_millis(threshold_ms))
+            .level(level);
+        Box::into_raw(Box::new(measure)) as *mut c_void
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn destroy_rs_scopemeasure(object: *mut c_void) {
     abort_on_panic(|| {
@@ -45,3 +64,75 @@ pub unsafe extern "C" fn rs_scopemeasure_stopover(
         mem::forget(object);
     })
 }
+
+#[no_mangle]
+pub extern "C" fn rs_operation_timer_new(threshold_ms: u64) -> *mut c_void {
+    abort_on_panic(|| {
+        let timer = OperationTimer::new(Duration::from_millis(threshold_ms));
+        Box::into_raw(Box::new(timer)) as *mut c_void
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_timer_free(timer: *mut c_void) {
+    abort_on_panic(|| {
+        if timer.is_null() {
+            return;
+        }
+        drop(Box::from_raw(timer as *mut OperationTimer));
+    })
+}
+
+unsafe fn with_timer<F, T>(timer: *mut c_void, action: F) -> T
+where
+    F: FnOnce(&mut OperationTimer) -> T + std::panic::UnwindSafe,
+{
+    abort_on_panic(|| {
+        assert!(!timer.is_null());
+        let mut timer = Box::from_raw(timer as *mut OperationTimer);
+        let result = action(&mut timer);
+        // Do not deallocate the object - C still has a pointer to it
+        mem::forget(timer);
+        result
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_timer_start_step(timer: *mut c_void) {
+    with_timer(timer, |t| t.start_step())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_timer_finish_step(
+    timer: *mut c_void,
+    name: *const c_char,
+    arguments: *const *const c_char,
+    arguments_count: usize,
+) {
+    let name = {
+        assert!(!name.is_null());
+        CStr::from_ptr(name)
+    }
+    .to_string_lossy()
+    .into_owned();
+    let arguments = (0..arguments_count)
+        .map(|i| {
+            let argument = *arguments.add(i);
+            assert!(!argument.is_null());
+            CStr::from_ptr(argument).to_string_lossy().into_owned()
+        })
+        .collect::<Vec<_>>();
+    with_timer(timer, move |t| t.finish_step(&name, &arguments))
+}
+
+/// Returns the status line message for a slow sequence, or a null pointer if it wasn't slow.
+#[no_mangle]
+pub unsafe extern "C" fn rs_operation_timer_slow_sequence_hint(timer: *mut c_void) -> *mut c_char {
+    with_timer(timer, |t| match t.slow_sequence_hint() {
+        // The hint is made of our own text and of C strings passed to
+        // `rs_operation_timer_finish_step`, none of which contain NUL bytes. Thus, `unwrap` won't
+        // panic.
+        Some(hint) => CString::new(hint).unwrap().into_raw(),
+        None => ptr::null_mut(),
+    })
+}
@@ -0,0 +1,148 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::selection::{RangeEnd, Selection};
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+unsafe fn to_strings(input: *const *const c_char, count: usize) -> Vec<String> {
+    (0..count).map(|i| to_string(*input.add(i))).collect()
+}
+
+#[no_mangle]
+pub extern "C" fn rs_selection_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(Selection::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_free(selection: *mut c_void) {
+    abort_on_panic(|| {
+        if selection.is_null() {
+            return;
+        }
+        drop(Box::from_raw(selection as *mut Selection));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_set_order(
+    selection: *mut c_void,
+    keys: *const *const c_char,
+    count: usize,
+) {
+    let keys = to_strings(keys, count);
+    abort_on_panic(move || {
+        assert!(!selection.is_null());
+        let selection = &mut *(selection as *mut Selection);
+        selection.set_order(keys);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_toggle(selection: *mut c_void, cursor: usize) -> bool {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &mut *(selection as *mut Selection);
+        selection.toggle(cursor)
+    })
+}
+
+/// Returns `false` if the range was anchored, and `true` if it was added to the selection, in
+/// which case `count` is set to the number of selected articles.
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_select_range(
+    selection: *mut c_void,
+    cursor: usize,
+    count: *mut usize,
+) -> bool {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        assert!(!count.is_null());
+        let selection = &mut *(selection as *mut Selection);
+        match selection.select_range(cursor) {
+            RangeEnd::Anchored => false,
+            RangeEnd::Selected(selected) => {
+                *count = selected;
+                true
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_is_anchored(selection: *mut c_void) -> bool {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &*(selection as *const Selection);
+        selection.is_anchored()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_is_active(selection: *mut c_void) -> bool {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &*(selection as *const Selection);
+        selection.is_active()
+    })
+}
+
+/// Writes up to `capacity` positions of selected articles to `positions`, and returns how many
+/// there are in total.
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_selected_positions(
+    selection: *mut c_void,
+    cursor: usize,
+    positions: *mut usize,
+    capacity: usize,
+) -> usize {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &*(selection as *const Selection);
+        let selected = selection.selected_positions(cursor);
+        if capacity > 0 {
+            assert!(!positions.is_null());
+            for (i, &position) in selected.iter().take(capacity).enumerate() {
+                *positions.add(i) = position;
+            }
+        }
+        selected.len()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_marker(
+    selection: *mut c_void,
+    position: usize,
+    cursor: usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &*(selection as *const Selection);
+        // The markers are constants without NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(selection.marker(position, cursor))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_needs_redraw(selection: *mut c_void, cursor: usize) -> bool {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &mut *(selection as *mut Selection);
+        selection.needs_redraw(cursor)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_selection_clear(selection: *mut c_void) {
+    abort_on_panic(|| {
+        assert!(!selection.is_null());
+        let selection = &mut *(selection as *mut Selection);
+        selection.clear();
+    })
+}
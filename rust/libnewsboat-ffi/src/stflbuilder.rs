@@ -0,0 +1,52 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::stflbuilder;
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+fn to_c_string(form: String) -> *mut c_char {
+    // The form is built out of C strings, which can't contain NUL bytes, so `unwrap` won't panic.
+    CString::new(form).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_stfl_help_form(
+    title: *const c_char,
+    hints: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let title = to_string(title);
+        let hints = to_string(hints);
+        to_c_string(stflbuilder::help_form(&title, &hints))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_stfl_urlview_form(
+    title: *const c_char,
+    hints: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let title = to_string(title);
+        let hints = to_string(hints);
+        to_c_string(stflbuilder::urlview_form(&title, &hints))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_stfl_filebrowser_form(
+    title: *const c_char,
+    prompt: *const c_char,
+    hints: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let title = to_string(title);
+        let prompt = to_string(prompt);
+        let hints = to_string(hints);
+        to_c_string(stflbuilder::filebrowser_form(&title, &prompt, &hints))
+    })
+}
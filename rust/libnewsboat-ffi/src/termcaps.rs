@@ -0,0 +1,56 @@
+use crate::abort_on_panic;
+use libc::c_char;
+use libnewsboat::termcaps::{self, ColorSupport};
+use std::ffi::{CStr, CString};
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// `colorterm` may be null if the environment variable isn't set.
+#[no_mangle]
+pub unsafe extern "C" fn rs_detect_color_support(colors: i32, colorterm: *const c_char) -> u8 {
+    abort_on_panic(|| {
+        let colorterm = if colorterm.is_null() {
+            None
+        } else {
+            Some(to_string(colorterm))
+        };
+        ColorSupport::detect(colors, colorterm.as_deref()) as u8
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_degrade_style(style: *const c_char, support: u8) -> *mut c_char {
+    abort_on_panic(|| {
+        let result = termcaps::degrade_style(&to_string(style), ColorSupport::from_u8(support));
+        // The result is made of parts of the input, which came from C and thus doesn't contain
+        // NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(result).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_title_format(
+    option: *const c_char,
+    configured: *const c_char,
+    width: u32,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let result = termcaps::title_format(&to_string(option), &to_string(configured), width);
+        // The result is either the input, which came from C, or one of our templates; neither
+        // contains NUL bytes. Thus, `unwrap` won't panic.
+        CString::new(result).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_preview_pane_fits(
+    total_width: u32,
+    total_height: u32,
+    percent: u32,
+    beside: bool,
+) -> bool {
+    abort_on_panic(|| termcaps::preview_pane_fits(total_width, total_height, percent, beside))
+}
@@ -0,0 +1,96 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::unknownoptions::{self, UnknownOptions};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Stores the value of the first `tolerate-unknown-options` in `contents` into `tolerate`.
+/// Returns `false`, leaving `tolerate` alone, if there is none.
+#[no_mangle]
+pub unsafe extern "C" fn rs_unknown_options_prescan(
+    contents: *const c_char,
+    tolerate: *mut bool,
+) -> bool {
+    let contents = to_string(contents);
+    abort_on_panic(move || {
+        assert!(!tolerate.is_null());
+        match unknownoptions::prescan(&contents) {
+            Some(value) => {
+                *tolerate = value;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// `known` is a newline-separated list of the options that do exist.
+#[no_mangle]
+pub unsafe extern "C" fn rs_unknown_option_message(
+    name: *const c_char,
+    known: *const c_char,
+) -> *mut c_char {
+    let name = to_string(name);
+    let known = to_string(known);
+    abort_on_panic(move || {
+        let known = known
+            .split('\n')
+            .filter(|option| !option.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let message = unknownoptions::unknown_option_message(&name, &known);
+        // The message consists of a translated text and strings that came from C. Thus, it
+        // doesn't contain NUL bytes, and `unwrap` won't panic.
+        CString::new(message).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rs_unknown_options_new() -> *mut c_void {
+    abort_on_panic(|| Box::into_raw(Box::new(UnknownOptions::new())) as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_unknown_options_free(options: *mut c_void) {
+    abort_on_panic(|| {
+        if options.is_null() {
+            return;
+        }
+        drop(Box::from_raw(options as *mut UnknownOptions));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_unknown_options_record(
+    options: *mut c_void,
+    name: *const c_char,
+    location: *const c_char,
+) {
+    let name = to_string(name);
+    let location = to_string(location);
+    abort_on_panic(move || {
+        assert!(!options.is_null());
+        let options = &mut *(options as *mut UnknownOptions);
+        options.record(&name, &location);
+    })
+}
+
+/// Returns the warning about the skipped options, or a null pointer if none were skipped.
+#[no_mangle]
+pub unsafe extern "C" fn rs_unknown_options_warning(options: *mut c_void) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!options.is_null());
+        let options = &*(options as *const UnknownOptions);
+        match options.warning() {
+            // The warning consists of a translated text and strings that came from C. Thus, it
+            // doesn't contain NUL bytes, and `unwrap` won't panic.
+            Some(warning) => CString::new(warning).unwrap().into_raw(),
+            None => ptr::null_mut(),
+        }
+    })
+}
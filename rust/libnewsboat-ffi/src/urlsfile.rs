@@ -0,0 +1,242 @@
+use crate::abort_on_panic;
+use libc::{c_char, c_void};
+use libnewsboat::urlreader::{self, UrlEntry};
+use libnewsboat::urlsfile::{self, TagEditError};
+use std::ffi::{CStr, CString};
+use std::path::PathBuf;
+use std::ptr;
+
+unsafe fn to_string(input: *const c_char) -> String {
+    assert!(!input.is_null());
+    CStr::from_ptr(input).to_string_lossy().into_owned()
+}
+
+/// Turns the result of an edit into what C expects: the new contents, or a null pointer with
+/// `error` set to the message.
+unsafe fn to_c_result(
+    result: Result<String, TagEditError>,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    assert!(!error.is_null());
+    *error = ptr::null_mut();
+    match result {
+        // The contents are made of strings that came from C. Thus, they don't contain NUL bytes,
+        // and `unwrap` won't panic.
+        Ok(contents) => CString::new(contents).unwrap().into_raw(),
+        Err(e) => {
+            // The message is a translated text and strings that came from C. Thus, `unwrap`
+            // won't panic.
+            *error = CString::new(e.to_message()).unwrap().into_raw();
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the feeds defined in the urls file `contents`, which have to be freed with
+/// `rs_urls_file_entries_free`. On failure, returns a null pointer, sets `error` to the message
+/// and `error_line` to the line that has the error.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_parse(
+    contents: *const c_char,
+    error: *mut *mut c_char,
+    error_line: *mut usize,
+) -> *mut c_void {
+    let contents = to_string(contents);
+    abort_on_panic(move || {
+        assert!(!error.is_null());
+        assert!(!error_line.is_null());
+        *error = ptr::null_mut();
+        *error_line = 0;
+        match urlreader::parse_urls_file(&contents) {
+            Ok(entries) => Box::into_raw(Box::new(entries)) as *mut c_void,
+            Err(e) => {
+                // The message is a translated text and bits of `contents`, which came from C.
+                // Thus, `unwrap` won't panic.
+                *error = CString::new(e.to_message()).unwrap().into_raw();
+                *error_line = e.line();
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_entries_free(entries: *mut c_void) {
+    abort_on_panic(|| {
+        if entries.is_null() {
+            return;
+        }
+        drop(Box::from_raw(entries as *mut Vec<UrlEntry>));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_entries_count(entries: *mut c_void) -> usize {
+    abort_on_panic(|| {
+        assert!(!entries.is_null());
+        let entries = &*(entries as *const Vec<UrlEntry>);
+        entries.len()
+    })
+}
+
+/// Returns the URL of the feed at `index`, and sets `tags_count` to the number of its tags.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_entries_get(
+    entries: *mut c_void,
+    index: usize,
+    tags_count: *mut usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!entries.is_null());
+        assert!(!tags_count.is_null());
+        let entries = &*(entries as *const Vec<UrlEntry>);
+        let entry = &entries[index];
+        *tags_count = entry.tags.len();
+        // The URL came from C, and unescaping never turns anything into a NUL byte. Thus,
+        // `unwrap` won't panic.
+        CString::new(entry.url.as_str()).unwrap().into_raw()
+    })
+}
+
+/// Returns the tag at `tag_index` of the feed at `index`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_entries_get_tag(
+    entries: *mut c_void,
+    index: usize,
+    tag_index: usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        assert!(!entries.is_null());
+        let entries = &*(entries as *const Vec<UrlEntry>);
+        // Same as with the URL in `rs_urls_file_entries_get`
+        CString::new(entries[index].tags[tag_index].as_str())
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Returns `contents` with `tag` added to the feed `url`, or a null pointer and sets `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_add_tag(
+    contents: *const c_char,
+    url: *const c_char,
+    tag: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let contents = to_string(contents);
+    let url = to_string(url);
+    let tag = to_string(tag);
+    abort_on_panic(move || to_c_result(urlsfile::add_tag(&contents, &url, &tag), error))
+}
+
+/// Returns `contents` with `tag` removed from the feed `url`, or a null pointer and sets `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_remove_tag(
+    contents: *const c_char,
+    url: *const c_char,
+    tag: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let contents = to_string(contents);
+    let url = to_string(url);
+    let tag = to_string(tag);
+    abort_on_panic(move || to_c_result(urlsfile::remove_tag(&contents, &url, &tag), error))
+}
+
+/// Returns `contents` with `old` renamed to `new`, and sets `count` to the number of feeds that
+/// had `old`. On failure, returns a null pointer and sets `error`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_rename_tag(
+    contents: *const c_char,
+    old: *const c_char,
+    new: *const c_char,
+    count: *mut usize,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let contents = to_string(contents);
+    let old = to_string(old);
+    let new = to_string(new);
+    abort_on_panic(move || {
+        assert!(!count.is_null());
+        *count = 0;
+        let result = urlsfile::rename_tag(&contents, &old, &new).map(|(contents, renamed)| {
+            *count = renamed;
+            contents
+        });
+        to_c_result(result, error)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_feeds_with_tag(
+    contents: *const c_char,
+    tag: *const c_char,
+) -> usize {
+    let contents = to_string(contents);
+    let tag = to_string(tag);
+    abort_on_panic(move || urlsfile::feeds_with_tag(&contents, &tag))
+}
+
+/// Returns `contents` with a line for the feed `url` added at the end.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_append_url(
+    contents: *const c_char,
+    url: *const c_char,
+) -> *mut c_char {
+    let contents = to_string(contents);
+    let url = to_string(url);
+    abort_on_panic(move || {
+        let entry = UrlEntry {
+            url,
+            tags: Vec::new(),
+            query: false,
+        };
+        // The contents are made of strings that came from C. Thus, `unwrap` won't panic.
+        CString::new(urlreader::append_entry(&contents, &entry))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Returns `base` with `overlay` merged into it.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_merge_overlay(
+    base: *const c_char,
+    overlay: *const c_char,
+) -> *mut c_char {
+    let base = to_string(base);
+    let overlay = to_string(overlay);
+    abort_on_panic(move || {
+        // The result is made of lines of the inputs, which came from C. Thus, `unwrap` won't
+        // panic.
+        CString::new(urlsfile::merge_overlay(&base, &overlay))
+            .unwrap()
+            .into_raw()
+    })
+}
+
+/// Saves `contents` as the urls file at `path`, or into the overlay at `overlay` if that isn't
+/// a null pointer. Returns the error message, or a null pointer if the file was written.
+#[no_mangle]
+pub unsafe extern "C" fn rs_urls_file_save(
+    path: *const c_char,
+    overlay: *const c_char,
+    contents: *const c_char,
+) -> *mut c_char {
+    // We simply assume that all the paths are in UTF-8 -- hence to_string_lossy().
+    let path = PathBuf::from(to_string(path));
+    let overlay = if overlay.is_null() {
+        None
+    } else {
+        Some(PathBuf::from(to_string(overlay)))
+    };
+    let contents = to_string(contents);
+    abort_on_panic(
+        move || match urlsfile::save(&path, overlay.as_deref(), &contents) {
+            Ok(()) => ptr::null_mut(),
+            // The message is made of our own text, messages of the OS and paths that came from
+            // C. Thus, `unwrap` won't panic.
+            Err(e) => CString::new(e.to_message()).unwrap().into_raw(),
+        },
+    )
+}
@@ -73,11 +73,11 @@ pub unsafe extern "C" fn rs_resolve_tilde(path: *const c_char) -> *mut c_char {
         let result = result.to_string_lossy().into_owned();
 
         // `result` consists of:
-        // 1. a path to the home dir, which can't contain NUL bytes, since it has to be handled by
+        // 1. a path to a home dir, which can't contain NUL bytes, since it has to be handled by
         //    C APIs;
         // 2. a path delimiter, which is a slash and not a NUL byte;
-        // 3. the original input, `path`, which came here as a C string, so doesn't contain NUL
-        //    bytes.
+        // 3. (the rest of) the original input, `path`, which came here as a C string, so doesn't
+        //    contain NUL bytes.
         //
         // Thus, `unwrap` won't panic.
         let result = CString::new(result).unwrap();
@@ -141,6 +141,24 @@ pub unsafe extern "C" fn rs_absolute_url(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_absolute_url_without_fragment(
+    base_url: *const c_char,
+    link: *const c_char,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let rs_base_url = CStr::from_ptr(base_url);
+        let rs_base_url = rs_base_url.to_string_lossy();
+        let rs_link = CStr::from_ptr(link);
+        let rs_link = rs_link.to_string_lossy();
+
+        let ret = utils::absolute_url_without_fragment(&rs_base_url, &rs_link);
+        // Same as in `rs_absolute_url`: the result is made of parts of `rs_base_url` and
+        // `rs_link`, which contain no NUL, and a slash. Thus, `unwrap` won't panic.
+        CString::new(ret).unwrap().into_raw()
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_is_special_url(in_str: *const c_char) -> bool {
     abort_on_panic(|| {
@@ -361,7 +379,12 @@ pub extern "C" fn rs_getcwd() -> *mut c_char {
     use std::os::unix::ffi::OsStringExt;
     abort_on_panic(|| {
         let result = utils::getcwd().unwrap_or_else(|err| {
-            log!(Level::Warn, "Error getting current directory: {}", err);
+            log!(
+                "utils",
+                Level::Warn,
+                "Error getting current directory: {}",
+                err
+            );
             path::PathBuf::new()
         });
         // Panic here can't happen because:
@@ -516,6 +539,34 @@ pub unsafe extern "C" fn rs_remove_soft_hyphens(text: *const c_char) -> *mut c_c
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_strip_bidi_controls(text: *const c_char) -> *mut c_char {
+    abort_on_panic(|| {
+        let rs_text = CStr::from_ptr(text);
+        let rs_text = rs_text.to_string_lossy();
+        let result = utils::strip_bidi_controls(&rs_text);
+        // `result` is a subset of `text`, which is a C string, so it can't contain null bytes.
+        // Thus, `unwrap` won't panic.
+        CString::new(result).unwrap().into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_format_urlview_line(
+    number: usize,
+    url: *const c_char,
+    strip_bidi: bool,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        let rs_url = CStr::from_ptr(url);
+        let rs_url = rs_url.to_string_lossy();
+        let result = utils::format_urlview_line(number, &rs_url, strip_bidi);
+        // `result` consists of `url`, which is a C string, and of digits, spaces and escapes we
+        // add ourselves. None of those contain null bytes, so `unwrap` won't panic.
+        CString::new(result).unwrap().into_raw()
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_is_valid_podcast_type(mimetype: *const c_char) -> bool {
     abort_on_panic(|| {
@@ -648,6 +699,38 @@ pub unsafe extern "C" fn rs_strip_comments(line: *const c_char) -> *mut c_char {
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rs_unescape_quoted(input: *const c_char) -> *mut c_char {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        // This won't panic because all strings in Newsboat are in UTF-8
+        let input = input.to_str().expect("input contained invalid UTF-8");
+
+        let result = utils::unescape_quoted(input);
+
+        // `input` is a C string, so it doesn't contain null bytes, and escapes never turn into
+        // one. Therefore, `CString::new` always returns `Some`.
+        let result = CString::new(result).unwrap();
+        result.into_raw()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_escape_quoted(input: *const c_char) -> *mut c_char {
+    abort_on_panic(|| {
+        let input = CStr::from_ptr(input);
+        // This won't panic because all strings in Newsboat are in UTF-8
+        let input = input.to_str().expect("input contained invalid UTF-8");
+
+        let result = utils::escape_quoted(input);
+
+        // `result` contains what `input` contained, plus backslashes and letters. Since `input`
+        // is a C string, `CString::new` always returns `Some`.
+        let result = CString::new(result).unwrap();
+        result.into_raw()
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rs_extract_filter(line: *const c_char) -> FilterUrl {
     abort_on_panic(|| {
@@ -676,3 +759,51 @@ pub unsafe extern "C" fn rs_get_string(line: *const c_char) -> *mut c_char {
     let result = CString::from(line);
     result.into_raw()
 }
+
+unsafe fn atomic_write_error(result: Result<(), utils::AtomicWriteError>) -> *mut c_char {
+    match result {
+        Ok(()) => ptr::null_mut(),
+        Err(error) => {
+            // The message is made of our own text, messages of the OS and paths that came from
+            // C++ as C strings. Thus, `unwrap` won't panic.
+            CString::new(error.to_string()).unwrap().into_raw()
+        }
+    }
+}
+
+/// Replaces the contents of the file at `path` with `contents_len` bytes at `contents`. Returns
+/// the error message, or a null pointer if the file was written.
+#[no_mangle]
+pub unsafe extern "C" fn rs_atomic_write(
+    path: *const c_char,
+    contents: *const c_char,
+    contents_len: usize,
+    mode: u32,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        // We simply assume that all the paths are in UTF-8 -- hence to_string_lossy().
+        let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+        let contents = std::slice::from_raw_parts(contents as *const u8, contents_len);
+        atomic_write_error(utils::atomic_write(path, contents, mode))
+    })
+}
+
+/// Like `rs_atomic_write`, but leaves the file alone if `contents` isn't a valid urls file.
+#[no_mangle]
+pub unsafe extern "C" fn rs_atomic_write_urls_file(
+    path: *const c_char,
+    contents: *const c_char,
+    contents_len: usize,
+) -> *mut c_char {
+    abort_on_panic(|| {
+        // We simply assume that all the paths are in UTF-8 -- hence to_string_lossy().
+        let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+        let contents = std::slice::from_raw_parts(contents as *const u8, contents_len);
+        atomic_write_error(utils::atomic_write_validated(
+            path,
+            contents,
+            0o600,
+            utils::validate_urls_file,
+        ))
+    })
+}
@@ -0,0 +1,68 @@
+//! Helpers shared by the benchmarks: seeded fixture generation.
+//!
+//! Fixtures are generated from a seed, so every run measures the same input. The seed can be
+//! changed with the `NEWSBOAT_BENCH_SEED` environment variable. If `NEWSBOAT_BENCH_FIXTURES` is set
+//! to a directory, each benchmark also writes its fixture there, to be looked at or reused
+//! elsewhere (e.g. to feed the same config to the C++ parser).
+
+// Not every benchmark uses every helper.
+#![allow(dead_code)]
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_SEED: u64 = 0x6e65_7773_626f_6174;
+
+/// Returns a random number generator seeded with `NEWSBOAT_BENCH_SEED`, or with a fixed seed if
+/// that isn't set.
+pub fn rng() -> StdRng {
+    let seed = env::var("NEWSBOAT_BENCH_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(DEFAULT_SEED);
+    StdRng::seed_from_u64(seed)
+}
+
+/// Writes `contents` to `name` inside `NEWSBOAT_BENCH_FIXTURES`, if that is set.
+pub fn save_fixture(name: &str, contents: &str) {
+    if let Some(dir) = env::var_os("NEWSBOAT_BENCH_FIXTURES") {
+        let path = PathBuf::from(dir).join(name);
+        fs::write(&path, contents)
+            .unwrap_or_else(|e| panic!("couldn't write {}: {}", path.display(), e));
+        println!("wrote {}", path.display());
+    }
+}
+
+/// Picks one of `choices`.
+pub fn pick<'a, R: Rng>(rng: &mut R, choices: &[&'a str]) -> &'a str {
+    choices[rng.gen_range(0, choices.len())]
+}
+
+/// Returns `count` random words separated by spaces.
+pub fn words<R: Rng>(rng: &mut R, count: usize) -> String {
+    const WORDS: &[&str] = &[
+        "newsboat",
+        "feed",
+        "article",
+        "rust",
+        "release",
+        "podcast",
+        "update",
+        "security",
+        "kernel",
+        "weather",
+        "review",
+        "interview",
+        "tutorial",
+        "changelog",
+        "Überblick",
+        "日本語",
+    ];
+    (0..count)
+        .map(|_| pick(rng, WORDS))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,149 @@
+//! Evaluating filter expressions against 100000 articles, like a query feed over a big cache
+//! does.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libnewsboat::filterparser::{Expression, Operator, Value};
+use libnewsboat::matchable::Matchable;
+use libnewsboat::matcher::Matcher;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+const ARTICLES: usize = 100_000;
+
+/// The moment the articles' dates are counted back from, and relative dates are matched at.
+const NOW: i64 = 1_600_000_000;
+
+/// Filters of the kinds people put into query feeds and `ignore-article`.
+const FILTERS: &[&str] = &[
+    r#"unread = "yes""#,
+    r#"title =~ "(rust|kernel)" and unread = "yes""#,
+    r#"age between 0:7 or flags # "s""#,
+    r#"(author != "ak" and title !~ "^Re:") or feedtitle =~ "podcast""#,
+];
+
+struct Article {
+    attributes: BTreeMap<&'static str, String>,
+    timestamp: i64,
+}
+
+impl Matchable for Article {
+    fn attribute_value(&self, attr: &str) -> Option<String> {
+        self.attributes.get(attr).cloned()
+    }
+
+    fn date_timestamp(&self, attr: &str) -> Option<i64> {
+        if attr == "date" {
+            Some(self.timestamp)
+        } else {
+            None
+        }
+    }
+}
+
+fn generate_articles<R: Rng>(rng: &mut R) -> Vec<Article> {
+    (0..ARTICLES)
+        .map(|_| {
+            let mut attributes = BTreeMap::new();
+            let title_words = rng.gen_range(2, 9);
+            let title = common::words(rng, title_words);
+            let title = if rng.gen_range(0, 10) == 0 {
+                format!("Re: {}", title)
+            } else {
+                title
+            };
+            attributes.insert("title", title);
+            attributes.insert(
+                "author",
+                common::pick(rng, &["ak", "vrld", "Minoru", ""]).to_string(),
+            );
+            attributes.insert(
+                "unread",
+                common::pick(rng, &["yes", "no", "no"]).to_string(),
+            );
+            attributes.insert("age", rng.gen_range(0, 365).to_string());
+            attributes.insert(
+                "flags",
+                common::pick(rng, &["", "", "s", "ab", "sz"]).to_string(),
+            );
+            attributes.insert("feedtitle", common::words(rng, 2));
+            let timestamp = NOW - rng.gen_range(0, 365 * 24 * 60 * 60);
+            Article {
+                attributes,
+                timestamp,
+            }
+        })
+        .collect()
+}
+
+/// `date > "-30 days" and unread = "yes"`, put together without the parser.
+fn built_filter() -> Matcher {
+    Matcher::from_expression(Expression::And(
+        Box::new(Expression::comparison(
+            "date",
+            Operator::GreaterThan,
+            Value::Literal("-30 days".to_string()),
+        )),
+        Box::new(Expression::comparison(
+            "unread",
+            Operator::Equals,
+            Value::Literal("yes".to_string()),
+        )),
+    ))
+}
+
+fn filters(c: &mut Criterion) {
+    let articles = generate_articles(&mut common::rng());
+    common::save_fixture(
+        "articles",
+        &articles
+            .iter()
+            .map(|article| {
+                article
+                    .attributes
+                    .iter()
+                    .map(|(name, value)| format!("{}={:?}", name, value))
+                    .chain(std::iter::once(format!("date={}", article.timestamp)))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    let now = Utc.timestamp(NOW, 0);
+    let mut matchers: Vec<Matcher> = FILTERS
+        .iter()
+        .map(|filter| Matcher::parse(filter).expect("the filters are valid"))
+        .collect();
+    matchers.push(built_filter());
+
+    // A round takes up to a second, so take fewer samples than Criterion's default of 100
+    let mut group = c.benchmark_group("filter 100000 articles");
+    group.sample_size(10);
+    for matcher in &matchers {
+        group.bench_function(matcher.get_expression(), |b| {
+            b.iter(|| {
+                articles
+                    .iter()
+                    .filter(|article| matcher.matches_at(*article, now).unwrap_or(false))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+
+    c.bench_function("parse the filters", |b| {
+        b.iter(|| {
+            for filter in FILTERS {
+                black_box(Matcher::parse(black_box(filter)).expect("the filters are valid"));
+            }
+        })
+    });
+    c.bench_function("build a filter", |b| b.iter(built_filter));
+}
+
+criterion_group!(benches, filters);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+//! Rendering 10000 lines of the article list with `FmtStrFormatter`, using the default
+//! `articlelist-format` and `feedlist-format`, and a format with conditionals and padding.
+
+mod common;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libnewsboat::fmtstrformatter::FmtStrFormatter;
+use rand::Rng;
+
+const LINES: usize = 10_000;
+const WIDTH: u32 = 120;
+
+const FORMATS: &[(&str, &str)] = &[
+    ("articlelist-format", "%4i %f %D %6L  %?T?|%-17T|  &?%t"),
+    ("feedlist-format", "%4i %n %11u %t"),
+    ("padded", "%-4i %?u?%u unread&no unread? %>-%t %-30a %20D"),
+];
+
+/// Generates the values for one line: index, flags, date, length, feed title, article title,
+/// author and unread count.
+fn generate_line<R: Rng>(rng: &mut R, index: usize) -> FmtStrFormatter {
+    let mut formatter = FmtStrFormatter::new();
+    formatter.register_fmt('i', index.to_string());
+    formatter.register_fmt('f', common::pick(rng, &["N", " ", "U", "!"]).to_string());
+    formatter.register_fmt(
+        'D',
+        format!(
+            "{:3}-{:02}",
+            common::pick(rng, &["Jan", "Feb", "Mar"]),
+            rng.gen_range(1, 29)
+        ),
+    );
+    formatter.register_fmt('L', format!("{}K", rng.gen_range(1, 120)));
+    let feed_title_words = rng.gen_range(0, 4);
+    formatter.register_fmt('T', common::words(rng, feed_title_words));
+    let title_words = rng.gen_range(2, 16);
+    formatter.register_fmt('t', common::words(rng, title_words));
+    formatter.register_fmt('a', common::words(rng, 2));
+    formatter.register_fmt('n', common::pick(rng, &["N", " "]).to_string());
+    let unread: u32 = rng.gen_range(0, 3);
+    formatter.register_fmt(
+        'u',
+        if unread == 0 {
+            String::new()
+        } else {
+            unread.to_string()
+        },
+    );
+    formatter
+}
+
+fn format_strings(c: &mut Criterion) {
+    let mut rng = common::rng();
+    let lines: Vec<FmtStrFormatter> = (0..LINES).map(|i| generate_line(&mut rng, i)).collect();
+
+    for (name, format) in FORMATS {
+        let rendered: Vec<String> = lines
+            .iter()
+            .map(|line| line.do_format(format, WIDTH))
+            .collect();
+        common::save_fixture(&format!("{}.txt", name), &rendered.join("\n"));
+
+        c.bench_function(&format!("render 10000 lines of {}", name), |b| {
+            b.iter(|| {
+                for line in &lines {
+                    black_box(line.do_format(black_box(format), WIDTH));
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(benches, format_strings);
+criterion_main!(benches);
@@ -0,0 +1,106 @@
+//! Tokenizing a large config: 2000 lines, 500 of which are macros.
+//!
+//! The C++ `ConfigParser` strips the comments off each line with `utils::strip_comments`, and
+//! macros are tokenized with `keymap::tokenize_operation_sequence`; that's the Rust part of
+//! loading a config, and it's what is measured here. The serializer is measured too, since
+//! `edit-macro` turns every macro back into a commandline.
+
+mod common;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libnewsboat::keymap::{quote_operation_sequence, tokenize_operation_sequence};
+use libnewsboat::utils::strip_comments;
+use rand::Rng;
+
+const LINES: usize = 2000;
+const MACROS: usize = 500;
+
+fn operation<R: Rng>(rng: &mut R) -> String {
+    match rng.gen_range(0, 4) {
+        0 => format!(
+            "set browser \"{} %u\"",
+            common::pick(rng, &["firefox", "w3m", "lynx -dump"])
+        ),
+        1 => common::pick(
+            rng,
+            &["open", "next-unread", "toggle-article-read", "reload"],
+        )
+        .to_string(),
+        2 => format!(
+            "set articlelist-format \"%4i %f %D  %?T?|%-17T| &?%t {}\"",
+            rng.gen::<u16>()
+        ),
+        _ => format!(
+            "open-in-browser-and-mark-read \"{}\"",
+            common::words(rng, 3)
+        ),
+    }
+}
+
+/// Generates the config: macros with up to six operations, mixed with `set`, `bind-key` and
+/// `highlight` lines, some of them followed by comments.
+fn generate_config<R: Rng>(rng: &mut R) -> String {
+    let mut lines = Vec::with_capacity(LINES);
+    for i in 0..LINES {
+        let mut line = if i % (LINES / MACROS) == 0 {
+            let count = rng.gen_range(1, 7);
+            let operations: Vec<String> = (0..count).map(|_| operation(rng)).collect();
+            format!("macro {} {}", rng.gen_range(0, 10), operations.join(" ; "))
+        } else {
+            match rng.gen_range(0, 3) {
+                0 => format!("set reload-threads {}", rng.gen_range(1, 16)),
+                1 => format!(
+                    "bind-key {} {}",
+                    (b'a' + rng.gen_range(0, 26)) as char,
+                    common::pick(rng, &["next", "prev", "open", "quit"])
+                ),
+                _ => format!(
+                    "highlight article \"{}\" red default bold",
+                    common::words(rng, 2)
+                ),
+            }
+        };
+        if rng.gen_range(0, 5) == 0 {
+            line.push_str(&format!(" # {}", common::words(rng, 4)));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn operation_sequences(c: &mut Criterion) {
+    let config = generate_config(&mut common::rng());
+    common::save_fixture("config", &config);
+
+    let lines: Vec<&str> = config.lines().collect();
+    c.bench_function("tokenize 2000-line config", |b| {
+        b.iter(|| {
+            for line in &lines {
+                let line = strip_comments(black_box(line));
+                black_box(tokenize_operation_sequence(line).expect("generated lines are valid"));
+            }
+        })
+    });
+
+    let macros: Vec<Vec<Vec<String>>> = lines
+        .iter()
+        .filter(|line| line.starts_with("macro "))
+        .map(|line| {
+            let line = strip_comments(line);
+            // Skip "macro" and the key
+            let body = line.splitn(3, ' ').nth(2).unwrap_or("");
+            tokenize_operation_sequence(body).expect("generated macros are valid")
+        })
+        .collect();
+    assert_eq!(macros.len(), MACROS);
+    c.bench_function("serialize 500 macros", |b| {
+        b.iter(|| {
+            for operations in &macros {
+                black_box(quote_operation_sequence(black_box(operations)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, operation_sequences);
+criterion_main!(benches);
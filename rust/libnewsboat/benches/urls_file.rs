@@ -0,0 +1,74 @@
+//! Parsing and writing a urls file with 1000 feeds, in the mix of plain URLs, tags, titles,
+//! comments and query feeds that a long-lived file ends up with.
+
+mod common;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libnewsboat::urlreader::{parse_urls_file, serialize_urls_file};
+use rand::Rng;
+
+const ENTRIES: usize = 1000;
+
+fn url<R: Rng>(rng: &mut R, index: usize) -> String {
+    match rng.gen_range(0, 10) {
+        0 => format!(
+            "\"query:{}:unread = \\\"yes\\\" and tags # \\\"{}\\\"\"",
+            common::words(rng, 2),
+            common::pick(rng, &["news", "tech", "podcasts"])
+        ),
+        1 => format!("\"exec:~/bin/feed --page {}\"", index),
+        2 => format!("filter:~/bin/strip-ads:https://example.org/{}/rss", index),
+        _ => format!(
+            "https://{}.example.com/{}/feed.xml",
+            common::pick(rng, &["blog", "news", "www", "planet"]),
+            index
+        ),
+    }
+}
+
+/// Generates the file: one feed per line with up to four tags, some of them quoted, titles and
+/// hiding tags, and a comment or a blank line here and there.
+fn generate_urls_file<R: Rng>(rng: &mut R) -> String {
+    let mut lines = Vec::with_capacity(ENTRIES + ENTRIES / 10);
+    for index in 0..ENTRIES {
+        if rng.gen_range(0, 20) == 0 {
+            lines.push(format!("# {}", common::words(rng, 5)));
+        } else if rng.gen_range(0, 20) == 0 {
+            lines.push(String::new());
+        }
+
+        let mut line = url(rng, index);
+        for _ in 0..rng.gen_range(0, 5) {
+            let tag = match rng.gen_range(0, 6) {
+                0 => format!("\"~{}\"", common::words(rng, 3)),
+                1 => "!hidden".to_string(),
+                2 => format!("\"{}\"", common::words(rng, 2)),
+                _ => {
+                    common::pick(rng, &["news", "tech", "podcasts", "rust", "friends"]).to_string()
+                }
+            };
+            line.push(' ');
+            line.push_str(&tag);
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn urls_file(c: &mut Criterion) {
+    let content = generate_urls_file(&mut common::rng());
+    common::save_fixture("urls", &content);
+
+    c.bench_function("parse a urls file with 1000 feeds", |b| {
+        b.iter(|| parse_urls_file(black_box(&content)).expect("the generated file is valid"))
+    });
+
+    let entries = parse_urls_file(&content).expect("the generated file is valid");
+    assert_eq!(entries.len(), ENTRIES);
+    c.bench_function("serialize 1000 feeds", |b| {
+        b.iter(|| serialize_urls_file(black_box(&entries)))
+    });
+}
+
+criterion_group!(benches, urls_file);
+criterion_main!(benches);
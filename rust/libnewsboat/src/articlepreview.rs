@@ -0,0 +1,421 @@
+//! Preview of the selected article, shown in a pane next to (or below) the article list.
+//!
+//! The article is rendered into plain text by the C++ side; here we fit that text into the pane
+//! and make it safe to put into an STFL richtext list. Since rendering HTML is expensive, the
+//! results are cached, and requests are debounced so that scrolling through the list doesn't
+//! render every article along the way.
+
+use crate::contentcache::{ByteSize, CacheStats, ContentCache};
+use crate::utils;
+use std::time::{Duration, Instant};
+
+/// Dimensions of the preview pane, in terminal cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaneSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Wraps `text` at `size.width` and keeps as many lines as fit into `size.height`.
+///
+/// Lines are wrapped at word boundaries; words wider than the pane are split. The text is cut
+/// after the last complete line that fits, never in the middle of one. Control characters are
+/// dropped, tabs are turned into spaces, and `<` is escaped for STFL richtext.
+pub fn fit_to_pane(text: &str, size: PaneSize) -> Vec<String> {
+    let mut result = Vec::new();
+    if size.width == 0 || size.height == 0 {
+        return result;
+    }
+
+    for line in text.lines() {
+        let line = sanitize(line);
+        for wrapped in wrap_line(&line, size.width) {
+            if result.len() == size.height {
+                return result;
+            }
+            result.push(utils::quote_for_stfl(&wrapped));
+        }
+    }
+    result
+}
+
+fn sanitize(line: &str) -> String {
+    line.chars()
+        .filter_map(|c| match c {
+            '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// Splits `line` into pieces no wider than `width`. An empty line stays a single empty line.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    for word in line.split(' ') {
+        let mut word = word.to_string();
+        let mut word_width = utils::strwidth(&word);
+
+        let separator = if current.is_empty() { 0 } else { 1 };
+        if current_width + separator + word_width <= width {
+            if separator == 1 {
+                current.push(' ');
+            }
+            current.push_str(&word);
+            current_width += separator + word_width;
+            continue;
+        }
+
+        if !current.is_empty() {
+            result.push(std::mem::take(&mut current));
+        }
+
+        while word_width > width {
+            let head = utils::substr_with_width(&word, width);
+            if head.is_empty() {
+                // A single character is wider than the pane; there's nothing sensible to show
+                break;
+            }
+            word = word[head.len()..].to_string();
+            word_width = utils::strwidth(&word);
+            result.push(head);
+        }
+        current = word;
+        current_width = word_width;
+    }
+
+    if !current.is_empty() || result.is_empty() {
+        result.push(current);
+    }
+    result
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PreviewKey {
+    item: String,
+    size: PaneSize,
+}
+
+impl ByteSize for PreviewKey {
+    fn byte_size(&self) -> usize {
+        self.item.len()
+    }
+}
+
+impl PreviewKey {
+    fn new(item: &str, size: PaneSize) -> PreviewKey {
+        PreviewKey {
+            item: item.to_string(),
+            size,
+        }
+    }
+}
+
+/// Previews that were rendered recently.
+///
+/// The same article renders differently in panes of different sizes, so the size is part of the
+/// key. The cache is bounded both by the number of previews and by their total size; when it's
+/// full, the least recently used previews are evicted, except for the one on the screen.
+#[derive(Debug)]
+pub struct PreviewCache {
+    cache: ContentCache<PreviewKey, Vec<String>>,
+}
+
+impl PreviewCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> PreviewCache {
+        PreviewCache {
+            cache: ContentCache::new(max_entries, max_bytes),
+        }
+    }
+
+    pub fn set_limits(&self, max_entries: usize, max_bytes: usize) {
+        self.cache.set_limits(max_entries, max_bytes);
+    }
+
+    /// Returns the cached preview of the article identified by `item` (usually its GUID),
+    /// rendered for a pane of the given size.
+    pub fn get(&self, item: &str, size: PaneSize) -> Option<Vec<String>> {
+        self.cache.get(&PreviewKey::new(item, size))
+    }
+
+    /// Fits `text` into the pane, remembers the result, and returns it.
+    pub fn insert(&self, item: &str, size: PaneSize, text: &str) -> Vec<String> {
+        let lines = fit_to_pane(text, size);
+        self.cache
+            .insert(PreviewKey::new(item, size), lines.clone());
+        lines
+    }
+
+    /// Marks the preview that's on the screen, so that it's not evicted while the user reads it.
+    pub fn pin(&self, item: &str, size: PaneSize) {
+        self.cache.pin(Some(PreviewKey::new(item, size)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.stats().entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Forgets all previews, e.g. because the articles were reloaded.
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+}
+
+/// Source of the current time, so that tests can control it.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Delays preview rendering until the selection stops changing.
+///
+/// Every time the selection changes, the UI calls `request()`; the preview is rendered only
+/// once `poll()` returns the item, which happens after no new item was requested for `delay`.
+#[derive(Debug)]
+pub struct Debouncer<C: Clock = SystemClock> {
+    clock: C,
+    delay: Duration,
+    /// Item that was requested most recently, and when that happened.
+    pending: Option<(String, Instant)>,
+    /// Item that `poll()` returned most recently.
+    delivered: Option<String>,
+}
+
+impl<C: Clock> Debouncer<C> {
+    pub fn new(delay: Duration, clock: C) -> Debouncer<C> {
+        Debouncer {
+            clock,
+            delay,
+            pending: None,
+            delivered: None,
+        }
+    }
+
+    /// Asks for `item` to be previewed. Requesting the item that's already pending or shown does
+    /// nothing, so this can be called on every redraw.
+    pub fn request(&mut self, item: &str) {
+        let already_pending = matches!(&self.pending, Some((pending, _)) if pending == item);
+        let already_shown = self.pending.is_none() && self.delivered.as_deref() == Some(item);
+        if !already_pending && !already_shown {
+            self.pending = Some((item.to_string(), self.clock.now()));
+        }
+    }
+
+    /// Returns the item that should be rendered now, if any.
+    pub fn poll(&mut self) -> Option<String> {
+        let ready = match &self.pending {
+            Some((_, since)) => self.clock.now().duration_since(*since) >= self.delay,
+            None => false,
+        };
+        if !ready {
+            return None;
+        }
+        let (item, _) = self.pending.take()?;
+        self.delivered = Some(item.clone());
+        Some(item)
+    }
+
+    /// How long until the pending item becomes ready, or `None` if nothing is pending.
+    pub fn time_left(&self) -> Option<Duration> {
+        self.pending.as_ref().map(|(_, since)| {
+            let elapsed = self.clock.now().duration_since(*since);
+            self.delay.checked_sub(elapsed).unwrap_or_default()
+        })
+    }
+
+    /// Forgets what was shown, so that the next request is delivered even if it's for the same
+    /// item. Used when the preview has to be redrawn, e.g. after the pane was resized.
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.delivered = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<Instant>>);
+
+    impl FakeClock {
+        fn new() -> FakeClock {
+            FakeClock(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, ms: u64) {
+            self.0.set(self.0.get() + Duration::from_millis(ms));
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    fn size(width: usize, height: usize) -> PaneSize {
+        PaneSize { width, height }
+    }
+
+    #[test]
+    fn t_fit_to_pane_wraps_at_word_boundaries() {
+        let lines = fit_to_pane("the quick brown fox jumps", size(10, 10));
+        assert_eq!(lines, vec!["the quick", "brown fox", "jumps"]);
+    }
+
+    #[test]
+    fn t_fit_to_pane_truncates_at_a_wrapped_line_boundary() {
+        let text = "first paragraph\nthe quick brown fox jumps\nlast";
+        let lines = fit_to_pane(text, size(10, 3));
+        // The second paragraph wraps into three lines; only the first two of them fit
+        assert_eq!(lines, vec!["first", "paragraph", "the quick"]);
+        for line in &lines {
+            assert!(utils::strwidth(line) <= 10);
+        }
+    }
+
+    #[test]
+    fn t_fit_to_pane_splits_words_wider_than_the_pane() {
+        let lines = fit_to_pane("abcdefghij xy", size(4, 10));
+        assert_eq!(lines, vec!["abcd", "efgh", "ij", "xy"]);
+    }
+
+    #[test]
+    fn t_fit_to_pane_counts_wide_characters() {
+        let lines = fit_to_pane("日本語のテキスト", size(6, 10));
+        assert_eq!(lines, vec!["日本語", "のテキ", "スト"]);
+    }
+
+    #[test]
+    fn t_fit_to_pane_keeps_empty_lines() {
+        let lines = fit_to_pane("Title\n\nBody", size(10, 10));
+        assert_eq!(lines, vec!["Title", "", "Body"]);
+    }
+
+    #[test]
+    fn t_fit_to_pane_produces_stfl_safe_output() {
+        let lines = fit_to_pane("a <b> c\td\u{1b}[31m", size(80, 10));
+        assert_eq!(lines, vec!["a <>b> c d[31m"]);
+    }
+
+    #[test]
+    fn t_fit_to_pane_with_an_empty_pane() {
+        assert!(fit_to_pane("text", size(0, 10)).is_empty());
+        assert!(fit_to_pane("text", size(10, 0)).is_empty());
+    }
+
+    #[test]
+    fn t_cache_is_keyed_by_item_and_pane_size() {
+        let cache = PreviewCache::new(10, 1000);
+        cache.insert("guid1", size(10, 2), "the quick brown fox");
+
+        assert_eq!(
+            cache.get("guid1", size(10, 2)),
+            Some(vec!["the quick".to_string(), "brown fox".to_string()])
+        );
+        assert_eq!(cache.get("guid1", size(20, 2)), None);
+        assert_eq!(cache.get("guid1", size(10, 3)), None);
+        assert_eq!(cache.get("guid2", size(10, 2)), None);
+
+        cache.insert("guid1", size(20, 2), "the quick brown fox");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            cache.get("guid1", size(20, 2)),
+            Some(vec!["the quick brown fox".to_string()])
+        );
+    }
+
+    #[test]
+    fn t_cache_evicts_the_least_recently_used_entry() {
+        let cache = PreviewCache::new(2, 1000);
+        cache.insert("a", size(10, 1), "a");
+        cache.insert("b", size(10, 1), "b");
+        // Re-inserting doesn't take more space, but makes "a" the most recently used entry
+        cache.insert("a", size(10, 1), "a");
+        assert_eq!(cache.len(), 2);
+
+        cache.insert("c", size(10, 1), "c");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b", size(10, 1)), None);
+        assert!(cache.get("a", size(10, 1)).is_some());
+        assert!(cache.get("c", size(10, 1)).is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn t_cache_keeps_the_pinned_preview() {
+        let cache = PreviewCache::new(1, 1000);
+        cache.pin("shown", size(10, 1));
+        cache.insert("shown", size(10, 1), "text");
+        cache.insert("other", size(10, 1), "text");
+        assert!(cache.get("shown", size(10, 1)).is_some());
+        assert_eq!(cache.get("other", size(10, 1)), None);
+    }
+
+    #[test]
+    fn t_debouncer_waits_until_selection_settles() {
+        let clock = FakeClock::new();
+        let mut debouncer = Debouncer::new(Duration::from_millis(100), clock.clone());
+
+        // User holds `j`: a new item every 30ms
+        for i in 0..50 {
+            debouncer.request(&format!("item{}", i));
+            assert_eq!(debouncer.poll(), None);
+            clock.advance(30);
+        }
+
+        assert_eq!(debouncer.time_left(), Some(Duration::from_millis(70)));
+        clock.advance(70);
+        assert_eq!(debouncer.poll(), Some("item49".to_string()));
+        assert_eq!(debouncer.poll(), None);
+        assert_eq!(debouncer.time_left(), None);
+    }
+
+    #[test]
+    fn t_debouncer_ignores_repeated_requests() {
+        let clock = FakeClock::new();
+        let mut debouncer = Debouncer::new(Duration::from_millis(100), clock.clone());
+
+        debouncer.request("item");
+        clock.advance(60);
+        // Redraws request the same item again; that doesn't restart the timer
+        debouncer.request("item");
+        clock.advance(40);
+        assert_eq!(debouncer.poll(), Some("item".to_string()));
+
+        // Once shown, the item isn't delivered again...
+        debouncer.request("item");
+        clock.advance(100);
+        assert_eq!(debouncer.poll(), None);
+
+        // ...unless the debouncer is reset
+        debouncer.reset();
+        debouncer.request("item");
+        clock.advance(100);
+        assert_eq!(debouncer.poll(), Some("item".to_string()));
+    }
+}
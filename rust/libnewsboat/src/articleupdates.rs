@@ -0,0 +1,137 @@
+//! Decides what happens when a feed brings an article that is already in the cache.
+//!
+//! Publishers sometimes edit articles after publishing them, keeping the GUID but changing the
+//! title or the content. Such an article replaces the stored one and gets marked as "updated";
+//! whether it becomes unread again is up to the user. Changes that only touch whitespace (e.g. the
+//! feed was re-indented) don't count as edits.
+
+/// Hash of a text that doesn't change if only insignificant whitespace changes: runs of whitespace
+/// are treated as a single space, and leading and trailing whitespace is ignored.
+///
+/// The hash is a 64-bit FNV-1a, so unlike `std::collections::hash_map::DefaultHasher`, it's the
+/// same on every platform and in every version of Rust.
+pub fn content_hash(text: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+    for (index, word) in text.split_whitespace().enumerate() {
+        if index > 0 {
+            feed(b" ");
+        }
+        feed(word.as_bytes());
+    }
+    hash
+}
+
+/// What to do with an article that is already stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateDecision {
+    /// Neither the title nor the content changed in a significant way.
+    Unchanged = 0,
+
+    /// The article was edited; its read state stays as it is.
+    Updated = 1,
+
+    /// The article was edited, and should become unread.
+    UpdatedUnread = 2,
+}
+
+/// Compares the stored version of an article with the one that was just fetched.
+///
+/// `mark_unread` is true if edited articles should become unread again (`mark-updated-unread`,
+/// or `reset-unread-on-update` for this feed).
+pub fn decide(
+    stored_title: &str,
+    stored_content: &str,
+    new_title: &str,
+    new_content: &str,
+    mark_unread: bool,
+) -> UpdateDecision {
+    let unchanged = content_hash(stored_title) == content_hash(new_title)
+        && content_hash(stored_content) == content_hash(new_content);
+    if unchanged {
+        UpdateDecision::Unchanged
+    } else if mark_unread {
+        UpdateDecision::UpdatedUnread
+    } else {
+        UpdateDecision::Updated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_content_hash_ignores_insignificant_whitespace() {
+        let original = "<p>Hello, world!</p>\n<p>Second paragraph</p>";
+        assert_eq!(
+            content_hash(original),
+            content_hash("  <p>Hello,   world!</p>\r\n\t<p>Second\nparagraph</p>\n")
+        );
+        assert_eq!(content_hash(""), content_hash(" \n\t "));
+    }
+
+    #[test]
+    fn t_content_hash_differs_for_different_texts() {
+        assert_ne!(content_hash("Hello, world!"), content_hash("Hello, World!"));
+        assert_ne!(content_hash("ab"), content_hash("a b"));
+        assert_ne!(content_hash(""), content_hash("a"));
+    }
+
+    #[test]
+    fn t_content_hash_is_stable() {
+        // Values from the FNV reference implementation
+        assert_eq!(content_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn t_edits_to_title_or_content_are_detected() {
+        assert_eq!(
+            decide("Title", "Content", "Title", "Content", false),
+            UpdateDecision::Unchanged
+        );
+        assert_eq!(
+            decide("Title", "Content", "Title", "Corrected content", false),
+            UpdateDecision::Updated
+        );
+        assert_eq!(
+            decide("Title", "Content", "Corrected title", "Content", false),
+            UpdateDecision::Updated
+        );
+    }
+
+    #[test]
+    fn t_edited_articles_only_become_unread_if_asked_to() {
+        assert_eq!(
+            decide("Title", "Content", "Title", "New content", true),
+            UpdateDecision::UpdatedUnread
+        );
+        assert_eq!(
+            decide("Title", "Content", "Title", "Content", true),
+            UpdateDecision::Unchanged
+        );
+    }
+
+    #[test]
+    fn t_whitespace_only_changes_are_not_edits() {
+        assert_eq!(
+            decide(
+                "Title",
+                "<p>Some text</p>",
+                " Title ",
+                "\n  <p>Some\ttext</p>\n",
+                true
+            ),
+            UpdateDecision::Unchanged
+        );
+    }
+}
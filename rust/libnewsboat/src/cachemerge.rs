@@ -0,0 +1,441 @@
+//! Decides how articles from another cache file are merged into ours (`--merge-cache`).
+//!
+//! This is meant for consolidating the caches of two machines. An article from the other cache is
+//! looked up in ours by its GUID; failing that, by its link; and failing that, by a hash of its
+//! title and content. The last two only match articles of the same feed. Articles that we don't
+//! have are copied over; for the ones we have, flags and notes are combined, and if one cache has
+//! the article read and the other doesn't, the user's preference decides.
+
+use crate::articleupdates::content_hash;
+use std::collections::HashMap;
+
+/// Which read state wins if the caches disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadPreference {
+    /// An article is read if it's read in either cache.
+    Read,
+    /// An article is unread if it's unread in either cache.
+    Unread,
+}
+
+impl Default for ReadPreference {
+    fn default() -> ReadPreference {
+        ReadPreference::Read
+    }
+}
+
+impl ReadPreference {
+    pub fn parse(value: &str) -> Option<ReadPreference> {
+        match value {
+            "read" => Some(ReadPreference::Read),
+            "unread" => Some(ReadPreference::Unread),
+            _ => None,
+        }
+    }
+}
+
+/// Parts of an article that identify it, and its state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Article {
+    pub guid: String,
+    pub feedurl: String,
+    pub link: String,
+    pub title: String,
+    pub content: String,
+    pub unread: bool,
+    pub flags: String,
+    /// The user's note on the article, or an empty string.
+    pub note: String,
+}
+
+/// What to do with an article from the other cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// We already have the article, in the same state.
+    Keep,
+
+    /// We don't have the article; copy it.
+    Add,
+
+    /// We have the article under `guid`, and its state should change.
+    Update {
+        guid: String,
+        unread: bool,
+        flags: String,
+        note: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Articles copied from the other cache.
+    pub added: usize,
+
+    /// Articles whose read state, flags or note changed.
+    pub merged: usize,
+
+    /// Articles that are read in one cache but unread in the other.
+    pub conflicts: usize,
+}
+
+#[derive(Debug)]
+struct Existing {
+    guid: String,
+    unread: bool,
+    flags: String,
+    note: String,
+}
+
+type HashKey = (String, u64, u64);
+
+#[derive(Debug, Default)]
+pub struct CacheMerge {
+    preference: ReadPreference,
+    articles: Vec<Existing>,
+    by_guid: HashMap<String, usize>,
+    by_link: HashMap<(String, String), usize>,
+    by_hash: HashMap<HashKey, usize>,
+    summary: Summary,
+}
+
+/// Flags that are set in either `a` or `b`, sorted.
+pub fn union_flags(a: &str, b: &str) -> String {
+    let mut flags = a
+        .chars()
+        .chain(b.chars())
+        .filter(char::is_ascii_alphabetic)
+        .collect::<Vec<_>>();
+    flags.sort_unstable();
+    flags.dedup();
+    flags.into_iter().collect()
+}
+
+/// Both notes, unless one of them already contains the other.
+pub fn combine_notes(ours: &str, theirs: &str) -> String {
+    if ours.contains(theirs) {
+        ours.to_string()
+    } else if theirs.contains(ours) {
+        theirs.to_string()
+    } else {
+        format!("{}; {}", ours, theirs)
+    }
+}
+
+fn hash_key(article: &Article) -> Option<HashKey> {
+    if article.title.trim().is_empty() && article.content.trim().is_empty() {
+        return None;
+    }
+    Some((
+        article.feedurl.clone(),
+        content_hash(&article.title),
+        content_hash(&article.content),
+    ))
+}
+
+impl CacheMerge {
+    pub fn new(preference: ReadPreference) -> CacheMerge {
+        CacheMerge {
+            preference,
+            ..CacheMerge::default()
+        }
+    }
+
+    /// Registers an article that is in our cache.
+    pub fn add_existing(&mut self, article: &Article) {
+        let index = self.articles.len();
+        self.articles.push(Existing {
+            guid: article.guid.clone(),
+            unread: article.unread,
+            flags: article.flags.clone(),
+            note: article.note.clone(),
+        });
+        // If our cache has duplicates already, the first one wins
+        self.by_guid.entry(article.guid.clone()).or_insert(index);
+        if !article.link.is_empty() {
+            self.by_link
+                .entry((article.feedurl.clone(), article.link.clone()))
+                .or_insert(index);
+        }
+        if let Some(key) = hash_key(article) {
+            self.by_hash.entry(key).or_insert(index);
+        }
+    }
+
+    fn find(&self, article: &Article) -> Option<usize> {
+        if let Some(index) = self.by_guid.get(&article.guid) {
+            return Some(*index);
+        }
+        if !article.link.is_empty() {
+            let key = (article.feedurl.clone(), article.link.clone());
+            if let Some(index) = self.by_link.get(&key) {
+                return Some(*index);
+            }
+        }
+        hash_key(article).and_then(|key| self.by_hash.get(&key).copied())
+    }
+
+    /// Decides what to do with an article from the other cache.
+    ///
+    /// The decision is taken into account for the articles that come after, so an article that
+    /// appears in the other cache twice is only added once.
+    pub fn decide(&mut self, article: &Article) -> Decision {
+        let index = match self.find(article) {
+            Some(index) => index,
+            None => {
+                self.add_existing(article);
+                self.summary.added += 1;
+                return Decision::Add;
+            }
+        };
+
+        let existing = &mut self.articles[index];
+        if existing.unread != article.unread {
+            self.summary.conflicts += 1;
+        }
+        let unread = match self.preference {
+            ReadPreference::Read => existing.unread && article.unread,
+            ReadPreference::Unread => existing.unread || article.unread,
+        };
+        let flags = union_flags(&existing.flags, &article.flags);
+        let note = combine_notes(&existing.note, &article.note);
+        if unread == existing.unread
+            && flags == union_flags(&existing.flags, "")
+            && note == existing.note
+        {
+            return Decision::Keep;
+        }
+
+        existing.unread = unread;
+        existing.flags = flags.clone();
+        existing.note = note.clone();
+        self.summary.merged += 1;
+        Decision::Update {
+            guid: existing.guid.clone(),
+            unread,
+            flags,
+            note,
+        }
+    }
+
+    pub fn summary(&self) -> Summary {
+        self.summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = "https://example.com/feed.xml";
+
+    fn article(guid: &str, link: &str, unread: bool, flags: &str) -> Article {
+        Article {
+            guid: guid.to_string(),
+            feedurl: FEED.to_string(),
+            link: link.to_string(),
+            title: format!("Title of {}", guid),
+            content: format!("<p>Content of {}</p>", guid),
+            unread,
+            flags: flags.to_string(),
+            note: String::new(),
+        }
+    }
+
+    fn merge_with(preference: ReadPreference, ours: &[Article]) -> CacheMerge {
+        let mut merge = CacheMerge::new(preference);
+        for article in ours {
+            merge.add_existing(article);
+        }
+        merge
+    }
+
+    #[test]
+    fn t_missing_articles_are_added_once() {
+        let mut merge = merge_with(ReadPreference::Read, &[article("a", "", true, "")]);
+        let new = article("b", "https://example.com/b", true, "");
+        assert_eq!(merge.decide(&new), Decision::Add);
+        assert_eq!(merge.decide(&new), Decision::Keep);
+        assert_eq!(
+            merge.summary(),
+            Summary {
+                added: 1,
+                merged: 0,
+                conflicts: 0
+            }
+        );
+    }
+
+    #[test]
+    fn t_articles_are_matched_by_guid_then_link_then_hash() {
+        let ours = article("ours", "https://example.com/1", true, "");
+        let mut merge = merge_with(ReadPreference::Read, std::slice::from_ref(&ours));
+
+        let mut same_link = article("theirs", "https://example.com/1", false, "");
+        same_link.title = "Another title".to_string();
+        assert_eq!(
+            merge.decide(&same_link),
+            Decision::Update {
+                guid: "ours".to_string(),
+                unread: false,
+                flags: String::new(),
+                note: String::new()
+            }
+        );
+
+        let mut same_text = ours.clone();
+        same_text.guid = "copy".to_string();
+        same_text.link = String::new();
+        same_text.content = format!("\n  {}  ", ours.content);
+        same_text.flags = "s".to_string();
+        assert!(matches!(
+            merge.decide(&same_text),
+            Decision::Update { ref guid, .. } if guid == "ours"
+        ));
+
+        // Links and texts only match within the same feed
+        let mut other_feed = ours;
+        other_feed.guid = "elsewhere".to_string();
+        other_feed.feedurl = "https://example.org/feed.xml".to_string();
+        assert_eq!(merge.decide(&other_feed), Decision::Add);
+    }
+
+    #[test]
+    fn t_prefer_read_marks_articles_read_in_either_cache_as_read() {
+        let mut merge = merge_with(
+            ReadPreference::Read,
+            &[article("a", "", true, ""), article("b", "", false, "")],
+        );
+        assert_eq!(
+            merge.decide(&article("a", "", false, "")),
+            Decision::Update {
+                guid: "a".to_string(),
+                unread: false,
+                flags: String::new(),
+                note: String::new()
+            }
+        );
+        assert_eq!(merge.decide(&article("b", "", true, "")), Decision::Keep);
+        assert_eq!(
+            merge.summary(),
+            Summary {
+                added: 0,
+                merged: 1,
+                conflicts: 2
+            }
+        );
+    }
+
+    #[test]
+    fn t_prefer_unread_marks_articles_unread_in_either_cache_as_unread() {
+        let mut merge = merge_with(
+            ReadPreference::Unread,
+            &[article("a", "", true, ""), article("b", "", false, "")],
+        );
+        assert_eq!(merge.decide(&article("a", "", false, "")), Decision::Keep);
+        assert_eq!(
+            merge.decide(&article("b", "", true, "")),
+            Decision::Update {
+                guid: "b".to_string(),
+                unread: true,
+                flags: String::new(),
+                note: String::new()
+            }
+        );
+    }
+
+    #[test]
+    fn t_flags_are_combined() {
+        assert_eq!(union_flags("sa", "bs"), "abs");
+        assert_eq!(union_flags("", ""), "");
+        assert_eq!(union_flags("x1!", "X"), "Xx");
+
+        let mut merge = merge_with(ReadPreference::Read, &[article("a", "", false, "ab")]);
+        assert_eq!(
+            merge.decide(&article("a", "", false, "bc")),
+            Decision::Update {
+                guid: "a".to_string(),
+                unread: false,
+                flags: "abc".to_string(),
+                note: String::new()
+            }
+        );
+        assert_eq!(merge.decide(&article("a", "", false, "c")), Decision::Keep);
+    }
+
+    #[test]
+    fn t_notes_are_combined() {
+        assert_eq!(combine_notes("", ""), "");
+        assert_eq!(combine_notes("report", ""), "report");
+        assert_eq!(combine_notes("", "report"), "report");
+        assert_eq!(combine_notes("Q3 report", "report"), "Q3 report");
+        assert_eq!(combine_notes("report", "Q3 report"), "Q3 report");
+        assert_eq!(combine_notes("report", "todo"), "report; todo");
+
+        let mut noted = article("a", "", false, "");
+        noted.note = "todo".to_string();
+        let mut merge = merge_with(ReadPreference::Read, &[article("a", "", false, "")]);
+        assert_eq!(
+            merge.decide(&noted),
+            Decision::Update {
+                guid: "a".to_string(),
+                unread: false,
+                flags: String::new(),
+                note: "todo".to_string()
+            }
+        );
+        assert_eq!(merge.decide(&noted), Decision::Keep);
+        assert_eq!(merge.decide(&article("a", "", false, "")), Decision::Keep);
+    }
+
+    #[test]
+    fn t_second_merge_changes_nothing() {
+        let ours = vec![article("a", "", true, "s"), article("b", "", false, "")];
+        let mut theirs = vec![
+            article("a", "", false, "f"),
+            article("b", "", true, "x"),
+            article("c", "", true, ""),
+        ];
+        theirs[1].note = "referenced in Q3 report".to_string();
+
+        // Apply the first merge to "our" articles, the way the cache would
+        let mut merge = merge_with(ReadPreference::Read, &ours);
+        let mut merged = ours;
+        for article in &theirs {
+            match merge.decide(article) {
+                Decision::Add => merged.push(article.clone()),
+                Decision::Update {
+                    guid,
+                    unread,
+                    flags,
+                    note,
+                } => {
+                    let ours = merged.iter_mut().find(|a| a.guid == guid).unwrap();
+                    ours.unread = unread;
+                    ours.flags = flags;
+                    ours.note = note;
+                }
+                Decision::Keep => {}
+            }
+        }
+        assert_eq!(merge.summary().added, 1);
+        assert_eq!(merge.summary().merged, 2);
+
+        let mut merge = merge_with(ReadPreference::Read, &merged);
+        for article in &theirs {
+            assert_eq!(merge.decide(article), Decision::Keep);
+        }
+        assert_eq!(merge.summary().added, 0);
+        assert_eq!(merge.summary().merged, 0);
+    }
+
+    #[test]
+    fn t_read_preference_is_parsed() {
+        assert_eq!(ReadPreference::parse("read"), Some(ReadPreference::Read));
+        assert_eq!(
+            ReadPreference::parse("unread"),
+            Some(ReadPreference::Unread)
+        );
+        assert_eq!(ReadPreference::parse("both"), None);
+        assert_eq!(ReadPreference::default(), ReadPreference::Read);
+    }
+}
@@ -0,0 +1,297 @@
+//! Bookkeeping for migrations of the cache's schema.
+//!
+//! Most migrations only add a table or a column, which SQLite does in an instant. Those that have
+//! to rewrite every article take minutes on a big cache, so they run in chunks of rows instead:
+//! each chunk is committed in its own transaction, along with a checkpoint. A migration that was
+//! interrupted picks up after the last checkpoint, and since the chunks are written so that doing
+//! one twice is harmless, it doesn't matter if that checkpoint is a bit behind. `MigrationPlan`
+//! hands out the chunks and tells how far along the migrations are.
+//!
+//! A migrated cache also records the version of newsboat that migrated it. Older versions may not
+//! know what the migration changed, so `check_reader_version()` keeps them from opening it.
+
+use crate::utils;
+use gettextrs::gettext;
+use std::cmp;
+use std::fmt;
+use strprintf::fmt;
+
+/// Rows that a migration rewrites in a single transaction.
+pub const CHUNK_ROWS: u64 = 5000;
+
+/// Version of newsboat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Version {
+    /// The version of this binary.
+    pub fn current() -> Version {
+        // Cargo won't even start compilation if it couldn't parse the version. Thus, `unwrap`
+        // won't panic.
+        Version {
+            major: utils::newsboat_major_version(),
+            minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap(),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Checks that `reader` may open a cache that was last migrated by `min_reader`. Returns the
+/// message to show the user otherwise.
+pub fn check_reader_version(min_reader: Version, reader: Version) -> Result<(), String> {
+    if reader >= min_reader {
+        return Ok(());
+    }
+    Err(fmt!(
+        &gettext(
+            "The cache was migrated by newsboat %s, and can't be opened by older versions. \
+             This is newsboat %s; please upgrade it, or point `cache-file' to another file."
+        ),
+        &min_reader.to_string(),
+        &reader.to_string()
+    ))
+}
+
+/// Rows with IDs greater than `after`, and at most `up_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk {
+    pub after: u64,
+    pub up_to: u64,
+}
+
+#[derive(Debug)]
+struct Migration {
+    /// Highest row ID in the table, which is what the size of the migration is estimated from.
+    last_row: u64,
+    /// Rows up to this one are migrated.
+    done_up_to: u64,
+}
+
+#[derive(Debug)]
+pub struct MigrationPlan {
+    migrations: Vec<Migration>,
+    chunk_rows: u64,
+    reported_percent: Option<u32>,
+}
+
+impl MigrationPlan {
+    pub fn new(chunk_rows: u64) -> MigrationPlan {
+        MigrationPlan {
+            migrations: Vec::new(),
+            chunk_rows: cmp::max(chunk_rows, 1),
+            reported_percent: None,
+        }
+    }
+
+    /// Adds a migration of the rows with IDs up to `last_row`, of which those up to `done_up_to`
+    /// were migrated before it was interrupted. Returns the index of the migration.
+    pub fn add(&mut self, last_row: u64, done_up_to: u64) -> usize {
+        self.migrations.push(Migration {
+            last_row,
+            done_up_to: cmp::min(done_up_to, last_row),
+        });
+        self.migrations.len() - 1
+    }
+
+    /// The next chunk of the migration at `index`, or `None` if it's done.
+    pub fn next_chunk(&self, index: usize) -> Option<Chunk> {
+        let migration = self.migrations.get(index)?;
+        if migration.done_up_to >= migration.last_row {
+            return None;
+        }
+        Some(Chunk {
+            after: migration.done_up_to,
+            up_to: cmp::min(
+                migration.done_up_to.saturating_add(self.chunk_rows),
+                migration.last_row,
+            ),
+        })
+    }
+
+    /// Records that `chunk` of the migration at `index` was committed.
+    pub fn chunk_done(&mut self, index: usize, chunk: Chunk) {
+        if let Some(migration) = self.migrations.get_mut(index) {
+            migration.done_up_to = cmp::max(
+                migration.done_up_to,
+                cmp::min(chunk.up_to, migration.last_row),
+            );
+        }
+    }
+
+    /// How much of all the migrations is done, from 0 to 100.
+    pub fn percent(&self) -> u32 {
+        let total: u64 = self.migrations.iter().map(|m| m.last_row).sum();
+        if total == 0 {
+            return 100;
+        }
+        let done: u64 = self.migrations.iter().map(|m| m.done_up_to).sum();
+        (u128::from(done) * 100 / u128::from(total)) as u32
+    }
+
+    /// Internationalized progress message, like "migrating cache: 42%", if the percentage
+    /// changed since the last one.
+    pub fn progress(&mut self) -> Option<String> {
+        let percent = self.percent();
+        if self.reported_percent == Some(percent) {
+            return None;
+        }
+        self.reported_percent = Some(percent);
+        // `fmt!` drops an escaped percent sign that comes after the last value, so the sign is
+        // put in with the number instead.
+        Some(fmt!(
+            &gettext("migrating cache: %s"),
+            &format!("{}%", percent)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(major: u32, minor: u32) -> Version {
+        Version { major, minor }
+    }
+
+    /// A table of `rows` rows whose value is `None` until they're migrated to `Some(id * 2)`.
+    fn generated_table(rows: u64) -> Vec<Option<u64>> {
+        vec![None; rows as usize]
+    }
+
+    /// Migrates the rows of `chunk`; like the statements that do it in SQL, this only acts on rows
+    /// that aren't migrated yet, so it can be done twice.
+    fn migrate_chunk(table: &mut [Option<u64>], chunk: Chunk) {
+        for id in chunk.after + 1..=chunk.up_to {
+            let row = &mut table[(id - 1) as usize];
+            if row.is_none() {
+                *row = Some(id * 2);
+            }
+        }
+    }
+
+    /// Runs the migration until it's done or `max_chunks` were committed. Returns the checkpoint.
+    fn run(table: &mut [Option<u64>], checkpoint: u64, max_chunks: usize) -> u64 {
+        let mut plan = MigrationPlan::new(100);
+        let index = plan.add(table.len() as u64, checkpoint);
+        let mut checkpoint = checkpoint;
+        for _ in 0..max_chunks {
+            let chunk = match plan.next_chunk(index) {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            migrate_chunk(table, chunk);
+            plan.chunk_done(index, chunk);
+            checkpoint = chunk.up_to;
+        }
+        checkpoint
+    }
+
+    #[test]
+    fn t_chunks_cover_all_rows_once() {
+        let mut plan = MigrationPlan::new(1000);
+        let index = plan.add(2500, 0);
+        let mut chunks = Vec::new();
+        while let Some(chunk) = plan.next_chunk(index) {
+            chunks.push(chunk);
+            plan.chunk_done(index, chunk);
+        }
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk {
+                    after: 0,
+                    up_to: 1000
+                },
+                Chunk {
+                    after: 1000,
+                    up_to: 2000
+                },
+                Chunk {
+                    after: 2000,
+                    up_to: 2500
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn t_interrupted_migration_resumes_after_the_checkpoint() {
+        let mut uninterrupted = generated_table(12_345);
+        run(&mut uninterrupted, 0, usize::MAX);
+
+        let mut interrupted = generated_table(12_345);
+        let checkpoint = run(&mut interrupted, 0, 42);
+        assert_eq!(checkpoint, 4200);
+        assert_eq!(interrupted[4200], None);
+
+        // The checkpoint of the last chunk didn't make it, so that chunk is done again
+        let checkpoint = run(&mut interrupted, checkpoint - 100, usize::MAX);
+        assert_eq!(checkpoint, 12_345);
+        assert_eq!(interrupted, uninterrupted);
+        assert!(interrupted.iter().all(|row| row.is_some()));
+    }
+
+    #[test]
+    fn t_migration_that_is_done_has_no_chunks() {
+        let mut plan = MigrationPlan::new(100);
+        let done = plan.add(500, 500);
+        let empty = plan.add(0, 0);
+        let overshot = plan.add(10, 20);
+        assert_eq!(plan.next_chunk(done), None);
+        assert_eq!(plan.next_chunk(empty), None);
+        assert_eq!(plan.next_chunk(overshot), None);
+        assert_eq!(plan.next_chunk(42), None);
+        assert_eq!(plan.percent(), 100);
+    }
+
+    #[test]
+    fn t_progress_is_reported_whenever_the_percentage_changes() {
+        let mut plan = MigrationPlan::new(100);
+        let first = plan.add(1000, 0);
+        let second = plan.add(3000, 500);
+
+        let mut messages = vec![plan.progress().unwrap()];
+        assert_eq!(plan.progress(), None);
+        for &index in &[first, second] {
+            while let Some(chunk) = plan.next_chunk(index) {
+                plan.chunk_done(index, chunk);
+                messages.extend(plan.progress());
+            }
+        }
+
+        assert_eq!(messages.first().unwrap(), "migrating cache: 12%");
+        assert_eq!(messages[1], "migrating cache: 15%");
+        assert_eq!(messages.last().unwrap(), "migrating cache: 100%");
+        // One message per chunk, since each chunk is 2.5% of the rows
+        assert_eq!(messages.len(), 36);
+    }
+
+    #[test]
+    fn t_older_versions_are_refused() {
+        assert_eq!(check_reader_version(version(2, 21), version(2, 21)), Ok(()));
+        assert_eq!(check_reader_version(version(2, 21), version(3, 0)), Ok(()));
+        assert_eq!(
+            check_reader_version(version(2, 22), version(2, 21)),
+            Err(
+                "The cache was migrated by newsboat 2.22, and can't be opened by older \
+                 versions. This is newsboat 2.21; please upgrade it, or point `cache-file' \
+                 to another file."
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn t_current_version_is_the_crate_version() {
+        let current = Version::current();
+        assert!(env!("CARGO_PKG_VERSION").starts_with(&format!("{}.", current)));
+    }
+}
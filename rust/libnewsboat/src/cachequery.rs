@@ -0,0 +1,270 @@
+//! Printing articles from the cache without starting the user interface (`--query-cache`).
+//!
+//! The filter expression picks the articles, and `--fields` picks what's printed about them.
+//! Fields are named like the attributes of the filter language (see `filterattributes`), so
+//! anything a filter can look at can be printed, too.
+
+use crate::clierror::json_string;
+use crate::filterattributes::{self, ATTRIBUTES};
+use crate::filterparser;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Fields printed if the user doesn't pick any.
+pub const DEFAULT_FIELDS: &[&str] = &["title", "link", "date"];
+
+/// How the articles are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Tab-separated values, with a header line that names the fields.
+    Tsv,
+    /// An array of objects, one per article, whose keys are the field names.
+    Json,
+}
+
+impl Default for OutputFormat {
+    fn default() -> OutputFormat {
+        OutputFormat::Tsv
+    }
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Option<OutputFormat> {
+        match value {
+            "tsv" => Some(OutputFormat::Tsv),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a comma-separated list of field names, like "title,link,date".
+///
+/// If a field isn't an attribute of the filter language, returns an internationalized error
+/// message that lists the valid ones.
+pub fn parse_fields(input: &str) -> Result<Vec<String>, String> {
+    let fields = input
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect::<Vec<_>>();
+
+    if fields.is_empty() {
+        return Err(fmt!(
+            &gettext("No fields given. Valid fields are: %s"),
+            valid_fields()
+        ));
+    }
+
+    match fields
+        .iter()
+        .find(|field| filterattributes::find(field).is_none())
+    {
+        Some(unknown) => Err(fmt!(
+            &gettext("Unknown field `%s'. Valid fields are: %s"),
+            unknown,
+            valid_fields()
+        )),
+        None => Ok(fields.into_iter().map(String::from).collect()),
+    }
+}
+
+fn valid_fields() -> String {
+    ATTRIBUTES
+        .iter()
+        .map(|attribute| attribute.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks that `expr` is a filter expression. If it isn't, returns an internationalized error
+/// message that says at which position parsing stopped.
+pub fn check_filter(expr: &str) -> Result<(), String> {
+    filterparser::parse(expr)
+        .map(|_| ())
+        .map_err(|error| fmt!(&gettext("Invalid filter expression `%s': %s"), expr, error))
+}
+
+/// The articles that matched the filter, in the order in which they should be printed.
+pub struct QueryResult {
+    format: OutputFormat,
+    fields: Vec<String>,
+    /// One value per field; `None` if the article doesn't have the attribute.
+    rows: Vec<Vec<Option<String>>>,
+}
+
+impl QueryResult {
+    pub fn new(format: OutputFormat, fields: Vec<String>) -> QueryResult {
+        QueryResult {
+            format,
+            fields,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds an article. `values` holds one value per field, in the order of the fields.
+    pub fn add_row(&mut self, values: Vec<Option<String>>) {
+        assert_eq!(values.len(), self.fields.len());
+        self.rows.push(values);
+    }
+
+    /// Renders the articles in the chosen format. The result ends in a newline.
+    pub fn render(&self) -> String {
+        match self.format {
+            OutputFormat::Tsv => self.render_tsv(),
+            OutputFormat::Json => self.render_json(),
+        }
+    }
+
+    fn render_tsv(&self) -> String {
+        let mut result = self.fields.join("\t");
+        result.push('\n');
+        for row in &self.rows {
+            let values = row
+                .iter()
+                .map(|value| tsv_value(value.as_deref().unwrap_or_default()))
+                .collect::<Vec<_>>();
+            result.push_str(&values.join("\t"));
+            result.push('\n');
+        }
+        result
+    }
+
+    fn render_json(&self) -> String {
+        if self.rows.is_empty() {
+            return "[]\n".to_string();
+        }
+
+        let objects = self
+            .rows
+            .iter()
+            .map(|row| {
+                let members = self
+                    .fields
+                    .iter()
+                    .zip(row)
+                    .map(|(field, value)| {
+                        let value = match value {
+                            Some(value) => json_string(value),
+                            None => "null".to_string(),
+                        };
+                        format!("{}:{}", json_string(field), value)
+                    })
+                    .collect::<Vec<_>>();
+                format!("  {{{}}}", members.join(","))
+            })
+            .collect::<Vec<_>>();
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+/// Escapes the characters that would break the columns or lines of TSV output.
+fn tsv_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn row(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|value| value.map(String::from)).collect()
+    }
+
+    #[test]
+    fn t_parses_output_formats() {
+        assert_eq!(OutputFormat::parse("tsv"), Some(OutputFormat::Tsv));
+        assert_eq!(OutputFormat::parse("json"), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::parse("JSON"), None);
+        assert_eq!(OutputFormat::parse("csv"), None);
+        assert_eq!(OutputFormat::default(), OutputFormat::Tsv);
+    }
+
+    #[test]
+    fn t_fields_are_named_like_filter_attributes() {
+        assert_eq!(
+            parse_fields("title,link,date"),
+            Ok(fields(&["title", "link", "date"]))
+        );
+        assert_eq!(
+            parse_fields(" feedtitle , unread,, reading_minutes "),
+            Ok(fields(&["feedtitle", "unread", "reading_minutes"]))
+        );
+        for field in DEFAULT_FIELDS {
+            assert!(filterattributes::find(field).is_some());
+        }
+    }
+
+    #[test]
+    fn t_unknown_field_lists_the_valid_ones() {
+        let error = parse_fields("title,summary").unwrap_err();
+        assert!(error.starts_with("Unknown field `summary'. Valid fields are: title, link, "));
+        for attribute in ATTRIBUTES {
+            assert!(error.contains(attribute.name));
+        }
+
+        let error = parse_fields(" , ").unwrap_err();
+        assert!(error.starts_with("No fields given. Valid fields are: title, link, "));
+    }
+
+    #[test]
+    fn t_filter_errors_mention_the_position() {
+        assert_eq!(check_filter("unread = \"yes\""), Ok(()));
+        assert_eq!(
+            check_filter("title =~"),
+            Err("Invalid filter expression `title =~': \
+                Parse error at position 8: expected one of: quoted string, range, number"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn t_renders_tsv_with_a_header_and_escapes() {
+        let mut result = QueryResult::new(OutputFormat::Tsv, fields(&["title", "link"]));
+        result.add_row(row(&[Some("First"), Some("https://example.com/1")]));
+        result.add_row(row(&[Some("Tabs\tand\nlines \\o/"), None]));
+
+        assert_eq!(
+            result.render(),
+            "title\tlink\n\
+             First\thttps://example.com/1\n\
+             Tabs\\tand\\nlines \\\\o/\t\n"
+        );
+    }
+
+    #[test]
+    fn t_renders_json_objects_keyed_by_field() {
+        let mut result = QueryResult::new(OutputFormat::Json, fields(&["title", "unread"]));
+        result.add_row(row(&[Some("A \"quoted\" title"), Some("yes")]));
+        result.add_row(row(&[Some("Second"), None]));
+
+        assert_eq!(
+            result.render(),
+            "[\n  {\"title\":\"A \\\"quoted\\\" title\",\"unread\":\"yes\"},\n  \
+             {\"title\":\"Second\",\"unread\":null}\n]\n"
+        );
+    }
+
+    #[test]
+    fn t_renders_no_articles() {
+        let result = QueryResult::new(OutputFormat::Json, fields(&["title"]));
+        assert_eq!(result.render(), "[]\n");
+
+        let result = QueryResult::new(OutputFormat::Tsv, fields(&["title", "date"]));
+        assert_eq!(result.render(), "title\tdate\n");
+    }
+}
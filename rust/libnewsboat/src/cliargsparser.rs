@@ -1,8 +1,11 @@
 use clap::{App, Arg};
 use gettextrs::gettext;
-use libc::{EXIT_FAILURE, EXIT_SUCCESS};
+use libc::EXIT_SUCCESS;
 use std::path::PathBuf;
 
+use crate::cachemerge::ReadPreference;
+use crate::cachequery::{self, OutputFormat};
+use crate::clierror::{CliError, ErrorCategory, ErrorFormat};
 use crate::logger::Level;
 use crate::utils;
 use strprintf::fmt;
@@ -10,6 +13,8 @@ use strprintf::fmt;
 #[derive(Default)]
 pub struct CliArgsParser {
     pub do_export: bool,
+    /// If `true`, the export requested by `do_export` should be in OPML 2.0 rather than 1.0.
+    pub export_opml2: bool,
     pub do_vacuum: bool,
     pub program_name: String,
     pub show_version: usize,
@@ -63,29 +68,122 @@ pub struct CliArgsParser {
     /// \note The parser does not check if the passed commands are valid.
     pub cmds_to_execute: Vec<String>,
 
+    /// Config options set with `-o key=value`, in the order in which they were given. The creator
+    /// of `CliArgsParser` should apply them after the config file is read, so that they win.
+    ///
+    /// \note The parser does not check if the keys are valid options.
+    pub config_overrides: Vec<(String, String)>,
+
     /// If this contains some value, it's the path to the log file specified by the user.
     pub log_file: Option<PathBuf>,
 
     /// If this contains some value, it's the log level specified by the user.
     pub log_level: Option<Level>,
+
+    /// If this contains some value, it's the name of the profile specified by the user.
+    ///
+    /// Each profile has its own set of config, urls, cache etc. files. See `ConfigPaths`.
+    pub profile: Option<String>,
+
+    /// If `list_profiles` is `true`, the creator of `CliArgsParser` should print the names of
+    /// existing profiles and exit.
+    pub list_profiles: bool,
+
+    /// How errors in non-interactive modes should be printed to stderr.
+    ///
+    /// \note If this is `ErrorFormat::Json`, usage errors are already formatted as JSON in
+    /// `display_msg`, and `should_print_usage` is not set for them.
+    pub error_format: ErrorFormat,
+
+    /// If this contains some value, it's the path to another cache file whose articles and read
+    /// states should be merged into ours.
+    pub merge_cache_file: Option<PathBuf>,
+
+    /// Which read state wins when merging caches that disagree about an article.
+    pub merge_prefer: ReadPreference,
+
+    /// If `cleanup` is `true`, the creator of `CliArgsParser` should do the cache cleanup that
+    /// normally happens on quit, compact the cache, and exit.
+    pub cleanup: bool,
+
+    /// If `recover_cache` is `true`, the creator of `CliArgsParser` should salvage what it can
+    /// from a damaged cache file into a fresh one, and exit.
+    pub recover_cache: bool,
+
+    /// If `force` is `true`, the cache should be recovered even if it passes the integrity check.
+    pub force: bool,
+
+    /// If `import_from_newsbeuter` is `true`, the creator of `CliArgsParser` should copy
+    /// Newsbeuter's config and data into Newsboat's directories, unless Newsboat already has
+    /// some, and exit.
+    pub import_from_newsbeuter: bool,
+
+    /// If `dry_run_migration` is `true`, the creator of `CliArgsParser` should print what
+    /// `import_from_newsbeuter` would copy, without copying anything, and exit.
+    pub dry_run_migration: bool,
+
+    /// If `convert_to_utf8` is `true`, the creator of `CliArgsParser` should rewrite the config
+    /// and urls files that aren't UTF-8 in UTF-8, and exit.
+    pub convert_to_utf8: bool,
+
+    /// If this contains some value, it's the URL of a feed that should be added to the urls
+    /// file, either by the instance that's already running or directly, and Newsboat should exit.
+    pub add_feed: Option<String>,
+
+    /// If this contains some value, it's a URL that was given without an option. It's handled
+    /// like `add_feed` if the `single-instance` setting is enabled, and is an error otherwise.
+    pub url_argument: Option<String>,
+
+    /// If `plain_output` is `true`, the output of non-interactive modes should look the same in
+    /// a terminal as it does in a pipe (see `clioutput::OutputStyle`).
+    pub plain_output: bool,
+
+    /// If this contains some value, it's a filter expression: the creator of `CliArgsParser`
+    /// should print the articles of the cache that match it, and exit.
+    pub query_cache: Option<String>,
+
+    /// Attributes of the articles that `query_cache` prints, in order.
+    pub query_fields: Vec<String>,
+
+    /// How `query_cache` prints the articles.
+    pub query_format: OutputFormat,
 }
 
 const LOCK_SUFFIX: &str = ".lock";
 
 impl CliArgsParser {
     pub fn new(opts: Vec<String>) -> CliArgsParser {
+        const ADD_FEED: &str = "add-feed";
         const CACHE_FILE: &str = "cache-file";
+        const CLEANUP: &str = "cleanup";
         const CONFIG_FILE: &str = "config-file";
+        const CONVERT_TO_UTF8: &str = "convert-to-utf8";
+        const ERROR_FORMAT: &str = "error-format";
         const EXECUTE: &str = "execute";
         const EXPORT_TO_FILE: &str = "export-to-file";
         const EXPORT_TO_OPML: &str = "export-to-opml";
+        const EXPORT_TO_OPML2: &str = "export-to-opml2";
+        const FIELDS: &str = "fields";
+        const FORCE: &str = "force";
+        const FORMAT: &str = "format";
         const HELP: &str = "help";
         const IMPORT_FROM_FILE: &str = "import-from-file";
+        const IMPORT_FROM_NEWSBEUTER: &str = "import-from-newsbeuter";
+        const DRY_RUN_MIGRATION: &str = "dry-run-migration";
         const IMPORT_FROM_OPML: &str = "import-from-opml";
         const LOG_FILE: &str = "log-file";
+        const LIST_PROFILES: &str = "list-profiles";
         const LOG_LEVEL: &str = "log-level";
+        const MERGE_CACHE: &str = "merge-cache";
+        const MERGE_PREFER: &str = "merge-prefer";
+        const OPTION: &str = "option";
+        const PLAIN: &str = "plain";
+        const PROFILE: &str = "profile";
+        const QUERY_CACHE: &str = "query-cache";
         const QUIET: &str = "quiet";
+        const RECOVER_CACHE: &str = "recover-cache";
         const REFRESH_ON_START: &str = "refresh-on-start";
+        const URL_ARGUMENT: &str = "url";
         const URL_FILE: &str = "url-file";
         const VACUUM: &str = "vacuum";
         const VERSION: &str = "version";
@@ -103,6 +201,7 @@ impl CliArgsParser {
                     .short("e")
                     .long(EXPORT_TO_OPML),
             )
+            .arg(Arg::with_name(EXPORT_TO_OPML2).long(EXPORT_TO_OPML2))
             .arg(
                 Arg::with_name(REFRESH_ON_START)
                     .short("r")
@@ -128,6 +227,7 @@ impl CliArgsParser {
                     .takes_value(true),
             )
             .arg(Arg::with_name(VACUUM).short("X").long(VACUUM))
+            .arg(Arg::with_name(CLEANUP).long(CLEANUP))
             .arg(
                 Arg::with_name(VERSION)
                     .short("v")
@@ -143,6 +243,14 @@ impl CliArgsParser {
                     .multiple(true),
             )
             .arg(Arg::with_name(QUIET).short("q").long(QUIET))
+            .arg(
+                Arg::with_name(OPTION)
+                    .short("o")
+                    .long(OPTION)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
             .arg(
                 Arg::with_name(IMPORT_FROM_FILE)
                     .short("I")
@@ -166,6 +274,44 @@ impl CliArgsParser {
                     .short("l")
                     .long(LOG_LEVEL)
                     .takes_value(true),
+            )
+            .arg(Arg::with_name(PROFILE).long(PROFILE).takes_value(true))
+            .arg(Arg::with_name(LIST_PROFILES).long(LIST_PROFILES))
+            .arg(
+                Arg::with_name(MERGE_CACHE)
+                    .long(MERGE_CACHE)
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name(MERGE_PREFER)
+                    .long(MERGE_PREFER)
+                    .takes_value(true),
+            )
+            .arg(Arg::with_name(RECOVER_CACHE).long(RECOVER_CACHE))
+            .arg(Arg::with_name(FORCE).long(FORCE))
+            .arg(Arg::with_name(IMPORT_FROM_NEWSBEUTER).long(IMPORT_FROM_NEWSBEUTER))
+            .arg(Arg::with_name(DRY_RUN_MIGRATION).long(DRY_RUN_MIGRATION))
+            .arg(Arg::with_name(CONVERT_TO_UTF8).long(CONVERT_TO_UTF8))
+            .arg(Arg::with_name(PLAIN).long(PLAIN))
+            .arg(
+                Arg::with_name(ADD_FEED)
+                    .long(ADD_FEED)
+                    .takes_value(true)
+                    .value_name("url"),
+            )
+            .arg(
+                Arg::with_name(QUERY_CACHE)
+                    .long(QUERY_CACHE)
+                    .takes_value(true)
+                    .value_name("filter"),
+            )
+            .arg(Arg::with_name(FIELDS).long(FIELDS).takes_value(true))
+            .arg(Arg::with_name(FORMAT).long(FORMAT).takes_value(true))
+            .arg(Arg::with_name(URL_ARGUMENT).index(1))
+            .arg(
+                Arg::with_name(ERROR_FORMAT)
+                    .long(ERROR_FORMAT)
+                    .takes_value(true),
             );
 
         let mut args = CliArgsParser::default();
@@ -176,19 +322,48 @@ impl CliArgsParser {
 
         let matches = match app.get_matches_from_safe(&opts) {
             Ok(matches) => matches,
-            Err(_) => {
-                args.should_print_usage = true;
-                args.return_code = Some(EXIT_FAILURE);
+            Err(e) => {
+                // We couldn't parse the options, but we still want to report this error in the
+                // format the user asked for.
+                args.error_format = find_error_format(&opts).unwrap_or_default();
+                let message = e.message.lines().next().unwrap_or_default();
+                let message = message.trim_start_matches("error: ");
+                args.usage_error(fmt!("%s: %s", &args.program_name, message));
+                args.finish_usage_error();
                 return args;
             }
         };
 
+        if let Some(error_format) = matches.value_of(ERROR_FORMAT) {
+            match ErrorFormat::parse(error_format) {
+                Some(error_format) => args.error_format = error_format,
+                None => args.usage_error(fmt!(
+                    &gettext("%s: %s: invalid error format (expected `text' or `json')"),
+                    &args.program_name,
+                    error_format
+                )),
+            }
+        }
+
         if matches.is_present(EXPORT_TO_OPML) {
             if args.importfile.is_some() {
-                args.should_print_usage = true;
-                args.return_code = Some(EXIT_FAILURE);
+                args.usage_error(conflict_message(&args.program_name, "-i", "-e"));
+            } else {
+                args.do_export = true;
+                args.silent = true;
+            }
+        }
+
+        if matches.is_present(EXPORT_TO_OPML2) {
+            if args.importfile.is_some() {
+                args.usage_error(conflict_message(
+                    &args.program_name,
+                    "-i",
+                    "--export-to-opml2",
+                ));
             } else {
                 args.do_export = true;
+                args.export_opml2 = true;
                 args.silent = true;
             }
         }
@@ -204,10 +379,17 @@ impl CliArgsParser {
 
         args.silent = args.silent || matches.is_present(QUIET);
 
+        args.plain_output = matches.is_present(PLAIN);
+
         if let Some(importfile) = matches.value_of(IMPORT_FROM_OPML) {
-            if args.do_export {
-                args.should_print_usage = true;
-                args.return_code = Some(EXIT_FAILURE);
+            if args.export_opml2 {
+                args.usage_error(conflict_message(
+                    &args.program_name,
+                    "-i",
+                    "--export-to-opml2",
+                ));
+            } else if args.do_export {
+                args.usage_error(conflict_message(&args.program_name, "-i", "-e"));
             } else {
                 args.importfile = Some(utils::resolve_tilde(PathBuf::from(importfile)));
             }
@@ -237,15 +419,34 @@ impl CliArgsParser {
         args.show_version =
             (matches.occurrences_of(VERSION) + matches.occurrences_of(VERSION_V)) as usize;
 
-        if let Some(mut commands) = matches.values_of_lossy(EXECUTE) {
+        if let Some(commands) = matches.values_of_lossy(EXECUTE) {
             args.silent = true;
-            args.cmds_to_execute.append(&mut commands);
+            match join_command_arguments(commands) {
+                Ok(mut commands) => args.cmds_to_execute.append(&mut commands),
+                Err(command) => args.usage_error(fmt!(
+                    &gettext("%s: `%s' needs an argument"),
+                    &args.program_name,
+                    command
+                )),
+            }
+        }
+
+        if let Some(options) = matches.values_of_lossy(OPTION) {
+            for option in options {
+                match parse_config_override(&option) {
+                    Some(pair) => args.config_overrides.push(pair),
+                    None => args.usage_error(fmt!(
+                        &gettext("%s: %s: invalid option (expected key=value)"),
+                        &args.program_name,
+                        &option
+                    )),
+                }
+            }
         }
 
         if let Some(importfile) = matches.value_of(IMPORT_FROM_FILE) {
             if args.readinfo_export_file.is_some() {
-                args.should_print_usage = true;
-                args.return_code = Some(EXIT_FAILURE);
+                args.usage_error(conflict_message(&args.program_name, "-I", "-E"));
             } else {
                 args.readinfo_import_file = Some(utils::resolve_tilde(PathBuf::from(importfile)));
             }
@@ -253,8 +454,7 @@ impl CliArgsParser {
 
         if let Some(exportfile) = matches.value_of(EXPORT_TO_FILE) {
             if args.readinfo_import_file.is_some() {
-                args.should_print_usage = true;
-                args.return_code = Some(EXIT_FAILURE);
+                args.usage_error(conflict_message(&args.program_name, "-I", "-E"));
             } else {
                 args.readinfo_export_file = Some(utils::resolve_tilde(PathBuf::from(exportfile)));
             }
@@ -290,19 +490,253 @@ impl CliArgsParser {
                         &opts[0],
                         log_level_str
                     );
-                    args.return_code = Some(EXIT_FAILURE);
+                    args.return_code = Some(ErrorCategory::Usage.exit_code());
                 }
             };
         }
 
+        if let Some(profile) = matches.value_of(PROFILE) {
+            if is_valid_profile_name(profile) {
+                args.profile = Some(profile.to_string());
+            } else {
+                args.display_msg =
+                    fmt!(&gettext("%s: %s: invalid profile name"), &opts[0], profile);
+                args.return_code = Some(ErrorCategory::Usage.exit_code());
+            }
+        }
+
+        if matches.is_present(LIST_PROFILES) {
+            args.list_profiles = true;
+            args.silent = true;
+        }
+
+        if let Some(merge_cache_file) = matches.value_of(MERGE_CACHE) {
+            args.merge_cache_file = Some(utils::resolve_tilde(PathBuf::from(merge_cache_file)));
+            args.silent = true;
+        }
+
+        if let Some(merge_prefer) = matches.value_of(MERGE_PREFER) {
+            match ReadPreference::parse(merge_prefer) {
+                Some(preference) => args.merge_prefer = preference,
+                None => args.usage_error(fmt!(
+                    &gettext("%s: %s: invalid merge preference (expected `read' or `unread')"),
+                    &args.program_name,
+                    merge_prefer
+                )),
+            }
+        }
+
+        if matches.is_present(RECOVER_CACHE) {
+            args.recover_cache = true;
+            args.silent = true;
+        }
+
+        if matches.is_present(FORCE) {
+            if args.recover_cache {
+                args.force = true;
+            } else {
+                args.usage_error(fmt!(
+                    &gettext("%s: --force can only be used together with --recover-cache"),
+                    &args.program_name
+                ));
+            }
+        }
+
+        if matches.is_present(IMPORT_FROM_NEWSBEUTER) {
+            args.import_from_newsbeuter = true;
+        }
+
+        if matches.is_present(DRY_RUN_MIGRATION) {
+            args.dry_run_migration = true;
+        }
+
+        if matches.is_present(CONVERT_TO_UTF8) {
+            args.convert_to_utf8 = true;
+        }
+
+        if let Some(url) = matches.value_of(ADD_FEED) {
+            args.add_feed = Some(url.to_string());
+            args.silent = true;
+        }
+
+        if let Some(url) = matches.value_of(URL_ARGUMENT) {
+            if args.add_feed.is_some() {
+                args.usage_error(fmt!(
+                    &gettext("%s: %s: a URL can't be given together with --add-feed"),
+                    &args.program_name,
+                    url
+                ));
+            } else {
+                args.url_argument = Some(url.to_string());
+                args.silent = true;
+            }
+        }
+
+        if let Some(filter) = matches.value_of(QUERY_CACHE) {
+            match cachequery::check_filter(filter) {
+                Ok(()) => {
+                    args.query_cache = Some(filter.to_string());
+                    args.silent = true;
+                }
+                Err(message) => args.usage_error(fmt!("%s: %s", &args.program_name, message)),
+            }
+        }
+
+        args.query_fields = cachequery::DEFAULT_FIELDS
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        for option in &[FIELDS, FORMAT] {
+            if matches.is_present(option) && !matches.is_present(QUERY_CACHE) {
+                args.usage_error(fmt!(
+                    &gettext("%s: --%s can only be used together with --query-cache"),
+                    &args.program_name,
+                    *option
+                ));
+            }
+        }
+
+        if let Some(fields) = matches.value_of(FIELDS) {
+            match cachequery::parse_fields(fields) {
+                Ok(fields) => args.query_fields = fields,
+                Err(message) => args.usage_error(fmt!("%s: %s", &args.program_name, message)),
+            }
+        }
+
+        if let Some(format) = matches.value_of(FORMAT) {
+            match OutputFormat::parse(format) {
+                Some(format) => args.query_format = format,
+                None => args.usage_error(fmt!(
+                    &gettext("%s: %s: invalid output format (expected `tsv' or `json')"),
+                    &args.program_name,
+                    format
+                )),
+            }
+        }
+
+        if matches.is_present(CLEANUP) {
+            let conflicting = [
+                (EXECUTE, "-x"),
+                (IMPORT_FROM_OPML, "-i"),
+                (EXPORT_TO_OPML, "-e"),
+                (EXPORT_TO_OPML2, "--export-to-opml2"),
+                (IMPORT_FROM_FILE, "-I"),
+                (EXPORT_TO_FILE, "-E"),
+            ];
+            match conflicting
+                .iter()
+                .find(|(option, _)| matches.is_present(option))
+            {
+                Some((_, flag)) => {
+                    args.usage_error(conflict_message(&args.program_name, "--cleanup", flag))
+                }
+                None => {
+                    args.cleanup = true;
+                    args.silent = true;
+                }
+            }
+        }
+
+        args.finish_usage_error();
+
         args
     }
 
+    /// Records a problem with the command-line arguments: the usage information should be
+    /// printed, and Newsboat should exit with the "usage" exit code. Only the first error is kept.
+    fn usage_error(&mut self, message: String) {
+        self.should_print_usage = true;
+        self.return_code = Some(ErrorCategory::Usage.exit_code());
+        if self.display_msg.is_empty() {
+            self.display_msg = message;
+        }
+    }
+
+    /// If there was a usage error and the user asked for JSON errors, replaces the usage
+    /// information with a JSON report.
+    fn finish_usage_error(&mut self) {
+        let failed = match self.return_code {
+            Some(code) => code != EXIT_SUCCESS,
+            None => false,
+        };
+        if failed && self.error_format == ErrorFormat::Json {
+            self.display_msg = CliError::new(ErrorCategory::Usage, &self.display_msg).to_json();
+            self.should_print_usage = false;
+        }
+    }
+
     pub fn using_nonstandard_configs(&self) -> bool {
-        self.url_file.is_some() || self.cache_file.is_some() || self.config_file.is_some()
+        self.url_file.is_some()
+            || self.cache_file.is_some()
+            || self.config_file.is_some()
+            || self.profile.is_some()
     }
 }
 
+/// Splits a `-o` argument into a key and a value at the first `=`. The value can be empty, but
+/// the key can't.
+fn parse_config_override(option: &str) -> Option<(String, String)> {
+    let mut parts = option.splitn(2, '=');
+    let key = parts.next().unwrap_or_default();
+    let value = parts.next()?;
+    if key.is_empty() {
+        return None;
+    }
+    Some((key.to_string(), value.to_string()))
+}
+
+/// Looks for `--error-format` among `opts` without parsing the rest of them.
+fn find_error_format(opts: &[String]) -> Option<ErrorFormat> {
+    let mut opts = opts.iter().skip(1);
+    while let Some(opt) = opts.next() {
+        if opt == "--" {
+            break;
+        } else if opt == "--error-format" {
+            return opts.next().and_then(|value| ErrorFormat::parse(value));
+        } else if opt.starts_with("--error-format=") {
+            return ErrorFormat::parse(&opt["--error-format=".len()..]);
+        }
+    }
+    None
+}
+
+/// Commands of `-x` that take an argument, which is the value that follows them.
+const COMMANDS_WITH_ARGUMENT: &[&str] = &["reload-tag"];
+
+/// Joins each command of `-x` that takes an argument with the value that follows it, so that
+/// `-x reload-tag news` executes `reload-tag news`. Returns the command that lacks its argument,
+/// if there is one.
+fn join_command_arguments(values: Vec<String>) -> Result<Vec<String>, String> {
+    let mut commands = Vec::with_capacity(values.len());
+    let mut values = values.into_iter();
+    while let Some(command) = values.next() {
+        if COMMANDS_WITH_ARGUMENT.contains(&command.as_str()) {
+            match values.next() {
+                Some(argument) => commands.push(format!("{} {}", command, argument)),
+                None => return Err(command),
+            }
+        } else {
+            commands.push(command);
+        }
+    }
+    Ok(commands)
+}
+
+fn conflict_message(program_name: &str, first: &str, second: &str) -> String {
+    fmt!(
+        &gettext("%s: %s and %s can't be used together"),
+        program_name,
+        first,
+        second
+    )
+}
+
+/// Profile names become directory names, so they can't be empty, refer to the current or parent
+/// directory, or contain a path separator.
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains('/')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,7 +746,7 @@ mod tests {
         let check = |opts| {
             let args = CliArgsParser::new(opts);
             assert!(args.should_print_usage);
-            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.return_code, Some(2));
         };
 
         check(vec![
@@ -355,7 +789,7 @@ mod tests {
             let args = CliArgsParser::new(opts);
 
             assert!(args.should_print_usage);
-            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.return_code, Some(2));
         };
 
         check(vec![
@@ -374,6 +808,57 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn t_sets_cleanup_if_dash_dash_cleanup_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert!(!args.cleanup);
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--cleanup".to_string()]);
+        assert!(args.cleanup);
+        assert!(args.silent);
+        assert!(!args.do_vacuum);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_cleanup_respects_cache_file() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--cleanup".to_string(),
+            "-c".to_string(),
+            "cache.db".to_string(),
+        ]);
+        assert!(args.cleanup);
+        assert_eq!(args.cache_file, Some(PathBuf::from("cache.db")));
+        assert_eq!(args.lock_file, Some(PathBuf::from("cache.db.lock")));
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_failure_if_cleanup_is_combined_with_other_modes() {
+        let check = |opts: &[&str], flag: &str| {
+            let mut all_opts = vec!["newsboat".to_string(), "--cleanup".to_string()];
+            all_opts.extend(opts.iter().map(|opt| opt.to_string()));
+            let args = CliArgsParser::new(all_opts);
+
+            assert!(!args.cleanup);
+            assert!(args.should_print_usage);
+            assert_eq!(
+                args.display_msg,
+                format!("newsboat: --cleanup and {} can't be used together", flag)
+            );
+            assert_eq!(args.return_code, Some(2));
+        };
+
+        check(&["-x", "reload"], "-x");
+        check(&["--execute=print-unread"], "-x");
+        check(&["-i", "feeds.opml"], "-i");
+        check(&["-e"], "-e");
+        check(&["--export-to-opml2"], "--export-to-opml2");
+        check(&["-I", "read.txt"], "-I");
+        check(&["--export-to-file=read.txt"], "-E");
+    }
+
     #[test]
     fn t_sets_refresh_on_start_if_dash_r_is_provided() {
         let check = |opts| {
@@ -413,6 +898,34 @@ mod tests {
         check(vec!["newsboat".to_string(), "--export-to-opml".to_string()]);
     }
 
+    #[test]
+    fn t_export_to_opml2_requests_an_opml_2_0_export() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--export-to-opml2".to_string(),
+        ]);
+
+        assert!(args.do_export);
+        assert!(args.export_opml2);
+        assert!(args.silent);
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "-e".to_string()]);
+        assert!(!args.export_opml2);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--export-to-opml2".to_string(),
+            "-i".to_string(),
+            "import.opml".to_string(),
+        ]);
+        assert!(args.should_print_usage);
+        assert_eq!(
+            args.display_msg,
+            "newsboat: -i and --export-to-opml2 can't be used together"
+        );
+        assert_eq!(args.return_code, Some(2));
+    }
+
     #[test]
     fn t_asks_to_print_usage_and_exit_with_success_if_dash_h_is_provided() {
         let check = |opts| {
@@ -497,6 +1010,107 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn t_collects_config_overrides_if_dash_o_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert!(args.config_overrides.is_empty());
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-o".to_string(),
+            "browser=firefox --private-window".to_string(),
+            "--option".to_string(),
+            "reload-time=5".to_string(),
+            "--option=html-renderer=w3m -dump -T text/html".to_string(),
+            "-o".to_string(),
+            "save-path=".to_string(),
+        ]);
+        assert_eq!(
+            args.config_overrides,
+            vec![
+                (
+                    "browser".to_string(),
+                    "firefox --private-window".to_string()
+                ),
+                ("reload-time".to_string(), "5".to_string()),
+                (
+                    "html-renderer".to_string(),
+                    "w3m -dump -T text/html".to_string()
+                ),
+                ("save-path".to_string(), "".to_string()),
+            ]
+        );
+        assert!(!args.should_print_usage);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_splits_config_overrides_only_on_the_first_equals_sign() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-o".to_string(),
+            "bookmark-cmd=tee -a a=b.txt".to_string(),
+            "-o".to_string(),
+            "feed-sort-order==".to_string(),
+        ]);
+        assert_eq!(
+            args.config_overrides,
+            vec![
+                ("bookmark-cmd".to_string(), "tee -a a=b.txt".to_string()),
+                ("feed-sort-order".to_string(), "=".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn t_config_overrides_go_together_with_config_and_url_files() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-C".to_string(),
+            "config file".to_string(),
+            "-o".to_string(),
+            "reload-time=5".to_string(),
+            "-u".to_string(),
+            "urlfile".to_string(),
+            "-o".to_string(),
+            "browser=lynx".to_string(),
+        ]);
+        assert_eq!(args.config_file, Some(PathBuf::from("config file")));
+        assert_eq!(args.url_file, Some(PathBuf::from("urlfile")));
+        assert_eq!(
+            args.config_overrides,
+            vec![
+                ("reload-time".to_string(), "5".to_string()),
+                ("browser".to_string(), "lynx".to_string()),
+            ]
+        );
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_asks_to_print_usage_and_exit_with_failure_if_dash_o_is_not_key_value() {
+        let check = |option: &str| {
+            let args = CliArgsParser::new(vec![
+                "newsboat".to_string(),
+                "-o".to_string(),
+                "reload-time=5".to_string(),
+                "-o".to_string(),
+                option.to_string(),
+            ]);
+
+            assert!(args.should_print_usage);
+            assert_eq!(
+                args.display_msg,
+                format!("newsboat: {}: invalid option (expected key=value)", option)
+            );
+            assert_eq!(args.return_code, Some(2));
+        };
+
+        check("reload-time");
+        check("=5");
+        check("");
+    }
+
     #[test]
     fn t_sets_do_vacuum_if_dash_capital_x_is_provided() {
         let check = |opts| {
@@ -616,6 +1230,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn t_joins_commands_of_dash_x_with_their_arguments() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-x".to_string(),
+            "reload-tag".to_string(),
+            "news".to_string(),
+            "print-unread".to_string(),
+        ]);
+
+        assert_eq!(
+            args.cmds_to_execute,
+            vec!["reload-tag news".to_string(), "print-unread".to_string()]
+        );
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_keeps_arguments_of_dash_x_commands_that_contain_spaces() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-x".to_string(),
+            "reload-tag".to_string(),
+            "morning news".to_string(),
+        ]);
+
+        assert_eq!(args.cmds_to_execute, vec!["reload-tag morning news"]);
+    }
+
+    #[test]
+    fn t_dash_x_command_without_its_argument_is_a_usage_error() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "-x".to_string(),
+            "print-unread".to_string(),
+            "reload-tag".to_string(),
+        ]);
+
+        assert!(args.should_print_usage);
+        assert_eq!(args.display_msg, "newsboat: `reload-tag' needs an argument");
+        assert_eq!(args.return_code, Some(2));
+    }
+
     #[test]
     fn t_requests_silent_mode_if_dash_q_is_provided() {
         let check = |opts| {
@@ -679,7 +1336,7 @@ mod tests {
             let args = CliArgsParser::new(opts);
 
             assert!(args.should_print_usage);
-            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.return_code, Some(2));
         };
 
         check(vec![
@@ -768,7 +1425,7 @@ mod tests {
             let args = CliArgsParser::new(opts);
 
             assert!(!args.display_msg.is_empty());
-            assert_eq!(args.return_code, Some(EXIT_FAILURE));
+            assert_eq!(args.return_code, Some(2));
         };
 
         check(vec!["newsboat".to_string(), "-l0".to_string()]);
@@ -806,4 +1463,421 @@ mod tests {
             "/usr/local/bin/app-with-a-path".to_string(),
         );
     }
+
+    #[test]
+    fn t_sets_profile_and_using_nonstandard_configs_if_dash_dash_profile_is_provided() {
+        let check = |opts| {
+            let args = CliArgsParser::new(opts);
+
+            assert_eq!(args.profile, Some("work".to_string()));
+            assert!(args.using_nonstandard_configs());
+            assert_eq!(args.return_code, None);
+        };
+
+        check(vec![
+            "newsboat".to_string(),
+            "--profile".to_string(),
+            "work".to_string(),
+        ]);
+        check(vec!["newsboat".to_string(), "--profile=work".to_string()]);
+    }
+
+    #[test]
+    fn t_sets_display_msg_and_asks_to_exit_with_failure_if_profile_name_is_invalid() {
+        let check = |name: &str| {
+            let args = CliArgsParser::new(vec![
+                "newsboat".to_string(),
+                "--profile".to_string(),
+                name.to_string(),
+            ]);
+
+            assert_eq!(args.profile, None);
+            assert_eq!(
+                args.display_msg,
+                format!("newsboat: {}: invalid profile name", name)
+            );
+            assert_eq!(args.return_code, Some(2));
+        };
+
+        check("");
+        check(".");
+        check("..");
+        check("../work");
+        check("work/personal");
+    }
+
+    #[test]
+    fn t_sets_list_profiles_and_requests_silent_mode_if_dash_dash_list_profiles_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--list-profiles".to_string()]);
+
+        assert!(args.list_profiles);
+        assert!(args.silent);
+        assert!(!args.using_nonstandard_configs());
+    }
+
+    #[test]
+    fn t_sets_merge_cache_file_and_requests_silent_mode_if_dash_dash_merge_cache_is_provided() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--merge-cache".to_string(),
+            "laptop.db".to_string(),
+        ]);
+
+        assert_eq!(args.merge_cache_file, Some(PathBuf::from("laptop.db")));
+        assert_eq!(args.merge_prefer, ReadPreference::Read);
+        assert!(args.silent);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_merge_prefer_if_dash_dash_merge_prefer_is_provided() {
+        let check = |value: &str, expected: ReadPreference| {
+            let args = CliArgsParser::new(vec![
+                "newsboat".to_string(),
+                "--merge-cache=laptop.db".to_string(),
+                "--merge-prefer".to_string(),
+                value.to_string(),
+            ]);
+
+            assert_eq!(args.merge_prefer, expected);
+            assert_eq!(args.return_code, None);
+        };
+
+        check("read", ReadPreference::Read);
+        check("unread", ReadPreference::Unread);
+    }
+
+    #[test]
+    fn t_asks_to_exit_with_usage_error_if_merge_prefer_is_invalid() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--merge-prefer=newest".to_string(),
+        ]);
+
+        assert!(args.should_print_usage);
+        assert_eq!(
+            args.display_msg,
+            "newsboat: newest: invalid merge preference (expected `read' or `unread')"
+        );
+        assert_eq!(args.return_code, Some(2));
+    }
+
+    #[test]
+    fn t_sets_plain_output_if_dash_dash_plain_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert!(!args.plain_output);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--plain".to_string(),
+            "-x".to_string(),
+            "reload".to_string(),
+            "print-unread".to_string(),
+        ]);
+        assert!(args.plain_output);
+        assert_eq!(args.cmds_to_execute, vec!["reload", "print-unread"]);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_recover_cache_and_requests_silent_mode_if_dash_dash_recover_cache_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--recover-cache".to_string()]);
+
+        assert!(args.recover_cache);
+        assert!(!args.force);
+        assert!(args.silent);
+        assert_eq!(args.return_code, None);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--recover-cache".to_string(),
+            "--force".to_string(),
+        ]);
+
+        assert!(args.recover_cache);
+        assert!(args.force);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_asks_to_exit_with_usage_error_if_dash_dash_force_is_provided_without_dash_dash_recover_cache(
+    ) {
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--force".to_string()]);
+
+        assert!(!args.force);
+        assert!(args.should_print_usage);
+        assert_eq!(
+            args.display_msg,
+            "newsboat: --force can only be used together with --recover-cache"
+        );
+        assert_eq!(args.return_code, Some(2));
+    }
+
+    #[test]
+    fn t_sets_import_from_newsbeuter_if_dash_dash_import_from_newsbeuter_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert!(!args.import_from_newsbeuter);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--import-from-newsbeuter".to_string(),
+        ]);
+        assert!(args.import_from_newsbeuter);
+        assert!(!args.silent);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_dry_run_migration_if_dash_dash_dry_run_migration_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert!(!args.dry_run_migration);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--dry-run-migration".to_string(),
+        ]);
+        assert!(args.dry_run_migration);
+        assert!(!args.import_from_newsbeuter);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_convert_to_utf8_if_dash_dash_convert_to_utf8_is_provided() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert!(!args.convert_to_utf8);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--convert-to-utf8".to_string(),
+        ]);
+        assert!(args.convert_to_utf8);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_add_feed_and_requests_silent_mode_if_dash_dash_add_feed_is_provided() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--add-feed".to_string(),
+            "https://example.com/feed.xml".to_string(),
+        ]);
+        assert_eq!(
+            args.add_feed,
+            Some("https://example.com/feed.xml".to_string())
+        );
+        assert_eq!(args.url_argument, None);
+        assert!(args.silent);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_url_argument_if_a_url_is_given_without_an_option() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "feed://example.com/feed.xml".to_string(),
+        ]);
+        assert_eq!(
+            args.url_argument,
+            Some("feed://example.com/feed.xml".to_string())
+        );
+        assert_eq!(args.add_feed, None);
+        assert!(args.silent);
+        assert_eq!(args.return_code, None);
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--add-feed=https://example.com/a.xml".to_string(),
+            "https://example.com/b.xml".to_string(),
+        ]);
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(2));
+    }
+
+    #[test]
+    fn t_error_format_defaults_to_text() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string()]);
+        assert_eq!(args.error_format, ErrorFormat::Text);
+    }
+
+    #[test]
+    fn t_sets_error_format_if_dash_dash_error_format_is_provided() {
+        let check = |opts, expected| {
+            let args = CliArgsParser::new(opts);
+            assert_eq!(args.error_format, expected);
+            assert_eq!(args.return_code, None);
+        };
+
+        check(
+            vec![
+                "newsboat".to_string(),
+                "--error-format".to_string(),
+                "json".to_string(),
+            ],
+            ErrorFormat::Json,
+        );
+        check(
+            vec!["newsboat".to_string(), "--error-format=text".to_string()],
+            ErrorFormat::Text,
+        );
+    }
+
+    #[test]
+    fn t_asks_to_exit_with_usage_error_if_error_format_is_invalid() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--error-format=yaml".to_string(),
+        ]);
+
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(2));
+        assert_eq!(
+            args.display_msg,
+            "newsboat: yaml: invalid error format (expected `text' or `json')"
+        );
+    }
+
+    #[test]
+    fn t_reports_usage_errors_as_json_instead_of_printing_usage_if_asked_to() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--error-format=json".to_string(),
+            "-i".to_string(),
+            "import.opml".to_string(),
+            "-e".to_string(),
+        ]);
+
+        assert!(!args.should_print_usage);
+        assert_eq!(args.return_code, Some(2));
+        assert_eq!(
+            args.display_msg,
+            r#"{"code":2,"category":"usage","message":"newsboat: -i and -e can't be used together"}"#
+        );
+    }
+
+    #[test]
+    fn t_reports_unknown_options_as_json_if_asked_to() {
+        let check = |opts: Vec<String>| {
+            let args = CliArgsParser::new(opts);
+
+            assert!(!args.should_print_usage);
+            assert_eq!(args.return_code, Some(2));
+            assert!(args
+                .display_msg
+                .starts_with(r#"{"code":2,"category":"usage","message":"newsboat: "#));
+            assert!(args.display_msg.contains("--some-unknown-option"));
+        };
+
+        check(vec![
+            "newsboat".to_string(),
+            "--error-format".to_string(),
+            "json".to_string(),
+            "--some-unknown-option".to_string(),
+        ]);
+        check(vec![
+            "newsboat".to_string(),
+            "--some-unknown-option".to_string(),
+            "--error-format=json".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn t_does_not_report_help_as_an_error() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--error-format=json".to_string(),
+            "-h".to_string(),
+        ]);
+
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(EXIT_SUCCESS));
+        assert!(args.display_msg.is_empty());
+    }
+
+    #[test]
+    fn t_sets_query_cache_and_requests_silent_mode_if_dash_dash_query_cache_is_provided() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--query-cache".to_string(),
+            "unread = \"yes\"".to_string(),
+        ]);
+
+        assert_eq!(args.query_cache, Some("unread = \"yes\"".to_string()));
+        assert!(args.silent);
+        assert_eq!(args.query_fields, vec!["title", "link", "date"]);
+        assert_eq!(args.query_format, OutputFormat::Tsv);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_sets_query_fields_and_format_if_provided_with_dash_dash_query_cache() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--query-cache=age < 7".to_string(),
+            "--fields=feedtitle,title,unread".to_string(),
+            "--format".to_string(),
+            "json".to_string(),
+        ]);
+
+        assert_eq!(args.query_cache, Some("age < 7".to_string()));
+        assert_eq!(args.query_fields, vec!["feedtitle", "title", "unread"]);
+        assert_eq!(args.query_format, OutputFormat::Json);
+        assert_eq!(args.return_code, None);
+    }
+
+    #[test]
+    fn t_asks_to_exit_with_usage_error_if_query_filter_is_invalid() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--query-cache=title =~".to_string(),
+        ]);
+
+        assert!(args.should_print_usage);
+        assert_eq!(args.return_code, Some(2));
+        assert_eq!(
+            args.display_msg,
+            "newsboat: Invalid filter expression `title =~': \
+             Parse error at position 8: expected one of: quoted string, range, number"
+        );
+    }
+
+    #[test]
+    fn t_asks_to_exit_with_usage_error_if_query_fields_or_format_are_invalid() {
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--query-cache=unread = \"yes\"".to_string(),
+            "--fields=title,summary".to_string(),
+        ]);
+        assert_eq!(args.return_code, Some(2));
+        assert!(args
+            .display_msg
+            .starts_with("newsboat: Unknown field `summary'. Valid fields are: title, link, "));
+
+        let args = CliArgsParser::new(vec![
+            "newsboat".to_string(),
+            "--query-cache=unread = \"yes\"".to_string(),
+            "--format=csv".to_string(),
+        ]);
+        assert_eq!(args.return_code, Some(2));
+        assert_eq!(
+            args.display_msg,
+            "newsboat: csv: invalid output format (expected `tsv' or `json')"
+        );
+    }
+
+    #[test]
+    fn t_asks_to_exit_with_usage_error_if_fields_or_format_are_provided_without_query_cache() {
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--fields=title".to_string()]);
+        assert_eq!(args.return_code, Some(2));
+        assert_eq!(
+            args.display_msg,
+            "newsboat: --fields can only be used together with --query-cache"
+        );
+
+        let args = CliArgsParser::new(vec!["newsboat".to_string(), "--format=json".to_string()]);
+        assert_eq!(args.return_code, Some(2));
+        assert_eq!(
+            args.display_msg,
+            "newsboat: --format can only be used together with --query-cache"
+        );
+    }
 }
@@ -0,0 +1,248 @@
+//! Errors that end non-interactive modes (import, export, `-x`, vacuum etc.), and how they are
+//! reported to scripts.
+
+use std::fmt::Write;
+
+/// What went wrong. Each category has its own exit code, so scripts can tell them apart without
+/// parsing the message.
+///
+/// \note The numeric values are also used by the C++ side (see `include/clierror.h`); keep them
+/// in sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Anything that doesn't fit the other categories.
+    Other = 0,
+    /// Invalid command-line arguments.
+    Usage = 1,
+    /// Config file couldn't be read or contains errors.
+    Config = 2,
+    /// Urls file (or another URL source) couldn't be read or is empty.
+    UrlsFile = 3,
+    /// Cache couldn't be opened or is locked by another instance.
+    Cache = 4,
+    /// Feeds couldn't be fetched or the remote API rejected us.
+    Network = 5,
+}
+
+impl ErrorCategory {
+    pub fn from_u8(value: u8) -> ErrorCategory {
+        match value {
+            1 => ErrorCategory::Usage,
+            2 => ErrorCategory::Config,
+            3 => ErrorCategory::UrlsFile,
+            4 => ErrorCategory::Cache,
+            5 => ErrorCategory::Network,
+            _ => ErrorCategory::Other,
+        }
+    }
+
+    /// Exit code that Newsboat returns when it stops because of this error.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Other => 1,
+            ErrorCategory::Usage => 2,
+            ErrorCategory::Config => 3,
+            ErrorCategory::UrlsFile => 4,
+            ErrorCategory::Cache => 5,
+            ErrorCategory::Network => 6,
+        }
+    }
+
+    /// Name of the category, as used in the "category" field of JSON reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorCategory::Other => "other",
+            ErrorCategory::Usage => "usage",
+            ErrorCategory::Config => "config",
+            ErrorCategory::UrlsFile => "urls-file",
+            ErrorCategory::Cache => "cache",
+            ErrorCategory::Network => "network",
+        }
+    }
+}
+
+/// How errors are printed to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Human-readable message.
+    Text,
+    /// A single-line JSON object; see `CliError::to_json()`.
+    Json,
+}
+
+impl Default for ErrorFormat {
+    fn default() -> ErrorFormat {
+        ErrorFormat::Text
+    }
+}
+
+impl ErrorFormat {
+    /// Parses the value of `--error-format`.
+    pub fn parse(value: &str) -> Option<ErrorFormat> {
+        match value {
+            "text" => Some(ErrorFormat::Text),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError {
+    pub category: ErrorCategory,
+    pub message: String,
+    /// File in which the error was found, if any.
+    pub file: Option<String>,
+    /// Line of `file` at which the error was found, if known.
+    pub line: Option<u32>,
+}
+
+impl CliError {
+    pub fn new(category: ErrorCategory, message: &str) -> CliError {
+        CliError {
+            category,
+            message: message.to_string(),
+            file: None,
+            line: None,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.category.exit_code()
+    }
+
+    /// Renders the error as a JSON object on a single line, e.g.:
+    ///
+    /// ```text
+    /// {"code":3,"category":"config","message":"unknown command `foo'","file":"/home/user/.newsboat/config","line":12}
+    /// ```
+    ///
+    /// "file" and "line" are only present if they're known.
+    pub fn to_json(&self) -> String {
+        let mut result = String::new();
+        // Writing into a String can't fail.
+        let _ = write!(
+            result,
+            "{{\"code\":{},\"category\":{},\"message\":{}",
+            self.exit_code(),
+            json_string(self.category.name()),
+            json_string(&self.message)
+        );
+        if let Some(ref file) = self.file {
+            let _ = write!(result, ",\"file\":{}", json_string(file));
+            if let Some(line) = self.line {
+                let _ = write!(result, ",\"line\":{}", line);
+            }
+        }
+        result.push('}');
+        result
+    }
+
+    /// Renders the error in the given format. Text errors are just the message: it's up to the
+    /// code that creates them to mention the location where that's helpful.
+    pub fn format(&self, format: ErrorFormat) -> String {
+        match format {
+            ErrorFormat::Json => self.to_json(),
+            ErrorFormat::Text => self.message.clone(),
+        }
+    }
+}
+
+/// Quotes and escapes `input` as a JSON string.
+pub(crate) fn json_string(input: &str) -> String {
+    let mut result = String::with_capacity(input.len() + 2);
+    result.push('"');
+    for c in input.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(result, "\\u{:04x}", c as u32);
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_each_category_has_its_own_exit_code() {
+        let categories = [
+            ErrorCategory::Other,
+            ErrorCategory::Usage,
+            ErrorCategory::Config,
+            ErrorCategory::UrlsFile,
+            ErrorCategory::Cache,
+            ErrorCategory::Network,
+        ];
+        let codes = categories.iter().map(|c| c.exit_code()).collect::<Vec<_>>();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6]);
+
+        for category in &categories {
+            assert_eq!(ErrorCategory::from_u8(*category as u8), *category);
+        }
+        assert_eq!(ErrorCategory::from_u8(42), ErrorCategory::Other);
+    }
+
+    #[test]
+    fn t_to_json_includes_code_category_and_message() {
+        let error = CliError::new(ErrorCategory::UrlsFile, "no URLs configured");
+        assert_eq!(
+            error.to_json(),
+            r#"{"code":4,"category":"urls-file","message":"no URLs configured"}"#
+        );
+    }
+
+    #[test]
+    fn t_to_json_includes_location_if_known() {
+        let mut error = CliError::new(ErrorCategory::Config, "unknown command `foo'");
+        error.file = Some("/home/user/.newsboat/config".to_string());
+        assert_eq!(
+            error.to_json(),
+            r#"{"code":3,"category":"config","message":"unknown command `foo'","file":"/home/user/.newsboat/config"}"#
+        );
+
+        error.line = Some(12);
+        assert_eq!(
+            error.to_json(),
+            r#"{"code":3,"category":"config","message":"unknown command `foo'","file":"/home/user/.newsboat/config","line":12}"#
+        );
+    }
+
+    #[test]
+    fn t_to_json_escapes_special_characters() {
+        let error = CliError::new(
+            ErrorCategory::Network,
+            "\"quoted\" back\\slash\nnew line\ttab\u{1}bell",
+        );
+        assert_eq!(
+            error.to_json(),
+            r#"{"code":6,"category":"network","message":"\"quoted\" back\\slash\nnew line\ttab\u0001bell"}"#
+        );
+    }
+
+    #[test]
+    fn t_text_format_is_just_the_message() {
+        let mut error = CliError::new(ErrorCategory::Config, "oops");
+        error.file = Some("config".to_string());
+        error.line = Some(3);
+        assert_eq!(error.format(ErrorFormat::Text), "oops");
+        assert_eq!(error.format(ErrorFormat::Json), error.to_json());
+    }
+
+    #[test]
+    fn t_parses_error_format_names() {
+        assert_eq!(ErrorFormat::parse("text"), Some(ErrorFormat::Text));
+        assert_eq!(ErrorFormat::parse("json"), Some(ErrorFormat::Json));
+        assert_eq!(ErrorFormat::parse("JSON"), None);
+        assert_eq!(ErrorFormat::parse(""), None);
+    }
+}
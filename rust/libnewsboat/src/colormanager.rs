@@ -0,0 +1,488 @@
+//! Parses and stores the `color` config command.
+//!
+//! The syntax is `color <element> <foreground> <background> [<attribute> ...]`. Colors are the
+//! eight named ones, `color0` to `color255`, `default`, or `#rrggbb`; the latter is mapped to the
+//! nearest color the terminal can show when the style is applied (see `termcaps`). Since an
+//! unquoted `#` starts a comment, hex colors have to be quoted in the config file.
+
+use crate::termcaps::{self, BASIC_COLORS};
+use crate::utils;
+use gettextrs::gettext;
+use std::collections::BTreeMap;
+use std::fmt;
+use strprintf::fmt;
+
+/// Elements that can be colored.
+pub const ELEMENTS: &[&str] = &[
+    "listnormal",
+    "listfocus",
+    "listnormal_unread",
+    "listfocus_unread",
+    "info",
+    "background",
+    "article",
+    "end-of-text-marker",
+];
+
+/// Attributes that STFL can apply to text.
+pub const ATTRIBUTES: &[&str] = &[
+    "bold",
+    "underline",
+    "reverse",
+    "blink",
+    "dim",
+    "standout",
+    "protect",
+    "invis",
+];
+
+/// A foreground or background color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// The terminal's own color.
+    Default,
+    /// One of the eight basic colors, e.g. "red".
+    Named(&'static str),
+    /// `color0` to `color255`.
+    Palette(u8),
+    /// `#rrggbb`.
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    pub fn parse(color: &str) -> Result<Color, ColorErrorKind> {
+        if color == "default" {
+            return Ok(Color::Default);
+        }
+        if let Some(name) = BASIC_COLORS.iter().find(|name| **name == color) {
+            return Ok(Color::Named(name));
+        }
+        if color.starts_with('#') {
+            return termcaps::parse_hex_color(color)
+                .map(|(r, g, b)| Color::Rgb(r, g, b))
+                .ok_or(ColorErrorKind::InvalidColor);
+        }
+        if let (Some("color"), Some(digits)) = (color.get(..5), color.get(5..)) {
+            let is_number = !digits.is_empty()
+                && digits.chars().all(|c| c.is_ascii_digit())
+                && (digits == "0" || !digits.starts_with('0'));
+            if is_number {
+                return digits
+                    .parse::<u8>()
+                    .map(Color::Palette)
+                    .map_err(|_| ColorErrorKind::ColorOutOfRange);
+            }
+        }
+        Err(ColorErrorKind::InvalidColor)
+    }
+}
+
+impl fmt::Display for Color {
+    /// Writes the color the way the config spells it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::Default => write!(f, "default"),
+            Color::Named(name) => write!(f, "{}", name),
+            Color::Palette(index) => write!(f, "color{}", index),
+            Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+/// The colors and attributes of an element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStyle {
+    pub fg: Color,
+    pub bg: Color,
+    /// Entries of `ATTRIBUTES`, in the order in which they were configured.
+    pub attributes: Vec<&'static str>,
+}
+
+impl TextStyle {
+    /// The style in STFL's syntax, e.g. "fg=red,attr=bold". Default colors are left out, so that
+    /// STFL picks the terminal's own. RGB colors are written as is; pass the style through
+    /// `termcaps::degrade_style()` before handing it to STFL.
+    pub fn to_stfl(&self) -> String {
+        let mut parts = Vec::new();
+        if self.fg != Color::Default {
+            parts.push(format!("fg={}", self.fg));
+        }
+        if self.bg != Color::Default {
+            parts.push(format!("bg={}", self.bg));
+        }
+        for attribute in &self.attributes {
+            parts.push(format!("attr={}", attribute));
+        }
+        parts.join(",")
+    }
+}
+
+/// What's wrong with an argument of the `color` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorErrorKind {
+    /// The element, the foreground or the background color is missing.
+    TooFewArguments,
+    UnknownElement {
+        /// Elements that look similar, closest first.
+        suggestions: Vec<&'static str>,
+    },
+    InvalidColor,
+    /// A palette color above `color255`.
+    ColorOutOfRange,
+    InvalidAttribute {
+        /// Attributes that look similar, closest first.
+        suggestions: Vec<&'static str>,
+    },
+}
+
+/// An error produced by `ColorManager::handle_color()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorError {
+    /// One-based index of the offending argument; the element is the first one.
+    pub position: usize,
+    /// The offending argument itself (empty if it's missing).
+    pub argument: String,
+    pub kind: ColorErrorKind,
+}
+
+impl ColorError {
+    /// Internationalized description of the error, including the suggestions if there are any.
+    pub fn to_message(&self) -> String {
+        let reason = match &self.kind {
+            ColorErrorKind::TooFewArguments => {
+                gettext("expected an element, a foreground and a background color")
+            }
+            ColorErrorKind::UnknownElement { suggestions } => with_suggestions(
+                fmt!(
+                    &gettext("`%s' is not a valid configuration element"),
+                    &self.argument
+                ),
+                suggestions,
+            ),
+            ColorErrorKind::InvalidColor => {
+                fmt!(&gettext("`%s' is not a valid color"), &self.argument)
+            }
+            ColorErrorKind::ColorOutOfRange => fmt!(
+                &gettext("`%s' is out of range (color0 to color255)"),
+                &self.argument
+            ),
+            ColorErrorKind::InvalidAttribute { suggestions } => with_suggestions(
+                fmt!(&gettext("`%s' is not a valid attribute"), &self.argument),
+                suggestions,
+            ),
+        };
+        fmt!(&gettext("argument %u: %s"), self.position as u32, reason)
+    }
+}
+
+fn with_suggestions(message: String, suggestions: &[&str]) -> String {
+    if suggestions.is_empty() {
+        message
+    } else {
+        fmt!(
+            &gettext("%s. Did you mean: %s?"),
+            message,
+            suggestions.join(", ")
+        )
+    }
+}
+
+/// Parses a foreground color, a background color and any number of attributes, i.e. the
+/// arguments of `color` that follow the element. In the error, positions are one-based indices
+/// into `params`.
+pub fn parse_style(params: &[String]) -> Result<TextStyle, ColorError> {
+    let error = |index: usize, kind: ColorErrorKind| ColorError {
+        position: index + 1,
+        argument: params.get(index).cloned().unwrap_or_default(),
+        kind,
+    };
+
+    if params.len() < 2 {
+        return Err(error(params.len(), ColorErrorKind::TooFewArguments));
+    }
+
+    let fg = Color::parse(&params[0]).map_err(|kind| error(0, kind))?;
+    let bg = Color::parse(&params[1]).map_err(|kind| error(1, kind))?;
+
+    let mut attributes = Vec::new();
+    for (index, attribute) in params.iter().enumerate().skip(2) {
+        match ATTRIBUTES.iter().find(|known| **known == attribute) {
+            Some(known) => attributes.push(*known),
+            None => {
+                let suggestions = utils::suggest(ATTRIBUTES.iter().copied(), attribute);
+                return Err(error(
+                    index,
+                    ColorErrorKind::InvalidAttribute { suggestions },
+                ));
+            }
+        }
+    }
+
+    Ok(TextStyle { fg, bg, attributes })
+}
+
+/// Styles of the elements configured with `color`.
+#[derive(Debug, Default)]
+pub struct ColorManager {
+    styles: BTreeMap<&'static str, TextStyle>,
+}
+
+impl ColorManager {
+    pub fn new() -> ColorManager {
+        ColorManager::default()
+    }
+
+    /// Validates the arguments of a `color` command and, if they are all fine, stores the style.
+    /// A later command for the same element replaces the earlier one.
+    pub fn handle_color(&mut self, params: &[String]) -> Result<(), ColorError> {
+        let error = |index: usize, kind: ColorErrorKind| ColorError {
+            position: index + 1,
+            argument: params.get(index).cloned().unwrap_or_default(),
+            kind,
+        };
+
+        if params.len() < 3 {
+            return Err(error(params.len(), ColorErrorKind::TooFewArguments));
+        }
+
+        let element = match ELEMENTS.iter().find(|element| **element == params[0]) {
+            Some(element) => *element,
+            None => {
+                let suggestions = utils::suggest(ELEMENTS.iter().copied(), &params[0]);
+                return Err(error(0, ColorErrorKind::UnknownElement { suggestions }));
+            }
+        };
+        let style = parse_style(&params[1..]).map_err(|error| ColorError {
+            position: error.position + 1,
+            ..error
+        })?;
+        self.styles.insert(element, style);
+        Ok(())
+    }
+
+    /// Configured elements and their styles, ordered by the name of the element.
+    pub fn styles(&self) -> impl Iterator<Item = (&'static str, &TextStyle)> {
+        self.styles.iter().map(|(element, style)| (*element, style))
+    }
+
+    pub fn style(&self, element: &str) -> Option<&TextStyle> {
+        self.styles.get(element)
+    }
+
+    /// `color` commands that recreate the configured styles.
+    pub fn dump_config(&self) -> Vec<String> {
+        // Quote hex colors, or else `#` would start a comment
+        let quoted = |color: Color| match color {
+            Color::Rgb(..) => format!("\"{}\"", color),
+            _ => color.to_string(),
+        };
+        self.styles()
+            .map(|(element, style)| {
+                let mut line = format!(
+                    "color {} {} {}",
+                    element,
+                    quoted(style.fg),
+                    quoted(style.bg)
+                );
+                for attribute in &style.attributes {
+                    line.push(' ');
+                    line.push_str(attribute);
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::termcaps::ColorSupport;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn handle(manager: &mut ColorManager, line: &str) -> Result<(), ColorError> {
+        manager.handle_color(&args(line))
+    }
+
+    fn error(line: &str) -> ColorError {
+        handle(&mut ColorManager::new(), line).unwrap_err()
+    }
+
+    #[test]
+    fn t_valid_commands_are_stored_as_typed_styles() {
+        let mut manager = ColorManager::new();
+        handle(&mut manager, "listfocus cyan default bold underline").unwrap();
+        handle(&mut manager, "info color0 color255").unwrap();
+
+        assert_eq!(
+            manager.style("listfocus"),
+            Some(&TextStyle {
+                fg: Color::Named("cyan"),
+                bg: Color::Default,
+                attributes: vec!["bold", "underline"],
+            })
+        );
+        assert_eq!(manager.style("info").unwrap().fg, Color::Palette(0));
+        assert_eq!(manager.style("info").unwrap().bg, Color::Palette(255));
+        assert_eq!(manager.style("article"), None);
+
+        handle(&mut manager, "listfocus red blue").unwrap();
+        assert_eq!(manager.styles().count(), 2);
+        assert!(manager.style("listfocus").unwrap().attributes.is_empty());
+    }
+
+    #[test]
+    fn t_missing_arguments_are_reported_at_the_first_missing_position() {
+        let err = error("listfocus red");
+        assert_eq!(err.position, 3);
+        assert_eq!(err.kind, ColorErrorKind::TooFewArguments);
+        assert_eq!(error("").position, 1);
+    }
+
+    #[test]
+    fn t_unknown_elements_come_with_suggestions() {
+        let err = error("lisfocus red blue");
+        assert_eq!(err.position, 1);
+        assert_eq!(
+            err.kind,
+            ColorErrorKind::UnknownElement {
+                suggestions: vec!["listfocus"]
+            }
+        );
+        assert_eq!(
+            err.to_message(),
+            "argument 1: `lisfocus' is not a valid configuration element. Did you mean: listfocus?"
+        );
+
+        assert_eq!(
+            error("awesome red blue").to_message(),
+            "argument 1: `awesome' is not a valid configuration element"
+        );
+    }
+
+    #[test]
+    fn t_invalid_colors_are_rejected() {
+        for color in &[
+            "awesome", "Red", "color", "color-1", "color007", "colorx", "#fff", "#gggggg",
+        ] {
+            let err = error(&format!("info {} default", color));
+            assert_eq!(err.position, 2, "{}", color);
+            assert_eq!(err.kind, ColorErrorKind::InvalidColor, "{}", color);
+
+            let err = error(&format!("info default {}", color));
+            assert_eq!(err.position, 3, "{}", color);
+            assert_eq!(
+                err.to_message(),
+                format!("argument 3: `{}' is not a valid color", color)
+            );
+        }
+    }
+
+    #[test]
+    fn t_palette_colors_above_255_are_out_of_range() {
+        for color in &["color256", "color1000", "color99999999999999999999"] {
+            let err = error(&format!("info {} default", color));
+            assert_eq!(err.position, 2);
+            assert_eq!(err.kind, ColorErrorKind::ColorOutOfRange, "{}", color);
+        }
+        assert_eq!(
+            error("info default color256").to_message(),
+            "argument 3: `color256' is out of range (color0 to color255)"
+        );
+    }
+
+    #[test]
+    fn t_invalid_attributes_are_reported_at_their_position() {
+        let err = error("info red blue bold unerline");
+        assert_eq!(err.position, 5);
+        assert_eq!(
+            err.kind,
+            ColorErrorKind::InvalidAttribute {
+                suggestions: vec!["underline"]
+            }
+        );
+        assert_eq!(
+            err.to_message(),
+            "argument 5: `unerline' is not a valid attribute. Did you mean: underline?"
+        );
+
+        let err = error("info red blue default");
+        assert_eq!(err.position, 4);
+        assert_eq!(
+            err.kind,
+            ColorErrorKind::InvalidAttribute {
+                suggestions: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn t_failed_commands_leave_the_styles_alone() {
+        let mut manager = ColorManager::new();
+        handle(&mut manager, "info red blue").unwrap();
+        assert!(handle(&mut manager, "info green blue foo").is_err());
+        assert_eq!(manager.style("info").unwrap().fg, Color::Named("red"));
+    }
+
+    #[test]
+    fn t_hex_colors_are_accepted_and_mapped_down_when_applied() {
+        let mut manager = ColorManager::new();
+        handle(&mut manager, "article #FF0000 #eeeeee bold").unwrap();
+
+        let style = manager.style("article").unwrap();
+        assert_eq!(style.fg, Color::Rgb(255, 0, 0));
+        assert_eq!(style.bg, Color::Rgb(238, 238, 238));
+        assert_eq!(style.to_stfl(), "fg=#ff0000,bg=#eeeeee,attr=bold");
+
+        assert_eq!(
+            termcaps::degrade_style(&style.to_stfl(), ColorSupport::TrueColor),
+            "fg=color196,bg=color255,attr=bold"
+        );
+        assert_eq!(
+            termcaps::degrade_style(&style.to_stfl(), ColorSupport::Colors8),
+            "fg=red,bg=white,attr=bold"
+        );
+    }
+
+    #[test]
+    fn t_stfl_styles_leave_out_default_colors() {
+        let mut manager = ColorManager::new();
+        handle(&mut manager, "info default default").unwrap();
+        handle(&mut manager, "listnormal default color33 reverse dim").unwrap();
+
+        assert_eq!(manager.style("info").unwrap().to_stfl(), "");
+        assert_eq!(
+            manager.style("listnormal").unwrap().to_stfl(),
+            "bg=color33,attr=reverse,attr=dim"
+        );
+    }
+
+    #[test]
+    fn t_dump_config_recreates_the_commands() {
+        let mut manager = ColorManager::new();
+        assert!(manager.dump_config().is_empty());
+
+        handle(&mut manager, "listnormal black yellow underline standout").unwrap();
+        handle(&mut manager, "background color7 #00Ff80").unwrap();
+
+        let dumped = manager.dump_config();
+        assert_eq!(
+            dumped,
+            vec![
+                "color background color7 \"#00ff80\"",
+                "color listnormal black yellow underline standout",
+            ]
+        );
+
+        // The config parser strips the quotes, so feed the arguments back without them
+        let mut reparsed = ColorManager::new();
+        for line in &dumped {
+            let unquoted = line.replace('"', "");
+            reparsed.handle_color(&args(&unquoted)[1..]).unwrap();
+        }
+        assert_eq!(reparsed.dump_config(), dumped);
+    }
+}
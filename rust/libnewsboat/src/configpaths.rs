@@ -1,10 +1,13 @@
 use crate::cliargsparser::CliArgsParser;
+use crate::legacyconfig::{self, MovedDir};
 use crate::logger::{self, Level};
 use crate::utils;
 use gettextrs::gettext;
-use std::fs::{self, DirBuilder};
+use std::env;
+use std::fmt;
+use std::fs;
 use std::io;
-use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use strprintf::fmt;
 
@@ -13,6 +16,7 @@ pub const NEWSBOAT_CONFIG_SUBDIR: &str = ".newsboat";
 pub const NEWSBEUTER_SUBDIR_XDG: &str = "newsbeuter";
 pub const NEWSBEUTER_CONFIG_SUBDIR: &str = ".newsbeuter";
 pub const LOCK_SUFFIX: &str = ".lock";
+pub const PROFILES_SUBDIR: &str = "profiles";
 
 const URLS_FILENAME: &str = "urls";
 const CACHE_FILENAME: &str = "cache.db";
@@ -20,6 +24,8 @@ const CONFIG_FILENAME: &str = "config";
 const QUEUE_FILENAME: &str = "queue";
 const SEARCH_HISTORY_FILENAME: &str = "history.search";
 const CMDLINE_HISTORY_FILENAME: &str = "history.cmdline";
+const FEEDLIST_SNAPSHOT_FILENAME: &str = "feedlist.snapshot";
+const URLS_OVERLAY_FILENAME: &str = "urls.overlay";
 
 #[derive(Debug, Default)]
 pub struct ConfigPaths {
@@ -35,14 +41,32 @@ pub struct ConfigPaths {
     ///
     /// This can be ~/.newsboat, or ~/.local/share/newsboat, or something else entirely if user
     /// changed it through the command line parameter.
+    ///
+    /// \note If a profile is used, its data is in a subdirectory of this one; see
+    /// `profile_data_dir()`.
     data_dir: PathBuf,
 
     /// Path to Newsboat's configuration directory.
     ///
     /// This can be ~/.newsboat, or ~/.config/newsboat, or something else entirely if user changed
     /// it through the command line parameter.
+    ///
+    /// \note If a profile is used, its configs are in a subdirectory of this one; see
+    /// `profile_config_dir()`.
     config_dir: PathBuf,
 
+    /// Path to Newsboat's state directory, which holds the lock file, the queue and the histories.
+    ///
+    /// This can be ~/.newsboat, or ~/.local/state/newsboat, or the data dir if the user already
+    /// keeps those files there.
+    ///
+    /// \note If a profile is used, its state is in a subdirectory of this one; see
+    /// `profile_state_dir()`.
+    state_dir: PathBuf,
+
+    /// Name of the profile specified by the user, if any.
+    profile: Option<String>,
+
     url_file: PathBuf,
     cache_file: PathBuf,
     config_file: PathBuf,
@@ -50,9 +74,14 @@ pub struct ConfigPaths {
     queue_file: PathBuf,
     search_file: PathBuf,
     cmdline_file: PathBuf,
+    feedlist_snapshot_file: PathBuf,
+    url_overlay_file: PathBuf,
 
     silent: bool,
     using_nonstandard_configs: bool,
+
+    /// Lines of Newsbeuter's config that migration couldn't translate, as "file:line: problem".
+    untranslated_config_lines: Vec<String>,
 }
 
 impl ConfigPaths {
@@ -63,6 +92,8 @@ impl ConfigPaths {
 
             data_dir: PathBuf::new(),
             config_dir: PathBuf::new(),
+            state_dir: PathBuf::new(),
+            profile: None,
 
             url_file: PathBuf::new(),
             cache_file: PathBuf::new(),
@@ -71,9 +102,13 @@ impl ConfigPaths {
             queue_file: PathBuf::new(),
             search_file: PathBuf::new(),
             cmdline_file: PathBuf::new(),
+            feedlist_snapshot_file: PathBuf::new(),
+            url_overlay_file: PathBuf::new(),
 
             silent: false,
             using_nonstandard_configs: false,
+
+            untranslated_config_lines: Vec::new(),
         };
 
         let env_home = utils::home_dir();
@@ -100,7 +135,8 @@ impl ConfigPaths {
         config_paths
     }
 
-    fn migrate_data_from_newsbeuter_xdg(&mut self) -> bool {
+    /// Plans the migration from Newsbeuter's XDG dirs, if they exist and Newsboat's don't.
+    fn plan_migration_from_newsbeuter_xdg(&self) -> Option<MigrationPlan> {
         // This can't panic because we've tested we can find the home directory in ConfigPaths::new
         // This should be replaced with proper error handling after this is not used by c++ anymore
         let xdg_dirs = xdg::BaseDirectories::new().unwrap();
@@ -114,13 +150,14 @@ impl ConfigPaths {
         let newsboat_data_dir = xdg_data_dir.join(NEWSBOAT_SUBDIR_XDG);
 
         if !newsbeuter_config_dir.is_dir() {
-            return false;
+            return None;
         }
 
         fn exists(path: &Path) -> bool {
             let exists = path.exists();
             if exists {
                 log!(
+                    "configpaths",
                     Level::Debug,
                     "{:?} already exists, aborting XDG migration.",
                     path
@@ -129,145 +166,191 @@ impl ConfigPaths {
             exists
         }
 
-        if exists(&newsboat_config_dir) {
-            return false;
-        }
-
-        if exists(&newsboat_data_dir) {
-            return false;
-        }
-
-        self.config_dir = newsboat_config_dir;
-        self.data_dir = newsboat_data_dir;
-
-        if !self.silent {
-            eprintln!(
-                "{}",
-                &gettext("Migrating configs and data from Newsbeuter's XDG dirs...")
-            );
-        }
-
-        if !try_mkdir(&self.config_dir) {
-            return false;
-        }
-
-        if !try_mkdir(&self.data_dir) {
-            return false;
+        if exists(&newsboat_config_dir) || exists(&newsboat_data_dir) {
+            return None;
         }
 
-        // We ignore the return codes because it's okay if some files are missing.
-
-        // in config
-        let _ = migrate_file(&newsbeuter_config_dir, &self.config_dir, URLS_FILENAME);
-        let _ = migrate_file(&newsbeuter_config_dir, &self.config_dir, CONFIG_FILENAME);
+        Some(MigrationPlan::new(
+            true,
+            vec![
+                PlannedDir {
+                    from: newsbeuter_config_dir,
+                    to: newsboat_config_dir,
+                    files: &[URLS_FILENAME, CONFIG_FILENAME],
+                },
+                PlannedDir {
+                    from: newsbeuter_data_dir,
+                    to: newsboat_data_dir,
+                    files: &[
+                        CACHE_FILENAME,
+                        QUEUE_FILENAME,
+                        SEARCH_HISTORY_FILENAME,
+                        CMDLINE_HISTORY_FILENAME,
+                    ],
+                },
+            ],
+        ))
+    }
 
-        // in data
-        let _ = migrate_file(&newsbeuter_data_dir, &self.data_dir, CACHE_FILENAME);
-        let _ = migrate_file(&newsbeuter_data_dir, &self.data_dir, QUEUE_FILENAME);
-        let _ = migrate_file(
-            &newsbeuter_data_dir,
-            &self.data_dir,
-            SEARCH_HISTORY_FILENAME,
-        );
-        let _ = migrate_file(
-            &newsbeuter_data_dir,
-            &self.data_dir,
-            CMDLINE_HISTORY_FILENAME,
-        );
-
-        true
-    }
-
-    fn migrate_data_from_newsbeuter_simple(&self) -> bool {
+    /// Plans the migration from ~/.newsbeuter, if it exists and ~/.newsboat doesn't.
+    fn plan_migration_from_newsbeuter_simple(&self) -> Option<MigrationPlan> {
         let newsbeuter_dir = self.env_home.join(NEWSBEUTER_CONFIG_SUBDIR);
 
         if !newsbeuter_dir.is_dir() {
-            return false;
+            return None;
         }
 
         let newsboat_dir = self.env_home.join(NEWSBOAT_CONFIG_SUBDIR);
 
         if newsboat_dir.exists() {
             log!(
+                "configpaths",
                 Level::Debug,
                 "{:?} already exists, aborting migration.",
                 newsboat_dir
             );
-            return false;
-        }
-
-        if !self.silent {
-            eprintln!(
-                "{}",
-                &gettext("Migrating configs and data from ~/.newsbeuter/...")
-            );
+            return None;
         }
 
-        match mkdir(&newsboat_dir, 0o700) {
-            Ok(_) => (),
-            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => (),
-            Err(err) => {
-                if !self.silent {
-                    eprintln!(
-                        "{}",
-                        &fmt!(
-                            &gettext("Aborting migration because mkdir on `%s' failed: %s"),
-                            &newsboat_dir.to_string_lossy().into_owned(),
-                            err.to_string()
-                        )
-                    );
-                }
-                return false;
-            }
-        };
-
-        // We ignore the return codes because it's okay if some files are missing.
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, URLS_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, CACHE_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, CONFIG_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, QUEUE_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, SEARCH_HISTORY_FILENAME);
-        let _ = migrate_file(&newsbeuter_dir, &newsboat_dir, CMDLINE_HISTORY_FILENAME);
-
-        true
+        Some(MigrationPlan::new(
+            false,
+            vec![PlannedDir {
+                from: newsbeuter_dir,
+                to: newsboat_dir,
+                files: &[
+                    URLS_FILENAME,
+                    CONFIG_FILENAME,
+                    CACHE_FILENAME,
+                    QUEUE_FILENAME,
+                    SEARCH_HISTORY_FILENAME,
+                    CMDLINE_HISTORY_FILENAME,
+                ],
+            }],
+        ))
     }
 
-    fn migrate_data_from_newsbeuter(&mut self) -> bool {
-        let mut migrated = self.migrate_data_from_newsbeuter_xdg();
-
-        if migrated {
-            // Re-running to pick up XDG dirs
-            self.find_dirs();
-        } else {
-            migrated = self.migrate_data_from_newsbeuter_simple();
+    /// Copies Newsbeuter's config from `input_filepath` to `output_filepath`, translating what
+    /// changed meaning. Lines that can't be translated are kept as they are, and reported.
+    fn migrate_config(
+        &mut self,
+        input_filepath: &Path,
+        output_filepath: &Path,
+        moved: &[MovedDir],
+    ) -> io::Result<()> {
+        let contents = fs::read(input_filepath)?;
+        let mode = fs::metadata(input_filepath)?.permissions().mode();
+
+        let translation =
+            legacyconfig::translate(&String::from_utf8_lossy(&contents), &self.env_home, moved);
+        for untranslated in translation.untranslated {
+            let msg = fmt!(
+                "%s:%u: %s",
+                input_filepath.to_string_lossy().into_owned(),
+                untranslated.line as u32,
+                untranslated.message
+            );
+            log!(
+                "configpaths",
+                Level::Warn,
+                "Couldn't translate Newsbeuter's config: {}",
+                msg
+            );
+            if !self.silent {
+                eprintln!("{}", msg);
+            }
+            self.untranslated_config_lines.push(msg);
         }
 
-        migrated
+        utils::atomic_write(output_filepath, translation.config.as_bytes(), mode)?;
+        Ok(())
     }
 
     pub fn create_dirs(&self) -> bool {
-        try_mkdir(&self.config_dir) && try_mkdir(&self.data_dir)
+        try_mkdir(self.profile_config_dir())
+            && try_mkdir(self.profile_data_dir())
+            && try_mkdir(self.profile_state_dir())
     }
 
     fn find_dirs(&mut self) {
         self.config_dir = self.env_home.join(NEWSBOAT_CONFIG_SUBDIR);
 
         self.data_dir = self.config_dir.clone();
+        self.state_dir = self.config_dir.clone();
 
-        // Will change config_dir and data_dir to point to XDG if XDG
+        // Will change config_dir, data_dir and state_dir to point to XDG if XDG
         // directories are available.
         self.find_dirs_xdg();
 
+        self.find_files();
+    }
+
+    /// Points all file paths into the config, data and state dirs, or into the profile's
+    /// subdirectories thereof if a profile is used.
+    fn find_files(&mut self) {
+        let config_dir = self.profile_config_dir();
+        let data_dir = self.profile_data_dir();
+        let state_dir = self.profile_state_dir();
+
         // in config
-        self.url_file = self.config_dir.join(URLS_FILENAME);
-        self.config_file = self.config_dir.join(CONFIG_FILENAME);
+        self.url_file = config_dir.join(URLS_FILENAME);
+        self.config_file = config_dir.join(CONFIG_FILENAME);
 
         // in data
-        self.cache_file = self.data_dir.join(CACHE_FILENAME);
-        self.lock_file = self.data_dir.join(CACHE_FILENAME.to_owned() + LOCK_SUFFIX);
-        self.queue_file = self.data_dir.join(QUEUE_FILENAME);
-        self.search_file = self.data_dir.join(SEARCH_HISTORY_FILENAME);
-        self.cmdline_file = self.data_dir.join(CMDLINE_HISTORY_FILENAME);
+        self.cache_file = data_dir.join(CACHE_FILENAME);
+        self.feedlist_snapshot_file = data_dir.join(FEEDLIST_SNAPSHOT_FILENAME);
+        self.url_overlay_file = data_dir.join(URLS_OVERLAY_FILENAME);
+
+        // in state
+        let state_file = |name: &str| {
+            let path = state_dir.join(name);
+            let old_path = data_dir.join(name);
+            // Keep using the file where it already is, so that updating Newsboat doesn't lose
+            // the user's history and queue
+            if !path.exists() && old_path.exists() {
+                old_path
+            } else {
+                path
+            }
+        };
+        self.lock_file = state_file(&(CACHE_FILENAME.to_owned() + LOCK_SUFFIX));
+        self.queue_file = state_file(QUEUE_FILENAME);
+        self.search_file = state_file(SEARCH_HISTORY_FILENAME);
+        self.cmdline_file = state_file(CMDLINE_HISTORY_FILENAME);
+    }
+
+    /// Directory with the profile's configs, or the config dir itself if no profile is used.
+    fn profile_config_dir(&self) -> PathBuf {
+        match self.profile {
+            Some(ref profile) => self.config_dir.join(PROFILES_SUBDIR).join(profile),
+            None => self.config_dir.clone(),
+        }
+    }
+
+    /// Directory with the profile's data, or the data dir itself if no profile is used.
+    fn profile_data_dir(&self) -> PathBuf {
+        match self.profile {
+            Some(ref profile) => self.data_dir.join(PROFILES_SUBDIR).join(profile),
+            None => self.data_dir.clone(),
+        }
+    }
+
+    /// Directory with the profile's state, or the state dir itself if no profile is used.
+    fn profile_state_dir(&self) -> PathBuf {
+        match self.profile {
+            Some(ref profile) => self.state_dir.join(PROFILES_SUBDIR).join(profile),
+            None => self.state_dir.clone(),
+        }
+    }
+
+    /// Path to `$XDG_STATE_HOME`, or its default, `~/.local/state`.
+    ///
+    /// The xdg crate we use predates the state dir, so we resolve it ourselves. As the spec says,
+    /// a relative path in the variable is invalid and should be ignored.
+    fn xdg_state_home(&self) -> PathBuf {
+        match env::var_os("XDG_STATE_HOME").map(PathBuf::from) {
+            Some(path) if path.is_absolute() => path,
+            _ => self.env_home.join(".local").join("state"),
+        }
     }
 
     fn find_dirs_xdg(&mut self) {
@@ -286,6 +369,15 @@ impl ConfigPaths {
          * At this point, we're confident we'll be using XDG. We don't check if
          * data dir exists, because if it doesn't we'll create it. */
 
+        /* A fresh install, i.e. one without a data dir yet, goes straight to the state dir. An
+         * existing install keeps its state files in the data dir until the user moves them. */
+        let state_dir = self.xdg_state_home().join(NEWSBOAT_SUBDIR_XDG);
+        self.state_dir = if state_dir.is_dir() || !data_dir.is_dir() {
+            state_dir
+        } else {
+            data_dir.clone()
+        };
+
         self.config_dir = config_dir;
         self.data_dir = data_dir;
     }
@@ -306,12 +398,24 @@ impl ConfigPaths {
     }
 
     /// Initializes paths to config, cache etc. from CLI arguments.
+    ///
+    /// If a profile is specified, all paths point into the profile's subdirectories, except for
+    /// those that were explicitly specified too: explicit paths win, but a warning is printed.
     pub fn process_args(&mut self, args: &CliArgsParser) {
+        self.silent = args.silent;
+
+        if let Some(ref profile) = args.profile {
+            self.profile = Some(profile.to_owned());
+            self.find_files();
+        }
+
         if let Some(ref url_file) = args.url_file {
+            self.warn_about_profile_override("--url-file");
             self.url_file = url_file.to_owned();
         }
 
         if let Some(ref cache_file) = args.cache_file {
+            self.warn_about_profile_override("--cache-file");
             self.cache_file = cache_file.to_owned();
         }
 
@@ -320,22 +424,158 @@ impl ConfigPaths {
         }
 
         if let Some(ref config_file) = args.config_file {
+            self.warn_about_profile_override("--config-file");
             self.config_file = config_file.to_owned();
         }
 
-        self.silent = args.silent;
         self.using_nonstandard_configs = args.using_nonstandard_configs();
     }
 
+    fn warn_about_profile_override(&self, option: &str) {
+        if let Some(ref profile) = self.profile {
+            let msg = fmt!(
+                &gettext("Warning: %s overrides the path from profile `%s'"),
+                option,
+                profile
+            );
+            log!("configpaths", Level::Warn, "{}", msg);
+            if !self.silent {
+                eprintln!("{}", msg);
+            }
+        }
+    }
+
+    /// Names of the existing profiles, in alphabetical order.
+    ///
+    /// A profile exists if it has a subdirectory in either the config or the data dir.
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles = Vec::new();
+        for dir in &[&self.config_dir, &self.data_dir] {
+            let entries = match fs::read_dir(dir.join(PROFILES_SUBDIR)) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(Result::ok) {
+                if entry.path().is_dir() {
+                    profiles.push(entry.file_name().to_string_lossy().into_owned());
+                }
+            }
+        }
+        profiles.sort();
+        profiles.dedup();
+        profiles
+    }
+
     /// Migrate configs and data from Newsbeuter if they exist. Return `true` if migrated
     /// something, `false` otherwise.
     pub fn try_migrate_from_newsbeuter(&mut self) -> bool {
-        if !self.using_nonstandard_configs && !&self.url_file.exists() {
-            return self.migrate_data_from_newsbeuter();
+        let plan = match self.plan_migration() {
+            Some(plan) => plan,
+            // No migration occurred.
+            None => return false,
+        };
+
+        if !self.silent {
+            let msg = if plan.xdg {
+                gettext("Migrating configs and data from Newsbeuter's XDG dirs...")
+            } else {
+                gettext("Migrating configs and data from ~/.newsbeuter/...")
+            };
+            eprintln!("{}", msg);
+        }
+
+        match self.apply_migration(&plan) {
+            Ok(report) => {
+                if !self.silent {
+                    eprint!("{}", report.describe());
+                }
+                true
+            }
+            Err(error) => {
+                if !self.silent {
+                    eprintln!("{}", error);
+                }
+                false
+            }
+        }
+    }
+
+    /// Decides what a migration from Newsbeuter would copy, without touching any files.
+    ///
+    /// Returns `None` if there is nothing to migrate: Newsboat already has its own urls file or
+    /// directories, paths were given on the command line, or there are no Newsbeuter directories.
+    pub fn plan_migration(&self) -> Option<MigrationPlan> {
+        if self.using_nonstandard_configs || self.url_file.exists() {
+            return None;
+        }
+
+        self.plan_migration_from_newsbeuter_xdg()
+            .or_else(|| self.plan_migration_from_newsbeuter_simple())
+    }
+
+    /// Creates Newsboat's directories and copies the files of `plan` into them. Files whose
+    /// destination exists are left alone. A file that can't be copied doesn't stop the migration;
+    /// the error is recorded in the report instead.
+    ///
+    /// Returns an error, and copies nothing, if one of the directories couldn't be created.
+    pub fn apply_migration(
+        &mut self,
+        plan: &MigrationPlan,
+    ) -> Result<MigrationReport, MigrationError> {
+        for dir in &plan.dirs {
+            if let Err(error) = utils::mkdir_parents(&dir.to, 0o700) {
+                return Err(MigrationError {
+                    dir: dir.to.clone(),
+                    error,
+                });
+            }
         }
 
-        // No migration occurred.
-        false
+        let moved = plan
+            .dirs
+            .iter()
+            .map(|dir| MovedDir {
+                from: &dir.from,
+                to: &dir.to,
+                files: dir.files,
+            })
+            .collect::<Vec<_>>();
+
+        let mut files = Vec::with_capacity(plan.files.len());
+        for file in &plan.files {
+            let outcome = if file.skipped || file.destination.exists() {
+                MigrationOutcome::Skipped
+            } else {
+                let result = if file.translated {
+                    self.migrate_config(&file.source, &file.destination, &moved)
+                } else {
+                    copy_file(&file.source, &file.destination)
+                };
+                match result {
+                    Ok(()) => MigrationOutcome::Copied,
+                    Err(error) => MigrationOutcome::Failed(error),
+                }
+            };
+            files.push((file.clone(), outcome));
+        }
+
+        if plan.xdg {
+            // Re-running to pick up XDG dirs
+            self.find_dirs();
+        }
+
+        Ok(MigrationReport { files })
+    }
+
+    /// Lines of Newsbeuter's config that `try_migrate_from_newsbeuter()` copied as they were,
+    /// because they couldn't be translated; each is "file:line: problem".
+    pub fn untranslated_config_lines(&self) -> &[String] {
+        &self.untranslated_config_lines
+    }
+
+    /// Directory that holds the cache and other data files of the current profile.
+    pub fn data_dir(&self) -> PathBuf {
+        self.profile_data_dir()
     }
 
     /// Path to the URLs file.
@@ -392,19 +632,185 @@ impl ConfigPaths {
     pub fn cmdline_file(&self) -> &Path {
         &self.cmdline_file
     }
+
+    /// Path to the snapshot of the feedlist that speeds up the next start; see
+    /// `feedlistsnapshot`.
+    pub fn feedlist_snapshot_file(&self) -> &Path {
+        &self.feedlist_snapshot_file
+    }
+
+    /// Path to the overlay that changes of the urls file go into if `urls-file-copy-on-write` is
+    /// set; see `urlsfile`. It's in the data dir, because the urls file's dir may be read-only.
+    pub fn url_overlay_file(&self) -> &Path {
+        &self.url_overlay_file
+    }
 }
 
-fn try_mkdir<R: AsRef<Path>>(path: R) -> bool {
-    utils::mkdir_parents(&path.as_ref(), 0o700).is_ok()
+/// A Newsbeuter directory whose files a migration copies into a Newsboat directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedDir {
+    from: PathBuf,
+    to: PathBuf,
+    /// Names of the files that are copied if they exist.
+    files: &'static [&'static str],
+}
+
+/// A file that a migration copies from Newsbeuter's directories into Newsboat's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// The file is Newsbeuter's config, which is translated rather than copied as it is.
+    pub translated: bool,
+    /// The destination already exists, so the file won't be copied.
+    pub skipped: bool,
+}
+
+/// What `ConfigPaths::apply_migration()` does; see `ConfigPaths::plan_migration()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationPlan {
+    /// Whether the files come from Newsbeuter's XDG dirs rather than from ~/.newsbeuter.
+    xdg: bool,
+    dirs: Vec<PlannedDir>,
+    /// Files that exist in Newsbeuter's directories, in the order in which they're copied.
+    pub files: Vec<PlannedFile>,
+}
+
+impl MigrationPlan {
+    fn new(xdg: bool, dirs: Vec<PlannedDir>) -> MigrationPlan {
+        let mut files = Vec::new();
+        for dir in &dirs {
+            for name in dir.files {
+                let source = dir.from.join(name);
+                if !source.exists() {
+                    continue;
+                }
+                let destination = dir.to.join(name);
+                files.push(PlannedFile {
+                    skipped: destination.exists(),
+                    translated: *name == CONFIG_FILENAME,
+                    source,
+                    destination,
+                });
+            }
+        }
+
+        MigrationPlan { xdg, dirs, files }
+    }
+
+    /// Newsboat's directories that the files are copied into. They're created if they don't
+    /// exist.
+    pub fn dirs(&self) -> Vec<&Path> {
+        self.dirs.iter().map(|dir| dir.to.as_path()).collect()
+    }
+
+    /// Internationalized description of the plan, one line per directory to create and per file.
+    pub fn describe(&self) -> String {
+        let mut result = String::new();
+        for dir in self.dirs() {
+            if !dir.is_dir() {
+                result.push_str(&fmt!(
+                    &gettext("Would create %s"),
+                    dir.to_string_lossy().into_owned()
+                ));
+                result.push('\n');
+            }
+        }
+        for file in &self.files {
+            let source = file.source.to_string_lossy().into_owned();
+            let destination = file.destination.to_string_lossy().into_owned();
+            let line = if file.skipped {
+                fmt!(
+                    &gettext("Would skip %s because %s already exists"),
+                    source,
+                    destination
+                )
+            } else if file.translated {
+                fmt!(&gettext("Would translate %s into %s"), source, destination)
+            } else {
+                fmt!(&gettext("Would copy %s to %s"), source, destination)
+            };
+            result.push_str(&line);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// What happened to a file during a migration.
+#[derive(Debug)]
+pub enum MigrationOutcome {
+    Copied,
+    /// The destination already existed, and was left alone.
+    Skipped,
+    /// The file couldn't be read, or its copy couldn't be written.
+    Failed(io::Error),
+}
+
+/// What `ConfigPaths::apply_migration()` did.
+#[derive(Debug)]
+pub struct MigrationReport {
+    /// Files of the plan, in the plan's order, each with what happened to it.
+    pub files: Vec<(PlannedFile, MigrationOutcome)>,
 }
 
-fn mkdir<R: AsRef<Path>>(path: R, mode: u32) -> io::Result<()> {
-    DirBuilder::new().mode(mode).create(path.as_ref())
+impl MigrationReport {
+    /// Internationalized description of the migration, one line per file.
+    pub fn describe(&self) -> String {
+        let mut result = String::new();
+        for (file, outcome) in &self.files {
+            let source = file.source.to_string_lossy().into_owned();
+            let destination = file.destination.to_string_lossy().into_owned();
+            let line = match outcome {
+                MigrationOutcome::Copied => fmt!("%s -> %s", source, destination),
+                MigrationOutcome::Skipped => fmt!(
+                    &gettext("Skipped %s because %s already exists"),
+                    source,
+                    destination
+                ),
+                MigrationOutcome::Failed(error) => fmt!(
+                    &gettext("Couldn't copy %s to %s: %s"),
+                    source,
+                    destination,
+                    error.to_string()
+                ),
+            };
+            result.push_str(&line);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// Why `ConfigPaths::apply_migration()` didn't copy anything.
+#[derive(Debug)]
+pub struct MigrationError {
+    /// The Newsboat directory that couldn't be created.
+    pub dir: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            fmt!(
+                &gettext("Aborting migration because mkdir on `%s' failed: %s"),
+                self.dir.to_string_lossy().into_owned(),
+                self.error.to_string()
+            )
+        )
+    }
+}
+
+fn try_mkdir<R: AsRef<Path>>(path: R) -> bool {
+    utils::mkdir_parents(&path.as_ref(), 0o700).is_ok()
 }
 
-fn migrate_file<R: AsRef<Path>>(newsbeuter_dir: R, newsboat_dir: R, file: &str) -> io::Result<()> {
-    let input_filepath = newsbeuter_dir.as_ref().join(file);
-    let output_filepath = newsboat_dir.as_ref().join(file);
-    eprintln!("{:?} -> {:?}", input_filepath, output_filepath);
-    fs::copy(input_filepath, output_filepath).map(|_| ())
+fn copy_file(source: &Path, destination: &Path) -> io::Result<()> {
+    let contents = fs::read(source)?;
+    let mode = fs::metadata(source)?.permissions().mode();
+    utils::atomic_write(destination, &contents, mode)?;
+    Ok(())
 }
@@ -0,0 +1,143 @@
+//! Checks the values of options whose syntax is richer than what their type (string or path)
+//! says: format strings, colors, and `mark-read-on`.
+//!
+//! The same checks apply to values from the config file and to ones set at runtime with `set`,
+//! so a value that would be refused on startup can't sneak in later.
+
+use crate::colormanager;
+use crate::fmtstrformatter::parser;
+use crate::markread::MarkReadPolicy;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// What an option's value is, as far as validation is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A format string, like `articlelist-format`.
+    Format,
+    /// A foreground color, a background color and attributes, like `search-highlight-colors`.
+    Colors,
+    /// A set of triggers for `mark-read-on`.
+    MarkRead,
+}
+
+/// Options whose values are checked, and how.
+const VALIDATED_OPTIONS: &[(&str, ValueKind)] = &[
+    ("articlelist-format", ValueKind::Format),
+    ("articlelist-title-format", ValueKind::Format),
+    ("dialogs-title-format", ValueKind::Format),
+    ("dirbrowser-title-format", ValueKind::Format),
+    ("download-filename-format", ValueKind::Format),
+    ("feedlist-format", ValueKind::Format),
+    ("feedlist-title-format", ValueKind::Format),
+    ("filebrowser-title-format", ValueKind::Format),
+    ("help-title-format", ValueKind::Format),
+    ("itemview-title-format", ValueKind::Format),
+    ("mark-read-on", ValueKind::MarkRead),
+    ("notify-format", ValueKind::Format),
+    ("podlist-format", ValueKind::Format),
+    ("search-highlight-colors", ValueKind::Colors),
+    ("searchresult-title-format", ValueKind::Format),
+    ("selectfilter-title-format", ValueKind::Format),
+    ("selecttag-format", ValueKind::Format),
+    ("selecttag-title-format", ValueKind::Format),
+    ("urlview-title-format", ValueKind::Format),
+];
+
+/// How the value of `option` is checked, or `None` if any string will do.
+pub fn kind_of(option: &str) -> Option<ValueKind> {
+    VALIDATED_OPTIONS
+        .iter()
+        .find(|(name, _)| *name == option)
+        .map(|(_, kind)| *kind)
+}
+
+/// Checks `value` for `option`. Returns an internationalized description of the problem if the
+/// value is invalid.
+pub fn validate(option: &str, value: &str) -> Result<(), String> {
+    match kind_of(option) {
+        Some(kind) => validate_as(kind, value),
+        None => Ok(()),
+    }
+}
+
+/// Checks `value` as a value of the given kind.
+pub fn validate_as(kind: ValueKind, value: &str) -> Result<(), String> {
+    match kind {
+        ValueKind::Format => match parser::invalid_position(value) {
+            None => Ok(()),
+            Some(position) => Err(fmt!(
+                &gettext("`%s' is not a valid format string: can't parse it from position %u"),
+                value,
+                // Positions are one-based for the user
+                position as u32 + 1
+            )),
+        },
+        ValueKind::Colors => {
+            let params = value
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            if params.len() < 2 {
+                return Err(gettext("expected a foreground and a background color"));
+            }
+            colormanager::parse_style(&params)
+                .map(|_| ())
+                .map_err(|error| error.to_message())
+        }
+        ValueKind::MarkRead => MarkReadPolicy::parse(value).map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_accepts_anything_for_options_without_a_validator() {
+        assert_eq!(validate("browser", "%"), Ok(()));
+        assert_eq!(validate("datetime-format", "%?"), Ok(()));
+        assert_eq!(validate("no-such-option", ""), Ok(()));
+    }
+
+    #[test]
+    fn t_rejects_malformed_format_strings() {
+        assert_eq!(
+            validate("articlelist-format", "%4i %f %D %?T?|%-17T| ?%t"),
+            Ok(())
+        );
+        assert_eq!(validate("feedlist-title-format", "%N %V"), Ok(()));
+
+        let error = validate("articlelist-format", "%4i %").unwrap_err();
+        assert!(error.contains("position 5"), "{}", error);
+        assert!(validate("notify-format", "%?d?unterminated").is_err());
+    }
+
+    #[test]
+    fn t_rejects_invalid_colors_and_attributes() {
+        assert_eq!(
+            validate("search-highlight-colors", "black yellow bold"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("search-highlight-colors", "#ff0000 default"),
+            Ok(())
+        );
+
+        let error = validate("search-highlight-colors", "black yelow").unwrap_err();
+        assert!(error.contains("argument 2"), "{}", error);
+        let error = validate("search-highlight-colors", "black yellow blod").unwrap_err();
+        assert!(error.contains("bold"), "{}", error);
+        assert!(validate("search-highlight-colors", "black").is_err());
+    }
+
+    #[test]
+    fn t_uses_the_mark_read_on_parser() {
+        assert_eq!(validate("mark-read-on", "dwell:5,next"), Ok(()));
+        assert_eq!(
+            validate("mark-read-on", "hover"),
+            MarkReadPolicy::parse("hover").map(|_| ())
+        );
+        assert!(validate("mark-read-on", "hover").is_err());
+    }
+}
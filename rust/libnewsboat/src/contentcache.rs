@@ -0,0 +1,413 @@
+//! A size-bounded cache for rendered article content.
+//!
+//! Rendering an article is expensive, so the results are kept around in case the user comes back
+//! to it. Without a bound, though, reading through a large backlog would keep every article ever
+//! opened in memory. This cache holds at most a given number of entries and a given number of
+//! bytes, evicting the least recently used entries first.
+
+use crate::{
+    log,
+    logger::{self, Level},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Approximate amount of memory taken by a value, in bytes.
+///
+/// Only the contents are counted, not the bookkeeping around them: the point is to notice that
+/// the cache holds a hundred megabytes of text, not to count every pointer.
+pub trait ByteSize {
+    fn byte_size(&self) -> usize;
+}
+
+impl ByteSize for String {
+    fn byte_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: ByteSize> ByteSize for Vec<T> {
+    fn byte_size(&self) -> usize {
+        self.iter().map(ByteSize::byte_size).sum()
+    }
+}
+
+/// A shared value counts as much as the value itself; it's up to the caller not to count the same
+/// value twice.
+impl<T: ByteSize> ByteSize for Arc<T> {
+    fn byte_size(&self) -> usize {
+        (**self).byte_size()
+    }
+}
+
+/// Counters describing how well the cache works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Number of entries currently in the cache.
+    pub entries: usize,
+    /// Approximate size of the entries currently in the cache.
+    pub bytes: usize,
+}
+
+impl CacheStats {
+    /// One-line summary for the log.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} hits, {} misses, {} evictions, {} entries, {} bytes",
+            self.hits, self.misses, self.evictions, self.entries, self.bytes
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    /// Size of the key and the value, as counted towards the limit.
+    size: usize,
+    /// Value of `Inner::clock` when this entry was last inserted or looked up.
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct Inner<K, V> {
+    max_entries: usize,
+    max_bytes: usize,
+    entries: HashMap<K, Entry<V>>,
+    /// Keys by the time they were last used, least recently used first.
+    recency: BTreeMap<u64, K>,
+    /// Incremented on every use, so that each entry has a unique `last_used`.
+    clock: u64,
+    /// Entry that must not be evicted, because it's on the screen right now.
+    pinned: Option<K>,
+    stats: CacheStats,
+}
+
+impl<K: Eq + Hash + Clone + ByteSize, V: ByteSize> Inner<K, V> {
+    fn touch(&mut self, key: &K) {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = clock;
+            self.recency.insert(clock, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<Entry<V>> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        self.stats.bytes -= entry.size;
+        self.stats.entries -= 1;
+        Some(entry)
+    }
+
+    fn over_limit(&self) -> bool {
+        self.entries.len() > self.max_entries || self.stats.bytes > self.max_bytes
+    }
+
+    /// Evicts least recently used entries until the cache fits its limits again. The pinned entry
+    /// is skipped, so a single pinned entry may keep the cache over the byte limit.
+    fn evict(&mut self) {
+        while self.over_limit() {
+            let victim = self
+                .recency
+                .values()
+                .find(|key| self.pinned.as_ref() != Some(*key))
+                .cloned();
+            match victim {
+                Some(key) => {
+                    self.remove(&key);
+                    self.stats.evictions += 1;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+/// LRU cache bounded by the number of entries and their total size.
+///
+/// All methods take `&self` and lock internally, so the cache can be shared between threads, e.g.
+/// the one that renders the article on the screen and the one that renders articles in advance.
+#[derive(Debug)]
+pub struct ContentCache<K: Eq + Hash, V> {
+    inner: Mutex<Inner<K, V>>,
+}
+
+impl<K: Eq + Hash + Clone + ByteSize, V: Clone + ByteSize> ContentCache<K, V> {
+    pub fn new(max_entries: usize, max_bytes: usize) -> ContentCache<K, V> {
+        ContentCache {
+            inner: Mutex::new(Inner {
+                max_entries,
+                max_bytes,
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                clock: 0,
+                pinned: None,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Changes the limits, evicting entries if the cache doesn't fit into the new ones.
+    pub fn set_limits(&self, max_entries: usize, max_bytes: usize) {
+        let mut inner = self.lock();
+        inner.max_entries = max_entries;
+        inner.max_bytes = max_bytes;
+        inner.evict();
+    }
+
+    /// Returns a copy of the value stored under `key`, and marks it as recently used.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut inner = self.lock();
+        if inner.entries.contains_key(key) {
+            inner.stats.hits += 1;
+            inner.touch(key);
+            inner.entries.get(key).map(|entry| entry.value.clone())
+        } else {
+            inner.stats.misses += 1;
+            None
+        }
+    }
+
+    /// Stores `value` under `key`, replacing the previous value, and evicts the least recently
+    /// used entries if the cache grew over its limits. An entry that's bigger than the whole
+    /// cache isn't stored at all (unless it's pinned), so that it doesn't push out everything
+    /// else.
+    pub fn insert(&self, key: K, value: V) {
+        let mut inner = self.lock();
+        inner.remove(&key);
+
+        let size = key.byte_size() + value.byte_size();
+        if size > inner.max_bytes && inner.pinned.as_ref() != Some(&key) {
+            return;
+        }
+        inner.clock += 1;
+        let clock = inner.clock;
+        inner.recency.insert(clock, key.clone());
+        inner.entries.insert(
+            key,
+            Entry {
+                value,
+                size,
+                last_used: clock,
+            },
+        );
+        inner.stats.bytes += size;
+        inner.stats.entries += 1;
+
+        inner.evict();
+    }
+
+    /// Protects the entry stored under `key` from eviction (even if it isn't in the cache yet),
+    /// and unpins the one that was pinned before. `None` unpins everything.
+    pub fn pin(&self, key: Option<K>) {
+        let mut inner = self.lock();
+        inner.pinned = key;
+        // The previously pinned entry might have kept the cache over the limit
+        inner.evict();
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.lock().entries.contains_key(key)
+    }
+
+    /// Removes all entries. Counters are kept, and so is the pin.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.recency.clear();
+        inner.stats.entries = 0;
+        inner.stats.bytes = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    fn lock(&self) -> MutexGuard<Inner<K, V>> {
+        // If another thread panicked while holding the lock, the cache might be inconsistent,
+        // but it's still memory-safe. Since losing the cache is better than losing the program,
+        // we carry on.
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<K: Eq + Hash, V> Drop for ContentCache<K, V> {
+    fn drop(&mut self) {
+        if let Ok(inner) = self.inner.lock() {
+            log!(
+                "contentcache",
+                Level::Debug,
+                &format!("ContentCache: final stats: {}", inner.stats.describe())
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn new_cache(max_entries: usize, max_bytes: usize) -> ContentCache<String, String> {
+        ContentCache::new(max_entries, max_bytes)
+    }
+
+    fn insert(cache: &ContentCache<String, String>, key: &str, value: &str) {
+        cache.insert(key.to_string(), value.to_string());
+    }
+
+    fn has(cache: &ContentCache<String, String>, key: &str) -> bool {
+        cache.contains(&key.to_string())
+    }
+
+    #[test]
+    fn t_evicts_least_recently_used_entries_first() {
+        let cache = new_cache(3, 1000);
+        insert(&cache, "a", "1");
+        insert(&cache, "b", "2");
+        insert(&cache, "c", "3");
+
+        // Looking up "a" makes "b" the least recently used one
+        assert_eq!(cache.get(&"a".to_string()), Some("1".to_string()));
+        insert(&cache, "d", "4");
+        assert!(has(&cache, "a"));
+        assert!(!has(&cache, "b"));
+        assert!(has(&cache, "c"));
+        assert!(has(&cache, "d"));
+
+        // Re-inserting counts as a use, too
+        insert(&cache, "c", "33");
+        insert(&cache, "e", "5");
+        assert!(!has(&cache, "a"));
+        assert!(has(&cache, "c"));
+        assert_eq!(cache.stats().evictions, 2);
+        assert_eq!(cache.stats().entries, 3);
+    }
+
+    #[test]
+    fn t_counts_bytes_of_keys_and_values() {
+        let cache = new_cache(100, 10);
+        insert(&cache, "ab", "cdef");
+        assert_eq!(cache.stats().bytes, 6);
+
+        // Replacing a value releases the old one
+        insert(&cache, "ab", "c");
+        assert_eq!(cache.stats().bytes, 3);
+        assert_eq!(cache.stats().entries, 1);
+
+        // 3 + 6 fits, 3 + 6 + 2 doesn't
+        insert(&cache, "gh", "ijkl");
+        assert_eq!(cache.stats().bytes, 9);
+        insert(&cache, "m", "n");
+        assert!(!has(&cache, "ab"));
+        assert_eq!(cache.stats().bytes, 8);
+
+        cache.clear();
+        assert_eq!(cache.stats().bytes, 0);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn t_entries_bigger_than_the_cache_are_not_kept() {
+        let cache = new_cache(100, 10);
+        insert(&cache, "small", "x");
+        insert(&cache, "huge", "0123456789");
+        assert!(!has(&cache, "huge"));
+        assert!(has(&cache, "small"));
+    }
+
+    #[test]
+    fn t_pinned_entry_is_never_evicted() {
+        let cache = new_cache(2, 1000);
+        cache.pin(Some("shown".to_string()));
+        insert(&cache, "shown", "article");
+        insert(&cache, "b", "2");
+        insert(&cache, "c", "3");
+        insert(&cache, "d", "4");
+        assert!(has(&cache, "shown"));
+        assert!(has(&cache, "d"));
+        assert_eq!(cache.stats().entries, 2);
+
+        // Pinned entries are kept even if they alone exceed the byte limit
+        let cache = new_cache(100, 5);
+        cache.pin(Some("shown".to_string()));
+        insert(&cache, "shown", "a long article");
+        assert!(has(&cache, "shown"));
+
+        // Once unpinned, the entry can be evicted
+        cache.pin(None);
+        assert!(!has(&cache, "shown"));
+    }
+
+    #[test]
+    fn t_set_limits_evicts_entries_that_no_longer_fit() {
+        let cache = new_cache(10, 1000);
+        for key in &["a", "b", "c", "d"] {
+            insert(&cache, key, "x");
+        }
+        cache.set_limits(2, 1000);
+        assert_eq!(cache.stats().entries, 2);
+        assert!(has(&cache, "c"));
+        assert!(has(&cache, "d"));
+    }
+
+    #[test]
+    fn t_counts_hits_and_misses() {
+        let cache = new_cache(10, 1000);
+        insert(&cache, "a", "1");
+        cache.get(&"a".to_string());
+        cache.get(&"a".to_string());
+        cache.get(&"b".to_string());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(
+            stats.describe(),
+            "2 hits, 1 misses, 0 evictions, 1 entries, 2 bytes"
+        );
+    }
+
+    #[test]
+    fn t_can_be_used_from_multiple_threads() {
+        let cache = Arc::new(new_cache(50, 10_000));
+
+        let threads = (0..4)
+            .map(|thread| {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    for i in 0..500 {
+                        let key = format!("{}-{}", thread, i % 40);
+                        if cache.get(&key).is_none() {
+                            cache.insert(key.clone(), format!("value of {}", key));
+                        }
+                        if i % 7 == 0 {
+                            cache.pin(Some(key));
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let stats = cache.stats();
+        assert!(stats.entries <= 50);
+        assert!(stats.bytes <= 10_000);
+        assert_eq!(stats.hits + stats.misses, 4 * 500);
+
+        // Bookkeeping is still consistent: every entry is accounted for
+        cache.clear();
+        assert_eq!(cache.stats().bytes, 0);
+    }
+}
@@ -0,0 +1,498 @@
+//! A Unix socket through which other `newsboat` processes talk to the running instance.
+//!
+//! Only one instance can hold the lock on the cache, so a second `newsboat` can't do much on its
+//! own. Instead, it connects to the socket next to the lock file and sends a command; the running
+//! instance carries it out and answers. Both are single lines of text:
+//!
+//! ```text
+//! add-feed https://example.com/feed.xml
+//! ok Added https://example.com/feed.xml to /home/user/.newsboat/urls.
+//! ```
+//!
+//! The answer starts with `ok` or `error`, followed by a message for the user.
+
+use crate::logger::{self, Level};
+use gettextrs::gettext;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use strprintf::fmt;
+use url::Url;
+
+/// How long either side waits for the other to send its line.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Name of the command that adds a feed to the urls file.
+pub const ADD_FEED: &str = "add-feed";
+
+/// The socket that belongs to the instance which holds `lock_file`.
+pub fn socket_path(lock_file: &Path) -> PathBuf {
+    lock_file.with_extension("sock")
+}
+
+/// Turns what a browser passes to a feed handler into a URL that can go into the urls file.
+///
+/// Besides http, https and file URLs, this accepts the `feed:` scheme, both in its
+/// `feed://example.com/rss` form (which means http) and in its `feed:https://example.com/rss`
+/// form. Returns an internationalized error message if there's no feed URL to be had.
+pub fn normalize_feed_url(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    let lowercase = input.to_lowercase();
+    let input = if lowercase.starts_with("feed://") {
+        format!("http://{}", &input["feed://".len()..])
+    } else if lowercase.starts_with("feed:") {
+        input["feed:".len()..].to_string()
+    } else {
+        input.to_string()
+    };
+
+    let invalid = || fmt!(&gettext("`%s' is not an http, https or file URL"), &input);
+    match Url::parse(&input) {
+        Ok(url) => match url.scheme() {
+            "http" | "https" if url.host().is_some() => Ok(input),
+            "file" => Ok(input),
+            _ => Err(invalid()),
+        },
+        Err(_) => Err(invalid()),
+    }
+}
+
+/// A command that the running instance carries out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Subscribe to a feed. The URL is normalized already.
+    AddFeed(String),
+}
+
+impl Command {
+    /// Parses a line that was sent to the socket. Returns an internationalized error message if
+    /// it isn't a valid command.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim();
+        let mut words = line.splitn(2, char::is_whitespace);
+        let name = words.next().unwrap_or("");
+        let argument = words.next().unwrap_or("").trim();
+        match name {
+            ADD_FEED if argument.is_empty() => Err(fmt!(&gettext("%s: expected a URL"), ADD_FEED)),
+            ADD_FEED => normalize_feed_url(argument).map(Command::AddFeed),
+            _ => Err(fmt!(&gettext("unknown command `%s'"), name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::AddFeed(_) => ADD_FEED,
+        }
+    }
+
+    pub fn argument(&self) -> &str {
+        match self {
+            Command::AddFeed(url) => url,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        format!("{} {}\n", self.name(), self.argument())
+    }
+}
+
+/// The running instance's answer to a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// Whether the command was carried out.
+    pub ok: bool,
+    /// Message to show to the user.
+    pub message: String,
+}
+
+impl Response {
+    pub fn ok(message: &str) -> Response {
+        Response {
+            ok: true,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn error(message: &str) -> Response {
+        Response {
+            ok: false,
+            message: message.to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Response> {
+        let line = line.trim_end_matches(&['\n', '\r'][..]);
+        let mut words = line.splitn(2, ' ');
+        let status = words.next()?;
+        let message = words.next().unwrap_or("");
+        match status {
+            "ok" => Some(Response::ok(message)),
+            "error" => Some(Response::error(message)),
+            _ => None,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let status = if self.ok { "ok" } else { "error" };
+        let message = self.message.replace(&['\n', '\r'][..], " ");
+        format!("{} {}\n", status, message)
+    }
+}
+
+/// Why a command couldn't be sent.
+#[derive(Debug)]
+pub enum SendError {
+    /// Nothing listens on the socket: there is no instance, or it doesn't accept commands.
+    NoInstance,
+    /// The instance was reached, but didn't answer properly.
+    Io(io::Error),
+}
+
+impl SendError {
+    /// Internationalized description of the problem.
+    pub fn to_message(&self) -> String {
+        match self {
+            SendError::NoInstance => gettext("nothing listens on the control socket"),
+            SendError::Io(error) => error.to_string(),
+        }
+    }
+}
+
+/// Sends `command` to the instance that listens on `path`, and waits for its answer.
+pub fn send(path: &Path, command: &Command) -> Result<Response, SendError> {
+    let mut stream = match UnixStream::connect(path) {
+        Ok(stream) => stream,
+        Err(error) => {
+            log!(
+                "controlsocket",
+                Level::Debug,
+                "controlsocket::send: can't connect to `{}': {}",
+                path.display(),
+                error
+            );
+            return Err(match error.kind() {
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => SendError::NoInstance,
+                _ => SendError::Io(error),
+            });
+        }
+    };
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(SendError::Io)?;
+    stream
+        .set_write_timeout(Some(TIMEOUT))
+        .map_err(SendError::Io)?;
+    stream
+        .write_all(command.to_line().as_bytes())
+        .map_err(SendError::Io)?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(SendError::Io)?;
+    Response::parse(&line).ok_or_else(|| {
+        SendError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            gettext("the running instance sent an invalid response"),
+        ))
+    })
+}
+
+/// The running instance's end of the socket.
+///
+/// `accept` blocks, so it's meant to be called in a loop on a thread of its own; `shut_down` ends
+/// that loop from any other thread.
+pub struct ControlServer {
+    listener: UnixListener,
+    path: PathBuf,
+    shutting_down: AtomicBool,
+}
+
+impl ControlServer {
+    /// Starts listening on `path`. A socket that was left behind by an instance that crashed is
+    /// replaced; one that another instance still listens on is not.
+    pub fn bind(path: &Path) -> io::Result<ControlServer> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    fmt!(
+                        &gettext("another instance listens on %s"),
+                        &path.display().to_string()
+                    ),
+                ));
+            }
+            fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        // Anyone who can connect can add feeds
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        log!(
+            "controlsocket",
+            Level::Debug,
+            "ControlServer: listening on `{}'",
+            path.display()
+        );
+        Ok(ControlServer {
+            listener,
+            path: path.to_owned(),
+            shutting_down: AtomicBool::new(false),
+        })
+    }
+
+    /// Waits for the next connection. Returns `None` once `shut_down` was called.
+    pub fn accept(&self) -> Option<Connection> {
+        loop {
+            let result = self.listener.accept();
+            if self.shutting_down.load(Ordering::SeqCst) {
+                return None;
+            }
+            match result {
+                Ok((stream, _)) => {
+                    if stream.set_read_timeout(Some(TIMEOUT)).is_ok()
+                        && stream.set_write_timeout(Some(TIMEOUT)).is_ok()
+                    {
+                        return Some(Connection { stream });
+                    }
+                }
+                Err(error) => {
+                    log!(
+                        "controlsocket",
+                        Level::Warn,
+                        "ControlServer::accept: {}",
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    /// Makes `accept` return `None`, now and on all later calls.
+    pub fn shut_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        // Wake up a thread that's blocked in `accept`
+        let _ = UnixStream::connect(&self.path);
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A connection from another `newsboat`, which sends one command and waits for the response.
+pub struct Connection {
+    stream: UnixStream,
+}
+
+impl Connection {
+    /// Reads the command. If it isn't valid, the error is sent back right away, and `None` is
+    /// returned.
+    pub fn read_command(&mut self) -> Option<Command> {
+        let mut line = String::new();
+        let mut reader = BufReader::new(&self.stream);
+        if let Err(error) = reader.read_line(&mut line) {
+            log!(
+                "controlsocket",
+                Level::Warn,
+                "Connection::read_command: {}",
+                error
+            );
+            return None;
+        }
+        match Command::parse(&line) {
+            Ok(command) => Some(command),
+            Err(message) => {
+                self.respond(&Response::error(&message));
+                None
+            }
+        }
+    }
+
+    pub fn respond(&mut self, response: &Response) {
+        if let Err(error) = self.stream.write_all(response.to_line().as_bytes()) {
+            log!(
+                "controlsocket",
+                Level::Warn,
+                "Connection::respond: {}",
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn t_socket_path_is_next_to_the_lock_file() {
+        assert_eq!(
+            socket_path(Path::new("/home/user/.newsboat/cache.db.lock")),
+            Path::new("/home/user/.newsboat/cache.db.sock")
+        );
+    }
+
+    #[test]
+    fn t_normalize_feed_url_accepts_feed_urls_and_the_feed_scheme() {
+        let ok = |url: &str| normalize_feed_url(url).unwrap();
+        assert_eq!(ok("https://example.com/rss"), "https://example.com/rss");
+        assert_eq!(ok(" http://example.com/rss\n"), "http://example.com/rss");
+        assert_eq!(ok("file:///tmp/feed.xml"), "file:///tmp/feed.xml");
+        assert_eq!(ok("feed://example.com/rss"), "http://example.com/rss");
+        assert_eq!(
+            ok("FEED:https://example.com/rss"),
+            "https://example.com/rss"
+        );
+
+        assert!(normalize_feed_url("example.com/rss").is_err());
+        assert!(normalize_feed_url("ftp://example.com/rss").is_err());
+        assert!(normalize_feed_url("exec:~/bin/make-a-feed").is_err());
+        assert!(normalize_feed_url("http://").is_err());
+    }
+
+    #[test]
+    fn t_command_parse() {
+        assert_eq!(
+            Command::parse("add-feed feed://example.com/rss\n"),
+            Ok(Command::AddFeed("http://example.com/rss".to_string()))
+        );
+        assert!(Command::parse("add-feed\n").is_err());
+        assert!(Command::parse("add-feed not a url\n").is_err());
+        assert_eq!(
+            Command::parse("remove-feed http://example.com/rss"),
+            Err("unknown command `remove-feed'".to_string())
+        );
+    }
+
+    #[test]
+    fn t_response_survives_the_round_trip() {
+        let response = Response::error("Error: no feed\nat all");
+        assert_eq!(
+            Response::parse(&response.to_line()),
+            Some(Response::error("Error: no feed at all"))
+        );
+        assert_eq!(Response::parse("ok\n"), Some(Response::ok("")));
+        assert_eq!(Response::parse("hello\n"), None);
+    }
+
+    #[test]
+    fn t_send_forwards_the_command_and_returns_the_response() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        // A fake instance that answers a single command
+        let listener = UnixListener::bind(&path).unwrap();
+        let instance = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            (&stream)
+                .write_all(b"ok Added http://example.com/rss to urls.\n")
+                .unwrap();
+            line
+        });
+
+        let command = Command::AddFeed("http://example.com/rss".to_string());
+        let response = send(&path, &command).unwrap();
+        assert_eq!(
+            response,
+            Response::ok("Added http://example.com/rss to urls.")
+        );
+        assert_eq!(
+            instance.join().unwrap(),
+            "add-feed http://example.com/rss\n"
+        );
+    }
+
+    #[test]
+    fn t_send_says_if_there_is_no_instance() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        assert!(matches!(
+            send(
+                &path,
+                &Command::AddFeed("http://example.com/rss".to_string())
+            ),
+            Err(SendError::NoInstance)
+        ));
+
+        // A socket that was left behind, with nobody listening on it
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        assert!(matches!(
+            send(
+                &path,
+                &Command::AddFeed("http://example.com/rss".to_string())
+            ),
+            Err(SendError::NoInstance)
+        ));
+    }
+
+    #[test]
+    fn t_server_answers_commands_and_rejects_invalid_ones() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        let server = std::sync::Arc::new(ControlServer::bind(&path).unwrap());
+
+        let instance = {
+            let server = server.clone();
+            thread::spawn(move || {
+                let mut commands = Vec::new();
+                while let Some(mut connection) = server.accept() {
+                    if let Some(command) = connection.read_command() {
+                        connection.respond(&Response::error(&format!(
+                            "You are already subscribed to {}.",
+                            command.argument()
+                        )));
+                        commands.push(command);
+                    }
+                }
+                commands
+            })
+        };
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream
+            .write_all(b"add-feed gopher://example.com\n")
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        assert_eq!(
+            line,
+            "error `gopher://example.com' is not an http, https or file URL\n"
+        );
+
+        let command = Command::AddFeed("https://example.com/rss".to_string());
+        assert_eq!(
+            send(&path, &command).unwrap(),
+            Response::error("You are already subscribed to https://example.com/rss.")
+        );
+
+        server.shut_down();
+        assert_eq!(instance.join().unwrap(), vec![command]);
+    }
+
+    #[test]
+    fn t_bind_replaces_a_stale_socket_but_not_a_live_one() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("cache.db.sock");
+        drop(UnixListener::bind(&path).unwrap());
+
+        let server = ControlServer::bind(&path).unwrap();
+        assert_eq!(
+            ControlServer::bind(&path).err().map(|e| e.kind()),
+            Some(io::ErrorKind::AddrInUse)
+        );
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
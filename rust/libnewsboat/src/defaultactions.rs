@@ -0,0 +1,259 @@
+//! `default-action-primary` and `default-action-secondary`: what Enter and Space do.
+//!
+//! Enter and Space aren't bound to operations directly, but to these two pseudo-operations, which
+//! stand for a different operation in each context. Out of the box, they stand for whatever Enter
+//! and Space used to be bound to; options like `articlelist-primary-action` change that, so that
+//! Enter can e.g. open the article in the browser without looking up which key name it has. Like
+//! any other operation, the pseudo-operations can be used in macros, where they do "whatever Enter
+//! does here".
+
+use gettextrs::gettext;
+use std::collections::{BTreeSet, HashMap};
+use strprintf::fmt;
+
+/// The operation that Enter is bound to.
+pub const PRIMARY_OPERATION: &str = "default-action-primary";
+
+/// The operation that Space is bound to.
+pub const SECONDARY_OPERATION: &str = "default-action-secondary";
+
+/// Contexts whose default actions can be changed with an option.
+pub const CONFIGURABLE_CONTEXTS: &[&str] = &["feedlist", "articlelist", "article", "urlview"];
+
+/// Which of the two default actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Slot {
+    /// Enter.
+    Primary,
+    /// Space.
+    Secondary,
+}
+
+impl Slot {
+    /// The slot that the pseudo-operation `operation` stands for, if it is one.
+    pub fn of_operation(operation: &str) -> Option<Slot> {
+        match operation {
+            PRIMARY_OPERATION => Some(Slot::Primary),
+            SECONDARY_OPERATION => Some(Slot::Secondary),
+            _ => None,
+        }
+    }
+
+    /// Name of the option that sets this slot in `context`, like `articlelist-primary-action`.
+    pub fn option_name(self, context: &str) -> String {
+        match self {
+            Slot::Primary => format!("{}-primary-action", context),
+            Slot::Secondary => format!("{}-secondary-action", context),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultActions {
+    /// Contexts that each operation works in.
+    contexts: HashMap<String, BTreeSet<String>>,
+    /// What the slots stand for unless they're configured, by context.
+    builtin: HashMap<(String, Slot), String>,
+    /// What the options set the slots to, by context.
+    configured: HashMap<(String, Slot), String>,
+}
+
+impl DefaultActions {
+    pub fn new() -> DefaultActions {
+        DefaultActions::default()
+    }
+
+    /// Records the contexts in which `operation` works.
+    pub fn register_operation(&mut self, operation: &str, contexts: &[String]) {
+        self.contexts
+            .insert(operation.to_string(), contexts.iter().cloned().collect());
+    }
+
+    /// Sets what `slot` stands for in `context` if no option says otherwise.
+    pub fn set_builtin(&mut self, context: &str, slot: Slot, operation: &str) {
+        self.builtin
+            .insert((context.to_string(), slot), operation.to_string());
+    }
+
+    /// Makes `slot` stand for `operation` in `context`; an empty `operation` goes back to the
+    /// built-in one. Returns an internationalized error message if `operation` doesn't work in
+    /// `context`, in which case nothing changes.
+    pub fn configure(&mut self, context: &str, slot: Slot, operation: &str) -> Result<(), String> {
+        let key = (context.to_string(), slot);
+        if operation.is_empty() {
+            self.configured.remove(&key);
+            return Ok(());
+        }
+
+        let option = slot.option_name(context);
+        if Slot::of_operation(operation).is_some() {
+            return Err(fmt!(
+                &gettext("`%s' can't be set to `%s': it has to be an actual operation"),
+                &option,
+                operation
+            ));
+        }
+        match self.contexts.get(operation) {
+            None => Err(fmt!(
+                &gettext("`%s' can't be set to `%s': there is no such operation"),
+                &option,
+                operation
+            )),
+            Some(contexts) if !contexts.contains(context) => Err(fmt!(
+                &gettext("`%s' can't be set to `%s': it doesn't work in context `%s'"),
+                &option,
+                operation,
+                context
+            )),
+            Some(_) => {
+                self.configured.insert(key, operation.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// The operation that `slot` stands for in `context`, or `None` if it stands for nothing
+    /// there.
+    pub fn resolve(&self, context: &str, slot: Slot) -> Option<&str> {
+        let key = (context.to_string(), slot);
+        self.configured
+            .get(&key)
+            .or_else(|| self.builtin.get(&key))
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contexts(contexts: &[&str]) -> Vec<String> {
+        contexts.iter().map(|context| context.to_string()).collect()
+    }
+
+    /// Operations and the places they work in, like the defaults of the real keymap.
+    fn actions() -> DefaultActions {
+        let mut actions = DefaultActions::new();
+        actions.register_operation("open", &contexts(&["feedlist", "articlelist", "urlview"]));
+        actions.register_operation(
+            "open-in-browser",
+            &contexts(&["articlelist", "article", "urlview"]),
+        );
+        actions.register_operation(
+            "pagedown",
+            &contexts(&["feedlist", "articlelist", "article", "urlview"]),
+        );
+        actions.register_operation(
+            "toggle-article-read",
+            &contexts(&["articlelist", "article"]),
+        );
+        actions.set_builtin("feedlist", Slot::Primary, "open");
+        actions.set_builtin("articlelist", Slot::Primary, "open");
+        actions.set_builtin("article", Slot::Secondary, "pagedown");
+        actions
+    }
+
+    #[test]
+    fn t_slots_resolve_to_their_builtin_operation_when_unset() {
+        let actions = actions();
+        assert_eq!(actions.resolve("feedlist", Slot::Primary), Some("open"));
+        assert_eq!(actions.resolve("articlelist", Slot::Primary), Some("open"));
+        assert_eq!(
+            actions.resolve("article", Slot::Secondary),
+            Some("pagedown")
+        );
+        assert_eq!(actions.resolve("article", Slot::Primary), None);
+        assert_eq!(actions.resolve("feedlist", Slot::Secondary), None);
+    }
+
+    #[test]
+    fn t_configured_operations_only_apply_to_their_context() {
+        let mut actions = actions();
+        assert_eq!(
+            actions.configure("articlelist", Slot::Primary, "open-in-browser"),
+            Ok(())
+        );
+        assert_eq!(
+            actions.configure("articlelist", Slot::Secondary, "toggle-article-read"),
+            Ok(())
+        );
+        assert_eq!(
+            actions.resolve("articlelist", Slot::Primary),
+            Some("open-in-browser")
+        );
+        assert_eq!(
+            actions.resolve("articlelist", Slot::Secondary),
+            Some("toggle-article-read")
+        );
+        assert_eq!(actions.resolve("feedlist", Slot::Primary), Some("open"));
+        assert_eq!(actions.resolve("urlview", Slot::Primary), None);
+    }
+
+    #[test]
+    fn t_empty_operation_goes_back_to_the_builtin_one() {
+        let mut actions = actions();
+        actions
+            .configure("articlelist", Slot::Primary, "open-in-browser")
+            .unwrap();
+        assert_eq!(actions.configure("articlelist", Slot::Primary, ""), Ok(()));
+        assert_eq!(actions.resolve("articlelist", Slot::Primary), Some("open"));
+    }
+
+    #[test]
+    fn t_operations_that_do_not_work_in_the_context_are_rejected() {
+        let mut actions = actions();
+        assert_eq!(
+            actions.configure("feedlist", Slot::Primary, "toggle-article-read"),
+            Err(
+                "`feedlist-primary-action' can't be set to `toggle-article-read': it doesn't \
+                 work in context `feedlist'"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            actions.configure("article", Slot::Secondary, "no-such-thing"),
+            Err(
+                "`article-secondary-action' can't be set to `no-such-thing': there is no such \
+                 operation"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            actions.configure("article", Slot::Primary, SECONDARY_OPERATION),
+            Err(
+                "`article-primary-action' can't be set to `default-action-secondary': it has \
+                 to be an actual operation"
+                    .to_string()
+            )
+        );
+
+        // The previous operations are still in place
+        assert_eq!(actions.resolve("feedlist", Slot::Primary), Some("open"));
+        assert_eq!(
+            actions.resolve("article", Slot::Secondary),
+            Some("pagedown")
+        );
+        assert_eq!(actions.resolve("article", Slot::Primary), None);
+    }
+
+    #[test]
+    fn t_pseudo_operations_map_to_slots() {
+        assert_eq!(
+            Slot::of_operation("default-action-primary"),
+            Some(Slot::Primary)
+        );
+        assert_eq!(
+            Slot::of_operation("default-action-secondary"),
+            Some(Slot::Secondary)
+        );
+        assert_eq!(Slot::of_operation("open"), None);
+        assert_eq!(
+            Slot::Primary.option_name("articlelist"),
+            "articlelist-primary-action"
+        );
+        assert_eq!(
+            Slot::Secondary.option_name("urlview"),
+            "urlview-secondary-action"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! What the `describe` command says about options, operations and keys.
+//!
+//! `describe reload-time` shows the type, default and current value of the option, and the config
+//! command that set it; `describe open-in-browser` shows what the operation does, where it works,
+//! the arguments it takes and the keys it's bound to. Keys are described by `describe-key`, which
+//! `describe key:o` defers to.
+//!
+//! The facts themselves live on the C++ side (`ConfigContainer` and `KeyMap`), which passes them
+//! here to be turned into text.
+
+use crate::utils;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Prefix that makes `describe` describe a key rather than an option or an operation.
+pub const KEY_PREFIX: &str = "key:";
+
+/// Arguments of the operations that take some when run from a macro or with `exec`, as they're
+/// shown in the usage of the operation.
+const OPERATION_ARGUMENTS: &[(&str, &str)] = &[
+    ("bookmark", "[<title>]"),
+    ("describe", "<name>"),
+    ("describe-key", "<key>"),
+    ("edit-flags", "[<flags>]"),
+    ("edit-macro", "<key>"),
+    ("edit-note-in-editor", "[<note>]"),
+    ("goto-percent", "<percent>"),
+    ("goto-url", "[<number>]"),
+    ("open", "[<position>]"),
+    ("open-search", "[<phrase>]"),
+    ("pipe-to", "[<command>]"),
+    ("preview-feed", "[<url>]"),
+    ("remove-tag", "[<tag>]"),
+    ("rename-tag", "[<tag> <new name>]"),
+    ("save", "[<filename>]"),
+    ("select-filter", "[<filter>]"),
+    ("select-tag", "[<tag>]"),
+    ("set-filter", "[<filter>]"),
+    ("toggle-article-read", "[read|unread]"),
+];
+
+/// If the argument of `describe` names a key (`key:` followed by the key), returns the key.
+pub fn key_argument(argument: &str) -> Option<&str> {
+    argument.strip_prefix(KEY_PREFIX)
+}
+
+/// Arguments that `operation` takes, or `None` if it takes none.
+pub fn operation_arguments(operation: &str) -> Option<&'static str> {
+    OPERATION_ARGUMENTS
+        .iter()
+        .find(|(name, _)| *name == operation)
+        .map(|(_, arguments)| *arguments)
+}
+
+/// Everything there is to say about an option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionDescription {
+    pub name: String,
+    /// One of "bool", "int", "str", "path" and "enum".
+    pub kind: String,
+    /// Values that an "enum" option can take.
+    pub values: Vec<String>,
+    pub default_value: String,
+    pub value: String,
+    /// File and line of the config command that last set the option, if one did.
+    pub origin: Option<String>,
+}
+
+impl OptionDescription {
+    fn kind_name(&self) -> String {
+        match self.kind.as_str() {
+            "bool" => gettext("yes/no"),
+            "int" => gettext("number"),
+            "path" => gettext("path"),
+            "enum" => fmt!(&gettext("one of %s"), &self.values.join(", ")),
+            _ => gettext("text"),
+        }
+    }
+
+    /// Values of text options are quoted, so that empty ones and surrounding spaces show.
+    fn show_value(&self, value: &str) -> String {
+        match self.kind.as_str() {
+            "str" | "path" => utils::quote(value.to_string()),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Internationalized lines of the description.
+    pub fn lines(&self) -> Vec<String> {
+        let origin = match self.origin {
+            Some(ref origin) => fmt!(&gettext("set at %s"), origin),
+            None if self.value == self.default_value => gettext("not set, default value"),
+            None => gettext("set outside of config files"),
+        };
+        vec![
+            format!("{} ({})", self.name, self.kind_name()),
+            format!(
+                "  {}",
+                fmt!(
+                    &gettext("default: %s"),
+                    &self.show_value(&self.default_value)
+                )
+            ),
+            format!(
+                "  {}",
+                fmt!(&gettext("current: %s"), &self.show_value(&self.value))
+            ),
+            format!("  {}", origin),
+        ]
+    }
+}
+
+/// Everything there is to say about an operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationDescription {
+    pub name: String,
+    pub description: String,
+    /// Contexts in which the operation does something.
+    pub contexts: Vec<String>,
+    /// Keys bound to the operation, as pairs of context and key.
+    pub bindings: Vec<(String, String)>,
+}
+
+impl OperationDescription {
+    /// Internationalized lines of the description.
+    pub fn lines(&self) -> Vec<String> {
+        let usage = match operation_arguments(&self.name) {
+            Some(arguments) => format!("{} {}", self.name, arguments),
+            None => self.name.clone(),
+        };
+        let mut lines = vec![format!("{}: {}", usage, self.description)];
+
+        if self.contexts.is_empty() {
+            lines.push(format!("  {}", gettext("works in no context")));
+        } else {
+            lines.push(format!(
+                "  {}",
+                fmt!(&gettext("works in: %s"), &self.contexts.join(", "))
+            ));
+        }
+
+        // Contexts grouped by key, keys in the order in which they first appear
+        let mut keys: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (context, key) in &self.bindings {
+            match keys.iter_mut().find(|(k, _)| k == key) {
+                Some((_, contexts)) => contexts.push(context),
+                None => keys.push((key, vec![context])),
+            }
+        }
+        if keys.is_empty() {
+            lines.push(format!("  {}", gettext("not bound to any key")));
+        } else {
+            let keys = keys
+                .iter()
+                .map(|(key, contexts)| fmt!(&gettext("%s in %s"), *key, &contexts.join(", ")))
+                .collect::<Vec<_>>()
+                .join("; ");
+            lines.push(format!("  {}", fmt!(&gettext("bound to: %s"), &keys)));
+        }
+        lines
+    }
+}
+
+/// Internationalized error about `name` being neither an option nor an operation, suggesting
+/// the options and operations whose names are close to it.
+pub fn unknown_name_message(name: &str, options: &[String], operations: &[String]) -> String {
+    let mut known = options.to_vec();
+    known.extend(operations.iter().cloned());
+    known.sort();
+    known.dedup();
+    let suggestions = utils::suggest(known.iter().map(String::as_str), name);
+
+    if suggestions.is_empty() {
+        fmt!(&gettext("`%s' is neither an option nor an operation"), name)
+    } else {
+        fmt!(
+            &gettext("`%s' is neither an option nor an operation. Did you mean: %s?"),
+            name,
+            suggestions.join(", ")
+        )
+    }
+}
+
+/// Arguments of `describe` that start with `fragment`: names of options and operations, and the
+/// prefix for keys. Sorted, without duplicates.
+pub fn completions(fragment: &str, options: &[String], operations: &[String]) -> Vec<String> {
+    if key_argument(fragment).is_some() {
+        // There's no telling which keys the user has in mind
+        return Vec::new();
+    }
+
+    let mut result = options
+        .iter()
+        .chain(operations.iter())
+        .filter(|name| name.starts_with(fragment))
+        .cloned()
+        .collect::<Vec<_>>();
+    if KEY_PREFIX.starts_with(fragment) {
+        result.push(KEY_PREFIX.to_string());
+    }
+    result.sort();
+    result.dedup();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn option(kind: &str, default_value: &str, value: &str) -> OptionDescription {
+        OptionDescription {
+            name: "some-option".to_string(),
+            kind: kind.to_string(),
+            values: Vec::new(),
+            default_value: default_value.to_string(),
+            value: value.to_string(),
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn t_key_argument_needs_the_prefix() {
+        assert_eq!(key_argument("key:^R"), Some("^R"));
+        assert_eq!(key_argument("key:"), Some(""));
+        assert_eq!(key_argument("keys"), None);
+        assert_eq!(key_argument("reload"), None);
+    }
+
+    #[test]
+    fn t_option_description_shows_type_and_values() {
+        let mut description = option("int", "60", "15");
+        description.name = "reload-time".to_string();
+        description.origin = Some("/home/user/.newsboat/config:12".to_string());
+        assert_eq!(
+            description.lines(),
+            vec![
+                "reload-time (number)",
+                "  default: 60",
+                "  current: 15",
+                "  set at /home/user/.newsboat/config:12",
+            ]
+        );
+
+        let mut description = option("enum", "none", "title");
+        description.values = names(&["none", "title", "firsttag"]);
+        assert_eq!(
+            description.lines()[0],
+            "some-option (one of none, title, firsttag)"
+        );
+
+        assert_eq!(
+            option("bool", "no", "yes").lines()[0],
+            "some-option (yes/no)"
+        );
+        assert_eq!(option("path", "~", "~").lines()[0], "some-option (path)");
+        assert_eq!(option("str", "", "").lines()[0], "some-option (text)");
+    }
+
+    #[test]
+    fn t_option_description_quotes_text_values() {
+        let lines = option("str", "", "%t %u").lines();
+        assert_eq!(lines[1], r#"  default: """#);
+        assert_eq!(lines[2], r#"  current: "%t %u""#);
+
+        let lines = option("bool", "no", "yes").lines();
+        assert_eq!(lines[1], "  default: no");
+        assert_eq!(lines[2], "  current: yes");
+    }
+
+    #[test]
+    fn t_option_description_tells_where_the_value_comes_from() {
+        let mut description = option("int", "60", "60");
+        assert_eq!(description.lines()[3], "  not set, default value");
+
+        description.value = "15".to_string();
+        assert_eq!(description.lines()[3], "  set outside of config files");
+
+        // Setting the default value explicitly still counts as setting it
+        description.value = "60".to_string();
+        description.origin = Some("conf.d/reload:3".to_string());
+        assert_eq!(description.lines()[3], "  set at conf.d/reload:3");
+    }
+
+    #[test]
+    fn t_operation_description_shows_usage_contexts_and_keys() {
+        let description = OperationDescription {
+            name: "goto-percent".to_string(),
+            description: "Jump to a position given in percent".to_string(),
+            contexts: names(&["feedlist", "articlelist"]),
+            bindings: vec![
+                ("feedlist".to_string(), "%".to_string()),
+                ("articlelist".to_string(), "%".to_string()),
+                ("articlelist".to_string(), "^P".to_string()),
+            ],
+        };
+        assert_eq!(
+            description.lines(),
+            vec![
+                "goto-percent <percent>: Jump to a position given in percent",
+                "  works in: feedlist, articlelist",
+                "  bound to: % in feedlist, articlelist; ^P in articlelist",
+            ]
+        );
+    }
+
+    #[test]
+    fn t_operation_description_of_unbound_operation() {
+        let description = OperationDescription {
+            name: "reload-urls".to_string(),
+            description: "Reload the list of URLs from the configuration".to_string(),
+            contexts: names(&["feedlist"]),
+            bindings: Vec::new(),
+        };
+        assert_eq!(
+            description.lines(),
+            vec![
+                "reload-urls: Reload the list of URLs from the configuration",
+                "  works in: feedlist",
+                "  not bound to any key",
+            ]
+        );
+    }
+
+    #[test]
+    fn t_operations_without_arguments_have_no_usage() {
+        assert_eq!(operation_arguments("open-in-browser"), None);
+        assert_eq!(operation_arguments("describe"), Some("<name>"));
+        assert_eq!(
+            operation_arguments("rename-tag"),
+            Some("[<tag> <new name>]")
+        );
+    }
+
+    #[test]
+    fn t_unknown_name_message_suggests_options_and_operations() {
+        let options = names(&["reload-threads", "reload-time"]);
+        let operations = names(&["reload", "reload-all"]);
+        assert_eq!(
+            unknown_name_message("reload-tme", &options, &operations),
+            "`reload-tme' is neither an option nor an operation. Did you mean: reload-time, reload-all?"
+        );
+        assert_eq!(
+            unknown_name_message("reloa", &options, &operations),
+            "`reloa' is neither an option nor an operation. Did you mean: reload?"
+        );
+        assert_eq!(
+            unknown_name_message("colour", &options, &operations),
+            "`colour' is neither an option nor an operation"
+        );
+    }
+
+    #[test]
+    fn t_completions_include_options_operations_and_key_prefix() {
+        let options = names(&["keep-articles-days", "reload-time", "show-read-feeds"]);
+        let operations = names(&["open", "reload", "reload-all"]);
+        assert_eq!(
+            completions("rel", &options, &operations),
+            names(&["reload", "reload-all", "reload-time"])
+        );
+        assert_eq!(
+            completions("ke", &options, &operations),
+            names(&["keep-articles-days", "key:"])
+        );
+        assert_eq!(
+            completions("key:", &options, &operations),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            completions("xyz", &options, &operations),
+            Vec::<String>::new()
+        );
+        assert_eq!(completions("", &options, &operations).len(), 7);
+    }
+
+    #[test]
+    fn t_completions_have_no_duplicates() {
+        let options = names(&["open"]);
+        let operations = names(&["open"]);
+        assert_eq!(completions("o", &options, &operations), names(&["open"]));
+    }
+}
@@ -0,0 +1,254 @@
+//! A model of the open dialogs (feed list, article lists, articles, help and so on).
+//!
+//! The View keeps the dialogs themselves; this tracks what kind each of them is, which dialog it
+//! was opened from, and in which order they were opened. That is enough to render a breadcrumb
+//! like "Feeds > Planet Rust > Article", and to decide which dialogs to close, and in which order,
+//! to get back to the feed list.
+//!
+//! Dialogs are identified by handles that the caller picks (the View uses the addresses of its
+//! objects). Any dialog can be removed at any time, not only the newest one: the dialog list lets
+//! the user close dialogs in the middle. Dialogs that were opened from a removed dialog are then
+//! treated as opened from the feed list, which is also what the View does.
+
+use gettextrs::gettext;
+
+/// The kind of the feed list, as returned by `FormAction::id()`.
+pub const FEEDLIST: &str = "feedlist";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Dialog {
+    handle: usize,
+    /// What `FormAction::id()` returns for the dialog, e.g. "articlelist".
+    kind: String,
+    /// Shown in the breadcrumb instead of the name of the kind, if not empty.
+    name: String,
+    /// Handle of the dialog that was current when this one was opened.
+    parent: Option<usize>,
+}
+
+/// Which dialogs are open, in the order in which they were opened.
+#[derive(Debug, Default)]
+pub struct DialogStack {
+    /// The oldest dialog first.
+    dialogs: Vec<Dialog>,
+}
+
+impl DialogStack {
+    pub fn new() -> DialogStack {
+        DialogStack::default()
+    }
+
+    /// Records that the dialog `handle` was opened while `parent` was the current dialog. If the
+    /// handle is already known, the old entry is replaced.
+    pub fn push(&mut self, handle: usize, kind: &str, name: &str, parent: Option<usize>) {
+        self.dialogs.retain(|dialog| dialog.handle != handle);
+        let parent = parent.filter(|parent| self.contains(*parent));
+        self.dialogs.push(Dialog {
+            handle,
+            kind: kind.to_string(),
+            name: name.to_string(),
+            parent,
+        });
+    }
+
+    /// Forgets the dialog `handle`. Returns `false` if there was no such dialog.
+    pub fn remove(&mut self, handle: usize) -> bool {
+        let len = self.dialogs.len();
+        self.dialogs.retain(|dialog| dialog.handle != handle);
+        if self.dialogs.len() == len {
+            return false;
+        }
+
+        let root = self.dialogs.first().map(|dialog| dialog.handle);
+        for dialog in &mut self.dialogs {
+            if dialog.parent == Some(handle) {
+                dialog.parent = root.filter(|root| *root != dialog.handle);
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.dialogs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dialogs.is_empty()
+    }
+
+    pub fn contains(&self, handle: usize) -> bool {
+        self.find(handle).is_some()
+    }
+
+    fn find(&self, handle: usize) -> Option<&Dialog> {
+        self.dialogs.iter().find(|dialog| dialog.handle == handle)
+    }
+
+    /// The dialogs from the one that `handle` descends from, down to `handle` itself.
+    fn path(&self, handle: usize) -> Vec<&Dialog> {
+        let mut path = Vec::new();
+        let mut next = self.find(handle);
+        while let Some(dialog) = next {
+            // Guard against cycles, which `push` could create if handles are reused
+            if path.len() > self.dialogs.len() {
+                break;
+            }
+            path.push(dialog);
+            next = dialog.parent.and_then(|parent| self.find(parent));
+        }
+        path.reverse();
+        path
+    }
+
+    /// Number of dialogs between the feed list and `handle`, counting both. Zero if `handle` is
+    /// unknown.
+    pub fn depth(&self, handle: usize) -> usize {
+        self.path(handle).len()
+    }
+
+    /// Labels of the dialogs leading to `handle`, separated by " > ", e.g. "Feeds > Planet Rust >
+    /// Article".
+    pub fn breadcrumb(&self, handle: usize) -> String {
+        self.path(handle)
+            .iter()
+            .map(|dialog| label(dialog))
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+
+    /// Handles of the dialogs that have to be closed, in order, to get from `handle` back to the
+    /// feed list: `handle` itself, then the dialog it was opened from, and so on. Dialogs that
+    /// aren't on that path stay open.
+    pub fn unwind_to_feedlist(&self, handle: usize) -> Vec<usize> {
+        self.path(handle)
+            .iter()
+            .rev()
+            .take_while(|dialog| dialog.kind != FEEDLIST)
+            .map(|dialog| dialog.handle)
+            .collect()
+    }
+
+    /// Handles of all dialogs except the feed list, the newest one first.
+    pub fn close_all(&self) -> Vec<usize> {
+        self.dialogs
+            .iter()
+            .rev()
+            .filter(|dialog| dialog.kind != FEEDLIST)
+            .map(|dialog| dialog.handle)
+            .collect()
+    }
+}
+
+fn label(dialog: &Dialog) -> String {
+    if !dialog.name.is_empty() {
+        return dialog.name.clone();
+    }
+    match dialog.kind.as_str() {
+        FEEDLIST => gettext("Feeds"),
+        "articlelist" => gettext("Articles"),
+        "article" => gettext("Article"),
+        "help" => gettext("Help"),
+        "urlview" => gettext("URLs"),
+        "dialogs" => gettext("Dialogs"),
+        "filebrowser" => gettext("Files"),
+        "dirbrowser" => gettext("Directories"),
+        "tagselection" => gettext("Tags"),
+        "filterselection" => gettext("Filters"),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEEDS: usize = 1;
+    const PLANET: usize = 2;
+    const ARTICLE: usize = 3;
+    const URLS: usize = 4;
+    const HELP: usize = 5;
+
+    /// feedlist → articlelist → article → urlview → help
+    fn nested() -> DialogStack {
+        let mut stack = DialogStack::new();
+        stack.push(FEEDS, FEEDLIST, "", None);
+        stack.push(PLANET, "articlelist", "Planet Rust", Some(FEEDS));
+        stack.push(ARTICLE, "article", "", Some(PLANET));
+        stack.push(URLS, "urlview", "", Some(ARTICLE));
+        stack.push(HELP, "help", "", Some(URLS));
+        stack
+    }
+
+    #[test]
+    fn t_breadcrumb_lists_the_dialogs_leading_to_the_given_one() {
+        let stack = nested();
+
+        assert_eq!(stack.breadcrumb(FEEDS), "Feeds");
+        assert_eq!(stack.breadcrumb(ARTICLE), "Feeds > Planet Rust > Article");
+        assert_eq!(
+            stack.breadcrumb(HELP),
+            "Feeds > Planet Rust > Article > URLs > Help"
+        );
+        assert_eq!(stack.depth(ARTICLE), 3);
+
+        assert_eq!(stack.breadcrumb(42), "");
+        assert_eq!(stack.depth(42), 0);
+    }
+
+    #[test]
+    fn t_unwinding_closes_the_newest_dialog_first_and_stops_at_the_feed_list() {
+        let stack = nested();
+
+        assert_eq!(
+            stack.unwind_to_feedlist(HELP),
+            vec![HELP, URLS, ARTICLE, PLANET]
+        );
+        assert_eq!(stack.unwind_to_feedlist(ARTICLE), vec![ARTICLE, PLANET]);
+        assert!(stack.unwind_to_feedlist(FEEDS).is_empty());
+        assert!(stack.unwind_to_feedlist(42).is_empty());
+    }
+
+    #[test]
+    fn t_unwinding_leaves_dialogs_on_other_paths_open() {
+        let mut stack = nested();
+        // Help opened from the feed list, after the rest
+        stack.push(6, "help", "", Some(FEEDS));
+
+        assert_eq!(stack.unwind_to_feedlist(URLS), vec![URLS, ARTICLE, PLANET]);
+        assert_eq!(stack.close_all(), vec![6, HELP, URLS, ARTICLE, PLANET]);
+    }
+
+    #[test]
+    fn t_removing_a_dialog_in_the_middle_attaches_its_children_to_the_feed_list() {
+        let mut stack = nested();
+
+        assert!(stack.remove(ARTICLE));
+        assert!(!stack.remove(ARTICLE));
+        assert_eq!(stack.len(), 4);
+        assert_eq!(stack.breadcrumb(HELP), "Feeds > URLs > Help");
+        assert_eq!(stack.unwind_to_feedlist(HELP), vec![HELP, URLS]);
+        assert_eq!(stack.breadcrumb(PLANET), "Feeds > Planet Rust");
+        assert_eq!(stack.close_all(), vec![HELP, URLS, PLANET]);
+    }
+
+    #[test]
+    fn t_pushing_a_known_handle_replaces_the_old_entry() {
+        let mut stack = nested();
+        stack.push(ARTICLE, "article", "", Some(FEEDS));
+
+        assert_eq!(stack.len(), 5);
+        assert_eq!(stack.breadcrumb(ARTICLE), "Feeds > Article");
+        assert_eq!(stack.close_all()[0], ARTICLE);
+        assert_eq!(stack.breadcrumb(URLS), "Feeds > Article > URLs");
+    }
+
+    #[test]
+    fn t_parents_that_are_not_open_are_ignored() {
+        let mut stack = DialogStack::new();
+        stack.push(FEEDS, FEEDLIST, "", None);
+        stack.push(PLANET, "articlelist", "", Some(42));
+
+        assert_eq!(stack.breadcrumb(PLANET), "Articles");
+        assert_eq!(stack.unwind_to_feedlist(PLANET), vec![PLANET]);
+    }
+}
@@ -0,0 +1,706 @@
+//! Listing of a directory, as shown by the file and directory browsers.
+//!
+//! Entries are `lstat`ed, so symlinks are listed as such; where they point to is resolved one
+//! link at a time, keeping track of the links already visited, so that a symlink loop makes the
+//! link dangling rather than hanging the dialog. Entries that can't be `lstat`ed (e.g. in
+//! a directory that can be read but not searched) are still listed, along with the error.
+
+use crate::utils;
+use gettextrs::gettext;
+use libc::c_char;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ffi::{CStr, OsString};
+use std::fs;
+use std::io;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+/// What an entry of the listing is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    BlockDevice,
+    CharDevice,
+    Fifo,
+    Socket,
+    /// The entry couldn't be `lstat`ed.
+    Unknown,
+}
+
+impl EntryKind {
+    fn of(file_type: fs::FileType) -> EntryKind {
+        if file_type.is_file() {
+            EntryKind::File
+        } else if file_type.is_dir() {
+            EntryKind::Directory
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_block_device() {
+            EntryKind::BlockDevice
+        } else if file_type.is_char_device() {
+            EntryKind::CharDevice
+        } else if file_type.is_fifo() {
+            EntryKind::Fifo
+        } else if file_type.is_socket() {
+            EntryKind::Socket
+        } else {
+            EntryKind::Unknown
+        }
+    }
+
+    /// The character `ls -l` shows for this kind of file.
+    pub fn type_char(self) -> char {
+        match self {
+            EntryKind::File => '-',
+            EntryKind::Directory => 'd',
+            EntryKind::Symlink => 'l',
+            EntryKind::BlockDevice => 'b',
+            EntryKind::CharDevice => 'c',
+            EntryKind::Fifo => 'p',
+            EntryKind::Socket => 's',
+            EntryKind::Unknown => '?',
+        }
+    }
+}
+
+/// Key the entries are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Alphabetically.
+    Name,
+    /// Most recently modified first.
+    Mtime,
+    /// Largest first.
+    Size,
+}
+
+impl SortKey {
+    /// Parses the value of `filebrowser-sort-order`.
+    pub fn from_name(name: &str) -> Option<SortKey> {
+        match name {
+            "name" => Some(SortKey::Name),
+            "mtime" => Some(SortKey::Mtime),
+            "size" => Some(SortKey::Size),
+            _ => None,
+        }
+    }
+}
+
+/// How to list a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirListOptions {
+    /// List entries whose names start with a dot.
+    pub show_hidden: bool,
+    /// List directories (and symlinks to them) before everything else.
+    pub dirs_first: bool,
+    pub sort_key: SortKey,
+    /// Only list directories (and symlinks to them), as the directory browser does.
+    pub directories_only: bool,
+}
+
+impl Default for DirListOptions {
+    fn default() -> DirListOptions {
+        DirListOptions {
+            show_hidden: true,
+            dirs_first: false,
+            sort_key: SortKey::Name,
+            directories_only: false,
+        }
+    }
+}
+
+/// An entry of a directory listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: OsString,
+    pub kind: EntryKind,
+    pub size: u64,
+    /// Seconds since the epoch.
+    pub mtime: i64,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// Whether this is a directory, or a symlink that ends up at one.
+    pub leads_to_directory: bool,
+    /// Why the entry couldn't be `lstat`ed, if it couldn't.
+    pub error: Option<String>,
+}
+
+impl DirEntry {
+    fn read(directory: &Path, name: OsString) -> DirEntry {
+        let path = directory.join(&name);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) => {
+                let kind = EntryKind::of(metadata.file_type());
+                let leads_to_directory = match kind {
+                    EntryKind::Directory => true,
+                    EntryKind::Symlink => resolve_symlinks(&path)
+                        .map(|target| target.is_dir())
+                        .unwrap_or(false),
+                    _ => false,
+                };
+                DirEntry {
+                    name,
+                    kind,
+                    size: metadata.len(),
+                    mtime: metadata.mtime(),
+                    mode: metadata.mode(),
+                    uid: metadata.uid(),
+                    gid: metadata.gid(),
+                    leads_to_directory,
+                    error: None,
+                }
+            }
+            Err(error) => DirEntry {
+                name,
+                kind: EntryKind::Unknown,
+                size: 0,
+                mtime: 0,
+                mode: 0,
+                uid: 0,
+                gid: 0,
+                leads_to_directory: false,
+                error: Some(describe(&error)),
+            },
+        }
+    }
+
+    /// The `..` entry of `directory`, which is always there to leave the directory, even if it
+    /// can't be `lstat`ed.
+    fn parent(directory: &Path) -> DirEntry {
+        let entry = DirEntry::read(directory, OsString::from(".."));
+        if entry.error.is_none() {
+            return entry;
+        }
+        DirEntry {
+            name: OsString::from(".."),
+            kind: EntryKind::Directory,
+            size: 0,
+            mtime: 0,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            leads_to_directory: true,
+            error: None,
+        }
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.name != ".." && self.name.as_bytes().starts_with(b".")
+    }
+
+    /// The line the browsers show for this entry, in the style of `ls -l`.
+    pub fn row(&self) -> String {
+        let name = self.name.to_string_lossy();
+        if let Some(ref error) = self.error {
+            return format!(
+                "?????????? ???????? ???????? {:>12} {} ({})",
+                "?", name, error
+            );
+        }
+
+        let suffix = match self.kind {
+            EntryKind::Directory => "/",
+            EntryKind::Symlink => "@",
+            EntryKind::Socket => "=",
+            EntryKind::Fifo => "|",
+            EntryKind::File if self.mode & 0o100 != 0 => "*",
+            _ => "",
+        };
+        format!(
+            "{}{} {:<8} {:<8} {:>12} {}{}",
+            self.kind.type_char(),
+            rwx(self.mode),
+            user_name(self.uid).unwrap_or_else(|| "????????".to_string()),
+            group_name(self.gid).unwrap_or_else(|| "????????".to_string()),
+            self.size,
+            name,
+            suffix
+        )
+    }
+}
+
+/// Entries of a directory, sorted for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirList {
+    entries: Vec<DirEntry>,
+    error: Option<String>,
+}
+
+impl DirList {
+    /// Lists `path`. Unless `path` is the root directory, the listing starts with `..`.
+    ///
+    /// If `path` itself can't be read, the listing only has the `..` entry, and `error()` says
+    /// what went wrong.
+    pub fn read(path: &Path, options: &DirListOptions) -> DirList {
+        let mut entries = Vec::new();
+        let mut error = None;
+        match fs::read_dir(path) {
+            Ok(dir) => {
+                for entry in dir {
+                    match entry {
+                        Ok(entry) => entries.push(DirEntry::read(path, entry.file_name())),
+                        Err(e) => error = Some(describe(&e)),
+                    }
+                }
+            }
+            Err(e) => error = Some(describe(&e)),
+        }
+
+        entries.retain(|entry| {
+            (options.show_hidden || !entry.is_hidden())
+                && (!options.directories_only || entry.leads_to_directory)
+        });
+        entries.sort_by(|a, b| compare(a, b, options));
+        if path.parent().is_some() {
+            entries.insert(0, DirEntry::parent(path));
+        }
+
+        DirList { entries, error }
+    }
+
+    pub fn entries(&self) -> &[DirEntry] {
+        &self.entries
+    }
+
+    /// Internationalized description of why the directory couldn't be (fully) read.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+fn compare(a: &DirEntry, b: &DirEntry, options: &DirListOptions) -> Ordering {
+    let group = if options.dirs_first {
+        b.leads_to_directory.cmp(&a.leads_to_directory)
+    } else {
+        Ordering::Equal
+    };
+    let key = match options.sort_key {
+        SortKey::Name => Ordering::Equal,
+        SortKey::Mtime => b.mtime.cmp(&a.mtime),
+        SortKey::Size => b.size.cmp(&a.size),
+    };
+    group.then(key).then_with(|| a.name.cmp(&b.name))
+}
+
+/// Completes the path typed into a browser's input field, as a shell would: extends the last
+/// component to the longest common prefix of the matching entries, and appends a slash if that
+/// names a directory. Hidden entries only match if the typed component starts with a dot, or if
+/// `options` shows them. Returns `input` unchanged if nothing matches.
+pub fn complete_path(input: &str, options: &DirListOptions) -> String {
+    let (directory, prefix) = match input.rfind('/') {
+        Some(index) => input.split_at(index + 1),
+        None => ("", input),
+    };
+    let listed = if directory.is_empty() {
+        PathBuf::from(".")
+    } else {
+        utils::resolve_tilde(PathBuf::from(directory))
+    };
+
+    let options = DirListOptions {
+        show_hidden: options.show_hidden || prefix.starts_with('.'),
+        ..*options
+    };
+    let list = DirList::read(&listed, &options);
+    let candidates = list
+        .entries()
+        .iter()
+        .filter(|entry| entry.name != ".." && entry.error.is_none())
+        .filter(|entry| entry.name.as_bytes().starts_with(prefix.as_bytes()))
+        .collect::<Vec<_>>();
+
+    let first = match candidates.first() {
+        Some(first) => first.name.as_bytes(),
+        None => return input.to_string(),
+    };
+    let mut common = first.len();
+    for candidate in &candidates[1..] {
+        common = first
+            .iter()
+            .zip(candidate.name.as_bytes())
+            .take(common)
+            .take_while(|(a, b)| a == b)
+            .count();
+    }
+    // Don't cut a multi-byte character in half
+    let mut completed = String::from_utf8_lossy(&first[..common]).into_owned();
+    while !first.starts_with(completed.as_bytes()) {
+        completed.pop();
+    }
+
+    let mut result = format!("{}{}", directory, completed);
+    if candidates.len() == 1 && candidates[0].leads_to_directory {
+        result.push('/');
+    }
+    result
+}
+
+/// Follows the symlink at `path` until it ends up at something that isn't a symlink, and returns
+/// the metadata of that. Returns `None` if the link is dangling, or if it's part of a loop.
+fn resolve_symlinks(path: &Path) -> Option<fs::Metadata> {
+    let mut visited = HashSet::new();
+    let mut current = path.to_path_buf();
+    loop {
+        let metadata = fs::symlink_metadata(&current).ok()?;
+        if !metadata.file_type().is_symlink() {
+            return Some(metadata);
+        }
+        if !visited.insert((metadata.dev(), metadata.ino())) {
+            return None;
+        }
+        let target = fs::read_link(&current).ok()?;
+        current = match current.parent() {
+            Some(parent) => parent.join(target),
+            None => target,
+        };
+    }
+}
+
+fn describe(error: &io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::PermissionDenied => gettext("permission denied"),
+        _ => error.to_string(),
+    }
+}
+
+fn rwx(mode: u32) -> String {
+    (0..9)
+        .map(|bit| {
+            if mode & (0o400 >> bit) == 0 {
+                '-'
+            } else {
+                ['r', 'w', 'x'][bit % 3]
+            }
+        })
+        .collect()
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as c_char; 1024];
+    loop {
+        // `passwd` is a plain C struct, for which all zeroes is a valid value
+        let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let status = unsafe {
+            libc::getpwuid_r(
+                uid,
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if status == libc::ERANGE {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        let name = unsafe { CStr::from_ptr(passwd.pw_name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+fn group_name(gid: u32) -> Option<String> {
+    let mut buffer = vec![0 as c_char; 1024];
+    loop {
+        // `group` is a plain C struct, for which all zeroes is a valid value
+        let mut group: libc::group = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let status = unsafe {
+            libc::getgrgid_r(
+                gid,
+                &mut group,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if status == libc::ERANGE {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        let name = unsafe { CStr::from_ptr(group.gr_name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn names(list: &DirList) -> Vec<String> {
+        list.entries()
+            .iter()
+            .map(|entry| entry.name.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn fixture() -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        fs::write(tmp.path().join("big"), vec![0u8; 300]).unwrap();
+        fs::write(tmp.path().join("small"), b"x").unwrap();
+        fs::write(tmp.path().join(".hidden"), b"xx").unwrap();
+        fs::create_dir(tmp.path().join("zdir")).unwrap();
+        fs::create_dir(tmp.path().join(".config")).unwrap();
+        tmp
+    }
+
+    fn set_mtime(path: &Path, seconds_ago: u64) {
+        let time = SystemTime::now() - Duration::from_secs(seconds_ago);
+        let file = fs::File::open(path).unwrap();
+        let times = [
+            libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_OMIT,
+            },
+            libc::timespec {
+                tv_sec: time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as libc::time_t,
+                tv_nsec: 0,
+            },
+        ];
+        use std::os::unix::io::AsRawFd;
+        assert_eq!(
+            unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) },
+            0
+        );
+    }
+
+    #[test]
+    fn t_lists_everything_by_name_after_the_parent_directory() {
+        let tmp = fixture();
+        let list = DirList::read(tmp.path(), &DirListOptions::default());
+        assert_eq!(
+            names(&list),
+            vec!["..", ".config", ".hidden", "big", "small", "zdir"]
+        );
+        assert_eq!(list.error(), None);
+
+        let big = &list.entries()[3];
+        assert_eq!(big.kind, EntryKind::File);
+        assert_eq!(big.size, 300);
+        assert!(!big.leads_to_directory);
+        assert!(list.entries()[5].leads_to_directory);
+    }
+
+    #[test]
+    fn t_root_directory_has_no_parent_entry() {
+        let list = DirList::read(Path::new("/"), &DirListOptions::default());
+        assert!(!names(&list).contains(&"..".to_string()));
+    }
+
+    #[test]
+    fn t_hidden_entries_can_be_left_out() {
+        let tmp = fixture();
+        let options = DirListOptions {
+            show_hidden: false,
+            ..DirListOptions::default()
+        };
+        let list = DirList::read(tmp.path(), &options);
+        assert_eq!(names(&list), vec!["..", "big", "small", "zdir"]);
+    }
+
+    #[test]
+    fn t_directories_can_be_grouped_first() {
+        let tmp = fixture();
+        let options = DirListOptions {
+            dirs_first: true,
+            ..DirListOptions::default()
+        };
+        let list = DirList::read(tmp.path(), &options);
+        assert_eq!(
+            names(&list),
+            vec!["..", ".config", "zdir", ".hidden", "big", "small"]
+        );
+    }
+
+    #[test]
+    fn t_entries_can_be_sorted_by_size_or_mtime() {
+        let tmp = fixture();
+        let options = DirListOptions {
+            show_hidden: false,
+            sort_key: SortKey::Size,
+            ..DirListOptions::default()
+        };
+        let list = DirList::read(tmp.path(), &options);
+        // The size of directories depends on the filesystem, so only look at the files
+        let files = names(&list)
+            .into_iter()
+            .filter(|name| name != "zdir")
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["..", "big", "small"]);
+
+        set_mtime(&tmp.path().join("big"), 3000);
+        set_mtime(&tmp.path().join("small"), 2000);
+        set_mtime(&tmp.path().join("zdir"), 1000);
+        let options = DirListOptions {
+            show_hidden: false,
+            sort_key: SortKey::Mtime,
+            ..DirListOptions::default()
+        };
+        let list = DirList::read(tmp.path(), &options);
+        assert_eq!(names(&list), vec!["..", "zdir", "small", "big"]);
+    }
+
+    #[test]
+    fn t_directory_browser_only_lists_directories_and_links_to_them() {
+        let tmp = fixture();
+        symlink("zdir", tmp.path().join("link-to-dir")).unwrap();
+        symlink("small", tmp.path().join("link-to-file")).unwrap();
+        let options = DirListOptions {
+            directories_only: true,
+            ..DirListOptions::default()
+        };
+        let list = DirList::read(tmp.path(), &options);
+        assert_eq!(names(&list), vec!["..", ".config", "link-to-dir", "zdir"]);
+        assert_eq!(list.entries()[2].kind, EntryKind::Symlink);
+    }
+
+    #[test]
+    fn t_symlink_loops_are_listed_as_dangling_links() {
+        let tmp = TempDir::new().unwrap();
+        symlink("b", tmp.path().join("a")).unwrap();
+        symlink("a", tmp.path().join("b")).unwrap();
+        symlink(".", tmp.path().join("self")).unwrap();
+        symlink("self/self/self/a", tmp.path().join("deep")).unwrap();
+
+        let list = DirList::read(tmp.path(), &DirListOptions::default());
+        assert_eq!(names(&list), vec!["..", "a", "b", "deep", "self"]);
+        let entries = list.entries();
+        for entry in &entries[1..4] {
+            assert_eq!(entry.kind, EntryKind::Symlink);
+            assert!(!entry.leads_to_directory);
+            assert_eq!(entry.error, None);
+        }
+        // A link to its own directory isn't a loop; only entering it repeatedly would be
+        assert!(entries[4].leads_to_directory);
+    }
+
+    #[test]
+    fn t_unreadable_entries_are_listed_with_the_error() {
+        if unsafe { libc::geteuid() } == 0 {
+            // root can look into any directory
+            return;
+        }
+        let tmp = TempDir::new().unwrap();
+        let locked = tmp.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("secret"), b"x").unwrap();
+        // Readable but not searchable: names can be listed, but not `lstat`ed
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o400)).unwrap();
+
+        let list = DirList::read(&locked, &DirListOptions::default());
+        let unreadable = list.entries().to_vec();
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o700)).unwrap();
+
+        assert_eq!(unreadable.len(), 2);
+        let secret = &unreadable[1];
+        assert_eq!(secret.name, "secret");
+        assert_eq!(secret.kind, EntryKind::Unknown);
+        assert_eq!(secret.error.as_deref(), Some("permission denied"));
+        assert!(secret.row().ends_with("secret (permission denied)"));
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        let list = DirList::read(&locked, &DirListOptions::default());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(names(&list), vec![".."]);
+        assert_eq!(list.error(), Some("permission denied"));
+    }
+
+    #[test]
+    fn t_missing_directory_is_listed_with_the_error() {
+        let tmp = TempDir::new().unwrap();
+        let list = DirList::read(&tmp.path().join("gone"), &DirListOptions::default());
+        assert_eq!(names(&list), vec![".."]);
+        assert!(list.error().is_some());
+    }
+
+    #[test]
+    fn t_rows_look_like_ls() {
+        let tmp = TempDir::new().unwrap();
+        let script = tmp.path().join("script");
+        fs::write(&script, b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o750)).unwrap();
+        fs::create_dir(tmp.path().join("dir")).unwrap();
+        fs::set_permissions(tmp.path().join("dir"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("script", tmp.path().join("link")).unwrap();
+
+        let list = DirList::read(tmp.path(), &DirListOptions::default());
+        let rows = list
+            .entries()
+            .iter()
+            .map(|entry| entry.row())
+            .collect::<Vec<_>>();
+        assert!(rows[1].starts_with("drwxr-xr-x "));
+        assert!(rows[1].ends_with(" dir/"));
+        assert!(rows[2].starts_with("lrwxrwxrwx "));
+        assert!(rows[2].ends_with(" link@"));
+        assert!(rows[3].starts_with("-rwxr-x--- "));
+        assert!(rows[3].ends_with("           10 script*"));
+    }
+
+    #[test]
+    fn t_complete_path_extends_to_the_common_prefix() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(tmp.path().join("report-2020.txt"), b"").unwrap();
+        fs::write(tmp.path().join("report-2021.txt"), b"").unwrap();
+        fs::create_dir(tmp.path().join("photos")).unwrap();
+        fs::write(tmp.path().join(".profile"), b"").unwrap();
+        let base = format!("{}/", tmp.path().display());
+        let options = DirListOptions {
+            show_hidden: false,
+            ..DirListOptions::default()
+        };
+
+        let complete = |typed: &str| complete_path(&format!("{}{}", base, typed), &options);
+        assert_eq!(complete("rep"), format!("{}report-202", base));
+        assert_eq!(complete("report-2021"), format!("{}report-2021.txt", base));
+        assert_eq!(complete("ph"), format!("{}photos/", base));
+        assert_eq!(complete("nothing"), format!("{}nothing", base));
+        assert_eq!(complete(""), base);
+        assert_eq!(complete(".pr"), format!("{}.profile", base));
+
+        let directories_only = DirListOptions {
+            directories_only: true,
+            ..options
+        };
+        assert_eq!(
+            complete_path(&base, &directories_only),
+            format!("{}photos/", base)
+        );
+    }
+
+    #[test]
+    fn t_complete_path_keeps_multibyte_characters_whole() {
+        let tmp = TempDir::new().unwrap();
+        // "é" and "è" only differ in their second byte
+        fs::write(tmp.path().join("été"), b"").unwrap();
+        fs::write(tmp.path().join("ève"), b"").unwrap();
+        fs::write(tmp.path().join("ärger"), b"").unwrap();
+        let base = format!("{}/", tmp.path().display());
+        let options = DirListOptions::default();
+        assert_eq!(complete_path(&base, &options), base);
+        assert_eq!(
+            complete_path(&format!("{}ä", base), &options),
+            format!("{}ärger", base)
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Limits that a feed download has to stay within.
+//!
+//! A server that accepts the connection and then sends one byte a second would keep a reload
+//! thread busy forever, and a connection that breaks off in the middle of a response would hand
+//! a short document to the parser. The transfer is therefore watched from curl's progress
+//! callback: it's aborted once the whole download took longer than `download-timeout`, or if it
+//! stayed below `download-low-speed-limit` bytes per second for `download-low-speed-time`
+//! seconds. Once it finished, the number of received bytes is compared to the `Content-Length`
+//! the server announced.
+//!
+//! Each of these failures has its own message, so that the user can tell from the reload errors
+//! which limit a feed ran into.
+
+use gettextrs::gettext;
+use std::time::{Duration, Instant};
+use strprintf::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// How long the whole download may take. Zero means there is no deadline.
+    pub timeout: Duration,
+
+    /// Bytes per second that the download mustn't stay below for `low_speed_time`. Zero turns
+    /// the check off.
+    pub low_speed_limit: u32,
+
+    pub low_speed_time: Duration,
+}
+
+impl Limits {
+    /// Limits as they're configured, in seconds and bytes per second.
+    pub fn from_config(timeout: u32, low_speed_limit: u32, low_speed_time: u32) -> Limits {
+        Limits {
+            timeout: Duration::from_secs(u64::from(timeout)),
+            low_speed_limit,
+            low_speed_time: Duration::from_secs(u64::from(low_speed_time)),
+        }
+    }
+}
+
+/// Why a download failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadError {
+    /// The download didn't finish within `download-timeout` seconds.
+    DeadlineExceeded { seconds: u32 },
+
+    /// The download stayed below `limit` bytes per second for `seconds`.
+    TooSlow { limit: u32, seconds: u32 },
+
+    /// The server announced `expected` bytes, but the connection ended after `received`.
+    Truncated { expected: u64, received: u64 },
+}
+
+impl DownloadError {
+    pub fn to_message(&self) -> String {
+        match *self {
+            DownloadError::DeadlineExceeded { seconds } => fmt_plural!(
+                seconds,
+                "Download didn't finish within %u second (download-timeout)",
+                "Download didn't finish within %u seconds (download-timeout)",
+                seconds
+            ),
+            DownloadError::TooSlow { limit, seconds } => fmt!(
+                &gettext("Download was slower than %s for %s (download-low-speed-limit)"),
+                fmt_plural!(limit, "%u byte per second", "%u bytes per second", limit),
+                fmt_plural!(seconds, "%u second", "%u seconds", seconds)
+            ),
+            DownloadError::Truncated { expected, received } => fmt_plural!(
+                expected,
+                "Download was cut short: got %s of %s byte",
+                "Download was cut short: got %s of %s bytes",
+                received.to_string(),
+                expected.to_string()
+            ),
+        }
+    }
+}
+
+/// Watches a single download.
+#[derive(Debug)]
+pub struct DownloadWatch {
+    limits: Limits,
+    started: Instant,
+
+    /// When the current low-speed window started, and how many bytes were received by then.
+    /// The first window starts with the first byte, since servers that take a while to generate
+    /// a feed aren't slow in the sense of this check; the deadline still covers them.
+    window: Option<(Instant, u64)>,
+}
+
+impl DownloadWatch {
+    pub fn new(limits: Limits, now: Instant) -> DownloadWatch {
+        DownloadWatch {
+            limits,
+            started: now,
+            window: None,
+        }
+    }
+
+    /// Takes a progress report: `received` bytes of the response so far, at `now`. Returns an
+    /// error if the download should be aborted.
+    pub fn progress(&mut self, received: u64, now: Instant) -> Result<(), DownloadError> {
+        let timeout = self.limits.timeout;
+        if timeout > Duration::from_secs(0) && now.duration_since(self.started) >= timeout {
+            return Err(DownloadError::DeadlineExceeded {
+                seconds: timeout.as_secs() as u32,
+            });
+        }
+
+        let low_speed_time = self.limits.low_speed_time;
+        if self.limits.low_speed_limit == 0 || low_speed_time == Duration::from_secs(0) {
+            return Ok(());
+        }
+
+        let (window_start, window_bytes) = match self.window {
+            Some(window) => window,
+            None => {
+                if received > 0 {
+                    self.window = Some((now, received));
+                }
+                return Ok(());
+            }
+        };
+
+        let elapsed = now.duration_since(window_start);
+        if elapsed < low_speed_time {
+            return Ok(());
+        }
+
+        // A redirect starts the count over, so there may be fewer bytes than before.
+        let bytes = received.saturating_sub(window_bytes);
+        let needed = u64::from(self.limits.low_speed_limit) * elapsed.as_secs();
+        if bytes < needed {
+            return Err(DownloadError::TooSlow {
+                limit: self.limits.low_speed_limit,
+                seconds: low_speed_time.as_secs() as u32,
+            });
+        }
+
+        self.window = Some((now, received));
+        Ok(())
+    }
+}
+
+/// Checks a finished download against the length the server announced, if it did.
+pub fn check_length(expected: Option<u64>, received: u64) -> Result<(), DownloadError> {
+    match expected {
+        Some(expected) if received < expected => {
+            Err(DownloadError::Truncated { expected, received })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn limits() -> Limits {
+        Limits::from_config(30, 10, 5)
+    }
+
+    /// What a fake server does after it received a request.
+    #[derive(Clone, Copy)]
+    enum Behaviour {
+        /// Sends the whole response at once.
+        Normal,
+        /// Sends the headers, then five bytes a second.
+        Dribble,
+        /// Announces more bytes than it sends, and closes the connection.
+        Truncate,
+    }
+
+    const BODY: &str = "<rss version=\"2.0\"><channel><title>t</title></channel></rss>";
+
+    /// Starts a server that handles a single request, and returns its address.
+    fn fake_server(behaviour: Behaviour) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let length = match behaviour {
+                Behaviour::Truncate => BODY.len() * 2,
+                _ => BODY.len(),
+            };
+            let headers = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", length);
+            let _ = stream.write_all(headers.as_bytes());
+            match behaviour {
+                Behaviour::Normal | Behaviour::Truncate => {
+                    let _ = stream.write_all(BODY.as_bytes());
+                }
+                Behaviour::Dribble => {
+                    for byte in BODY.as_bytes() {
+                        if stream.write_all(&[*byte]).is_err() {
+                            break;
+                        }
+                        thread::sleep(Duration::from_millis(200));
+                    }
+                }
+            }
+        });
+        address
+    }
+
+    /// Downloads from `address` the way the downloader does, reporting progress to a watch every
+    /// time something arrived, or after `tick` without anything.
+    fn download(address: &str, limits: Limits, tick: Duration) -> Result<String, DownloadError> {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(tick)).unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+
+        let mut watch = DownloadWatch::new(limits, Instant::now());
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 256];
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => response.extend_from_slice(&buffer[..n]),
+                Err(_) => {}
+            }
+            let received = body_of(&response).map(|body| body.len()).unwrap_or(0);
+            watch.progress(received as u64, Instant::now())?;
+        }
+
+        let response = String::from_utf8(response).unwrap();
+        let body = body_of(response.as_bytes()).unwrap_or(&[]);
+        let expected = response
+            .lines()
+            .find(|line| line.starts_with("Content-Length: "))
+            .map(|line| line["Content-Length: ".len()..].trim().parse().unwrap());
+        check_length(expected, body.len() as u64)?;
+        Ok(String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn body_of(response: &[u8]) -> Option<&[u8]> {
+        response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .map(|end| &response[end + 4..])
+    }
+
+    #[test]
+    fn t_download_is_aborted_once_it_takes_longer_than_the_timeout() {
+        let start = Instant::now();
+        let mut watch = DownloadWatch::new(limits(), start);
+        // Plenty fast, but the deadline is about the whole download
+        for second in 1..30 {
+            assert_eq!(watch.progress(second * 1000, start + secs(second)), Ok(()));
+        }
+        assert_eq!(
+            watch.progress(30_000, start + secs(30)),
+            Err(DownloadError::DeadlineExceeded { seconds: 30 })
+        );
+    }
+
+    #[test]
+    fn t_download_is_aborted_if_it_stays_too_slow() {
+        let start = Instant::now();
+        let mut watch = DownloadWatch::new(limits(), start);
+        // Waiting for the first byte doesn't count as slow
+        assert_eq!(watch.progress(0, start + secs(10)), Ok(()));
+        assert_eq!(watch.progress(1, start + secs(11)), Ok(()));
+        // 10 bytes a second for 5 seconds is just enough
+        assert_eq!(watch.progress(51, start + secs(16)), Ok(()));
+        assert_eq!(watch.progress(60, start + secs(20)), Ok(()));
+        assert_eq!(
+            watch.progress(70, start + secs(21)),
+            Err(DownloadError::TooSlow {
+                limit: 10,
+                seconds: 5
+            })
+        );
+    }
+
+    #[test]
+    fn t_zero_turns_the_limits_off() {
+        let start = Instant::now();
+        let mut watch = DownloadWatch::new(Limits::from_config(0, 0, 5), start);
+        assert_eq!(watch.progress(1, start + secs(1)), Ok(()));
+        assert_eq!(watch.progress(1, start + secs(3600)), Ok(()));
+    }
+
+    #[test]
+    fn t_check_length_detects_truncated_downloads() {
+        assert_eq!(check_length(Some(100), 100), Ok(()));
+        assert_eq!(check_length(None, 10), Ok(()));
+        assert_eq!(
+            check_length(Some(100), 60),
+            Err(DownloadError::Truncated {
+                expected: 100,
+                received: 60
+            })
+        );
+    }
+
+    #[test]
+    fn t_errors_have_distinct_messages() {
+        let messages = [
+            DownloadError::DeadlineExceeded { seconds: 30 }.to_message(),
+            DownloadError::TooSlow {
+                limit: 10,
+                seconds: 5,
+            }
+            .to_message(),
+            DownloadError::Truncated {
+                expected: 100,
+                received: 60,
+            }
+            .to_message(),
+        ];
+        assert!(messages[0].contains("30 seconds"));
+        assert!(messages[1].contains("10 bytes per second"));
+        assert!(messages[2].contains("60 of 100 bytes"));
+        assert_ne!(messages[0], messages[1]);
+        assert_ne!(messages[1], messages[2]);
+    }
+
+    #[test]
+    fn t_normal_download_from_a_fake_server_is_unaffected() {
+        let address = fake_server(Behaviour::Normal);
+        let limits = Limits::from_config(5, 10, 1);
+        assert_eq!(
+            download(&address, limits, Duration::from_millis(100)),
+            Ok(BODY.to_string())
+        );
+    }
+
+    #[test]
+    fn t_dribbling_fake_server_runs_into_the_deadline() {
+        let address = fake_server(Behaviour::Dribble);
+        // The low-speed check is off, so only the deadline can stop this
+        let limits = Limits::from_config(1, 0, 0);
+        assert_eq!(
+            download(&address, limits, Duration::from_millis(100)),
+            Err(DownloadError::DeadlineExceeded { seconds: 1 })
+        );
+    }
+
+    #[test]
+    fn t_dribbling_fake_server_runs_into_the_low_speed_limit() {
+        let address = fake_server(Behaviour::Dribble);
+        let limits = Limits::from_config(60, 100, 1);
+        assert_eq!(
+            download(&address, limits, Duration::from_millis(100)),
+            Err(DownloadError::TooSlow {
+                limit: 100,
+                seconds: 1
+            })
+        );
+    }
+
+    #[test]
+    fn t_truncating_fake_server_is_detected() {
+        let address = fake_server(Behaviour::Truncate);
+        let limits = Limits::from_config(5, 10, 1);
+        assert_eq!(
+            download(&address, limits, Duration::from_millis(100)),
+            Err(DownloadError::Truncated {
+                expected: 2 * BODY.len() as u64,
+                received: BODY.len() as u64
+            })
+        );
+    }
+}
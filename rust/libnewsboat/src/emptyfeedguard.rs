@@ -0,0 +1,232 @@
+//! Protects cached articles from servers that sometimes return a valid, but empty feed.
+//!
+//! If a feed that has articles in the cache suddenly comes back without any, it's more likely
+//! that the server misbehaved than that the publisher deleted everything. Such a fetch is
+//! ignored, and the cached articles are kept. Only after a few empty fetches in a row, or if the
+//! user said that the feed can legitimately be empty (`accept-empty-feed <rssurl>`), the empty
+//! feed is accepted.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Default for `empty-feed-min-items`.
+pub const DEFAULT_MIN_CACHED_ITEMS: usize = 0;
+
+/// Default for `empty-feed-accept-after`.
+pub const DEFAULT_ACCEPT_AFTER: u32 = 3;
+
+/// What to do with a feed that was just fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Replace the cached feed with the fetched one, as usual.
+    Accept,
+
+    /// Ignore the fetched feed and keep the cached articles: the fetch brought no articles,
+    /// which is suspicious. This is the `consecutive`-th such fetch in a row.
+    KeepCached {
+        consecutive: u32,
+        cached_items: usize,
+    },
+}
+
+impl Verdict {
+    /// Warning to show the user, if the fetched feed is ignored.
+    pub fn warning(&self) -> Option<String> {
+        match *self {
+            Verdict::Accept => None,
+            Verdict::KeepCached { cached_items, .. } => Some(fmt_plural!(
+                cached_items,
+                "feed returned empty document, keeping %u cached item",
+                "feed returned empty document, keeping %u cached items",
+                cached_items as u32
+            )),
+        }
+    }
+}
+
+pub struct EmptyFeedGuard {
+    /// Empty fetches are only suspicious if the cache holds more than this many articles.
+    min_cached_items: usize,
+
+    /// An empty fetch is accepted if it's this many empty fetches in a row.
+    accept_after: u32,
+
+    /// Feeds that are allowed to be empty.
+    forced: HashSet<String>,
+
+    /// Number of consecutive empty fetches of each feed. Feeds are reloaded from multiple threads
+    /// at once, hence the mutex.
+    empty_fetches: Mutex<HashMap<String, u32>>,
+}
+
+impl Default for EmptyFeedGuard {
+    fn default() -> EmptyFeedGuard {
+        EmptyFeedGuard::new()
+    }
+}
+
+impl EmptyFeedGuard {
+    pub fn new() -> EmptyFeedGuard {
+        EmptyFeedGuard {
+            min_cached_items: DEFAULT_MIN_CACHED_ITEMS,
+            accept_after: DEFAULT_ACCEPT_AFTER,
+            forced: HashSet::new(),
+            empty_fetches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_thresholds(&mut self, min_cached_items: usize, accept_after: u32) {
+        self.min_cached_items = min_cached_items;
+        self.accept_after = accept_after;
+    }
+
+    /// Always accept empty fetches of the feed at `url`.
+    pub fn force_accept(&mut self, url: &str) {
+        self.forced.insert(url.to_string());
+    }
+
+    /// URLs passed to `force_accept`, sorted.
+    pub fn forced_urls(&self) -> Vec<String> {
+        let mut urls = self.forced.iter().cloned().collect::<Vec<_>>();
+        urls.sort();
+        urls
+    }
+
+    /// Decides whether a fetch of the feed at `url`, which brought `fetched_items` articles, should
+    /// replace the `cached_items` articles that are in the cache.
+    pub fn check(&self, url: &str, fetched_items: usize, cached_items: usize) -> Verdict {
+        let mut empty_fetches = self
+            .empty_fetches
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if fetched_items > 0 || cached_items <= self.min_cached_items {
+            empty_fetches.remove(url);
+            return Verdict::Accept;
+        }
+
+        let consecutive = empty_fetches.entry(url.to_string()).or_insert(0);
+        // Saturate, so that a feed that stays empty forever doesn't overflow the counter
+        *consecutive = consecutive.saturating_add(1);
+        if self.forced.contains(url) || *consecutive >= self.accept_after {
+            Verdict::Accept
+        } else {
+            Verdict::KeepCached {
+                consecutive: *consecutive,
+                cached_items,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/feed.xml";
+
+    fn guard(min_cached_items: usize, accept_after: u32) -> EmptyFeedGuard {
+        let mut guard = EmptyFeedGuard::new();
+        guard.set_thresholds(min_cached_items, accept_after);
+        guard
+    }
+
+    #[test]
+    fn t_first_empty_fetch_keeps_cached_items() {
+        let guard = guard(0, 3);
+        let verdict = guard.check(URL, 0, 134);
+        assert_eq!(
+            verdict,
+            Verdict::KeepCached {
+                consecutive: 1,
+                cached_items: 134
+            }
+        );
+        assert_eq!(
+            verdict.warning(),
+            Some("feed returned empty document, keeping 134 cached items".to_string())
+        );
+    }
+
+    #[test]
+    fn t_empty_fetches_are_accepted_after_a_few_in_a_row() {
+        let guard = guard(0, 3);
+        assert!(matches!(
+            guard.check(URL, 0, 10),
+            Verdict::KeepCached { consecutive: 1, .. }
+        ));
+        assert!(matches!(
+            guard.check(URL, 0, 10),
+            Verdict::KeepCached { consecutive: 2, .. }
+        ));
+        assert_eq!(guard.check(URL, 0, 10), Verdict::Accept);
+        // The feed stays accepted as long as it's empty
+        assert_eq!(guard.check(URL, 0, 10), Verdict::Accept);
+        assert_eq!(Verdict::Accept.warning(), None);
+    }
+
+    #[test]
+    fn t_non_empty_fetch_resets_the_count() {
+        let guard = guard(0, 2);
+        assert!(matches!(
+            guard.check(URL, 0, 10),
+            Verdict::KeepCached { .. }
+        ));
+        assert_eq!(guard.check(URL, 5, 10), Verdict::Accept);
+        assert!(matches!(
+            guard.check(URL, 0, 10),
+            Verdict::KeepCached { consecutive: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn t_feeds_are_counted_separately() {
+        let guard = guard(0, 2);
+        assert!(matches!(
+            guard.check(URL, 0, 10),
+            Verdict::KeepCached { .. }
+        ));
+        assert!(matches!(
+            guard.check("https://example.org/other.xml", 0, 10),
+            Verdict::KeepCached { consecutive: 1, .. }
+        ));
+        assert_eq!(guard.check(URL, 0, 10), Verdict::Accept);
+    }
+
+    #[test]
+    fn t_forced_feeds_may_be_empty() {
+        let mut guard = guard(0, 3);
+        guard.force_accept(URL);
+        assert_eq!(guard.check(URL, 0, 134), Verdict::Accept);
+        assert_eq!(guard.forced_urls(), vec![URL.to_string()]);
+    }
+
+    #[test]
+    fn t_small_feeds_may_be_empty() {
+        let guard = guard(5, 3);
+        assert_eq!(guard.check(URL, 0, 5), Verdict::Accept);
+        assert!(matches!(guard.check(URL, 0, 6), Verdict::KeepCached { .. }));
+        // Feeds that weren't cached yet are never suspicious
+        assert_eq!(EmptyFeedGuard::new().check(URL, 0, 0), Verdict::Accept);
+    }
+
+    #[test]
+    fn t_cached_items_are_kept_throughout_the_suspicious_window() {
+        let guard = guard(0, 5);
+        for consecutive in 1..5 {
+            assert_eq!(
+                guard.check(URL, 0, 42),
+                Verdict::KeepCached {
+                    consecutive,
+                    cached_items: 42
+                }
+            );
+        }
+        assert_eq!(guard.check(URL, 0, 42), Verdict::Accept);
+    }
+
+    #[test]
+    fn t_accept_after_one_disables_the_check() {
+        let guard = guard(0, 1);
+        assert_eq!(guard.check(URL, 0, 134), Verdict::Accept);
+    }
+}
@@ -0,0 +1,173 @@
+//! Finds the feeds that a web page links to (feed autodiscovery).
+//!
+//! Pages advertise their feeds with `<link rel="alternate" type="application/rss+xml"
+//! href="...">` in their head. This lets the user preview a feed by giving the address of a blog
+//! instead of the address of its feed.
+
+use crate::utils::absolute_url;
+
+/// MIME types of the feed formats that we can parse.
+const FEED_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/rdf+xml",
+];
+
+/// Splits the inside of a tag, like `link rel="alternate" href=/feed`, into lowercased attribute
+/// names and their values. Attributes without a value get an empty one.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut rest = tag.trim_start();
+    // Skip the element name
+    rest = rest.trim_start_matches(|c: char| !c.is_whitespace() && c != '/');
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            break;
+        }
+
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if rest.starts_with('=') {
+            rest = rest[1..].trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'');
+            match quote {
+                Some(quote) => {
+                    let inner = &rest[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    value = inner[..end].to_string();
+                    rest = inner.get(end + 1..).unwrap_or("");
+                }
+                None => {
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    value = rest[..end].to_string();
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        result.push((name, decode_entities(&value)));
+    }
+
+    result
+}
+
+/// Decodes the few entities that commonly appear in URLs inside attributes.
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+}
+
+/// Returns the position of the next `<link` at or after `from`, ignoring case.
+fn find_link_tag(html: &str, from: usize) -> Option<usize> {
+    const NEEDLE: &[u8] = b"<link";
+    html.as_bytes()[from..]
+        .windows(NEEDLE.len())
+        .position(|window| window.eq_ignore_ascii_case(NEEDLE))
+        .map(|position| from + position)
+}
+
+/// Returns the URLs of the feeds that `html` links to, in the order of the page, without
+/// duplicates. Relative links are resolved against `page_url`.
+pub fn discover_feeds(html: &str, page_url: &str) -> Vec<String> {
+    let mut feeds: Vec<String> = Vec::new();
+
+    let mut position = 0;
+    while let Some(start) = find_link_tag(html, position) {
+        let start = start + 1;
+        let end = match html[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        position = end;
+
+        let tag = &html[start..end];
+        let attributes = attributes(tag);
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let is_alternate = attribute("rel")
+            .map(|rel| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+            })
+            .unwrap_or(false);
+        let is_feed = attribute("type")
+            .map(|t| FEED_TYPES.contains(&t.trim().to_lowercase().as_str()))
+            .unwrap_or(false);
+        let href = attribute("href").map(str::trim).unwrap_or("");
+
+        if is_alternate && is_feed && !href.is_empty() {
+            let url = absolute_url(page_url, href);
+            if !feeds.contains(&url) {
+                feeds.push(url);
+            }
+        }
+    }
+
+    feeds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_finds_feeds_advertised_in_the_head() {
+        let html = r#"<!DOCTYPE html>
+<html><head>
+<title>A blog</title>
+<link rel="stylesheet" href="/style.css">
+<link rel="alternate" type="application/rss+xml" title="Posts" href="https://example.com/feed.xml">
+<LINK REL=alternate TYPE="application/atom+xml" HREF=/atom.xml />
+</head><body><a href="/other.xml">Not a feed link</a></body></html>"#;
+
+        assert_eq!(
+            discover_feeds(html, "https://example.com/blog/"),
+            vec![
+                "https://example.com/feed.xml".to_string(),
+                "https://example.com/atom.xml".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn t_resolves_relative_links_and_drops_duplicates() {
+        let html = r#"<link rel='alternate' type='application/rss+xml' href='feed?a=1&amp;b=2'>
+<link type="application/rss+xml" rel="alternate home" href="https://example.com/blog/feed?a=1&b=2">"#;
+
+        assert_eq!(
+            discover_feeds(html, "https://example.com/blog/index.html"),
+            vec!["https://example.com/blog/feed?a=1&b=2".to_string()]
+        );
+    }
+
+    #[test]
+    fn t_ignores_links_that_are_not_feeds() {
+        let html = r#"<link rel="alternate" type="text/html" hreflang="de" href="/de/">
+<link rel="alternate" type="application/rss+xml">
+<link rel="icon" type="application/rss+xml" href="/feed.xml">
+<p>Tschüß <link rel="alternate" type="application/rss+xml" href="/ü.xml"></p>"#;
+
+        assert_eq!(
+            discover_feeds(html, "https://example.com/"),
+            vec!["https://example.com/%C3%BC.xml".to_string()]
+        );
+        assert!(discover_feeds("", "https://example.com/").is_empty());
+        assert!(discover_feeds("<link rel=alternate", "https://example.com/").is_empty());
+    }
+}
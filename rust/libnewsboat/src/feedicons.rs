@@ -0,0 +1,244 @@
+//! One-column glyphs that tell apart kinds of feeds in the feed list (`%I` in `feedlist-format`).
+//!
+//! The glyphs are configured with `feedlist-icon <category> <glyph>`. Each glyph can be at most
+//! two columns wide; all of them are padded to the width of the widest one, so that the column
+//! stays aligned when some glyphs are double-width and others aren't.
+
+use crate::utils;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Widest glyph allowed, in columns.
+pub const MAX_GLYPH_WIDTH: usize = 2;
+
+/// Kind of a feed. If a feed fits several categories, the one listed first here wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// The last reload of the feed failed.
+    Error = 0,
+    /// A query feed (`query:` in the urls file).
+    Query = 1,
+    /// At least half of the feed's articles have an enclosure.
+    Podcast = 2,
+    /// Everything else.
+    Plain = 3,
+}
+
+const CATEGORIES: [(Category, &str, &str); 4] = [
+    (Category::Error, "error", "!"),
+    (Category::Query, "query", "Q"),
+    (Category::Podcast, "podcast", "P"),
+    (Category::Plain, "plain", " "),
+];
+
+impl Category {
+    pub fn from_name(name: &str) -> Option<Category> {
+        CATEGORIES
+            .iter()
+            .find(|(_, known, _)| *known == name)
+            .map(|(category, _, _)| *category)
+    }
+}
+
+/// What the classification looks at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeedFacts {
+    pub is_query: bool,
+    pub has_error: bool,
+    pub items: usize,
+    pub items_with_enclosures: usize,
+}
+
+pub fn classify(facts: &FeedFacts) -> Category {
+    if facts.has_error {
+        Category::Error
+    } else if facts.is_query {
+        Category::Query
+    } else if facts.items_with_enclosures > 0 && facts.items_with_enclosures * 2 >= facts.items {
+        Category::Podcast
+    } else {
+        Category::Plain
+    }
+}
+
+#[derive(Debug)]
+pub struct FeedIcons {
+    glyphs: [String; 4],
+}
+
+impl Default for FeedIcons {
+    fn default() -> FeedIcons {
+        FeedIcons {
+            glyphs: [
+                CATEGORIES[0].2.to_string(),
+                CATEGORIES[1].2.to_string(),
+                CATEGORIES[2].2.to_string(),
+                CATEGORIES[3].2.to_string(),
+            ],
+        }
+    }
+}
+
+impl FeedIcons {
+    pub fn new() -> FeedIcons {
+        FeedIcons::default()
+    }
+
+    /// Handles the arguments of `feedlist-icon`: a category and a glyph. Returns an
+    /// internationalized description of the problem if they're invalid.
+    pub fn set_glyph(&mut self, category: &str, glyph: &str) -> Result<(), String> {
+        let category = Category::from_name(category).ok_or_else(|| {
+            let names = CATEGORIES
+                .iter()
+                .map(|(_, name, _)| *name)
+                .collect::<Vec<_>>();
+            fmt!(
+                &gettext("`%s' is not a feed category; expected one of: %s"),
+                category,
+                names.join(", ")
+            )
+        })?;
+
+        let width = utils::strwidth(glyph);
+        if width > MAX_GLYPH_WIDTH {
+            let columns = |n: usize| fmt_plural!(n, "%u column", "%u columns", n as u32);
+            return Err(fmt!(
+                &gettext("`%s' is %s wide, but feed icons can be at most %s wide"),
+                glyph,
+                columns(width),
+                columns(MAX_GLYPH_WIDTH)
+            ));
+        }
+
+        self.glyphs[category as usize] = glyph.to_string();
+        Ok(())
+    }
+
+    /// Width of the icon column: that of the widest glyph.
+    pub fn column_width(&self) -> usize {
+        self.glyphs
+            .iter()
+            .map(|glyph| utils::strwidth(glyph))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Glyph of the category, padded with spaces to `column_width()`.
+    pub fn glyph(&self, category: Category) -> String {
+        let glyph = &self.glyphs[category as usize];
+        let padding = self.column_width() - utils::strwidth(glyph);
+        format!("{}{}", glyph, " ".repeat(padding))
+    }
+
+    /// `feedlist-icon` commands for glyphs that differ from the defaults.
+    pub fn dump_config(&self) -> Vec<String> {
+        CATEGORIES
+            .iter()
+            .filter(|(category, _, default)| self.glyphs[*category as usize] != *default)
+            .map(|(category, name, _)| {
+                format!(
+                    "feedlist-icon {} {}",
+                    name,
+                    utils::quote(self.glyphs[*category as usize].clone())
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_classifies_feeds() {
+        let plain = FeedFacts {
+            items: 10,
+            items_with_enclosures: 1,
+            ..FeedFacts::default()
+        };
+        assert_eq!(classify(&plain), Category::Plain);
+        assert_eq!(classify(&FeedFacts::default()), Category::Plain);
+
+        let podcast = FeedFacts {
+            items: 10,
+            items_with_enclosures: 5,
+            ..FeedFacts::default()
+        };
+        assert_eq!(classify(&podcast), Category::Podcast);
+
+        let query = FeedFacts {
+            is_query: true,
+            ..FeedFacts::default()
+        };
+        assert_eq!(classify(&query), Category::Query);
+
+        let error = FeedFacts {
+            has_error: true,
+            ..FeedFacts::default()
+        };
+        assert_eq!(classify(&error), Category::Error);
+    }
+
+    #[test]
+    fn t_errors_take_precedence_over_queries_which_take_precedence_over_podcasts() {
+        let everything = FeedFacts {
+            is_query: true,
+            has_error: true,
+            items: 2,
+            items_with_enclosures: 2,
+        };
+        assert_eq!(classify(&everything), Category::Error);
+
+        let query_of_podcasts = FeedFacts {
+            has_error: false,
+            ..everything
+        };
+        assert_eq!(classify(&query_of_podcasts), Category::Query);
+    }
+
+    #[test]
+    fn t_rejects_unknown_categories_and_wide_glyphs() {
+        let mut icons = FeedIcons::new();
+
+        let error = icons.set_glyph("video", "V").unwrap_err();
+        assert!(error.contains("podcast"), "{}", error);
+
+        let error = icons.set_glyph("podcast", "♪♪♪").unwrap_err();
+        assert!(error.contains("3 columns"), "{}", error);
+        let error = icons.set_glyph("podcast", "音楽").unwrap_err();
+        assert!(error.contains("4 columns"), "{}", error);
+
+        assert_eq!(icons.glyph(Category::Podcast), "P");
+        assert!(icons.dump_config().is_empty());
+    }
+
+    #[test]
+    fn t_pads_glyphs_to_the_widest_one() {
+        let mut icons = FeedIcons::new();
+        assert_eq!(icons.column_width(), 1);
+
+        icons.set_glyph("podcast", "音").unwrap();
+        icons.set_glyph("plain", "").unwrap();
+        assert_eq!(icons.column_width(), 2);
+        assert_eq!(icons.glyph(Category::Podcast), "音");
+        assert_eq!(icons.glyph(Category::Query), "Q ");
+        assert_eq!(icons.glyph(Category::Plain), "  ");
+        for category in &[
+            Category::Error,
+            Category::Query,
+            Category::Podcast,
+            Category::Plain,
+        ] {
+            assert_eq!(utils::strwidth(&icons.glyph(*category)), 2);
+        }
+
+        assert_eq!(
+            icons.dump_config(),
+            vec![
+                "feedlist-icon podcast \"音\"".to_string(),
+                "feedlist-icon plain \"\"".to_string(),
+            ]
+        );
+    }
+}
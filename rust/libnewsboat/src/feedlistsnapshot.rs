@@ -0,0 +1,492 @@
+//! Snapshot of the feedlist, used to show it right away at startup.
+//!
+//! Building the feedlist means loading every feed from the cache, which takes a while on large
+//! setups. On a clean exit, the feedlist is written to a snapshot along with stamps of the urls
+//! file and the cache. If neither changed by the next start, the feedlist is shown from the
+//! snapshot while the feeds are loaded in the background, and then replaced by the real thing.
+//!
+//! Anything unexpected in the snapshot file makes it unusable; newsboat then starts the usual way.
+
+use crate::history::FileStamp;
+use crate::utils;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// First line of a snapshot file. The number is bumped whenever the format changes, so that
+/// snapshots written by other versions aren't misread.
+const HEADER: &str = "newsboat-feedlist-snapshot 1";
+
+/// A feed as it was shown in the feedlist.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SnapshotFeed {
+    /// The feed's URL, which identifies it.
+    pub url: String,
+    /// Title as it came from the feed, before any custom title from the tags is applied.
+    pub title: String,
+    pub tags: Vec<String>,
+    pub unread: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FeedListSnapshot {
+    urls_file: Option<FileStamp>,
+    cache_file: Option<FileStamp>,
+    /// Feeds in the order they were shown.
+    feeds: Vec<SnapshotFeed>,
+}
+
+impl FeedListSnapshot {
+    pub fn new() -> FeedListSnapshot {
+        FeedListSnapshot::default()
+    }
+
+    pub fn add_feed(&mut self, feed: SnapshotFeed) {
+        self.feeds.push(feed);
+    }
+
+    /// Feeds in the order they were shown.
+    pub fn feeds(&self) -> &[SnapshotFeed] {
+        &self.feeds
+    }
+
+    /// Writes the snapshot to `path`, along with the current stamps of `urls_file` and
+    /// `cache_file`. Should be called once the cache won't be changed anymore.
+    pub fn save(&mut self, path: &Path, urls_file: &Path, cache_file: &Path) -> io::Result<()> {
+        self.urls_file = FileStamp::of(urls_file);
+        self.cache_file = FileStamp::of(cache_file);
+        utils::atomic_write(path, self.to_file_contents().as_bytes(), 0o600)?;
+        Ok(())
+    }
+
+    /// Reads the snapshot at `path`, if it's still current: `urls_file` and `cache_file` are the
+    /// same as when it was saved, and it has exactly the feeds in `urls`. A missing, unreadable,
+    /// corrupted, or stale snapshot gives `None`.
+    pub fn load_if_current(
+        path: &Path,
+        urls_file: &Path,
+        cache_file: &Path,
+        urls: &[String],
+    ) -> Option<FeedListSnapshot> {
+        let contents = fs::read_to_string(path).ok()?;
+        let snapshot = FeedListSnapshot::from_file_contents(&contents)?;
+        let unchanged = snapshot.urls_file == FileStamp::of(urls_file)
+            && snapshot.cache_file == FileStamp::of(cache_file);
+        if unchanged && snapshot.has_feeds(urls) {
+            Some(snapshot)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the snapshot has exactly the feeds in `urls`, regardless of the order.
+    fn has_feeds(&self, urls: &[String]) -> bool {
+        let expected = urls.iter().map(String::as_str).collect::<HashSet<_>>();
+        let actual = self
+            .feeds
+            .iter()
+            .map(|feed| feed.url.as_str())
+            .collect::<HashSet<_>>();
+        expected.len() == urls.len() && actual.len() == self.feeds.len() && expected == actual
+    }
+
+    /// Replaces the snapshot's idea of a feed with `fresh`, loaded from the cache. Returns `true`
+    /// if the snapshot was off, i.e. the feed was shown wrong.
+    pub fn reconcile(&mut self, fresh: SnapshotFeed) -> bool {
+        match self.feeds.iter_mut().find(|feed| feed.url == fresh.url) {
+            Some(feed) if *feed == fresh => false,
+            Some(feed) => {
+                *feed = fresh;
+                true
+            }
+            None => {
+                self.feeds.push(fresh);
+                true
+            }
+        }
+    }
+
+    fn to_file_contents(&self) -> String {
+        let mut contents = String::new();
+        contents.push_str(HEADER);
+        contents.push('\n');
+        contents.push_str(&format!("urls {}\n", stamp_to_string(&self.urls_file)));
+        contents.push_str(&format!("cache {}\n", stamp_to_string(&self.cache_file)));
+        for feed in &self.feeds {
+            let mut fields = vec![
+                "feed".to_string(),
+                escape(&feed.url),
+                escape(&feed.title),
+                feed.unread.to_string(),
+                feed.total.to_string(),
+            ];
+            fields.extend(feed.tags.iter().map(|tag| escape(tag)));
+            contents.push_str(&fields.join("\t"));
+            contents.push('\n');
+        }
+        contents
+    }
+
+    fn from_file_contents(contents: &str) -> Option<FeedListSnapshot> {
+        // A snapshot that wasn't written to the end lacks the final newline. NUL bytes can't
+        // come from the strings that were saved, so they mean the file is damaged
+        if !contents.ends_with('\n') || contents.contains('\0') {
+            return None;
+        }
+        let mut lines = contents.lines();
+        if lines.next()? != HEADER {
+            return None;
+        }
+        let urls_file = parse_stamp_line(lines.next()?, "urls")?;
+        let cache_file = parse_stamp_line(lines.next()?, "cache")?;
+
+        let mut feeds = Vec::new();
+        for line in lines {
+            let mut fields = line.split('\t');
+            if fields.next()? != "feed" {
+                return None;
+            }
+            let url = unescape(fields.next()?)?;
+            let title = unescape(fields.next()?)?;
+            let unread = fields.next()?.parse().ok()?;
+            let total = fields.next()?.parse().ok()?;
+            let tags = fields.map(unescape).collect::<Option<Vec<_>>>()?;
+            if url.is_empty() || unread > total {
+                return None;
+            }
+            feeds.push(SnapshotFeed {
+                url,
+                title,
+                tags,
+                unread,
+                total,
+            });
+        }
+
+        Some(FeedListSnapshot {
+            urls_file,
+            cache_file,
+            feeds,
+        })
+    }
+}
+
+/// `size seconds.nanoseconds`, or `none` for a file that didn't exist.
+fn stamp_to_string(stamp: &Option<FileStamp>) -> String {
+    match stamp {
+        Some(stamp) => {
+            // Files modified before 1970 are out of luck: their snapshot won't match
+            let modified = stamp
+                .modified
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            format!(
+                "{} {}.{:09}",
+                stamp.size,
+                modified.as_secs(),
+                modified.subsec_nanos()
+            )
+        }
+        None => "none".to_string(),
+    }
+}
+
+/// Parses `<name> <stamp>`. Returns `None` if the line is malformed, and `Some(None)` for a file
+/// that didn't exist.
+fn parse_stamp_line(line: &str, name: &str) -> Option<Option<FileStamp>> {
+    let mut fields = line.split(' ');
+    if fields.next()? != name {
+        return None;
+    }
+    let stamp = match (fields.next()?, fields.next()) {
+        ("none", None) => None,
+        (size, Some(modified)) => {
+            let dot = modified.find('.')?;
+            let seconds = modified[..dot].parse().ok()?;
+            let nanoseconds = modified[dot + 1..].parse().ok()?;
+            if nanoseconds >= 1_000_000_000 {
+                return None;
+            }
+            Some(FileStamp {
+                modified: UNIX_EPOCH + Duration::new(seconds, nanoseconds),
+                size: size.parse().ok()?,
+            })
+        }
+        _ => return None,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(stamp)
+}
+
+/// Escapes the characters that separate fields and lines of the snapshot.
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for chr in text.chars() {
+        match chr {
+            '\\' => result.push_str(r"\\"),
+            '\t' => result.push_str(r"\t"),
+            '\n' => result.push_str(r"\n"),
+            '\r' => result.push_str(r"\r"),
+            _ => result.push(chr),
+        }
+    }
+    result
+}
+
+/// The inverse of `escape`. Returns `None` for escapes that `escape` doesn't produce.
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(chr) = chars.next() {
+        if chr != '\\' {
+            result.push(chr);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => result.push('\\'),
+            't' => result.push('\t'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn feed(url: &str, title: &str, tags: &[&str], unread: u32, total: u32) -> SnapshotFeed {
+        SnapshotFeed {
+            url: url.to_string(),
+            title: title.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            unread,
+            total,
+        }
+    }
+
+    fn urls(snapshot: &FeedListSnapshot) -> Vec<String> {
+        snapshot
+            .feeds()
+            .iter()
+            .map(|feed| feed.url.clone())
+            .collect()
+    }
+
+    struct Setup {
+        _tmp: TempDir,
+        snapshot_file: std::path::PathBuf,
+        urls_file: std::path::PathBuf,
+        cache_file: std::path::PathBuf,
+    }
+
+    fn setup() -> Setup {
+        let tmp = TempDir::new().unwrap();
+        let snapshot_file = tmp.path().join("feedlist.snapshot");
+        let urls_file = tmp.path().join("urls");
+        let cache_file = tmp.path().join("cache.db");
+        fs::write(&urls_file, "https://example.com/feed.xml\n").unwrap();
+        fs::write(&cache_file, "pretend this is SQLite").unwrap();
+        Setup {
+            _tmp: tmp,
+            snapshot_file,
+            urls_file,
+            cache_file,
+        }
+    }
+
+    fn sample() -> FeedListSnapshot {
+        let mut snapshot = FeedListSnapshot::new();
+        snapshot.add_feed(feed(
+            "https://example.com/feed.xml",
+            "Example\tnews\\n",
+            &["tech", "~Custom title"],
+            3,
+            10,
+        ));
+        snapshot.add_feed(feed("query:Unread:unread = \"yes\"", "", &[], 0, 0));
+        snapshot
+    }
+
+    #[test]
+    fn t_snapshot_round_trips_through_the_file() {
+        let s = setup();
+        let mut snapshot = sample();
+        snapshot
+            .save(&s.snapshot_file, &s.urls_file, &s.cache_file)
+            .unwrap();
+
+        let loaded = FeedListSnapshot::load_if_current(
+            &s.snapshot_file,
+            &s.urls_file,
+            &s.cache_file,
+            &urls(&snapshot),
+        )
+        .unwrap();
+        assert_eq!(loaded, snapshot);
+        assert_eq!(loaded.feeds()[0].title, "Example\tnews\\n");
+        assert_eq!(loaded.feeds()[0].tags, vec!["tech", "~Custom title"]);
+    }
+
+    #[test]
+    fn t_snapshot_is_stale_once_the_urls_file_changes() {
+        let s = setup();
+        let mut snapshot = sample();
+        snapshot
+            .save(&s.snapshot_file, &s.urls_file, &s.cache_file)
+            .unwrap();
+
+        fs::write(
+            &s.urls_file,
+            "https://example.com/feed.xml\nhttps://example.org/other.xml\n",
+        )
+        .unwrap();
+        assert_eq!(
+            FeedListSnapshot::load_if_current(
+                &s.snapshot_file,
+                &s.urls_file,
+                &s.cache_file,
+                &urls(&snapshot),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn t_snapshot_is_stale_once_the_cache_changes() {
+        let s = setup();
+        let mut snapshot = sample();
+        snapshot
+            .save(&s.snapshot_file, &s.urls_file, &s.cache_file)
+            .unwrap();
+
+        fs::write(&s.cache_file, "pretend this is SQLite, with a new article").unwrap();
+        assert_eq!(
+            FeedListSnapshot::load_if_current(
+                &s.snapshot_file,
+                &s.urls_file,
+                &s.cache_file,
+                &urls(&snapshot),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn t_snapshot_must_have_exactly_the_configured_feeds() {
+        let s = setup();
+        let mut snapshot = sample();
+        snapshot
+            .save(&s.snapshot_file, &s.urls_file, &s.cache_file)
+            .unwrap();
+        let load = |urls: &[String]| {
+            FeedListSnapshot::load_if_current(&s.snapshot_file, &s.urls_file, &s.cache_file, urls)
+        };
+
+        let mut reordered = urls(&snapshot);
+        reordered.reverse();
+        assert!(load(&reordered).is_some());
+
+        let mut more = urls(&snapshot);
+        more.push("https://example.org/other.xml".to_string());
+        assert_eq!(load(&more), None);
+        assert_eq!(load(&urls(&snapshot)[..1]), None);
+    }
+
+    #[test]
+    fn t_corrupted_snapshots_are_ignored() {
+        let s = setup();
+        let mut snapshot = sample();
+        snapshot
+            .save(&s.snapshot_file, &s.urls_file, &s.cache_file)
+            .unwrap();
+        let contents = fs::read_to_string(&s.snapshot_file).unwrap();
+        let load = || {
+            FeedListSnapshot::load_if_current(
+                &s.snapshot_file,
+                &s.urls_file,
+                &s.cache_file,
+                &urls(&snapshot),
+            )
+        };
+
+        let corruptions = vec![
+            // Cut short
+            contents[..contents.len() - 5].to_string(),
+            // Written by a different version
+            contents.replacen("snapshot 1", "snapshot 2", 1),
+            String::new(),
+            contents.replacen("\t3\t10\t", "\tthree\t10\t", 1),
+            contents.replacen("\t3\t10\t", "\t30\t10\t", 1),
+            contents.replacen(r"\\n", r"\q", 1),
+            contents.replacen("feed\t", "food\t", 1),
+            contents.replacen("cache ", "cache\t", 1),
+            contents.replacen("Example", "Exa\u{0}mple", 1),
+        ];
+        for corrupted in corruptions {
+            fs::write(&s.snapshot_file, &corrupted).unwrap();
+            assert_eq!(load(), None, "{:?}", corrupted);
+        }
+
+        fs::remove_file(&s.snapshot_file).unwrap();
+        assert_eq!(load(), None);
+    }
+
+    #[test]
+    fn t_missing_urls_file_is_part_of_the_stamp() {
+        let s = setup();
+        fs::remove_file(&s.urls_file).unwrap();
+        let mut snapshot = sample();
+        snapshot
+            .save(&s.snapshot_file, &s.urls_file, &s.cache_file)
+            .unwrap();
+
+        let load = || {
+            FeedListSnapshot::load_if_current(
+                &s.snapshot_file,
+                &s.urls_file,
+                &s.cache_file,
+                &urls(&snapshot),
+            )
+        };
+        assert!(load().is_some());
+        fs::write(&s.urls_file, "https://example.com/feed.xml\n").unwrap();
+        assert_eq!(load(), None);
+    }
+
+    #[test]
+    fn t_reconcile_fixes_a_stale_unread_count() {
+        let mut snapshot = sample();
+        let fresh = feed(
+            "https://example.com/feed.xml",
+            "Example\tnews\\n",
+            &["tech", "~Custom title"],
+            1,
+            10,
+        );
+
+        assert!(snapshot.reconcile(fresh.clone()));
+        assert_eq!(snapshot.feeds()[0], fresh);
+        // Now that it's right, there's nothing to fix
+        assert!(!snapshot.reconcile(fresh));
+        assert!(!snapshot.reconcile(feed("query:Unread:unread = \"yes\"", "", &[], 0, 0)));
+    }
+
+    #[test]
+    fn t_reconcile_adds_feeds_that_the_snapshot_lacked() {
+        let mut snapshot = sample();
+        let fresh = feed("https://example.org/other.xml", "Other", &[], 5, 5);
+
+        assert!(snapshot.reconcile(fresh.clone()));
+        assert_eq!(snapshot.feeds().len(), 3);
+        assert_eq!(snapshot.feeds()[2], fresh);
+    }
+}
@@ -0,0 +1,163 @@
+//! Jumping between feeds of the feed list, as done by `next-feed-with-unread` and
+//! `prev-feed-with-unread`.
+//!
+//! The functions here only look at the feeds the user can see, i.e. after the tag, the filter,
+//! `show-read-feeds` and hidden feeds were applied; the caller passes the number of unread
+//! articles of each visible feed, in the order they're shown.
+
+/// Which way to look for a feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Next,
+    Previous,
+}
+
+/// Where to jump to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jump {
+    /// To the visible feed with this index.
+    To(usize),
+    /// Nowhere: the current feed is the only one with unread articles.
+    OnlyCurrent,
+    /// Nowhere: there are feeds with unread articles, but only in the other direction, and
+    /// wrapping around isn't allowed.
+    NoneInDirection,
+    /// Nowhere: no feed has unread articles.
+    NoUnread,
+}
+
+/// Finds the closest feed with unread articles in the given direction from `current` (the index
+/// of the selected feed, if any). If `wrap` is `true`, the search continues from the other end of
+/// the list; the current feed itself is never a target.
+pub fn feed_with_unread(
+    unread_counts: &[u32],
+    current: Option<usize>,
+    direction: Direction,
+    wrap: bool,
+) -> Jump {
+    let has_unread = |index: &usize| unread_counts[*index] > 0;
+    let len = unread_counts.len();
+    let current = current.filter(|&current| current < len);
+
+    // Indices in the order they're looked at: first the feeds ahead of the current one, then (if
+    // wrapping) the ones behind it.
+    let (ahead, behind): (Vec<usize>, Vec<usize>) = match (direction, current) {
+        (Direction::Next, Some(current)) => ((current + 1..len).collect(), (0..current).collect()),
+        (Direction::Previous, Some(current)) => (
+            (0..current).rev().collect(),
+            (current + 1..len).rev().collect(),
+        ),
+        (Direction::Next, None) => ((0..len).collect(), Vec::new()),
+        (Direction::Previous, None) => ((0..len).rev().collect(), Vec::new()),
+    };
+
+    if let Some(index) = ahead.into_iter().find(has_unread) {
+        return Jump::To(index);
+    }
+    if let Some(index) = behind.into_iter().find(has_unread) {
+        return if wrap {
+            Jump::To(index)
+        } else {
+            Jump::NoneInDirection
+        };
+    }
+    match current {
+        Some(current) if unread_counts[current] > 0 => Jump::OnlyCurrent,
+        _ => Jump::NoUnread,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_jumps_to_the_closest_feed_with_unread_articles() {
+        let unread = [0, 3, 0, 0, 1, 0];
+        assert_eq!(
+            feed_with_unread(&unread, Some(1), Direction::Next, false),
+            Jump::To(4)
+        );
+        assert_eq!(
+            feed_with_unread(&unread, Some(4), Direction::Previous, false),
+            Jump::To(1)
+        );
+        assert_eq!(
+            feed_with_unread(&unread, Some(2), Direction::Next, true),
+            Jump::To(4)
+        );
+        assert_eq!(
+            feed_with_unread(&unread, Some(2), Direction::Previous, true),
+            Jump::To(1)
+        );
+    }
+
+    #[test]
+    fn t_wraps_around_only_if_allowed() {
+        let unread = [2, 0, 0, 5, 0];
+        assert_eq!(
+            feed_with_unread(&unread, Some(3), Direction::Next, true),
+            Jump::To(0)
+        );
+        assert_eq!(
+            feed_with_unread(&unread, Some(3), Direction::Next, false),
+            Jump::NoneInDirection
+        );
+        assert_eq!(
+            feed_with_unread(&unread, Some(0), Direction::Previous, true),
+            Jump::To(3)
+        );
+        assert_eq!(
+            feed_with_unread(&unread, Some(0), Direction::Previous, false),
+            Jump::NoneInDirection
+        );
+    }
+
+    #[test]
+    fn t_reports_when_all_feeds_are_read() {
+        let unread = [0, 0, 0];
+        for &direction in &[Direction::Next, Direction::Previous] {
+            for &wrap in &[false, true] {
+                assert_eq!(
+                    feed_with_unread(&unread, Some(1), direction, wrap),
+                    Jump::NoUnread
+                );
+            }
+        }
+        assert_eq!(
+            feed_with_unread(&[], None, Direction::Next, true),
+            Jump::NoUnread
+        );
+    }
+
+    #[test]
+    fn t_reports_when_the_current_feed_is_the_only_one_with_unread_articles() {
+        let unread = [0, 0, 7, 0];
+        for &direction in &[Direction::Next, Direction::Previous] {
+            for &wrap in &[false, true] {
+                assert_eq!(
+                    feed_with_unread(&unread, Some(2), direction, wrap),
+                    Jump::OnlyCurrent
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn t_without_a_current_feed_searches_the_whole_list() {
+        let unread = [0, 1, 0, 1, 0];
+        assert_eq!(
+            feed_with_unread(&unread, None, Direction::Next, false),
+            Jump::To(1)
+        );
+        assert_eq!(
+            feed_with_unread(&unread, None, Direction::Previous, false),
+            Jump::To(3)
+        );
+        // A position past the end of the list (e.g. the list shrank) counts as no position
+        assert_eq!(
+            feed_with_unread(&unread, Some(10), Direction::Next, false),
+            Jump::To(1)
+        );
+    }
+}
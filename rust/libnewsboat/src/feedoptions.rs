@@ -0,0 +1,442 @@
+//! Per-feed overrides for how feeds are fetched.
+//!
+//! The user sets them with `feedopts <rssurl> <option> <value> [<option> <value> ...]`. There
+//! are two options:
+//! - `http-header "Name: value"` adds a header to every request for the feed; it can be given
+//!   more than once;
+//! - `user-agent "..."` replaces the global `user-agent` for the feed.
+//!
+//! Headers often carry secrets, like access tokens, so they are never logged in full: use
+//! `HttpHeader::redacted` for that.
+
+use crate::utils;
+use gettextrs::gettext;
+use std::collections::BTreeMap;
+use std::fmt;
+use strprintf::fmt;
+
+/// Headers that Newsboat sets itself: the user agent has its own option, and the rest are used
+/// for conditional requests, which would break if the user overrode them.
+const RESERVED_HEADERS: [&str; 5] = [
+    "User-Agent",
+    "If-Modified-Since",
+    "If-None-Match",
+    "A-IM",
+    "Host",
+];
+
+/// Replaces header values in logs.
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderError {
+    /// There is no colon between the name and the value.
+    MissingColon,
+    /// There is nothing before the colon.
+    EmptyName,
+    /// The name contains a character that isn't allowed in header names.
+    InvalidName(String),
+    /// There is nothing after the colon.
+    EmptyValue(String),
+    /// The value of the named header contains a line break or a null byte, which would let it
+    /// smuggle more headers into the request.
+    InvalidValue(String),
+    /// The named header is one that Newsboat sets itself.
+    Reserved(String),
+}
+
+impl HeaderError {
+    /// The error message. It never contains the header's value.
+    pub fn to_message(&self) -> String {
+        match self {
+            HeaderError::MissingColon => gettext("header should look like `Name: value'"),
+            HeaderError::EmptyName => gettext("header name is empty"),
+            HeaderError::InvalidName(name) => {
+                fmt!(&gettext("invalid header name `%s'"), name.as_str())
+            }
+            HeaderError::EmptyValue(name) => {
+                fmt!(&gettext("header `%s' has no value"), name.as_str())
+            }
+            HeaderError::InvalidValue(name) => fmt!(
+                &gettext("value of header `%s' contains a line break or a null byte"),
+                name.as_str()
+            ),
+            HeaderError::Reserved(name) => fmt!(
+                &gettext("header `%s' can't be set: Newsboat sets it itself"),
+                name.as_str()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_message())
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+/// Characters allowed in header names, besides letters and digits (the "tchar" of RFC 7230).
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn has_line_break_or_null(text: &str) -> bool {
+    text.contains(&['\r', '\n', '\0'][..])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpHeader {
+    name: String,
+    value: String,
+}
+
+impl HttpHeader {
+    /// Parses a header written as `Name: value`. Whitespace around the value is dropped.
+    pub fn parse(text: &str) -> Result<HttpHeader, HeaderError> {
+        let colon = text.find(':').ok_or(HeaderError::MissingColon)?;
+        let name = text[..colon].trim_start();
+        let value = text[colon + 1..].trim_matches(|c| c == ' ' || c == '\t');
+
+        if name.is_empty() {
+            return Err(HeaderError::EmptyName);
+        }
+        if !name.chars().all(is_token_char) {
+            // The name may contain the line break, so quoting it in the message wouldn't help.
+            return Err(HeaderError::InvalidName(
+                name.replace(&['\r', '\n', '\0'][..], " "),
+            ));
+        }
+        if RESERVED_HEADERS
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(name))
+        {
+            return Err(HeaderError::Reserved(name.to_string()));
+        }
+        if has_line_break_or_null(value) {
+            return Err(HeaderError::InvalidValue(name.to_string()));
+        }
+        if value.is_empty() {
+            // curl takes `Name:` to mean "don't send this header at all"
+            return Err(HeaderError::EmptyValue(name.to_string()));
+        }
+
+        Ok(HttpHeader {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The header as it is sent, e.g. "X-Auth: secret".
+    pub fn to_line(&self) -> String {
+        format!("{}: {}", self.name, self.value)
+    }
+
+    /// The header with its value hidden, e.g. "X-Auth: [redacted]", for logs.
+    pub fn redacted(&self) -> String {
+        format!("{}: {}", self.name, REDACTED)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedOptionError {
+    /// There is no option with this name.
+    UnknownOption(String),
+    InvalidHeader(HeaderError),
+    /// The user agent contains a line break or a null byte.
+    InvalidUserAgent,
+}
+
+impl FeedOptionError {
+    pub fn to_message(&self) -> String {
+        match self {
+            FeedOptionError::UnknownOption(option) => {
+                fmt!(&gettext("unknown feed option `%s'"), option.as_str())
+            }
+            FeedOptionError::InvalidHeader(error) => error.to_message(),
+            FeedOptionError::InvalidUserAgent => {
+                gettext("user-agent contains a line break or a null byte")
+            }
+        }
+    }
+}
+
+impl fmt::Display for FeedOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_message())
+    }
+}
+
+impl std::error::Error for FeedOptionError {}
+
+impl From<HeaderError> for FeedOptionError {
+    fn from(error: HeaderError) -> FeedOptionError {
+        FeedOptionError::InvalidHeader(error)
+    }
+}
+
+/// How a particular feed is fetched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// Replaces the global `user-agent`.
+    pub user_agent: Option<String>,
+    /// Extra headers, in the order they were given.
+    pub headers: Vec<HttpHeader>,
+}
+
+#[derive(Debug, Default)]
+pub struct FeedOptions {
+    /// Options by feed URL; sorted, so that the dumped config is stable.
+    by_url: BTreeMap<String, FetchOptions>,
+}
+
+impl FeedOptions {
+    pub fn new() -> FeedOptions {
+        FeedOptions::default()
+    }
+
+    /// Sets `option` of the feed at `url` to `value`. A header is added to the ones the feed
+    /// already has; a user agent replaces the previous one.
+    pub fn set_option(
+        &mut self,
+        url: &str,
+        option: &str,
+        value: &str,
+    ) -> Result<(), FeedOptionError> {
+        match option {
+            "http-header" => {
+                let header = HttpHeader::parse(value)?;
+                self.entry(url).headers.push(header);
+            }
+            "user-agent" => {
+                if has_line_break_or_null(value) {
+                    return Err(FeedOptionError::InvalidUserAgent);
+                }
+                self.entry(url).user_agent = Some(value.to_string());
+            }
+            _ => return Err(FeedOptionError::UnknownOption(option.to_string())),
+        }
+        Ok(())
+    }
+
+    fn entry(&mut self, url: &str) -> &mut FetchOptions {
+        self.by_url.entry(url.to_string()).or_default()
+    }
+
+    pub fn fetch_options(&self, url: &str) -> Option<&FetchOptions> {
+        self.by_url.get(url)
+    }
+
+    pub fn user_agent(&self, url: &str) -> Option<&str> {
+        self.fetch_options(url)
+            .and_then(|options| options.user_agent.as_deref())
+    }
+
+    /// Extra headers to send with requests for the feed at `url`, as "Name: value" lines.
+    pub fn header_lines(&self, url: &str) -> Vec<String> {
+        self.headers(url).map(HttpHeader::to_line).collect()
+    }
+
+    /// Same as `header_lines`, but with the values hidden.
+    pub fn redacted_header_lines(&self, url: &str) -> Vec<String> {
+        self.headers(url).map(HttpHeader::redacted).collect()
+    }
+
+    fn headers<'a>(&'a self, url: &str) -> impl Iterator<Item = &'a HttpHeader> {
+        self.fetch_options(url)
+            .into_iter()
+            .flat_map(|options| options.headers.iter())
+    }
+
+    /// Config lines that recreate the options, one line per feed.
+    pub fn dump_config(&self) -> Vec<String> {
+        self.by_url
+            .iter()
+            .map(|(url, options)| {
+                let mut line = format!("feedopts {}", utils::quote(url.clone()));
+                for header in &options.headers {
+                    line.push_str(" http-header ");
+                    line.push_str(&utils::quote(header.to_line()));
+                }
+                if let Some(ref user_agent) = options.user_agent {
+                    line.push_str(" user-agent ");
+                    line.push_str(&utils::quote(user_agent.clone()));
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/feed.xml";
+
+    #[test]
+    fn t_headers_are_parsed_into_name_and_value() {
+        let header = HttpHeader::parse("X-Auth:   secret token ").unwrap();
+        assert_eq!(header.name(), "X-Auth");
+        assert_eq!(header.to_line(), "X-Auth: secret token");
+
+        assert_eq!(
+            HttpHeader::parse("Authorization:Bearer abc:def")
+                .unwrap()
+                .to_line(),
+            "Authorization: Bearer abc:def"
+        );
+    }
+
+    #[test]
+    fn t_malformed_headers_are_rejected() {
+        assert_eq!(HttpHeader::parse("X-Auth"), Err(HeaderError::MissingColon));
+        assert_eq!(HttpHeader::parse(": secret"), Err(HeaderError::EmptyName));
+        assert_eq!(
+            HttpHeader::parse("X Auth: secret"),
+            Err(HeaderError::InvalidName("X Auth".to_string()))
+        );
+        assert_eq!(
+            HttpHeader::parse("X-Auth:  "),
+            Err(HeaderError::EmptyValue("X-Auth".to_string()))
+        );
+    }
+
+    #[test]
+    fn t_headers_cannot_smuggle_in_other_headers() {
+        for text in &[
+            "X-Auth: secret\r\nCookie: session=1",
+            "X-Auth: secret\nCookie: session=1",
+            "X-Auth: secret\r",
+            "X-Auth: secret\0",
+        ] {
+            assert_eq!(
+                HttpHeader::parse(text),
+                Err(HeaderError::InvalidValue("X-Auth".to_string()))
+            );
+        }
+
+        assert_eq!(
+            HttpHeader::parse("X-Auth\r\nCookie: session=1"),
+            Err(HeaderError::InvalidName("X-Auth  Cookie".to_string()))
+        );
+
+        let mut options = FeedOptions::new();
+        assert_eq!(
+            options.set_option(URL, "user-agent", "Mozilla/5.0\r\nX-Auth: secret"),
+            Err(FeedOptionError::InvalidUserAgent)
+        );
+        assert_eq!(options.fetch_options(URL), None);
+    }
+
+    #[test]
+    fn t_headers_that_newsboat_sets_cannot_be_overridden() {
+        // The conditional request headers come from the cache, and would clash
+        for name in &[
+            "If-None-Match",
+            "if-modified-since",
+            "A-IM",
+            "User-Agent",
+            "HOST",
+        ] {
+            assert_eq!(
+                HttpHeader::parse(&format!("{}: x", name)),
+                Err(HeaderError::Reserved(name.to_string()))
+            );
+        }
+        // ...but similar ones are fine
+        assert!(HttpHeader::parse("If-Match: x").is_ok());
+    }
+
+    #[test]
+    fn t_error_messages_do_not_contain_header_values() {
+        let errors = vec![
+            HttpHeader::parse("X-Auth: secret\r\n").unwrap_err(),
+            HttpHeader::parse("If-None-Match: secret").unwrap_err(),
+        ];
+        for error in errors {
+            assert!(!error.to_message().contains("secret"));
+        }
+    }
+
+    #[test]
+    fn t_options_only_apply_to_their_feed() {
+        let mut options = FeedOptions::new();
+        options
+            .set_option(URL, "http-header", "X-Auth: secret")
+            .unwrap();
+        options
+            .set_option(URL, "http-header", "X-Tenant: 42")
+            .unwrap();
+        options
+            .set_option(URL, "user-agent", "Mozilla/4.0")
+            .unwrap();
+        options
+            .set_option(URL, "user-agent", "Mozilla/5.0")
+            .unwrap();
+
+        assert_eq!(options.user_agent(URL), Some("Mozilla/5.0"));
+        assert_eq!(
+            options.header_lines(URL),
+            vec!["X-Auth: secret", "X-Tenant: 42"]
+        );
+
+        let other = "https://example.org/feed.xml";
+        assert_eq!(options.user_agent(other), None);
+        assert!(options.header_lines(other).is_empty());
+    }
+
+    #[test]
+    fn t_redacted_header_lines_hide_the_values() {
+        let mut options = FeedOptions::new();
+        options
+            .set_option(URL, "http-header", "X-Auth: secret")
+            .unwrap();
+        assert_eq!(
+            options.redacted_header_lines(URL),
+            vec!["X-Auth: [redacted]"]
+        );
+    }
+
+    #[test]
+    fn t_unknown_options_are_rejected() {
+        let mut options = FeedOptions::new();
+        assert_eq!(
+            options.set_option(URL, "cookie", "session=1"),
+            Err(FeedOptionError::UnknownOption("cookie".to_string()))
+        );
+        assert_eq!(
+            options
+                .set_option(URL, "http-header", "X-Auth")
+                .unwrap_err()
+                .to_message(),
+            "header should look like `Name: value'"
+        );
+    }
+
+    #[test]
+    fn t_dump_config_recreates_the_options() {
+        let mut options = FeedOptions::new();
+        options
+            .set_option(URL, "http-header", "X-Auth: \"quoted\"")
+            .unwrap();
+        options
+            .set_option(URL, "user-agent", "Mozilla/5.0")
+            .unwrap();
+        options
+            .set_option("https://example.org/", "user-agent", "curl")
+            .unwrap();
+        assert_eq!(
+            options.dump_config(),
+            vec![
+                r#"feedopts "https://example.com/feed.xml" http-header "X-Auth: \"quoted\"" user-agent "Mozilla/5.0""#,
+                r#"feedopts "https://example.org/" user-agent "curl""#,
+            ]
+        );
+    }
+}
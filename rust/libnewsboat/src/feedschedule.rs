@@ -0,0 +1,402 @@
+//! Honors the `<ttl>`, `<skipHours>` and `<skipDays>` elements of RSS feeds.
+//!
+//! A feed can say how long it may be cached (`<ttl>`, in minutes), and during which hours and on
+//! which days it shouldn't be fetched at all. Hours and days are in UTC, as the RSS 2.0 spec
+//! requires. Only scheduled reloads (the ones `auto-reload` does) take this into account; when the
+//! user asks for a reload, the feed is fetched regardless. Feeds listed in `ignore-feed-schedule`
+//! are always fetched.
+//!
+//! The schedule is kept in the cache as a string (see `FeedSchedule::serialize()`), so it's known
+//! even if the feed wasn't modified since Newsboat started.
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use gettextrs::gettext;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use strprintf::fmt;
+
+/// A scheduled reload fetches the feed if at most this much of the TTL is left. The reload thread
+/// wakes up a few seconds late or early, and a feed that asks for hourly fetches shouldn't be
+/// postponed for another `reload-time` because of that.
+const TTL_TOLERANCE_SECS: i64 = 60;
+
+const DAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "Monday"),
+    (Weekday::Tue, "Tuesday"),
+    (Weekday::Wed, "Wednesday"),
+    (Weekday::Thu, "Thursday"),
+    (Weekday::Fri, "Friday"),
+    (Weekday::Sat, "Saturday"),
+    (Weekday::Sun, "Sunday"),
+];
+
+/// What a feed said about when it should be fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeedSchedule {
+    /// Number of minutes the feed may be cached.
+    ttl: Option<u32>,
+    /// Bit `n` is set if the feed shouldn't be fetched between `n`:00 and `n`:59 UTC.
+    skip_hours: u32,
+    /// Bit `n` is set if the feed shouldn't be fetched on the `n`-th day of the week (counting
+    /// from Monday, which is 0), UTC.
+    skip_days: u8,
+}
+
+impl FeedSchedule {
+    /// Builds a schedule from the text of the `<ttl>` element, and of the `<hour>` and `<day>`
+    /// elements inside `<skipHours>` and `<skipDays>`. Values that don't make sense are ignored.
+    pub fn from_elements(ttl: &str, hours: &[String], days: &[String]) -> FeedSchedule {
+        let mut schedule = FeedSchedule {
+            ttl: parse_ttl(ttl),
+            ..FeedSchedule::default()
+        };
+        for hour in hours.iter().filter_map(|hour| parse_hour(hour)) {
+            schedule.skip_hours |= 1 << hour;
+        }
+        for day in days.iter().filter_map(|day| parse_day(day)) {
+            schedule.skip_days |= 1 << day.num_days_from_monday();
+        }
+        schedule
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == FeedSchedule::default()
+    }
+
+    pub fn ttl(&self) -> Option<u32> {
+        self.ttl
+    }
+
+    /// Writes the schedule as e.g. "ttl=60 skipHours=0,1,2 skipDays=Saturday,Sunday". An empty
+    /// schedule is an empty string.
+    pub fn serialize(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(ttl) = self.ttl {
+            parts.push(format!("ttl={}", ttl));
+        }
+        if self.skip_hours != 0 {
+            let hours: Vec<String> = (0..24)
+                .filter(|hour| self.skip_hours & (1 << hour) != 0)
+                .map(|hour| hour.to_string())
+                .collect();
+            parts.push(format!("skipHours={}", hours.join(",")));
+        }
+        if self.skip_days != 0 {
+            let days: Vec<&str> = DAYS
+                .iter()
+                .filter(|(day, _)| self.skip_days & (1 << day.num_days_from_monday()) != 0)
+                .map(|(_, name)| *name)
+                .collect();
+            parts.push(format!("skipDays={}", days.join(",")));
+        }
+        parts.join(" ")
+    }
+
+    /// The inverse of `serialize()`. Parts it doesn't understand are ignored, so a damaged or
+    /// empty string gives an empty schedule.
+    pub fn deserialize(input: &str) -> FeedSchedule {
+        let mut ttl = "";
+        let mut hours = Vec::new();
+        let mut days = Vec::new();
+        for part in input.split_whitespace() {
+            let mut key_value = part.splitn(2, '=');
+            let key = key_value.next().unwrap_or("");
+            let value = key_value.next().unwrap_or("");
+            let values = || value.split(',').map(String::from);
+            match key {
+                "ttl" => ttl = value,
+                "skipHours" => hours.extend(values()),
+                "skipDays" => days.extend(values()),
+                _ => {}
+            }
+        }
+        FeedSchedule::from_elements(ttl, &hours, &days)
+    }
+
+    /// Whether `skipHours` or `skipDays` say that the feed shouldn't be fetched at `at`.
+    pub fn skips(&self, at: DateTime<Utc>) -> bool {
+        let hour_skipped = self.skip_hours & (1 << at.hour()) != 0;
+        let day_skipped = self.skip_days & (1 << at.weekday().num_days_from_monday()) != 0;
+        hour_skipped || day_skipped
+    }
+}
+
+fn parse_ttl(input: &str) -> Option<u32> {
+    match input.trim().parse::<u32>() {
+        Ok(0) | Err(_) => None,
+        Ok(ttl) => Some(ttl),
+    }
+}
+
+fn parse_hour(input: &str) -> Option<u32> {
+    match input.trim().parse::<u32>() {
+        // Some feeds write midnight as 24
+        Ok(24) => Some(0),
+        Ok(hour) if hour < 24 => Some(hour),
+        _ => None,
+    }
+}
+
+fn parse_day(input: &str) -> Option<Weekday> {
+    let input = input.trim();
+    DAYS.iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(input))
+        .map(|(day, _)| *day)
+}
+
+/// Decides whether a scheduled reload should fetch a feed, and remembers when each feed was
+/// fetched. Feeds are reloaded from multiple threads at once, hence the mutex.
+#[derive(Default)]
+pub struct FeedScheduler {
+    /// Feeds whose schedule is ignored (`ignore-feed-schedule`).
+    ignored: HashSet<String>,
+
+    last_fetched: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl FeedScheduler {
+    pub fn new() -> FeedScheduler {
+        FeedScheduler::default()
+    }
+
+    /// Don't honor the schedule of the feed at `url`.
+    pub fn ignore_schedule(&mut self, url: &str) {
+        self.ignored.insert(url.to_string());
+    }
+
+    /// URLs passed to `ignore_schedule()`, sorted.
+    pub fn ignored_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self.ignored.iter().cloned().collect();
+        urls.sort();
+        urls
+    }
+
+    /// Records that the feed at `url` was fetched at `at`.
+    pub fn fetched(&self, url: &str, at: DateTime<Utc>) {
+        self.last_fetched
+            .lock()
+            .expect("another thread panicked while holding the lock")
+            .insert(url.to_string(), at);
+    }
+
+    /// Returns why the feed at `url` shouldn't be fetched at `now`, or `None` if it should be.
+    /// A `manual` reload always fetches the feed.
+    pub fn postpone_reason(
+        &self,
+        url: &str,
+        schedule: &FeedSchedule,
+        now: DateTime<Utc>,
+        manual: bool,
+    ) -> Option<String> {
+        if manual || self.ignored.contains(url) {
+            return None;
+        }
+
+        if schedule.skips(now) {
+            return Some(fmt!(
+                &gettext("the feed asks not to be fetched at %02u:00 UTC on %s"),
+                now.hour(),
+                &now.format("%A").to_string()
+            ));
+        }
+
+        let ttl = schedule.ttl?;
+        let last_fetched = *self
+            .last_fetched
+            .lock()
+            .expect("another thread panicked while holding the lock")
+            .get(url)?;
+        let next_fetch = last_fetched + Duration::minutes(i64::from(ttl));
+        if next_fetch - now > Duration::seconds(TTL_TOLERANCE_SECS) {
+            return Some(fmt_plural!(
+                ttl,
+                "the feed asks to be cached for %u minute",
+                "the feed asks to be cached for %u minutes",
+                ttl
+            ));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const URL: &str = "https://example.com/feed.xml";
+
+    fn strings(input: &[&str]) -> Vec<String> {
+        input.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// 2020-06-01 is a Monday.
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.ymd(2020, 6, day).and_hms(hour, minute, second)
+    }
+
+    #[test]
+    fn t_elements_are_parsed_leniently() {
+        let schedule = FeedSchedule::from_elements(
+            " 60 ",
+            &strings(&["1", "24", "25", "noon", " 23 "]),
+            &strings(&["saturday", "Sunday", "Caturday"]),
+        );
+
+        assert_eq!(schedule.ttl(), Some(60));
+        assert_eq!(
+            schedule.serialize(),
+            "ttl=60 skipHours=0,1,23 skipDays=Saturday,Sunday"
+        );
+
+        assert!(FeedSchedule::from_elements("", &[], &[]).is_empty());
+        assert!(FeedSchedule::from_elements("0", &[], &[]).is_empty());
+        assert!(FeedSchedule::from_elements("-5", &[], &[]).is_empty());
+    }
+
+    #[test]
+    fn t_serialized_schedule_is_read_back_unchanged() {
+        let schedule =
+            FeedSchedule::from_elements("180", &strings(&["0", "12"]), &strings(&["Wednesday"]));
+
+        assert_eq!(FeedSchedule::deserialize(&schedule.serialize()), schedule);
+        assert!(FeedSchedule::deserialize("").is_empty());
+        assert!(FeedSchedule::deserialize("garbage skipHours=").is_empty());
+    }
+
+    #[test]
+    fn t_ttl_overrides_a_shorter_reload_time() {
+        let scheduler = FeedScheduler::new();
+        let schedule = FeedSchedule::from_elements("60", &[], &[]);
+
+        // Never fetched: fetch right away
+        assert_eq!(
+            scheduler.postpone_reason(URL, &schedule, at(1, 10, 0, 0), false),
+            None
+        );
+        scheduler.fetched(URL, at(1, 10, 0, 0));
+
+        // `reload-time` is 15 minutes, but the feed asked for an hour
+        for minute in &[15, 30, 45] {
+            assert_eq!(
+                scheduler.postpone_reason(URL, &schedule, at(1, 10, *minute, 0), false),
+                Some("the feed asks to be cached for 60 minutes".to_string())
+            );
+        }
+        // The reload thread woke up a few seconds early; close enough
+        assert_eq!(
+            scheduler.postpone_reason(URL, &schedule, at(1, 10, 59, 55), false),
+            None
+        );
+        assert_eq!(
+            scheduler.postpone_reason(URL, &schedule, at(1, 11, 0, 0), false),
+            None
+        );
+    }
+
+    #[test]
+    fn t_ttl_shorter_than_reload_time_changes_nothing() {
+        let scheduler = FeedScheduler::new();
+        let schedule = FeedSchedule::from_elements("5", &[], &[]);
+        scheduler.fetched(URL, at(1, 10, 0, 0));
+
+        assert_eq!(
+            scheduler.postpone_reason(URL, &schedule, at(1, 10, 30, 0), false),
+            None
+        );
+    }
+
+    #[test]
+    fn t_skip_hours_boundaries_around_midnight_utc() {
+        let scheduler = FeedScheduler::new();
+
+        let late = FeedSchedule::from_elements("", &strings(&["23"]), &[]);
+        assert_eq!(
+            scheduler.postpone_reason(URL, &late, at(1, 22, 59, 59), false),
+            None
+        );
+        assert_eq!(
+            scheduler.postpone_reason(URL, &late, at(1, 23, 0, 0), false),
+            Some("the feed asks not to be fetched at 23:00 UTC on Monday".to_string())
+        );
+        assert!(scheduler
+            .postpone_reason(URL, &late, at(1, 23, 59, 59), false)
+            .is_some());
+        assert_eq!(
+            scheduler.postpone_reason(URL, &late, at(2, 0, 0, 0), false),
+            None
+        );
+
+        let midnight = FeedSchedule::from_elements("", &strings(&["0"]), &[]);
+        assert_eq!(
+            scheduler.postpone_reason(URL, &midnight, at(1, 23, 59, 59), false),
+            None
+        );
+        assert!(scheduler
+            .postpone_reason(URL, &midnight, at(2, 0, 0, 0), false)
+            .is_some());
+        assert_eq!(
+            scheduler.postpone_reason(URL, &midnight, at(2, 1, 0, 0), false),
+            None
+        );
+    }
+
+    #[test]
+    fn t_skip_days_are_evaluated_in_utc() {
+        let scheduler = FeedScheduler::new();
+        let weekend = FeedSchedule::from_elements("", &[], &strings(&["Saturday", "Sunday"]));
+
+        // 2020-06-05 is a Friday, 2020-06-08 is a Monday
+        assert_eq!(
+            scheduler.postpone_reason(URL, &weekend, at(5, 23, 59, 59), false),
+            None
+        );
+        assert!(scheduler
+            .postpone_reason(URL, &weekend, at(6, 0, 0, 0), false)
+            .is_some());
+        assert!(scheduler
+            .postpone_reason(URL, &weekend, at(7, 23, 59, 59), false)
+            .is_some());
+        assert_eq!(
+            scheduler.postpone_reason(URL, &weekend, at(8, 0, 0, 0), false),
+            None
+        );
+    }
+
+    #[test]
+    fn t_manual_reload_bypasses_the_schedule() {
+        let scheduler = FeedScheduler::new();
+        let schedule = FeedSchedule::from_elements("60", &strings(&["10"]), &[]);
+        scheduler.fetched(URL, at(1, 10, 0, 0));
+
+        assert!(scheduler
+            .postpone_reason(URL, &schedule, at(1, 10, 5, 0), false)
+            .is_some());
+        assert_eq!(
+            scheduler.postpone_reason(URL, &schedule, at(1, 10, 5, 0), true),
+            None
+        );
+    }
+
+    #[test]
+    fn t_ignored_feeds_are_always_fetched() {
+        let mut scheduler = FeedScheduler::new();
+        scheduler.ignore_schedule(URL);
+        scheduler.ignore_schedule("https://a.example/feed");
+        let schedule = FeedSchedule::from_elements("60", &strings(&["10"]), &[]);
+        scheduler.fetched(URL, at(1, 10, 0, 0));
+
+        assert_eq!(
+            scheduler.postpone_reason(URL, &schedule, at(1, 10, 5, 0), false),
+            None
+        );
+        assert!(scheduler
+            .postpone_reason("https://other.example/", &schedule, at(1, 10, 5, 0), false)
+            .is_some());
+        assert_eq!(
+            scheduler.ignored_urls(),
+            vec!["https://a.example/feed".to_string(), URL.to_string()]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Reading config and urls files that aren't UTF-8.
+//!
+//! Files written on older systems are often Latin-1, or UTF-8 with a few Latin-1 lines pasted in.
+//! Rather than failing on them, or mangling tag names and titles, each line that isn't valid UTF-8
+//! is transcoded from a single-byte encoding, and the user is told about it.
+
+use crate::utils::{self, AtomicWriteError};
+use gettextrs::gettext;
+use std::fs;
+use std::path::Path;
+use strprintf::fmt;
+
+/// Encoding that the lines of a file were read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Latin1,
+    Windows1252,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Windows1252 => "Windows-1252",
+        }
+    }
+
+    /// Single-byte encoding to try for lines that aren't UTF-8. That's the locale's codeset if it's
+    /// Windows-1252, and Latin-1 otherwise.
+    pub fn fallback(locale_codeset: &str) -> Encoding {
+        let codeset: String = locale_codeset
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        match codeset.as_str() {
+            "CP1252" | "WINDOWS1252" => Encoding::Windows1252,
+            _ => Encoding::Latin1,
+        }
+    }
+}
+
+/// Contents of a file, converted to UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub text: String,
+    /// Encoding of the lines that weren't UTF-8, or `Encoding::Utf8` if they all were.
+    pub encoding: Encoding,
+}
+
+/// Converts `bytes` to UTF-8 line by line. Lines that aren't valid UTF-8 are read in the fallback
+/// encoding for `locale_codeset`; bytes that don't mean anything in it become U+FFFD.
+pub fn decode(bytes: &[u8], locale_codeset: &str) -> Decoded {
+    let fallback = Encoding::fallback(locale_codeset);
+    let mut encoding = Encoding::Utf8;
+    let mut text = String::with_capacity(bytes.len());
+    for (i, line) in bytes.split(|&byte| byte == b'\n').enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        match std::str::from_utf8(line) {
+            Ok(line) => text.push_str(line),
+            Err(_) => {
+                encoding = fallback;
+                text.extend(line.iter().map(|&byte| decode_byte(byte, fallback)));
+            }
+        }
+    }
+    Decoded { text, encoding }
+}
+
+fn decode_byte(byte: u8, encoding: Encoding) -> char {
+    const REPLACEMENT: char = '\u{fffd}';
+    // Windows-1252 characters for 0x80 to 0x9f; the unassigned ones are U+FFFD
+    const WINDOWS_1252: [char; 32] = [
+        '\u{20ac}',
+        REPLACEMENT,
+        '\u{201a}',
+        '\u{0192}',
+        '\u{201e}',
+        '\u{2026}',
+        '\u{2020}',
+        '\u{2021}',
+        '\u{02c6}',
+        '\u{2030}',
+        '\u{0160}',
+        '\u{2039}',
+        '\u{0152}',
+        REPLACEMENT,
+        '\u{017d}',
+        REPLACEMENT,
+        REPLACEMENT,
+        '\u{2018}',
+        '\u{2019}',
+        '\u{201c}',
+        '\u{201d}',
+        '\u{2022}',
+        '\u{2013}',
+        '\u{2014}',
+        '\u{02dc}',
+        '\u{2122}',
+        '\u{0161}',
+        '\u{203a}',
+        '\u{0153}',
+        REPLACEMENT,
+        '\u{017e}',
+        '\u{0178}',
+    ];
+
+    match (byte, encoding) {
+        (0x80..=0x9f, Encoding::Windows1252) => WINDOWS_1252[usize::from(byte - 0x80)],
+        // C1 control characters, which don't belong in a text file
+        (0x80..=0x9f, _) => REPLACEMENT,
+        _ => char::from(byte),
+    }
+}
+
+/// Warning to show once for a file that wasn't UTF-8.
+pub fn warning(path: &str, encoding: Encoding) -> String {
+    fmt!(
+        &gettext(
+            "Warning: %s is not valid UTF-8; the lines that aren't were read as %s. \
+             Run `newsboat --convert-to-utf8' to convert the file."
+        ),
+        path,
+        encoding.name()
+    )
+}
+
+/// Rewrites the file at `path` in UTF-8, decoding it like `decode` does. Returns the encoding it
+/// was converted from, or `None` if the file already was UTF-8 and has been left alone.
+pub fn convert_file(
+    path: &Path,
+    locale_codeset: &str,
+) -> Result<Option<Encoding>, AtomicWriteError> {
+    let bytes = fs::read(path)?;
+    let decoded = decode(&bytes, locale_codeset);
+    if decoded.encoding == Encoding::Utf8 {
+        return Ok(None);
+    }
+    utils::atomic_write(path, decoded.text.as_bytes(), 0o644)?;
+    Ok(Some(decoded.encoding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // "Nachrichten für Köln" in Latin-1
+    const LATIN1_TAGS: &[u8] = b"https://example.com/feed.xml \"Nachrichten f\xfcr K\xf6ln\"\n";
+
+    #[test]
+    fn t_utf8_and_ascii_are_passed_through() {
+        let ascii = decode(b"bind-key j down\n", "UTF-8");
+        assert_eq!(ascii.encoding, Encoding::Utf8);
+        assert_eq!(ascii.text, "bind-key j down\n");
+
+        let utf8 = decode("\"Nachrichten für Köln\"".as_bytes(), "ISO-8859-1");
+        assert_eq!(utf8.encoding, Encoding::Utf8);
+        assert_eq!(utf8.text, "\"Nachrichten für Köln\"");
+    }
+
+    #[test]
+    fn t_latin1_lines_are_transcoded() {
+        let mut bytes = b"# urls\n".to_vec();
+        bytes.extend_from_slice(LATIN1_TAGS);
+        bytes.extend_from_slice("https://example.com/other.xml \"Straße\"".as_bytes());
+
+        let decoded = decode(&bytes, "UTF-8");
+        assert_eq!(decoded.encoding, Encoding::Latin1);
+        assert_eq!(
+            decoded.text,
+            "# urls\n\
+             https://example.com/feed.xml \"Nachrichten für Köln\"\n\
+             https://example.com/other.xml \"Straße\""
+        );
+    }
+
+    #[test]
+    fn t_bytes_without_a_meaning_become_replacement_characters() {
+        let latin1 = decode(b"title \x93quoted\x94 \xe9\n", "UTF-8");
+        assert_eq!(latin1.text, "title \u{fffd}quoted\u{fffd} é\n");
+
+        let windows = decode(b"title \x93quoted\x94 \x81\n", "CP1252");
+        assert_eq!(windows.encoding, Encoding::Windows1252);
+        assert_eq!(windows.text, "title “quoted” \u{fffd}\n");
+    }
+
+    #[test]
+    fn t_fallback_follows_the_locale_codeset_if_it_is_windows_1252() {
+        assert_eq!(Encoding::fallback("UTF-8"), Encoding::Latin1);
+        assert_eq!(Encoding::fallback("ISO-8859-1"), Encoding::Latin1);
+        assert_eq!(Encoding::fallback("cp1252"), Encoding::Windows1252);
+        assert_eq!(Encoding::fallback("WINDOWS-1252"), Encoding::Windows1252);
+    }
+
+    #[test]
+    fn t_warning_names_the_file_and_the_encoding() {
+        assert_eq!(
+            warning("/home/user/.newsboat/urls", Encoding::Latin1),
+            "Warning: /home/user/.newsboat/urls is not valid UTF-8; the lines that aren't \
+             were read as ISO-8859-1. Run `newsboat --convert-to-utf8' to convert the file."
+        );
+    }
+
+    #[test]
+    fn t_convert_file_rewrites_latin1_as_utf8() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("urls");
+        fs::write(&path, LATIN1_TAGS).unwrap();
+
+        assert_eq!(
+            convert_file(&path, "UTF-8").unwrap(),
+            Some(Encoding::Latin1)
+        );
+        let contents = String::from_utf8(fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            contents,
+            "https://example.com/feed.xml \"Nachrichten für Köln\"\n"
+        );
+
+        // Converting again finds nothing to do
+        assert_eq!(convert_file(&path, "UTF-8").unwrap(), None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn t_convert_file_fails_if_the_file_can_not_be_read() {
+        let tmp = TempDir::new().unwrap();
+        assert!(convert_file(&tmp.path().join("missing"), "UTF-8").is_err());
+    }
+}
@@ -0,0 +1,456 @@
+//! Attributes that filter expressions can compare, along with the kind of values they hold.
+//!
+//! This mirrors the "Available Attributes" table of the documentation, and is what the filter
+//! builder offers to the user. The types decide which operators make sense for an attribute, and
+//! how the values typed by the user are checked.
+
+use crate::filterparser::{self, Operator, Value};
+use chrono::DateTime;
+use gettextrs::gettext;
+use regex_rs::{CompFlags, Regex};
+use strprintf::fmt;
+
+/// Format in which Newsboat shows the `date` and `feeddate` attributes.
+const DATE_FORMAT: &str = "%a, %d %b %Y %T %z";
+
+/// The kind of value an attribute holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    Text,
+    Number,
+    /// Either "yes" or "no".
+    YesNo,
+    /// Date and time, as written by Newsboat.
+    Date,
+    /// Space-separated words, like tags.
+    Words,
+}
+
+impl AttributeType {
+    /// Operators that make sense for values of this type, the most useful first.
+    pub fn operators(self) -> &'static [Operator] {
+        match self {
+            AttributeType::Text => &[
+                Operator::RegexMatches,
+                Operator::NotRegexMatches,
+                Operator::Equals,
+                Operator::NotEquals,
+            ],
+            AttributeType::Number => &[
+                Operator::Equals,
+                Operator::NotEquals,
+                Operator::LessThan,
+                Operator::GreaterThan,
+                Operator::LessThanOrEquals,
+                Operator::GreaterThanOrEquals,
+                Operator::Between,
+            ],
+            AttributeType::YesNo => &[Operator::Equals, Operator::NotEquals],
+            AttributeType::Date => &[
+                Operator::RegexMatches,
+                Operator::NotRegexMatches,
+                Operator::Equals,
+                Operator::NotEquals,
+            ],
+            AttributeType::Words => &[
+                Operator::Contains,
+                Operator::NotContains,
+                Operator::RegexMatches,
+                Operator::NotRegexMatches,
+            ],
+        }
+    }
+
+    /// Checks `input` as the right-hand side of `op`, and turns it into a value. If it doesn't
+    /// fit, returns an internationalized description of the problem.
+    pub fn parse_value(self, op: &Operator, input: &str) -> Result<Value, String> {
+        if input.contains('"') {
+            return Err(gettext("Values can't contain double quotes."));
+        }
+        if input.ends_with('\\') {
+            return Err(gettext("Values can't end with a backslash."));
+        }
+
+        match (self, op) {
+            // The filter parser reads these as relative dates, which regexes can't be matched
+            // against
+            (AttributeType::Date, Operator::RegexMatches)
+            | (AttributeType::Date, Operator::NotRegexMatches)
+                if matches!(filterparser::relative_date(input), Ok(Some(_))) =>
+            {
+                Err(fmt!(
+                    &gettext("`%s' is a relative date, which can't be matched by a regex."),
+                    input
+                ))
+            }
+            (_, Operator::RegexMatches) | (_, Operator::NotRegexMatches) => {
+                let flags = CompFlags::EXTENDED | CompFlags::IGNORE_CASE | CompFlags::NO_SUB;
+                match Regex::new(input, flags) {
+                    Ok(_) => Ok(Value::Literal(input.to_string())),
+                    Err(error) => Err(fmt!(
+                        &gettext("`%s' is not a valid regular expression: %s"),
+                        input,
+                        error
+                    )),
+                }
+            }
+            (AttributeType::Number, Operator::Between) => {
+                let bound = |field: &str| {
+                    let field = field.trim();
+                    if is_number(field) {
+                        field.parse::<i64>().ok()
+                    } else {
+                        None
+                    }
+                };
+                let fields = input.split(':').collect::<Vec<_>>();
+                let bounds = match fields.as_slice() {
+                    [start, end] => (bound(start), bound(end)),
+                    _ => (None, None),
+                };
+                match bounds {
+                    (Some(start), Some(end)) => Ok(Value::Range(start, end)),
+                    _ => Err(fmt!(
+                        &gettext("`%s' is not a range of numbers, like 1:10."),
+                        input
+                    )),
+                }
+            }
+            (AttributeType::Number, _) => {
+                if is_number(input.trim()) {
+                    Ok(Value::Literal(input.trim().to_string()))
+                } else {
+                    Err(fmt!(&gettext("`%s' is not a number."), input))
+                }
+            }
+            (AttributeType::YesNo, _) => match input.trim() {
+                "yes" | "no" => Ok(Value::Literal(input.trim().to_string())),
+                _ => Err(gettext("Expected `yes' or `no'.")),
+            },
+            (AttributeType::Date, _) => match DateTime::parse_from_rfc2822(input.trim()) {
+                Ok(date) => Ok(Value::Literal(date.format(DATE_FORMAT).to_string())),
+                Err(_) => Err(fmt!(
+                    &gettext("`%s' is not a date like `Mon, 02 Jan 2006 15:04:05 +0000'."),
+                    input
+                )),
+            },
+            (AttributeType::Words, Operator::Contains)
+            | (AttributeType::Words, Operator::NotContains) => {
+                let word = input.trim();
+                if word.is_empty() || word.contains(' ') {
+                    Err(gettext("Expected a single word."))
+                } else {
+                    Ok(Value::Literal(word.to_string()))
+                }
+            }
+            _ => Ok(Value::Literal(input.to_string())),
+        }
+    }
+}
+
+/// A number the way the filter language writes it: an optional minus followed by digits.
+fn is_number(input: &str) -> bool {
+    let digits = input.trim_start_matches('-');
+    input.len() - digits.len() <= 1
+        && !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Where an attribute can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeContext {
+    /// Only in the article list.
+    Article,
+    /// In the feed list, and in the article list (where it refers to the article's feed).
+    FeedAndArticle,
+}
+
+/// An attribute of feeds or articles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: &'static str,
+    pub context: AttributeContext,
+    pub kind: AttributeType,
+    description: &'static str,
+}
+
+impl Attribute {
+    /// Internationalized description of the attribute.
+    pub fn description(&self) -> String {
+        gettext(self.description)
+    }
+}
+
+macro_rules! attribute {
+    ($name:expr, $context:ident, $kind:ident, $description:expr) => {
+        Attribute {
+            name: $name,
+            context: AttributeContext::$context,
+            kind: AttributeType::$kind,
+            description: $description,
+        }
+    };
+}
+
+/// All the attributes, in the order of the documentation.
+pub const ATTRIBUTES: &[Attribute] = &[
+    attribute!("title", Article, Text, "article title"),
+    attribute!("link", Article, Text, "article link"),
+    attribute!("author", Article, Text, "article author"),
+    attribute!("content", Article, Text, "article body"),
+    attribute!("date", Article, Date, "publication date of the article"),
+    attribute!("guid", Article, Text, "a unique identifier of the article"),
+    attribute!(
+        "unread",
+        Article,
+        YesNo,
+        "indicates whether the article has been read"
+    ),
+    attribute!(
+        "updated",
+        Article,
+        YesNo,
+        "indicates whether the article was edited by the publisher since it was last read"
+    ),
+    attribute!(
+        "enclosure_url",
+        Article,
+        Text,
+        "the URL of an enclosure (e.g. podcast file), empty if there is no enclosure"
+    ),
+    attribute!(
+        "enclosure_type",
+        Article,
+        Text,
+        "the MIME type of the enclosure, empty if there is no enclosure"
+    ),
+    attribute!("flags", Article, Text, "The set of flags of the article"),
+    attribute!(
+        "note",
+        Article,
+        Text,
+        "The user's note on the article, empty if there is none"
+    ),
+    attribute!(
+        "words",
+        Article,
+        Number,
+        "number of words in the article, not counting markup, links and code"
+    ),
+    attribute!(
+        "reading_minutes",
+        Article,
+        Number,
+        "estimated time to read the article (in minutes)"
+    ),
+    attribute!("age", Article, Number, "Age of an article (in days)"),
+    attribute!(
+        "articleindex",
+        Article,
+        Number,
+        "Index of an article in an article list"
+    ),
+    attribute!("feedtitle", FeedAndArticle, Text, "title of the feed"),
+    attribute!("description", FeedAndArticle, Text, "feed description"),
+    attribute!("feedlink", FeedAndArticle, Text, "link to the feed"),
+    attribute!(
+        "feeddate",
+        FeedAndArticle,
+        Date,
+        "publication date of the feed"
+    ),
+    attribute!("rssurl", FeedAndArticle, Text, "RSS URL of the feed"),
+    attribute!(
+        "unread_count",
+        FeedAndArticle,
+        Number,
+        "number of unread articles in the feed"
+    ),
+    attribute!(
+        "total_count",
+        FeedAndArticle,
+        Number,
+        "total number of articles in the feed"
+    ),
+    attribute!(
+        "tags",
+        FeedAndArticle,
+        Words,
+        "all tags that are associated with the feed"
+    ),
+    attribute!(
+        "feedindex",
+        FeedAndArticle,
+        Number,
+        "Index of a feed in the feed list"
+    ),
+];
+
+/// Attributes that can be used in the given context: "article" for the article list, "feed" for
+/// the feed list. Any other context has no attributes.
+pub fn attributes_for(context: &str) -> Vec<&'static Attribute> {
+    ATTRIBUTES
+        .iter()
+        .filter(|attribute| match context {
+            "article" => true,
+            "feed" => attribute.context == AttributeContext::FeedAndArticle,
+            _ => false,
+        })
+        .collect()
+}
+
+/// Looks up an attribute by name.
+pub fn find(name: &str) -> Option<&'static Attribute> {
+    ATTRIBUTES.iter().find(|attribute| attribute.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_operators_depend_on_the_type() {
+        assert_eq!(
+            AttributeType::Number.operators(),
+            &[
+                Operator::Equals,
+                Operator::NotEquals,
+                Operator::LessThan,
+                Operator::GreaterThan,
+                Operator::LessThanOrEquals,
+                Operator::GreaterThanOrEquals,
+                Operator::Between,
+            ]
+        );
+        assert_eq!(
+            AttributeType::YesNo.operators(),
+            &[Operator::Equals, Operator::NotEquals]
+        );
+        assert_eq!(AttributeType::Words.operators()[0], Operator::Contains);
+        assert!(!AttributeType::Text
+            .operators()
+            .contains(&Operator::LessThan));
+        assert!(!AttributeType::Date.operators().contains(&Operator::Between));
+    }
+
+    #[test]
+    fn t_values_are_checked_against_the_type_and_operator() {
+        let number = AttributeType::Number;
+        assert_eq!(
+            number.parse_value(&Operator::LessThan, " -3 "),
+            Ok(Value::Literal("-3".to_string()))
+        );
+        assert_eq!(
+            number.parse_value(&Operator::LessThan, "three"),
+            Err("`three' is not a number.".to_string())
+        );
+        assert_eq!(
+            number.parse_value(&Operator::Between, "1 : 10"),
+            Ok(Value::Range(1, 10))
+        );
+        assert_eq!(
+            number.parse_value(&Operator::Between, "1:"),
+            Err("`1:' is not a range of numbers, like 1:10.".to_string())
+        );
+
+        let yes_no = AttributeType::YesNo;
+        assert_eq!(
+            yes_no.parse_value(&Operator::Equals, "yes"),
+            Ok(Value::Literal("yes".to_string()))
+        );
+        assert_eq!(
+            yes_no.parse_value(&Operator::Equals, "true"),
+            Err("Expected `yes' or `no'.".to_string())
+        );
+
+        let words = AttributeType::Words;
+        assert_eq!(
+            words.parse_value(&Operator::Contains, "two words"),
+            Err("Expected a single word.".to_string())
+        );
+    }
+
+    #[test]
+    fn t_regexes_are_compiled() {
+        let text = AttributeType::Text;
+        assert_eq!(
+            text.parse_value(&Operator::RegexMatches, "^(Rust|C\\+\\+)"),
+            Ok(Value::Literal("^(Rust|C\\+\\+)".to_string()))
+        );
+        let error = text
+            .parse_value(&Operator::NotRegexMatches, "(unclosed")
+            .unwrap_err();
+        assert!(error.starts_with("`(unclosed' is not a valid regular expression: "));
+        // Without a regex, the text is taken as is
+        assert_eq!(
+            text.parse_value(&Operator::Equals, "(unclosed"),
+            Ok(Value::Literal("(unclosed".to_string()))
+        );
+    }
+
+    #[test]
+    fn t_dates_are_parsed_and_written_the_way_newsboat_shows_them() {
+        let date = AttributeType::Date;
+        assert_eq!(
+            date.parse_value(&Operator::Equals, "Tue, 1 Jul 2003 10:52:37 +0200"),
+            Ok(Value::Literal(
+                "Tue, 01 Jul 2003 10:52:37 +0200".to_string()
+            ))
+        );
+        assert_eq!(
+            date.parse_value(&Operator::Equals, "2003-07-01"),
+            Err("`2003-07-01' is not a date like `Mon, 02 Jan 2006 15:04:05 +0000'.".to_string())
+        );
+        // Regexes are matched against the text of the date
+        assert_eq!(
+            date.parse_value(&Operator::RegexMatches, "Jul 2003"),
+            Ok(Value::Literal("Jul 2003".to_string()))
+        );
+        assert_eq!(
+            date.parse_value(&Operator::NotRegexMatches, "-7 days"),
+            Err("`-7 days' is a relative date, which can't be matched by a regex.".to_string())
+        );
+    }
+
+    #[test]
+    fn t_values_that_cant_be_quoted_are_rejected() {
+        let text = AttributeType::Text;
+        assert_eq!(
+            text.parse_value(&Operator::Equals, "say \"hi\""),
+            Err("Values can't contain double quotes.".to_string())
+        );
+        assert_eq!(
+            text.parse_value(&Operator::Equals, "C:\\"),
+            Err("Values can't end with a backslash.".to_string())
+        );
+    }
+
+    #[test]
+    fn t_attributes_for_context() {
+        let names = |context| {
+            attributes_for(context)
+                .iter()
+                .map(|attribute| attribute.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("article").len(), ATTRIBUTES.len());
+        assert_eq!(
+            names("feed"),
+            vec![
+                "feedtitle",
+                "description",
+                "feedlink",
+                "feeddate",
+                "rssurl",
+                "unread_count",
+                "total_count",
+                "tags",
+                "feedindex"
+            ]
+        );
+        assert!(names("help").is_empty());
+
+        assert_eq!(find("age").map(|a| a.kind), Some(AttributeType::Number));
+        assert_eq!(find("nonexistent"), None);
+    }
+}
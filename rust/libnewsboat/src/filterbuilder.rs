@@ -0,0 +1,466 @@
+//! Model of the filter builder dialog, which puts a filter expression together from conditions
+//! the user picks from lists.
+//!
+//! Each condition takes three steps: an attribute (see `filterattributes`), an operator that
+//! suits the attribute's type, and a value, which is checked before the condition is added.
+//! Conditions are joined with "and" or "or", in the order in which they're added: "a and b or c"
+//! means "(a and b) or c". The expression is rendered after every step, so the dialog can show it
+//! as it grows.
+
+use crate::filterattributes::{self, Attribute, AttributeType};
+use crate::filterparser::{Expression, Operator};
+use crate::utils;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Column at which descriptions start in the lists of attributes and operators.
+const DESCRIPTION_COLUMN: usize = 16;
+
+/// What the dialog is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// The user picks an attribute from `items()`.
+    Attribute,
+    /// The user picks an operator from `items()`.
+    Operator,
+    /// The user types a value; see `prompt()` and `set_value()`.
+    Value,
+    /// The user picks whether to add another condition, or to apply the filter.
+    Next,
+    /// The user applied the filter; see `expression()`.
+    Done,
+}
+
+/// How a condition is joined to the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connective {
+    And,
+    Or,
+}
+
+impl Connective {
+    fn keyword(self) -> &'static str {
+        match self {
+            Connective::And => "and",
+            Connective::Or => "or",
+        }
+    }
+}
+
+/// Items of the list shown in the `Next` stage, in order.
+const NEXT_AND: usize = 0;
+const NEXT_OR: usize = 1;
+const NEXT_APPLY: usize = 2;
+const NEXT_REMOVE_LAST: usize = 3;
+
+/// State of the filter builder dialog.
+#[derive(Debug, Clone)]
+pub struct FilterBuilder {
+    attributes: Vec<&'static Attribute>,
+    /// Finished conditions, along with the connective that joins each to the previous ones (it's
+    /// ignored for the first condition).
+    conditions: Vec<(Connective, Expression)>,
+    stage: Stage,
+    /// Parts of the condition that's being put together.
+    connective: Connective,
+    attribute: Option<&'static Attribute>,
+    operator: Option<Operator>,
+}
+
+impl FilterBuilder {
+    /// Starts building a filter for the given context: "article" for the article list, "feed" for
+    /// the feed list.
+    pub fn new(context: &str) -> FilterBuilder {
+        FilterBuilder {
+            attributes: filterattributes::attributes_for(context),
+            conditions: Vec::new(),
+            stage: Stage::Attribute,
+            connective: Connective::And,
+            attribute: None,
+            operator: None,
+        }
+    }
+
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    fn operators(&self) -> &'static [Operator] {
+        self.attribute
+            .map(|attribute| attribute.kind.operators())
+            .unwrap_or(&[])
+    }
+
+    /// Internationalized lines of the list that the user picks from at the current stage.
+    pub fn items(&self) -> Vec<String> {
+        match self.stage {
+            Stage::Attribute => self
+                .attributes
+                .iter()
+                .map(|attribute| in_columns(attribute.name, &attribute.description()))
+                .collect(),
+            Stage::Operator => self
+                .operators()
+                .iter()
+                .map(|op| in_columns(&op.to_string(), &describe_operator(op)))
+                .collect(),
+            Stage::Value => vec![gettext("Press ENTER to type the value")],
+            Stage::Next => vec![
+                gettext("and: add a condition that must hold as well"),
+                gettext("or: add a condition that may hold instead"),
+                gettext("Apply the filter"),
+                gettext("Remove the last condition"),
+            ],
+            Stage::Done => Vec::new(),
+        }
+    }
+
+    /// Internationalized heading of the dialog at the current stage, followed by the expression
+    /// put together so far.
+    pub fn title(&self) -> String {
+        let heading = match self.stage {
+            Stage::Attribute => gettext("Choose an attribute"),
+            Stage::Operator => gettext("Choose an operator"),
+            Stage::Value => gettext("Enter a value"),
+            Stage::Next | Stage::Done => gettext("Add a condition, or apply the filter"),
+        };
+        let preview = self.preview();
+        if preview.is_empty() {
+            heading
+        } else {
+            fmt!("%s: %s", heading, preview)
+        }
+    }
+
+    /// Acts on the item at `index` of `items()`. Out-of-range indices are ignored, and so is
+    /// everything in the `Value` and `Done` stages.
+    pub fn select(&mut self, index: usize) {
+        match self.stage {
+            Stage::Attribute => {
+                if let Some(attribute) = self.attributes.get(index) {
+                    self.attribute = Some(attribute);
+                    self.stage = Stage::Operator;
+                }
+            }
+            Stage::Operator => {
+                if let Some(op) = self.operators().get(index) {
+                    self.operator = Some(op.clone());
+                    self.stage = Stage::Value;
+                }
+            }
+            Stage::Next => match index {
+                NEXT_AND | NEXT_OR => {
+                    self.connective = if index == NEXT_AND {
+                        Connective::And
+                    } else {
+                        Connective::Or
+                    };
+                    self.stage = Stage::Attribute;
+                }
+                NEXT_APPLY => self.stage = Stage::Done,
+                NEXT_REMOVE_LAST => {
+                    self.conditions.pop();
+                    if self.conditions.is_empty() {
+                        self.connective = Connective::And;
+                        self.stage = Stage::Attribute;
+                    }
+                }
+                _ => {}
+            },
+            Stage::Value | Stage::Done => {}
+        }
+    }
+
+    /// Internationalized prompt for the value, describing what's expected. Empty outside of the
+    /// `Value` stage.
+    pub fn prompt(&self) -> String {
+        let (attribute, op) = match (self.stage, self.attribute, &self.operator) {
+            (Stage::Value, Some(attribute), Some(op)) => (attribute, op),
+            _ => return String::new(),
+        };
+        let expected = match (attribute.kind, op) {
+            (_, Operator::RegexMatches) | (_, Operator::NotRegexMatches) => {
+                gettext("regular expression")
+            }
+            (AttributeType::Number, Operator::Between) => gettext("range, like 1:10"),
+            (AttributeType::Number, _) => gettext("number"),
+            (AttributeType::YesNo, _) => gettext("yes or no"),
+            (AttributeType::Date, _) => gettext("date, like Mon, 02 Jan 2006 15:04:05 +0000"),
+            (AttributeType::Words, Operator::Contains)
+            | (AttributeType::Words, Operator::NotContains) => gettext("word"),
+            _ => gettext("text"),
+        };
+        fmt!("%s %s (%s): ", attribute.name, op.to_string(), expected)
+    }
+
+    /// Checks the value typed by the user, and adds the condition if it's fine. Otherwise, returns
+    /// an internationalized description of the problem, and stays in the `Value` stage.
+    pub fn set_value(&mut self, input: &str) -> Result<(), String> {
+        let (attribute, op) = match (self.stage, self.attribute, &self.operator) {
+            (Stage::Value, Some(attribute), Some(op)) => (attribute, op.clone()),
+            _ => return Ok(()),
+        };
+        let value = attribute.kind.parse_value(&op, input)?;
+        let condition = Expression::Comparison {
+            attribute: attribute.name.to_string(),
+            op,
+            value,
+        };
+        self.conditions.push((self.connective, condition));
+        self.attribute = None;
+        self.operator = None;
+        self.stage = Stage::Next;
+        Ok(())
+    }
+
+    /// Goes back to picking an operator, e.g. because the user cancelled typing the value.
+    pub fn cancel_value(&mut self) {
+        if self.stage == Stage::Value {
+            self.operator = None;
+            self.stage = Stage::Operator;
+        }
+    }
+
+    /// The expression made of the finished conditions, or `None` if there are none yet.
+    pub fn expression(&self) -> Option<Expression> {
+        let mut conditions = self.conditions.iter().cloned();
+        let (_, first) = conditions.next()?;
+        Some(
+            conditions.fold(first, |result, (connective, condition)| match connective {
+                Connective::And => Expression::And(Box::new(result), Box::new(condition)),
+                Connective::Or => Expression::Or(Box::new(result), Box::new(condition)),
+            }),
+        )
+    }
+
+    /// The expression as it would be written by hand, followed by the parts of the condition
+    /// that's being put together.
+    pub fn preview(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(expression) = self.expression() {
+            parts.push(expression.to_string());
+        }
+        let building = matches!(
+            self.stage,
+            Stage::Attribute | Stage::Operator | Stage::Value
+        );
+        if building && !self.conditions.is_empty() {
+            parts.push(self.connective.keyword().to_string());
+        }
+        if let Some(attribute) = self.attribute {
+            parts.push(attribute.name.to_string());
+        }
+        if let Some(op) = &self.operator {
+            parts.push(op.to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+/// `name`, followed by `description` starting at `DESCRIPTION_COLUMN`.
+fn in_columns(name: &str, description: &str) -> String {
+    let mut line = name.to_string();
+    let width = utils::strwidth(&line);
+    let padding = if width < DESCRIPTION_COLUMN {
+        DESCRIPTION_COLUMN - width
+    } else {
+        1
+    };
+    line.push_str(&" ".repeat(padding));
+    line.push_str(description);
+    line
+}
+
+/// Internationalized description of an operator, as in the documentation.
+fn describe_operator(op: &Operator) -> String {
+    match op {
+        Operator::Equals => gettext("is equal to"),
+        Operator::NotEquals => gettext("is not equal to"),
+        Operator::RegexMatches => gettext("matches a regular expression, ignoring case"),
+        Operator::NotRegexMatches => gettext("doesn't match a regular expression"),
+        Operator::LessThan => gettext("is less than"),
+        Operator::GreaterThan => gettext("is greater than"),
+        Operator::LessThanOrEquals => gettext("is less than or equal to"),
+        Operator::GreaterThanOrEquals => gettext("is greater than or equal to"),
+        Operator::Between => gettext("is within a range"),
+        Operator::Contains => gettext("contains a word"),
+        Operator::NotContains => gettext("doesn't contain a word"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filterparser;
+
+    /// Index of the attribute called `name` in the attribute list.
+    fn attribute_index(builder: &FilterBuilder, name: &str) -> usize {
+        builder
+            .items()
+            .iter()
+            .position(|item| item.split(' ').next() == Some(name))
+            .unwrap()
+    }
+
+    /// Index of `op` in the operator list.
+    fn operator_index(builder: &FilterBuilder, op: &str) -> usize {
+        builder
+            .items()
+            .iter()
+            .position(|item| item.split(' ').next() == Some(op))
+            .unwrap()
+    }
+
+    fn add_condition(builder: &mut FilterBuilder, attribute: &str, op: &str, value: &str) {
+        builder.select(attribute_index(builder, attribute));
+        builder.select(operator_index(builder, op));
+        builder.set_value(value).unwrap();
+    }
+
+    #[test]
+    fn t_operators_offered_depend_on_the_attribute_type() {
+        let operators = |attribute: &str| {
+            let mut builder = FilterBuilder::new("article");
+            builder.select(attribute_index(&builder, attribute));
+            assert_eq!(builder.stage(), Stage::Operator);
+            builder
+                .items()
+                .iter()
+                .map(|item| item.split(' ').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(operators("title"), vec!["=~", "!~", "=", "!="]);
+        assert_eq!(
+            operators("age"),
+            vec!["=", "!=", "<", ">", "<=", ">=", "between"]
+        );
+        assert_eq!(operators("unread"), vec!["=", "!="]);
+        assert_eq!(operators("tags"), vec!["#", "!#", "=~", "!~"]);
+        assert_eq!(operators("date"), vec!["=~", "!~", "=", "!="]);
+    }
+
+    #[test]
+    fn t_feed_context_only_offers_feed_attributes() {
+        let builder = FilterBuilder::new("feed");
+        let items = builder.items();
+        assert_eq!(items[0], "feedtitle       title of the feed");
+        assert!(!items.iter().any(|item| item.starts_with("title ")));
+    }
+
+    #[test]
+    fn t_invalid_values_are_reported_and_can_be_retyped() {
+        let mut builder = FilterBuilder::new("article");
+        builder.select(attribute_index(&builder, "age"));
+        builder.select(operator_index(&builder, "between"));
+        assert_eq!(builder.prompt(), "age between (range, like 1:10): ");
+
+        assert_eq!(
+            builder.set_value("ten"),
+            Err("`ten' is not a range of numbers, like 1:10.".to_string())
+        );
+        assert_eq!(builder.stage(), Stage::Value);
+        assert_eq!(builder.expression(), None);
+
+        assert_eq!(builder.set_value("0:10"), Ok(()));
+        assert_eq!(builder.stage(), Stage::Next);
+        assert_eq!(builder.preview(), "age between 0:10");
+    }
+
+    #[test]
+    fn t_cancelling_the_value_goes_back_to_the_operators() {
+        let mut builder = FilterBuilder::new("article");
+        builder.select(attribute_index(&builder, "title"));
+        builder.select(operator_index(&builder, "=~"));
+        assert_eq!(builder.preview(), "title =~");
+
+        builder.cancel_value();
+        assert_eq!(builder.stage(), Stage::Operator);
+        assert_eq!(builder.preview(), "title");
+        assert_eq!(builder.prompt(), "");
+    }
+
+    #[test]
+    fn t_preview_grows_with_every_step() {
+        let mut builder = FilterBuilder::new("article");
+        assert_eq!(builder.preview(), "");
+        assert_eq!(builder.title(), "Choose an attribute");
+
+        add_condition(&mut builder, "title", "=~", "rust");
+        builder.select(NEXT_OR);
+        assert_eq!(builder.preview(), r#"title =~ "rust" or"#);
+        builder.select(attribute_index(&builder, "tags"));
+        assert_eq!(
+            builder.title(),
+            r#"Choose an operator: title =~ "rust" or tags"#
+        );
+    }
+
+    #[test]
+    fn t_rendered_expression_is_what_the_ast_printer_writes() {
+        let mut builder = FilterBuilder::new("article");
+        add_condition(&mut builder, "title", "=~", "rust");
+        builder.select(NEXT_OR);
+        add_condition(&mut builder, "tags", "#", "programming");
+        builder.select(NEXT_AND);
+        add_condition(&mut builder, "unread", "=", "yes");
+
+        let expression = builder.expression().unwrap();
+        assert_eq!(builder.preview(), expression.to_string());
+        assert_eq!(
+            builder.preview(),
+            r#"(title =~ "rust" or tags # "programming") and unread = "yes""#
+        );
+    }
+
+    #[test]
+    fn t_rendered_expression_parses_back_into_the_same_tree() {
+        let mut builder = FilterBuilder::new("article");
+        add_condition(&mut builder, "age", "between", "-1:7");
+        builder.select(NEXT_AND);
+        add_condition(&mut builder, "author", "!=", "");
+        builder.select(NEXT_AND);
+        add_condition(
+            &mut builder,
+            "feeddate",
+            "=",
+            "Tue, 1 Jul 2003 10:52:37 +0200",
+        );
+        builder.select(NEXT_OR);
+        add_condition(&mut builder, "flags", "=~", "s");
+
+        let expression = builder.expression().unwrap();
+        assert_eq!(filterparser::parse(&builder.preview()), Ok(expression));
+    }
+
+    #[test]
+    fn t_last_condition_can_be_removed_and_the_filter_applied() {
+        let mut builder = FilterBuilder::new("article");
+        add_condition(&mut builder, "unread", "=", "yes");
+        builder.select(NEXT_AND);
+        add_condition(&mut builder, "age", "<", "7");
+
+        builder.select(NEXT_REMOVE_LAST);
+        assert_eq!(builder.stage(), Stage::Next);
+        assert_eq!(builder.preview(), r#"unread = "yes""#);
+
+        builder.select(NEXT_APPLY);
+        assert_eq!(builder.stage(), Stage::Done);
+        assert!(builder.items().is_empty());
+
+        builder.select(NEXT_REMOVE_LAST);
+        assert_eq!(
+            builder.expression().map(|e| e.to_string()),
+            Some(r#"unread = "yes""#.to_string())
+        );
+    }
+
+    #[test]
+    fn t_removing_the_only_condition_starts_over() {
+        let mut builder = FilterBuilder::new("article");
+        add_condition(&mut builder, "unread", "=", "no");
+        builder.select(NEXT_REMOVE_LAST);
+        assert_eq!(builder.stage(), Stage::Attribute);
+        assert_eq!(builder.expression(), None);
+        assert_eq!(builder.preview(), "");
+    }
+}
@@ -1,21 +1,34 @@
 //! Parses filter expressions.
 
+use crate::filterattributes::{self, AttributeType};
+use chrono::Duration;
 use gettextrs::gettext;
 use lazy_static::lazy_static;
 use nom::{
     branch::alt,
     bytes::complete::{escaped, is_not, tag, take, take_while, take_while1},
     character::{is_alphanumeric, is_digit},
-    combinator::{complete, map, opt, peek, recognize, value},
-    error::{context, ParseError, VerboseError, VerboseErrorKind},
+    combinator::{complete, map, map_res, opt, peek, recognize, value},
+    error::{context, ErrorKind, ParseError, VerboseError, VerboseErrorKind},
     sequence::{delimited, separated_pair, terminated, tuple},
     IResult, Offset,
 };
 use std::collections::BTreeMap;
-use strprintf::fmt;
+use std::fmt;
+use strprintf::{fmt, PRIu64};
+
+/// Units that relative dates can be written in, along with their length in minutes, from the
+/// shortest to the longest. The singular forms (without the trailing "s") are accepted, too.
+const TIME_UNITS: [(&str, i64); 4] = [
+    ("minutes", 1),
+    ("hours", 60),
+    ("days", 24 * 60),
+    ("weeks", 7 * 24 * 60),
+];
 
 /// Operators that can be used in comparisons.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Operator {
     Equals,
     NotEquals,
@@ -32,16 +45,31 @@ pub enum Operator {
 
 /// Values that can be used on the right-hand side of comparisons.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Value(pub String);
+#[non_exhaustive]
+pub enum Value {
+    /// A string or a number, exactly as it was written.
+    Literal(String),
+    /// Bounds of a range like `1:10`, in the order they were written. `between` includes both
+    /// bounds, whichever of them is lower.
+    Range(i64, i64),
+    /// A moment relative to the time of matching, like `"-7 days"`, or `now` for a zero offset.
+    /// Only compared against dates.
+    RelativeDate(Duration),
+}
 
 /// Parsed filter expression.
 ///
-/// This is a tree, where nodes are logical operators (`and`, `or`), and leaves are simple
+/// This is a tree, where nodes are logical operators (`and`, `or`, `!`), and leaves are simple
 /// comparisons (`title = "hello"`, `age > 14` etc.)
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Expression {
     And(Box<Expression>, Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
+    /// `!` or `not` in front of a comparison or a parenthesized expression. Negation only flips
+    /// the result: a comparison against an attribute that the item doesn't have is an error
+    /// whether it's negated or not.
+    Not(Box<Expression>),
     Comparison {
         attribute: String,
         op: Operator,
@@ -49,6 +77,121 @@ pub enum Expression {
     },
 }
 
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            Operator::Equals => "=",
+            Operator::NotEquals => "!=",
+            Operator::RegexMatches => "=~",
+            Operator::NotRegexMatches => "!~",
+            Operator::LessThan => "<",
+            Operator::GreaterThan => ">",
+            Operator::LessThanOrEquals => "<=",
+            Operator::GreaterThanOrEquals => ">=",
+            Operator::Between => "between",
+            Operator::Contains => "#",
+            Operator::NotContains => "!#",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Numbers, ranges and `now` are written as is, everything else is double-quoted. Relative dates
+/// use the largest unit that expresses them exactly.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let literal = match self {
+            Value::Literal(literal) => literal,
+            Value::Range(start, end) => return write!(f, "{}:{}", start, end),
+            Value::RelativeDate(offset) => {
+                let minutes = offset.num_minutes();
+                if minutes == 0 {
+                    return write!(f, "now");
+                }
+                let (unit, length) = TIME_UNITS
+                    .iter()
+                    .rev()
+                    .find(|(_, length)| minutes % length == 0)
+                    .unwrap_or(&TIME_UNITS[0]);
+                let count = minutes / length;
+                let unit = if count.abs() == 1 {
+                    unit.trim_end_matches('s')
+                } else {
+                    *unit
+                };
+                return write!(f, "\"{} {}\"", count, unit);
+            }
+        };
+        let is_number = |s: &str| {
+            s.bytes().any(|b| b.is_ascii_digit())
+                && s.bytes()
+                    .enumerate()
+                    .all(|(i, b)| b.is_ascii_digit() || (i == 0 && b == b'-'))
+        };
+        let fields = literal.split(':').collect::<Vec<_>>();
+        if fields.len() <= 2 && fields.iter().all(|field| is_number(field)) {
+            write!(f, "{}", literal)
+        } else {
+            write!(f, "\"{}\"", literal)
+        }
+    }
+}
+
+impl Expression {
+    /// Builds the comparison `attribute op value`, reading `value` like `parse` reads the
+    /// right-hand side: a literal like "-7 days" compared against a date attribute turns into a
+    /// `Value::RelativeDate`. Where `parse` would fail, the literal stays a literal: when its unit
+    /// isn't one of minutes, hours, days or weeks, or when `op` doesn't order dates (like `=~`).
+    pub fn comparison(attribute: &str, op: Operator, value: Value) -> Expression {
+        let value = match value {
+            Value::Literal(literal) if orders_dates(&op) => {
+                comparison_value(attribute, literal).unwrap_or_else(|(literal, _)| literal)
+            }
+            value => value,
+        };
+        Expression::Comparison {
+            attribute: attribute.to_string(),
+            op,
+            value,
+        }
+    }
+}
+
+/// Writes the expression so that `parse` reads it back unchanged. Sub-expressions are
+/// parenthesized unless they continue a chain of the same logical operator, or are comparisons
+/// (negated or not).
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (left, connective, right) = match self {
+            Expression::Comparison {
+                attribute,
+                op,
+                value,
+            } => return write!(f, "{} {} {}", attribute, op, value),
+            Expression::Not(inner) => {
+                return match **inner {
+                    Expression::Comparison { .. } | Expression::Not(_) => write!(f, "!{}", inner),
+                    _ => write!(f, "!({})", inner),
+                };
+            }
+            Expression::And(left, right) => (left, "and", right),
+            Expression::Or(left, right) => (left, "or", right),
+        };
+        match **left {
+            Expression::Comparison { .. } | Expression::Not(_) => write!(f, "{}", left)?,
+            _ => write!(f, "({})", left)?,
+        }
+        write!(f, " {} ", connective)?;
+        match (self, &**right) {
+            (_, Expression::Comparison { .. })
+            | (_, Expression::Not(_))
+            | (Expression::And(..), Expression::And(..))
+            | (Expression::Or(..), Expression::Or(..)) => write!(f, "{}", right),
+            _ => write!(f, "({})", right),
+        }
+    }
+}
+
 /// Errors that may come up during parsing.
 #[derive(PartialEq, Debug)]
 enum Error<'a> {
@@ -59,6 +202,10 @@ enum Error<'a> {
     /// Parsing error at given position. "Explanation string" is one of `EXPECTED_*` constants.
     AtPos(usize, &'static str),
 
+    /// A relative date uses a unit that isn't one of `TIME_UNITS`. The unit starts at given
+    /// position.
+    UnknownTimeUnit(usize),
+
     /// Parse error that has no explanations attached to it.
     Internal,
 }
@@ -69,6 +216,8 @@ enum Error<'a> {
 static EXPECTED_ATTRIBUTE_NAME: &str = "EXPECTED_ATTRIBUTE_NAME";
 static EXPECTED_OPERATORS: &str = "EXPECTED_OPERATORS";
 static EXPECTED_VALUE: &str = "EXPECTED_VALUE";
+static EXPECTED_TIME_UNIT: &str = "EXPECTED_TIME_UNIT";
+static EXPECTED_DATE_OPERATORS: &str = "EXPECTED_DATE_OPERATORS";
 
 fn expected_to_i18n_msg(expected_id: &'static str) -> &'static str {
     lazy_static! {
@@ -83,6 +232,14 @@ fn expected_to_i18n_msg(expected_id: &'static str) -> &'static str {
                 EXPECTED_VALUE,
                 gettext("one of: quoted string, range, number"),
             );
+            result.insert(
+                EXPECTED_TIME_UNIT,
+                gettext("one of: minutes, hours, days, weeks"),
+            );
+            result.insert(
+                EXPECTED_DATE_OPERATORS,
+                gettext("one of: =, ==, !=, <=, >=, <, > before a relative date"),
+            );
             result
         };
     }
@@ -126,7 +283,7 @@ fn quoted_string<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str,
         Ok((leftovers, String::from(chr)))
     };
 
-    map(alt((nonempty_string, empty_string)), Value)(input)
+    map(alt((nonempty_string, empty_string)), Value::Literal)(input)
 }
 
 fn number<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
@@ -134,8 +291,10 @@ fn number<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a st
 }
 
 fn range<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Value, E> {
-    separated_pair(number, tag(":"), number)(input)
-        .map(|(leftovers, (a, b))| (leftovers, Value(format!("{}:{}", a, b))))
+    let bound = |input| map_res(number, |n: &str| n.parse::<i64>())(input);
+    map(separated_pair(bound, tag(":"), bound), |(start, end)| {
+        Value::Range(start, end)
+    })(input)
 }
 
 /// Skips zero or more space characters.
@@ -154,6 +313,85 @@ fn space1<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a st
     take_while1(|c| c == ' ')(input)
 }
 
+/// Turns a relative date like "-7 days" into an offset from now. Returns `Ok(None)` if `input`
+/// doesn't look like a relative date at all, and `Err` with the unit if the unit is unknown.
+pub(crate) fn relative_date(input: &str) -> Result<Option<Duration>, &str> {
+    let digits_start = if input.starts_with('-') || input.starts_with('+') {
+        1
+    } else {
+        0
+    };
+    let digits_end = match input[digits_start..].find(|c: char| !c.is_ascii_digit()) {
+        Some(end) if end > 0 => end + digits_start,
+        _ => return Ok(None),
+    };
+    let unit = input[digits_end..].trim_start_matches(' ');
+    if unit.is_empty() || !unit.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Ok(None);
+    }
+    // Counts that don't fit stay literals rather than overflowing the offset
+    let count = match input[digits_start..digits_end].parse::<u32>() {
+        Ok(count) => i64::from(count),
+        Err(_) => return Ok(None),
+    };
+    let count = if input.starts_with('-') {
+        -count
+    } else {
+        count
+    };
+    match time_unit_length(unit) {
+        Some(length) => Ok(Some(Duration::minutes(count * length))),
+        None => Err(unit),
+    }
+}
+
+/// Length of the time unit in minutes, if it's one of `TIME_UNITS`.
+fn time_unit_length(unit: &str) -> Option<i64> {
+    TIME_UNITS
+        .iter()
+        .find(|(name, _)| unit == *name || unit == name.trim_end_matches('s'))
+        .map(|(_, length)| *length)
+}
+
+/// Whether `attribute` holds a date, so that a quoted "-7 days" compared against it is a relative
+/// date.
+fn is_date_attribute(attribute: &str) -> bool {
+    filterattributes::find(attribute)
+        .map(|attribute| attribute.kind == AttributeType::Date)
+        .unwrap_or(false)
+}
+
+/// Whether `op` can compare a date against a relative date. The others (`=~`, `#`, `between` etc.)
+/// only make sense for text and numbers.
+fn orders_dates(op: &Operator) -> bool {
+    match op {
+        Operator::Equals
+        | Operator::NotEquals
+        | Operator::LessThan
+        | Operator::GreaterThan
+        | Operator::LessThanOrEquals
+        | Operator::GreaterThanOrEquals => true,
+        _ => false,
+    }
+}
+
+/// The value of a comparison of `attribute` against `literal`: a relative date if that's what the
+/// literal is, and the literal itself otherwise. Fails with the literal and the byte offset of the
+/// unit in it if the unit is unknown.
+fn comparison_value(attribute: &str, literal: String) -> Result<Value, (Value, usize)> {
+    if !is_date_attribute(attribute) {
+        return Ok(Value::Literal(literal));
+    }
+    match relative_date(&literal) {
+        Ok(Some(offset)) => Ok(Value::RelativeDate(offset)),
+        Ok(None) => Ok(Value::Literal(literal)),
+        Err(unit) => {
+            let unit_offset = literal.as_str().offset(unit);
+            Err((Value::Literal(literal), unit_offset))
+        }
+    }
+}
+
 fn comparison<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expression, E> {
     let attribute_name = context(
         EXPECTED_ATTRIBUTE_NAME,
@@ -162,14 +400,47 @@ fn comparison<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Ex
 
     let (input, attr) = attribute_name(input)?;
     let attribute = attr.to_string();
-    let (input, _) = space0(input)?;
-    let (input, op) = operators(input)?;
+    let (op_input, _) = space0(input)?;
+    let (input, op) = operators(op_input)?;
     let (input, _) = space0(input)?;
     let (leftovers, value) = context(
         EXPECTED_VALUE,
-        alt((quoted_string, range, map(number, |n| Value(n.to_string())))),
+        alt((
+            quoted_string,
+            range,
+            map(number, |n| Value::Literal(n.to_string())),
+            value(Value::RelativeDate(Duration::zero()), tag("now")),
+        )),
     )(input)?;
 
+    let value = match value {
+        Value::Literal(literal) => match comparison_value(&attribute, literal) {
+            Ok(value) => value,
+            // Text like "10 Jun" is a fine regex for a date, and only looks like a relative date
+            Err((literal, _)) if !orders_dates(&op) => literal,
+            Err((_, unit_offset)) => {
+                // Skip the opening quote and everything up to the unit
+                let unit_input = &input[1 + unit_offset..];
+                return Err(nom::Err::Failure(E::add_context(
+                    unit_input,
+                    EXPECTED_TIME_UNIT,
+                    E::from_error_kind(unit_input, ErrorKind::Verify),
+                )));
+            }
+        },
+        value => value,
+    };
+
+    if let Value::RelativeDate(_) = value {
+        if !orders_dates(&op) {
+            return Err(nom::Err::Failure(E::add_context(
+                op_input,
+                EXPECTED_DATE_OPERATORS,
+                E::from_error_kind(op_input, ErrorKind::Verify),
+            )));
+        }
+    }
+
     Ok((
         leftovers,
         Expression::Comparison {
@@ -183,7 +454,7 @@ fn comparison<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Ex
 fn parens<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expression, E> {
     let (input, _) = tag("(")(input)?;
     let (input, _) = space0(input)?;
-    let (input, result) = alt((expression, parens, comparison))(input)?;
+    let (input, result) = alt((expression, term))(input)?;
     let (input, _) = space0(input)?;
     let (leftovers, _) = tag(")")(input)?;
 
@@ -208,6 +479,23 @@ fn space_after_logop<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a
     peek(parser)(input)
 }
 
+/// Parses `!` or `not` followed by a comparison, a parenthesized expression, or another negation.
+///
+/// Like `and` and `or`, the word form needs a space after it unless it's followed by a paren, so
+/// that attributes whose names start with "not" are still parsed as such.
+fn negation<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expression, E> {
+    let (input, _) = alt((tag("!"), terminated(tag("not"), space_after_logop)))(input)?;
+    let (input, _) = space0(input)?;
+    let (leftovers, inner) = term(input)?;
+
+    Ok((leftovers, Expression::Not(Box::new(inner))))
+}
+
+/// Parses anything that binds tighter than `and` and `or`.
+fn term<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expression, E> {
+    alt((negation, parens, comparison))(input)
+}
+
 fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expression, E> {
     // `Expression`s enum variants can't be used as return values without filling in their
     // arguments, so we have to create another enum, which variants we can return and `match` on.
@@ -220,14 +508,14 @@ fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Ex
         Or,
     };
 
-    let (input, left) = alt((parens, comparison))(input)?;
+    let (input, left) = term(input)?;
     let (input, _) = space0(input)?;
     let (input, op) = terminated(
         alt((value(Op::And, tag("and")), value(Op::Or, tag("or")))),
         space_after_logop,
     )(input)?;
     let (input, _) = space0(input)?;
-    let (leftovers, right) = alt((expression, parens, comparison))(input)?;
+    let (leftovers, right) = alt((expression, term))(input)?;
 
     let op = match op {
         Op::And => Expression::And(Box::new(left), Box::new(right)),
@@ -238,7 +526,7 @@ fn expression<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Ex
 }
 
 fn parser<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, Expression, E> {
-    let parsers = alt((expression, parens, comparison));
+    let parsers = alt((expression, term));
     // Ignore leading and trailing whitespace
     let parsers = delimited(space0, parsers, space0);
     // Try to parse input. If parser says it needs more data, make that an error, since `input` is
@@ -263,6 +551,9 @@ fn internal_parse(expr: &str) -> Result<Expression, Error> {
                 for (chunk, err) in e.errors {
                     let pos = expr.offset(chunk);
                     match err {
+                        VerboseErrorKind::Context(expected) if expected == EXPECTED_TIME_UNIT => {
+                            return Error::UnknownTimeUnit(pos)
+                        }
                         VerboseErrorKind::Context(expected) => return Error::AtPos(pos, expected),
                         _ => continue,
                     }
@@ -281,29 +572,59 @@ fn internal_parse(expr: &str) -> Result<Expression, Error> {
     }
 }
 
+/// Turns the `%{}` placeholder of a message into a specifier that `fmt!` formats a `u64` with.
+fn with_u64_specifier(message: &str) -> String {
+    message.replace("%{}", &format!("%{}", PRIu64))
+}
+
 /// Parse a string `expr` as a filter expression.
 ///
 /// If parsing fails, returns an internationalized error message.
 pub fn parse(expr: &str) -> Result<Expression, String> {
+    parse_with_position(expr).map_err(|(_position, message)| message)
+}
+
+/// Like `parse`, but the error also carries the zero-based offset at which parsing stopped, if
+/// there is one.
+pub(crate) fn parse_with_position(expr: &str) -> Result<Expression, (Option<usize>, String)> {
     match internal_parse(expr) {
         Ok(expression) => Ok(expression),
         Err(error) => {
             let err = match error {
-                Error::TrailingCharacters(pos, tail) => fmt!(
-                    // The "%{}" thing is a number, a zero-based offset into a string.
-                    &gettext("Parse error: trailing characters after position %{}: %s"),
-                    PRIu64,
-                    pos as u64,
-                    tail
+                Error::TrailingCharacters(pos, tail) => (
+                    Some(pos),
+                    fmt!(
+                        // The "%{}" thing is a number, a zero-based offset into a string.
+                        &with_u64_specifier(&gettext(
+                            "Parse error: trailing characters after position %{}: %s"
+                        )),
+                        pos as u64,
+                        tail
+                    ),
+                ),
+                Error::AtPos(pos, expected) => (
+                    Some(pos),
+                    fmt!(
+                        // The "%{}" thing is a number, a zero-based offset into a string.
+                        &with_u64_specifier(&gettext("Parse error at position %{}: expected %s")),
+                        pos as u64,
+                        expected_to_i18n_msg(expected)
+                    ),
                 ),
-                Error::AtPos(pos, expected) => fmt!(
-                    // The "%{}" thing is a number, a zero-based offset into a string.
-                    &gettext("Parse error at position %{}: expected %s"),
-                    PRIu64,
-                    pos as u64,
-                    expected_to_i18n_msg(expected)
+                Error::UnknownTimeUnit(pos) => (
+                    Some(pos),
+                    fmt!(
+                        // The "%{}" thing is a number, a zero-based offset into a string. The
+                        // second %s is a list of units, like "one of: minutes, hours".
+                        &with_u64_specifier(&gettext(
+                            "Parse error at position %{}: unknown time unit `%s', expected %s"
+                        )),
+                        pos as u64,
+                        expr[pos..].split('"').next().unwrap_or_default(),
+                        expected_to_i18n_msg(EXPECTED_TIME_UNIT)
+                    ),
                 ),
-                Error::Internal => fmt!(&gettext("Internal parse error")),
+                Error::Internal => (None, fmt!(&gettext("Internal parse error"))),
             };
             Err(err)
         }
@@ -364,6 +685,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn t_parse_errors_mention_the_position() {
+        assert_eq!(
+            parse("title =~"),
+            Err(
+                "Parse error at position 8: expected one of: quoted string, range, number"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse_with_position("a = \"b\" c"),
+            Err((
+                Some(8),
+                "Parse error: trailing characters after position 8: c".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn t_no_error_on_valid_queries() {
         assert!(internal_parse("a = \"b\"").is_ok());
@@ -381,7 +720,7 @@ mod tests {
         let expected = Ok(Expression::Comparison {
             attribute: "a".to_string(),
             op: Operator::Equals,
-            value: Value("abc".to_string()),
+            value: Value::Literal("abc".to_string()),
         });
 
         assert_eq!(internal_parse("a = \"abc\""), expected);
@@ -404,7 +743,7 @@ mod tests {
             Ok(Expression::Comparison {
                 attribute: "attribute".to_string(),
                 op: Operator::Equals,
-                value: Value("hello\0world".to_string()),
+                value: Value::Literal("hello\0world".to_string()),
             })
         );
     }
@@ -414,7 +753,7 @@ mod tests {
         let expected = Ok(Expression::Comparison {
             attribute: "title".to_string(),
             op: Operator::Equals,
-            value: Value(String::new()),
+            value: Value::Literal(String::new()),
         });
 
         assert_eq!(internal_parse("title==\"\""), expected);
@@ -430,12 +769,12 @@ mod tests {
             Box::new(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::Equals,
-                value: Value("42".to_string()),
+                value: Value::Literal("42".to_string()),
             }),
             Box::new(Comparison {
                 attribute: "y".to_string(),
                 op: Operator::Equals,
-                value: Value("0".to_string()),
+                value: Value::Literal("0".to_string()),
             }),
         );
 
@@ -454,12 +793,12 @@ mod tests {
             Box::new(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::Equals,
-                value: Value("42".to_string()),
+                value: Value::Literal("42".to_string()),
             }),
             Box::new(Comparison {
                 attribute: "y".to_string(),
                 op: Operator::Equals,
-                value: Value("0".to_string()),
+                value: Value::Literal("0".to_string()),
             }),
         );
 
@@ -468,12 +807,108 @@ mod tests {
         assert_eq!(internal_parse("a=42or y=0"), Ok(expected_tree));
     }
 
+    #[test]
+    fn t_parses_negations() {
+        let comparison = |attribute: &str, op, value: &str| {
+            Box::new(Comparison {
+                attribute: attribute.to_string(),
+                op,
+                value: Value::Literal(value.to_string()),
+            })
+        };
+
+        let group = Not(Box::new(Or(
+            comparison("tags", Operator::Contains, "podcast"),
+            comparison("title", Operator::RegexMatches, "sponsored"),
+        )));
+        assert_eq!(
+            internal_parse(r#"!(tags # "podcast" or title =~ "sponsored")"#),
+            Ok(group.clone())
+        );
+        assert_eq!(
+            internal_parse(r#"not (tags # "podcast" or title =~ "sponsored")"#),
+            Ok(group.clone())
+        );
+        assert_eq!(
+            internal_parse(r#"not(tags # "podcast" or title =~ "sponsored")"#),
+            Ok(group)
+        );
+
+        let single = Not(comparison("unread", Operator::Equals, "yes"));
+        assert_eq!(internal_parse(r#"!unread = "yes""#), Ok(single.clone()));
+        assert_eq!(internal_parse(r#"! unread = "yes""#), Ok(single.clone()));
+        assert_eq!(internal_parse(r#"not unread = "yes""#), Ok(single));
+
+        assert_eq!(
+            internal_parse(r#"!!(x = "y")"#),
+            Ok(Not(Box::new(Not(comparison("x", Operator::Equals, "y")))))
+        );
+        assert_eq!(
+            internal_parse(r#"not !x = "y""#),
+            Ok(Not(Box::new(Not(comparison("x", Operator::Equals, "y")))))
+        );
+    }
+
+    #[test]
+    fn t_negation_binds_tighter_than_and_and_or() {
+        let comparison = |attribute: &str| {
+            Box::new(Comparison {
+                attribute: attribute.to_string(),
+                op: Operator::Equals,
+                value: Value::Literal("1".to_string()),
+            })
+        };
+
+        assert_eq!(
+            internal_parse("!a = 1 and b = 1"),
+            Ok(And(Box::new(Not(comparison("a"))), comparison("b")))
+        );
+        assert_eq!(
+            internal_parse("a = 1 or not b = 1 and c = 1"),
+            Ok(Or(
+                comparison("a"),
+                Box::new(And(Box::new(Not(comparison("b"))), comparison("c")))
+            ))
+        );
+        assert_eq!(
+            internal_parse("!(a = 1 and b = 1) or c = 1"),
+            Ok(Or(
+                Box::new(Not(Box::new(And(comparison("a"), comparison("b"))))),
+                comparison("c")
+            ))
+        );
+    }
+
+    #[test]
+    fn t_attributes_starting_with_not_are_not_negations() {
+        assert_eq!(
+            internal_parse("notes = 1"),
+            Ok(Comparison {
+                attribute: "notes".to_string(),
+                op: Operator::Equals,
+                value: Value::Literal("1".to_string()),
+            })
+        );
+        assert_eq!(
+            internal_parse("not = 1"),
+            Ok(Comparison {
+                attribute: "not".to_string(),
+                op: Operator::Equals,
+                value: Value::Literal("1".to_string()),
+            })
+        );
+        assert!(internal_parse("!").is_err());
+        assert!(internal_parse("not").is_err());
+        assert!(internal_parse("!()").is_err());
+        assert!(internal_parse(r#"a = 1 !b = 1"#).is_err());
+    }
+
     #[test]
     fn t_space_chars_in_filter_expr_dont_affect_parsing() {
         let expected = Comparison {
             attribute: "array".to_string(),
             op: Operator::Contains,
-            value: Value("bar".to_string()),
+            value: Value::Literal("bar".to_string()),
         };
 
         assert_eq!(internal_parse("array # \"bar\""), Ok(expected.clone()));
@@ -520,12 +955,12 @@ mod tests {
                 Box::new(Comparison {
                     attribute: "x".to_string(),
                     op: Operator::Equals,
-                    value: Value("42".to_string())
+                    value: Value::Literal("42".to_string())
                 }),
                 Box::new(Comparison {
                     attribute: "y".to_string(),
                     op: Operator::Equals,
-                    value: Value("0".to_string())
+                    value: Value::Literal("0".to_string())
                 })
             ))
         );
@@ -536,12 +971,12 @@ mod tests {
                 Box::new(Comparison {
                     attribute: "x".to_string(),
                     op: Operator::Equals,
-                    value: Value("42".to_string())
+                    value: Value::Literal("42".to_string())
                 }),
                 Box::new(Comparison {
                     attribute: "y".to_string(),
                     op: Operator::Equals,
-                    value: Value("0".to_string())
+                    value: Value::Literal("0".to_string())
                 })
             ))
         );
@@ -552,12 +987,12 @@ mod tests {
                 Box::new(Comparison {
                     attribute: "x".to_string(),
                     op: Operator::Equals,
-                    value: Value("42".to_string())
+                    value: Value::Literal("42".to_string())
                 }),
                 Box::new(Comparison {
                     attribute: "y".to_string(),
                     op: Operator::Equals,
-                    value: Value("42".to_string())
+                    value: Value::Literal("42".to_string())
                 })
             ))
         );
@@ -572,7 +1007,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::Equals,
-                value: Value("b".to_string())
+                value: Value::Literal("b".to_string())
             })
         );
 
@@ -581,7 +1016,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::NotEquals,
-                value: Value("b".to_string())
+                value: Value::Literal("b".to_string())
             })
         );
 
@@ -590,7 +1025,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::RegexMatches,
-                value: Value("b".to_string())
+                value: Value::Literal("b".to_string())
             })
         );
 
@@ -599,7 +1034,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::NotRegexMatches,
-                value: Value("b".to_string())
+                value: Value::Literal("b".to_string())
             })
         );
 
@@ -610,7 +1045,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::LessThan,
-                value: Value("b".to_string())
+                value: Value::Literal("b".to_string())
             })
         );
 
@@ -619,7 +1054,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::LessThanOrEquals,
-                value: Value("b".to_string())
+                value: Value::Literal("b".to_string())
             })
         );
 
@@ -628,7 +1063,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::GreaterThan,
-                value: Value("abc".to_string())
+                value: Value::Literal("abc".to_string())
             })
         );
 
@@ -637,7 +1072,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::Equals,
-                value: Value("abc".to_string())
+                value: Value::Literal("abc".to_string())
             })
         );
 
@@ -646,7 +1081,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "a".to_string(),
                 op: Operator::GreaterThanOrEquals,
-                value: Value("3".to_string())
+                value: Value::Literal("3".to_string())
             })
         );
 
@@ -655,7 +1090,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "some_value".to_string(),
                 op: Operator::Between,
-                value: Value("0:-1".to_string())
+                value: Value::Range(0, -1)
             })
         );
 
@@ -664,7 +1099,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "other_string".to_string(),
                 op: Operator::Between,
-                value: Value("impossible".to_string())
+                value: Value::Literal("impossible".to_string())
             })
         );
 
@@ -673,7 +1108,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "array".to_string(),
                 op: Operator::Contains,
-                value: Value("name".to_string())
+                value: Value::Literal("name".to_string())
             })
         );
 
@@ -682,7 +1117,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "answers".to_string(),
                 op: Operator::NotContains,
-                value: Value("42".to_string())
+                value: Value::Literal("42".to_string())
             })
         );
 
@@ -691,7 +1126,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "author".to_string(),
                 op: Operator::RegexMatches,
-                value: Value("\\s*Doe$".to_string())
+                value: Value::Literal("\\s*Doe$".to_string())
             })
         );
     }
@@ -704,18 +1139,18 @@ mod tests {
                 Box::new(Comparison {
                     attribute: "a".to_string(),
                     op: Operator::Equals,
-                    value: Value("b".to_string())
+                    value: Value::Literal("b".to_string())
                 }),
                 Box::new(Or(
                     Box::new(Comparison {
                         attribute: "b".to_string(),
                         op: Operator::Equals,
-                        value: Value("c".to_string())
+                        value: Value::Literal("c".to_string())
                     }),
                     Box::new(Comparison {
                         attribute: "c".to_string(),
                         op: Operator::Equals,
-                        value: Value("d".to_string())
+                        value: Value::Literal("d".to_string())
                     }),
                 ))
             )
@@ -727,18 +1162,18 @@ mod tests {
                 Box::new(Comparison {
                     attribute: "a".to_string(),
                     op: Operator::Equals,
-                    value: Value("b".to_string())
+                    value: Value::Literal("b".to_string())
                 }),
                 Box::new(And(
                     Box::new(Comparison {
                         attribute: "b".to_string(),
                         op: Operator::Equals,
-                        value: Value("c".to_string())
+                        value: Value::Literal("c".to_string())
                     }),
                     Box::new(Comparison {
                         attribute: "c".to_string(),
                         op: Operator::Equals,
-                        value: Value("d".to_string())
+                        value: Value::Literal("d".to_string())
                     }),
                 ))
             )
@@ -751,18 +1186,18 @@ mod tests {
                     Box::new(Comparison {
                         attribute: "a".to_string(),
                         op: Operator::Equals,
-                        value: Value("b".to_string())
+                        value: Value::Literal("b".to_string())
                     }),
                     Box::new(Comparison {
                         attribute: "b".to_string(),
                         op: Operator::Equals,
-                        value: Value("c".to_string())
+                        value: Value::Literal("c".to_string())
                     }),
                 )),
                 Box::new(Comparison {
                     attribute: "c".to_string(),
                     op: Operator::Equals,
-                    value: Value("d".to_string())
+                    value: Value::Literal("d".to_string())
                 })
             )
         );
@@ -779,7 +1214,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "value".to_string(),
                 op: Operator::Between,
-                value: Value("-100:-1".to_string())
+                value: Value::Range(-100, -1)
             })
         );
 
@@ -788,7 +1223,7 @@ mod tests {
             Ok(Comparison {
                 attribute: "value".to_string(),
                 op: Operator::Between,
-                value: Value("-100:100500".to_string())
+                value: Value::Range(-100, 100500)
             })
         );
 
@@ -797,11 +1232,217 @@ mod tests {
             Ok(Comparison {
                 attribute: "value".to_string(),
                 op: Operator::Between,
-                value: Value("123:-10".to_string())
+                value: Value::Range(123, -10)
             })
         );
     }
 
+    #[test]
+    fn t_malformed_ranges_are_errors() {
+        for expression in &[
+            "value between 1:",
+            "value between :10",
+            "value between a:b",
+            "value between 1:b",
+            "value between 1.5:2",
+            "value between 1:99999999999999999999",
+        ] {
+            assert!(internal_parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn t_parses_relative_dates_for_date_attributes() {
+        let relative = |input: &str| match parse(input) {
+            Ok(Comparison {
+                value: Value::RelativeDate(offset),
+                ..
+            }) => Some(offset),
+            _ => None,
+        };
+        assert_eq!(relative(r#"date > "-7 days""#), Some(Duration::days(-7)));
+        assert_eq!(
+            relative(r#"date < "-12 hours""#),
+            Some(Duration::hours(-12))
+        );
+        assert_eq!(
+            relative(r#"feeddate >= "-1 week""#),
+            Some(Duration::weeks(-1))
+        );
+        assert_eq!(
+            relative(r#"date <= "+30minutes""#),
+            Some(Duration::minutes(30))
+        );
+        assert_eq!(relative(r#"date != "2 days""#), Some(Duration::days(2)));
+        assert_eq!(relative("date < now"), Some(Duration::zero()));
+    }
+
+    #[test]
+    fn t_relative_dates_are_literals_elsewhere() {
+        let literal = |input: &str, attribute: &str, op: Operator, expected: &str| {
+            assert_eq!(
+                parse(input),
+                Ok(Comparison {
+                    attribute: attribute.to_string(),
+                    op,
+                    value: Value::Literal(expected.to_string()),
+                })
+            );
+        };
+        // Not a date attribute
+        literal(r#"title = "-7 days""#, "title", Operator::Equals, "-7 days");
+        // Not relative dates
+        literal(
+            r#"date = "Tue, 01 Jul 2003 10:52:37 +0200""#,
+            "date",
+            Operator::Equals,
+            "Tue, 01 Jul 2003 10:52:37 +0200",
+        );
+        literal(r#"date = "7""#, "date", Operator::Equals, "7");
+        literal(r#"date = "days""#, "date", Operator::Equals, "days");
+        literal(
+            r#"date = "-7 days ago""#,
+            "date",
+            Operator::Equals,
+            "-7 days ago",
+        );
+    }
+
+    #[test]
+    fn t_relative_dates_are_only_compared_by_operators_that_order_dates() {
+        for (input, position) in &[
+            (r#"date =~ "-7 days""#, 5),
+            (r#"date !~ now"#, 5),
+            (r#"feeddate # "-1 week""#, 9),
+            (r#"date !# now"#, 5),
+            (r#"unread = "yes" and date between "2 hours""#, 24),
+        ] {
+            assert_eq!(
+                internal_parse(input),
+                Err(Error::AtPos(*position, EXPECTED_DATE_OPERATORS)),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            parse(r#"date =~ "-7 days""#),
+            Err(
+                "Parse error at position 5: expected one of: =, ==, !=, <=, >=, <, > \
+                 before a relative date"
+                    .to_string()
+            )
+        );
+        // Not relative dates, so any operator goes
+        assert!(parse(r#"date =~ "Jul 2003""#).is_ok());
+        assert_eq!(
+            parse(r#"date !~ "10 Jun""#),
+            Ok(Comparison {
+                attribute: "date".to_string(),
+                op: Operator::NotRegexMatches,
+                value: Value::Literal("10 Jun".to_string()),
+            })
+        );
+        assert!(parse(r#"title =~ "-7 days""#).is_ok());
+    }
+
+    #[test]
+    fn t_unknown_time_units_are_reported() {
+        assert_eq!(
+            internal_parse(r#"date > "-7 fortnights""#),
+            Err(Error::UnknownTimeUnit(11))
+        );
+        assert_eq!(
+            internal_parse(r#"unread = "yes" and feeddate<"3 years""#),
+            Err(Error::UnknownTimeUnit(31))
+        );
+        assert_eq!(
+            parse(r#"date > "-7 fortnights""#),
+            Err(
+                "Parse error at position 11: unknown time unit `fortnights', \
+                 expected one of: minutes, hours, days, weeks"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn t_comparison_builder_reads_values_like_parse() {
+        let literal = |value: &str| Value::Literal(value.to_string());
+        for (attribute, op, value) in &[
+            ("date", Operator::GreaterThan, "-7 days"),
+            ("feeddate", Operator::LessThanOrEquals, "+1 hour"),
+            ("title", Operator::Equals, "-7 days"),
+            ("date", Operator::Equals, "Tue, 01 Jul 2003 10:52:37 +0200"),
+            ("age", Operator::LessThan, "7"),
+        ] {
+            let built = Expression::comparison(attribute, op.clone(), literal(value));
+            assert_eq!(parse(&built.to_string()), Ok(built));
+        }
+
+        assert_eq!(
+            Expression::comparison("date", Operator::GreaterThan, literal("-7 days")),
+            Comparison {
+                attribute: "date".to_string(),
+                op: Operator::GreaterThan,
+                value: Value::RelativeDate(Duration::days(-7)),
+            }
+        );
+        assert_eq!(
+            Expression::comparison("age", Operator::Between, Value::Range(1, 10)),
+            parse("age between 1:10").unwrap()
+        );
+        // `parse` would fail on these
+        assert_eq!(
+            Expression::comparison("date", Operator::RegexMatches, literal("-7 days")),
+            Comparison {
+                attribute: "date".to_string(),
+                op: Operator::RegexMatches,
+                value: literal("-7 days"),
+            }
+        );
+        assert_eq!(
+            Expression::comparison("date", Operator::GreaterThan, literal("-7 fortnights")),
+            Comparison {
+                attribute: "date".to_string(),
+                op: Operator::GreaterThan,
+                value: literal("-7 fortnights"),
+            }
+        );
+    }
+
+    #[test]
+    fn t_expressions_are_printed_the_way_they_are_written() {
+        let check = |input: &str| {
+            let expression = parse(input).unwrap();
+            assert_eq!(expression.to_string(), input);
+        };
+        check(r#"title =~ "rust""#);
+        check("age between -1:10");
+        check("unread_count > 0");
+        check(r#"title = "" and tags # "news" and flags != "s""#);
+        check(r#"a = 1 or (b = 2 and c = 3)"#);
+        check(r#"(a = 1 or b = 2) and c = 3"#);
+        check(r#"(a = 1 and b = 2) and c = 3"#);
+        check(r#"date > "-7 days" and feeddate <= now"#);
+        check(r#"date < "-1 hour" or date = "90 minutes""#);
+        check(r#"!unread = "yes" and !(tags # "podcast" or title =~ "sponsored")"#);
+        check(r#"!!x = "y""#);
+    }
+
+    #[test]
+    fn t_printed_expressions_parse_back_into_the_same_tree() {
+        for input in &[
+            r#"a == "x" and b = 1 or c = 2"#,
+            r#"( a = "b") and ( b = "c" ) or ( ( c != "d" ) and ( c !~ "asdf" )) or c != "xx""#,
+            r#"x = "1:2:3" or y = "-" or z = 1:2"#,
+            r#"date > "-14 days" and date < "-48 hours" and feeddate != "+60 minutes""#,
+            r#"not (a = 1 and not(b = 2 or !!c = 3)) or !d = 4"#,
+        ] {
+            let expression = parse(input).unwrap();
+            assert_eq!(parse(&expression.to_string()), Ok(expression));
+        }
+    }
+
     proptest::proptest! {
         #[test]
         fn does_not_crash_on_any_input(ref input in "\\PC*") {
@@ -816,7 +1457,7 @@ mod tests {
                 Ok(Comparison {
                     attribute: "a".to_string(),
                     op: Operator::NotEquals,
-                    value: Value("b".to_string())
+                    value: Value::Literal("b".to_string())
                 })
             );
         }
@@ -828,7 +1469,7 @@ mod tests {
                 Ok(Comparison {
                     attribute: "a".to_string(),
                     op: Operator::NotEquals,
-                    value: Value("b".to_string())
+                    value: Value::Literal("b".to_string())
                 })
             );
         }
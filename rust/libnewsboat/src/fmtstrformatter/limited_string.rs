@@ -5,12 +5,32 @@
 //! For example, "abc" has length 3, but "ＡＢＣ" has length 6, even though they both contain 3 graphemes.
 
 use crate::utils;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct LimitedString {
     /// Maximum length of this string, counted by "displayed width".
     max_length: Option<usize>,
     /// The contents of the limited string.
     content: String,
+    /// The "displayed width" of `content`, kept up to date so that pushes don't measure the whole
+    /// string again.
+    width: usize,
+}
+
+/// The "displayed width" of `content`, which is `width` wide, once `tail` is added to it.
+///
+/// The start of `tail` may become part of the last grapheme cluster of `content` (e.g. a combining
+/// mark or a zero width joiner), so that cluster is measured again together with `tail`.
+fn width_with(content: &str, width: usize, tail: &str) -> usize {
+    match content.grapheme_indices(true).next_back() {
+        Some((start, last)) => {
+            let mut joined = String::with_capacity(content.len() - start + tail.len());
+            joined.push_str(last);
+            joined.push_str(tail);
+            width - utils::strwidth(last) + utils::strwidth(&joined)
+        }
+        None => utils::strwidth(tail),
+    }
 }
 
 impl LimitedString {
@@ -19,23 +39,24 @@ impl LimitedString {
         LimitedString {
             max_length,
             content: String::new(),
+            width: 0,
         }
     }
 
     /// Returns the "displayed width" of the string
     pub fn length(&self) -> usize {
-        utils::strwidth(&self.content)
+        self.width
     }
 
     /// Adds given character to the end of the string, or does nothing if the string length reached
     /// the limit.
     pub fn push(&mut self, c: char) {
-        if let Some(limit) = self.max_length {
-            if self.length() + utils::strwidth(&c.to_string()) <= limit {
-                self.content.push(c);
-            }
-        } else {
-            self.content.push(c);
+        let mut buffer = [0; 4];
+        let c = c.encode_utf8(&mut buffer);
+        let width = width_with(&self.content, self.width, c);
+        if self.max_length.map_or(true, |limit| width <= limit) {
+            self.content.push_str(c);
+            self.width = width;
         }
     }
 
@@ -44,9 +65,11 @@ impl LimitedString {
     pub fn push_str(&mut self, s: &str) {
         if let Some(limit) = self.max_length {
             let remaining_length = limit - self.length();
-            self.content
-                .push_str(&utils::substr_with_width(s, remaining_length));
+            let s = utils::substr_with_width(s, remaining_length);
+            self.width = width_with(&self.content, self.width, &s);
+            self.content.push_str(&s);
         } else {
+            self.width = width_with(&self.content, self.width, s);
             self.content.push_str(s);
         }
     }
@@ -125,6 +148,29 @@ mod tests {
         assert_eq!(s.length(), limit);
     }
 
+    #[test]
+    fn t_push_measures_characters_that_join_the_last_grapheme() {
+        let limit = 2usize;
+        let mut s = LimitedString::new(Some(limit));
+
+        s.push('a');
+        s.push('e');
+        assert_eq!(s.length(), limit);
+        // A combining acute accent doesn't take a column of its own
+        s.push('\u{301}');
+        assert_eq!(s.length(), limit);
+        s.push('b');
+        assert_eq!(s.into_string(), "ae\u{301}");
+
+        // Woman, zero width joiner, girl: a single two-column emoji
+        let mut s = LimitedString::new(Some(limit));
+        s.push('\u{1F469}');
+        s.push('\u{200D}');
+        s.push('\u{1F467}');
+        assert_eq!(s.length(), limit);
+        assert_eq!(s.into_string(), "\u{1F469}\u{200D}\u{1F467}");
+    }
+
     proptest::proptest! {
         #[test]
         fn length_never_exceeds_the_limit_one_string(
@@ -179,5 +225,20 @@ mod tests {
                 .map(|chr| { s.push(chr); assert!(s.length() <= limit); })
                 .collect::<Vec<_>>();
         }
+
+        #[test]
+        fn length_is_the_width_of_the_content(
+            limit in 1usize..1024,
+            ref input1 in "\\PC*",
+            ref input2 in "\\PC*")
+        {
+            let mut s = LimitedString::new(Some(limit));
+            s.push_str(&input1);
+            assert_eq!(s.length(), utils::strwidth(&s.content));
+            let _ = input2
+                .chars()
+                .map(|chr| { s.push(chr); assert_eq!(s.length(), utils::strwidth(&s.content)); })
+                .collect::<Vec<_>>();
+        }
     }
 }
@@ -1,7 +1,7 @@
 //! Produces strings of values in a specified format, strftime(3)-like.
 
 mod limited_string;
-mod parser;
+pub(crate) mod parser;
 
 use crate::utils;
 use limited_string::LimitedString;
@@ -56,6 +56,8 @@ use std::collections::BTreeMap;
 pub struct FmtStrFormatter {
     /// Stores keys and their values.
     fmts: BTreeMap<char, String>,
+    /// Ends values that were cut to the width of their specifier. Empty if they should just be cut.
+    truncation_marker: String,
 }
 
 impl FmtStrFormatter {
@@ -63,6 +65,7 @@ impl FmtStrFormatter {
     pub fn new() -> FmtStrFormatter {
         FmtStrFormatter {
             fmts: BTreeMap::new(),
+            truncation_marker: String::new(),
         }
     }
 
@@ -71,12 +74,80 @@ impl FmtStrFormatter {
         self.fmts.insert(key, value);
     }
 
+    /// Makes values that don't fit into the width of their specifier (like `%-60t`) end with
+    /// `marker`, e.g. "…", so that it's visible that they were cut. The marker is left out if it
+    /// isn't narrower than the specifier. An empty marker turns this off, which is the default.
+    pub fn set_truncation_marker(&mut self, marker: &str) {
+        self.truncation_marker = marker.to_string();
+    }
+
     /// Takes a format string and replaces format specifiers with their values.
     pub fn do_format(&self, format: &str, width: u32) -> String {
         let ast = parse(format);
         self.formatting_helper(&ast, width)
     }
 
+    /// Like `do_format`, but the value of `key` continues on up to `max_rows - 1` further rows
+    /// rather than being cut off at `width`.
+    ///
+    /// The first row is formatted as usual. The continuation rows only contain the rest of the
+    /// value, indented to the column at which it starts on the first row. If even `max_rows`
+    /// rows aren't enough, the last one is cut off at `width`.
+    ///
+    /// Only a `%` specifier without padding outside of conditionals wraps, and only if there's
+    /// no spacing (`%>`) before it; otherwise, this returns the single row that `do_format`
+    /// would.
+    pub fn do_format_wrapped(
+        &self,
+        format: &str,
+        width: u32,
+        key: char,
+        max_rows: usize,
+    ) -> Vec<String> {
+        let ast = parse(format);
+        let position = ast
+            .iter()
+            .position(|specifier| *specifier == Specifier::Format(key, Padding::None));
+        let (position, value) = match (position, self.fmts.get(&key)) {
+            (Some(position), Some(value)) if width > 0 && max_rows > 1 => (position, value),
+            _ => return vec![self.formatting_helper(&ast, width)],
+        };
+
+        let (before, after) = (&ast[..position], &ast[position + 1..]);
+        if before.iter().any(|specifier| {
+            matches!(
+                specifier,
+                Specifier::Spacing(_) | Specifier::CenterSpacing(_)
+            )
+        }) {
+            return vec![self.formatting_helper(&ast, width)];
+        }
+
+        let indent = utils::strwidth(&self.formatting_helper(before, width));
+        let suffix_width = utils::strwidth(&self.formatting_helper(after, 0));
+        let width = width as usize;
+        if indent >= width {
+            return vec![self.formatting_helper(&ast, width as u32)];
+        }
+
+        let first_width = width.saturating_sub(indent + suffix_width);
+        let rows = wrap_rows(value, first_width, width - indent, max_rows);
+
+        let mut first_row = FmtStrFormatter {
+            fmts: self.fmts.clone(),
+            truncation_marker: self.truncation_marker.clone(),
+        };
+        first_row.register_fmt(key, rows.first().cloned().unwrap_or_default());
+        let mut result = vec![first_row.formatting_helper(&ast, width as u32)];
+        let indentation = " ".repeat(indent);
+        result.extend(
+            rows.iter()
+                .skip(1)
+                .map(|row| format!("{}{}", indentation, row)),
+        );
+        result
+    }
+
     fn format_spacing(&self, c: char, rest: &[Specifier], width: u32, result: &mut LimitedString) {
         let rest = self.formatting_helper(rest, 0);
         if width == 0 {
@@ -98,6 +169,45 @@ impl FmtStrFormatter {
         result.push_str(&rest);
     }
 
+    fn format_center_spacing(
+        &self,
+        c: char,
+        rest: &[Specifier],
+        width: u32,
+        result: &mut LimitedString,
+    ) {
+        let rest = self.formatting_helper(rest, 0);
+        if width == 0 {
+            result.push(c);
+            result.push_str(&rest);
+            return;
+        }
+
+        let content_width = utils::strwidth(&rest) + result.length();
+        let padding_width = (width as usize).saturating_sub(content_width);
+        let left = padding_width / 2;
+        let right = padding_width - left;
+        let padding_value = format!("{}", c);
+        result.push_str(&padding_value.repeat(left));
+        result.push_str(&rest);
+        result.push_str(&padding_value.repeat(right));
+    }
+
+    /// Cuts `value` to `width` columns, ending it with the truncation marker if anything was cut.
+    fn fit(&self, value: &str, width: usize) -> String {
+        let marker_width = utils::strwidth(&self.truncation_marker);
+        if self.truncation_marker.is_empty()
+            || marker_width >= width
+            || utils::strwidth(value) <= width
+        {
+            return utils::substr_with_width(value, width);
+        }
+
+        let mut text = utils::substr_with_width(value, width - marker_width);
+        text.push_str(&self.truncation_marker);
+        text
+    }
+
     fn format_format(&self, c: char, padding: &Padding, width: u32, result: &mut LimitedString) {
         let empty_string = String::new();
         let value = self.fmts.get(&c).unwrap_or_else(|| &empty_string);
@@ -105,7 +215,7 @@ impl FmtStrFormatter {
             Padding::None => result.push_str(value),
 
             Padding::Left(total_width) => {
-                let text = &utils::substr_with_width(value, total_width);
+                let text = &self.fit(value, total_width);
                 let padding_width = total_width - utils::strwidth(text);
                 let padding = String::from(" ").repeat(padding_width);
                 result.push_str(&padding);
@@ -113,7 +223,7 @@ impl FmtStrFormatter {
             }
 
             Padding::Right(total_width) => {
-                let text = &utils::substr_with_width(value, total_width);
+                let text = &self.fit(value, total_width);
                 let padding_width = total_width - utils::strwidth(text);
                 let padding = String::from(" ").repeat(padding_width);
                 result.push_str(text);
@@ -126,7 +236,7 @@ impl FmtStrFormatter {
                 } else {
                     total_width
                 };
-                let text = &utils::substr_with_width(value, w);
+                let text = &self.fit(value, w);
                 let padding_width = w - utils::strwidth(text);
                 if padding_width > 0 {
                     let left: usize = padding_width / 2;
@@ -179,6 +289,13 @@ impl FmtStrFormatter {
                     break;
                 }
 
+                Specifier::CenterSpacing(c) => {
+                    let rest = &format_ast[i + 1..];
+                    self.format_center_spacing(c, rest, width, &mut result);
+                    // Same as with format_spacing above
+                    break;
+                }
+
                 Specifier::Format(c, ref padding) => {
                     self.format_format(c, &padding, width, &mut result);
                 }
@@ -207,6 +324,37 @@ impl FmtStrFormatter {
     }
 }
 
+/// Splits `text` into at most `max_rows` rows. The first one is at most `first_width` columns
+/// wide, the others at most `width`. Rows are broken at spaces where possible, and whatever
+/// doesn't fit into the last row is cut off.
+fn wrap_rows(text: &str, first_width: usize, width: usize, max_rows: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() && rows.len() < max_rows {
+        let row_width = if rows.is_empty() { first_width } else { width };
+        let is_last_row = rows.len() + 1 == max_rows;
+        if utils::strwidth(rest) <= row_width || is_last_row {
+            rows.push(utils::substr_with_width(rest, row_width));
+            break;
+        }
+
+        let fitting = utils::substr_with_width(rest, row_width);
+        let remaining = &rest[fitting.len()..];
+        let row = if remaining.starts_with(' ') {
+            fitting.as_str()
+        } else {
+            match fitting.rfind(' ') {
+                Some(space) if space > 0 => &fitting[..space],
+                // A single word that's wider than a row
+                _ => fitting.as_str(),
+            }
+        };
+        rows.push(row.trim_end().to_string());
+        rest = rest[row.len()..].trim_start();
+    }
+    rows
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -553,6 +701,99 @@ mod tests {
         assert_eq!(fmt.do_format(format, 30), "_short_aaaaaaaa_short_b_short_");
     }
 
+    #[test]
+    fn t_center_spacer_centers_consecutive_text_using_specified_char() {
+        let mut fmt = FmtStrFormatter::new();
+
+        fmt.register_fmt('t', "title".to_string());
+        fmt.register_fmt('w', "日本".to_string());
+
+        // Without a width, the spacer is a single char, just like `%>`
+        assert_eq!(fmt.do_format("%=-%t", 0), "-title");
+
+        assert_eq!(fmt.do_format("%=-%t", 11), "---title---");
+        // The extra column goes to the right
+        assert_eq!(fmt.do_format("%=-%t", 12), "---title----");
+        // Text before the spacer takes up room
+        assert_eq!(fmt.do_format("ab%= %t", 11), "ab  title  ");
+        // Wide characters are measured by their width on screen
+        assert_eq!(fmt.do_format("%=*%w", 8), "**日本**");
+        // Text that doesn't fit is cut off at the width, like with `%>`
+        assert_eq!(fmt.do_format("%=-%t", 3), "tit");
+    }
+
+    #[test]
+    fn t_center_spacer_only_first_one_works() {
+        let mut fmt = FmtStrFormatter::new();
+
+        fmt.register_fmt('t', "title".to_string());
+
+        assert_eq!(fmt.do_format("%=-%t%=+", 11), "--title+---");
+        assert_eq!(fmt.do_format("%> %t%=-", 8), "  title-");
+    }
+
+    #[test]
+    fn t_values_are_cut_without_a_marker_by_default() {
+        let mut fmt = FmtStrFormatter::new();
+
+        fmt.register_fmt('t', "abcdef".to_string());
+
+        assert_eq!(fmt.do_format("%-5t", 0), "abcde");
+        assert_eq!(fmt.do_format("%5t", 0), "abcde");
+        assert_eq!(fmt.do_format("%=5t", 0), "abcde");
+    }
+
+    #[test]
+    fn t_truncation_marker_ends_values_that_were_cut() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.set_truncation_marker("…");
+
+        // Exactly as wide as the field: nothing is cut
+        fmt.register_fmt('t', "abcde".to_string());
+        assert_eq!(fmt.do_format("%-5t", 0), "abcde");
+        assert_eq!(fmt.do_format("%5t", 0), "abcde");
+        assert_eq!(fmt.do_format("%=5t", 0), "abcde");
+
+        // One column over
+        fmt.register_fmt('t', "abcdef".to_string());
+        assert_eq!(fmt.do_format("%-5t", 0), "abcd…");
+        assert_eq!(fmt.do_format("%5t", 0), "abcd…");
+        assert_eq!(fmt.do_format("%=5t", 0), "abcd…");
+
+        // Values without a width are only cut by the width of the line
+        assert_eq!(fmt.do_format("%t", 3), "abc");
+    }
+
+    #[test]
+    fn t_truncation_marker_is_measured_in_columns() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.set_truncation_marker("…");
+
+        fmt.register_fmt('t', "日本語のニュース".to_string());
+        assert_eq!(fmt.do_format("%-7t", 0), "日本語…");
+        // A wide character doesn't fit into the last column before the marker, so it's padded
+        assert_eq!(fmt.do_format("%-6t", 0), "日本… ");
+        assert_eq!(fmt.do_format("%6t", 0), " 日本…");
+        assert_eq!(fmt.do_format("%-16t", 0), "日本語のニュース");
+
+        fmt.set_truncation_marker("...");
+        fmt.register_fmt('t', "abcdef".to_string());
+        assert_eq!(fmt.do_format("%-5t", 0), "ab...");
+    }
+
+    #[test]
+    fn t_truncation_marker_is_left_out_if_it_does_not_fit() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.set_truncation_marker("[...]");
+
+        fmt.register_fmt('t', "abcdefg".to_string());
+        assert_eq!(fmt.do_format("%-6t", 0), "a[...]");
+        // As wide as the field: no room for the value, so it's cut as if there were no marker
+        assert_eq!(fmt.do_format("%-5t", 0), "abcde");
+        // Wider than the field
+        assert_eq!(fmt.do_format("%-3t", 0), "abc");
+    }
+
     #[test]
     fn t_conditional_is_replaced_by_appropriate_branch_standard_case() {
         let mut fmt = FmtStrFormatter::new();
@@ -617,6 +858,74 @@ mod tests {
         assert_eq!(fmt.do_format("%x? %y", 0), "What's the ultimate answer? 42");
     }
 
+    #[test]
+    fn t_conditionals_can_be_nested_in_either_branch() {
+        let mut fmt = FmtStrFormatter::new();
+        let format = "%?F?[%F]&%?a?by %a&-??";
+
+        fmt.register_fmt('F', "!U".to_string());
+        fmt.register_fmt('a', "Jane".to_string());
+        assert_eq!(fmt.do_format(format, 0), "[!U]");
+
+        fmt.register_fmt('F', String::new());
+        assert_eq!(fmt.do_format(format, 0), "by Jane");
+
+        fmt.register_fmt('a', String::new());
+        assert_eq!(fmt.do_format(format, 0), "-");
+
+        let format = "%?x?%?y?both&only x?&none?";
+        fmt.register_fmt('x', "x".to_string());
+        fmt.register_fmt('y', "y".to_string());
+        assert_eq!(fmt.do_format(format, 0), "both");
+        fmt.register_fmt('y', String::new());
+        assert_eq!(fmt.do_format(format, 0), "only x");
+        fmt.register_fmt('x', String::new());
+        assert_eq!(fmt.do_format(format, 0), "none");
+    }
+
+    #[test]
+    fn t_conditionals_can_be_nested_three_levels_deep() {
+        let mut fmt = FmtStrFormatter::new();
+        let format = "<%?x?X%?y?Y%?z?Z??&%?y?&%?z?z&none???>";
+
+        let cases = [
+            ("x", "y", "z", "<XYZ>"),
+            ("x", "y", "", "<XY>"),
+            ("x", "", "z", "<X>"),
+            ("", "y", "z", "<>"),
+            ("", "", "z", "<z>"),
+            ("", "", "", "<none>"),
+        ];
+        for &(x, y, z, expected) in &cases {
+            fmt.register_fmt('x', x.to_string());
+            fmt.register_fmt('y', y.to_string());
+            fmt.register_fmt('z', z.to_string());
+            assert_eq!(fmt.do_format(format, 0), expected);
+        }
+    }
+
+    #[test]
+    fn t_nested_conditionals_respect_the_width() {
+        let mut fmt = FmtStrFormatter::new();
+
+        fmt.register_fmt('a', "Jane".to_string());
+
+        assert_eq!(fmt.do_format("%?F?[%F]&%?a?by %a&-??", 5), "by Ja");
+    }
+
+    #[test]
+    fn t_escaped_question_marks_and_ampersands_are_literal_inside_conditionals() {
+        let mut fmt = FmtStrFormatter::new();
+
+        fmt.register_fmt('x', "x".to_string());
+
+        assert_eq!(fmt.do_format(r"%?x?why\? R\&D&none?", 0), "why? R&D");
+        assert_eq!(fmt.do_format(r"%?m?none&why\? R\&D?", 0), "why? R&D");
+        assert_eq!(fmt.do_format(r"%?x?C:\dir\&?", 0), r"C:\dir&");
+        // Outside conditionals, question marks and ampersands need no escaping
+        assert_eq!(fmt.do_format(r"why\? R\&D", 0), r"why\? R\&D");
+    }
+
     proptest::proptest! {
         #[test]
         fn does_not_crash_when_formatting_with_no_formats_registered(ref input in "\\PC*") {
@@ -645,4 +954,98 @@ mod tests {
             assert!(utils::strwidth(&result) <= length as usize);
         }
     }
+
+    #[test]
+    fn t_do_format_wrapped_continues_the_value_on_indented_rows() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.register_fmt('i', "1".to_string());
+        fmt.register_fmt(
+            't',
+            "The quick brown fox jumps over the lazy dog".to_string(),
+        );
+
+        assert_eq!(
+            fmt.do_format_wrapped("%i. %t", 20, 't', 3),
+            vec![
+                "1. The quick brown".to_string(),
+                "   fox jumps over".to_string(),
+                "   the lazy dog".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn t_do_format_wrapped_puts_other_specifiers_on_the_first_row_only() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.register_fmt('n', "N".to_string());
+        fmt.register_fmt('t', "aaaa bbbb cccc".to_string());
+        fmt.register_fmt('a', "me".to_string());
+
+        assert_eq!(
+            fmt.do_format_wrapped("%n %t (%a)", 12, 't', 3),
+            vec!["N aaaa (me)".to_string(), "  bbbb cccc".to_string()]
+        );
+    }
+
+    #[test]
+    fn t_do_format_wrapped_returns_a_single_row_if_the_value_fits() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.register_fmt('t', "short".to_string());
+
+        assert_eq!(
+            fmt.do_format_wrapped("> %t", 20, 't', 3),
+            vec!["> short".to_string()]
+        );
+        assert_eq!(
+            fmt.do_format_wrapped("> %t", 4, 't', 1),
+            vec!["> sh".to_string()]
+        );
+    }
+
+    #[test]
+    fn t_do_format_wrapped_cuts_off_the_last_row_by_width() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.register_fmt('t', "one two three four five six".to_string());
+
+        assert_eq!(
+            fmt.do_format_wrapped("* %t", 10, 't', 2),
+            vec!["* one two".to_string(), "  three fo".to_string()]
+        );
+
+        // Wide characters that don't fit aren't split
+        fmt.register_fmt('t', "\u{3042}\u{3044}\u{3046}\u{3048}\u{304a}".to_string());
+        assert_eq!(
+            fmt.do_format_wrapped("* %t", 7, 't', 2),
+            vec![
+                "* \u{3042}\u{3044}".to_string(),
+                "  \u{3046}\u{3048}".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn t_do_format_wrapped_breaks_words_that_are_wider_than_a_row() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.register_fmt('t', "abcdefghijkl".to_string());
+
+        assert_eq!(
+            fmt.do_format_wrapped("%t", 5, 't', 3),
+            vec!["abcde".to_string(), "fghij".to_string(), "kl".to_string()]
+        );
+    }
+
+    #[test]
+    fn t_do_format_wrapped_does_not_wrap_padded_values_or_after_spacing() {
+        let mut fmt = FmtStrFormatter::new();
+        fmt.register_fmt('t', "aaaa bbbb cccc".to_string());
+
+        assert_eq!(
+            fmt.do_format_wrapped("%-6t|", 20, 't', 3),
+            vec!["aaaa b|".to_string()]
+        );
+        assert_eq!(
+            fmt.do_format_wrapped("%> %t", 8, 't', 3),
+            vec!["aaaa bbb".to_string()]
+        );
+    }
 }
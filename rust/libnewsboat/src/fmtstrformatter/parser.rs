@@ -1,12 +1,14 @@
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take, take_till1, take_while};
+use nom::combinator::verify;
 use nom::multi::many0;
 use nom::IResult;
 use std::cmp::Ordering;
 use std::str;
 
 /// Describes how formats should be padded: on the left, on the right, or not at all.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[non_exhaustive]
 pub enum Padding {
     /// Do not pad the value.
     None,
@@ -20,10 +22,14 @@ pub enum Padding {
 
 /// Describes all the different "format specifiers" we support, plus a chunk of text that would be
 /// copied to the output verbatim.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[non_exhaustive]
 pub enum Specifier<'a> {
     /// Will expand to pad everything that comes next to the right. Given char is used for padding.
     Spacing(char),
+    /// Will expand on both sides of everything that comes next, centering it. Given char is used
+    /// for padding.
+    CenterSpacing(char),
     /// A format to be replaced with a value (`%a`, `%t` etc.), padded to the given width on the
     /// left (if it's positive) or on the right (if it's negative).
     Format(char, Padding),
@@ -48,6 +54,20 @@ fn spacing(input: &str) -> IResult<&str, Specifier> {
     Ok((input, Specifier::Spacing(chr)))
 }
 
+/// `%=` followed by a letter or a width is a centered format, so only other characters can fill
+/// the space around centered text.
+fn center_spacing(input: &str) -> IResult<&str, Specifier> {
+    let (input, _) = tag("%=")(input)?;
+    let (input, c) = verify(take(1usize), |c: &str| {
+        !c.starts_with(|chr: char| chr.is_ascii_alphanumeric())
+    })(input)?;
+
+    // unwrap() won't panic because we use take!(1) in parser above
+    let chr = c.chars().next().unwrap();
+
+    Ok((input, Specifier::CenterSpacing(chr)))
+}
+
 fn center_format(input: &str) -> IResult<&str, Specifier> {
     let (input, _) = tag("%=")(input)?;
     let (input, width) = take_while(|chr: char| chr.is_ascii() && (chr.is_numeric()))(input)?;
@@ -86,11 +106,23 @@ fn text_outside_conditional(input: &str) -> IResult<&str, Specifier> {
 }
 
 fn text_inside_conditional(input: &str) -> IResult<&str, Specifier> {
-    let (input, text) = take_till1(|chr: char| chr == '%' || chr == '&' || chr == '?')(input)?;
+    let (input, text) =
+        take_till1(|chr: char| chr == '%' || chr == '&' || chr == '?' || chr == '\\')(input)?;
 
     Ok((input, Specifier::Text(text)))
 }
 
+/// `\?` and `\&` stand for a literal `?` and `&` inside a conditional, where these characters
+/// would otherwise end a branch.
+fn escaped_branch_delimiter(input: &str) -> IResult<&str, Specifier> {
+    alt((tag("\\?"), tag("\\&")))(input).map(|result| (result.0, Specifier::Text(&result.1[1..2])))
+}
+
+/// A backslash that doesn't escape anything is copied verbatim.
+fn backslash(input: &str) -> IResult<&str, Specifier> {
+    tag("\\")(input).map(|result| (result.0, Specifier::Text(result.1)))
+}
+
 fn conditional(input: &str) -> IResult<&str, Specifier> {
     // Prepared partial parsers
     let start_tag = tag("%?");
@@ -125,13 +157,19 @@ fn conditional(input: &str) -> IResult<&str, Specifier> {
     Ok((input, Specifier::Conditional(cond, then, els)))
 }
 
+/// Branches can contain other conditionals. A `%?` that doesn't start a complete conditional is
+/// parsed as a format with key `?`, just like before conditionals could be nested.
 fn conditional_branch(input: &str) -> IResult<&str, Vec<Specifier>> {
     let alternatives = (
+        conditional,
         escaped_percent_sign,
+        escaped_branch_delimiter,
         spacing,
+        center_spacing,
         center_format,
         padded_format,
         text_inside_conditional,
+        backslash,
     );
     many0(alt(alternatives))(input)
 }
@@ -141,6 +179,7 @@ fn parser(input: &str) -> IResult<&str, Vec<Specifier>> {
         conditional,
         escaped_percent_sign,
         spacing,
+        center_spacing,
         center_format,
         padded_format,
         text_outside_conditional,
@@ -159,6 +198,9 @@ fn sanitize(mut input: Vec<Specifier>) -> Vec<Specifier> {
     input
 }
 
+/// Splits a format string into specifiers and the text between them. Never fails: formats whose
+/// key isn't an ASCII character are dropped, and a string that can't be parsed at all yields
+/// a single empty text.
 pub fn parse(input: &str) -> Vec<Specifier> {
     match parser(input) {
         Ok((_leftovers, ast)) => sanitize(ast),
@@ -166,6 +208,30 @@ pub fn parse(input: &str) -> Vec<Specifier> {
     }
 }
 
+/// Byte offset at which `input` stops being a well-formed format string, or `None` if all of it
+/// is. `parse` silently drops whatever follows that point, e.g. a lone `%` at the end, or turns a
+/// conditional without its closing `?` into a format with key `?`.
+pub fn invalid_position(input: &str) -> Option<usize> {
+    let mut rest = input;
+    while !rest.is_empty() {
+        let position = input.len() - rest.len();
+        let alternatives = (
+            conditional,
+            escaped_percent_sign,
+            spacing,
+            center_spacing,
+            center_format,
+            padded_format,
+            text_outside_conditional,
+        );
+        match alt(alternatives)(rest) {
+            Ok((_, Specifier::Format('?', _))) | Err(_) => return Some(position),
+            Ok((remaining, _)) => rest = remaining,
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,6 +329,23 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn t_parses_center_spacing_with_characters_that_cannot_be_formats() {
+        let input = "%=-%t%= %=20t%=T";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+
+        let expected = vec![
+            Specifier::CenterSpacing('-'),
+            Specifier::Format('t', Padding::None),
+            Specifier::CenterSpacing(' '),
+            Specifier::Format('t', Padding::Center(20)),
+            Specifier::Format('T', Padding::Center(0)),
+        ];
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn t_parses_conditionals() {
         let input = "%?x?success&failure?";
@@ -329,4 +412,140 @@ mod tests {
         let expected = vec![Specifier::Conditional('x', vec![], Some(vec![]))];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn t_parses_conditionals_nested_in_the_then_branch() {
+        let input = "%?x?%?y?both&only x?&none?";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+
+        let expected = vec![Specifier::Conditional(
+            'x',
+            vec![Specifier::Conditional(
+                'y',
+                vec![Specifier::Text("both")],
+                Some(vec![Specifier::Text("only x")]),
+            )],
+            Some(vec![Specifier::Text("none")]),
+        )];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn t_parses_conditionals_nested_in_the_else_branch_only() {
+        let input = "%?F?[%F]&%?a?by %a&-??";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+
+        let expected = vec![Specifier::Conditional(
+            'F',
+            vec![
+                Specifier::Text("["),
+                Specifier::Format('F', Padding::None),
+                Specifier::Text("]"),
+            ],
+            Some(vec![Specifier::Conditional(
+                'a',
+                vec![
+                    Specifier::Text("by "),
+                    Specifier::Format('a', Padding::None),
+                ],
+                Some(vec![Specifier::Text("-")]),
+            )]),
+        )];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn t_parses_conditionals_nested_three_levels_deep() {
+        let input = "%?x?X%?y?Y%?z?Z??&%?y?&%?z?z&none???";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+
+        let expected = vec![Specifier::Conditional(
+            'x',
+            vec![
+                Specifier::Text("X"),
+                Specifier::Conditional(
+                    'y',
+                    vec![
+                        Specifier::Text("Y"),
+                        Specifier::Conditional('z', vec![Specifier::Text("Z")], None),
+                    ],
+                    None,
+                ),
+            ],
+            Some(vec![Specifier::Conditional(
+                'y',
+                vec![],
+                Some(vec![Specifier::Conditional(
+                    'z',
+                    vec![Specifier::Text("z")],
+                    Some(vec![Specifier::Text("none")]),
+                )]),
+            )]),
+        )];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn t_parses_escaped_question_marks_and_ampersands_inside_conditionals() {
+        let input = r"%?x?why\? R\&D&\n?";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+
+        let expected = vec![Specifier::Conditional(
+            'x',
+            vec![
+                Specifier::Text("why"),
+                Specifier::Text("?"),
+                Specifier::Text(" R"),
+                Specifier::Text("&"),
+                Specifier::Text("D"),
+            ],
+            Some(vec![Specifier::Text("\\"), Specifier::Text("n")]),
+        )];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn t_backslashes_are_literal_outside_conditionals() {
+        let input = r"what\?";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+        assert_eq!(result, vec![Specifier::Text(r"what\?")]);
+    }
+
+    #[test]
+    fn t_incomplete_conditional_inside_a_branch_is_a_format() {
+        let input = "%?x?a%?y?";
+        let (leftovers, result) = parser(input).unwrap();
+
+        assert_eq!(leftovers, "");
+
+        let expected = vec![Specifier::Conditional(
+            'x',
+            vec![
+                Specifier::Text("a"),
+                Specifier::Format('?', Padding::None),
+                Specifier::Text("y"),
+            ],
+            None,
+        )];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn t_invalid_position_points_at_the_part_that_does_not_parse() {
+        assert_eq!(invalid_position(""), None);
+        assert_eq!(invalid_position("%4i %-20t %?T?|%T|&-? %%"), None);
+        assert_eq!(invalid_position("%i %"), Some(3));
+        assert_eq!(invalid_position("%t %?T?no end"), Some(3));
+        assert_eq!(invalid_position("%-"), Some(0));
+    }
 }
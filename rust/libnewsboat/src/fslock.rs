@@ -1,20 +1,34 @@
 use crate::logger::{self, Level};
 use std::fs::{self, File, OpenOptions};
-use std::io::{Error, Read, Write};
-use std::os::unix::fs::OpenOptionsExt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::process;
 
+/// How many times `try_lock` retries if the lock file is removed or replaced under its feet.
+const MAX_ATTEMPTS: usize = 5;
+
 fn remove_lock(lock_path: &Path) {
     fs::remove_file(lock_path).ok();
     log!(
+        "fslock",
         Level::Debug,
         "FsLock: removed lockfile {}",
         lock_path.display()
     );
 }
 
+/// Why `FsLock::try_lock` didn't take the lock.
+#[derive(Debug)]
+pub enum LockError {
+    /// Another process holds the lock. `pid` is its PID, if the lock file contains one and that
+    /// process is still alive.
+    AlreadyLocked { pid: Option<libc::pid_t> },
+    /// The lock file couldn't be created, opened, locked or written.
+    Io(io::Error),
+}
+
 #[derive(Default)]
 pub struct FsLock {
     lock_path: PathBuf,
@@ -30,71 +44,158 @@ impl Drop for FsLock {
 }
 
 impl FsLock {
-    pub fn try_lock(&mut self, new_lock_path: &Path, pid: &mut libc::pid_t) -> bool {
+    /// Takes the lock at `new_lock_path` and writes our PID into it, without waiting for another
+    /// process to release it. If this object already holds a lock elsewhere, that one is released.
+    ///
+    /// A lock file that exists but isn't locked was left behind by a process that crashed; it's
+    /// removed and created anew.
+    pub fn try_lock(&mut self, new_lock_path: &Path) -> Result<(), LockError> {
         if self.lock_file.is_some() && self.lock_path == new_lock_path {
-            return true;
+            return Ok(());
         }
 
-        // pid == 0 indicates that something went majorly wrong during locking
-        *pid = 0;
-
         log!(
+            "fslock",
             Level::Debug,
             "FsLock: trying to lock `{}'",
             new_lock_path.display()
         );
 
-        // first we open (and possibly create) the lock file
-        let mut options = OpenOptions::new();
-        options.create(true).read(true).write(true).mode(0o600);
-        let mut file = match options.open(&new_lock_path) {
-            Ok(file) => file,
-            Err(_) => return false,
-        };
+        for _ in 0..MAX_ATTEMPTS {
+            let (mut file, created) = match open_lock_file(new_lock_path) {
+                Ok(Some(opened)) => opened,
+                // The file was removed between our attempts to create and to open it
+                Ok(None) => continue,
+                Err(error) => {
+                    log!(
+                        "fslock",
+                        Level::Error,
+                        "FsLock: couldn't open `{}': {}",
+                        new_lock_path.display(),
+                        error
+                    );
+                    return Err(LockError::Io(error));
+                }
+            };
+
+            // returns immediately if locking is not possible
+            if unsafe { libc::lockf(file.as_raw_fd(), libc::F_TLOCK, 0) } != 0 {
+                let error = io::Error::last_os_error();
+                return match error.raw_os_error() {
+                    Some(libc::EACCES) | Some(libc::EAGAIN) => {
+                        let pid = read_pid(&mut file).filter(|&pid| is_alive(pid));
+                        log!(
+                            "fslock",
+                            Level::Debug,
+                            "FsLock: locking failed, already locked by {:?}",
+                            pid
+                        );
+                        Err(LockError::AlreadyLocked { pid })
+                    }
+                    _ => {
+                        log!(
+                            "fslock",
+                            Level::Error,
+                            "FsLock: something went wrong during locking: {}",
+                            error
+                        );
+                        Err(LockError::Io(error))
+                    }
+                };
+            }
+
+            // Whoever held the file before us could have removed it after we opened it, in which
+            // case we locked a file that nobody else will look at
+            if !is_same_file(&file, new_lock_path) {
+                continue;
+            }
+
+            if !created {
+                log!(
+                    "fslock",
+                    Level::Info,
+                    "FsLock: removing stale lock `{}' of PID {:?}",
+                    new_lock_path.display(),
+                    read_pid(&mut file)
+                );
+                // Removing the file while we hold its lock makes anyone who opened it before us
+                // retry, too
+                fs::remove_file(new_lock_path).map_err(LockError::Io)?;
+                continue;
+            }
 
-        // then we lock it (returns immediately if locking is not possible)
-        if unsafe { libc::lockf(file.as_raw_fd(), libc::F_TLOCK, 0) } == 0 {
             log!(
+                "fslock",
                 Level::Debug,
                 "FsLock: locked `{}', writing PID...",
                 new_lock_path.display()
             );
             let pid = process::id().to_string();
-            let success = file
-                .set_len(0)
-                .and_then(|_| file.write_all(&pid.as_bytes()))
-                .is_ok();
-            log!(
-                Level::Debug,
-                "FsLock: PID written successfully: {}",
-                success as usize
-            );
-            if success {
-                if self.lock_file.take().is_some() {
-                    remove_lock(&self.lock_path);
-                }
-                self.lock_file = Some(file);
-                self.lock_path = new_lock_path.to_owned();
+            if let Err(error) = file.write_all(pid.as_bytes()) {
+                log!(
+                    "fslock",
+                    Level::Error,
+                    "FsLock: couldn't write PID: {}",
+                    error
+                );
+                fs::remove_file(new_lock_path).ok();
+                return Err(LockError::Io(error));
             }
-            success
-        } else {
-            log!(
-                Level::Error,
-                "FsLock: something went wrong during locking: {}",
-                Error::last_os_error()
-            );
 
-            // locking was not successful -> read PID of locking process from the file
-            let mut buf = String::new();
-            if file.read_to_string(&mut buf).is_ok() {
-                *pid = buf.parse().unwrap_or(0);
+            if self.lock_file.take().is_some() {
+                remove_lock(&self.lock_path);
             }
-            log!(
-                Level::Debug,
-                "FsLock: locking failed, already locked by {}",
-                pid
-            );
-            false
+            self.lock_file = Some(file);
+            self.lock_path = new_lock_path.to_owned();
+            return Ok(());
         }
+
+        Err(LockError::Io(io::Error::new(
+            io::ErrorKind::Other,
+            "the lock file kept changing while we tried to lock it",
+        )))
+    }
+}
+
+/// Creates the lock file, or opens it if it already exists. Returns the file and whether it was
+/// created, or `None` if it disappeared in between.
+fn open_lock_file(path: &Path) -> io::Result<Option<(File, bool)>> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).mode(0o600);
+
+    match options.clone().create_new(true).open(path) {
+        Ok(file) => return Ok(Some((file, true))),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(error) => return Err(error),
+    }
+
+    match options.open(path) {
+        Ok(file) => Ok(Some((file, false))),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// PID that the holder of the lock wrote into the file.
+fn read_pid(file: &mut File) -> Option<libc::pid_t> {
+    let mut buf = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut buf).ok()?;
+    buf.trim().parse().ok().filter(|&pid| pid > 0)
+}
+
+fn is_alive(pid: libc::pid_t) -> bool {
+    // Signal 0 only checks if the process exists. EPERM means it does, but belongs to someone else
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Checks that `path` still refers to `file`.
+fn is_same_file(file: &File, path: &Path) -> bool {
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(opened), Ok(current)) => opened.dev() == current.dev() && opened.ino() == current.ino(),
+        _ => false,
     }
 }
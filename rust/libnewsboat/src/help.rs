@@ -0,0 +1,357 @@
+//! Contents of the help dialog.
+//!
+//! The dialog lists the operations that can be used in the dialog it was opened from, along with
+//! the keys they're bound to. Rows are grouped into sections by the category of their operation,
+//! and can be narrowed down with a filter, which is applied while the user types it.
+
+use crate::utils;
+use gettextrs::gettext;
+
+/// Context of the rows that apply in every dialog, like the generic bindings for moving around.
+pub const EVERYWHERE: &str = "everywhere";
+
+/// Column at which the operation starts.
+const OPERATION_COLUMN: usize = 16;
+
+/// Column at which the description starts.
+const DESCRIPTION_COLUMN: usize = 40;
+
+/// Section of the help dialog in which an operation is listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Navigation,
+    Feeds,
+    Articles,
+    Macros,
+    Other,
+}
+
+impl Category {
+    /// All categories, in the order in which their sections are shown.
+    pub const ALL: [Category; 5] = [
+        Category::Navigation,
+        Category::Feeds,
+        Category::Articles,
+        Category::Macros,
+        Category::Other,
+    ];
+
+    pub fn heading(self) -> String {
+        match self {
+            Category::Navigation => gettext("Navigation:"),
+            Category::Feeds => gettext("Feed management:"),
+            Category::Articles => gettext("Articles:"),
+            Category::Macros => gettext("Macros:"),
+            Category::Other => gettext("Other functions:"),
+        }
+    }
+}
+
+/// An operation, and one of the keys it's bound to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpRow {
+    /// Empty if the operation isn't bound to any key.
+    pub key: String,
+    pub operation: String,
+    pub description: String,
+    pub category: Category,
+}
+
+impl HelpRow {
+    fn matches(&self, filter: &str) -> bool {
+        utils::contains_case_folded(&self.key, filter)
+            || utils::contains_case_folded(&self.operation, filter)
+            || utils::contains_case_folded(&self.description, filter)
+    }
+
+    /// The row as a line of text, with the key, operation and description in columns.
+    fn to_line(&self) -> String {
+        let mut line = self.key.clone();
+        pad_to_column(&mut line, OPERATION_COLUMN);
+        line.push_str(&self.operation);
+        pad_to_column(&mut line, DESCRIPTION_COLUMN);
+        line.push_str(&self.description);
+        line
+    }
+}
+
+/// Appends spaces to `line` until it's `column` wide, or a single space if it's already wider.
+fn pad_to_column(line: &mut String, column: usize) {
+    let width = utils::strwidth(line);
+    let padding = if width < column { column - width } else { 1 };
+    line.push_str(&" ".repeat(padding));
+}
+
+/// Quotes `text` for STFL, and highlights the occurrences of `filter` in it.
+fn highlight(text: &str, filter: &str) -> String {
+    let mut result = String::new();
+    let mut last_end = 0;
+    for (start, end) in utils::find_case_folded(text, filter) {
+        result.push_str(&utils::quote_for_stfl(&text[last_end..start]));
+        result.push_str("<hl>");
+        result.push_str(&utils::quote_for_stfl(&text[start..end]));
+        result.push_str("</>");
+        last_end = end;
+    }
+    result.push_str(&utils::quote_for_stfl(&text[last_end..]));
+    result
+}
+
+/// Rows of the help dialog opened from a single context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Help {
+    context: String,
+    rows: Vec<HelpRow>,
+    filter: String,
+}
+
+impl Help {
+    pub fn new(context: &str) -> Help {
+        Help {
+            context: context.to_string(),
+            rows: Vec::new(),
+            filter: String::new(),
+        }
+    }
+
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// Adds a row that applies in `context`. Rows of other contexts are ignored, except for those
+    /// of `EVERYWHERE`.
+    pub fn add_row(&mut self, context: &str, row: HelpRow) {
+        if context == self.context || context == EVERYWHERE {
+            self.rows.push(row);
+        }
+    }
+
+    /// Only rows whose key, operation or description contain `filter` (ignoring case) are shown.
+    /// An empty filter shows all rows.
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Categories that have rows matching the filter, in the order of `Category::ALL`, along with
+    /// those rows. Within a section, rows keep the order in which they were added, except that
+    /// unbound operations come last.
+    pub fn sections(&self) -> Vec<(Category, Vec<&HelpRow>)> {
+        Category::ALL
+            .iter()
+            .filter_map(|&category| {
+                let (mut rows, unbound): (Vec<&HelpRow>, Vec<&HelpRow>) = self
+                    .rows
+                    .iter()
+                    .filter(|row| row.category == category && row.matches(&self.filter))
+                    .partition(|row| !row.key.is_empty());
+                rows.extend(unbound);
+                if rows.is_empty() {
+                    None
+                } else {
+                    Some((category, rows))
+                }
+            })
+            .collect()
+    }
+
+    /// Lines of the dialog, cut to `width` columns (zero means they aren't cut). They're quoted
+    /// for STFL, with the occurrences of the filter wrapped in `<hl>` tags.
+    pub fn lines(&self, width: usize) -> Vec<String> {
+        let fit = |line: String| {
+            if width == 0 {
+                line
+            } else {
+                utils::substr_with_width(&line, width)
+            }
+        };
+
+        let sections = self.sections();
+        if sections.is_empty() {
+            return vec![utils::quote_for_stfl(&fit(gettext(
+                "No operation matches the filter.",
+            )))];
+        }
+
+        let mut lines = Vec::new();
+        for (category, rows) in sections {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(utils::quote_for_stfl(&fit(category.heading())));
+            for row in rows {
+                lines.push(highlight(&fit(row.to_line()), &self.filter));
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(key: &str, operation: &str, description: &str, category: Category) -> HelpRow {
+        HelpRow {
+            key: key.to_string(),
+            operation: operation.to_string(),
+            description: description.to_string(),
+            category,
+        }
+    }
+
+    fn articlelist_help() -> Help {
+        let mut help = Help::new("articlelist");
+        help.add_row(
+            "articlelist",
+            row(
+                "r",
+                "reload",
+                "Reload currently selected feed",
+                Category::Feeds,
+            ),
+        );
+        help.add_row(
+            "articlelist",
+            row("", "save-all", "Save all articles", Category::Articles),
+        );
+        help.add_row(
+            "articlelist",
+            row("s", "save", "Save article", Category::Articles),
+        );
+        help.add_row(
+            "articlelist",
+            row(",", "macro-prefix", "Call a macro", Category::Macros),
+        );
+        help.add_row(
+            EVERYWHERE,
+            row(
+                "UP",
+                "up",
+                "Move to the previous entry",
+                Category::Navigation,
+            ),
+        );
+        help
+    }
+
+    fn operations(help: &Help) -> Vec<&str> {
+        help.sections()
+            .into_iter()
+            .flat_map(|(_, rows)| rows.into_iter().map(|row| row.operation.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn t_only_rows_of_the_context_and_those_that_apply_everywhere_are_kept() {
+        let mut help = Help::new("feedlist");
+        help.add_row(
+            "feedlist",
+            row("R", "reload-all", "Reload all feeds", Category::Feeds),
+        );
+        help.add_row(
+            "article",
+            row("#", "goto-url", "Open URL dialog", Category::Navigation),
+        );
+        help.add_row(
+            EVERYWHERE,
+            row("^L", "redraw", "Redraw screen", Category::Other),
+        );
+
+        assert_eq!(help.context(), "feedlist");
+        assert_eq!(operations(&help), vec!["reload-all", "redraw"]);
+    }
+
+    #[test]
+    fn t_filter_narrows_rows_by_key_operation_or_description_ignoring_case() {
+        let mut help = articlelist_help();
+
+        help.set_filter("SAVE");
+        assert_eq!(operations(&help), vec!["save", "save-all"]);
+
+        help.set_filter("up");
+        assert_eq!(operations(&help), vec!["up"]);
+
+        help.set_filter(",");
+        assert_eq!(operations(&help), vec!["macro-prefix"]);
+
+        help.set_filter("currently selected");
+        assert_eq!(operations(&help), vec!["reload"]);
+
+        help.set_filter("");
+        assert_eq!(operations(&help).len(), 5);
+    }
+
+    #[test]
+    fn t_sections_follow_the_order_of_categories() {
+        let help = articlelist_help();
+
+        let categories = help
+            .sections()
+            .into_iter()
+            .map(|(category, _)| category)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            categories,
+            vec![
+                Category::Navigation,
+                Category::Feeds,
+                Category::Articles,
+                Category::Macros
+            ]
+        );
+        // Unbound operations come after the bound ones
+        assert_eq!(
+            operations(&help),
+            vec!["up", "reload", "save", "save-all", "macro-prefix"]
+        );
+    }
+
+    #[test]
+    fn t_lines_have_columns_headings_and_highlighted_matches() {
+        let mut help = articlelist_help();
+        help.set_filter("save");
+
+        assert_eq!(
+            help.lines(0),
+            vec![
+                "Articles:",
+                "s               <hl>save</>                    <hl>Save</> article",
+                "                <hl>save</>-all                <hl>Save</> all articles",
+            ]
+        );
+    }
+
+    #[test]
+    fn t_lines_are_cut_to_the_width_and_quoted_for_stfl() {
+        let mut help = Help::new("article");
+        help.add_row(
+            "article",
+            row(
+                "<",
+                "prev",
+                "Go to the previous entry",
+                Category::Navigation,
+            ),
+        );
+
+        assert_eq!(
+            help.lines(45),
+            vec![
+                "Navigation:",
+                "<>               prev                    Go to"
+            ]
+        );
+    }
+
+    #[test]
+    fn t_lines_say_so_if_nothing_matches() {
+        let mut help = articlelist_help();
+        help.set_filter("no such operation");
+
+        assert_eq!(help.lines(80), vec!["No operation matches the filter."]);
+    }
+}
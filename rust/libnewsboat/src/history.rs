@@ -1,11 +1,49 @@
-use std::fs::OpenOptions;
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+//! Input history of prompts (commandline, search and such).
+//!
+//! Several instances of Newsboat can share the same history files, e.g. when running in two
+//! terminals at once. To not lose each other's lines, saving merges the lines added in this
+//! instance into whatever is in the file at that moment, rather than overwriting it. Lines saved by
+//! other instances are also picked up when the user starts browsing the history.
+//!
+//! A line that's the same as the newest one isn't added again, so repeating a search doesn't
+//! fill the history with copies of it.
+
+use crate::utils;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Modification time and size of a file, used to cheaply detect that another instance changed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    pub(crate) modified: SystemTime,
+    pub(crate) size: u64,
+}
+
+impl FileStamp {
+    pub(crate) fn of(path: &Path) -> Option<FileStamp> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(FileStamp {
+            modified: metadata.modified().ok()?,
+            size: metadata.len(),
+        })
+    }
+}
 
 #[derive(Default)]
 pub struct History {
     idx: usize,
+    /// Newest line first.
     lines: Vec<String>,
+    /// Number of lines at the start of `lines` that were added since the history was last loaded or
+    /// saved, i.e. that aren't in the file yet.
+    unsaved: usize,
+    /// File that the history was loaded from or saved to, and its stamp at that time.
+    file: Option<(PathBuf, Option<FileStamp>)>,
+    /// Maximum number of lines to keep; the oldest ones are dropped first.
+    limit: Option<usize>,
 }
 
 impl History {
@@ -13,13 +51,25 @@ impl History {
         History {
             idx: 0,
             lines: Vec::new(),
+            unsaved: 0,
+            file: None,
+            limit: None,
+        }
+    }
+    /// A history that keeps at most `limit` lines, in memory as well as in files.
+    pub fn with_limit(limit: usize) -> History {
+        History {
+            limit: Some(limit),
+            ..History::new()
         }
     }
     pub fn add_line(&mut self, line: String) {
         // When a line is added, we need to do so and reset the index so that the next
         // previous_line()/next_line() operations start from the beginning again.
-        if !line.is_empty() {
-            self.lines.insert(0, line)
+        if !line.is_empty() && self.lines.first() != Some(&line) {
+            self.lines.insert(0, line);
+            self.unsaved += 1;
+            self.apply_limit();
         }
         self.idx = 0;
     }
@@ -33,6 +83,11 @@ impl History {
         }
     }
     pub fn previous_line(&mut self) -> String {
+        if self.idx == 0 {
+            // The user starts browsing, so this is the time to pick up lines that other instances
+            // saved in the meantime. Failing to do so is not a reason to stop browsing.
+            let _ = self.reload_if_changed();
+        }
         match self.lines.len() {
             0 => String::new(),
             len if self.idx < len => {
@@ -43,33 +98,145 @@ impl History {
             _ => self.lines[self.idx - 1].clone(),
         }
     }
+    /// The first line that starts with `prefix`, going back in history from the current position
+    /// like previous_line() does. The position doesn't change.
+    pub fn search_prefix(&self, prefix: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .skip(self.idx)
+            .find(|line| line.starts_with(prefix))
+            .map(String::as_str)
+    }
+    /// Replaces the lines that came from a file with the lines of the file at `path`. Lines added
+    /// to this history but not saved yet are kept and stay the newest.
+    ///
+    /// If the file holds more lines than the history's limit, only the newest ones are kept.
     pub fn load_from_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
-        let file = OpenOptions::new().read(true).open(path)?;
-        let lines: Vec<String> = BufReader::new(file)
-            .lines()
-            .collect::<Result<Vec<_>, _>>()?;
-        lines.into_iter().for_each(|ln| self.add_line(ln));
+        let path = path.as_ref();
+        let stamp = FileStamp::of(path);
+        let file_lines = read_lines(path)?;
+        self.merge(file_lines);
+        self.file = Some((path.to_owned(), stamp));
         Ok(())
     }
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P, limit: usize) -> io::Result<()> {
+    /// Re-reads the file that the history was loaded from or saved to, if another instance changed
+    /// it since. Returns `true` if the file was re-read.
+    pub fn reload_if_changed(&mut self) -> io::Result<bool> {
+        let (path, stamp) = match self.file {
+            Some((ref path, stamp)) => (path.clone(), stamp),
+            None => return Ok(false),
+        };
+        if FileStamp::of(&path) == stamp {
+            return Ok(false);
+        }
+        self.load_from_file(path)?;
+        Ok(true)
+    }
+    /// Merges the unsaved lines into the file at `path`, keeping at most `limit` newest lines
+    /// (or fewer, if the history's own limit is lower).
+    ///
+    /// The file is locked while it's being updated, and is replaced atomically, so instances that
+    /// save at the same time don't lose each other's lines.
+    pub fn save_to_file<P: AsRef<Path>>(&mut self, path: P, limit: usize) -> io::Result<()> {
         if limit == 0 || self.lines.is_empty() {
             return Ok(());
         }
 
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(path)?;
-        let mut f = BufWriter::new(file);
+        let path = path.as_ref();
+        let _lock = lock(path)?;
+        let file_lines = match read_lines(path) {
+            Ok(lines) => lines,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        self.merge(file_lines);
+        self.lines.truncate(limit);
+        self.apply_limit();
 
-        self.lines
-            .iter()
-            .take(limit)
-            .rev()
-            .map(|ln| writeln!(f, "{}", ln).map(|_| ()))
-            .collect()
+        utils::atomic_write(path, to_file_contents(&self.lines).as_bytes(), 0o600)?;
+
+        self.unsaved = 0;
+        self.file = Some((path.to_owned(), FileStamp::of(path)));
+        Ok(())
+    }
+    /// Puts the unsaved lines on top of `file_lines` (oldest first, as they're stored in the file).
+    fn merge(&mut self, file_lines: Vec<String>) {
+        let unsaved = self.lines.drain(..self.unsaved).collect::<Vec<_>>();
+        self.lines = unsaved;
+        self.lines.extend(file_lines.into_iter().rev());
+        // The oldest unsaved line might be the one that's now the newest in the file, e.g. if
+        // another instance saved the same search
+        if self.unsaved > 0 && self.lines.get(self.unsaved) == self.lines.get(self.unsaved - 1) {
+            self.lines.remove(self.unsaved - 1);
+            self.unsaved -= 1;
+        }
+        self.apply_limit();
+        self.idx = 0;
+    }
+    /// Drops the oldest lines that don't fit into the limit.
+    fn apply_limit(&mut self) {
+        if let Some(limit) = self.limit {
+            self.lines.truncate(limit);
+            self.unsaved = self.unsaved.min(self.lines.len());
+        }
+    }
+}
+
+/// `path` with `suffix` appended to the file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Blocks until this process holds the lock that guards updates to the history file at `path`.
+/// The lock is released when the returned file is closed.
+fn lock(path: &Path) -> io::Result<File> {
+    let lock_file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sibling(path, ".lock"))?;
+    if unsafe { libc::lockf(lock_file.as_raw_fd(), libc::F_LOCK, 0) } != 0 {
+        return Err(io::Error::last_os_error());
     }
+    Ok(lock_file)
+}
+
+/// Reads the lines of a history file, oldest first.
+///
+/// An instance that crashed while writing, or a full disk, can leave a partial line at the
+/// end of the file. Everything from the first line that isn't newline-terminated valid UTF-8 on is
+/// dropped.
+fn read_lines(path: &Path) -> io::Result<Vec<String>> {
+    let contents = fs::read(path)?;
+    let mut pieces = contents.split(|byte| *byte == b'\n').collect::<Vec<_>>();
+    // The piece after the last newline is either empty, or a partial line
+    pieces.pop();
+
+    let mut lines = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        let piece = match piece.last() {
+            Some(b'\r') => &piece[..piece.len() - 1],
+            _ => piece,
+        };
+        match std::str::from_utf8(piece) {
+            Ok("") => {}
+            Ok(line) => lines.push(line.to_string()),
+            Err(_) => break,
+        }
+    }
+    Ok(lines)
+}
+
+/// Contents of a history file with `lines` (newest first), oldest first.
+fn to_file_contents(lines: &[String]) -> String {
+    let mut contents = String::new();
+    for line in lines.iter().rev() {
+        contents.push_str(line);
+        contents.push('\n');
+    }
+    contents
 }
 
 #[cfg(test)]
@@ -162,4 +329,221 @@ mod tests {
         assert_eq!(loaded_h.previous_line(), "2");
         assert_eq!(loaded_h.previous_line(), "2");
     }
+
+    fn add_lines(h: &mut History, lines: &[&str]) {
+        for line in lines {
+            h.add_line(line.to_string());
+        }
+    }
+
+    fn browse(h: &mut History) -> Vec<String> {
+        let mut result = Vec::new();
+        loop {
+            let idx = h.idx;
+            let line = h.previous_line();
+            if h.idx == idx {
+                break result;
+            }
+            result.push(line);
+        }
+    }
+
+    #[test]
+    fn t_interleaved_saves_of_two_instances_lose_nothing() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+
+        let mut first = History::new();
+        let mut second = History::new();
+        first.load_from_file(&file_path).unwrap_err();
+        second.load_from_file(&file_path).unwrap_err();
+
+        add_lines(&mut first, &["a1"]);
+        add_lines(&mut second, &["b1"]);
+        first.save_to_file(&file_path, 10).unwrap();
+        add_lines(&mut second, &["b2"]);
+        second.save_to_file(&file_path, 10).unwrap();
+        add_lines(&mut first, &["a2"]);
+        first.save_to_file(&file_path, 10).unwrap();
+        // Saving again without adding lines doesn't duplicate anything
+        second.save_to_file(&file_path, 10).unwrap();
+
+        let mut loaded = History::new();
+        loaded.load_from_file(&file_path).unwrap();
+        assert_eq!(browse(&mut loaded), vec!["a2", "b2", "b1", "a1"]);
+    }
+
+    #[test]
+    fn t_merged_saves_respect_the_limit() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+
+        let mut first = History::new();
+        let mut second = History::new();
+        add_lines(&mut first, &["1", "2", "3"]);
+        add_lines(&mut second, &["4", "5"]);
+        first.save_to_file(&file_path, 4).unwrap();
+        second.save_to_file(&file_path, 4).unwrap();
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "2\n3\n4\n5\n");
+        assert_eq!(browse(&mut second), vec!["5", "4", "3", "2"]);
+    }
+
+    #[test]
+    fn t_lines_saved_by_other_instances_show_up_when_browsing() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+        fs::write(&file_path, "old\n").unwrap();
+
+        let mut first = History::new();
+        first.load_from_file(&file_path).unwrap();
+        add_lines(&mut first, &["unsaved"]);
+
+        let mut second = History::new();
+        second.load_from_file(&file_path).unwrap();
+        add_lines(&mut second, &["other"]);
+        second.save_to_file(&file_path, 10).unwrap();
+
+        // Lines that weren't saved yet stay the newest
+        assert_eq!(browse(&mut first), vec!["unsaved", "other", "old"]);
+        assert!(!first.reload_if_changed().unwrap());
+
+        first.save_to_file(&file_path, 10).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "old\nother\nunsaved\n"
+        );
+    }
+
+    #[test]
+    fn t_limited_history_drops_the_oldest_lines() {
+        let mut h = History::with_limit(3);
+        add_lines(&mut h, &["1", "2", "3"]);
+        assert_eq!(browse(&mut h), vec!["3", "2", "1"]);
+
+        add_lines(&mut h, &["4"]);
+        assert_eq!(browse(&mut h), vec!["4", "3", "2"]);
+        add_lines(&mut h, &["5", "6"]);
+        assert_eq!(browse(&mut h), vec!["6", "5", "4"]);
+
+        // A limit of zero keeps nothing
+        let mut h = History::with_limit(0);
+        add_lines(&mut h, &["1"]);
+        assert_eq!(h.previous_line(), "");
+    }
+
+    #[test]
+    fn t_repeating_the_newest_line_does_not_add_it_again() {
+        let mut h = History::new();
+        add_lines(&mut h, &["a", "a", "b", "a", "a"]);
+        assert_eq!(browse(&mut h), vec!["a", "b", "a"]);
+
+        // Adding a duplicate still resets the position
+        h.add_line("a".to_string());
+        assert_eq!(h.previous_line(), "a");
+
+        // Duplicates don't push older lines out of a full history
+        let mut h = History::with_limit(2);
+        add_lines(&mut h, &["x", "y", "y", "y"]);
+        assert_eq!(browse(&mut h), vec!["y", "x"]);
+    }
+
+    #[test]
+    fn t_duplicates_are_skipped_across_the_file_boundary() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+        fs::write(&file_path, "a\nb\n").unwrap();
+
+        // The newest line of the file counts as the newest line of the history
+        let mut first = History::new();
+        first.load_from_file(&file_path).unwrap();
+        add_lines(&mut first, &["b"]);
+        assert_eq!(browse(&mut first), vec!["b", "a"]);
+
+        // Another instance saving the same line doesn't make it appear twice
+        let mut second = History::new();
+        second.load_from_file(&file_path).unwrap();
+        add_lines(&mut first, &["c"]);
+        add_lines(&mut second, &["c"]);
+        second.save_to_file(&file_path, 10).unwrap();
+        first.save_to_file(&file_path, 10).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "a\nb\nc\n");
+        assert_eq!(browse(&mut first), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn t_limited_history_loads_and_saves_only_the_newest_lines() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.cmdline");
+        // Written by a version that didn't limit the history
+        fs::write(&file_path, "1\n2\n3\n4\n5\n").unwrap();
+
+        let mut h = History::with_limit(3);
+        h.load_from_file(&file_path).unwrap();
+        assert_eq!(browse(&mut h), vec!["5", "4", "3"]);
+
+        add_lines(&mut h, &["6"]);
+        h.save_to_file(&file_path, 100).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "4\n5\n6\n");
+
+        // The lower of the two limits wins
+        add_lines(&mut h, &["7"]);
+        h.save_to_file(&file_path, 2).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "6\n7\n");
+    }
+
+    #[test]
+    fn t_search_prefix_finds_the_newest_matching_line() {
+        let mut h = History::new();
+        add_lines(&mut h, &["save", "set bar", "open", "set foo"]);
+
+        assert_eq!(h.search_prefix("set"), Some("set foo"));
+        assert_eq!(h.search_prefix("o"), Some("open"));
+        assert_eq!(h.search_prefix(""), Some("set foo"));
+        assert_eq!(h.search_prefix("quit"), None);
+    }
+
+    #[test]
+    fn t_search_prefix_starts_at_the_cursor() {
+        let mut h = History::new();
+        add_lines(&mut h, &["save", "set bar", "open", "set foo"]);
+
+        assert_eq!(h.previous_line(), "set foo");
+        assert_eq!(h.search_prefix("set"), Some("set bar"));
+        assert_eq!(h.previous_line(), "open");
+        assert_eq!(h.search_prefix("set"), Some("set bar"));
+        assert_eq!(h.search_prefix("open"), None);
+
+        // Searching doesn't move the cursor
+        assert_eq!(h.previous_line(), "set bar");
+        assert_eq!(h.search_prefix("set"), None);
+        assert_eq!(h.next_line(), "set bar");
+        assert_eq!(h.search_prefix("s"), Some("set bar"));
+        assert_eq!(h.search_prefix("sa"), Some("save"));
+
+        // Adding a line starts over from the newest one
+        h.add_line("sort".to_string());
+        assert_eq!(h.search_prefix("s"), Some("sort"));
+    }
+
+    #[test]
+    fn t_corrupted_tail_of_history_file_is_dropped() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("history.search");
+
+        fs::write(&file_path, "first\nsecond\nunfinish").unwrap();
+        let mut h = History::new();
+        h.load_from_file(&file_path).unwrap();
+        assert_eq!(browse(&mut h), vec!["second", "first"]);
+
+        fs::write(&file_path, b"first\n\xff\xfe\nthird\n").unwrap();
+        let mut h = History::new();
+        h.load_from_file(&file_path).unwrap();
+        assert_eq!(browse(&mut h), vec!["first"]);
+
+        // Saving repairs the file
+        add_lines(&mut h, &["new"]);
+        h.save_to_file(&file_path, 10).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "first\nnew\n");
+    }
 }
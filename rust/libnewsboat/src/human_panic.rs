@@ -65,24 +65,196 @@
 //! --->8----->8----->8----->8----->8----->8----->8----->8----->8----->8----->8---
 //! ```
 //!
+//! The same report is also written into a file, so that it survives even if the terminal was in
+//! STFL mode and the message got lost. The file is called `newsboat-crash-<timestamp>.txt`; it's
+//! put into the directory set with `set_report_dir()`, or into the temporary directory (`$TMPDIR`)
+//! if that's not set or not writable. Besides the above, the file contains the values of
+//! environment variables that affect the display, like `TERM` and the locale settings. If the file
+//! couldn't be written, only the message above is printed.
+//!
 //! All you (the programmer) need to do is run this module's `setup()` somewhere towards the
-//! beginning of the program.
+//! beginning of the program, and `set_report_dir()` once the program knows where its data lives.
+//!
+//! Code that can recover from a panic (e.g. a reload of a single feed) runs under `catch()`
+//! instead. Such panics don't print anything; their reports are kept in a buffer (see
+//! `caught_reports()`), so that the user can still submit them.
+use crate::utils;
 use backtrace::Backtrace;
+use chrono::offset::Local;
+use lazy_static::lazy_static;
+use std::any::Any;
+use std::cell::RefCell;
+use std::env;
+use std::fs::OpenOptions;
 use std::io::{self, stderr, BufWriter, Write};
-use std::panic::{self, PanicInfo};
+use std::panic::{self, PanicInfo, UnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+
+/// How many reports of caught panics to keep. The oldest ones are dropped first.
+const MAX_CAUGHT_REPORTS: usize = 16;
+
+/// Environment variables whose values are added to the crash report file.
+const REPORTED_ENV_VARS: [&str; 6] = [
+    "TERM",
+    "COLORTERM",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "LC_MESSAGES",
+];
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// `true` if the hook should print a user-friendly message rather than defer to the default hook.
+static FRIENDLY: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref CAUGHT_REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static ref REPORT_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// What `catch()` and the hook share on a given thread.
+#[derive(Default)]
+struct CatchState {
+    /// `true` while `catch()` runs a function on this thread.
+    catching: bool,
+    /// The panic that the hook recorded for `catch()`.
+    caught: Option<CaughtPanic>,
+}
+
+thread_local! {
+    static CATCH_STATE: RefCell<CatchState> = RefCell::new(CatchState::default());
+}
+
+/// A panic that was caught by `catch()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaughtPanic {
+    message: String,
+    location: String,
+    backtrace: String,
+}
+
+impl CaughtPanic {
+    /// The message the code panicked with.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// A crash report, in the same format as the one printed when the program crashes.
+    pub fn report(&self) -> String {
+        format!(
+            "Newsboat version: {}\nMessage: {}\n{}\n{}",
+            env!("CARGO_PKG_VERSION"),
+            self.message,
+            self.location,
+            self.backtrace
+        )
+    }
+}
 
 /// Sets up a panic hook with a user-friendly message.
 ///
 /// See module description for details.
 pub fn setup() {
     if ::std::env::var("RUST_BACKTRACE").is_err() {
+        FRIENDLY.store(true, Ordering::SeqCst);
+    }
+    install_hook();
+}
+
+/// Makes the hook write crash report files into `dir` instead of the temporary directory.
+pub fn set_report_dir(dir: &Path) {
+    let mut report_dir = match REPORT_DIR.lock() {
+        Ok(report_dir) => report_dir,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    *report_dir = Some(dir.to_owned());
+}
+
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
         panic::set_hook(Box::new(move |panic_info: &PanicInfo| {
-            print_panic_msg(panic_info).expect("An error occurred while preparing a crash report");
+            if CATCH_STATE.with(|state| state.borrow().catching) {
+                let caught = CaughtPanic {
+                    message: payload_message(panic_info.payload()),
+                    location: get_location(panic_info),
+                    backtrace: format!("{:#?}", Backtrace::new()),
+                };
+                CATCH_STATE.with(|state| state.borrow_mut().caught = Some(caught));
+            } else if FRIENDLY.load(Ordering::SeqCst) {
+                print_panic_msg(panic_info)
+                    .expect("An error occurred while preparing a crash report");
+            } else {
+                default_hook(panic_info);
+            }
         }));
+    });
+}
+
+/// Runs `function`, turning a panic into an error instead of letting it unwind further.
+///
+/// The report of the panic is added to the buffer returned by `caught_reports()`.
+pub fn catch<F, T>(function: F) -> Result<T, CaughtPanic>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    install_hook();
+
+    let was_catching = CATCH_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let was_catching = state.catching;
+        state.catching = true;
+        was_catching
+    });
+    let result = panic::catch_unwind(function);
+    let caught = CATCH_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.catching = was_catching;
+        state.caught.take()
+    });
+
+    result.map_err(|payload| {
+        let caught = caught.unwrap_or_else(|| CaughtPanic {
+            message: payload_message(&*payload),
+            location: String::from("Crash location unknown"),
+            backtrace: String::new(),
+        });
+
+        let mut reports = match CAUGHT_REPORTS.lock() {
+            Ok(reports) => reports,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if reports.len() == MAX_CAUGHT_REPORTS {
+            reports.remove(0);
+        }
+        reports.push(caught.report());
+
+        caught
+    })
+}
+
+/// Reports of the panics caught by `catch()`, oldest first.
+pub fn caught_reports() -> Vec<String> {
+    match CAUGHT_REPORTS.lock() {
+        Ok(reports) => reports.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
     }
 }
 
 fn print_panic_msg(panic_info: &PanicInfo) -> io::Result<()> {
+    let report = format!(
+        "Newsboat version: {}\n{}\n{}\n{}\n{:#?}",
+        env!("CARGO_PKG_VERSION"),
+        get_crash_cause(&panic_info),
+        get_error_message(&panic_info),
+        get_location(&panic_info),
+        Backtrace::new()
+    );
+    let report_file = write_report_file(&report);
+
     // Locking the handle to make sure all the messages are printed out in one chunk.
     let stderr = stderr();
     let handle = stderr.lock();
@@ -100,23 +272,25 @@ fn print_panic_msg(panic_info: &PanicInfo) -> io::Result<()> {
          - via email: newsboat@googlegroups.com\n\
          \n\
          We might have some follow-up questions, so please check your email\n\
-         periodically. Thank you!\n\
-         \n\
-         The crash report:\n\
-         \n\
-         --->8----->8----->8----->8----->8----->8----->8----->8----->8----->8----->8---\n"
+         periodically. Thank you!\n"
     )?;
 
+    if let Some(report_file) = report_file {
+        writeln!(
+            &mut stderr,
+            "The crash report was saved to {}; please attach that file.\n",
+            report_file.display()
+        )?;
+    }
+
     writeln!(
         &mut stderr,
-        "Newsboat version: {}",
-        env!("CARGO_PKG_VERSION")
+        "The crash report:\n\
+         \n\
+         --->8----->8----->8----->8----->8----->8----->8----->8----->8----->8----->8---\n"
     )?;
-    writeln!(&mut stderr, "{}", get_crash_cause(&panic_info))?;
-    writeln!(&mut stderr, "{}", get_error_message(&panic_info))?;
-    writeln!(&mut stderr, "{}", get_location(&panic_info))?;
 
-    writeln!(&mut stderr, "{:#?}", Backtrace::new())?;
+    writeln!(&mut stderr, "{}", report)?;
 
     writeln!(
         &mut stderr,
@@ -126,6 +300,44 @@ fn print_panic_msg(panic_info: &PanicInfo) -> io::Result<()> {
     Ok(())
 }
 
+/// Writes `report`, along with the build and the environment details, into a new file. Returns
+/// the path to that file, or `None` if it couldn't be written anywhere.
+fn write_report_file(report: &str) -> Option<PathBuf> {
+    let mut contents = format!(
+        "{}\n\nBuilt from: {}\n\nEnvironment:\n",
+        report,
+        utils::program_version().trim()
+    );
+    for name in &REPORTED_ENV_VARS {
+        match env::var_os(name) {
+            Some(value) => contents.push_str(&format!("{}={}\n", name, value.to_string_lossy())),
+            None => contents.push_str(&format!("{} is not set\n", name)),
+        }
+    }
+
+    let filename = format!(
+        "newsboat-crash-{}.txt",
+        Local::now().format("%Y-%m-%dT%H-%M-%S")
+    );
+    // `try_lock` because the panic might've happened while the lock was held
+    let report_dir = REPORT_DIR
+        .try_lock()
+        .ok()
+        .and_then(|report_dir| report_dir.clone());
+    report_dir
+        .into_iter()
+        .chain(Some(env::temp_dir()))
+        .map(|dir| dir.join(&filename))
+        .find(|path| {
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .is_ok()
+        })
+}
+
 #[cfg(feature = "nightly")]
 fn get_crash_cause(panic_info: &PanicInfo) -> String {
     match panic_info.message() {
@@ -148,9 +360,47 @@ fn get_error_message(panic_info: &PanicInfo) -> String {
     }
 }
 
+/// The message passed to `panic!`, whether it's a literal or was formatted.
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("No error message")
+    }
+}
+
 fn get_location(panic_info: &PanicInfo) -> String {
     match panic_info.location() {
         Some(location) => format!("Crash location: {}:{}", location.file(), location.line()),
         None => String::from("Crash location unknown"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_catch_returns_the_result_if_function_does_not_panic() {
+        assert_eq!(catch(|| 42), Ok(42));
+    }
+
+    #[test]
+    fn t_catch_turns_panic_into_an_error_with_a_report() {
+        let url = "https://example.com/feed.xml";
+        let caught = catch(|| -> u32 { panic!("unexpected element in {}", url) }).unwrap_err();
+        assert_eq!(
+            caught.message(),
+            "unexpected element in https://example.com/feed.xml"
+        );
+
+        let report = caught.report();
+        assert!(report.starts_with("Newsboat version: "));
+        assert!(report.contains("Message: unexpected element in https://example.com/feed.xml"));
+        assert!(report.contains("Crash location: rust/libnewsboat/src/human_panic.rs:"));
+
+        assert!(caught_reports().contains(&report));
+    }
+}
@@ -0,0 +1,358 @@
+//! Hyphenation of words that don't fit at the end of a line.
+//!
+//! Soft hyphens (U+00AD) that are already present in the text are always honored. On top of that,
+//! English words can be hyphenated with Liang's algorithm (the one used by TeX). The patterns are
+//! a small hand-written set rather than the full TeX one, so the results are conservative: it's
+//! fine to miss a break opportunity, but not to break a word in an odd place.
+
+use crate::utils;
+use lazy_static::lazy_static;
+
+pub const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Words with fewer letters than this are never hyphenated by patterns.
+const MIN_WORD_LENGTH: usize = 6;
+
+/// Minimum number of letters that a pattern can leave before the hyphen.
+const LEFT_MIN: usize = 2;
+
+/// Minimum number of letters that a pattern can move to the next line.
+const RIGHT_MIN: usize = 3;
+
+/// Patterns for Liang's algorithm.
+///
+/// Digits are the values of the positions between letters; a break is allowed at positions whose
+/// highest value is odd. `.` matches the start or the end of the word. Besides letters, patterns
+/// can contain `V`, which matches any vowel, and `C`, which matches any consonant.
+#[rustfmt::skip]
+const PATTERNS: &[&str] = &[
+    // Split a pair of consonants between vowels: "win-dow", "let-ter"
+    "VC1CV",
+    // ...unless they are read as one sound: "ath-lete" is wrong, and so is "sing-ing"
+    "c2h", "c2k", "g2h", "n2g", "p2h", "s2h", "t2h", "w2h", "q2u",
+    // Consonants before "-ing" and "-ed" stay together: "tell-ing", "stand-ing", "stopped"
+    "C2Cing.", "C2Ced.",
+    // Suffixes
+    "1ing.", "1ings.", "1ment.", "1ments.", "1ness.", "1less.", "1ful.", "1ship.", "1ships.",
+    "1tion.", "1tions.", "1sion.", "1sions.", "1ward.", "1wards.", "1ity.", "1ities.",
+    // Prefixes
+    ".un1", ".uni2", ".un2der", ".dis1", ".mis1", ".non1", ".out1", ".over1", ".under1", ".sub1",
+    ".re1", ".rea2",
+];
+
+struct Pattern {
+    /// Letters, classes and word boundaries to match.
+    chars: Vec<char>,
+    /// Values of positions before, between and after `chars`; `values.len() == chars.len() + 1`.
+    values: Vec<u8>,
+}
+
+impl Pattern {
+    fn parse(text: &str) -> Pattern {
+        let mut chars = Vec::new();
+        let mut values = vec![0];
+        for c in text.chars() {
+            match c.to_digit(10) {
+                Some(value) => {
+                    // `to_digit(10)` returns values less than 10, so they fit into u8.
+                    if let Some(last) = values.last_mut() {
+                        *last = value as u8;
+                    }
+                }
+                None => {
+                    chars.push(c);
+                    values.push(0);
+                }
+            }
+        }
+        Pattern { chars, values }
+    }
+
+    fn matches_at(&self, word: &[char], start: usize) -> bool {
+        if start + self.chars.len() > word.len() {
+            return false;
+        }
+        self.chars
+            .iter()
+            .zip(&word[start..])
+            .all(|(&p, &c)| match p {
+                'V' => is_vowel(c),
+                'C' => c != '.' && !is_vowel(c),
+                _ => p == c,
+            })
+    }
+}
+
+lazy_static! {
+    static ref PARSED_PATTERNS: Vec<Pattern> = PATTERNS.iter().map(|p| Pattern::parse(p)).collect();
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+fn without_soft_hyphens(text: &str) -> String {
+    let mut result = text.to_string();
+    utils::remove_soft_hyphens(&mut result);
+    result
+}
+
+/// Returns `true` for words that should never be hyphenated: URLs, e-mail addresses, and code.
+fn is_exempt(word: &str) -> bool {
+    word.contains("://")
+        || word.starts_with("www.")
+        || word.contains('@')
+        || word.contains('`')
+        || word.contains('<')
+        || word.contains('>')
+}
+
+/// Positions at which `word` can be hyphenated, as indices of characters of `word` with soft
+/// hyphens removed. The hyphen goes before the character at that index.
+pub fn break_points(word: &str, use_patterns: bool) -> Vec<usize> {
+    if is_exempt(word) {
+        return vec![];
+    }
+
+    let mut result = Vec::new();
+    let mut position = 0;
+    for c in word.chars() {
+        if c == SOFT_HYPHEN {
+            if position > 0 {
+                result.push(position);
+            }
+        } else {
+            position += 1;
+        }
+    }
+    let length = position;
+
+    if use_patterns {
+        for point in pattern_break_points(&without_soft_hyphens(word)) {
+            if !result.contains(&point) {
+                result.push(point);
+            }
+        }
+    }
+
+    result.retain(|&point| point > 0 && point < length);
+    result.sort_unstable();
+    result
+}
+
+/// Break points found by Liang's algorithm.
+fn pattern_break_points(word: &str) -> Vec<usize> {
+    let chars = word.chars().collect::<Vec<_>>();
+
+    // Only the letters are hyphenated; leading and trailing punctuation (quotes, commas etc.) is
+    // left alone.
+    let first = match chars.iter().position(|c| c.is_alphabetic()) {
+        Some(first) => first,
+        None => return vec![],
+    };
+    let last = chars
+        .iter()
+        .rposition(|c| c.is_alphabetic())
+        .unwrap_or(first);
+    let letters = &chars[first..=last];
+    if letters.len() < MIN_WORD_LENGTH
+        || !letters.iter().all(|c| c.is_ascii_alphabetic())
+        || chars.iter().any(|c| c.is_numeric())
+    {
+        return vec![];
+    }
+
+    let mut padded = vec!['.'];
+    padded.extend(letters.iter().map(|c| c.to_ascii_lowercase()));
+    padded.push('.');
+
+    // values[i] is the value of the position before padded[i]
+    let mut values = vec![0u8; padded.len() + 1];
+    for start in 0..padded.len() {
+        for pattern in PARSED_PATTERNS.iter() {
+            if pattern.matches_at(&padded, start) {
+                for (offset, &value) in pattern.values.iter().enumerate() {
+                    let value_ref = &mut values[start + offset];
+                    *value_ref = (*value_ref).max(value);
+                }
+            }
+        }
+    }
+
+    // Position before letters[i] is the position before padded[i + 1]
+    (LEFT_MIN..=letters.len().saturating_sub(RIGHT_MIN))
+        .filter(|&i| values[i + 1] % 2 == 1)
+        .map(|i| first + i)
+        .collect()
+}
+
+/// Splits `word` so that the first part, including the hyphen that's appended to it, is at most
+/// `max_width` columns wide. The break that leaves the widest first part is chosen.
+///
+/// Returns `None` if `word` can't be split like that. Soft hyphens are removed from the first
+/// part, but not from the second, because it might need to be hyphenated again.
+pub fn hyphenate(word: &str, max_width: usize, use_patterns: bool) -> Option<(String, String)> {
+    let visible = without_soft_hyphens(word);
+    let visible_chars = visible.chars().collect::<Vec<_>>();
+
+    let point = break_points(word, use_patterns)
+        .into_iter()
+        .rev()
+        .find(|&point| {
+            let head = visible_chars[..point].iter().collect::<String>();
+            utils::strwidth(&head) + 1 <= max_width
+        })?;
+
+    let head = visible_chars[..point].iter().collect::<String>() + "-";
+
+    // Find where the second part starts in the original word, which still has soft hyphens in it
+    let mut seen = 0;
+    let mut tail_start = word.len();
+    for (index, c) in word.char_indices() {
+        if c == SOFT_HYPHEN {
+            continue;
+        }
+        if seen == point {
+            tail_start = index;
+            break;
+        }
+        seen += 1;
+    }
+    let tail = word[tail_start..].to_string();
+
+    Some((head, tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hyphenated(word: &str) -> String {
+        let chars = word.chars().collect::<Vec<_>>();
+        let points = break_points(word, true);
+        let mut result = String::new();
+        for (i, c) in chars.iter().enumerate() {
+            if points.contains(&i) {
+                result.push('-');
+            }
+            result.push(*c);
+        }
+        result
+    }
+
+    #[test]
+    fn t_breaks_at_soft_hyphen_and_renders_it_as_hyphen() {
+        let word = "hyphen\u{AD}ation";
+        assert_eq!(
+            hyphenate(word, 8, false),
+            Some(("hyphen-".to_string(), "ation".to_string()))
+        );
+    }
+
+    #[test]
+    fn t_returns_none_if_no_part_fits() {
+        // "hyphen-" is 7 columns wide
+        let word = "hyphen\u{AD}ation";
+        assert_eq!(hyphenate(word, 6, false), None);
+        assert_eq!(hyphenate(word, 5, false), None);
+    }
+
+    #[test]
+    fn t_keeps_remaining_soft_hyphens_in_the_second_part() {
+        let word = "su\u{AD}per\u{AD}cal\u{AD}i\u{AD}fra\u{AD}gil\u{AD}is\u{AD}tic";
+        let (head, tail) = hyphenate(word, 9, false).unwrap();
+        assert_eq!(head, "supercal-");
+        assert_eq!(tail, "i\u{AD}fra\u{AD}gil\u{AD}is\u{AD}tic");
+
+        let (head, tail) = hyphenate(&tail, 9, false).unwrap();
+        assert_eq!(head, "ifragil-");
+        assert_eq!(tail, "is\u{AD}tic");
+    }
+
+    #[test]
+    fn t_width_includes_the_inserted_hyphen() {
+        let word = "abc\u{AD}def\u{AD}ghi";
+        // "abcdef-" is 7 columns wide
+        assert_eq!(
+            hyphenate(word, 7, false),
+            Some(("abcdef-".to_string(), "ghi".to_string()))
+        );
+        assert_eq!(
+            hyphenate(word, 6, false),
+            Some(("abc-".to_string(), "def\u{AD}ghi".to_string()))
+        );
+        assert_eq!(hyphenate(word, 3, false), None);
+
+        // Wide characters take two columns each
+        let word = "日本\u{AD}語";
+        assert_eq!(
+            hyphenate(word, 5, false),
+            Some(("日本-".to_string(), "語".to_string()))
+        );
+        assert_eq!(hyphenate(word, 4, false), None);
+    }
+
+    #[test]
+    fn t_never_hyphenates_urls_emails_or_code() {
+        for word in &[
+            "https://example.com/hyphen\u{AD}ation",
+            "www.hyphen\u{AD}ation.org",
+            "hyphen\u{AD}ation@example.com",
+            "`hyphen\u{AD}ation`",
+        ] {
+            assert_eq!(break_points(word, true), Vec::<usize>::new());
+            assert_eq!(hyphenate(word, 10, true), None);
+        }
+        assert_eq!(
+            break_points("https://example.com/information", true),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn t_patterns_are_only_used_if_asked_to() {
+        assert_eq!(break_points("information", false), Vec::<usize>::new());
+        assert_ne!(break_points("information", true), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn t_patterns_never_break_short_words() {
+        for word in &["hello", "after", "under", "going", "Happy"] {
+            assert_eq!(break_points(word, true), Vec::<usize>::new());
+        }
+    }
+
+    #[test]
+    fn t_patterns_hyphenate_common_english_words() {
+        let check = |word: &str, expected: &str| {
+            assert_eq!(hyphenated(word), expected);
+        };
+
+        check("window", "win-dow");
+        check("information", "in-for-ma-tion");
+        check("happiness", "hap-pi-ness");
+        check("telling", "tell-ing");
+        check("stopped", "stopped");
+        check("singing", "sing-ing");
+        check("athletes", "athletes");
+        check("Wonderful", "Won-der-ful");
+        check("understanding", "under-stand-ing");
+        check("\"nevertheless,\"", "\"neverthe-less,\"");
+    }
+
+    #[test]
+    fn t_patterns_respect_minimum_fragment_lengths() {
+        // "un-" would be allowed by LEFT_MIN, but "-ly" is too short for RIGHT_MIN
+        for point in break_points("kindness", true) {
+            assert!(point >= LEFT_MIN);
+            assert!(point <= "kindness".len() - RIGHT_MIN);
+        }
+        assert_eq!(hyphenated("kindly"), "kindly");
+    }
+
+    #[test]
+    fn t_patterns_skip_words_with_digits_or_non_ascii_letters() {
+        assert_eq!(break_points("windows10", true), Vec::<usize>::new());
+        assert_eq!(break_points("Übersetzung", true), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,446 @@
+//! Plans the first sync of a feed from a remote API, which brings in the articles that are too
+//! old to be part of a regular fetch.
+//!
+//! The articles are fetched in pages, newest first: each page asks for the articles that are older
+//! than the oldest one seen so far. The caller stores each page together with the `Progress` that
+//! `InitialSync::page_fetched` returns for it, so an interrupted sync continues with the next page
+//! instead of starting over.
+
+use gettextrs::gettext;
+use std::cmp;
+use std::time::Duration;
+use strprintf::fmt;
+
+/// Default for `miniflux-sync-page-size`.
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Backoff after failed pages never grows beyond this.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// How often a page is tried, and how long to wait between requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times a page is tried before the sync gives up (`download-retries`).
+    pub attempts: u32,
+    /// Pause between two pages (`miniflux-sync-page-delay`). Retries wait twice as long after each
+    /// failure, but at least a second.
+    pub page_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// How long to wait before trying a page again after it failed `failures` times in a row, or
+    /// `None` if it shouldn't be tried again.
+    pub fn delay_after_failure(&self, failures: u32) -> Option<Duration> {
+        if failures == 0 || failures >= cmp::max(self.attempts, 1) {
+            return None;
+        }
+        let base = cmp::max(self.page_delay, Duration::from_secs(1));
+        let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+        Some(
+            base.checked_mul(factor)
+                .map_or(MAX_RETRY_DELAY, |delay| cmp::min(delay, MAX_RETRY_DELAY)),
+        )
+    }
+}
+
+/// How far the sync of a feed got. This is what's persisted between runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// All articles were fetched.
+    pub complete: bool,
+    /// Number of pages fetched so far.
+    pub pages: u32,
+    /// Number of articles fetched so far.
+    pub articles: u32,
+    /// ID of the oldest article fetched so far. The next page starts right before it.
+    pub before_id: Option<i64>,
+}
+
+impl Progress {
+    /// Parses what `serialize()` returned. The empty string is a sync that hasn't started yet.
+    pub fn parse(input: &str) -> Option<Progress> {
+        let fields = input.split_whitespace().collect::<Vec<_>>();
+        match fields.as_slice() {
+            [] => Some(Progress::default()),
+            [complete, pages, articles, before_id] => Some(Progress {
+                complete: match *complete {
+                    "done" => true,
+                    "running" => false,
+                    _ => return None,
+                },
+                pages: pages.parse().ok()?,
+                articles: articles.parse().ok()?,
+                before_id: match *before_id {
+                    "-" => None,
+                    id => Some(id.parse().ok()?),
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            if self.complete { "done" } else { "running" },
+            self.pages,
+            self.articles,
+            self.before_id
+                .map_or_else(|| "-".to_string(), |id| id.to_string())
+        )
+    }
+}
+
+/// A page to fetch: up to `limit` articles, newest first, that are older than `before_id` (if
+/// any).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub limit: usize,
+    pub before_id: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct InitialSync {
+    page_size: usize,
+    policy: RetryPolicy,
+    progress: Progress,
+    /// Failed attempts at the current page.
+    failures: u32,
+}
+
+impl InitialSync {
+    /// Continues the sync from `progress`; `Progress::default()` starts it from scratch.
+    pub fn new(page_size: usize, policy: RetryPolicy, progress: Progress) -> InitialSync {
+        InitialSync {
+            page_size: cmp::max(page_size, 1),
+            policy,
+            progress,
+            failures: 0,
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress.complete
+    }
+
+    /// The page to fetch next, or `None` if the sync is complete.
+    pub fn next_request(&self) -> Option<PageRequest> {
+        if self.progress.complete {
+            None
+        } else {
+            Some(PageRequest {
+                limit: self.page_size,
+                before_id: self.progress.before_id,
+            })
+        }
+    }
+
+    /// Records that the requested page was fetched and contains articles with the given `ids`.
+    /// Returns the progress to store along with the articles.
+    pub fn page_fetched(&mut self, ids: &[i64]) -> Progress {
+        self.failures = 0;
+        self.progress.pages += 1;
+        self.progress.articles += ids.len() as u32;
+
+        let oldest = ids.iter().min().cloned();
+        let went_back = match (oldest, self.progress.before_id) {
+            (Some(oldest), Some(before_id)) => oldest < before_id,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if went_back {
+            self.progress.before_id = oldest;
+        }
+        // A short page is the last one. A page that doesn't go back in time is one from a server
+        // that ignored the cursor; asking again would bring the same page forever.
+        if ids.len() < self.page_size || !went_back {
+            self.progress.complete = true;
+        }
+        self.progress
+    }
+
+    /// Records that the requested page couldn't be fetched. Returns how long to wait before trying
+    /// it again, or `None` if the sync should give up for now; it can be resumed later from
+    /// `progress()`.
+    pub fn page_failed(&mut self) -> Option<Duration> {
+        self.failures += 1;
+        self.policy.delay_after_failure(self.failures)
+    }
+
+    /// Pause between two pages, so that the server isn't flooded with requests.
+    pub fn page_delay(&self) -> Duration {
+        self.policy.page_delay
+    }
+
+    /// Internationalized summary of the progress, for the status bar.
+    pub fn status(&self) -> String {
+        let articles = fmt_plural!(
+            self.progress.articles,
+            "%u article",
+            "%u articles",
+            self.progress.articles
+        );
+        let pages = fmt_plural!(
+            self.progress.pages,
+            "%u page",
+            "%u pages",
+            self.progress.pages
+        );
+        if self.progress.complete {
+            fmt!(&gettext("first sync complete: %s in %s"), articles, pages)
+        } else {
+            fmt!(&gettext("first sync: %s in %s so far"), articles, pages)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        attempts: 3,
+        page_delay: Duration::from_millis(0),
+    };
+
+    /// A remote API that has articles with IDs `1..=articles`, and fails the requests whose
+    /// (zero-based) numbers are listed in `failing_requests`.
+    struct FakeApi {
+        articles: i64,
+        failing_requests: Vec<usize>,
+        requests: usize,
+    }
+
+    impl FakeApi {
+        fn new(articles: i64) -> FakeApi {
+            FakeApi {
+                articles,
+                failing_requests: Vec::new(),
+                requests: 0,
+            }
+        }
+
+        fn fetch(&mut self, request: PageRequest) -> Result<Vec<i64>, ()> {
+            let number = self.requests;
+            self.requests += 1;
+            if self.failing_requests.contains(&number) {
+                return Err(());
+            }
+            let newest = request.before_id.map_or(self.articles, |id| id - 1);
+            Ok((1..=newest).rev().take(request.limit).collect())
+        }
+    }
+
+    /// Stores pages the way the cache does: articles and progress at once.
+    #[derive(Default)]
+    struct FakeCache {
+        articles: Vec<i64>,
+        progress: String,
+        /// Articles and progress as they were right after each page was stored.
+        snapshots: Vec<(usize, Progress)>,
+    }
+
+    impl FakeCache {
+        fn store_page(&mut self, ids: &[i64], progress: Progress) {
+            self.articles.extend_from_slice(ids);
+            self.progress = progress.serialize();
+            self.snapshots.push((self.articles.len(), progress));
+        }
+    }
+
+    /// Runs the sync until it's complete, gives up, or `max_pages` pages were stored. Returns the
+    /// delays that it waited for.
+    fn run(
+        sync: &mut InitialSync,
+        api: &mut FakeApi,
+        cache: &mut FakeCache,
+        max_pages: usize,
+    ) -> Vec<Duration> {
+        let mut delays = Vec::new();
+        let mut pages = 0;
+        while let Some(request) = sync.next_request() {
+            if pages == max_pages {
+                break;
+            }
+            match api.fetch(request) {
+                Ok(ids) => {
+                    let progress = sync.page_fetched(&ids);
+                    cache.store_page(&ids, progress);
+                    pages += 1;
+                }
+                Err(()) => match sync.page_failed() {
+                    Some(delay) => delays.push(delay),
+                    None => break,
+                },
+            }
+        }
+        delays
+    }
+
+    #[test]
+    fn t_fetches_all_articles_page_by_page() {
+        let mut api = FakeApi::new(25);
+        let mut cache = FakeCache::default();
+        let mut sync = InitialSync::new(10, POLICY, Progress::default());
+
+        assert_eq!(
+            sync.next_request(),
+            Some(PageRequest {
+                limit: 10,
+                before_id: None
+            })
+        );
+        run(&mut sync, &mut api, &mut cache, usize::MAX);
+
+        assert!(sync.is_complete());
+        assert_eq!(sync.next_request(), None);
+        assert_eq!(cache.articles, (1..=25).rev().collect::<Vec<_>>());
+        assert_eq!(api.requests, 3);
+        assert_eq!(
+            sync.progress(),
+            Progress {
+                complete: true,
+                pages: 3,
+                articles: 25,
+                before_id: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn t_resumes_an_interrupted_sync_from_the_stored_progress() {
+        let mut api = FakeApi::new(45);
+        let mut cache = FakeCache::default();
+
+        let mut sync = InitialSync::new(10, POLICY, Progress::default());
+        run(&mut sync, &mut api, &mut cache, 2);
+        assert!(!sync.is_complete());
+        assert_eq!(cache.articles.len(), 20);
+
+        // Newsboat was restarted
+        let progress = Progress::parse(&cache.progress).unwrap();
+        let mut sync = InitialSync::new(10, POLICY, progress);
+        assert_eq!(
+            sync.next_request(),
+            Some(PageRequest {
+                limit: 10,
+                before_id: Some(26)
+            })
+        );
+        run(&mut sync, &mut api, &mut cache, usize::MAX);
+
+        assert!(sync.is_complete());
+        // Every article was fetched exactly once
+        assert_eq!(cache.articles, (1..=45).rev().collect::<Vec<_>>());
+        assert_eq!(api.requests, 5);
+        assert_eq!(sync.progress().pages, 5);
+        assert_eq!(sync.progress().articles, 45);
+    }
+
+    #[test]
+    fn t_progress_stored_with_a_page_accounts_for_exactly_that_page() {
+        let mut api = FakeApi::new(30);
+        let mut cache = FakeCache::default();
+        let mut sync = InitialSync::new(10, POLICY, Progress::default());
+        run(&mut sync, &mut api, &mut cache, usize::MAX);
+
+        let reported = cache
+            .snapshots
+            .iter()
+            .map(|(stored, progress)| (*stored as u32, progress.articles, progress.pages))
+            .collect::<Vec<_>>();
+        // The last full page might be followed by older articles, so it takes an empty page to
+        // find out that there are none.
+        assert_eq!(
+            reported,
+            vec![(10, 10, 1), (20, 20, 2), (30, 30, 3), (30, 30, 4)]
+        );
+        assert!(cache
+            .snapshots
+            .iter()
+            .rev()
+            .skip(1)
+            .all(|(_, p)| !p.complete));
+        assert!(cache.snapshots.last().unwrap().1.complete);
+    }
+
+    #[test]
+    fn t_backs_off_on_failures_and_gives_up_after_the_configured_attempts() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            page_delay: Duration::from_secs(2),
+        };
+        assert_eq!(policy.delay_after_failure(1), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay_after_failure(2), Some(Duration::from_secs(4)));
+        assert_eq!(policy.delay_after_failure(3), None);
+
+        let mut api = FakeApi::new(30);
+        api.failing_requests = vec![1, 3, 4, 5];
+        let mut cache = FakeCache::default();
+        let mut sync = InitialSync::new(10, policy, Progress::default());
+        let delays = run(&mut sync, &mut api, &mut cache, usize::MAX);
+
+        // The second page succeeded on the second try, the third one failed three times
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_secs(2),
+                Duration::from_secs(2),
+                Duration::from_secs(4)
+            ]
+        );
+        assert!(!sync.is_complete());
+        assert_eq!(cache.articles.len(), 20);
+        assert_eq!(Progress::parse(&cache.progress), Some(sync.progress()));
+    }
+
+    #[test]
+    fn t_backoff_is_at_least_a_second_and_at_most_max_retry_delay() {
+        let policy = RetryPolicy {
+            attempts: 100,
+            page_delay: Duration::from_millis(0),
+        };
+        assert_eq!(policy.delay_after_failure(1), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay_after_failure(50), Some(MAX_RETRY_DELAY));
+
+        let no_retries = RetryPolicy {
+            attempts: 1,
+            page_delay: Duration::from_secs(1),
+        };
+        assert_eq!(no_retries.delay_after_failure(1), None);
+    }
+
+    #[test]
+    fn t_stops_if_the_server_ignores_the_cursor() {
+        let mut sync = InitialSync::new(2, POLICY, Progress::default());
+        sync.page_fetched(&[10, 9]);
+        assert!(!sync.is_complete());
+        sync.page_fetched(&[10, 9]);
+        assert!(sync.is_complete());
+    }
+
+    #[test]
+    fn t_progress_survives_a_round_trip() {
+        let progress = Progress {
+            complete: false,
+            pages: 7,
+            articles: 3500,
+            before_id: Some(77164),
+        };
+        assert_eq!(progress.serialize(), "running 7 3500 77164");
+        assert_eq!(Progress::parse(&progress.serialize()), Some(progress));
+        assert_eq!(
+            Progress::parse(&Progress::default().serialize()),
+            Some(Progress::default())
+        );
+        assert_eq!(Progress::parse(""), Some(Progress::default()));
+
+        assert_eq!(Progress::parse("paused 7 3500 77164"), None);
+        assert_eq!(Progress::parse("done 7 3500"), None);
+        assert_eq!(Progress::parse("done seven 3500 -"), None);
+    }
+}
@@ -0,0 +1,335 @@
+//! Parses key names used in `bind-key` and `unbind-key` commands.
+//!
+//! Newsboat names keys the way STFL reports them: printable characters are written literally,
+//! special keys by their uppercase names (`ENTER`, `PPAGE`, `F1`), and Ctrl combinations with
+//! a caret (`^R`). Configs found in the wild use other spellings too, e.g. `<Space>`, `C-r` or
+//! `Enter`; where we can tell unambiguously which key is meant, we accept those and report the
+//! canonical spelling.
+
+use crate::utils;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Names of the special keys, as reported by STFL.
+pub(crate) const NAMED_KEYS: &[&str] = &[
+    "ENTER",
+    "BACKSPACE",
+    "LEFT",
+    "RIGHT",
+    "UP",
+    "DOWN",
+    "PPAGE",
+    "NPAGE",
+    "HOME",
+    "END",
+    "ESC",
+    "TAB",
+    "SPACE",
+    "DC",
+    "IC",
+    "BTAB",
+    "F1",
+    "F2",
+    "F3",
+    "F4",
+    "F5",
+    "F6",
+    "F7",
+    "F8",
+    "F9",
+    "F10",
+    "F11",
+    "F12",
+];
+
+/// Spellings used by other programs (mostly Vim and Emacs), and the named keys they stand for.
+/// Compared case-insensitively.
+const ALIASES: &[(&str, &str)] = &[
+    ("RETURN", "ENTER"),
+    ("CR", "ENTER"),
+    ("ESCAPE", "ESC"),
+    ("BS", "BACKSPACE"),
+    ("PAGEUP", "PPAGE"),
+    ("PGUP", "PPAGE"),
+    ("PAGEDOWN", "NPAGE"),
+    ("PGDN", "NPAGE"),
+    ("PGDOWN", "NPAGE"),
+    ("DEL", "DC"),
+    ("DELETE", "DC"),
+    ("INS", "IC"),
+    ("INSERT", "IC"),
+];
+
+/// Prefixes that other programs use for Ctrl combinations. Compared case-insensitively.
+const CTRL_PREFIXES: &[&str] = &["C-", "CTRL-", "CTRL+", "CONTROL-", "CONTROL+"];
+
+/// A successfully parsed key name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyName {
+    /// The spelling that Newsboat uses, e.g. `^R`.
+    pub canonical: String,
+    /// `true` if the input used some other spelling, e.g. `C-r`.
+    pub is_legacy: bool,
+}
+
+/// A key name that we couldn't make sense of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKeyName {
+    pub name: String,
+    /// Named keys that look similar to `name`, closest first.
+    pub suggestions: Vec<&'static str>,
+}
+
+impl KeyName {
+    fn canonical(name: &str) -> KeyName {
+        KeyName {
+            canonical: name.to_string(),
+            is_legacy: false,
+        }
+    }
+
+    fn legacy(name: &str) -> KeyName {
+        KeyName {
+            canonical: name.to_string(),
+            is_legacy: true,
+        }
+    }
+
+    /// Internationalized warning telling the user how to write `original` properly. Returns
+    /// `None` if `original` is already written the canonical way.
+    pub fn warning(&self, original: &str) -> Option<String> {
+        if self.is_legacy {
+            Some(fmt!(
+                &gettext("Warning: key `%s' should be written as `%s'"),
+                original,
+                &self.canonical
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl UnknownKeyName {
+    /// Internationalized description of the error, including the suggestions if there are any.
+    pub fn to_message(&self) -> String {
+        if self.suggestions.is_empty() {
+            fmt!(&gettext("`%s' is not a valid key name"), &self.name)
+        } else {
+            fmt!(
+                &gettext("`%s' is not a valid key name. Did you mean: %s?"),
+                &self.name,
+                self.suggestions.join(", ")
+            )
+        }
+    }
+}
+
+/// Parses a key name, accepting foreign spellings where they're unambiguous.
+pub fn parse_key_name(name: &str) -> Result<KeyName, UnknownKeyName> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => return Err(unknown(name)),
+        // STFL reports the space bar as "SPACE", so a literal space would never match
+        (Some(' '), None) => return Ok(KeyName::legacy("SPACE")),
+        (Some(_), None) => return Ok(KeyName::canonical(name)),
+        _ => {}
+    }
+
+    if let Some(key) = parse_ctrl_combination(name) {
+        return Ok(key);
+    }
+
+    if let Some(key) = parse_named_key(name) {
+        return Ok(key);
+    }
+
+    // Vim-style names in angle brackets, like `<Space>` or `<C-r>`
+    if name.len() > 2 && name.starts_with('<') && name.ends_with('>') {
+        let inner = &name[1..name.len() - 1];
+        if let Ok(key) = parse_key_name(inner) {
+            return Ok(KeyName::legacy(&key.canonical));
+        }
+    }
+
+    Err(unknown(name))
+}
+
+fn parse_ctrl_combination(name: &str) -> Option<KeyName> {
+    if name.starts_with('^') {
+        let chr = single_char(&name[1..])?;
+        let canonical = ctrl_key(chr)?;
+        return Some(if canonical == name {
+            KeyName::canonical(&canonical)
+        } else {
+            KeyName::legacy(&canonical)
+        });
+    }
+
+    CTRL_PREFIXES.iter().find_map(|prefix| {
+        let head = name.get(..prefix.len())?;
+        if head.eq_ignore_ascii_case(prefix) {
+            let chr = single_char(&name[prefix.len()..])?;
+            ctrl_key(chr).map(|canonical| KeyName::legacy(&canonical))
+        } else {
+            None
+        }
+    })
+}
+
+fn single_char(input: &str) -> Option<char> {
+    let mut chars = input.chars();
+    match (chars.next(), chars.next()) {
+        (Some(chr), None) => Some(chr),
+        _ => None,
+    }
+}
+
+/// Returns the canonical name of Ctrl + `chr`, if there is such a key.
+pub(crate) fn ctrl_key(chr: char) -> Option<String> {
+    let chr = chr.to_ascii_uppercase();
+    if ('@'..='_').contains(&chr) {
+        Some(format!("^{}", chr))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn parse_named_key(name: &str) -> Option<KeyName> {
+    if NAMED_KEYS.contains(&name) {
+        return Some(KeyName::canonical(name));
+    }
+
+    let upper = name.to_uppercase();
+    if let Some(key) = NAMED_KEYS.iter().find(|key| **key == upper) {
+        return Some(KeyName::legacy(key));
+    }
+
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == upper)
+        .map(|(_, key)| KeyName::legacy(key))
+}
+
+pub(crate) fn unknown(name: &str) -> UnknownKeyName {
+    let upper = name.to_uppercase();
+    let upper = upper.trim_start_matches('<').trim_end_matches('>');
+
+    UnknownKeyName {
+        name: name.to_string(),
+        suggestions: utils::suggest(NAMED_KEYS.iter().copied(), upper),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(name: &str) -> String {
+        parse_key_name(name).unwrap().canonical
+    }
+
+    #[test]
+    fn t_canonical_names_are_accepted_as_is() {
+        for name in &[
+            "a", "Q", "/", "^", "ENTER", "SPACE", "PPAGE", "F12", "^R", "^[",
+        ] {
+            let key = parse_key_name(name).unwrap();
+            assert_eq!(key.canonical, *name);
+            assert!(!key.is_legacy);
+            assert_eq!(key.warning(name), None);
+        }
+    }
+
+    #[test]
+    fn t_named_keys_are_case_insensitive() {
+        assert_eq!(canonical("Enter"), "ENTER");
+        assert_eq!(canonical("enter"), "ENTER");
+        assert_eq!(canonical("space"), "SPACE");
+        assert_eq!(canonical("f1"), "F1");
+        assert!(parse_key_name("Enter").unwrap().is_legacy);
+    }
+
+    #[test]
+    fn t_names_in_angle_brackets() {
+        assert_eq!(canonical("<SPACE>"), "SPACE");
+        assert_eq!(canonical("<Space>"), "SPACE");
+        assert_eq!(canonical("<CR>"), "ENTER");
+        assert_eq!(canonical("<Esc>"), "ESC");
+        assert_eq!(canonical("<C-r>"), "^R");
+        assert_eq!(canonical("<x>"), "x");
+        assert!(parse_key_name("<x>").unwrap().is_legacy);
+    }
+
+    #[test]
+    fn t_ctrl_combinations() {
+        assert_eq!(canonical("^r"), "^R");
+        assert_eq!(canonical("C-r"), "^R");
+        assert_eq!(canonical("C-R"), "^R");
+        assert_eq!(canonical("c-r"), "^R");
+        assert_eq!(canonical("Ctrl-r"), "^R");
+        assert_eq!(canonical("CTRL+R"), "^R");
+        assert_eq!(canonical("Control-r"), "^R");
+        assert!(parse_key_name("^r").unwrap().is_legacy);
+        assert!(parse_key_name("C-R").unwrap().is_legacy);
+    }
+
+    #[test]
+    fn t_aliases_from_other_programs() {
+        assert_eq!(canonical("Return"), "ENTER");
+        assert_eq!(canonical("Escape"), "ESC");
+        assert_eq!(canonical("BS"), "BACKSPACE");
+        assert_eq!(canonical("PageUp"), "PPAGE");
+        assert_eq!(canonical("PgDn"), "NPAGE");
+        assert_eq!(canonical("Delete"), "DC");
+        assert_eq!(canonical("Insert"), "IC");
+    }
+
+    #[test]
+    fn t_literal_space_is_the_space_key() {
+        let key = parse_key_name(" ").unwrap();
+        assert_eq!(key.canonical, "SPACE");
+        assert!(key.is_legacy);
+    }
+
+    #[test]
+    fn t_warning_mentions_both_spellings() {
+        let key = parse_key_name("C-r").unwrap();
+        assert_eq!(
+            key.warning("C-r"),
+            Some("Warning: key `C-r' should be written as `^R'".to_string())
+        );
+    }
+
+    #[test]
+    fn t_unknown_names_are_rejected() {
+        for name in &[
+            "", "FOO", "C-", "C-ab", "^1", "M-x", "<>", "<FOO>", "ENTERR x",
+        ] {
+            assert!(parse_key_name(name).is_err(), "{:?} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn t_unknown_names_come_with_suggestions() {
+        let error = parse_key_name("ENTR").unwrap_err();
+        assert_eq!(error.suggestions, vec!["ENTER", "END"]);
+        assert_eq!(
+            error.to_message(),
+            "`ENTR' is not a valid key name. Did you mean: ENTER, END?"
+        );
+
+        let error = parse_key_name("<Pgae>").unwrap_err();
+        assert_eq!(error.suggestions, vec!["PPAGE"]);
+
+        let error = parse_key_name("F13").unwrap_err();
+        assert_eq!(error.suggestions, vec!["F1", "F3", "F10"]);
+    }
+
+    #[test]
+    fn t_no_suggestions_for_gibberish() {
+        let error = parse_key_name("xyzzyplugh").unwrap_err();
+        assert!(error.suggestions.is_empty());
+        assert_eq!(error.to_message(), "`xyzzyplugh' is not a valid key name");
+    }
+}
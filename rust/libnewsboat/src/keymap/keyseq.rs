@@ -0,0 +1,487 @@
+//! Parses key sequences of multi-key bindings, like `gg` or `,q`.
+//!
+//! A sequence is a string of key presses. Printable characters stand for themselves; special keys
+//! and modifier combinations are written in angle brackets, Vim-style: `<ENTER>`, `<F1>`, `<C-x>`,
+//! `<M-a>`. Names in brackets are case-insensitive, and a literal `<` is written as `<LT>`.
+//!
+//! For compatibility with single-key bindings, `^X` still means Ctrl-X, and a sequence that is
+//! exactly the canonical name of a special key, like `ENTER`, means that key. To bind the letters
+//! instead, bracket one of them: `<E>NTER`.
+
+use crate::keycombination::{self, NAMED_KEYS};
+use gettextrs::gettext;
+use std::error;
+use std::fmt;
+use strprintf::fmt;
+
+/// A single key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    /// A printable character.
+    Char(char),
+    /// A special key, by its canonical name, e.g. `ENTER` or `F1`.
+    Named(String),
+    /// Ctrl and a character, e.g. `X` for Ctrl-X; always between `@` and `_`.
+    Ctrl(char),
+    /// Meta (Alt) and a key.
+    Meta(Box<Key>),
+}
+
+impl Key {
+    /// How the key is written between angle brackets.
+    fn bracketed_name(&self) -> String {
+        match self {
+            Key::Char('<') => "LT".to_string(),
+            Key::Char(chr) => chr.to_string(),
+            Key::Named(name) => name.clone(),
+            Key::Ctrl(chr) => format!("C-{}", chr),
+            Key::Meta(key) => format!("M-{}", key.bracketed_name()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    /// Writes the key the way `parse_key_sequence` reads it back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // A bare caret could turn into Ctrl together with the next key
+            Key::Char('<') | Key::Char('^') => write!(f, "<{}>", self.bracketed_name()),
+            Key::Char(chr) => write!(f, "{}", chr),
+            Key::Ctrl(chr) => write!(f, "^{}", chr),
+            Key::Named(_) | Key::Meta(_) => write!(f, "<{}>", self.bracketed_name()),
+        }
+    }
+}
+
+/// A key sequence that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySeqError {
+    /// The sequence has no keys at all.
+    Empty,
+    /// A `<` that is never closed; holds the rest of the sequence, starting at the `<`.
+    Unterminated(String),
+    /// A name in angle brackets that isn't a key, as written (brackets included), along with
+    /// named keys that look similar.
+    UnknownName {
+        name: String,
+        suggestions: Vec<&'static str>,
+    },
+    /// Ctrl combined with something a terminal can't send, e.g. `<C-1>`, as written.
+    InvalidCtrl(String),
+}
+
+impl KeySeqError {
+    /// Internationalized description of the error.
+    pub fn to_message(&self) -> String {
+        match self {
+            KeySeqError::Empty => gettext("key sequence is empty"),
+            KeySeqError::Unterminated(rest) => fmt!(
+                &gettext("`%s' is missing the closing `>' (write `<LT>' for a literal `<')"),
+                rest
+            ),
+            KeySeqError::UnknownName { name, suggestions } => keycombination::UnknownKeyName {
+                name: name.clone(),
+                suggestions: suggestions.clone(),
+            }
+            .to_message(),
+            KeySeqError::InvalidCtrl(name) => fmt!(
+                &gettext("`%s' is not a key combination that terminals can send"),
+                name
+            ),
+        }
+    }
+}
+
+impl fmt::Display for KeySeqError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_message())
+    }
+}
+
+impl error::Error for KeySeqError {}
+
+/// Splits a binding specification into individual key presses.
+///
+/// ```
+/// use libnewsboat::keymap::keyseq::{parse_key_sequence, Key};
+///
+/// assert_eq!(
+///     parse_key_sequence(",<C-q>").unwrap(),
+///     vec![Key::Char(','), Key::Ctrl('Q')]
+/// );
+/// assert!(parse_key_sequence("<Entre>").is_err());
+/// ```
+pub fn parse_key_sequence(input: &str) -> Result<Vec<Key>, KeySeqError> {
+    if NAMED_KEYS.contains(&input) {
+        return Ok(vec![Key::Named(input.to_string())]);
+    }
+
+    let mut keys = Vec::new();
+    let mut rest = input;
+    while let Some(chr) = rest.chars().next() {
+        match chr {
+            '<' => {
+                let end = rest
+                    .find('>')
+                    .ok_or_else(|| KeySeqError::Unterminated(rest.to_string()))?;
+                keys.push(parse_bracketed(&rest[..=end])?);
+                rest = &rest[end + 1..];
+            }
+            '^' => {
+                let mut chars = rest[1..].chars();
+                match chars.next().and_then(keycombination::ctrl_key) {
+                    Some(ctrl) => {
+                        keys.push(ctrl_from_canonical(&ctrl));
+                        rest = chars.as_str();
+                    }
+                    // `^` on its own, or followed by something that can't be combined with Ctrl
+                    None => {
+                        keys.push(Key::Char('^'));
+                        rest = &rest[1..];
+                    }
+                }
+            }
+            // STFL reports the space bar as "SPACE", so a literal space would never match
+            ' ' => {
+                keys.push(Key::Named("SPACE".to_string()));
+                rest = &rest[1..];
+            }
+            _ => {
+                keys.push(Key::Char(chr));
+                rest = &rest[chr.len_utf8()..];
+            }
+        }
+    }
+
+    if keys.is_empty() {
+        return Err(KeySeqError::Empty);
+    }
+    Ok(keys)
+}
+
+/// Parses one `<...>` group, brackets included.
+fn parse_bracketed(written: &str) -> Result<Key, KeySeqError> {
+    let unknown = || {
+        let error = keycombination::unknown(written);
+        KeySeqError::UnknownName {
+            name: error.name,
+            suggestions: error.suggestions,
+        }
+    };
+
+    let mut inner = &written[1..written.len() - 1];
+    let mut ctrl = false;
+    let mut meta = false;
+    loop {
+        let prefix = match inner.get(..2) {
+            Some(prefix) if inner.len() > 2 => prefix.to_ascii_uppercase(),
+            _ => break,
+        };
+        match prefix.as_str() {
+            "C-" => ctrl = true,
+            "M-" | "A-" => meta = true,
+            _ => break,
+        }
+        inner = &inner[2..];
+    }
+
+    let key = if inner.eq_ignore_ascii_case("LT") {
+        Key::Char('<')
+    } else if let Some(chr) = single_char(inner) {
+        Key::Char(chr)
+    } else if let Some(named) = keycombination::parse_named_key(inner) {
+        Key::Named(named.canonical)
+    } else {
+        return Err(unknown());
+    };
+
+    let key = if ctrl {
+        match key {
+            Key::Char(chr) => match keycombination::ctrl_key(chr) {
+                Some(canonical) => ctrl_from_canonical(&canonical),
+                None => return Err(KeySeqError::InvalidCtrl(written.to_string())),
+            },
+            _ => return Err(KeySeqError::InvalidCtrl(written.to_string())),
+        }
+    } else {
+        key
+    };
+
+    if meta {
+        Ok(Key::Meta(Box::new(key)))
+    } else {
+        Ok(key)
+    }
+}
+
+/// Turns `^X`, as returned by `keycombination::ctrl_key`, into a `Key`.
+fn ctrl_from_canonical(canonical: &str) -> Key {
+    // `ctrl_key` always returns a caret followed by one ASCII character
+    Key::Ctrl(canonical[1..].chars().next().unwrap_or('@'))
+}
+
+fn single_char(input: &str) -> Option<char> {
+    let mut chars = input.chars();
+    match (chars.next(), chars.next()) {
+        (Some(chr), None) => Some(chr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> Key {
+        Key::Named(name.to_string())
+    }
+
+    /// Every special key that `KeyMap` accepts in `bind-key`.
+    const SPECIAL_KEYS: &[&str] = &[
+        "ENTER",
+        "BACKSPACE",
+        "LEFT",
+        "RIGHT",
+        "UP",
+        "DOWN",
+        "PPAGE",
+        "NPAGE",
+        "HOME",
+        "END",
+        "ESC",
+        "TAB",
+        "SPACE",
+        "DC",
+        "IC",
+        "BTAB",
+        "F1",
+        "F2",
+        "F3",
+        "F4",
+        "F5",
+        "F6",
+        "F7",
+        "F8",
+        "F9",
+        "F10",
+        "F11",
+        "F12",
+    ];
+
+    #[test]
+    fn t_plain_characters_are_one_key_each() {
+        assert_eq!(
+            parse_key_sequence("gg").unwrap(),
+            vec![Key::Char('g'), Key::Char('g')]
+        );
+        assert_eq!(
+            parse_key_sequence(",q").unwrap(),
+            vec![Key::Char(','), Key::Char('q')]
+        );
+        assert_eq!(
+            parse_key_sequence("äö>").unwrap(),
+            vec![Key::Char('ä'), Key::Char('ö'), Key::Char('>')]
+        );
+        assert_eq!(
+            parse_key_sequence("a b").unwrap(),
+            vec![Key::Char('a'), named("SPACE"), Key::Char('b')]
+        );
+    }
+
+    #[test]
+    fn t_every_special_key_can_be_written_in_brackets() {
+        for name in SPECIAL_KEYS {
+            let expected = vec![named(name)];
+            assert_eq!(
+                parse_key_sequence(&format!("<{}>", name)).unwrap(),
+                expected,
+                "{}",
+                name
+            );
+            assert_eq!(
+                parse_key_sequence(&format!("<{}>", name.to_lowercase())).unwrap(),
+                expected,
+                "{}",
+                name
+            );
+            assert_eq!(
+                parse_key_sequence(&format!("g<{}>", name)).unwrap(),
+                vec![Key::Char('g'), named(name)],
+                "{}",
+                name
+            );
+            assert_eq!(
+                parse_key_sequence(&format!("<M-{}>", name)).unwrap(),
+                vec![Key::Meta(Box::new(named(name)))],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn t_every_special_key_on_its_own_keeps_its_old_meaning() {
+        for name in SPECIAL_KEYS {
+            assert_eq!(parse_key_sequence(name).unwrap(), vec![named(name)]);
+        }
+        // Only the canonical spelling, and only on its own
+        assert_eq!(parse_key_sequence("end").unwrap().len(), 3);
+        assert_eq!(parse_key_sequence("ENDEND").unwrap().len(), 6);
+        assert_eq!(
+            parse_key_sequence("<E>ND").unwrap(),
+            vec![Key::Char('E'), Key::Char('N'), Key::Char('D')]
+        );
+    }
+
+    #[test]
+    fn t_aliases_are_accepted_in_brackets() {
+        assert_eq!(parse_key_sequence("<CR>").unwrap(), vec![named("ENTER")]);
+        assert_eq!(parse_key_sequence("<Esc>").unwrap(), vec![named("ESC")]);
+        assert_eq!(
+            parse_key_sequence("<PageUp>").unwrap(),
+            vec![named("PPAGE")]
+        );
+        assert_eq!(parse_key_sequence("<Del>").unwrap(), vec![named("DC")]);
+    }
+
+    #[test]
+    fn t_caret_means_ctrl_for_every_key_keymap_accepts() {
+        for chr in (b'@'..=b'_').map(char::from) {
+            assert_eq!(
+                parse_key_sequence(&format!("^{}", chr)).unwrap(),
+                vec![Key::Ctrl(chr)],
+                "^{}",
+                chr
+            );
+        }
+        assert_eq!(parse_key_sequence("^r").unwrap(), vec![Key::Ctrl('R')]);
+        assert_eq!(
+            parse_key_sequence("^Xq").unwrap(),
+            vec![Key::Ctrl('X'), Key::Char('q')]
+        );
+    }
+
+    #[test]
+    fn t_caret_that_cant_be_ctrl_is_a_literal_caret() {
+        assert_eq!(parse_key_sequence("^").unwrap(), vec![Key::Char('^')]);
+        assert_eq!(
+            parse_key_sequence("^1").unwrap(),
+            vec![Key::Char('^'), Key::Char('1')]
+        );
+        assert_eq!(
+            parse_key_sequence("x^").unwrap(),
+            vec![Key::Char('x'), Key::Char('^')]
+        );
+    }
+
+    #[test]
+    fn t_modifier_combinations() {
+        assert_eq!(parse_key_sequence("<C-x>").unwrap(), vec![Key::Ctrl('X')]);
+        assert_eq!(parse_key_sequence("<c-X>").unwrap(), vec![Key::Ctrl('X')]);
+        assert_eq!(
+            parse_key_sequence("<M-a>").unwrap(),
+            vec![Key::Meta(Box::new(Key::Char('a')))]
+        );
+        assert_eq!(
+            parse_key_sequence("<A-a>").unwrap(),
+            vec![Key::Meta(Box::new(Key::Char('a')))]
+        );
+        assert_eq!(
+            parse_key_sequence("<M-C-x>").unwrap(),
+            vec![Key::Meta(Box::new(Key::Ctrl('X')))]
+        );
+        assert_eq!(
+            parse_key_sequence("<C-M-x>").unwrap(),
+            vec![Key::Meta(Box::new(Key::Ctrl('X')))]
+        );
+        assert_eq!(
+            parse_key_sequence("<C-->").unwrap_err().to_string(),
+            "`<C-->' is not a key combination that terminals can send"
+        );
+        assert_eq!(
+            parse_key_sequence("<C-ENTER>").unwrap_err(),
+            KeySeqError::InvalidCtrl("<C-ENTER>".to_string())
+        );
+        assert_eq!(
+            parse_key_sequence("<C-1>").unwrap_err(),
+            KeySeqError::InvalidCtrl("<C-1>".to_string())
+        );
+    }
+
+    #[test]
+    fn t_single_characters_in_brackets() {
+        assert_eq!(parse_key_sequence("<x>").unwrap(), vec![Key::Char('x')]);
+        assert_eq!(parse_key_sequence("<C>").unwrap(), vec![Key::Char('C')]);
+        assert_eq!(parse_key_sequence("<->").unwrap(), vec![Key::Char('-')]);
+    }
+
+    #[test]
+    fn t_literal_less_than_is_written_as_lt() {
+        assert_eq!(parse_key_sequence("<LT>").unwrap(), vec![Key::Char('<')]);
+        assert_eq!(
+            parse_key_sequence("<lt>q").unwrap(),
+            vec![Key::Char('<'), Key::Char('q')]
+        );
+        assert_eq!(
+            parse_key_sequence("<M-LT>").unwrap(),
+            vec![Key::Meta(Box::new(Key::Char('<')))]
+        );
+    }
+
+    #[test]
+    fn t_unterminated_brackets_are_an_error() {
+        let error = parse_key_sequence("g<q").unwrap_err();
+        assert_eq!(error, KeySeqError::Unterminated("<q".to_string()));
+        assert_eq!(
+            error.to_message(),
+            "`<q' is missing the closing `>' (write `<LT>' for a literal `<')"
+        );
+        assert!(parse_key_sequence("<").is_err());
+    }
+
+    #[test]
+    fn t_unknown_names_in_brackets_are_an_error_naming_them() {
+        let error = parse_key_sequence("g<Entre>").unwrap_err();
+        assert_eq!(
+            error,
+            KeySeqError::UnknownName {
+                name: "<Entre>".to_string(),
+                suggestions: vec!["ENTER"],
+            }
+        );
+        assert_eq!(
+            error.to_message(),
+            "`<Entre>' is not a valid key name. Did you mean: ENTER?"
+        );
+
+        for input in &["<>", "<FOO>", "<F13>", "<C->", "<qq>", "<C-xy>"] {
+            match parse_key_sequence(input) {
+                Err(KeySeqError::UnknownName { name, .. }) => assert_eq!(name, *input),
+                other => panic!("{:?} gave {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn t_empty_sequence_is_an_error() {
+        assert_eq!(parse_key_sequence(""), Err(KeySeqError::Empty));
+        assert_eq!(KeySeqError::Empty.to_message(), "key sequence is empty");
+    }
+
+    #[test]
+    fn t_keys_are_displayed_the_way_they_are_parsed() {
+        let sequences = [
+            "gg",
+            "<LT>q",
+            "^R<ENTER>",
+            "<M-a><M-C-X><M-F1><M-LT>",
+            "<^>R<SPACE>",
+        ];
+        for sequence in &sequences {
+            let keys = parse_key_sequence(sequence).unwrap();
+            let written = keys.iter().map(Key::to_string).collect::<String>();
+            assert_eq!(written, *sequence);
+            assert_eq!(parse_key_sequence(&written).unwrap(), keys);
+        }
+    }
+}
@@ -0,0 +1,2713 @@
+//! Tokenizes operation sequences used in macros and on the internal commandline.
+
+pub mod keyseq;
+
+use crate::listposition;
+use crate::utils;
+use gettextrs::gettext;
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag},
+    character::complete::{space0, space1},
+    combinator::{complete, map, opt, recognize, value, verify},
+    error::ErrorKind,
+    multi::{many0, many1, separated_nonempty_list},
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult, Offset,
+};
+use strprintf::fmt;
+
+fn unquoted_token(input: &str) -> IResult<&str, String> {
+    let parser = map(recognize(is_not("\t \";")), String::from);
+    let parser = complete(parser);
+    parser(input)
+}
+
+/// Like `unquoted_token`, but also stops at equals sign. Used for arguments of `set` command on
+/// the commandline, which may be written as `set name=value`.
+fn unquoted_assignment_token(input: &str) -> IResult<&str, String> {
+    let parser = map(recognize(is_not("\t \";=")), String::from);
+    let parser = complete(parser);
+    parser(input)
+}
+
+fn quoted_token(input: &str) -> IResult<&str, String> {
+    // Escaped backticks are passed through, still escaped. They're un-escaped by
+    // ConfigParser::evaluate_backticks
+    quoted_token_with_escaped_backtick(input, r#"\`"#, true)
+}
+
+/// Like `quoted_token`, but the closing quote is required.
+fn closed_quoted_token(input: &str) -> IResult<&str, String> {
+    quoted_token_with_escaped_backtick(input, r#"\`"#, false)
+}
+
+/// The text of the invalid escape sequence at `input`, which starts right after its backslash,
+/// for error messages.
+fn invalid_escape_text(input: &str) -> String {
+    let len = if input.starts_with("u{") {
+        input.find(&['}', '"'][..]).map(|i| i + 1)
+    } else if input.starts_with('u') {
+        Some(5)
+    } else {
+        Some(3)
+    };
+    let text: String = match len {
+        Some(len) => input.chars().take(len).take_while(|&c| c != '"').collect(),
+        None => input.to_string(),
+    };
+    format!("\\{}", text)
+}
+
+/// Like `quoted_token`, but an escaped backtick turns into `escaped_backtick`. Unless
+/// `close_at_end` is set, a missing closing quote makes the parser fail.
+fn quoted_token_with_escaped_backtick<'a>(
+    input: &'a str,
+    escaped_backtick: &'static str,
+    close_at_end: bool,
+) -> IResult<&'a str, String> {
+    let (rest, contents) = quoted_contents(input, close_at_end)?;
+    match utils::unescape_quoted_strict(contents, escaped_backtick) {
+        Ok(token) => Ok((rest, token)),
+        // An invalid escape makes the quoted token fail to parse at all; `tokenize` turns that into
+        // `InvalidEscape`, pointing right after the backslash
+        Err(error) => Err(nom::Err::Failure((
+            &contents[error.offset + 1..],
+            ErrorKind::Escaped,
+        ))),
+    }
+}
+
+/// The text between the double quote that `input` starts with and the closing quote, with the
+/// escapes still in it. A missing closing quote at the end of the input is implicitly added, if
+/// `close_at_end` is set; a backslash at the very end escapes the quote, and can't be closed.
+fn quoted_contents(input: &str, close_at_end: bool) -> IResult<&str, &str> {
+    if !input.starts_with('"') {
+        return Err(nom::Err::Error((input, ErrorKind::Tag)));
+    }
+    let contents = &input[1..];
+    let mut chars = contents.char_indices();
+    while let Some((idx, chr)) = chars.next() {
+        match chr {
+            '"' => return Ok((&contents[idx + 1..], &contents[..idx])),
+            '\\' => {
+                if chars.next().is_none() {
+                    return Err(nom::Err::Error((input, ErrorKind::Escaped)));
+                }
+            }
+            _ => {}
+        }
+    }
+    if close_at_end {
+        Ok((&contents[contents.len()..], contents))
+    } else {
+        Err(nom::Err::Error((input, ErrorKind::Tag)))
+    }
+}
+
+/// A token in single quotes, like in the shell: everything up to the next single quote is taken
+/// as is, backslashes included. A missing closing quote at the end of the input is implicitly
+/// added.
+fn single_quoted_token(input: &str) -> IResult<&str, String> {
+    single_quoted(input, true)
+}
+
+/// Like `single_quoted_token`, but the closing quote is required.
+fn closed_single_quoted_token(input: &str) -> IResult<&str, String> {
+    single_quoted(input, false)
+}
+
+fn single_quoted(input: &str, close_at_end: bool) -> IResult<&str, String> {
+    let contents = map(opt(is_not("'")), |text: Option<&str>| {
+        text.unwrap_or_default().to_string()
+    });
+    let implicit_quote = |input| {
+        if close_at_end {
+            end_of_input(input)
+        } else {
+            Err(nom::Err::Error((input, ErrorKind::Tag)))
+        }
+    };
+    let closing_quote = alt((tag("'"), implicit_quote));
+    let parser = delimited(tag("'"), contents, closing_quote);
+    let parser = complete(parser);
+    parser(input)
+}
+
+/// Succeeds only if there is no input left.
+fn end_of_input(input: &str) -> IResult<&str, &str> {
+    if input.is_empty() {
+        Ok((input, input))
+    } else {
+        Err(nom::Err::Error((input, nom::error::ErrorKind::Eof)))
+    }
+}
+
+fn token(input: &str) -> IResult<&str, String> {
+    let parser = alt((quoted_token, single_quoted_token, unquoted_token));
+    parser(input)
+}
+
+fn assignment_token(input: &str) -> IResult<&str, String> {
+    let parser = alt((quoted_token, single_quoted_token, unquoted_assignment_token));
+    parser(input)
+}
+
+/// Runs the next operation only if the previous one succeeded.
+pub const AND_THEN: &str = "&&";
+
+/// Runs the next operation only if the previous one failed.
+pub const OR_ELSE: &str = "||";
+
+fn is_connector(token: &str) -> bool {
+    token == AND_THEN || token == OR_ELSE
+}
+
+/// Separates the operations of a macro from its description.
+pub const DESCRIPTION_SEPARATOR: &str = "--";
+
+/// Part of a token returned by `tokenize_operation_sequence_with_backticks`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// Text that is used as is.
+    Literal(String),
+    /// Shell command that was enclosed in backticks, and whose output goes in its place.
+    ShellEval(String),
+}
+
+/// A token returned by `tokenize_operation_sequence_with_backticks`: the parts that make up the
+/// token once the output of its shell commands is put in.
+pub type Argument = Vec<Token>;
+
+/// What the tokens of an operation sequence are parsed into.
+trait SequenceToken: Sized {
+    /// Whether a quote or a backtick that isn't closed by the end of the input is implicitly
+    /// closed. If it isn't, that's an error.
+    const CLOSES_AT_END: bool = true;
+
+    fn quoted(input: &str) -> IResult<&str, Self>;
+    fn unquoted(input: &str) -> IResult<&str, Self>;
+    /// The text of a macro's description.
+    fn description(input: &str) -> IResult<&str, String> {
+        quoted_token(input)
+    }
+    /// The token that stands for a connector in the sequence.
+    fn connector(connector: &str) -> Self;
+    /// Whether the token consists of nothing but the text `word`.
+    fn is_word(&self, word: &str) -> bool;
+}
+
+impl SequenceToken for String {
+    fn quoted(input: &str) -> IResult<&str, Self> {
+        alt((quoted_token, single_quoted_token))(input)
+    }
+
+    fn unquoted(input: &str) -> IResult<&str, Self> {
+        unquoted_token(input)
+    }
+
+    fn connector(connector: &str) -> Self {
+        connector.to_string()
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self == word
+    }
+}
+
+impl SequenceToken for Argument {
+    fn quoted(input: &str) -> IResult<&str, Self> {
+        // Inside double quotes, backticks are just characters, and escaping them changes nothing
+        let parser = |input| quoted_token_with_escaped_backtick(input, "`", true);
+        let parser = alt((parser, single_quoted_token));
+        map(parser, |text| vec![Token::Literal(text)])(input)
+    }
+
+    fn unquoted(input: &str) -> IResult<&str, Self> {
+        let literal = alt((
+            value("`", tag(r"\`")),
+            is_not("\t \";`\\"),
+            // A backslash that doesn't escape a backtick is just a backslash
+            tag(r"\"),
+        ));
+        let literal = map(literal, |text: &str| Token::Literal(text.to_string()));
+        let shell_eval = map(backtick_span, Token::ShellEval);
+        let parser = map(many1(alt((shell_eval, literal))), merge_literals);
+        let parser = complete(parser);
+        parser(input)
+    }
+
+    fn connector(connector: &str) -> Self {
+        vec![Token::Literal(connector.to_string())]
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.as_slice() == [Token::Literal(word.to_string())]
+    }
+}
+
+/// A token returned by `tokenize_operation_sequence_strict`: like `String`, except that quotes
+/// have to be closed.
+struct StrictToken(String);
+
+impl SequenceToken for StrictToken {
+    const CLOSES_AT_END: bool = false;
+
+    fn quoted(input: &str) -> IResult<&str, Self> {
+        map(
+            alt((closed_quoted_token, closed_single_quoted_token)),
+            StrictToken,
+        )(input)
+    }
+
+    fn unquoted(input: &str) -> IResult<&str, Self> {
+        // A single quote that isn't closed doesn't start an unquoted token instead
+        let parser = verify(unquoted_token, |token: &str| !token.starts_with('\''));
+        map(parser, StrictToken)(input)
+    }
+
+    fn description(input: &str) -> IResult<&str, String> {
+        closed_quoted_token(input)
+    }
+
+    fn connector(connector: &str) -> Self {
+        StrictToken(connector.to_string())
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.0 == word
+    }
+}
+
+/// Part of a token returned by `tokenize_operation_sequence_with_env`.
+#[derive(Debug, Clone, PartialEq)]
+enum EnvPart {
+    /// Text that is used as is.
+    Literal(String),
+    /// Name of the environment variable whose value goes in its place.
+    Variable(String),
+}
+
+/// A token returned by `tokenize_operation_sequence_with_env`, before the variables are looked
+/// up.
+struct EnvToken(Vec<EnvPart>);
+
+impl EnvToken {
+    fn expand<F: FnMut(&str) -> Option<String>>(self, lookup: &mut F) -> String {
+        let mut result = String::new();
+        for part in self.0 {
+            match part {
+                EnvPart::Literal(text) => result.push_str(&text),
+                // Unset variables expand to nothing, like in the shell
+                EnvPart::Variable(name) => result.push_str(&lookup(&name).unwrap_or_default()),
+            }
+        }
+        result
+    }
+}
+
+impl SequenceToken for EnvToken {
+    fn quoted(input: &str) -> IResult<&str, Self> {
+        let single_quoted = map(single_quoted_token, |text| {
+            EnvToken(vec![EnvPart::Literal(text)])
+        });
+        alt((env_quoted_token, single_quoted))(input)
+    }
+
+    fn unquoted(input: &str) -> IResult<&str, Self> {
+        // Backslashes are literal in unquoted tokens, except for the one that escapes a dollar
+        map(unquoted_token, |token| {
+            EnvToken(env_parts(&token, |text| text.replace(r"\$", "$")))
+        })(input)
+    }
+
+    fn connector(connector: &str) -> Self {
+        EnvToken(vec![EnvPart::Literal(connector.to_string())])
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.0.as_slice() == [EnvPart::Literal(word.to_string())]
+    }
+}
+
+/// A double-quoted token, split into literals and references to environment variables.
+fn env_quoted_token(input: &str) -> IResult<&str, EnvToken> {
+    // Fails the same way as `quoted_token` does, e.g. on an invalid escape
+    let (rest, _) = quoted_token(input)?;
+    let (_, contents) = quoted_contents(input, true)?;
+    Ok((rest, EnvToken(env_parts(contents, utils::unescape_quoted))))
+}
+
+/// Splits the `text` of a token into literals and references to environment variables: `$NAME`
+/// and `${NAME}`, where the name is made of ASCII letters, digits and underscores, and doesn't
+/// start with a digit. A dollar sign that isn't followed by a name, or is escaped (`\$`), is
+/// literal. `unescape` is applied to the literal parts.
+fn env_parts<F: Fn(&str) -> String>(text: &str, unescape: F) -> Vec<EnvPart> {
+    fn name_length(text: &str) -> usize {
+        let starts_with_digit = text.chars().next().map_or(false, |c| c.is_ascii_digit());
+        if starts_with_digit {
+            return 0;
+        }
+        text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| text.len())
+    }
+
+    let mut parts = Vec::new();
+    let mut literal_start = 0;
+    let mut chars = text.char_indices();
+    while let Some((idx, chr)) = chars.next() {
+        match chr {
+            '\\' => {
+                chars.next();
+            }
+            '$' => {
+                let after = &text[idx + 1..];
+                let (name, length) = if after.starts_with('{') {
+                    let length = name_length(&after[1..]);
+                    if length > 0 && after[1 + length..].starts_with('}') {
+                        (&after[1..1 + length], length + 2)
+                    } else {
+                        continue;
+                    }
+                } else {
+                    let length = name_length(after);
+                    if length == 0 {
+                        continue;
+                    }
+                    (&after[..length], length)
+                };
+
+                if literal_start < idx {
+                    parts.push(EnvPart::Literal(unescape(&text[literal_start..idx])));
+                }
+                parts.push(EnvPart::Variable(name.to_string()));
+                literal_start = idx + 1 + length;
+                // Skip the name; it's all ASCII, so one character is one byte
+                for _ in 0..length {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    if literal_start < text.len() || parts.is_empty() {
+        parts.push(EnvPart::Literal(unescape(&text[literal_start..])));
+    }
+    parts
+}
+
+/// A shell command enclosed in backticks, like `` `date` ``. Inside, an escaped backtick stands
+/// for a backtick and doesn't end the command; other backslashes are left for the shell. A missing
+/// closing backtick at the end of the input is implicitly added.
+fn backtick_span(input: &str) -> IResult<&str, String> {
+    let character = alt((value("`", tag(r"\`")), tag(r"\"), is_not(r"`\")));
+    let command = map(many0(character), |parts: Vec<&str>| parts.concat());
+    let closing_backtick = alt((tag("`"), end_of_input));
+    delimited(tag("`"), command, closing_backtick)(input)
+}
+
+/// Joins adjacent literals into one.
+fn merge_literals(tokens: Vec<Token>) -> Argument {
+    let mut result: Argument = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match (result.last_mut(), token) {
+            (Some(Token::Literal(previous)), Token::Literal(text)) => previous.push_str(&text),
+            (_, token) => result.push(token),
+        }
+    }
+    result
+}
+
+/// Like `token`, but an unquoted `&&` or `||` is a connector rather than a token. In the
+/// definition of a macro, so is an unquoted `--`, which starts the description.
+fn operation_token<T: SequenceToken>(input: &str, in_macro: bool) -> IResult<&str, T> {
+    let unquoted = verify(T::unquoted, |token: &T| {
+        let starts_description = in_macro && token.is_word(DESCRIPTION_SEPARATOR);
+        !(token.is_word(AND_THEN) || token.is_word(OR_ELSE) || starts_description)
+    });
+    let parser = alt((T::quoted, unquoted));
+    parser(input)
+}
+
+fn operation_with_args<T: SequenceToken>(input: &str, in_macro: bool) -> IResult<&str, Vec<T>> {
+    let parser = separated_nonempty_list(space1, |input| operation_token(input, in_macro));
+    parser(input)
+}
+
+fn semicolon(input: &str) -> IResult<&str, &str> {
+    delimited(space0, tag(";"), space0)(input)
+}
+
+fn connector<T: SequenceToken>(input: &str) -> IResult<&str, Vec<T>> {
+    let parser = alt((tag(AND_THEN), tag(OR_ELSE)));
+    let parser = map(parser, |connector: &str| vec![T::connector(connector)]);
+    delimited(space1, parser, space1)(input)
+}
+
+/// An operation, and the connector that precedes it if there is one.
+fn next_operation<T: SequenceToken>(input: &str, in_macro: bool) -> IResult<&str, Vec<Vec<T>>> {
+    let operation = |input| operation_with_args(input, in_macro);
+    let after_semicolon = map(preceded(many1(semicolon), operation), |op| vec![op]);
+    let after_connector = map(tuple((connector, operation)), |(connector, op)| {
+        vec![connector, op]
+    });
+    alt((after_connector, after_semicolon))(input)
+}
+
+/// The description at the end of a macro's definition: `-- "text"`.
+fn description<T: SequenceToken>(input: &str) -> IResult<&str, String> {
+    let separator = tuple((space1, tag(DESCRIPTION_SEPARATOR), space1));
+    preceded(separator, T::description)(input)
+}
+
+fn operation_sequence<T: SequenceToken>(
+    input: &str,
+    in_macro: bool,
+) -> IResult<&str, (Vec<Vec<T>>, Option<String>)> {
+    let (input, _) = tuple((space0, many0(semicolon)))(input)?;
+    let (input, first) = opt(|input| operation_with_args(input, in_macro))(input)?;
+
+    let mut operations = Vec::new();
+    let mut input = input;
+    let mut text = None;
+    if let Some(first) = first {
+        operations.push(first);
+        let (rest, next) = many0(|input| next_operation(input, in_macro))(input)?;
+        operations.extend(next.into_iter().flatten());
+        input = rest;
+        if in_macro {
+            let (rest, found) = opt(description::<T>)(input)?;
+            text = found;
+            input = rest;
+        }
+    }
+
+    let parser = tuple((many0(semicolon), space0));
+    let (input, _) = complete(parser)(input)?;
+    Ok((input, (operations, text)))
+}
+
+/// Split a semicolon-separated list of operations into a vector. Each operation is represented by
+/// a non-empty sub-vector, where the first element is the name of the operation, and the rest of
+/// the elements are operation's arguments.
+///
+/// Instead of a semicolon, operations can be separated by `&&` or `||` surrounded by whitespace.
+/// The connector then appears in the vector on its own, as a sub-vector with a single element,
+/// between the two operations.
+///
+/// Tokens can be double-quoted. Such tokens can contain spaces and C-like escaped sequences: `\n`
+/// for newline, `\r` for carriage return, `\t` for tab, `\"` for double quote, `\\` for backslash.
+/// Unsupported sequences are stripped of the escaping, e.g. `\e` turns into `e`; the only
+/// exception is an escaped backtick, which is left escaped.
+///
+/// This function assumes that the input string:
+/// 1. doesn't contain a comment (see `tokenize_operation_sequence_with_comments` for input that
+///    does);
+/// 2. doesn't contain backticks that need to be processed (see
+///    `tokenize_operation_sequence_with_backticks` for input that does).
+///
+/// Returns `None` if the input could not be parsed; `tokenize_operation_sequence_verbose` says
+/// why.
+pub fn tokenize_operation_sequence(input: &str) -> Option<Vec<Vec<String>>> {
+    tokenize_operation_sequence_verbose(input).ok()
+}
+
+/// Like `tokenize_operation_sequence`, but returns where and why parsing failed, if it did.
+pub fn tokenize_operation_sequence_verbose(
+    input: &str,
+) -> Result<Vec<Vec<String>>, OperationSequenceError> {
+    tokenize(input, false).map(|(operations, _)| operations)
+}
+
+/// Like `tokenize_operation_sequence_verbose`, but for input that is worth being picky about, like
+/// config files: a quote that isn't closed is an error rather than implicitly closed at the end of
+/// the input. Errors hold the text that couldn't be tokenized, so that it can be shown to the
+/// user.
+pub fn tokenize_operation_sequence_strict(
+    input: &str,
+) -> Result<Vec<Vec<String>>, OperationSequenceError> {
+    tokenize_strict(input, false).map(|(operations, _)| operations)
+}
+
+/// Like `tokenize_macro_definition`, but strict in the same way as
+/// `tokenize_operation_sequence_strict`.
+pub fn tokenize_macro_definition_strict(
+    input: &str,
+) -> Result<(Vec<Vec<String>>, Option<String>), OperationSequenceError> {
+    tokenize_strict(input, true)
+}
+
+fn tokenize_strict(
+    input: &str,
+    in_macro: bool,
+) -> Result<(Vec<Vec<String>>, Option<String>), OperationSequenceError> {
+    let (operations, text) = tokenize::<StrictToken>(input, in_macro)?;
+    let operations = operations
+        .into_iter()
+        .map(|operation| operation.into_iter().map(|token| token.0).collect())
+        .collect();
+    Ok((operations, text))
+}
+
+/// Tokenizes the operations of a macro, which may be followed by a description of the macro: an
+/// unquoted `--` and a quoted string, like in `open; quit -- "open, then quit"`. Returns the
+/// operations along with the description, if there is one.
+///
+/// Only an unquoted `--` that is a token of its own starts the description, so `"--"` and
+/// `--new-tab` are tokens like any other. A `--` that isn't followed by a quoted string is an
+/// error.
+pub fn tokenize_macro_definition(
+    input: &str,
+) -> Result<(Vec<Vec<String>>, Option<String>), OperationSequenceError> {
+    tokenize(input, true)
+}
+
+/// Like `tokenize_operation_sequence_verbose`, but backticks are processed rather than assumed
+/// to be gone: a part of an unquoted token that is enclosed in backticks is a shell command, which
+/// the caller runs and replaces with its output. Each token is returned as the list of its parts,
+/// e.g. ``--title=`date`!`` is a literal `--title=`, the command `date`, and a literal `!`.
+///
+/// Inside double quotes, backticks are ordinary characters, so `"mpv `--no-video`"` is a single
+/// literal. An escaped backtick (`` \` ``) is a literal backtick, both inside and outside of
+/// quotes; within a command, it's a backtick that doesn't end the command. Like a quote, a
+/// backtick that isn't closed by the end of the input is implicitly closed.
+pub fn tokenize_operation_sequence_with_backticks(
+    input: &str,
+) -> Result<Vec<Vec<Argument>>, OperationSequenceError> {
+    tokenize(input, false).map(|(operations, _)| operations)
+}
+
+/// Like `tokenize_operation_sequence_verbose`, but environment variables are expanded, like in the
+/// shell: `$NAME` and `${NAME}` in double-quoted and unquoted tokens are replaced with the value
+/// that `lookup` returns for `NAME`, or with nothing if it returns `None`. Single-quoted tokens
+/// are left alone, and so are an escaped dollar sign (`\$`) and a dollar sign that isn't followed
+/// by a name.
+///
+/// For example, `set browser "$BROWSER --new-tab %u"` turns into `set`, `browser` and e.g.
+/// `firefox --new-tab %u`.
+pub fn tokenize_operation_sequence_with_env<F>(
+    input: &str,
+    mut lookup: F,
+) -> Result<Vec<Vec<String>>, OperationSequenceError>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let (operations, _) = tokenize::<EnvToken>(input, false)?;
+    Ok(operations
+        .into_iter()
+        .map(|operation| {
+            operation
+                .into_iter()
+                .map(|token| token.expand(&mut lookup))
+                .collect()
+        })
+        .collect())
+}
+
+fn tokenize<T: SequenceToken>(
+    input: &str,
+    in_macro: bool,
+) -> Result<(Vec<Vec<T>>, Option<String>), OperationSequenceError> {
+    // `operation_sequence` parser only fails on an invalid escape sequence: in the worst case, it
+    // consumes nothing and returns no operations, leaving the whole input as leftovers.
+    let (leftovers, sequence) = match operation_sequence(input, in_macro) {
+        Ok(result) => result,
+        Err(nom::Err::Failure((rest, _))) => {
+            return Err(OperationSequenceError::invalid_escape(input, rest))
+        }
+        Err(_) => (input, (Vec::new(), None)),
+    };
+    let chr = match leftovers.chars().next() {
+        None => return Ok(sequence),
+        Some(chr) => chr,
+    };
+
+    let kind = if leftovers.starts_with(AND_THEN) || leftovers.starts_with(OR_ELSE) {
+        // A connector must be followed by an operation
+        OperationSequenceErrorKind::MissingOperation(leftovers[..2].to_string())
+    } else if in_macro
+        && unquoted_token(leftovers)
+            .map(|(_, token)| token == DESCRIPTION_SEPARATOR)
+            .unwrap_or(false)
+    {
+        OperationSequenceErrorKind::MissingDescription
+    } else if chr == '"' && quoted_token(leftovers).is_err() {
+        OperationSequenceErrorKind::UnterminatedQuote
+    } else if !T::CLOSES_AT_END && is_unclosed_quote(leftovers) {
+        OperationSequenceErrorKind::UnclosedQuote(leftovers.to_string())
+    } else if !T::CLOSES_AT_END {
+        OperationSequenceErrorKind::UnexpectedText(leftovers.to_string())
+    } else {
+        OperationSequenceErrorKind::UnexpectedCharacter(chr)
+    };
+    Err(OperationSequenceError {
+        column: input[..input.offset(leftovers)].chars().count() + 1,
+        kind,
+    })
+}
+
+/// Whether `input` starts with a quoted token that is only closed because the input ends.
+fn is_unclosed_quote(input: &str) -> bool {
+    type Parser = fn(&str) -> IResult<&str, String>;
+    let implicitly_closed = |lenient: Parser, strict: Parser| {
+        lenient(input)
+            .map(|(rest, _)| rest.is_empty())
+            .unwrap_or(false)
+            && strict(input).is_err()
+    };
+    implicitly_closed(quoted_token, closed_quoted_token)
+        || implicitly_closed(single_quoted_token, closed_single_quoted_token)
+}
+
+/// Like `tokenize_operation_sequence_verbose`, but the input may end in a comment: a `#` outside
+/// of double quotes and backticks ends the sequence. Returns the operations along with the text of
+/// the comment after the `#`, if there is one.
+///
+/// A `#` in a quoted token is part of the token, escaped (`\#`) or not.
+pub fn tokenize_operation_sequence_with_comments(
+    input: &str,
+) -> Result<(Vec<Vec<String>>, Option<&str>), OperationSequenceError> {
+    let sequence = utils::strip_comments(input);
+    let comment = if sequence.len() < input.len() {
+        // `strip_comments` stops right before the `#`
+        Some(&input[sequence.len() + 1..])
+    } else {
+        None
+    };
+    let operations = tokenize_operation_sequence_verbose(sequence)?;
+    Ok((operations, comment))
+}
+
+/// Reasons why an operation sequence couldn't be tokenized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperationSequenceErrorKind {
+    /// The tokenizer stopped before the end of the input, at the given character.
+    UnexpectedCharacter(char),
+
+    /// A quoted token ends in a backslash, which escapes the closing quote.
+    UnterminatedQuote,
+
+    /// The given connector isn't followed by an operation.
+    MissingOperation(String),
+
+    /// The `--` in a macro's definition isn't followed by a quoted description.
+    MissingDescription,
+
+    /// A `\x` or `\u` escape in a quoted token has digits that aren't hexadecimal, or stands for
+    /// something that isn't a character. Holds the sequence, e.g. `\uD800`.
+    InvalidEscape(String),
+
+    /// In strict mode, a quoted token isn't closed by the end of the input. Holds the input from
+    /// the opening quote on.
+    UnclosedQuote(String),
+
+    /// In strict mode, the tokenizer stopped before the end of the input. Holds the input that
+    /// was left over.
+    UnexpectedText(String),
+}
+
+/// An error produced by `tokenize_operation_sequence_verbose` and `tokenize_macro_definition`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationSequenceError {
+    /// One-based offset of the offending character, in characters (not bytes).
+    pub column: usize,
+    pub kind: OperationSequenceErrorKind,
+}
+
+impl OperationSequenceError {
+    /// The error for the invalid escape sequence that starts with the backslash right before
+    /// `rest`, a suffix of `input`.
+    fn invalid_escape(input: &str, rest: &str) -> OperationSequenceError {
+        let backslash = input.offset(rest) - 1;
+        OperationSequenceError {
+            column: input[..backslash].chars().count() + 1,
+            kind: OperationSequenceErrorKind::InvalidEscape(invalid_escape_text(rest)),
+        }
+    }
+
+    /// Internationalized description of the error, without the column.
+    pub fn to_message(&self) -> String {
+        match self.kind {
+            OperationSequenceErrorKind::UnexpectedCharacter(chr) => {
+                fmt!(&gettext("unexpected character `%s'"), chr.to_string())
+            }
+            OperationSequenceErrorKind::UnterminatedQuote => gettext("unterminated quoted string"),
+            OperationSequenceErrorKind::MissingOperation(ref connector) => fmt!(
+                &gettext("`%s' must be followed by an operation"),
+                connector.as_str()
+            ),
+            OperationSequenceErrorKind::MissingDescription => fmt!(
+                &gettext("`%s' must be followed by a quoted description"),
+                DESCRIPTION_SEPARATOR
+            ),
+            OperationSequenceErrorKind::InvalidEscape(ref sequence) => {
+                fmt!(&gettext("invalid escape sequence `%s'"), sequence.as_str())
+            }
+            OperationSequenceErrorKind::UnclosedQuote(ref text) => {
+                fmt!(&gettext("quote isn't closed: %s"), text.as_str())
+            }
+            OperationSequenceErrorKind::UnexpectedText(ref text) => {
+                fmt!(&gettext("unexpected `%s'"), text.as_str())
+            }
+        }
+    }
+}
+
+/// Reasons why a commandline couldn't be tokenized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CmdlineErrorKind {
+    /// The line contains more than one command, separated by an unquoted semicolon.
+    MultipleCommands,
+
+    /// The tokenizer stopped before the end of the line, at the given character.
+    UnexpectedCharacter(char),
+
+    /// A quoted token contains the given invalid `\x` or `\u` escape sequence.
+    InvalidEscape(String),
+}
+
+/// An error produced by `tokenize_cmdline`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CmdlineError {
+    /// One-based offset of the offending character, in characters (not bytes).
+    pub column: usize,
+    pub kind: CmdlineErrorKind,
+}
+
+impl CmdlineError {
+    fn at_offset(input: &str, offset: usize, kind: CmdlineErrorKind) -> CmdlineError {
+        CmdlineError {
+            column: input[..offset].chars().count() + 1,
+            kind,
+        }
+    }
+
+    /// Internationalized description of the error, with the offending character highlighted in
+    /// the echoed `input`.
+    pub fn to_message(&self, input: &str) -> String {
+        let reason = match self.kind {
+            CmdlineErrorKind::MultipleCommands => gettext("only one command can be run at a time"),
+            CmdlineErrorKind::UnexpectedCharacter(chr) => {
+                fmt!(&gettext("unexpected character `%s'"), chr.to_string())
+            }
+            CmdlineErrorKind::InvalidEscape(ref sequence) => {
+                fmt!(&gettext("invalid escape sequence `%s'"), sequence.as_str())
+            }
+        };
+        fmt!(
+            &gettext("Parse error at column %u: %s: %s"),
+            self.column as u32,
+            reason,
+            highlight_column(input, self.column)
+        )
+    }
+}
+
+/// Surrounds the character at the given one-based `column` with `>>` and `<<`.
+fn highlight_column(input: &str, column: usize) -> String {
+    let mut result = String::with_capacity(input.len() + 4);
+    for (idx, chr) in input.chars().enumerate() {
+        if idx + 1 == column {
+            result.push_str(">>");
+            result.push(chr);
+            result.push_str("<<");
+        } else {
+            result.push(chr);
+        }
+    }
+    result
+}
+
+/// Tokenizes a line typed into the internal commandline (the `:` prompt).
+///
+/// The line is parsed with the same rules as a single operation in `tokenize_operation_sequence`,
+/// so quoting and escaping work the same way as in macros. On top of that:
+/// 1. comments are stripped, just like in config files;
+/// 2. for the `set` command, an equals sign separates the name of the variable from its value,
+///    like in `set reload-time=15`;
+/// 3. an unquoted semicolon is an error, because the commandline runs just one command.
+///
+/// Returns an empty vector if the line contains nothing but whitespace.
+pub fn tokenize_cmdline(input: &str) -> Result<Vec<String>, CmdlineError> {
+    let line = utils::strip_comments(input);
+    // `cmdline` parser only fails on an invalid escape sequence: in the worst case, it consumes
+    // nothing and returns no tokens, leaving the whole line as leftovers.
+    let (leftovers, tokens) = match cmdline(line) {
+        Ok(result) => result,
+        Err(nom::Err::Failure((rest, _))) => {
+            return Err(CmdlineError::at_offset(
+                input,
+                input.offset(rest) - 1,
+                CmdlineErrorKind::InvalidEscape(invalid_escape_text(rest)),
+            ))
+        }
+        Err(_) => (line, Vec::new()),
+    };
+    match leftovers.chars().next() {
+        None => Ok(tokens),
+        Some(';') => Err(CmdlineError::at_offset(
+            input,
+            input.offset(leftovers),
+            CmdlineErrorKind::MultipleCommands,
+        )),
+        Some(chr) => Err(CmdlineError::at_offset(
+            input,
+            input.offset(leftovers),
+            CmdlineErrorKind::UnexpectedCharacter(chr),
+        )),
+    }
+}
+
+fn cmdline(input: &str) -> IResult<&str, Vec<String>> {
+    let (input, _) = space0(input)?;
+    let (input, command) = opt(token)(input)?;
+
+    let mut tokens = Vec::new();
+    let mut input = input;
+    if let Some(command) = command {
+        let (rest, mut arguments) = if command == "set" {
+            let separator = alt((recognize(tuple((space0, tag("="), space0))), space1));
+            many0(preceded(separator, assignment_token))(input)?
+        } else {
+            many0(preceded(space1, token))(input)?
+        };
+        tokens.push(command);
+        tokens.append(&mut arguments);
+        input = rest;
+    }
+
+    let (leftovers, _) = space0(input)?;
+    Ok((leftovers, tokens))
+}
+
+/// Name of the commandline command that runs a sequence of operations, the same way a macro does.
+pub const RUN_COMMAND: &str = "run";
+
+/// Writes a token so that `tokenize_operation_sequence` and `tokenize_cmdline` read it back
+/// unchanged. Tokens are only quoted if they have to be; quoted ones escape `"`, `\`, and
+/// line-breaking and tab characters.
+pub fn quote_token(token: &str) -> String {
+    let needs_quotes = token.is_empty()
+        || token
+            .chars()
+            .any(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '"' | ';' | '#' | '`' | '\\'))
+        || token.starts_with('\'')
+        || is_connector(token);
+    if !needs_quotes {
+        return token.to_string();
+    }
+
+    format!("\"{}\"", utils::escape_quoted(token))
+}
+
+/// The inverse of `tokenize_operation_sequence`: joins operations with semicolons, or with the
+/// connectors between them, quoting the tokens that need it.
+pub fn quote_operation_sequence(operations: &[Vec<String>]) -> String {
+    let mut result = String::new();
+    let mut separator = "";
+    for tokens in operations {
+        if let [connector] = tokens.as_slice() {
+            if is_connector(connector) {
+                result.push(' ');
+                result.push_str(connector);
+                result.push(' ');
+                separator = "";
+                continue;
+            }
+        }
+
+        result.push_str(separator);
+        separator = "; ";
+        let quoted = tokens
+            .iter()
+            .map(|token| quote_token(token))
+            .collect::<Vec<_>>();
+        result.push_str(&quoted.join(" "));
+    }
+    result
+}
+
+/// Commandline that runs `operations` when executed; see `tokenize_run_cmdline`.
+pub fn run_cmdline(operations: &[Vec<String>]) -> String {
+    format!("{} {}", RUN_COMMAND, quote_operation_sequence(operations))
+}
+
+/// Tokenizes a commandline that starts with the `run` command. Unlike other commands, `run` takes
+/// a whole semicolon-separated operation sequence, which is tokenized like a macro.
+///
+/// Returns `None` if the line is some other command.
+pub fn tokenize_run_cmdline(input: &str) -> Option<Vec<Vec<String>>> {
+    let line = input.trim_start();
+    if !line.starts_with(RUN_COMMAND) {
+        return None;
+    }
+    let sequence = &line[RUN_COMMAND.len()..];
+    if !(sequence.is_empty()
+        || sequence.starts_with(' ')
+        || sequence.starts_with('\t')
+        || sequence.starts_with('#'))
+    {
+        return None;
+    }
+    tokenize_operation_sequence_with_comments(sequence)
+        .ok()
+        .map(|(operations, _comment)| operations)
+}
+
+/// Largest number of positional parameters a macro can take: `%1` to `%9`.
+pub const MAX_MACRO_PARAMETERS: usize = 9;
+
+/// Replaces placeholders in an argument of a parameterized macro with what `expand` returns for
+/// their numbers. `%%` is a literal percent sign; a percent sign that isn't followed by a digit or
+/// another percent sign is left as is.
+fn expand_placeholders<F: FnMut(usize) -> String>(token: &str, mut expand: F) -> String {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars().peekable();
+    while let Some(chr) = chars.next() {
+        if chr != '%' {
+            result.push(chr);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('%') => {
+                chars.next();
+                result.push('%');
+            }
+            Some(digit) if digit.is_ascii_digit() => {
+                chars.next();
+                result.push_str(&expand(digit as usize - '0' as usize));
+            }
+            _ => result.push('%'),
+        }
+    }
+    result
+}
+
+/// Returns the number of arguments taken by a parameterized macro (`macro-with-args`), i.e. the
+/// largest `%1`..`%9` that appears in the arguments of its operations. A macro that only uses
+/// `%0`, which stands for all the arguments joined with spaces, takes one argument.
+///
+/// Returns an error message if a parameter is used without all of the preceding ones, like `%3`
+/// without `%2`.
+pub fn macro_parameter_count(operations: &[Vec<String>]) -> Result<usize, String> {
+    let mut used = [false; MAX_MACRO_PARAMETERS + 1];
+    for tokens in operations {
+        for argument in tokens.iter().skip(1) {
+            expand_placeholders(argument, |number| {
+                used[number] = true;
+                String::new()
+            });
+        }
+    }
+
+    let highest = (1..=MAX_MACRO_PARAMETERS)
+        .rev()
+        .find(|&number| used[number]);
+    let count = match highest {
+        Some(highest) => highest,
+        None if used[0] => 1,
+        None => 0,
+    };
+    if let Some(missing) = (1..count).find(|&number| !used[number]) {
+        let user = (missing + 1..=count)
+            .find(|&number| used[number])
+            .unwrap_or(count);
+        return Err(fmt!(
+            &gettext("macro uses `%%%u' without `%%%u'"),
+            user as u32,
+            missing as u32
+        ));
+    }
+    Ok(count)
+}
+
+/// Puts `arguments` into the placeholders of a parameterized macro. Substitution happens after
+/// tokenization, so arguments never have to be quoted: each one ends up inside the token that
+/// contained its placeholder, whatever characters it consists of. Placeholders for which no
+/// argument was given are replaced with nothing.
+pub fn substitute_macro_arguments(
+    operations: &[Vec<String>],
+    arguments: &[String],
+) -> Vec<Vec<String>> {
+    let all_arguments = arguments.join(" ");
+    operations
+        .iter()
+        .map(|tokens| {
+            let mut substituted = Vec::with_capacity(tokens.len());
+            let mut tokens = tokens.iter();
+            // The operation itself can't be parameterized
+            substituted.extend(tokens.next().cloned());
+            substituted.extend(tokens.map(|token| {
+                expand_placeholders(token, |number| match number {
+                    0 => all_arguments.clone(),
+                    _ => arguments.get(number - 1).cloned().unwrap_or_default(),
+                })
+            }));
+            substituted
+        })
+        .collect()
+}
+
+/// Checks the arguments of the operations that take a fixed kind of argument, like the number
+/// of `goto-percent`. Returns an error message for the first operation whose arguments are wrong.
+///
+/// Arguments with placeholders can only be checked once the macro is run, so they're accepted.
+pub fn check_operation_arguments(operations: &[Vec<String>]) -> Result<(), String> {
+    for tokens in operations {
+        let arguments = &tokens[1..];
+        let has_placeholders = arguments.iter().any(|argument| {
+            let mut found = false;
+            expand_placeholders(argument, |_| {
+                found = true;
+                String::new()
+            });
+            found
+        });
+        if has_placeholders {
+            continue;
+        }
+        if tokens[0] == "goto-percent" {
+            listposition::parse_percent(arguments)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prompt for the next argument of a macro that takes `count` arguments, of which `supplied` are
+/// already known. Arguments are asked for in order; returns `None` once all of them are known.
+pub fn next_macro_argument_prompt(count: usize, supplied: usize) -> Option<String> {
+    if supplied >= count {
+        None
+    } else {
+        Some(fmt!(
+            &gettext("Argument %%%u of %u: "),
+            (supplied + 1) as u32,
+            count as u32
+        ))
+    }
+}
+
+/// Dialogs that a key can be bound in.
+pub const CONTEXTS: &[&str] = &[
+    "feedlist",
+    "filebrowser",
+    "help",
+    "articlelist",
+    "article",
+    "tagselection",
+    "filterselection",
+    "urlview",
+    "podboat",
+    "dialogs",
+    "dirbrowser",
+];
+
+/// Names that stand for all the dialogs.
+const EVERYWHERE: &[&str] = &["all", "everywhere"];
+
+/// The parameters of `bind-key`: `<key> <operations> [<dialogs>]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    /// The key, as written; it's not checked or canonicalized.
+    pub key: String,
+    pub operations: Vec<Vec<String>>,
+    /// Dialogs the binding applies to, in the order they were given, or `None` if it applies
+    /// everywhere.
+    pub contexts: Option<Vec<String>>,
+}
+
+/// Reasons why `parse_binding` failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingError {
+    /// The key or the operations are missing.
+    TooFewParams,
+
+    /// Something follows the dialogs.
+    UnexpectedParam(String),
+
+    /// A quoted parameter contains the given invalid `\x` or `\u` escape sequence.
+    InvalidEscape(String),
+
+    /// The operations couldn't be tokenized.
+    InvalidOperations {
+        operations: String,
+        error: OperationSequenceError,
+    },
+
+    /// The list of dialogs has an empty entry, e.g. `feedlist,,article`.
+    EmptyContext(String),
+
+    /// A dialog that doesn't exist.
+    UnknownContext(String),
+}
+
+impl BindingError {
+    /// Internationalized description of the error.
+    pub fn to_message(&self) -> String {
+        match self {
+            BindingError::TooFewParams => gettext("too few parameters."),
+            BindingError::UnexpectedParam(param) => {
+                fmt!(&gettext("unexpected parameter `%s'"), param.as_str())
+            }
+            BindingError::InvalidEscape(sequence) => {
+                fmt!(&gettext("invalid escape sequence `%s'"), sequence.as_str())
+            }
+            BindingError::InvalidOperations { operations, error } => fmt!(
+                &gettext("invalid operations `%s' at column %u: %s"),
+                operations.as_str(),
+                error.column as u32,
+                error.to_message()
+            ),
+            BindingError::EmptyContext(list) => {
+                fmt!(&gettext("empty dialog name in `%s'"), list.as_str())
+            }
+            BindingError::UnknownContext(name) => {
+                fmt!(&gettext("`%s' is not a valid context"), name.as_str())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for BindingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_message())
+    }
+}
+
+impl std::error::Error for BindingError {}
+
+/// A parameter of `bind-key`: either double-quoted, or anything up to the next whitespace, so
+/// that keys like `;` and `'` don't need quoting.
+fn binding_param(input: &str) -> IResult<&str, String> {
+    let unquoted = map(recognize(is_not("\t ")), String::from);
+    // Backticks were evaluated by the config parser already
+    let quoted = |input| quoted_token_with_escaped_backtick(input, "`", true);
+    alt((quoted, complete(unquoted)))(input)
+}
+
+fn binding_params(input: &str) -> IResult<&str, Vec<String>> {
+    let (input, _) = space0(input)?;
+    let (input, params) = many0(terminated(binding_param, space0))(input)?;
+    Ok((input, params))
+}
+
+/// Parses a comma-separated list of dialogs, like `feedlist,articlelist`. Returns `None` if one
+/// of them is `all` or `everywhere`. Duplicates are dropped.
+fn parse_contexts(list: &str) -> Result<Option<Vec<String>>, BindingError> {
+    let mut contexts: Vec<String> = Vec::new();
+    let mut everywhere = false;
+    for name in list.split(',').map(str::trim) {
+        if name.is_empty() {
+            return Err(BindingError::EmptyContext(list.to_string()));
+        } else if EVERYWHERE.contains(&name) {
+            everywhere = true;
+        } else if !CONTEXTS.contains(&name) {
+            return Err(BindingError::UnknownContext(name.to_string()));
+        } else if !contexts.iter().any(|context| context == name) {
+            contexts.push(name.to_string());
+        }
+    }
+    Ok(if everywhere { None } else { Some(contexts) })
+}
+
+/// Parses the parameters of `bind-key`, like `j down article` or
+/// `x "set browser lynx; open-in-browser" feedlist,articlelist`.
+///
+/// The operations are tokenized like `tokenize_operation_sequence_strict` does, so more than one
+/// has to be quoted. Dialogs are separated by commas; if none are given, the binding applies everywhere.
+pub fn parse_binding(input: &str) -> Result<Binding, BindingError> {
+    // `binding_params` only fails on an invalid escape sequence: every other character is either
+    // whitespace or part of a parameter
+    let mut params = match binding_params(input) {
+        Ok((_, params)) => params,
+        Err(nom::Err::Failure((rest, _))) => {
+            return Err(BindingError::InvalidEscape(invalid_escape_text(rest)))
+        }
+        Err(_) => Vec::new(),
+    };
+    if params.len() < 2 {
+        return Err(BindingError::TooFewParams);
+    }
+    if params.len() > 3 {
+        return Err(BindingError::UnexpectedParam(params.swap_remove(3)));
+    }
+
+    let contexts = match params.get(2) {
+        Some(list) => parse_contexts(list)?,
+        None => None,
+    };
+    let operations = match tokenize_operation_sequence_strict(&params[1]) {
+        Ok(operations) if operations.is_empty() => return Err(BindingError::TooFewParams),
+        Ok(operations) => operations,
+        Err(error) => {
+            return Err(BindingError::InvalidOperations {
+                operations: params.swap_remove(1),
+                error,
+            })
+        }
+    };
+    Ok(Binding {
+        key: params.swap_remove(0),
+        operations,
+        contexts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_tokenize_operation_sequence_splits_operations_and_arguments() {
+        assert_eq!(
+            tokenize_operation_sequence("").unwrap(),
+            Vec::<Vec<String>>::new()
+        );
+        assert_eq!(
+            tokenize_operation_sequence("open").unwrap(),
+            vec![vec!["open"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("open-all-unread-in-browser-and-mark-read").unwrap(),
+            vec![vec!["open-all-unread-in-browser-and-mark-read"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("set browser \"firefox --new-tab %u\"").unwrap(),
+            vec![vec!["set", "browser", "firefox --new-tab %u"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence(
+                "set browser \"firefox; echo \\\"hi\\\"\"; open-in-browser"
+            )
+            .unwrap(),
+            vec![
+                vec!["set", "browser", "firefox; echo \"hi\""],
+                vec!["open-in-browser"]
+            ]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("open ; next ;; quit").unwrap(),
+            vec![vec!["open"], vec!["next"], vec!["quit"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence(";;open;").unwrap(),
+            vec![vec!["open"]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_handles_escapes_in_quoted_tokens() {
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "a\nb\tc\rd\\e\"f\qg""#).unwrap(),
+            vec![vec!["set", "x", "a\nb\tc\rd\\e\"fqg"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "\`date\`""#).unwrap(),
+            vec![vec!["set", "x", r#"\`date\`"#]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_handles_hex_and_unicode_escapes_in_quoted_tokens() {
+        assert_eq!(
+            tokenize_operation_sequence(r#"set datetime-format "%d %b\u2009%H:%M""#).unwrap(),
+            vec![vec!["set", "datetime-format", "%d %b\u{2009}%H:%M"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "\x41\x7e\u00e4\u{1F600}\u{41}""#).unwrap(),
+            vec![vec!["set", "x", "A~ä😀A"]]
+        );
+        // The digits are case-insensitive, and what follows them is left alone
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "\u00C4BC\x2a2""#).unwrap(),
+            vec![vec!["set", "x", "ÄBC*2"]]
+        );
+        // Other escapes still just lose their backslash, and unquoted tokens have no escapes
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "\d\e\q" \u2009"#).unwrap(),
+            vec![vec!["set", "x", "deq", r#"\u2009"#]]
+        );
+    }
+
+    /// Quoted text, and what it unescapes to. `utils::tokenize_quoted()` on the C++ side is tested
+    /// against the same table.
+    const ESCAPES: &[(&str, &str)] = &[
+        ("plain", "plain"),
+        (r#"\"quoted\""#, r#""quoted""#),
+        (r"back\\slash", r"back\slash"),
+        (r"\n\r\t", "\n\r\t"),
+        (r"\e\q", "eq"),
+        (r"\x41\u00e4\u{1F600}", "Aä😀"),
+        (r"\`date\`", r"\`date\`"),
+        ("#not a comment", "#not a comment"),
+        ("a;b", "a;b"),
+        (r"ä\\", r"ä\"),
+    ];
+
+    #[test]
+    fn t_quoted_tokens_unescape_like_unescape_quoted() {
+        for (escaped, expected) in ESCAPES {
+            assert_eq!(utils::unescape_quoted(escaped), *expected);
+            assert_eq!(
+                tokenize_operation_sequence(&format!(r#"set x "{}""#, escaped)).unwrap(),
+                vec![vec!["set", "x", expected]]
+            );
+            assert_eq!(
+                tokenize_cmdline(&format!(r#"set x "{}""#, escaped)).unwrap(),
+                vec!["set", "x", expected]
+            );
+        }
+    }
+
+    #[test]
+    fn t_invalid_hex_and_unicode_escapes_are_errors() {
+        let invalid = |column, sequence: &str| {
+            Err(sequence_error(
+                column,
+                OperationSequenceErrorKind::InvalidEscape(sequence.to_string()),
+            ))
+        };
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "ab\xZZ""#),
+            invalid(10, r#"\xZZ"#)
+        );
+        // `\x` is for ASCII only
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "\xff""#),
+            invalid(8, r#"\xff"#)
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"open; set x "\u12g4""#),
+            invalid(14, r#"\u12g4"#)
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "\u12""#),
+            invalid(8, r#"\u12"#)
+        );
+        // Surrogates and code points past U+10FFFF aren't characters
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "\uD800""#),
+            invalid(8, r#"\uD800"#)
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "\u{110000}""#),
+            invalid(8, r#"\u{110000}"#)
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "\u{}""#),
+            invalid(8, r#"\u{}"#)
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "ä\u{1F600""#),
+            invalid(9, r#"\u{1F600"#)
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "\uD800""#)
+                .unwrap_err()
+                .to_message(),
+            r#"invalid escape sequence `\uD800'"#
+        );
+
+        assert_eq!(
+            tokenize_cmdline(r#"set x "\xZZ""#),
+            Err(CmdlineError {
+                column: 8,
+                kind: CmdlineErrorKind::InvalidEscape(r#"\xZZ"#.to_string())
+            })
+        );
+        assert_eq!(
+            parse_binding(r#"x "set x \"\u{D800}\"""#),
+            Err(BindingError::InvalidEscape(r#"\u{D800}"#.to_string()))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_implicitly_closes_quotes_at_end_of_input() {
+        assert_eq!(
+            tokenize_operation_sequence("set x \"unterminated").unwrap(),
+            vec![vec!["set", "x", "unterminated"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("set x \"").unwrap(),
+            vec![vec!["set", "x", ""]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_accepts_empty_quoted_tokens() {
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x """#).unwrap(),
+            vec![vec!["set", "x", ""]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_takes_single_quoted_tokens_literally() {
+        assert_eq!(
+            tokenize_operation_sequence("set browser 'mpv %u'; open-in-browser").unwrap(),
+            vec![vec!["set", "browser", "mpv %u"], vec!["open-in-browser"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x 'say "hi"; quit \n \' && next"#).unwrap(),
+            vec![
+                vec!["set", "x", r#"say "hi"; quit \n \"#],
+                vec!["&&"],
+                vec!["next"]
+            ]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("set x ''").unwrap(),
+            vec![vec!["set", "x", ""]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_implicitly_closes_single_quotes_at_end_of_input() {
+        assert_eq!(
+            tokenize_operation_sequence(r"set x 'C:\temp; dir").unwrap(),
+            vec![vec!["set", "x", r"C:\temp; dir"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("set x '").unwrap(),
+            vec![vec!["set", "x", ""]]
+        );
+    }
+
+    #[test]
+    fn t_single_quotes_inside_double_quotes_are_just_characters() {
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "it's 'quoted'" don't"#).unwrap(),
+            vec![vec!["set", "x", "it's 'quoted'", "don't"]]
+        );
+        assert_eq!(
+            tokenize_cmdline(r#"set x="it's""#).unwrap(),
+            vec!["set", "x", "it's"]
+        );
+        assert_eq!(
+            tokenize_cmdline("set x='a \"b\"'").unwrap(),
+            vec!["set", "x", "a \"b\""]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_keeps_connectors_between_operations() {
+        assert_eq!(
+            tokenize_operation_sequence("open-in-browser && quit || reload; help").unwrap(),
+            vec![
+                vec!["open-in-browser"],
+                vec!["&&"],
+                vec!["quit"],
+                vec!["||"],
+                vec!["reload"],
+                vec!["help"]
+            ]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("set browser lynx\t&&\topen-in-browser").unwrap(),
+            vec![
+                vec!["set", "browser", "lynx"],
+                vec!["&&"],
+                vec!["open-in-browser"]
+            ]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_treats_quoted_or_glued_connectors_as_tokens() {
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x "&&" && set y "||""#).unwrap(),
+            vec![vec!["set", "x", "&&"], vec!["&&"], vec!["set", "y", "||"]]
+        );
+        assert_eq!(
+            tokenize_operation_sequence("open-in-browser&&quit").unwrap(),
+            vec![vec!["open-in-browser&&quit"]]
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_rejects_connectors_without_an_operation_after_them() {
+        assert_eq!(tokenize_operation_sequence("open-in-browser &&"), None);
+        assert_eq!(
+            tokenize_operation_sequence("open-in-browser || ; quit"),
+            None
+        );
+        assert_eq!(tokenize_operation_sequence("open && && quit"), None);
+    }
+
+    fn sequence_error(column: usize, kind: OperationSequenceErrorKind) -> OperationSequenceError {
+        OperationSequenceError { column, kind }
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_verbose_reports_errors_in_the_first_token() {
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"fire"fox" open"#),
+            Err(sequence_error(
+                5,
+                OperationSequenceErrorKind::UnexpectedCharacter('"')
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#""open\"#),
+            Err(sequence_error(
+                1,
+                OperationSequenceErrorKind::UnterminatedQuote
+            ))
+        );
+        assert_eq!(tokenize_operation_sequence(r#"fire"fox" open"#), None);
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_verbose_reports_errors_after_a_semicolon() {
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"open; set x a"b""#),
+            Err(sequence_error(
+                14,
+                OperationSequenceErrorKind::UnexpectedCharacter('"')
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose("open ; quit &&"),
+            Err(sequence_error(
+                13,
+                OperationSequenceErrorKind::MissingOperation("&&".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_verbose_reports_errors_inside_quoted_tokens() {
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "ab\"#),
+            Err(sequence_error(
+                7,
+                OperationSequenceErrorKind::UnterminatedQuote
+            ))
+        );
+        // Columns are counted in characters, not bytes
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "ÄÖÜ"; set y "ab\"#),
+            Err(sequence_error(
+                20,
+                OperationSequenceErrorKind::UnterminatedQuote
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_verbose(r#"set x "ÄÖÜ"ä"#),
+            Err(sequence_error(
+                12,
+                OperationSequenceErrorKind::UnexpectedCharacter('ä')
+            ))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_comments_strips_the_comment() {
+        assert_eq!(
+            tokenize_operation_sequence_with_comments("open; quit # leave quickly"),
+            Ok((
+                vec![vec!["open".to_string()], vec!["quit".to_string()]],
+                Some(" leave quickly")
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_comments("open"),
+            Ok((vec![vec!["open".to_string()]], None))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_comments_keeps_hashes_in_quoted_tokens() {
+        assert_eq!(
+            tokenize_operation_sequence_with_comments(
+                r#"set browser "foo #bar"; open-in-browser #comment"#
+            ),
+            Ok((
+                vec![
+                    vec!["set".to_string(), "browser".into(), "foo #bar".into()],
+                    vec!["open-in-browser".to_string()]
+                ],
+                Some("comment")
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_comments(r#"set x "a\#b""#),
+            Ok((
+                vec![vec!["set".to_string(), "x".into(), "a#b".into()]],
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_comments_accepts_lines_that_are_only_a_comment() {
+        assert_eq!(
+            tokenize_operation_sequence_with_comments("# nothing to see here"),
+            Ok((vec![], Some(" nothing to see here")))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_comments("  #"),
+            Ok((vec![], Some("")))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_comments_reports_columns_in_the_whole_line() {
+        assert_eq!(
+            tokenize_operation_sequence_with_comments(r#"open fire"fox" # comment"#),
+            Err(OperationSequenceError {
+                column: 10,
+                kind: OperationSequenceErrorKind::UnexpectedCharacter('"')
+            })
+        );
+    }
+
+    /// Tokenizes `input`, looking variables up in `vars` rather than the real environment.
+    fn tokenize_with_vars(
+        input: &str,
+        vars: &[(&str, &str)],
+    ) -> Result<Vec<Vec<String>>, OperationSequenceError> {
+        tokenize_operation_sequence_with_env(input, |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_env_expands_variables() {
+        let vars = [("BROWSER", "firefox"), ("HOME", "/home/user")];
+        assert_eq!(
+            tokenize_with_vars(
+                r#"set browser "$BROWSER --new-tab %u"; open-in-browser"#,
+                &vars
+            ),
+            Ok(vec![
+                vec![
+                    "set".to_string(),
+                    "browser".to_string(),
+                    "firefox --new-tab %u".to_string()
+                ],
+                vec!["open-in-browser".to_string()]
+            ])
+        );
+        assert_eq!(
+            tokenize_with_vars(r#"save ${HOME}/articles/ "${HOME}_backup""#, &vars),
+            Ok(vec![vec![
+                "save".to_string(),
+                "/home/user/articles/".to_string(),
+                "/home/user_backup".to_string()
+            ]])
+        );
+        // Without braces, the name extends as far as it can
+        assert_eq!(
+            tokenize_with_vars(r#"save $HOME_backup"#, &vars),
+            Ok(vec![vec!["save".to_string(), "".to_string()]])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_env_expands_unset_variables_to_nothing() {
+        assert_eq!(
+            tokenize_with_vars(r#"set browser "x${UNSET}y$UNSET""#, &[]),
+            Ok(vec![vec![
+                "set".to_string(),
+                "browser".to_string(),
+                "xy".to_string()
+            ]])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_env_leaves_some_dollars_alone() {
+        let vars = [("HOME", "/home/user")];
+        assert_eq!(
+            tokenize_with_vars(r#"set x '$HOME' "\$HOME" \$HOME"#, &vars),
+            Ok(vec![vec![
+                "set".to_string(),
+                "x".to_string(),
+                "$HOME".to_string(),
+                "$HOME".to_string(),
+                "$HOME".to_string()
+            ]])
+        );
+        assert_eq!(
+            tokenize_with_vars(r#"set x "costs 5$" $1 ${HOME ${} $-"#, &vars),
+            Ok(vec![vec![
+                "set".to_string(),
+                "x".to_string(),
+                "costs 5$".to_string(),
+                "$1".to_string(),
+                "${HOME".to_string(),
+                "${}".to_string(),
+                "$-".to_string()
+            ]])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_env_still_unescapes_quoted_tokens() {
+        let vars = [("NAME", "a\"b")];
+        assert_eq!(
+            tokenize_with_vars(r#"set x "\t$NAME\n" && open"#, &vars),
+            Ok(vec![
+                vec!["set".to_string(), "x".to_string(), "\ta\"b\n".to_string()],
+                vec![AND_THEN.to_string()],
+                vec!["open".to_string()]
+            ])
+        );
+        assert_eq!(
+            tokenize_with_vars(r#"set x "\uD800$NAME""#, &vars),
+            tokenize_operation_sequence_verbose(r#"set x "\uD800$NAME""#)
+        );
+    }
+
+    fn literal(text: &str) -> Token {
+        Token::Literal(text.to_string())
+    }
+
+    fn shell_eval(command: &str) -> Token {
+        Token::ShellEval(command.to_string())
+    }
+
+    /// Operations whose tokens are all literals.
+    fn literal_ops(operations: &[&[&str]]) -> Vec<Vec<Argument>> {
+        operations
+            .iter()
+            .map(|op| op.iter().map(|token| vec![literal(token)]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_backticks_returns_shell_commands_apart() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks("set browser `which firefox`; open"),
+            Ok(vec![
+                vec![
+                    vec![literal("set")],
+                    vec![literal("browser")],
+                    vec![shell_eval("which firefox")]
+                ],
+                vec![vec![literal("open")]]
+            ])
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks("set x --title=`date +%F`!`whoami`"),
+            Ok(vec![vec![
+                vec![literal("set")],
+                vec![literal("x")],
+                vec![
+                    literal("--title="),
+                    shell_eval("date +%F"),
+                    literal("!"),
+                    shell_eval("whoami")
+                ]
+            ]])
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks("set x ``"),
+            Ok(vec![vec![
+                vec![literal("set")],
+                vec![literal("x")],
+                vec![shell_eval("")]
+            ]])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_backticks_leaves_quoted_backticks_alone() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set browser "mpv `--no-video`""#),
+            Ok(literal_ops(&[&["set", "browser", "mpv `--no-video`"]]))
+        );
+        // A backtick inside quotes doesn't start a command that extends past the closing quote
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set x "`a"`b`"c`""#),
+            Err(OperationSequenceError {
+                column: 11,
+                kind: OperationSequenceErrorKind::UnexpectedCharacter('`')
+            })
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_backticks_unescapes_backticks_everywhere() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set x \`a\` "\`b\`""#),
+            Ok(literal_ops(&[&["set", "x", "`a`", "`b`"]]))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r"set x `echo \`date\``"),
+            Ok(vec![vec![
+                vec![literal("set")],
+                vec![literal("x")],
+                vec![shell_eval("echo `date`")]
+            ]])
+        );
+        // Other backslashes are kept: outside of quotes they don't escape anything, and inside
+        // commands they're for the shell
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r"set x a\b `printf '\n'`"),
+            Ok(vec![vec![
+                vec![literal("set")],
+                vec![literal("x")],
+                vec![literal(r"a\b")],
+                vec![shell_eval(r"printf '\n'")]
+            ]])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_backticks_keeps_quoted_escapes() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set x "a\nb\tc\rd\\e\"f\qg""#),
+            Ok(literal_ops(&[&["set", "x", "a\nb\tc\rd\\e\"fqg"]]))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set x """#),
+            Ok(literal_ops(&[&["set", "x", ""]]))
+        );
+    }
+
+    #[test]
+    fn t_shell_commands_can_contain_separators_and_quotes() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set x `a; b && "c" || d` && set y `&&`"#),
+            Ok(vec![
+                vec![
+                    vec![literal("set")],
+                    vec![literal("x")],
+                    vec![shell_eval(r#"a; b && "c" || d"#)]
+                ],
+                vec![vec![literal(AND_THEN)]],
+                vec![
+                    vec![literal("set")],
+                    vec![literal("y")],
+                    vec![shell_eval(AND_THEN)]
+                ]
+            ])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_backticks_implicitly_closes_backticks() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks("set x `date; quit"),
+            Ok(vec![vec![
+                vec![literal("set")],
+                vec![literal("x")],
+                vec![shell_eval("date; quit")]
+            ]])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_with_backticks_reports_errors_like_the_plain_tokenizer() {
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks(r#"set `date`"x""#),
+            Err(OperationSequenceError {
+                column: 11,
+                kind: OperationSequenceErrorKind::UnexpectedCharacter('"')
+            })
+        );
+        assert_eq!(
+            tokenize_operation_sequence_with_backticks("open &&"),
+            Err(OperationSequenceError {
+                column: 6,
+                kind: OperationSequenceErrorKind::MissingOperation(AND_THEN.to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn t_tokenizers_agree_on_inputs_without_backticks() {
+        let inputs = [
+            "",
+            "open ; next ;; quit",
+            r#"set browser "firefox --new-tab %u"; open-in-browser"#,
+            r#"set x "a\nb\"c" && quit || reload"#,
+            r"set x a\b",
+            r#"set x "&&" && set y "||""#,
+        ];
+        for input in &inputs {
+            let plain = tokenize_operation_sequence_verbose(input).unwrap();
+            let expected = plain
+                .iter()
+                .map(|op| op.iter().map(|token| vec![literal(token)]).collect())
+                .collect::<Vec<Vec<Argument>>>();
+            assert_eq!(
+                tokenize_operation_sequence_with_backticks(input),
+                Ok(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn t_tokenize_macro_definition_returns_the_description_separately() {
+        assert_eq!(
+            tokenize_macro_definition(r#"open; quit -- "open then quit""#),
+            Ok((
+                ops(&[&["open"], &["quit"]]),
+                Some("open then quit".to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_macro_definition("open; quit"),
+            Ok((ops(&[&["open"], &["quit"]]), None))
+        );
+    }
+
+    #[test]
+    fn t_macro_descriptions_can_contain_semicolons_and_escaped_quotes() {
+        assert_eq!(
+            tokenize_macro_definition(r#"open && quit -- "open; then \"quit\" -- if it worked""#),
+            Ok((
+                ops(&[&["open"], &["&&"], &["quit"]]),
+                Some(r#"open; then "quit" -- if it worked"#.to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn t_only_an_unquoted_double_dash_token_starts_a_macro_description() {
+        assert_eq!(
+            tokenize_macro_definition(r#"set browser "foo -- bar"; open-in-browser"#),
+            Ok((
+                ops(&[&["set", "browser", "foo -- bar"], &["open-in-browser"]]),
+                None
+            ))
+        );
+        assert_eq!(
+            tokenize_macro_definition(r#"set browser "--" --new-tab"#),
+            Ok((ops(&[&["set", "browser", "--", "--new-tab"]]), None))
+        );
+
+        // Outside of macro definitions, `--` is a token like any other
+        assert_eq!(
+            tokenize_operation_sequence(r#"set x -- "y""#),
+            Some(ops(&[&["set", "x", "--", "y"]]))
+        );
+    }
+
+    #[test]
+    fn t_macro_description_separator_without_a_quoted_string_is_an_error() {
+        let missing = |column| {
+            Err(OperationSequenceError {
+                column,
+                kind: OperationSequenceErrorKind::MissingDescription,
+            })
+        };
+        assert_eq!(tokenize_macro_definition("open; quit --"), missing(12));
+        assert_eq!(tokenize_macro_definition("open --  "), missing(6));
+        assert_eq!(tokenize_macro_definition("open -- quit"), missing(6));
+        assert_eq!(
+            tokenize_macro_definition("open --")
+                .unwrap_err()
+                .to_message(),
+            "`--' must be followed by a quoted description"
+        );
+    }
+
+    #[test]
+    fn t_macro_description_must_come_last() {
+        assert_eq!(
+            tokenize_macro_definition(r#"open -- "description"; quit"#),
+            Err(OperationSequenceError {
+                column: 24,
+                kind: OperationSequenceErrorKind::UnexpectedCharacter('q')
+            })
+        );
+    }
+
+    #[test]
+    fn t_operation_sequence_error_messages_describe_the_problem() {
+        let message = |input| {
+            tokenize_operation_sequence_verbose(input)
+                .unwrap_err()
+                .to_message()
+        };
+        assert_eq!(message(r#"open; a"b""#), "unexpected character `\"'");
+        assert_eq!(message(r#"set x "ab\"#), "unterminated quoted string");
+        assert_eq!(message("open ||"), "`||' must be followed by an operation");
+    }
+
+    /// Inputs that mean the same to the lenient and the strict tokenizers.
+    const CLOSED_INPUTS: &[&str] = &[
+        "",
+        "open",
+        r#"set browser "firefox; echo \"hi\""; open-in-browser"#,
+        r#"set x """#,
+        "set x ''",
+        r#"set x 'say "hi"; quit \n \' && next"#,
+        r#"set x "it's 'quoted'" don't"#,
+        "open-in-browser || set browser lynx",
+        r#"set datetime-format "%d %b\u2009%H:%M""#,
+        "  ;; open ;; quit ;  ",
+    ];
+
+    #[test]
+    fn t_tokenize_operation_sequence_strict_agrees_with_the_lenient_tokenizer_on_closed_quotes() {
+        for input in CLOSED_INPUTS {
+            assert_eq!(
+                tokenize_operation_sequence_strict(input),
+                tokenize_operation_sequence_verbose(input),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_strict_rejects_unclosed_quotes() {
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"set x "unterminated"#),
+            Err(sequence_error(
+                7,
+                OperationSequenceErrorKind::UnclosedQuote(r#""unterminated"#.to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"open; set x ""#),
+            Err(sequence_error(
+                13,
+                OperationSequenceErrorKind::UnclosedQuote(r#"""#.to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_strict(r"set x 'C:\temp; dir"),
+            Err(sequence_error(
+                7,
+                OperationSequenceErrorKind::UnclosedQuote(r"'C:\temp; dir".to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_strict("set x '"),
+            Err(sequence_error(
+                7,
+                OperationSequenceErrorKind::UnclosedQuote("'".to_string())
+            ))
+        );
+        // A closing quote that is escaped doesn't count
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"set x "ab\""#),
+            Err(sequence_error(
+                7,
+                OperationSequenceErrorKind::UnclosedQuote(r#""ab\""#.to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_strict_reports_the_leftover_text() {
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"fire"fox" open"#),
+            Err(sequence_error(
+                5,
+                OperationSequenceErrorKind::UnexpectedText(r#""fox" open"#.to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"set x "ÄÖÜ"ä; quit"#),
+            Err(sequence_error(
+                12,
+                OperationSequenceErrorKind::UnexpectedText("ä; quit".to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"set x "ÄÖÜ"ä; quit"#)
+                .unwrap_err()
+                .to_message(),
+            "unexpected `ä; quit'"
+        );
+        assert_eq!(
+            tokenize_operation_sequence_strict(r#"set x "abc"#)
+                .unwrap_err()
+                .to_message(),
+            r#"quote isn't closed: "abc"#
+        );
+    }
+
+    #[test]
+    fn t_tokenize_operation_sequence_strict_reports_other_errors_like_the_lenient_tokenizer() {
+        for input in &["open &&", "open ||  ", r#"set x "\uD800""#] {
+            assert_eq!(
+                tokenize_operation_sequence_strict(input),
+                tokenize_operation_sequence_verbose(input),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn t_tokenize_macro_definition_strict_requires_closed_quotes() {
+        assert_eq!(
+            tokenize_macro_definition_strict(r#"open; quit -- "open, then quit""#),
+            tokenize_macro_definition(r#"open; quit -- "open, then quit""#)
+        );
+        assert_eq!(
+            tokenize_macro_definition_strict(r#"set browser "lynx; open-in-browser"#),
+            Err(sequence_error(
+                13,
+                OperationSequenceErrorKind::UnclosedQuote(r#""lynx; open-in-browser"#.to_string())
+            ))
+        );
+        assert_eq!(
+            tokenize_macro_definition_strict(r#"open -- "unclosed"#),
+            Err(sequence_error(
+                6,
+                OperationSequenceErrorKind::MissingDescription
+            ))
+        );
+    }
+
+    /// Inputs that are valid both in a macro and on the commandline, along with the expected
+    /// tokens.
+    const SHARED_INPUTS: &[(&str, &[&str])] = &[
+        ("quit", &["quit"]),
+        ("  quit  ", &["quit"]),
+        ("set browser lynx", &["set", "browser", "lynx"]),
+        (
+            "set browser \"firefox --new-tab\"",
+            &["set", "browser", "firefox --new-tab"],
+        ),
+        ("set browser \"a;b\"", &["set", "browser", "a;b"]),
+        (
+            r#"set browser "echo \"%u\"""#,
+            &["set", "browser", "echo \"%u\""],
+        ),
+        (r#"set x "tab\there""#, &["set", "x", "tab\there"]),
+        (r#"set x "back\\slash""#, &["set", "x", "back\\slash"]),
+        (r#"set x "\e""#, &["set", "x", "e"]),
+        ("set x \"\"", &["set", "x", ""]),
+        ("set x \"unterminated", &["set", "x", "unterminated"]),
+        ("source ~/a ~/b", &["source", "~/a", "~/b"]),
+        ("exec\topen", &["exec", "open"]),
+        (
+            "set datetime-format \"%d %b — %H:%M\"",
+            &["set", "datetime-format", "%d %b — %H:%M"],
+        ),
+    ];
+
+    #[test]
+    fn t_cmdline_and_macro_tokenizers_agree_on_shared_inputs() {
+        for (input, expected) in SHARED_INPUTS {
+            let expected: Vec<String> = expected.iter().map(|s| s.to_string()).collect();
+
+            let macro_tokens = tokenize_operation_sequence(input).unwrap();
+            assert_eq!(macro_tokens, vec![expected.clone()], "input: {}", input);
+
+            let cmdline_tokens = tokenize_cmdline(input).unwrap();
+            assert_eq!(cmdline_tokens, expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn t_tokenize_cmdline_returns_nothing_for_empty_lines() {
+        assert_eq!(tokenize_cmdline(""), Ok(vec![]));
+        assert_eq!(tokenize_cmdline(" \t "), Ok(vec![]));
+        assert_eq!(tokenize_cmdline("# just a comment"), Ok(vec![]));
+    }
+
+    #[test]
+    fn t_tokenize_cmdline_splits_set_arguments_on_equals_sign() {
+        assert_eq!(
+            tokenize_cmdline("set reload-time=15"),
+            Ok(vec!["set".to_string(), "reload-time".into(), "15".into()])
+        );
+        assert_eq!(
+            tokenize_cmdline("set browser = \"firefox --new-tab\""),
+            Ok(vec![
+                "set".to_string(),
+                "browser".into(),
+                "firefox --new-tab".into()
+            ])
+        );
+        assert_eq!(
+            tokenize_cmdline("set browser=\"a=b\""),
+            Ok(vec!["set".to_string(), "browser".into(), "a=b".into()])
+        );
+        // Other commands don't treat equals sign specially
+        assert_eq!(
+            tokenize_cmdline("tag a=b"),
+            Ok(vec!["tag".to_string(), "a=b".into()])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_cmdline_strips_comments() {
+        assert_eq!(
+            tokenize_cmdline("set browser lynx # a comment"),
+            Ok(vec!["set".to_string(), "browser".into(), "lynx".into()])
+        );
+        assert_eq!(
+            tokenize_cmdline("set browser \"lynx # not a comment\""),
+            Ok(vec![
+                "set".to_string(),
+                "browser".into(),
+                "lynx # not a comment".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn t_tokenize_cmdline_rejects_multiple_commands() {
+        assert_eq!(
+            tokenize_cmdline("set browser lynx; quit"),
+            Err(CmdlineError {
+                column: 17,
+                kind: CmdlineErrorKind::MultipleCommands
+            })
+        );
+        assert_eq!(
+            tokenize_cmdline("quit ;"),
+            Err(CmdlineError {
+                column: 6,
+                kind: CmdlineErrorKind::MultipleCommands
+            })
+        );
+    }
+
+    #[test]
+    fn t_tokenize_cmdline_reports_unexpected_characters() {
+        assert_eq!(
+            tokenize_cmdline("set browser fire\"fox\""),
+            Err(CmdlineError {
+                column: 17,
+                kind: CmdlineErrorKind::UnexpectedCharacter('"')
+            })
+        );
+    }
+
+    #[test]
+    fn t_tokenize_cmdline_counts_columns_in_characters() {
+        assert_eq!(
+            tokenize_cmdline("set x \"ÄÖÜ\"; quit"),
+            Err(CmdlineError {
+                column: 12,
+                kind: CmdlineErrorKind::MultipleCommands
+            })
+        );
+    }
+
+    #[test]
+    fn t_cmdline_error_message_highlights_the_column() {
+        let input = "set x \"ÄÖÜ\"; quit";
+        let error = tokenize_cmdline(input).unwrap_err();
+        assert!(error.to_message(input).ends_with("set x \"ÄÖÜ\">>;<< quit"));
+    }
+
+    fn ops(operations: &[&[&str]]) -> Vec<Vec<String>> {
+        operations
+            .iter()
+            .map(|tokens| tokens.iter().map(|t| t.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn t_quote_operation_sequence_only_quotes_tokens_that_need_it() {
+        assert_eq!(quote_operation_sequence(&[]), "");
+        assert_eq!(
+            quote_operation_sequence(&ops(&[
+                &["set", "browser", "firefox --new-tab %u"],
+                &["open-in-browser"],
+                &["set", "browser", "lynx"]
+            ])),
+            r#"set browser "firefox --new-tab %u"; open-in-browser; set browser lynx"#
+        );
+        assert_eq!(
+            quote_operation_sequence(&ops(&[&["set", "x", ""]])),
+            r#"set x """#
+        );
+        assert_eq!(
+            quote_operation_sequence(&ops(&[&["set", "x", "a;b"]])),
+            r#"set x "a;b""#
+        );
+        assert_eq!(
+            quote_operation_sequence(&ops(&[
+                &["open-in-browser"],
+                &["&&"],
+                &["quit"],
+                &["||"],
+                &["set", "x", "&&"],
+                &["reload"]
+            ])),
+            r#"open-in-browser && quit || set x "&&"; reload"#
+        );
+    }
+
+    #[test]
+    fn t_quoted_operation_sequence_is_tokenized_back_unchanged() {
+        let sequences = vec![
+            ops(&[&["open"], &["reload"], &["quit"]]),
+            ops(&[
+                &["set", "browser", "firefox; echo \"hi\""],
+                &["open-in-browser"],
+            ]),
+            ops(&[&["set", "x", ""], &["set", "y", "tab\there"]]),
+            ops(&[&["set", "x", "new\nline\r"], &["set", "y", r"back\slash"]]),
+            ops(&[&["set", "x", "# not a comment"], &["set", "y", "`date`"]]),
+            ops(&[&["set", "x", r"\`escaped\`"], &["set", "y", "ÄÖÜ"]]),
+            ops(&[&["open"], &["&&"], &["quit"], &["||"], &["set", "x", "||"]]),
+        ];
+        for sequence in sequences {
+            let serialized = quote_operation_sequence(&sequence);
+            assert_eq!(
+                tokenize_operation_sequence(&serialized).unwrap(),
+                sequence,
+                "{}",
+                serialized
+            );
+            assert_eq!(
+                tokenize_run_cmdline(&run_cmdline(&sequence)).unwrap(),
+                sequence
+            );
+        }
+    }
+
+    #[test]
+    fn t_quote_token_round_trips_nasty_inputs() {
+        let nasty = [
+            "",
+            "\"",
+            "\\",
+            "\\\"",
+            "\"\"",
+            "\"leading quote",
+            "trailing quote\"",
+            "'",
+            "'single'",
+            " ",
+            "  leading and trailing  ",
+            ";",
+            "a;b",
+            "\t",
+            "\n",
+            "\r\n",
+            "#",
+            "`",
+            "&&",
+            "||",
+            "&&&",
+            "\\n",
+            "ÄÖÜ \u{1f600}",
+            "plain",
+        ];
+        for token in nasty.iter() {
+            let sequence = ops(&[&["set", "x", token], &["set", token, "y"]]);
+            let quoted = quote_operation_sequence(&sequence);
+            assert_eq!(
+                tokenize_operation_sequence(&quoted).unwrap(),
+                sequence,
+                "{}",
+                quoted
+            );
+            assert_eq!(
+                tokenize_cmdline(&quote_token(token)).unwrap(),
+                vec![token.to_string()]
+            );
+        }
+        assert_eq!(quote_token("plain"), "plain");
+        assert_eq!(quote_token("\""), r#""\"""#);
+        assert_eq!(quote_token("\\"), r#""\\""#);
+    }
+
+    #[test]
+    fn t_tokenize_run_cmdline_parses_an_operation_sequence() {
+        assert_eq!(
+            tokenize_run_cmdline(r#"run set browser "lynx %u"; open-in-browser # comment"#),
+            Some(ops(&[&["set", "browser", "lynx %u"], &["open-in-browser"]]))
+        );
+        assert_eq!(
+            tokenize_run_cmdline("  run\treload"),
+            Some(ops(&[&["reload"]]))
+        );
+        assert_eq!(tokenize_run_cmdline("run"), Some(ops(&[])));
+    }
+
+    #[test]
+    fn t_tokenize_run_cmdline_ignores_other_commands() {
+        assert_eq!(tokenize_run_cmdline(""), None);
+        assert_eq!(tokenize_run_cmdline("set browser lynx"), None);
+        assert_eq!(tokenize_run_cmdline("runner; quit"), None);
+        assert_eq!(tokenize_run_cmdline("# run reload"), None);
+    }
+
+    fn macro_ops(input: &str) -> Vec<Vec<String>> {
+        tokenize_operation_sequence(input).unwrap()
+    }
+
+    #[test]
+    fn t_macro_parameter_count_is_the_highest_parameter() {
+        assert_eq!(
+            macro_parameter_count(&macro_ops("open; set browser lynx")),
+            Ok(0)
+        );
+        assert_eq!(macro_parameter_count(&macro_ops("save %1")), Ok(1));
+        assert_eq!(
+            macro_parameter_count(&macro_ops(
+                "set browser \"%2 %1\" ; open-in-browser; set browser %1"
+            )),
+            Ok(2)
+        );
+        assert_eq!(macro_parameter_count(&macro_ops("save %0")), Ok(1));
+        assert_eq!(
+            macro_parameter_count(&macro_ops("set a %1; set b %0; set c %2")),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn t_macro_parameter_count_ignores_percent_signs_that_are_not_placeholders() {
+        assert_eq!(
+            macro_parameter_count(&macro_ops("set articlelist-format \"%%4i %t\"")),
+            Ok(0)
+        );
+        assert_eq!(
+            macro_parameter_count(&macro_ops("set a 100% ; set b %")),
+            Ok(0)
+        );
+        // Placeholders in operation names are never expanded
+        assert_eq!(macro_parameter_count(&macro_ops("%1")), Ok(0));
+    }
+
+    #[test]
+    fn t_macro_parameter_count_rejects_gaps() {
+        assert_eq!(
+            macro_parameter_count(&macro_ops("set a %1; set b %3")),
+            Err("macro uses `%3' without `%2'".to_string())
+        );
+        assert_eq!(
+            macro_parameter_count(&macro_ops("set a %2")),
+            Err("macro uses `%2' without `%1'".to_string())
+        );
+        assert_eq!(
+            macro_parameter_count(&macro_ops("set a %0%4 %3")),
+            Err("macro uses `%3' without `%1'".to_string())
+        );
+    }
+
+    #[test]
+    fn t_check_operation_arguments_validates_goto_percent() {
+        assert_eq!(
+            check_operation_arguments(&macro_ops("goto-percent 50; open")),
+            Ok(())
+        );
+        assert_eq!(
+            check_operation_arguments(&macro_ops("open; goto-percent")),
+            Err("goto-percent takes exactly one argument, a number from 0 to 100".to_string())
+        );
+        assert_eq!(
+            check_operation_arguments(&macro_ops("goto-percent 150")),
+            Err("`150' is not a number from 0 to 100".to_string())
+        );
+        // The argument of a parameterized macro is only known when it runs
+        assert_eq!(
+            check_operation_arguments(&macro_ops("goto-percent %1")),
+            Ok(())
+        );
+        // Other operations aren't checked
+        assert_eq!(check_operation_arguments(&macro_ops("open 150")), Ok(()));
+    }
+
+    #[test]
+    fn t_substitute_macro_arguments_puts_values_into_tokens_verbatim() {
+        let arguments = vec![
+            "file with spaces.txt".to_string(),
+            r#"say "hi"; \o/"#.to_string(),
+        ];
+        assert_eq!(
+            substitute_macro_arguments(
+                &macro_ops("save %1; set a \"[%2]\" ; set b 50%%"),
+                &arguments
+            ),
+            vec![
+                vec!["save".to_string(), "file with spaces.txt".to_string()],
+                vec![
+                    "set".to_string(),
+                    "a".to_string(),
+                    r#"[say "hi"; \o/]"#.to_string()
+                ],
+                vec!["set".to_string(), "b".to_string(), "50%".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn t_substituted_macro_survives_a_run_cmdline_round_trip() {
+        let arguments = vec![r#"a "quoted" ; value"#.to_string()];
+        let substituted = substitute_macro_arguments(&macro_ops("save %1"), &arguments);
+        assert_eq!(
+            tokenize_run_cmdline(&run_cmdline(&substituted)),
+            Some(substituted)
+        );
+    }
+
+    #[test]
+    fn t_percent_zero_expands_to_all_arguments() {
+        let arguments = vec!["one".to_string(), "two words".to_string()];
+        assert_eq!(
+            substitute_macro_arguments(&macro_ops("set a %0; set b %2"), &arguments),
+            vec![
+                vec![
+                    "set".to_string(),
+                    "a".to_string(),
+                    "one two words".to_string()
+                ],
+                vec!["set".to_string(), "b".to_string(), "two words".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn t_missing_arguments_are_replaced_with_nothing() {
+        assert_eq!(
+            substitute_macro_arguments(&macro_ops("set a x%1y"), &[]),
+            vec![vec!["set".to_string(), "a".to_string(), "xy".to_string()]]
+        );
+    }
+
+    #[test]
+    fn t_macro_arguments_are_prompted_for_in_order() {
+        assert_eq!(
+            next_macro_argument_prompt(3, 0),
+            Some("Argument %1 of 3: ".to_string())
+        );
+        assert_eq!(
+            next_macro_argument_prompt(3, 1),
+            Some("Argument %2 of 3: ".to_string())
+        );
+        assert_eq!(
+            next_macro_argument_prompt(3, 2),
+            Some("Argument %3 of 3: ".to_string())
+        );
+        assert_eq!(next_macro_argument_prompt(3, 3), None);
+        assert_eq!(next_macro_argument_prompt(0, 0), None);
+    }
+
+    fn binding(key: &str, operations: &[&[&str]], contexts: Option<&[&str]>) -> Binding {
+        Binding {
+            key: key.to_string(),
+            operations: operations
+                .iter()
+                .map(|tokens| tokens.iter().map(|token| token.to_string()).collect())
+                .collect(),
+            contexts: contexts.map(|contexts| contexts.iter().map(|c| c.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn t_parse_binding_without_dialogs_applies_everywhere() {
+        assert_eq!(
+            parse_binding("j down"),
+            Ok(binding("j", &[&["down"]], None))
+        );
+        assert_eq!(
+            parse_binding("  ^R   reload-all  "),
+            Ok(binding("^R", &[&["reload-all"]], None))
+        );
+        assert_eq!(
+            parse_binding("j down all"),
+            Ok(binding("j", &[&["down"]], None))
+        );
+        assert_eq!(
+            parse_binding("j down feedlist,everywhere"),
+            Ok(binding("j", &[&["down"]], None))
+        );
+    }
+
+    #[test]
+    fn t_parse_binding_takes_a_comma_separated_list_of_dialogs() {
+        assert_eq!(
+            parse_binding("j down article"),
+            Ok(binding("j", &[&["down"]], Some(&["article"])))
+        );
+        assert_eq!(
+            parse_binding("j down feedlist,articlelist,feedlist"),
+            Ok(binding(
+                "j",
+                &[&["down"]],
+                Some(&["feedlist", "articlelist"])
+            ))
+        );
+        assert_eq!(
+            parse_binding(r#"j down "help, urlview""#),
+            Ok(binding("j", &[&["down"]], Some(&["help", "urlview"])))
+        );
+    }
+
+    #[test]
+    fn t_parse_binding_combines_quoted_operations_with_dialogs() {
+        assert_eq!(
+            parse_binding(r#"x "set browser \"lynx %u\"; open-in-browser" feedlist,articlelist"#),
+            Ok(binding(
+                "x",
+                &[&["set", "browser", "lynx %u"], &["open-in-browser"]],
+                Some(&["feedlist", "articlelist"])
+            ))
+        );
+        assert_eq!(
+            parse_binding(r#"x "toggle-article-read \"read\"""#),
+            Ok(binding("x", &[&["toggle-article-read", "read"]], None))
+        );
+    }
+
+    #[test]
+    fn t_parse_binding_keys_need_no_quoting() {
+        assert_eq!(
+            parse_binding("; quit"),
+            Ok(binding(";", &[&["quit"]], None))
+        );
+        assert_eq!(
+            parse_binding("' quit help"),
+            Ok(binding("'", &[&["quit"]], Some(&["help"])))
+        );
+        assert_eq!(
+            parse_binding(r#"" " quit"#),
+            Ok(binding(" ", &[&["quit"]], None))
+        );
+    }
+
+    #[test]
+    fn t_parse_binding_rejects_unknown_and_empty_dialogs() {
+        assert_eq!(
+            parse_binding("j down feedlist,artcilelist"),
+            Err(BindingError::UnknownContext("artcilelist".to_string()))
+        );
+        assert_eq!(
+            parse_binding("j down feedlist,,article"),
+            Err(BindingError::EmptyContext("feedlist,,article".to_string()))
+        );
+        assert_eq!(
+            parse_binding("j down feedlist,"),
+            Err(BindingError::EmptyContext("feedlist,".to_string()))
+        );
+        assert_eq!(
+            parse_binding("j down Feedlist").unwrap_err().to_message(),
+            "`Feedlist' is not a valid context"
+        );
+    }
+
+    #[test]
+    fn t_parse_binding_rejects_missing_and_extra_parameters() {
+        assert_eq!(parse_binding(""), Err(BindingError::TooFewParams));
+        assert_eq!(parse_binding("j"), Err(BindingError::TooFewParams));
+        assert_eq!(parse_binding(r#"j """#), Err(BindingError::TooFewParams));
+        assert_eq!(
+            parse_binding("j down article garbage"),
+            Err(BindingError::UnexpectedParam("garbage".to_string()))
+        );
+        assert_eq!(
+            parse_binding(r#"j "down && " article"#),
+            Err(BindingError::InvalidOperations {
+                operations: "down && ".to_string(),
+                error: OperationSequenceError {
+                    column: 6,
+                    kind: OperationSequenceErrorKind::MissingOperation("&&".to_string()),
+                },
+            })
+        );
+    }
+}
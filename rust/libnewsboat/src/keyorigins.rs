@@ -0,0 +1,530 @@
+//! Where key bindings and macros were defined.
+//!
+//! With the configuration split across several included files, it's hard to tell which one bound
+//! a key. `KeyOrigins` remembers the file and line of each `bind-key` and `macro`, so that
+//! a binding that replaces another one can say where both of them come from.
+//!
+//! Each operation also only does something in some contexts: `open-in-browser` has no effect in
+//! the file browser, for example. Once the config is loaded, `context_warnings()` points out the
+//! bindings and macros that will never trigger because of that.
+
+use gettextrs::gettext;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use strprintf::fmt;
+
+/// File and line of a config command.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Origin {
+    pub file: String,
+    pub line: u32,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Binding {
+    operation: String,
+    origin: Origin,
+    /// Whether the key was bound in all contexts at once (`bind-key` without a context).
+    everywhere: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Macro {
+    operations: Vec<String>,
+    origin: Origin,
+}
+
+#[derive(Debug, Default)]
+pub struct KeyOrigins {
+    /// Bindings by context and key.
+    bindings: BTreeMap<(String, String), Binding>,
+    macros: BTreeMap<String, Macro>,
+    /// Contexts in which each operation does something.
+    operation_contexts: BTreeMap<String, BTreeSet<String>>,
+    /// Contexts of the program that reads the config, which are the ones macros run in.
+    program_contexts: BTreeSet<String>,
+}
+
+impl KeyOrigins {
+    pub fn new() -> KeyOrigins {
+        KeyOrigins::default()
+    }
+
+    /// Records that `key` was bound to `operation` in `contexts` by the command at `origin`. A
+    /// binding for more than one context is a binding for all of them.
+    ///
+    /// Returns a warning if this replaces bindings that were defined elsewhere. Binding a key
+    /// again from the same place (e.g. when the config is sourced twice) isn't a conflict, and
+    /// neither is replacing a default binding.
+    pub fn bind(
+        &mut self,
+        contexts: &[String],
+        key: &str,
+        operation: &str,
+        origin: &Origin,
+    ) -> Option<String> {
+        // Contexts grouped by the binding they override, so that `bind-key` without a context
+        // doesn't produce a warning for each context
+        let mut overridden: Vec<(Binding, Vec<&str>)> = Vec::new();
+        for context in contexts {
+            let binding = Binding {
+                operation: operation.to_string(),
+                origin: origin.clone(),
+                everywhere: contexts.len() > 1,
+            };
+            let previous = self
+                .bindings
+                .insert((context.clone(), key.to_string()), binding);
+            match previous {
+                Some(previous) if previous.origin != *origin => {
+                    match overridden.iter_mut().find(|(b, _)| *b == previous) {
+                        Some((_, overridden_contexts)) => overridden_contexts.push(context),
+                        None => overridden.push((previous, vec![context])),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if overridden.is_empty() {
+            return None;
+        }
+
+        let sites = overridden
+            .iter()
+            .map(|(previous, overridden_contexts)| {
+                fmt!(
+                    &gettext("%s (%s in %s)"),
+                    &previous.origin.to_string(),
+                    &previous.operation,
+                    &overridden_contexts.join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        Some(fmt!(
+            &gettext("%s: binding `%s' to %s overrides binding from %s"),
+            &origin.to_string(),
+            key,
+            operation,
+            &sites
+        ))
+    }
+
+    /// Forgets where `key` was bound in `contexts`.
+    pub fn unbind(&mut self, contexts: &[String], key: &str) {
+        for context in contexts {
+            self.bindings.remove(&(context.clone(), key.to_string()));
+        }
+    }
+
+    /// Forgets where the keys in `contexts` were bound.
+    pub fn unbind_all(&mut self, contexts: &[String]) {
+        self.bindings
+            .retain(|(context, _), _| !contexts.contains(context));
+    }
+
+    pub fn binding_origin(&self, context: &str, key: &str) -> Option<&Origin> {
+        self.bindings
+            .get(&(context.to_string(), key.to_string()))
+            .map(|binding| &binding.origin)
+    }
+
+    /// Records that the macro for `key`, which runs `operations`, was defined by the command at
+    /// `origin`.
+    ///
+    /// Returns a warning if this replaces a macro that was defined elsewhere.
+    pub fn define_macro(
+        &mut self,
+        key: &str,
+        operations: &[String],
+        origin: &Origin,
+    ) -> Option<String> {
+        let definition = Macro {
+            operations: operations.to_vec(),
+            origin: origin.clone(),
+        };
+        match self.macros.insert(key.to_string(), definition) {
+            Some(previous) if previous.origin != *origin => Some(fmt!(
+                &gettext("%s: macro `%s' overrides macro from %s"),
+                &origin.to_string(),
+                key,
+                &previous.origin.to_string()
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn macro_origin(&self, key: &str) -> Option<&Origin> {
+        self.macros.get(key).map(|definition| &definition.origin)
+    }
+
+    /// Records that `operation` does something in `contexts`. Operations that aren't registered
+    /// are assumed to work everywhere.
+    pub fn register_operation(&mut self, operation: &str, contexts: &[String]) {
+        self.operation_contexts
+            .insert(operation.to_string(), contexts.iter().cloned().collect());
+    }
+
+    /// Records the contexts of the program that reads the config.
+    pub fn set_program_contexts(&mut self, contexts: &[String]) {
+        self.program_contexts = contexts.iter().cloned().collect();
+    }
+
+    /// Whether `operation` does something in any of `contexts`.
+    fn works_in<'a, I>(&self, operation: &str, mut contexts: I) -> bool
+    where
+        I: Iterator<Item = &'a String>,
+    {
+        match self.operation_contexts.get(operation) {
+            Some(valid) => contexts.any(|context| valid.contains(context)),
+            None => true,
+        }
+    }
+
+    /// Warnings about bindings and macros that will never trigger because their operations don't
+    /// do anything in the contexts where they're used.
+    ///
+    /// A binding for a single context must have an operation that works in that context. A key
+    /// bound everywhere only has to work in one of them, and a macro's operations each have to
+    /// work in one of the contexts of the program.
+    pub fn context_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut everywhere = BTreeSet::new();
+        for ((context, key), binding) in &self.bindings {
+            if binding.everywhere {
+                everywhere.insert((&binding.origin, key, &binding.operation));
+            } else if !self.works_in(&binding.operation, Some(context).into_iter()) {
+                warnings.push(fmt!(
+                    &gettext("%s: `%s' bound to `%s' in context `%s' will never trigger"),
+                    &binding.origin.to_string(),
+                    &binding.operation,
+                    key,
+                    context
+                ));
+            }
+        }
+
+        let all_contexts = self
+            .operation_contexts
+            .values()
+            .flatten()
+            .collect::<BTreeSet<_>>();
+        for (origin, key, operation) in everywhere {
+            if !self.works_in(operation, all_contexts.iter().cloned()) {
+                warnings.push(fmt!(
+                    &gettext("%s: `%s' bound to `%s' will never trigger: it works in no context"),
+                    &origin.to_string(),
+                    operation,
+                    key
+                ));
+            }
+        }
+
+        let macro_contexts: Vec<&String> = if self.program_contexts.is_empty() {
+            all_contexts.iter().cloned().collect()
+        } else {
+            self.program_contexts.iter().collect()
+        };
+        for (key, definition) in &self.macros {
+            for operation in &definition.operations {
+                if !self.works_in(operation, macro_contexts.iter().cloned()) {
+                    warnings.push(fmt!(
+                        &gettext("%s: `%s' in macro `%s' will never run: it works in no context"),
+                        &definition.origin.to_string(),
+                        operation,
+                        key
+                    ));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Describes what `key` does in `context`, and where that was defined. `operation` is the
+    /// operation bound to the key, if any, and `has_macro` tells if there's a macro for the key.
+    pub fn describe(
+        &self,
+        context: &str,
+        key: &str,
+        operation: Option<&str>,
+        has_macro: bool,
+    ) -> String {
+        let binding = match operation {
+            None => fmt!(&gettext("`%s' in %s: not bound"), key, context),
+            Some(operation) => match self.binding_origin(context, key) {
+                Some(origin) => fmt!(
+                    &gettext("`%s' in %s: %s, bound at %s"),
+                    key,
+                    context,
+                    operation,
+                    &origin.to_string()
+                ),
+                None => fmt!(
+                    &gettext("`%s' in %s: %s, default binding"),
+                    key,
+                    context,
+                    operation
+                ),
+            },
+        };
+
+        if !has_macro {
+            return binding;
+        }
+        let definition = match self.macro_origin(key) {
+            Some(origin) => fmt!(&gettext("macro defined at %s"), &origin.to_string()),
+            None => gettext("macro"),
+        };
+        format!("{}; {}", binding, definition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(file: &str, line: u32) -> Origin {
+        Origin {
+            file: file.to_string(),
+            line,
+        }
+    }
+
+    fn contexts(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn operations(names: &[&str]) -> Vec<String> {
+        contexts(names)
+    }
+
+    /// Origins that know a few operations of newsboat (feedlist, articlelist, filebrowser) and
+    /// podboat (podboat).
+    fn registered() -> KeyOrigins {
+        let mut origins = KeyOrigins::new();
+        origins.register_operation("open", &contexts(&["feedlist", "articlelist"]));
+        origins.register_operation("open-in-browser", &contexts(&["articlelist"]));
+        origins.register_operation(
+            "quit",
+            &contexts(&["feedlist", "articlelist", "filebrowser"]),
+        );
+        origins.register_operation("pb-download", &contexts(&["podboat"]));
+        origins.register_operation("nothing-anywhere", &[]);
+        origins.set_program_contexts(&contexts(&["feedlist", "articlelist", "filebrowser"]));
+        origins
+    }
+
+    #[test]
+    fn t_origin_is_displayed_as_file_colon_line() {
+        assert_eq!(
+            origin("conf.d/10-keys.conf", 14).to_string(),
+            "conf.d/10-keys.conf:14"
+        );
+    }
+
+    #[test]
+    fn t_rebinding_a_key_elsewhere_mentions_both_sites() {
+        let mut origins = KeyOrigins::new();
+        let first = origin("conf.d/10-keys.conf", 14);
+        let second = origin("config", 3);
+
+        assert_eq!(
+            origins.bind(&contexts(&["articlelist"]), "o", "open", &first),
+            None
+        );
+        assert_eq!(
+            origins.bind(&contexts(&["articlelist"]), "o", "open-in-browser", &second),
+            Some(
+                "config:3: binding `o' to open-in-browser overrides binding from \
+                conf.d/10-keys.conf:14 (open in articlelist)"
+                    .to_string()
+            )
+        );
+        assert_eq!(origins.binding_origin("articlelist", "o"), Some(&second));
+    }
+
+    #[test]
+    fn t_rebinding_from_the_same_site_is_not_a_conflict() {
+        let mut origins = KeyOrigins::new();
+        let site = origin("config", 3);
+
+        assert_eq!(
+            origins.bind(&contexts(&["feedlist"]), "o", "open", &site),
+            None
+        );
+        assert_eq!(
+            origins.bind(&contexts(&["feedlist"]), "o", "open", &site),
+            None
+        );
+    }
+
+    #[test]
+    fn t_contexts_that_override_the_same_binding_are_reported_together() {
+        let mut origins = KeyOrigins::new();
+        let all = contexts(&["feedlist", "articlelist", "article"]);
+        origins.bind(&all, "o", "open", &origin("a.conf", 1));
+        origins.bind(
+            &contexts(&["article"]),
+            "o",
+            "open-in-browser",
+            &origin("b.conf", 2),
+        );
+
+        assert_eq!(
+            origins.bind(&all, "o", "reload", &origin("c.conf", 3)),
+            Some(
+                "c.conf:3: binding `o' to reload overrides binding from \
+                a.conf:1 (open in feedlist, articlelist); \
+                b.conf:2 (open-in-browser in article)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn t_unbinding_forgets_the_origin() {
+        let mut origins = KeyOrigins::new();
+        let all = contexts(&["feedlist", "articlelist"]);
+        origins.bind(&all, "o", "open", &origin("a.conf", 1));
+        origins.bind(&all, "r", "reload", &origin("a.conf", 2));
+
+        origins.unbind(&contexts(&["feedlist"]), "o");
+        assert_eq!(origins.binding_origin("feedlist", "o"), None);
+        assert!(origins.binding_origin("articlelist", "o").is_some());
+
+        origins.unbind_all(&contexts(&["articlelist"]));
+        assert_eq!(origins.binding_origin("articlelist", "o"), None);
+        assert_eq!(origins.binding_origin("articlelist", "r"), None);
+        assert!(origins.binding_origin("feedlist", "r").is_some());
+
+        assert_eq!(origins.bind(&all, "o", "open", &origin("b.conf", 1)), None);
+    }
+
+    #[test]
+    fn t_redefining_a_macro_elsewhere_mentions_both_sites() {
+        let mut origins = KeyOrigins::new();
+        assert_eq!(
+            origins.define_macro("x", &operations(&["open"]), &origin("a.conf", 4)),
+            None
+        );
+        assert_eq!(
+            origins.define_macro("x", &operations(&["open"]), &origin("b.conf", 7)),
+            Some("b.conf:7: macro `x' overrides macro from a.conf:4".to_string())
+        );
+    }
+
+    #[test]
+    fn t_describe_tells_the_operation_context_and_definition_site() {
+        let mut origins = KeyOrigins::new();
+        origins.bind(
+            &contexts(&["articlelist"]),
+            "o",
+            "open-in-browser",
+            &origin("conf.d/10-keys.conf", 14),
+        );
+        origins.define_macro("o", &operations(&["open"]), &origin("config", 2));
+
+        assert_eq!(
+            origins.describe("articlelist", "o", Some("open-in-browser"), false),
+            "`o' in articlelist: open-in-browser, bound at conf.d/10-keys.conf:14"
+        );
+        assert_eq!(
+            origins.describe("feedlist", "r", Some("reload"), false),
+            "`r' in feedlist: reload, default binding"
+        );
+        assert_eq!(
+            origins.describe("feedlist", "z", None, false),
+            "`z' in feedlist: not bound"
+        );
+        assert_eq!(
+            origins.describe("feedlist", "o", Some("open"), true),
+            "`o' in feedlist: open, default binding; macro defined at config:2"
+        );
+    }
+
+    #[test]
+    fn t_binding_in_a_context_where_the_operation_does_nothing_is_reported() {
+        let mut origins = registered();
+        origins.bind(
+            &contexts(&["filebrowser"]),
+            "o",
+            "open-in-browser",
+            &origin("config", 12),
+        );
+        origins.bind(
+            &contexts(&["articlelist"]),
+            "O",
+            "open-in-browser",
+            &origin("config", 13),
+        );
+        assert_eq!(
+            origins.context_warnings(),
+            vec![
+                "config:12: `open-in-browser' bound to `o' in context `filebrowser' will never \
+                trigger"
+                    .to_string()
+            ]
+        );
+
+        origins.unbind(&contexts(&["filebrowser"]), "o");
+        assert!(origins.context_warnings().is_empty());
+    }
+
+    #[test]
+    fn t_binding_everywhere_only_needs_one_context_where_the_operation_works() {
+        let mut origins = registered();
+        let all = contexts(&["feedlist", "articlelist", "filebrowser", "podboat"]);
+        origins.bind(&all, "o", "open-in-browser", &origin("config", 1));
+        origins.bind(&all, "d", "pb-download", &origin("config", 2));
+        assert!(origins.context_warnings().is_empty());
+
+        origins.bind(&all, "n", "nothing-anywhere", &origin("config", 3));
+        assert_eq!(
+            origins.context_warnings(),
+            vec![
+                "config:3: `nothing-anywhere' bound to `n' will never trigger: it works in no \
+                context"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn t_macro_steps_that_work_in_no_context_of_the_program_are_reported() {
+        let mut origins = registered();
+        origins.define_macro(
+            "x",
+            &operations(&["open", "pb-download", "quit"]),
+            &origin("config", 5),
+        );
+        assert_eq!(
+            origins.context_warnings(),
+            vec![
+                "config:5: `pb-download' in macro `x' will never run: it works in no context"
+                    .to_string()
+            ]
+        );
+        // The warning doesn't affect the definition
+        assert_eq!(origins.macro_origin("x"), Some(&origin("config", 5)));
+    }
+
+    #[test]
+    fn t_operations_that_are_not_registered_are_assumed_to_work_everywhere() {
+        let mut origins = KeyOrigins::new();
+        origins.bind(
+            &contexts(&["filebrowser"]),
+            "o",
+            "open",
+            &origin("config", 1),
+        );
+        origins.define_macro("x", &operations(&["open"]), &origin("config", 2));
+        assert!(origins.context_warnings().is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+//! Translates Newsbeuter's config file into one that means the same thing to Newsboat.
+//!
+//! Most of Newsbeuter's config works unchanged. The exceptions are:
+//! - the `podbeuter` context of `bind-key` and `unbind-key`, which is now called `podboat`;
+//! - palette colors with leading zeros, like `color08`, which Newsboat doesn't accept anymore;
+//! - paths into Newsbeuter's directories. Those that point to a file which is imported along with
+//!   the config are redirected to the copy; the others can't be translated, since Newsboat would
+//!   then write into Newsbeuter's files, or read ones that were left behind.
+
+use crate::utils;
+use gettextrs::gettext;
+use std::path::{Path, PathBuf};
+use strprintf::fmt;
+
+/// A Newsbeuter directory whose files are copied into a Newsboat directory.
+#[derive(Debug, Clone, Copy)]
+pub struct MovedDir<'a> {
+    pub from: &'a Path,
+    pub to: &'a Path,
+    /// Names of the files that are copied.
+    pub files: &'a [&'a str],
+}
+
+/// A line that couldn't be translated, and was kept as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Untranslated {
+    /// One-based line number.
+    pub line: usize,
+    /// Internationalized explanation.
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    pub config: String,
+    pub untranslated: Vec<Untranslated>,
+}
+
+/// A token of a config line: its byte range in the line, and its text without the quotes.
+struct Token<'a> {
+    start: usize,
+    end: usize,
+    quoted: bool,
+    text: &'a str,
+}
+
+fn tokenize(line: &str) -> Vec<Token> {
+    let line = utils::strip_comments(line);
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, chr)) = chars.peek() {
+        if chr.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let quoted = chr == '"';
+        let mut end = line.len();
+        let mut prev_was_backslash = false;
+        chars.next();
+        while let Some(&(idx, chr)) = chars.peek() {
+            if quoted {
+                chars.next();
+                if chr == '"' && !prev_was_backslash {
+                    end = idx + 1;
+                    break;
+                }
+                prev_was_backslash = chr == '\\' && !prev_was_backslash;
+            } else if chr.is_whitespace() {
+                end = idx;
+                break;
+            } else {
+                chars.next();
+            }
+        }
+
+        let text = if quoted && end - start >= 2 && line[..end].ends_with('"') {
+            &line[start + 1..end - 1]
+        } else {
+            &line[start..end]
+        };
+        tokens.push(Token {
+            start,
+            end,
+            quoted,
+            text,
+        });
+    }
+    tokens
+}
+
+/// Translates a config written for Newsbeuter. `home` is the user's home directory, which `~`
+/// stands for.
+pub fn translate(config: &str, home: &Path, moved: &[MovedDir]) -> Translation {
+    let mut untranslated = Vec::new();
+    let lines = config
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            let (translated, problems) = translate_line(line, home, moved);
+            untranslated.extend(problems.into_iter().map(|message| Untranslated {
+                line: index + 1,
+                message,
+            }));
+            translated
+        })
+        .collect::<Vec<_>>();
+    Translation {
+        config: lines.join("\n"),
+        untranslated,
+    }
+}
+
+fn translate_line(line: &str, home: &Path, moved: &[MovedDir]) -> (String, Vec<String>) {
+    let tokens = tokenize(line);
+    let command = match tokens.first() {
+        Some(token) => token.text,
+        None => return (line.to_string(), Vec::new()),
+    };
+
+    let context_index = match command {
+        "bind-key" => Some(3),
+        "unbind-key" => Some(2),
+        _ => None,
+    };
+    let color_indices: &[usize] = match command {
+        "color" | "highlight-article" => &[2, 3],
+        "search-highlight-colors" => &[1, 2],
+        "highlight" => &[3, 4],
+        _ => &[],
+    };
+
+    let mut problems = Vec::new();
+    let mut replacements = Vec::new();
+    for (index, token) in tokens.iter().enumerate().skip(1) {
+        if Some(index) == context_index && token.text == "podbeuter" {
+            replacements.push((token, "podboat".to_string()));
+        } else if color_indices.contains(&index) {
+            match translate_color(token.text) {
+                Ok(Some(color)) => replacements.push((token, color)),
+                Ok(None) => {}
+                Err(message) => problems.push(message),
+            }
+        } else {
+            match translate_path(token.text, home, moved) {
+                Ok(Some(path)) => replacements.push((token, path)),
+                Ok(None) => {}
+                Err(message) => problems.push(message),
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        // A partly translated line would be even more confusing than an untranslated one
+        return (line.to_string(), problems);
+    }
+
+    let mut translated = String::with_capacity(line.len());
+    let mut copied_up_to = 0;
+    for (token, replacement) in replacements {
+        translated.push_str(&line[copied_up_to..token.start]);
+        if token.quoted {
+            translated.push('"');
+            translated.push_str(&replacement);
+            translated.push('"');
+        } else {
+            translated.push_str(&replacement);
+        }
+        copied_up_to = token.end;
+    }
+    translated.push_str(&line[copied_up_to..]);
+    (translated, Vec::new())
+}
+
+/// Strips leading zeros from `colorN`. Returns `None` if the color doesn't need translation.
+fn translate_color(color: &str) -> Result<Option<String>, String> {
+    let digits = match (color.get(..5), color.get(5..)) {
+        (Some("color"), Some(digits))
+            if digits.len() > 1
+                && digits.starts_with('0')
+                && digits.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            digits
+        }
+        _ => return Ok(None),
+    };
+
+    let trimmed = digits.trim_start_matches('0');
+    let number = if trimmed.is_empty() { "0" } else { trimmed };
+    match number.parse::<u8>() {
+        Ok(number) => Ok(Some(format!("color{}", number))),
+        Err(_) => Err(fmt!(
+            &gettext("`%s' is out of range (color0 to color255)"),
+            color
+        )),
+    }
+}
+
+/// Redirects a path into a Newsbeuter directory to the imported copy of the file. Returns
+/// `None` if the path doesn't point into a Newsbeuter directory.
+fn translate_path(path: &str, home: &Path, moved: &[MovedDir]) -> Result<Option<String>, String> {
+    let tilde = path == "~" || path.starts_with("~/");
+    let resolved = if tilde {
+        home.join(path[1..].trim_start_matches('/'))
+    } else {
+        PathBuf::from(path)
+    };
+
+    for dir in moved {
+        let relative = match resolved.strip_prefix(dir.from) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let is_imported = dir.files.iter().any(|file| relative == Path::new(file));
+        if !is_imported {
+            return Err(fmt!(
+                &gettext("`%s' is in Newsbeuter's directory, and wasn't imported; copy it yourself and change the path"),
+                path
+            ));
+        }
+
+        let target = dir.to.join(relative);
+        let target = match target.strip_prefix(home) {
+            Ok(relative) if tilde => Path::new("~").join(relative),
+            _ => target,
+        };
+        return Ok(Some(target.to_string_lossy().into_owned()));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILES: &[&str] = &["urls", "cache.db", "config"];
+
+    fn translate_dotdir(config: &str) -> Translation {
+        let home = Path::new("/home/user");
+        let moved = [MovedDir {
+            from: &home.join(".newsbeuter"),
+            to: &home.join(".newsboat"),
+            files: FILES,
+        }];
+        translate(config, home, &moved)
+    }
+
+    #[test]
+    fn t_keeps_lines_that_mean_the_same_thing_to_newsboat() {
+        let config =
+            "# my config\n\nauto-reload yes\nbind-key j down\ncolor listfocus red default bold\n";
+        assert_eq!(
+            translate_dotdir(config),
+            Translation {
+                config: config.to_string(),
+                untranslated: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn t_renames_the_podbeuter_context() {
+        let translation = translate_dotdir(
+            "bind-key d pb-download podbeuter\nunbind-key  q  \"podbeuter\" # old",
+        );
+        assert_eq!(
+            translation.config,
+            "bind-key d pb-download podboat\nunbind-key  q  \"podboat\" # old"
+        );
+        assert!(translation.untranslated.is_empty());
+
+        // Only the context is renamed
+        let config = "macro p set browser podbeuter";
+        assert_eq!(translate_dotdir(config).config, config);
+    }
+
+    #[test]
+    fn t_strips_leading_zeros_from_palette_colors() {
+        let translation = translate_dotdir(
+            "color article color08 color000\nsearch-highlight-colors color01 default\nhighlight all color01 color0255",
+        );
+        assert_eq!(
+            translation.config,
+            "color article color8 color0\nsearch-highlight-colors color1 default\nhighlight all color01 color255",
+        );
+        assert!(translation.untranslated.is_empty());
+    }
+
+    #[test]
+    fn t_reports_colors_that_are_out_of_range() {
+        let translation = translate_dotdir("auto-reload yes\ncolor article color0256 default");
+        assert_eq!(
+            translation.config,
+            "auto-reload yes\ncolor article color0256 default"
+        );
+        assert_eq!(translation.untranslated.len(), 1);
+        assert_eq!(translation.untranslated[0].line, 2);
+        assert!(translation.untranslated[0].message.contains("color0256"));
+    }
+
+    #[test]
+    fn t_redirects_paths_to_imported_files() {
+        let translation = translate_dotdir(
+            "cache-file \"~/.newsbeuter/cache.db\"\ncache-file /home/user/.newsbeuter/cache.db\nbrowser ~/bin/browser",
+        );
+        assert_eq!(
+            translation.config,
+            "cache-file \"~/.newsboat/cache.db\"\ncache-file /home/user/.newsboat/cache.db\nbrowser ~/bin/browser",
+        );
+        assert!(translation.untranslated.is_empty());
+    }
+
+    #[test]
+    fn t_reports_paths_to_files_that_were_not_imported() {
+        let translation =
+            translate_dotdir("include ~/.newsbeuter/colors\nerror-log ~/.newsbeuter/error.log");
+        assert_eq!(
+            translation.config,
+            "include ~/.newsbeuter/colors\nerror-log ~/.newsbeuter/error.log"
+        );
+        let lines = translation
+            .untranslated
+            .iter()
+            .map(|untranslated| untranslated.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![1, 2]);
+        assert!(translation.untranslated[0]
+            .message
+            .contains("~/.newsbeuter/colors"));
+    }
+}
@@ -6,17 +6,77 @@
 #[macro_use]
 pub mod logger;
 
+// Declared early for the same reason: it exports `fmt_plural!`.
+#[macro_use]
+pub mod plural;
+
 pub mod human_panic;
 pub mod utils;
 
+pub mod articlepreview;
+pub mod articleupdates;
+pub mod browser;
+pub mod cachemerge;
+pub mod cachemigration;
+pub mod cachequery;
 pub mod cliargsparser;
+pub mod clierror;
+pub mod clioutput;
+pub mod colormanager;
 pub mod configpaths;
+pub mod configvalidation;
+pub mod contentcache;
+pub mod controlsocket;
+pub mod defaultactions;
+pub mod describe;
+pub mod dialogstack;
+pub mod dirlist;
+pub mod downloadlimits;
+pub mod emptyfeedguard;
+pub mod feeddiscovery;
+pub mod feedicons;
+pub mod feedlistsnapshot;
+pub mod feednavigation;
+pub mod feedoptions;
+pub mod feedschedule;
+pub mod fileencoding;
+pub mod filterattributes;
+pub mod filterbuilder;
 pub mod filterparser;
 pub mod fmtstrformatter;
 pub mod fslock;
+pub mod help;
 pub mod history;
 pub mod htmlrenderer;
+pub mod hyphenation;
+pub mod initialsync;
+pub mod keycombination;
+pub mod keymap;
+pub mod keyorigins;
+pub mod legacyconfig;
+pub mod listposition;
+pub mod longoperation;
+pub mod markread;
+pub mod markreadundo;
 pub mod matchable;
 pub mod matcher;
 pub mod matchererror;
+pub mod memoryreport;
+pub mod networkretry;
+pub mod opml;
+pub mod parsers;
+pub mod readingtime;
+pub mod pipedarticle;
+pub mod reloadhooks;
+pub mod reloadprogress;
+pub mod reloadtag;
+pub mod renderjob;
+pub mod rowgroups;
+pub mod scopedcounts;
 pub mod scopemeasure;
+pub mod selection;
+pub mod stflbuilder;
+pub mod termcaps;
+pub mod unknownoptions;
+pub mod urlreader;
+pub mod urlsfile;
@@ -0,0 +1,268 @@
+//! Positions that `first-unread`, `last-unread` and `goto-percent` jump to in a list, and that
+//! `mark-above-read` and `mark-below-read` mark.
+//!
+//! An article list can easily have thousands of entries, so the unread ones aren't searched for
+//! on every jump. `UnreadIndex` keeps their positions in order, and is updated entry by entry as
+//! their state changes.
+
+use gettextrs::gettext;
+use std::collections::{BTreeSet, HashMap};
+use strprintf::fmt;
+
+/// Part of the list on one side of the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The entries shown before the cursor.
+    Above,
+    /// The entries shown after the cursor.
+    Below,
+}
+
+#[derive(Debug, Default)]
+pub struct UnreadIndex {
+    /// Position of each entry in the list, by key.
+    positions: HashMap<String, usize>,
+    /// Positions of the unread entries.
+    unread: BTreeSet<usize>,
+}
+
+impl UnreadIndex {
+    pub fn new() -> UnreadIndex {
+        UnreadIndex::default()
+    }
+
+    /// Sets the entries of the list, in the order in which they're shown, along with whether
+    /// each of them is unread.
+    pub fn reset<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = (String, bool)>,
+    {
+        self.positions.clear();
+        self.unread.clear();
+        for (position, (key, unread)) in entries.into_iter().enumerate() {
+            if unread {
+                self.unread.insert(position);
+            }
+            self.positions.insert(key, position);
+        }
+    }
+
+    /// Records whether the entry `key` is unread now. Keys that aren't in the list are ignored.
+    pub fn update(&mut self, key: &str, unread: bool) {
+        if let Some(&position) = self.positions.get(key) {
+            if unread {
+                self.unread.insert(position);
+            } else {
+                self.unread.remove(&position);
+            }
+        }
+    }
+
+    /// Position of the first unread entry.
+    pub fn first(&self) -> Option<usize> {
+        self.unread.iter().next().copied()
+    }
+
+    /// Position of the last unread entry.
+    pub fn last(&self) -> Option<usize> {
+        self.unread.iter().next_back().copied()
+    }
+
+    /// Positions of the unread entries on `side` of `cursor`, in the order in which they're
+    /// shown. The entry at `cursor` itself is never included.
+    pub fn unread_beside(&self, cursor: usize, side: Side) -> Vec<usize> {
+        match side {
+            Side::Above => self.unread.range(..cursor).copied().collect(),
+            Side::Below => match cursor.checked_add(1) {
+                Some(start) => self.unread.range(start..).copied().collect(),
+                None => Vec::new(),
+            },
+        }
+    }
+}
+
+/// Position that is `percent` of the way through a list of `len` entries: 0 is the first entry,
+/// and 100 (or anything above) the last one. Returns `None` if the list is empty.
+pub fn percent_position(percent: u32, len: usize) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    let percent = percent.min(100) as usize;
+    Some((last * percent + 50) / 100)
+}
+
+/// Parses the arguments of `goto-percent`, which takes a single number from 0 to 100. Returns
+/// an error message otherwise.
+pub fn parse_percent(arguments: &[String]) -> Result<u32, String> {
+    if arguments.len() != 1 {
+        return Err(gettext(
+            "goto-percent takes exactly one argument, a number from 0 to 100",
+        ));
+    }
+    match arguments[0].parse::<u32>() {
+        Ok(percent) if percent <= 100 => Ok(percent),
+        _ => Err(fmt!(
+            &gettext("`%s' is not a number from 0 to 100"),
+            arguments[0].as_str()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unread states of a long list, in a pattern that has long runs of both read and unread
+    /// entries as well as isolated ones.
+    fn states(len: usize) -> Vec<bool> {
+        (0..len).map(|i| i % 7 == 3 || (i / 100) % 3 == 1).collect()
+    }
+
+    fn index_of(states: &[bool]) -> UnreadIndex {
+        let mut index = UnreadIndex::new();
+        index.reset(
+            states
+                .iter()
+                .enumerate()
+                .map(|(i, &unread)| (format!("guid{}", i), unread)),
+        );
+        index
+    }
+
+    /// What the jumps used to do: look at every entry.
+    fn linear_first(states: &[bool]) -> Option<usize> {
+        states.iter().position(|&unread| unread)
+    }
+
+    fn linear_last(states: &[bool]) -> Option<usize> {
+        states.iter().rposition(|&unread| unread)
+    }
+
+    #[test]
+    fn t_unread_jumps_match_a_linear_scan() {
+        let mut states = states(2000);
+        let mut index = index_of(&states);
+        assert_eq!(index.first(), linear_first(&states));
+        assert_eq!(index.last(), linear_last(&states));
+
+        // Read everything from both ends, one entry at a time, checking after each change
+        for step in 0..1000 {
+            for &i in &[step, 1999 - step] {
+                states[i] = false;
+                index.update(&format!("guid{}", i), false);
+                assert_eq!(index.first(), linear_first(&states));
+                assert_eq!(index.last(), linear_last(&states));
+            }
+        }
+        assert_eq!(index.first(), None);
+        assert_eq!(index.last(), None);
+
+        states[1234] = true;
+        index.update("guid1234", true);
+        assert_eq!(index.first(), Some(1234));
+        assert_eq!(index.last(), Some(1234));
+    }
+
+    #[test]
+    fn t_update_ignores_entries_that_are_not_in_the_list() {
+        let mut index = index_of(&[false, true]);
+        index.update("unknown", true);
+        assert_eq!(index.first(), Some(1));
+        assert_eq!(index.last(), Some(1));
+    }
+
+    #[test]
+    fn t_reset_replaces_the_entries() {
+        let mut index = index_of(&[true, false, true]);
+        index.reset(vec![("b".to_string(), false), ("a".to_string(), true)]);
+        assert_eq!(index.first(), Some(1));
+        assert_eq!(index.last(), Some(1));
+        index.update("guid0", false);
+        assert_eq!(index.first(), Some(1));
+    }
+
+    #[test]
+    fn t_unread_beside_excludes_the_cursor_and_read_entries() {
+        let index = index_of(&[true, false, true, true, false, true]);
+        assert_eq!(index.unread_beside(3, Side::Above), vec![0, 2]);
+        assert_eq!(index.unread_beside(3, Side::Below), vec![5]);
+        assert_eq!(index.unread_beside(4, Side::Above), vec![0, 2, 3]);
+        assert_eq!(index.unread_beside(4, Side::Below), vec![5]);
+    }
+
+    #[test]
+    fn t_unread_beside_at_the_ends_of_the_list() {
+        let index = index_of(&[true, true, true]);
+        assert_eq!(index.unread_beside(0, Side::Above), Vec::<usize>::new());
+        assert_eq!(index.unread_beside(0, Side::Below), vec![1, 2]);
+        assert_eq!(index.unread_beside(2, Side::Above), vec![0, 1]);
+        assert_eq!(index.unread_beside(2, Side::Below), Vec::<usize>::new());
+        // A cursor past the end, e.g. in an empty list
+        assert_eq!(
+            index.unread_beside(usize::MAX, Side::Below),
+            Vec::<usize>::new()
+        );
+        assert_eq!(index.unread_beside(7, Side::Above), vec![0, 1, 2]);
+        assert_eq!(
+            UnreadIndex::new().unread_beside(0, Side::Above),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn t_unread_beside_follows_the_shown_order() {
+        // The list is whatever is shown: filtered, and sorted in reverse
+        let shown = vec![
+            ("guid9".to_string(), true),
+            ("guid7".to_string(), false),
+            ("guid4".to_string(), true),
+            ("guid1".to_string(), true),
+        ];
+        let mut index = UnreadIndex::new();
+        index.reset(shown);
+        assert_eq!(index.unread_beside(1, Side::Above), vec![0]);
+        assert_eq!(index.unread_beside(1, Side::Below), vec![2, 3]);
+
+        index.update("guid4", false);
+        assert_eq!(index.unread_beside(1, Side::Below), vec![3]);
+    }
+
+    #[test]
+    fn t_percent_position_maps_0_to_100_onto_the_list() {
+        assert_eq!(percent_position(0, 2000), Some(0));
+        assert_eq!(percent_position(100, 2000), Some(1999));
+        assert_eq!(percent_position(50, 2000), Some(1000));
+        assert_eq!(percent_position(76, 2000), Some(1519));
+        assert_eq!(percent_position(50, 1), Some(0));
+        assert_eq!(percent_position(50, 0), None);
+    }
+
+    #[test]
+    fn t_percent_position_clamps_to_the_last_entry() {
+        assert_eq!(percent_position(101, 10), Some(9));
+        assert_eq!(percent_position(u32::MAX, 10), Some(9));
+    }
+
+    #[test]
+    fn t_parse_percent_requires_one_number_from_0_to_100() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_percent(&args(&["0"])), Ok(0));
+        assert_eq!(parse_percent(&args(&["100"])), Ok(100));
+        assert_eq!(parse_percent(&args(&["42"])), Ok(42));
+
+        let arity = "goto-percent takes exactly one argument, a number from 0 to 100";
+        assert_eq!(parse_percent(&args(&[])), Err(arity.to_string()));
+        assert_eq!(parse_percent(&args(&["1", "2"])), Err(arity.to_string()));
+
+        assert_eq!(
+            parse_percent(&args(&["101"])),
+            Err("`101' is not a number from 0 to 100".to_string())
+        );
+        assert_eq!(
+            parse_percent(&args(&["-1"])),
+            Err("`-1' is not a number from 0 to 100".to_string())
+        );
+        assert_eq!(
+            parse_percent(&args(&["half"])),
+            Err("`half' is not a number from 0 to 100".to_string())
+        );
+    }
+}
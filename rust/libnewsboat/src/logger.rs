@@ -3,10 +3,12 @@
 use chrono::{offset::Local, Datelike, Timelike};
 use once_cell::sync::OnceCell;
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Mutex;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 /// "Importance levels" for log messages.
@@ -75,17 +77,159 @@ impl Default for Level {
     }
 }
 
+/// Number of rotated logs that are kept unless `set_keep_files()` says otherwise.
+const DEFAULT_KEEP_FILES: usize = 1;
+
+/// An open logfile, and the path it was opened at, which is needed to rotate it.
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    /// Device and inode of the file, which tell if two logs are written to the same file.
+    id: Option<(u64, u64)>,
+    /// Size of the file: what it held when it was opened, plus what was written to it since.
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> io::Result<LogFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata().ok();
+        Ok(LogFile {
+            file,
+            path: path.to_owned(),
+            id: metadata.as_ref().map(|m| (m.dev(), m.ino())),
+            size: metadata.map_or(0, |m| m.len()),
+        })
+    }
+}
+
+/// One of the two logs that `Logger` writes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Log {
+    General,
+    UserError,
+}
+
 /// Stores the handles for logfiles.
 ///
 /// This is part of `Logger` struct. This struct is not thread-safe, but in `Logger`, it will be
 /// behind a `Mutex`.
 struct LogFiles {
     /// The file to which all messages at and above `loglevel` will be written.
-    logfile: Option<File>,
+    logfile: Option<LogFile>,
 
     /// The file to which all Level::UserError messages will be written if `loglevel` is not
     /// Level::None.
-    user_error_logfile: Option<File>,
+    user_error_logfile: Option<LogFile>,
+
+    /// Size, in bytes, that a logfile may not exceed. Zero means that logfiles grow without bound.
+    max_size: u64,
+
+    /// Number of rotated logs to keep next to each logfile.
+    keep_files: usize,
+}
+
+impl LogFiles {
+    fn get(&mut self, log: Log) -> Option<&mut LogFile> {
+        match log {
+            Log::General => self.logfile.as_mut(),
+            Log::UserError => self.user_error_logfile.as_mut(),
+        }
+    }
+
+    /// Appends `line` to `log`, rotating the logfile first if `line` would make it too big.
+    ///
+    /// # Errors
+    ///
+    /// Failures are ignored, see `Logger::log_raw()`.
+    fn write_line(&mut self, log: Log, line: &[u8]) {
+        let max_size = self.max_size;
+        let length = line.len() as u64;
+        // A line that's longer than the limit still has to go somewhere, so an empty logfile is
+        // never rotated
+        let too_big = |size: u64| max_size != 0 && size != 0 && size + length > max_size;
+
+        let size = match self.get(log) {
+            Some(logfile) => logfile.size,
+            None => return,
+        };
+        if too_big(size) {
+            // The file could have been truncated or replaced behind our back, so only its actual
+            // size can tell if it has to be rotated
+            if let Some(logfile) = self.get(log) {
+                if let Ok(metadata) = logfile.file.metadata() {
+                    logfile.size = metadata.len();
+                }
+                if too_big(logfile.size) {
+                    self.rotate(log);
+                }
+            }
+        }
+
+        let (written, id) = match self.get(log) {
+            // Ignoring the error since checking every log() call will be too bothersome.
+            Some(logfile) => (logfile.file.write_all(line).is_ok(), logfile.id),
+            None => return,
+        };
+        // The other log could be written to the same file
+        if written {
+            for &other in &[Log::General, Log::UserError] {
+                if let Some(logfile) = self.get(other) {
+                    if other == log || (id.is_some() && logfile.id == id) {
+                        logfile.size += length;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renames the logfile of `log` to `<name>.1`, shifting older logs up to `<name>.<keep_files>`,
+    /// and starts a new logfile. The other log is moved to the new logfile too if it was written
+    /// to the same file.
+    fn rotate(&mut self, log: Log) {
+        let (path, id) = match self.get(log) {
+            Some(logfile) => (logfile.path.clone(), logfile.id),
+            None => return,
+        };
+
+        if rotate_file(&path, self.keep_files).is_err() {
+            return;
+        }
+
+        for &other in &[Log::General, Log::UserError] {
+            if let Some(logfile) = self.get(other) {
+                if logfile.id == id {
+                    if let Ok(reopened) = LogFile::open(&logfile.path) {
+                        *logfile = reopened;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Path of the `n`-th rotated log of the logfile at `path`.
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", n));
+    PathBuf::from(rotated)
+}
+
+/// Moves the file at `path` to `<path>.1`, after moving each `<path>.<n>` to `<path>.<n + 1>`. The
+/// oldest log is overwritten once there are `keep_files` of them; if `keep_files` is zero, the
+/// file is simply removed.
+fn rotate_file(path: &Path, keep_files: usize) -> io::Result<()> {
+    if keep_files == 0 {
+        return fs::remove_file(path);
+    }
+
+    for n in (1..keep_files).rev() {
+        match fs::rename(rotated_path(path, n), rotated_path(path, n + 1)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
 }
 
 /// Keeps a record of what the program did.
@@ -100,6 +244,15 @@ struct LogFiles {
 ///
 /// Each message in the log is time-stamped, and marked with its importance level.
 ///
+/// Each message also comes from a module, like "reloader" or "cache". set_module_levels() can
+/// give some modules a level of their own, which overrides the one from set_loglevel(). The levels
+/// can be changed at any time, from any thread.
+///
+/// Logs can be rotated: once set_max_size() is called, a logfile that's about to outgrow the limit
+/// is renamed to `<name>.1` (older logs are renamed to `<name>.2` and so on, up to the number set
+/// by set_keep_files()), and a new one is started in its place. Messages are never split across
+/// files.
+///
 /// This is meant to be a long-lived, shared object that exists for the duration of the program.
 /// Users would call its `log` method to add messages to the log file, like this:
 ///
@@ -115,10 +268,14 @@ struct LogFiles {
 ///
 /// // -- snip --
 ///
-/// logger.log(Level::UserError, "Please specify a remote API to use");
+/// logger.log("remoteapi", Level::UserError, "Please specify a remote API to use");
 ///
 /// // This one won't be logged because its importance level is too low
-/// logger.log(Level::Debug, &format!("feeds.len() == {}", 42));
+/// logger.log("feedcontainer", Level::Debug, &format!("feeds.len() == {}", 42));
+///
+/// // ...unless the module has a level of its own
+/// logger.set_module_levels(&[("feedcontainer", Level::Debug)]);
+/// logger.log("feedcontainer", Level::Debug, &format!("feeds.len() == {}", 42));
 /// ```
 pub struct Logger {
     /// Handles for the files to which messages should be written.
@@ -126,6 +283,16 @@ pub struct Logger {
 
     /// Maximum "importance level" of the messages that will be written to the log.
     loglevel: AtomicUsize,
+
+    /// Levels of the modules that don't use `loglevel`. Changed under `module_levels`' lock.
+    module_levels: RwLock<Vec<(String, Level)>>,
+
+    /// Whether `module_levels` is non-empty, so that it only has to be locked if it is.
+    has_module_levels: AtomicBool,
+
+    /// Maximum of `loglevel` and the module levels. Messages above it are dropped without
+    /// looking at their module.
+    max_loglevel: AtomicUsize,
 }
 
 impl Logger {
@@ -137,8 +304,13 @@ impl Logger {
             files: Mutex::new(LogFiles {
                 logfile: None,
                 user_error_logfile: None,
+                max_size: 0,
+                keep_files: DEFAULT_KEEP_FILES,
             }),
             loglevel: AtomicUsize::new(Level::None as usize),
+            module_levels: RwLock::new(Vec::new()),
+            has_module_levels: AtomicBool::new(false),
+            max_loglevel: AtomicUsize::new(Level::None as usize),
         }
     }
 
@@ -154,7 +326,7 @@ impl Logger {
     /// This can't fail, but if the file couldn't be created or opened, an error message will be
     /// printed to stderr.
     pub fn set_logfile(&self, filename: &str) {
-        let file = OpenOptions::new().create(true).append(true).open(filename);
+        let file = LogFile::open(Path::new(filename));
 
         match file {
             Ok(file) => {
@@ -179,7 +351,7 @@ impl Logger {
     /// This can't fail, but if the file couldn't be created or opened, an error message will be
     /// printed to stderr.
     pub fn set_user_error_logfile(&self, filename: &str) {
-        let file = OpenOptions::new().create(true).append(true).open(filename);
+        let file = LogFile::open(Path::new(filename));
 
         match file {
             Ok(file) => {
@@ -193,22 +365,66 @@ impl Logger {
         }
     }
 
-    /// Writes a message to a log.
+    /// Sets the size, in bytes, above which logfiles are rotated. Zero, the default, means that
+    /// logfiles are never rotated.
+    pub fn set_max_size(&self, bytes: u64) {
+        let mut files = self.files.lock().expect("Someone poisoned logger's mutex");
+        files.max_size = bytes;
+    }
+
+    /// Sets the number of rotated logs that are kept next to each logfile; older ones are
+    /// removed. Defaults to 1.
+    pub fn set_keep_files(&self, n: usize) {
+        let mut files = self.files.lock().expect("Someone poisoned logger's mutex");
+        files.keep_files = n;
+    }
+
+    /// Writes a message from `module` to a log.
     ///
     /// This method is a wrapper around `log_raw()`.
-    pub fn log(&self, level: Level, message: &str) {
-        if level == Level::UserError || level as usize <= self.get_loglevel() {
-            self.log_raw(level, message.as_bytes())
+    pub fn log(&self, module: &str, level: Level, message: &str) {
+        if self.is_enabled(module, level) {
+            self.log_raw(module, level, message.as_bytes())
         }
     }
 
-    /// Writes binary data to the log.
+    /// Whether a message from `module` at `level` would be written to any log. Callers can check
+    /// this before formatting a message.
+    ///
+    /// Unless some modules have levels of their own, this doesn't take any locks.
+    pub fn is_enabled(&self, module: &str, level: Level) -> bool {
+        level == Level::UserError || self.writes_to_logfile(module, level)
+    }
+
+    /// Whether a message from `module` at `level` should be written to the general log.
+    fn writes_to_logfile(&self, module: &str, level: Level) -> bool {
+        let level = level as usize;
+        if level > self.max_loglevel.load(Ordering::Relaxed) {
+            return false;
+        }
+        if !self.has_module_levels.load(Ordering::Relaxed) {
+            return level <= self.get_loglevel();
+        }
+
+        let module = module_name(module);
+        let module_levels = self
+            .module_levels
+            .read()
+            .expect("Someone poisoned logger's module levels");
+        let module_level = module_levels
+            .iter()
+            .find(|(name, _)| name == module)
+            .map(|&(_, level)| level as usize);
+        level <= module_level.unwrap_or_else(|| self.get_loglevel())
+    }
+
+    /// Writes binary data from `module` to the log.
     ///
     /// This method is primarily used for logging things received from C++. Since there is no
     /// guarantee that the data we got from C++ is valid UTF-8, we can't put it into &str. So we
     /// treat it as a binary stream, and log it using this method.
     ///
-    /// If `level` is lower than the logger's current level, the message won't be written. For
+    /// If `level` is lower than the current level of `module`, the message won't be written. For
     /// example, if Logger's level is set to Level::Critical, then Level::Error won't be written.
     ///
     /// Level::UserError messages are written to two logs: the general one, and error-logfile. See
@@ -218,7 +434,7 @@ impl Logger {
     ///
     /// If the message couldn't be written for whatever reason, this function ignores the failure.
     /// Were you to check the return value of every log() call, you'd just stop writing logs.
-    pub fn log_raw(&self, level: Level, data: &[u8]) {
+    pub fn log_raw(&self, module: &str, level: Level, data: &[u8]) {
         let timestamp = Local::now();
         // DateTime::format() is extremely slow; format! is way faster. See
         // https://github.com/chronotope/chrono/issues/94 for details.
@@ -234,25 +450,23 @@ impl Logger {
 
         let mut files = self.files.lock().expect("Someone poisoned logger's mutex");
 
-        if level as usize <= self.get_loglevel() {
-            if let Some(ref mut logfile) = files.logfile {
-                let level = format!("{}: ", level);
-
-                // Ignoring the error since checking every log() call will be too bothersome.
-                let _ = logfile.write_all(timestamp.as_bytes());
-                let _ = logfile.write_all(level.as_bytes());
-                let _ = logfile.write_all(data);
-                let _ = logfile.write_all(b"\n");
-            }
+        // Each line is written at once, so that it's never split by a rotation.
+        if self.writes_to_logfile(module, level) {
+            let level = format!("{}: ", level);
+            let mut line = Vec::with_capacity(timestamp.len() + level.len() + data.len() + 1);
+            line.extend_from_slice(timestamp.as_bytes());
+            line.extend_from_slice(level.as_bytes());
+            line.extend_from_slice(data);
+            line.push(b'\n');
+            files.write_line(Log::General, &line);
         }
 
         if level == Level::UserError {
-            if let Some(ref mut user_error_logfile) = files.user_error_logfile {
-                // Ignoring the error since checking every log() call will be too bothersome.
-                let _ = user_error_logfile.write_all(timestamp.as_bytes());
-                let _ = user_error_logfile.write_all(data);
-                let _ = user_error_logfile.write_all(b"\n");
-            }
+            let mut line = Vec::with_capacity(timestamp.len() + data.len() + 1);
+            line.extend_from_slice(timestamp.as_bytes());
+            line.extend_from_slice(data);
+            line.push(b'\n');
+            files.write_line(Log::UserError, &line);
         }
     }
 
@@ -264,9 +478,46 @@ impl Logger {
     /// If new level is Level::None, no logs will be written from now on. Otherwise, at least
     /// error-logfile will be written.
     ///
+    /// Modules that got a level from set_module_levels() keep it.
+    ///
     /// Calling this doesn't close already opened logs.
     pub fn set_loglevel(&self, level: Level) {
+        let module_levels = self
+            .module_levels
+            .write()
+            .expect("Someone poisoned logger's module levels");
         self.loglevel.store(level as usize, Ordering::SeqCst);
+        self.update_max_loglevel(&module_levels);
+    }
+
+    /// Gives each of the modules a level that's used instead of the one from set_loglevel().
+    /// Modules that were given a level by a previous call, but aren't in `levels`, go back to
+    /// using the one from set_loglevel().
+    ///
+    /// A module is named after the source file its messages come from, without the directory or
+    /// extension: e.g. "reloader" for src/reloader.cpp.
+    pub fn set_module_levels(&self, levels: &[(&str, Level)]) {
+        let mut module_levels = self
+            .module_levels
+            .write()
+            .expect("Someone poisoned logger's module levels");
+        *module_levels = levels
+            .iter()
+            .map(|&(module, level)| (module.to_string(), level))
+            .collect();
+        self.has_module_levels
+            .store(!module_levels.is_empty(), Ordering::SeqCst);
+        self.update_max_loglevel(&module_levels);
+    }
+
+    /// Recomputes `max_loglevel`. The caller holds `module_levels`' lock, so that concurrent
+    /// changes of the levels can't leave a stale maximum behind.
+    fn update_max_loglevel(&self, module_levels: &[(String, Level)]) {
+        let max = module_levels
+            .iter()
+            .map(|&(_, level)| level as usize)
+            .fold(self.get_loglevel(), usize::max);
+        self.max_loglevel.store(max, Ordering::SeqCst);
     }
 
     /// Returns current maximum "importance level" of the messages that will be written to the log.
@@ -283,6 +534,19 @@ impl Default for Logger {
     }
 }
 
+/// Turns `__FILE__` of C++ code, like "src/reloader.cpp", into a module name like "reloader".
+/// Names of Rust modules are returned as is.
+fn module_name(module: &str) -> &str {
+    let file_name = match module.rfind('/') {
+        Some(slash) => &module[slash + 1..],
+        None => module,
+    };
+    match file_name.find('.') {
+        Some(dot) => &file_name[..dot],
+        None => file_name,
+    }
+}
+
 static GLOBAL_LOGGER: OnceCell<Logger> = OnceCell::new();
 
 /// Returns a global logger instance.
@@ -295,22 +559,32 @@ pub fn get_instance() -> &'static Logger {
 
 /// Convenience macro for logging.
 ///
-/// Most of the time, you should just use this. For example:
+/// Most of the time, you should just use this. The first argument is the name of the module the
+/// message comes from, which is usually the name of the file. For example:
 /// ```no_run
 /// use libnewsboat::{log, logger::{self, Level}};
 ///
 /// fn super_cool_function(value: u32) {
-///     log!(Level::Debug, "super_cool_function(): value = {}", value);
+///     log!("supercool", Level::Debug, "super_cool_function(): value = {}", value);
 /// }
 /// ```
+///
+/// The message isn't formatted (or even evaluated) unless it's going to be written.
 #[macro_export]
 macro_rules! log {
-    ( $level:expr, $message:expr ) => {
-        logger::get_instance().log($level, $message);
-    };
-    ( $level:expr, $format:expr, $( $arg:expr ),+ ) => {
-        logger::get_instance().log($level, &format!($format, $( $arg ),+));
-    }
+    ( $module:expr, $level:expr, $message:expr ) => {{
+        let logger = logger::get_instance();
+        if logger.is_enabled($module, $level) {
+            let message: &str = $message;
+            logger.log_raw($module, $level, message.as_bytes());
+        }
+    }};
+    ( $module:expr, $level:expr, $format:expr, $( $arg:expr ),+ ) => {{
+        let logger = logger::get_instance();
+        if logger.is_enabled($module, $level) {
+            logger.log_raw($module, $level, format!($format, $( $arg ),+).as_bytes());
+        }
+    }};
 }
 
 #[cfg(test)]
@@ -318,8 +592,11 @@ mod tests {
     use super::*;
 
     use chrono::{Duration, TimeZone};
+    use std::collections::HashMap;
     use std::io::{self, BufRead, BufReader};
     use std::path;
+    use std::sync::Arc;
+    use std::thread;
     use tempfile::TempDir;
 
     fn setup_logger() -> io::Result<(TempDir, path::PathBuf, path::PathBuf, Logger)> {
@@ -446,7 +723,7 @@ mod tests {
                 logger.set_loglevel(*level);
 
                 for &(level, ref msg) in &self.messages {
-                    logger.log(level, msg);
+                    logger.log("test", level, msg);
                 }
 
                 drop(logger);
@@ -485,7 +762,7 @@ mod tests {
 
         let start_time = Local::now();
         for msg in &messages {
-            logger.log(Level::Debug, msg);
+            logger.log("test", Level::Debug, msg);
         }
         let finish_time = Local::now();
 
@@ -537,7 +814,7 @@ mod tests {
         logger.set_loglevel(Level::Debug);
 
         for &(level, _level_str) in &levels {
-            logger.log(level, msg);
+            logger.log("test", level, msg);
         }
 
         // Dropping logger to force it to flush the log and close the file
@@ -578,7 +855,7 @@ mod tests {
         let msg = "Some test message";
 
         for &(level, _level_str) in &levels {
-            logger.log(level, msg);
+            logger.log("test", level, msg);
         }
 
         // Dropping logger to force it to flush the log and close the file
@@ -805,7 +1082,7 @@ mod tests {
 
         let start_time = Local::now();
         for msg in &messages {
-            logger.log(Level::UserError, msg);
+            logger.log("test", Level::UserError, msg);
         }
         let finish_time = Local::now();
 
@@ -838,4 +1115,349 @@ mod tests {
             }
         }
     }
+
+    /// Lines of the logfile and of its rotated logs, oldest first, and the number of files.
+    fn read_rotated_logs(logfile: &path::Path) -> (Vec<String>, usize) {
+        let mut files = vec![logfile.to_owned()];
+        while rotated_path(logfile, files.len()).exists() {
+            files.push(rotated_path(logfile, files.len()));
+        }
+
+        let mut lines = vec![];
+        for file in files.iter().rev() {
+            let reader = BufReader::new(File::open(file).unwrap());
+            lines.extend(reader.lines().map(Result::unwrap));
+        }
+        (lines, files.len())
+    }
+
+    fn assert_no_file_exceeds(logfile: &path::Path, max_size: u64) {
+        let (_, count) = read_rotated_logs(logfile);
+        for n in 1..count {
+            assert!(fs::metadata(rotated_path(logfile, n)).unwrap().len() <= max_size);
+        }
+        assert!(fs::metadata(logfile).unwrap().len() <= max_size);
+    }
+
+    #[test]
+    fn t_rotates_logfile_that_would_exceed_max_size() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+        logger.set_max_size(2000);
+        logger.set_keep_files(100);
+
+        let messages = (0..200)
+            .map(|i| format!("message number {}", i))
+            .collect::<Vec<_>>();
+        for msg in &messages {
+            logger.log("test", Level::Debug, msg);
+        }
+        drop(logger);
+
+        let (lines, count) = read_rotated_logs(&logfile);
+        assert!(count >= 3);
+        assert_no_file_exceeds(&logfile, 2000);
+
+        assert_eq!(lines.len(), messages.len());
+        for (line, expected) in lines.iter().zip(&messages) {
+            let (_timestamp, level, message) =
+                parse_log_line(line).expect("Failed to split the log line into parts");
+            assert_eq!(level, "DEBUG");
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[test]
+    fn t_removes_rotated_logs_beyond_keep_files() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+        logger.set_max_size(500);
+        logger.set_keep_files(2);
+
+        for i in 0..200 {
+            logger.log("test", Level::Debug, &format!("message number {}", i));
+        }
+        drop(logger);
+
+        let (lines, count) = read_rotated_logs(&logfile);
+        assert_eq!(count, 3);
+        assert!(!rotated_path(&logfile, 3).exists());
+
+        // The newest messages survive, in order
+        let last = lines.len();
+        for (offset, line) in lines.iter().enumerate() {
+            let (_timestamp, _level, message) = parse_log_line(line).unwrap();
+            assert_eq!(message, format!("message number {}", 200 - last + offset));
+        }
+    }
+
+    #[test]
+    fn t_zero_keep_files_starts_the_logfile_anew() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+        logger.set_max_size(500);
+        logger.set_keep_files(0);
+
+        for i in 0..100 {
+            logger.log("test", Level::Debug, &format!("message number {}", i));
+        }
+        drop(logger);
+
+        let (lines, count) = read_rotated_logs(&logfile);
+        assert_eq!(count, 1);
+        assert!(!lines.is_empty());
+        let (_timestamp, _level, message) = parse_log_line(lines.last().unwrap()).unwrap();
+        assert_eq!(message, "message number 99");
+    }
+
+    #[test]
+    fn t_logs_are_not_rotated_by_default() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+
+        for i in 0..1000 {
+            logger.log("test", Level::Debug, &format!("message number {}", i));
+        }
+        drop(logger);
+
+        assert_eq!(read_rotated_logs(&logfile).1, 1);
+        log_contains_n_lines(&logfile, 1000).unwrap();
+    }
+
+    #[test]
+    fn t_logfile_truncated_by_someone_else_is_not_rotated_early() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+        logger.set_max_size(1000);
+        logger.set_keep_files(100);
+
+        let message = "x".repeat(200);
+        for _ in 0..4 {
+            logger.log("test", Level::Debug, &message);
+        }
+        OpenOptions::new()
+            .write(true)
+            .open(&logfile)
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+        for _ in 0..3 {
+            logger.log("test", Level::Debug, &message);
+        }
+        drop(logger);
+
+        assert_eq!(read_rotated_logs(&logfile).1, 1);
+        log_contains_n_lines(&logfile, 3).unwrap();
+    }
+
+    #[test]
+    fn t_rotation_works_when_both_logs_share_a_file() {
+        let tmp = TempDir::new().unwrap();
+        let logfile = tmp.path().join("shared.log");
+
+        let logger = Logger::new();
+        logger.set_logfile(logfile.to_str().unwrap());
+        logger.set_user_error_logfile(logfile.to_str().unwrap());
+        logger.set_loglevel(Level::Debug);
+        logger.set_max_size(1000);
+        logger.set_keep_files(100);
+
+        for i in 0..100 {
+            logger.log("test", Level::UserError, &format!("message number {}", i));
+        }
+        drop(logger);
+
+        let (lines, count) = read_rotated_logs(&logfile);
+        assert!(count >= 3);
+        assert_no_file_exceeds(&logfile, 1000);
+
+        // Each message is written once by each log
+        assert_eq!(lines.len(), 200);
+        for (i, pair) in lines.chunks(2).enumerate() {
+            let expected = format!("message number {}", i);
+            let (_timestamp, level, message) = parse_log_line(&pair[0]).unwrap();
+            assert_eq!(level, "USERERROR");
+            assert_eq!(message, expected);
+            let (_timestamp, message) = parse_errorlog_line(&pair[1]).unwrap();
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[test]
+    fn t_concurrent_messages_are_not_lost_or_split_by_rotation() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Debug);
+        logger.set_max_size(4096);
+        logger.set_keep_files(1000);
+
+        let logger = Arc::new(logger);
+        let threads = (0..4)
+            .map(|t| {
+                let logger = Arc::clone(&logger);
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        logger.log("test", Level::Debug, &format!("thread {} message {}", t, i));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        drop(logger);
+
+        let (lines, count) = read_rotated_logs(&logfile);
+        assert!(count >= 3);
+        assert_no_file_exceeds(&logfile, 4096);
+
+        // Every thread's messages are all there, in the order in which the thread wrote them
+        let mut next_message = HashMap::new();
+        for line in &lines {
+            let (_timestamp, _level, message) = parse_log_line(line).unwrap();
+            let words = message.split(' ').collect::<Vec<_>>();
+            assert_eq!(words.len(), 4);
+            let next = next_message.entry(words[1].to_string()).or_insert(0);
+            assert_eq!(words[3], next.to_string());
+            *next += 1;
+        }
+        assert_eq!(lines.len(), 1000);
+        assert!(next_message.values().all(|&count| count == 250));
+    }
+
+    #[test]
+    fn t_module_names_are_file_names_without_directory_and_extension() {
+        assert_eq!(module_name("reloader"), "reloader");
+        assert_eq!(module_name("src/reloader.cpp"), "reloader");
+        assert_eq!(module_name("/build/newsboat/src/cache.cpp"), "cache");
+        assert_eq!(module_name("include/logger.h"), "logger");
+        assert_eq!(module_name(""), "");
+    }
+
+    #[test]
+    fn t_module_levels_override_the_global_level_in_both_directions() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        logger.set_loglevel(Level::Error);
+        logger.set_module_levels(&[("reloader", Level::Debug), ("cache", Level::None)]);
+
+        assert!(logger.is_enabled("reloader", Level::Debug));
+        assert!(logger.is_enabled("src/reloader.cpp", Level::Info));
+        assert!(!logger.is_enabled("controller", Level::Warn));
+        assert!(logger.is_enabled("controller", Level::Error));
+        assert!(!logger.is_enabled("cache", Level::Critical));
+        // User errors still go to the error log
+        assert!(logger.is_enabled("cache", Level::UserError));
+
+        logger.log("reloader", Level::Debug, "reloader debug");
+        logger.log("controller", Level::Debug, "controller debug");
+        logger.log("controller", Level::Error, "controller error");
+        logger.log("cache", Level::Error, "cache error");
+        logger.log("src/reloader.cpp", Level::Info, "reloader info");
+
+        // The global level can change without affecting the modules
+        logger.set_loglevel(Level::Debug);
+        logger.log("controller", Level::Debug, "controller debug, again");
+        logger.log("cache", Level::Debug, "cache debug");
+
+        // Modules that don't get a level anymore use the global one
+        logger.set_module_levels(&[("reloader", Level::Error)]);
+        logger.log("reloader", Level::Debug, "reloader debug, again");
+        logger.log("cache", Level::Debug, "cache debug, again");
+        drop(logger);
+
+        let file = File::open(logfile).unwrap();
+        let messages = BufReader::new(file)
+            .lines()
+            .map(|line| parse_log_line(&line.unwrap()).unwrap().2.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "reloader debug",
+                "controller error",
+                "reloader info",
+                "controller debug, again",
+                "cache debug, again",
+            ]
+        );
+    }
+
+    #[test]
+    fn t_max_loglevel_covers_the_global_and_the_module_levels() {
+        let logger = Logger::new();
+        assert!(!logger.is_enabled("reloader", Level::Critical));
+
+        logger.set_module_levels(&[("reloader", Level::Info)]);
+        assert!(logger.is_enabled("reloader", Level::Info));
+        assert!(!logger.is_enabled("reloader", Level::Debug));
+        assert!(!logger.is_enabled("cache", Level::Critical));
+
+        logger.set_loglevel(Level::Debug);
+        assert!(logger.is_enabled("cache", Level::Debug));
+        assert!(!logger.is_enabled("reloader", Level::Debug));
+
+        logger.set_module_levels(&[]);
+        logger.set_loglevel(Level::Warn);
+        assert!(logger.is_enabled("reloader", Level::Warn));
+        assert!(!logger.is_enabled("reloader", Level::Info));
+    }
+
+    #[test]
+    fn t_levels_can_change_while_other_threads_log() {
+        let (_tmp, logfile, _error_logfile, logger) = setup_logger().unwrap();
+        let logger = Arc::new(logger);
+
+        let writers = (0..4)
+            .map(|t| {
+                let logger = Arc::clone(&logger);
+                thread::spawn(move || {
+                    for i in 0..500 {
+                        logger.log(
+                            "reloader",
+                            Level::Debug,
+                            &format!("thread {} message {}", t, i),
+                        );
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let changer = {
+            let logger = Arc::clone(&logger);
+            thread::spawn(move || {
+                for i in 0..500 {
+                    if i % 2 == 0 {
+                        logger.set_loglevel(Level::Debug);
+                        logger.set_module_levels(&[("cache", Level::Error)]);
+                    } else {
+                        logger.set_module_levels(&[("reloader", Level::None)]);
+                        logger.set_loglevel(Level::None);
+                    }
+                }
+                logger.set_module_levels(&[]);
+                logger.set_loglevel(Level::Debug);
+            })
+        };
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        changer.join().unwrap();
+
+        // Now that the levels settled, every message gets through
+        logger.log("reloader", Level::Debug, "last message");
+        drop(logger);
+
+        let file = File::open(logfile).unwrap();
+        let lines = BufReader::new(file)
+            .lines()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert!(lines.len() <= 2001);
+        for line in &lines {
+            let (_timestamp, level, message) = parse_log_line(line).unwrap();
+            assert_eq!(level, "DEBUG");
+            assert!(message.starts_with("thread ") || message == "last message");
+        }
+        assert!(lines.last().unwrap().ends_with("last message"));
+    }
 }
@@ -0,0 +1,263 @@
+//! Feedback for operations that can take a while, like `mark-all-feeds-read` on a big cache.
+//!
+//! While such an operation runs, the status line shows a spinner and its name. Once it's done,
+//! it's written to the log, and if it took so long that the user probably turned to something
+//! else, they can be notified. Operations that turn out to be fast leave no trace.
+
+use gettextrs::gettext;
+use std::time::{Duration, Instant};
+use strprintf::fmt;
+
+use crate::{
+    articlepreview::{Clock, SystemClock},
+    log,
+    logger::{self, Level},
+};
+
+/// Operations that finish sooner than this are fast: they aren't logged, and nobody is notified.
+pub const MIN_RECORDED_DURATION: Duration = Duration::from_millis(500);
+
+/// How long the spinner shows each of its frames.
+pub const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// An operation that took long enough to be worth mentioning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationRecord {
+    /// Name of the operation, like `mark-all-feeds-read`.
+    pub name: String,
+    pub duration: Duration,
+    pub succeeded: bool,
+}
+
+impl OperationRecord {
+    /// The line that's written to the log.
+    pub fn log_line(&self) -> String {
+        format!(
+            "LongOperation: `{}' {} after {:.3} s",
+            self.name,
+            if self.succeeded {
+                "succeeded"
+            } else {
+                "failed"
+            },
+            self.duration.as_secs_f64()
+        )
+    }
+
+    /// What the operation did, for the status line and notifications.
+    pub fn summary(&self) -> String {
+        if self.succeeded {
+            fmt!(
+                &gettext("`%s' finished in %.1f s"),
+                &self.name,
+                self.duration.as_secs_f64()
+            )
+        } else {
+            fmt!(
+                &gettext("`%s' failed after %.1f s"),
+                &self.name,
+                self.duration.as_secs_f64()
+            )
+        }
+    }
+}
+
+/// An operation that's running, and is expected to take a while.
+pub struct LongOperation<C: Clock = SystemClock> {
+    clock: C,
+    name: String,
+    start: Instant,
+}
+
+impl LongOperation<SystemClock> {
+    /// Starts timing the operation called `name`.
+    pub fn start(name: &str) -> LongOperation<SystemClock> {
+        LongOperation::with_clock(name, SystemClock)
+    }
+}
+
+impl<C: Clock> LongOperation<C> {
+    /// Starts timing the operation called `name` by `clock`.
+    pub fn with_clock(name: &str, clock: C) -> LongOperation<C> {
+        let start = clock.now();
+        LongOperation {
+            clock,
+            name: name.to_string(),
+            start,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now().duration_since(self.start)
+    }
+
+    /// The status line while the operation runs: a spinner that moves on every
+    /// `SPINNER_INTERVAL`, followed by the name of the operation.
+    pub fn status(&self) -> String {
+        let frame = (self.elapsed().as_millis() / SPINNER_INTERVAL.as_millis()) as usize
+            % SPINNER_FRAMES.len();
+        fmt!(
+            &gettext("%s Running `%s'..."),
+            &SPINNER_FRAMES[frame].to_string(),
+            &self.name
+        )
+    }
+
+    /// Ends the operation. Returns `None` if it was fast; otherwise writes it to the log and
+    /// returns the record.
+    pub fn finish(self, succeeded: bool) -> Option<OperationRecord> {
+        let duration = self.elapsed();
+        if duration < MIN_RECORDED_DURATION {
+            return None;
+        }
+
+        let record = OperationRecord {
+            name: self.name,
+            duration,
+            succeeded,
+        };
+        log!("longoperation", Level::Info, &record.log_line());
+        Some(record)
+    }
+}
+
+/// Whether to notify the user that the operation of `record` has finished: only if it took longer
+/// than `threshold` (`None` turns notifications off), and the terminal isn't focused. `focused` is
+/// `None` if the terminal doesn't tell, in which case the duration alone decides.
+pub fn should_notify(
+    record: &OperationRecord,
+    threshold: Option<Duration>,
+    focused: Option<bool>,
+) -> bool {
+    match threshold {
+        Some(threshold) => record.duration > threshold && focused != Some(true),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<Instant>>);
+
+    impl FakeClock {
+        fn new() -> FakeClock {
+            FakeClock(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, ms: u64) {
+            self.0.set(self.0.get() + Duration::from_millis(ms));
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    fn record(duration_ms: u64) -> OperationRecord {
+        OperationRecord {
+            name: "mark-all-feeds-read".to_string(),
+            duration: Duration::from_millis(duration_ms),
+            succeeded: true,
+        }
+    }
+
+    #[test]
+    fn t_fast_operations_leave_no_record() {
+        let clock = FakeClock::new();
+        let operation = LongOperation::with_clock("mark-all-feeds-read", clock.clone());
+
+        clock.advance(499);
+
+        assert_eq!(operation.finish(true), None);
+    }
+
+    #[test]
+    fn t_slow_operations_are_recorded() {
+        let clock = FakeClock::new();
+        let operation = LongOperation::with_clock("purge-deleted", clock.clone());
+
+        clock.advance(2345);
+
+        let record = operation.finish(false).unwrap();
+        assert_eq!(
+            record,
+            OperationRecord {
+                name: "purge-deleted".to_string(),
+                duration: Duration::from_millis(2345),
+                succeeded: false,
+            }
+        );
+        assert_eq!(
+            record.log_line(),
+            "LongOperation: `purge-deleted' failed after 2.345 s"
+        );
+        assert_eq!(record.summary(), "`purge-deleted' failed after 2.3 s");
+    }
+
+    #[test]
+    fn t_record_of_a_successful_operation() {
+        let record = record(MIN_RECORDED_DURATION.as_millis() as u64);
+
+        assert_eq!(
+            record.log_line(),
+            "LongOperation: `mark-all-feeds-read' succeeded after 0.500 s"
+        );
+        assert_eq!(record.summary(), "`mark-all-feeds-read' finished in 0.5 s");
+    }
+
+    #[test]
+    fn t_spinner_moves_on_every_interval_and_wraps_around() {
+        let clock = FakeClock::new();
+        let operation = LongOperation::with_clock("mark-all-feeds-read", clock.clone());
+
+        let mut frames = vec![];
+        for _ in 0..6 {
+            frames.push(operation.status());
+            clock.advance(SPINNER_INTERVAL.as_millis() as u64);
+        }
+
+        let expected = ['|', '/', '-', '\\', '|', '/']
+            .iter()
+            .map(|frame| format!("{} Running `mark-all-feeds-read'...", frame))
+            .collect::<Vec<_>>();
+        assert_eq!(frames, expected);
+    }
+
+    #[test]
+    fn t_spinner_stays_on_a_frame_within_an_interval() {
+        let clock = FakeClock::new();
+        let operation = LongOperation::with_clock("mark-all-feeds-read", clock.clone());
+
+        let first = operation.status();
+        clock.advance(SPINNER_INTERVAL.as_millis() as u64 - 1);
+        assert_eq!(operation.status(), first);
+        clock.advance(1);
+        assert_ne!(operation.status(), first);
+    }
+
+    #[test]
+    fn t_notifications_are_gated_by_the_threshold() {
+        let threshold = Some(Duration::from_secs(10));
+
+        assert!(!should_notify(&record(10_000), threshold, None));
+        assert!(should_notify(&record(10_001), threshold, None));
+        assert!(!should_notify(&record(60_000), None, None));
+    }
+
+    #[test]
+    fn t_nobody_is_notified_while_looking_at_the_terminal() {
+        let threshold = Some(Duration::from_secs(10));
+
+        assert!(!should_notify(&record(60_000), threshold, Some(true)));
+        assert!(should_notify(&record(60_000), threshold, Some(false)));
+    }
+}
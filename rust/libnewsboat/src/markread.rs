@@ -0,0 +1,340 @@
+//! Decides when an article that's being read becomes "read" (`mark-read-on`).
+//!
+//! The option is a comma-separated set of triggers:
+//! - `open`: as soon as the article is opened (the default);
+//! - `dwell:N`: after the article was shown for N seconds;
+//! - `scroll-end`: once the end of the article is on the screen;
+//! - `next`: when the user moves on to another article.
+//!
+//! An empty set means that articles only become read when the user marks them explicitly.
+
+use crate::articlepreview::{Clock, SystemClock};
+use gettextrs::gettext;
+use std::time::{Duration, Instant};
+use strprintf::fmt;
+
+/// Default for `mark-read-on`.
+pub const DEFAULT_MARK_READ_ON: &str = "open";
+
+/// Events that might make an article read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The article was opened, or the user did something in the article view.
+    Open = 0,
+
+    /// The article view checks whether the article was shown long enough.
+    Dwell = 1,
+
+    /// The article view was scrolled, or redrawn.
+    ScrollEnd = 2,
+
+    /// The user moves on to another article.
+    Next = 3,
+}
+
+impl Trigger {
+    pub fn from_u8(value: u8) -> Option<Trigger> {
+        match value {
+            0 => Some(Trigger::Open),
+            1 => Some(Trigger::Dwell),
+            2 => Some(Trigger::ScrollEnd),
+            3 => Some(Trigger::Next),
+            _ => None,
+        }
+    }
+}
+
+/// What the article view knows about the article at the time of a trigger.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    /// How long the article has been shown.
+    pub dwelled: Duration,
+
+    /// Whether the last line of the article is on the screen.
+    pub at_end: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkReadPolicy {
+    open: bool,
+    dwell: Option<Duration>,
+    scroll_end: bool,
+    next: bool,
+}
+
+impl Default for MarkReadPolicy {
+    fn default() -> MarkReadPolicy {
+        MarkReadPolicy {
+            open: true,
+            dwell: None,
+            scroll_end: false,
+            next: false,
+        }
+    }
+}
+
+impl MarkReadPolicy {
+    /// Parses the value of `mark-read-on`. Returns a message for the user if it's invalid.
+    pub fn parse(value: &str) -> Result<MarkReadPolicy, String> {
+        let mut policy = MarkReadPolicy {
+            open: false,
+            dwell: None,
+            scroll_end: false,
+            next: false,
+        };
+
+        for trigger in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let mut parts = trigger.splitn(2, ':');
+            let name = parts.next().unwrap_or_default();
+            let parameter = parts.next();
+            match (name, parameter) {
+                ("dwell", Some(seconds)) => match seconds.trim().parse::<u64>() {
+                    Ok(seconds) if seconds > 0 => {
+                        policy.dwell = Some(Duration::from_secs(seconds));
+                    }
+                    _ => {
+                        return Err(fmt!(
+                            &gettext("`%s' is not a positive number of seconds"),
+                            seconds
+                        ))
+                    }
+                },
+                ("dwell", None) => {
+                    return Err(gettext("`dwell' needs a number of seconds, e.g. `dwell:5'"))
+                }
+                ("open", None) => policy.open = true,
+                ("scroll-end", None) => policy.scroll_end = true,
+                ("next", None) => policy.next = true,
+                ("open", Some(_)) | ("scroll-end", Some(_)) | ("next", Some(_)) => {
+                    return Err(fmt!(&gettext("`%s' doesn't take a parameter"), name))
+                }
+                _ => return Err(fmt!(&gettext("unknown trigger `%s'"), trigger)),
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// How long an article has to be shown to become read, if `dwell` is enabled.
+    pub fn dwell(&self) -> Option<Duration> {
+        self.dwell
+    }
+
+    pub fn should_mark_read(&self, trigger: Trigger, context: &Context) -> bool {
+        match trigger {
+            Trigger::Open => self.open,
+            Trigger::Dwell => matches!(self.dwell, Some(dwell) if context.dwelled >= dwell),
+            Trigger::ScrollEnd => self.scroll_end && context.at_end,
+            Trigger::Next => self.next,
+        }
+    }
+}
+
+/// Measures how long the current article has been shown.
+#[derive(Debug)]
+pub struct DwellTimer<C: Clock = SystemClock> {
+    clock: C,
+    threshold: Option<Duration>,
+    started: Option<Instant>,
+}
+
+impl<C: Clock> DwellTimer<C> {
+    pub fn new(clock: C) -> DwellTimer<C> {
+        DwellTimer {
+            clock,
+            threshold: None,
+            started: None,
+        }
+    }
+
+    /// Starts measuring anew, e.g. because another article was opened. `threshold` is the dwell
+    /// time after which the article becomes read, if any.
+    pub fn start(&mut self, threshold: Option<Duration>) {
+        self.threshold = threshold;
+        self.started = Some(self.clock.now());
+    }
+
+    /// Stops measuring, because the user left the article.
+    pub fn cancel(&mut self) {
+        self.started = None;
+    }
+
+    /// How long the article has been shown; zero if the timer isn't running.
+    pub fn elapsed(&self) -> Duration {
+        self.started
+            .map(|started| self.clock.now().duration_since(started))
+            .unwrap_or_default()
+    }
+
+    /// How long until the threshold is reached, or `None` if the timer isn't running or has no
+    /// threshold.
+    pub fn time_left(&self) -> Option<Duration> {
+        self.started?;
+        let threshold = self.threshold?;
+        Some(threshold.checked_sub(self.elapsed()).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<Instant>>);
+
+    impl FakeClock {
+        fn new() -> FakeClock {
+            FakeClock(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, by: Duration) {
+            self.0.set(self.0.get() + by);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    fn context(dwelled_secs: u64, at_end: bool) -> Context {
+        Context {
+            dwelled: Duration::from_secs(dwelled_secs),
+            at_end,
+        }
+    }
+
+    #[test]
+    fn t_default_marks_read_on_open() {
+        let policy = MarkReadPolicy::parse(DEFAULT_MARK_READ_ON).unwrap();
+        assert_eq!(policy, MarkReadPolicy::default());
+        assert!(policy.should_mark_read(Trigger::Open, &Context::default()));
+        assert!(!policy.should_mark_read(Trigger::Next, &Context::default()));
+        assert!(!policy.should_mark_read(Trigger::ScrollEnd, &context(0, true)));
+        assert!(!policy.should_mark_read(Trigger::Dwell, &context(3600, false)));
+    }
+
+    #[test]
+    fn t_parses_sets_of_triggers() {
+        let policy = MarkReadPolicy::parse("dwell:5, scroll-end,next").unwrap();
+        assert_eq!(
+            policy,
+            MarkReadPolicy {
+                open: false,
+                dwell: Some(Duration::from_secs(5)),
+                scroll_end: true,
+                next: true,
+            }
+        );
+        assert_eq!(policy.dwell(), Some(Duration::from_secs(5)));
+
+        // The last `dwell` wins
+        let policy = MarkReadPolicy::parse("dwell:5,dwell:10").unwrap();
+        assert_eq!(policy.dwell(), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn t_empty_value_means_only_explicit_marking() {
+        for value in &["", " ", ","] {
+            let policy = MarkReadPolicy::parse(value).unwrap();
+            for trigger in &[
+                Trigger::Open,
+                Trigger::Dwell,
+                Trigger::ScrollEnd,
+                Trigger::Next,
+            ] {
+                assert!(!policy.should_mark_read(*trigger, &context(3600, true)));
+            }
+        }
+    }
+
+    #[test]
+    fn t_invalid_values_are_rejected() {
+        assert_eq!(
+            MarkReadPolicy::parse("open,hover"),
+            Err("unknown trigger `hover'".to_string())
+        );
+        assert_eq!(
+            MarkReadPolicy::parse("dwell"),
+            Err("`dwell' needs a number of seconds, e.g. `dwell:5'".to_string())
+        );
+        for seconds in &["0", "-3", "five", ""] {
+            assert_eq!(
+                MarkReadPolicy::parse(&format!("dwell:{}", seconds)),
+                Err(format!("`{}' is not a positive number of seconds", seconds))
+            );
+        }
+        assert_eq!(
+            MarkReadPolicy::parse("next:1"),
+            Err("`next' doesn't take a parameter".to_string())
+        );
+    }
+
+    #[test]
+    fn t_dwell_marks_read_only_after_the_threshold() {
+        let policy = MarkReadPolicy::parse("dwell:5").unwrap();
+        assert!(!policy.should_mark_read(Trigger::Open, &Context::default()));
+        assert!(!policy.should_mark_read(Trigger::Dwell, &context(4, false)));
+        assert!(policy.should_mark_read(Trigger::Dwell, &context(5, false)));
+    }
+
+    #[test]
+    fn t_scroll_end_marks_read_only_at_the_end() {
+        let policy = MarkReadPolicy::parse("scroll-end").unwrap();
+        assert!(!policy.should_mark_read(Trigger::ScrollEnd, &context(0, false)));
+        assert!(policy.should_mark_read(Trigger::ScrollEnd, &context(0, true)));
+        assert!(!policy.should_mark_read(Trigger::Open, &context(0, true)));
+    }
+
+    #[test]
+    fn t_next_marks_read_when_moving_on() {
+        let policy = MarkReadPolicy::parse("next").unwrap();
+        assert!(policy.should_mark_read(Trigger::Next, &Context::default()));
+        assert!(!policy.should_mark_read(Trigger::Open, &Context::default()));
+    }
+
+    #[test]
+    fn t_dwell_timer_counts_down_to_the_threshold() {
+        let clock = FakeClock::new();
+        let mut timer = DwellTimer::new(clock.clone());
+        assert_eq!(timer.time_left(), None);
+        assert_eq!(timer.elapsed(), Duration::from_secs(0));
+
+        timer.start(Some(Duration::from_secs(5)));
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(timer.elapsed(), Duration::from_secs(2));
+        assert_eq!(timer.time_left(), Some(Duration::from_secs(3)));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(timer.time_left(), Some(Duration::from_secs(0)));
+
+        // Opening another article starts over
+        timer.start(Some(Duration::from_secs(5)));
+        assert_eq!(timer.time_left(), Some(Duration::from_secs(5)));
+
+        timer.start(None);
+        assert_eq!(timer.time_left(), None);
+    }
+
+    #[test]
+    fn t_dwell_timer_is_cancelled_when_the_user_leaves() {
+        let clock = FakeClock::new();
+        let policy = MarkReadPolicy::parse("dwell:5").unwrap();
+        let mut timer = DwellTimer::new(clock.clone());
+
+        timer.start(policy.dwell());
+        clock.advance(Duration::from_secs(3));
+        timer.cancel();
+        clock.advance(Duration::from_secs(3));
+
+        let context = Context {
+            dwelled: timer.elapsed(),
+            at_end: false,
+        };
+        assert!(!policy.should_mark_read(Trigger::Dwell, &context));
+        assert_eq!(timer.time_left(), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Undo for `mark-above-read` and `mark-below-read`.
+//!
+//! Both can mark hundreds of articles read with a single key press, so each of them records the
+//! articles it marked, by key. `undo-mark-read` makes the most recently recorded ones unread
+//! again; the articles that were read already aren't part of the record, and stay read.
+
+/// How many records are kept; older ones are forgotten.
+pub const MAX_RECORDS: usize = 16;
+
+#[derive(Debug, Default)]
+pub struct MarkReadUndo {
+    /// Keys of the articles each operation marked read, oldest operation first.
+    records: Vec<Vec<String>>,
+}
+
+impl MarkReadUndo {
+    pub fn new() -> MarkReadUndo {
+        MarkReadUndo::default()
+    }
+
+    /// Records that the articles `keys` were marked read. Operations that marked nothing aren't
+    /// recorded, so that undoing doesn't seem to do nothing.
+    pub fn record(&mut self, keys: Vec<String>) {
+        if keys.is_empty() {
+            return;
+        }
+        if self.records.len() == MAX_RECORDS {
+            self.records.remove(0);
+        }
+        self.records.push(keys);
+    }
+
+    /// Keys of the articles that the most recent operation marked read, which is forgotten.
+    pub fn undo(&mut self) -> Option<Vec<String>> {
+        self.records.pop()
+    }
+
+    /// Forgets everything, e.g. when the list shows another feed.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn t_undo_returns_exactly_the_recorded_keys_newest_first() {
+        let mut undo = MarkReadUndo::new();
+        assert_eq!(undo.undo(), None);
+
+        undo.record(keys(&["guid1", "guid2"]));
+        undo.record(keys(&["guid7"]));
+        assert_eq!(undo.undo(), Some(keys(&["guid7"])));
+        assert_eq!(undo.undo(), Some(keys(&["guid1", "guid2"])));
+        assert_eq!(undo.undo(), None);
+    }
+
+    #[test]
+    fn t_empty_operations_are_not_recorded() {
+        let mut undo = MarkReadUndo::new();
+        undo.record(keys(&["guid1"]));
+        undo.record(Vec::new());
+        assert_eq!(undo.undo(), Some(keys(&["guid1"])));
+    }
+
+    #[test]
+    fn t_only_the_most_recent_records_are_kept() {
+        let mut undo = MarkReadUndo::new();
+        for i in 0..MAX_RECORDS + 2 {
+            undo.record(vec![format!("guid{}", i)]);
+        }
+        let mut undone = Vec::new();
+        while let Some(record) = undo.undo() {
+            undone.extend(record);
+        }
+        assert_eq!(undone.len(), MAX_RECORDS);
+        assert_eq!(undone.first().unwrap(), &format!("guid{}", MAX_RECORDS + 1));
+        assert_eq!(undone.last().unwrap(), "guid2");
+    }
+
+    #[test]
+    fn t_clear_forgets_everything() {
+        let mut undo = MarkReadUndo::new();
+        undo.record(keys(&["guid1"]));
+        undo.clear();
+        assert_eq!(undo.undo(), None);
+    }
+}
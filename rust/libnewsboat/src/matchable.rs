@@ -2,4 +2,11 @@
 pub trait Matchable {
     /// Returns the value of the attribute named `attr`, or `None` if there is no such attribute.
     fn attribute_value(&self, attr: &str) -> Option<String>;
+
+    /// Returns the date attribute `attr` as seconds since the Unix epoch, or `None` if there is no
+    /// such attribute.
+    ///
+    /// Relative dates like `"-7 days"` are compared against this rather than against
+    /// `attribute_value`, which shows the date in the user's locale and can't be parsed back.
+    fn date_timestamp(&self, attr: &str) -> Option<i64>;
 }
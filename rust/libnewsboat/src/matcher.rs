@@ -3,7 +3,9 @@
 use crate::filterparser::{self, Expression, Expression::*, Operator, Value};
 use crate::matchable::Matchable;
 use crate::matchererror::MatcherError;
+use chrono::{DateTime, TimeZone, Utc};
 use regex_rs::{CompFlags, MatchFlags, Regex};
+use std::collections::HashMap;
 
 /// Checks if given filter expression is true for a given feed or article.
 ///
@@ -14,6 +16,73 @@ pub struct Matcher {
 
     /// Input string from which `expr` was created
     text: String,
+
+    /// Regular expressions used in `expr`
+    regexes: Regexes,
+}
+
+/// Regular expressions of `=~` and `!~` comparisons, compiled once when the filter is parsed
+/// rather than every time it's matched against an item.
+struct Regexes(HashMap<String, Result<Regex, String>>);
+
+impl Regexes {
+    fn compile(expr: &Expression) -> Regexes {
+        let mut regexes = Regexes(HashMap::new());
+        regexes.add(expr);
+        regexes
+    }
+
+    fn add(&mut self, expr: &Expression) {
+        match expr {
+            Comparison {
+                op: Operator::RegexMatches,
+                value: Value::Literal(pattern),
+                ..
+            }
+            | Comparison {
+                op: Operator::NotRegexMatches,
+                value: Value::Literal(pattern),
+                ..
+            } => {
+                if !self.0.contains_key(pattern) {
+                    self.0.insert(pattern.clone(), compile_regex(pattern));
+                }
+            }
+            Comparison { .. } => {}
+            And(left, right) | Or(left, right) => {
+                self.add(left);
+                self.add(right);
+            }
+            Not(inner) => self.add(inner),
+        }
+    }
+
+    /// The compiled `pattern`, if it's one of the patterns of the expression.
+    fn get(&self, pattern: &str) -> Option<Result<&Regex, MatcherError>> {
+        self.0.get(pattern).map(|regex| match regex {
+            Ok(regex) => Ok(regex),
+            Err(errmsg) => Err(MatcherError::InvalidRegex {
+                regex: pattern.to_string(),
+                errmsg: errmsg.clone(),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of regexes that `compile_regex` compiled on this thread.
+    static REGEX_COMPILATIONS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+fn compile_regex(pattern: &str) -> Result<Regex, String> {
+    #[cfg(test)]
+    REGEX_COMPILATIONS.with(|count| count.set(count.get() + 1));
+
+    Regex::new(
+        pattern,
+        CompFlags::EXTENDED | CompFlags::IGNORE_CASE | CompFlags::NO_SUB,
+    )
 }
 
 impl Matcher {
@@ -23,15 +92,39 @@ impl Matcher {
     /// If `input` can't be parsed, returns an internalized error message.
     pub fn parse(input: &str) -> Result<Matcher, String> {
         let expr = filterparser::parse(input)?;
+        let regexes = Regexes::compile(&expr);
         Ok(Matcher {
             expr,
             text: input.to_string(),
+            regexes,
         })
     }
 
+    /// Prepare a `Matcher` for an expression that was built rather than parsed, e.g. with
+    /// `Expression::comparison`. `get_expression` then returns the expression as `parse` would
+    /// read it.
+    pub fn from_expression(expr: Expression) -> Matcher {
+        let regexes = Regexes::compile(&expr);
+        Matcher {
+            text: expr.to_string(),
+            expr,
+            regexes,
+        }
+    }
+
     /// Check if given matchable `item` matches the filter.
     pub fn matches(&self, item: &impl Matchable) -> Result<bool, MatcherError> {
-        evaluate_expression(&self.expr, item)
+        self.matches_at(item, Utc::now())
+    }
+
+    /// Like `matches`, but relative dates like `"-7 days"` are counted from `now` rather than
+    /// from the current time.
+    pub fn matches_at(
+        &self,
+        item: &impl Matchable,
+        now: DateTime<Utc>,
+    ) -> Result<bool, MatcherError> {
+        evaluate_expression(&self.expr, item, now, &self.regexes)
     }
 
     /// The filter expression from which this `Matcher` was constructed.
@@ -41,38 +134,88 @@ impl Matcher {
 }
 
 impl Operator {
-    fn apply(&self, attr: &str, value: &Value) -> Result<bool, MatcherError> {
+    /// Compares the text of an attribute to the bounds of a range. Only `between` reads the
+    /// bounds as numbers; other operators see the range as it was written, e.g. "1:10". An
+    /// attribute without a numeric prefix is never between anything.
+    fn apply_to_range(
+        &self,
+        attr: &str,
+        start: i64,
+        end: i64,
+        regexes: &Regexes,
+    ) -> Result<bool, MatcherError> {
+        match self {
+            Operator::Between => {
+                let low = std::cmp::min(start, end);
+                let high = std::cmp::max(start, end);
+                Ok(numeric_prefix(attr)
+                    .map(|i| i64::from(i) >= low && i64::from(i) <= high)
+                    .unwrap_or(false))
+            }
+            _ => self.apply_to_literal(attr, &format!("{}:{}", start, end), regexes),
+        }
+    }
+
+    /// Compares the date `timestamp` (seconds since the Unix epoch) to `moment`. Timestamps out of
+    /// range, and operators that don't order things, never match.
+    fn compare_date(&self, timestamp: i64, moment: DateTime<Utc>) -> bool {
+        let date = match Utc.timestamp_opt(timestamp, 0).single() {
+            Some(date) => date,
+            None => return false,
+        };
         match self {
-            Operator::Equals => Ok(attr == value.0),
-            Operator::NotEquals => Operator::Equals.apply(attr, value).map(|result| !result),
-            Operator::RegexMatches => match Regex::new(
-                &value.0,
-                CompFlags::EXTENDED | CompFlags::IGNORE_CASE | CompFlags::NO_SUB,
-            ) {
-                Ok(regex) => {
-                    let mut result = false;
-                    let max_matches = 1;
-                    if let Ok(matches) = regex.matches(attr, max_matches, MatchFlags::empty()) {
-                        // Ok with non-empty Vec inside means a match was found
-                        result = !matches.is_empty();
+            Operator::Equals => date == moment,
+            Operator::NotEquals => date != moment,
+            Operator::LessThan => date < moment,
+            Operator::GreaterThan => date > moment,
+            Operator::LessThanOrEquals => date <= moment,
+            Operator::GreaterThanOrEquals => date >= moment,
+            _ => false,
+        }
+    }
+
+    fn apply_to_literal(
+        &self,
+        attr: &str,
+        value: &str,
+        regexes: &Regexes,
+    ) -> Result<bool, MatcherError> {
+        match self {
+            Operator::Equals => Ok(attr == value),
+            Operator::NotEquals => Operator::Equals
+                .apply_to_literal(attr, value, regexes)
+                .map(|result| !result),
+            Operator::RegexMatches => {
+                let compiled;
+                let regex = match regexes.get(value) {
+                    Some(regex) => regex?,
+                    None => {
+                        compiled =
+                            compile_regex(value).map_err(|errmsg| MatcherError::InvalidRegex {
+                                regex: value.to_string(),
+                                errmsg,
+                            })?;
+                        &compiled
                     }
-                    Ok(result)
-                }
+                };
 
-                Err(errmsg) => Err(MatcherError::InvalidRegex {
-                    regex: value.0.clone(),
-                    errmsg,
-                }),
-            },
+                let mut result = false;
+                let max_matches = 1;
+                if let Ok(matches) = regex.matches(attr, max_matches, MatchFlags::empty()) {
+                    // Ok with non-empty Vec inside means a match was found
+                    result = !matches.is_empty();
+                }
+                Ok(result)
+            }
             Operator::NotRegexMatches => Operator::RegexMatches
-                .apply(attr, value)
+                .apply_to_literal(attr, value, regexes)
                 .map(|result| !result),
-            Operator::LessThan => Ok(string_to_num(attr) < string_to_num(&value.0)),
-            Operator::GreaterThan => Ok(dbg!(string_to_num(attr)) > dbg!(string_to_num(&value.0))),
-            Operator::LessThanOrEquals => Ok(string_to_num(attr) <= string_to_num(&value.0)),
-            Operator::GreaterThanOrEquals => Ok(string_to_num(attr) >= string_to_num(&value.0)),
+            Operator::LessThan => Ok(string_to_num(attr) < string_to_num(value)),
+            Operator::GreaterThan => Ok(string_to_num(attr) > string_to_num(value)),
+            Operator::LessThanOrEquals => Ok(string_to_num(attr) <= string_to_num(value)),
+            Operator::GreaterThanOrEquals => Ok(string_to_num(attr) >= string_to_num(value)),
             Operator::Between => {
-                let fields = value.0.split(':').collect::<Vec<_>>();
+                let fields = value.split(':').collect::<Vec<_>>();
                 if fields.len() != 2 {
                     return Ok(false);
                 }
@@ -82,18 +225,21 @@ impl Operator {
 
                 let low = std::cmp::min(a, b);
                 let high = std::cmp::max(a, b);
-                let i = string_to_num(attr);
-                Ok(i >= low && i <= high)
+                Ok(numeric_prefix(attr)
+                    .map(|i| i >= low && i <= high)
+                    .unwrap_or(false))
             }
             Operator::Contains => {
                 for token in attr.split(' ') {
-                    if token == value.0 {
+                    if token == value {
                         return Ok(true);
                     }
                 }
                 Ok(false)
             }
-            Operator::NotContains => Operator::Contains.apply(attr, value).map(|result| !result),
+            Operator::NotContains => Operator::Contains
+                .apply_to_literal(attr, value, regexes)
+                .map(|result| !result),
         }
     }
 }
@@ -103,56 +249,82 @@ impl Operator {
 /// Return 0 if there is no numeric prefix. On underflow, return `std::i32::MIN`. On overflow,
 /// return `std::i32::MAX`.
 fn string_to_num(input: &str) -> i32 {
+    numeric_prefix(input).unwrap_or(0)
+}
+
+/// Like `string_to_num`, but returns `None` if there is no numeric prefix.
+fn numeric_prefix(input: &str) -> Option<i32> {
     let search_start = if input.starts_with('-') { 1 } else { 0 };
 
     let numerics_end = input[search_start..]
         .find(|c: char| !c.is_numeric())
-        .unwrap_or_else(|| input.len())
+        .unwrap_or_else(|| input.len() - search_start)
         + search_start; // Adding the starting offset to get an index inside the original input
 
     if numerics_end - search_start == 0 {
         // No numeric prefix
-        return 0;
+        return None;
     }
 
     if let Ok(number) = input[..numerics_end].parse::<i32>() {
-        number
+        Some(number)
     } else if search_start == 1 {
         // Number starts with minus and couldn't be parsed => underflow
-        std::i32::MIN
+        Some(std::i32::MIN)
     } else {
         // Number doesn't start with minus and couldn't be parsed => overflow
-        std::i32::MAX
+        Some(std::i32::MAX)
     }
 }
 
-fn evaluate_expression(expr: &Expression, item: &impl Matchable) -> Result<bool, MatcherError> {
+fn evaluate_expression(
+    expr: &Expression,
+    item: &impl Matchable,
+    now: DateTime<Utc>,
+    regexes: &Regexes,
+) -> Result<bool, MatcherError> {
     match expr {
         Comparison {
             attribute,
             op,
             value,
-        } => match item.attribute_value(&attribute) {
-            None => Err(MatcherError::AttributeUnavailable {
+        } => {
+            let unavailable = || MatcherError::AttributeUnavailable {
                 attr: attribute.clone(),
-            }),
-
-            Some(ref attr) => op.apply(attr, &value),
-        },
-        And(left, right) => evaluate_expression(left, item).and_then(|result| {
+            };
+            match value {
+                Value::RelativeDate(offset) => {
+                    let timestamp = item.date_timestamp(&attribute).ok_or_else(unavailable)?;
+                    Ok(now
+                        .checked_add_signed(*offset)
+                        .map(|moment| op.compare_date(timestamp, moment))
+                        .unwrap_or(false))
+                }
+                Value::Literal(literal) => {
+                    let attr = item.attribute_value(&attribute).ok_or_else(unavailable)?;
+                    op.apply_to_literal(&attr, literal, regexes)
+                }
+                Value::Range(start, end) => {
+                    let attr = item.attribute_value(&attribute).ok_or_else(unavailable)?;
+                    op.apply_to_range(&attr, *start, *end, regexes)
+                }
+            }
+        }
+        And(left, right) => evaluate_expression(left, item, now, regexes).and_then(|result| {
             if result {
-                evaluate_expression(right, item)
+                evaluate_expression(right, item, now, regexes)
             } else {
                 Ok(false)
             }
         }),
-        Or(left, right) => evaluate_expression(left, item).and_then(|result| {
+        Or(left, right) => evaluate_expression(left, item, now, regexes).and_then(|result| {
             if result {
                 Ok(true)
             } else {
-                evaluate_expression(right, item)
+                evaluate_expression(right, item, now, regexes)
             }
         }),
+        Not(inner) => evaluate_expression(inner, item, now, regexes).map(|result| !result),
     }
 }
 
@@ -160,10 +332,12 @@ fn evaluate_expression(expr: &Expression, item: &impl Matchable) -> Result<bool,
 mod tests {
     use super::*;
 
+    use chrono::Duration;
     use std::collections::BTreeMap;
 
     struct MockMatchable {
         values: BTreeMap<String, String>,
+        timestamps: BTreeMap<String, i64>,
     }
 
     impl MockMatchable {
@@ -173,7 +347,19 @@ mod tests {
                     .iter()
                     .map(|(a, b)| (String::from(*a), String::from(*b)))
                     .collect::<BTreeMap<_, _>>(),
+                timestamps: BTreeMap::new(),
+            }
+        }
+
+        /// A matchable with date attributes, given as they are shown, and their timestamps taken
+        /// from `dates`, which are in RFC 2822 format.
+        pub fn with_dates(values: &[(&str, &str)], dates: &[(&str, &str)]) -> MockMatchable {
+            let mut mock = MockMatchable::new(values);
+            for (attr, date) in dates {
+                let timestamp = DateTime::parse_from_rfc2822(date).unwrap().timestamp();
+                mock.timestamps.insert(String::from(*attr), timestamp);
             }
+            mock
         }
     }
 
@@ -181,6 +367,10 @@ mod tests {
         fn attribute_value(&self, attr: &str) -> Option<String> {
             self.values.get(attr).cloned()
         }
+
+        fn date_timestamp(&self, attr: &str) -> Option<i64> {
+            self.timestamps.get(attr).copied()
+        }
     }
 
     #[test]
@@ -393,6 +583,49 @@ mod tests {
         check("BBBB between 1:23");
     }
 
+    #[test]
+    fn t_negation_flips_the_result() {
+        let mock = MockMatchable::new(&[("tags", "podcast news"), ("title", "Sponsored post")]);
+        let check = |expression| Matcher::parse(expression).unwrap().matches(&mock).unwrap();
+
+        assert!(!check(r#"!(tags # "podcast" or title =~ "sponsored")"#));
+        assert!(check(r#"!(tags # "music" or title =~ "review")"#));
+        assert!(check(r#"not tags # "music" and title =~ "post""#));
+        assert!(check(r#"!!(tags # "news")"#));
+        assert!(!check(r#"not !(tags # "music")"#));
+    }
+
+    #[test]
+    fn t_negation_doesnt_hide_undefined_fields() {
+        let mock = MockMatchable::new(&[("AAAA", "1")]);
+
+        // An unavailable attribute is an error, negated or not
+        match Matcher::parse("!(BBBB = 1)").unwrap().matches(&mock) {
+            Err(MatcherError::AttributeUnavailable { .. }) => { /* that's the expected result */ }
+            result => panic!(format!("unexpected result: {:?}", result)),
+        }
+
+        // ...unless it's never looked at, just like without negation
+        assert!(Matcher::parse("!(AAAA = 2 and BBBB = 1)")
+            .unwrap()
+            .matches(&mock)
+            .unwrap());
+    }
+
+    #[test]
+    fn t_regexes_inside_negations_are_compiled() {
+        let mock = MockMatchable::new(&[("AAAA", "12345")]);
+
+        match Matcher::parse("!(AAAA =~ \"[[\")").unwrap().matches(&mock) {
+            Err(MatcherError::InvalidRegex { .. }) => { /* that's the expected result */ }
+            result => panic!(format!("unexpected result: {:?}", result)),
+        }
+        assert!(!Matcher::parse("!(AAAA =~ \"^1\")")
+            .unwrap()
+            .matches(&mock)
+            .unwrap());
+    }
+
     #[test]
     fn t_error_on_invalid_regex() {
         let mock = MockMatchable::new(&[("AAAA", "12345")]);
@@ -408,6 +641,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn t_regexes_are_compiled_once_per_matcher() {
+        let compilations = || REGEX_COMPILATIONS.with(|count| count.get());
+        let before = compilations();
+
+        let matcher =
+            Matcher::parse(r#"title =~ "rust" or (author !~ "^ak$" and title =~ "rust")"#).unwrap();
+        // The repeated pattern is compiled only once
+        assert_eq!(compilations() - before, 2);
+
+        let mock = MockMatchable::new(&[("title", "Rust 1.0"), ("author", "ak")]);
+        for _ in 0..1000 {
+            assert!(matcher.matches(&mock).unwrap());
+        }
+        assert_eq!(compilations() - before, 2);
+    }
+
+    #[test]
+    fn t_invalid_regex_is_reported_on_every_match() {
+        let mock = MockMatchable::new(&[("AAAA", "12345")]);
+        let matcher = Matcher::parse(r#"AAAA = "x" or AAAA =~ "[[""#).unwrap();
+        for _ in 0..2 {
+            match matcher.matches(&mock) {
+                Err(MatcherError::InvalidRegex { regex, .. }) => assert_eq!(regex, "[["),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+    }
+
     #[test]
     fn t_test_not_regex_match_works_with_strings() {
         let mock = MockMatchable::new(&[("AAAA", "12345")]);
@@ -912,7 +1174,7 @@ mod tests {
             .unwrap()
             .matches(&mock)
             .unwrap());
-        assert!(Matcher::parse("practically_zero between 0:1")
+        assert!(!Matcher::parse("practically_zero between 0:1")
             .unwrap()
             .matches(&mock)
             .unwrap());
@@ -922,6 +1184,167 @@ mod tests {
             .unwrap());
     }
 
+    #[test]
+    fn t_test_operator_between_includes_both_bounds() {
+        let mock = MockMatchable::new(&[("low", "10"), ("high", "20"), ("outside", "21")]);
+        let matches =
+            |expression: &str| Matcher::parse(expression).unwrap().matches(&mock).unwrap();
+
+        assert!(matches("low between 10:20"));
+        assert!(matches("high between 10:20"));
+        assert!(!matches("outside between 10:20"));
+
+        // Reversed bounds mean the same range
+        assert!(matches("low between 20:10"));
+        assert!(matches("high between 20:10"));
+        assert!(!matches("outside between 20:10"));
+    }
+
+    #[test]
+    fn t_test_operator_between_works_with_negative_ranges() {
+        let mock = MockMatchable::new(&[("temp", "-5"), ("zero", "0")]);
+        let matches =
+            |expression: &str| Matcher::parse(expression).unwrap().matches(&mock).unwrap();
+
+        assert!(matches("temp between -10:-1"));
+        assert!(matches("temp between -1:-10"));
+        assert!(matches("temp between -5:-5"));
+        assert!(!matches("temp between -4:-1"));
+        assert!(matches("zero between -1:1"));
+        assert!(!matches("zero between -10:-1"));
+    }
+
+    #[test]
+    fn t_test_operator_between_doesnt_match_non_numeric_attributes() {
+        // Unlike `<` and `>`, which read them as zero
+        let mock = MockMatchable::new(&[("title", "Rust"), ("empty", ""), ("minus", "-")]);
+        let matches =
+            |expression: &str| Matcher::parse(expression).unwrap().matches(&mock).unwrap();
+
+        assert!(!matches("title between -1:1"));
+        assert!(!matches("title between 1:10"));
+        assert!(!matches("empty between 0:0"));
+        assert!(!matches("empty between -10:-1"));
+        assert!(!matches("minus between -1:1"));
+        assert!(!matches(r#"title between "-1:1""#));
+        assert!(matches("title < 1"));
+    }
+
+    /// Pretends that it's noon of 10 June 2021 in UTC.
+    fn fake_now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc2822("Thu, 10 Jun 2021 12:00:00 +0000")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn t_relative_dates_are_counted_from_the_time_of_matching() {
+        let dates = [
+            ("date", "Mon, 07 Jun 2021 12:00:00 +0000"),
+            ("feeddate", "Thu, 10 Jun 2021 02:00:00 +0200"),
+        ];
+        let mock = MockMatchable::with_dates(&dates, &dates);
+        let matches = |expression: &str| {
+            Matcher::parse(expression)
+                .unwrap()
+                .matches_at(&mock, fake_now())
+                .unwrap()
+        };
+
+        assert!(matches(r#"date > "-7 days""#));
+        assert!(!matches(r#"date > "-2 days""#));
+        assert!(matches(r#"date < "-2 days""#));
+        assert!(matches(r#"date >= "-3 days""#));
+        assert!(matches(r#"date <= "-3 days""#));
+        assert!(matches(r#"date = "-72 hours""#));
+        assert!(matches(r#"date != "-71 hours""#));
+        assert!(matches(r#"date < now"#));
+
+        // Time zones are taken into account: this is midnight UTC
+        assert!(matches(r#"feeddate = "-12 hours""#));
+        assert!(matches(r#"feeddate < "-719 minutes""#));
+        assert!(matches(r#"feeddate > "-1 week""#));
+    }
+
+    #[test]
+    fn t_the_same_filter_matches_differently_as_time_passes() {
+        let dates = [("date", "Thu, 10 Jun 2021 11:00:00 +0000")];
+        let mock = MockMatchable::with_dates(&dates, &dates);
+        let matcher = Matcher::parse(r#"date > "-2 hours""#).unwrap();
+        assert!(matcher.matches_at(&mock, fake_now()).unwrap());
+        let later = fake_now() + Duration::hours(1);
+        assert!(!matcher.matches_at(&mock, later).unwrap());
+    }
+
+    #[test]
+    fn t_matcher_from_expression_works_like_parsed_one() {
+        let expr = Expression::And(
+            Box::new(Expression::comparison(
+                "title",
+                Operator::RegexMatches,
+                Value::Literal("^rust".to_string()),
+            )),
+            Box::new(Expression::comparison(
+                "unread",
+                Operator::Equals,
+                Value::Literal("yes".to_string()),
+            )),
+        );
+        let built = Matcher::from_expression(expr);
+        let parsed = Matcher::parse(built.get_expression()).unwrap();
+        assert_eq!(
+            built.get_expression(),
+            r#"title =~ "^rust" and unread = "yes""#
+        );
+
+        for (title, unread, expected) in &[
+            ("Rust 1.44 released", "yes", true),
+            ("Rust 1.44 released", "no", false),
+            ("Why I like rust", "yes", false),
+        ] {
+            let item = MockMatchable::new(&[("title", *title), ("unread", *unread)]);
+            assert_eq!(built.matches(&item).unwrap(), *expected, "{}", title);
+            assert_eq!(parsed.matches(&item).unwrap(), *expected, "{}", title);
+        }
+
+        let invalid = Matcher::from_expression(Expression::comparison(
+            "title",
+            Operator::RegexMatches,
+            Value::Literal("(".to_string()),
+        ));
+        let item = MockMatchable::new(&[("title", "whatever")]);
+        assert!(invalid.matches(&item).is_err());
+    }
+
+    #[test]
+    fn t_relative_dates_are_compared_against_timestamps_not_localized_text() {
+        // This is how the date is shown in a German locale
+        let mock = MockMatchable::with_dates(
+            &[("date", "Mo, 07 Jun 2021 14:00:00 +0200")],
+            &[("date", "Mon, 07 Jun 2021 12:00:00 +0000")],
+        );
+        let matches = |expression: &str| {
+            Matcher::parse(expression)
+                .unwrap()
+                .matches_at(&mock, fake_now())
+                .unwrap()
+        };
+
+        assert!(matches(r#"date > "-7 days""#));
+        assert!(!matches(r#"date > "-2 days""#));
+        assert!(matches(r#"date = "-3 days""#));
+    }
+
+    #[test]
+    fn t_relative_dates_need_a_timestamp() {
+        let mock = MockMatchable::new(&[("date", "Mon, 07 Jun 2021 12:00:00 +0000")]);
+        let matcher = Matcher::parse(r#"date > "-7 days""#).unwrap();
+        match matcher.matches_at(&mock, fake_now()) {
+            Err(MatcherError::AttributeUnavailable { attr }) => assert_eq!(attr, "date"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
     #[test]
     fn t_get_expression_method_returns_parsed_expression_as_string() {
         let expression = "AAAA between 1:30000";
@@ -1052,6 +1475,7 @@ mod tests {
         assert_eq!(string_to_num("7654"), 7654);
         assert_eq!(string_to_num("123foo"), 123);
         assert_eq!(string_to_num("-999999bar"), -999999);
+        assert_eq!(string_to_num("-42"), -42);
 
         assert_eq!(string_to_num("-2147483648min"), -2147483648);
         assert_eq!(string_to_num("2147483647 is ok"), 2147483647);
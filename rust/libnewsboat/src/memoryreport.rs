@@ -0,0 +1,280 @@
+//! How much memory the items of the open dialogs take.
+//!
+//! Dialogs don't own their items: the feed list, a query feed and a search can all show the same
+//! article, and they all point to a single copy of it. Each dialog only keeps the order of the
+//! items it shows. The report counts an item towards every dialog that shows it, but only once
+//! towards the total; items are told apart by their addresses.
+
+use crate::contentcache::ByteSize;
+use gettextrs::gettext;
+use std::collections::HashMap;
+use std::sync::Arc;
+use strprintf::fmt;
+
+/// Items shown by a single dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Dialog {
+    title: String,
+    /// Addresses of the items, in the order they were added.
+    items: Vec<usize>,
+}
+
+/// What a single dialog takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogUsage {
+    pub title: String,
+    pub items: usize,
+    /// Number of the items that other dialogs show, too.
+    pub shared_items: usize,
+    /// Approximate size of the items, including the shared ones.
+    pub bytes: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryReport {
+    dialogs: Vec<Dialog>,
+    /// Size of every item, and the number of dialogs that show it, by address.
+    items: HashMap<usize, (usize, usize)>,
+}
+
+impl MemoryReport {
+    pub fn new() -> MemoryReport {
+        MemoryReport::default()
+    }
+
+    /// Starts a new dialog. The items added after this belong to it.
+    pub fn add_dialog(&mut self, title: &str) {
+        self.dialogs.push(Dialog {
+            title: title.to_string(),
+            items: Vec::new(),
+        });
+    }
+
+    /// Records that the last dialog shows the item at `address`, which takes about `bytes`.
+    /// Adding an item before any dialog does nothing, and so does adding the same item to the
+    /// same dialog twice.
+    pub fn add_item(&mut self, address: usize, bytes: usize) {
+        let dialog = match self.dialogs.last_mut() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        if dialog.items.contains(&address) {
+            return;
+        }
+        dialog.items.push(address);
+        let entry = self.items.entry(address).or_insert((bytes, 0));
+        entry.1 += 1;
+    }
+
+    /// Like `add_item`, for an item that's shared through an `Arc`.
+    pub fn add_shared<T: ByteSize>(&mut self, item: &Arc<T>) {
+        let address = &**item as *const T as usize;
+        self.add_item(address, item.byte_size());
+    }
+
+    /// Usage of each dialog, in the order they were added.
+    pub fn dialogs(&self) -> Vec<DialogUsage> {
+        self.dialogs
+            .iter()
+            .map(|dialog| {
+                let mut usage = DialogUsage {
+                    title: dialog.title.clone(),
+                    items: dialog.items.len(),
+                    shared_items: 0,
+                    bytes: 0,
+                };
+                for address in &dialog.items {
+                    let (bytes, dialogs) = self.items[address];
+                    usage.bytes += bytes;
+                    if dialogs > 1 {
+                        usage.shared_items += 1;
+                    }
+                }
+                usage
+            })
+            .collect()
+    }
+
+    /// Number of items in all dialogs, counting each item once.
+    pub fn distinct_items(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Approximate size of the items in all dialogs, counting each item once.
+    pub fn total_bytes(&self) -> usize {
+        self.items.values().map(|(bytes, _)| bytes).sum()
+    }
+
+    /// The report as lines of text: one per dialog, and the total.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = self
+            .dialogs()
+            .into_iter()
+            .map(|usage| {
+                let items = fmt_plural!(usage.items, "%u item", "%u items", usage.items as u32);
+                if usage.shared_items > 0 {
+                    fmt!(
+                        &gettext("%s: %s (%u shared), %s"),
+                        usage.title,
+                        items,
+                        usage.shared_items as u32,
+                        format_bytes(usage.bytes)
+                    )
+                } else {
+                    fmt!(
+                        &gettext("%s: %s, %s"),
+                        usage.title,
+                        items,
+                        format_bytes(usage.bytes)
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+        let distinct = self.distinct_items();
+        lines.push(fmt!(
+            &gettext("Total: %s, %s"),
+            fmt_plural!(
+                distinct,
+                "%u distinct item",
+                "%u distinct items",
+                distinct as u32
+            ),
+            format_bytes(self.total_bytes())
+        ));
+        lines
+    }
+}
+
+/// Formats a size in the largest binary unit that keeps it at least 1, e.g. "512 B" or "1.5 MiB".
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_items_shared_between_dialogs_are_counted_once_in_the_total() {
+        let first = Arc::new("first article".to_string());
+        let second = Arc::new("second".to_string());
+        let feed = vec![Arc::clone(&first), Arc::clone(&second)];
+        let search = vec![Arc::clone(&second)];
+
+        // Both views point to the same item, rather than to copies of it
+        assert!(Arc::ptr_eq(&feed[1], &search[0]));
+
+        let mut report = MemoryReport::new();
+        report.add_dialog("Feed");
+        for item in &feed {
+            report.add_shared(item);
+        }
+        report.add_dialog("Search");
+        for item in &search {
+            report.add_shared(item);
+        }
+
+        assert_eq!(report.distinct_items(), 2);
+        assert_eq!(report.total_bytes(), first.len() + second.len());
+        assert_eq!(
+            report.dialogs(),
+            vec![
+                DialogUsage {
+                    title: "Feed".to_string(),
+                    items: 2,
+                    shared_items: 1,
+                    bytes: first.len() + second.len(),
+                },
+                DialogUsage {
+                    title: "Search".to_string(),
+                    items: 1,
+                    shared_items: 1,
+                    bytes: second.len(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn t_equal_items_in_different_places_are_different_items() {
+        let one = Arc::new("same text".to_string());
+        let other = Arc::new("same text".to_string());
+
+        let mut report = MemoryReport::new();
+        report.add_dialog("Feed");
+        report.add_shared(&one);
+        report.add_shared(&other);
+
+        assert_eq!(report.distinct_items(), 2);
+        assert_eq!(report.total_bytes(), 2 * "same text".len());
+        assert_eq!(report.dialogs()[0].shared_items, 0);
+    }
+
+    #[test]
+    fn t_adding_an_item_twice_to_a_dialog_counts_it_once() {
+        let mut report = MemoryReport::new();
+        report.add_dialog("Feed");
+        report.add_item(1, 100);
+        report.add_item(1, 100);
+        assert_eq!(report.dialogs()[0].items, 1);
+        assert_eq!(report.dialogs()[0].shared_items, 0);
+        assert_eq!(report.total_bytes(), 100);
+    }
+
+    #[test]
+    fn t_items_without_a_dialog_are_ignored() {
+        let mut report = MemoryReport::new();
+        report.add_item(1, 100);
+        assert_eq!(report.distinct_items(), 0);
+        assert!(report.dialogs().is_empty());
+    }
+
+    #[test]
+    fn t_byte_size_of_items_counts_their_contents() {
+        let items = vec!["abc".to_string(), String::new(), "defgh".to_string()];
+        assert_eq!(items.byte_size(), 8);
+        assert_eq!(Arc::new(items).byte_size(), 8);
+    }
+
+    #[test]
+    fn t_format_bytes_picks_a_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 + 400 * 1024), "3.4 MiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
+    #[test]
+    fn t_lines_list_dialogs_and_the_total() {
+        let mut report = MemoryReport::new();
+        report.add_dialog("Feed list");
+        report.add_item(1, 1024);
+        report.add_item(2, 512);
+        report.add_dialog("Query feed");
+        report.add_item(2, 512);
+        report.add_dialog("Empty search");
+
+        assert_eq!(
+            report.lines(),
+            vec![
+                "Feed list: 2 items (1 shared), 1.5 KiB",
+                "Query feed: 1 item (1 shared), 512 B",
+                "Empty search: 0 items, 0 B",
+                "Total: 2 distinct items, 1.5 KiB",
+            ]
+        );
+    }
+}
@@ -0,0 +1,517 @@
+//! Reloads feeds again once the network comes back.
+//!
+//! Right after a laptop resumes, the reload that `auto-reload` starts tends to fail for most
+//! feeds because the network isn't up yet, and the feeds then stay errored until the next reload,
+//! `reload-time` minutes later. If a large enough share of the feeds of a reload fail with network
+//! errors, `NetworkRetry` schedules a probe: a single request that tells if the network is back.
+//! Failed probes are repeated with exponentially growing spacing. Once a probe succeeds, the feeds
+//! that failed with network errors are reloaded again; feeds that failed for other reasons, e.g.
+//! because they couldn't be parsed, aren't.
+//!
+//! The retried feeds go through the usual reload, with the usual download limits and schedules.
+//! The probe itself is one request at a time, at most once per `INITIAL_PROBE_DELAY`.
+
+use std::cmp;
+use std::collections::BTreeSet;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Time between a failed reload and the first probe.
+pub const INITIAL_PROBE_DELAY: Duration = Duration::from_secs(30);
+
+/// The spacing between probes doubles after every failed probe, up to this.
+pub const MAX_PROBE_DELAY: Duration = Duration::from_secs(30 * 60);
+
+/// curl error codes which mean that the server couldn't be reached at all: CURLE_COULDNT_RESOLVE_PROXY,
+/// CURLE_COULDNT_RESOLVE_HOST, CURLE_COULDNT_CONNECT, CURLE_OPERATION_TIMEDOUT,
+/// CURLE_SSL_CONNECT_ERROR, CURLE_GOT_NOTHING, CURLE_SEND_ERROR and CURLE_RECV_ERROR.
+const NETWORK_ERRORS: [u32; 8] = [5, 6, 7, 28, 35, 52, 55, 56];
+
+/// Why a feed couldn't be reloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The server couldn't be reached. Retrying once the network is back may help.
+    Network,
+    /// Anything else, like an HTTP error or a feed that couldn't be parsed. Retrying won't help.
+    Other,
+}
+
+impl FailureKind {
+    /// Classifies a failure by the curl error code that caused it. Failures that didn't come from
+    /// curl have a code of 0.
+    pub fn from_curl_code(code: u32) -> FailureKind {
+        if NETWORK_ERRORS.contains(&code) {
+            FailureKind::Network
+        } else {
+            FailureKind::Other
+        }
+    }
+}
+
+/// The settings that `network-retry` and `network-retry-threshold` configure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub enabled: bool,
+    /// Percentage of the reloaded feeds that have to fail with network errors for a probe to be
+    /// scheduled.
+    pub threshold_percent: u32,
+}
+
+#[derive(Debug)]
+struct State {
+    /// Number of feeds that the current reload tried to fetch.
+    attempted: usize,
+    /// Feeds that failed with network errors during the current reload.
+    failed: BTreeSet<String>,
+    /// Feeds that are waiting for a probe to succeed.
+    waiting: BTreeSet<String>,
+    /// When to send the next probe, if any.
+    next_probe: Option<Instant>,
+    /// Time between the last probe and the next one.
+    delay: Duration,
+}
+
+/// Keeps track of the feeds that failed with network errors, and of when to probe the network.
+///
+/// Feeds are reloaded from multiple threads at once, hence the mutex.
+#[derive(Debug)]
+pub struct NetworkRetry {
+    state: Mutex<State>,
+}
+
+impl Default for NetworkRetry {
+    fn default() -> NetworkRetry {
+        NetworkRetry {
+            state: Mutex::new(State {
+                attempted: 0,
+                failed: BTreeSet::new(),
+                waiting: BTreeSet::new(),
+                next_probe: None,
+                delay: INITIAL_PROBE_DELAY,
+            }),
+        }
+    }
+}
+
+impl NetworkRetry {
+    pub fn new() -> NetworkRetry {
+        NetworkRetry::default()
+    }
+
+    fn lock(&self) -> MutexGuard<State> {
+        self.state
+            .lock()
+            .expect("another thread panicked while holding the lock")
+    }
+
+    /// Starts counting the feeds of a new reload.
+    pub fn start_cycle(&self) {
+        let mut state = self.lock();
+        state.attempted = 0;
+        state.failed.clear();
+    }
+
+    /// Records that the feed at `url` was fetched, or failed to be fetched with `failure`.
+    pub fn feed_finished(&self, url: &str, failure: Option<FailureKind>) {
+        let mut state = self.lock();
+        state.attempted += 1;
+        if failure == Some(FailureKind::Network) {
+            state.failed.insert(url.to_string());
+        } else {
+            // Reloaded after all, or failed in a way that retrying won't fix
+            state.waiting.remove(url);
+        }
+    }
+
+    /// Ends the current reload. If enough of its feeds failed with network errors, schedules
+    /// a probe, and returns `true`. Otherwise, forgets about any probe that was scheduled before.
+    pub fn cycle_finished(&self, policy: RetryPolicy, now: Instant) -> bool {
+        let mut state = self.lock();
+        let failed = state.failed.len();
+        let threshold = policy.threshold_percent as usize;
+        if policy.enabled && failed > 0 && failed * 100 >= threshold * state.attempted {
+            let failed = std::mem::take(&mut state.failed);
+            state.waiting.extend(failed);
+            if state.next_probe.is_none() {
+                state.delay = INITIAL_PROBE_DELAY;
+                state.next_probe = Some(now + INITIAL_PROBE_DELAY);
+            }
+            true
+        } else {
+            state.failed.clear();
+            if !policy.enabled || state.waiting.is_empty() {
+                state.waiting.clear();
+                state.next_probe = None;
+            }
+            false
+        }
+    }
+
+    /// Time left until the next probe, or `None` if no probe is scheduled.
+    pub fn time_until_probe(&self, now: Instant) -> Option<Duration> {
+        self.lock()
+            .next_probe
+            .map(|at| at.saturating_duration_since(now))
+    }
+
+    /// If a probe is due at `now`, returns the URL to send it to: `configured_url`, or if that's
+    /// empty, one of the failed feeds.
+    pub fn probe_target(&self, configured_url: &str, now: Instant) -> Option<String> {
+        let state = self.lock();
+        match state.next_probe {
+            Some(at) if at <= now => {}
+            _ => return None,
+        }
+        if !configured_url.is_empty() {
+            return Some(configured_url.to_string());
+        }
+        state.waiting.iter().next().cloned()
+    }
+
+    /// Records the outcome of a probe. If it `succeeded`, returns the feeds to reload and forgets
+    /// about them. Otherwise, schedules the next probe twice as far away as the last one, and
+    /// returns nothing.
+    pub fn probe_finished(&self, succeeded: bool, now: Instant) -> Vec<String> {
+        let mut state = self.lock();
+        if state.next_probe.is_none() {
+            // Cancelled while the probe was running
+            return Vec::new();
+        }
+        if succeeded {
+            state.next_probe = None;
+            state.delay = INITIAL_PROBE_DELAY;
+            std::mem::take(&mut state.waiting).into_iter().collect()
+        } else {
+            state.delay = cmp::min(state.delay * 2, MAX_PROBE_DELAY);
+            state.next_probe = Some(now + state.delay);
+            Vec::new()
+        }
+    }
+
+    /// Sends a probe with `fetch` if one is due at `now`, and returns the feeds to reload.
+    ///
+    /// `fetch` is called without holding the lock, so other threads can keep recording reloads
+    /// while the probe is underway.
+    pub fn probe<F>(&self, configured_url: &str, now: Instant, fetch: F) -> Vec<String>
+    where
+        F: FnOnce(&str) -> bool,
+    {
+        match self.probe_target(configured_url, now) {
+            Some(url) => {
+                let succeeded = fetch(&url);
+                self.probe_finished(succeeded, now)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Feeds that wait for the network to come back, sorted.
+    pub fn waiting_feeds(&self) -> Vec<String> {
+        self.lock().waiting.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    const ENABLED: RetryPolicy = RetryPolicy {
+        enabled: true,
+        threshold_percent: 50,
+    };
+
+    const DISABLED: RetryPolicy = RetryPolicy {
+        enabled: false,
+        threshold_percent: 50,
+    };
+
+    /// Fetcher that records the URLs it was asked for, and answers with the given outcomes in
+    /// turn.
+    struct FakeFetcher {
+        outcomes: RefCell<Vec<bool>>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl FakeFetcher {
+        fn new(outcomes: &[bool]) -> FakeFetcher {
+            FakeFetcher {
+                outcomes: RefCell::new(outcomes.iter().rev().cloned().collect()),
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn fetch(&self, url: &str) -> bool {
+            self.requests.borrow_mut().push(url.to_string());
+            self.outcomes.borrow_mut().pop().expect("unexpected fetch")
+        }
+    }
+
+    /// Runs a reload in which each feed fails as given.
+    fn reload(retry: &NetworkRetry, feeds: &[(&str, Option<FailureKind>)]) {
+        retry.start_cycle();
+        for (url, failure) in feeds {
+            retry.feed_finished(url, *failure);
+        }
+    }
+
+    #[test]
+    fn t_failures_are_classified_by_curl_code() {
+        // Couldn't resolve host, couldn't connect, timeout
+        assert_eq!(FailureKind::from_curl_code(6), FailureKind::Network);
+        assert_eq!(FailureKind::from_curl_code(7), FailureKind::Network);
+        assert_eq!(FailureKind::from_curl_code(28), FailureKind::Network);
+        // No curl error, i.e. the feed couldn't be parsed
+        assert_eq!(FailureKind::from_curl_code(0), FailureKind::Other);
+        // HTTP error, like 404
+        assert_eq!(FailureKind::from_curl_code(22), FailureKind::Other);
+        // Unsupported protocol
+        assert_eq!(FailureKind::from_curl_code(1), FailureKind::Other);
+    }
+
+    #[test]
+    fn t_probe_is_scheduled_when_enough_feeds_fail_with_network_errors() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[
+                ("https://a.example/feed", Some(FailureKind::Network)),
+                ("https://b.example/feed", Some(FailureKind::Network)),
+                ("https://c.example/feed", None),
+                ("https://d.example/feed", Some(FailureKind::Other)),
+            ],
+        );
+        assert!(retry.cycle_finished(ENABLED, now));
+        assert_eq!(retry.time_until_probe(now), Some(INITIAL_PROBE_DELAY));
+        assert_eq!(
+            retry.waiting_feeds(),
+            vec!["https://a.example/feed", "https://b.example/feed"]
+        );
+    }
+
+    #[test]
+    fn t_no_probe_is_scheduled_below_the_threshold() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[
+                ("https://a.example/feed", Some(FailureKind::Network)),
+                ("https://b.example/feed", None),
+                ("https://c.example/feed", Some(FailureKind::Other)),
+            ],
+        );
+        assert!(!retry.cycle_finished(ENABLED, now));
+        assert_eq!(retry.time_until_probe(now), None);
+        assert!(retry.waiting_feeds().is_empty());
+    }
+
+    #[test]
+    fn t_parse_failures_alone_dont_schedule_a_probe() {
+        let retry = NetworkRetry::new();
+        reload(
+            &retry,
+            &[
+                ("https://a.example/feed", Some(FailureKind::Other)),
+                ("https://b.example/feed", Some(FailureKind::Other)),
+            ],
+        );
+        let policy = RetryPolicy {
+            enabled: true,
+            threshold_percent: 0,
+        };
+        assert!(!retry.cycle_finished(policy, Instant::now()));
+    }
+
+    #[test]
+    fn t_disabled_retry_never_probes() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        assert!(!retry.cycle_finished(DISABLED, now));
+        assert_eq!(retry.time_until_probe(now), None);
+
+        let later = now + MAX_PROBE_DELAY;
+        let fetcher = FakeFetcher::new(&[]);
+        assert!(retry.probe("", later, |url| fetcher.fetch(url)).is_empty());
+        assert!(fetcher.requests.borrow().is_empty());
+    }
+
+    #[test]
+    fn t_disabling_retry_cancels_a_scheduled_probe() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        assert!(retry.cycle_finished(ENABLED, now));
+
+        reload(&retry, &[("https://b.example/feed", None)]);
+        assert!(!retry.cycle_finished(DISABLED, now));
+        assert_eq!(retry.time_until_probe(now), None);
+        assert!(retry.waiting_feeds().is_empty());
+    }
+
+    #[test]
+    fn t_probe_waits_until_it_is_due() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        retry.cycle_finished(ENABLED, now);
+
+        assert_eq!(retry.probe_target("", now), None);
+        assert_eq!(
+            retry.probe_target("", now + INITIAL_PROBE_DELAY),
+            Some("https://a.example/feed".to_string())
+        );
+    }
+
+    #[test]
+    fn t_probe_uses_the_configured_url_if_there_is_one() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        retry.cycle_finished(ENABLED, now);
+
+        let fetcher = FakeFetcher::new(&[true]);
+        retry.probe("https://probe.example/", now + INITIAL_PROBE_DELAY, |url| {
+            fetcher.fetch(url)
+        });
+        assert_eq!(*fetcher.requests.borrow(), vec!["https://probe.example/"]);
+    }
+
+    #[test]
+    fn t_failed_probes_back_off_exponentially() {
+        let retry = NetworkRetry::new();
+        let mut now = Instant::now();
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        retry.cycle_finished(ENABLED, now);
+
+        let fetcher = FakeFetcher::new(&[false; 8]);
+        let mut delays = Vec::new();
+        for _ in 0..8 {
+            now += retry.time_until_probe(now).unwrap();
+            assert!(retry.probe("", now, |url| fetcher.fetch(url)).is_empty());
+            delays.push(retry.time_until_probe(now).unwrap().as_secs());
+        }
+        assert_eq!(delays, vec![60, 120, 240, 480, 960, 1800, 1800, 1800]);
+        assert_eq!(fetcher.requests.borrow().len(), 8);
+    }
+
+    #[test]
+    fn t_successful_probe_requeues_only_network_failures() {
+        let retry = NetworkRetry::new();
+        let mut now = Instant::now();
+        reload(
+            &retry,
+            &[
+                ("https://a.example/feed", Some(FailureKind::Network)),
+                ("https://b.example/feed", Some(FailureKind::Other)),
+                ("https://c.example/feed", Some(FailureKind::Network)),
+            ],
+        );
+        retry.cycle_finished(ENABLED, now);
+
+        let fetcher = FakeFetcher::new(&[false, true]);
+        now += INITIAL_PROBE_DELAY;
+        assert!(retry.probe("", now, |url| fetcher.fetch(url)).is_empty());
+        now += retry.time_until_probe(now).unwrap();
+        assert_eq!(
+            retry.probe("", now, |url| fetcher.fetch(url)),
+            vec!["https://a.example/feed", "https://c.example/feed"]
+        );
+
+        // Nothing is left to retry
+        assert_eq!(retry.time_until_probe(now), None);
+        assert!(retry.waiting_feeds().is_empty());
+    }
+
+    #[test]
+    fn t_successful_probe_resets_the_backoff() {
+        let retry = NetworkRetry::new();
+        let mut now = Instant::now();
+        let fetcher = FakeFetcher::new(&[false, true, false]);
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        retry.cycle_finished(ENABLED, now);
+        now += retry.time_until_probe(now).unwrap();
+        retry.probe("", now, |url| fetcher.fetch(url));
+        now += retry.time_until_probe(now).unwrap();
+        retry.probe("", now, |url| fetcher.fetch(url));
+        assert_eq!(retry.time_until_probe(now), None);
+
+        // The network goes away again; the next round starts from scratch
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        retry.cycle_finished(ENABLED, now);
+        assert_eq!(retry.time_until_probe(now), Some(INITIAL_PROBE_DELAY));
+        now += INITIAL_PROBE_DELAY;
+        retry.probe("", now, |url| fetcher.fetch(url));
+        assert_eq!(retry.time_until_probe(now), Some(INITIAL_PROBE_DELAY * 2));
+    }
+
+    #[test]
+    fn t_feeds_that_reload_in_the_meantime_are_not_requeued() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[
+                ("https://a.example/feed", Some(FailureKind::Network)),
+                ("https://b.example/feed", Some(FailureKind::Network)),
+            ],
+        );
+        retry.cycle_finished(ENABLED, now);
+
+        // The user reloads one of the feeds by hand, and this time it works
+        reload(&retry, &[("https://a.example/feed", None)]);
+        assert!(!retry.cycle_finished(ENABLED, now));
+
+        let fetcher = FakeFetcher::new(&[true]);
+        assert_eq!(
+            retry.probe("", now + INITIAL_PROBE_DELAY, |url| fetcher.fetch(url)),
+            vec!["https://b.example/feed"]
+        );
+    }
+
+    #[test]
+    fn t_later_failures_dont_postpone_a_scheduled_probe() {
+        let retry = NetworkRetry::new();
+        let now = Instant::now();
+        reload(
+            &retry,
+            &[("https://a.example/feed", Some(FailureKind::Network))],
+        );
+        retry.cycle_finished(ENABLED, now);
+
+        let later = now + Duration::from_secs(20);
+        reload(
+            &retry,
+            &[("https://b.example/feed", Some(FailureKind::Network))],
+        );
+        assert!(retry.cycle_finished(ENABLED, later));
+        assert_eq!(retry.time_until_probe(later), Some(Duration::from_secs(10)));
+        assert_eq!(
+            retry.waiting_feeds(),
+            vec!["https://a.example/feed", "https://b.example/feed"]
+        );
+    }
+}
@@ -0,0 +1,772 @@
+//! Reads and writes OPML, the format in which feed readers exchange lists of subscriptions.
+//!
+//! Feeds are `<outline>` elements with an `xmlUrl` attribute, anywhere inside `<body>`. Outlines
+//! without one are folders; their titles become the tags of the feeds inside them, so a feed in
+//! folder "Linux" inside folder "Tech" gets tags `Tech` and `Linux`.
+//!
+//! The XML reader only understands as much as OPML needs: elements, attributes and the predefined
+//! and numeric entities. Declarations, comments, CDATA sections and text are skipped.
+
+use crate::logger::{self, Level};
+use gettextrs::gettext;
+use std::collections::HashSet;
+use strprintf::fmt;
+
+/// A feed found in an OPML document, or one to be written into it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpmlFeed {
+    pub xml_url: String,
+    pub html_url: Option<String>,
+    pub title: Option<String>,
+    /// Titles of the enclosing folders, the outermost first.
+    pub folders: Vec<String>,
+    /// The `filtercmd` attribute, with which Liferea pipes the feed through a program.
+    pub filter_command: Option<String>,
+}
+
+impl OpmlFeed {
+    /// The feed's URL as it's written in the urls file. Liferea marks feeds read from the output
+    /// of a program with a leading pipe symbol; these become `exec:` URLs. A filter command turns
+    /// the URL into a `filter:` one.
+    pub fn newsboat_url(&self) -> String {
+        let url = if self.xml_url.starts_with('|') {
+            format!("exec:{}", &self.xml_url[1..])
+        } else {
+            self.xml_url.clone()
+        };
+        match self.filter_command {
+            Some(ref command) => format!("filter:{}:{}", command, url),
+            None => url,
+        }
+    }
+}
+
+/// The version of OPML that `generate` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpmlVersion {
+    V1,
+    V2,
+}
+
+/// Why an OPML document couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpmlError {
+    /// The document isn't well-formed XML. `line` starts at 1; `reason` is translated.
+    Malformed { line: usize, reason: String },
+    /// The document has no root element, or it isn't `<opml>`.
+    NotOpml,
+}
+
+impl OpmlError {
+    pub fn to_message(&self) -> String {
+        match self {
+            OpmlError::Malformed { line, reason } => {
+                fmt!(&gettext("OPML, line %u: %s"), *line as u32, reason.as_str())
+            }
+            OpmlError::NotOpml => gettext("the file isn't an OPML document"),
+        }
+    }
+}
+
+/// An attribute of a start tag, with entities already replaced.
+struct Attribute {
+    name: String,
+    value: String,
+}
+
+fn attribute<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .map(|attribute| attribute.value.as_str())
+}
+
+/// Like `attribute`, but treats values made of whitespace as missing.
+fn non_empty_attribute<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a str> {
+    attribute(attributes, name)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+enum Event {
+    Start {
+        name: String,
+        attributes: Vec<Attribute>,
+        empty: bool,
+    },
+    End {
+        name: String,
+    },
+}
+
+fn is_name_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '<' | '>' | '/' | '=' | '"' | '\'')
+}
+
+/// Replaces entities in `text`. An ampersand that doesn't start a known entity is kept as is:
+/// exporters that forget to escape URLs are common.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let replacement = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16)
+                        .ok()
+                        .and_then(std::char::from_u32)
+                }
+                entity if entity.starts_with('#') => {
+                    entity[1..].parse().ok().and_then(std::char::from_u32)
+                }
+                _ => None,
+            };
+            // NUL isn't allowed in XML, not even as a reference
+            c.filter(|&c| c != '\0').map(|c| (c, end))
+        });
+        match replacement {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(input: &'a str) -> Reader<'a> {
+        Reader { input, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn error(&self, reason: String) -> OpmlError {
+        OpmlError::Malformed {
+            line: self.input[..self.position].matches('\n').count() + 1,
+            reason,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Moves past the next `terminator`, or fails if there is none.
+    fn skip_past(&mut self, terminator: &str) -> Result<(), OpmlError> {
+        match self.rest().find(terminator) {
+            Some(offset) => {
+                self.position += offset + terminator.len();
+                Ok(())
+            }
+            None => {
+                self.position = self.input.len();
+                Err(self.error(fmt!(&gettext("expected `%s'"), terminator)))
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, OpmlError> {
+        let rest = self.rest();
+        let length = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error(gettext("expected a name")));
+        }
+        self.position += length;
+        Ok(rest[..length].to_owned())
+    }
+
+    fn attribute_value(&mut self) -> Result<String, OpmlError> {
+        let quote = match self.rest().chars().next() {
+            Some(quote @ '"') | Some(quote @ '\'') => quote,
+            _ => return Err(self.error(gettext("expected a quoted attribute value"))),
+        };
+        self.position += 1;
+        let rest = self.rest();
+        match rest.find(quote) {
+            Some(length) => {
+                self.position += length + 1;
+                Ok(unescape(&rest[..length]))
+            }
+            None => {
+                self.position = self.input.len();
+                Err(self.error(gettext("the attribute value isn't closed")))
+            }
+        }
+    }
+
+    /// Reads a start tag, the `<` already consumed.
+    fn start_tag(&mut self) -> Result<Event, OpmlError> {
+        let name = self.name()?;
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.position += 2;
+                return Ok(Event::Start {
+                    name,
+                    attributes,
+                    empty: true,
+                });
+            } else if rest.starts_with('>') {
+                self.position += 1;
+                return Ok(Event::Start {
+                    name,
+                    attributes,
+                    empty: false,
+                });
+            } else if rest.is_empty() {
+                return Err(self.error(fmt!(&gettext("the tag `<%s>' isn't closed"), &name)));
+            }
+
+            let attribute_name = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error(fmt!(
+                    &gettext("expected `=' after attribute `%s'"),
+                    &attribute_name
+                )));
+            }
+            self.position += 1;
+            self.skip_whitespace();
+            let value = self.attribute_value()?;
+            attributes.push(Attribute {
+                name: attribute_name,
+                value,
+            });
+        }
+    }
+
+    /// Returns the next tag, or `None` at the end of the input.
+    fn next_event(&mut self) -> Result<Option<Event>, OpmlError> {
+        loop {
+            match self.rest().find('<') {
+                Some(offset) => self.position += offset,
+                None => {
+                    self.position = self.input.len();
+                    return Ok(None);
+                }
+            }
+
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")?;
+            } else if rest.starts_with("<!") {
+                // A DOCTYPE; the internal subset, if any, is in brackets and can contain `>`
+                match (rest.find('>'), rest.find('[')) {
+                    (Some(end), Some(bracket)) if bracket < end => self.skip_past("]>")?,
+                    (Some(end), _) => self.position += end + 1,
+                    (None, _) => self.skip_past(">")?,
+                }
+            } else if rest.starts_with("</") {
+                self.position += 2;
+                let name = self.name()?;
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error(fmt!(&gettext("the tag `</%s>' isn't closed"), &name)));
+                }
+                self.position += 1;
+                return Ok(Some(Event::End { name }));
+            } else {
+                self.position += 1;
+                return self.start_tag().map(Some);
+            }
+        }
+    }
+}
+
+/// What an `<outline>` element stands for, as far as its children are concerned.
+enum Outline {
+    /// A folder. `None` if it has no title, in which case it doesn't add a tag.
+    Folder(Option<String>),
+    Feed,
+}
+
+/// `elements` are the names of the open elements, starting with the one inside `<opml>`.
+fn is_in_body(elements: &[String]) -> bool {
+    elements.first().map(String::as_str) == Some("body")
+}
+
+/// Returns the feeds listed in the OPML document `content`, in the order they appear in it.
+/// Outlines without an `xmlUrl` (or `url`, which some exporters use instead) are folders. A feed
+/// that's listed several times is returned once, with the folders of its first appearance.
+pub fn parse(content: &str) -> Result<Vec<OpmlFeed>, OpmlError> {
+    let mut reader = Reader::new(content);
+
+    match reader.next_event()? {
+        Some(Event::Start { ref name, .. }) if name == "opml" => {}
+        _ => return Err(OpmlError::NotOpml),
+    }
+
+    let mut feeds: Vec<OpmlFeed> = Vec::new();
+    let mut seen_urls = HashSet::new();
+    // Names of the open elements, starting with the one inside `<opml>`
+    let mut elements: Vec<String> = Vec::new();
+    // Open `<outline>` elements inside `<body>`
+    let mut outlines: Vec<Outline> = Vec::new();
+    loop {
+        match reader.next_event()? {
+            Some(Event::Start {
+                name,
+                attributes,
+                empty,
+            }) => {
+                let in_body = is_in_body(&elements);
+                if !in_body || name != "outline" {
+                    if !empty {
+                        elements.push(name);
+                    }
+                    continue;
+                }
+
+                let url = non_empty_attribute(&attributes, "xmlUrl")
+                    .or_else(|| non_empty_attribute(&attributes, "url"));
+                let outline = match url {
+                    Some(url) => {
+                        if seen_urls.insert(url.to_owned()) {
+                            log!(
+                                "opml",
+                                Level::Debug,
+                                "opml::parse: found feed with URL {}",
+                                url
+                            );
+                            feeds.push(OpmlFeed {
+                                xml_url: url.to_owned(),
+                                html_url: non_empty_attribute(&attributes, "htmlUrl")
+                                    .map(String::from),
+                                title: non_empty_attribute(&attributes, "title")
+                                    .or_else(|| non_empty_attribute(&attributes, "text"))
+                                    .map(String::from),
+                                folders: outlines
+                                    .iter()
+                                    .filter_map(|outline| match outline {
+                                        Outline::Folder(title) => title.clone(),
+                                        Outline::Feed => None,
+                                    })
+                                    .collect(),
+                                filter_command: non_empty_attribute(&attributes, "filtercmd")
+                                    .map(String::from),
+                            });
+                        } else {
+                            log!(
+                                "opml",
+                                Level::Debug,
+                                "opml::parse: skipping duplicate of {}",
+                                url
+                            );
+                        }
+                        Outline::Feed
+                    }
+                    None => Outline::Folder(
+                        non_empty_attribute(&attributes, "text")
+                            .or_else(|| non_empty_attribute(&attributes, "title"))
+                            .map(String::from),
+                    ),
+                };
+                if !empty {
+                    elements.push(name);
+                    outlines.push(outline);
+                }
+            }
+            Some(Event::End { name }) => match elements.pop() {
+                Some(open) if open == name => {
+                    if open == "outline" && is_in_body(&elements) {
+                        outlines.pop();
+                    }
+                }
+                Some(open) => {
+                    return Err(reader.error(fmt!(
+                        &gettext("expected `</%s>', found `</%s>'"),
+                        &open,
+                        &name
+                    )))
+                }
+                None if name == "opml" => return Ok(feeds),
+                None => {
+                    return Err(
+                        reader.error(fmt!(&gettext("expected `</opml>', found `</%s>'"), &name))
+                    )
+                }
+            },
+            None => {
+                let open = elements.last().map_or("opml", String::as_str);
+                return Err(reader.error(fmt!(&gettext("the document ends before `</%s>'"), open)));
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\n' => result.push_str("&#10;"),
+            '\r' => result.push_str("&#13;"),
+            '\t' => result.push_str("&#9;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Feeds grouped by the folders they're in, in the order in which they first appear.
+#[derive(Default)]
+struct Folder<'a> {
+    title: &'a str,
+    feeds: Vec<&'a OpmlFeed>,
+    subfolders: Vec<Folder<'a>>,
+}
+
+impl<'a> Folder<'a> {
+    fn add(&mut self, path: &'a [String], feed: &'a OpmlFeed) {
+        match path.split_first() {
+            None => self.feeds.push(feed),
+            Some((title, rest)) => {
+                let index = match self
+                    .subfolders
+                    .iter()
+                    .position(|folder| folder.title == title)
+                {
+                    Some(index) => index,
+                    None => {
+                        self.subfolders.push(Folder {
+                            title,
+                            ..Folder::default()
+                        });
+                        self.subfolders.len() - 1
+                    }
+                };
+                self.subfolders[index].add(rest, feed);
+            }
+        }
+    }
+
+    fn write(&self, output: &mut String, depth: usize, version: OpmlVersion) {
+        let indent = "  ".repeat(depth);
+        for feed in &self.feeds {
+            output.push_str(&indent);
+            output.push_str("<outline type=\"rss\"");
+            if version == OpmlVersion::V2 {
+                // Required in OPML 2.0
+                let text = feed.title.as_ref().unwrap_or(&feed.xml_url);
+                output.push_str(&format!(" text=\"{}\"", escape(text)));
+            }
+            output.push_str(&format!(" xmlUrl=\"{}\"", escape(&feed.xml_url)));
+            if let Some(ref html_url) = feed.html_url {
+                output.push_str(&format!(" htmlUrl=\"{}\"", escape(html_url)));
+            }
+            if let Some(ref title) = feed.title {
+                output.push_str(&format!(" title=\"{}\"", escape(title)));
+            }
+            if let Some(ref filter_command) = feed.filter_command {
+                output.push_str(&format!(" filtercmd=\"{}\"", escape(filter_command)));
+            }
+            output.push_str("/>\n");
+        }
+        for folder in &self.subfolders {
+            let title = escape(folder.title);
+            output.push_str(&format!(
+                "{}<outline text=\"{}\" title=\"{}\">\n",
+                indent, title, title
+            ));
+            folder.write(output, depth + 1, version);
+            output.push_str(&indent);
+            output.push_str("</outline>\n");
+        }
+    }
+}
+
+/// Returns an OPML document that lists `feeds`. Feeds are nested in outlines for their folders.
+pub fn generate(feeds: &[OpmlFeed], version: OpmlVersion) -> String {
+    let mut root = Folder::default();
+    for feed in feeds {
+        root.add(&feed.folders, feed);
+    }
+
+    let version_number = match version {
+        OpmlVersion::V1 => "1.0",
+        OpmlVersion::V2 => "2.0",
+    };
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str(&format!("<opml version=\"{}\">\n", version_number));
+    output.push_str("  <head>\n");
+    output.push_str("    <title>newsboat - Exported Feeds</title>\n");
+    output.push_str("  </head>\n");
+    output.push_str("  <body>\n");
+    root.write(&mut output, 2, version);
+    output.push_str("  </body>\n");
+    output.push_str("</opml>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(url: &str, folders: &[&str]) -> OpmlFeed {
+        OpmlFeed {
+            xml_url: url.to_owned(),
+            folders: folders.iter().map(|&folder| folder.to_owned()).collect(),
+            ..OpmlFeed::default()
+        }
+    }
+
+    fn urls_and_folders(feeds: &[OpmlFeed]) -> Vec<(&str, Vec<&str>)> {
+        feeds
+            .iter()
+            .map(|feed| {
+                (
+                    feed.xml_url.as_str(),
+                    feed.folders.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn t_parse_turns_nested_folders_into_tags() {
+        let feeds = parse(include_str!("../tests/data/opml/nested-folders.opml")).unwrap();
+
+        assert_eq!(
+            urls_and_folders(&feeds),
+            vec![
+                ("https://lwn.net/headlines/rss", vec!["Tech", "Linux"]),
+                (
+                    "https://blog.rust-lang.org/feed.xml",
+                    vec!["Tech", "Programming"]
+                ),
+                (
+                    "https://example.com/untyped.xml",
+                    vec!["Tech", "Programming"]
+                ),
+                ("https://news.example.org/world.rss", vec!["News"]),
+                ("https://xkcd.com/atom.xml", vec![]),
+            ]
+        );
+
+        assert_eq!(
+            feeds[0],
+            OpmlFeed {
+                xml_url: "https://lwn.net/headlines/rss".to_owned(),
+                html_url: Some("https://lwn.net".to_owned()),
+                title: Some("LWN.net".to_owned()),
+                folders: vec!["Tech".to_owned(), "Linux".to_owned()],
+                filter_command: None,
+            }
+        );
+        // Falls back to `text` if there is no `title`
+        assert_eq!(feeds[2].title, Some("Untyped & unescaped".to_owned()));
+        assert_eq!(feeds[3].title, Some("World \"news\"".to_owned()));
+    }
+
+    #[test]
+    fn t_parse_skips_outlines_without_url_and_duplicates() {
+        let feeds = parse(include_str!("../tests/data/opml/broken-entries.opml")).unwrap();
+
+        assert_eq!(
+            urls_and_folders(&feeds),
+            vec![
+                ("https://example.com/first.xml", vec!["Reading"]),
+                (
+                    "https://example.com/from-url-attribute.xml",
+                    vec!["Reading"]
+                ),
+                ("|~/bin/fetch-tweets", vec![]),
+            ]
+        );
+        assert_eq!(feeds[1].title, None);
+    }
+
+    #[test]
+    fn t_newsboat_url_converts_liferea_commands_and_filters() {
+        let mut opml_feed = feed("|~/bin/fetch-tweets", &[]);
+        assert_eq!(opml_feed.newsboat_url(), "exec:~/bin/fetch-tweets");
+
+        opml_feed.filter_command = Some("~/bin/keep-interesting".to_owned());
+        assert_eq!(
+            opml_feed.newsboat_url(),
+            "filter:~/bin/keep-interesting:exec:~/bin/fetch-tweets"
+        );
+
+        let plain = feed("https://example.com/feed.xml", &[]);
+        assert_eq!(plain.newsboat_url(), "https://example.com/feed.xml");
+    }
+
+    #[test]
+    fn t_parse_rejects_documents_that_arent_opml() {
+        assert_eq!(parse(""), Err(OpmlError::NotOpml));
+        assert_eq!(
+            parse("<?xml version=\"1.0\"?>\n<rss version=\"2.0\"></rss>"),
+            Err(OpmlError::NotOpml)
+        );
+    }
+
+    #[test]
+    fn t_parse_reports_the_line_of_malformed_xml() {
+        let input = "<opml version=\"1.0\">\n<body>\n<outline xmlUrl=\"a\">\n</body>\n</opml>";
+        assert_eq!(
+            parse(input),
+            Err(OpmlError::Malformed {
+                line: 4,
+                reason: "expected `</outline>', found `</body>'".to_owned(),
+            })
+        );
+
+        let input = "<opml version=\"1.0\">\n<body>\n<outline xmlUrl=\"a/>\n</body>\n</opml>";
+        assert_eq!(
+            parse(input),
+            Err(OpmlError::Malformed {
+                line: 5,
+                reason: "the attribute value isn't closed".to_owned(),
+            })
+        );
+
+        let input = "<opml version=\"1.0\">\n<body>\n";
+        assert_eq!(
+            parse(input),
+            Err(OpmlError::Malformed {
+                line: 3,
+                reason: "the document ends before `</body>'".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn t_unescape_keeps_unknown_entities_and_bare_ampersands() {
+        assert_eq!(unescape("a &amp; b"), "a & b");
+        assert_eq!(unescape("&lt;&gt;&quot;&apos;"), "<>\"'");
+        assert_eq!(unescape("&#65;&#x42;"), "AB");
+        assert_eq!(unescape("?a=1&b=2"), "?a=1&b=2");
+        assert_eq!(unescape("&nbsp; &"), "&nbsp; &");
+        assert_eq!(unescape("&#0;"), "&#0;");
+    }
+
+    #[test]
+    fn t_generate_writes_opml_1_0() {
+        let feeds = vec![
+            OpmlFeed {
+                xml_url: "https://example.com/feed.xml?a=1&b=2".to_owned(),
+                html_url: Some("https://example.com/".to_owned()),
+                title: Some("Tom & Jerry's \"best\" <bits>".to_owned()),
+                ..OpmlFeed::default()
+            },
+            feed("https://example.com/other.xml", &[]),
+        ];
+
+        assert_eq!(
+            generate(&feeds, OpmlVersion::V1),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <opml version=\"1.0\">\n  \
+               <head>\n    \
+                 <title>newsboat - Exported Feeds</title>\n  \
+               </head>\n  \
+               <body>\n    \
+                 <outline type=\"rss\" xmlUrl=\"https://example.com/feed.xml?a=1&amp;b=2\" \
+                   htmlUrl=\"https://example.com/\" \
+                   title=\"Tom &amp; Jerry's &quot;best&quot; &lt;bits&gt;\"/>\n    \
+                 <outline type=\"rss\" xmlUrl=\"https://example.com/other.xml\"/>\n  \
+               </body>\n\
+             </opml>\n"
+        );
+    }
+
+    #[test]
+    fn t_generate_writes_opml_2_0_with_text_attributes() {
+        let feeds = vec![OpmlFeed {
+            title: Some("Feed".to_owned()),
+            ..feed("https://example.com/feed.xml", &[])
+        }];
+
+        let output = generate(&feeds, OpmlVersion::V2);
+
+        assert!(output.contains("<opml version=\"2.0\">"));
+        assert!(output.contains(
+            "<outline type=\"rss\" text=\"Feed\" xmlUrl=\"https://example.com/feed.xml\" \
+             title=\"Feed\"/>"
+        ));
+    }
+
+    #[test]
+    fn t_generate_nests_folders() {
+        let feeds = vec![
+            feed("https://example.com/1", &["Tech", "Linux"]),
+            feed("https://example.com/2", &[]),
+            feed("https://example.com/3", &["Tech"]),
+            feed("https://example.com/4", &["Tech", "Linux"]),
+        ];
+
+        assert_eq!(
+            generate(&feeds, OpmlVersion::V1),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <opml version=\"1.0\">\n  \
+               <head>\n    \
+                 <title>newsboat - Exported Feeds</title>\n  \
+               </head>\n  \
+               <body>\n    \
+                 <outline type=\"rss\" xmlUrl=\"https://example.com/2\"/>\n    \
+                 <outline text=\"Tech\" title=\"Tech\">\n      \
+                   <outline type=\"rss\" xmlUrl=\"https://example.com/3\"/>\n      \
+                   <outline text=\"Linux\" title=\"Linux\">\n        \
+                     <outline type=\"rss\" xmlUrl=\"https://example.com/1\"/>\n        \
+                     <outline type=\"rss\" xmlUrl=\"https://example.com/4\"/>\n      \
+                   </outline>\n    \
+                 </outline>\n  \
+               </body>\n\
+             </opml>\n"
+        );
+    }
+
+    #[test]
+    fn t_generated_documents_parse_back() {
+        let feeds = vec![
+            OpmlFeed {
+                xml_url: "|~/bin/fetch \"all\"".to_owned(),
+                html_url: None,
+                title: Some("Line\nbreak &\ttab".to_owned()),
+                folders: vec!["A & B".to_owned(), "<C>".to_owned()],
+                filter_command: Some("~/bin/filter".to_owned()),
+            },
+            OpmlFeed {
+                title: Some("Feed".to_owned()),
+                ..feed("https://example.com/feed.xml", &[])
+            },
+        ];
+
+        for &version in &[OpmlVersion::V1, OpmlVersion::V2] {
+            let mut parsed = parse(&generate(&feeds, version)).unwrap();
+            // Feeds in folders are written after the ones outside
+            parsed.reverse();
+            assert_eq!(parsed, feeds);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Filter expressions, like `title =~ "rust" and unread = "yes"`.
+
+use crate::filterparser;
+use std::error;
+use std::fmt;
+
+pub use crate::filterparser::{Expression, Operator, Value};
+
+/// An error produced by `parse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    position: Option<usize>,
+    message: String,
+}
+
+impl ParseError {
+    /// Zero-based byte offset into the expression at which parsing stopped, if it's known.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Parses a filter expression into a tree of comparisons joined by `and` and `or`, and negated by
+/// `!` or `not`.
+///
+/// ```
+/// use libnewsboat::parsers::filter::{parse, Expression, Operator, Value};
+///
+/// match parse(r#"title =~ "rust""#).unwrap() {
+///     Expression::Comparison { attribute, op, value } => {
+///         assert_eq!(attribute, "title");
+///         assert_eq!(op, Operator::RegexMatches);
+///         assert_eq!(value, Value::Literal("rust".to_string()));
+///     }
+///     _ => panic!("expected a comparison"),
+/// }
+///
+/// let error = parse("title =~").unwrap_err();
+/// assert_eq!(error.position(), Some(8));
+/// ```
+pub fn parse(expr: &str) -> Result<Expression, ParseError> {
+    filterparser::parse_with_position(expr)
+        .map_err(|(position, message)| ParseError { position, message })
+}
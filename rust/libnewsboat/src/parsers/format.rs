@@ -0,0 +1,25 @@
+//! Format strings, like `%4i %f %D %?T?|%-17T| ?%t`.
+
+use crate::fmtstrformatter::parser;
+
+pub use crate::fmtstrformatter::parser::{Padding, Specifier};
+
+/// Splits a format string into specifiers and the text between them. Never fails: formats whose
+/// key isn't an ASCII character are dropped, and a string that can't be parsed at all yields
+/// a single empty text.
+///
+/// ```
+/// use libnewsboat::parsers::format::{parse, Padding, Specifier};
+///
+/// assert_eq!(
+///     parse("%-4i %t"),
+///     vec![
+///         Specifier::Format('i', Padding::Right(4)),
+///         Specifier::Text(" "),
+///         Specifier::Format('t', Padding::None),
+///     ]
+/// );
+/// ```
+pub fn parse(input: &str) -> Vec<Specifier> {
+    parser::parse(input)
+}
@@ -0,0 +1,83 @@
+//! Key names, like `^R`, `ENTER` or `q`.
+
+use crate::keycombination;
+use std::error;
+use std::fmt;
+
+/// A key that Newsboat knows of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    canonical: String,
+    is_legacy: bool,
+}
+
+impl Key {
+    /// The spelling that Newsboat uses, e.g. `^R`.
+    pub fn canonical(&self) -> &str {
+        &self.canonical
+    }
+
+    /// `true` if the key was written some other way, e.g. `C-r` or `<Space>`. Newsboat accepts
+    /// those, but warns about them.
+    pub fn is_legacy(&self) -> bool {
+        self.is_legacy
+    }
+}
+
+/// A key name that Newsboat doesn't accept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    name: String,
+    suggestions: Vec<&'static str>,
+}
+
+impl UnknownKey {
+    /// The name as it was written.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Named keys that look similar, closest first.
+    pub fn suggestions(&self) -> &[&'static str] {
+        &self.suggestions
+    }
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let error = keycombination::UnknownKeyName {
+            name: self.name.clone(),
+            suggestions: self.suggestions.clone(),
+        };
+        write!(f, "{}", error.to_message())
+    }
+}
+
+impl error::Error for UnknownKey {}
+
+/// Parses a key name, accepting other programs' spellings where it's clear which key they mean.
+///
+/// ```
+/// use libnewsboat::parsers::keys::parse;
+///
+/// let key = parse("C-r").unwrap();
+/// assert_eq!(key.canonical(), "^R");
+/// assert!(key.is_legacy());
+///
+/// assert!(!parse("ENTER").unwrap().is_legacy());
+///
+/// let error = parse("ENTRE").unwrap_err();
+/// assert_eq!(error.suggestions(), &["ENTER"]);
+/// ```
+pub fn parse(name: &str) -> Result<Key, UnknownKey> {
+    match keycombination::parse_key_name(name) {
+        Ok(key) => Ok(Key {
+            canonical: key.canonical,
+            is_legacy: key.is_legacy,
+        }),
+        Err(error) => Err(UnknownKey {
+            name: error.name,
+            suggestions: error.suggestions,
+        }),
+    }
+}
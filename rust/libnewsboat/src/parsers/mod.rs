@@ -0,0 +1,22 @@
+//! Parsers for the small languages used in Newsboat's config files, usable without the rest of
+//! Newsboat.
+//!
+//! This is the part of libnewsboat that third-party tools, like config linters, can rely on. The
+//! items re-exported or defined under `parsers` follow the crate's semantic versioning: they won't
+//! change in an incompatible way without a bump of the major version. Enums and structs that are
+//! likely to grow (new filter operators, new kinds of format specifiers) are `#[non_exhaustive]`,
+//! so match on them with a wildcard arm. Everything else in libnewsboat is internal to Newsboat and
+//! can change at any time.
+//!
+//! - `operations`: sequences of operations, as used in macros and `run`;
+//! - `keys`: key names, as used in `bind-key` and `unbind-key`;
+//! - `filter`: filter expressions, as used in `ignore-article`, `define-filter` and query feeds;
+//! - `format`: format strings, as used in `articlelist-format` and friends.
+//!
+//! Errors implement `std::error::Error`, and their `Display` is the same (translated) message that
+//! Newsboat shows to its users.
+
+pub mod filter;
+pub mod format;
+pub mod keys;
+pub mod operations;
@@ -0,0 +1,106 @@
+//! Sequences of operations, like `open ; set browser "w3m %u" && open-in-browser`.
+
+use crate::keymap;
+use gettextrs::gettext;
+use std::error;
+use std::fmt;
+
+/// A step of an operation sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Step {
+    /// The name of an operation, followed by its arguments. Never empty.
+    Operation(Vec<String>),
+    /// `&&`: the next operation only runs if the previous one succeeded.
+    AndThen,
+    /// `||`: the next operation only runs if the previous one failed.
+    OrElse,
+}
+
+/// An error produced by `tokenize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenizeError {
+    input: String,
+}
+
+impl TokenizeError {
+    /// The sequence that couldn't be tokenized.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            strprintf::fmt!(
+                &gettext("failed to tokenize operation sequence: %s"),
+                &self.input
+            )
+        )
+    }
+}
+
+impl error::Error for TokenizeError {}
+
+/// Splits an operation sequence into operations, and the connectors between them.
+///
+/// Operations are separated by semicolons, `&&` or `||`. Tokens can be double-quoted, in which
+/// case they can contain spaces and C-like escapes. The input must not contain comments.
+///
+/// ```
+/// use libnewsboat::parsers::operations::{tokenize, Step};
+///
+/// let steps = tokenize(r#"set browser "w3m %u" && open-in-browser"#).unwrap();
+/// assert_eq!(
+///     steps,
+///     vec![
+///         Step::Operation(vec!["set".into(), "browser".into(), "w3m %u".into()]),
+///         Step::AndThen,
+///         Step::Operation(vec!["open-in-browser".into()]),
+///     ]
+/// );
+///
+/// assert!(tokenize("open &&").is_err());
+/// ```
+pub fn tokenize(input: &str) -> Result<Vec<Step>, TokenizeError> {
+    let operations = keymap::tokenize_operation_sequence(input).ok_or_else(|| TokenizeError {
+        input: input.to_string(),
+    })?;
+    Ok(operations
+        .into_iter()
+        .map(|tokens| match tokens.as_slice() {
+            [connector] if connector == keymap::AND_THEN => Step::AndThen,
+            [connector] if connector == keymap::OR_ELSE => Step::OrElse,
+            _ => Step::Operation(tokens),
+        })
+        .collect())
+}
+
+/// The inverse of `tokenize`: writes `steps` as an operation sequence, quoting the tokens that
+/// need it.
+///
+/// ```
+/// use libnewsboat::parsers::operations::{serialize, tokenize, Step};
+///
+/// let steps = vec![
+///     Step::Operation(vec!["set".into(), "browser".into(), "w3m %u".into()]),
+///     Step::Operation(vec!["open-in-browser".into()]),
+/// ];
+/// let serialized = serialize(&steps);
+/// assert_eq!(serialized, r#"set browser "w3m %u"; open-in-browser"#);
+/// assert_eq!(tokenize(&serialized).unwrap(), steps);
+/// ```
+pub fn serialize(steps: &[Step]) -> String {
+    let operations = steps
+        .iter()
+        .map(|step| match step {
+            Step::Operation(tokens) => tokens.clone(),
+            Step::AndThen => vec![keymap::AND_THEN.to_string()],
+            Step::OrElse => vec![keymap::OR_ELSE.to_string()],
+        })
+        .collect::<Vec<_>>();
+    keymap::quote_operation_sequence(&operations)
+}
@@ -0,0 +1,222 @@
+//! The text that `pipe-to` sends to a command.
+//!
+//! Scripts parse this, so it doesn't depend on the terminal or the locale: it's UTF-8 with LF line
+//! endings, has no style tags or escape sequences, and starts with the same header block every
+//! time.
+
+/// Flag that makes `pipe-to` send the article's HTML instead of the rendered text.
+pub const RAW_HTML_FLAG: &str = "--raw-html";
+
+/// Fields of the header block. They're always present, in this order, even if they're empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    pub title: String,
+    pub author: String,
+    pub date: String,
+    pub link: String,
+}
+
+impl Header {
+    /// The header lines, followed by an empty line. The labels aren't translated, so that scripts
+    /// can look for them.
+    pub fn render(&self) -> String {
+        let fields = [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Date", &self.date),
+            ("Link", &self.link),
+        ];
+        let mut result = String::new();
+        for (name, value) in fields.iter() {
+            let value = normalize_line_endings(&strip_escape_sequences(value)).replace('\n', " ");
+            result.push_str(name);
+            result.push(':');
+            if !value.trim().is_empty() {
+                result.push(' ');
+                result.push_str(value.trim());
+            }
+            result.push('\n');
+        }
+        result.push('\n');
+        result
+    }
+}
+
+/// `header` followed by `text`, which is the rendered article.
+///
+/// Escape sequences that an external `html-renderer` might have produced are removed, along with
+/// trailing whitespace and empty lines at the start and end.
+pub fn plain_text(header: &Header, text: &str) -> String {
+    let text = strip_escape_sequences(&normalize_line_endings(text));
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+
+    let mut result = header.render();
+    if let (Some(first), Some(last)) = (first, last) {
+        for line in &lines[first..=last] {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    result
+}
+
+/// `header` followed by the article's original `html`. Apart from the line endings, the HTML is
+/// passed on as is.
+pub fn raw_html(header: &Header, html: &str) -> String {
+    let mut result = header.render();
+    let html = normalize_line_endings(html);
+    if !html.trim().is_empty() {
+        result.push_str(&html);
+        if !html.ends_with('\n') {
+            result.push('\n');
+        }
+    }
+    result
+}
+
+/// Splits `--raw-html` off the front of a `pipe-to` command. Returns whether the flag was given,
+/// and the command to run.
+pub fn parse_command(input: &str) -> (bool, &str) {
+    let input = input.trim_start();
+    let mut words = input.splitn(2, char::is_whitespace);
+    if words.next() == Some(RAW_HTML_FLAG) {
+        return (true, words.next().unwrap_or("").trim_start());
+    }
+    (false, input)
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Removes ANSI escape sequences: CSI (colours, cursor movement), OSC (e.g. hyperlinks), character
+/// set designations, and the two-character ones.
+fn strip_escape_sequences(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                // Parameters and intermediate bytes, up to the final byte
+                for c in &mut chars {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                // Terminated by BEL or by ST, which is ESC followed by a backslash
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            Some('(') | Some(')') => {
+                // Character set designation, which has one more character
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_header() -> Header {
+        Header {
+            title: "A frivolous test item".to_string(),
+            author: "Johnny Doe Jr.".to_string(),
+            date: "Sun, 30 Sep 2018 19:34:25 +0000".to_string(),
+            link: "https://example.com/see-more".to_string(),
+        }
+    }
+
+    #[test]
+    fn t_plain_text_is_header_blank_line_and_body() {
+        let text = " \r\nHello, world! \r\n \r\nSee also this site[1].\r\n \r\nLinks: \r\n\
+                    [1]: https://example.com/ (link)\r\n";
+        assert_eq!(
+            plain_text(&fixture_header(), text),
+            "Title: A frivolous test item\n\
+             Author: Johnny Doe Jr.\n\
+             Date: Sun, 30 Sep 2018 19:34:25 +0000\n\
+             Link: https://example.com/see-more\n\
+             \n\
+             Hello, world!\n\
+             \n\
+             See also this site[1].\n\
+             \n\
+             Links:\n\
+             [1]: https://example.com/ (link)\n"
+        );
+    }
+
+    #[test]
+    fn t_raw_html_passes_the_html_on() {
+        let html =
+            "<p>Hello, world!</p>\r\n<p>See also <a href='https://example.com'>this site</a>.</p>";
+        assert_eq!(
+            raw_html(&fixture_header(), html),
+            "Title: A frivolous test item\n\
+             Author: Johnny Doe Jr.\n\
+             Date: Sun, 30 Sep 2018 19:34:25 +0000\n\
+             Link: https://example.com/see-more\n\
+             \n\
+             <p>Hello, world!</p>\n\
+             <p>See also <a href='https://example.com'>this site</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn t_header_has_the_same_lines_even_if_fields_are_empty() {
+        let header = Header {
+            title: "Multi\nline\r\ntitle".to_string(),
+            ..Header::default()
+        };
+        assert_eq!(
+            header.render(),
+            "Title: Multi line title\nAuthor:\nDate:\nLink:\n\n"
+        );
+        assert_eq!(plain_text(&header, ""), header.render());
+        assert_eq!(raw_html(&header, " \n"), header.render());
+    }
+
+    #[test]
+    fn t_escape_sequences_are_removed() {
+        let text =
+            "\x1b[1mbold\x1b[0m and \x1b]8;;https://example.com\x07a link\x1b]8;;\x1b\\\x1b(B";
+        let header = Header {
+            author: "\x1b[31mRed\x1b[m".to_string(),
+            ..fixture_header()
+        };
+        let result = plain_text(&header, text);
+        assert!(result.contains("Author: Red\n"));
+        assert!(result.ends_with("\n\nbold and a link\n"));
+        assert!(!result.contains('\x1b'));
+    }
+
+    #[test]
+    fn t_parse_command_splits_off_the_raw_html_flag() {
+        assert_eq!(parse_command("less"), (false, "less"));
+        assert_eq!(
+            parse_command("--raw-html  w3m -T text/html"),
+            (true, "w3m -T text/html")
+        );
+        assert_eq!(parse_command(" --raw-html"), (true, ""));
+        assert_eq!(parse_command("--raw-htmlfoo"), (false, "--raw-htmlfoo"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Messages with a count in them, like "keeping 3 cached items".
+//!
+//! Languages differ in how many plural forms they have and in which counts take which form, so
+//! such messages can't be a fixed text with a number put in. Their singular and plural are looked
+//! up together, and the translation picks the form that the count needs; messages that aren't
+//! translated are pluralized like English ones.
+//!
+//! Most of the time, you should just use `fmt_plural!`, which takes the count, the singular and
+//! the plural, and then the values to format like `fmt!` does:
+//! ```
+//! use libnewsboat::fmt_plural;
+//!
+//! let cached_items = 3;
+//! let warning = fmt_plural!(
+//!     cached_items,
+//!     "keeping %u cached item",
+//!     "keeping %u cached items",
+//!     cached_items as u32
+//! );
+//! assert_eq!(warning, "keeping 3 cached items");
+//! ```
+//!
+//! A message with more than one count is put together from parts that have one count each.
+
+/// Picks the form for a count: 0 is the singular, 1 the first plural, and so on.
+pub type PluralRule = fn(u64) -> usize;
+
+/// The rule of English, which is used for messages that aren't translated: one, or many.
+pub fn english_rule(n: u64) -> usize {
+    if n == 1 {
+        0
+    } else {
+        1
+    }
+}
+
+/// Where the translations of messages with a count come from.
+pub trait Catalog {
+    /// The translation of `singular`/`plural` in the form that `n` needs, or `None` if the
+    /// message isn't translated.
+    fn lookup(&self, singular: &str, plural: &str, n: u64) -> Option<String>;
+}
+
+/// The translations that were loaded with `textdomain()`.
+pub struct Gettext;
+
+impl Catalog for Gettext {
+    fn lookup(&self, singular: &str, plural: &str, n: u64) -> Option<String> {
+        // gettext falls back to the English rule by itself.
+        Some(gettextrs::ngettext(singular, plural, gettext_count(n)))
+    }
+}
+
+/// gettext takes counts that fit into 32 bits. Plural rules only look at the last few digits of
+/// larger ones, so those are kept, and the count stays large.
+fn gettext_count(n: u64) -> u32 {
+    if n <= u64::from(u32::MAX) {
+        n as u32
+    } else {
+        (1_000_000_000 + n % 1_000_000_000) as u32
+    }
+}
+
+/// Translates a message with a count using `catalog`, or pluralizes it like English if it isn't
+/// translated there.
+pub fn ngettext_in<C: Catalog>(catalog: &C, singular: &str, plural: &str, n: u64) -> String {
+    catalog
+        .lookup(singular, plural, n)
+        .unwrap_or_else(|| match english_rule(n) {
+            0 => singular.to_string(),
+            _ => plural.to_string(),
+        })
+}
+
+/// Translates a message with a count using the loaded translations.
+pub fn ngettext(singular: &str, plural: &str, n: u64) -> String {
+    ngettext_in(&Gettext, singular, plural, n)
+}
+
+/// Translates a message with a count, and formats it like `fmt!`.
+///
+/// `fmt_plural!(count, singular, plural, args...)`; `count` picks the form, and is usually one of
+/// the `args` too. The Makefile's `extract` target knows about this macro, so that translators
+/// get both forms.
+#[macro_export]
+macro_rules! fmt_plural {
+    ( $n:expr, $singular:expr, $plural:expr ) => {
+        $crate::plural::ngettext($singular, $plural, $n as u64)
+    };
+    ( $n:expr, $singular:expr, $plural:expr, $( $arg:expr ),+ ) => {
+        ::strprintf::fmt!(
+            &$crate::plural::ngettext($singular, $plural, $n as u64),
+            $( $arg ),+
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Translations with a plural rule of their own, like a PO file.
+    struct Translations {
+        rule: PluralRule,
+        /// Forms of each message, by its singular.
+        messages: HashMap<&'static str, Vec<&'static str>>,
+    }
+
+    impl Catalog for Translations {
+        fn lookup(&self, singular: &str, _plural: &str, n: u64) -> Option<String> {
+            let forms = self.messages.get(singular)?;
+            forms
+                .get((self.rule)(n))
+                .or_else(|| forms.last())
+                .map(|form| form.to_string())
+        }
+    }
+
+    /// A rule with three forms, like Polish or Russian: one, a few (2-4, 22-24, ...), many.
+    fn three_forms(n: u64) -> usize {
+        if n == 1 {
+            0
+        } else if n % 10 >= 2 && n % 10 <= 4 && (n % 100 < 12 || n % 100 > 14) {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// A pseudo-locale that marks which form was picked.
+    fn pseudo_locale() -> Translations {
+        let mut messages = HashMap::new();
+        messages.insert(
+            "%u cached item",
+            vec![
+                "[one] %u cached item",
+                "[few] %u cached items",
+                "[many] %u cached items",
+            ],
+        );
+        Translations {
+            rule: three_forms,
+            messages,
+        }
+    }
+
+    fn cached_items(catalog: &Translations, n: u64) -> String {
+        strprintf::fmt!(
+            &ngettext_in(catalog, "%u cached item", "%u cached items", n),
+            n as u32
+        )
+    }
+
+    #[test]
+    fn t_untranslated_messages_are_pluralized_like_english() {
+        let items = |n: u32| fmt_plural!(n, "%u cached item", "%u cached items", n);
+        assert_eq!(items(0), "0 cached items");
+        assert_eq!(items(1), "1 cached item");
+        assert_eq!(items(2), "2 cached items");
+
+        let without_translations = pseudo_locale();
+        assert_eq!(
+            ngettext_in(&without_translations, "%u page", "%u pages", 1),
+            "%u page"
+        );
+        assert_eq!(
+            ngettext_in(&without_translations, "%u page", "%u pages", 0),
+            "%u pages"
+        );
+    }
+
+    #[test]
+    fn t_translations_pick_the_form_with_their_own_rule() {
+        let catalog = pseudo_locale();
+        assert_eq!(cached_items(&catalog, 0), "[many] 0 cached items");
+        assert_eq!(cached_items(&catalog, 1), "[one] 1 cached item");
+        assert_eq!(cached_items(&catalog, 2), "[few] 2 cached items");
+        assert_eq!(cached_items(&catalog, 5), "[many] 5 cached items");
+        assert_eq!(cached_items(&catalog, 12), "[many] 12 cached items");
+        assert_eq!(cached_items(&catalog, 22), "[few] 22 cached items");
+        assert_eq!(cached_items(&catalog, 111), "[many] 111 cached items");
+    }
+
+    #[test]
+    fn t_translations_with_too_few_forms_use_the_last_one() {
+        let mut catalog = pseudo_locale();
+        catalog
+            .messages
+            .insert("%u page", vec!["[one] %u page", "[other] %u pages"]);
+        assert_eq!(
+            ngettext_in(&catalog, "%u page", "%u pages", 5),
+            "[other] %u pages"
+        );
+    }
+
+    #[test]
+    fn t_large_counts_keep_their_last_digits_for_gettext() {
+        assert_eq!(gettext_count(42), 42);
+        assert_eq!(gettext_count(u64::from(u32::MAX)), u32::MAX);
+
+        let large = 5_000_000_022u64;
+        let count = u64::from(gettext_count(large));
+        assert!(count > 1);
+        assert_eq!(three_forms(count), three_forms(large));
+        assert_eq!(english_rule(count), english_rule(large));
+    }
+}
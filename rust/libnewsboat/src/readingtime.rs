@@ -0,0 +1,321 @@
+//! Length of articles in words, and how long it takes to read them.
+//!
+//! Counting words means going through the whole content of an article, which is too slow to do
+//! for every line of the article list. The cache does it when it stores an article, and keeps the
+//! count along with a hash of the content it was computed from; as long as the hash doesn't
+//! change, the stored count is reused. The reading time is derived from the count whenever it's
+//! needed, so that changing `reading-speed` doesn't require recounting anything.
+
+use crate::articleupdates::content_hash;
+
+/// Reading speed that is used if the configured one is zero.
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 200;
+
+/// Elements whose content isn't prose, and is skipped when counting words.
+const SKIPPED_ELEMENTS: [&str; 4] = ["pre", "code", "script", "style"];
+
+/// Number of words in an article, along with the hash of the content they were counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArticleLength {
+    pub content_hash: u64,
+    pub words: u64,
+}
+
+impl ArticleLength {
+    /// Measures `content`. If `previous` was measured from the same content, it's returned as is,
+    /// without counting the words again.
+    pub fn measure(content: &str, previous: Option<ArticleLength>) -> ArticleLength {
+        let hash = content_hash(content);
+        match previous {
+            Some(previous) if previous.content_hash == hash => previous,
+            _ => ArticleLength {
+                content_hash: hash,
+                words: word_count(content),
+            },
+        }
+    }
+}
+
+/// Whether `c` belongs to a script that doesn't separate words with spaces, so that each
+/// character is counted as a word of its own.
+fn is_ideographic(c: char) -> bool {
+    match c as u32 {
+        // Hiragana and Katakana
+        0x3040..=0x30FF => true,
+        // CJK Unified Ideographs Extension A
+        0x3400..=0x4DBF => true,
+        // CJK Unified Ideographs
+        0x4E00..=0x9FFF => true,
+        // CJK Compatibility Ideographs
+        0xF900..=0xFAFF => true,
+        // Halfwidth Katakana
+        0xFF66..=0xFF9D => true,
+        // CJK Unified Ideographs Extension B and later, and the supplement of compatibility
+        // ideographs
+        0x2_0000..=0x2_FA1F => true,
+        _ => false,
+    }
+}
+
+fn looks_like_url(token: &str) -> bool {
+    token.contains("://") || token.starts_with("www.") || token.starts_with("mailto:")
+}
+
+/// Words in a whitespace-separated token: one if it contains letters or digits, plus one for
+/// every ideographic character.
+fn words_in_token(token: &str) -> u64 {
+    if looks_like_url(token) {
+        return 0;
+    }
+
+    let mut words = 0;
+    let mut in_word = false;
+    for c in token.chars() {
+        if is_ideographic(c) {
+            words += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+                in_word = true;
+            }
+        } else if !(c == '\'' || c == '’' || c == '-') {
+            // Apostrophes and hyphens join the parts of words like "don't" and "well-known"
+            in_word = false;
+        }
+    }
+    words
+}
+
+/// Name of the element that a tag (without the angle brackets) opens or closes, in lowercase.
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Turns HTML into plain text, dropping the markup, code, scripts and styles. Text that doesn't
+/// contain markup comes out as is.
+fn prose(content: &str) -> String {
+    let mut text = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut skipping: Option<String> = None;
+
+    while let Some(start) = rest.find(&['<', '&'][..]) {
+        if skipping.is_none() {
+            text.push_str(&rest[..start]);
+        }
+        rest = &rest[start..];
+
+        if rest.starts_with('&') {
+            // Entities don't separate words, except for non-breaking spaces
+            let entity_end = rest.find(';').filter(|&end| {
+                end <= 10
+                    && rest[1..end]
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '#')
+            });
+            match entity_end {
+                Some(end) => {
+                    let entity = &rest[1..end];
+                    if entity == "nbsp" || entity == "#160" || entity == "#xa0" {
+                        text.push(' ');
+                    }
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    if skipping.is_none() {
+                        text.push('&');
+                    }
+                    rest = &rest[1..];
+                }
+            }
+            continue;
+        }
+
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => {
+                // Not a tag after all
+                if skipping.is_none() {
+                    text.push_str(rest);
+                }
+                return text;
+            }
+        };
+        let tag = &rest[1..end];
+        let name = tag_name(tag);
+        match skipping {
+            Some(ref skipped) => {
+                if tag.starts_with('/') && name == *skipped {
+                    skipping = None;
+                }
+            }
+            None => {
+                if !tag.starts_with('/')
+                    && !tag.ends_with('/')
+                    && SKIPPED_ELEMENTS.contains(&name.as_str())
+                {
+                    skipping = Some(name);
+                }
+            }
+        }
+        text.push(' ');
+        rest = &rest[end + 1..];
+    }
+
+    if skipping.is_none() {
+        text.push_str(rest);
+    }
+    text
+}
+
+/// Number of words in the content of an article.
+///
+/// The content can be HTML or plain text. Markup isn't counted, and neither are URLs, code blocks
+/// (`<pre>` and `<code>` elements, and blocks fenced with three backticks), scripts and styles.
+/// Scripts that don't separate words with spaces (Chinese and Japanese) count every character as
+/// a word.
+pub fn word_count(content: &str) -> u64 {
+    let text = prose(content);
+
+    let mut words = 0;
+    let mut in_fence = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        words += line.split_whitespace().map(words_in_token).sum::<u64>();
+    }
+    words
+}
+
+/// Minutes it takes to read `words` words at `words_per_minute`, rounded up. Zero words take zero
+/// minutes. A speed of zero is replaced by `DEFAULT_WORDS_PER_MINUTE`.
+pub fn reading_minutes(words: u64, words_per_minute: u32) -> u64 {
+    let speed = if words_per_minute == 0 {
+        DEFAULT_WORDS_PER_MINUTE
+    } else {
+        words_per_minute
+    };
+    (words as f64 / f64::from(speed)).ceil() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_word_count_counts_whitespace_separated_words() {
+        assert_eq!(word_count(""), 0);
+        assert_eq!(word_count("   \n\t"), 0);
+        assert_eq!(word_count("Hello, world!"), 2);
+        assert_eq!(word_count("one two\nthree\tfour  five"), 5);
+        assert_eq!(word_count("Don't split well-known words — or dashes"), 6);
+    }
+
+    #[test]
+    fn t_word_count_handles_mixed_language_content() {
+        assert_eq!(word_count("Grüße aus Köln"), 3);
+        assert_eq!(word_count("Привет, мир"), 2);
+        // Every ideograph and kana is a word of its own
+        assert_eq!(word_count("日本語のテキスト"), 8);
+        assert_eq!(word_count("Rust言語 is nice"), 5);
+        assert_eq!(word_count("中文，English"), 3);
+    }
+
+    #[test]
+    fn t_word_count_ignores_markup() {
+        assert_eq!(
+            word_count("<p>Some <b>bold</b> text</p><p>and <a href=\"x\">a link</a></p>"),
+            6
+        );
+        assert_eq!(word_count("<p>word<br/>word</p>"), 2);
+        assert_eq!(word_count("fish&amp;chips"), 1);
+        assert_eq!(word_count("non&nbsp;breaking"), 2);
+        assert_eq!(word_count("1 < 2 & 3"), 3);
+        assert_eq!(word_count("this & that;"), 2);
+    }
+
+    #[test]
+    fn t_word_count_ignores_urls() {
+        assert_eq!(
+            word_count("See https://example.com/some/long/path and www.example.org"),
+            2
+        );
+        assert_eq!(word_count("write to mailto:me@example.com"), 2);
+    }
+
+    #[test]
+    fn t_word_count_ignores_code() {
+        assert_eq!(
+            word_count("<p>Run this:</p><pre>let x = foo(bar);\nprintln!(x);</pre><p>Done</p>"),
+            3
+        );
+        assert_eq!(
+            word_count("Call <code>do_something(with, args)</code> now"),
+            2
+        );
+        assert_eq!(
+            word_count("<PRE class=\"x\"><code>nested code</code> more</PRE>after"),
+            1
+        );
+        assert_eq!(word_count("<script>var a = 1;</script>text"), 1);
+        assert_eq!(
+            word_count("Before\n```\nfn main() {}\n```\nafter the fence"),
+            4
+        );
+    }
+
+    #[test]
+    fn t_measure_counts_new_content() {
+        let length = ArticleLength::measure("three little words", None);
+        assert_eq!(length.words, 3);
+        assert_eq!(length.content_hash, content_hash("three little words"));
+    }
+
+    #[test]
+    fn t_measure_reuses_previous_count_for_unchanged_content() {
+        // A count that couldn't have come from the content shows that it wasn't recomputed
+        let previous = ArticleLength {
+            content_hash: content_hash("three little words"),
+            words: 42,
+        };
+        assert_eq!(
+            ArticleLength::measure("three little words", Some(previous)),
+            previous
+        );
+        assert_eq!(
+            ArticleLength::measure("  three little\nwords ", Some(previous)),
+            previous
+        );
+    }
+
+    #[test]
+    fn t_measure_recounts_changed_content() {
+        let previous = ArticleLength::measure("three little words", None);
+        let length = ArticleLength::measure("now four little words", Some(previous));
+        assert_eq!(length.words, 4);
+        assert_ne!(length.content_hash, previous.content_hash);
+    }
+
+    #[test]
+    fn t_reading_minutes_rounds_up() {
+        assert_eq!(reading_minutes(0, 200), 0);
+        assert_eq!(reading_minutes(1, 200), 1);
+        assert_eq!(reading_minutes(200, 200), 1);
+        assert_eq!(reading_minutes(201, 200), 2);
+        assert_eq!(reading_minutes(1000, 250), 4);
+    }
+
+    #[test]
+    fn t_reading_minutes_uses_default_speed_instead_of_zero() {
+        assert_eq!(reading_minutes(400, 0), 2);
+    }
+}
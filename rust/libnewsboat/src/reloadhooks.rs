@@ -0,0 +1,473 @@
+//! Commands that are run before and after particular feeds are fetched.
+//!
+//! The user sets them with `pre-reload-command <rssurl> <command>` and
+//! `post-reload-command <rssurl> <command>`. A typical use is refreshing an authentication token
+//! that the feed's URL or a cookie file depends on.
+
+use crate::utils;
+use gettextrs::gettext;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use strprintf::fmt;
+
+/// How many lines from the end of a hook's stderr are kept for error messages.
+const STDERR_TAIL_LINES: usize = 3;
+
+/// How often a running hook is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookKind {
+    /// Runs before the feed is fetched; if it fails, the feed isn't fetched.
+    PreReload,
+    /// Runs after the feed is fetched, whatever the outcome.
+    PostReload,
+}
+
+impl HookKind {
+    /// Name of the config command that sets hooks of this kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookKind::PreReload => "pre-reload-command",
+            HookKind::PostReload => "post-reload-command",
+        }
+    }
+}
+
+/// Result of fetching a feed, as passed to post-reload hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadOutcome {
+    Ok,
+    Error,
+    NotModified,
+}
+
+impl ReloadOutcome {
+    pub fn from_u8(value: u8) -> ReloadOutcome {
+        match value {
+            1 => ReloadOutcome::Error,
+            2 => ReloadOutcome::NotModified,
+            _ => ReloadOutcome::Ok,
+        }
+    }
+
+    /// Name of the outcome, as passed to the hook.
+    pub fn name(self) -> &'static str {
+        match self {
+            ReloadOutcome::Ok => "ok",
+            ReloadOutcome::Error => "error",
+            ReloadOutcome::NotModified => "not-modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookStatus {
+    /// The command exited with zero status.
+    Succeeded,
+    /// The command exited with non-zero status, or was killed by a signal (`None`).
+    Failed(Option<i32>),
+    /// The command didn't finish in time and was killed.
+    TimedOut,
+    /// The command couldn't be started at all.
+    CouldNotStart(String),
+}
+
+/// A single run of a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookExecution {
+    pub url: String,
+    pub kind: HookKind,
+    pub status: HookStatus,
+    /// Last few lines that the command printed to stderr.
+    pub stderr_tail: String,
+}
+
+impl HookExecution {
+    pub fn succeeded(&self) -> bool {
+        self.status == HookStatus::Succeeded
+    }
+
+    /// Explains what went wrong, e.g. "exited with status 1: token expired". Empty if the hook
+    /// succeeded.
+    pub fn error_message(&self) -> String {
+        let reason = match self.status {
+            HookStatus::Succeeded => return String::new(),
+            HookStatus::Failed(Some(code)) => fmt!(&gettext("exited with status %i"), code),
+            HookStatus::Failed(None) => gettext("was killed by a signal"),
+            HookStatus::TimedOut => gettext("timed out"),
+            HookStatus::CouldNotStart(ref error) => {
+                fmt!(&gettext("couldn't be started: %s"), error.as_str())
+            }
+        };
+        if self.stderr_tail.is_empty() {
+            reason
+        } else {
+            fmt!("%s: %s", reason, self.stderr_tail.as_str())
+        }
+    }
+
+    /// A line for the reload report, e.g. "pre-reload-command for https://example.com/feed.xml:
+    /// ok".
+    pub fn describe(&self) -> String {
+        let result = if self.succeeded() {
+            gettext("ok")
+        } else {
+            self.error_message()
+        };
+        fmt!(
+            &gettext("%s for %s: %s"),
+            self.kind.name(),
+            self.url.as_str(),
+            result
+        )
+    }
+}
+
+/// Runs `command` through the shell, passing `args` to it as positional parameters (`$1`, `$2`
+/// etc.). The command is killed if it doesn't exit within `timeout`.
+///
+/// Returns the status and the last few lines of stderr; stdout is discarded.
+pub fn run_with_timeout(command: &str, args: &[String], timeout: Duration) -> (HookStatus, String) {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", command))
+        // Becomes $0
+        .arg("sh")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(error) => return (HookStatus::CouldNotStart(error.to_string()), String::new()),
+    };
+
+    // Stderr is read on a separate thread, so that a chatty command doesn't block on a full pipe
+    // while we wait for it to exit.
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = stderr.read_to_end(&mut output);
+            output
+        })
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break HookStatus::Succeeded,
+            Ok(Some(status)) => break HookStatus::Failed(status.code()),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break HookStatus::TimedOut;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(error) => break HookStatus::CouldNotStart(error.to_string()),
+        }
+    };
+
+    // If the command timed out, its own children might still hold stderr open, so we don't wait
+    // for the reader.
+    let stderr = match (status == HookStatus::TimedOut, stderr_reader) {
+        (false, Some(reader)) => reader.join().unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    (status, tail(&String::from_utf8_lossy(&stderr)))
+}
+
+/// Last `STDERR_TAIL_LINES` non-empty lines of `text`, joined with "; ".
+fn tail(text: &str) -> String {
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let start = lines.len().saturating_sub(STDERR_TAIL_LINES);
+    lines[start..].join("; ")
+}
+
+/// Hooks configured by the user, and the record of their runs during the current reload.
+///
+/// All methods can be called from multiple reload threads at once.
+#[derive(Debug)]
+pub struct ReloadHooks {
+    /// Commands by kind and feed URL.
+    commands: HashMap<(HookKind, String), String>,
+    timeout: Duration,
+    /// Hooks that ran during the current reload cycle, in the order they ran.
+    runs: Mutex<Vec<HookExecution>>,
+}
+
+impl Default for ReloadHooks {
+    fn default() -> ReloadHooks {
+        ReloadHooks {
+            commands: HashMap::new(),
+            timeout: Duration::from_secs(30),
+            runs: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl ReloadHooks {
+    pub fn new() -> ReloadHooks {
+        ReloadHooks::default()
+    }
+
+    /// Sets the command of the given kind for the feed at `url`, replacing the previous one.
+    pub fn set_command(&mut self, kind: HookKind, url: &str, command: &str) {
+        self.commands
+            .insert((kind, url.to_string()), command.to_string());
+    }
+
+    pub fn command(&self, kind: HookKind, url: &str) -> Option<&str> {
+        self.commands
+            .get(&(kind, url.to_string()))
+            .map(String::as_str)
+    }
+
+    /// Config lines that recreate the configured hooks, sorted for stable output.
+    pub fn dump_config(&self) -> Vec<String> {
+        let mut result = self
+            .commands
+            .iter()
+            .map(|((kind, url), command)| {
+                format!(
+                    "{} {} {}",
+                    kind.name(),
+                    utils::quote(url.clone()),
+                    utils::quote(command.clone())
+                )
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Starts a new reload cycle, forgetting which hooks ran during the previous one.
+    pub fn start_cycle(&self) {
+        self.lock_runs().clear();
+    }
+
+    /// Runs the pre-reload hook for `url`.
+    ///
+    /// Returns `None` if the feed can be fetched: either there is no hook, or it succeeded. Returns
+    /// the failed run otherwise. Each hook runs at most once per cycle; later calls reuse the
+    /// result of the first run.
+    pub fn run_pre_reload(&self, url: &str) -> Option<HookExecution> {
+        self.run(HookKind::PreReload, url, &[])
+            .filter(|run| !run.succeeded())
+    }
+
+    /// Runs the post-reload hook for `url`, passing it the outcome and the number of new items as
+    /// arguments. Returns the run, or `None` if there's no hook.
+    pub fn run_post_reload(
+        &self,
+        url: &str,
+        outcome: ReloadOutcome,
+        new_items: u32,
+    ) -> Option<HookExecution> {
+        let args = [outcome.name().to_string(), new_items.to_string()];
+        self.run(HookKind::PostReload, url, &args)
+    }
+
+    /// Hooks that ran during the current cycle.
+    pub fn report(&self) -> Vec<HookExecution> {
+        self.lock_runs().clone()
+    }
+
+    fn run(&self, kind: HookKind, url: &str, args: &[String]) -> Option<HookExecution> {
+        let command = self.command(kind, url)?;
+
+        if let Some(previous) = self
+            .lock_runs()
+            .iter()
+            .find(|run| run.kind == kind && run.url == url)
+        {
+            return Some(previous.clone());
+        }
+
+        let (status, stderr_tail) = run_with_timeout(command, args, self.timeout);
+        let execution = HookExecution {
+            url: url.to_string(),
+            kind,
+            status,
+            stderr_tail,
+        };
+        self.lock_runs().push(execution.clone());
+        Some(execution)
+    }
+
+    fn lock_runs(&self) -> std::sync::MutexGuard<Vec<HookExecution>> {
+        // A poisoned lock only means that some other thread panicked while holding it; the list
+        // itself is still fine.
+        self.runs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const URL: &str = "https://example.com/feed.xml";
+
+    #[test]
+    fn t_pre_reload_hook_failure_is_reported_with_stderr_tail() {
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(
+            HookKind::PreReload,
+            URL,
+            "echo first >&2; echo second >&2; echo third >&2; echo 'token expired' >&2; exit 3",
+        );
+
+        let failure = hooks.run_pre_reload(URL).unwrap();
+        assert_eq!(failure.status, HookStatus::Failed(Some(3)));
+        assert_eq!(failure.stderr_tail, "second; third; token expired");
+        assert_eq!(
+            failure.error_message(),
+            "exited with status 3: second; third; token expired"
+        );
+    }
+
+    #[test]
+    fn t_successful_pre_reload_hook_lets_the_feed_be_fetched() {
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(HookKind::PreReload, URL, "true");
+        assert_eq!(hooks.run_pre_reload(URL), None);
+
+        // Feeds without hooks are always fetched
+        assert_eq!(hooks.run_pre_reload("https://example.com/other.xml"), None);
+    }
+
+    #[test]
+    fn t_post_reload_hook_receives_outcome_and_new_item_count() {
+        let tmp = TempDir::new().unwrap();
+        let output = tmp.path().join("args");
+
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(
+            HookKind::PostReload,
+            URL,
+            &format!("printf '%s,' > '{}'", output.display()),
+        );
+
+        let run = hooks
+            .run_post_reload(URL, ReloadOutcome::NotModified, 0)
+            .unwrap();
+        assert!(run.succeeded());
+        assert_eq!(fs::read_to_string(&output).unwrap(), "not-modified,0,");
+
+        hooks.start_cycle();
+        hooks.run_post_reload(URL, ReloadOutcome::Ok, 42).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "ok,42,");
+
+        hooks.start_cycle();
+        hooks.run_post_reload(URL, ReloadOutcome::Error, 0).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "error,0,");
+    }
+
+    #[test]
+    fn t_hooks_that_run_too_long_are_killed() {
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(HookKind::PreReload, URL, "sleep 10");
+        hooks.set_timeout(Duration::from_millis(100));
+
+        let start = Instant::now();
+        let failure = hooks.run_pre_reload(URL).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(failure.status, HookStatus::TimedOut);
+        assert_eq!(failure.error_message(), "timed out");
+    }
+
+    #[test]
+    fn t_hooks_run_at_most_once_per_cycle() {
+        let tmp = TempDir::new().unwrap();
+        let counter = tmp.path().join("counter");
+
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(
+            HookKind::PreReload,
+            URL,
+            &format!("echo run >> '{}'; exit 1", counter.display()),
+        );
+
+        assert!(hooks.run_pre_reload(URL).is_some());
+        // The second run reuses the result of the first one, so the feed is still skipped
+        assert!(hooks.run_pre_reload(URL).is_some());
+        assert_eq!(fs::read_to_string(&counter).unwrap(), "run\n");
+
+        hooks.start_cycle();
+        hooks.run_pre_reload(URL);
+        assert_eq!(fs::read_to_string(&counter).unwrap(), "run\nrun\n");
+    }
+
+    #[test]
+    fn t_report_lists_hooks_that_ran_in_this_cycle() {
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(HookKind::PreReload, URL, "true");
+        hooks.set_command(HookKind::PostReload, URL, "echo oops >&2; false");
+
+        assert_eq!(hooks.report(), vec![]);
+
+        hooks.run_pre_reload(URL);
+        hooks.run_post_reload(URL, ReloadOutcome::Ok, 1);
+
+        let report = hooks
+            .report()
+            .iter()
+            .map(HookExecution::describe)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            report,
+            vec![
+                format!("pre-reload-command for {}: ok", URL),
+                format!(
+                    "post-reload-command for {}: exited with status 1: oops",
+                    URL
+                ),
+            ]
+        );
+
+        hooks.start_cycle();
+        assert_eq!(hooks.report(), vec![]);
+    }
+
+    #[test]
+    fn t_dump_config_recreates_the_hooks() {
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(HookKind::PostReload, URL, "notify-send \"done\"");
+        hooks.set_command(HookKind::PreReload, URL, "refresh-token");
+        assert_eq!(
+            hooks.dump_config(),
+            vec![
+                format!(r#"post-reload-command "{}" "notify-send \"done\"""#, URL),
+                format!(r#"pre-reload-command "{}" "refresh-token""#, URL),
+            ]
+        );
+    }
+
+    #[test]
+    fn t_commands_that_cannot_be_found_fail() {
+        let mut hooks = ReloadHooks::new();
+        hooks.set_command(HookKind::PreReload, URL, "/nonexistent/newsboat-hook");
+        let failure = hooks.run_pre_reload(URL).unwrap();
+        // sh reports "not found" with exit status 127
+        assert_eq!(failure.status, HookStatus::Failed(Some(127)));
+        assert!(!failure.stderr_tail.is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+//! Progress of a reload, shared between the threads that fetch feeds and the UI thread.
+
+use crate::human_panic;
+use crate::logger::{self, Level};
+use crate::utils;
+use gettextrs::gettext;
+use std::panic::UnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Progress of a reload.
+///
+/// Threads that fetch feeds report each feed they start and finish; the UI thread takes a
+/// `snapshot()` on every redraw. Both counters are packed into a single atomic, so a snapshot
+/// never sees more feeds done than there are in total, nor counters from two different reloads.
+///
+/// The fetchers also poll `is_cancelled()` between (and during) requests, so that the user can stop
+/// a reload midway. Feeds that were fetched by that time stay updated.
+///
+/// Each feed is fetched by a task that runs under `run_feed()`: if the task panics, the feed is
+/// recorded as failed (see `failures()`) and counted as done, so the reload still completes.
+#[derive(Debug, Default)]
+pub struct ReloadProgress {
+    /// Number of feeds done in the upper 32 bits, total number of feeds in the lower 32 bits.
+    counters: AtomicU64,
+    cancelled: AtomicBool,
+    /// Title of the feed that was started most recently.
+    current: Mutex<String>,
+    failures: Mutex<Vec<FeedFailure>>,
+}
+
+/// A feed that couldn't be reloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedFailure {
+    pub url: String,
+    /// Internationalized description of the problem.
+    pub message: String,
+}
+
+/// Counts a feed as done when dropped, however its task ended. Returned by
+/// `ReloadProgress::begin_feed()`.
+#[must_use]
+#[derive(Debug)]
+pub struct FeedGuard<'a> {
+    progress: &'a ReloadProgress,
+}
+
+impl<'a> Drop for FeedGuard<'a> {
+    fn drop(&mut self) {
+        self.progress.feed_finished();
+    }
+}
+
+/// A consistent view of `ReloadProgress` at some point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReloadProgressSnapshot {
+    /// Number of feeds that were fetched (successfully or not).
+    pub done: u32,
+    /// Number of feeds in this reload; zero if no reload is running.
+    pub total: u32,
+    /// Title of the feed that's being fetched.
+    pub current: String,
+    /// `true` if the user asked to stop the reload.
+    pub cancelled: bool,
+}
+
+fn pack(done: u32, total: u32) -> u64 {
+    (u64::from(done) << 32) | u64::from(total)
+}
+
+fn unpack(counters: u64) -> (u32, u32) {
+    ((counters >> 32) as u32, counters as u32)
+}
+
+impl ReloadProgress {
+    pub fn new() -> ReloadProgress {
+        ReloadProgress::default()
+    }
+
+    /// Begins a new reload of `total` feeds, forgetting any previous progress or cancellation.
+    pub fn start(&self, total: u32) {
+        self.set_current("");
+        self.lock_failures().clear();
+        self.cancelled.store(false, Ordering::SeqCst);
+        self.counters.store(pack(0, total), Ordering::SeqCst);
+    }
+
+    /// Records that fetching of the feed titled `title` has begun.
+    pub fn feed_started(&self, title: &str) {
+        self.set_current(title);
+    }
+
+    /// Records that a feed was fetched (successfully or not). Never counts past the total.
+    pub fn feed_finished(&self) {
+        // The closure always returns `Some`, so this can't fail.
+        let _ = self
+            .counters
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |counters| {
+                let (done, total) = unpack(counters);
+                Some(pack(done.saturating_add(1).min(total), total))
+            });
+    }
+
+    /// Records that fetching of the feed titled `title` has begun; the feed is counted as done
+    /// once the returned guard is dropped.
+    pub fn begin_feed(&self, title: &str) -> FeedGuard {
+        self.feed_started(title);
+        FeedGuard { progress: self }
+    }
+
+    /// Runs the task that reloads the feed at `url`, titled `title`.
+    ///
+    /// The feed is counted as done once the task ends. If the task panics, the panic is logged
+    /// along with its report, the feed is recorded as failed, and `None` is returned.
+    pub fn run_feed<F, T>(&self, url: &str, title: &str, task: F) -> Option<T>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        let _guard = self.begin_feed(title);
+        match human_panic::catch(task) {
+            Ok(result) => Some(result),
+            Err(panic) => {
+                let message = strprintf::fmt!(
+                    &gettext("internal error while parsing feed, please report: %s"),
+                    panic.message()
+                );
+                log!(
+                    "reloadprogress",
+                    Level::UserError,
+                    &strprintf::fmt!(
+                        &gettext("Error while retrieving %s: %s"),
+                        utils::censor_url(url),
+                        &message
+                    )
+                );
+                log!(
+                    "reloadprogress",
+                    Level::Error,
+                    &format!(
+                        "ReloadProgress::run_feed: task for {} panicked:\n{}",
+                        url,
+                        panic.report()
+                    )
+                );
+                self.feed_failed(url, &message);
+                None
+            }
+        }
+    }
+
+    /// Records that the feed at `url` couldn't be reloaded.
+    pub fn feed_failed(&self, url: &str, message: &str) {
+        self.lock_failures().push(FeedFailure {
+            url: url.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Feeds that couldn't be reloaded since the last `start()`, in the order they failed. Empty
+    /// once the reload is `finish()`ed.
+    pub fn failures(&self) -> Vec<FeedFailure> {
+        self.lock_failures().clone()
+    }
+
+    /// Marks the reload as finished, i.e. there's no more progress to show.
+    pub fn finish(&self) {
+        self.counters.store(pack(0, 0), Ordering::SeqCst);
+        self.cancelled.store(false, Ordering::SeqCst);
+        self.set_current("");
+        self.lock_failures().clear();
+    }
+
+    /// Asks the fetchers to stop: no new feeds should be fetched, and requests that are in flight
+    /// should be aborted. Does nothing if no reload is running.
+    pub fn cancel(&self) {
+        let (_, total) = unpack(self.counters.load(Ordering::SeqCst));
+        if total > 0 {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns `true` if the user asked to stop the current reload.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn snapshot(&self) -> ReloadProgressSnapshot {
+        let (done, total) = unpack(self.counters.load(Ordering::SeqCst));
+        ReloadProgressSnapshot {
+            done,
+            total,
+            current: self.current(),
+            cancelled: self.is_cancelled(),
+        }
+    }
+
+    fn current(&self) -> String {
+        match self.current.lock() {
+            Ok(current) => current.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn lock_failures(&self) -> std::sync::MutexGuard<Vec<FeedFailure>> {
+        match self.failures.lock() {
+            Ok(failures) => failures,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn set_current(&self, title: &str) {
+        let mut current = match self.current.lock() {
+            Ok(current) => current,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        current.clear();
+        current.push_str(title);
+    }
+}
+
+impl ReloadProgressSnapshot {
+    /// Returns `true` if a reload is running.
+    pub fn in_progress(&self) -> bool {
+        self.total > 0
+    }
+
+    /// Percentage of feeds done, rounded down.
+    pub fn percent(&self) -> u32 {
+        if self.total == 0 {
+            return 0;
+        }
+        (u64::from(self.done) * 100 / u64::from(self.total)) as u32
+    }
+
+    /// Value of the `%P` format specifier: percentage of feeds done, or an empty string if no
+    /// reload is running (so that `%?P?...&...?` can tell the two apart).
+    pub fn percent_text(&self) -> String {
+        if self.in_progress() {
+            self.percent().to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Value of the `%C` format specifier: title of the feed that's being fetched, or an empty
+    /// string if no reload is running.
+    pub fn current_text(&self) -> String {
+        if self.in_progress() {
+            self.current.clone()
+        } else {
+            String::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fmtstrformatter::FmtStrFormatter;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn t_no_progress_before_the_first_reload() {
+        let progress = ReloadProgress::new();
+        let snapshot = progress.snapshot();
+        assert!(!snapshot.in_progress());
+        assert_eq!(snapshot.percent(), 0);
+        assert_eq!(snapshot.percent_text(), "");
+        assert_eq!(snapshot.current_text(), "");
+    }
+
+    #[test]
+    fn t_counts_finished_feeds_and_remembers_the_current_one() {
+        let progress = ReloadProgress::new();
+        progress.start(3);
+        progress.feed_started("Planet Rust");
+        progress.feed_finished();
+        progress.feed_started("LWN.net");
+
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.done, 1);
+        assert_eq!(snapshot.total, 3);
+        assert_eq!(snapshot.percent(), 33);
+        assert_eq!(snapshot.current, "LWN.net");
+
+        progress.feed_finished();
+        progress.feed_finished();
+        progress.feed_finished();
+        assert_eq!(progress.snapshot().done, 3);
+        assert_eq!(progress.snapshot().percent(), 100);
+
+        progress.finish();
+        assert!(!progress.snapshot().in_progress());
+        assert_eq!(progress.snapshot().current, "");
+    }
+
+    #[test]
+    fn t_snapshots_are_consistent_under_concurrent_updates() {
+        const THREADS: u32 = 8;
+        const FEEDS_PER_THREAD: u32 = 500;
+
+        let progress = Arc::new(ReloadProgress::new());
+        progress.start(THREADS * FEEDS_PER_THREAD);
+
+        let fetchers = (0..THREADS)
+            .map(|i| {
+                let progress = Arc::clone(&progress);
+                thread::spawn(move || {
+                    for j in 0..FEEDS_PER_THREAD {
+                        progress.feed_started(&format!("feed {}-{}", i, j));
+                        progress.feed_finished();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut last_done = 0;
+        for _ in 0..1000 {
+            let snapshot = progress.snapshot();
+            assert_eq!(snapshot.total, THREADS * FEEDS_PER_THREAD);
+            assert!(snapshot.done <= snapshot.total);
+            assert!(snapshot.done >= last_done);
+            assert!(snapshot.percent() <= 100);
+            last_done = snapshot.done;
+        }
+
+        for fetcher in fetchers {
+            fetcher.join().unwrap();
+        }
+        assert_eq!(progress.snapshot().done, THREADS * FEEDS_PER_THREAD);
+    }
+
+    #[test]
+    fn t_cancel_keeps_the_progress_made_so_far() {
+        let progress = ReloadProgress::new();
+        progress.start(4);
+        progress.feed_finished();
+        progress.feed_finished();
+
+        progress.cancel();
+        assert!(progress.is_cancelled());
+        let snapshot = progress.snapshot();
+        assert!(snapshot.cancelled);
+        assert_eq!(snapshot.done, 2);
+
+        // Requests that were in flight still finish (by being aborted)
+        progress.feed_finished();
+        assert_eq!(progress.snapshot().done, 3);
+
+        // The next reload starts afresh
+        progress.start(4);
+        assert!(!progress.is_cancelled());
+        assert_eq!(progress.snapshot().done, 0);
+    }
+
+    #[test]
+    fn t_cancel_does_nothing_if_no_reload_is_running() {
+        let progress = ReloadProgress::new();
+        progress.cancel();
+        assert!(!progress.is_cancelled());
+
+        progress.start(1);
+        progress.feed_finished();
+        progress.finish();
+        progress.cancel();
+        assert!(!progress.is_cancelled());
+    }
+
+    #[test]
+    fn t_finished_feeds_never_exceed_the_total() {
+        let progress = ReloadProgress::new();
+        progress.start(1);
+        progress.feed_finished();
+        progress.feed_finished();
+        assert_eq!(progress.snapshot().done, 1);
+    }
+
+    #[test]
+    fn t_reload_completes_even_if_a_feed_task_panics() {
+        const FEEDS: u32 = 4;
+        const BROKEN: u32 = 2;
+
+        let progress = Arc::new(ReloadProgress::new());
+        progress.start(FEEDS);
+
+        let workers = (0..FEEDS)
+            .map(|i| {
+                let progress = Arc::clone(&progress);
+                thread::spawn(move || {
+                    let url = format!("https://example.com/{}.xml", i);
+                    progress.run_feed(&url, &format!("feed {}", i), || {
+                        if i == BROKEN {
+                            panic!("pathological feed");
+                        }
+                        i
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(results, vec![Some(0), Some(1), None, Some(3)]);
+        assert_eq!(progress.snapshot().done, FEEDS);
+        assert_eq!(progress.snapshot().percent(), 100);
+        assert_eq!(
+            progress.failures(),
+            vec![FeedFailure {
+                url: "https://example.com/2.xml".to_string(),
+                message: "internal error while parsing feed, please report: pathological feed"
+                    .to_string(),
+            }]
+        );
+        assert!(human_panic::caught_reports()
+            .iter()
+            .any(|report| report.contains("Message: pathological feed")));
+
+        progress.finish();
+        assert_eq!(progress.failures(), vec![]);
+    }
+
+    #[test]
+    fn t_feed_guard_counts_the_feed_however_the_task_ends() {
+        let progress = ReloadProgress::new();
+        progress.start(3);
+
+        {
+            let _guard = progress.begin_feed("Planet Rust");
+            assert_eq!(progress.snapshot().current, "Planet Rust");
+            assert_eq!(progress.snapshot().done, 0);
+        }
+        assert_eq!(progress.snapshot().done, 1);
+
+        let early_return = |fail: bool| -> Result<(), ()> {
+            let _guard = progress.begin_feed("LWN.net");
+            if fail {
+                return Err(());
+            }
+            Ok(())
+        };
+        assert_eq!(early_return(true), Err(()));
+        assert_eq!(early_return(false), Ok(()));
+        assert_eq!(progress.snapshot().done, 3);
+    }
+
+    #[test]
+    fn t_format_specifiers_render_progress_mid_reload() {
+        let format = "Feeds%?P? - reloading %P%% (%C)&?";
+        let render = |snapshot: &ReloadProgressSnapshot| {
+            let mut fmt = FmtStrFormatter::new();
+            fmt.register_fmt('P', snapshot.percent_text());
+            fmt.register_fmt('C', snapshot.current_text());
+            fmt.do_format(format, 0)
+        };
+
+        let progress = ReloadProgress::new();
+        assert_eq!(render(&progress.snapshot()), "Feeds");
+
+        progress.start(4);
+        progress.feed_started("Planet Rust");
+        progress.feed_finished();
+        assert_eq!(
+            render(&progress.snapshot()),
+            "Feeds - reloading 25% (Planet Rust)"
+        );
+
+        progress.finish();
+        assert_eq!(render(&progress.snapshot()), "Feeds");
+    }
+}
@@ -0,0 +1,197 @@
+//! Reloading only the feeds that carry a given tag: `reload-tag <tag>`, or `reload-all <tag>`.
+//!
+//! Such a reload is a scheduled pass over the tagged feeds, just like the ones `auto-reload`
+//! does: feeds whose `<ttl>`, `<skipHours>` or `<skipDays>` say they shouldn't be fetched right
+//! now are skipped (see `feedschedule`), and per-host download limits apply as usual.
+
+use crate::utils;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Tags that start with this character set the title of a feed, so they can't be reloaded.
+const TITLE_TAG_PREFIX: char = '~';
+
+/// The feeds that a reload of a tag covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagScope {
+    tag: String,
+    /// Positions of the feeds that carry the tag in the feed list.
+    indexes: Vec<usize>,
+    /// Number of feeds in the feed list.
+    total: usize,
+}
+
+/// The tag to reload isn't carried by any feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTag {
+    pub tag: String,
+    /// Tags of the feed list that are close to `tag`, closest first.
+    pub suggestions: Vec<String>,
+}
+
+impl UnknownTag {
+    /// Internationalized description of the error, including the suggestions if there are any.
+    pub fn message(&self) -> String {
+        let message = fmt!(&gettext("No feed is tagged `%s'"), &self.tag);
+        if self.suggestions.is_empty() {
+            message
+        } else {
+            fmt!(
+                &gettext("%s. Did you mean: %s?"),
+                message,
+                self.suggestions.join(", ")
+            )
+        }
+    }
+}
+
+impl TagScope {
+    /// Picks the feeds that carry `tag`. `feed_tags` holds the tags of every feed in the feed
+    /// list, in order; feeds that can't be reloaded, like query feeds, should have no tags.
+    pub fn select(tag: &str, feed_tags: &[Vec<String>]) -> Result<TagScope, UnknownTag> {
+        let indexes = feed_tags
+            .iter()
+            .enumerate()
+            .filter(|(_, tags)| tags.iter().any(|candidate| candidate == tag))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        if indexes.is_empty() || tag.starts_with(TITLE_TAG_PREFIX) {
+            return Err(UnknownTag {
+                tag: tag.to_string(),
+                suggestions: suggestions(tag, feed_tags),
+            });
+        }
+
+        Ok(TagScope {
+            tag: tag.to_string(),
+            indexes,
+            total: feed_tags.len(),
+        })
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Positions of the feeds to reload in the feed list, in ascending order.
+    pub fn indexes(&self) -> &[usize] {
+        &self.indexes
+    }
+
+    /// Notes the scope of the reload, for the status line and the report of `-x reload-tag`.
+    pub fn summary(&self) -> String {
+        let count = self.indexes.len();
+        fmt_plural!(
+            count,
+            "Reloading %u feed tagged `%s' (out of %u)",
+            "Reloading %u feeds tagged `%s' (out of %u)",
+            count as u32,
+            &self.tag,
+            self.total as u32
+        )
+    }
+}
+
+/// Tags in `feed_tags` that are close to `tag`, closest first. Among equally close tags, the one
+/// that comes first in the feed list wins.
+fn suggestions(tag: &str, feed_tags: &[Vec<String>]) -> Vec<String> {
+    let candidates = feed_tags
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .filter(|candidate| !candidate.starts_with(TITLE_TAG_PREFIX));
+    utils::suggest(candidates, tag)
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_tags(feeds: &[&[&str]]) -> Vec<Vec<String>> {
+        feeds
+            .iter()
+            .map(|tags| tags.iter().map(|tag| tag.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn t_selects_only_the_feeds_that_carry_the_tag() {
+        let feeds = feed_tags(&[
+            &["news", "daily"],
+            &["tech"],
+            &[],
+            &["tech", "news"],
+            &["newsletters"],
+        ]);
+
+        let scope = TagScope::select("news", &feeds).unwrap();
+        assert_eq!(scope.tag(), "news");
+        assert_eq!(scope.indexes(), &[0, 3]);
+
+        let scope = TagScope::select("tech", &feeds).unwrap();
+        assert_eq!(scope.indexes(), &[1, 3]);
+    }
+
+    #[test]
+    fn t_tags_are_matched_exactly() {
+        let feeds = feed_tags(&[&["News"], &["news feed"], &["!news"]]);
+
+        let scope = TagScope::select("!news", &feeds).unwrap();
+        assert_eq!(scope.indexes(), &[2]);
+
+        assert!(TagScope::select("news", &feeds).is_err());
+    }
+
+    #[test]
+    fn t_title_tags_cannot_be_reloaded() {
+        let feeds = feed_tags(&[&["~My feed", "news"]]);
+
+        let error = TagScope::select("~My feed", &feeds).unwrap_err();
+        assert_eq!(error.suggestions, Vec::<String>::new());
+    }
+
+    #[test]
+    fn t_unknown_tag_suggests_close_ones() {
+        let feeds = feed_tags(&[&["news", "~Newz"], &["tech", "new"], &["news"], &["sport"]]);
+
+        let error = TagScope::select("nwes", &feeds).unwrap_err();
+        assert_eq!(
+            error,
+            UnknownTag {
+                tag: "nwes".to_string(),
+                suggestions: vec!["news".to_string(), "new".to_string()],
+            }
+        );
+        assert_eq!(
+            error.message(),
+            "No feed is tagged `nwes'. Did you mean: news, new?"
+        );
+    }
+
+    #[test]
+    fn t_unknown_tag_without_close_ones_suggests_nothing() {
+        let feeds = feed_tags(&[&["news"], &["tech"]]);
+
+        let error = TagScope::select("podcasts", &feeds).unwrap_err();
+        assert!(error.suggestions.is_empty());
+        assert_eq!(error.message(), "No feed is tagged `podcasts'");
+    }
+
+    #[test]
+    fn t_summary_notes_the_tag_and_the_number_of_feeds() {
+        let feeds = feed_tags(&[&["news"], &["tech"], &["news", "tech"]]);
+
+        let scope = TagScope::select("news", &feeds).unwrap();
+        assert_eq!(
+            scope.summary(),
+            "Reloading 2 feeds tagged `news' (out of 3)"
+        );
+
+        let scope = TagScope::select("tech", &feed_tags(&[&["tech"], &[]])).unwrap();
+        assert_eq!(scope.summary(), "Reloading 1 feed tagged `tech' (out of 2)");
+    }
+}
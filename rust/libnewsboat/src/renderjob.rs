@@ -0,0 +1,274 @@
+//! Rendering an article a bit at a time.
+//!
+//! A pathological article (megabytes of nested lists) takes the HTML renderer seconds, and the UI
+//! used to freeze for that long. Now the article view renders in bounded units of work between
+//! key presses. `RenderJob` decides what to do after each unit: show the first screenful as soon
+//! as it's there, keep going, or stop because the user navigated away. Only a complete rendering
+//! goes into the `RenderCache`, so a cancelled one is simply done again next time.
+
+use crate::contentcache::{ByteSize, CacheStats, ContentCache};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Number of HTML nodes that are rendered in one unit of work. Small enough that a unit takes a
+/// few milliseconds even for deeply nested markup.
+pub const NODES_PER_UNIT: usize = 500;
+
+/// Tells a `RenderJob` to stop. Clones share the flag, so whoever navigates away can cancel a job
+/// that's owned by someone else.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// What to do after a unit of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Render another unit; there's nothing new to show yet.
+    Continue,
+    /// Enough is rendered to fill the screen: show it, then render the rest.
+    ShowFirstScreen,
+    /// The article is rendered completely: show it.
+    Finished,
+    /// The job was cancelled: show nothing, and don't cache what was rendered.
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct RenderJob {
+    token: CancelToken,
+    screen_height: usize,
+    first_screen_shown: bool,
+    finished: bool,
+}
+
+impl RenderJob {
+    /// Starts a job for a screen that's `screen_height` lines high.
+    pub fn new(screen_height: usize) -> RenderJob {
+        RenderJob {
+            token: CancelToken::new(),
+            screen_height,
+            first_screen_shown: false,
+            finished: false,
+        }
+    }
+
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Whether another unit should be rendered. This is checked between units, so cancelling
+    /// takes effect after at most one unit.
+    pub fn should_continue(&self) -> bool {
+        !self.finished && !self.token.is_cancelled()
+    }
+
+    /// Records that a unit was rendered, after which there are `lines` lines of output.
+    /// `finished` tells if that was the last unit.
+    ///
+    /// Every line takes at least one row on the screen, so `lines` reaching the screen height
+    /// means that the screen can be filled. That's reported once; if the article is finished
+    /// before it gets that long, only `Step::Finished` is.
+    pub fn unit_done(&mut self, lines: usize, finished: bool) -> Step {
+        if self.token.is_cancelled() {
+            return Step::Cancelled;
+        }
+        if finished {
+            self.finished = true;
+            return Step::Finished;
+        }
+        if !self.first_screen_shown && lines >= self.screen_height {
+            self.first_screen_shown = true;
+            return Step::ShowFirstScreen;
+        }
+        Step::Continue
+    }
+
+    /// Whether the article was rendered completely, without being cancelled.
+    pub fn is_complete(&self) -> bool {
+        self.finished && !self.token.is_cancelled()
+    }
+}
+
+/// The renderer's output for an article: lines with their `LineType`, and links with their
+/// `LinkType`. Both types are C++ enums, and are kept as numbers here.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedArticle {
+    pub lines: Vec<(u8, String)>,
+    pub links: Vec<(String, u8)>,
+}
+
+impl ByteSize for RenderedArticle {
+    fn byte_size(&self) -> usize {
+        let lines: usize = self.lines.iter().map(|(_, line)| line.len() + 1).sum();
+        let links: usize = self.links.iter().map(|(url, _)| url.len() + 1).sum();
+        lines + links
+    }
+}
+
+/// Rendered articles by GUID.
+pub struct RenderCache {
+    cache: ContentCache<String, RenderedArticle>,
+}
+
+impl RenderCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> RenderCache {
+        RenderCache {
+            cache: ContentCache::new(max_entries, max_bytes),
+        }
+    }
+
+    pub fn set_limits(&self, max_entries: usize, max_bytes: usize) {
+        self.cache.set_limits(max_entries, max_bytes);
+    }
+
+    /// Returns the rendered article `guid`, and keeps it from being evicted while it's shown.
+    pub fn get(&self, guid: &str) -> Option<RenderedArticle> {
+        let guid = guid.to_string();
+        self.cache.pin(Some(guid.clone()));
+        self.cache.get(&guid)
+    }
+
+    /// Remembers `article` if `job` rendered it completely. Returns whether it did.
+    pub fn store(&self, guid: &str, job: &RenderJob, article: RenderedArticle) -> bool {
+        if !job.is_complete() {
+            return false;
+        }
+        let guid = guid.to_string();
+        self.cache.pin(Some(guid.clone()));
+        self.cache.insert(guid, article);
+        true
+    }
+
+    pub fn contains(&self, guid: &str) -> bool {
+        self.cache.contains(&guid.to_string())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Forgets all articles, e.g. because another feed was opened.
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(lines: usize) -> RenderedArticle {
+        RenderedArticle {
+            lines: (0..lines).map(|i| (1, format!("line {}", i))).collect(),
+            links: vec![("https://example.com/".to_string(), 0)],
+        }
+    }
+
+    /// Runs `job` over units that end with the given numbers of lines, the last of which
+    /// finishes the article, and collects the steps.
+    fn run(job: &mut RenderJob, units: &[usize]) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for (i, &lines) in units.iter().enumerate() {
+            if !job.should_continue() {
+                break;
+            }
+            steps.push(job.unit_done(lines, i + 1 == units.len()));
+        }
+        steps
+    }
+
+    #[test]
+    fn t_first_screen_is_shown_once_before_the_rest() {
+        let mut job = RenderJob::new(10);
+        assert_eq!(
+            run(&mut job, &[3, 8, 12, 20, 25]),
+            vec![
+                Step::Continue,
+                Step::Continue,
+                Step::ShowFirstScreen,
+                Step::Continue,
+                Step::Finished,
+            ]
+        );
+        assert!(job.is_complete());
+        assert!(!job.should_continue());
+    }
+
+    #[test]
+    fn t_short_article_is_only_shown_when_finished() {
+        let mut job = RenderJob::new(10);
+        assert_eq!(run(&mut job, &[4, 9]), vec![Step::Continue, Step::Finished]);
+
+        // Finishing in the unit that fills the screen shows it only once, too
+        let mut job = RenderJob::new(10);
+        assert_eq!(
+            run(&mut job, &[4, 30]),
+            vec![Step::Continue, Step::Finished]
+        );
+    }
+
+    #[test]
+    fn t_cancelling_stops_the_job_between_units() {
+        let mut job = RenderJob::new(10);
+        let token = job.token();
+        assert_eq!(job.unit_done(12, false), Step::ShowFirstScreen);
+
+        token.cancel();
+        assert!(!job.should_continue());
+        // A unit that was already underway is reported as cancelled, even if it was the last
+        assert_eq!(job.unit_done(20, true), Step::Cancelled);
+        assert!(!job.is_complete());
+    }
+
+    #[test]
+    fn t_cancelled_job_leaves_no_cache_entry() {
+        let cache = RenderCache::new(10, 10_000);
+        let mut job = RenderJob::new(10);
+        job.unit_done(12, false);
+        job.cancel();
+        job.unit_done(20, true);
+
+        assert!(!cache.store("guid", &job, article(20)));
+        assert!(!cache.contains("guid"));
+        assert_eq!(cache.get("guid"), None);
+    }
+
+    #[test]
+    fn t_only_complete_renderings_are_cached() {
+        let cache = RenderCache::new(10, 10_000);
+        let mut job = RenderJob::new(10);
+        job.unit_done(12, false);
+        assert!(!cache.store("guid", &job, article(12)));
+        assert!(!cache.contains("guid"));
+
+        job.unit_done(20, true);
+        assert!(cache.store("guid", &job, article(20)));
+        assert_eq!(cache.get("guid"), Some(article(20)));
+    }
+
+    #[test]
+    fn t_rendered_article_counts_lines_and_links() {
+        let article = RenderedArticle {
+            lines: vec![(1, "hello".to_string()), (4, String::new())],
+            links: vec![("https://example.com/".to_string(), 0)],
+        };
+        assert_eq!(article.byte_size(), 6 + 1 + 21);
+    }
+}
@@ -0,0 +1,180 @@
+//! Maps the rows of a list widget to the items they show.
+//!
+//! Most lists show each item on a single row, but with `articlelist-wrap-titles` an article can
+//! take up several. The cursor, and everything that moves it, works with items; STFL only knows
+//! about rows.
+
+/// The rows of each item in a list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowGroups {
+    /// Index of the first row of each item.
+    first_rows: Vec<u32>,
+    rows: u32,
+}
+
+impl RowGroups {
+    /// Items that take up `row_counts` rows each. An item always takes at least one row.
+    pub fn new(row_counts: &[u32]) -> RowGroups {
+        let mut first_rows = Vec::with_capacity(row_counts.len());
+        let mut rows = 0;
+        for &count in row_counts {
+            first_rows.push(rows);
+            rows += count.max(1);
+        }
+        RowGroups { first_rows, rows }
+    }
+
+    /// `items` items of one row each.
+    pub fn uniform(items: u32) -> RowGroups {
+        RowGroups {
+            first_rows: (0..items).collect(),
+            rows: items,
+        }
+    }
+
+    pub fn item_count(&self) -> u32 {
+        self.first_rows.len() as u32
+    }
+
+    pub fn row_count(&self) -> u32 {
+        self.rows
+    }
+
+    /// First row of `item`. Items past the end are assumed to take up a row each, so that
+    /// a position set before the list is filled in isn't lost.
+    pub fn first_row(&self, item: u32) -> u32 {
+        match self.first_rows.get(item as usize) {
+            Some(&row) => row,
+            None => self.rows.saturating_add(item - self.item_count()),
+        }
+    }
+
+    /// Last row of `item`. Items past the end are assumed to take up a row each.
+    pub fn last_row(&self, item: u32) -> u32 {
+        match self.first_rows.get(item as usize + 1) {
+            Some(next) => next - 1,
+            None if item + 1 == self.item_count() => self.rows - 1,
+            None => self.first_row(item),
+        }
+    }
+
+    /// Item shown on `row`. Rows past the end are assumed to show an item each.
+    pub fn item_at_row(&self, row: u32) -> u32 {
+        if row >= self.rows {
+            return self.item_count().saturating_add(row - self.rows);
+        }
+        match self.first_rows.binary_search(&row) {
+            Ok(item) => item as u32,
+            Err(next) => next as u32 - 1,
+        }
+    }
+
+    /// Item that's `height` rows further down than `item`. Always moves at least one item, unless
+    /// `item` is the last one.
+    pub fn page_down(&self, item: u32, height: u32) -> u32 {
+        if self.first_rows.is_empty() {
+            return 0;
+        }
+        let target = self.item_at_row(self.first_row(item).saturating_add(height));
+        let last = self.item_count() - 1;
+        if target <= item && item < last {
+            item + 1
+        } else {
+            target.min(last)
+        }
+    }
+
+    /// Item that's `height` rows further up than `item`. Always moves at least one item, unless
+    /// `item` is the first one.
+    pub fn page_up(&self, item: u32, height: u32) -> u32 {
+        let target = self.item_at_row(self.first_row(item).saturating_sub(height));
+        if target >= item && item > 0 {
+            item - 1
+        } else {
+            target
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_uniform_groups_map_rows_to_items_one_to_one() {
+        let groups = RowGroups::uniform(4);
+        assert_eq!(groups, RowGroups::new(&[1, 1, 1, 1]));
+        assert_eq!(groups.item_count(), 4);
+        assert_eq!(groups.row_count(), 4);
+        for i in 0..4 {
+            assert_eq!(groups.first_row(i), i);
+            assert_eq!(groups.last_row(i), i);
+            assert_eq!(groups.item_at_row(i), i);
+        }
+    }
+
+    #[test]
+    fn t_every_row_of_a_group_belongs_to_its_item() {
+        let groups = RowGroups::new(&[1, 3, 2]);
+        assert_eq!(groups.item_count(), 3);
+        assert_eq!(groups.row_count(), 6);
+
+        let items: Vec<u32> = (0..6).map(|row| groups.item_at_row(row)).collect();
+        assert_eq!(items, vec![0, 1, 1, 1, 2, 2]);
+
+        assert_eq!(groups.first_row(1), 1);
+        assert_eq!(groups.last_row(1), 3);
+        assert_eq!(groups.first_row(2), 4);
+        assert_eq!(groups.last_row(2), 5);
+    }
+
+    #[test]
+    fn t_items_always_take_at_least_one_row() {
+        let groups = RowGroups::new(&[0, 2]);
+        assert_eq!(groups.row_count(), 3);
+        assert_eq!(groups.item_at_row(1), 1);
+    }
+
+    #[test]
+    fn t_items_past_the_end_take_up_a_row_each() {
+        let groups = RowGroups::new(&[2, 2]);
+        assert_eq!(groups.last_row(1), 3);
+        assert_eq!(groups.first_row(2), 4);
+        assert_eq!(groups.last_row(2), 4);
+        assert_eq!(groups.first_row(5), 7);
+        assert_eq!(groups.item_at_row(4), 2);
+        assert_eq!(groups.item_at_row(10), 8);
+
+        let empty = RowGroups::default();
+        assert_eq!(empty.item_count(), 0);
+        assert_eq!(empty.item_at_row(3), 3);
+        assert_eq!(empty.first_row(3), 3);
+        assert_eq!(empty.last_row(3), 3);
+        assert_eq!(empty.page_down(0, 5), 0);
+        assert_eq!(empty.page_up(0, 5), 0);
+    }
+
+    #[test]
+    fn t_paging_moves_by_rows_and_lands_on_whole_items() {
+        let groups = RowGroups::new(&[1, 3, 1, 3, 1]);
+        // Row 4 is the third item, row 5 is the first row of the fourth
+        assert_eq!(groups.page_down(0, 4), 2);
+        assert_eq!(groups.page_down(0, 5), 3);
+        // From the second item, five rows down is the middle of the fourth
+        assert_eq!(groups.page_down(1, 5), 3);
+        assert_eq!(groups.page_down(3, 10), 4);
+
+        assert_eq!(groups.page_up(4, 2), 3);
+        assert_eq!(groups.page_up(4, 5), 1);
+        assert_eq!(groups.page_up(4, 100), 0);
+    }
+
+    #[test]
+    fn t_paging_always_moves_by_at_least_one_item() {
+        let groups = RowGroups::new(&[3, 3, 3]);
+        assert_eq!(groups.page_down(0, 1), 1);
+        assert_eq!(groups.page_down(2, 1), 2);
+        assert_eq!(groups.page_up(2, 1), 1);
+        assert_eq!(groups.page_up(0, 1), 0);
+    }
+}
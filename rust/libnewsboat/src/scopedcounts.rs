@@ -0,0 +1,198 @@
+//! Counts of what the current view is scoped to.
+//!
+//! The title formats show how many articles there are, and how many of them are unread. In
+//! a filtered article list, or in a feed list that shows a single tag, the global numbers are
+//! misleading. `ScopedCounts` keeps the numbers for the members of the scope (the articles that
+//! match the filter, or the feeds with the tag), and is updated member by member as their state
+//! changes, so that the filter doesn't have to be re-run over everything to redraw the title.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct ScopedCounts {
+    /// Unread articles of each member, by key. An article has one or none.
+    members: HashMap<String, u32>,
+    /// Sum of the unread articles of all members.
+    unread: u64,
+    /// Members that have at least one unread article.
+    unread_members: usize,
+}
+
+impl ScopedCounts {
+    pub fn new() -> ScopedCounts {
+        ScopedCounts::default()
+    }
+
+    /// Replaces the members with `members`, which are pairs of a key and the number of unread
+    /// articles. Used after the scope was computed from scratch.
+    pub fn reset<I>(&mut self, members: I)
+    where
+        I: IntoIterator<Item = (String, u32)>,
+    {
+        self.clear();
+        for (key, unread) in members {
+            self.update(&key, true, unread);
+        }
+    }
+
+    /// Removes all members.
+    pub fn clear(&mut self) {
+        self.members.clear();
+        self.unread = 0;
+        self.unread_members = 0;
+    }
+
+    /// Records that the member `key` now has `unread` unread articles. `in_scope` tells if it still
+    /// belongs to the scope, which can change along with its state, e.g. if the filter looks at
+    /// the unread flag.
+    pub fn update(&mut self, key: &str, in_scope: bool, unread: u32) {
+        if let Some(previous) = self.members.remove(key) {
+            self.unread -= u64::from(previous);
+            if previous > 0 {
+                self.unread_members -= 1;
+            }
+        }
+        if in_scope {
+            self.members.insert(key.to_string(), unread);
+            self.unread += u64::from(unread);
+            if unread > 0 {
+                self.unread_members += 1;
+            }
+        }
+    }
+
+    /// Number of members.
+    pub fn total(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Number of unread articles of all members.
+    pub fn unread(&self) -> u64 {
+        self.unread
+    }
+
+    /// Number of members that have unread articles.
+    pub fn unread_members(&self) -> usize {
+        self.unread_members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Articles as (guid, unread, matches the filter).
+    fn articles() -> Vec<(String, bool, bool)> {
+        (0..10)
+            .map(|i| (format!("guid-{}", i), i % 3 != 0, i % 2 == 0))
+            .collect()
+    }
+
+    fn scoped(articles: &[(String, bool, bool)]) -> ScopedCounts {
+        let mut counts = ScopedCounts::new();
+        counts.reset(
+            articles
+                .iter()
+                .filter(|(_, _, matches)| *matches)
+                .map(|(guid, unread, _)| (guid.clone(), u32::from(*unread))),
+        );
+        counts
+    }
+
+    fn recount(articles: &[(String, bool, bool)]) -> (usize, u64) {
+        let matching: Vec<_> = articles.iter().filter(|(_, _, m)| *m).collect();
+        let unread = matching.iter().filter(|(_, unread, _)| *unread).count();
+        (matching.len(), unread as u64)
+    }
+
+    #[test]
+    fn t_counts_only_cover_the_members_of_the_scope() {
+        let articles = articles();
+        let counts = scoped(&articles);
+        // Articles 0, 2, 4, 6 and 8 match; 0 and 6 are read
+        assert_eq!(counts.total(), 5);
+        assert_eq!(counts.unread(), 3);
+        assert_eq!(counts.unread_members(), 3);
+        assert_eq!((counts.total(), counts.unread()), recount(&articles));
+    }
+
+    #[test]
+    fn t_marking_read_updates_the_counts() {
+        let mut counts = scoped(&articles());
+        counts.update("guid-2", true, 0);
+        assert_eq!(counts.total(), 5);
+        assert_eq!(counts.unread(), 2);
+
+        // Marking it read again changes nothing
+        counts.update("guid-2", true, 0);
+        assert_eq!(counts.unread(), 2);
+
+        counts.update("guid-0", true, 1);
+        assert_eq!(counts.unread(), 3);
+    }
+
+    #[test]
+    fn t_members_leave_and_join_the_scope_when_their_state_changes() {
+        let mut counts = scoped(&articles());
+        // With a filter like `unread = "yes"`, marking an article read drops it from the scope
+        counts.update("guid-4", false, 0);
+        assert_eq!(counts.total(), 4);
+        assert_eq!(counts.unread(), 2);
+
+        counts.update("guid-1", true, 1);
+        assert_eq!(counts.total(), 5);
+        assert_eq!(counts.unread(), 3);
+
+        // Articles that aren't in the scope can't leave it
+        counts.update("guid-3", false, 0);
+        assert_eq!(counts.total(), 5);
+    }
+
+    #[test]
+    fn t_feed_counts_sum_up_the_unread_articles() {
+        let mut counts = ScopedCounts::new();
+        counts.reset(vec![
+            ("https://example.com/a.xml".to_string(), 4),
+            ("https://example.com/b.xml".to_string(), 0),
+            ("https://example.com/c.xml".to_string(), 10),
+        ]);
+        assert_eq!(counts.total(), 3);
+        assert_eq!(counts.unread(), 14);
+        assert_eq!(counts.unread_members(), 2);
+
+        counts.update("https://example.com/c.xml", true, 9);
+        assert_eq!(counts.unread(), 13);
+        counts.update("https://example.com/a.xml", true, 0);
+        assert_eq!(counts.unread(), 9);
+        assert_eq!(counts.unread_members(), 1);
+    }
+
+    #[test]
+    fn t_incremental_updates_agree_with_a_recount() {
+        let mut articles = articles();
+        let mut counts = scoped(&articles);
+
+        // A fixed sequence of state changes; the filter matches unread articles with an even
+        // index, plus the first and the sixth article
+        for step in 0..100usize {
+            let i = (step * 7 + 3) % articles.len();
+            let unread = !articles[i].1;
+            let matches = (unread && i & 1 == 0) || [0, 5].contains(&i);
+            articles[i].1 = unread;
+            articles[i].2 = matches;
+            counts.update(&articles[i].0, matches, u32::from(unread));
+
+            assert_eq!((counts.total(), counts.unread()), recount(&articles));
+            assert_eq!(counts.unread_members() as u64, counts.unread());
+        }
+    }
+
+    #[test]
+    fn t_reset_forgets_the_previous_members() {
+        let mut counts = scoped(&articles());
+        counts.reset(vec![("guid-42".to_string(), 1)]);
+        assert_eq!(counts.total(), 1);
+        assert_eq!(counts.unread(), 1);
+        assert_eq!(counts.unread_members(), 1);
+    }
+}
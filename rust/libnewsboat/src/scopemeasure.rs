@@ -1,8 +1,15 @@
 //! Measures time spent in a given scope, and writes it to the log.
+//!
+//! `OperationTimer` does the same for each operation of a macro or a key binding, and points out
+//! the slowest one if the whole sequence took too long.
 
-use std::time::Instant;
+use gettextrs::gettext;
+use std::time::{Duration, Instant};
+use strprintf::fmt;
 
 use crate::{
+    articlepreview::{Clock, SystemClock},
+    keymap::quote_operation_sequence,
     log,
     logger::{self, Level},
 };
@@ -17,44 +24,409 @@ use crate::{
 /// Calling `stopover()` will write a debug message to the log mentioning: 1) the name of the
 /// enclosing scope; 2) the name of the stopover; 3) the time that elapsed between constructing the
 /// object and calling `stopover()`.
-pub struct ScopeMeasure {
+///
+/// For hot code, where only the slow runs are interesting, set a `threshold()`: the messages are
+/// then only written if that much time has elapsed. `level()` changes the level they're logged at.
+pub struct ScopeMeasure<C: Clock = SystemClock> {
+    clock: C,
     start_time: Instant,
     scope_name: String,
+    threshold: Option<Duration>,
+    level: Level,
 }
 
-impl ScopeMeasure {
+impl ScopeMeasure<SystemClock> {
     /// Construct an object that will measure time spent in the scope named `scope_name`.
-    pub fn new(scope_name: String) -> ScopeMeasure {
+    pub fn new(scope_name: String) -> ScopeMeasure<SystemClock> {
+        ScopeMeasure::with_clock(scope_name, SystemClock)
+    }
+}
+
+impl<C: Clock> ScopeMeasure<C> {
+    /// Like `new()`, but reads the time from `clock`.
+    pub fn with_clock(scope_name: String, clock: C) -> ScopeMeasure<C> {
         ScopeMeasure {
-            start_time: Instant::now(),
+            start_time: clock.now(),
+            clock,
             scope_name,
+            threshold: None,
+            level: Level::Debug,
         }
     }
 
+    /// Only write messages if at least `threshold` has elapsed.
+    pub fn threshold(mut self, threshold: Duration) -> ScopeMeasure<C> {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Write messages at `level` instead of `Level::Debug`.
+    pub fn level(mut self, level: Level) -> ScopeMeasure<C> {
+        self.level = level;
+        self
+    }
+
     /// Write a message to the log mentioning the scope name, `stopover_name`, and the time elapsed
     /// since the object was constructed.
     pub fn stopover(&self, stopover_name: &str) {
-        log!(
-            Level::Debug,
-            &format!(
-                "ScopeMeasure: function `{}' (stop over `{}') took {:.6} s so far",
+        if let Some(message) = self.message(Some(stopover_name)) {
+            log!("scopemeasure", self.level, &message);
+        }
+    }
+
+    /// The message to log at a stopover named `stopover_name`, or at the end of the scope if
+    /// that's `None`. Returns `None` if the elapsed time is under the threshold.
+    fn message(&self, stopover_name: Option<&str>) -> Option<String> {
+        let elapsed = self.clock.now().duration_since(self.start_time);
+        let stopover = match stopover_name {
+            Some(name) => format!(" (stop over `{}')", name),
+            None => String::new(),
+        };
+        let so_far = if stopover_name.is_some() {
+            " so far"
+        } else {
+            ""
+        };
+
+        match self.threshold {
+            None => Some(format!(
+                "ScopeMeasure: function `{}'{} took {:.6} s{}",
                 self.scope_name,
-                stopover_name,
-                self.start_time.elapsed().as_secs_f64()
-            )
-        );
+                stopover,
+                elapsed.as_secs_f64(),
+                so_far
+            )),
+            Some(threshold) if elapsed >= threshold => Some(format!(
+                "ScopeMeasure: function `{}'{} took {}ms{}, threshold {}ms",
+                self.scope_name,
+                stopover,
+                elapsed.as_millis(),
+                so_far,
+                threshold.as_millis()
+            )),
+            Some(_) => None,
+        }
     }
 }
 
-impl Drop for ScopeMeasure {
+impl<C: Clock> Drop for ScopeMeasure<C> {
     fn drop(&mut self) {
+        if let Some(message) = self.message(None) {
+            log!("scopemeasure", self.level, &message);
+        }
+    }
+}
+
+/// Arguments longer than this are cut off in the log and in the status line.
+const MAX_ARGUMENT_LENGTH: usize = 40;
+
+/// Default for `operation-timing-threshold`.
+pub const DEFAULT_THRESHOLD: Duration = Duration::from_millis(1000);
+
+/// Operation name with its arguments, the way it would be written in a macro. Long arguments
+/// (e.g. URLs with tokens in them) are shortened.
+fn describe(name: &str, args: &[String]) -> String {
+    let mut tokens = Vec::with_capacity(args.len() + 1);
+    tokens.push(name.to_string());
+    for arg in args {
+        if arg.chars().count() > MAX_ARGUMENT_LENGTH {
+            let mut shortened: String = arg.chars().take(MAX_ARGUMENT_LENGTH).collect();
+            shortened.push_str("...");
+            tokens.push(shortened);
+        } else {
+            tokens.push(arg.clone());
+        }
+    }
+    quote_operation_sequence(&[tokens])
+}
+
+/// Times each operation of a sequence (a macro, or the single operation a key is bound to).
+///
+/// Call `start_step()` before running an operation and `finish_step()` after it; each finished
+/// step is written to the log. Once the sequence is done, `slow_sequence_hint()` returns a message
+/// naming the slowest step if the sequence took longer than the threshold.
+///
+/// A disabled timer doesn't look at the clock at all.
+pub struct OperationTimer<C: Clock = SystemClock> {
+    clock: C,
+    /// `None` if timing is disabled.
+    threshold: Option<Duration>,
+    step_start: Option<Instant>,
+    total: Duration,
+    /// Description and duration of the slowest step so far.
+    slowest: Option<(String, Duration)>,
+}
+
+impl OperationTimer<SystemClock> {
+    pub fn new(threshold: Duration) -> OperationTimer<SystemClock> {
+        OperationTimer::with_clock(Some(threshold), SystemClock)
+    }
+
+    pub fn disabled() -> OperationTimer<SystemClock> {
+        OperationTimer::with_clock(None, SystemClock)
+    }
+}
+
+impl<C: Clock> OperationTimer<C> {
+    /// A timer that uses `clock`; `threshold` of `None` disables it.
+    pub fn with_clock(threshold: Option<Duration>, clock: C) -> OperationTimer<C> {
+        OperationTimer {
+            clock,
+            threshold,
+            step_start: None,
+            total: Duration::default(),
+            slowest: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.threshold.is_some()
+    }
+
+    pub fn start_step(&mut self) {
+        if self.is_enabled() {
+            self.step_start = Some(self.clock.now());
+        }
+    }
+
+    /// Records that operation `name` with `args` has finished. Does nothing if `start_step()`
+    /// wasn't called.
+    pub fn finish_step(&mut self, name: &str, args: &[String]) {
+        let start = match self.step_start.take() {
+            Some(start) => start,
+            None => return,
+        };
+        let elapsed = self.clock.now().duration_since(start);
+        self.total += elapsed;
+
+        let description = describe(name, args);
         log!(
+            "scopemeasure",
             Level::Debug,
             &format!(
-                "ScopeMeasure: function `{}' took {:.6} s",
-                self.scope_name,
-                self.start_time.elapsed().as_secs_f64()
+                "OperationTimer: `{}' took {:.6} s",
+                description,
+                elapsed.as_secs_f64()
             )
         );
+
+        let is_slowest = match &self.slowest {
+            Some((_, slowest)) => elapsed > *slowest,
+            None => true,
+        };
+        if is_slowest {
+            self.slowest = Some((description, elapsed));
+        }
+    }
+
+    /// Runs `execute` as one step.
+    pub fn time<F, R>(&mut self, name: &str, args: &[String], execute: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        self.start_step();
+        let result = execute();
+        self.finish_step(name, args);
+        result
+    }
+
+    /// Time spent in all finished steps.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Description and duration of the slowest finished step.
+    pub fn slowest_step(&self) -> Option<(&str, Duration)> {
+        self.slowest
+            .as_ref()
+            .map(|(description, elapsed)| (description.as_str(), *elapsed))
+    }
+
+    /// A message for the status line if the steps took longer than the threshold together.
+    pub fn slow_sequence_hint(&self) -> Option<String> {
+        let threshold = self.threshold?;
+        if self.total <= threshold {
+            return None;
+        }
+        let (description, elapsed) = self.slowest_step()?;
+        Some(fmt!(
+            &gettext("Operations took %.1f s; the slowest was `%s' (%.1f s)"),
+            self.total.as_secs_f64(),
+            description,
+            elapsed.as_secs_f64()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<Instant>>);
+
+    impl FakeClock {
+        fn new() -> FakeClock {
+            FakeClock(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, ms: u64) {
+            self.0.set(self.0.get() + Duration::from_millis(ms));
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    /// Runs a sequence of operations, each of which takes as many milliseconds as it says.
+    fn run(timer: &mut OperationTimer<FakeClock>, clock: &FakeClock, steps: &[(&str, u64)]) {
+        for (name, delay) in steps {
+            let args = vec![format!("{}ms", delay)];
+            timer.time(name, &args, || clock.advance(*delay));
+        }
+    }
+
+    #[test]
+    fn t_no_hint_if_the_sequence_is_faster_than_the_threshold() {
+        let clock = FakeClock::new();
+        let mut timer = OperationTimer::with_clock(Some(DEFAULT_THRESHOLD), clock.clone());
+
+        run(&mut timer, &clock, &[("open", 300), ("reload", 700)]);
+
+        assert_eq!(timer.total(), Duration::from_millis(1000));
+        assert_eq!(timer.slow_sequence_hint(), None);
+    }
+
+    #[test]
+    fn t_hint_names_the_slowest_step() {
+        let clock = FakeClock::new();
+        let mut timer = OperationTimer::with_clock(Some(DEFAULT_THRESHOLD), clock.clone());
+
+        run(
+            &mut timer,
+            &clock,
+            &[("next-unread", 20), ("open-in-browser", 1200), ("quit", 5)],
+        );
+
+        assert_eq!(
+            timer.slowest_step(),
+            Some(("open-in-browser 1200ms", Duration::from_millis(1200)))
+        );
+        assert_eq!(
+            timer.slow_sequence_hint(),
+            Some(
+                "Operations took 1.2 s; the slowest was `open-in-browser 1200ms' (1.2 s)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn t_many_fast_steps_can_exceed_the_threshold_together() {
+        let clock = FakeClock::new();
+        let mut timer = OperationTimer::with_clock(Some(Duration::from_millis(100)), clock.clone());
+
+        run(
+            &mut timer,
+            &clock,
+            &[("down", 30), ("down", 40), ("toggle-article-read", 35)],
+        );
+
+        assert_eq!(
+            timer.slowest_step().map(|(_, elapsed)| elapsed),
+            Some(Duration::from_millis(40))
+        );
+        assert!(timer.slow_sequence_hint().is_some());
+    }
+
+    #[test]
+    fn t_disabled_timer_measures_nothing() {
+        let clock = FakeClock::new();
+        let mut timer = OperationTimer::with_clock(None, clock.clone());
+
+        run(&mut timer, &clock, &[("open-in-browser", 5000)]);
+
+        assert!(!timer.is_enabled());
+        assert_eq!(timer.total(), Duration::default());
+        assert_eq!(timer.slowest_step(), None);
+        assert_eq!(timer.slow_sequence_hint(), None);
+    }
+
+    #[test]
+    fn t_finishing_a_step_that_was_not_started_does_nothing() {
+        let clock = FakeClock::new();
+        let mut timer = OperationTimer::with_clock(Some(DEFAULT_THRESHOLD), clock);
+
+        timer.finish_step("open", &[]);
+
+        assert_eq!(timer.slowest_step(), None);
+    }
+
+    #[test]
+    fn t_scope_measure_without_threshold_always_reports() {
+        let clock = FakeClock::new();
+        let measure = ScopeMeasure::with_clock("Matcher::parse".to_string(), clock.clone());
+
+        assert_eq!(
+            measure.message(None),
+            Some("ScopeMeasure: function `Matcher::parse' took 0.000000 s".to_string())
+        );
+
+        clock.advance(1500);
+        assert_eq!(
+            measure.message(Some("parsed")),
+            Some(
+                "ScopeMeasure: function `Matcher::parse' (stop over `parsed') took 1.500000 s so far"
+                    .to_string()
+            )
+        );
+        assert_eq!(measure.level, Level::Debug);
+    }
+
+    #[test]
+    fn t_scope_measure_with_threshold_only_reports_slow_runs() {
+        let clock = FakeClock::new();
+        let measure = ScopeMeasure::with_clock("Matcher::matches".to_string(), clock.clone())
+            .threshold(Duration::from_millis(50))
+            .level(Level::Info);
+        assert_eq!(measure.level, Level::Info);
+
+        clock.advance(49);
+        assert_eq!(measure.message(None), None);
+        assert_eq!(measure.message(Some("matched")), None);
+
+        clock.advance(263);
+        assert_eq!(
+            measure.message(None),
+            Some(
+                "ScopeMeasure: function `Matcher::matches' took 312ms, threshold 50ms".to_string()
+            )
+        );
+        assert_eq!(
+            measure.message(Some("matched")),
+            Some(
+                "ScopeMeasure: function `Matcher::matches' (stop over `matched') took 312ms so far, threshold 50ms"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn t_long_arguments_are_shortened_and_quoted() {
+        let url = format!("https://example.com/?token={}", "x".repeat(50));
+
+        assert_eq!(
+            describe("open-in-browser", &[url]),
+            r#"open-in-browser https://example.com/?token=xxxxxxxxxxxxx..."#
+        );
+        assert_eq!(
+            describe("set", &["browser".to_string(), "lynx %u".to_string()]),
+            r#"set browser "lynx %u""#
+        );
     }
 }
@@ -0,0 +1,329 @@
+//! Articles that are selected for a batch operation.
+//!
+//! `toggle-select` adds the article under the cursor to the selection or removes it, and
+//! `select-range` anchors a range at the cursor that follows the cursor until `select-range` is
+//! pressed again. Operations like `toggle-article-read` then apply to the whole selection.
+//!
+//! Articles are remembered by their key (the GUID) rather than by their position, so that the
+//! selection stays the same when the list is re-sorted or reloaded. Positions are only looked up
+//! in the current order of the list, which `set_order()` gives.
+
+use std::collections::{HashMap, HashSet};
+
+/// What's shown in front of an article while there is a selection.
+pub const SELECTED_MARKER: &str = "* ";
+/// What's shown in front of the other articles while there is a selection.
+pub const UNSELECTED_MARKER: &str = "  ";
+
+/// What `select_range()` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeEnd {
+    /// The range is anchored at the cursor, and follows it from now on.
+    Anchored,
+    /// The range was added to the selection, which now has this many articles.
+    Selected(usize),
+}
+
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// Keys of the articles in the list, in the order in which they're shown.
+    order: Vec<String>,
+    positions: HashMap<String, usize>,
+    /// Articles that were selected one by one, or with a range that was finished.
+    selected: HashSet<String>,
+    /// Where the range that follows the cursor starts.
+    anchor: Option<String>,
+    /// Whether the selection changed since it was last drawn.
+    changed: bool,
+    /// The cursor position the range was last drawn for.
+    drawn_cursor: Option<usize>,
+}
+
+impl Selection {
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    /// Sets the keys of the articles in the list, e.g. after it was sorted or filtered. Selected
+    /// articles that are no longer shown stay selected, but aren't included in
+    /// `selected_positions()` until they are shown again.
+    pub fn set_order(&mut self, keys: Vec<String>) {
+        self.positions = keys
+            .iter()
+            .enumerate()
+            .map(|(position, key)| (key.clone(), position))
+            .collect();
+        self.order = keys;
+        self.changed = true;
+    }
+
+    /// Selects the article at `cursor`, or unselects it if it's selected. Returns whether it's
+    /// selected now.
+    pub fn toggle(&mut self, cursor: usize) -> bool {
+        let key = match self.order.get(cursor) {
+            Some(key) => key.clone(),
+            None => return false,
+        };
+        self.changed = true;
+        if self.selected.remove(&key) {
+            false
+        } else {
+            self.selected.insert(key);
+            true
+        }
+    }
+
+    /// Anchors a range at `cursor`, or, if one is anchored already, adds the articles from the
+    /// anchor to `cursor` to the selection.
+    pub fn select_range(&mut self, cursor: usize) -> RangeEnd {
+        if cursor >= self.order.len() {
+            return RangeEnd::Selected(self.selected_positions(cursor).len());
+        }
+        self.changed = true;
+        match self.range(cursor) {
+            Some((first, last)) => {
+                let keys = self.order[first..=last].iter().cloned();
+                self.selected.extend(keys);
+                self.anchor = None;
+                RangeEnd::Selected(self.selected_positions(cursor).len())
+            }
+            None => {
+                self.anchor = Some(self.order[cursor].clone());
+                RangeEnd::Anchored
+            }
+        }
+    }
+
+    /// Whether a range is anchored and follows the cursor.
+    pub fn is_anchored(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    /// The first and the last position of the range that follows the cursor, if one is
+    /// anchored at an article that's shown.
+    fn range(&self, cursor: usize) -> Option<(usize, usize)> {
+        let anchor = *self.positions.get(self.anchor.as_ref()?)?;
+        let cursor = cursor.min(self.order.len().checked_sub(1)?);
+        Some((anchor.min(cursor), anchor.max(cursor)))
+    }
+
+    /// Whether the article at `position` is selected, with the cursor at `cursor`.
+    pub fn is_selected(&self, position: usize, cursor: usize) -> bool {
+        if let Some((first, last)) = self.range(cursor) {
+            if position >= first && position <= last {
+                return true;
+            }
+        }
+        match self.order.get(position) {
+            Some(key) => self.selected.contains(key),
+            None => false,
+        }
+    }
+
+    /// Whether there is anything to apply an operation to.
+    pub fn is_active(&self) -> bool {
+        self.anchor.is_some()
+            || self
+                .selected
+                .iter()
+                .any(|key| self.positions.contains_key(key))
+    }
+
+    /// Positions of the selected articles that are shown, in order.
+    pub fn selected_positions(&self, cursor: usize) -> Vec<usize> {
+        (0..self.order.len())
+            .filter(|&position| self.is_selected(position, cursor))
+            .collect()
+    }
+
+    /// The marker to show in front of the article at `position`: nothing if there is no
+    /// selection, so that the list looks like it always did, and a marker column otherwise.
+    pub fn marker(&self, position: usize, cursor: usize) -> &'static str {
+        if !self.is_active() {
+            ""
+        } else if self.is_selected(position, cursor) {
+            SELECTED_MARKER
+        } else {
+            UNSELECTED_MARKER
+        }
+    }
+
+    /// Whether the markers have to be drawn again, with the cursor at `cursor`: the selection
+    /// changed, or it has a range that moved along with the cursor. Each change is reported once.
+    pub fn needs_redraw(&mut self, cursor: usize) -> bool {
+        let cursor = if self.is_anchored() {
+            Some(cursor)
+        } else {
+            None
+        };
+        let needed = self.changed || cursor != self.drawn_cursor;
+        self.changed = false;
+        self.drawn_cursor = cursor;
+        needed
+    }
+
+    /// Unselects everything, e.g. after an operation was applied to the selection.
+    pub fn clear(&mut self) {
+        if self.anchor.is_some() || !self.selected.is_empty() {
+            self.changed = true;
+        }
+        self.selected.clear();
+        self.anchor = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    fn selected_keys(selection: &Selection, cursor: usize) -> Vec<String> {
+        selection
+            .selected_positions(cursor)
+            .into_iter()
+            .map(|position| selection.order[position].clone())
+            .collect()
+    }
+
+    fn selection() -> Selection {
+        let mut selection = Selection::new();
+        selection.set_order(keys(&["a", "b", "c", "d", "e", "f"]));
+        selection
+    }
+
+    #[test]
+    fn t_toggle_selects_and_unselects_the_article_under_the_cursor() {
+        let mut selection = selection();
+        assert!(!selection.is_active());
+
+        assert!(selection.toggle(1));
+        assert!(selection.toggle(4));
+        assert!(selection.is_active());
+        assert_eq!(selection.selected_positions(0), vec![1, 4]);
+
+        assert!(!selection.toggle(1));
+        assert_eq!(selection.selected_positions(0), vec![4]);
+
+        // There's nothing to select past the end of the list
+        assert!(!selection.toggle(6));
+    }
+
+    #[test]
+    fn t_range_follows_the_cursor_until_it_is_finished() {
+        let mut selection = selection();
+        assert_eq!(selection.select_range(2), RangeEnd::Anchored);
+        assert!(selection.is_anchored());
+        assert!(selection.is_active());
+        assert_eq!(selection.selected_positions(2), vec![2]);
+        assert_eq!(selection.selected_positions(4), vec![2, 3, 4]);
+        // Upwards, too
+        assert_eq!(selection.selected_positions(0), vec![0, 1, 2]);
+
+        assert_eq!(selection.select_range(3), RangeEnd::Selected(2));
+        assert!(!selection.is_anchored());
+        // The range doesn't move with the cursor any more
+        assert_eq!(selection.selected_positions(5), vec![2, 3]);
+    }
+
+    #[test]
+    fn t_range_is_added_to_the_articles_selected_one_by_one() {
+        let mut selection = selection();
+        selection.toggle(0);
+        selection.select_range(3);
+        assert_eq!(selection.selected_positions(4), vec![0, 3, 4]);
+        assert_eq!(selection.select_range(4), RangeEnd::Selected(3));
+    }
+
+    #[test]
+    fn t_range_anchor_stays_with_its_article_across_a_resort() {
+        let mut selection = selection();
+        selection.toggle(0);
+        selection.select_range(2);
+
+        // Reversed: "c" is at position 3 now, and "a" at 5
+        selection.set_order(keys(&["f", "e", "d", "c", "b", "a"]));
+        assert_eq!(selected_keys(&selection, 1), keys(&["e", "d", "c", "a"]));
+
+        assert_eq!(selection.select_range(4), RangeEnd::Selected(3));
+        assert_eq!(selected_keys(&selection, 0), keys(&["c", "b", "a"]));
+    }
+
+    #[test]
+    fn t_hidden_articles_stay_selected_but_are_not_applied_to() {
+        let mut selection = selection();
+        selection.toggle(1);
+        selection.toggle(3);
+
+        // A filter hides "b"
+        selection.set_order(keys(&["a", "c", "d"]));
+        assert_eq!(selected_keys(&selection, 0), keys(&["d"]));
+
+        selection.set_order(keys(&["a", "c"]));
+        assert!(!selection.is_active());
+        assert_eq!(selection.marker(0, 0), "");
+
+        selection.set_order(keys(&["a", "b", "c", "d"]));
+        assert_eq!(selected_keys(&selection, 0), keys(&["b", "d"]));
+    }
+
+    #[test]
+    fn t_markers_are_only_shown_while_there_is_a_selection() {
+        let mut selection = selection();
+        assert_eq!(selection.marker(0, 0), "");
+
+        selection.toggle(1);
+        assert_eq!(selection.marker(0, 0), UNSELECTED_MARKER);
+        assert_eq!(selection.marker(1, 0), SELECTED_MARKER);
+
+        selection.select_range(3);
+        assert_eq!(selection.marker(4, 5), SELECTED_MARKER);
+        assert_eq!(selection.marker(4, 3), UNSELECTED_MARKER);
+        // The markers line up
+        assert_eq!(SELECTED_MARKER.len(), UNSELECTED_MARKER.len());
+    }
+
+    #[test]
+    fn t_clear_unselects_everything() {
+        let mut selection = selection();
+        selection.toggle(0);
+        selection.select_range(2);
+        selection.clear();
+        assert!(!selection.is_active());
+        assert!(!selection.is_anchored());
+        assert!(selection.selected_positions(5).is_empty());
+
+        // The order is kept, so selecting works right away
+        assert!(selection.toggle(5));
+        assert_eq!(selected_keys(&selection, 0), keys(&["f"]));
+    }
+
+    #[test]
+    fn t_needs_redraw_reports_changes_and_moves_of_the_range() {
+        let mut selection = selection();
+        assert!(selection.needs_redraw(0));
+        assert!(!selection.needs_redraw(0));
+
+        // Without a range, moving the cursor changes nothing
+        selection.toggle(1);
+        assert!(selection.needs_redraw(0));
+        assert!(!selection.needs_redraw(3));
+
+        selection.select_range(3);
+        assert!(selection.needs_redraw(3));
+        assert!(!selection.needs_redraw(3));
+        assert!(selection.needs_redraw(4));
+
+        selection.select_range(4);
+        assert!(selection.needs_redraw(4));
+        assert!(!selection.needs_redraw(5));
+
+        selection.clear();
+        assert!(selection.needs_redraw(5));
+        // Clearing an empty selection doesn't
+        selection.clear();
+        assert!(!selection.needs_redraw(5));
+    }
+}
@@ -0,0 +1,438 @@
+//! Builds STFL form descriptions out of typed widgets.
+//!
+//! Writing STFL by hand invites quoting bugs: a translated string or a piece of user data that
+//! contains a quote or a colon can end the value early and corrupt the rest of the form. Here,
+//! every value is quoted when the form is turned into text, so callers never deal with STFL
+//! syntax at all.
+//!
+//! The forms of the dialogs whose contents depend on runtime data are also defined here.
+
+use std::fmt::Write;
+
+/// Kinds of STFL widgets that Newsboat uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetKind {
+    VBox,
+    HBox,
+    Label,
+    List,
+    ListItem,
+    Textview,
+    Input,
+}
+
+impl WidgetKind {
+    fn name(self) -> &'static str {
+        match self {
+            WidgetKind::VBox => "vbox",
+            WidgetKind::HBox => "hbox",
+            WidgetKind::Label => "label",
+            WidgetKind::List => "list",
+            WidgetKind::ListItem => "listitem",
+            WidgetKind::Textview => "textview",
+            WidgetKind::Input => "input",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Attribute {
+    key: &'static str,
+    variable: Option<&'static str>,
+    value: String,
+}
+
+/// A widget with its attributes and children.
+///
+/// Keys, names and classes are part of the form's structure, so they're `&'static str`; only
+/// values can come from translations or from the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Widget {
+    kind: WidgetKind,
+    name: Option<&'static str>,
+    class: Option<&'static str>,
+    focused: bool,
+    attributes: Vec<Attribute>,
+    children: Vec<Widget>,
+}
+
+impl Widget {
+    pub fn new(kind: WidgetKind) -> Widget {
+        Widget {
+            kind,
+            name: None,
+            class: None,
+            focused: false,
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn vbox() -> Widget {
+        Widget::new(WidgetKind::VBox)
+    }
+
+    pub fn hbox() -> Widget {
+        Widget::new(WidgetKind::HBox)
+    }
+
+    pub fn label() -> Widget {
+        Widget::new(WidgetKind::Label)
+    }
+
+    pub fn list() -> Widget {
+        Widget::new(WidgetKind::List)
+    }
+
+    pub fn textview() -> Widget {
+        Widget::new(WidgetKind::Textview)
+    }
+
+    pub fn input() -> Widget {
+        Widget::new(WidgetKind::Input)
+    }
+
+    /// Name by which the widget can be looked up, e.g. in `modify`.
+    pub fn named(mut self, name: &'static str) -> Widget {
+        self.name = Some(name);
+        self
+    }
+
+    /// Class of the widget, to which `@class#style` attributes of its parents apply.
+    pub fn class(mut self, class: &'static str) -> Widget {
+        self.class = Some(class);
+        self
+    }
+
+    /// Makes the widget get the focus when the form is shown.
+    pub fn focused(mut self) -> Widget {
+        self.focused = true;
+        self
+    }
+
+    /// Sets an attribute (`.expand`, `@style_normal` and such are attributes, too).
+    pub fn attr<S: Into<String>>(mut self, key: &'static str, value: S) -> Widget {
+        self.attributes.push(Attribute {
+            key,
+            variable: None,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Sets an attribute whose value can later be read and changed through `variable`.
+    pub fn var<S: Into<String>>(
+        mut self,
+        key: &'static str,
+        variable: &'static str,
+        value: S,
+    ) -> Widget {
+        self.attributes.push(Attribute {
+            key,
+            variable: Some(variable),
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn child(mut self, child: Widget) -> Widget {
+        self.children.push(child);
+        self
+    }
+
+    /// The widget and its children in STFL syntax.
+    pub fn to_stfl(&self) -> String {
+        let mut result = String::new();
+        self.write_stfl(&mut result);
+        result
+    }
+
+    fn write_stfl(&self, output: &mut String) {
+        output.push('{');
+        if self.focused {
+            output.push('!');
+        }
+        output.push_str(self.kind.name());
+        if let Some(class) = self.class {
+            output.push('#');
+            output.push_str(class);
+        }
+        if let Some(name) = self.name {
+            // Writing into a String can't fail
+            let _ = write!(output, "[{}]", name);
+        }
+        for attribute in &self.attributes {
+            output.push(' ');
+            output.push_str(attribute.key);
+            if let Some(variable) = attribute.variable {
+                let _ = write!(output, "[{}]", variable);
+            }
+            output.push(':');
+            output.push_str(&quote(&attribute.value));
+        }
+        for child in &self.children {
+            output.push(' ');
+            child.write_stfl(output);
+        }
+        output.push('}');
+    }
+}
+
+/// Quotes a value the way `stfl_quote` does.
+///
+/// STFL has no escape sequences, but it concatenates adjacent quoted strings, so the text is
+/// split into pieces that are alternately quoted with `"` and `'`, each piece ending right before
+/// the next occurrence of its own quote character.
+pub fn quote(text: &str) -> String {
+    if text.is_empty() {
+        return r#""""#.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len() + 2);
+    let mut quote_char = '"';
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest.find(quote_char).unwrap_or(rest.len());
+        result.push(quote_char);
+        result.push_str(&rest[..end]);
+        result.push(quote_char);
+        rest = &rest[end..];
+        quote_char = if quote_char == '"' { '\'' } else { '"' };
+    }
+    result
+}
+
+/// Attributes shared by all the dialogs: the styles and the key bindings that the list or textview
+/// uses for navigation.
+fn dialog() -> Widget {
+    Widget::vbox()
+        .var("@style_normal", "background", "")
+        .var("@info#style_normal", "info", "bg=blue,fg=yellow,attr=bold")
+        .var("@bind_up", "bind_up", "")
+        .var("@bind_down", "bind_down", "")
+        .var("@bind_page_up", "bind_page_up", "")
+        .var("@bind_page_down", "bind_page_down", "")
+        .var("@bind_home", "bind_home", "")
+        .var("@bind_end", "bind_end", "")
+        .attr("@on_TAB", "TAB")
+}
+
+fn title_label(title: &str) -> Widget {
+    Widget::label()
+        .class("info")
+        .named("title")
+        .var("text", "head", title)
+        .attr(".expand", "h")
+        .var(".display", "showtitle", "1")
+}
+
+fn hints_label(hints: &str) -> Widget {
+    Widget::label()
+        .class("info")
+        .var("text", "help", hints)
+        .attr(".expand", "h")
+}
+
+/// The status line, which also hosts the commandline and other prompts.
+fn last_line() -> Widget {
+    Widget::hbox().named("lastline").attr(".expand", "0").child(
+        Widget::label()
+            .named("msglabel")
+            .var("text", "msg", "")
+            .attr(".expand", "h"),
+    )
+}
+
+/// The help dialog, with the given `title` and keymap `hints`.
+pub fn help_form(title: &str, hints: &str) -> String {
+    dialog()
+        .child(title_label(title))
+        .child(
+            Widget::textview()
+                .named("helptext")
+                .attr("richtext", "1")
+                .var("style_hl_normal", "highlight", "")
+                .var("style_normal", "article", "")
+                .var("style_end", "end-of-text-marker", "fg=blue,attr=bold")
+                .attr(".expand", "vh")
+                .var("offset", "helptext_offset", "0"),
+        )
+        .child(
+            Widget::vbox()
+                .named("hints")
+                .attr(".expand", "0")
+                .var(".display", "showhint", "1")
+                .child(hints_label(hints)),
+        )
+        .child(last_line())
+        .to_stfl()
+}
+
+/// The URL view, with the given `title` and keymap `hints`.
+pub fn urlview_form(title: &str, hints: &str) -> String {
+    dialog()
+        .child(title_label(title))
+        .child(
+            Widget::list()
+                .named("urls")
+                .focused()
+                .var("style_normal", "listnormal", "")
+                .var("style_focus", "listfocus", "fg=yellow,bg=blue,attr=bold")
+                .attr(".expand", "vh")
+                .attr("richtext", "1")
+                .var("pos", "urls_pos", "0")
+                .var("offset", "urls_offset", "0"),
+        )
+        .child(
+            Widget::vbox()
+                .named("hints")
+                .attr(".expand", "0")
+                .var(".display", "showhint", "1")
+                .child(hints_label(hints)),
+        )
+        .child(last_line())
+        .to_stfl()
+}
+
+/// The file and directory browsers, with the given `title`, filename `prompt` and keymap `hints`.
+pub fn filebrowser_form(title: &str, prompt: &str, hints: &str) -> String {
+    dialog()
+        .child(title_label(title))
+        .child(
+            Widget::list()
+                .named("files")
+                // The height is only a hack to make the list expand correctly
+                .attr(".expand", "vh")
+                .attr(".height", "65535")
+                .attr("richtext", "1")
+                .var("style_normal", "listnormal", "")
+                .var("style_focus", "listfocus", "fg=yellow,bg=blue,attr=bold")
+                .var("pos", "files_pos", "0")
+                .var("offset", "files_offset", "0"),
+        )
+        .child(
+            Widget::hbox()
+                .named("hints")
+                .attr(".expand", "0")
+                .attr(".height", "1")
+                .child(
+                    Widget::label()
+                        .attr(".expand", "0")
+                        .var("text", "fileprompt", prompt),
+                )
+                .child(
+                    Widget::input()
+                        .named("filename")
+                        .focused()
+                        .attr(".expand", "h")
+                        .var("text", "filenametext", "")
+                        .var("pos", "filenametext_pos", "0"),
+                ),
+        )
+        .child(
+            Widget::vbox()
+                .attr(".expand", "0")
+                .var(".display", "showhint", "1")
+                .child(hints_label(hints).attr(".height", "1")),
+        )
+        .child(last_line())
+        .to_stfl()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads back a value written by `quote`: a run of quoted strings.
+    fn unquote(input: &str) -> (String, &str) {
+        let mut value = String::new();
+        let mut rest = input;
+        while let Some(quote_char) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let end = rest[1..].find(quote_char).expect("unterminated quote") + 1;
+            value.push_str(&rest[1..end]);
+            rest = &rest[end + 1..];
+        }
+        (value, rest)
+    }
+
+    /// Finds the value of the attribute that starts with `prefix` (e.g. `text[head]:`).
+    fn attribute_value(form: &str, prefix: &str) -> String {
+        let start = form.find(prefix).expect("no such attribute") + prefix.len();
+        let (value, rest) = unquote(&form[start..]);
+        // The value has to be followed by the next token, or by the end of the widget
+        assert!(rest.starts_with(' ') || rest.starts_with('}'));
+        value
+    }
+
+    #[test]
+    fn t_quote_wraps_text_in_double_quotes() {
+        assert_eq!(quote(""), r#""""#);
+        assert_eq!(quote("q:Quit"), r#""q:Quit""#);
+        assert_eq!(quote("it's {here}"), r#""it's {here}""#);
+    }
+
+    #[test]
+    fn t_quote_switches_quote_characters_around_quotes() {
+        assert_eq!(quote(r#"say "hi""#), r#""say "'"hi"'"#);
+        assert_eq!(quote(r#"""#), r#"""'"'"#);
+        for text in &[r#"say "hi""#, r#"""#, r#"'"'"#, r#"a"b'c"d"#, r#""'""#] {
+            assert_eq!(unquote(&quote(text)), (text.to_string(), ""));
+        }
+    }
+
+    #[test]
+    fn t_widgets_are_written_with_name_class_and_attributes() {
+        let widget = Widget::vbox()
+            .named("outer")
+            .attr(".expand", "h")
+            .child(
+                Widget::label()
+                    .class("info")
+                    .focused()
+                    .var("text", "msg", "Hi"),
+            )
+            .child(Widget::list());
+        assert_eq!(
+            widget.to_stfl(),
+            r#"{vbox[outer] .expand:"h" {!label#info text[msg]:"Hi"} {list}}"#
+        );
+    }
+
+    #[test]
+    fn t_help_form_matches_golden_file() {
+        assert_eq!(
+            help_form("Help", "q:Quit"),
+            include_str!("../tests/data/stfl/help.stfl").trim_end()
+        );
+    }
+
+    #[test]
+    fn t_urlview_form_matches_golden_file() {
+        assert_eq!(
+            urlview_form("URLs", "q:Quit ENTER:Open Feed"),
+            include_str!("../tests/data/stfl/urlview.stfl").trim_end()
+        );
+    }
+
+    #[test]
+    fn t_filebrowser_form_matches_golden_file() {
+        assert_eq!(
+            filebrowser_form("File Browser", "File: ", "q:Abort"),
+            include_str!("../tests/data/stfl/filebrowser.stfl").trim_end()
+        );
+    }
+
+    #[test]
+    fn t_translated_labels_with_quotes_do_not_break_the_form() {
+        let title = r#"Hilfe: "Tasten" } {label text:"x"#;
+        let prompt = r#"Datei "'": "#;
+        let form = filebrowser_form(title, prompt, "q:Abort");
+        assert_eq!(attribute_value(&form, "text[head]:"), title);
+        assert_eq!(attribute_value(&form, "text[fileprompt]:"), prompt);
+        assert_eq!(attribute_value(&form, "text[help]:"), "q:Abort");
+
+        let form = help_form(title, r#"q:"Quit""#);
+        assert_eq!(attribute_value(&form, "text[head]:"), title);
+        assert_eq!(attribute_value(&form, "text[help]:"), r#"q:"Quit""#);
+    }
+}
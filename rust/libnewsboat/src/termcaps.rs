@@ -0,0 +1,437 @@
+//! Rendering decisions for terminals that lack colors or space.
+//!
+//! Newsboat is sometimes run on serial consoles and other terminals that can show just a few
+//! colors, or none at all, and only have room for a handful of lines. Instead of drawing styles
+//! that such a terminal can't express, configured colors are mapped down to what it supports, and
+//! optional parts of the UI are dropped when there's no room for them.
+//!
+//! Everything here is a pure function of its arguments; detecting the capabilities of the actual
+//! terminal is left to the caller.
+
+use gettextrs::gettext;
+
+/// How many colors a terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    NoColors,
+    Colors8,
+    Colors256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Decides on a support level from the number of colors terminfo reports for the terminal
+    /// (negative if it doesn't know), and the value of the `COLORTERM` environment variable.
+    pub fn detect(colors: i32, colorterm: Option<&str>) -> ColorSupport {
+        if let Some("truecolor") | Some("24bit") = colorterm {
+            return ColorSupport::TrueColor;
+        }
+        match colors {
+            c if c >= 0x100_0000 => ColorSupport::TrueColor,
+            c if c >= 256 => ColorSupport::Colors256,
+            c if c >= 8 => ColorSupport::Colors8,
+            _ => ColorSupport::NoColors,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> ColorSupport {
+        match value {
+            0 => ColorSupport::NoColors,
+            1 => ColorSupport::Colors8,
+            2 => ColorSupport::Colors256,
+            _ => ColorSupport::TrueColor,
+        }
+    }
+}
+
+/// Names of the eight basic colors, in the order of their ANSI numbers.
+pub const BASIC_COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// RGB values that xterm uses for the first sixteen colors of the 256-color palette: the basic
+/// colors and their bright variants.
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Intensities of each component in the 6×6×6 color cube (colors 16 to 231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_RGB[usize::from(index)],
+        16..=231 => {
+            let index = usize::from(index - 16);
+            (
+                CUBE_LEVELS[index / 36],
+                CUBE_LEVELS[(index / 6) % 6],
+                CUBE_LEVELS[index % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Index of the color among the first sixteen that is closest to `rgb`.
+fn nearest_ansi_color(rgb: (u8, u8, u8)) -> usize {
+    let distance = |other: &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(rgb.0, other.0) + d(rgb.1, other.1) + d(rgb.2, other.2)
+    };
+    ANSI_RGB
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+/// Index of the palette color that is closest to `rgb`. Only the color cube and the grays (16 to
+/// 255) are considered, since terminals let users change the first sixteen colors.
+fn nearest_palette_color(rgb: (u8, u8, u8)) -> u8 {
+    let distance = |other: (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(rgb.0, other.0) + d(rgb.1, other.1) + d(rgb.2, other.2)
+    };
+    (16..=255u8)
+        .min_by_key(|index| distance(palette_rgb(*index)))
+        .unwrap_or(16)
+}
+
+/// Parses a color written as `#rrggbb`, in either case.
+pub fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    if color.len() != 7 || !color.starts_with('#') {
+        return None;
+    }
+    let digits = &color[1..];
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some((component(0)?, component(2)?, component(4)?))
+}
+
+/// A color as the terminal can show it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedColor {
+    /// Name of the color to use, or `None` to leave the default color.
+    pub name: Option<String>,
+
+    /// Whether the original color is a bright one, which can be approximated with the bold
+    /// attribute on terminals that only have eight colors.
+    pub bright: bool,
+}
+
+/// Maps a color from the config (a name like "red", "color0" to "color255", or "#rrggbb") to one
+/// that a terminal with the given `support` can show.
+///
+/// On 8-color terminals, palette and RGB colors are replaced with the nearest of the sixteen ANSI
+/// ones; bright ones turn into their basic counterparts, and `bright` is set. Terminals with 256
+/// colors or more get the color unchanged, except that RGB colors become the nearest palette
+/// color, because STFL can't pass RGB colors on to the terminal.
+pub fn map_color(color: &str, support: ColorSupport) -> MappedColor {
+    let unchanged = MappedColor {
+        name: Some(color.to_string()),
+        bright: false,
+    };
+    if color == "default" {
+        return MappedColor {
+            name: None,
+            bright: false,
+        };
+    }
+    match support {
+        ColorSupport::NoColors => MappedColor {
+            name: None,
+            bright: false,
+        },
+        ColorSupport::Colors256 | ColorSupport::TrueColor => match parse_hex_color(color) {
+            Some(rgb) => MappedColor {
+                name: Some(format!("color{}", nearest_palette_color(rgb))),
+                bright: false,
+            },
+            None => unchanged,
+        },
+        ColorSupport::Colors8 => {
+            let rgb = if color.starts_with("color") {
+                color[5..].parse::<u8>().ok().map(palette_rgb)
+            } else {
+                parse_hex_color(color)
+            };
+            match rgb {
+                Some(rgb) => {
+                    let nearest = nearest_ansi_color(rgb);
+                    MappedColor {
+                        name: Some(BASIC_COLORS[nearest % 8].to_string()),
+                        bright: nearest >= 8,
+                    }
+                }
+                None => unchanged,
+            }
+        }
+    }
+}
+
+/// Rewrites an STFL style (e.g. "fg=color208,bg=black,attr=underline") so that it only uses
+/// colors that a terminal with the given `support` can show. A bright foreground on an 8-color
+/// terminal is rendered bold instead; a bright background is simply dropped to its basic variant.
+pub fn degrade_style(style: &str, support: ColorSupport) -> String {
+    let mut parts = Vec::new();
+    let mut needs_bold = false;
+    for part in style.split(',').filter(|part| !part.is_empty()) {
+        let mut key_value = part.splitn(2, '=');
+        let key = key_value.next().unwrap_or("");
+        let value = key_value.next().unwrap_or("");
+        match key {
+            "fg" | "bg" => {
+                let mapped = map_color(value, support);
+                if let Some(name) = mapped.name {
+                    parts.push(format!("{}={}", key, name));
+                    needs_bold |= key == "fg" && mapped.bright;
+                }
+            }
+            _ => parts.push(part.to_string()),
+        }
+    }
+    if needs_bold && !parts.iter().any(|part| part == "attr=bold") {
+        parts.push("attr=bold".to_string());
+    }
+    parts.join(",")
+}
+
+/// Titles narrower than this use a short template instead of the configured format.
+pub const SHORT_TITLE_WIDTH: u32 = 50;
+
+/// Templates for narrow titles, keyed by the name of the option holding the usual format. The
+/// templates are translated before use.
+const SHORT_TITLE_FORMATS: [(&str, &str); 4] = [
+    ("articlelist-title-format", "%T (%u/%t)"),
+    ("feedlist-title-format", "%?T?%T&Feeds? (%u/%t)"),
+    ("itemview-title-format", "%T"),
+    ("searchresult-title-format", "Search results (%u/%t)"),
+];
+
+/// Picks the format for a title that's `width` columns wide: the `configured` one, unless the
+/// title is too narrow to fit it and `option` has a short template.
+///
+/// A width of zero means the title wasn't drawn yet, so the configured format is used.
+pub fn title_format(option: &str, configured: &str, width: u32) -> String {
+    if width == 0 || width >= SHORT_TITLE_WIDTH {
+        return configured.to_string();
+    }
+    SHORT_TITLE_FORMATS
+        .iter()
+        .find(|(name, _)| *name == option)
+        .map(|(_, short)| gettext(*short))
+        .unwrap_or_else(|| configured.to_string())
+}
+
+/// Narrowest article list or preview pane that's still useful, in columns.
+pub const MIN_PANE_WIDTH: u32 = 30;
+
+/// Lowest article list or preview pane that's still useful, in lines.
+pub const MIN_PANE_HEIGHT: u32 = 5;
+
+/// Whether the article list and the preview pane both fit into an area of the given size, if the
+/// preview takes `percent` of it and is placed `beside` the list (or below it).
+pub fn preview_pane_fits(total_width: u32, total_height: u32, percent: u32, beside: bool) -> bool {
+    let (total, minimum, other_side) = if beside {
+        (total_width, MIN_PANE_WIDTH, total_height)
+    } else {
+        (total_height, MIN_PANE_HEIGHT, total_width)
+    };
+    let preview = total * percent / 100;
+    let list = total - preview;
+    let other_minimum = if beside {
+        MIN_PANE_HEIGHT
+    } else {
+        MIN_PANE_WIDTH
+    };
+    preview >= minimum && list >= minimum && other_side >= other_minimum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_detect_prefers_colorterm_and_falls_back_to_terminfo() {
+        assert_eq!(ColorSupport::detect(-1, None), ColorSupport::NoColors);
+        assert_eq!(ColorSupport::detect(0, None), ColorSupport::NoColors);
+        assert_eq!(ColorSupport::detect(8, None), ColorSupport::Colors8);
+        assert_eq!(ColorSupport::detect(16, None), ColorSupport::Colors8);
+        assert_eq!(ColorSupport::detect(256, None), ColorSupport::Colors256);
+        assert_eq!(
+            ColorSupport::detect(0x100_0000, None),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::detect(8, Some("truecolor")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::detect(256, Some("24bit")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::detect(256, Some("yes")),
+            ColorSupport::Colors256
+        );
+    }
+
+    fn mapped(name: &str, bright: bool) -> MappedColor {
+        MappedColor {
+            name: Some(name.to_string()),
+            bright,
+        }
+    }
+
+    #[test]
+    fn t_map_color_to_8_colors() {
+        let table = [
+            ("red", mapped("red", false)),
+            ("white", mapped("white", false)),
+            ("color0", mapped("black", false)),
+            ("color4", mapped("blue", false)),
+            ("color9", mapped("red", true)),
+            ("color15", mapped("white", true)),
+            ("color16", mapped("black", false)),
+            ("color196", mapped("red", true)),
+            ("color28", mapped("green", false)),
+            ("color208", mapped("yellow", false)),
+            ("color231", mapped("white", true)),
+            ("color232", mapped("black", false)),
+            ("color244", mapped("black", true)),
+            ("color250", mapped("white", false)),
+            ("color255", mapped("white", false)),
+        ];
+        for (color, expected) in &table {
+            assert_eq!(
+                &map_color(color, ColorSupport::Colors8),
+                expected,
+                "{}",
+                color
+            );
+        }
+    }
+
+    #[test]
+    fn t_map_color_drops_all_colors_without_color_support() {
+        for color in &["red", "color3", "color200", "default"] {
+            assert_eq!(map_color(color, ColorSupport::NoColors).name, None);
+        }
+    }
+
+    #[test]
+    fn t_map_color_passes_colors_through_on_256_colors_and_truecolor() {
+        for support in &[ColorSupport::Colors256, ColorSupport::TrueColor] {
+            for color in &["red", "color9", "color200", "color255"] {
+                assert_eq!(&map_color(color, *support), &mapped(color, false));
+            }
+            assert_eq!(map_color("default", *support).name, None);
+        }
+    }
+
+    #[test]
+    fn t_hex_colors_are_mapped_to_the_nearest_supported_color() {
+        assert_eq!(parse_hex_color("#FF8000"), Some((255, 128, 0)));
+        assert_eq!(parse_hex_color("#ff8000"), Some((255, 128, 0)));
+        for invalid in &["ff8000", "#ff800", "#ff80000", "#gg8000", "#+f8000"] {
+            assert_eq!(parse_hex_color(invalid), None, "{}", invalid);
+        }
+
+        let table = [
+            ("#000000", "color16", mapped("black", false)),
+            ("#ff0000", "color196", mapped("red", true)),
+            ("#875fd7", "color98", mapped("blue", true)),
+            ("#808080", "color244", mapped("black", true)),
+            ("#eeeeee", "color255", mapped("white", false)),
+        ];
+        for (color, palette, ansi) in &table {
+            for support in &[ColorSupport::Colors256, ColorSupport::TrueColor] {
+                assert_eq!(&map_color(color, *support), &mapped(palette, false));
+            }
+            assert_eq!(&map_color(color, ColorSupport::Colors8), ansi, "{}", color);
+            assert_eq!(map_color(color, ColorSupport::NoColors).name, None);
+        }
+    }
+
+    #[test]
+    fn t_degrade_style_maps_colors_and_keeps_attributes() {
+        let style = "fg=color196,bg=color21,attr=underline";
+        assert_eq!(
+            degrade_style(style, ColorSupport::TrueColor),
+            "fg=color196,bg=color21,attr=underline"
+        );
+        assert_eq!(
+            degrade_style(style, ColorSupport::Colors8),
+            "fg=red,bg=blue,attr=underline,attr=bold"
+        );
+        assert_eq!(
+            degrade_style(style, ColorSupport::NoColors),
+            "attr=underline"
+        );
+        assert_eq!(
+            degrade_style("fg=color9,attr=bold", ColorSupport::Colors8),
+            "fg=red,attr=bold"
+        );
+        assert_eq!(degrade_style("bg=color9", ColorSupport::Colors8), "bg=red");
+        assert_eq!(degrade_style("", ColorSupport::Colors8), "");
+    }
+
+    #[test]
+    fn t_title_format_falls_back_to_short_template_on_narrow_titles() {
+        let configured = "%N %V - Articles in feed '%T' (%u unread, %t total)";
+        let option = "articlelist-title-format";
+        assert_eq!(title_format(option, configured, 80), configured);
+        assert_eq!(
+            title_format(option, configured, SHORT_TITLE_WIDTH),
+            configured
+        );
+        assert_eq!(
+            title_format(option, configured, SHORT_TITLE_WIDTH - 1),
+            "%T (%u/%t)"
+        );
+        assert_eq!(title_format(option, configured, 0), configured);
+    }
+
+    #[test]
+    fn t_title_format_keeps_formats_without_short_template() {
+        let configured = "%N %V - Help";
+        assert_eq!(
+            title_format("help-title-format", configured, 20),
+            configured
+        );
+    }
+
+    #[test]
+    fn t_preview_pane_needs_room_for_both_panes() {
+        assert!(preview_pane_fits(80, 24, 50, false));
+        assert!(!preview_pane_fits(80, 9, 50, false));
+        assert!(!preview_pane_fits(80, 24, 90, false));
+        assert!(!preview_pane_fits(20, 24, 50, false));
+
+        assert!(preview_pane_fits(80, 24, 50, true));
+        assert!(!preview_pane_fits(50, 24, 50, true));
+        assert!(!preview_pane_fits(120, 3, 50, true));
+    }
+}
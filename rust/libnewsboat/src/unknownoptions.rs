@@ -0,0 +1,168 @@
+//! What to do about options that this version of newsboat doesn't know.
+//!
+//! A config that is shared between versions may set options that only the newer ones know. With
+//! `tolerate-unknown-options yes`, config files may do that: the unknown options are skipped, and
+//! listed in a single warning once the config is loaded. The toggle is picked up by a quick scan
+//! of the file before it's parsed, so it also covers the lines above it.
+//!
+//! Options set anywhere else (with `:set`, in macros, or with `-x`) are typed by hand rather than
+//! shared, so an unknown name there is most likely a typo, and always an error.
+
+use crate::utils;
+use gettextrs::gettext;
+use strprintf::fmt;
+
+/// Name of the config command that makes config files tolerate unknown options.
+pub const TOLERATE_UNKNOWN_OPTIONS: &str = "tolerate-unknown-options";
+
+/// Looks through the contents of a config file for `tolerate-unknown-options`, and returns the
+/// value of the first one, if there is any with a valid value.
+///
+/// This doesn't parse the file, so it's fooled by e.g. a `tolerate-unknown-options` that is
+/// produced by a backtick command; the parser still gets to see the line, though.
+pub fn prescan(contents: &str) -> Option<bool> {
+    contents.lines().find_map(|line| {
+        let mut tokens = utils::strip_comments(line).split_whitespace();
+        if tokens.next() != Some(TOLERATE_UNKNOWN_OPTIONS) {
+            return None;
+        }
+        match tokens.next().map(|value| value.trim_matches('"')) {
+            Some("yes") | Some("true") => Some(true),
+            Some("no") | Some("false") => Some(false),
+            _ => None,
+        }
+    })
+}
+
+/// Internationalized error about setting the unknown option `name`, suggesting entries of
+/// `known` that are close to it.
+pub fn unknown_option_message(name: &str, known: &[String]) -> String {
+    let suggestions = utils::suggest(known.iter().map(String::as_str), name);
+    if suggestions.is_empty() {
+        fmt!(&gettext("unknown configuration option `%s'"), name)
+    } else {
+        fmt!(
+            &gettext("unknown configuration option `%s'. Did you mean: %s?"),
+            name,
+            suggestions.join(", ")
+        )
+    }
+}
+
+/// Unknown options that config files were allowed to set.
+#[derive(Debug, Default)]
+pub struct UnknownOptions {
+    /// Names, in the order they were first seen, along with where that was.
+    options: Vec<(String, String)>,
+}
+
+impl UnknownOptions {
+    pub fn new() -> UnknownOptions {
+        UnknownOptions::default()
+    }
+
+    /// Records that the unknown option `name` was skipped at `location`.
+    pub fn record(&mut self, name: &str, location: &str) {
+        if self.options.iter().all(|(known, _)| known != name) {
+            self.options.push((name.to_string(), location.to_string()));
+        }
+    }
+
+    /// Internationalized warning that lists the skipped options and where each was first set, or
+    /// `None` if nothing was skipped.
+    pub fn warning(&self) -> Option<String> {
+        if self.options.is_empty() {
+            return None;
+        }
+        let options = self
+            .options
+            .iter()
+            .map(|(name, location)| format!("`{}' ({})", name, location))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some(fmt!(
+            &gettext("ignored unknown options because of `%s': %s"),
+            TOLERATE_UNKNOWN_OPTIONS,
+            options
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_prescan_finds_the_toggle_anywhere_in_the_file() {
+        let contents = "\
+            auto-reload yes\n\
+            some-future-option 42\n\
+            \t tolerate-unknown-options yes # for the laptop\n\
+            reload-time 30\n";
+        assert_eq!(prescan(contents), Some(true));
+        assert_eq!(prescan("tolerate-unknown-options \"true\""), Some(true));
+        assert_eq!(prescan("tolerate-unknown-options no"), Some(false));
+    }
+
+    #[test]
+    fn t_prescan_uses_the_first_valid_toggle() {
+        let contents = "\
+            tolerate-unknown-options maybe\n\
+            tolerate-unknown-options false\n\
+            tolerate-unknown-options yes\n";
+        assert_eq!(prescan(contents), Some(false));
+    }
+
+    #[test]
+    fn t_prescan_ignores_comments_and_lookalikes() {
+        assert_eq!(prescan(""), None);
+        assert_eq!(prescan("# tolerate-unknown-options yes"), None);
+        assert_eq!(prescan("tolerate-unknown-options-too yes"), None);
+        assert_eq!(prescan("macro t set tolerate-unknown-options yes"), None);
+        assert_eq!(prescan("tolerate-unknown-options"), None);
+    }
+
+    #[test]
+    fn t_unknown_option_message_suggests_close_names() {
+        let known = vec![
+            "auto-reload".to_string(),
+            "reload-threads".to_string(),
+            "reload-time".to_string(),
+        ];
+        assert_eq!(
+            unknown_option_message("reload-tim", &known),
+            "unknown configuration option `reload-tim'. Did you mean: reload-time?"
+        );
+        assert_eq!(
+            unknown_option_message("reload-tme", &known),
+            "unknown configuration option `reload-tme'. Did you mean: reload-time?"
+        );
+        assert_eq!(
+            unknown_option_message("reload-t", &known),
+            "unknown configuration option `reload-t'. Did you mean: reload-time?"
+        );
+        assert_eq!(
+            unknown_option_message("colour", &known),
+            "unknown configuration option `colour'"
+        );
+    }
+
+    #[test]
+    fn t_skipped_options_are_listed_in_one_warning() {
+        let mut unknown = UnknownOptions::new();
+        assert_eq!(unknown.warning(), None);
+
+        unknown.record("scrollbar-style", "/home/user/.newsboat/config line 3");
+        unknown.record("feedlist-layout", "/home/user/.newsboat/extra line 1");
+        unknown.record("scrollbar-style", "/home/user/.newsboat/config line 9");
+        assert_eq!(
+            unknown.warning(),
+            Some(
+                "ignored unknown options because of `tolerate-unknown-options': \
+                 `scrollbar-style' (/home/user/.newsboat/config line 3), \
+                 `feedlist-layout' (/home/user/.newsboat/extra line 1)"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Parses the urls file.
+//!
+//! Each line of the file holds a URL and, optionally, tags, separated by whitespace. Tokens can be
+//! quoted, in which case they can contain whitespace and C-like escapes (see
+//! `utils::unescape_quoted`). Lines that start with `#`, and everything from a token that starts
+//! with `#` on, are comments.
+//!
+//! Some tags mean more than that: `~Title` sets the title of the feed, and a tag starting with `!`
+//! hides it. A URL like `"query:Name:filter expression"` defines a query feed, which shows the
+//! articles of other feeds that match the expression.
+
+use crate::filterparser;
+use crate::urlsfile::{self, Token};
+use gettextrs::gettext;
+use strprintf::fmt;
+
+const QUERY_PREFIX: &str = "query:";
+
+/// A feed defined in the urls file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlEntry {
+    pub url: String,
+    /// All the tags, in the order they were written, including the title and the hiding ones.
+    pub tags: Vec<String>,
+    /// `true` if this is a query feed.
+    pub query: bool,
+}
+
+impl UrlEntry {
+    /// The title set with a `~` tag, if any. If there are several, the last one wins.
+    pub fn title(&self) -> Option<&str> {
+        self.tags
+            .iter()
+            .rev()
+            .find(|tag| tag.starts_with('~'))
+            .map(|tag| &tag[1..])
+    }
+
+    /// `true` if the feed has a tag that starts with `!`.
+    pub fn is_hidden(&self) -> bool {
+        self.tags.iter().any(|tag| tag.starts_with('!'))
+    }
+}
+
+/// Why the urls file couldn't be parsed. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlFileError {
+    /// A quoted token isn't closed by the end of the line.
+    UnterminatedQuote { line: usize, column: usize },
+    /// A query feed has no name, or no filter expression after it.
+    IncompleteQuery { line: usize, column: usize },
+    /// The filter expression of a query feed is invalid. `message` is what the filter parser said.
+    InvalidQuery {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl UrlFileError {
+    /// The line that has the error.
+    pub fn line(&self) -> usize {
+        match self {
+            UrlFileError::UnterminatedQuote { line, .. }
+            | UrlFileError::IncompleteQuery { line, .. }
+            | UrlFileError::InvalidQuery { line, .. } => *line,
+        }
+    }
+
+    pub fn to_message(&self) -> String {
+        match self {
+            UrlFileError::UnterminatedQuote { line, column } => fmt!(
+                &gettext("urls file, line %u, column %u: the quote isn't closed"),
+                *line as u32,
+                *column as u32
+            ),
+            UrlFileError::IncompleteQuery { line, column } => fmt!(
+                &gettext(
+                    "urls file, line %u, column %u: expected a query feed like \"query:Name:filter\""
+                ),
+                *line as u32,
+                *column as u32
+            ),
+            UrlFileError::InvalidQuery {
+                line,
+                column,
+                message,
+            } => fmt!(
+                &gettext("urls file, line %u, column %u: %s"),
+                *line as u32,
+                *column as u32,
+                message.as_str()
+            ),
+        }
+    }
+}
+
+/// 1-based column of the byte `offset` in `line`.
+fn column(line: &str, offset: usize) -> usize {
+    line.get(..offset)
+        .map_or(0, |prefix| prefix.chars().count())
+        + 1
+}
+
+/// Checks that `token` is a query feed with a name and a valid filter expression.
+fn check_query(line: &str, line_number: usize, token: &Token) -> Result<(), UrlFileError> {
+    let definition = &token.value[QUERY_PREFIX.len()..];
+    let mut parts = definition.splitn(2, ':');
+    let name = parts.next().unwrap_or_default();
+    let expression = match parts.next() {
+        Some(expression) if !name.is_empty() && !expression.trim().is_empty() => expression,
+        _ => {
+            return Err(UrlFileError::IncompleteQuery {
+                line: line_number,
+                column: column(line, token.start),
+            })
+        }
+    };
+
+    filterparser::parse_with_position(expression)
+        .map(|_| ())
+        .map_err(|(position, message)| {
+            // Exact unless there are escapes in the token before the error
+            let text_start = if line[token.start..].starts_with('"') {
+                token.start + 1
+            } else {
+                token.start
+            };
+            let expression_start = text_start + QUERY_PREFIX.len() + name.len() + 1;
+            let offset = position.unwrap_or(0);
+            let column = column(line, expression_start)
+                + expression
+                    .get(..offset)
+                    .map_or(0, |prefix| prefix.chars().count());
+            UrlFileError::InvalidQuery {
+                line: line_number,
+                column,
+                message,
+            }
+        })
+}
+
+/// Parses the `content` of a urls file. Comments and blank lines are skipped.
+pub fn parse_urls_file(content: &str) -> Result<Vec<UrlEntry>, UrlFileError> {
+    let mut entries = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        if line.starts_with('#') {
+            continue;
+        }
+        let (tokens, unterminated_quote) = urlsfile::tokenize_line(line);
+        if let Some(start) = unterminated_quote {
+            return Err(UrlFileError::UnterminatedQuote {
+                line: line_number,
+                column: column(line, start),
+            });
+        }
+        let url = match tokens.first() {
+            Some(url) => url,
+            None => continue,
+        };
+
+        let query = url.value.starts_with(QUERY_PREFIX);
+        if query {
+            check_query(line, line_number, url)?;
+        }
+
+        entries.push(UrlEntry {
+            url: url.value.clone(),
+            tags: tokens[1..].iter().map(|tag| tag.value.clone()).collect(),
+            query,
+        });
+    }
+    Ok(entries)
+}
+
+/// Writes `entries` in the format `parse_urls_file` reads, one per line. Tokens are separated by
+/// single spaces, and only quoted if they have to be.
+pub fn serialize_urls_file(entries: &[UrlEntry]) -> String {
+    let mut result = String::new();
+    for entry in entries {
+        result.push_str(&urlsfile::quote_tag(&entry.url));
+        for tag in &entry.tags {
+            result.push(' ');
+            result.push_str(&urlsfile::quote_tag(tag));
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Adds a line for `entry` to the end of the `content` of a urls file, written the way
+/// `serialize_urls_file` writes it. The lines that are there already are kept byte for byte.
+pub fn append_entry(content: &str, entry: &UrlEntry) -> String {
+    let mut result = content.to_owned();
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(&serialize_urls_file(std::slice::from_ref(entry)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/data/urls/representative.urls");
+
+    fn entry(url: &str, tags: &[&str]) -> UrlEntry {
+        UrlEntry {
+            url: url.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            query: url.starts_with(QUERY_PREFIX),
+        }
+    }
+
+    #[test]
+    fn t_parse_urls_file_reads_urls_and_tags() {
+        assert_eq!(
+            parse_urls_file(FIXTURE),
+            Ok(vec![
+                entry("https://example.com/feed.xml", &["news", "tech"]),
+                entry(
+                    "https://blog.example.org/atom.xml",
+                    &["long reads", "~Example Blog"]
+                ),
+                entry("https://example.net/releases.rss", &["!", "releases"]),
+                entry(
+                    r#"query:Unread releases:unread = "yes" and tags # "releases""#,
+                    &[]
+                ),
+                entry(
+                    r#"query:Big articles:content =~ "[a-z]{5000}""#,
+                    &["long reads"]
+                ),
+                entry("exec:~/bin/fetch-feed.sh", &["scripts"]),
+                entry(
+                    "filter:~/bin/clean.py:https://example.com/dirty.xml",
+                    &[r#"tag with "quotes""#, "~Cleaned"]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn t_url_entry_knows_title_hidden_and_query_feeds() {
+        let entries = parse_urls_file(FIXTURE).unwrap();
+
+        assert_eq!(entries[0].title(), None);
+        assert_eq!(entries[1].title(), Some("Example Blog"));
+        assert_eq!(entries[6].title(), Some("Cleaned"));
+
+        assert!(!entries[0].is_hidden());
+        assert!(entries[2].is_hidden());
+
+        assert!(!entries[0].query);
+        assert!(entries[3].query);
+        assert!(entries[4].query);
+    }
+
+    #[test]
+    fn t_parse_urls_file_skips_comments_and_blank_lines() {
+        assert_eq!(parse_urls_file(""), Ok(vec![]));
+        assert_eq!(
+            parse_urls_file("# comment\n\n   \t\n  # indented comment\nhttps://example.com/"),
+            Ok(vec![entry("https://example.com/", &[])])
+        );
+    }
+
+    #[test]
+    fn t_parse_urls_file_fails_on_unterminated_quote() {
+        assert_eq!(
+            parse_urls_file("https://example.com/ ok\nhttps://example.org/ \"long reads\n"),
+            Err(UrlFileError::UnterminatedQuote {
+                line: 2,
+                column: 22
+            })
+        );
+        assert_eq!(
+            parse_urls_file(r#""query:Name:unread = \"yes\""#),
+            Err(UrlFileError::UnterminatedQuote { line: 1, column: 1 })
+        );
+        assert_eq!(
+            UrlFileError::UnterminatedQuote {
+                line: 2,
+                column: 22
+            }
+            .to_message(),
+            "urls file, line 2, column 22: the quote isn't closed"
+        );
+        assert_eq!(
+            UrlFileError::UnterminatedQuote {
+                line: 2,
+                column: 22
+            }
+            .line(),
+            2
+        );
+    }
+
+    #[test]
+    fn t_parse_urls_file_fails_on_incomplete_query_feeds() {
+        for line in &[
+            "query:",
+            "\"query:Name\"",
+            "\"query::unread = \\\"yes\\\"\"",
+            "query:Name:",
+        ] {
+            let input = format!("https://example.com/\n  {}", line);
+            assert_eq!(
+                parse_urls_file(&input),
+                Err(UrlFileError::IncompleteQuery { line: 2, column: 3 }),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn t_parse_urls_file_points_at_the_error_in_query_filter() {
+        let input = "https://example.com/\n\"query:Name:a = b\" tag";
+        match parse_urls_file(input) {
+            Err(UrlFileError::InvalidQuery {
+                line,
+                column,
+                message,
+            }) => {
+                assert_eq!(line, 2);
+                // At the `b`, which isn't a value
+                assert_eq!(column, 17);
+                assert_eq!(
+                    message,
+                    "Parse error at position 4: expected one of: quoted string, range, number"
+                );
+            }
+            other => panic!("expected an invalid query, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn t_serialize_urls_file_normalizes_spacing_and_quoting() {
+        let entries = vec![
+            entry(
+                "https://example.com/feed.xml",
+                &["news", "long reads", "~Title"],
+            ),
+            entry(r#"query:Unread:unread = "yes""#, &["#not-a-comment"]),
+        ];
+        assert_eq!(
+            serialize_urls_file(&entries),
+            "https://example.com/feed.xml news \"long reads\" ~Title\n\
+             \"query:Unread:unread = \\\"yes\\\"\" \"#not-a-comment\"\n"
+        );
+        assert_eq!(serialize_urls_file(&[]), "");
+    }
+
+    #[test]
+    fn t_serialize_urls_file_round_trips() {
+        let entries = parse_urls_file(FIXTURE).unwrap();
+        let serialized = serialize_urls_file(&entries);
+        assert_eq!(parse_urls_file(&serialized), Ok(entries));
+
+        // Once normalized, the file doesn't change anymore
+        assert_eq!(
+            serialize_urls_file(&parse_urls_file(&serialized).unwrap()),
+            serialized
+        );
+    }
+
+    #[test]
+    fn t_append_entry_keeps_existing_lines_as_they_are() {
+        let content = "# my feeds\nhttps://example.com/   \"long reads\"";
+        let feed = entry("exec:~/bin/feed --all", &[]);
+        assert_eq!(
+            append_entry(content, &feed),
+            "# my feeds\nhttps://example.com/   \"long reads\"\n\"exec:~/bin/feed --all\"\n"
+        );
+        assert_eq!(
+            append_entry("", &entry("https://example.org/", &["news"])),
+            "https://example.org/ news\n"
+        );
+
+        let appended = append_entry(FIXTURE, &feed);
+        let mut entries = parse_urls_file(FIXTURE).unwrap();
+        entries.push(feed);
+        assert_eq!(parse_urls_file(&appended), Ok(entries));
+    }
+}
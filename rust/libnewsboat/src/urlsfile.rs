@@ -0,0 +1,768 @@
+//! Changing and saving the urls file.
+//!
+//! The urls file is written by hand, so it has comments, blank lines, and spacing that the user
+//! cares about. Rather than writing the whole file from what was parsed, these functions change
+//! only the tokens that need changing, and leave every other byte where it was.
+//!
+//! The file may be out of Newsboat's reach, e.g. a symlink into a read-only dotfiles store. With
+//! `urls-file-copy-on-write`, changes then go into an overlay: a urls file of its own that holds
+//! the lines of the feeds that were added or changed, and is merged into the urls file whenever
+//! that's read.
+
+use crate::utils::{self, AtomicWriteError};
+use gettextrs::gettext;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use strprintf::fmt;
+
+/// Why the tags couldn't be changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagEditError {
+    /// The feed isn't in the file.
+    UnknownFeed(String),
+    /// The feed has the tag already.
+    AlreadyTagged { url: String, tag: String },
+    /// The feed doesn't have the tag.
+    NotTagged { url: String, tag: String },
+    /// No feed has the tag.
+    UnusedTag(String),
+    /// The tag is empty, or it would be read as something else: tags that start with `~` set the
+    /// title of a feed, and ones that start with `!` hide it.
+    InvalidTag(String),
+}
+
+impl TagEditError {
+    pub fn to_message(&self) -> String {
+        match self {
+            TagEditError::UnknownFeed(url) => {
+                fmt!(&gettext("%s isn't in the urls file."), url.as_str())
+            }
+            TagEditError::AlreadyTagged { url, tag } => fmt!(
+                &gettext("%s already has the tag `%s'."),
+                url.as_str(),
+                tag.as_str()
+            ),
+            TagEditError::NotTagged { url, tag } => fmt!(
+                &gettext("%s doesn't have the tag `%s'."),
+                url.as_str(),
+                tag.as_str()
+            ),
+            TagEditError::UnusedTag(tag) => {
+                fmt!(&gettext("No feed has the tag `%s'."), tag.as_str())
+            }
+            TagEditError::InvalidTag(tag) => {
+                fmt!(&gettext("`%s' can't be used as a tag."), tag.as_str())
+            }
+        }
+    }
+}
+
+/// A token of a line, as `utils::tokenize_quoted()` on the C++ side reads it.
+#[derive(Debug)]
+pub(crate) struct Token {
+    /// Byte offsets of the token in the line, including the quotes.
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// The token with quotes and escapes removed.
+    pub(crate) value: String,
+}
+
+fn is_delimiter(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r' || c == '\n'
+}
+
+fn tokenize(line: &str) -> Vec<Token> {
+    tokenize_line(line).0
+}
+
+/// Splits `line` into tokens, stopping at a comment. A quote that isn't closed makes its token
+/// run to the end of the line; the second value is the byte offset of that quote.
+pub(crate) fn tokenize_line(line: &str) -> (Vec<Token>, Option<usize>) {
+    let mut unterminated_quote = None;
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    loop {
+        while let Some(&(_, c)) = chars.peek() {
+            if !is_delimiter(c) {
+                break;
+            }
+            chars.next();
+        }
+        let (start, first) = match chars.next() {
+            Some((_, '#')) | None => break,
+            Some(next) => next,
+        };
+
+        let mut value = String::new();
+        let mut end = line.len();
+        if first == '"' {
+            let contents = start + 1;
+            let mut contents_end = line.len();
+            unterminated_quote = Some(start);
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        contents_end = i;
+                        end = i + 1;
+                        unterminated_quote = None;
+                        break;
+                    }
+                    '\\' => {
+                        chars.next();
+                    }
+                    _ => {}
+                }
+            }
+            value = utils::unescape_quoted(&line[contents..contents_end]);
+        } else {
+            value.push(first);
+            while let Some(&(i, c)) = chars.peek() {
+                if is_delimiter(c) {
+                    end = i;
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(Token { start, end, value });
+    }
+    (tokens, unterminated_quote)
+}
+
+/// `tag` as it has to be written into the urls file: as is if that reads back the same, and in
+/// quotes otherwise.
+pub(crate) fn quote_tag(tag: &str) -> String {
+    let needs_quotes = tag.starts_with('#')
+        || tag.starts_with('"')
+        || tag
+            .chars()
+            .any(|c| is_delimiter(c) || c == '\\' || c == '"');
+    if !needs_quotes {
+        return tag.to_string();
+    }
+    format!("\"{}\"", utils::escape_quoted(tag))
+}
+
+fn check_tag(tag: &str) -> Result<(), TagEditError> {
+    if tag.trim().is_empty() || tag.starts_with('~') || tag.starts_with('!') {
+        return Err(TagEditError::InvalidTag(tag.to_string()));
+    }
+    Ok(())
+}
+
+/// Applies `edit` to the tokens of every line that defines a feed, and puts the file back
+/// together. `edit` returns the changed line, or `None` to leave it alone.
+fn edit_lines<F>(contents: &str, mut edit: F) -> String
+where
+    F: FnMut(&str, &[Token]) -> Option<String>,
+{
+    let mut result = String::with_capacity(contents.len() + 16);
+    for (i, line) in contents.split('\n').enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        // The same lines that FileUrlReader skips
+        if line.is_empty() || line.starts_with('#') {
+            result.push_str(line);
+            continue;
+        }
+        let tokens = tokenize(line);
+        if tokens.is_empty() {
+            result.push_str(line);
+            continue;
+        }
+        match edit(line, &tokens) {
+            Some(changed) => result.push_str(&changed),
+            None => result.push_str(line),
+        }
+    }
+    result
+}
+
+/// `line` without the tag at `tokens[index]`, and the whitespace in front of it.
+fn without_token(line: &str, tokens: &[Token], index: usize) -> String {
+    let from = tokens[index - 1].end;
+    format!("{}{}", &line[..from], &line[tokens[index].end..])
+}
+
+fn tag_index(tokens: &[Token], tag: &str) -> Option<usize> {
+    tokens
+        .iter()
+        .skip(1)
+        .position(|token| token.value == tag)
+        .map(|i| i + 1)
+}
+
+/// Adds `tag` to the feed `url`, after its other tags.
+pub fn add_tag(contents: &str, url: &str, tag: &str) -> Result<String, TagEditError> {
+    check_tag(tag)?;
+    let mut found = false;
+    let mut tagged = false;
+    let result = edit_lines(contents, |line, tokens| {
+        if tokens[0].value != url {
+            return None;
+        }
+        found = true;
+        if tag_index(tokens, tag).is_some() {
+            tagged = true;
+            return None;
+        }
+        let at = tokens.last().map_or(line.len(), |token| token.end);
+        Some(format!("{} {}{}", &line[..at], quote_tag(tag), &line[at..]))
+    });
+    if !found {
+        return Err(TagEditError::UnknownFeed(url.to_string()));
+    }
+    if tagged {
+        return Err(TagEditError::AlreadyTagged {
+            url: url.to_string(),
+            tag: tag.to_string(),
+        });
+    }
+    Ok(result)
+}
+
+/// Removes `tag` from the feed `url`.
+pub fn remove_tag(contents: &str, url: &str, tag: &str) -> Result<String, TagEditError> {
+    let mut found = false;
+    let mut removed = false;
+    let result = edit_lines(contents, |line, tokens| {
+        if tokens[0].value != url {
+            return None;
+        }
+        found = true;
+        let index = tag_index(tokens, tag)?;
+        removed = true;
+        Some(without_token(line, tokens, index))
+    });
+    if !found {
+        return Err(TagEditError::UnknownFeed(url.to_string()));
+    }
+    if !removed {
+        return Err(TagEditError::NotTagged {
+            url: url.to_string(),
+            tag: tag.to_string(),
+        });
+    }
+    Ok(result)
+}
+
+/// Number of feeds that have `tag`.
+pub fn feeds_with_tag(contents: &str, tag: &str) -> usize {
+    let mut count = 0;
+    edit_lines(contents, |_, tokens| {
+        if tag_index(tokens, tag).is_some() {
+            count += 1;
+        }
+        None
+    });
+    count
+}
+
+/// Renames `old` to `new` on every feed that has it. Feeds that already have `new` just lose
+/// `old`. Returns the changed contents and the number of feeds that had `old`.
+pub fn rename_tag(contents: &str, old: &str, new: &str) -> Result<(String, usize), TagEditError> {
+    check_tag(new)?;
+    let mut renamed = 0;
+    let result = edit_lines(contents, |line, tokens| {
+        let index = tag_index(tokens, old)?;
+        renamed += 1;
+        if tag_index(tokens, new).is_some() {
+            return Some(without_token(line, tokens, index));
+        }
+        let token = &tokens[index];
+        Some(format!(
+            "{}{}{}",
+            &line[..token.start],
+            quote_tag(new),
+            &line[token.end..]
+        ))
+    });
+    if renamed == 0 {
+        return Err(TagEditError::UnusedTag(old.to_string()));
+    }
+    Ok((result, renamed))
+}
+
+/// Written at the top of the overlay, which is otherwise just feed lines.
+const OVERLAY_HEADER: &str =
+    "# Feeds that Newsboat added or changed while urls-file-copy-on-write \
+                              was set. They're merged into the urls file whenever it's read.";
+
+/// The URLs of the feed lines of `contents`, with the lines, in order.
+fn feed_lines(contents: &str) -> Vec<(String, &str)> {
+    contents
+        .split('\n')
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut tokens = tokenize(line);
+            if tokens.is_empty() {
+                None
+            } else {
+                Some((tokens.swap_remove(0).value, line))
+            }
+        })
+        .collect()
+}
+
+/// The urls file `base` with the `overlay` merged into it: the lines of feeds that are in both
+/// are taken from the overlay, and the feeds that are only in the overlay are added at the end, in
+/// its order.
+pub fn merge_overlay(base: &str, overlay: &str) -> String {
+    let overlay_lines = feed_lines(overlay);
+    let replacements: HashMap<&str, &str> = overlay_lines
+        .iter()
+        .map(|(url, line)| (url.as_str(), *line))
+        .collect();
+
+    let mut merged_urls = HashSet::new();
+    let mut result = edit_lines(base, |_, tokens| {
+        let url = tokens[0].value.as_str();
+        replacements.get(url).map(|line| {
+            merged_urls.insert(url.to_string());
+            line.to_string()
+        })
+    });
+
+    for (url, line) in &overlay_lines {
+        if merged_urls.insert(url.clone()) {
+            if !result.is_empty() && !result.ends_with('\n') {
+                result.push('\n');
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    result
+}
+
+/// The overlay that turns `base` into `merged` when merged into it: the lines of the feeds whose
+/// URL or tags differ from `base`. Feeds that are in `base` but not in `merged` can't be expressed,
+/// and stay in.
+pub fn split_overlay(base: &str, merged: &str) -> String {
+    let tags = |line: &str| -> Vec<String> {
+        tokenize(line)
+            .into_iter()
+            .map(|token| token.value)
+            .collect()
+    };
+    let base_tags: HashMap<String, Vec<String>> = feed_lines(base)
+        .into_iter()
+        .map(|(url, line)| (url, tags(line)))
+        .collect();
+
+    let mut result = String::new();
+    for (url, line) in feed_lines(merged) {
+        if base_tags.get(&url) != Some(&tags(line)) {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    if result.is_empty() {
+        result
+    } else {
+        format!("{}\n{}", OVERLAY_HEADER, result)
+    }
+}
+
+/// Why `save` didn't write the urls file.
+#[derive(Debug)]
+pub enum UrlsFileWriteError {
+    /// The urls file (or the one it links to) is read-only, and there is no overlay to write to
+    /// instead.
+    UrlsFileReadOnly(PathBuf),
+    /// The file couldn't be written, or the new contents aren't a valid urls file.
+    Write {
+        path: PathBuf,
+        error: AtomicWriteError,
+    },
+}
+
+impl UrlsFileWriteError {
+    pub fn to_message(&self) -> String {
+        match self {
+            UrlsFileWriteError::UrlsFileReadOnly(path) => fmt!(
+                &gettext(
+                    "urls file %s is read-only; changes not saved. Make them by hand, or set \
+                     `urls-file-copy-on-write'."
+                ),
+                path.to_string_lossy().into_owned()
+            ),
+            UrlsFileWriteError::Write { path, error } => fmt!(
+                &gettext("Error: couldn't write to %s: %s"),
+                path.to_string_lossy().into_owned(),
+                error.to_string()
+            ),
+        }
+    }
+}
+
+/// Saves `contents` as the urls file at `path`. If the file is a symlink, the file it points to is
+/// written, and the symlink is kept.
+///
+/// With an `overlay`, the urls file is left as it is, and the overlay is written instead, so that
+/// `merge_overlay` turns the urls file into `contents`.
+pub fn save(path: &Path, overlay: Option<&Path>, contents: &str) -> Result<(), UrlsFileWriteError> {
+    let (destination, contents) = match overlay {
+        Some(overlay) => {
+            let base = fs::read(path).unwrap_or_default();
+            let base = String::from_utf8_lossy(&base);
+            (overlay, split_overlay(&base, contents))
+        }
+        None => (path, contents.to_string()),
+    };
+    utils::atomic_write_validated(
+        destination,
+        contents.as_bytes(),
+        0o600,
+        utils::validate_urls_file,
+    )
+    .map_err(|error| match error {
+        AtomicWriteError::ReadOnly(target) if overlay.is_none() => {
+            UrlsFileWriteError::UrlsFileReadOnly(target)
+        }
+        error => UrlsFileWriteError::Write {
+            path: destination.to_owned(),
+            error,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URLS: &str = "# My feeds\n\
+                        \n\
+                        https://example.com/news.xml   news \"local news\"  # daily\n\
+                        https://example.com/tech.xml\ttech news\r\n\
+                        \"query:Unread:unread = \\\"yes\\\"\" news\n\
+                        https://example.com/untagged.xml\n";
+
+    /// The lines that differ between `old` and `new`, which must have the same number of lines.
+    fn changed_lines<'a>(old: &'a str, new: &'a str) -> Vec<(&'a str, &'a str)> {
+        let old: Vec<&str> = old.split('\n').collect();
+        let new: Vec<&str> = new.split('\n').collect();
+        assert_eq!(old.len(), new.len());
+        old.into_iter()
+            .zip(new)
+            .filter(|(old, new)| old != new)
+            .collect()
+    }
+
+    fn tag_values(line: &str) -> Vec<String> {
+        tokenize(line).into_iter().map(|t| t.value).collect()
+    }
+
+    #[test]
+    fn t_tokenize_reads_tokens_like_tokenize_quoted() {
+        assert_eq!(
+            tag_values("https://example.com/ tag \"with space\" \"a \\\"b\\\"\" # comment"),
+            vec!["https://example.com/", "tag", "with space", "a \"b\""]
+        );
+        assert_eq!(tag_values("   # just a comment"), Vec::<String>::new());
+        assert_eq!(
+            tag_values("url \"unterminated"),
+            vec!["url", "unterminated"]
+        );
+    }
+
+    #[test]
+    fn t_add_tag_only_changes_the_line_of_the_feed() {
+        let result = add_tag(URLS, "https://example.com/news.xml", "world").unwrap();
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "https://example.com/news.xml   news \"local news\"  # daily",
+                "https://example.com/news.xml   news \"local news\" world  # daily"
+            )]
+        );
+
+        // The line ending is kept, too
+        let result = add_tag(URLS, "https://example.com/tech.xml", "rust").unwrap();
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "https://example.com/tech.xml\ttech news\r",
+                "https://example.com/tech.xml\ttech news rust\r"
+            )]
+        );
+
+        let result = add_tag(URLS, "https://example.com/untagged.xml", "misc").unwrap();
+        assert!(result.ends_with("https://example.com/untagged.xml misc\n"));
+    }
+
+    #[test]
+    fn t_tags_with_spaces_and_quotes_are_quoted() {
+        let result = add_tag(URLS, "https://example.com/untagged.xml", "open source").unwrap();
+        assert!(result.ends_with("https://example.com/untagged.xml \"open source\"\n"));
+
+        let result = add_tag(URLS, "https://example.com/untagged.xml", "say \"hi\"").unwrap();
+        let line = result.lines().last().unwrap();
+        assert_eq!(
+            tag_values(line),
+            vec!["https://example.com/untagged.xml", "say \"hi\""]
+        );
+
+        // Query feeds are identified by their quoted definition
+        let query = "query:Unread:unread = \"yes\"";
+        let result = add_tag(URLS, query, "local news").unwrap();
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "\"query:Unread:unread = \\\"yes\\\"\" news",
+                "\"query:Unread:unread = \\\"yes\\\"\" news \"local news\""
+            )]
+        );
+    }
+
+    #[test]
+    fn t_remove_tag_takes_the_whitespace_in_front_of_it() {
+        let result = remove_tag(URLS, "https://example.com/news.xml", "local news").unwrap();
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "https://example.com/news.xml   news \"local news\"  # daily",
+                "https://example.com/news.xml   news  # daily"
+            )]
+        );
+
+        let result = remove_tag(URLS, "https://example.com/news.xml", "news").unwrap();
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "https://example.com/news.xml   news \"local news\"  # daily",
+                "https://example.com/news.xml \"local news\"  # daily"
+            )]
+        );
+    }
+
+    #[test]
+    fn t_rename_tag_rewrites_every_feed_that_has_it() {
+        assert_eq!(feeds_with_tag(URLS, "news"), 3);
+        let (result, renamed) = rename_tag(URLS, "news", "headlines").unwrap();
+        assert_eq!(renamed, 3);
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![
+                (
+                    "https://example.com/news.xml   news \"local news\"  # daily",
+                    "https://example.com/news.xml   headlines \"local news\"  # daily"
+                ),
+                (
+                    "https://example.com/tech.xml\ttech news\r",
+                    "https://example.com/tech.xml\ttech headlines\r"
+                ),
+                (
+                    "\"query:Unread:unread = \\\"yes\\\"\" news",
+                    "\"query:Unread:unread = \\\"yes\\\"\" headlines"
+                ),
+            ]
+        );
+        assert_eq!(feeds_with_tag(&result, "news"), 0);
+        assert_eq!(feeds_with_tag(&result, "headlines"), 3);
+    }
+
+    #[test]
+    fn t_rename_to_a_tag_the_feed_has_already_drops_the_old_one() {
+        let (result, renamed) = rename_tag(URLS, "local news", "news").unwrap();
+        assert_eq!(renamed, 1);
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "https://example.com/news.xml   news \"local news\"  # daily",
+                "https://example.com/news.xml   news  # daily"
+            )]
+        );
+    }
+
+    #[test]
+    fn t_rename_quotes_the_new_name_if_needed() {
+        let (result, renamed) = rename_tag(URLS, "tech", "tech talk").unwrap();
+        assert_eq!(renamed, 1);
+        assert_eq!(
+            changed_lines(URLS, &result),
+            vec![(
+                "https://example.com/tech.xml\ttech news\r",
+                "https://example.com/tech.xml\t\"tech talk\" news\r"
+            )]
+        );
+    }
+
+    #[test]
+    fn t_errors() {
+        assert_eq!(
+            add_tag(URLS, "https://example.com/missing.xml", "news"),
+            Err(TagEditError::UnknownFeed(
+                "https://example.com/missing.xml".to_string()
+            ))
+        );
+        assert_eq!(
+            add_tag(URLS, "https://example.com/news.xml", "news"),
+            Err(TagEditError::AlreadyTagged {
+                url: "https://example.com/news.xml".to_string(),
+                tag: "news".to_string()
+            })
+        );
+        assert_eq!(
+            remove_tag(URLS, "https://example.com/untagged.xml", "news"),
+            Err(TagEditError::NotTagged {
+                url: "https://example.com/untagged.xml".to_string(),
+                tag: "news".to_string()
+            })
+        );
+        assert_eq!(
+            rename_tag(URLS, "sports", "football"),
+            Err(TagEditError::UnusedTag("sports".to_string()))
+        );
+        for tag in &["", "  ", "~Title", "!hidden"] {
+            assert_eq!(
+                add_tag(URLS, "https://example.com/news.xml", tag),
+                Err(TagEditError::InvalidTag(tag.to_string()))
+            );
+            assert_eq!(
+                rename_tag(URLS, "news", tag),
+                Err(TagEditError::InvalidTag(tag.to_string()))
+            );
+        }
+        // Comments aren't feeds
+        assert_eq!(
+            add_tag(URLS, "My", "news"),
+            Err(TagEditError::UnknownFeed("My".to_string()))
+        );
+    }
+
+    #[test]
+    fn t_error_messages() {
+        assert_eq!(
+            TagEditError::NotTagged {
+                url: "https://example.com/".to_string(),
+                tag: "news".to_string()
+            }
+            .to_message(),
+            "https://example.com/ doesn't have the tag `news'."
+        );
+        assert_eq!(
+            TagEditError::InvalidTag("~Title".to_string()).to_message(),
+            "`~Title' can't be used as a tag."
+        );
+    }
+
+    #[test]
+    fn t_save_writes_through_a_symlink_and_keeps_it() {
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let target = tmp.path().join("dotfiles-urls");
+        let link = tmp.path().join("urls");
+        fs::write(&target, "https://example.com/feed.xml\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let edited = add_tag(
+            "https://example.com/feed.xml\n",
+            "https://example.com/feed.xml",
+            "news",
+        )
+        .unwrap();
+        save(&link, None, &edited).unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            "https://example.com/feed.xml news\n"
+        );
+    }
+
+    #[test]
+    fn t_save_reports_a_read_only_urls_file() {
+        use std::os::unix::fs::PermissionsExt;
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("urls");
+        fs::write(&path, "https://example.com/feed.xml\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).unwrap();
+
+        let error = save(&path, None, "https://example.com/other.xml\n").unwrap_err();
+        match error {
+            UrlsFileWriteError::UrlsFileReadOnly(ref reported) => assert_eq!(reported, &path),
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(
+            error.to_message(),
+            format!(
+                "urls file {} is read-only; changes not saved. Make them by hand, or set \
+                 `urls-file-copy-on-write'.",
+                path.display()
+            )
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "https://example.com/feed.xml\n"
+        );
+
+        // With an overlay, the changes go there instead
+        let overlay = tmp.path().join("urls.overlay");
+        save(&path, Some(&overlay), "https://example.com/other.xml\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "https://example.com/feed.xml\n"
+        );
+        assert_eq!(
+            merge_overlay(
+                &fs::read_to_string(&path).unwrap(),
+                &fs::read_to_string(&overlay).unwrap()
+            ),
+            "https://example.com/feed.xml\nhttps://example.com/other.xml\n"
+        );
+    }
+
+    #[test]
+    fn t_merge_overlay_replaces_lines_in_place_and_appends_new_feeds_in_order() {
+        let base = "# my feeds\nhttps://a.example/ news\n\nhttps://b.example/\nhttps://c.example/";
+        let overlay =
+            "# header\nhttps://d.example/ new\nhttps://b.example/ tech\nhttps://e.example/\n";
+        assert_eq!(
+            merge_overlay(base, overlay),
+            "# my feeds\nhttps://a.example/ news\n\nhttps://b.example/ tech\nhttps://c.example/\n\
+             https://d.example/ new\nhttps://e.example/\n"
+        );
+        assert_eq!(merge_overlay(base, ""), base);
+        assert_eq!(
+            merge_overlay("", overlay),
+            "https://d.example/ new\nhttps://b.example/ tech\nhttps://e.example/\n"
+        );
+    }
+
+    #[test]
+    fn t_split_overlay_round_trips_through_merge_overlay() {
+        let base = "# my feeds\nhttps://a.example/ news\nhttps://b.example/\n";
+        let merged = merge_overlay(base, "https://c.example/\n");
+        let merged = add_tag(&merged, "https://b.example/", "tech").unwrap();
+        let (merged, _) = rename_tag(&merged, "news", "world").unwrap();
+
+        let overlay = split_overlay(base, &merged);
+        assert!(overlay.starts_with(OVERLAY_HEADER));
+        assert_eq!(
+            feed_lines(&overlay),
+            vec![
+                ("https://a.example/".to_string(), "https://a.example/ world"),
+                ("https://b.example/".to_string(), "https://b.example/ tech"),
+                ("https://c.example/".to_string(), "https://c.example/"),
+            ]
+        );
+        assert_eq!(merge_overlay(base, &overlay), merged);
+
+        // Feeds that are back to what the urls file says drop out of the overlay
+        let (reverted, _) = rename_tag(&merged, "world", "news").unwrap();
+        let overlay = split_overlay(base, &reverted);
+        assert_eq!(feed_lines(&overlay).len(), 2);
+        assert_eq!(merge_overlay(base, &overlay), reverted);
+        assert_eq!(split_overlay(base, base), "");
+    }
+}
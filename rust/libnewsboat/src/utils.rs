@@ -1,15 +1,25 @@
 use crate::htmlrenderer;
 use crate::logger::{self, Level};
-use libc::{c_ulong, close, execvp, exit, fork, waitpid};
+use gettextrs::gettext;
+use libc::{c_char, c_int, c_ulong, close, execvp, exit, fork, kill, pid_t, waitpid, SIGTERM};
 use percent_encoding::*;
-use std::ffi::CString;
-use std::fs::DirBuilder;
-use std::io::{self, Write};
-use std::os::unix::fs::DirBuilderExt;
-use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::cmp::Ordering;
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::fmt;
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::ptr;
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 use url::Url;
 
 pub fn replace_all(input: String, from: &str, to: &str) -> String {
@@ -50,7 +60,9 @@ pub fn to_u(rs_str: String, default_value: u32) -> u32 {
     result.unwrap()
 }
 
-/// Combine a base URL and a link to a new absolute URL.
+/// Combine a base URL and a link to a new absolute URL, as described in section 5 of RFC 3986:
+/// protocol-relative links (`//host/path`) take only the scheme of the base URL, `.` and `..`
+/// segments are collapsed, and an empty link stands for the base URL without its fragment.
 /// If the base URL is malformed or joining with the link fails, link will be returned.
 /// # Examples
 /// ```
@@ -76,21 +88,94 @@ pub fn absolute_url(base_url: &str, link: &str) -> String {
         .to_owned()
 }
 
+/// Like `absolute_url`, but drops the fragment (the part after `#`) of the result, which is of no
+/// use when the URL is downloaded, e.g. for enclosures. If the base URL is malformed or joining
+/// with the link fails, link will be returned unchanged.
+/// # Examples
+/// ```
+/// use libnewsboat::utils::absolute_url_without_fragment;
+/// assert_eq!(absolute_url_without_fragment("http://foobar/feed.xml", "ep1.mp3#t=10"),
+///     "http://foobar/ep1.mp3".to_owned());
+/// assert_eq!(absolute_url_without_fragment("foo", "bar#baz"), "bar#baz".to_owned());
+/// ```
+pub fn absolute_url_without_fragment(base_url: &str, link: &str) -> String {
+    match Url::parse(base_url).and_then(|url| url.join(link)) {
+        Ok(mut url) => {
+            url.set_fragment(None);
+            url.as_str().to_owned()
+        }
+        Err(_) => link.to_owned(),
+    }
+}
+
 /// Path to the home directory, if known. Doesn't work on Windows.
+///
+/// This is `$HOME`, or the home directory of the current user's passwd entry if `$HOME` is unset
+/// or empty.
 #[cfg(not(target_os = "windows"))]
 pub fn home_dir() -> Option<PathBuf> {
-    // This function got deprecated because it examines HOME environment variable even on Windows,
-    // which is wrong. But Newsboat doesn't support Windows, so we're fine using that.
-    //
-    // Cf. https://github.com/rust-lang/rust/issues/28940
-    #[allow(deprecated)]
-    std::env::home_dir()
+    home_dir_from(std::env::var_os("HOME"))
+}
+
+fn home_dir_from(home: Option<OsString>) -> Option<PathBuf> {
+    match home {
+        Some(home) if !home.is_empty() => Some(PathBuf::from(home)),
+        _ => passwd_home_dir(|passwd, buffer, buffer_len, result| unsafe {
+            libc::getpwuid_r(libc::getuid(), passwd, buffer, buffer_len, result)
+        }),
+    }
+}
+
+/// Path to the home directory of the user called `name`, if there is such a user.
+fn user_home_dir(name: &OsStr) -> Option<PathBuf> {
+    let name = CString::new(name.as_bytes()).ok()?;
+    passwd_home_dir(|passwd, buffer, buffer_len, result| unsafe {
+        libc::getpwnam_r(name.as_ptr(), passwd, buffer, buffer_len, result)
+    })
+}
+
+/// Home directory from the passwd entry that `lookup` finds. `lookup` is a call to `getpwnam_r`
+/// or `getpwuid_r`, and gets the arguments that follow the name or uid.
+fn passwd_home_dir<F>(lookup: F) -> Option<PathBuf>
+where
+    F: Fn(*mut libc::passwd, *mut c_char, usize, *mut *mut libc::passwd) -> c_int,
+{
+    let mut buffer = vec![0 as c_char; 1024];
+    loop {
+        // `passwd` is a plain C struct, for which all zeroes is a valid value
+        let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let status = lookup(&mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result);
+        if status == libc::ERANGE {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if status != 0 || result.is_null() || passwd.pw_dir.is_null() {
+            return None;
+        }
+        let dir = unsafe { CStr::from_ptr(passwd.pw_dir) };
+        return Some(PathBuf::from(OsStr::from_bytes(dir.to_bytes())));
+    }
 }
 
-/// Replaces tilde (`~`) at the beginning of the path with the path to user's home directory.
+/// Replaces tilde (`~`) at the beginning of the path with the path to user's home directory, and
+/// `~name` with the path to the home directory of the user called `name`.
 pub fn resolve_tilde(path: PathBuf) -> PathBuf {
-    if let (Some(home), Ok(suffix)) = (home_dir(), path.strip_prefix("~")) {
-        return home.join(suffix);
+    let mut components = path.components();
+    let home = match components.next() {
+        Some(Component::Normal(first)) if first.as_bytes().starts_with(b"~") => {
+            let user = &first.as_bytes()[1..];
+            if user.is_empty() {
+                home_dir()
+            } else {
+                user_home_dir(OsStr::from_bytes(user))
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(home) = home {
+        return home.join(components.as_path());
     }
 
     // Either the `path` doesn't start with tilde, or we couldn't figure out the path to the
@@ -285,8 +370,170 @@ pub fn is_valid_attribute(attribute: &str) -> bool {
     VALID_ATTRIBUTES.contains(&attribute)
 }
 
+/// Length in bytes of the start of `haystack` that matches `needle` (already case-folded), if it
+/// does.
+fn case_folded_prefix_len(haystack: &str, needle: &[char]) -> Option<usize> {
+    let mut remaining = needle;
+    for (offset, c) in haystack.char_indices() {
+        for folded in c.to_lowercase() {
+            match remaining.split_first() {
+                Some((&expected, rest)) if expected == folded => remaining = rest,
+                _ => return None,
+            }
+        }
+        if remaining.is_empty() {
+            return Some(offset + c.len_utf8());
+        }
+    }
+    None
+}
+
+/// Byte ranges of the occurrences of `needle` in `haystack`, ignoring case. The occurrences don't
+/// overlap; an empty `needle` doesn't occur anywhere.
+/// ```
+/// use libnewsboat::utils::find_case_folded;
+/// assert_eq!(find_case_folded("Open URL, open feed", "OPEN"), vec![(0, 4), (10, 14)]);
+/// assert_eq!(find_case_folded("Straße", "SSE"), vec![]);
+/// assert_eq!(find_case_folded("ÜBER", "über"), vec![(0, 5)]);
+/// ```
+pub fn find_case_folded(haystack: &str, needle: &str) -> Vec<(usize, usize)> {
+    let needle = needle
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+    let mut next_start = 0;
+    for (start, _) in haystack.char_indices() {
+        if start < next_start {
+            continue;
+        }
+        if let Some(len) = case_folded_prefix_len(&haystack[start..], &needle) {
+            ranges.push((start, start + len));
+            next_start = start + len;
+        }
+    }
+    ranges
+}
+
+/// Returns `true` if `haystack` contains `needle`, ignoring case. Every string contains an empty
+/// `needle`.
+/// ```
+/// use libnewsboat::utils::contains_case_folded;
+/// assert!(contains_case_folded("Reload all feeds", "ALL"));
+/// assert!(contains_case_folded("Reload all feeds", ""));
+/// assert!(!contains_case_folded("Reload all feeds", "articles"));
+/// ```
+pub fn contains_case_folded(haystack: &str, needle: &str) -> bool {
+    needle.is_empty() || !find_case_folded(haystack, needle).is_empty()
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_chr) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_chr) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_chr == *b_chr { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Maximum number of entries that `suggest` returns.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Entries of `candidates` that are close enough to `input` to be offered as "did you mean"
+/// suggestions, closest first. Among equally close entries, the one that comes first in
+/// `candidates` wins, and an entry that is repeated is only suggested once.
+///
+/// An entry is close if it's at most two edits away from `input`, or three if `input` is seven or
+/// more characters long.
+pub fn suggest<'a, I>(candidates: I, input: &str) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = if input.chars().count() >= 7 { 3 } else { 2 };
+    let mut close: Vec<(usize, &str)> = Vec::new();
+    for candidate in candidates {
+        if close.iter().any(|(_, known)| *known == candidate) {
+            continue;
+        }
+        let distance = edit_distance(input, candidate);
+        if distance <= max_distance {
+            close.push((distance, candidate));
+        }
+    }
+    // Stable sort keeps the order of `candidates` among equally distant entries
+    close.sort_by_key(|(distance, _)| *distance);
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Returns `true` if `c` is one of the regional indicators, pairs of which make up flag emoji.
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Returns the on-screen width of the grapheme cluster `grapheme`, which is drawn as a single
+/// glyph.
+///
+/// That's the width of its first character: the ones that follow (combining marks, emoji
+/// modifiers, characters glued on with zero width joiners) are drawn as a part of it. Flags are
+/// the exception, since they're made of two regional indicators that are a column wide each.
+/// Control chars count as width 0.
+fn grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return 0,
+    };
+    if is_regional_indicator(first) && chars.next().map_or(false, is_regional_indicator) {
+        2
+    } else {
+        UnicodeWidthChar::width(first).unwrap_or(0)
+    }
+}
+
+/// Returns the longest prefix of `text` that is made of whole grapheme clusters and is at most
+/// `max_width` wide, along with its width.
+fn graphemes_with_width(text: &str, max_width: usize) -> (&str, usize) {
+    let mut width = 0;
+    let mut end = 0;
+    for (start, grapheme) in text.grapheme_indices(true) {
+        let w = grapheme_width(grapheme);
+        if width + w > max_width {
+            break;
+        }
+        width += w;
+        end = start + grapheme.len();
+    }
+    (&text[..end], width)
+}
+
+/// Returns the width of `rs_str` when displayed on screen.
+///
+/// Zero-width characters, such as joiners and bidi controls, don't count. Each grapheme cluster
+/// (e.g. a letter with combining accents, or emoji glued together with zero width joiners) is
+/// counted as a single glyph.
+/// ```
+/// use libnewsboat::utils::strwidth;
+/// assert_eq!(strwidth("abc"), 3);
+/// assert_eq!(strwidth("ＡＢＣ"), 6);
+/// assert_eq!(strwidth("a\u{200B}b"), 2);
+/// assert_eq!(strwidth("\u{1F469}\u{200D}\u{1F4BB}"), 2);
+/// ```
 pub fn strwidth(rs_str: &str) -> usize {
-    UnicodeWidthStr::width(rs_str)
+    rs_str.graphemes(true).map(grapheme_width).sum()
 }
 
 /// Returns the width of `rs_str` when displayed on screen.
@@ -329,8 +576,9 @@ pub fn strwidth_stfl(rs_str: &str) -> usize {
 /// Returns a longest substring fits to the given width.
 /// Returns an empty string if `str` is an empty string or `max_width` is zero.
 ///
-/// Each chararacter width is calculated with UnicodeWidthChar::width. If UnicodeWidthChar::width()
-/// returns None, the character width is treated as 0.
+/// The string is never cut inside a grapheme cluster, like a letter with combining accents or
+/// emoji glued together with zero width joiners; a cluster that doesn't fit is left out entirely.
+/// See `strwidth` for how wide a cluster is.
 /// ```
 /// use libnewsboat::utils::substr_with_width;
 /// assert_eq!(substr_with_width("a", 1), "a");
@@ -340,26 +588,14 @@ pub fn strwidth_stfl(rs_str: &str) -> usize {
 /// assert_eq!(substr_with_width("A\u{3042}B\u{3044}C\u{3046}", 5), "A\u{3042}B")
 ///```
 pub fn substr_with_width(string: &str, max_width: usize) -> String {
-    let mut result = String::new();
-    let mut width = 0;
-    for c in string.chars() {
-        // Control chars count as width 0
-        let w = UnicodeWidthChar::width(c).unwrap_or(0);
-        if width + w > max_width {
-            break;
-        }
-        width += w;
-        result.push(c);
-    }
-    result
+    graphemes_with_width(string, max_width).0.to_string()
 }
 
 /// Returns a longest substring fits to the given width.
 /// Returns an empty string if `str` is an empty string or `max_width` is zero.
 ///
-/// Each chararacter width is calculated with UnicodeWidthChar::width. If UnicodeWidthChar::width()
-/// returns None, the character width is treated as 0. A STFL tag (e.g. `<b>`, `<foobar>`, `</>`)
-/// width is treated as 0, but escaped less-than (`<>`) width is treated as 1.
+/// Like `substr_with_width`, this never cuts inside a grapheme cluster. A STFL tag (e.g. `<b>`,
+/// `<foobar>`, `</>`) width is treated as 0, but escaped less-than (`<>`) width is treated as 1.
 /// ```
 /// use libnewsboat::utils::substr_with_width_stfl;
 /// assert_eq!(substr_with_width_stfl("a", 1), "a");
@@ -370,37 +606,34 @@ pub fn substr_with_width(string: &str, max_width: usize) -> String {
 ///```
 pub fn substr_with_width_stfl(string: &str, max_width: usize) -> String {
     let mut result = String::new();
-    let mut in_bracket = false;
-    let mut tagbuf = Vec::<char>::new();
     let mut width = 0;
-    for c in string.chars() {
-        if in_bracket {
-            tagbuf.push(c);
-            if c == '>' {
-                in_bracket = false;
-                if tagbuf == ['<', '>'] {
-                    if width + 1 > max_width {
-                        break;
-                    }
-                    result += "<>"; // escaped less-than
-                    tagbuf.clear();
-                    width += 1;
-                } else {
-                    result += &tagbuf.iter().collect::<String>();
-                    tagbuf.clear();
+    let mut rest = string;
+    while !rest.is_empty() {
+        let text_end = rest.find('<').unwrap_or_else(|| rest.len());
+        let (text, text_width) = graphemes_with_width(&rest[..text_end], max_width - width);
+        result += text;
+        width += text_width;
+        if text.len() < text_end {
+            break;
+        }
+
+        rest = &rest[text_end..];
+        match rest.find('>') {
+            // Found "<>" which stfl uses to encode a literal '<'
+            Some(1) => {
+                if width + 1 > max_width {
+                    break;
                 }
+                result += "<>";
+                width += 1;
+                rest = &rest[2..];
             }
-        } else if c == '<' {
-            in_bracket = true;
-            tagbuf.push(c);
-        } else {
-            // Control chars count as width 0
-            let w = UnicodeWidthChar::width(c).unwrap_or(0);
-            if width + w > max_width {
-                break;
+            Some(tag_end) => {
+                result += &rest[..=tag_end];
+                rest = &rest[tag_end + 1..];
             }
-            width += w;
-            result.push(c);
+            // '<' without closing '>', so drop the rest of the string
+            None => break,
         }
     }
     result
@@ -413,6 +646,83 @@ pub fn remove_soft_hyphens(text: &mut String) {
     text.retain(|c| c != '\u{00AD}')
 }
 
+/// Returns `true` if `c` is a Unicode bidirectional formatting character.
+///
+/// These are the embeddings and overrides (LRE, RLE, LRO, RLO, PDF), the isolates (LRI, RLI, FSI,
+/// PDI), and the marks (LRM, RLM, ALM). They are invisible, but reorder the text around them: an
+/// RLO can make "exe.txt" look like "txt.exe".
+pub fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Returns `true` if `c` is a bidi control or an invisible zero-width character (space, joiner
+/// etc.)
+fn is_invisible(c: char) -> bool {
+    is_bidi_control(c) || matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// Removes all bidi control characters (see `is_bidi_control`) from `text`.
+///
+/// Zero width joiners are kept, as they are needed to display some emoji.
+pub fn strip_bidi_controls(text: &str) -> String {
+    text.chars().filter(|&c| !is_bidi_control(c)).collect()
+}
+
+/// Replaces bidi control characters (see `is_bidi_control`) with their code points, e.g. U+202E
+/// becomes "<U+202E>". That way they are visible, but can't reorder the text anymore.
+pub fn escape_bidi_controls(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_bidi_control(c) {
+            result.push_str(&format!("<U+{:04X}>", c as u32));
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Returns `true` if `url` might lead somewhere other than it appears to when displayed.
+///
+/// That's the case if the URL contains invisible characters (bidi controls or zero-width ones),
+/// either verbatim or percent-encoded, or if its host is an internationalized domain name, i.e.
+/// the name that is displayed isn't the punycode that will actually be looked up.
+pub fn is_deceptive_url(url: &str) -> bool {
+    let decoded = percent_decode_str(url).decode_utf8_lossy();
+    if url.chars().any(is_invisible) || decoded.chars().any(is_invisible) {
+        return true;
+    }
+
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .map(|host| host.split('.').any(|label| label.starts_with("xn--")))
+        .unwrap_or(false)
+}
+
+/// Formats a line of the URL view, which lists the links found in an article.
+///
+/// Bidi control characters in `url` are removed if `strip_bidi` is `true`, and escaped otherwise;
+/// either way, they can't reorder the line. URLs that might be deceptive (see `is_deceptive_url`)
+/// are marked with an exclamation mark after their number.
+/// ```
+/// use libnewsboat::utils::format_urlview_line;
+/// assert_eq!(format_urlview_line(1, "https://example.com", true), " 1  https://example.com");
+/// assert_eq!(format_urlview_line(12, "https://ex\u{200B}ample.com", true), "12! https://ex\u{200B}ample.com");
+/// ```
+pub fn format_urlview_line(number: usize, url: &str, strip_bidi: bool) -> String {
+    let marker = if is_deceptive_url(url) { "!" } else { " " };
+    let displayed_url = if strip_bidi {
+        strip_bidi_controls(url)
+    } else {
+        escape_bidi_controls(url)
+    };
+    format!("{:2}{} {}", number, marker, displayed_url)
+}
+
 /// An array of "MIME matchers" and their associated LinkTypes
 ///
 /// This is used for two tasks:
@@ -472,6 +782,7 @@ pub fn get_auth_method(method: &str) -> c_ulong {
         "any" | "" => curl_sys::CURLAUTH_ANY,
         _ => {
             log!(
+                "utils",
                 Level::UserError,
                 "utils::get_auth_method: you configured an invalid proxy authentication method: {}",
                 method
@@ -515,6 +826,7 @@ pub fn run_command(cmd: &str, param: &str) {
             -1 => {
                 // Parent process, fork failed.
                 log!(
+                    "utils",
                     Level::Debug,
                     "utils::run_command: failed to fork. Aborting run_command."
                 );
@@ -527,7 +839,7 @@ pub fn run_command(cmd: &str, param: &str) {
                 // Parent process, fork succeeded: reap child and return.
                 let mut status = 0;
                 if waitpid(forked_pid, &mut status, 0) == -1 {
-                    log!(Level::Debug, "utils::run_command: waitpid failed.");
+                    log!("utils", Level::Debug, "utils::run_command: waitpid failed.");
                 }
                 return;
             }
@@ -545,6 +857,7 @@ pub fn run_command(cmd: &str, param: &str) {
                     execvp(c_cmd.as_ptr(), c_arg.as_ptr());
                 }
                 _ => log!(
+                    "utils",
                     Level::UserError,
                     "Conversion of \"{}\" and/or \"{}\" to CString failed.",
                     cmd,
@@ -571,6 +884,7 @@ pub fn run_program(cmd_with_args: &[&str], input: &str) -> String {
         .spawn()
         .map_err(|error| {
             log!(
+                "utils",
                 Level::Debug,
                 "utils::run_program: spawning a child for \"{:?}\" \
                  with input \"{}\" failed: {}",
@@ -583,6 +897,7 @@ pub fn run_program(cmd_with_args: &[&str], input: &str) -> String {
             if let Some(stdin) = child.stdin.as_mut() {
                 if let Err(error) = stdin.write_all(input.as_bytes()) {
                     log!(
+                        "utils",
                         Level::Debug,
                         "utils::run_program: failed to write to child's stdin: {}",
                         error
@@ -594,6 +909,7 @@ pub fn run_program(cmd_with_args: &[&str], input: &str) -> String {
                 .wait_with_output()
                 .map_err(|error| {
                     log!(
+                        "utils",
                         Level::Debug,
                         "utils::run_program: failed to read child's stdout: {}",
                         error
@@ -604,6 +920,268 @@ pub fn run_program(cmd_with_args: &[&str], input: &str) -> String {
         .unwrap_or_else(|_| String::new())
 }
 
+/// How long a program gets to exit after `SIGTERM` before it's killed with `SIGKILL`.
+const KILL_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// How often `run_program_with_timeout` checks if the program has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// What a program printed, and how it exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CmdOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub status: ExitStatus,
+}
+
+/// Why `run_program_with_timeout` has no output to return.
+#[derive(Debug)]
+pub enum CmdError {
+    /// The program couldn't be started.
+    Spawn(io::Error),
+    /// Waiting for the program failed.
+    Io(io::Error),
+    /// The program didn't exit in time, and was killed. `stdout` holds what it printed before
+    /// that.
+    TimedOut { stdout: String },
+}
+
+impl fmt::Display for CmdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CmdError::Spawn(error) | CmdError::Io(error) => write!(f, "{}", error),
+            CmdError::TimedOut { .. } => write!(f, "{}", gettext("the program timed out")),
+        }
+    }
+}
+
+/// Reads `source` to the end on a thread of its own, so that a program that fills one of its
+/// pipes doesn't block. What's read so far can be looked at any time.
+struct PipeReader {
+    data: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<()>,
+}
+
+impl PipeReader {
+    fn new<R: Read + Send + 'static>(source: Option<R>) -> PipeReader {
+        let data = Arc::new(Mutex::new(Vec::new()));
+        let (done_tx, done) = mpsc::channel();
+        if let Some(mut source) = source {
+            let data = Arc::clone(&data);
+            thread::spawn(move || {
+                let mut buffer = [0u8; 4096];
+                loop {
+                    match source.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(length) => data.lock().unwrap().extend_from_slice(&buffer[..length]),
+                        Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+                        Err(_) => break,
+                    }
+                }
+                let _ = done_tx.send(());
+            });
+        }
+        PipeReader { data, done }
+    }
+
+    /// Waits at most `timeout` for the pipe to be closed, and returns what was read.
+    ///
+    /// The pipe can stay open after the program exits if it started a program of its own, so
+    /// this doesn't wait forever.
+    fn finish(self, timeout: Duration) -> String {
+        let _ = self.done.recv_timeout(timeout);
+        let data = self.data.lock().unwrap();
+        String::from_utf8_lossy(&data).into_owned()
+    }
+}
+
+/// Like `run_program`, but gives up on the program if it runs longer than `timeout`: it's sent
+/// `SIGTERM`, and if it doesn't exit within a short grace period, `SIGKILL`. Unlike
+/// `run_program`, this also returns the program's stderr and exit status.
+pub fn run_program_with_timeout(
+    argv: &[&str],
+    input: &str,
+    timeout: Duration,
+) -> Result<CmdOutput, CmdError> {
+    if argv.is_empty() {
+        return Err(CmdError::Spawn(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no program to run",
+        )));
+    }
+
+    let mut child = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(CmdError::Spawn)?;
+
+    // The program might not read all of its input, or read it only after printing something,
+    // so the input is written on a thread of its own. Dropping stdin closes it once written.
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_string();
+        thread::spawn(move || {
+            if let Err(error) = stdin.write_all(input.as_bytes()) {
+                log!(
+                    "utils",
+                    Level::Debug,
+                    "utils::run_program_with_timeout: failed to write to child's stdin: {}",
+                    error
+                );
+            }
+        });
+    }
+    let stdout = PipeReader::new(child.stdout.take());
+    let stderr = PipeReader::new(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                log!(
+                    "utils",
+                    Level::Info,
+                    "utils::run_program_with_timeout: {:?} didn't exit within {:?}, killing it",
+                    argv,
+                    timeout
+                );
+                terminate(&mut child);
+                return Err(CmdError::TimedOut {
+                    stdout: stdout.finish(KILL_GRACE_PERIOD),
+                });
+            }
+            Err(error) => return Err(CmdError::Io(error)),
+        }
+    };
+
+    Ok(CmdOutput {
+        stdout: stdout.finish(KILL_GRACE_PERIOD),
+        stderr: stderr.finish(KILL_GRACE_PERIOD),
+        status,
+    })
+}
+
+/// Sends `SIGTERM` to `child`, then `SIGKILL` if it's still running after `KILL_GRACE_PERIOD`,
+/// and reaps it.
+fn terminate(child: &mut Child) {
+    // `Child::id()` is the PID of a child that hasn't been reaped yet, so it fits into `pid_t`
+    // and can't belong to another process.
+    unsafe {
+        kill(child.id() as pid_t, SIGTERM);
+    }
+
+    let deadline = Instant::now() + KILL_GRACE_PERIOD;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Tells `get_command_output_lines` whether to keep reading the program's output.
+///
+/// This mirrors `std::ops::ControlFlow`, which needs a newer Rust than we support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow<B> {
+    Continue,
+    Break(B),
+}
+
+/// Runs `cmd` (the program and its arguments), writes `input` to its stdin, and calls `on_line`
+/// for each line of its stdout as soon as that line is printed.
+///
+/// Lines are passed without the trailing `\n`; the last line is passed even if it isn't
+/// terminated. Bytes that aren't valid UTF-8 are replaced with U+FFFD. If `on_line` returns
+/// `ControlFlow::Break`, the program is killed and nothing more is read. The program's stderr is
+/// discarded.
+pub fn get_command_output_lines<F>(
+    cmd: &[&str],
+    input: Option<&str>,
+    mut on_line: F,
+) -> io::Result<ExitStatus>
+where
+    F: FnMut(&str) -> ControlFlow<()>,
+{
+    if cmd.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no program to run",
+        ));
+    }
+
+    let mut child = Command::new(cmd[0])
+        .args(&cmd[1..])
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Written on a thread of its own, so that a program that prints before it's done reading
+    // doesn't block us both. Dropping stdin closes it once written.
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        let input = input.to_string();
+        thread::spawn(move || {
+            if let Err(error) = stdin.write_all(input.as_bytes()) {
+                log!(
+                    "utils",
+                    Level::Debug,
+                    "utils::get_command_output_lines: failed to write to child's stdin: {}",
+                    error
+                );
+            }
+        });
+    }
+
+    let mut stdout = match child.stdout.take() {
+        Some(stdout) => BufReader::new(stdout),
+        None => return child.wait(),
+    };
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let length = match stdout.read_until(b'\n', &mut line) {
+            Ok(length) => length,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(error);
+            }
+        };
+        if length == 0 {
+            break;
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+
+        if on_line(&String::from_utf8_lossy(&line)) == ControlFlow::Break(()) {
+            log!(
+                "utils",
+                Level::Debug,
+                "utils::get_command_output_lines: stopped reading from {:?}, killing it",
+                cmd
+            );
+            let _ = child.kill();
+            break;
+        }
+    }
+
+    child.wait()
+}
+
 pub fn make_title(rs_str: String) -> String {
     /* Sometimes it is possible to construct the title from the URL
      * This attempts to do just that. eg:
@@ -663,13 +1241,18 @@ pub fn make_title(rs_str: String) -> String {
 /// // that it returns just the lowest 8 bits.
 /// ```
 pub fn run_interactively(command: &str, caller: &str) -> Option<u8> {
-    log!(Level::Debug, &format!("{}: running `{}'", caller, command));
+    log!(
+        "utils",
+        Level::Debug,
+        &format!("{}: running `{}'", caller, command)
+    );
     Command::new("sh")
         .arg("-c")
         .arg(command)
         .status()
         .map_err(|err| {
             log!(
+                "utils",
                 Level::Warn,
                 &format!("{}: Couldn't create child process: {}", caller, err)
             )
@@ -685,8 +1268,82 @@ pub fn getcwd() -> Result<PathBuf, io::Error> {
     env::current_dir()
 }
 
-pub fn strnaturalcmp(a: &str, b: &str) -> std::cmp::Ordering {
-    natord::compare(a, b)
+/// Compares strings in "natural" order: runs of digits are compared by their numeric value, so
+/// "Feed 2" comes before "Feed 10", and everything else is compared case-insensitively, so
+/// "Épisode 2" comes before "épisode 10".
+///
+/// Numbers are compared digit by digit, so they can be arbitrarily long. Numbers that differ only
+/// in leading zeros, and strings that differ only in case, are ordered by the first such
+/// difference, so that the order is total: only equal strings compare equal.
+pub fn strnaturalcmp(a: &str, b: &str) -> Ordering {
+    let left = casefold(a);
+    let right = casefold(b);
+
+    // Ordering of the numbers that were equal except for their leading zeros
+    let mut leading_zeros = Ordering::Equal;
+
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i].is_ascii_digit() && right[j].is_ascii_digit() {
+            let l = digit_run(&left[i..]);
+            let r = digit_run(&right[j..]);
+            i += l.len();
+            j += r.len();
+
+            let l_value = trim_leading_zeros(l);
+            let r_value = trim_leading_zeros(r);
+            let ordering = l_value
+                .len()
+                .cmp(&r_value.len())
+                .then_with(|| l_value.cmp(r_value));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            if leading_zeros == Ordering::Equal {
+                // Fewer leading zeros come first
+                leading_zeros = l.len().cmp(&r.len());
+            }
+        } else {
+            let ordering = left[i].cmp(&right[j]);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    (left.len() - i)
+        .cmp(&(right.len() - j))
+        .then(leading_zeros)
+        .then_with(|| a.cmp(b))
+}
+
+/// Case-folds `input`, approximating Unicode's full case folding by upper- and then lowercasing
+/// each character. That makes e.g. "ß" equal to "SS", and "ς" equal to "Σ".
+fn casefold(input: &str) -> Vec<char> {
+    input
+        .chars()
+        .flat_map(char::to_uppercase)
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The ASCII digits at the start of `chars`.
+fn digit_run(chars: &[char]) -> &[char] {
+    let end = chars
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(chars.len());
+    &chars[..end]
+}
+
+fn trim_leading_zeros(digits: &[char]) -> &[char] {
+    let start = digits
+        .iter()
+        .position(|&c| c != '0')
+        .unwrap_or(digits.len());
+    &digits[start..]
 }
 
 /// Calculate the number of padding tabs when formatting columns
@@ -736,46 +1393,314 @@ pub fn mkdir_parents<R: AsRef<Path>>(p: &R, mode: u32) -> io::Result<()> {
         .create(p.as_ref())
 }
 
-/// The tag and Git commit ID the program was built from, or a pre-defined value from config.h if
-/// there is no Git directory.
-pub fn program_version() -> String {
-    // NEWSBOAT_VERSION is set by this crate's build script, "build.rs"
-    env!("NEWSBOAT_VERSION").to_string()
+/// Why `atomic_write` didn't replace a file. Either way, the file is left as it was.
+#[derive(Debug)]
+pub enum AtomicWriteError {
+    /// The new contents couldn't be written, or couldn't replace the file.
+    Io(io::Error),
+    /// The new contents didn't pass validation. They're kept in the file at `kept_at`, next to the
+    /// original, so the user can look at them.
+    Invalid { reason: String, kept_at: PathBuf },
+    /// The file at the given path has no write permissions, or it's in a directory or on
+    /// a filesystem that can't be written to. Nothing was written.
+    ReadOnly(PathBuf),
 }
 
-/// Newsboat's major version number.
-pub fn newsboat_major_version() -> u32 {
-    // This will panic if the version couldn't be parsed, which is virtually impossible as Cargo
-    // won't even start compilation if it couldn't parse the version.
-    env!("CARGO_PKG_VERSION_MAJOR").parse::<u32>().unwrap()
+impl fmt::Display for AtomicWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AtomicWriteError::Io(error) => write!(f, "{}", error),
+            AtomicWriteError::Invalid { reason, kept_at } => write!(
+                f,
+                "{}",
+                strprintf::fmt!(
+                    &gettext("%s (the new contents were kept in %s)"),
+                    reason.as_str(),
+                    kept_at.to_string_lossy().into_owned()
+                )
+            ),
+            AtomicWriteError::ReadOnly(path) => write!(
+                f,
+                "{}",
+                strprintf::fmt!(
+                    &gettext("%s is read-only"),
+                    path.to_string_lossy().into_owned()
+                )
+            ),
+        }
+    }
 }
 
-/// Returns the part of the string before first # character (or the whole input string if there are
-/// no # character in it). Pound characters inside double quotes and backticks are ignored.
-pub fn strip_comments(line: &str) -> &str {
-    let mut prev_was_backslash = false;
-    let mut inside_quotes = false;
-    let mut inside_backticks = false;
+impl From<io::Error> for AtomicWriteError {
+    fn from(error: io::Error) -> AtomicWriteError {
+        AtomicWriteError::Io(error)
+    }
+}
 
-    let mut first_pound_chr_idx = line.len();
+impl From<AtomicWriteError> for io::Error {
+    fn from(error: AtomicWriteError) -> io::Error {
+        match error {
+            AtomicWriteError::Io(error) => error,
+            AtomicWriteError::ReadOnly(path) => io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                AtomicWriteError::ReadOnly(path).to_string(),
+            ),
+            invalid => io::Error::new(io::ErrorKind::InvalidData, invalid.to_string()),
+        }
+    }
+}
 
-    for (idx, chr) in line.char_indices() {
-        match chr {
-            '\\' => {
-                prev_was_backslash = true;
-                continue;
-            }
-            '"' => {
-                // If the quote is escaped or we're inside backticks, do nothing
-                if !prev_was_backslash && !inside_backticks {
-                    inside_quotes = !inside_quotes;
-                }
-            }
-            '`' => {
-                // If the backtick is escaped, do nothing
-                if !prev_was_backslash {
-                    inside_backticks = !inside_backticks;
-                }
+/// Replaces the contents of the file at `path` with `contents`, so that the file has either the
+/// old contents or the new ones even if Newsboat crashes or the power goes out.
+///
+/// The contents are written to a temporary file in the same directory, which is synced and then
+/// renamed over the original. If `path` is a symlink, the file it points to is replaced, and the
+/// symlink stays as it is. The file keeps its permissions and, where possible, its owner; a new
+/// file gets `mode` (minus the umask).
+///
+/// A file without any write permissions isn't replaced, even though the rename would work: that's
+/// how files are marked as not to be touched, e.g. in a Nix store. Like a file in a directory or on
+/// a filesystem that can't be written to, it's reported as `AtomicWriteError::ReadOnly` before
+/// anything is written.
+pub fn atomic_write<P: AsRef<Path>>(
+    path: P,
+    contents: &[u8],
+    mode: u32,
+) -> Result<(), AtomicWriteError> {
+    atomic_write_validated(path, contents, mode, |_| Ok(()))
+}
+
+/// Like `atomic_write`, but `validate` is called with the path of the temporary file before it
+/// replaces the original. If it returns an error, the original is left alone, and the temporary
+/// file is kept for inspection.
+pub fn atomic_write_validated<P, F>(
+    path: P,
+    contents: &[u8],
+    mode: u32,
+    validate: F,
+) -> Result<(), AtomicWriteError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&Path) -> Result<(), String>,
+{
+    write_atomically(path.as_ref(), contents, mode, validate, |_| Ok(()))
+}
+
+/// Does the work of `atomic_write_validated`. `before_rename` runs right before the temporary file
+/// is renamed; tests use it to simulate a crash at that point.
+fn write_atomically<F, H>(
+    path: &Path,
+    contents: &[u8],
+    mode: u32,
+    validate: F,
+    before_rename: H,
+) -> Result<(), AtomicWriteError>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+    H: FnOnce(&Path) -> io::Result<()>,
+{
+    let is_symlink = fs::symlink_metadata(path)
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false);
+    let target = if is_symlink {
+        fs::canonicalize(path)?
+    } else {
+        path.to_owned()
+    };
+    let existing = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    if is_read_only(&target, existing.as_ref()) {
+        return Err(AtomicWriteError::ReadOnly(target));
+    }
+
+    let (tmp_path, mut file) = create_sibling_file(&target, mode)?;
+    let written = file.write_all(contents).and_then(|_| {
+        if let Some(ref metadata) = existing {
+            file.set_permissions(metadata.permissions())?;
+            // Only root can give files away, so this fails for most users. The file still ends
+            // up owned by whoever runs Newsboat, which is usually who owned it in the first place.
+            unsafe { libc::fchown(file.as_raw_fd(), metadata.uid(), metadata.gid()) };
+        }
+        file.sync_all()
+    });
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+
+    if let Err(reason) = validate(&tmp_path) {
+        return Err(AtomicWriteError::Invalid {
+            reason,
+            kept_at: tmp_path,
+        });
+    }
+
+    if let Err(e) = before_rename(&tmp_path).and_then(|_| fs::rename(&tmp_path, &target)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+
+    // Makes the rename itself durable. Some filesystems can't sync directories; the file is in
+    // place either way, so that's not worth failing over.
+    let dir = target
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    if let Err(e) = File::open(dir).and_then(|dir| dir.sync_all()) {
+        log!(
+            "utils",
+            Level::Debug,
+            &format!(
+                "utils::atomic_write: couldn't sync directory {:?}: {}",
+                dir, e
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Whether `atomic_write` has to leave `target` alone. `existing` is its metadata, if it exists.
+fn is_read_only(target: &Path, existing: Option<&fs::Metadata>) -> bool {
+    if existing
+        .map(|metadata| metadata.permissions().readonly())
+        .unwrap_or(false)
+    {
+        return true;
+    }
+
+    let dir = target
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let dir = match CString::new(dir.as_os_str().as_bytes()) {
+        Ok(dir) => dir,
+        Err(_) => return false,
+    };
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(dir.as_ptr(), &mut stats) } == 0
+        && stats.f_flag & libc::ST_RDONLY != 0
+    {
+        return true;
+    }
+    // Other errors, like a missing directory, are reported once the file is created
+    if unsafe { libc::access(dir.as_ptr(), libc::W_OK) } != 0 {
+        let error = io::Error::last_os_error().raw_os_error();
+        return error == Some(libc::EACCES) || error == Some(libc::EROFS);
+    }
+    false
+}
+
+/// Creates a new file next to `path`, with a name that starts with a dot and the name of `path`.
+fn create_sibling_file(path: &Path, mode: u32) -> io::Result<(PathBuf, File)> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut attempt = 0;
+    loop {
+        let tmp_path = path.with_file_name(format!(
+            ".{}.newsboat-{}-{}",
+            name,
+            std::process::id(),
+            attempt
+        ));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&tmp_path)
+        {
+            Ok(file) => return Ok((tmp_path, file)),
+            // Left behind by a previous write that failed validation
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Checks that the file at `path` is a valid urls file: every line that isn't empty or a comment
+/// starts with a feed URL, a query feed or a filter.
+pub fn validate_urls_file(path: &Path) -> Result<(), String> {
+    let contents = fs::read(path).map_err(|e| e.to_string())?;
+    let contents = String::from_utf8(contents).map_err(|e| {
+        let line = contents_line(e.as_bytes(), e.utf8_error().valid_up_to());
+        strprintf::fmt!(&gettext("line %u: invalid UTF-8"), line)
+    })?;
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let url = line.split_whitespace().next().unwrap_or("");
+        let url = url.trim_matches('"');
+        if !url.contains("://") && !is_special_url(url) {
+            return Err(strprintf::fmt!(
+                &gettext("line %u: `%s' isn't a URL"),
+                (i + 1) as u32,
+                url
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Number of the line that contains the byte at `offset`.
+fn contents_line(contents: &[u8], offset: usize) -> u32 {
+    contents[..offset]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count() as u32
+        + 1
+}
+
+/// The tag and Git commit ID the program was built from, or a pre-defined value from config.h if
+/// there is no Git directory.
+pub fn program_version() -> String {
+    // NEWSBOAT_VERSION is set by this crate's build script, "build.rs"
+    env!("NEWSBOAT_VERSION").to_string()
+}
+
+/// Newsboat's major version number.
+pub fn newsboat_major_version() -> u32 {
+    // This will panic if the version couldn't be parsed, which is virtually impossible as Cargo
+    // won't even start compilation if it couldn't parse the version.
+    env!("CARGO_PKG_VERSION_MAJOR").parse::<u32>().unwrap()
+}
+
+/// Returns the part of the string before first # character (or the whole input string if there are
+/// no # character in it). Pound characters inside double quotes and backticks are ignored.
+///
+/// A backslash escapes the character that follows it, so `\#` is a literal pound character and
+/// `\"` doesn't start or end a quoted region; `\\` is an escaped backslash, which escapes nothing.
+/// Quotes that aren't closed extend to the end of the line, so nothing after them is a comment.
+pub fn strip_comments(line: &str) -> &str {
+    let mut prev_was_backslash = false;
+    let mut inside_quotes = false;
+    let mut inside_backticks = false;
+
+    let mut first_pound_chr_idx = line.len();
+
+    for (idx, chr) in line.char_indices() {
+        match chr {
+            '\\' => {
+                // An escaped backslash doesn't escape the character that follows it
+                prev_was_backslash = !prev_was_backslash;
+                continue;
+            }
+            '"' => {
+                // If the quote is escaped or we're inside backticks, do nothing
+                if !prev_was_backslash && !inside_backticks {
+                    inside_quotes = !inside_quotes;
+                }
+            }
+            '`' => {
+                // If the backtick is escaped, do nothing
+                if !prev_was_backslash {
+                    inside_backticks = !inside_backticks;
+                }
             }
             '#' => {
                 if !prev_was_backslash && !inside_quotes && !inside_backticks {
@@ -794,6 +1719,136 @@ pub fn strip_comments(line: &str) -> &str {
     &line[0..first_pound_chr_idx]
 }
 
+/// An escape sequence in quoted text that doesn't stand for anything: a `\x` or `\u` escape whose
+/// digits aren't hexadecimal, or whose code point isn't a character. The NUL character counts as
+/// invalid too, since C strings can't hold it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidEscape {
+    /// Byte offset of the escape's backslash in the text.
+    pub offset: usize,
+}
+
+/// The character of a `\xNN`, `\uXXXX` or `\u{X...}` escape, and the length of the escape. The
+/// input starts right after the backslash. `\x` takes ASCII characters only.
+fn unicode_escape(input: &str) -> Option<(char, usize)> {
+    let (digits, len, max) = if input.starts_with('x') {
+        (input.get(1..3)?, 3, 0x7F)
+    } else if input.starts_with("u{") {
+        let end = input.find('}')?;
+        (&input[2..end], end + 1, 0x10_FFFF)
+    } else if input.starts_with('u') {
+        (input.get(1..5)?, 5, 0xFFFF)
+    } else {
+        return None;
+    };
+
+    if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|&code| code > 0 && code <= max)
+        // `from_u32` rejects surrogates
+        .and_then(std::char::from_u32)
+        .map(|chr| (chr, len))
+}
+
+fn unescape(input: &str, escaped_backtick: &str, lenient: bool) -> Result<String, InvalidEscape> {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(backslash) = rest.find('\\') {
+        result.push_str(&rest[..backslash]);
+        let escape = &rest[backslash + 1..];
+        let len = match escape.chars().next() {
+            // A backslash at the very end escapes nothing, and is dropped
+            None => 0,
+            Some('n') => {
+                result.push('\n');
+                1
+            }
+            Some('r') => {
+                result.push('\r');
+                1
+            }
+            Some('t') => {
+                result.push('\t');
+                1
+            }
+            Some('`') => {
+                result.push_str(escaped_backtick);
+                1
+            }
+            Some(kind @ 'x') | Some(kind @ 'u') => match unicode_escape(escape) {
+                Some((chr, len)) => {
+                    result.push(chr);
+                    len
+                }
+                None if lenient => {
+                    result.push('\\');
+                    result.push(kind);
+                    1
+                }
+                None => {
+                    return Err(InvalidEscape {
+                        offset: input.len() - rest.len() + backslash,
+                    })
+                }
+            },
+            // `\"` and `\\` stand for themselves, and so do all the other escaped characters
+            Some(chr) => {
+                result.push(chr);
+                chr.len_utf8()
+            }
+        };
+        rest = &escape[len..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Removes the escaping from the text of a double-quoted token, as found between the quotes.
+///
+/// `\n`, `\r` and `\t` turn into a new line, carriage return and tab; `\xNN`, `\uXXXX` and
+/// `\u{X...}` turn into the character with that code point. Any other escaped character stands for
+/// itself, so `\"` is a quote, `\\` is a backslash, and `\e` is just `e`. The exception is an
+/// escaped backtick, which is left escaped so that it isn't taken for the start of a shell command
+/// later on.
+///
+/// Invalid `\x` and `\u` escapes are kept as they are, and a backslash at the very end is dropped.
+pub fn unescape_quoted(input: &str) -> String {
+    match unescape(input, "\\`", true) {
+        Ok(result) => result,
+        // Lenient unescaping never fails
+        Err(_) => unreachable!(),
+    }
+}
+
+/// Like `unescape_quoted`, but an escaped backtick turns into `escaped_backtick`, and an invalid
+/// `\x` or `\u` escape is an error.
+pub fn unescape_quoted_strict(
+    input: &str,
+    escaped_backtick: &str,
+) -> Result<String, InvalidEscape> {
+    unescape(input, escaped_backtick, false)
+}
+
+/// The inverse of `unescape_quoted`: escapes the text so that it can be put between double quotes.
+/// Quotes, backslashes, and line-breaking and tab characters are escaped.
+pub fn escape_quoted(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    for chr in input.chars() {
+        match chr {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(chr),
+        }
+    }
+    result
+}
+
 /// Extract filter and url from line separated by ':'.
 pub fn extract_filter(line: &str) -> (&str, &str) {
     debug_assert!(line.starts_with("filter:"));
@@ -802,6 +1857,7 @@ pub fn extract_filter(line: &str) -> (&str, &str) {
     let (filter, url) = line.split_at(line.find(':').unwrap_or(0));
     let url = url.get(1..).unwrap_or("");
     log!(
+        "utils",
         Level::Debug,
         "utils::extract_filter: {} -> filter: {} url: {}",
         line,
@@ -970,6 +2026,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn t_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("info", "info"), 0);
+        assert_eq!(edit_distance("", "info"), 4);
+        assert_eq!(edit_distance("lisfocus", "listfocus"), 1);
+        assert_eq!(edit_distance("unerline", "underline"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("ünïcode", "unicode"), 2);
+    }
+
+    #[test]
+    fn t_suggest_returns_close_candidates_closest_first() {
+        let candidates = ["listnormal", "listfocus", "info", "list", "listfocus"];
+        assert_eq!(
+            suggest(candidates.iter().copied(), "lisfocus"),
+            vec!["listfocus"]
+        );
+        assert_eq!(suggest(candidates.iter().copied(), "lst"), vec!["list"]);
+        assert_eq!(suggest(candidates.iter().copied(), "inf"), vec!["info"]);
+        assert!(suggest(candidates.iter().copied(), "background").is_empty());
+        assert!(suggest(Vec::new(), "info").is_empty());
+    }
+
+    #[test]
+    fn t_suggest_allows_more_edits_for_longer_input() {
+        let candidates = ["reload", "reload-time"];
+        // Three edits away from "reload", but too short for that
+        assert!(suggest(candidates.iter().copied(), "rel").is_empty());
+        assert_eq!(
+            suggest(candidates.iter().copied(), "reload-t"),
+            vec!["reload", "reload-time"]
+        );
+    }
+
+    #[test]
+    fn t_suggest_returns_at_most_three_candidates_in_their_order() {
+        let candidates = ["F1", "F2", "F3", "F10", "F11"];
+        assert_eq!(
+            suggest(candidates.iter().copied(), "F13"),
+            vec!["F1", "F3", "F10"]
+        );
+    }
+
     #[test]
     fn t_is_valid_color() {
         let invalid = [
@@ -1260,6 +2360,271 @@ mod tests {
         );
     }
 
+    fn collect_lines(cmd: &[&str], input: Option<&str>) -> (Vec<String>, ExitStatus) {
+        let mut lines = Vec::new();
+        let status = get_command_output_lines(cmd, input, |line| {
+            lines.push(line.to_string());
+            ControlFlow::Continue
+        })
+        .unwrap();
+        (lines, status)
+    }
+
+    #[test]
+    fn t_get_command_output_lines_passes_each_line_to_the_callback() {
+        let (lines, status) = collect_lines(&["printf", "first\\nsecond\\n\\nlast"], None);
+        assert_eq!(lines, vec!["first", "second", "", "last"]);
+        assert!(status.success());
+
+        let (lines, _) = collect_lines(&["printf", "terminated\\n"], None);
+        assert_eq!(lines, vec!["terminated"]);
+
+        let (lines, _) = collect_lines(&["true"], None);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn t_get_command_output_lines_writes_input_to_the_program() {
+        let (lines, status) = collect_lines(&["cat"], Some("this is a multi-line\ntest string"));
+        assert_eq!(lines, vec!["this is a multi-line", "test string"]);
+        assert!(status.success());
+
+        let (lines, status) = collect_lines(&["sh", "-c", "exit 3"], Some(""));
+        assert!(lines.is_empty());
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn t_get_command_output_lines_replaces_invalid_utf8() {
+        let (lines, _) = collect_lines(&["printf", "caf\\351\\nok"], None);
+        assert_eq!(lines, vec!["caf\u{FFFD}", "ok"]);
+    }
+
+    #[test]
+    fn t_get_command_output_lines_kills_the_program_when_asked_to_stop() {
+        let start = Instant::now();
+        let mut lines = Vec::new();
+        let status = get_command_output_lines(&["yes", "y"], None, |line| {
+            lines.push(line.to_string());
+            if lines.len() == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue
+            }
+        })
+        .unwrap();
+
+        assert_eq!(lines, vec!["y", "y", "y"]);
+        assert!(!status.success());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn t_get_command_output_lines_fails_if_program_cannot_be_started() {
+        let result = get_command_output_lines(
+            &["a-program-that-is-guaranteed-to-not-exists"],
+            None,
+            |_| ControlFlow::Continue,
+        );
+        assert!(result.is_err());
+
+        let result = get_command_output_lines(&[], None, |_| ControlFlow::Continue);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn t_run_program_with_timeout_returns_output_and_status() {
+        let input = "this is a multi-line\ntest string";
+        let output = run_program_with_timeout(&["cat"], input, Duration::from_secs(10)).unwrap();
+        assert_eq!(output.stdout, input);
+        assert_eq!(output.stderr, "");
+        assert!(output.status.success());
+
+        let output = run_program_with_timeout(
+            &["sh", "-c", "echo out; echo err >&2; exit 3"],
+            "",
+            Duration::from_secs(10),
+        )
+        .unwrap();
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn t_run_program_with_timeout_doesnt_need_the_program_to_read_its_input() {
+        let input = "x".repeat(1024 * 1024);
+        let output =
+            run_program_with_timeout(&["echo", "ignored"], &input, Duration::from_secs(10))
+                .unwrap();
+        assert_eq!(output.stdout, "ignored\n");
+    }
+
+    #[test]
+    fn t_run_program_with_timeout_kills_programs_that_take_too_long() {
+        let start = Instant::now();
+        let result = run_program_with_timeout(
+            &["sh", "-c", "echo partial; exec sleep 10"],
+            "",
+            Duration::from_millis(500),
+        );
+        let runtime = start.elapsed();
+
+        match result {
+            Err(CmdError::TimedOut { stdout }) => assert_eq!(stdout, "partial\n"),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        assert!(runtime >= Duration::from_millis(500));
+        assert!(runtime < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn t_run_program_with_timeout_kills_programs_that_ignore_sigterm() {
+        let start = Instant::now();
+        let result = run_program_with_timeout(
+            &["sh", "-c", "trap '' TERM; echo stubborn; exec sleep 10"],
+            "",
+            Duration::from_millis(200),
+        );
+        let runtime = start.elapsed();
+
+        match result {
+            Err(CmdError::TimedOut { stdout }) => assert_eq!(stdout, "stubborn\n"),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        assert!(runtime >= Duration::from_millis(200) + KILL_GRACE_PERIOD);
+        assert!(runtime < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn t_run_program_with_timeout_reports_programs_that_cant_be_started() {
+        let result = run_program_with_timeout(
+            &["a-program-that-is-guaranteed-to-not-exists"],
+            "",
+            Duration::from_secs(10),
+        );
+        assert!(matches!(result, Err(CmdError::Spawn(_))));
+
+        let result = run_program_with_timeout(&[], "", Duration::from_secs(10));
+        assert!(matches!(result, Err(CmdError::Spawn(_))));
+    }
+
+    /// The examples of section 5.4 of RFC 3986, resolved against its base URL.
+    const RFC3986_BASE_URL: &str = "http://a/b/c/d;p?q";
+    const RFC3986_EXAMPLES: &[(&str, &str)] = &[
+        // 5.4.1. Normal Examples
+        ("g:h", "g:h"),
+        ("g", "http://a/b/c/g"),
+        ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"),
+        ("/g", "http://a/g"),
+        // RFC 3986 gives "http://g", which is the same URL (section 6.2.3); the URL standard
+        // always writes the path of HTTP URLs.
+        ("//g", "http://g/"),
+        ("?y", "http://a/b/c/d;p?y"),
+        ("g?y", "http://a/b/c/g?y"),
+        ("#s", "http://a/b/c/d;p?q#s"),
+        ("g#s", "http://a/b/c/g#s"),
+        ("g?y#s", "http://a/b/c/g?y#s"),
+        (";x", "http://a/b/c/;x"),
+        ("g;x", "http://a/b/c/g;x"),
+        ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+        ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"),
+        ("./", "http://a/b/c/"),
+        ("..", "http://a/b/"),
+        ("../", "http://a/b/"),
+        ("../g", "http://a/b/g"),
+        ("../..", "http://a/"),
+        ("../../", "http://a/"),
+        ("../../g", "http://a/g"),
+        // 5.4.2. Abnormal Examples
+        ("../../../g", "http://a/g"),
+        ("../../../../g", "http://a/g"),
+        ("/./g", "http://a/g"),
+        ("/../g", "http://a/g"),
+        ("g.", "http://a/b/c/g."),
+        (".g", "http://a/b/c/.g"),
+        ("g..", "http://a/b/c/g.."),
+        ("..g", "http://a/b/c/..g"),
+        ("./../g", "http://a/b/g"),
+        ("./g/.", "http://a/b/c/g/"),
+        ("g/./h", "http://a/b/c/g/h"),
+        ("g/../h", "http://a/b/c/h"),
+        ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+        ("g;x=1/../y", "http://a/b/c/y"),
+        ("g?y/./x", "http://a/b/c/g?y/./x"),
+        ("g?y/../x", "http://a/b/c/g?y/../x"),
+        ("g#s/./x", "http://a/b/c/g#s/./x"),
+        ("g#s/../x", "http://a/b/c/g#s/../x"),
+        // The RFC allows both "http:g" and this, for backward compatibility.
+        ("http:g", "http://a/b/c/g"),
+    ];
+
+    #[test]
+    fn t_absolute_url_resolves_the_examples_of_rfc3986() {
+        for (link, expected) in RFC3986_EXAMPLES {
+            assert_eq!(
+                absolute_url(RFC3986_BASE_URL, link),
+                *expected,
+                "link: {}",
+                link
+            );
+        }
+    }
+
+    #[test]
+    fn t_absolute_url_without_fragment_resolves_the_examples_of_rfc3986_without_fragments() {
+        for (link, expected) in RFC3986_EXAMPLES {
+            let expected = expected.splitn(2, '#').next().unwrap();
+            assert_eq!(
+                absolute_url_without_fragment(RFC3986_BASE_URL, link),
+                expected,
+                "link: {}",
+                link
+            );
+        }
+    }
+
+    #[test]
+    fn t_absolute_url_handles_protocol_relative_links() {
+        assert_eq!(
+            absolute_url("https://example.com/feed.xml", "//cdn.example.com/img.png"),
+            "https://cdn.example.com/img.png"
+        );
+        assert_eq!(
+            absolute_url("http://example.com/feed.xml", "//cdn.example.com/img.png"),
+            "http://cdn.example.com/img.png"
+        );
+    }
+
+    #[test]
+    fn t_empty_link_stands_for_the_base_url_without_its_fragment() {
+        assert_eq!(
+            absolute_url("http://example.com/a/b?c#d", ""),
+            "http://example.com/a/b?c"
+        );
+        assert_eq!(
+            absolute_url_without_fragment("http://example.com/a/b?c#d", ""),
+            "http://example.com/a/b?c"
+        );
+    }
+
+    #[test]
+    fn t_absolute_url_returns_the_link_if_the_base_url_is_invalid() {
+        for base_url in &["", "foo", "not a url", "http://[::1"] {
+            assert_eq!(absolute_url(base_url, "bar#baz"), "bar#baz");
+            assert_eq!(
+                absolute_url_without_fragment(base_url, "bar#baz"),
+                "bar#baz"
+            );
+            assert_eq!(
+                absolute_url_without_fragment(base_url, "//cdn.example.com/x#y"),
+                "//cdn.example.com/x#y"
+            );
+        }
+    }
+
     #[test]
     fn t_make_title() {
         let mut input = String::from("http://example.com/Item");
@@ -1316,6 +2681,72 @@ mod tests {
         assert_eq!(make_title(String::from("")), String::from(""));
     }
 
+    /// Name and home directory of the user running the tests, from the passwd database.
+    fn current_user() -> (String, PathBuf) {
+        let passwd = unsafe { libc::getpwuid(libc::getuid()) };
+        assert!(!passwd.is_null());
+        unsafe {
+            let name = CStr::from_ptr((*passwd).pw_name)
+                .to_string_lossy()
+                .into_owned();
+            let dir = OsStr::from_bytes(CStr::from_ptr((*passwd).pw_dir).to_bytes());
+            (name, PathBuf::from(dir))
+        }
+    }
+
+    #[test]
+    fn t_resolve_tilde_expands_to_the_home_directory() {
+        let home = home_dir().unwrap();
+        assert_eq!(resolve_tilde(PathBuf::from("~")), home.join(""));
+        assert_eq!(resolve_tilde(PathBuf::from("~/")), home.join(""));
+        assert_eq!(resolve_tilde(PathBuf::from("~/news/")), home.join("news"));
+        assert_eq!(
+            resolve_tilde(PathBuf::from("/home/~")),
+            PathBuf::from("/home/~")
+        );
+        assert_eq!(
+            resolve_tilde(PathBuf::from("news/~/x")),
+            PathBuf::from("news/~/x")
+        );
+    }
+
+    #[test]
+    fn t_resolve_tilde_expands_to_the_home_directory_of_a_named_user() {
+        let (name, home) = current_user();
+        let tilde_user = format!("~{}", name);
+
+        assert_eq!(resolve_tilde(PathBuf::from(&tilde_user)), home.join(""));
+        assert_eq!(
+            resolve_tilde(PathBuf::from(format!("{}/", tilde_user))),
+            home.join("")
+        );
+        assert_eq!(
+            resolve_tilde(PathBuf::from(format!("{}/bin/open.sh %u", tilde_user))),
+            home.join("bin/open.sh %u")
+        );
+    }
+
+    #[test]
+    fn t_resolve_tilde_leaves_unknown_users_alone() {
+        for path in &[
+            "~no-such-user-for-newsboat",
+            "~no-such-user-for-newsboat/news/",
+        ] {
+            assert_eq!(resolve_tilde(PathBuf::from(path)), PathBuf::from(path));
+        }
+    }
+
+    #[test]
+    fn t_home_dir_falls_back_to_the_passwd_entry() {
+        let (_, home) = current_user();
+        assert_eq!(home_dir_from(None), Some(home.clone()));
+        assert_eq!(home_dir_from(Some(OsString::new())), Some(home));
+        assert_eq!(
+            home_dir_from(Some(OsString::from("/tmp/home"))),
+            Some(PathBuf::from("/tmp/home"))
+        );
+    }
+
     #[test]
     fn t_resolve_relative() {
         assert_eq!(
@@ -1375,6 +2806,168 @@ mod tests {
         }
     }
 
+    #[test]
+    fn t_strip_bidi_controls_defeats_right_to_left_override_spoofing() {
+        // U+202E RIGHT-TO-LEFT OVERRIDE makes this display as "invoice-exe.pdf"
+        let spoofed = "invoice-\u{202E}fdp.exe";
+        assert_eq!(strip_bidi_controls(spoofed), "invoice-fdp.exe");
+        assert_eq!(escape_bidi_controls(spoofed), "invoice-<U+202E>fdp.exe");
+
+        let isolates = "\u{2068}a\u{2069}\u{2066}b\u{2069}\u{200F}";
+        assert_eq!(strip_bidi_controls(isolates), "ab");
+    }
+
+    #[test]
+    fn t_strip_bidi_controls_keeps_zero_width_joiner_emoji_intact() {
+        // WOMAN, ZWJ, PERSONAL COMPUTER: "woman technologist"
+        let emoji = "\u{1F469}\u{200D}\u{1F4BB}";
+        assert_eq!(strip_bidi_controls(emoji), emoji);
+        assert_eq!(escape_bidi_controls(emoji), emoji);
+    }
+
+    #[test]
+    fn t_strwidth_ignores_zero_width_and_bidi_control_characters() {
+        assert_eq!(strwidth("abc"), 3);
+        assert_eq!(strwidth("a\u{200B}b\u{200C}c"), 3);
+        assert_eq!(strwidth("\u{202E}abc\u{202C}"), 3);
+        assert_eq!(strwidth("\u{FEFF}abc"), 3);
+
+        let emoji = "\u{1F469}\u{200D}\u{1F4BB}";
+        assert_eq!(strwidth(emoji), 2);
+        assert_eq!(strwidth_stfl(&format!("<b>{}</>", emoji)), 2);
+        assert_eq!(
+            strwidth(&strip_bidi_controls("\u{202E}abc")),
+            strwidth("\u{202E}abc")
+        );
+    }
+
+    #[test]
+    fn t_substr_with_width_does_not_split_zero_width_joiner_sequences() {
+        let emoji = "\u{1F469}\u{200D}\u{1F4BB}";
+        let text = format!("{}x", emoji);
+        assert_eq!(substr_with_width(&text, 1), "");
+        assert_eq!(substr_with_width(&text, 2), emoji);
+        assert_eq!(substr_with_width(&text, 3), text);
+        assert_eq!(substr_with_width_stfl(&text, 2), emoji);
+    }
+
+    #[test]
+    fn t_strwidth_counts_grapheme_clusters_as_single_glyphs() {
+        // "e" followed by COMBINING ACUTE ACCENT and COMBINING DIAERESIS
+        assert_eq!(strwidth("e\u{0301}\u{0308}"), 1);
+        // THUMBS UP SIGN, EMOJI MODIFIER FITZPATRICK TYPE-4
+        assert_eq!(strwidth("\u{1F44D}\u{1F3FD}"), 2);
+        // MAN, ZWJ, WOMAN, ZWJ, GIRL, ZWJ, BOY: "family"
+        assert_eq!(
+            strwidth("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}"),
+            2
+        );
+        // REGIONAL INDICATOR SYMBOL LETTERS U and A: Ukrainian flag
+        assert_eq!(strwidth("\u{1F1FA}\u{1F1E6}"), 2);
+        assert_eq!(strwidth_stfl("<b>e\u{0301}</>\u{1F44D}\u{1F3FD}"), 3);
+    }
+
+    #[test]
+    fn t_substr_with_width_does_not_split_combining_diacritics() {
+        let text = "Cafe\u{0301} cre\u{0300}me";
+        assert_eq!(substr_with_width(text, 3), "Caf");
+        assert_eq!(substr_with_width(text, 4), "Cafe\u{0301}");
+        assert_eq!(substr_with_width(text, 8), "Cafe\u{0301} cre\u{0300}");
+
+        assert_eq!(substr_with_width_stfl(text, 4), "Cafe\u{0301}");
+        assert_eq!(
+            substr_with_width_stfl("<b>Cafe\u{0301}</>", 4),
+            "<b>Cafe\u{0301}</>"
+        );
+    }
+
+    #[test]
+    fn t_substr_with_width_does_not_split_emoji_with_skin_tones() {
+        let thumbs_up = "\u{1F44D}\u{1F3FD}";
+        let text = format!("{}{}ok", thumbs_up, thumbs_up);
+        assert_eq!(substr_with_width(&text, 1), "");
+        assert_eq!(substr_with_width(&text, 2), thumbs_up);
+        assert_eq!(substr_with_width(&text, 3), thumbs_up);
+        assert_eq!(
+            substr_with_width(&text, 5),
+            format!("{}{}o", thumbs_up, thumbs_up)
+        );
+
+        assert_eq!(substr_with_width_stfl(&text, 3), thumbs_up);
+        assert_eq!(
+            substr_with_width_stfl(&format!("<b>{}</>{}", thumbs_up, thumbs_up), 3),
+            format!("<b>{}</>", thumbs_up)
+        );
+    }
+
+    #[test]
+    fn t_substr_with_width_does_not_split_zero_width_joiner_families() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        let text = format!("a{}b", family);
+        assert_eq!(substr_with_width(&text, 1), "a");
+        assert_eq!(substr_with_width(&text, 2), "a");
+        assert_eq!(substr_with_width(&text, 3), format!("a{}", family));
+        assert_eq!(substr_with_width(&text, 4), text);
+
+        assert_eq!(substr_with_width_stfl(&text, 2), "a");
+        assert_eq!(substr_with_width_stfl(&text, 3), format!("a{}", family));
+    }
+
+    #[test]
+    fn t_substr_with_width_does_not_split_flags() {
+        let flag = "\u{1F1FA}\u{1F1E6}";
+        let text = format!("{}{}", flag, flag);
+        assert_eq!(substr_with_width(&text, 1), "");
+        assert_eq!(substr_with_width(&text, 3), flag);
+        assert_eq!(substr_with_width_stfl(&text, 3), flag);
+    }
+
+    #[test]
+    fn t_substr_with_width_drops_full_width_characters_that_straddle_the_boundary() {
+        let text = "日本語のニュース";
+        assert_eq!(substr_with_width(text, 5), "日本");
+        assert_eq!(substr_with_width(text, 6), "日本語");
+        assert_eq!(substr_with_width(text, 1), "");
+
+        assert_eq!(substr_with_width_stfl(text, 5), "日本");
+        assert_eq!(substr_with_width_stfl("<b>日本</>語の", 5), "<b>日本</>");
+        assert_eq!(substr_with_width_stfl("日本<>語", 5), "日本<>");
+    }
+
+    #[test]
+    fn t_is_deceptive_url() {
+        assert!(!is_deceptive_url("https://example.com/path?query=1"));
+        assert!(!is_deceptive_url("https://example.com/%20space"));
+        assert!(!is_deceptive_url("not a URL"));
+
+        assert!(is_deceptive_url("https://example.com/\u{202E}fdp.exe"));
+        assert!(is_deceptive_url("https://example.com/%E2%80%AEfdp.exe"));
+        assert!(is_deceptive_url("https://exa\u{200B}mple.com"));
+        // Cyrillic "а" instead of Latin "a"
+        assert!(is_deceptive_url("https://ex\u{0430}mple.com"));
+        assert!(is_deceptive_url("https://xn--exmple-4nf.com"));
+    }
+
+    #[test]
+    fn t_format_urlview_line_marks_deceptive_urls() {
+        assert_eq!(
+            format_urlview_line(1, "https://example.com", true),
+            " 1  https://example.com"
+        );
+        assert_eq!(
+            format_urlview_line(2, "https://example.com/\u{202E}fdp.exe", true),
+            " 2! https://example.com/fdp.exe"
+        );
+        assert_eq!(
+            format_urlview_line(3, "https://example.com/\u{202E}fdp.exe", false),
+            " 3! https://example.com/<U+202E>fdp.exe"
+        );
+        assert_eq!(
+            format_urlview_line(42, "https://ex\u{0430}mple.com", true),
+            "42! https://ex\u{0430}mple.com"
+        );
+    }
+
     #[test]
     fn t_mkdir_parents() {
         use std::fs;
@@ -1401,7 +2994,6 @@ mod tests {
 
     #[test]
     fn t_strnaturalcmp() {
-        use std::cmp::Ordering;
         assert_eq!(strnaturalcmp("", ""), Ordering::Equal);
         assert_eq!(strnaturalcmp("", "a"), Ordering::Less);
         assert_eq!(strnaturalcmp("a", ""), Ordering::Greater);
@@ -1429,6 +3021,70 @@ mod tests {
         assert_eq!(strnaturalcmp("aa10", "aa2"), Ordering::Greater);
     }
 
+    #[test]
+    fn t_strnaturalcmp_compares_numbers_by_value() {
+        assert_eq!(strnaturalcmp("Feed 2", "Feed 10"), Ordering::Less);
+        assert_eq!(strnaturalcmp("Feed 10", "Feed 2"), Ordering::Greater);
+        assert_eq!(strnaturalcmp("v1.10.2", "v1.9.12"), Ordering::Greater);
+        assert_eq!(strnaturalcmp("2 apples", "10 apples"), Ordering::Less);
+        assert_eq!(strnaturalcmp("x9y", "x10"), Ordering::Less);
+
+        let mut titles = vec!["Part 10b", "part 2", "Part 10a", "Part 1", "part 10"];
+        titles.sort_by(|a, b| strnaturalcmp(a, b));
+        assert_eq!(
+            titles,
+            vec!["Part 1", "part 2", "part 10", "Part 10a", "Part 10b"]
+        );
+    }
+
+    #[test]
+    fn t_strnaturalcmp_compares_numbers_longer_than_u64() {
+        let big = "Episode 123456789012345678901234567890";
+        let bigger = "Episode 123456789012345678901234567891";
+        let longer = "Episode 1234567890123456789012345678900";
+        assert_eq!(strnaturalcmp(big, bigger), Ordering::Less);
+        assert_eq!(strnaturalcmp(bigger, longer), Ordering::Less);
+        assert_eq!(strnaturalcmp(longer, big), Ordering::Greater);
+        assert_eq!(strnaturalcmp(big, big), Ordering::Equal);
+        assert_eq!(
+            strnaturalcmp(
+                "Episode 18446744073709551616",
+                "Episode 18446744073709551615"
+            ),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn t_strnaturalcmp_breaks_ties_between_leading_zeros_by_length() {
+        assert_eq!(strnaturalcmp("Feed 007", "Feed 7"), Ordering::Greater);
+        assert_eq!(strnaturalcmp("Feed 7", "Feed 007"), Ordering::Less);
+        assert_eq!(strnaturalcmp("Feed 007", "Feed 8"), Ordering::Less);
+        assert_eq!(strnaturalcmp("Feed 0", "Feed 00"), Ordering::Less);
+        // The numeric value matters more than the leading zeros
+        assert_eq!(strnaturalcmp("a01b2", "a1b1"), Ordering::Greater);
+        // The first difference in leading zeros decides
+        assert_eq!(strnaturalcmp("a01b2", "a1b02"), Ordering::Greater);
+        // So does the rest of the string
+        assert_eq!(strnaturalcmp("a01b", "a1c"), Ordering::Less);
+    }
+
+    #[test]
+    fn t_strnaturalcmp_ignores_case() {
+        assert_eq!(strnaturalcmp("Épisode 2", "épisode 10"), Ordering::Less);
+        assert_eq!(strnaturalcmp("épisode 2", "Épisode 10"), Ordering::Less);
+        assert_eq!(strnaturalcmp("alpha", "BETA"), Ordering::Less);
+        assert_eq!(strnaturalcmp("ΣΟΦΙΑ 2", "σοφια 10"), Ordering::Less);
+        assert_eq!(strnaturalcmp("Straße 3", "STRASSE 12"), Ordering::Less);
+
+        // Strings that differ only in case aren't equal, so that the order is total
+        assert_ne!(strnaturalcmp("News", "news"), Ordering::Equal);
+        assert_eq!(
+            strnaturalcmp("News", "news"),
+            strnaturalcmp("news", "News").reverse()
+        );
+    }
+
     #[test]
     fn t_strip_comments() {
         // no comments in line
@@ -1478,6 +3134,25 @@ mod tests {
         let expected = r#"one two \# three four"#;
         let input = expected.to_owned() + "# and a comment";
         assert_eq!(strip_comments(&input), expected);
+
+        // An escaped backslash doesn't escape the # or the quote that follows it
+        assert_eq!(strip_comments(r#"one two \\# comment"#), r#"one two \\"#);
+        let expected = r#"save-path "C:\\" "#;
+        let input = expected.to_owned() + "# a comment";
+        assert_eq!(strip_comments(&input), expected);
+
+        // Quotes inside a comment don't matter
+        assert_eq!(
+            strip_comments(r#"browser lynx # "quoted # text" `and` more"#),
+            "browser lynx "
+        );
+        assert_eq!(strip_comments(r#"# unterminated "quote"#), "");
+
+        // Unterminated quotes extend to the end of the line
+        let input = r#"browser "foo # bar"#;
+        assert_eq!(strip_comments(input), input);
+        let input = r#"browser `foo # bar"#;
+        assert_eq!(strip_comments(input), input);
     }
 
     #[test]
@@ -1502,4 +3177,239 @@ mod tests {
         let input = "filter:";
         assert_eq!(extract_filter(input), expected);
     }
+
+    #[test]
+    fn t_unescape_quoted() {
+        assert_eq!(unescape_quoted(""), "");
+        assert_eq!(unescape_quoted("no escapes"), "no escapes");
+        assert_eq!(unescape_quoted(r#"\"a\" \\ b"#), r#""a" \ b"#);
+        assert_eq!(unescape_quoted(r"\n\r\t"), "\n\r\t");
+        assert_eq!(unescape_quoted(r"\x41\u00e4\u{1F600}"), "Aä😀");
+        // Other escaped characters stand for themselves
+        assert_eq!(unescape_quoted(r"\e\q\#\ä"), "eq#ä");
+        // Escaped backticks are left for the evaluation of backticks
+        assert_eq!(unescape_quoted(r"\`date\`"), r"\`date\`");
+        // A backslash at the end escapes nothing
+        assert_eq!(unescape_quoted(r"abc\"), "abc");
+    }
+
+    #[test]
+    fn t_unescape_quoted_keeps_invalid_escapes() {
+        assert_eq!(unescape_quoted(r"\xZZ and \uD800"), r"\xZZ and \uD800");
+        assert_eq!(unescape_quoted(r"\u{110000}"), r"\u{110000}");
+        assert_eq!(unescape_quoted(r"\x00"), r"\x00");
+    }
+
+    #[test]
+    fn t_unescape_quoted_strict_reports_invalid_escapes() {
+        assert_eq!(unescape_quoted_strict(r"\`\x41", "`"), Ok("`A".to_string()));
+        assert_eq!(
+            unescape_quoted_strict(r"ab\xZZ", r"\`"),
+            Err(InvalidEscape { offset: 2 })
+        );
+        assert_eq!(
+            unescape_quoted_strict(r"ä\u{1F600", r"\`"),
+            Err(InvalidEscape { offset: 2 })
+        );
+        // NUL can't be passed to C
+        assert_eq!(
+            unescape_quoted_strict(r"\u{0}", r"\`"),
+            Err(InvalidEscape { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn t_escape_quoted_is_the_inverse_of_unescape_quoted() {
+        assert_eq!(escape_quoted(""), "");
+        assert_eq!(escape_quoted("a \"b\" \\ c\n"), r#"a \"b\" \\ c\n"#);
+        for text in &[
+            "",
+            "plain",
+            "\"",
+            "\\",
+            "\\\"",
+            "trailing backslash\\",
+            "\r\n\t",
+            "\\x41 is not A",
+            "\\`escaped backtick\\`",
+            "`backticks`",
+            "ä😀",
+        ] {
+            assert_eq!(unescape_quoted(&escape_quoted(text)), *text);
+        }
+    }
+
+    /// Names of the files in `dir`.
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn t_atomic_write_creates_a_file_with_the_given_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("urls");
+
+        atomic_write(&path, b"https://example.com/feed.xml\n", 0o600).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"https://example.com/feed.xml\n");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(file_names(tmp.path()), vec!["urls"]);
+    }
+
+    #[test]
+    fn t_atomic_write_keeps_the_permissions_of_the_file_it_replaces() {
+        use std::os::unix::fs::PermissionsExt;
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("config");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        atomic_write(&path, b"new", 0o600).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+    }
+
+    #[test]
+    fn t_atomic_write_replaces_the_target_of_a_symlink() {
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let target = tmp.path().join("dotfiles-urls");
+        let link = tmp.path().join("urls");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        atomic_write(&link, b"new", 0o600).unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
+
+    #[test]
+    fn t_atomic_write_refuses_to_replace_a_read_only_file() {
+        use std::os::unix::fs::PermissionsExt;
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let target = tmp.path().join("store-urls");
+        let link = tmp.path().join("urls");
+        fs::write(&target, "old").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o444)).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        match atomic_write(&link, b"new", 0o600) {
+            Err(AtomicWriteError::ReadOnly(path)) => {
+                assert_eq!(path, fs::canonicalize(&target).unwrap())
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(file_names(tmp.path()), vec!["store-urls", "urls"]);
+    }
+
+    #[test]
+    fn t_atomic_write_leaves_the_original_alone_if_validation_fails() {
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("urls");
+        fs::write(&path, "https://example.com/feed.xml\n").unwrap();
+
+        let result = atomic_write_validated(&path, b"not a url\n", 0o600, validate_urls_file);
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "https://example.com/feed.xml\n"
+        );
+        match result {
+            Err(AtomicWriteError::Invalid { reason, kept_at }) => {
+                assert_eq!(reason, "line 1: `not' isn't a URL");
+                assert_eq!(kept_at.parent(), Some(tmp.path()));
+                assert_eq!(fs::read_to_string(&kept_at).unwrap(), "not a url\n");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The kept file doesn't get in the way of the next write
+        atomic_write(&path, b"https://example.com/other.xml\n", 0o600).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "https://example.com/other.xml\n"
+        );
+    }
+
+    #[test]
+    fn t_atomic_write_leaves_the_original_alone_if_interrupted_before_the_rename() {
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("history");
+        fs::write(&path, "old\n").unwrap();
+
+        let result = write_atomically(
+            &path,
+            b"new\n",
+            0o600,
+            |_| Ok(()),
+            |tmp_path| {
+                // Everything is on disk by now
+                assert_eq!(fs::read_to_string(tmp_path).unwrap(), "new\n");
+                Err(io::Error::new(io::ErrorKind::Interrupted, "power loss"))
+            },
+        );
+
+        assert!(matches!(result, Err(AtomicWriteError::Io(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\n");
+        assert_eq!(file_names(tmp.path()), vec!["history"]);
+    }
+
+    #[test]
+    fn t_validate_urls_file() {
+        use tempfile::TempDir;
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("urls");
+        let check = |contents: &[u8]| {
+            fs::write(&path, contents).unwrap();
+            validate_urls_file(&path)
+        };
+
+        assert_eq!(
+            check(
+                b"# comment\n\nhttps://example.com/feed.xml \"tag\"\n\
+                  \"query:Unread:unread = \\\"yes\\\"\"\n\
+                  exec:~/bin/feed.sh\n\
+                  filter:~/bin/filter.sh:https://example.com\n"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check(b"https://example.com/feed.xml\nexample.com\n"),
+            Err("line 2: `example.com' isn't a URL".to_string())
+        );
+        assert_eq!(
+            check(b"https://example.com/feed.xml\nhttps://\xff\n"),
+            Err("line 2: invalid UTF-8".to_string())
+        );
+    }
 }
@@ -0,0 +1,93 @@
+use libnewsboat::configpaths::{ConfigPaths, MigrationOutcome};
+use std::{env, fs};
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+use crate::configpaths_helpers::libc::S_IWUSR;
+
+#[test]
+fn t_configpaths_apply_migration_reports_what_happened_to_each_file() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let sentries = configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    let plan = paths.plan_migration().unwrap();
+
+    // Things change between planning and applying: a history file appears in Newsboat's dotdir,
+    // and the queue becomes unreadable
+    let dotdir = tmp.path().join(".newsboat");
+    assert!(fs::create_dir(&dotdir).is_ok());
+    assert!(configpaths_helpers::create_file(
+        &dotdir.join("history.search"),
+        "newsboat's own history"
+    ));
+    let queue = tmp.path().join(".newsbeuter").join("queue");
+    let _queue_chmod = configpaths_helpers::Chmod::new(&queue, S_IWUSR);
+
+    let report = paths.apply_migration(&plan).unwrap();
+
+    let outcomes = report
+        .files
+        .iter()
+        .map(|(file, outcome)| {
+            let name = file
+                .destination
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            let outcome = match outcome {
+                MigrationOutcome::Copied => "copied",
+                MigrationOutcome::Skipped => "skipped",
+                MigrationOutcome::Failed(_) => "failed",
+            };
+            (name, outcome)
+        })
+        .collect::<Vec<_>>();
+    let expected = [
+        ("urls", "copied"),
+        ("config", "copied"),
+        ("cache.db", "copied"),
+        ("queue", "failed"),
+        ("history.search", "skipped"),
+        ("history.cmdline", "copied"),
+    ]
+    .iter()
+    .map(|(name, outcome)| (name.to_string(), *outcome))
+    .collect::<Vec<_>>();
+    assert_eq!(outcomes, expected);
+
+    let description = report.describe();
+    assert!(description.contains(&format!("Couldn't copy {} to ", queue.display())));
+    assert!(description.contains(&format!(
+        "Skipped {} because {} already exists\n",
+        tmp.path()
+            .join(".newsbeuter")
+            .join("history.search")
+            .display(),
+        dotdir.join("history.search").display()
+    )));
+
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("urls")),
+        &sentries.urls
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("history.search")),
+        "newsboat's own history"
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("history.cmdline")),
+        &sentries.cmdline
+    );
+    assert!(!dotdir.join("queue").exists());
+}
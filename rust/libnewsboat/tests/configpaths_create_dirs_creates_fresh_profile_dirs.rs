@@ -0,0 +1,52 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::{env, fs};
+use tempfile::TempDir;
+
+fn profile_paths(profile: &str) -> ConfigPaths {
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile".to_string(),
+        profile.to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+    paths
+}
+
+#[test]
+fn t_configpaths_create_dirs_creates_fresh_profile_dirs() {
+    let tmp = TempDir::new().unwrap();
+    env::set_var("HOME", tmp.path());
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let config_dir = tmp.path().join(".config").join("newsboat");
+    fs::create_dir_all(&config_dir).unwrap();
+    let data_dir = tmp.path().join(".local").join("share").join("newsboat");
+
+    let paths = profile_paths("personal");
+    assert!(paths.profiles().is_empty());
+    // A fresh profile isn't a candidate for migration from Newsbeuter
+    let newsbeuter_dir = tmp.path().join(".newsbeuter");
+    fs::create_dir_all(&newsbeuter_dir).unwrap();
+    fs::write(
+        newsbeuter_dir.join("urls"),
+        "https://example.com/feed.xml\n",
+    )
+    .unwrap();
+    assert!(!profile_paths("personal").try_migrate_from_newsbeuter());
+    assert!(!tmp.path().join(".newsboat").exists());
+
+    assert!(paths.create_dirs());
+    assert!(config_dir.join("profiles").join("personal").is_dir());
+    assert!(data_dir.join("profiles").join("personal").is_dir());
+
+    assert!(profile_paths("work").create_dirs());
+    assert_eq!(paths.profiles(), vec!["personal", "work"]);
+
+    // Plain files in the profiles directory are not profiles
+    fs::write(config_dir.join("profiles").join("README"), "").unwrap();
+    assert_eq!(paths.profiles(), vec!["personal", "work"]);
+}
@@ -30,8 +30,10 @@ enable_sections! {
 fn t_configpaths_create_dirs_returns_true_if_both_config_and_data_dirs_exist_now() {
     let tmp = TempDir::new().unwrap();
     env::set_var("HOME", tmp.path());
+    env::remove_var("XDG_STATE_HOME");
 
     let dotdir = tmp.path().join(".newsboat");
+    let state_dir = tmp.path().join(".local").join("state").join("newsboat");
 
     if section!("Using dotdir") {
         env::remove_var("XDG_CONFIG_HOME");
@@ -60,10 +62,10 @@ fn t_configpaths_create_dirs_returns_true_if_both_config_and_data_dirs_exist_now
                 assert_dirs_exist_after_create_dirs(&[&dotdir], &tmp);
             }
 
-            if section!("Config dir existed, data dir didn't => data dir created") {
+            if section!("Config dir existed, data dir didn't => data and state dirs created") {
                 assert!(fs::create_dir_all(&config_dir).is_ok());
 
-                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir], &tmp);
+                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir, &state_dir], &tmp);
             }
 
             if section!("Data dir existed, config dir didn't => dotdir created") {
@@ -96,10 +98,10 @@ fn t_configpaths_create_dirs_returns_true_if_both_config_and_data_dirs_exist_now
                 assert_dirs_exist_after_create_dirs(&[&dotdir], &tmp);
             }
 
-            if section!("Config dir existed, data dir didn't => data dir created") {
+            if section!("Config dir existed, data dir didn't => data and state dirs created") {
                 assert!(fs::create_dir_all(&config_dir).is_ok());
 
-                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir], &tmp);
+                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir, &state_dir], &tmp);
             }
 
             if section!("Data dir existed, config dir didn't => dotdir created") {
@@ -132,10 +134,10 @@ fn t_configpaths_create_dirs_returns_true_if_both_config_and_data_dirs_exist_now
                 assert_dirs_exist_after_create_dirs(&[&dotdir], &tmp);
             }
 
-            if section!("Config dir existed, data dir didn't => data dir created") {
+            if section!("Config dir existed, data dir didn't => data and state dirs created") {
                 assert!(fs::create_dir_all(&config_dir).is_ok());
 
-                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir], &tmp);
+                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir, &state_dir], &tmp);
             }
 
             if section!("Data dir existed, config dir didn't => dotdir created") {
@@ -172,10 +174,10 @@ fn t_configpaths_create_dirs_returns_true_if_both_config_and_data_dirs_exist_now
                 assert_dirs_exist_after_create_dirs(&[&dotdir], &tmp);
             }
 
-            if section!("Config dir existed, data dir didn't => data dir created") {
+            if section!("Config dir existed, data dir didn't => data and state dirs created") {
                 assert!(fs::create_dir_all(&config_dir).is_ok());
 
-                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir], &tmp);
+                assert_dirs_exist_after_create_dirs(&[&config_dir, &data_dir, &state_dir], &tmp);
             }
 
             if section!("Data dir existed, config dir didn't => dotdir created") {
@@ -0,0 +1,84 @@
+use libnewsboat::configpaths::ConfigPaths;
+use section_testing::{enable_sections, section};
+use std::{env, fs};
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+enable_sections! {
+#[test]
+fn t_configpaths_plan_migration_lists_files_without_copying_them() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    if section!("No Newsbeuter dirs => no plan") {
+        let paths = ConfigPaths::new();
+        assert!(paths.initialized());
+        assert!(paths.plan_migration().is_none());
+    }
+
+    if section!("Newsbeuter dotdir => plan to copy its files into Newsboat's dotdir") {
+        configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+        assert!(fs::remove_file(tmp.path().join(".newsbeuter").join("cache.db")).is_ok());
+
+        let paths = ConfigPaths::new();
+        assert!(paths.initialized());
+        let plan = paths.plan_migration().unwrap();
+
+        let newsbeuter_dir = tmp.path().join(".newsbeuter");
+        let newsboat_dir = tmp.path().join(".newsboat");
+        assert_eq!(plan.dirs(), vec![newsboat_dir.as_path()]);
+
+        let files = plan
+            .files
+            .iter()
+            .map(|file| (file.source.clone(), file.destination.clone(), file.translated))
+            .collect::<Vec<_>>();
+        let expected = ["urls", "config", "queue", "history.search", "history.cmdline"]
+            .iter()
+            .map(|name| (newsbeuter_dir.join(name), newsboat_dir.join(name), *name == "config"))
+            .collect::<Vec<_>>();
+        assert_eq!(files, expected);
+        assert!(plan.files.iter().all(|file| !file.skipped));
+
+        let description = plan.describe();
+        assert!(description.starts_with(&format!("Would create {}\n", newsboat_dir.display())));
+        assert!(description.contains(&format!(
+            "Would translate {} into {}\n",
+            newsbeuter_dir.join("config").display(),
+            newsboat_dir.join("config").display()
+        )));
+        assert!(description.contains(&format!(
+            "Would copy {} to {}\n",
+            newsbeuter_dir.join("queue").display(),
+            newsboat_dir.join("queue").display()
+        )));
+
+        assert!(!newsboat_dir.exists());
+    }
+
+    if section!("Newsbeuter XDG dirs => plan to copy into Newsboat's XDG dirs") {
+        configpaths_helpers::mock_newsbeuter_xdg_dirs(&tmp);
+
+        let paths = ConfigPaths::new();
+        assert!(paths.initialized());
+        let plan = paths.plan_migration().unwrap();
+
+        let config_dir = tmp.path().join(".config").join("newsboat");
+        let data_dir = tmp.path().join(".local").join("share").join("newsboat");
+        assert_eq!(plan.dirs(), vec![config_dir.as_path(), data_dir.as_path()]);
+        assert_eq!(plan.files.len(), 6);
+        assert_eq!(plan.files[0].destination, config_dir.join("urls"));
+        assert_eq!(plan.files[2].destination, data_dir.join("cache.db"));
+
+        assert!(!config_dir.exists());
+        assert!(!data_dir.exists());
+    }
+}
+}
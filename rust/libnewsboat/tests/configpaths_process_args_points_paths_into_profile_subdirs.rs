@@ -0,0 +1,58 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use section_testing::{enable_sections, section};
+use std::{env, fs, path};
+use tempfile::TempDir;
+
+fn assert_paths_are_inside_profile_dirs(
+    config_dir: &path::Path,
+    data_dir: &path::Path,
+    state_dir: &path::Path,
+) {
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile".to_string(),
+        "work".to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    let config_dir = config_dir.join("profiles").join("work");
+    let data_dir = data_dir.join("profiles").join("work");
+    let state_dir = state_dir.join("profiles").join("work");
+
+    assert_eq!(paths.config_file(), config_dir.join("config"));
+    assert_eq!(paths.url_file(), config_dir.join("urls"));
+
+    assert_eq!(paths.cache_file(), data_dir.join("cache.db"));
+    assert_eq!(paths.lock_file(), state_dir.join("cache.db.lock"));
+    assert_eq!(paths.queue_file(), state_dir.join("queue"));
+    assert_eq!(paths.search_file(), state_dir.join("history.search"));
+    assert_eq!(paths.cmdline_file(), state_dir.join("history.cmdline"));
+}
+
+enable_sections! {
+#[test]
+fn t_configpaths_process_args_points_paths_into_profile_subdirs() {
+    let tmp = TempDir::new().unwrap();
+    env::set_var("HOME", tmp.path());
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+    env::remove_var("XDG_STATE_HOME");
+
+    if section!("Using dotdir") {
+        let dotdir = tmp.path().join(".newsboat");
+        assert_paths_are_inside_profile_dirs(&dotdir, &dotdir, &dotdir);
+    }
+
+    if section!("Using XDG dirs") {
+        let config_dir = tmp.path().join(".config").join("newsboat");
+        fs::create_dir_all(&config_dir).unwrap();
+        let data_dir = tmp.path().join(".local").join("share").join("newsboat");
+        let state_dir = tmp.path().join(".local").join("state").join("newsboat");
+
+        assert_paths_are_inside_profile_dirs(&config_dir, &data_dir, &state_dir);
+    }
+}
+}
@@ -0,0 +1,39 @@
+use libnewsboat::{cliargsparser::CliArgsParser, configpaths::ConfigPaths};
+use std::env;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn t_configpaths_process_args_prefers_explicit_paths_over_profile() {
+    let tmp = TempDir::new().unwrap();
+    env::set_var("HOME", tmp.path());
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let cache_file = Path::new("/path/to/cache file.db");
+    let lock_file = Path::new("/path/to/cache file.db.lock");
+    let config_file = Path::new("this is a/config");
+
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile=work".to_string(),
+        "-c".to_string(),
+        cache_file.to_string_lossy().into_owned(),
+        "-C".to_string(),
+        config_file.to_string_lossy().into_owned(),
+        "-q".to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+
+    assert_eq!(paths.cache_file(), cache_file);
+    assert_eq!(paths.lock_file(), lock_file);
+    assert_eq!(paths.config_file(), config_file);
+
+    // Paths that weren't overridden still come from the profile
+    let profile_dir = tmp.path().join(".newsboat").join("profiles").join("work");
+    assert_eq!(paths.url_file(), profile_dir.join("urls"));
+    assert_eq!(paths.queue_file(), profile_dir.join("queue"));
+}
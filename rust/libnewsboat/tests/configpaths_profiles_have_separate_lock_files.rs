@@ -0,0 +1,82 @@
+use libnewsboat::{
+    cliargsparser::CliArgsParser,
+    configpaths::ConfigPaths,
+    fslock::{FsLock, LockError},
+};
+use std::env;
+use std::io::{Error, ErrorKind};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+fn get_exe_path(exe: &str) -> Result<PathBuf, Error> {
+    let mut p = env::current_exe().expect("exe path");
+    while p.pop() {
+        if p.join(exe).exists() {
+            return Ok(p.join(exe));
+        }
+    }
+    Err(Error::new(ErrorKind::NotFound, exe))
+}
+
+fn profile_paths(profile: &str) -> ConfigPaths {
+    let parser = CliArgsParser::new(vec![
+        "newsboat".to_string(),
+        "--profile".to_string(),
+        profile.to_string(),
+    ]);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    paths.process_args(&parser);
+    assert!(paths.create_dirs());
+    paths
+}
+
+#[test]
+fn t_configpaths_profiles_have_separate_lock_files() {
+    let tmp = TempDir::new().unwrap();
+    env::set_var("HOME", tmp.path());
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let work = profile_paths("work");
+    let personal = profile_paths("personal");
+    assert_ne!(work.lock_file(), personal.lock_file());
+
+    let cmd = match env::var("CARGO_BIN_EXE_lock-process") {
+        Ok(dir) => dir,
+        Err(_) => get_exe_path("lock-process")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    };
+
+    // Another instance of Newsboat is running with the "work" profile
+    let mut child = Command::new(cmd)
+        .arg(work.lock_file())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // wait for locked signal
+    let stdout = child.stdout.as_mut().unwrap();
+    stdout.read_exact(&mut [0]).unwrap();
+
+    let mut work_lock = FsLock::default();
+    match work_lock.try_lock(work.lock_file()) {
+        Err(LockError::AlreadyLocked { pid }) => assert_eq!(pid, Some(child.id() as i32)),
+        other => panic!("expected the lock to be taken, got {:?}", other),
+    }
+
+    let mut personal_lock = FsLock::default();
+    assert!(personal_lock.try_lock(personal.lock_file()).is_ok());
+
+    // notify child to exit and drop lock
+    let stdin = child.stdin.as_mut().unwrap();
+    stdin.write_all(b"\n").unwrap();
+    child.wait().unwrap();
+}
@@ -0,0 +1,94 @@
+use libnewsboat::configpaths::ConfigPaths;
+use section_testing::{enable_sections, section};
+use std::{env, fs, path};
+use tempfile::TempDir;
+
+fn assert_state_files_are_inside(state_dir: &path::Path) {
+    let paths = ConfigPaths::new();
+    assert!(paths.initialized());
+
+    assert_eq!(paths.lock_file(), state_dir.join("cache.db.lock"));
+    assert_eq!(paths.queue_file(), state_dir.join("queue"));
+    assert_eq!(paths.search_file(), state_dir.join("history.search"));
+    assert_eq!(paths.cmdline_file(), state_dir.join("history.cmdline"));
+
+    assert!(paths.create_dirs());
+    assert!(state_dir.is_dir());
+}
+
+enable_sections! {
+#[test]
+fn t_configpaths_puts_state_files_into_xdg_state_dir() {
+    let tmp = TempDir::new().unwrap();
+    env::set_var("HOME", tmp.path());
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+    env::remove_var("XDG_STATE_HOME");
+
+    let config_dir = tmp.path().join(".config").join("newsboat");
+    fs::create_dir_all(&config_dir).unwrap();
+    let data_dir = tmp.path().join(".local").join("share").join("newsboat");
+    let state_dir = tmp.path().join(".local").join("state").join("newsboat");
+
+    if section!("Fresh install => state dir") {
+        assert_state_files_are_inside(&state_dir);
+
+        let paths = ConfigPaths::new();
+        assert_eq!(paths.cache_file(), data_dir.join("cache.db"));
+    }
+
+    if section!("Data dir existed, state dir didn't => data dir") {
+        fs::create_dir_all(&data_dir).unwrap();
+
+        assert_state_files_are_inside(&data_dir);
+    }
+
+    if section!("Both data and state dirs existed => state dir") {
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::create_dir_all(&state_dir).unwrap();
+
+        assert_state_files_are_inside(&state_dir);
+
+        if section!("Files that are still in the data dir are used from there") {
+            fs::write(data_dir.join("history.search"), "").unwrap();
+            fs::write(data_dir.join("queue"), "").unwrap();
+            fs::write(state_dir.join("queue"), "").unwrap();
+
+            let paths = ConfigPaths::new();
+            assert_eq!(paths.search_file(), data_dir.join("history.search"));
+            assert_eq!(paths.queue_file(), state_dir.join("queue"));
+            assert_eq!(paths.cmdline_file(), state_dir.join("history.cmdline"));
+        }
+    }
+
+    if section!("XDG_STATE_HOME is set => overrides ~/.local/state") {
+        let state_home = tmp.path().join("state");
+        env::set_var("XDG_STATE_HOME", &state_home);
+        fs::create_dir_all(&data_dir).unwrap();
+
+        if section!("State dir didn't exist => data dir") {
+            assert_state_files_are_inside(&data_dir);
+        }
+
+        if section!("State dir existed => state dir") {
+            fs::create_dir_all(state_home.join("newsboat")).unwrap();
+
+            assert_state_files_are_inside(&state_home.join("newsboat"));
+        }
+
+        if section!("Relative path is ignored") {
+            env::set_var("XDG_STATE_HOME", "state");
+            fs::create_dir_all(&state_dir).unwrap();
+
+            assert_state_files_are_inside(&state_dir);
+        }
+    }
+
+    if section!("Dotdir is used => dotdir") {
+        fs::remove_dir_all(tmp.path().join(".config")).unwrap();
+        fs::create_dir_all(&state_dir).unwrap();
+
+        assert_state_files_are_inside(&tmp.path().join(".newsboat"));
+    }
+}
+}
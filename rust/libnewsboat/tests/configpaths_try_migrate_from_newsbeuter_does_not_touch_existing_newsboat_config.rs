@@ -0,0 +1,39 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_try_migrate_from_newsbeuter_does_not_touch_existing_newsboat_config() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    assert!(configpaths_helpers::create_file(
+        &tmp.path().join(".newsbeuter").join("config"),
+        "bind-key d pb-download podbeuter\n"
+    ));
+    let sentries = configpaths_helpers::mock_newsboat_dotdir(&tmp);
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert!(!paths.try_migrate_from_newsbeuter());
+    assert!(paths.untranslated_config_lines().is_empty());
+
+    let dotdir = tmp.path().join(".newsboat");
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("config")),
+        &sentries.config
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("urls")),
+        &sentries.urls
+    );
+}
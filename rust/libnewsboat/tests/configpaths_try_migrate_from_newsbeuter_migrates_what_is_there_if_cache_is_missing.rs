@@ -0,0 +1,39 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::{env, fs};
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_try_migrate_from_newsbeuter_migrates_what_is_there_if_cache_is_missing() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let sentries = configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    assert!(fs::remove_file(tmp.path().join(".newsbeuter").join("cache.db")).is_ok());
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert!(paths.try_migrate_from_newsbeuter());
+
+    let dotdir = tmp.path().join(".newsboat");
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("config")),
+        &sentries.config
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("urls")),
+        &sentries.urls
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("queue")),
+        &sentries.queue
+    );
+    assert!(!dotdir.join("cache.db").exists());
+}
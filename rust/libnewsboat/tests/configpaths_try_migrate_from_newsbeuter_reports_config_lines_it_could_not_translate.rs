@@ -0,0 +1,43 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_try_migrate_from_newsbeuter_reports_config_lines_it_could_not_translate() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    let newsbeuter_config = tmp.path().join(".newsbeuter").join("config");
+    let config = "auto-reload yes\n\
+                  include ~/.newsbeuter/colors\n\
+                  color article color0300 default\n";
+    assert!(configpaths_helpers::create_file(&newsbeuter_config, config));
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert!(paths.try_migrate_from_newsbeuter());
+
+    // Untranslated lines are copied as they are
+    let dotdir = tmp.path().join(".newsboat");
+    assert_eq!(
+        configpaths_helpers::file_contents(&dotdir.join("config")),
+        config
+    );
+
+    let untranslated = paths.untranslated_config_lines();
+    assert_eq!(untranslated.len(), 2);
+    let prefix = newsbeuter_config.to_string_lossy().into_owned();
+    assert!(untranslated[0].starts_with(&format!("{}:2: ", prefix)));
+    assert!(untranslated[0].contains("~/.newsbeuter/colors"));
+    assert!(untranslated[1].starts_with(&format!("{}:3: ", prefix)));
+    assert!(untranslated[1].contains("color0300"));
+}
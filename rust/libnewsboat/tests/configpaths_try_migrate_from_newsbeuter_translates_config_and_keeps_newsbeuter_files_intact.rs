@@ -0,0 +1,64 @@
+use libnewsboat::configpaths::ConfigPaths;
+use std::env;
+use tempfile::TempDir;
+
+mod configpaths_helpers;
+
+#[test]
+fn t_configpaths_try_migrate_from_newsbeuter_translates_config_and_keeps_newsbeuter_files_intact() {
+    let tmp = TempDir::new().unwrap();
+
+    env::set_var("HOME", tmp.path());
+
+    // ConfigPaths rely on these variables, so let's sanitize them to ensure
+    // that the tests aren't affected
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+
+    let sentries = configpaths_helpers::mock_newsbeuter_dotdir(&tmp);
+    let newsbeuter_dir = tmp.path().join(".newsbeuter");
+    let config = "auto-reload yes\n\
+                  bind-key d pb-download podbeuter\n\
+                  color listfocus color07 default bold\n\
+                  cache-file \"~/.newsbeuter/cache.db\"\n";
+    assert!(configpaths_helpers::create_file(
+        &newsbeuter_dir.join("config"),
+        config
+    ));
+
+    let mut paths = ConfigPaths::new();
+    assert!(paths.initialized());
+    assert!(paths.try_migrate_from_newsbeuter());
+    assert!(paths.untranslated_config_lines().is_empty());
+
+    let dotdir = tmp.path().join(".newsboat");
+    assert_eq!(
+        configpaths_helpers::file_contents(&dotdir.join("config")),
+        "auto-reload yes\n\
+         bind-key d pb-download podboat\n\
+         color listfocus color7 default bold\n\
+         cache-file \"~/.newsboat/cache.db\"\n"
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("urls")),
+        &sentries.urls
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&dotdir.join("cache.db")),
+        &sentries.cache
+    );
+
+    // Newsbeuter's files are copied, not moved
+    assert_eq!(
+        configpaths_helpers::file_contents(&newsbeuter_dir.join("config")),
+        config
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&newsbeuter_dir.join("urls")),
+        &sentries.urls
+    );
+    assert_eq!(
+        &configpaths_helpers::file_contents(&newsbeuter_dir.join("cache.db")),
+        &sentries.cache
+    );
+}
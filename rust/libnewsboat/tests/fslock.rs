@@ -1,9 +1,10 @@
-use libnewsboat::fslock::FsLock;
+use libnewsboat::fslock::{FsLock, LockError};
 use std::env;
+use std::fs;
 use std::io::{Error, ErrorKind};
 use std::io::{Read, Write};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{self, Command, Stdio};
 use tempfile::NamedTempFile;
 
 fn get_exe_path(exe: &str) -> Result<PathBuf, Error> {
@@ -16,6 +17,17 @@ fn get_exe_path(exe: &str) -> Result<PathBuf, Error> {
     Err(Error::new(ErrorKind::NotFound, exe))
 }
 
+fn lock_process_cmd() -> String {
+    match env::var("CARGO_BIN_EXE_lock-process") {
+        Ok(dir) => dir,
+        Err(_) => get_exe_path("lock-process")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    }
+}
+
 #[test]
 fn t_returns_an_error_if_invalid_lock_location() {
     let tmp = tempfile::tempdir().unwrap();
@@ -23,10 +35,9 @@ fn t_returns_an_error_if_invalid_lock_location() {
     let lock_location = non_existing_dir.join("lockfile");
 
     let mut lock = FsLock::default();
-    let mut pid = -1;
 
-    assert!(!lock.try_lock(lock_location.as_ref(), &mut pid));
-    assert_eq!(pid, 0);
+    let result = lock.try_lock(lock_location.as_ref());
+    assert!(matches!(result, Err(LockError::Io(_))));
 }
 
 #[test]
@@ -37,25 +48,15 @@ fn t_returns_an_error_lock_file_without_write_access() {
     lock_location.as_file().set_permissions(perms).unwrap();
 
     let mut lock = FsLock::default();
-    let mut pid = -1;
-    assert!(!lock.try_lock(lock_location.as_ref(), &mut pid));
-    assert_eq!(pid, 0);
+    let result = lock.try_lock(lock_location.as_ref());
+    assert!(matches!(result, Err(LockError::Io(_))));
 }
 
 #[test]
 fn t_fails_if_lock_was_already_created() {
     let lock_location = NamedTempFile::new().unwrap();
 
-    let cmd = match env::var("CARGO_BIN_EXE_lock-process") {
-        Ok(dir) => dir,
-        Err(_) => get_exe_path("lock-process")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string(),
-    };
-
-    let mut child = Command::new(cmd)
+    let mut child = Command::new(lock_process_cmd())
         .arg(lock_location.path())
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -63,38 +64,68 @@ fn t_fails_if_lock_was_already_created() {
         .unwrap();
 
     let mut lock = FsLock::default();
-    let mut pid = 0;
     let cid = child.id() as i32;
 
     // wait for locked signal
     let stdout = child.stdout.as_mut().unwrap();
     stdout.read_exact(&mut [0]).unwrap();
 
-    assert!(!lock.try_lock(lock_location.as_ref(), &mut pid));
-    assert_eq!(pid, cid, "pid should be process holding the lock");
+    match lock.try_lock(lock_location.as_ref()) {
+        Err(LockError::AlreadyLocked { pid }) => {
+            assert_eq!(pid, Some(cid), "pid should be process holding the lock")
+        }
+        other => panic!("expected the lock to be taken, got {:?}", other),
+    }
 
     // notify child to exit and drop lock
     let stdin = child.stdin.as_mut().unwrap();
     stdin.write_all(b"\n").unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn t_removes_lock_left_behind_by_dead_process() {
+    let tmp = tempfile::tempdir().unwrap();
+    let lock_location = tmp.path().join("lockfile");
+
+    // Simulate an instance that crashed: the lock file is still there, but nobody holds the lock,
+    // and the PID written into it doesn't exist anymore
+    let mut child = Command::new(lock_process_cmd())
+        .arg(&lock_location)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let cid = child.id();
+    child.stdout.as_mut().unwrap().read_exact(&mut [0]).unwrap();
+    child.stdin.as_mut().unwrap().write_all(b"\n").unwrap();
+    child.wait().unwrap();
+    fs::write(&lock_location, cid.to_string()).unwrap();
+
+    let mut lock = FsLock::default();
+    assert!(lock.try_lock(&lock_location).is_ok());
+    assert_eq!(
+        fs::read_to_string(&lock_location).unwrap(),
+        process::id().to_string()
+    );
 }
 
 #[test]
 fn t_succeeds_if_lock_file_location_is_valid_and_not_locked_by_different_process() {
     let lock_location = NamedTempFile::new().unwrap();
     let mut lock = FsLock::default();
-    let mut pid = 0;
 
-    assert!(lock.try_lock(lock_location.as_ref(), &mut pid));
+    assert!(lock.try_lock(lock_location.as_ref()).is_ok());
     assert!(lock_location.path().exists());
     assert!(
-        lock.try_lock(lock_location.as_ref(), &mut pid),
+        lock.try_lock(lock_location.as_ref()).is_ok(),
         "recall succeeds"
     );
 
     let new_lock_location = NamedTempFile::new().unwrap();
 
     assert!(lock_location.path().exists());
-    assert!(lock.try_lock(new_lock_location.as_ref(), &mut pid));
+    assert!(lock.try_lock(new_lock_location.as_ref()).is_ok());
     assert!(!lock_location.path().exists());
     assert!(new_lock_location.path().exists());
 }
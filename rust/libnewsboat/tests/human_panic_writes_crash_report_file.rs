@@ -0,0 +1,33 @@
+use libnewsboat::human_panic;
+use std::{env, fs, thread};
+use tempfile::TempDir;
+
+#[test]
+fn t_human_panic_writes_crash_report_file() {
+    let tmp = TempDir::new().unwrap();
+    env::remove_var("RUST_BACKTRACE");
+    env::set_var("TERM", "xterm-256color");
+
+    human_panic::setup();
+    human_panic::set_report_dir(tmp.path());
+
+    let result = thread::spawn(|| panic!("Can't obtain the global logger")).join();
+    assert!(result.is_err());
+
+    let reports = fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(reports.len(), 1);
+
+    let report = &reports[0];
+    let filename = report.file_name().unwrap().to_string_lossy().into_owned();
+    assert!(filename.starts_with("newsboat-crash-"));
+    assert!(filename.ends_with(".txt"));
+
+    let contents = fs::read_to_string(report).unwrap();
+    assert!(contents.starts_with("Newsboat version: "));
+    assert!(contents.contains("Message: Can't obtain the global logger"));
+    assert!(contents.contains("Crash location: "));
+    assert!(contents.contains("TERM=xterm-256color"));
+}
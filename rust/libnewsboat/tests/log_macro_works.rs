@@ -26,9 +26,9 @@ fn t_log_macro_writes_messages_to_the_log() {
     get_instance().set_logfile(logfile.to_str().unwrap());
     get_instance().set_loglevel(Level::Debug);
 
-    log!(Level::Debug, "Greetings");
-    log!(Level::UserError, "Please set some settings");
-    log!(Level::Error, "Answer invalid: {}", 41);
+    log!("test", Level::Debug, "Greetings");
+    log!("test", Level::UserError, "Please set some settings");
+    log!("test", Level::Error, "Answer invalid: {}", 41);
 
     log_contains_n_lines(&logfile, 3).unwrap();
 }
@@ -0,0 +1,137 @@
+//! Lints a config the way a third-party tool would, using nothing but `libnewsboat::parsers`.
+
+use libnewsboat::parsers::{filter, format, keys, operations};
+
+const CONFIG: &str = r#"
+# Keys
+bind-key ^R reload-all
+bind-key C-d pagedown
+bind-key ENTRE open articlelist
+unbind-key q
+
+# Macros
+macro w set browser "w3m %u" && open-in-browser ; set browser firefox
+macro x open ||
+
+# Filters
+ignore-article "*" "title =~ \"Sponsored\" or author = \"spam\""
+define-filter "Unread" "unread = \"yes\""
+highlight-article "title =~" red default
+ignore-article "*" "age between 1:"
+
+# Formats
+articlelist-format "%4i %f %D %?T?|%-17T| ?%t"
+feedlist-format "%4i %n %11u %t %Q"
+"#;
+
+/// Format keys that `articlelist-format` and `feedlist-format` understand.
+const ARTICLELIST_KEYS: &str = "aDdfiLnsTtu";
+const FEEDLIST_KEYS: &str = "bdilnSsTtUuc";
+
+fn check_format(specifiers: &[format::Specifier], known_keys: &str, diagnostics: &mut Vec<String>) {
+    for specifier in specifiers {
+        match specifier {
+            format::Specifier::Format(key, _) if !known_keys.contains(*key) => {
+                diagnostics.push(format!("unknown format `%{}'", key));
+            }
+            format::Specifier::Conditional(_, then, otherwise) => {
+                check_format(then, known_keys, diagnostics);
+                if let Some(otherwise) = otherwise {
+                    check_format(otherwise, known_keys, diagnostics);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_key(name: &str, diagnostics: &mut Vec<String>) {
+    match keys::parse(name) {
+        Ok(key) if key.is_legacy() => {
+            diagnostics.push(format!("`{}' should be `{}'", name, key.canonical()))
+        }
+        Ok(_) => {}
+        Err(error) => diagnostics.push(error.to_string()),
+    }
+}
+
+fn check_filter(expr: &str, diagnostics: &mut Vec<String>) {
+    if let Err(error) = filter::parse(expr) {
+        diagnostics.push(error.to_string());
+    }
+}
+
+/// Diagnostics for the config, each prefixed with its one-based line number.
+fn lint(config: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    for (idx, line) in config.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut diagnostics = Vec::new();
+        // A config line is tokenized just like an operation; for `macro`, the rest of the
+        // sequence follows the first operation.
+        match operations::tokenize(line) {
+            Err(error) => diagnostics.push(error.to_string()),
+            Ok(steps) => match steps.first() {
+                Some(operations::Step::Operation(tokens)) => {
+                    let args = &tokens[1..];
+                    match (tokens[0].as_str(), args) {
+                        ("bind-key", [key, ..]) | ("unbind-key", [key, ..]) => {
+                            check_key(key, &mut diagnostics)
+                        }
+                        ("macro", [key, ..]) => check_key(key, &mut diagnostics),
+                        ("ignore-article", [_, expr]) | ("define-filter", [_, expr]) => {
+                            check_filter(expr, &mut diagnostics)
+                        }
+                        ("highlight-article", [expr, ..]) => check_filter(expr, &mut diagnostics),
+                        ("articlelist-format", [fmt]) => {
+                            check_format(&format::parse(fmt), ARTICLELIST_KEYS, &mut diagnostics)
+                        }
+                        ("feedlist-format", [fmt]) => {
+                            check_format(&format::parse(fmt), FEEDLIST_KEYS, &mut diagnostics)
+                        }
+                        _ => {}
+                    }
+                }
+                _ => diagnostics.push("expected a command".to_string()),
+            },
+        }
+
+        result.extend(
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| format!("{}: {}", idx + 1, diagnostic)),
+        );
+    }
+    result
+}
+
+#[test]
+fn t_lint_collects_diagnostics_of_every_line() {
+    assert_eq!(
+        lint(CONFIG),
+        vec![
+            "4: `C-d' should be `^D'",
+            "5: `ENTRE' is not a valid key name. Did you mean: ENTER?",
+            "10: failed to tokenize operation sequence: macro x open ||",
+            "15: Parse error at position 8: expected one of: quoted string, range, number",
+            "16: Parse error: trailing characters after position 13: :",
+            "20: unknown format `%Q'",
+        ]
+    );
+}
+
+#[test]
+fn t_macro_operations_can_be_serialized_back() {
+    let line = r#"macro w set browser "w3m %u" && open-in-browser ; set browser firefox"#;
+    let steps = operations::tokenize(line).unwrap();
+    assert_eq!(steps.len(), 4);
+    assert_eq!(steps[1], operations::Step::AndThen);
+    assert_eq!(
+        operations::tokenize(&operations::serialize(&steps)).unwrap(),
+        steps
+    );
+}
@@ -0,0 +1,36 @@
+use libnewsboat::{
+    logger::{self, Level},
+    scopemeasure::ScopeMeasure,
+};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Result};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn file_lines_count(logfile: &Path) -> Result<usize> {
+    let file = File::open(logfile)?;
+    let reader = BufReader::new(file);
+    Ok(reader.lines().count())
+}
+
+#[test]
+fn scopemeasure_under_threshold_writes_nothing_to_the_log() {
+    let tmp = TempDir::new().unwrap();
+    let logfile = {
+        let mut logfile = tmp.path().to_owned();
+        logfile.push("example.log");
+        logfile
+    };
+
+    {
+        logger::get_instance().set_logfile(logfile.to_str().unwrap());
+        logger::get_instance().set_loglevel(Level::Debug);
+        let sm = ScopeMeasure::new(String::from("test"))
+            .threshold(Duration::from_secs(3600))
+            .level(Level::Info);
+        sm.stopover("halfway");
+    }
+
+    assert_eq!(file_lines_count(&logfile).unwrap_or(0), 0);
+}
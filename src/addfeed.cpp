@@ -0,0 +1,78 @@
+#include "addfeed.h"
+
+#include <algorithm>
+
+#include "config.h"
+#include "feedpreview.h"
+#include "fileurlreader.h"
+#include "logger.h"
+#include "rssfeed.h"
+#include "ruststring.h"
+#include "strprintf.h"
+
+extern "C" {
+	char* rs_control_socket_normalize_feed_url(const char* url, char** error);
+}
+
+namespace newsboat {
+
+namespace addfeed {
+
+static bool is_subscribed(FileUrlReader& urls, const std::string& url)
+{
+	const auto subscribed = urls.get_urls();
+	return std::find(subscribed.begin(), subscribed.end(), url) !=
+		subscribed.end();
+}
+
+static Result already_subscribed(const std::string& url)
+{
+	return {Outcome::ALREADY_SUBSCRIBED,
+			url,
+			strprintf::fmt(_("You are already subscribed to %s."), url)};
+}
+
+Result add(const std::string& input, FileUrlReader& urls, ConfigContainer* cfg)
+{
+	char* error = nullptr;
+	const std::string url = RustString(
+			rs_control_socket_normalize_feed_url(input.c_str(), &error));
+	if (url.empty()) {
+		return {Outcome::FAILED,
+				"",
+				strprintf::fmt(_("Error: %s"), RustString(error))};
+	}
+
+	urls.reload();
+	if (is_subscribed(urls, url)) {
+		return already_subscribed(url);
+	}
+
+	std::string fetch_error;
+	const auto feed = feedpreview::fetch(url, cfg, fetch_error);
+	if (feed == nullptr) {
+		return {Outcome::FAILED,
+				url,
+				strprintf::fmt(_("Error: couldn't subscribe to %s"), fetch_error)};
+	}
+
+	// The page might have linked to a feed that's in the file already
+	const std::string feed_url = feed->rssurl();
+	if (feed_url != url && is_subscribed(urls, feed_url)) {
+		return already_subscribed(feed_url);
+	}
+
+	const auto write_error = urls.append_url(feed_url);
+	if (write_error.has_value()) {
+		return {Outcome::FAILED, feed_url, write_error.value()};
+	}
+
+	LOG(Level::INFO, "addfeed::add: added %s to %s", feed_url, urls.get_source());
+	return {Outcome::ADDED,
+			feed_url,
+			strprintf::fmt(_("Added %s to %s."), feed_url, urls.get_source())};
+}
+
+} // namespace addfeed
+
+} // namespace newsboat
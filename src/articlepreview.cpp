@@ -0,0 +1,140 @@
+#include "articlepreview.h"
+
+#include <cstdint>
+
+#include "logger.h"
+#include "ruststring.h"
+#include "scopemeasure.h"
+
+extern "C" {
+	void* rs_preview_cache_new(std::size_t max_entries, std::size_t max_bytes);
+
+	void rs_preview_cache_free(void* cache);
+
+	char* rs_preview_cache_get(void* cache,
+		const char* item,
+		std::size_t width,
+		std::size_t height);
+
+	char* rs_preview_cache_insert(void* cache,
+		const char* item,
+		std::size_t width,
+		std::size_t height,
+		const char* text);
+
+	void rs_preview_cache_set_limits(void* cache,
+		std::size_t max_entries,
+		std::size_t max_bytes);
+
+	void rs_preview_cache_pin(void* cache,
+		const char* item,
+		std::size_t width,
+		std::size_t height);
+
+	char* rs_preview_cache_stats(void* cache);
+
+	void rs_preview_cache_clear(void* cache);
+
+	void* rs_debouncer_new(std::uint64_t delay_ms);
+
+	void rs_debouncer_free(void* debouncer);
+
+	void rs_debouncer_request(void* debouncer, const char* item);
+
+	char* rs_debouncer_poll(void* debouncer);
+
+	std::int64_t rs_debouncer_time_left_ms(void* debouncer);
+
+	void rs_debouncer_reset(void* debouncer);
+}
+
+namespace newsboat {
+
+ArticlePreview::ArticlePreview(unsigned int debounce_ms,
+	std::size_t max_entries,
+	std::size_t max_bytes)
+{
+	rs_cache = rs_preview_cache_new(max_entries, max_bytes);
+	rs_debouncer = rs_debouncer_new(debounce_ms);
+}
+
+ArticlePreview::~ArticlePreview()
+{
+	rs_debouncer_free(rs_debouncer);
+	rs_preview_cache_free(rs_cache);
+}
+
+void ArticlePreview::request(const std::string& guid)
+{
+	rs_debouncer_request(rs_debouncer, guid.c_str());
+}
+
+nonstd::optional<std::string> ArticlePreview::poll()
+{
+	char* item = rs_debouncer_poll(rs_debouncer);
+	if (item == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(item));
+}
+
+int ArticlePreview::time_left_ms()
+{
+	return rs_debouncer_time_left_ms(rs_debouncer);
+}
+
+void ArticlePreview::reset()
+{
+	rs_debouncer_reset(rs_debouncer);
+}
+
+void ArticlePreview::set_cache_limits(std::size_t max_entries,
+	std::size_t max_bytes)
+{
+	rs_preview_cache_set_limits(rs_cache, max_entries, max_bytes);
+}
+
+std::vector<std::string> ArticlePreview::lines(const std::string& guid,
+	unsigned int width,
+	unsigned int height,
+	std::function<std::string()> render)
+{
+	ScopeMeasure m("ArticlePreview::lines");
+
+	// Pin first, so that inserting this preview can't evict it
+	rs_preview_cache_pin(rs_cache, guid.c_str(), width, height);
+
+	char* cached = rs_preview_cache_get(rs_cache, guid.c_str(), width, height);
+	std::string joined;
+	if (cached != nullptr) {
+		joined = RustString(cached);
+	} else {
+		const std::string text = render();
+		joined = RustString(rs_preview_cache_insert(
+					rs_cache, guid.c_str(), width, height, text.c_str()));
+	}
+	LOG(Level::DEBUG, "ArticlePreview::lines: cache: %s",
+		std::string(RustString(rs_preview_cache_stats(rs_cache))));
+
+	std::vector<std::string> result;
+	if (joined.empty()) {
+		return result;
+	}
+	std::string::size_type start = 0;
+	while (true) {
+		const auto end = joined.find('\n', start);
+		result.push_back(joined.substr(start, end - start));
+		if (end == std::string::npos) {
+			break;
+		}
+		start = end + 1;
+	}
+	return result;
+}
+
+void ArticlePreview::clear_cache()
+{
+	rs_preview_cache_clear(rs_cache);
+}
+
+} // namespace newsboat
@@ -0,0 +1,52 @@
+#include "browser.h"
+
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	char* rs_browser_cmdline(const char* browser, const char* url);
+
+	void* rs_detached_browsers_new();
+
+	void rs_detached_browsers_free(void* browsers);
+
+	bool rs_detached_browsers_launch(void* browsers,
+		const char* url,
+		const char* cmdline);
+
+	char* rs_detached_browsers_take_failures(void* browsers);
+}
+
+namespace newsboat {
+
+std::string browser::cmdline(const std::string& browser,
+	const std::string& url)
+{
+	return RustString(rs_browser_cmdline(browser.c_str(), url.c_str()));
+}
+
+DetachedBrowsers::DetachedBrowsers()
+{
+	rs_object = rs_detached_browsers_new();
+}
+
+DetachedBrowsers::~DetachedBrowsers()
+{
+	rs_detached_browsers_free(rs_object);
+}
+
+bool DetachedBrowsers::launch(const std::string& url,
+	const std::string& cmdline)
+{
+	return rs_detached_browsers_launch(rs_object, url.c_str(),
+			cmdline.c_str());
+}
+
+std::vector<std::string> DetachedBrowsers::take_failures()
+{
+	const std::string failures = RustString(
+			rs_detached_browsers_take_failures(rs_object));
+	return utils::tokenize(failures, "\n");
+}
+
+} // namespace newsboat
@@ -1,11 +1,16 @@
 #include "cache.h"
 
+#include <algorithm>
 #include <cassert>
+#include <cerrno>
 #include <cinttypes>
+#include <cstdio>
 #include <cstdlib>
 #include <cstring>
 #include <fstream>
 #include <iostream>
+#include <map>
+#include <set>
 #include <sqlite3.h>
 #include <sstream>
 #include <time.h>
@@ -17,12 +22,102 @@
 #include "logger.h"
 #include "matcherexception.h"
 #include "rssfeed.h"
+#include "ruststring.h"
 #include "scopemeasure.h"
 #include "strprintf.h"
 #include "utils.h"
 
+extern "C" {
+	uint8_t rs_decide_article_update(const char* stored_title,
+		const char* stored_content,
+		const char* new_title,
+		const char* new_content,
+		bool mark_unread);
+
+	void* rs_cache_merge_new(bool prefer_read);
+
+	void rs_cache_merge_free(void* merge);
+
+	void rs_cache_merge_add_existing(void* merge,
+		const char* guid,
+		const char* feedurl,
+		const char* link,
+		const char* title,
+		const char* content,
+		bool unread,
+		const char* flags,
+		const char* note);
+
+	uint8_t rs_cache_merge_decide(void* merge,
+		const char* guid,
+		const char* feedurl,
+		const char* link,
+		const char* title,
+		const char* content,
+		bool unread,
+		const char* flags,
+		const char* note,
+		char** guid_out,
+		bool* unread_out,
+		char** flags_out,
+		char** note_out);
+
+	void rs_cache_merge_summary(void* merge,
+		size_t* added,
+		size_t* merged,
+		size_t* conflicts);
+
+	void* rs_migration_plan_new();
+
+	void rs_migration_plan_free(void* plan);
+
+	size_t rs_migration_plan_add(void* plan,
+		uint64_t last_row,
+		uint64_t done_up_to);
+
+	bool rs_migration_plan_next_chunk(void* plan,
+		size_t index,
+		uint64_t* after,
+		uint64_t* up_to);
+
+	void rs_migration_plan_chunk_done(void* plan,
+		size_t index,
+		uint64_t after,
+		uint64_t up_to);
+
+	char* rs_migration_plan_progress(void* plan);
+
+	void rs_cache_min_reader_version(uint32_t* major, uint32_t* minor);
+
+	char* rs_check_reader_version(uint32_t min_reader_major,
+		uint32_t min_reader_minor);
+
+	void rs_article_length_measure(const char* content,
+		bool has_previous,
+		uint64_t previous_hash,
+		uint64_t previous_words,
+		uint64_t* hash,
+		uint64_t* words);
+
+	uint64_t rs_reading_minutes(uint64_t words, uint32_t words_per_minute);
+}
+
 namespace newsboat {
 
+// Mirrors `UpdateDecision` in rust/libnewsboat/src/articleupdates.rs
+enum class ArticleUpdate : uint8_t {
+	UNCHANGED = 0,
+	UPDATED = 1,
+	UPDATED_UNREAD = 2,
+};
+
+// Mirrors `Decision` in rust/libnewsboat/src/cachemerge.rs
+enum class MergeDecision : uint8_t {
+	KEEP = 0,
+	ADD = 1,
+	UPDATE = 2,
+};
+
 inline void Cache::run_sql_impl(const std::string& query,
 	int (*callback)(void*, int, char**, char**),
 	void* callback_argument,
@@ -77,6 +172,23 @@ struct HeaderValues {
 	std::string etag;
 };
 
+struct StoredArticle {
+	std::string title;
+	std::string content;
+	// Whether the article's words were counted; articles stored by older
+	// versions weren't
+	bool has_length = false;
+	uint64_t content_hash = 0;
+	uint64_t words = 0;
+};
+
+/// Word count of an article that was stored without one.
+struct ArticleLength {
+	sqlite3_int64 id;
+	uint64_t content_hash;
+	uint64_t words;
+};
+
 static int count_callback(void* handler, int argc, char** argv,
 	char** /* azColName */)
 {
@@ -104,6 +216,50 @@ static int single_string_callback(void* handler,
 	return 0;
 }
 
+static int stored_article_callback(void* handler,
+	int argc,
+	char** argv,
+	char** /* azColName */)
+{
+	StoredArticle* article = static_cast<StoredArticle*>(handler);
+	assert(argc == 4);
+	article->title = argv[0] ? argv[0] : "";
+	article->content = argv[1] ? argv[1] : "";
+	article->has_length = argv[2] != nullptr;
+	if (argv[2]) {
+		// The hash is stored as a signed integer, since that's all SQLite has
+		article->content_hash = static_cast<uint64_t>(std::strtoll(argv[2],
+					nullptr, 10));
+		article->words = std::strtoull(argv[3] ? argv[3] : "0", nullptr, 10);
+	}
+	return 0;
+}
+
+static int article_length_callback(void* handler,
+	int argc,
+	char** argv,
+	char** /* azColName */)
+{
+	auto lengths = static_cast<std::vector<ArticleLength>*>(handler);
+	assert(argc == 2);
+	ArticleLength length;
+	length.id = std::strtoll(argv[0], nullptr, 10);
+	rs_article_length_measure(argv[1] ? argv[1] : "",
+		false,
+		0,
+		0,
+		&length.content_hash,
+		&length.words);
+	lengths->push_back(length);
+	return 0;
+}
+
+static uint64_t reading_minutes(ConfigContainer* cfg, uint64_t words)
+{
+	return rs_reading_minutes(words,
+			cfg->get_configvalue_as_int("reading-speed"));
+}
+
 static int rssfeed_callback(void* myfeed, int argc, char** argv,
 	char** /* azColName */)
 {
@@ -111,13 +267,15 @@ static int rssfeed_callback(void* myfeed, int argc, char** argv,
 		static_cast<std::shared_ptr<RssFeed>*>(myfeed);
 	// normaly, this shouldn't happen, but we keep the assert()s here
 	// nevertheless
-	assert(argc == 3);
+	assert(argc == 4);
 	assert(argv[0] != nullptr);
 	assert(argv[1] != nullptr);
 	assert(argv[2] != nullptr);
+	assert(argv[3] != nullptr);
 	(*feed)->set_title(argv[0]);
 	(*feed)->set_link(argv[1]);
 	(*feed)->set_rtl(strcmp(argv[2], "1") == 0);
+	(*feed)->set_schedule(argv[3]);
 	LOG(Level::INFO,
 		"rssfeed_callback: title = %s link = %s is_rtl = %s",
 		argv[0],
@@ -173,7 +331,7 @@ static int rssitem_callback(void* myfeed, int argc, char** argv,
 {
 	std::shared_ptr<RssFeed>* feed =
 		static_cast<std::shared_ptr<RssFeed>*>(myfeed);
-	assert(argc == 13);
+	assert(argc == 16);
 	std::shared_ptr<RssItem> item(new RssItem(nullptr));
 	item->set_guid(argv[0]);
 	item->set_title(argv[1]);
@@ -195,6 +353,10 @@ static int rssitem_callback(void* myfeed, int argc, char** argv,
 	item->set_enqueued((std::string("1") == (argv[10] ? argv[10] : "")));
 	item->set_flags(argv[11] ? argv[11] : "");
 	item->set_base(argv[12] ? argv[12] : "");
+	item->set_updated((std::string("1") == (argv[13] ? argv[13] : "")));
+	item->set_note(argv[14] ? argv[14] : "");
+	item->set_word_count(std::strtoull(argv[15] ? argv[15] : "0", nullptr, 10),
+		0);
 
 	//(*feed)->items().push_back(item);
 	(*feed)->add_item(item);
@@ -223,7 +385,7 @@ static int search_item_callback(void* myfeed,
 {
 	std::vector<std::shared_ptr<RssItem>>* items =
 			static_cast<std::vector<std::shared_ptr<RssItem>>*>(myfeed);
-	assert(argc == 13);
+	assert(argc == 16);
 	std::shared_ptr<RssItem> item(new RssItem(nullptr));
 	item->set_guid(argv[0]);
 	item->set_title(argv[1]);
@@ -244,6 +406,10 @@ static int search_item_callback(void* myfeed,
 	item->set_enqueued((std::string("1") == argv[10]));
 	item->set_flags(argv[11] ? argv[11] : "");
 	item->set_base(argv[12] ? argv[12] : "");
+	item->set_updated((std::string("1") == (argv[13] ? argv[13] : "")));
+	item->set_note(argv[14] ? argv[14] : "");
+	item->set_word_count(std::strtoull(argv[15] ? argv[15] : "0", nullptr, 10),
+		0);
 
 	items->push_back(item);
 	return 0;
@@ -258,9 +424,25 @@ static int guid_callback(void* myguids, int argc, char** argv,
 	return 0;
 }
 
-Cache::Cache(const std::string& cachefile, ConfigContainer* c)
+static nonstd::optional<sqlite3_int64> query_integer(sqlite3* db,
+	const std::string& query)
+{
+	nonstd::optional<sqlite3_int64> result;
+	sqlite3_stmt* stmt = nullptr;
+	if (sqlite3_prepare_v2(db, query.c_str(), -1, &stmt, nullptr) == SQLITE_OK
+		&& sqlite3_step(stmt) == SQLITE_ROW
+		&& sqlite3_column_type(stmt, 0) != SQLITE_NULL) {
+		result = sqlite3_column_int64(stmt, 0);
+	}
+	sqlite3_finalize(stmt);
+	return result;
+}
+
+Cache::Cache(const std::string& cachefile, ConfigContainer* c,
+	const std::function<void(const std::string&)>& migration_progress)
 	: db(0)
 	, cfg(c)
+	, read_only(false)
 {
 	const int error = sqlite3_open(cachefile.c_str(), &db);
 	if (error != SQLITE_OK) {
@@ -271,7 +453,7 @@ Cache::Cache(const std::string& cachefile, ConfigContainer* c)
 		throw DbException(db);
 	}
 
-	populate_tables();
+	populate_tables(migration_progress);
 	set_pragmas();
 
 	clean_old_articles();
@@ -280,6 +462,13 @@ Cache::Cache(const std::string& cachefile, ConfigContainer* c)
 	// explicit support for multithreading.
 }
 
+Cache::Cache(sqlite3* handle, ConfigContainer* c)
+	: db(handle)
+	, cfg(c)
+	, read_only(true)
+{
+}
+
 Cache::~Cache()
 {
 	sqlite3_close(db);
@@ -380,9 +569,84 @@ static const schema_patches schemaPatches{
 
 			"INSERT INTO metadata VALUES ( 2, 11 );"
 		}
+	},
+	{	{2, 22},
+		{
+			"ALTER TABLE rss_item ADD updated INTEGER(1) NOT NULL "
+			"DEFAULT 0;",
+
+			"UPDATE metadata SET db_schema_version_major = 2, "
+			"db_schema_version_minor = 22;"
+		}
+	},
+	{	{2, 23},
+		{
+			"ALTER TABLE rss_feed ADD schedule VARCHAR(128) NOT NULL "
+			"DEFAULT \"\";",
+
+			"UPDATE metadata SET db_schema_version_major = 2, "
+			"db_schema_version_minor = 23;"
+		}
+	},
+	{	{2, 24},
+		{
+			"CREATE TABLE rss_item_note ( "
+			" guid VARCHAR(64) PRIMARY KEY NOT NULL, "
+			" note TEXT NOT NULL );",
+
+			"UPDATE metadata SET db_schema_version_major = 2, "
+			"db_schema_version_minor = 24;"
+		}
+	},
+	{	{2, 25},
+		{
+			"CREATE TABLE rss_sync_progress ( "
+			" feedurl VARCHAR(1024) PRIMARY KEY NOT NULL, "
+			" progress VARCHAR(128) NOT NULL );",
+
+			"UPDATE metadata SET db_schema_version_major = 2, "
+			"db_schema_version_minor = 25;"
+		}
+	},
+	{	{2, 26},
+		{
+			"ALTER TABLE metadata ADD min_reader_major INTEGER NOT NULL "
+			"DEFAULT 0;",
+
+			"ALTER TABLE metadata ADD min_reader_minor INTEGER NOT NULL "
+			"DEFAULT 0;",
+
+			"UPDATE metadata SET db_schema_version_major = 2, "
+			"db_schema_version_minor = 26;"
+		}
+	},
+	{	{2, 27},
+		{
+			"ALTER TABLE rss_item ADD content_hash INTEGER;",
+
+			"ALTER TABLE rss_item ADD word_count INTEGER NOT NULL "
+			"DEFAULT 0;",
+
+			"UPDATE metadata SET db_schema_version_major = 2, "
+			"db_schema_version_minor = 27;"
+		}
+	}};
+
+// The chunked migrations of a version run before its patches, so the version
+// isn't recorded until all of its rows are migrated.
+static const chunked_migrations chunkedMigrations{
+	{	{2, 26},
+		{
+			{
+				"rss_item",
+				"UPDATE rss_item SET flags = '' "
+				"WHERE flags IS NULL AND id > %lld AND id <= %lld;"
+			}
+		}
 	}};
 
-void Cache::populate_tables()
+void Cache::populate_tables(
+	const std::function<void(const std::string&)>& migration_progress)
 {
 	const SchemaVersion version = get_schema_version();
 	LOG(Level::INFO,
@@ -397,6 +661,8 @@ void Cache::populate_tables()
 		throw std::runtime_error(msg);
 	}
 
+	check_min_reader_version(version);
+
 	auto patches_it = schemaPatches.cbegin();
 
 	// rewind to the first patch that should be applied
@@ -405,6 +671,43 @@ void Cache::populate_tables()
 		++patches_it;
 	}
 
+	if (patches_it == schemaPatches.cend()) {
+		return;
+	}
+
+	std::unique_ptr<void, void (*)(void*)> plan(
+		rs_migration_plan_new(),
+		rs_migration_plan_free);
+	// Indexes into `plan` of the chunked migrations of each version
+	std::map<SchemaVersion, std::vector<size_t>> planned;
+	const auto first_chunked = chunkedMigrations.upper_bound(version);
+	if (first_chunked != chunkedMigrations.cend()) {
+		// Checkpoints of the chunked migrations, which let an interrupted
+		// one resume where it stopped
+		run_sql("CREATE TABLE IF NOT EXISTS schema_migration_progress ( "
+			" major INTEGER NOT NULL, "
+			" minor INTEGER NOT NULL, "
+			" migration INTEGER NOT NULL, "
+			" done_up_to INTEGER NOT NULL, "
+			" PRIMARY KEY (major, minor, migration) );");
+	}
+	for (auto it = first_chunked; it != chunkedMigrations.cend(); ++it) {
+		for (unsigned int i = 0; i < it->second.size(); ++i) {
+			// A table that doesn't exist yet has no rows to migrate
+			const auto last_row = query_integer(db,
+					"SELECT max(id) FROM " + it->second[i].table + ";");
+			const auto done_up_to = query_integer(db, prepare_query(
+						"SELECT done_up_to FROM schema_migration_progress "
+						"WHERE major = %u AND minor = %u AND migration = %u;",
+						it->first.major,
+						it->first.minor,
+						i));
+			planned[it->first].push_back(rs_migration_plan_add(plan.get(),
+					std::max<sqlite3_int64>(last_row.value_or(0), 0),
+					std::max<sqlite3_int64>(done_up_to.value_or(0), 0)));
+		}
+	}
+
 	for (; patches_it != schemaPatches.cend(); ++patches_it) {
 		const SchemaVersion patch_version = patches_it->first;
 		LOG(Level::INFO,
@@ -412,10 +715,167 @@ void Cache::populate_tables()
 			"for version %u.%u",
 			patch_version.major,
 			patch_version.minor);
+		const auto chunked = planned.find(patch_version);
+		if (chunked != planned.cend()) {
+			run_chunked_migrations(plan.get(),
+				patch_version,
+				chunked->second,
+				migration_progress);
+		}
 		for (const auto& query : patches_it->second) {
 			run_sql_nothrow(query);
 		}
 	}
+
+	if (version < SchemaVersion{2, 27}) {
+		fill_in_article_lengths();
+	}
+
+	// The older versions might not know what the patches changed
+	uint32_t min_reader_major = 0;
+	uint32_t min_reader_minor = 0;
+	rs_cache_min_reader_version(&min_reader_major, &min_reader_minor);
+	run_sql_nothrow(prepare_query(
+			"UPDATE metadata SET min_reader_major = %u, min_reader_minor = %u;",
+			min_reader_major,
+			min_reader_minor));
+	run_sql_nothrow("DROP TABLE IF EXISTS schema_migration_progress;");
+}
+
+void Cache::fill_in_article_lengths()
+{
+	LOG(Level::INFO,
+		"Cache::fill_in_article_lengths: counting words of stored articles");
+	std::vector<ArticleLength> lengths;
+	run_sql("SELECT id, content FROM rss_item WHERE content_hash IS NULL;",
+		article_length_callback,
+		&lengths);
+
+	run_sql("BEGIN TRANSACTION;");
+	try {
+		for (const auto& length : lengths) {
+			run_sql(prepare_query(
+					"UPDATE rss_item SET content_hash = %lld, word_count = %llu "
+					"WHERE id = %lld;",
+					static_cast<sqlite3_int64>(length.content_hash),
+					static_cast<unsigned long long>(length.words),
+					length.id));
+		}
+		run_sql("COMMIT;");
+	} catch (const DbException&) {
+		run_sql_nothrow("ROLLBACK;");
+		throw;
+	}
+}
+
+void Cache::check_min_reader_version(const SchemaVersion& version)
+{
+	if (version < SchemaVersion{2, 26}) {
+		// Older schemas don't record it
+		return;
+	}
+
+	const auto min_reader_major = query_integer(db,
+			"SELECT min_reader_major FROM metadata;");
+	const auto min_reader_minor = query_integer(db,
+			"SELECT min_reader_minor FROM metadata;");
+	const std::string msg = RustString(rs_check_reader_version(
+				min_reader_major.value_or(0),
+				min_reader_minor.value_or(0)));
+	if (!msg.empty()) {
+		LOG(Level::ERROR, msg);
+		throw std::runtime_error(msg);
+	}
+}
+
+std::unique_ptr<Cache> Cache::open_read_only(const std::string& cachefile,
+	ConfigContainer* c)
+{
+	sqlite3* handle = nullptr;
+	if (sqlite3_open_v2(cachefile.c_str(), &handle, SQLITE_OPEN_READONLY,
+			nullptr) != SQLITE_OK) {
+		const DbException e(handle);
+		sqlite3_close(handle);
+		throw e;
+	}
+	// The instance that's running might be in the middle of a reload
+	sqlite3_busy_timeout(handle, 5000);
+
+	std::unique_ptr<Cache> cache(new Cache(handle, c));
+	const SchemaVersion version = cache->get_schema_version();
+	LOG(Level::INFO,
+		"Cache::open_read_only: DB schema version %u.%u",
+		version.major,
+		version.minor);
+	if (version < schemaPatches.crbegin()->first) {
+		const std::string msg = strprintf::fmt(
+				_("The cache file is in an old format. Start %s once to update it."),
+				PROGRAM_NAME);
+		LOG(Level::ERROR, msg);
+		throw std::runtime_error(msg);
+	}
+	cache->check_min_reader_version(version);
+	cache->set_pragmas();
+
+	return cache;
+}
+
+std::vector<std::string> Cache::get_feed_urls()
+{
+	std::lock_guard<std::mutex> lock(mtx);
+	std::vector<std::string> urls;
+	run_sql("SELECT rssurl FROM rss_feed ORDER BY rssurl;",
+		vectorofstring_callback,
+		&urls);
+	return urls;
+}
+
+void Cache::run_chunked_migrations(void* plan,
+	const SchemaVersion& version,
+	const std::vector<size_t>& migrations,
+	const std::function<void(const std::string&)>& migration_progress)
+{
+	const auto report_progress = [&]() {
+		const std::string message = RustString(rs_migration_plan_progress(plan));
+		if (!message.empty()) {
+			LOG(Level::INFO, "Cache::run_chunked_migrations: %s", message);
+			if (migration_progress) {
+				migration_progress(message);
+			}
+		}
+	};
+
+	const auto& statements = chunkedMigrations.at(version);
+	for (unsigned int i = 0; i < migrations.size(); ++i) {
+		uint64_t after = 0;
+		uint64_t up_to = 0;
+		while (rs_migration_plan_next_chunk(plan, migrations[i], &after,
+				&up_to)) {
+			report_progress();
+
+			run_sql("BEGIN TRANSACTION;");
+			try {
+				run_sql(prepare_query(statements[i].statement,
+						static_cast<sqlite3_int64>(after),
+						static_cast<sqlite3_int64>(up_to)));
+				run_sql(prepare_query(
+						"INSERT OR REPLACE INTO schema_migration_progress "
+						"(major, minor, migration, done_up_to) "
+						"VALUES (%u, %u, %u, %lld);",
+						version.major,
+						version.minor,
+						i,
+						static_cast<sqlite3_int64>(up_to)));
+				run_sql("COMMIT;");
+			} catch (const DbException&) {
+				run_sql_nothrow("ROLLBACK;");
+				throw;
+			}
+
+			rs_migration_plan_chunk_done(plan, migrations[i], after, up_to);
+			report_progress();
+		}
+	}
 }
 
 void Cache::fetch_lastmodified(const std::string& feedurl,
@@ -495,7 +955,62 @@ void Cache::externalize_rssfeed(std::shared_ptr<RssFeed> feed,
 	std::lock_guard<std::mutex> lock(mtx);
 	std::lock_guard<std::mutex> feedlock(feed->item_mutex);
 	// scope_transaction dbtrans(db);
+	externalize_rssfeed_unlocked(feed, reset_unread);
+}
+
+void Cache::store_sync_page(std::shared_ptr<RssFeed> page,
+	bool reset_unread,
+	const std::string& progress)
+{
+	ScopeMeasure m1("Cache::store_sync_page");
 
+	std::lock_guard<std::mutex> lock(mtx);
+	std::lock_guard<std::mutex> feedlock(page->item_mutex);
+	run_sql("BEGIN TRANSACTION;");
+	try {
+		externalize_rssfeed_unlocked(page, reset_unread);
+		run_sql(prepare_query(
+				"INSERT OR REPLACE INTO rss_sync_progress (feedurl, progress) "
+				"VALUES ('%q', '%q');",
+				page->rssurl(),
+				progress));
+	} catch (const DbException&) {
+		run_sql_nothrow("ROLLBACK;");
+		throw;
+	}
+	run_sql("COMMIT;");
+}
+
+void Cache::set_sync_progress(const std::string& feedurl,
+	const std::string& progress)
+{
+	std::lock_guard<std::mutex> lock(mtx);
+	run_sql(prepare_query(
+			"INSERT OR REPLACE INTO rss_sync_progress (feedurl, progress) "
+			"VALUES ('%q', '%q');",
+			feedurl,
+			progress));
+}
+
+nonstd::optional<std::string> Cache::fetch_sync_progress(
+	const std::string& feedurl)
+{
+	std::lock_guard<std::mutex> lock(mtx);
+	std::string progress;
+	run_sql(prepare_query(
+			"SELECT progress FROM rss_sync_progress WHERE feedurl = '%q';",
+			feedurl),
+		single_string_callback,
+		&progress);
+	if (progress.empty()) {
+		return nonstd::nullopt;
+	}
+	return progress;
+}
+
+void Cache::externalize_rssfeed_unlocked(std::shared_ptr<RssFeed> feed,
+	bool reset_unread)
+{
 	CbHandler count_cbh;
 	auto query = prepare_query(
 			"SELECT count(*) FROM rss_feed WHERE rssurl = '%q';",
@@ -512,21 +1027,23 @@ void Cache::externalize_rssfeed(std::shared_ptr<RssFeed> feed,
 	if (count > 0) {
 		const std::string updatequery = prepare_query(
 				"UPDATE rss_feed "
-				"SET title = '%q', url = '%q', is_rtl = %u "
+				"SET title = '%q', url = '%q', is_rtl = %u, schedule = '%q' "
 				"WHERE rssurl = '%q';",
 				feed->title_raw(),
 				feed->link(),
 				feed->is_rtl() ? 1 : 0,
+				feed->schedule(),
 				feed->rssurl());
 		run_sql(updatequery);
 	} else {
 		const std::string insertquery = prepare_query(
-				"INSERT INTO rss_feed (rssurl, url, title, is_rtl) "
-				"VALUES ( '%q', '%q', '%q', %u );",
+				"INSERT INTO rss_feed (rssurl, url, title, is_rtl, schedule) "
+				"VALUES ( '%q', '%q', '%q', %u, '%q' );",
 				feed->rssurl(),
 				feed->link(),
 				feed->title_raw(),
-				feed->is_rtl() ? 1 : 0);
+				feed->is_rtl() ? 1 : 0,
+				feed->schedule());
 		run_sql(insertquery);
 	}
 
@@ -586,7 +1103,8 @@ std::shared_ptr<RssFeed> Cache::internalize_rssfeed(std::string rssurl,
 
 	/* then we first read the feed from the database */
 	query = prepare_query(
-			"SELECT title, url, is_rtl FROM rss_feed WHERE rssurl = '%q';",
+			"SELECT title, url, is_rtl, schedule FROM rss_feed "
+			"WHERE rssurl = '%q';",
 			rssurl);
 	run_sql(query, rssfeed_callback, &feed);
 
@@ -594,7 +1112,11 @@ std::shared_ptr<RssFeed> Cache::internalize_rssfeed(std::string rssurl,
 	query = prepare_query(
 			"SELECT guid, title, author, url, pubDate, length(content), "
 			"unread, "
-			"feedurl, enclosure_url, enclosure_type, enqueued, flags, base "
+			"feedurl, enclosure_url, enclosure_type, enqueued, flags, base, "
+			"updated, "
+			"(SELECT note FROM rss_item_note "
+			"WHERE rss_item_note.guid = rss_item.guid), "
+			"word_count "
 			"FROM rss_item "
 			"WHERE feedurl = '%q' "
 			"AND deleted = 0 "
@@ -607,6 +1129,8 @@ std::shared_ptr<RssFeed> Cache::internalize_rssfeed(std::string rssurl,
 		item->set_cache(this);
 		item->set_feedptr(feed_weak_ptr);
 		item->set_feedurl(feed->rssurl());
+		item->set_word_count(item->word_count(),
+			reading_minutes(cfg, item->word_count()));
 	}
 
 	if (ign != nullptr) {
@@ -636,8 +1160,13 @@ std::shared_ptr<RssFeed> Cache::internalize_rssfeed(std::string rssurl,
 		std::vector<std::shared_ptr<RssItem>> flagged_items;
 		for (unsigned int j = max_items; j < feed->total_item_count();
 			++j) {
-			if (feed->items()[j]->flags().length() == 0) {
-				delete_item(feed->items()[j]);
+			if (feed->items()[j]->flags().length() == 0
+				&& feed->items()[j]->note().empty()) {
+				// The next instance that writes to the cache will
+				// delete it
+				if (!read_only) {
+					delete_item(feed->items()[j]);
+				}
 			} else {
 				flagged_items.push_back(feed->items()[j]);
 			}
@@ -667,7 +1196,10 @@ std::vector<std::shared_ptr<RssItem>> Cache::search_for_items(
 				"SELECT guid, title, author, url, pubDate, "
 				"length(content), "
 				"unread, feedurl, enclosure_url, enclosure_type, "
-				"enqueued, flags, base "
+				"enqueued, flags, base, updated, "
+				"(SELECT note FROM rss_item_note "
+				"WHERE rss_item_note.guid = rss_item.guid), "
+				"word_count "
 				"FROM rss_item "
 				"WHERE (title LIKE '%%%q%%' OR content LIKE '%%%q%%') "
 				"AND feedurl = '%q' "
@@ -681,7 +1213,10 @@ std::vector<std::shared_ptr<RssItem>> Cache::search_for_items(
 				"SELECT guid, title, author, url, pubDate, "
 				"length(content), "
 				"unread, feedurl, enclosure_url, enclosure_type, "
-				"enqueued, flags, base "
+				"enqueued, flags, base, updated, "
+				"(SELECT note FROM rss_item_note "
+				"WHERE rss_item_note.guid = rss_item.guid), "
+				"word_count "
 				"FROM rss_item "
 				"WHERE (title LIKE '%%%q%%' OR content LIKE '%%%q%%') "
 				"AND deleted = 0 "
@@ -693,6 +1228,8 @@ std::vector<std::shared_ptr<RssItem>> Cache::search_for_items(
 	run_sql(query, search_item_callback, &items);
 	for (const auto& item : items) {
 		item->set_cache(this);
+		item->set_word_count(item->word_count(),
+			reading_minutes(cfg, item->word_count()));
 	}
 
 	return items;
@@ -756,35 +1293,7 @@ void Cache::cleanup_cache(std::vector<std::shared_ptr<RssFeed>> feeds)
 	 */
 	if (cfg->get_configvalue_as_bool("cleanup-on-quit")) {
 		LOG(Level::DEBUG, "Cache::cleanup_cache: cleaning up cache...");
-		std::string list = "(";
-
-		for (const auto& feed : feeds) {
-			std::string name =
-				prepare_query("'%q'", feed->rssurl());
-			list.append(name);
-			list.append(", ");
-		}
-		list.append("'')");
-
-		std::string cleanup_rss_feeds_statement(
-			"DELETE FROM rss_feed WHERE rssurl NOT IN ");
-		cleanup_rss_feeds_statement.append(list);
-		cleanup_rss_feeds_statement.push_back(';');
-
-		std::string cleanup_rss_items_statement(
-			"DELETE FROM rss_item WHERE feedurl NOT IN ");
-		cleanup_rss_items_statement.append(list);
-		cleanup_rss_items_statement.push_back(';');
-
-		std::string cleanup_read_items_statement(
-			"UPDATE rss_item SET deleted = 1 WHERE unread = 0");
-
-		run_sql(cleanup_rss_feeds_statement);
-		run_sql(cleanup_rss_items_statement);
-		if (cfg->get_configvalue_as_bool(
-				"delete-read-articles-on-quit")) {
-			run_sql(cleanup_read_items_statement);
-		}
+		remove_unused_entries_unlocked(feeds);
 
 		// WARNING: THE MISSING UNLOCK OPERATION IS MISSING FOR A
 		// PURPOSE! It's missing so that no database operation can occur
@@ -795,6 +1304,50 @@ void Cache::cleanup_cache(std::vector<std::shared_ptr<RssFeed>> feeds)
 	}
 }
 
+void Cache::cleanup_and_vacuum(std::vector<std::shared_ptr<RssFeed>> feeds)
+{
+	std::lock_guard<std::mutex> lock(mtx);
+	LOG(Level::DEBUG, "Cache::cleanup_and_vacuum: cleaning up cache...");
+	remove_unused_entries_unlocked(feeds);
+	run_sql("VACUUM;");
+}
+
+void Cache::remove_unused_entries_unlocked(
+	const std::vector<std::shared_ptr<RssFeed>>& feeds)
+{
+	std::string list = "(";
+
+	for (const auto& feed : feeds) {
+		std::string name =
+			prepare_query("'%q'", feed->rssurl());
+		list.append(name);
+		list.append(", ");
+	}
+	list.append("'')");
+
+	std::string cleanup_rss_feeds_statement(
+		"DELETE FROM rss_feed WHERE rssurl NOT IN ");
+	cleanup_rss_feeds_statement.append(list);
+	cleanup_rss_feeds_statement.push_back(';');
+
+	std::string cleanup_rss_items_statement(
+		"DELETE FROM rss_item WHERE feedurl NOT IN ");
+	cleanup_rss_items_statement.append(list);
+	cleanup_rss_items_statement.push_back(';');
+
+	std::string cleanup_read_items_statement(
+		"UPDATE rss_item SET deleted = 1 WHERE unread = 0");
+
+	run_sql(cleanup_rss_feeds_statement);
+	run_sql(cleanup_rss_items_statement);
+	run_sql("DELETE FROM rss_item_note "
+		"WHERE guid NOT IN (SELECT guid FROM rss_item);");
+	if (cfg->get_configvalue_as_bool(
+			"delete-read-articles-on-quit")) {
+		run_sql(cleanup_read_items_statement);
+	}
+}
+
 void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 	const std::string& feedurl,
 	bool reset_unread)
@@ -805,26 +1358,45 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 	CbHandler count_cbh;
 	run_sql(query, count_callback, &count_cbh);
 	if (count_cbh.count() > 0) {
-		if (reset_unread) {
-			std::string content;
+		StoredArticle stored;
+		query = prepare_query(
+				"SELECT title, content, content_hash, word_count "
+				"FROM rss_item WHERE guid = '%q';",
+				item->guid());
+		run_sql(query, stored_article_callback, &stored);
+
+		uint64_t content_hash = 0;
+		uint64_t words = 0;
+		rs_article_length_measure(item->description().c_str(),
+			stored.has_length,
+			stored.content_hash,
+			stored.words,
+			&content_hash,
+			&words);
+		item->set_word_count(words, reading_minutes(cfg, words));
+
+		const bool mark_unread = reset_unread ||
+			cfg->get_configvalue_as_bool("mark-updated-unread");
+		const auto decision = static_cast<ArticleUpdate>(
+				rs_decide_article_update(stored.title.c_str(),
+					stored.content.c_str(),
+					item->title().c_str(),
+					item->description().c_str(),
+					mark_unread));
+		if (decision != ArticleUpdate::UNCHANGED) {
+			LOG(Level::DEBUG,
+				"Cache::update_rssitem_unlocked: article `%s' was "
+				"updated by the publisher",
+				item->guid());
 			query = prepare_query(
-					"SELECT content FROM rss_item WHERE guid = "
-					"'%q';",
+					"UPDATE rss_item SET updated = 1%s WHERE "
+					"guid = '%q';",
+					decision == ArticleUpdate::UPDATED_UNREAD
+					? ", unread = 1"
+					: "",
 					item->guid());
-			run_sql(query, single_string_callback, &content);
-			if (content != item->description()) {
-				LOG(Level::DEBUG,
-					"Cache::update_rssitem_unlocked: '%s' "
-					"is "
-					"different from '%s'",
-					content,
-					item->description());
-				query = prepare_query(
-						"UPDATE rss_item SET unread = 1 WHERE "
-						"guid = '%q';",
-						item->guid());
-				run_sql(query);
-			}
+			run_sql(query);
+			item->set_updated(true);
 		}
 		std::string update;
 		if (item->override_unread()) {
@@ -834,7 +1406,7 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 					"feedurl = '%q', "
 					"content = '%q', enclosure_url = '%q', "
 					"enclosure_type = '%q', base = '%q', unread = "
-					"'%d' "
+					"'%d', content_hash = %lld, word_count = %llu "
 					"WHERE guid = '%q'",
 					item->title(),
 					item->author(),
@@ -845,6 +1417,8 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 					item->enclosure_type(),
 					item->get_base(),
 					(item->unread() ? 1 : 0),
+					static_cast<sqlite3_int64>(content_hash),
+					static_cast<unsigned long long>(words),
 					item->guid());
 		} else {
 			update = prepare_query(
@@ -852,7 +1426,8 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 					"SET title = '%q', author = '%q', url = '%q', "
 					"feedurl = '%q', "
 					"content = '%q', enclosure_url = '%q', "
-					"enclosure_type = '%q', base = '%q' "
+					"enclosure_type = '%q', base = '%q', "
+					"content_hash = %lld, word_count = %llu "
 					"WHERE guid = '%q'",
 					item->title(),
 					item->author(),
@@ -862,19 +1437,32 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 					item->enclosure_url(),
 					item->enclosure_type(),
 					item->get_base(),
+					static_cast<sqlite3_int64>(content_hash),
+					static_cast<unsigned long long>(words),
 					item->guid());
 		}
 		run_sql(update);
 	} else {
+		uint64_t content_hash = 0;
+		uint64_t words = 0;
+		rs_article_length_measure(item->description().c_str(),
+			false,
+			0,
+			0,
+			&content_hash,
+			&words);
+		item->set_word_count(words, reading_minutes(cfg, words));
+
 		std::string insert = prepare_query(
 				"INSERT INTO rss_item (guid, title, author, url, "
 				"feedurl, "
 				"pubDate, content, unread, enclosure_url, "
-				"enclosure_type, enqueued, base) "
+				"enclosure_type, enqueued, base, content_hash, "
+				"word_count) "
 				"VALUES "
 				"('%q','%q','%q','%q','%q','%u','%q','%d','%q','%q',%d,"
 				" "
-				"'%q')",
+				"'%q', %lld, %llu)",
 				item->guid(),
 				item->title(),
 				item->author(),
@@ -886,7 +1474,9 @@ void Cache::update_rssitem_unlocked(std::shared_ptr<RssItem> item,
 				item->enclosure_url(),
 				item->enclosure_type(),
 				item->enqueued() ? 1 : 0,
-				item->get_base());
+				item->get_base(),
+				static_cast<sqlite3_int64>(content_hash),
+				static_cast<unsigned long long>(words));
 		run_sql(insert);
 	}
 }
@@ -896,8 +1486,8 @@ void Cache::mark_all_read(std::shared_ptr<RssFeed> feed)
 	std::lock_guard<std::mutex> lock(mtx);
 	std::lock_guard<std::mutex> itemlock(feed->item_mutex);
 	std::string query =
-		"UPDATE rss_item SET unread = '0' WHERE unread != '0' AND guid "
-		"IN (";
+		"UPDATE rss_item SET unread = '0', updated = '0' "
+		"WHERE (unread != '0' OR updated != '0') AND guid IN (";
 
 	for (const auto& item : feed->items()) {
 		query.append(prepare_query("'%q',", item->guid()));
@@ -917,15 +1507,15 @@ void Cache::mark_all_read(const std::string& feedurl)
 	if (feedurl.length() > 0) {
 		query = prepare_query(
 				"UPDATE rss_item "
-				"SET unread = '0' "
-				"WHERE unread != '0' "
+				"SET unread = '0', updated = '0' "
+				"WHERE (unread != '0' OR updated != '0') "
 				"AND feedurl = '%q';",
 				feedurl);
 	} else {
 		query = prepare_query(
 				"UPDATE rss_item "
-				"SET unread = '0' "
-				"WHERE unread != '0';");
+				"SET unread = '0', updated = '0' "
+				"WHERE (unread != '0' OR updated != '0');");
 	}
 	run_sql(query);
 }
@@ -937,10 +1527,11 @@ void Cache::update_rssitem_unread_and_enqueued(RssItem* item,
 
 	const auto query = prepare_query(
 			"UPDATE rss_item "
-			"SET unread = '%d', enqueued = '%d' "
+			"SET unread = '%d', enqueued = '%d', updated = '%d' "
 			"WHERE guid = '%q'",
 			item->unread() ? 1 : 0,
 			item->enqueued() ? 1 : 0,
+			item->updated() ? 1 : 0,
 			item->guid());
 	run_sql(query);
 }
@@ -996,6 +1587,50 @@ void Cache::update_rssitem_flags(RssItem* item)
 	run_sql(update);
 }
 
+void Cache::update_rssitem_note(RssItem* item)
+{
+	std::lock_guard<std::mutex> lock(mtx);
+
+	if (item->note().empty()) {
+		run_sql(prepare_query(
+				"DELETE FROM rss_item_note WHERE guid = '%q';",
+				item->guid()));
+	} else {
+		run_sql(prepare_query(
+				"INSERT OR REPLACE INTO rss_item_note (guid, note) "
+				"VALUES ('%q', '%q');",
+				item->guid(),
+				item->note()));
+	}
+}
+
+void Cache::update_rssitem_states(
+	const std::vector<std::shared_ptr<RssItem>>& items)
+{
+	ScopeMeasure m1("Cache::update_rssitem_states");
+
+	std::lock_guard<std::mutex> lock(mtx);
+	run_sql("BEGIN TRANSACTION;");
+	try {
+		for (const auto& item : items) {
+			run_sql(prepare_query(
+					"UPDATE rss_item "
+					"SET unread = '%d', updated = '%d', deleted = %u, "
+					"flags = '%q' "
+					"WHERE guid = '%q';",
+					item->unread() ? 1 : 0,
+					item->updated() ? 1 : 0,
+					item->deleted() ? 1 : 0,
+					item->flags(),
+					item->guid()));
+		}
+	} catch (const DbException&) {
+		run_sql_nothrow("ROLLBACK;");
+		throw;
+	}
+	run_sql("COMMIT;");
+}
+
 void Cache::remove_old_deleted_items(RssFeed* feed)
 {
 	ScopeMeasure m1("Cache::remove_old_deleted_items");
@@ -1119,6 +1754,502 @@ std::string Cache::fetch_description(const RssItem& item)
 	return description;
 }
 
+struct MergedArticle {
+	std::string guid;
+	std::string title;
+	std::string author;
+	std::string url;
+	std::string feedurl;
+	std::string pubDate;
+	std::string content;
+	bool unread;
+	std::string enclosure_url;
+	std::string enclosure_type;
+	bool enqueued;
+	std::string flags;
+	std::string base;
+	std::string note;
+};
+
+static int merged_article_callback(void* vp, int argc, char** argv,
+	char** /* azColName */)
+{
+	auto articles = static_cast<std::vector<MergedArticle>*>(vp);
+	assert(argc == 13);
+	auto column = [&](int i) -> std::string {
+		return argv[i] ? argv[i] : "";
+	};
+
+	MergedArticle article;
+	article.guid = column(0);
+	article.title = column(1);
+	article.author = column(2);
+	article.url = column(3);
+	article.feedurl = column(4);
+	article.pubDate = column(5);
+	article.content = column(6);
+	article.unread = column(7) == "1";
+	article.enclosure_url = column(8);
+	article.enclosure_type = column(9);
+	article.enqueued = column(10) == "1";
+	article.flags = column(11);
+	article.base = column(12);
+	articles->push_back(std::move(article));
+
+	return 0;
+}
+
+static int existing_article_callback(void* merge, int argc, char** argv,
+	char** /* azColName */)
+{
+	assert(argc == 8);
+	auto column = [&](int i) -> const char* {
+		return argv[i] ? argv[i] : "";
+	};
+
+	rs_cache_merge_add_existing(merge,
+		column(0),
+		column(1),
+		column(2),
+		column(3),
+		column(4),
+		std::string(column(5)) == "1",
+		column(6),
+		column(7));
+
+	return 0;
+}
+
+static void load_from_other_cache(sqlite3* other,
+	std::vector<MergedArticle>& articles,
+	std::vector<std::vector<std::string>>& feeds)
+{
+	const std::string articles_query =
+		"SELECT guid, title, author, url, feedurl, pubDate, content, unread, "
+		"enclosure_url, enclosure_type, enqueued, flags, base "
+		"FROM rss_item WHERE deleted = 0 ORDER BY id;";
+	int rc = sqlite3_exec(other, articles_query.c_str(), merged_article_callback,
+			&articles, nullptr);
+	if (rc != SQLITE_OK) {
+		throw DbException(other);
+	}
+
+	auto store_feed = [](void* vp, int argc, char** argv, char**) -> int {
+		auto& feeds = *static_cast<std::vector<std::vector<std::string>>*>(vp);
+		std::vector<std::string> feed;
+		for (int i = 0; i < argc; ++i) {
+			feed.push_back(argv[i] ? argv[i] : "");
+		}
+		feeds.push_back(feed);
+		return 0;
+	};
+	rc = sqlite3_exec(other, "SELECT rssurl, url, title FROM rss_feed;",
+			store_feed, &feeds, nullptr);
+	if (rc != SQLITE_OK) {
+		throw DbException(other);
+	}
+
+	// Caches created by older versions have no notes table; that's fine
+	std::map<std::string, std::string> notes;
+	auto store_note = [](void* vp, int argc, char** argv, char**) -> int {
+		auto& notes = *static_cast<std::map<std::string, std::string>*>(vp);
+		assert(argc == 2);
+		notes[argv[0] ? argv[0] : ""] = argv[1] ? argv[1] : "";
+		return 0;
+	};
+	rc = sqlite3_exec(other, "SELECT guid, note FROM rss_item_note;",
+			store_note, &notes, nullptr);
+	if (rc == SQLITE_OK) {
+		for (auto& article : articles) {
+			const auto note = notes.find(article.guid);
+			if (note != notes.end()) {
+				article.note = note->second;
+			}
+		}
+	}
+}
+
+CacheMergeSummary Cache::merge_from(const std::string& other_cachefile,
+	bool prefer_read)
+{
+	std::vector<MergedArticle> articles;
+	std::vector<std::vector<std::string>> feeds;
+
+	sqlite3* other = nullptr;
+	const int rc = sqlite3_open_v2(other_cachefile.c_str(), &other,
+			SQLITE_OPEN_READONLY, nullptr);
+	try {
+		if (rc != SQLITE_OK) {
+			throw DbException(other);
+		}
+		load_from_other_cache(other, articles, feeds);
+	} catch (const DbException&) {
+		sqlite3_close(other);
+		throw;
+	}
+	sqlite3_close(other);
+
+	std::lock_guard<std::mutex> lock(mtx);
+
+	std::unique_ptr<void, void (*)(void*)> merge(
+		rs_cache_merge_new(prefer_read),
+		rs_cache_merge_free);
+	run_sql("SELECT guid, feedurl, url, title, content, unread, flags, "
+		"(SELECT note FROM rss_item_note "
+		"WHERE rss_item_note.guid = rss_item.guid) "
+		"FROM rss_item ORDER BY id;",
+		existing_article_callback,
+		merge.get());
+
+	run_sql("BEGIN TRANSACTION;");
+	try {
+		std::set<std::string> added_feeds;
+		for (const auto& article : articles) {
+			char* guid = nullptr;
+			bool unread = article.unread;
+			char* flags = nullptr;
+			char* note = nullptr;
+			const auto decision = static_cast<MergeDecision>(
+					rs_cache_merge_decide(merge.get(),
+						article.guid.c_str(),
+						article.feedurl.c_str(),
+						article.url.c_str(),
+						article.title.c_str(),
+						article.content.c_str(),
+						article.unread,
+						article.flags.c_str(),
+						article.note.c_str(),
+						&guid,
+						&unread,
+						&flags,
+						&note));
+
+			switch (decision) {
+			case MergeDecision::KEEP:
+				break;
+			case MergeDecision::ADD: {
+				uint64_t content_hash = 0;
+				uint64_t words = 0;
+				rs_article_length_measure(article.content.c_str(),
+					false,
+					0,
+					0,
+					&content_hash,
+					&words);
+				run_sql(prepare_query(
+						"INSERT INTO rss_item (guid, title, author, url, "
+						"feedurl, pubDate, content, unread, enclosure_url, "
+						"enclosure_type, enqueued, flags, base, "
+						"content_hash, word_count) "
+						"VALUES ('%q','%q','%q','%q','%q','%q','%q',%d,"
+						"'%q','%q',%d,'%q','%q',%lld,%llu);",
+						article.guid,
+						article.title,
+						article.author,
+						article.url,
+						article.feedurl,
+						article.pubDate,
+						article.content,
+						article.unread ? 1 : 0,
+						article.enclosure_url,
+						article.enclosure_type,
+						article.enqueued ? 1 : 0,
+						article.flags,
+						article.base,
+						static_cast<sqlite3_int64>(content_hash),
+						static_cast<unsigned long long>(words)));
+				if (!article.note.empty()) {
+					run_sql(prepare_query(
+							"INSERT OR REPLACE INTO rss_item_note (guid, note) "
+							"VALUES ('%q', '%q');",
+							article.guid,
+							article.note));
+				}
+				added_feeds.insert(article.feedurl);
+				break;
+			}
+			case MergeDecision::UPDATE: {
+				const std::string stored_guid = RustString(guid);
+				const std::string merged_flags = RustString(flags);
+				const std::string merged_note = RustString(note);
+				run_sql(prepare_query(
+						"UPDATE rss_item SET unread = %d, flags = '%q' "
+						"WHERE guid = '%q';",
+						unread ? 1 : 0,
+						merged_flags,
+						stored_guid));
+				if (!merged_note.empty()) {
+					run_sql(prepare_query(
+							"INSERT OR REPLACE INTO rss_item_note (guid, note) "
+							"VALUES ('%q', '%q');",
+							stored_guid,
+							merged_note));
+				}
+				break;
+			}
+			}
+		}
+
+		for (const auto& feed : feeds) {
+			if (added_feeds.count(feed[0]) > 0) {
+				run_sql(prepare_query(
+						"INSERT OR IGNORE INTO rss_feed (rssurl, url, title) "
+						"VALUES ('%q', '%q', '%q');",
+						feed[0],
+						feed[1],
+						feed[2]));
+			}
+		}
+	} catch (const DbException&) {
+		run_sql_nothrow("ROLLBACK;");
+		throw;
+	}
+	run_sql("COMMIT;");
+
+	size_t added = 0;
+	size_t merged = 0;
+	size_t conflicts = 0;
+	rs_cache_merge_summary(merge.get(), &added, &merged, &conflicts);
+
+	CacheMergeSummary summary;
+	summary.added = added;
+	summary.merged = merged;
+	summary.conflicts = conflicts;
+	return summary;
+}
+
+/// Tables whose rows are copied when recovering a damaged cache. The metadata
+/// table isn't among them: the fresh cache records its own schema version.
+static const std::vector<std::string> recovered_tables = {
+	"rss_feed", "rss_item", "google_replay", "rss_item_note"
+};
+
+static std::vector<std::string> table_columns(sqlite3* db,
+	const std::string& table)
+{
+	auto store_column = [](void* vp, int argc, char** argv, char**) -> int {
+		auto& columns = *static_cast<std::vector<std::string>*>(vp);
+		// PRAGMA table_info returns: cid, name, type, notnull, dflt_value, pk
+		assert(argc == 6);
+		columns.push_back(argv[1]);
+		return 0;
+	};
+
+	std::vector<std::string> columns;
+	const std::string query = "PRAGMA table_info(" + table + ");";
+	if (sqlite3_exec(db, query.c_str(), store_column, &columns,
+			nullptr) != SQLITE_OK) {
+		throw DbException(db);
+	}
+	return columns;
+}
+
+/// Copies the rows of `table` that can still be read from `damaged` into
+/// `fresh`. Rows are read in the order of their rowids; if reading stops at
+/// a damaged page, the following rowids are tried one by one until reading
+/// works again.
+static CacheRecoveryTable copy_table(sqlite3* damaged, sqlite3* fresh,
+	const std::string& table)
+{
+	CacheRecoveryTable result;
+	result.name = table;
+	result.recovered = 0;
+
+	// Caches created by older versions lack some of the columns
+	const auto damaged_columns = table_columns(damaged, table);
+	std::vector<std::string> columns;
+	for (const auto& column : table_columns(fresh, table)) {
+		if (std::find(damaged_columns.cbegin(), damaged_columns.cend(),
+				column) != damaged_columns.cend()) {
+			columns.push_back(column);
+		}
+	}
+	if (columns.empty()) {
+		result.lost = 0;
+		return result;
+	}
+
+	const std::string column_list = utils::join(columns, ", ");
+	const std::vector<std::string> placeholders(columns.size(), "?");
+	const std::string select_query = "SELECT rowid, " + column_list +
+		" FROM " + table + " WHERE rowid > ? ORDER BY rowid;";
+	const std::string insert_query = "INSERT OR IGNORE INTO " + table + " (" +
+		column_list + ") VALUES (" + utils::join(placeholders, ", ") + ");";
+
+	sqlite3_stmt* select = nullptr;
+	sqlite3_stmt* insert = nullptr;
+	if (sqlite3_prepare_v2(damaged, select_query.c_str(), -1, &select,
+			nullptr) != SQLITE_OK) {
+		const DbException e(damaged);
+		sqlite3_finalize(select);
+		throw e;
+	}
+	if (sqlite3_prepare_v2(fresh, insert_query.c_str(), -1, &insert,
+			nullptr) != SQLITE_OK) {
+		const DbException e(fresh);
+		sqlite3_finalize(select);
+		sqlite3_finalize(insert);
+		throw e;
+	}
+
+	// These only need the first or the last page of the table, or one of its
+	// indexes, so they often work even if other pages are damaged
+	const auto last_rowid = query_integer(damaged,
+			"SELECT max(rowid) FROM " + table + ";");
+	const auto row_count = query_integer(damaged,
+			"SELECT count(*) FROM " + table + ";");
+
+	// Rowids start at 1
+	sqlite3_int64 after = 0;
+	while (true) {
+		sqlite3_bind_int64(select, 1, after);
+		int rc;
+		while ((rc = sqlite3_step(select)) == SQLITE_ROW) {
+			after = sqlite3_column_int64(select, 0);
+			for (size_t i = 0; i < columns.size(); ++i) {
+				sqlite3_bind_value(insert, i + 1,
+					sqlite3_column_value(select, i + 1));
+			}
+			const int insert_rc = sqlite3_step(insert);
+			sqlite3_reset(insert);
+			if (insert_rc != SQLITE_DONE) {
+				const DbException e(fresh);
+				sqlite3_finalize(select);
+				sqlite3_finalize(insert);
+				throw e;
+			}
+			// Rows that violate a constraint are ignored
+			if (sqlite3_changes(fresh) > 0) {
+				result.recovered++;
+			}
+		}
+		sqlite3_reset(select);
+		if (rc == SQLITE_DONE) {
+			break;
+		}
+
+		LOG(Level::DEBUG,
+			"copy_table: reading %s after rowid %" PRId64 " failed: %s",
+			table,
+			static_cast<int64_t>(after),
+			sqlite3_errmsg(damaged));
+		if (!last_rowid.has_value() || after >= last_rowid.value()) {
+			break;
+		}
+		after++;
+	}
+
+	sqlite3_finalize(select);
+	sqlite3_finalize(insert);
+
+	if (row_count.has_value() && row_count.value() >= result.recovered) {
+		result.lost = row_count.value() - result.recovered;
+	}
+	return result;
+}
+
+static bool copy_file(const std::string& from, const std::string& to)
+{
+	std::ifstream in(from, std::ios::binary);
+	std::ofstream out(to, std::ios::binary | std::ios::trunc);
+	if (!in.is_open() || !out.is_open()) {
+		return false;
+	}
+	out << in.rdbuf();
+	out.close();
+	return !out.fail();
+}
+
+std::vector<std::string> Cache::check_integrity(const std::string& cachefile)
+{
+	sqlite3* damaged = nullptr;
+	if (sqlite3_open_v2(cachefile.c_str(), &damaged, SQLITE_OPEN_READONLY,
+			nullptr) != SQLITE_OK) {
+		const DbException e(damaged);
+		sqlite3_close(damaged);
+		throw e;
+	}
+
+	auto store_problem = [](void* vp, int argc, char** argv, char**) -> int {
+		auto& problems = *static_cast<std::vector<std::string>*>(vp);
+		assert(argc == 1);
+		if (argv[0] != nullptr && std::string(argv[0]) != "ok") {
+			problems.push_back(argv[0]);
+		}
+		return 0;
+	};
+
+	std::vector<std::string> problems;
+	if (sqlite3_exec(damaged, "PRAGMA integrity_check;", store_problem,
+			&problems, nullptr) != SQLITE_OK) {
+		// The check itself can stumble over the damage
+		problems.push_back(sqlite3_errmsg(damaged));
+	}
+	sqlite3_close(damaged);
+	return problems;
+}
+
+nonstd::optional<CacheRecoverySummary> Cache::recover(
+	const std::string& cachefile,
+	ConfigContainer* cfg,
+	bool force,
+	const std::function<void(const CacheRecoveryTable&)>& progress)
+{
+	const auto problems = check_integrity(cachefile);
+	if (problems.empty() && !force) {
+		return nonstd::nullopt;
+	}
+	for (const auto& problem : problems) {
+		LOG(Level::INFO, "Cache::recover: %s", problem);
+	}
+
+	sqlite3* damaged = nullptr;
+	if (sqlite3_open_v2(cachefile.c_str(), &damaged, SQLITE_OPEN_READONLY,
+			nullptr) != SQLITE_OK) {
+		const DbException e(damaged);
+		sqlite3_close(damaged);
+		throw e;
+	}
+
+	const std::string fresh_file = cachefile + ".recovered";
+	std::remove(fresh_file.c_str());
+
+	CacheRecoverySummary summary;
+	try {
+		Cache fresh(fresh_file, cfg);
+		fresh.run_sql("BEGIN TRANSACTION;");
+		for (const auto& table : recovered_tables) {
+			summary.tables.push_back(copy_table(damaged, fresh.db, table));
+			progress(summary.tables.back());
+		}
+		fresh.run_sql("COMMIT;");
+		fresh.run_sql("REINDEX;");
+		fresh.run_sql("ANALYZE;");
+	} catch (const DbException&) {
+		sqlite3_close(damaged);
+		std::remove(fresh_file.c_str());
+		throw;
+	}
+	sqlite3_close(damaged);
+
+	summary.backup_file = cachefile + ".corrupt";
+	if (!copy_file(cachefile, summary.backup_file)) {
+		std::remove(fresh_file.c_str());
+		throw std::runtime_error("couldn't write " + summary.backup_file);
+	}
+
+	// rename() replaces the file atomically, so there is always a cache file,
+	// either the damaged one or the fresh one
+	if (std::rename(fresh_file.c_str(), cachefile.c_str()) != 0) {
+		const std::string error = strerror(errno);
+		std::remove(fresh_file.c_str());
+		throw std::runtime_error("couldn't replace " + cachefile + ": " +
+			error);
+	}
+
+	return summary;
+}
+
 SchemaVersion Cache::get_schema_version()
 {
 	sqlite3_stmt* stmt{};
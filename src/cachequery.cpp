@@ -0,0 +1,59 @@
+#include "cachequery.h"
+
+#include <cstdint>
+
+#include "matchable.h"
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_query_result_new(bool json,
+		const char* const* fields,
+		std::size_t field_count);
+
+	void rs_query_result_free(void* result);
+
+	void rs_query_result_add_row(void* result,
+		const char* const* values,
+		std::size_t value_count);
+
+	char* rs_query_result_render(void* result);
+}
+
+namespace newsboat {
+
+QueryResult::QueryResult(const std::vector<std::string>& fields, bool json)
+	: fields(fields)
+{
+	std::vector<const char*> names;
+	for (const auto& field : fields) {
+		names.push_back(field.c_str());
+	}
+	rs_result = rs_query_result_new(json, names.data(), names.size());
+}
+
+QueryResult::~QueryResult()
+{
+	rs_query_result_free(rs_result);
+}
+
+void QueryResult::add_article(const Matchable& article)
+{
+	std::vector<nonstd::optional<std::string>> values;
+	for (const auto& field : fields) {
+		values.push_back(article.attribute_value(field));
+	}
+
+	// Null pointers stand for attributes that the article doesn't have
+	std::vector<const char*> pointers;
+	for (const auto& value : values) {
+		pointers.push_back(value.has_value() ? value->c_str() : nullptr);
+	}
+	rs_query_result_add_row(rs_result, pointers.data(), pointers.size());
+}
+
+std::string QueryResult::render() const
+{
+	return RustString(rs_query_result_render(rs_result));
+}
+
+} // namespace newsboat
@@ -15,9 +15,12 @@ extern "C" {
 	bool rs_cliargsparser_do_import(void* rs_cliargsparser);
 
 	bool rs_cliargsparser_do_export(void* rs_cliargsparser);
+	bool rs_cliargsparser_export_opml2(void* rs_cliargsparser);
 
 	bool rs_cliargsparser_do_vacuum(void* rs_cliargsparser);
 
+	bool rs_cliargsparser_cleanup(void* rs_cliargsparser);
+
 	char* rs_cliargsparser_importfile(void* rs_cliargsparser);
 
 	char* rs_cliargsparser_program_name(void* rs_cliargsparser);
@@ -67,6 +70,10 @@ extern "C" {
 	unsigned int rs_cliargsparser_cmds_to_execute_count(void* rs_cliargsparser);
 	char* rs_cliargsparser_cmd_to_execute_n(void* rs_cliargsparser, unsigned int n);
 
+	size_t rs_cliargsparser_config_overrides_count(void* rs_cliargsparser);
+	char* rs_cliargsparser_config_override_key_n(void* rs_cliargsparser, size_t n);
+	char* rs_cliargsparser_config_override_value_n(void* rs_cliargsparser, size_t n);
+
 	bool rs_cliargsparser_set_log_file(void* rs_cliargsparser);
 
 	char* rs_cliargsparser_log_file(void* rs_cliargsparser);
@@ -74,6 +81,49 @@ extern "C" {
 	bool rs_cliargsparser_set_log_level(void* rs_cliargsparser);
 
 	unsigned char rs_cliargsparser_log_level(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_set_profile(void* rs_cliargsparser);
+
+	char* rs_cliargsparser_profile(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_list_profiles(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_json_errors(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_set_merge_cache_file(void* rs_cliargsparser);
+
+	char* rs_cliargsparser_merge_cache_file(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_merge_prefer_read(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_recover_cache(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_force(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_import_from_newsbeuter(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_dry_run_migration(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_convert_to_utf8(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_plain_output(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_set_add_feed(void* rs_cliargsparser);
+
+	char* rs_cliargsparser_add_feed(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_set_url_argument(void* rs_cliargsparser);
+
+	char* rs_cliargsparser_url_argument(void* rs_cliargsparser);
+
+	bool rs_cliargsparser_set_query_cache(void* rs_cliargsparser);
+
+	char* rs_cliargsparser_query_cache(void* rs_cliargsparser);
+
+	size_t rs_cliargsparser_query_fields_count(void* rs_cliargsparser);
+	char* rs_cliargsparser_query_field_n(void* rs_cliargsparser, size_t n);
+
+	bool rs_cliargsparser_query_format_json(void* rs_cliargsparser);
 }
 
 #define GET_VALUE(NAME, DEFAULT) \
@@ -136,11 +186,21 @@ bool CliArgsParser::do_export() const
 	GET_VALUE(do_export, false);
 }
 
+bool CliArgsParser::export_opml2() const
+{
+	GET_VALUE(export_opml2, false);
+}
+
 bool CliArgsParser::do_vacuum() const
 {
 	GET_VALUE(do_vacuum, false);
 }
 
+bool CliArgsParser::cleanup() const
+{
+	GET_VALUE(cleanup, false);
+}
+
 std::string CliArgsParser::importfile() const
 {
 	GET_STRING(importfile);
@@ -238,6 +298,23 @@ nonstd::optional<std::vector<std::string>> CliArgsParser::cmds_to_execute()
 	}
 }
 
+std::vector<std::pair<std::string, std::string>>
+		CliArgsParser::config_overrides() const
+{
+	std::vector<std::pair<std::string, std::string>> result;
+	if (rs_cliargsparser) {
+		const auto count = rs_cliargsparser_config_overrides_count(rs_cliargsparser);
+		for (size_t i = 0; i < count; ++i) {
+			result.emplace_back(
+				std::string(RustString(
+						rs_cliargsparser_config_override_key_n(rs_cliargsparser, i))),
+				std::string(RustString(
+						rs_cliargsparser_config_override_value_n(rs_cliargsparser, i))));
+		}
+	}
+	return result;
+}
+
 nonstd::optional<std::string> CliArgsParser::log_file() const
 {
 	GET_OPTIONAL_STRING(set_log_file, log_file);
@@ -256,6 +333,94 @@ nonstd::optional<Level> CliArgsParser::log_level() const
 	}
 }
 
+nonstd::optional<std::string> CliArgsParser::profile() const
+{
+	GET_OPTIONAL_STRING(set_profile, profile);
+}
+
+bool CliArgsParser::list_profiles() const
+{
+	GET_VALUE(list_profiles, false);
+}
+
+bool CliArgsParser::json_errors() const
+{
+	GET_VALUE(json_errors, false);
+}
+
+nonstd::optional<std::string> CliArgsParser::merge_cache_file() const
+{
+	GET_OPTIONAL_STRING(set_merge_cache_file, merge_cache_file);
+}
+
+bool CliArgsParser::merge_prefer_read() const
+{
+	GET_VALUE(merge_prefer_read, true);
+}
+
+bool CliArgsParser::recover_cache() const
+{
+	GET_VALUE(recover_cache, false);
+}
+
+bool CliArgsParser::force() const
+{
+	GET_VALUE(force, false);
+}
+
+bool CliArgsParser::import_from_newsbeuter() const
+{
+	GET_VALUE(import_from_newsbeuter, false);
+}
+
+bool CliArgsParser::dry_run_migration() const
+{
+	GET_VALUE(dry_run_migration, false);
+}
+
+bool CliArgsParser::convert_to_utf8() const
+{
+	GET_VALUE(convert_to_utf8, false);
+}
+
+bool CliArgsParser::plain_output() const
+{
+	GET_VALUE(plain_output, false);
+}
+
+nonstd::optional<std::string> CliArgsParser::add_feed() const
+{
+	GET_OPTIONAL_STRING(set_add_feed, add_feed);
+}
+
+nonstd::optional<std::string> CliArgsParser::url_argument() const
+{
+	GET_OPTIONAL_STRING(set_url_argument, url_argument);
+}
+
+nonstd::optional<std::string> CliArgsParser::query_cache() const
+{
+	GET_OPTIONAL_STRING(set_query_cache, query_cache);
+}
+
+std::vector<std::string> CliArgsParser::query_fields() const
+{
+	std::vector<std::string> result;
+	if (rs_cliargsparser) {
+		const auto count = rs_cliargsparser_query_fields_count(rs_cliargsparser);
+		for (size_t i = 0; i < count; ++i) {
+			result.push_back(RustString(rs_cliargsparser_query_field_n(
+						rs_cliargsparser, i)));
+		}
+	}
+	return result;
+}
+
+bool CliArgsParser::query_format_json() const
+{
+	GET_VALUE(query_format_json, false);
+}
+
 void* CliArgsParser::get_rust_pointer() const
 {
 	return rs_cliargsparser;
@@ -0,0 +1,40 @@
+#include "clierror.h"
+
+#include "ruststring.h"
+
+extern "C" {
+	int rs_cli_error_exit_code(std::uint8_t category);
+
+	char* rs_cli_error_format(std::uint8_t category,
+		const char* message,
+		const char* file,
+		unsigned int line,
+		bool json);
+}
+
+namespace newsboat {
+
+namespace clierror {
+
+int exit_code(ErrorCategory category)
+{
+	return rs_cli_error_exit_code(static_cast<std::uint8_t>(category));
+}
+
+std::string format(ErrorCategory category,
+	const std::string& message,
+	bool json,
+	const std::string& file,
+	unsigned int line)
+{
+	return RustString(rs_cli_error_format(
+				static_cast<std::uint8_t>(category),
+				message.c_str(),
+				file.empty() ? nullptr : file.c_str(),
+				line,
+				json));
+}
+
+} // namespace clierror
+
+} // namespace newsboat
@@ -0,0 +1,75 @@
+#include "clioutput.h"
+
+#include <cstdint>
+
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_cli_report_new(bool force_plain);
+
+	void rs_cli_report_free(void* report);
+
+	void rs_cli_report_add_feed(void* report,
+		const char* title,
+		std::uint32_t unread);
+
+	void rs_cli_report_add_failure(void* report,
+		const char* url,
+		const char* message);
+
+	char* rs_cli_report_unread_summary(void* report, std::uint32_t total);
+
+	char* rs_cli_report_failure_report(void* report);
+}
+
+namespace newsboat {
+
+namespace {
+
+std::vector<std::string> split_lines(const std::string& lines)
+{
+	if (lines.empty()) {
+		return {};
+	}
+	return utils::tokenize(lines, "\n");
+}
+
+} // namespace
+
+CliReport::CliReport(bool force_plain)
+{
+	rs_report = rs_cli_report_new(force_plain);
+}
+
+CliReport::~CliReport()
+{
+	rs_cli_report_free(rs_report);
+}
+
+void CliReport::add_feed(const std::string& title, unsigned int unread)
+{
+	rs_cli_report_add_feed(rs_report, title.c_str(), unread);
+}
+
+void CliReport::add_failure(const std::string& url,
+	const std::string& message)
+{
+	rs_cli_report_add_failure(rs_report, url.c_str(), message.c_str());
+}
+
+std::vector<std::string> CliReport::unread_summary(unsigned int total) const
+{
+	const std::string lines = RustString(rs_cli_report_unread_summary(
+				rs_report, total));
+	return split_lines(lines);
+}
+
+std::vector<std::string> CliReport::failure_report() const
+{
+	const std::string lines = RustString(rs_cli_report_failure_report(
+				rs_report));
+	return split_lines(lines);
+}
+
+} // namespace newsboat
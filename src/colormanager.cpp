@@ -10,20 +10,47 @@
 #include "logger.h"
 #include "matcherexception.h"
 #include "pbview.h"
+#include "ruststring.h"
 #include "selectformaction.h"
 #include "strprintf.h"
+#include "termcaps.h"
 #include "urlviewformaction.h"
 #include "utils.h"
 
+extern "C" {
+	void* rs_color_manager_new();
+
+	void rs_color_manager_free(void* manager);
+
+	char* rs_color_manager_handle_color(void* manager,
+		const char* const* params,
+		std::size_t params_count);
+
+	char* rs_color_manager_elements(void* manager);
+
+	char* rs_color_manager_style(void* manager,
+		const char* element,
+		char** fg,
+		char** bg);
+
+	char* rs_color_manager_stfl_style(void* manager, const char* element);
+
+	char* rs_color_manager_dump_config(void* manager);
+}
+
 using namespace podboat;
 
 namespace newsboat {
 
 ColorManager::ColorManager()
 {
+	rs_object = rs_color_manager_new();
 }
 
-ColorManager::~ColorManager() {}
+ColorManager::~ColorManager()
+{
+	rs_color_manager_free(rs_object);
+}
 
 void ColorManager::register_commands(ConfigParser& cfgparser)
 {
@@ -42,43 +69,15 @@ void ColorManager::handle_action(const std::string& action,
 				ActionHandlerStatus::TOO_FEW_PARAMS);
 		}
 
-		/*
-		 * the command syntax is:
-		 * color <element> <fgcolor> <bgcolor> [<attribute> ...]
-		 */
-		std::string element = params[0];
-		std::string fgcolor = params[1];
-		std::string bgcolor = params[2];
-
-		if (!utils::is_valid_color(fgcolor))
-			throw ConfigHandlerException(strprintf::fmt(
-					_("`%s' is not a valid color"), fgcolor));
-		if (!utils::is_valid_color(bgcolor))
-			throw ConfigHandlerException(strprintf::fmt(
-					_("`%s' is not a valid color"), bgcolor));
-
-		std::vector<std::string> attribs;
-		for (unsigned int i = 3; i < params.size(); ++i) {
-			if (!utils::is_valid_attribute(params[i]))
-				throw ConfigHandlerException(strprintf::fmt(
-						_("`%s' is not a valid attribute"),
-						params[i]));
-			attribs.push_back(params[i]);
+		std::vector<const char*> c_params;
+		for (const auto& param : params) {
+			c_params.push_back(param.c_str());
+		}
+		char* error = rs_color_manager_handle_color(rs_object,
+				c_params.data(), c_params.size());
+		if (error != nullptr) {
+			throw ConfigHandlerException(std::string(RustString(error)));
 		}
-
-		/* we only allow certain elements to be configured, also to
-		 * indicate the user possible mis-spellings */
-		if (element == "listnormal" || element == "listfocus" ||
-			element == "listnormal_unread" ||
-			element == "listfocus_unread" || element == "info" ||
-			element == "background" || element == "article" ||
-			element == "end-of-text-marker") {
-			element_styles[element] = {fgcolor, bgcolor, attribs};
-		} else
-			throw ConfigHandlerException(strprintf::fmt(
-					_("`%s' is not a valid configuration element"),
-					element));
-
 	} else
 		throw ConfigHandlerException(
 			ActionHandlerStatus::INVALID_COMMAND);
@@ -86,51 +85,51 @@ void ColorManager::handle_action(const std::string& action,
 
 void ColorManager::dump_config(std::vector<std::string>& config_output) const
 {
-	for (const auto& element_style : element_styles) {
-		const std::string& element = element_style.first;
-		const TextStyle& style = element_style.second;
-		std::string configline = strprintf::fmt("color %s %s %s",
-				element,
-				style.fg_color,
-				style.bg_color);
-		for (const auto& attrib : style.attributes) {
-			configline.append(" ");
-			configline.append(attrib);
+	const std::string lines = RustString(
+			rs_color_manager_dump_config(rs_object));
+	for (const auto& line : utils::tokenize(lines, "\n")) {
+		config_output.push_back(line);
+	}
+}
+
+std::vector<std::string> ColorManager::elements() const
+{
+	const std::string elements = RustString(
+			rs_color_manager_elements(rs_object));
+	return utils::tokenize(elements, "\n");
+}
+
+std::map<std::string, TextStyle> ColorManager::get_styles() const
+{
+	std::map<std::string, TextStyle> styles;
+	for (const auto& element : elements()) {
+		char* fg = nullptr;
+		char* bg = nullptr;
+		char* attributes = rs_color_manager_style(rs_object,
+				element.c_str(), &fg, &bg);
+		if (attributes == nullptr) {
+			continue;
 		}
-		config_output.push_back(configline);
+		TextStyle& style = styles[element];
+		style.fg_color = RustString(fg);
+		style.bg_color = RustString(bg);
+		style.attributes = utils::tokenize(RustString(attributes), "\n");
 	}
+	return styles;
 }
 
 void ColorManager::apply_colors(Stfl::Form& form) const
 {
-	for (const auto& element_style : element_styles) {
-		const std::string& element = element_style.first;
-		const TextStyle& style = element_style.second;
-		std::string colorattr;
-		if (style.fg_color != "default") {
-			colorattr.append("fg=");
-			colorattr.append(style.fg_color);
-		}
-		if (style.bg_color != "default") {
-			if (colorattr.length() > 0) {
-				colorattr.append(",");
-			}
-			colorattr.append("bg=");
-			colorattr.append(style.bg_color);
-		}
-		for (const auto& attr : style.attributes) {
-			if (colorattr.length() > 0) {
-				colorattr.append(",");
-			}
-			colorattr.append("attr=");
-			colorattr.append(attr);
-		}
+	for (const auto& element : elements()) {
+		std::string colorattr = RustString(
+				rs_color_manager_stfl_style(rs_object, element.c_str()));
 
 		LOG(Level::DEBUG,
 			"ColorManager::set_pb_colors: %s %s\n",
 			element,
 			colorattr);
 
+		colorattr = termcaps::degrade_style(colorattr);
 		form.set(element, colorattr);
 
 		if (element == "article") {
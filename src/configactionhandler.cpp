@@ -16,4 +16,6 @@ void ConfigActionHandler::handle_action(const std::string&,
 {
 }
 
+void ConfigActionHandler::set_origin(const std::string&, unsigned int) {}
+
 } // namespace newsboat
@@ -12,9 +12,21 @@
 #include "configdata.h"
 #include "confighandlerexception.h"
 #include "logger.h"
+#include "ruststring.h"
 #include "strprintf.h"
 #include "utils.h"
 
+extern "C" {
+	char* rs_config_validate(const char* option, const char* value);
+	char* rs_unknown_option_message(const char* name, const char* known);
+	char* rs_describe_option(const char* name,
+		const char* kind,
+		const char* values,
+		const char* default_value,
+		const char* value,
+		const char* origin);
+}
+
 namespace newsboat {
 
 const std::string ConfigContainer::PARTIAL_FILE_SUFFIX = ".part";
@@ -23,6 +35,10 @@ ConfigContainer::ConfigContainer()
 // create the config options and set their resp. default value and type
 	: config_data{{"always-display-description",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"article-cache-entries", ConfigData("64", ConfigDataType::INT)},
+	{"article-cache-size", ConfigData("4096", ConfigDataType::INT)},
+	{"article-primary-action", ConfigData("", ConfigDataType::STR)},
+	{"article-secondary-action", ConfigData("", ConfigDataType::STR)},
 	{
 		"article-sort-order",
 		ConfigData("date-asc", ConfigDataType::STR)},
@@ -30,6 +46,9 @@ ConfigContainer::ConfigContainer()
 		"articlelist-format",
 		ConfigData("%4i %f %D %6L  %?T?|%-17T|  &?%t",
 			ConfigDataType::STR)},
+	{"articlelist-primary-action", ConfigData("", ConfigDataType::STR)},
+	{"articlelist-secondary-action", ConfigData("", ConfigDataType::STR)},
+	{"articlelist-wrap-titles", ConfigData("no", ConfigDataType::BOOL)},
 	{"auto-reload", ConfigData("no", ConfigDataType::BOOL)},
 	{
 		"bookmark-autopilot",
@@ -62,9 +81,13 @@ ConfigContainer::ConfigContainer()
 	{
 		"download-full-page",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"download-low-speed-limit", ConfigData("10", ConfigDataType::INT)},
+	{"download-low-speed-time", ConfigData("15", ConfigDataType::INT)},
 	{"download-path", ConfigData("~/", ConfigDataType::PATH)},
 	{"download-retries", ConfigData("1", ConfigDataType::INT)},
 	{"download-timeout", ConfigData("30", ConfigDataType::INT)},
+	{"empty-feed-accept-after", ConfigData("3", ConfigDataType::INT)},
+	{"empty-feed-min-items", ConfigData("0", ConfigDataType::INT)},
 	{"error-log", ConfigData("", ConfigDataType::PATH)},
 	{"external-url-viewer", ConfigData("", ConfigDataType::PATH)},
 	{
@@ -86,10 +109,20 @@ ConfigContainer::ConfigContainer()
 	{
 		"feedlist-format",
 		ConfigData("%4i %n %11u %t", ConfigDataType::STR)},
+	{"feedlist-primary-action", ConfigData("", ConfigDataType::STR)},
+	{"feedlist-secondary-action", ConfigData("", ConfigDataType::STR)},
+	{"filebrowser-dirs-first", ConfigData("no", ConfigDataType::BOOL)},
+	{"filebrowser-show-hidden", ConfigData("yes", ConfigDataType::BOOL)},
+	{
+		"filebrowser-sort-order",
+		ConfigData("name",
+		std::unordered_set<std::string>({"name", "mtime", "size"}))},
 	{"goto-first-unread", ConfigData("true", ConfigDataType::BOOL)},
 	{"goto-next-feed", ConfigData("yes", ConfigDataType::BOOL)},
 	{"history-limit", ConfigData("100", ConfigDataType::INT)},
+	{"html-max-nesting", ConfigData("16", ConfigDataType::INT)},
 	{"html-renderer", ConfigData("internal", ConfigDataType::PATH)},
+	{"hyphenation", ConfigData("no", ConfigDataType::BOOL)},
 	{
 		"http-auth-method",
 		ConfigData("any",
@@ -120,9 +153,16 @@ ConfigContainer::ConfigContainer()
 	{"inoreader-flag-star", ConfigData("", ConfigDataType::STR)},
 	{"inoreader-min-items", ConfigData("20", ConfigDataType::INT)},
 	{"keep-articles-days", ConfigData("0", ConfigDataType::INT)},
+	{
+		"long-operation-notify-threshold",
+		ConfigData("0", ConfigDataType::INT)},
 	{
 		"mark-as-read-on-hover",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"mark-read-on", ConfigData("open", ConfigDataType::STR)},
+	{
+		"mark-updated-unread",
+		ConfigData("false", ConfigDataType::BOOL)},
 	{"max-browser-tabs", ConfigData("10", ConfigDataType::INT)},
 	{
 		"markfeedread-jumps-to-next-unread",
@@ -130,6 +170,10 @@ ConfigContainer::ConfigContainer()
 	{"max-download-speed", ConfigData("0", ConfigDataType::INT)},
 	{"max-downloads", ConfigData("1", ConfigDataType::INT)},
 	{"max-items", ConfigData("0", ConfigDataType::INT)},
+	{"max-title-rows", ConfigData("3", ConfigDataType::INT)},
+	{"network-retry", ConfigData("yes", ConfigDataType::BOOL)},
+	{"network-retry-threshold", ConfigData("50", ConfigDataType::INT)},
+	{"network-retry-url", ConfigData("", ConfigDataType::STR)},
 	{"newsblur-login", ConfigData("", ConfigDataType::STR)},
 	{"newsblur-min-items", ConfigData("20", ConfigDataType::INT)},
 	{"newsblur-password", ConfigData("", ConfigDataType::STR)},
@@ -164,6 +208,8 @@ ConfigContainer::ConfigContainer()
 	{
 		"openbrowser-and-mark-jumps-to-next-unread",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"operation-timing", ConfigData("yes", ConfigDataType::BOOL)},
+	{"operation-timing-threshold", ConfigData("1000", ConfigDataType::INT)},
 	{"opml-url", ConfigData("", ConfigDataType::STR, true)},
 	{"pager", ConfigData("internal", ConfigDataType::PATH)},
 	{"player", ConfigData("", ConfigDataType::PATH)},
@@ -176,6 +222,13 @@ ConfigContainer::ConfigContainer()
 	{
 		"prepopulate-query-feeds",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"preview-pane", ConfigData("no", ConfigDataType::BOOL)},
+	{
+		"preview-pane-position",
+		ConfigData("below",
+			std::unordered_set<std::string>(
+		{"below", "right"}))},
+	{"preview-pane-size", ConfigData("50", ConfigDataType::INT)},
 	{"ssl-verifyhost", ConfigData("true", ConfigDataType::BOOL)},
 	{"ssl-verifypeer", ConfigData("true", ConfigDataType::BOOL)},
 	{"proxy", ConfigData("", ConfigDataType::STR)},
@@ -198,10 +251,12 @@ ConfigContainer::ConfigContainer()
 			"socks4a",
 			"socks5",
 			"socks5h"}))},
+	{"reading-speed", ConfigData("200", ConfigDataType::INT)},
 	{"refresh-on-startup", ConfigData("no", ConfigDataType::BOOL)},
 	{
 		"reload-only-visible-feeds",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"reload-hook-timeout", ConfigData("30", ConfigDataType::INT)},
 	{"reload-threads", ConfigData("1", ConfigDataType::INT)},
 	{"reload-time", ConfigData("60", ConfigDataType::INT)},
 	{"save-path", ConfigData("~/", ConfigDataType::PATH)},
@@ -218,6 +273,12 @@ ConfigContainer::ConfigContainer()
 	{"show-title-bar", ConfigData("yes", ConfigDataType::BOOL)},
 	{"show-read-articles", ConfigData("yes", ConfigDataType::BOOL)},
 	{"show-read-feeds", ConfigData("yes", ConfigDataType::BOOL)},
+	{"single-instance", ConfigData("no", ConfigDataType::BOOL)},
+	{
+		"strip-bidi-controls",
+		ConfigData("urls",
+			std::unordered_set<std::string>(
+		{"none", "urls", "all"}))},
 	{
 		"suppress-first-reload",
 		ConfigData("no", ConfigDataType::BOOL)},
@@ -228,6 +289,7 @@ ConfigContainer::ConfigContainer()
 	{
 		"toggleitemread-jumps-to-next-unread",
 		ConfigData("false", ConfigDataType::BOOL)},
+	{"truncation-marker", ConfigData("", ConfigDataType::STR)},
 	{"ttrss-flag-publish", ConfigData("", ConfigDataType::STR)},
 	{"ttrss-flag-star", ConfigData("", ConfigDataType::STR)},
 	{"ttrss-login", ConfigData("", ConfigDataType::STR)},
@@ -250,6 +312,8 @@ ConfigContainer::ConfigContainer()
 	{"miniflux-password", ConfigData("", ConfigDataType::STR)},
 	{"miniflux-passwordfile", ConfigData("", ConfigDataType::PATH)},
 	{"miniflux-passwordeval", ConfigData("", ConfigDataType::STR)},
+	{"miniflux-sync-page-delay", ConfigData("1", ConfigDataType::INT)},
+	{"miniflux-sync-page-size", ConfigData("500", ConfigDataType::INT)},
 	{"miniflux-url", ConfigData("", ConfigDataType::STR)},
 	{
 		"urls-source",
@@ -263,6 +327,9 @@ ConfigContainer::ConfigContainer()
 			"ocnews",
 			"miniflux",
 			"inoreader"}))},
+	{"urls-file-copy-on-write", ConfigData("no", ConfigDataType::BOOL)},
+	{"urlview-primary-action", ConfigData("", ConfigDataType::STR)},
+	{"urlview-secondary-action", ConfigData("", ConfigDataType::STR)},
 	{"use-proxy", ConfigData("no", ConfigDataType::BOOL)},
 	{"user-agent", ConfigData("", ConfigDataType::STR)},
 
@@ -374,17 +441,35 @@ void ConfigContainer::handle_action(const std::string& action,
 
 	case ConfigDataType::STR:
 	case ConfigDataType::PATH:
-		if (cfgdata.multi_option()) {
-			cfgdata.set_value(utils::join(params, " "));
-		} else {
-			cfgdata.set_value(params[0]);
+	{
+		const std::string value = cfgdata.multi_option()
+			? utils::join(params, " ")
+			: params[0];
+		const auto error = validate(action, value);
+		if (error.has_value()) {
+			throw ConfigHandlerException(error.value());
 		}
-		break;
+		cfgdata.set_value(value);
+	}
+	break;
 
 	case ConfigDataType::INVALID:
 		// we already handled this at the beginning of the function
 		break;
 	}
+
+	if (origin_file.empty()) {
+		origins.erase(action);
+	} else {
+		origins[action] = strprintf::fmt("%s:%u", origin_file, origin_line);
+	}
+}
+
+void ConfigContainer::set_origin(const std::string& file, unsigned int line)
+{
+	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
+	origin_file = file;
+	origin_line = line;
 }
 
 std::string ConfigContainer::get_configvalue(const std::string& key) const
@@ -439,12 +524,70 @@ void ConfigContainer::set_configvalue(const std::string& key,
 		value);
 	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
 	config_data[key].set_value(value);
+	origins.erase(key);
+}
+
+nonstd::optional<std::string> ConfigContainer::try_set_configvalue(
+	const std::string& key,
+	const std::string& value)
+{
+	LOG(Level::DEBUG,
+		"ConfigContainer::try_set_configvalue(%s, %s) called",
+		key,
+		value);
+	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
+	auto it = config_data.find(key);
+	if (it == config_data.end()) {
+		return check_option_exists(key);
+	}
+
+	auto& cfgdata = it->second;
+	if (cfgdata.type() == ConfigDataType::STR
+		|| cfgdata.type() == ConfigDataType::PATH) {
+		const auto error = validate(key, value);
+		if (error.has_value()) {
+			return error;
+		}
+	}
+	const auto result = cfgdata.set_value(value);
+	if (!result) {
+		return result.error();
+	}
+	origins.erase(key);
+	return nonstd::nullopt;
+}
+
+nonstd::optional<std::string> ConfigContainer::check_option_exists(
+	const std::string& key) const
+{
+	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
+	if (config_data.find(key) != config_data.cend()) {
+		return nonstd::nullopt;
+	}
+
+	std::string known;
+	for (const auto& option : config_data) {
+		known += option.first + "\n";
+	}
+	return std::string(RustString(rs_unknown_option_message(key.c_str(),
+					known.c_str())));
+}
+
+nonstd::optional<std::string> ConfigContainer::validate(const std::string& key,
+	const std::string& value)
+{
+	char* error = rs_config_validate(key.c_str(), value.c_str());
+	if (error == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(error));
 }
 
 void ConfigContainer::reset_to_default(const std::string& key)
 {
 	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
 	config_data[key].reset_to_default();
+	origins.erase(key);
 }
 
 void ConfigContainer::toggle(const std::string& key)
@@ -515,6 +658,49 @@ std::vector<std::string> ConfigContainer::get_suggestions(
 	return result;
 }
 
+nonstd::optional<std::string> ConfigContainer::describe_option(
+	const std::string& key) const
+{
+	std::lock_guard<std::recursive_mutex> guard(config_data_mtx);
+	const auto it = config_data.find(key);
+	if (it == config_data.cend()) {
+		return nonstd::nullopt;
+	}
+	const ConfigData& cfgdata = it->second;
+
+	std::string kind;
+	switch (cfgdata.type()) {
+	case ConfigDataType::BOOL:
+		kind = "bool";
+		break;
+	case ConfigDataType::INT:
+		kind = "int";
+		break;
+	case ConfigDataType::PATH:
+		kind = "path";
+		break;
+	case ConfigDataType::ENUM:
+		kind = "enum";
+		break;
+	case ConfigDataType::STR:
+	case ConfigDataType::INVALID:
+		kind = "str";
+		break;
+	}
+
+	std::vector<std::string> values(cfgdata.enum_values().begin(),
+		cfgdata.enum_values().end());
+	std::sort(values.begin(), values.end());
+
+	const auto origin = origins.find(key);
+	return std::string(RustString(rs_describe_option(key.c_str(),
+					kind.c_str(),
+					utils::join(values, "\n").c_str(),
+					cfgdata.default_value().c_str(),
+					cfgdata.value().c_str(),
+					origin != origins.cend() ? origin->second.c_str() : "")));
+}
+
 FeedSortStrategy ConfigContainer::get_feed_sort_strategy() const
 {
 	FeedSortStrategy ss;
@@ -586,6 +772,8 @@ ArticleSortStrategy ConfigContainer::get_article_sort_strategy() const
 			ss.sm = ArtSortMethod::LINK;
 		} else if (methods[0] == "guid") {
 			ss.sm = ArtSortMethod::GUID;
+		} else if (methods[0] == "words") {
+			ss.sm = ArtSortMethod::WORDS;
 		} else if (methods[0] == "random") {
 			ss.sm = ArtSortMethod::RANDOM;
 		}
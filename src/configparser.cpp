@@ -3,7 +3,7 @@
 #include <algorithm>
 #include <cstdlib>
 #include <cstring>
-#include <fstream>
+#include <sstream>
 #include <pwd.h>
 #include <sys/types.h>
 
@@ -11,18 +11,38 @@
 #include "configexception.h"
 #include "confighandlerexception.h"
 #include "logger.h"
+#include "ruststring.h"
 #include "strprintf.h"
 #include "tagsouppullparser.h"
 #include "utils.h"
 
+extern "C" {
+	bool rs_unknown_options_prescan(const char* contents, bool* tolerate);
+	void* rs_unknown_options_new();
+	void rs_unknown_options_free(void* options);
+	void rs_unknown_options_record(void* options,
+		const char* name,
+		const char* location);
+	char* rs_unknown_options_warning(void* options);
+}
+
 namespace newsboat {
 
 ConfigParser::ConfigParser()
+	: ignore_unknown_directives(false)
+	, tolerate_unknown_options(false)
+	, rs_unknown_options(rs_unknown_options_new())
+	, origin_line(0)
 {
 	register_handler("include", *this);
+	register_handler("ignore-unknown-directives", *this);
+	register_handler("tolerate-unknown-options", *this);
 }
 
-ConfigParser::~ConfigParser() {}
+ConfigParser::~ConfigParser()
+{
+	rs_unknown_options_free(rs_unknown_options);
+}
 
 void ConfigParser::handle_action(const std::string& action,
 	const std::vector<std::string>& params)
@@ -42,6 +62,25 @@ void ConfigParser::handle_action(const std::string& action,
 		if (!this->parse_file(utils::resolve_relative(current_fpath, tilde_expanded)))
 			throw ConfigHandlerException(
 				ActionHandlerStatus::FILENOTFOUND);
+	} else if (action == "ignore-unknown-directives"
+		|| action == "tolerate-unknown-options") {
+		if (params.size() < 1) {
+			throw ConfigHandlerException(
+				ActionHandlerStatus::TOO_FEW_PARAMS);
+		}
+		bool& flag = action == "ignore-unknown-directives"
+			? ignore_unknown_directives
+			: tolerate_unknown_options;
+		const std::string& value = params[0];
+		if (value == "yes" || value == "true") {
+			flag = true;
+		} else if (value == "no" || value == "false") {
+			flag = false;
+		} else {
+			throw ConfigHandlerException(strprintf::fmt(
+					_("expected boolean value, found `%s' instead"),
+					value));
+		}
 	} else
 		throw ConfigHandlerException(
 			ActionHandlerStatus::INVALID_COMMAND);
@@ -80,17 +119,34 @@ bool ConfigParser::parse_file(const std::string& tmp_filename)
 	}
 	included_files.push_back(filename);
 
+	// Restored when done, so that lines parsed afterwards don't claim to come
+	// from this file
+	const std::string including_file = origin_file;
+	const unsigned int including_line = origin_line;
+
 	unsigned int linecounter = 0;
-	std::ifstream f(filename.c_str());
-	std::string line;
-	if (!f.is_open()) {
+	std::string encoding_warning;
+	const auto contents = utils::read_text_file(filename, encoding_warning);
+	if (!contents.has_value()) {
 		LOG(Level::WARN,
 			"ConfigParser::parse_file: file %s couldn't be opened",
 			filename);
 		return false;
 	}
+	if (!encoding_warning.empty()) {
+		LOG(Level::WARN, "ConfigParser::parse_file: %s", encoding_warning);
+		encoding_warnings.push_back(encoding_warning);
+		non_utf8_files.push_back(filename);
+	}
+
+	// `tolerate-unknown-options` also covers the lines above it, so that
+	// it doesn't matter where in the file it is
+	rs_unknown_options_prescan(contents.value().c_str(),
+		&tolerate_unknown_options);
 
-	while (f.is_open() && !f.eof()) {
+	std::istringstream f(contents.value());
+	std::string line;
+	while (!f.eof()) {
 		getline(f, line);
 		++linecounter;
 		LOG(Level::DEBUG, "ConfigParser::parse_file: tokenizing %s", line);
@@ -98,9 +154,23 @@ bool ConfigParser::parse_file(const std::string& tmp_filename)
 		const std::string location = strprintf::fmt(_("%s line %u"), filename,
 				linecounter);
 
-		parse_line(line, location);
+		origin_file = filename;
+		origin_line = linecounter;
+		try {
+			parse_line(line, location);
+		} catch (const ConfigException& e) {
+			origin_file = including_file;
+			origin_line = including_line;
+			if (!e.file().empty()) {
+				// Comes from an included file, which already knows its location
+				throw;
+			}
+			throw ConfigException(e.what(), filename, linecounter);
+		}
 	}
 	included_files.pop_back();
+	origin_file = including_file;
+	origin_line = including_line;
 	return true;
 }
 
@@ -115,10 +185,12 @@ void ConfigParser::parse_line(const std::string& line,
 		const std::string params = evaluated;
 
 		if (action_handlers.count(cmd) < 1) {
-			throw ConfigException(strprintf::fmt(_("unknown command `%s'"), cmd));
+			handle_unknown_directive(cmd, location);
+			return;
 		}
 		ConfigActionHandler& handler = action_handlers.at(cmd);
 		try {
+			handler.set_origin(origin_file, origin_line);
 			handler.handle_action(cmd, params);
 		} catch (const ConfigHandlerException& e) {
 			throw ConfigException(strprintf::fmt(
@@ -130,6 +202,79 @@ void ConfigParser::parse_line(const std::string& line,
 	}
 }
 
+void ConfigParser::handle_unknown_directive(const std::string& cmd,
+	const std::string& location)
+{
+	// Commands prefixed with "x-" are reserved for third-party tools that
+	// share the config file with us
+	if (cmd.compare(0, 2, "x-") == 0) {
+		LOG(Level::DEBUG,
+			"ConfigParser::parse_line: ignoring `%s' (%s)",
+			cmd,
+			location);
+		return;
+	}
+
+	if (!ignore_unknown_directives) {
+		if (tolerate_unknown_options) {
+			LOG(Level::WARN,
+				"ConfigParser::parse_line: tolerating unknown option `%s' (%s)",
+				cmd,
+				location);
+			rs_unknown_options_record(rs_unknown_options, cmd.c_str(),
+				location.c_str());
+			return;
+		}
+		throw ConfigException(strprintf::fmt(_("unknown command `%s'"), cmd));
+	}
+
+	LOG(Level::WARN,
+		"ConfigParser::parse_line: ignoring unknown command `%s' (%s)",
+		cmd,
+		location);
+
+	const auto directive = std::find_if(unknown_directives.begin(),
+			unknown_directives.end(),
+	[&cmd](const UnknownDirective& d) {
+		return d.name == cmd;
+	});
+	if (directive != unknown_directives.end()) {
+		directive->count++;
+	} else {
+		unknown_directives.push_back({cmd, location, 1});
+	}
+}
+
+std::vector<std::string> ConfigParser::get_warnings() const
+{
+	std::vector<std::string> result = encoding_warnings;
+	for (const auto& directive : unknown_directives) {
+		if (directive.count == 1) {
+			result.push_back(strprintf::fmt(
+					_("ignored unknown command `%s' (%s)"),
+					directive.name,
+					directive.first_location));
+		} else {
+			result.push_back(strprintf::fmt(
+					_("ignored unknown command `%s' %u times (first at %s)"),
+					directive.name,
+					directive.count,
+					directive.first_location));
+		}
+	}
+	const std::string unknown_options = RustString(rs_unknown_options_warning(
+				rs_unknown_options));
+	if (!unknown_options.empty()) {
+		result.push_back(unknown_options);
+	}
+	return result;
+}
+
+std::vector<std::string> ConfigParser::get_non_utf8_files() const
+{
+	return non_utf8_files;
+}
+
 void ConfigParser::register_handler(const std::string& cmd,
 	ConfigActionHandler& handler)
 {
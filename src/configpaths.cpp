@@ -27,10 +27,14 @@ extern "C" {
 
 	bool rs_configpaths_try_migrate_from_newsbeuter(void* rs_configpaths);
 
+	char* rs_configpaths_describe_migration_plan(void* rs_configpaths);
+
 	bool rs_configpaths_create_dirs(void* rs_configpaths);
 
 	char* rs_configpaths_url_file(void* rs_configpaths);
 
+	char* rs_configpaths_data_dir(void* rs_configpaths);
+
 	char* rs_configpaths_cache_file(void* rs_configpaths);
 
 	void rs_configpaths_set_cache_file(void* rs_configpaths, const char*);
@@ -44,6 +48,14 @@ extern "C" {
 	char* rs_configpaths_search_file(void* rs_configpaths);
 
 	char* rs_configpaths_cmdline_file(void* rs_configpaths);
+
+	char* rs_configpaths_feedlist_snapshot_file(void* rs_configpaths);
+
+	char* rs_configpaths_url_overlay_file(void* rs_configpaths);
+
+	size_t rs_configpaths_profiles_count(void* rs_configpaths);
+
+	char* rs_configpaths_profile_n(void* rs_configpaths, size_t n);
 }
 
 #define SIMPLY_RUN(NAME) \
@@ -101,6 +113,17 @@ bool ConfigPaths::try_migrate_from_newsbeuter()
 	GET_VALUE(try_migrate_from_newsbeuter, false);
 }
 
+nonstd::optional<std::string> ConfigPaths::describe_migration_plan() const
+{
+	if (rs_configpaths) {
+		char* plan = rs_configpaths_describe_migration_plan(rs_configpaths);
+		if (plan != nullptr) {
+			return std::string(RustString(plan));
+		}
+	}
+	return nonstd::nullopt;
+}
+
 bool ConfigPaths::create_dirs() const
 {
 	GET_VALUE(create_dirs, false);
@@ -118,6 +141,11 @@ std::string ConfigPaths::url_file() const
 	GET_STRING(url_file);
 }
 
+std::string ConfigPaths::data_dir() const
+{
+	GET_STRING(data_dir);
+}
+
 std::string ConfigPaths::cache_file() const
 {
 	GET_STRING(cache_file);
@@ -148,4 +176,26 @@ std::string ConfigPaths::cmdline_file() const
 	GET_STRING(cmdline_file);
 }
 
+std::string ConfigPaths::feedlist_snapshot_file() const
+{
+	GET_STRING(feedlist_snapshot_file);
+}
+
+std::string ConfigPaths::url_overlay_file() const
+{
+	GET_STRING(url_overlay_file);
+}
+
+std::vector<std::string> ConfigPaths::profiles() const
+{
+	std::vector<std::string> result;
+	if (rs_configpaths) {
+		const auto count = rs_configpaths_profiles_count(rs_configpaths);
+		for (size_t i = 0; i < count; ++i) {
+			result.push_back(RustString(rs_configpaths_profile_n(rs_configpaths, i)));
+		}
+	}
+	return result;
+}
+
 } // namespace newsboat
@@ -1,5 +1,6 @@
 #include "controller.h"
 
+#include <algorithm>
 #include <cassert>
 #include <cerrno>
 #include <cstdlib>
@@ -8,11 +9,11 @@
 #include <fstream>
 #include <functional>
 #include <iostream>
+#include <iterator>
 #include <langinfo.h>
 #include <libgen.h>
 #include <libxml/parser.h>
 #include <libxml/tree.h>
-#include <libxml/xmlsave.h>
 #include <libxml/xmlversion.h>
 #include <mutex>
 #include <pwd.h>
@@ -23,13 +24,18 @@
 #include <sys/wait.h>
 #include <unistd.h>
 
+#include "addfeed.h"
+#include "cachequery.h"
 #include "cliargsparser.h"
+#include "clierror.h"
+#include "clioutput.h"
 #include "colormanager.h"
 #include "config.h"
 #include "configcontainer.h"
 #include "configexception.h"
 #include "configparser.h"
 #include "configpaths.h"
+#include "controlsocket.h"
 #include "dbexception.h"
 #include "downloadthread.h"
 #include "exception.h"
@@ -41,6 +47,8 @@
 #include "inoreaderurlreader.h"
 #include "itemrenderer.h"
 #include "logger.h"
+#include "matcher.h"
+#include "matcherexception.h"
 #include "minifluxapi.h"
 #include "minifluxurlreader.h"
 #include "newsblurapi.h"
@@ -51,9 +59,11 @@
 #include "oldreaderurlreader.h"
 #include "opmlurlreader.h"
 #include "regexmanager.h"
+#include "reloadtag.h"
 #include "remoteapi.h"
 #include "rssfeed.h"
 #include "rssparser.h"
+#include "ruststring.h"
 #include "scopemeasure.h"
 #include "stflpp.h"
 #include "strprintf.h"
@@ -62,6 +72,41 @@
 #include "utils.h"
 #include "view.h"
 
+extern "C" {
+	void rs_human_panic_set_report_dir(const char* dir);
+
+	void rs_feedlist_snapshot_free(void* snapshot);
+	void* rs_feedlist_snapshot_new();
+	void rs_feedlist_snapshot_add_feed(void* snapshot,
+		const char* url,
+		const char* title,
+		const char* tags,
+		unsigned int unread,
+		unsigned int total);
+	bool rs_feedlist_snapshot_save(void* snapshot,
+		const char* path,
+		const char* urls_file,
+		const char* cache_file);
+	void* rs_feedlist_snapshot_load_if_current(const char* path,
+		const char* urls_file,
+		const char* cache_file,
+		const char* urls);
+	unsigned int rs_feedlist_snapshot_feed_count(void* snapshot);
+	void rs_feedlist_snapshot_get_feed(void* snapshot,
+		unsigned int index,
+		char** url,
+		char** title,
+		char** tags,
+		unsigned int* unread,
+		unsigned int* total);
+	bool rs_feedlist_snapshot_reconcile(void* snapshot,
+		const char* url,
+		const char* title,
+		const char* tags,
+		unsigned int unread,
+		unsigned int total);
+}
+
 namespace newsboat {
 
 void sighup_action(int /* sig */)
@@ -76,11 +121,29 @@ void ignore_signal(int sig)
 	LOG(Level::WARN, "caught signal %d but ignored it", sig);
 }
 
+namespace {
+
+std::string already_running_message(pid_t pid)
+{
+	if (pid == 0) {
+		return strprintf::fmt(
+				_("Error: couldn't lock the cache; another instance of %s may be running"),
+				PROGRAM_NAME);
+	}
+	return strprintf::fmt(_("Error: another instance of %s (PID %u) is running"),
+			PROGRAM_NAME,
+			pid);
+}
+
+} // namespace
+
 Controller::Controller(ConfigPaths& configpaths)
 	: v(0)
 	, urlcfg(0)
 	, rsscache(0)
 	, refresh_on_start(false)
+	, json_errors(false)
+	, plain_output(false)
 	, api(0)
 	, configpaths(configpaths)
 	, queueManager(&cfg, &configpaths)
@@ -89,6 +152,7 @@ Controller::Controller(ConfigPaths& configpaths)
 
 Controller::~Controller()
 {
+	wait_for_feedlist();
 	delete rsscache;
 	delete urlcfg;
 	delete api;
@@ -106,6 +170,8 @@ int Controller::run(const CliArgsParser& args)
 	::signal(SIGHUP, sighup_action);
 
 	refresh_on_start = args.refresh_on_start();
+	json_errors = args.json_errors();
+	plain_output = args.plain_output();
 
 	if (args.log_file().has_value()) {
 		Logger::set_logfile(args.log_file().value());
@@ -123,7 +189,33 @@ int Controller::run(const CliArgsParser& args)
 		return args.return_code().value();
 	}
 
+	if (args.dry_run_migration()) {
+		const auto plan = configpaths.describe_migration_plan();
+		if (plan.has_value()) {
+			std::cout << plan.value();
+		} else {
+			std::cout << _("Nothing would be imported: either Newsboat "
+					"already has its own config and data, or there's no "
+					"Newsbeuter directory.")
+				<< std::endl;
+		}
+		return EXIT_SUCCESS;
+	}
+
 	const auto migrated = configpaths.try_migrate_from_newsbeuter();
+	if (args.import_from_newsbeuter()) {
+		if (migrated) {
+			std::cerr << _("Imported Newsbeuter's config and data. Please "
+					"check the results, especially the lines reported above.")
+				<< std::endl;
+		} else {
+			std::cerr << _("Nothing was imported: either Newsboat already has "
+					"its own config and data, or there's no Newsbeuter "
+					"directory.")
+				<< std::endl;
+		}
+		return EXIT_SUCCESS;
+	}
 	if (migrated) {
 		std::cerr << "\nPlease check the results and press Enter to "
 			"continue.";
@@ -133,16 +225,34 @@ int Controller::run(const CliArgsParser& args)
 	if (!configpaths.create_dirs()) {
 		return EXIT_FAILURE;
 	}
+	rs_human_panic_set_report_dir(configpaths.data_dir().c_str());
 
 	if (args.do_import()) {
 		LOG(Level::INFO, "Importing OPML file from %s", args.importfile());
-		import_opml(args.importfile(), configpaths.url_file());
+		if (!import_opml(args.importfile(), configpaths.url_file())) {
+			return report_error(ErrorCategory::URLS_FILE,
+					strprintf::fmt(
+						_("An error occurred while parsing %s."),
+						args.importfile()),
+					args.importfile());
+		}
 		return EXIT_SUCCESS;
 	}
 
 	LOG(Level::INFO, "nl_langinfo(CODESET): %s", nl_langinfo(CODESET));
 
-	if (!args.do_export()) {
+	// When adding a feed, an instance that holds the lock is asked to do it,
+	// so that's not an error
+	const bool adding_feed = args.add_feed().has_value() ||
+		args.url_argument().has_value();
+	bool running_elsewhere = false;
+	pid_t running_pid = 0;
+
+	// The cache is only read while it's queried, so another instance may
+	// hold the lock
+	const bool querying_cache = args.query_cache().has_value();
+
+	if (!args.do_export() && !querying_cache) {
 		if (!args.silent())
 			std::cout << strprintf::fmt(_("Starting %s %s..."),
 					PROGRAM_NAME,
@@ -152,14 +262,17 @@ int Controller::run(const CliArgsParser& args)
 		fslock = std::unique_ptr<FsLock>(new FsLock());
 		pid_t pid;
 		if (!fslock->try_lock(configpaths.lock_file(), pid)) {
-			if (!args.cmds_to_execute().has_value()) {
-				std::cout << strprintf::fmt(
-						_("Error: an instance of %s is already running (PID: %u)"),
-						PROGRAM_NAME,
-						pid)
-					<< std::endl;
+			if (adding_feed) {
+				running_elsewhere = true;
+				running_pid = pid;
+			} else if (args.cmds_to_execute().has_value() && !json_errors) {
+				// Stay quiet, so that cron jobs don't spam their owners
+				return clierror::exit_code(ErrorCategory::CACHE);
+			} else {
+				return report_error(ErrorCategory::CACHE,
+						already_running_message(pid),
+						configpaths.lock_file());
 			}
-			return EXIT_FAILURE;
 		}
 	}
 
@@ -175,12 +288,20 @@ int Controller::run(const CliArgsParser& args)
 	cfgparser.register_handler("bind-key", keys);
 	cfgparser.register_handler("unbind-key", keys);
 	cfgparser.register_handler("macro", keys);
+	cfgparser.register_handler("macro-with-args", keys);
 	cfgparser.register_handler("run-on-startup", keys);
 
 	cfgparser.register_handler("ignore-article", ign);
 	cfgparser.register_handler("always-download", ign);
 	cfgparser.register_handler("reset-unread-on-update", ign);
 
+	cfgparser.register_handler("pre-reload-command", reload_hooks);
+	cfgparser.register_handler("post-reload-command", reload_hooks);
+	cfgparser.register_handler("feedopts", feed_options);
+	cfgparser.register_handler("accept-empty-feed", empty_feed_guard);
+	cfgparser.register_handler("ignore-feed-schedule", feed_scheduler);
+	cfgparser.register_handler("feedlist-icon", feed_icons);
+
 	cfgparser.register_handler("define-filter", filters);
 	cfgparser.register_handler("highlight", rxman);
 	cfgparser.register_handler("highlight-article", rxman);
@@ -193,67 +314,171 @@ int Controller::run(const CliArgsParser& args)
 			"an exception occurred while parsing the configuration "
 			"file: %s",
 			ex.what());
-		std::cout << ex.what() << std::endl;
-		return EXIT_FAILURE;
+		return report_error(ErrorCategory::CONFIG,
+				ex.what(),
+				ex.file(),
+				ex.line());
+	}
+
+	for (const auto& option : args.config_overrides()) {
+		const auto error = cfg.try_set_configvalue(option.first, option.second);
+		if (error.has_value()) {
+			return report_error(ErrorCategory::USAGE,
+					strprintf::fmt(_("-o %s=%s: %s"),
+						option.first,
+						option.second,
+						error.value()));
+		}
 	}
 
 	update_config();
 
+	try {
+		keys.apply_default_actions(cfg);
+	} catch (const ConfigException& ex) {
+		return report_error(ErrorCategory::CONFIG, ex.what());
+	}
+
 	if (!args.silent()) {
 		std::cout << _("done.") << std::endl;
 	}
 
+	if (args.convert_to_utf8()) {
+		std::vector<std::string> files = cfgparser.get_non_utf8_files();
+		if (std::ifstream(configpaths.url_file()).is_open()) {
+			files.push_back(configpaths.url_file());
+		}
+		int status = EXIT_SUCCESS;
+		for (const auto& file : files) {
+			bool converted = false;
+			const auto error = utils::convert_file_to_utf8(file, converted);
+			if (error.has_value()) {
+				std::cerr << strprintf::fmt(
+						_("Error: couldn't convert %s to UTF-8: %s"),
+						file,
+						error.value())
+					<< std::endl;
+				status = EXIT_FAILURE;
+			} else if (converted) {
+				std::cout << strprintf::fmt(_("Converted %s to UTF-8."), file)
+					<< std::endl;
+			}
+		}
+		return status;
+	}
+
+	for (const auto& warning : cfgparser.get_warnings()) {
+		std::cerr << warning << std::endl;
+	}
+	for (const auto& warning : keys.get_warnings()) {
+		std::cerr << warning << std::endl;
+	}
+
 	// create cache object
 	std::string cachefilepath = cfg.get_configvalue("cache-file");
 	if (cachefilepath.length() > 0 && !args.cache_file().has_value()) {
 		configpaths.set_cache_file(cachefilepath);
+	}
+
+	if (querying_cache) {
+		return query_cache(args.query_cache().value(),
+				args.query_fields(),
+				args.query_format_json());
+	}
+
+	if (cachefilepath.length() > 0 && !args.cache_file().has_value()) {
 		fslock = std::unique_ptr<FsLock>(new FsLock());
 		pid_t pid;
+		running_elsewhere = false;
 		if (!fslock->try_lock(configpaths.lock_file(), pid)) {
-			std::cout << strprintf::fmt(
-					_("Error: an instance of %s is "
-						"already running (PID: %u)"),
-					PROGRAM_NAME,
-					pid)
-				<< std::endl;
-			return EXIT_FAILURE;
+			if (adding_feed) {
+				running_elsewhere = true;
+				running_pid = pid;
+			} else {
+				return report_error(ErrorCategory::CACHE,
+						already_running_message(pid),
+						configpaths.lock_file());
+			}
 		}
 	}
 
+	if (adding_feed) {
+		return add_feed_from_cli(args, running_elsewhere, running_pid);
+	}
+
+	if (args.recover_cache()) {
+		return recover_cache(args.force());
+	}
+
 	if (!args.silent()) {
 		std::cout << _("Opening cache...");
 		std::cout.flush();
 	}
 	try {
-		rsscache = new Cache(configpaths.cache_file(), &cfg);
+		const bool silent = args.silent();
+		rsscache = new Cache(configpaths.cache_file(), &cfg,
+		[silent](const std::string& progress) {
+			if (!silent) {
+				std::cout << "\r" << _("Opening cache...") << " " << progress << " ";
+				std::cout.flush();
+			}
+		});
 	} catch (const DbException& e) {
-		std::cerr << strprintf::fmt(
-				_("Error: opening the cache file `%s' "
-					"failed: %s"),
+		std::string message = strprintf::fmt(
+				_("Error: opening the cache file `%s' failed: %s"),
 				configpaths.cache_file(),
-				e.what())
-			<< std::endl;
-		return EXIT_FAILURE;
+				e.what());
+		if (std::string(e.what()) == sqlite3_errstr(SQLITE_CORRUPT)) {
+			message += "\n" + strprintf::fmt(
+					_("Run `%s --recover-cache' to salvage what can still be read."),
+					PROGRAM_NAME);
+		}
+		return report_error(ErrorCategory::CACHE,
+				message,
+				configpaths.cache_file());
 	} catch (const std::runtime_error& e) {
-		std::cerr << strprintf::fmt(
-				_("Error: opening the cache file `%s' "
-					"failed: %s"),
-				configpaths.cache_file(),
-				e.what())
-			<< std::endl;
-		return EXIT_FAILURE;
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: opening the cache file `%s' "
+						"failed: %s"),
+					configpaths.cache_file(),
+					e.what()),
+				configpaths.cache_file());
 	}
 
 	if (!args.silent()) {
 		std::cout << _("done.") << std::endl;
 	}
 
+	if (args.merge_cache_file().has_value()) {
+		const std::string other_cachefile = args.merge_cache_file().value();
+		try {
+			const auto summary = rsscache->merge_from(other_cachefile,
+					args.merge_prefer_read());
+			std::cout << strprintf::fmt(
+					_("Merged %s: %u articles added, %u states merged, %u conflicts"),
+					other_cachefile,
+					summary.added,
+					summary.merged,
+					summary.conflicts)
+				<< std::endl;
+		} catch (const DbException& e) {
+			return report_error(ErrorCategory::CACHE,
+					strprintf::fmt(
+						_("Error: merging the cache file `%s' failed: %s"),
+						other_cachefile,
+						e.what()),
+					other_cachefile);
+		}
+		return EXIT_SUCCESS;
+	}
+
 	reloader =
 		std::unique_ptr<Reloader>(new Reloader(this, rsscache, &cfg));
 
 	std::string type = cfg.get_configvalue("urls-source");
 	if (type == "local") {
-		urlcfg = new FileUrlReader(configpaths.url_file());
+		urlcfg = new FileUrlReader(configpaths.url_file(), url_overlay_file());
 	} else if (type == "opml") {
 		urlcfg = new OpmlUrlReader(&cfg);
 	} else if (type == "oldreader") {
@@ -266,18 +491,18 @@ int Controller::run(const CliArgsParser& args)
 	} else if (type == "newsblur") {
 		const auto cookies = cfg.get_configvalue("cookie-cache");
 		if (cookies.empty()) {
-			std::cout << strprintf::fmt(
+			return report_error(ErrorCategory::CONFIG,
 					_("ERROR: You must set `cookie-cache` to use "
-						"NewsBlur.\n"));
-			return EXIT_FAILURE;
+						"NewsBlur."));
 		}
 
 		std::ofstream check(cookies);
 		if (!check.is_open()) {
-			std::cout << strprintf::fmt(
-					_("%s is inaccessible and can't be created\n"),
+			return report_error(ErrorCategory::CONFIG,
+					strprintf::fmt(
+						_("%s is inaccessible and can't be created"),
+						cookies),
 					cookies);
-			return EXIT_FAILURE;
 		}
 
 		api = new NewsBlurApi(&cfg);
@@ -291,9 +516,8 @@ int Controller::run(const CliArgsParser& args)
 	} else if (type == "miniflux") {
 		const auto miniflux_url = cfg.get_configvalue("miniflux-url");
 		if (miniflux_url.empty()) {
-			std::cerr <<
-				_("ERROR: You must set `miniflux-url` to use Miniflux\n");
-			return EXIT_FAILURE;
+			return report_error(ErrorCategory::CONFIG,
+					_("ERROR: You must set `miniflux-url` to use Miniflux"));
 		}
 
 		const std::string user = cfg.get_configvalue("miniflux-login");
@@ -303,11 +527,10 @@ int Controller::run(const CliArgsParser& args)
 		const bool creds_set = !user.empty() &&
 			(!pass.empty() || !pass_file.empty() || !pass_eval.empty());
 		if (!creds_set) {
-			std::cerr <<
-				_("ERROR: You must set `miniflux-login` and one of `miniflux-password`, "
-					"`miniflux-passwordfile` or `miniflux-passwordeval` to use "
-					"Miniflux\n");
-			return EXIT_FAILURE;
+			return report_error(ErrorCategory::CONFIG,
+					_("ERROR: You must set `miniflux-login` and one of `miniflux-password`, "
+						"`miniflux-passwordfile` or `miniflux-passwordeval` to use "
+						"Miniflux"));
 		}
 
 		api = new MinifluxApi(&cfg);
@@ -316,17 +539,15 @@ int Controller::run(const CliArgsParser& args)
 		const auto all_set = !cfg.get_configvalue("inoreader-app-id").empty()
 			&& !cfg.get_configvalue("inoreader-app-key").empty();
 		if (!all_set) {
-			std::cerr <<
-				_("ERROR: You must set *both* `inoreader-app-id` and `inoreader-app-key` to use Inoreader.\n");
-			return EXIT_FAILURE;
+			return report_error(ErrorCategory::CONFIG,
+					_("ERROR: You must set *both* `inoreader-app-id` and `inoreader-app-key` to use Inoreader."));
 		}
 
 		api = new InoreaderApi(&cfg);
 		urlcfg = new InoreaderUrlReader(&cfg, configpaths.url_file(), api);
 	} else {
-		std::cerr << strprintf::fmt(_("ERROR: Unknown urls-source `%s'"),
-				type) << std::endl;
-		return EXIT_FAILURE;
+		return report_error(ErrorCategory::CONFIG,
+				strprintf::fmt(_("ERROR: Unknown urls-source `%s'"), type));
 	}
 
 	if (!args.do_export() && !args.silent()) {
@@ -336,11 +557,21 @@ int Controller::run(const CliArgsParser& args)
 	}
 	if (api) {
 		if (!api->authenticate()) {
-			std::cout << "Authentication failed." << std::endl;
-			return EXIT_FAILURE;
+			return report_error(ErrorCategory::NETWORK,
+					_("Authentication failed."));
 		}
 	}
 	urlcfg->reload();
+	const auto urlfile = dynamic_cast<FileUrlReader*>(urlcfg);
+	if (urlfile != nullptr && urlfile->get_error().has_value()) {
+		if (!args.do_export() && !args.silent()) {
+			std::cout << std::endl;
+		}
+		return report_error(ErrorCategory::URLS_FILE,
+				urlfile->get_error().value(),
+				configpaths.url_file(),
+				urlfile->get_error_line());
+	}
 	if (!args.do_export() && !args.silent()) {
 		std::cout << _("done.") << std::endl;
 	}
@@ -387,8 +618,9 @@ int Controller::run(const CliArgsParser& args)
 		} else {
 			assert(0); // shouldn't happen
 		}
-		std::cout << msg << std::endl << std::endl;
-		return EXIT_FAILURE;
+		return report_error(ErrorCategory::URLS_FILE,
+				msg,
+				type == "local" ? configpaths.url_file() : std::string());
 	}
 
 	if (!args.do_export() && !args.do_vacuum() && !args.silent()) {
@@ -403,38 +635,77 @@ int Controller::run(const CliArgsParser& args)
 		std::cout << _("done.") << std::endl;
 		std::cout << _("Cleaning up cache thoroughly...");
 		std::cout.flush();
-		rsscache->do_vacuum();
+		try {
+			rsscache->do_vacuum();
+		} catch (const DbException& e) {
+			std::cout << std::endl;
+			return report_error(ErrorCategory::CACHE,
+					strprintf::fmt(_("Error: cleaning up the cache failed: %s"),
+						e.what()),
+					configpaths.cache_file());
+		}
 		std::cout << _("done.") << std::endl;
 		return EXIT_SUCCESS;
 	}
 
-	unsigned int i = 0;
-	for (const auto& url : urlcfg->get_urls()) {
+	if (args.cleanup()) {
+		// Articles older than `keep-articles-days` were already deleted
+		// when the cache was opened
+		std::vector<std::shared_ptr<RssFeed>> feeds;
+		for (const auto& url : urlcfg->get_urls()) {
+			auto feed = std::make_shared<RssFeed>(rsscache);
+			feed->set_rssurl(url);
+			feeds.push_back(feed);
+		}
 		try {
-			bool ignore_disp =
-				(cfg.get_configvalue("ignore-mode") ==
-					"display");
-			std::shared_ptr<RssFeed> feed =
-				rsscache->internalize_rssfeed(
-					url, ignore_disp ? &ign : nullptr);
-			feed->set_tags(urlcfg->get_tags(url));
-			feed->set_order(i);
-			feedcontainer.add_feed(feed);
+			rsscache->cleanup_and_vacuum(feeds);
 		} catch (const DbException& e) {
-			std::cout << _("Error while loading feeds from "
-					"database: ")
-				<< e.what() << std::endl;
-			return EXIT_FAILURE;
-		} catch (const std::string& str) {
-			std::cout << strprintf::fmt(
-					_("Error while loading feed '%s': "
-						"%s"),
-					url,
-					str)
-				<< std::endl;
-			return EXIT_FAILURE;
+			return report_error(ErrorCategory::CACHE,
+					strprintf::fmt(_("Error: cleaning up the cache failed: %s"),
+						e.what()),
+					configpaths.cache_file());
+		}
+		return EXIT_SUCCESS;
+	}
+
+	// If nothing changed since the last run, the feedlist is shown from its
+	// snapshot right away, and the feeds are loaded in the background
+	void* snapshot = nullptr;
+	if (!args.do_export() && !args.readinfo_import_file().has_value()
+		&& !args.readinfo_export_file().has_value()
+		&& !args.cmds_to_execute().has_value()) {
+		snapshot = load_feedlist_snapshot();
+	}
+
+	if (snapshot == nullptr) {
+		unsigned int i = 0;
+		for (const auto& url : urlcfg->get_urls()) {
+			try {
+				bool ignore_disp =
+					(cfg.get_configvalue("ignore-mode") ==
+						"display");
+				std::shared_ptr<RssFeed> feed =
+					rsscache->internalize_rssfeed(
+						url, ignore_disp ? &ign : nullptr);
+				feed->set_tags(urlcfg->get_tags(url));
+				feed->set_order(i);
+				feedcontainer.add_feed(feed);
+			} catch (const DbException& e) {
+				return report_error(ErrorCategory::CACHE,
+						_("Error while loading feeds from database: ") +
+						std::string(e.what()),
+						configpaths.cache_file());
+			} catch (const std::string& str) {
+				return report_error(ErrorCategory::CACHE,
+						strprintf::fmt(
+							_("Error while loading feed '%s': "
+								"%s"),
+							url,
+							str),
+						configpaths.cache_file());
+			}
+			i++;
 		}
-		i++;
 	}
 
 	std::vector<std::string> tags = urlcfg->get_alltags();
@@ -444,8 +715,11 @@ int Controller::run(const CliArgsParser& args)
 	}
 
 	// if configured, we fill all query feeds with some data; no need to
-	// sort it, it will be refilled when actually opening it.
-	if (cfg.get_configvalue_as_bool("prepopulate-query-feeds")) {
+	// sort it, it will be refilled when actually opening it. Placeholders
+	// have no articles to fill them with, so that waits until the feeds are
+	// loaded.
+	if (snapshot == nullptr
+		&& cfg.get_configvalue_as_bool("prepopulate-query-feeds")) {
 		if (!args.do_export() && !args.silent()) {
 			std::cout << _("Prepopulating query feeds...");
 			std::cout.flush();
@@ -458,10 +732,14 @@ int Controller::run(const CliArgsParser& args)
 		}
 	}
 
-	feedcontainer.sort_feeds(cfg.get_feed_sort_strategy());
+	// The snapshot is in the order the feeds were shown in, and some sort
+	// orders need articles that placeholders don't have
+	if (snapshot == nullptr) {
+		feedcontainer.sort_feeds(cfg.get_feed_sort_strategy());
+	}
 
 	if (args.do_export()) {
-		export_opml();
+		export_opml(args.export_opml2());
 		return EXIT_SUCCESS;
 	}
 
@@ -471,7 +749,13 @@ int Controller::run(const CliArgsParser& args)
 			args.readinfo_import_file().value());
 		std::cout << _("Importing list of read articles...");
 		std::cout.flush();
-		import_read_information(args.readinfo_import_file().value());
+		if (!import_read_information(args.readinfo_import_file().value())) {
+			std::cout << std::endl;
+			return report_error(ErrorCategory::OTHER,
+					strprintf::fmt(_("Error: couldn't open `%s'"),
+						args.readinfo_import_file().value()),
+					args.readinfo_import_file().value());
+		}
 		std::cout << _("done.") << std::endl;
 		return EXIT_SUCCESS;
 	}
@@ -482,7 +766,13 @@ int Controller::run(const CliArgsParser& args)
 			args.readinfo_export_file().value());
 		std::cout << _("Exporting list of read articles...");
 		std::cout.flush();
-		export_read_information(args.readinfo_export_file().value());
+		if (!export_read_information(args.readinfo_export_file().value())) {
+			std::cout << std::endl;
+			return report_error(ErrorCategory::OTHER,
+					strprintf::fmt(_("Error: couldn't open `%s'"),
+						args.readinfo_export_file().value()),
+					args.readinfo_export_file().value());
+		}
 		std::cout << _("done.") << std::endl;
 		return EXIT_SUCCESS;
 	}
@@ -494,8 +784,7 @@ int Controller::run(const CliArgsParser& args)
 	v->set_cache(rsscache);
 
 	if (args.cmds_to_execute().has_value()) {
-		execute_commands(args.cmds_to_execute().value());
-		return EXIT_SUCCESS;
+		return execute_commands(args.cmds_to_execute().value());
 	}
 
 	// if the user wants to refresh on startup via configuration file, then
@@ -508,8 +797,19 @@ int Controller::run(const CliArgsParser& args)
 	FormAction::load_histories(
 		configpaths.search_file(), configpaths.cmdline_file());
 
+	start_control_socket();
+
+	if (snapshot != nullptr) {
+		feedlist_loader = std::thread(&Controller::load_feeds_in_background,
+				this,
+				snapshot);
+	}
+
 	// run the View
 	int ret = v->run();
+	wait_for_feedlist();
+	control_socket.stop();
+	reloader->stop_initial_syncs();
 
 	unsigned int history_limit =
 		cfg.get_configvalue_as_int("history-limit");
@@ -527,17 +827,166 @@ int Controller::run(const CliArgsParser& args)
 		if (!args.silent()) {
 			std::cout << _("done.") << std::endl;
 		}
+		save_feedlist_snapshot();
 	} catch (const DbException& e) {
 		LOG(Level::USERERROR, "Cleaning up cache failed: %s", e.what());
 		if (!args.silent()) {
 			std::cout << _("failed: ") << e.what() << std::endl;
-			ret = EXIT_FAILURE;
+			ret = clierror::exit_code(ErrorCategory::CACHE);
 		}
 	}
 
 	return ret;
 }
 
+void* Controller::load_feedlist_snapshot()
+{
+	std::string urls;
+	for (const auto& url : urlcfg->get_urls()) {
+		urls += url + "\n";
+	}
+	void* snapshot = rs_feedlist_snapshot_load_if_current(
+			configpaths.feedlist_snapshot_file().c_str(),
+			configpaths.url_file().c_str(),
+			configpaths.cache_file().c_str(),
+			urls.c_str());
+	if (snapshot == nullptr) {
+		LOG(Level::DEBUG,
+			"Controller::load_feedlist_snapshot: no current snapshot");
+		return nullptr;
+	}
+
+	const std::vector<std::string> all_urls = urlcfg->get_urls();
+	std::vector<std::shared_ptr<RssFeed>> placeholders;
+	const unsigned int count = rs_feedlist_snapshot_feed_count(snapshot);
+	for (unsigned int i = 0; i < count; i++) {
+		char* url = nullptr;
+		char* title = nullptr;
+		char* tags = nullptr;
+		unsigned int unread = 0;
+		unsigned int total = 0;
+		rs_feedlist_snapshot_get_feed(snapshot, i, &url, &title, &tags,
+			&unread, &total);
+		const std::string feed_url = RustString(url);
+		const std::string feed_title = RustString(title);
+		const std::string feed_tags = RustString(tags);
+
+		auto feed = std::make_shared<RssFeed>(rsscache);
+		try {
+			feed->set_rssurl(feed_url);
+		} catch (const std::string& error) {
+			LOG(Level::WARN,
+				"Controller::load_feedlist_snapshot: can't use `%s': %s",
+				feed_url,
+				error);
+			rs_feedlist_snapshot_free(snapshot);
+			return nullptr;
+		}
+		feed->set_title(feed_title);
+		feed->set_tags(utils::tokenize(feed_tags, "\n"));
+		const auto pos = std::find(all_urls.begin(), all_urls.end(), feed_url);
+		feed->set_order(std::distance(all_urls.begin(), pos));
+		feed->set_placeholder_counts(unread, total);
+		placeholders.push_back(feed);
+	}
+
+	LOG(Level::INFO,
+		"Controller::load_feedlist_snapshot: showing %u feeds from the "
+		"snapshot",
+		count);
+	feedcontainer.set_feeds(placeholders);
+	return snapshot;
+}
+
+void Controller::load_feeds_in_background(void* snapshot)
+{
+	ScopeMeasure m("Controller::load_feeds_in_background");
+	const bool ignore_disp = (cfg.get_configvalue("ignore-mode") == "display");
+	for (const auto& placeholder : feedcontainer.get_all_feeds()) {
+		if (!placeholder->is_placeholder()) {
+			continue;
+		}
+
+		const std::string url = placeholder->rssurl();
+		std::shared_ptr<RssFeed> feed;
+		try {
+			feed = rsscache->internalize_rssfeed(url,
+					ignore_disp ? &ign : nullptr);
+		} catch (const DbException& e) {
+			LOG(Level::ERROR,
+				"Controller::load_feeds_in_background: couldn't load `%s': %s",
+				url,
+				e.what());
+			continue;
+		} catch (const std::string& error) {
+			LOG(Level::ERROR,
+				"Controller::load_feeds_in_background: couldn't load `%s': %s",
+				url,
+				error);
+			continue;
+		}
+		const auto tags = urlcfg->get_tags(url);
+		feed->set_tags(tags);
+		feed->set_order(placeholder->get_order());
+
+		const bool drifted = rs_feedlist_snapshot_reconcile(snapshot,
+				url.c_str(),
+				feed->title_raw().c_str(),
+				utils::join(tags, "\n").c_str(),
+				feed->unread_item_count(),
+				feed->total_item_count());
+		if (drifted) {
+			LOG(Level::INFO,
+				"Controller::load_feeds_in_background: the snapshot was off "
+				"for `%s'",
+				url);
+		}
+
+		// A reload might have replaced the placeholder already, with
+		// something newer than what was just loaded
+		feedcontainer.replace_feed(placeholder, feed);
+	}
+	rs_feedlist_snapshot_free(snapshot);
+
+	if (cfg.get_configvalue_as_bool("prepopulate-query-feeds")) {
+		feedcontainer.populate_query_feeds();
+	}
+	feedcontainer.sort_feeds(cfg.get_feed_sort_strategy());
+	update_feedlist();
+}
+
+void Controller::wait_for_feedlist()
+{
+	if (feedlist_loader.joinable()) {
+		feedlist_loader.join();
+	}
+}
+
+void Controller::save_feedlist_snapshot()
+{
+	void* snapshot = rs_feedlist_snapshot_new();
+	for (const auto& feed : feedcontainer.get_all_feeds()) {
+		const std::string tags = utils::join(urlcfg->get_tags(feed->rssurl()),
+				"\n");
+		rs_feedlist_snapshot_add_feed(snapshot,
+			feed->rssurl().c_str(),
+			feed->title_raw().c_str(),
+			tags.c_str(),
+			feed->unread_item_count(),
+			feed->total_item_count());
+	}
+	const std::string path = configpaths.feedlist_snapshot_file();
+	if (!rs_feedlist_snapshot_save(snapshot,
+			path.c_str(),
+			configpaths.url_file().c_str(),
+			configpaths.cache_file().c_str())) {
+		LOG(Level::WARN,
+			"Controller::save_feedlist_snapshot: couldn't write `%s'",
+			path);
+	}
+	rs_feedlist_snapshot_free(snapshot);
+}
+
 void Controller::update_feedlist()
 {
 	v->set_feedlist(feedcontainer.get_all_feeds());
@@ -589,6 +1038,7 @@ void Controller::mark_article_read(const std::string& guid, bool read)
 
 void Controller::mark_all_read(unsigned int pos)
 {
+	wait_for_feedlist();
 	ScopeMeasure m("Controller::mark_all_read");
 	const auto feed = feedcontainer.get_feed(pos);
 	if (feed == nullptr) {
@@ -641,33 +1091,25 @@ void Controller::replace_feed(std::shared_ptr<RssFeed> oldfeed,
 	}
 }
 
-void Controller::import_opml(const std::string& opmlFile,
+bool Controller::import_opml(const std::string& opmlFile,
 	const std::string& urlFile)
 {
 	auto urlReader = FileUrlReader(urlFile);
 	urlReader.reload(); // Load existing URLs
 
 	if (!opml::import(opmlFile, urlReader)) {
-		std::cout << strprintf::fmt(
-				_("An error occurred while parsing %s."), opmlFile)
-			<< std::endl;
-		return;
-	} else {
-		std::cout << strprintf::fmt(
-				_("Import of %s finished."), opmlFile)
-			<< std::endl;
+		return false;
 	}
+
+	std::cout << strprintf::fmt(
+			_("Import of %s finished."), opmlFile)
+		<< std::endl;
+	return true;
 }
 
-void Controller::export_opml()
+void Controller::export_opml(bool version2)
 {
-	xmlDocPtr root = opml::generate(feedcontainer);
-
-	xmlSaveCtxtPtr savectx = xmlSaveToFd(1, nullptr, 1);
-	xmlSaveDoc(savectx, root);
-	xmlSaveClose(savectx);
-
-	xmlFreeDoc(root);
+	std::cout << opml::generate(feedcontainer, version2);
 }
 
 std::vector<std::shared_ptr<RssItem>> Controller::search_for_items(
@@ -708,6 +1150,12 @@ void Controller::enqueue_url(std::shared_ptr<RssItem> item,
 void Controller::reload_urls_file()
 {
 	urlcfg->reload();
+	const auto urlfile = dynamic_cast<FileUrlReader*>(urlcfg);
+	if (urlfile != nullptr && urlfile->get_error().has_value()) {
+		// The feeds that were read before are still there
+		v->show_error(urlfile->get_error().value());
+		return;
+	}
 	std::vector<std::shared_ptr<RssFeed>> new_feeds;
 	unsigned int i = 0;
 
@@ -746,7 +1194,9 @@ void Controller::reload_urls_file()
 	update_feedlist();
 }
 
-void Controller::edit_urls_file()
+namespace {
+
+std::string editor_cmdline(const std::string& filename)
 {
 	const char* editor;
 
@@ -758,18 +1208,262 @@ void Controller::edit_urls_file()
 		editor = "vi";
 	}
 
-	std::string cmdline = strprintf::fmt("%s \"%s\"",
+	return strprintf::fmt("%s \"%s\"",
 			editor,
-			utils::replace_all(configpaths.url_file(), "\"", "\\\""));
+			utils::replace_all(filename, "\"", "\\\""));
+}
+
+} // namespace
+
+void Controller::edit_urls_file()
+{
+	const std::string url_file = configpaths.url_file();
+	std::string original;
+	{
+		std::ifstream in(url_file, std::ios::binary);
+		original.assign(std::istreambuf_iterator<char>(in),
+			std::istreambuf_iterator<char>());
+	}
+
+	// The user edits a copy, so that the file is replaced all at once, and
+	// only if what they saved is still a valid urls file.
+	const std::string filename = create_temporary_file("newsboat-urls");
+	if (filename.empty()) {
+		v->show_error(_("Error: couldn't create a temporary file."));
+		return;
+	}
+	{
+		std::ofstream out(filename, std::ios::binary);
+		out << original;
+	}
 
 	v->push_empty_formaction();
 	Stfl::reset();
 
-	utils::run_interactively(cmdline, "Controller::edit_urls_file");
+	utils::run_interactively(editor_cmdline(filename),
+		"Controller::edit_urls_file");
 
 	v->pop_current_formaction();
 
+	std::string edited;
+	{
+		std::ifstream in(filename, std::ios::binary);
+		edited.assign(std::istreambuf_iterator<char>(in),
+			std::istreambuf_iterator<char>());
+	}
+	::unlink(filename.c_str());
+
+	if (edited != original) {
+		const auto error = utils::atomic_write_urls_file(url_file, edited);
+		if (error.has_value()) {
+			v->show_error(strprintf::fmt(_("Error: couldn't save %s: %s"),
+					url_file,
+					error.value()));
+			return;
+		}
+	}
+
+	reload_urls_file();
+}
+
+FileUrlReader* Controller::tags_urls_file(std::string& error)
+{
+	FileUrlReader* urlfile = dynamic_cast<FileUrlReader*>(urlcfg);
+	if (urlfile == nullptr) {
+		error = _("Error: editing tags is only possible if `urls-source' is `local'.");
+	}
+	return urlfile;
+}
+
+std::string Controller::url_overlay_file() const
+{
+	if (!cfg.get_configvalue_as_bool("urls-file-copy-on-write")) {
+		return "";
+	}
+	return configpaths.url_overlay_file();
+}
+
+bool Controller::subscribe(const std::string& url, std::string& error)
+{
+	FileUrlReader* urlfile = dynamic_cast<FileUrlReader*>(urlcfg);
+	if (urlfile == nullptr) {
+		error = _("Error: subscribing is only possible if `urls-source' is `local'.");
+		return false;
+	}
+	if (feedcontainer.get_feed_by_url(url) != nullptr) {
+		error = strprintf::fmt(_("Error: you are already subscribed to %s."), url);
+		return false;
+	}
+	const auto write_error = urlfile->append_url(url);
+	if (write_error.has_value()) {
+		error = write_error.value();
+		return false;
+	}
+
+	reload_urls_file();
+	return true;
+}
+
+bool Controller::add_tag(const std::string& url,
+	const std::string& tag,
+	std::string& error)
+{
+	FileUrlReader* urlfile = tags_urls_file(error);
+	if (urlfile == nullptr) {
+		return false;
+	}
+	const auto edit_error = urlfile->add_tag(url, tag);
+	if (edit_error.has_value()) {
+		error = edit_error.value();
+		return false;
+	}
+
+	reload_urls_file();
+	return true;
+}
+
+bool Controller::remove_tag(const std::string& url,
+	const std::string& tag,
+	std::string& error)
+{
+	FileUrlReader* urlfile = tags_urls_file(error);
+	if (urlfile == nullptr) {
+		return false;
+	}
+	const auto edit_error = urlfile->remove_tag(url, tag);
+	if (edit_error.has_value()) {
+		error = edit_error.value();
+		return false;
+	}
+
+	reload_urls_file();
+	return true;
+}
+
+bool Controller::rename_tag(const std::string& old_tag,
+	const std::string& new_tag,
+	std::size_t& renamed,
+	std::string& error)
+{
+	renamed = 0;
+	FileUrlReader* urlfile = tags_urls_file(error);
+	if (urlfile == nullptr) {
+		return false;
+	}
+	const auto edit_error = urlfile->rename_tag(old_tag, new_tag, renamed);
+	if (edit_error.has_value()) {
+		error = edit_error.value();
+		return false;
+	}
+
 	reload_urls_file();
+	return true;
+}
+
+std::size_t Controller::count_feeds_with_tag(const std::string& tag)
+{
+	std::string error;
+	FileUrlReader* urlfile = tags_urls_file(error);
+	if (urlfile == nullptr) {
+		return 0;
+	}
+	return urlfile->count_feeds_with_tag(tag);
+}
+
+int Controller::add_feed_from_cli(const CliArgsParser& args,
+	bool running_elsewhere,
+	pid_t pid)
+{
+	std::string url;
+	if (args.add_feed().has_value()) {
+		url = args.add_feed().value();
+	} else {
+		url = args.url_argument().value();
+		if (!cfg.get_configvalue_as_bool("single-instance")) {
+			return report_error(ErrorCategory::USAGE,
+					strprintf::fmt(
+						_("Error: unexpected argument `%s'. To subscribe to it, use "
+							"--add-feed, or set `single-instance' to `yes'."),
+						url));
+		}
+	}
+
+	if (running_elsewhere) {
+		ControlSocket::Response response;
+		const auto status = ControlSocket::send_add_feed(
+				ControlSocket::path_for_lock_file(configpaths.lock_file()),
+				url,
+				response);
+		if (status != ControlSocket::SendStatus::RESPONDED) {
+			return report_error(ErrorCategory::CACHE,
+					strprintf::fmt(
+						_("Error: an instance of %s is already running (PID: %u), "
+							"but it couldn't be asked to add the feed: %s"),
+						PROGRAM_NAME,
+						pid,
+						response.message),
+					configpaths.lock_file());
+		}
+		if (!response.ok) {
+			return report_error(ErrorCategory::URLS_FILE, response.message);
+		}
+		std::cout << response.message << std::endl;
+		return EXIT_SUCCESS;
+	}
+
+	if (cfg.get_configvalue("urls-source") != "local") {
+		return report_error(ErrorCategory::CONFIG,
+				_("Error: subscribing is only possible if `urls-source' is `local'."));
+	}
+	FileUrlReader urls(configpaths.url_file(), url_overlay_file());
+	const auto result = addfeed::add(url, urls, &cfg);
+	if (result.outcome == addfeed::Outcome::FAILED) {
+		return report_error(ErrorCategory::URLS_FILE,
+				result.message,
+				configpaths.url_file());
+	}
+	std::cout << result.message << std::endl;
+	return EXIT_SUCCESS;
+}
+
+void Controller::start_control_socket()
+{
+	const std::string path =
+		ControlSocket::path_for_lock_file(configpaths.lock_file());
+	std::string error;
+	const bool started = control_socket.start(path,
+	[this](const std::string& command, const std::string& argument) {
+		return handle_control_command(command, argument);
+	},
+	error);
+	if (!started) {
+		LOG(Level::WARN,
+			"Controller::start_control_socket: can't listen on %s: %s",
+			path,
+			error);
+	}
+}
+
+ControlSocket::Response Controller::handle_control_command(
+	const std::string& command,
+	const std::string& argument)
+{
+	if (command != "add-feed") {
+		return {false, strprintf::fmt(_("unknown command `%s'"), command)};
+	}
+
+	if (dynamic_cast<FileUrlReader*>(urlcfg) == nullptr) {
+		return {false, _("Error: subscribing is only possible if `urls-source' is `local'.")};
+	}
+	// A reader of its own, so that the feed list's isn't touched until the
+	// feed was added
+	FileUrlReader urls(configpaths.url_file(), url_overlay_file());
+	const auto result = addfeed::add(argument, urls, &cfg);
+	if (result.outcome == addfeed::Outcome::ADDED) {
+		reload_urls_file();
+		v->set_status(result.message);
+	}
+	return {result.outcome != addfeed::Outcome::FAILED, result.message};
 }
 
 int Controller::execute_commands(const std::vector<std::string>& cmds)
@@ -781,41 +1475,264 @@ int Controller::execute_commands(const std::vector<std::string>& cmds)
 		LOG(Level::DEBUG,
 			"Controller::execute_commands: executing `%s'",
 			cmd);
-		if (cmd == "reload") {
-			reloader->reload_all(true);
+		const std::string reload_tag_prefix = "reload-tag ";
+		if (cmd == "reload" || cmd.compare(0, reload_tag_prefix.length(),
+				reload_tag_prefix) == 0) {
+			nonstd::optional<TagScope> scope;
+			if (cmd == "reload") {
+				reloader->reload_all(true);
+			} else {
+				std::string error;
+				scope = TagScope::select(cmd.substr(reload_tag_prefix.length()),
+						feedcontainer.get_all_feeds(),
+						error);
+				if (!scope.has_value()) {
+					return report_error(ErrorCategory::USAGE, error);
+				}
+				std::cout << scope->summary << std::endl;
+				reloader->reload_indexes(scope->indexes, true, true);
+			}
+			for (const auto& line : reload_hooks.report()) {
+				std::cout << line << std::endl;
+			}
+			CliReport report(plain_output);
+			const auto failures = reloader->last_failures();
+			for (const auto& failure : failures) {
+				report.add_failure(failure.url, failure.message);
+			}
+			for (const auto& line : report.failure_report()) {
+				std::cout << line << std::endl;
+			}
+			const bool all_failed = scope.has_value()
+				? failures.size() == scope->indexes.size()
+				: all_feeds_failed_to_download();
+			if (all_failed) {
+				return report_error(ErrorCategory::NETWORK,
+						_("Error: none of the feeds could be downloaded"));
+			}
 		} else if (cmd == "print-unread") {
-			std::cout << strprintf::fmt(_("%u unread articles"),
-					feedcontainer.unread_item_count())
-				<< std::endl;
+			CliReport report(plain_output);
+			for (const auto& feed : feedcontainer.get_all_feeds()) {
+				if (feed->is_query_feed()) {
+					continue;
+				}
+				const std::string title = feed->title().empty()
+					? utils::censor_url(feed->rssurl())
+					: feed->title();
+				report.add_feed(title, feed->unread_item_count());
+			}
+			const auto lines = report.unread_summary(
+					feedcontainer.unread_item_count());
+			for (const auto& line : lines) {
+				std::cout << line << std::endl;
+			}
+		} else if (cmd.compare(0, 4, "set ") == 0) {
+			std::string error;
+			const auto tokens = KeyMap::tokenize_cmdline(cmd, error);
+			if (!tokens.has_value()) {
+				return report_error(ErrorCategory::USAGE, error);
+			}
+			if (tokens.value().size() != 3) {
+				return report_error(ErrorCategory::USAGE,
+						_("usage: set <variable> <value>"));
+			}
+			const auto set_error = cfg.try_set_configvalue(tokens.value()[1],
+					tokens.value()[2]);
+			if (set_error.has_value()) {
+				return report_error(ErrorCategory::CONFIG,
+						strprintf::fmt(_("Error: couldn't set %s: %s"),
+							tokens.value()[1],
+							set_error.value()));
+			}
 		} else {
-			std::cerr
-					<< strprintf::fmt(_("%s: %s: unknown command"),
-							"newsboat",
-							cmd)
-						<< std::endl;
-			return EXIT_FAILURE;
+			return report_error(ErrorCategory::USAGE,
+					strprintf::fmt(_("%s: %s: unknown command"),
+						"newsboat",
+						cmd));
+		}
+	}
+	return EXIT_SUCCESS;
+}
+
+bool Controller::all_feeds_failed_to_download()
+{
+	unsigned int failed = 0;
+	for (const auto& feed : feedcontainer.get_all_feeds()) {
+		if (feed->is_query_feed()) {
+			continue;
+		}
+		if (feed->get_dl_status() != DlStatus::DL_ERROR) {
+			return false;
+		}
+		failed++;
+	}
+	return failed > 0;
+}
+
+int Controller::report_error(ErrorCategory category,
+	const std::string& message,
+	const std::string& file,
+	unsigned int line)
+{
+	LOG(Level::ERROR, "Controller::report_error: %s", message);
+	std::cerr << clierror::format(category, message, json_errors, file, line)
+		<< std::endl;
+	return clierror::exit_code(category);
+}
+
+int Controller::recover_cache(bool force)
+{
+	const std::string cachefile = configpaths.cache_file();
+	std::cout << strprintf::fmt(_("Recovering %s..."), cachefile) << std::endl;
+
+	nonstd::optional<CacheRecoverySummary> summary;
+	try {
+		summary = Cache::recover(cachefile, &cfg, force,
+		[](const CacheRecoveryTable& table) {
+			if (table.lost.has_value()) {
+				std::cout << strprintf::fmt(
+						_("%s: %u rows recovered, %u lost"),
+						table.name,
+						table.recovered,
+						table.lost.value());
+			} else {
+				std::cout << strprintf::fmt(
+						_("%s: %u rows recovered, the number of lost rows is unknown"),
+						table.name,
+						table.recovered);
+			}
+			std::cout << std::endl;
+		});
+	} catch (const DbException& e) {
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: recovering the cache file `%s' failed: %s"),
+					cachefile,
+					e.what()),
+				cachefile);
+	} catch (const std::runtime_error& e) {
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: recovering the cache file `%s' failed: %s"),
+					cachefile,
+					e.what()),
+				cachefile);
+	}
+
+	if (!summary.has_value()) {
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: the cache file `%s' isn't damaged. Use --force to recover it anyway."),
+					cachefile),
+				cachefile);
+	}
+
+	unsigned int recovered = 0;
+	unsigned int lost = 0;
+	bool lost_is_known = true;
+	for (const auto& table : summary->tables) {
+		recovered += table.recovered;
+		lost += table.lost.value_or(0);
+		lost_is_known = lost_is_known && table.lost.has_value();
+	}
+	const std::string message = lost_is_known
+		? _("Recovered %u rows, lost %u. The damaged file was kept as %s.")
+		: _("Recovered %u rows, lost at least %u. The damaged file was kept as %s.");
+	std::cout << strprintf::fmt(message, recovered, lost, summary->backup_file)
+		<< std::endl;
+
+	return EXIT_SUCCESS;
+}
+
+int Controller::query_cache(const std::string& filter,
+	const std::vector<std::string>& fields,
+	bool json)
+{
+	Matcher matcher;
+	if (!matcher.parse(filter)) {
+		return report_error(ErrorCategory::USAGE,
+				strprintf::fmt(
+					_("Error: invalid filter expression `%s': %s"),
+					filter,
+					matcher.get_parse_error()));
+	}
+
+	const std::string cachefile = configpaths.cache_file();
+	std::unique_ptr<Cache> cache;
+	try {
+		cache = Cache::open_read_only(cachefile, &cfg);
+	} catch (const DbException& e) {
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: opening the cache file `%s' failed: %s"),
+					cachefile,
+					e.what()),
+				cachefile);
+	} catch (const std::runtime_error& e) {
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: opening the cache file `%s' failed: %s"),
+					cachefile,
+					e.what()),
+				cachefile);
+	}
+
+	QueryResult result(fields, json);
+	try {
+		for (const auto& url : cache->get_feed_urls()) {
+			const auto feed = cache->internalize_rssfeed(url, nullptr);
+			for (const auto& item : feed->items()) {
+				if (matcher.matches(item.get())) {
+					result.add_article(*item);
+				}
+			}
 		}
+	} catch (const MatcherException& e) {
+		return report_error(ErrorCategory::USAGE,
+				strprintf::fmt(
+					_("Error: invalid filter expression `%s': %s"),
+					filter,
+					e.what()));
+	} catch (const DbException& e) {
+		return report_error(ErrorCategory::CACHE,
+				strprintf::fmt(
+					_("Error: reading the cache file `%s' failed: %s"),
+					cachefile,
+					e.what()),
+				cachefile);
 	}
+
+	std::cout << result.render();
 	return EXIT_SUCCESS;
 }
 
 std::string Controller::write_temporary_item(std::shared_ptr<RssItem> item)
+{
+	const std::string filename = create_temporary_file("newsboat-article");
+	if (!filename.empty()) {
+		write_item(item, filename);
+	}
+	return filename;
+}
+
+std::string Controller::create_temporary_file(const std::string& prefix)
 {
 	char filename[_POSIX_PATH_MAX];
 	char* tmpdir = getenv("TMPDIR");
 	if (tmpdir != nullptr) {
 		snprintf(filename,
 			sizeof(filename),
-			"%s/newsboat-article.XXXXXX",
-			tmpdir);
+			"%s/%s.XXXXXX",
+			tmpdir,
+			prefix.c_str());
 	} else {
 		snprintf(filename,
 			sizeof(filename),
-			"/tmp/newsboat-article.XXXXXX");
+			"/tmp/%s.XXXXXX",
+			prefix.c_str());
 	}
 	int fd = mkstemp(filename);
 	if (fd != -1) {
-		write_item(item, filename);
 		close(fd);
 		return std::string(filename);
 	} else {
@@ -823,6 +1740,41 @@ std::string Controller::write_temporary_item(std::shared_ptr<RssItem> item)
 	}
 }
 
+bool Controller::edit_note_in_editor(std::shared_ptr<RssItem> item)
+{
+	const std::string filename = create_temporary_file("newsboat-note");
+	if (filename.empty()) {
+		return false;
+	}
+
+	{
+		std::ofstream f(filename);
+		f << item->note();
+		if (!item->note().empty()) {
+			f << std::endl;
+		}
+	}
+
+	v->push_empty_formaction();
+	Stfl::reset();
+	const auto exit_code = utils::run_interactively(editor_cmdline(filename),
+			"Controller::edit_note_in_editor");
+	v->pop_current_formaction();
+
+	std::ifstream f(filename);
+	std::string note((std::istreambuf_iterator<char>(f)),
+		std::istreambuf_iterator<char>());
+	::unlink(filename.c_str());
+	if (!exit_code.has_value() || exit_code.value() != 0) {
+		return false;
+	}
+
+	utils::trim_end(note);
+	item->set_note(note);
+	update_note(item);
+	return true;
+}
+
 void Controller::write_item(std::shared_ptr<RssItem> item,
 	const std::string& filename)
 {
@@ -840,7 +1792,7 @@ void Controller::write_item(std::shared_ptr<RssItem> item, std::ostream& ostr)
 	ostr << item_renderer::to_plain_text(cfg, item) << std::endl;
 }
 
-void Controller::import_read_information(const std::string& readinfofile)
+bool Controller::import_read_information(const std::string& readinfofile)
 {
 	std::vector<std::string> guids;
 
@@ -848,32 +1800,43 @@ void Controller::import_read_information(const std::string& readinfofile)
 	std::string line;
 	getline(f, line);
 	if (!f.is_open()) {
-		return;
+		return false;
 	}
 	while (f.is_open() && !f.eof()) {
 		guids.push_back(line);
 		getline(f, line);
 	}
 	rsscache->mark_items_read_by_guid(guids);
+	return true;
 }
 
-void Controller::export_read_information(const std::string& readinfofile)
+bool Controller::export_read_information(const std::string& readinfofile)
 {
 	std::vector<std::string> guids = rsscache->get_read_item_guids();
 
-	std::fstream f;
-	f.open(readinfofile.c_str(), std::fstream::out);
-	if (f.is_open()) {
-		for (const auto& guid : guids) {
-			f << guid << std::endl;
-		}
+	std::string contents;
+	for (const auto& guid : guids) {
+		contents.append(guid + "\n");
+	}
+	const auto error = utils::atomic_write(readinfofile, contents);
+	if (error.has_value()) {
+		LOG(Level::ERROR, "Controller::export_read_information: %s",
+			error.value());
+		return false;
 	}
+	return true;
 }
 
 void Controller::update_config()
 {
 	v->apply_colors_to_all_formactions();
 
+	reload_hooks.set_timeout(
+		cfg.get_configvalue_as_int("reload-hook-timeout"));
+	empty_feed_guard.set_thresholds(
+		cfg.get_configvalue_as_int("empty-feed-min-items"),
+		cfg.get_configvalue_as_int("empty-feed-accept-after"));
+
 	if (cfg.get_configvalue("error-log").length() > 0) {
 		try {
 			Logger::set_user_error_logfile(cfg.get_configvalue("error-log"));
@@ -892,6 +1855,7 @@ void Controller::load_configfile(const std::string& filename)
 {
 	if (cfgparser.parse_file(filename)) {
 		update_config();
+		v->get_keymap()->apply_default_actions(cfg);
 	} else {
 		v->show_error(strprintf::fmt(
 				_("Error: couldn't open configuration file `%s'!"),
@@ -899,7 +1863,8 @@ void Controller::load_configfile(const std::string& filename)
 	}
 }
 
-void Controller::dump_config(const std::string& filename) const
+nonstd::optional<std::string> Controller::dump_config(
+	const std::string& filename) const
 {
 	std::vector<std::string> configlines;
 	cfg.dump_config(configlines);
@@ -907,16 +1872,19 @@ void Controller::dump_config(const std::string& filename) const
 		v->get_keymap()->dump_config(configlines);
 	}
 	ign.dump_config(configlines);
+	reload_hooks.dump_config(configlines);
+	feed_options.dump_config(configlines);
+	empty_feed_guard.dump_config(configlines);
+	feed_scheduler.dump_config(configlines);
+	feed_icons.dump_config(configlines);
 	filters.dump_config(configlines);
 	colorman.dump_config(configlines);
 	rxman.dump_config(configlines);
-	std::fstream f;
-	f.open(filename.c_str(), std::fstream::out);
-	if (f.is_open()) {
-		for (const auto& line : configlines) {
-			f << line << std::endl;
-		}
+	std::string contents;
+	for (const auto& line : configlines) {
+		contents.append(line + "\n");
 	}
+	return utils::atomic_write(filename, contents);
 }
 
 void Controller::update_flags(std::shared_ptr<RssItem> item)
@@ -928,4 +1896,28 @@ void Controller::update_flags(std::shared_ptr<RssItem> item)
 	item->update_flags();
 }
 
+void Controller::update_note(std::shared_ptr<RssItem> item)
+{
+	item->update_note();
+}
+
+void Controller::sync_article_states(
+	const std::vector<std::shared_ptr<RssItem>>& items,
+	bool read_changed,
+	bool flags_changed)
+{
+	if (!api) {
+		return;
+	}
+	for (const auto& item : items) {
+		if (read_changed) {
+			api->mark_article_read(item->guid(), !item->unread());
+		}
+		if (flags_changed) {
+			api->update_article_flags(
+				item->oldflags(), item->flags(), item->guid());
+		}
+	}
+}
+
 } // namespace newsboat
@@ -0,0 +1,115 @@
+#include "controlsocket.h"
+
+#include <cstdint>
+
+#include "logger.h"
+#include "ruststring.h"
+
+extern "C" {
+	char* rs_control_socket_path(const char* lock_file);
+
+	std::uint8_t rs_control_socket_send_add_feed(const char* path,
+		const char* url,
+		bool* ok,
+		char** message);
+
+	void* rs_control_server_bind(const char* path, char** error);
+
+	void rs_control_server_free(void* server);
+
+	void* rs_control_server_accept(void* server);
+
+	void rs_control_server_shut_down(void* server);
+
+	char* rs_control_connection_read_command(void* connection, char** argument);
+
+	void rs_control_connection_respond(void* connection,
+		bool ok,
+		const char* message);
+
+	void rs_control_connection_free(void* connection);
+}
+
+namespace newsboat {
+
+ControlSocket::~ControlSocket()
+{
+	stop();
+}
+
+bool ControlSocket::start(const std::string& path,
+	Handler handler,
+	std::string& error)
+{
+	if (rs_server != nullptr) {
+		return true;
+	}
+
+	char* error_ptr = nullptr;
+	rs_server = rs_control_server_bind(path.c_str(), &error_ptr);
+	if (rs_server == nullptr) {
+		error = RustString(error_ptr);
+		return false;
+	}
+
+	void* server = rs_server;
+	thread = std::thread([server, handler]() {
+		void* connection = nullptr;
+		while ((connection = rs_control_server_accept(server)) != nullptr) {
+			char* argument = nullptr;
+			const std::string command =
+				RustString(rs_control_connection_read_command(connection, &argument));
+			const std::string arg = RustString(argument);
+			if (!command.empty()) {
+				LOG(Level::INFO, "ControlSocket: received `%s %s'", command, arg);
+				const Response response = handler(command, arg);
+				rs_control_connection_respond(connection,
+					response.ok,
+					response.message.c_str());
+			}
+			rs_control_connection_free(connection);
+		}
+	});
+	return true;
+}
+
+void ControlSocket::stop()
+{
+	if (rs_server == nullptr) {
+		return;
+	}
+	rs_control_server_shut_down(rs_server);
+	thread.join();
+	rs_control_server_free(rs_server);
+	rs_server = nullptr;
+}
+
+std::string ControlSocket::path_for_lock_file(const std::string& lock_file)
+{
+	return RustString(rs_control_socket_path(lock_file.c_str()));
+}
+
+ControlSocket::SendStatus ControlSocket::send_add_feed(
+	const std::string& path,
+	const std::string& url,
+	Response& response)
+{
+	bool ok = false;
+	char* message = nullptr;
+	const auto status = rs_control_socket_send_add_feed(path.c_str(),
+			url.c_str(),
+			&ok,
+			&message);
+	response.ok = ok;
+	response.message = RustString(message);
+	switch (status) {
+	case 0:
+		return SendStatus::RESPONDED;
+	case 1:
+		return SendStatus::NO_INSTANCE;
+	default:
+		return SendStatus::FAILED;
+	}
+}
+
+} // namespace newsboat
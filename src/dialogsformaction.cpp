@@ -35,6 +35,7 @@ void DialogsFormAction::init()
 	FmtStrFormatter fmt;
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 	f.set("head", fmt.do_format(title_format, width));
 }
 
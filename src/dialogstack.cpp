@@ -0,0 +1,99 @@
+#include "dialogstack.h"
+
+#include <cstdint>
+
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_dialog_stack_new();
+
+	void rs_dialog_stack_free(void* stack);
+
+	void rs_dialog_stack_push(void* stack,
+		std::uintptr_t handle,
+		const char* kind,
+		const char* name,
+		std::uintptr_t parent,
+		bool has_parent);
+
+	bool rs_dialog_stack_remove(void* stack, std::uintptr_t handle);
+
+	std::size_t rs_dialog_stack_len(void* stack);
+
+	char* rs_dialog_stack_breadcrumb(void* stack, std::uintptr_t handle);
+
+	std::size_t rs_dialog_stack_unwind_to_feedlist(void* stack,
+		std::uintptr_t handle,
+		std::uintptr_t* out);
+
+	std::size_t rs_dialog_stack_close_all(void* stack, std::uintptr_t* out);
+}
+
+namespace {
+
+std::uintptr_t to_handle(const void* dialog)
+{
+	return reinterpret_cast<std::uintptr_t>(dialog);
+}
+
+std::vector<const void*> to_dialogs(const std::vector<std::uintptr_t>& handles,
+	std::size_t count)
+{
+	std::vector<const void*> result;
+	for (std::size_t i = 0; i < count; i++) {
+		result.push_back(reinterpret_cast<const void*>(handles[i]));
+	}
+	return result;
+}
+
+} // namespace
+
+namespace newsboat {
+
+DialogStack::DialogStack()
+{
+	rs_stack = rs_dialog_stack_new();
+}
+
+DialogStack::~DialogStack()
+{
+	rs_dialog_stack_free(rs_stack);
+}
+
+void DialogStack::push(const void* dialog, const std::string& kind,
+	const std::string& name, const void* parent)
+{
+	rs_dialog_stack_push(rs_stack,
+		to_handle(dialog),
+		kind.c_str(),
+		name.c_str(),
+		to_handle(parent),
+		parent != nullptr);
+}
+
+bool DialogStack::remove(const void* dialog)
+{
+	return rs_dialog_stack_remove(rs_stack, to_handle(dialog));
+}
+
+std::string DialogStack::breadcrumb(const void* dialog)
+{
+	return RustString(rs_dialog_stack_breadcrumb(rs_stack, to_handle(dialog)));
+}
+
+std::vector<const void*> DialogStack::unwind_to_feedlist(const void* dialog)
+{
+	std::vector<std::uintptr_t> handles(rs_dialog_stack_len(rs_stack));
+	const auto count = rs_dialog_stack_unwind_to_feedlist(rs_stack,
+			to_handle(dialog), handles.data());
+	return to_dialogs(handles, count);
+}
+
+std::vector<const void*> DialogStack::close_all()
+{
+	std::vector<std::uintptr_t> handles(rs_dialog_stack_len(rs_stack));
+	const auto count = rs_dialog_stack_close_all(rs_stack, handles.data());
+	return to_dialogs(handles, count);
+}
+
+} // namespace newsboat
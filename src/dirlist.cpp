@@ -0,0 +1,91 @@
+#include "dirlist.h"
+
+#include "configcontainer.h"
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_dir_list_read(const char* path,
+		bool show_hidden,
+		bool dirs_first,
+		const char* sort_order,
+		bool directories_only);
+
+	void rs_dir_list_free(void* list);
+
+	char* rs_dir_list_error(void* list);
+
+	unsigned int rs_dir_list_count(void* list);
+
+	char* rs_dir_list_row(void* list, unsigned int index);
+
+	char* rs_dir_list_name(void* list, unsigned int index);
+
+	char rs_dir_list_type_char(void* list, unsigned int index);
+
+	bool rs_dir_list_leads_to_directory(void* list, unsigned int index);
+
+	char* rs_dir_list_complete_path(const char* input,
+		bool show_hidden,
+		bool directories_only);
+}
+
+namespace newsboat {
+
+DirList::DirList(const std::string& path, ConfigContainer& cfg,
+	bool directories_only)
+{
+	rs_list = rs_dir_list_read(path.c_str(),
+			cfg.get_configvalue_as_bool("filebrowser-show-hidden"),
+			cfg.get_configvalue_as_bool("filebrowser-dirs-first"),
+			cfg.get_configvalue("filebrowser-sort-order").c_str(),
+			directories_only);
+}
+
+DirList::~DirList()
+{
+	rs_dir_list_free(rs_list);
+}
+
+unsigned int DirList::size() const
+{
+	return rs_dir_list_count(rs_list);
+}
+
+std::string DirList::row(unsigned int index) const
+{
+	return RustString(rs_dir_list_row(rs_list, index));
+}
+
+std::string DirList::name(unsigned int index) const
+{
+	return RustString(rs_dir_list_name(rs_list, index));
+}
+
+char DirList::type(unsigned int index) const
+{
+	if (rs_dir_list_leads_to_directory(rs_list, index)) {
+		return 'd';
+	}
+	const char type = rs_dir_list_type_char(rs_list, index);
+	return type == 'l' ? '-' : type;
+}
+
+nonstd::optional<std::string> DirList::error() const
+{
+	char* error = rs_dir_list_error(rs_list);
+	if (error == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(error));
+}
+
+std::string DirList::complete_path(const std::string& input,
+	ConfigContainer& cfg,
+	bool directories_only)
+{
+	return RustString(rs_dir_list_complete_path(input.c_str(),
+				cfg.get_configvalue_as_bool("filebrowser-show-hidden"),
+				directories_only));
+}
+
+} // namespace newsboat
@@ -4,8 +4,10 @@
 
 namespace newsboat {
 
-DownloadThread::DownloadThread(Reloader& r, const std::vector<int>& idxs)
-	: reloader(r), indexes(idxs) {}
+DownloadThread::DownloadThread(Reloader& r,
+	const std::vector<int>& idxs,
+	bool scheduled)
+	: reloader(r), indexes(idxs), scheduled(scheduled) {}
 
 DownloadThread::~DownloadThread() {}
 
@@ -21,9 +23,9 @@ void DownloadThread::operator()()
 		"feeds...");
 	if (reloader.trylock_reload_mutex()) {
 		if (indexes.size() == 0) {
-			reloader.reload_all();
+			reloader.reload_all(false, scheduled);
 		} else {
-			reloader.reload_indexes(indexes);
+			reloader.reload_indexes(indexes, false, scheduled);
 		}
 		reloader.unlock_reload_mutex();
 	}
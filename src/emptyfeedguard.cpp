@@ -0,0 +1,85 @@
+#include "emptyfeedguard.h"
+
+#include <cstdint>
+
+#include "confighandlerexception.h"
+#include "ruststring.h"
+#include "strprintf.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_empty_feed_guard_new();
+
+	void rs_empty_feed_guard_free(void* guard);
+
+	void rs_empty_feed_guard_set_thresholds(void* guard,
+		std::size_t min_cached_items,
+		std::uint32_t accept_after);
+
+	void rs_empty_feed_guard_force_accept(void* guard, const char* url);
+
+	char* rs_empty_feed_guard_check(void* guard,
+		const char* url,
+		std::size_t fetched_items,
+		std::size_t cached_items);
+
+	char* rs_empty_feed_guard_forced_urls(void* guard);
+}
+
+namespace newsboat {
+
+EmptyFeedGuard::EmptyFeedGuard()
+{
+	rs_guard = rs_empty_feed_guard_new();
+}
+
+EmptyFeedGuard::~EmptyFeedGuard()
+{
+	rs_empty_feed_guard_free(rs_guard);
+}
+
+void EmptyFeedGuard::handle_action(const std::string& action,
+	const std::vector<std::string>& params)
+{
+	if (action != "accept-empty-feed") {
+		throw ConfigHandlerException(ActionHandlerStatus::INVALID_COMMAND);
+	}
+	if (params.empty()) {
+		throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+	}
+
+	for (const auto& url : params) {
+		rs_empty_feed_guard_force_accept(rs_guard, url.c_str());
+	}
+}
+
+void EmptyFeedGuard::dump_config(std::vector<std::string>& config_output) const
+{
+	const std::string urls = RustString(rs_empty_feed_guard_forced_urls(rs_guard));
+	for (const auto& url : utils::tokenize(urls, "\n")) {
+		config_output.push_back(strprintf::fmt(
+				"accept-empty-feed %s", utils::quote(url)));
+	}
+}
+
+void EmptyFeedGuard::set_thresholds(unsigned int min_cached_items,
+	unsigned int accept_after)
+{
+	rs_empty_feed_guard_set_thresholds(rs_guard, min_cached_items, accept_after);
+}
+
+nonstd::optional<std::string> EmptyFeedGuard::check(const std::string& url,
+	unsigned int fetched_items,
+	unsigned int cached_items)
+{
+	char* warning = rs_empty_feed_guard_check(rs_guard,
+			url.c_str(),
+			fetched_items,
+			cached_items);
+	if (warning == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(warning));
+}
+
+} // namespace newsboat
@@ -257,11 +257,12 @@ unsigned int FeedContainer::unread_item_count() const
 	std::lock_guard<std::mutex> feedslock(feeds_mutex);
 
 	using guid_set = std::unordered_set<std::string>;
+	unsigned int placeholder_unread = 0;
 	const auto unread_guids =
 		std::accumulate(feeds.begin(),
 			feeds.end(),
 			guid_set(),
-	[](guid_set guids, const std::shared_ptr<RssFeed> feed) {
+	[&placeholder_unread](guid_set guids, const std::shared_ptr<RssFeed> feed) {
 		// Hidden feeds can't be viewed. The only way to read their articles is
 		// via a query feed; items that aren't in query feeds are completely
 		// inaccessible. Thus, we skip hidden feeds altogether to avoid
@@ -269,6 +270,12 @@ unsigned int FeedContainer::unread_item_count() const
 		if (feed->hidden()) {
 			return guids;
 		}
+		// Placeholders have no items whose GUIDs could be collected, so
+		// their count is added as it is
+		if (feed->is_placeholder()) {
+			placeholder_unread += feed->unread_item_count();
+			return guids;
+		}
 
 		std::lock_guard<std::mutex> itemslock(feed->item_mutex);
 		for (const auto& item : feed->items()) {
@@ -280,7 +287,7 @@ unsigned int FeedContainer::unread_item_count() const
 		return guids;
 	});
 
-	return unread_guids.size();
+	return unread_guids.size() + placeholder_unread;
 }
 
 void FeedContainer::replace_feed(unsigned int pos,
@@ -291,4 +298,17 @@ void FeedContainer::replace_feed(unsigned int pos,
 	feeds[pos] = feed;
 }
 
+bool FeedContainer::replace_feed(const std::shared_ptr<RssFeed>& oldfeed,
+	std::shared_ptr<RssFeed> feed)
+{
+	std::lock_guard<std::mutex> feedslock(feeds_mutex);
+	const auto it = std::find(feeds.begin(), feeds.end(), oldfeed);
+	if (it == feeds.end()) {
+		return false;
+	}
+	feed->set_index(oldfeed->get_index());
+	*it = feed;
+	return true;
+}
+
 } // namespace newsboat
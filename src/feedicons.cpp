@@ -0,0 +1,91 @@
+#include "feedicons.h"
+
+#include <cstdint>
+#include <mutex>
+
+#include "confighandlerexception.h"
+#include "rssfeed.h"
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_feed_icons_new();
+
+	void rs_feed_icons_free(void* icons);
+
+	char* rs_feed_icons_set_glyph(void* icons,
+		const char* category,
+		const char* glyph);
+
+	char* rs_feed_icons_glyph(void* icons,
+		bool is_query,
+		bool has_error,
+		std::size_t items,
+		std::size_t items_with_enclosures);
+
+	char* rs_feed_icons_dump_config(void* icons);
+}
+
+namespace newsboat {
+
+FeedIcons::FeedIcons()
+{
+	rs_icons = rs_feed_icons_new();
+}
+
+FeedIcons::~FeedIcons()
+{
+	rs_feed_icons_free(rs_icons);
+}
+
+void FeedIcons::handle_action(const std::string& action,
+	const std::vector<std::string>& params)
+{
+	if (action != "feedlist-icon") {
+		throw ConfigHandlerException(ActionHandlerStatus::INVALID_COMMAND);
+	}
+	if (params.size() < 2) {
+		throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+	}
+	if (params.size() > 2) {
+		throw ConfigHandlerException(ActionHandlerStatus::INVALID_PARAMS);
+	}
+
+	char* error = rs_feed_icons_set_glyph(rs_icons,
+			params[0].c_str(),
+			params[1].c_str());
+	if (error != nullptr) {
+		throw ConfigHandlerException(std::string(RustString(error)));
+	}
+}
+
+void FeedIcons::dump_config(std::vector<std::string>& config_output) const
+{
+	const std::string lines = RustString(rs_feed_icons_dump_config(rs_icons));
+	for (const auto& line : utils::tokenize(lines, "\n")) {
+		config_output.push_back(line);
+	}
+}
+
+std::string FeedIcons::glyph(std::shared_ptr<RssFeed> feed) const
+{
+	std::size_t items = 0;
+	std::size_t items_with_enclosures = 0;
+	{
+		std::lock_guard<std::mutex> lock(feed->item_mutex);
+		for (const auto& item : feed->items()) {
+			items++;
+			if (!item->enclosure_url().empty()) {
+				items_with_enclosures++;
+			}
+		}
+	}
+
+	return RustString(rs_feed_icons_glyph(rs_icons,
+				feed->is_query_feed(),
+				feed->get_dl_status() == DlStatus::DL_ERROR,
+				items,
+				items_with_enclosures));
+}
+
+} // namespace newsboat
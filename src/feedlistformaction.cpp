@@ -2,6 +2,8 @@
 
 #include <algorithm>
 #include <cassert>
+#include <cstddef>
+#include <cstdint>
 #include <cstring>
 #include <langinfo.h>
 #include <sstream>
@@ -13,15 +15,48 @@
 #include "fmtstrformatter.h"
 #include "listformatter.h"
 #include "logger.h"
+#include "memoryreport.h"
 #include "reloader.h"
+#include "reloadtag.h"
 #include "rssfeed.h"
 #include "scopemeasure.h"
 #include "strprintf.h"
+#include "termcaps.h"
 #include "utils.h"
 #include "view.h"
 
+extern "C" {
+	std::uint8_t rs_feed_with_unread(const std::uint32_t* unread_counts,
+		std::size_t count,
+		std::size_t current,
+		bool has_current,
+		bool forward,
+		bool wrap,
+		std::size_t* index);
+}
+
 namespace newsboat {
 
+namespace {
+
+/// Whether \a op works with the articles of the feeds, rather than just with
+/// what the feedlist shows.
+bool needs_articles(Operation op)
+{
+	switch (op) {
+	case OP_OPEN:
+	case OP_OPENALLUNREADINBROWSER:
+	case OP_OPENALLUNREADINBROWSER_AND_MARK:
+	case OP_MARKFEEDREAD:
+	case OP_MARKALLFEEDSREAD:
+		return true;
+	default:
+		return false;
+	}
+}
+
+} // namespace
+
 FeedListFormAction::FeedListFormAction(View* vv,
 	std::string formstr,
 	Cache* cc,
@@ -86,6 +121,13 @@ bool FeedListFormAction::process_operation(Operation op,
 	bool automatic,
 	std::vector<std::string>* args)
 {
+	// Until the feeds are loaded, the feedlist may show placeholders from the
+	// snapshot of the last run, which have no articles. Loading them may
+	// also re-sort the list, so the selection is looked at afterwards.
+	if (needs_articles(op)) {
+		v->get_ctrl()->wait_for_feedlist();
+	}
+
 	unsigned int pos = 0;
 	if (visible_feeds.size() >= 1) {
 		const auto selected_pos = list.get_position();
@@ -214,6 +256,7 @@ REDO:
 	}
 	break;
 	case OP_OPENINBROWSER:
+	case OP_OPENINBROWSER_NONINTERACTIVELY:
 		if (visible_feeds.size() > 0 && feedpos.length() > 0) {
 			std::shared_ptr<RssFeed> feed =
 				v->get_ctrl()->get_feedcontainer()->get_feed(pos);
@@ -243,7 +286,8 @@ REDO:
 					}
 
 					if (!url.empty()) {
-						const auto exit_code = v->open_in_browser(url);
+						const auto exit_code = v->open_in_browser(url,
+								op == OP_OPENINBROWSER);
 						if (!exit_code.has_value()) {
 							v->show_error(_("Failed to spawn browser"));
 							return false;
@@ -310,6 +354,10 @@ REDO:
 		}
 		break;
 	case OP_RELOADALL:
+		if (automatic && args->size() > 0) {
+			reload_tag((*args)[0]);
+			break;
+		}
 		LOG(Level::INFO, "FeedListFormAction: reloading all feeds");
 		{
 			bool reload_only_visible_feeds =
@@ -324,6 +372,30 @@ REDO:
 			v->get_ctrl()->get_reloader()->start_reload_all_thread(idxs);
 		}
 		break;
+	case OP_RELOADTAG: {
+		std::string tag;
+		if (automatic && args->size() > 0) {
+			tag = (*args)[0];
+		} else {
+			tag = v->select_tag();
+		}
+		if (!tag.empty()) {
+			reload_tag(tag);
+		}
+	}
+	break;
+	case OP_CANCEL_RELOAD: {
+		LOG(Level::INFO, "FeedListFormAction: cancelling reload");
+		ReloadProgress& progress =
+			v->get_ctrl()->get_reloader()->get_progress();
+		if (progress.snapshot().total > 0) {
+			progress.cancel();
+			v->set_status(_("Cancelling reload..."));
+		} else {
+			v->show_error(_("No reload is running."));
+		}
+	}
+	break;
 	case OP_MARKFEEDREAD: {
 		LOG(Level::INFO,
 			"FeedListFormAction: marking feed read at position "
@@ -396,6 +468,17 @@ REDO:
 		}
 	}
 	break;
+	case OP_NEXTFEEDWITHUNREAD:
+	case OP_PREVFEEDWITHUNREAD: {
+		LOG(Level::INFO,
+			"FeedListFormAction: opening feed with unread items");
+		const auto error =
+			v->open_feed_with_unread(op == OP_NEXTFEEDWITHUNREAD);
+		if (error.has_value()) {
+			v->show_error(error.value());
+		}
+	}
+	break;
 	case OP_RANDOMUNREAD: {
 		unsigned int local_tmp;
 		LOG(Level::INFO,
@@ -405,6 +488,23 @@ REDO:
 		}
 	}
 	break;
+	case OP_FIRST_UNREAD:
+	case OP_LAST_UNREAD:
+		if (!jump_to_unread_end(op == OP_FIRST_UNREAD)) {
+			v->show_error(_("No feeds with unread items."));
+			return false;
+		}
+		show_position();
+		break;
+	case OP_GOTO_PERCENT:
+		if (automatic) {
+			return goto_percent(args ? *args : std::vector<std::string>());
+		} else {
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(_("Go to percent: "), ""));
+			this->start_qna(qna, OP_INT_GOTO_PERCENT);
+		}
+		break;
 	case OP_MARKALLFEEDSREAD:
 		if (!cfg->get_configvalue_as_bool(
 				"confirm-mark-all-feeds-read") ||
@@ -512,6 +612,52 @@ REDO:
 	case OP_EDIT_URLS:
 		v->get_ctrl()->edit_urls_file();
 		break;
+	case OP_PREVIEW_FEED:
+		if (automatic && args->size() > 0) {
+			qna_responses.clear();
+			qna_responses.push_back((*args)[0]);
+			finished_qna(OP_INT_END_PREVIEW_FEED);
+		} else {
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(_("Feed URL: "), ""));
+			this->start_qna(qna, OP_INT_END_PREVIEW_FEED);
+		}
+		break;
+	case OP_ADD_TAG:
+	case OP_REMOVE_TAG: {
+		if (visible_feeds.empty()) {
+			v->show_error(_("No feed selected!"));
+			break;
+		}
+		const Operation end_op = (op == OP_ADD_TAG) ? OP_INT_END_ADD_TAG :
+			OP_INT_END_REMOVE_TAG;
+		if (automatic && args->size() > 0) {
+			qna_responses.clear();
+			qna_responses.push_back((*args)[0]);
+			finished_qna(end_op);
+		} else {
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(
+					(op == OP_ADD_TAG) ? _("Add tag: ") : _("Remove tag: "),
+					""));
+			this->start_qna(qna, end_op);
+		}
+	}
+	break;
+	case OP_RENAME_TAG:
+		if (automatic && args->size() > 1) {
+			qna_responses.clear();
+			qna_responses.push_back((*args)[0]);
+			qna_responses.push_back((*args)[1]);
+			finished_qna(OP_INT_END_RENAME_TAG);
+		} else {
+			// The tag that's shown is the likeliest one to be renamed
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(_("Tag to rename: "), tag));
+			qna.push_back(QnaPair(_("New name: "), ""));
+			this->start_qna(qna, OP_INT_END_RENAME_TAG);
+		}
+		break;
 	case OP_QUIT:
 		if (tag != "") {
 			op = OP_CLEARTAG;
@@ -551,20 +697,39 @@ void FeedListFormAction::update_visible_feeds(
 	assert(cfg != nullptr); // must not happen
 
 	visible_feeds.clear();
+	tag_counts.clear();
 
 	bool show_read = cfg->get_configvalue_as_bool("show-read-feeds");
 
 	unsigned int i = 0;
 	for (const auto& feed : feeds) {
 		feed->set_index(i + 1);
-		if ((tag == "" || feed->matches_tag(tag)) &&
+		const bool in_tag = (tag == "" || feed->matches_tag(tag)) &&
+			!feed->hidden();
+		// Articles of query feeds belong to other feeds, and would be
+		// counted twice
+		if (in_tag && !feed->is_query_feed()) {
+			tag_counts.update(feed->rssurl(),
+				true,
+				feed->unread_item_count());
+		}
+		if (in_tag &&
 			(show_read || feed->unread_item_count() > 0) &&
-			(!apply_filter || matcher.matches(feed.get())) &&
-			!feed->hidden()) {
+			(!apply_filter || matcher.matches(feed.get()))) {
 			visible_feeds.push_back(FeedPtrPosPair(feed, i));
 		}
 		i++;
 	}
+
+	std::vector<std::string> urls;
+	std::vector<bool> unread;
+	urls.reserve(visible_feeds.size());
+	unread.reserve(visible_feeds.size());
+	for (const auto& feed : visible_feeds) {
+		urls.push_back(feed.first->rssurl());
+		unread.push_back(feed.first->unread_item_count() > 0);
+	}
+	unread_index.reset(urls, unread);
 }
 
 void FeedListFormAction::set_feedlist(
@@ -693,6 +858,49 @@ bool FeedListFormAction::jump_to_random_unread_feed(unsigned int& feedpos)
 	return false;
 }
 
+bool FeedListFormAction::jump_to_unread_end(bool first)
+{
+	while (true) {
+		const auto pos = first ? unread_index.first() : unread_index.last();
+		if (!pos.has_value() || *pos >= visible_feeds.size()) {
+			return false;
+		}
+		const auto& feed = visible_feeds[*pos].first;
+		if (feed->unread_item_count() > 0) {
+			list.set_position(*pos);
+			return true;
+		}
+		// Read since the list was last updated
+		unread_index.update(feed->rssurl(), false);
+	}
+}
+
+bool FeedListFormAction::goto_percent(
+	const std::vector<std::string>& arguments)
+{
+	std::string error;
+	const auto percent = parse_percent(arguments, error);
+	if (!percent.has_value()) {
+		v->show_error(error);
+		return false;
+	}
+	const auto pos = percent_position(*percent, visible_feeds.size());
+	if (!pos.has_value()) {
+		v->show_error(_("No feed selected!"));
+		return false;
+	}
+	list.set_position(*pos);
+	show_position();
+	return true;
+}
+
+void FeedListFormAction::show_position()
+{
+	v->set_status(strprintf::fmt(_("feed %u/%u"),
+			list.get_position() + 1,
+			static_cast<unsigned int>(visible_feeds.size())));
+}
+
 bool FeedListFormAction::jump_to_next_unread_feed(unsigned int& feedpos)
 {
 	const unsigned int curpos = list.get_position();
@@ -735,6 +943,35 @@ bool FeedListFormAction::jump_to_next_unread_feed(unsigned int& feedpos)
 	return false;
 }
 
+FeedJump FeedListFormAction::jump_to_feed_with_unread(bool forward,
+	unsigned int& feedpos)
+{
+	std::vector<std::uint32_t> unread_counts;
+	for (const auto& feed : visible_feeds) {
+		unread_counts.push_back(feed.first->unread_item_count());
+	}
+
+	std::size_t index = 0;
+	const auto jump = static_cast<FeedJump>(rs_feed_with_unread(
+				unread_counts.data(),
+				unread_counts.size(),
+				list.get_position(),
+				!visible_feeds.empty(),
+				forward,
+				cfg->get_configvalue_as_bool("wrap-scroll"),
+				&index));
+	LOG(Level::DEBUG,
+		"FeedListFormAction::jump_to_feed_with_unread: forward = %s "
+		"result = %u",
+		forward ? "yes" : "no",
+		static_cast<unsigned int>(jump));
+	if (jump == FeedJump::JUMPED) {
+		list.set_position(index);
+		feedpos = visible_feeds[index].second;
+	}
+	return jump;
+}
+
 bool FeedListFormAction::jump_to_previous_feed(unsigned int& feedpos)
 {
 	const unsigned int curpos = list.get_position();
@@ -799,8 +1036,11 @@ void FeedListFormAction::handle_cmdline(const std::string& cmd)
 		handle_cmdline_num(idx);
 	} else {
 		// hand over all other commands to formaction
-		std::vector<std::string> tokens =
-			utils::tokenize_quoted(cmd, " \t");
+		const auto parsed = tokenize_cmdline(cmd);
+		if (!parsed.has_value()) {
+			return;
+		}
+		const std::vector<std::string>& tokens = parsed.value();
 		if (!tokens.empty()) {
 			if (tokens[0] == "tag") {
 				if (tokens.size() >= 2 && tokens[1] != "") {
@@ -830,11 +1070,94 @@ void FeedListFormAction::finished_qna(Operation op)
 	case OP_INT_START_SEARCH:
 		op_start_search();
 		break;
+	case OP_INT_END_PREVIEW_FEED: {
+		const std::string url = qna_responses[0];
+		if (!url.empty()) {
+			v->push_feed_preview(url);
+		}
+	}
+	break;
+	case OP_INT_END_ADD_TAG:
+	case OP_INT_END_REMOVE_TAG:
+		op_end_edit_tag(op == OP_INT_END_ADD_TAG);
+		break;
+	case OP_INT_END_RENAME_TAG:
+		op_end_rename_tag();
+		break;
+	case OP_INT_GOTO_PERCENT:
+		if (!qna_responses[0].empty()) {
+			goto_percent(qna_responses);
+		}
+		break;
 	default:
 		break;
 	}
 }
 
+void FeedListFormAction::op_end_edit_tag(bool add)
+{
+	const std::string tag_name = qna_responses[0];
+	if (tag_name.empty() || visible_feeds.empty()) {
+		return;
+	}
+
+	const auto feed = get_feed();
+	std::string error;
+	const bool edited = add ?
+		v->get_ctrl()->add_tag(feed->rssurl(), tag_name, error) :
+		v->get_ctrl()->remove_tag(feed->rssurl(), tag_name, error);
+	if (!edited) {
+		v->show_error(error);
+		return;
+	}
+
+	v->set_status(strprintf::fmt(add ? _("Added tag `%s' to %s.") :
+			_("Removed tag `%s' from %s."),
+			tag_name,
+			get_title(feed)));
+	do_redraw = true;
+}
+
+void FeedListFormAction::op_end_rename_tag()
+{
+	const std::string old_tag = qna_responses[0];
+	const std::string new_tag = qna_responses[1];
+	if (old_tag.empty() || new_tag.empty() || old_tag == new_tag) {
+		return;
+	}
+
+	const std::size_t count = v->get_ctrl()->count_feeds_with_tag(old_tag);
+	if (count == 0) {
+		v->show_error(strprintf::fmt(_("No feed has the tag `%s'."), old_tag));
+		return;
+	}
+	const std::string question = strprintf::fmt(
+			_("Rename tag `%s' to `%s' on %u feed(s) (y:Yes n:No)? "),
+			old_tag,
+			new_tag,
+			static_cast<unsigned int>(count));
+	if (v->confirm(question, _("yn")) != *_("y")) {
+		v->set_status("");
+		return;
+	}
+
+	std::size_t renamed = 0;
+	std::string error;
+	if (!v->get_ctrl()->rename_tag(old_tag, new_tag, renamed, error)) {
+		v->show_error(error);
+		return;
+	}
+
+	if (tag == old_tag) {
+		tag = new_tag;
+	}
+	v->set_status(strprintf::fmt(_("Renamed tag `%s' to `%s' on %u feed(s)."),
+			old_tag,
+			new_tag,
+			static_cast<unsigned int>(renamed)));
+	do_redraw = true;
+}
+
 void FeedListFormAction::mark_pos_if_visible(unsigned int pos)
 {
 	ScopeMeasure m1("FeedListFormAction::mark_pos_if_visible");
@@ -877,30 +1200,57 @@ void FeedListFormAction::save_filterpos()
 	}
 }
 
+void FeedListFormAction::reload_tag(const std::string& tag)
+{
+	std::string error;
+	const auto scope = TagScope::select(tag,
+			v->get_ctrl()->get_feedcontainer()->get_all_feeds(),
+			error);
+	if (!scope.has_value()) {
+		v->show_error(error);
+		return;
+	}
+	LOG(Level::INFO, "FeedListFormAction: %s", scope->summary);
+	v->set_status(scope->summary);
+	v->get_ctrl()->get_reloader()->start_reload_all_thread(scope->indexes,
+		true);
+}
+
 void FeedListFormAction::register_format_styles()
 {
 	const std::string attrstr = rxman.get_attrs_stfl_string("feedlist", true);
 	const std::string textview = strprintf::fmt(
 			"{!list[feeds] .expand:vh style_normal[listnormal]: "
-			"style_focus[listfocus]:fg=yellow,bg=blue,attr=bold "
+			"style_focus[listfocus]:%s "
 			"pos[feeds_pos]:0 offset[feeds_offset]:0 %s richtext:1}",
+			termcaps::degrade_style("fg=yellow,bg=blue,attr=bold"),
 			attrstr);
 	list.stfl_replace_list(0, textview);
 }
 
 void FeedListFormAction::update_form_title(unsigned int width)
 {
-	std::string title_format =
-		cfg->get_configvalue("feedlist-title-format");
+	const std::string title_format = termcaps::title_format(
+			"feedlist-title-format",
+			cfg->get_configvalue("feedlist-title-format"),
+			width);
 
 	FmtStrFormatter fmt;
 	fmt.register_fmt('T', tag);
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 	fmt.register_fmt('u', std::to_string(count_unread_feeds()));
 	fmt.register_fmt('t', std::to_string(visible_feeds.size()));
+	fmt.register_fmt('m', std::to_string(tag_counts.total()));
+	fmt.register_fmt('r', std::to_string(tag_counts.unread()));
 	fmt.register_fmt('F', apply_filter ? matcher.get_expression() : "");
 
+	const auto progress =
+		v->get_ctrl()->get_reloader()->get_progress().snapshot();
+	fmt.register_fmt('P', progress.percent_text);
+	fmt.register_fmt('C', progress.current_text);
+
 	f.set("head", fmt.do_format(title_format, width));
 }
 
@@ -932,6 +1282,7 @@ void FeedListFormAction::op_end_setfilter()
 
 void FeedListFormAction::op_start_search()
 {
+	v->get_ctrl()->wait_for_feedlist();
 	std::string searchphrase = qna_responses[0];
 	LOG(Level::DEBUG,
 		"FeedListFormAction::op_start_search: starting search for "
@@ -1017,9 +1368,18 @@ std::string FeedListFormAction::format_line(const std::string& feedlist_format,
 	unsigned int width)
 {
 	FmtStrFormatter fmt;
+	fmt.set_truncation_marker(cfg->get_configvalue("truncation-marker"));
 	unsigned int unread_count = feed->unread_item_count();
 
+	// Bidi controls have to be stripped while the text is still in UTF-8,
+	// i.e. before it's converted to the locale's charset
+	const bool strip_bidi = cfg->get_configvalue("strip-bidi-controls") == "all";
+	const auto feed_text = [&](const std::string& text) {
+		return strip_bidi ? utils::strip_bidi_controls(text) : text;
+	};
+
 	fmt.register_fmt('i', strprintf::fmt("%u", pos + 1));
+	fmt.register_fmt('I', v->get_ctrl()->get_feed_icons().glyph(feed));
 	fmt.register_fmt('u',
 		strprintf::fmt("(%u/%u)",
 			unread_count,
@@ -1028,11 +1388,12 @@ std::string FeedListFormAction::format_line(const std::string& feedlist_format,
 	fmt.register_fmt('c', std::to_string(feed->total_item_count()));
 	fmt.register_fmt('n', unread_count > 0 ? "N" : " ");
 	fmt.register_fmt('S', feed->get_status());
-	fmt.register_fmt('t', get_title(feed));
+	fmt.register_fmt('t', feed_text(get_title(feed)));
 	fmt.register_fmt('T', feed->get_firsttag());
-	fmt.register_fmt('l', utils::censor_url(feed->link()));
-	fmt.register_fmt('L', utils::censor_url(feed->rssurl()));
-	fmt.register_fmt('d', utils::utf8_to_locale(feed->description()));
+	fmt.register_fmt('l', feed_text(utils::censor_url(feed->link())));
+	fmt.register_fmt('L', feed_text(utils::censor_url(feed->rssurl())));
+	fmt.register_fmt('d',
+		utils::utf8_to_locale(feed_text(feed->description())));
 
 	auto formattedLine = fmt.do_format(feedlist_format, width);
 	formattedLine = utils::quote_for_stfl(formattedLine);
@@ -1050,4 +1411,15 @@ std::string FeedListFormAction::title()
 			static_cast<unsigned int>(visible_feeds.size()));
 }
 
+void FeedListFormAction::add_to_memory_report(MemoryReport& report)
+{
+	report.add_dialog(title());
+	for (const auto& feed : visible_feeds) {
+		std::lock_guard<std::mutex> lock(feed.first->item_mutex);
+		for (const auto& item : feed.first->items()) {
+			report.add_item(item);
+		}
+	}
+}
+
 } // namespace newsboat
@@ -0,0 +1,95 @@
+#include "feedoptions.h"
+
+#include "confighandlerexception.h"
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_feed_options_new();
+
+	void rs_feed_options_free(void* options);
+
+	char* rs_feed_options_set(void* options,
+		const char* url,
+		const char* option,
+		const char* value);
+
+	char* rs_feed_options_user_agent(void* options, const char* url);
+
+	char* rs_feed_options_headers(void* options, const char* url);
+
+	char* rs_feed_options_redacted_headers(void* options, const char* url);
+
+	char* rs_feed_options_dump_config(void* options);
+}
+
+namespace newsboat {
+
+FeedOptions::FeedOptions()
+{
+	rs_options = rs_feed_options_new();
+}
+
+FeedOptions::~FeedOptions()
+{
+	rs_feed_options_free(rs_options);
+}
+
+void FeedOptions::handle_action(const std::string& action,
+	const std::vector<std::string>& params)
+{
+	if (action != "feedopts") {
+		throw ConfigHandlerException(ActionHandlerStatus::INVALID_COMMAND);
+	}
+	// The URL, then options with their values
+	if (params.size() < 3 || params.size() % 2 == 0) {
+		throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+	}
+
+	for (std::size_t i = 1; i + 1 < params.size(); i += 2) {
+		char* error = rs_feed_options_set(rs_options,
+				params[0].c_str(),
+				params[i].c_str(),
+				params[i + 1].c_str());
+		if (error != nullptr) {
+			throw ConfigHandlerException(RustString(error));
+		}
+	}
+}
+
+void FeedOptions::dump_config(std::vector<std::string>& config_output) const
+{
+	const std::string lines =
+		RustString(rs_feed_options_dump_config(rs_options));
+	for (const auto& line : utils::tokenize(lines, "\n")) {
+		config_output.push_back(line);
+	}
+}
+
+nonstd::optional<std::string> FeedOptions::user_agent(
+	const std::string& url) const
+{
+	char* user_agent = rs_feed_options_user_agent(rs_options, url.c_str());
+	if (user_agent == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(user_agent));
+}
+
+std::vector<std::string> FeedOptions::http_headers(
+	const std::string& url) const
+{
+	const std::string lines =
+		RustString(rs_feed_options_headers(rs_options, url.c_str()));
+	return utils::tokenize(lines, "\n");
+}
+
+std::vector<std::string> FeedOptions::redacted_http_headers(
+	const std::string& url) const
+{
+	const std::string lines =
+		RustString(rs_feed_options_redacted_headers(rs_options, url.c_str()));
+	return utils::tokenize(lines, "\n");
+}
+
+} // namespace newsboat
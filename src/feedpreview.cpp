@@ -0,0 +1,73 @@
+#include "feedpreview.h"
+
+#include "logger.h"
+#include "rss/exception.h"
+#include "rssfeed.h"
+#include "rssparser.h"
+#include "ruststring.h"
+#include "strprintf.h"
+#include "utils.h"
+
+extern "C" {
+	char* rs_discover_feed(const char* html, const char* page_url);
+}
+
+namespace newsboat {
+
+namespace feedpreview {
+
+static std::shared_ptr<RssFeed> parse(const std::string& url,
+	ConfigContainer* cfg,
+	std::string& error)
+{
+	try {
+		RssParser parser(url, nullptr, cfg, nullptr);
+		const auto feed = parser.parse();
+		if (feed) {
+			return feed;
+		}
+		error = _("not a feed");
+	} catch (const rsspp::Exception& e) {
+		error = e.what();
+	} catch (const std::string& e) {
+		error = e;
+	}
+	return nullptr;
+}
+
+std::shared_ptr<RssFeed> fetch(const std::string& url,
+	ConfigContainer* cfg,
+	std::string& error)
+{
+	std::string feed_error;
+	auto feed = parse(url, cfg, feed_error);
+	if (feed) {
+		return feed;
+	}
+
+	if (utils::is_http_url(url)) {
+		// Maybe it's a web page that links to its feed
+		const std::string html = utils::retrieve_url(url, cfg);
+		char* discovered = rs_discover_feed(html.c_str(), url.c_str());
+		if (discovered != nullptr) {
+			const std::string feed_url = RustString(discovered);
+			LOG(Level::INFO,
+				"feedpreview::fetch: %s links to the feed %s",
+				url,
+				feed_url);
+			feed = parse(feed_url, cfg, feed_error);
+			if (feed) {
+				return feed;
+			}
+			error = strprintf::fmt("%s: %s", feed_url, feed_error);
+			return nullptr;
+		}
+	}
+
+	error = strprintf::fmt("%s: %s", url, feed_error);
+	return nullptr;
+}
+
+} // namespace feedpreview
+
+} // namespace newsboat
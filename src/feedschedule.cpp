@@ -0,0 +1,114 @@
+#include "feedschedule.h"
+
+#include "confighandlerexception.h"
+#include "ruststring.h"
+#include "strprintf.h"
+#include "utils.h"
+
+extern "C" {
+	char* rs_feed_schedule_from_elements(const char* ttl,
+		const char* const* hours,
+		std::size_t hours_count,
+		const char* const* days,
+		std::size_t days_count);
+
+	void* rs_feed_scheduler_new();
+
+	void rs_feed_scheduler_free(void* scheduler);
+
+	void rs_feed_scheduler_ignore_schedule(void* scheduler, const char* url);
+
+	char* rs_feed_scheduler_ignored_urls(void* scheduler);
+
+	void rs_feed_scheduler_fetched(void* scheduler, const char* url);
+
+	char* rs_feed_scheduler_postpone_reason(void* scheduler,
+		const char* url,
+		const char* schedule,
+		bool manual);
+}
+
+namespace newsboat {
+
+namespace {
+
+std::vector<const char*> to_c_strings(const std::vector<std::string>& input)
+{
+	std::vector<const char*> result;
+	for (const auto& s : input) {
+		result.push_back(s.c_str());
+	}
+	return result;
+}
+
+} // namespace
+
+std::string feedschedule::from_elements(const std::string& ttl,
+	const std::vector<std::string>& skip_hours,
+	const std::vector<std::string>& skip_days)
+{
+	const auto hours = to_c_strings(skip_hours);
+	const auto days = to_c_strings(skip_days);
+	return RustString(rs_feed_schedule_from_elements(ttl.c_str(),
+				hours.data(),
+				hours.size(),
+				days.data(),
+				days.size()));
+}
+
+FeedScheduler::FeedScheduler()
+{
+	rs_scheduler = rs_feed_scheduler_new();
+}
+
+FeedScheduler::~FeedScheduler()
+{
+	rs_feed_scheduler_free(rs_scheduler);
+}
+
+void FeedScheduler::handle_action(const std::string& action,
+	const std::vector<std::string>& params)
+{
+	if (action != "ignore-feed-schedule") {
+		throw ConfigHandlerException(ActionHandlerStatus::INVALID_COMMAND);
+	}
+	if (params.empty()) {
+		throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+	}
+
+	for (const auto& url : params) {
+		rs_feed_scheduler_ignore_schedule(rs_scheduler, url.c_str());
+	}
+}
+
+void FeedScheduler::dump_config(std::vector<std::string>& config_output) const
+{
+	const std::string urls = RustString(rs_feed_scheduler_ignored_urls(
+				rs_scheduler));
+	for (const auto& url : utils::tokenize(urls, "\n")) {
+		config_output.push_back(strprintf::fmt(
+				"ignore-feed-schedule %s", utils::quote(url)));
+	}
+}
+
+void FeedScheduler::fetched(const std::string& url)
+{
+	rs_feed_scheduler_fetched(rs_scheduler, url.c_str());
+}
+
+nonstd::optional<std::string> FeedScheduler::postpone_reason(
+	const std::string& url,
+	const std::string& schedule,
+	bool manual)
+{
+	char* reason = rs_feed_scheduler_postpone_reason(rs_scheduler,
+			url.c_str(),
+			schedule.c_str(),
+			manual);
+	if (reason == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(reason));
+}
+
+} // namespace newsboat
@@ -1,20 +1,11 @@
 #include "filebrowserformaction.h"
 
-#include <algorithm>
-#include <cinttypes>
-#include <cstring>
 #include <curses.h>
-#include <dirent.h>
-#include <grp.h>
-#include <iomanip>
-#include <iostream>
-#include <pwd.h>
-#include <sstream>
 #include <sys/stat.h>
-#include <sys/types.h>
 #include <unistd.h>
 
 #include "config.h"
+#include "dirlist.h"
 #include "fmtstrformatter.h"
 #include "listformatter.h"
 #include "logger.h"
@@ -141,6 +132,16 @@ bool FileBrowserFormAction::process_operation(Operation op,
 		const std::string focus = f.get_focus();
 		if (focus == "files") {
 			f.set_focus("filename");
+			break;
+		}
+		// In the input field, complete the path first, and only switch
+		// once there's nothing left to complete
+		const std::string typed = f.get("filenametext");
+		const std::string completed =
+			DirList::complete_path(typed, *cfg, false);
+		if (completed != typed) {
+			f.set("filenametext", completed);
+			f.set("filenametext_pos", std::to_string(completed.length()));
 		} else {
 			f.set_focus("files");
 		}
@@ -213,6 +214,7 @@ void FileBrowserFormAction::update_title(const std::string& working_directory)
 	FmtStrFormatter fmt;
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 	fmt.register_fmt('f', working_directory);
 
 	const std::string title = fmt.do_format(
@@ -221,34 +223,6 @@ void FileBrowserFormAction::update_title(const std::string& working_directory)
 	f.set("head", title);
 }
 
-std::vector<std::string> get_sorted_filelist()
-{
-	std::vector<std::string> ret;
-
-	const std::string cwdtmp = utils::getcwd();
-
-	DIR* dirp = ::opendir(cwdtmp.c_str());
-	if (dirp) {
-		struct dirent* de = ::readdir(dirp);
-		while (de) {
-			if (strcmp(de->d_name, ".") != 0 &&
-				strcmp(de->d_name, "..") != 0) {
-				ret.push_back(de->d_name);
-			}
-			de = ::readdir(dirp);
-		}
-		::closedir(dirp);
-	}
-
-	std::sort(ret.begin(), ret.end());
-
-	if (cwdtmp != "/") {
-		ret.insert(ret.begin(), "..");
-	}
-
-	return ret;
-}
-
 void FileBrowserFormAction::prepare()
 {
 	/*
@@ -257,16 +231,24 @@ void FileBrowserFormAction::prepare()
 	 * in the current directory.
 	 */
 	if (do_redraw) {
-		std::vector<std::string> files = get_sorted_filelist();
+		const DirList list(utils::getcwd(), *cfg, false);
 
 		ListFormatter listfmt;
 
 		id_at_position.clear();
-		for (std::string filename : files) {
-			add_file(listfmt, id_at_position, filename);
+		for (unsigned int i = 0; i < list.size(); ++i) {
+			listfmt.add_line(utils::quote_for_stfl(list.row(i)));
+			id_at_position.push_back(
+				strprintf::fmt("%c%s", list.type(i), list.name(i)));
 		}
 
 		files_list.stfl_replace_lines(listfmt);
+		const auto error = list.error();
+		if (error.has_value()) {
+			v->show_error(strprintf::fmt(_("Couldn't list %s: %s"),
+					utils::getcwd(),
+					error.value()));
+		}
 		do_redraw = false;
 	}
 
@@ -317,130 +299,6 @@ KeyMapHintEntry* FileBrowserFormAction::get_keymap_hint()
 	return hints;
 }
 
-void FileBrowserFormAction::add_file(
-	ListFormatter& listfmt,
-	std::vector<std::string>& id_at_position,
-	std::string filename)
-{
-	struct stat sb;
-	if (::lstat(filename.c_str(), &sb) == 0) {
-		char ftype = get_filetype(sb.st_mode);
-
-		std::string rwxbits = get_rwx(sb.st_mode & 0777);
-		std::string owner = get_owner(sb.st_uid);
-		std::string group = get_group(sb.st_gid);
-		std::string formattedfilename =
-			get_formatted_filename(filename, ftype, sb.st_mode);
-
-		std::string sizestr = strprintf::fmt(
-				"%12" PRIi64,
-				// `st_size` is `off_t`, which is a signed integer type of
-				// unspecified size. We'll have to bet it's no larger than 64
-				// bits.
-				static_cast<int64_t>(sb.st_size));
-		std::string line = strprintf::fmt("%c%s %s %s %s %s",
-				ftype,
-				rwxbits,
-				owner,
-				group,
-				sizestr,
-				formattedfilename);
-		listfmt.add_line(utils::quote_for_stfl(line));
-		const std::string id = strprintf::fmt("%c%s", ftype, filename);
-		id_at_position.push_back(id);
-	}
-}
-
-std::string FileBrowserFormAction::get_formatted_filename(std::string filename,
-	char ftype,
-	mode_t mode)
-{
-	char suffix = 0;
-
-	switch (ftype) {
-	case 'd':
-		suffix = '/';
-		break;
-	case 'l':
-		suffix = '@';
-		break;
-	case 's':
-		suffix = '=';
-		break;
-	case 'p':
-		suffix = '|';
-		break;
-	default:
-		if (mode & S_IXUSR) {
-			suffix = '*';
-		}
-	}
-
-	return strprintf::fmt("%s%c", filename, suffix);
-}
-
-std::string FileBrowserFormAction::get_rwx(unsigned short val)
-{
-	std::string str;
-	const char* bitstrs[] = {
-		"---", "--x", "-w-", "-wx", "r--", "r-x", "rw-", "rwx"
-	};
-	for (int i = 0; i < 3; ++i) {
-		unsigned char bits = val % 8;
-		val /= 8;
-		str.insert(0, bitstrs[bits]);
-	}
-	return str;
-}
-
-char FileBrowserFormAction::get_filetype(mode_t mode)
-{
-	static struct FlagChar {
-		mode_t flag;
-		char ftype;
-	} flags[] = {{S_IFREG, '-'},
-		{S_IFDIR, 'd'},
-		{S_IFBLK, 'b'},
-		{S_IFCHR, 'c'},
-		{S_IFIFO, 'p'},
-		{S_IFLNK, 'l'},
-		{S_IFSOCK, 's'},
-		{0, 0}
-	};
-	for (unsigned int i = 0; flags[i].flag != 0; i++) {
-		if ((mode & S_IFMT) == flags[i].flag) {
-			return flags[i].ftype;
-		}
-	}
-	return '?';
-}
-
-std::string FileBrowserFormAction::get_owner(uid_t uid)
-{
-	struct passwd* spw = getpwuid(uid);
-	if (spw) {
-		std::string owner = spw->pw_name;
-		for (int i = owner.length(); i < 8; ++i) {
-			owner.append(" ");
-		}
-		return owner;
-	}
-	return "????????";
-}
-
-std::string FileBrowserFormAction::get_group(gid_t gid)
-{
-	struct group* sgr = getgrgid(gid);
-	if (sgr) {
-		std::string group = sgr->gr_name;
-		for (int i = group.length(); i < 8; ++i) {
-			group.append(" ");
-		}
-		return group;
-	}
-	return "????????";
-}
-
 std::string FileBrowserFormAction::title()
 {
 	return strprintf::fmt(_("Save File - %s"), utils::getcwd());
@@ -1,13 +1,73 @@
 #include "fileurlreader.h"
 
-#include <fstream>
+#include <iostream>
+#include <mutex>
+#include <set>
 
+#include "config.h"
+#include "logger.h"
+#include "ruststring.h"
+#include "strprintf.h"
 #include "utils.h"
 
+extern "C" {
+	void* rs_urls_file_parse(const char* contents,
+		char** error,
+		std::size_t* error_line);
+	void rs_urls_file_entries_free(void* entries);
+	std::size_t rs_urls_file_entries_count(void* entries);
+	char* rs_urls_file_entries_get(void* entries,
+		std::size_t index,
+		std::size_t* tags_count);
+	char* rs_urls_file_entries_get_tag(void* entries,
+		std::size_t index,
+		std::size_t tag_index);
+	char* rs_urls_file_add_tag(const char* contents,
+		const char* url,
+		const char* tag,
+		char** error);
+	char* rs_urls_file_remove_tag(const char* contents,
+		const char* url,
+		const char* tag,
+		char** error);
+	char* rs_urls_file_rename_tag(const char* contents,
+		const char* old_tag,
+		const char* new_tag,
+		std::size_t* count,
+		char** error);
+	std::size_t rs_urls_file_feeds_with_tag(const char* contents,
+		const char* tag);
+	char* rs_urls_file_append_url(const char* contents, const char* url);
+	char* rs_urls_file_merge_overlay(const char* base, const char* overlay);
+	char* rs_urls_file_save(const char* path,
+		const char* overlay,
+		const char* contents);
+}
+
 namespace newsboat {
 
-FileUrlReader::FileUrlReader(const std::string& file)
+namespace {
+
+/// The urls file is re-read on every reload, and the remote urls-sources
+/// create a new reader each time; the warning is only shown once per file.
+void warn_about_encoding(const std::string& warning)
+{
+	static std::mutex mutex;
+	static std::set<std::string> shown;
+
+	std::lock_guard<std::mutex> guard(mutex);
+	if (shown.insert(warning).second) {
+		LOG(Level::WARN, "FileUrlReader::reload: %s", warning);
+		std::cerr << warning << std::endl;
+	}
+}
+
+}
+
+FileUrlReader::FileUrlReader(const std::string& file,
+	const std::string& overlay_file)
 	: filename(file)
+	, overlay_filename(overlay_file)
 {
 }
 
@@ -18,55 +78,195 @@ std::string FileUrlReader::get_source()
 
 void FileUrlReader::reload()
 {
+	error = nonstd::nullopt;
+	error_line = 0;
+
+	const auto contents = read_contents();
+	if (!contents.has_value()) {
+		urls.clear();
+		tags.clear();
+		alltags.clear();
+		return;
+	}
+
+	char* message = nullptr;
+	std::size_t line = 0;
+	void* entries = rs_urls_file_parse(contents.value().c_str(), &message,
+			&line);
+	if (entries == nullptr) {
+		// Keep the feeds we've got rather than dropping all of them
+		error = std::string(RustString(message));
+		error_line = line;
+		LOG(Level::ERROR, "FileUrlReader::reload: %s", error.value());
+		return;
+	}
+
 	urls.clear();
 	tags.clear();
 	alltags.clear();
 
-	std::fstream f;
-	f.open(filename, std::fstream::in);
-	if (!f.is_open()) {
-		return;
-	}
+	const std::size_t count = rs_urls_file_entries_count(entries);
+	for (std::size_t i = 0; i < count; i++) {
+		std::size_t tags_count = 0;
+		const std::string url = RustString(
+				rs_urls_file_entries_get(entries, i, &tags_count));
+		urls.push_back(url);
 
-	for (std::string line; std::getline(f, line); /* nothing */) {
-		// skip empty lines and comments
-		if (line.empty() || line[0] == '#') {
+		if (tags_count == 0) {
 			continue;
 		}
-
-		std::vector<std::string> tokens = utils::tokenize_quoted(line);
-		if (tokens.empty()) {
-			continue;
+		std::vector<std::string> url_tags;
+		for (std::size_t j = 0; j < tags_count; j++) {
+			const std::string tag = RustString(
+					rs_urls_file_entries_get_tag(entries, i, j));
+			url_tags.push_back(tag);
+			alltags.insert(tag);
 		}
+		tags[url] = url_tags;
+	}
+	rs_urls_file_entries_free(entries);
+}
 
-		std::string url = tokens[0];
-		urls.push_back(url);
+nonstd::optional<std::string> FileUrlReader::get_error() const
+{
+	return error;
+}
 
-		tokens.erase(tokens.begin());
-		if (!tokens.empty()) {
-			tags[url] = tokens;
-			for (const auto& token : tokens) {
-				alltags.insert(token);
-			}
-		}
-	};
+unsigned int FileUrlReader::get_error_line() const
+{
+	return error_line;
 }
 
-void FileUrlReader::write_config()
+nonstd::optional<std::string> FileUrlReader::read_contents()
 {
-	std::fstream f;
-	f.open(filename, std::fstream::out);
-	if (f.is_open()) {
-		for (const auto& url : urls) {
-			f << url;
-			if (tags[url].size() > 0) {
-				for (const auto& tag : tags[url]) {
-					f << " \"" << tag << "\"";
-				}
-			}
-			f << std::endl;
+	std::string encoding_warning;
+	const auto contents = utils::read_text_file(filename, encoding_warning);
+	if (!encoding_warning.empty()) {
+		warn_about_encoding(encoding_warning);
+	}
+	if (overlay_filename.empty()) {
+		return contents;
+	}
+
+	std::string overlay_warning;
+	const auto overlay = utils::read_text_file(overlay_filename,
+			overlay_warning);
+	if (!overlay.has_value()) {
+		return contents;
+	}
+	// The feeds in the overlay are still there if the urls file is gone
+	return std::string(RustString(rs_urls_file_merge_overlay(
+					contents.value_or("").c_str(), overlay.value().c_str())));
+}
+
+nonstd::optional<std::string> FileUrlReader::write_contents(
+	const std::string& contents)
+{
+	char* error = rs_urls_file_save(filename.c_str(),
+			overlay_filename.empty() ? nullptr : overlay_filename.c_str(),
+			contents.c_str());
+	if (error != nullptr) {
+		return std::string(RustString(error));
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<std::string> FileUrlReader::write_config()
+{
+	std::string contents;
+	for (const auto& url : urls) {
+		contents.append(url);
+		for (const auto& tag : tags[url]) {
+			contents.append(" \"" + tag + "\"");
 		}
+		contents.append("\n");
+	}
+	return write_contents(contents);
+}
+
+nonstd::optional<std::string> FileUrlReader::append_url(const std::string& url)
+{
+	// A missing file is created
+	const std::string old_contents = read_contents().value_or("");
+	const std::string contents = RustString(rs_urls_file_append_url(
+				old_contents.c_str(), url.c_str()));
+	const auto error = write_contents(contents);
+	if (error.has_value()) {
+		LOG(Level::ERROR, "FileUrlReader::append_url: %s", error.value());
+		return error;
+	}
+
+	urls.push_back(url);
+	return nonstd::nullopt;
+}
+
+template<typename Edit>
+nonstd::optional<std::string> FileUrlReader::edit_file(Edit edit)
+{
+	// A file that isn't UTF-8 is written back in UTF-8; the editor only
+	// understands that, and it's what --convert-to-utf8 would do anyway.
+	const auto contents = read_contents();
+	if (!contents.has_value()) {
+		return strprintf::fmt(_("Error: couldn't read %s."), filename);
+	}
+
+	char* error = nullptr;
+	char* edited = edit(contents.value().c_str(), &error);
+	if (edited == nullptr) {
+		return std::string(RustString(error));
+	}
+	const std::string new_contents = RustString(edited);
+
+	const auto write_error = write_contents(new_contents);
+	if (write_error.has_value()) {
+		LOG(Level::ERROR, "FileUrlReader::edit_file: %s", write_error.value());
+		return write_error;
+	}
+
+	reload();
+	return nonstd::nullopt;
+}
+
+nonstd::optional<std::string> FileUrlReader::add_tag(const std::string& url,
+	const std::string& tag)
+{
+	return edit_file([&](const char* contents, char** error) {
+		return rs_urls_file_add_tag(contents, url.c_str(), tag.c_str(), error);
+	});
+}
+
+nonstd::optional<std::string> FileUrlReader::remove_tag(
+	const std::string& url,
+	const std::string& tag)
+{
+	return edit_file([&](const char* contents, char** error) {
+		return rs_urls_file_remove_tag(
+				contents, url.c_str(), tag.c_str(), error);
+	});
+}
+
+nonstd::optional<std::string> FileUrlReader::rename_tag(
+	const std::string& old_tag,
+	const std::string& new_tag,
+	std::size_t& renamed)
+{
+	renamed = 0;
+	return edit_file([&](const char* contents, char** error) {
+		return rs_urls_file_rename_tag(contents,
+				old_tag.c_str(),
+				new_tag.c_str(),
+				&renamed,
+				error);
+	});
+}
+
+std::size_t FileUrlReader::count_feeds_with_tag(const std::string& tag)
+{
+	const auto contents = read_contents();
+	if (!contents.has_value()) {
+		return 0;
 	}
+	return rs_urls_file_feeds_with_tag(contents.value().c_str(), tag.c_str());
 }
 
 }
@@ -0,0 +1,95 @@
+#include "filterbuilder.h"
+
+#include <cstddef>
+#include <cstdint>
+
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_filter_builder_new(const char* context);
+
+	void rs_filter_builder_free(void* builder);
+
+	std::uint8_t rs_filter_builder_stage(void* builder);
+
+	char* rs_filter_builder_items(void* builder);
+
+	char* rs_filter_builder_title(void* builder);
+
+	void rs_filter_builder_select(void* builder, std::size_t index);
+
+	char* rs_filter_builder_prompt(void* builder);
+
+	char* rs_filter_builder_set_value(void* builder, const char* value);
+
+	void rs_filter_builder_cancel_value(void* builder);
+
+	char* rs_filter_builder_expression(void* builder);
+}
+
+namespace newsboat {
+
+FilterBuilder::FilterBuilder(const std::string& context)
+{
+	rs_object = rs_filter_builder_new(context.c_str());
+}
+
+FilterBuilder::~FilterBuilder()
+{
+	rs_filter_builder_free(rs_object);
+}
+
+FilterBuilder::Stage FilterBuilder::stage() const
+{
+	return static_cast<Stage>(rs_filter_builder_stage(rs_object));
+}
+
+std::vector<std::string> FilterBuilder::items() const
+{
+	// Items are never empty, so it's fine that the tokenizer drops empty
+	// lines
+	return utils::tokenize(RustString(rs_filter_builder_items(rs_object)),
+			"\n");
+}
+
+std::string FilterBuilder::title() const
+{
+	return RustString(rs_filter_builder_title(rs_object));
+}
+
+void FilterBuilder::select(unsigned int index)
+{
+	rs_filter_builder_select(rs_object, index);
+}
+
+std::string FilterBuilder::prompt() const
+{
+	return RustString(rs_filter_builder_prompt(rs_object));
+}
+
+nonstd::optional<std::string> FilterBuilder::set_value(
+	const std::string& value)
+{
+	char* error = rs_filter_builder_set_value(rs_object, value.c_str());
+	if (error != nullptr) {
+		return std::string(RustString(error));
+	}
+	return nonstd::nullopt;
+}
+
+void FilterBuilder::cancel_value()
+{
+	rs_filter_builder_cancel_value(rs_object);
+}
+
+nonstd::optional<std::string> FilterBuilder::expression() const
+{
+	char* expression = rs_filter_builder_expression(rs_object);
+	if (expression != nullptr) {
+		return std::string(RustString(expression));
+	}
+	return nonstd::nullopt;
+}
+
+} // namespace newsboat
@@ -0,0 +1,179 @@
+#include "filterbuilderformaction.h"
+
+#include "config.h"
+#include "listformatter.h"
+#include "utils.h"
+#include "view.h"
+
+namespace newsboat {
+
+FilterBuilderFormAction::FilterBuilderFormAction(View* vv,
+	std::string formstr,
+	ConfigContainer* cfg)
+	: FormAction(vv, formstr, cfg)
+	, quit(false)
+	, context("article")
+	, entering_value(false)
+	, items_list("taglist", FormAction::f,
+		  cfg->get_configvalue_as_int("scrolloff"))
+{
+}
+
+FilterBuilderFormAction::~FilterBuilderFormAction() {}
+
+bool FilterBuilderFormAction::process_operation(Operation op,
+	bool /* automatic */,
+	std::vector<std::string>* /* args */)
+{
+	bool hardquit = false;
+	switch (op) {
+	case OP_PREV:
+	case OP_SK_UP:
+		items_list.move_up(cfg->get_configvalue_as_bool("wrap-scroll"));
+		break;
+	case OP_NEXT:
+	case OP_SK_DOWN:
+		items_list.move_down(cfg->get_configvalue_as_bool("wrap-scroll"));
+		break;
+	case OP_SK_HOME:
+		items_list.move_to_first();
+		break;
+	case OP_SK_END:
+		items_list.move_to_last();
+		break;
+	case OP_SK_PGUP:
+		items_list.move_page_up(cfg->get_configvalue_as_bool("wrap-scroll"));
+		break;
+	case OP_SK_PGDOWN:
+		items_list.move_page_down(cfg->get_configvalue_as_bool("wrap-scroll"));
+		break;
+	case OP_QUIT:
+		if (builder->stage() == FilterBuilder::Stage::VALUE) {
+			// Step back to the operators rather than losing the
+			// conditions added so far
+			builder->cancel_value();
+			last_value.clear();
+			do_redraw = true;
+		} else {
+			expression = "";
+			quit = true;
+		}
+		break;
+	case OP_HARDQUIT:
+		expression = "";
+		hardquit = true;
+		break;
+	case OP_OPEN:
+		if (builder->stage() == FilterBuilder::Stage::VALUE) {
+			start_value_qna();
+			break;
+		}
+		builder->select(items_list.get_position());
+		items_list.set_position(0);
+		do_redraw = true;
+		switch (builder->stage()) {
+		case FilterBuilder::Stage::VALUE:
+			start_value_qna();
+			break;
+		case FilterBuilder::Stage::DONE:
+			expression = builder->expression().value_or("");
+			quit = true;
+			break;
+		default:
+			break;
+		}
+		break;
+	default:
+		break;
+	}
+
+	if (hardquit) {
+		while (v->formaction_stack_size() > 0) {
+			v->pop_current_formaction();
+		}
+	} else if (quit) {
+		v->pop_current_formaction();
+	}
+	return true;
+}
+
+void FilterBuilderFormAction::start_value_qna()
+{
+	entering_value = true;
+	std::vector<QnaPair> qna;
+	qna.push_back(QnaPair(builder->prompt(), last_value));
+	this->start_qna(qna, OP_INT_END_FILTER_VALUE);
+}
+
+void FilterBuilderFormAction::finished_qna(Operation op)
+{
+	FormAction::finished_qna(op); // important!
+
+	switch (op) {
+	case OP_INT_END_FILTER_VALUE: {
+		entering_value = false;
+		last_value = qna_responses[0];
+		const auto error = builder->set_value(last_value);
+		if (error.has_value()) {
+			// The dialog stays at the value, so the user can press
+			// ENTER and correct it
+			v->show_error(error.value());
+		} else {
+			last_value.clear();
+		}
+		do_redraw = true;
+	}
+	break;
+	default:
+		break;
+	}
+}
+
+void FilterBuilderFormAction::prepare()
+{
+	if (entering_value && f.get_focus() != "qnainput") {
+		// The prompt was cancelled
+		entering_value = false;
+		do_redraw = true;
+	}
+
+	if (do_redraw) {
+		f.set("head", builder->title());
+
+		ListFormatter listfmt;
+		for (const auto& item : builder->items()) {
+			listfmt.add_line(utils::quote_for_stfl(item));
+		}
+		items_list.stfl_replace_lines(listfmt);
+
+		do_redraw = false;
+	}
+}
+
+void FilterBuilderFormAction::init()
+{
+	do_redraw = true;
+	quit = false;
+	expression = "";
+	entering_value = false;
+	last_value.clear();
+	builder.reset(new FilterBuilder(context));
+
+	set_keymap_hints();
+}
+
+KeyMapHintEntry* FilterBuilderFormAction::get_keymap_hint()
+{
+	static KeyMapHintEntry hints[] = {{OP_QUIT, _("Cancel")},
+		{OP_OPEN, _("Select")},
+		{OP_NIL, nullptr}
+	};
+	return hints;
+}
+
+std::string FilterBuilderFormAction::title()
+{
+	return _("Build Filter");
+}
+
+} // namespace newsboat
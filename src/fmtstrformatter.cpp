@@ -17,10 +17,21 @@ extern "C" {
 		char key,
 		const char* value);
 
+	void rs_fmtstrformatter_set_truncation_marker(
+		void* fmt,
+		const char* marker);
+
 	char* rs_fmtstrformatter_do_format(
 		void* fmt,
 		const char* format,
 		std::uint32_t width);
+
+	char* rs_fmtstrformatter_do_format_wrapped(
+		void* fmt,
+		const char* format,
+		std::uint32_t width,
+		char key,
+		std::size_t max_rows);
 }
 
 namespace newsboat {
@@ -40,10 +51,35 @@ void FmtStrFormatter::register_fmt(char f, const std::string& value)
 	rs_fmtstrformatter_register_fmt(rs_fmt, f, value.c_str());
 }
 
+void FmtStrFormatter::set_truncation_marker(const std::string& marker)
+{
+	rs_fmtstrformatter_set_truncation_marker(rs_fmt, marker.c_str());
+}
+
 std::string FmtStrFormatter::do_format(const std::string& fmt,
 	unsigned int width)
 {
 	return RustString(rs_fmtstrformatter_do_format(rs_fmt, fmt.c_str(), width));
 }
 
+std::vector<std::string> FmtStrFormatter::do_format_wrapped(
+	const std::string& fmt,
+	unsigned int width,
+	char key,
+	unsigned int max_rows)
+{
+	const std::string rows = RustString(rs_fmtstrformatter_do_format_wrapped(
+				rs_fmt, fmt.c_str(), width, key, max_rows));
+
+	std::vector<std::string> result;
+	std::string::size_type start = 0;
+	std::string::size_type end = 0;
+	while ((end = rows.find('\n', start)) != std::string::npos) {
+		result.push_back(rows.substr(start, end - start));
+		start = end + 1;
+	}
+	result.push_back(rows.substr(start));
+	return result;
+}
+
 } // namespace newsboat
@@ -6,16 +6,29 @@
 
 #include "config.h"
 #include "configexception.h"
+#include "confighandlerexception.h"
 #include "logger.h"
 #include "matcherexception.h"
+#include "ruststring.h"
 #include "strprintf.h"
 #include "utils.h"
 #include "view.h"
 
+extern "C" {
+	char* rs_describe_key_argument(const char* argument);
+	char* rs_describe_unknown_name(const char* name,
+		const char* options,
+		const char* operations);
+	char* rs_describe_completions(const char* fragment,
+		const char* options,
+		const char* operations);
+}
+
 namespace newsboat {
 
 History FormAction::searchhistory;
 History FormAction::cmdlinehistory;
+std::map<unsigned int, History> FormAction::macro_argument_histories;
 
 FormAction::FormAction(View* vv, std::string formstr, ConfigContainer* cfg)
 	: v(vv)
@@ -48,6 +61,11 @@ FormAction::FormAction(View* vv, std::string formstr, ConfigContainer* cfg)
 	valid_cmds.push_back("dumpconfig");
 	valid_cmds.push_back("dumpform");
 	valid_cmds.push_back("exec");
+	valid_cmds.push_back("edit-macro");
+	valid_cmds.push_back("describe-key");
+	valid_cmds.push_back("describe");
+	valid_cmds.push_back("memory-report");
+	valid_cmds.push_back("run");
 }
 
 void FormAction::set_keymap_hints()
@@ -115,20 +133,40 @@ bool FormAction::process_op(Operation op,
 	case OP_CMDLINE:
 		start_cmdline();
 		break;
+	case OP_EDIT_MACRO:
+		if (args != nullptr && !args->empty()) {
+			edit_macro(args->front());
+		} else {
+			start_cmdline("edit-macro ");
+		}
+		break;
+	case OP_DESCRIBE_KEY:
+		if (args != nullptr && !args->empty()) {
+			describe_key(args->front(), id());
+		} else {
+			start_cmdline("describe-key ");
+		}
+		break;
+	case OP_DESCRIBE:
+		if (args != nullptr && !args->empty()) {
+			describe(args->front());
+		} else {
+			start_cmdline("describe ");
+		}
+		break;
 	case OP_INT_SET:
 		if (automatic) {
-			std::string cmdline = "set ";
+			std::vector<std::string> command = {"set"};
 			if (args) {
-				for (const auto& arg : *args) {
-					cmdline.append(strprintf::fmt(
-							"%s ", Stfl::quote(arg)));
-				}
+				command.insert(command.end(), args->begin(), args->end());
 			}
 			LOG(Level::DEBUG,
-				"FormAction::process_op: running commandline "
-				"`%s'",
-				cmdline);
-			this->handle_cmdline(cmdline);
+				"FormAction::process_op: running `set' with %" PRIu64
+				" arguments",
+				static_cast<uint64_t>(command.size() - 1));
+			if (!this->handle_parsed_cmdline(command)) {
+				return false;
+			}
 		} else {
 			LOG(Level::WARN,
 				"FormAction::process_op: got OP_INT_SET, but "
@@ -175,6 +213,12 @@ bool FormAction::process_op(Operation op,
 	case OP_PREVDIALOG:
 		v->goto_prev_dialog();
 		break;
+	case OP_POP_TO_FEEDLIST:
+		v->pop_to_feedlist();
+		break;
+	case OP_CLOSE_ALL_DIALOGS:
+		v->close_all_dialogs();
+		break;
 	default:
 		return this->process_operation(op, automatic, args);
 	}
@@ -230,6 +274,18 @@ std::vector<std::string> FormAction::get_suggestions(
 							line);
 					}
 				}
+			} else if (tokens[0] == "describe") {
+				if (tokens.size() <= 2) {
+					const std::string start = (tokens.size() == 2) ? tokens[1] : "";
+					const std::string names = RustString(rs_describe_completions(
+								start.c_str(),
+								utils::join(cfg->get_suggestions(""), "\n").c_str(),
+								utils::join(v->get_keymap()->get_operation_names(),
+									"\n").c_str()));
+					for (const auto& name : utils::tokenize(names, "\n")) {
+						result.push_back(std::string("describe ") + name);
+					}
+				}
 			} else if (tokens[0] == "exec") {
 				if (tokens.size() <= 2) {
 					const std::string start = (tokens.size() == 2) ? tokens[1] : "";
@@ -252,6 +308,21 @@ std::vector<std::string> FormAction::get_suggestions(
 	return result;
 }
 
+nonstd::optional<std::vector<std::string>> FormAction::tokenize_cmdline(
+	const std::string& cmdline)
+{
+	std::string error;
+	const auto tokens = KeyMap::tokenize_cmdline(cmdline, error);
+	if (!tokens.has_value()) {
+		LOG(Level::DEBUG,
+			"FormAction::tokenize_cmdline: failed to tokenize `%s': %s",
+			cmdline,
+			error);
+		v->show_error(error);
+	}
+	return tokens;
+}
+
 void FormAction::handle_cmdline(const std::string& cmdline)
 {
 	/*
@@ -261,10 +332,17 @@ void FormAction::handle_cmdline(const std::string& cmdline)
 	 * class doesn't implement the handle_cmdline() method by itself.
 	 *
 	 * It works the same way basically everywhere: first the command line
-	 * is tokenized, and then the tokens are looked at.
+	 * is tokenized (with the same rules as macros), and then the tokens are
+	 * looked at.
 	 */
-	std::vector<std::string> tokens =
-		utils::tokenize_quoted(cmdline, " \t=");
+	const auto tokens = tokenize_cmdline(cmdline);
+	if (tokens.has_value()) {
+		handle_parsed_cmdline(tokens.value());
+	}
+}
+
+bool FormAction::handle_parsed_cmdline(std::vector<std::string> tokens)
+{
 	assert(cfg != nullptr);
 	if (!tokens.empty()) {
 		std::string cmd = tokens[0];
@@ -276,13 +354,19 @@ void FormAction::handle_cmdline(const std::string& cmdline)
 			} else if (tokens.size() == 1) {
 				std::string var = tokens[0];
 				if (var.length() > 0) {
-					if (var[var.length() - 1] == '!') {
+					const char suffix = var[var.length() - 1];
+					if (suffix == '!' || suffix == '&') {
 						var.erase(var.length() - 1);
+					}
+					const auto error = cfg->check_option_exists(var);
+					if (error.has_value()) {
+						v->show_error(error.value());
+						return false;
+					}
+					if (suffix == '!') {
 						cfg->toggle(var);
 						set_redraw(true);
-					} else if (var[var.length() - 1] ==
-						'&') {
-						var.erase(var.length() - 1);
+					} else if (suffix == '&') {
 						cfg->reset_to_default(var);
 						set_redraw(true);
 					}
@@ -297,7 +381,22 @@ void FormAction::handle_cmdline(const std::string& cmdline)
 					ConfigParser::evaluate_backticks(
 						tokens[1]);
 				utils::trim_end(result);
-				cfg->set_configvalue(tokens[0], result);
+				const std::string previous = cfg->get_configvalue(tokens[0]);
+				const auto error = cfg->try_set_configvalue(tokens[0], result);
+				if (error.has_value()) {
+					v->show_error(strprintf::fmt(_("Error: couldn't set %s: %s"),
+							tokens[0],
+							error.value()));
+					return false;
+				}
+				try {
+					v->get_keymap()->apply_default_actions(*cfg);
+				} catch (const ConfigException& ex) {
+					// The value names an operation that doesn't work there
+					cfg->set_configvalue(tokens[0], previous);
+					v->show_error(ex.what());
+					return false;
+				}
 				// because some configuration value might have changed something UI-related
 				set_redraw(true);
 			} else {
@@ -327,11 +426,18 @@ void FormAction::handle_cmdline(const std::string& cmdline)
 			if (tokens.size() != 1) {
 				v->show_error(_("usage: dumpconfig <file>"));
 			} else {
-				v->get_ctrl()->dump_config(
-					utils::resolve_tilde(tokens[0]));
-				v->show_error(strprintf::fmt(
-						_("Saved configuration to %s"),
-						tokens[0]));
+				const auto error = v->get_ctrl()->dump_config(
+						utils::resolve_tilde(tokens[0]));
+				if (error.has_value()) {
+					v->show_error(strprintf::fmt(
+							_("Error: couldn't save configuration to %s: %s"),
+							tokens[0],
+							error.value()));
+				} else {
+					v->show_error(strprintf::fmt(
+							_("Saved configuration to %s"),
+							tokens[0]));
+				}
 			}
 		} else if (cmd == "dumpform") {
 			v->dump_current_form();
@@ -346,11 +452,137 @@ void FormAction::handle_cmdline(const std::string& cmdline)
 					v->show_error(_("Operation not found"));
 				}
 			}
+		} else if (cmd == "preview-feed") {
+			if (tokens.size() != 1) {
+				v->show_error(_("usage: preview-feed <url>"));
+			} else {
+				v->push_feed_preview(tokens[0]);
+			}
+		} else if (cmd == "edit-macro") {
+			if (tokens.size() != 1) {
+				v->show_error(_("usage: edit-macro <key>"));
+			} else {
+				edit_macro(tokens[0]);
+			}
+		} else if (cmd == "describe-key") {
+			if (tokens.empty() || tokens.size() > 2) {
+				v->show_error(_("usage: describe-key <key> [<context>]"));
+			} else {
+				describe_key(tokens[0], tokens.size() == 2 ? tokens[1] : id());
+			}
+		} else if (cmd == "describe") {
+			if (tokens.size() != 1) {
+				v->show_error(_("usage: describe <option|operation|key:<key>>"));
+			} else {
+				describe(tokens[0]);
+			}
+		} else if (cmd == "memory-report") {
+			v->show_memory_report();
 		} else {
 			v->show_error(strprintf::fmt(
-					_("Not a command: %s"), cmdline));
+					_("Not a command: %s"), cmd));
 		}
 	}
+	return true;
+}
+
+bool FormAction::handle_run_cmdline(const std::string& cmdline)
+{
+	nonstd::optional<std::vector<MacroCmd>> commands;
+	try {
+		commands = v->get_keymap()->parse_run_cmdline(cmdline);
+	} catch (const ConfigHandlerException& e) {
+		v->show_error(e.what());
+		return true;
+	}
+	if (!commands.has_value()) {
+		return false;
+	}
+
+	LOG(Level::DEBUG,
+		"FormAction::handle_run_cmdline: running %" PRIu64 " operations",
+		static_cast<uint64_t>(commands->size()));
+	v->run_commands(commands.value());
+	return true;
+}
+
+void FormAction::edit_macro(const std::string& key)
+{
+	std::string error;
+	const auto cmdline = v->get_keymap()->get_macro_cmdline(key, error);
+	if (!cmdline.has_value()) {
+		v->show_error(error);
+		return;
+	}
+	start_cmdline(cmdline.value());
+}
+
+void FormAction::describe_key(const std::string& key,
+	const std::string& context)
+{
+	try {
+		v->set_status(v->get_keymap()->describe_key(key, context));
+	} catch (const ConfigHandlerException& e) {
+		v->show_error(e.what());
+	}
+}
+
+void FormAction::describe(const std::string& name)
+{
+	char* key = rs_describe_key_argument(name.c_str());
+	if (key != nullptr) {
+		describe_key(RustString(key), id());
+		return;
+	}
+
+	KeyMap* keys = v->get_keymap();
+	std::vector<std::string> descriptions;
+	const auto option = cfg->describe_option(name);
+	if (option.has_value()) {
+		descriptions.push_back(option.value());
+	}
+	const auto operation = keys->describe_operation(name);
+	if (operation.has_value()) {
+		descriptions.push_back(operation.value());
+	}
+
+	if (descriptions.empty()) {
+		v->show_error(RustString(rs_describe_unknown_name(name.c_str(),
+					utils::join(cfg->get_suggestions(""), "\n").c_str(),
+					utils::join(keys->get_operation_names(), "\n").c_str())));
+		return;
+	}
+	v->show_text(utils::join(descriptions, "\n\n"));
+}
+
+void FormAction::start_macro_argument_qna(const std::string& key)
+{
+	macro_key = key;
+	macro_arguments.clear();
+	ask_next_macro_argument();
+}
+
+void FormAction::ask_next_macro_argument()
+{
+	KeyMap* keys = v->get_keymap();
+	const auto prompt = KeyMap::next_macro_argument_prompt(
+			keys->get_macro_parameter_count(macro_key),
+			macro_arguments.size());
+	if (prompt.has_value()) {
+		std::vector<QnaPair> qna;
+		qna.push_back(QnaPair(prompt.value(), ""));
+		start_qna(qna,
+			OP_INT_END_MACRO_ARGUMENT,
+			&macro_argument_histories[macro_arguments.size()]);
+		return;
+	}
+
+	LOG(Level::DEBUG,
+		"FormAction::ask_next_macro_argument: running macro `%s' with "
+		"%" PRIu64 " arguments",
+		macro_key,
+		static_cast<uint64_t>(macro_arguments.size()));
+	v->run_commands(keys->get_macro(macro_key, macro_arguments));
 }
 
 void FormAction::start_qna(const std::vector<QnaPair>& prompts,
@@ -415,12 +647,21 @@ void FormAction::finished_qna(Operation op)
 		}
 	}
 	break;
+	case OP_INT_END_MACRO_ARGUMENT: {
+		const std::string argument = qna_responses[0];
+		macro_argument_histories[macro_arguments.size()].add_line(argument);
+		macro_arguments.push_back(argument);
+		ask_next_macro_argument();
+	}
+	break;
 	case OP_INT_END_CMDLINE: {
 		f.set_focus("feeds");
 		std::string cmdline = qna_responses[0];
 		FormAction::cmdlinehistory.add_line(cmdline);
 		LOG(Level::DEBUG, "FormAction: commandline = `%s'", cmdline);
-		this->handle_cmdline(cmdline);
+		if (!handle_run_cmdline(cmdline)) {
+			this->handle_cmdline(cmdline);
+		}
 	}
 	break;
 	default:
@@ -0,0 +1,75 @@
+#include "helpdata.h"
+
+#include <cinttypes>
+#include <cstdint>
+#include <sstream>
+
+#include "keymap.h"
+#include "logger.h"
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_help_new(const char* context);
+
+	void rs_help_free(void* help);
+
+	void rs_help_add_row(void* help,
+		const char* context,
+		const char* key,
+		const char* operation,
+		const char* description,
+		std::uint8_t category);
+
+	void rs_help_set_filter(void* help, const char* filter);
+
+	char* rs_help_lines(void* help, std::size_t width);
+}
+
+namespace newsboat {
+
+HelpData::HelpData(KeyMap& keys, const std::string& context)
+{
+	rs_object = rs_help_new(context.c_str());
+
+	const auto descs = keys.get_keymap_descriptions(context);
+	LOG(Level::DEBUG,
+		"HelpData::HelpData: %" PRIu64 " rows for context `%s'",
+		static_cast<uint64_t>(descs.size()),
+		context);
+	for (const auto& desc : descs) {
+		// Generic bindings apply in every dialog, whatever context they
+		// were looked up in
+		const std::string row_context =
+			(desc.flags & KM_SYSKEYS) ? "everywhere" : desc.ctx;
+		rs_help_add_row(rs_object,
+			row_context.c_str(),
+			desc.key.c_str(),
+			desc.cmd.c_str(),
+			desc.desc.c_str(),
+			static_cast<std::uint8_t>(desc.category));
+	}
+}
+
+HelpData::~HelpData()
+{
+	rs_help_free(rs_object);
+}
+
+void HelpData::set_filter(const std::string& filter)
+{
+	rs_help_set_filter(rs_object, filter.c_str());
+}
+
+std::vector<std::string> HelpData::lines(unsigned int width) const
+{
+	std::istringstream lines(RustString(rs_help_lines(rs_object, width)));
+	// Sections are separated by empty lines, so we can't use utils::tokenize
+	std::vector<std::string> result;
+	std::string line;
+	while (std::getline(lines, line)) {
+		result.push_back(line);
+	}
+	return result;
+}
+
+} // namespace newsboat
@@ -1,13 +1,11 @@
 #include "helpformaction.h"
 
-#include <cstring>
-#include <sstream>
-
 #include "config.h"
 #include "fmtstrformatter.h"
+#include "helpdata.h"
 #include "keymap.h"
 #include "listformatter.h"
-#include "strprintf.h"
+#include "termcaps.h"
 #include "utils.h"
 #include "view.h"
 
@@ -18,7 +16,7 @@ HelpFormAction::HelpFormAction(View* vv,
 	ConfigContainer* cfg)
 	: FormAction(vv, formstr, cfg)
 	, quit(false)
-	, apply_search(false)
+	, filtering(false)
 	, textview("helptext", FormAction::f)
 {
 }
@@ -56,13 +54,15 @@ bool HelpFormAction::process_operation(Operation op,
 		hardquit = true;
 		break;
 	case OP_SEARCH: {
+		filter_before_edit = filter;
+		filtering = true;
 		std::vector<QnaPair> qna;
-		qna.push_back(QnaPair(_("Search for: "), ""));
+		qna.push_back(QnaPair(_("Filter: "), filter));
 		this->start_qna(qna, OP_INT_START_SEARCH, &searchhistory);
 	}
 	break;
 	case OP_CLEARFILTER:
-		apply_search = false;
+		filter.clear();
 		do_redraw = true;
 		break;
 	default:
@@ -80,6 +80,25 @@ bool HelpFormAction::process_operation(Operation op,
 
 void HelpFormAction::prepare()
 {
+	if (filtering) {
+		// prepare() runs after every key the user presses, so the rows
+		// follow the filter while it's typed
+		if (f.get_focus() != "qnainput") {
+			// The prompt was cancelled
+			filtering = false;
+			if (filter != filter_before_edit) {
+				filter = filter_before_edit;
+				do_redraw = true;
+			}
+		} else {
+			const std::string typed = f.get("qna_value");
+			if (typed != filter) {
+				filter = typed;
+				do_redraw = true;
+			}
+		}
+	}
+
 	if (do_redraw) {
 		f.run(-3); // compute all widget dimensions
 
@@ -88,142 +107,24 @@ void HelpFormAction::prepare()
 		FmtStrFormatter fmt;
 		fmt.register_fmt('N', PROGRAM_NAME);
 		fmt.register_fmt('V', utils::program_version());
+		fmt.register_fmt('S', v->dialog_breadcrumb(this));
 		f.set("head",
 			fmt.do_format(cfg->get_configvalue("help-title-format"),
 				width));
 
-		const auto descs = v->get_keymap()->get_keymap_descriptions(context);
+		if (!help_data) {
+			help_data.reset(new HelpData(*v->get_keymap(), context));
+		}
+		help_data->set_filter(filter);
 
-		std::string highlighted_searchphrase =
-			strprintf::fmt("<hl>%s</>", searchphrase);
 		std::vector<std::string> colors = utils::tokenize(
 				cfg->get_configvalue("search-highlight-colors"), " ");
 		f.set("highlight", make_colorstring(colors));
-		ListFormatter listfmt;
-
-		unsigned int unbound_count = 0;
-		unsigned int syskey_count = 0;
 
-		for (unsigned int i = 0; i < 3; i++) {
-			for (const auto& desc : descs) {
-				bool condition;
-				switch (i) {
-				case 0:
-					condition = (desc.key.length() == 0 ||
-							desc.flags & KM_SYSKEYS);
-					if (desc.key.length() == 0) {
-						unbound_count++;
-					}
-					if (desc.flags & KM_SYSKEYS) {
-						syskey_count++;
-					}
-					break;
-				case 1:
-					condition = !(desc.flags & KM_SYSKEYS);
-					break;
-				case 2:
-					condition = (desc.key.length() > 0 ||
-							desc.flags & KM_SYSKEYS);
-					break;
-				default:
-					condition = true;
-					break;
-				}
-				if (context.length() > 0 &&
-					(desc.ctx != context || condition)) {
-					continue;
-				}
-				if (!apply_search ||
-					strcasestr(desc.key.c_str(),
-						searchphrase.c_str()) !=
-					nullptr ||
-					strcasestr(desc.cmd.c_str(),
-						searchphrase.c_str()) !=
-					nullptr ||
-					strcasestr(desc.desc.c_str(),
-						searchphrase.c_str()) !=
-					nullptr) {
-					char tabs_1[] = "                ";
-					char tabs_2[] =
-						"                        ";
-					int how_often_1 = strlen(tabs_1) -
-						desc.key.length();
-					int how_often_2 = strlen(tabs_2) -
-						desc.cmd.length();
-					if (how_often_1 <= 0) {
-						how_often_1 = 1;
-					}
-					if (how_often_2 <= 0) {
-						how_often_2 = 1;
-					}
-					tabs_1[how_often_1] = '\0';
-					tabs_2[how_often_2] = '\0';
-					std::string line;
-					switch (i) {
-					case 0:
-					case 1:
-						line = strprintf::fmt(
-								"%s%s%s%s%s",
-								desc.key,
-								tabs_1,
-								desc.cmd,
-								tabs_2,
-								desc.desc);
-						break;
-					case 2:
-						line = strprintf::fmt(
-								"%s%s%s%s",
-								desc.cmd,
-								tabs_1,
-								tabs_2,
-								desc.desc);
-						break;
-					}
-					LOG(Level::DEBUG,
-						"HelpFormAction::prepare: "
-						"step 1 "
-						"- line = %s",
-						line);
-					line = utils::quote_for_stfl(line);
-					LOG(Level::DEBUG,
-						"HelpFormAction::prepare: "
-						"step 2 "
-						"- line = %s",
-						line);
-					if (apply_search &&
-						searchphrase.length() > 0) {
-						line = utils::replace_all(line,
-								searchphrase,
-								highlighted_searchphrase);
-						LOG(Level::DEBUG,
-							"HelpFormAction::"
-							"prepare: "
-							"step 3 - line = %s",
-							line);
-					}
-					listfmt.add_line(line);
-				}
-			}
-			switch (i) {
-			case 0:
-				if (syskey_count > 0) {
-					listfmt.add_line("");
-					listfmt.add_line(
-						_("Generic bindings:"));
-					listfmt.add_line("");
-				}
-				break;
-			case 1:
-				if (unbound_count > 0) {
-					listfmt.add_line("");
-					listfmt.add_line(
-						_("Unbound functions:"));
-					listfmt.add_line("");
-				}
-				break;
-			}
+		ListFormatter listfmt;
+		for (const auto& line : help_data->lines(width)) {
+			listfmt.add_line(line);
 		}
-
 		textview.stfl_replace_lines(listfmt.get_lines_count(), listfmt.format_list());
 
 		do_redraw = false;
@@ -251,8 +152,8 @@ void HelpFormAction::finished_qna(Operation op)
 	v->inside_qna(false);
 	switch (op) {
 	case OP_INT_START_SEARCH:
-		searchphrase = qna_responses[0];
-		apply_search = true;
+		filtering = false;
+		filter = qna_responses[0];
 		do_redraw = true;
 		break;
 	default:
@@ -265,6 +166,8 @@ void HelpFormAction::set_context(const std::string& ctx)
 	if (context != ctx) {
 		do_redraw = true;
 		context = ctx;
+		// Rebuilt for the new context the next time the dialog is drawn
+		help_data.reset();
 	}
 }
 
@@ -299,7 +202,7 @@ std::string HelpFormAction::make_colorstring(
 			result.append(colors[i]);
 		}
 	}
-	return result;
+	return termcaps::degrade_style(result);
 }
 
 } // namespace newsboat
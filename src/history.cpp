@@ -17,6 +17,8 @@ extern "C" {
 
 	char* rs_history_next_line(void* hst);
 
+	char* rs_history_search_prefix(void* hst, const char* prefix);
+
 	void rs_history_load_from_file(
 		void* hst,
 		const char* file);
@@ -54,6 +56,16 @@ std::string History::next_line()
 	return RustString(rs_history_next_line(rs_hst));
 }
 
+nonstd::optional<std::string> History::search_prefix(
+	const std::string& prefix) const
+{
+	char* line = rs_history_search_prefix(rs_hst, prefix.c_str());
+	if (line == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(line));
+}
+
 void History::load_from_file(const std::string& file)
 {
 	rs_history_load_from_file(rs_hst, file.c_str());
@@ -5,7 +5,9 @@
 #include <cstdio>
 #include <cstring>
 #include <iostream>
+#include <iterator>
 #include <libgen.h>
+#include <limits>
 #include <sstream>
 #include <stdexcept>
 
@@ -17,8 +19,9 @@
 
 namespace newsboat {
 
-HtmlRenderer::HtmlRenderer(bool raw)
+HtmlRenderer::HtmlRenderer(bool raw, unsigned int max_nesting)
 	: raw_(raw)
+	, max_nesting(max_nesting)
 {
 	tags["a"] = HtmlTag::A;
 	tags["embed"] = HtmlTag::EMBED;
@@ -64,8 +67,13 @@ void HtmlRenderer::render(const std::string& source,
 	std::vector<LinkPair>& links,
 	const std::string& url)
 {
-	std::istringstream input(source);
-	render(input, lines, links, url);
+	State state(source, url);
+	state.lines = std::move(lines);
+	state.links = std::move(links);
+	while (!render_some(state, std::numeric_limits<std::size_t>::max())) {
+	}
+	lines = std::move(state.lines);
+	links = std::move(state.links);
 }
 
 unsigned int HtmlRenderer::add_link(std::vector<LinkPair>& links,
@@ -93,25 +101,15 @@ void HtmlRenderer::render(std::istream& input,
 	std::vector<LinkPair>& links,
 	const std::string& url)
 {
-	unsigned int image_count = 0;
-	unsigned int video_count = 0;
-	unsigned int audio_count = 0;
-	unsigned int source_count = 0;
-	std::string curline;
-	int indent_level = 0;
-	std::vector<HtmlTag> list_elements_stack;
-	bool inside_pre = false;
-	bool itunes_hack = false;
-	size_t inside_script = 0;
-	size_t inside_style = 0;
-	bool inside_video = false;
-	bool inside_audio = false;
-	std::vector<unsigned int> ol_counts;
-	std::vector<char> ol_types;
-	HtmlTag current_tag;
-	int link_num = -1;
-	std::vector<Table> tables;
+	const std::string source((std::istreambuf_iterator<char>(input)),
+		std::istreambuf_iterator<char>());
+	render(source, lines, links, url);
+}
 
+HtmlRenderer::State::State(const std::string& source, const std::string& url)
+	: input(source)
+	, url(url)
+{
 	/*
 	 * to render the HTML, we use a self-developed "XML" pull parser.
 	 *
@@ -121,12 +119,46 @@ void HtmlRenderer::render(std::istream& input,
 	 *   - we then can iterate over all continuous elements, such as start
 	 * tag, close tag, text element, ...
 	 */
-	TagSoupPullParser xpp;
 	xpp.set_input(input);
+}
+
+bool HtmlRenderer::render_some(State& state, std::size_t max_nodes)
+{
+	if (state.finished) {
+		return true;
+	}
+
+	// The rendering picks up where the previous call left off
+	auto& lines = state.lines;
+	auto& links = state.links;
+	const std::string& url = state.url;
+	TagSoupPullParser& xpp = state.xpp;
+	unsigned int& image_count = state.image_count;
+	unsigned int& video_count = state.video_count;
+	unsigned int& audio_count = state.audio_count;
+	unsigned int& source_count = state.source_count;
+	std::string& curline = state.curline;
+	int& indent_level = state.indent_level;
+	std::vector<HtmlTag>& list_elements_stack = state.list_elements_stack;
+	bool& inside_pre = state.inside_pre;
+	bool& itunes_hack = state.itunes_hack;
+	size_t& inside_script = state.inside_script;
+	size_t& inside_style = state.inside_style;
+	bool& inside_video = state.inside_video;
+	bool& inside_audio = state.inside_audio;
+	std::vector<unsigned int>& ol_counts = state.ol_counts;
+	std::vector<char>& ol_types = state.ol_types;
+	HtmlTag& current_tag = state.current_tag;
+	int& link_num = state.link_num;
+	std::vector<Table>& tables = state.tables;
+
+	for (std::size_t nodes = 0; nodes < max_nodes; ++nodes) {
+		const TagSoupPullParser::Event e = xpp.next();
+		if (e == TagSoupPullParser::Event::END_DOCUMENT) {
+			state.finished = true;
+			break;
+		}
 
-	for (TagSoupPullParser::Event e = xpp.next();
-		e != TagSoupPullParser::Event::END_DOCUMENT;
-		e = xpp.next()) {
 		std::string tagname;
 		switch (e) {
 		case TagSoupPullParser::Event::START_TAG:
@@ -284,7 +316,7 @@ void HtmlRenderer::render(std::istream& input,
 			break;
 
 			case HtmlTag::BLOCKQUOTE:
-				++indent_level;
+				enter_nesting(state, 1);
 				add_nonempty_line(curline, tables, lines);
 				add_line("", tables, lines);
 				prepare_new_line(curline,
@@ -364,10 +396,7 @@ void HtmlRenderer::render(std::istream& input,
 				if (list_elements_stack.size() >= 1
 					&& list_elements_stack.back() == HtmlTag::LI) {
 					list_elements_stack.pop_back();
-					indent_level -= 2;
-					if (indent_level < 0) {
-						indent_level = 0;
-					}
+					leave_nesting(state, 2);
 					add_nonempty_line(
 						curline, tables, lines);
 					prepare_new_line(curline,
@@ -378,7 +407,7 @@ void HtmlRenderer::render(std::istream& input,
 				add_nonempty_line(curline, tables, lines);
 				prepare_new_line(curline,
 					tables.size() ? 0 : indent_level);
-				indent_level += 2;
+				enter_nesting(state, 2);
 
 				const auto latest_list = std::find_if(list_elements_stack.rbegin(),
 				list_elements_stack.rend(), [](const HtmlTag& tag) {
@@ -410,7 +439,7 @@ void HtmlRenderer::render(std::istream& input,
 				break;
 
 			case HtmlTag::DD:
-				indent_level += 4;
+				enter_nesting(state, 4);
 				add_nonempty_line(curline, tables, lines);
 				prepare_new_line(curline,
 					tables.size() ? 0 : indent_level);
@@ -607,10 +636,7 @@ void HtmlRenderer::render(std::istream& input,
 
 			switch (current_tag) {
 			case HtmlTag::BLOCKQUOTE:
-				--indent_level;
-				if (indent_level < 0) {
-					indent_level = 0;
-				}
+				leave_nesting(state, 1);
 				add_nonempty_line(curline, tables, lines);
 				add_line("", tables, lines);
 				prepare_new_line(curline,
@@ -627,10 +653,7 @@ void HtmlRenderer::render(std::istream& input,
 				if (list_elements_stack.size() >= 1
 					&& list_elements_stack.back() == HtmlTag::LI) {
 					list_elements_stack.pop_back();
-					indent_level -= 2;
-					if (indent_level < 0) {
-						indent_level = 0;
-					}
+					leave_nesting(state, 2);
 					add_nonempty_line(
 						curline, tables, lines);
 					prepare_new_line(curline,
@@ -652,10 +675,7 @@ void HtmlRenderer::render(std::istream& input,
 				break;
 
 			case HtmlTag::DD:
-				indent_level -= 4;
-				if (indent_level < 0) {
-					indent_level = 0;
-				}
+				leave_nesting(state, 4);
 				add_nonempty_line(curline, tables, lines);
 				add_line("", tables, lines);
 				prepare_new_line(curline,
@@ -667,10 +687,7 @@ void HtmlRenderer::render(std::istream& input,
 				break;
 
 			case HtmlTag::LI:
-				indent_level -= 2;
-				if (indent_level < 0) {
-					indent_level = 0;
-				}
+				leave_nesting(state, 2);
 				if (list_elements_stack.size() >= 1
 					&& list_elements_stack.back() == HtmlTag::LI) {
 					list_elements_stack.pop_back();
@@ -934,6 +951,10 @@ void HtmlRenderer::render(std::istream& input,
 		}
 	}
 
+	if (!state.finished) {
+		return false;
+	}
+
 	// and the rest
 	add_nonempty_line(curline, tables, lines);
 
@@ -963,6 +984,17 @@ void HtmlRenderer::render(std::istream& input,
 			add_line_softwrappable(link_text, lines);
 		}
 	}
+
+	if (state.truncated_nestings > 0) {
+		LOG(Level::INFO,
+			"HtmlRenderer::render_some: %s nests %u elements deeper than "
+			"%u levels, they weren't indented",
+			url,
+			state.truncated_nestings,
+			max_nesting);
+	}
+
+	return true;
 }
 
 void HtmlRenderer::add_media_link(std::string& curline,
@@ -1052,6 +1084,40 @@ void HtmlRenderer::prepare_new_line(std::string& line, int indent_level)
 	line.append(indent_level * 2, ' ');
 }
 
+void HtmlRenderer::enter_nesting(State& state, int indent)
+{
+	++state.nesting_depth;
+	if (state.nesting_depth <= max_nesting) {
+		state.indent_level += indent;
+		return;
+	}
+
+	// Hostile feeds nest hundreds of levels, which would leave no room
+	// for text; mark the spot where indentation stops, but only once
+	if (state.truncated_nestings++ == 0) {
+		const int indent_level = state.tables.size() ? 0 : state.indent_level;
+		add_nonempty_line(state.curline, state.tables, state.lines);
+		prepare_new_line(state.curline, indent_level);
+		state.curline.append(_("[nesting truncated]"));
+		add_line(state.curline, state.tables, state.lines);
+		prepare_new_line(state.curline, indent_level);
+	}
+}
+
+void HtmlRenderer::leave_nesting(State& state, int indent)
+{
+	const bool was_indented = state.nesting_depth <= max_nesting;
+	if (state.nesting_depth > 0) {
+		--state.nesting_depth;
+	}
+	if (was_indented) {
+		state.indent_level -= indent;
+		if (state.indent_level < 0) {
+			state.indent_level = 0;
+		}
+	}
+}
+
 bool HtmlRenderer::line_is_nonempty(const std::string& line)
 {
 	for (std::string::size_type i = 0; i < line.length(); ++i) {
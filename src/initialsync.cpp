@@ -0,0 +1,161 @@
+#include "initialsync.h"
+
+#include <algorithm>
+#include <cinttypes>
+#include <stdexcept>
+#include <thread>
+#include <vector>
+
+#include "logger.h"
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_initial_sync_new(std::size_t page_size,
+		std::uint32_t attempts,
+		std::uint64_t page_delay_ms,
+		const char* progress);
+
+	void rs_initial_sync_free(void* sync);
+
+	bool rs_initial_sync_next_request(void* sync,
+		std::size_t* limit,
+		bool* has_before_id,
+		std::int64_t* before_id);
+
+	char* rs_initial_sync_page_fetched(void* sync,
+		const std::int64_t* ids,
+		std::size_t count);
+
+	std::int64_t rs_initial_sync_page_failed(void* sync);
+
+	std::uint64_t rs_initial_sync_page_delay_ms(void* sync);
+
+	bool rs_initial_sync_is_complete(void* sync);
+
+	char* rs_initial_sync_progress(void* sync);
+
+	char* rs_initial_sync_status(void* sync);
+}
+
+namespace newsboat {
+
+InitialSync::InitialSync(unsigned int page_size,
+	unsigned int attempts,
+	std::chrono::milliseconds page_delay,
+	const std::string& progress)
+{
+	rs_sync = rs_initial_sync_new(page_size,
+			attempts,
+			page_delay.count(),
+			progress.c_str());
+}
+
+InitialSync::~InitialSync()
+{
+	rs_initial_sync_free(rs_sync);
+}
+
+bool InitialSync::run(const FetchPage& fetch,
+	const StorePage& store,
+	const std::function<bool()>& should_stop,
+	const std::function<void(const std::string&)>& report,
+	const Sleep& sleep)
+{
+	// Waits in short steps, so that a long backoff doesn't hold up a stop
+	const auto wait = [&](std::chrono::milliseconds duration) {
+		const std::chrono::milliseconds step(500);
+		while (duration.count() > 0 && !should_stop()) {
+			const auto slice = std::min(duration, step);
+			sleep(slice);
+			duration -= slice;
+		}
+		return !should_stop();
+	};
+
+	bool pause_first = false;
+	std::size_t limit = 0;
+	bool has_before_id = false;
+	std::int64_t before_id = 0;
+	while (rs_initial_sync_next_request(rs_sync, &limit, &has_before_id,
+			&before_id)) {
+		if (pause_first) {
+			const std::chrono::milliseconds delay(
+				rs_initial_sync_page_delay_ms(rs_sync));
+			if (!wait(delay)) {
+				break;
+			}
+		}
+		if (should_stop()) {
+			break;
+		}
+
+		const auto page = fetch(limit,
+				has_before_id
+				? nonstd::optional<std::int64_t>(before_id)
+				: nonstd::nullopt);
+		if (!page.has_value()) {
+			const std::int64_t retry_after = rs_initial_sync_page_failed(rs_sync);
+			if (retry_after < 0) {
+				LOG(Level::ERROR,
+					"InitialSync::run: giving up after repeated failures");
+				break;
+			}
+			LOG(Level::INFO,
+				"InitialSync::run: page failed, retrying in %" PRId64 " ms",
+				retry_after);
+			if (!wait(std::chrono::milliseconds(retry_after))) {
+				break;
+			}
+			// The backoff already made up for the pause between pages
+			pause_first = false;
+			continue;
+		}
+
+		std::vector<std::int64_t> ids;
+		for (const auto& item : page.value().items) {
+			try {
+				ids.push_back(std::stoll(item.guid));
+			} catch (const std::logic_error&) {
+				LOG(Level::WARN,
+					"InitialSync::run: ignoring article with non-numeric ID `%s'",
+					item.guid);
+			}
+		}
+		const std::string progress = RustString(rs_initial_sync_page_fetched(
+					rs_sync, ids.data(), ids.size()));
+		store(page.value(), progress);
+		report(status());
+		pause_first = true;
+	}
+
+	return is_complete();
+}
+
+bool InitialSync::is_complete() const
+{
+	return rs_initial_sync_is_complete(rs_sync);
+}
+
+std::string InitialSync::initial_progress()
+{
+	const InitialSync sync(1, 1, std::chrono::milliseconds(0), "");
+	return RustString(rs_initial_sync_progress(sync.rs_sync));
+}
+
+bool InitialSync::is_complete(const std::string& progress)
+{
+	const InitialSync sync(1, 1, std::chrono::milliseconds(0), progress);
+	return sync.is_complete();
+}
+
+std::string InitialSync::status() const
+{
+	return RustString(rs_initial_sync_status(rs_sync));
+}
+
+void InitialSync::default_sleep(std::chrono::milliseconds duration)
+{
+	std::this_thread::sleep_for(duration);
+}
+
+} // namespace newsboat
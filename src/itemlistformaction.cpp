@@ -1,5 +1,6 @@
 #include <itemlistformaction.h>
 
+#include <algorithm>
 #include <cassert>
 #include <cinttypes>
 #include <cstdio>
@@ -7,21 +8,29 @@
 #include <sstream>
 #include <string>
 #include <sys/stat.h>
+#include <unordered_set>
 
 #include "config.h"
 #include "controller.h"
 #include "dbexception.h"
 #include "fmtstrformatter.h"
+#include "itemrenderer.h"
 #include "logger.h"
+#include "memoryreport.h"
 #include "matcherexception.h"
 #include "rssfeed.h"
 #include "scopemeasure.h"
 #include "strprintf.h"
+#include "termcaps.h"
 #include "utils.h"
 #include "view.h"
 
 namespace newsboat {
 
+// How long the selection has to stay put before the preview is rendered.
+// Holding a movement key shouldn't render every article along the way.
+static const unsigned int PREVIEW_DEBOUNCE_MS = 150;
+
 ItemListFormAction::ItemListFormAction(View* vv,
 	std::string formstr,
 	Cache* cc,
@@ -39,6 +48,12 @@ ItemListFormAction::ItemListFormAction(View* vv,
 	, old_itempos(-1)
 	, invalidation_mode(InvalidationMode::NONE)
 	, listfmt(&rxman, "articlelist")
+	, preview(PREVIEW_DEBOUNCE_MS,
+		  cfg->get_configvalue_as_int("article-cache-entries"),
+		  cfg->get_configvalue_as_int("article-cache-size") * 1024)
+	, render_cache(cfg->get_configvalue_as_int("article-cache-entries"),
+		  cfg->get_configvalue_as_int("article-cache-size") * 1024)
+	, old_preview_size(0, 0)
 	, rsscache(cc)
 	, filters(f)
 {
@@ -81,7 +96,11 @@ bool ItemListFormAction::process_operation(Operation op,
 	break;
 	case OP_DELETE: {
 		ScopeMeasure m1("OP_DELETE");
-		if (!visible_items.empty()) {
+		if (!feed->is_cached()) {
+			v->show_error(_("Error: articles of a previewed feed can't be deleted."));
+		} else if (selection.is_active()) {
+			delete_selection();
+		} else if (!visible_items.empty()) {
 			// mark as read
 			v->get_ctrl()->mark_article_read(
 				visible_items[itempos].first->guid(), true);
@@ -104,7 +123,9 @@ bool ItemListFormAction::process_operation(Operation op,
 	break;
 	case OP_DELETE_ALL: {
 		ScopeMeasure m1("OP_DELETE_ALL");
-		if (visible_items.size() > 0) {
+		if (!feed->is_cached()) {
+			v->show_error(_("Error: articles of a previewed feed can't be deleted."));
+		} else if (visible_items.size() > 0) {
 			if (!feed->is_search_feed()) {
 				v->get_ctrl()->mark_all_read(pos);
 			} else {
@@ -165,12 +186,14 @@ bool ItemListFormAction::process_operation(Operation op,
 		}
 	}
 	break;
-	case OP_OPENINBROWSER: {
+	case OP_OPENINBROWSER:
+	case OP_OPENINBROWSER_NONINTERACTIVELY: {
 		LOG(Level::INFO, "ItemListFormAction: opening item at pos `%u'", itempos);
 		if (!visible_items.empty()) {
 			if (itempos < visible_items.size()) {
 				const auto link = visible_items[itempos].first->link();
-				const auto exit_code = v->open_in_browser(link);
+				const auto exit_code = v->open_in_browser(link,
+						op == OP_OPENINBROWSER);
 				if (!exit_code.has_value()) {
 					v->show_error(_("Failed to spawn browser"));
 					return false;
@@ -223,7 +246,10 @@ bool ItemListFormAction::process_operation(Operation op,
 	break;
 	case OP_TOGGLEITEMREAD: {
 		LOG(Level::INFO, "ItemListFormAction: toggling item read at pos `%u'", itempos);
-		if (!visible_items.empty()) {
+		if (selection.is_active()) {
+			toggle_read_of_selection(automatic && args->size() > 0 ?
+				(*args)[0] : "");
+		} else if (!visible_items.empty()) {
 			v->set_status(_("Toggling read flag for article..."));
 			try {
 				if (automatic && args->size() > 0) {
@@ -249,10 +275,12 @@ bool ItemListFormAction::process_operation(Operation op,
 					// mark as undeleted
 					visible_items[itempos]
 					.first->set_deleted(false);
-					rsscache->mark_item_deleted(
-						visible_items[itempos]
-						.first->guid(),
-						false);
+					if (feed->is_cached()) {
+						rsscache->mark_item_deleted(
+							visible_items[itempos]
+							.first->guid(),
+							false);
+					}
 					// toggle read
 					bool unread = visible_items[itempos]
 						.first->unread();
@@ -366,7 +394,11 @@ bool ItemListFormAction::process_operation(Operation op,
 					}
 				} else {
 					std::vector<QnaPair> qna;
+					// The flags of the article under the
+					// cursor would replace those of all the
+					// selected ones
 					qna.push_back(QnaPair(_("Flags: "),
+							selection.is_active() ? "" :
 							visible_items[itempos]
 							.first->flags()));
 					this->start_qna(
@@ -379,6 +411,61 @@ bool ItemListFormAction::process_operation(Operation op,
 		}
 	}
 	break;
+	case OP_TOGGLE_SELECT:
+		if (!visible_items.empty()) {
+			selection.toggle(itempos);
+			if (itempos < visible_items.size() - 1) {
+				list.set_position(itempos + 1);
+			}
+		} else {
+			v->show_error(_("No item selected!"));
+		}
+		break;
+	case OP_SELECT_RANGE:
+		if (!visible_items.empty()) {
+			std::size_t count = 0;
+			if (selection.select_range(itempos, count)) {
+				v->set_status(strprintf::fmt(_("%u article(s) selected."),
+						static_cast<unsigned int>(count)));
+			} else {
+				v->set_status(_("Move to the other end of the range, "
+						"then run select-range again."));
+			}
+		} else {
+			v->show_error(_("No item selected!"));
+		}
+		break;
+	case OP_ENQUEUE:
+		enqueue_selection(itempos);
+		break;
+	case OP_EDITNOTE:
+	case OP_EDITNOTE_IN_EDITOR: {
+		if (visible_items.empty() || itempos >= visible_items.size()) {
+			v->show_error(_("No item selected!")); // should not happen
+			break;
+		}
+		const auto item = visible_items[itempos].first;
+		if (automatic && op == OP_EDITNOTE) {
+			if (args->size() > 0) {
+				qna_responses.clear();
+				qna_responses.push_back((*args)[0]);
+				finished_qna(OP_INT_EDITNOTE_END);
+			}
+		} else if (op == OP_EDITNOTE_IN_EDITOR
+			|| item->note().find('\n') != std::string::npos) {
+			if (v->get_ctrl()->edit_note_in_editor(item)) {
+				v->set_status(_("Note updated."));
+				invalidate(itempos);
+			} else {
+				v->show_error(_("Error: couldn't edit the note."));
+			}
+		} else {
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(_("Note: "), item->note()));
+			this->start_qna(qna, OP_INT_EDITNOTE_END);
+		}
+	}
+	break;
 	case OP_SAVE: {
 		LOG(Level::INFO, "ItemListFormAction: saving item at pos `%u'", itempos);
 		if (!visible_items.empty()) {
@@ -405,7 +492,10 @@ bool ItemListFormAction::process_operation(Operation op,
 		v->push_help();
 		break;
 	case OP_RELOAD:
-		if (!show_searchresult) {
+		if (!feed->is_cached()) {
+			v->show_error(
+				_("Error: you can't reload a previewed feed."));
+		} else if (!show_searchresult) {
 			LOG(Level::INFO,
 				"ItemListFormAction: reloading current feed");
 			v->get_ctrl()->get_reloader()->reload(pos);
@@ -417,6 +507,7 @@ bool ItemListFormAction::process_operation(Operation op,
 		break;
 	case OP_QUIT:
 		LOG(Level::INFO, "ItemListFormAction: quitting");
+		selection.clear();
 		v->feedlist_mark_pos_if_visible(pos);
 		feed->purge_deleted_items();
 		feed->unload();
@@ -424,6 +515,7 @@ bool ItemListFormAction::process_operation(Operation op,
 		break;
 	case OP_HARDQUIT:
 		LOG(Level::INFO, "ItemListFormAction: hard quitting");
+		selection.clear();
 		v->feedlist_mark_pos_if_visible(pos);
 		feed->purge_deleted_items();
 		hardquit = true;
@@ -470,6 +562,27 @@ bool ItemListFormAction::process_operation(Operation op,
 			}
 		}
 		break;
+	case OP_FIRST_UNREAD:
+	case OP_LAST_UNREAD: {
+		const bool found = (op == OP_FIRST_UNREAD)
+			? jump_to_first_unread_item()
+			: jump_to_last_unread_item();
+		if (!found) {
+			v->show_error(_("No unread items."));
+			return false;
+		}
+		show_position();
+	}
+	break;
+	case OP_GOTO_PERCENT:
+		if (automatic) {
+			return goto_percent(args ? *args : std::vector<std::string>());
+		} else {
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(_("Go to percent: "), ""));
+			this->start_qna(qna, OP_INT_GOTO_PERCENT);
+		}
+		break;
 	case OP_NEXTUNREADFEED:
 		if (!v->get_next_unread_feed(*this)) {
 			v->show_error(_("No unread feeds."));
@@ -480,6 +593,15 @@ bool ItemListFormAction::process_operation(Operation op,
 			v->show_error(_("No unread feeds."));
 		}
 		break;
+	case OP_NEXTFEEDWITHUNREAD:
+	case OP_PREVFEEDWITHUNREAD: {
+		const auto error = v->open_feed_with_unread(
+				op == OP_NEXTFEEDWITHUNREAD, this);
+		if (error.has_value()) {
+			v->show_error(error.value());
+		}
+	}
+	break;
 	case OP_NEXTFEED:
 		if (!v->get_next_feed(*this)) {
 			v->show_error(_("Already on last feed."));
@@ -494,7 +616,9 @@ bool ItemListFormAction::process_operation(Operation op,
 		LOG(Level::INFO, "ItemListFormAction: marking feed read");
 		v->set_status(_("Marking feed read..."));
 		try {
-			if (feed->rssurl() != "") {
+			if (!feed->is_cached()) {
+				feed->mark_all_items_read();
+			} else if (feed->rssurl() != "") {
 				v->get_ctrl()->mark_all_read(pos);
 			} else {
 				{
@@ -565,6 +689,23 @@ bool ItemListFormAction::process_operation(Operation op,
 		}
 		v->set_status("");
 		break;
+	case OP_MARKABOVEREAD:
+		mark_beside_read(itempos, false);
+		break;
+	case OP_MARKBELOWREAD:
+		mark_beside_read(itempos, true);
+		break;
+	case OP_UNDOMARKREAD:
+		return undo_mark_read();
+	case OP_TOGGLE_PREVIEW:
+		LOG(Level::DEBUG, "ItemListFormAction: toggling preview-pane");
+		if (cfg->get_configvalue_as_bool("preview-pane")) {
+			cfg->set_configvalue("preview-pane", "no");
+		} else {
+			cfg->set_configvalue("preview-pane", "yes");
+		}
+		preview.reset();
+		break;
 	case OP_TOGGLESHOWREAD:
 		LOG(Level::DEBUG,
 			"ItemListFormAction: toggling show-read-articles");
@@ -582,7 +723,8 @@ bool ItemListFormAction::process_operation(Operation op,
 			if (automatic) {
 				if (args->size() > 0) {
 					qna_responses.clear();
-					qna_responses.push_back((*args)[0]);
+					// `--raw-html` is a separate argument
+					qna_responses.push_back(utils::join(*args, " "));
 					finished_qna(OP_PIPE_TO);
 				}
 			} else {
@@ -613,6 +755,20 @@ bool ItemListFormAction::process_operation(Operation op,
 	case OP_EDIT_URLS:
 		v->get_ctrl()->edit_urls_file();
 		break;
+	case OP_SUBSCRIBE:
+		if (feed->is_cached()) {
+			v->show_error(_("Error: only previewed feeds can be subscribed to."));
+		} else {
+			std::string error;
+			if (v->get_ctrl()->subscribe(feed->rssurl(), error)) {
+				v->set_status(strprintf::fmt(
+						_("Subscribed to %s. Reload it to fetch its articles."),
+						feed->rssurl()));
+			} else {
+				v->show_error(error);
+			}
+		}
+		break;
 	case OP_SELECTFILTER:
 		if (filters.size() > 0) {
 			std::string newfilter;
@@ -664,6 +820,17 @@ bool ItemListFormAction::process_operation(Operation op,
 				qna, OP_INT_END_SETFILTER, &filterhistory);
 		}
 		break;
+	case OP_BUILDFILTER: {
+		const std::string expression = v->build_filter("article");
+		if (!expression.empty()) {
+			// The builder renders the expression the way it'd be
+			// typed, so it goes through the same path as `set-filter`
+			qna_responses.clear();
+			qna_responses.push_back(expression);
+			qna_end_setfilter();
+		}
+	}
+	break;
 	case OP_CLEARFILTER:
 		apply_filter = false;
 		invalidate_everything();
@@ -780,22 +947,34 @@ void ItemListFormAction::finished_qna(Operation op)
 		qna_end_editflags();
 		break;
 
+	case OP_INT_EDITNOTE_END:
+		qna_end_editnote();
+		break;
+
 	case OP_INT_START_SEARCH:
 		qna_start_search();
 		break;
 
+	case OP_INT_GOTO_PERCENT:
+		if (!qna_responses[0].empty()) {
+			goto_percent(qna_responses);
+		}
+		break;
+
 	case OP_PIPE_TO: {
 		if (!visible_items.empty()) {
 			unsigned int itempos = list.get_position();
-			std::string cmd = qna_responses[0];
-			std::ostringstream ostr;
-			v->get_ctrl()->write_item(
-				visible_items[itempos].first, ostr);
+			bool raw_html = false;
+			const std::string cmd =
+				item_renderer::split_pipe_command(qna_responses[0], raw_html);
+			const auto item = visible_items[itempos].first;
+			const std::string data = raw_html
+				? item_renderer::to_piped_html(item)
+				: item_renderer::to_piped_text(*cfg, item);
 			v->push_empty_formaction();
 			Stfl::reset();
 			FILE* f = popen(cmd.c_str(), "w");
 			if (f) {
-				std::string data = ostr.str();
 				fwrite(data.c_str(), data.length(), 1, f);
 				pclose(f);
 			}
@@ -834,6 +1013,17 @@ void ItemListFormAction::qna_end_editflags()
 		return;
 	}
 
+	if (selection.is_active()) {
+		const auto items = selected_items();
+		for (const auto& item : items) {
+			item->set_flags(qna_responses[0]);
+		}
+		store_selection(items, false, true,
+			strprintf::fmt(_("Flags of %u article(s) updated."),
+				static_cast<unsigned int>(items.size())));
+		return;
+	}
+
 	const unsigned int itempos = list.get_position();
 	if (itempos < visible_items.size()) {
 		visible_items[itempos].first->set_flags(qna_responses[0]);
@@ -845,6 +1035,22 @@ void ItemListFormAction::qna_end_editflags()
 	}
 }
 
+void ItemListFormAction::qna_end_editnote()
+{
+	if (visible_items.empty()) {
+		v->show_error(_("No item selected!")); // should not happen
+		return;
+	}
+
+	const unsigned int itempos = list.get_position();
+	if (itempos < visible_items.size()) {
+		visible_items[itempos].first->set_note(qna_responses[0]);
+		v->get_ctrl()->update_note(visible_items[itempos].first);
+		v->set_status(_("Note updated."));
+		invalidate(itempos);
+	}
+}
+
 void ItemListFormAction::qna_start_search()
 {
 	const std::string searchphrase = qna_responses[0];
@@ -902,11 +1108,14 @@ void ItemListFormAction::do_update_visible_items()
 
 	bool show_read = cfg->get_configvalue_as_bool("show-read-articles");
 
+	filter_counts.clear();
+
 	unsigned int i = 0;
 	for (const auto& item : items) {
 		item->set_index(i + 1);
-		if ((show_read || item->unread()) &&
-			(!apply_filter || matcher.matches(item.get()))) {
+		const bool matches = !apply_filter || matcher.matches(item.get());
+		filter_counts.update(item->guid(), matches, item->unread());
+		if ((show_read || item->unread()) && matches) {
 			new_visible_items.push_back(ItemPtrPosPair(item, i));
 		}
 		i++;
@@ -917,6 +1126,36 @@ void ItemListFormAction::do_update_visible_items()
 		static_cast<uint64_t>(visible_items.size()));
 
 	visible_items = new_visible_items;
+
+	std::vector<std::string> guids;
+	std::vector<bool> unread;
+	guids.reserve(visible_items.size());
+	unread.reserve(visible_items.size());
+	for (const auto& item : visible_items) {
+		guids.push_back(item.first->guid());
+		unread.push_back(item.first->unread());
+	}
+	selection.set_order(guids);
+	unread_index.reset(guids, unread);
+}
+
+void ItemListFormAction::update_filter_counts(
+	const std::shared_ptr<RssItem>& item)
+{
+	// The article's state changed, which can also change whether the filter
+	// matches it
+	bool matches = true;
+	if (apply_filter) {
+		try {
+			matches = matcher.matches(item.get());
+		} catch (const MatcherException& e) {
+			LOG(Level::ERROR,
+				"ItemListFormAction::update_filter_counts: %s",
+				e.what());
+			matches = false;
+		}
+	}
+	filter_counts.update(item->guid(), matches, item->unread());
 }
 
 void ItemListFormAction::prepare()
@@ -951,6 +1190,8 @@ void ItemListFormAction::prepare()
 		}
 	}
 
+	update_preview();
+
 	const unsigned int width = list.get_width();
 
 	if (old_width != width) {
@@ -958,6 +1199,12 @@ void ItemListFormAction::prepare()
 		old_width = width;
 	}
 
+	// A range that follows the cursor changes the markers of many articles
+	const unsigned int cursor = list.get_position();
+	if (selection.needs_redraw(cursor)) {
+		invalidate_everything();
+	}
+
 	if (invalidation_mode == InvalidationMode::NONE) {
 		return;
 	}
@@ -965,28 +1212,38 @@ void ItemListFormAction::prepare()
 	auto datetime_format = cfg->get_configvalue("datetime-format");
 	auto itemlist_format =
 		cfg->get_configvalue("articlelist-format");
+	const unsigned int max_rows = cfg->get_configvalue_as_bool(
+			"articlelist-wrap-titles")
+		? std::max(1, cfg->get_configvalue_as_int("max-title-rows"))
+		: 1;
 
 	switch (invalidation_mode) {
 	case InvalidationMode::COMPLETE:
 		listfmt.clear();
 
-		for (const auto& item : visible_items) {
-			auto line = item2formatted_line(item,
+		for (unsigned int i = 0; i < visible_items.size(); ++i) {
+			auto lines = item2formatted_lines(visible_items[i],
 					width,
 					itemlist_format,
-					datetime_format);
-			listfmt.add_line(line);
+					datetime_format,
+					max_rows,
+					selection.marker(i, cursor));
+			listfmt.add_lines(lines);
 		}
 		break;
 
 	case InvalidationMode::PARTIAL:
 		for (const auto& itempos : invalidated_itempos) {
 			auto item = visible_items[itempos];
-			auto line = item2formatted_line(item,
+			update_filter_counts(item.first);
+			unread_index.update(item.first->guid(), item.first->unread());
+			auto lines = item2formatted_lines(item,
 					width,
 					itemlist_format,
-					datetime_format);
-			listfmt.set_line(itempos, line);
+					datetime_format,
+					max_rows,
+					selection.marker(itempos, cursor));
+			listfmt.set_lines(itempos, lines);
 		}
 		break;
 	case InvalidationMode::NONE:
@@ -1006,56 +1263,87 @@ void ItemListFormAction::prepare()
 	prepare_set_filterpos();
 }
 
-std::string ItemListFormAction::item2formatted_line(const ItemPtrPosPair& item,
+std::vector<std::string> ItemListFormAction::item2formatted_lines(
+	const ItemPtrPosPair& item,
 	const unsigned int width,
 	const std::string& itemlist_format,
-	const std::string& datetime_format)
+	const std::string& datetime_format,
+	unsigned int max_rows,
+	const std::string& marker)
 {
 	FmtStrFormatter fmt;
+	fmt.set_truncation_marker(cfg->get_configvalue("truncation-marker"));
+
+	// Bidi controls have to be stripped while the text is still in UTF-8,
+	// i.e. before it's converted to the locale's charset
+	const bool strip_bidi = cfg->get_configvalue("strip-bidi-controls") == "all";
+	const auto feed_text = [&](const std::string& text) {
+		return strip_bidi ? utils::strip_bidi_controls(text) : text;
+	};
+
 	fmt.register_fmt('i', strprintf::fmt("%u", item.second + 1));
 	fmt.register_fmt('f', gen_flags(item.first));
 	fmt.register_fmt('n', item.first->unread() ? "N" : " ");
 	fmt.register_fmt('d', item.first->deleted() ? "D" : " ");
+	fmt.register_fmt('u', item.first->updated() ? "U" : " ");
 	fmt.register_fmt('F', item.first->flags());
+	fmt.register_fmt('N', item.first->note().empty() ? " " : "@");
 	fmt.register_fmt('D',
 		utils::mt_strf_localtime(
 			datetime_format,
 			item.first->pubDate_timestamp()));
 	if (feed->rssurl() != item.first->feedurl() &&
 		item.first->get_feedptr() != nullptr) {
-		auto feedtitle = item.first->get_feedptr()->title();
+		auto feedtitle = feed_text(item.first->get_feedptr()->title());
 		utils::remove_soft_hyphens(feedtitle);
 		fmt.register_fmt('T', feedtitle);
 	}
 
-	auto itemtitle = utils::utf8_to_locale(item.first->title());
+	auto itemtitle = utils::utf8_to_locale(feed_text(item.first->title()));
 	utils::remove_soft_hyphens(itemtitle);
 	fmt.register_fmt('t', itemtitle);
 
-	auto itemauthor = utils::utf8_to_locale(item.first->author());
+	auto itemauthor = utils::utf8_to_locale(feed_text(item.first->author()));
 	utils::remove_soft_hyphens(itemauthor);
 	fmt.register_fmt('a', itemauthor);
 
 	fmt.register_fmt('L', item.first->length());
-
-	auto formattedLine = fmt.do_format(itemlist_format, width);
-	formattedLine = utils::quote_for_stfl(formattedLine);
+	fmt.register_fmt('w', std::to_string(item.first->word_count()));
+	fmt.register_fmt('m', std::to_string(item.first->reading_minutes()));
 
 	const int id = rxman.article_matches(item.first.get());
-	if (id != -1) {
-		formattedLine = strprintf::fmt("<%d>%s</>", id, formattedLine);
-	}
 
-	if (item.first->unread()) {
-		formattedLine = strprintf::fmt("<unread>%s</>", formattedLine);
+	// Continuation rows of the title get the same highlighting as the first
+	// row, so that they read as a part of it
+	const unsigned int marker_width = marker.length();
+	auto formattedLines = fmt.do_format_wrapped(itemlist_format,
+			width > marker_width ? width - marker_width : 0,
+			't',
+			max_rows);
+	for (std::size_t row = 0; row < formattedLines.size(); ++row) {
+		auto& formattedLine = formattedLines[row];
+		if (!marker.empty()) {
+			formattedLine = (row == 0 ? marker :
+					std::string(marker_width, ' ')) + formattedLine;
+		}
+		formattedLine = utils::quote_for_stfl(formattedLine);
+
+		if (id != -1) {
+			formattedLine = strprintf::fmt("<%d>%s</>", id, formattedLine);
+		}
+
+		if (item.first->unread()) {
+			formattedLine = strprintf::fmt("<unread>%s</>", formattedLine);
+		}
 	}
 
-	return formattedLine;
+	return formattedLines;
 }
 
 void ItemListFormAction::init()
 {
 	list.set_position(0);
+	selection.clear();
 	f.set("msg", "");
 	set_keymap_hints();
 	invalidate_everything();
@@ -1080,6 +1368,7 @@ void ItemListFormAction::set_head(const std::string& s,
 
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 
 	fmt.register_fmt('u', std::to_string(unread));
 	fmt.register_fmt('t', std::to_string(total));
@@ -1092,14 +1381,21 @@ void ItemListFormAction::set_head(const std::string& s,
 
 	fmt.register_fmt('F', apply_filter ? matcher.get_expression() : "");
 
+	fmt.register_fmt('m', std::to_string(filter_counts.total()));
+	fmt.register_fmt('r', std::to_string(filter_counts.unread()));
+
 	const unsigned int width = utils::to_u(f.get("title:w"));
 	if (!show_searchresult) {
-		title = fmt.do_format(
-				cfg->get_configvalue("articlelist-title-format"),
+		title = fmt.do_format(termcaps::title_format(
+					"articlelist-title-format",
+					cfg->get_configvalue("articlelist-title-format"),
+					width),
 				width);
 	} else {
-		title = fmt.do_format(
-				cfg->get_configvalue("searchresult-title-format"),
+		title = fmt.do_format(termcaps::title_format(
+					"searchresult-title-format",
+					cfg->get_configvalue("searchresult-title-format"),
+					width),
 				width);
 	}
 	f.set("head", title);
@@ -1177,6 +1473,67 @@ bool ItemListFormAction::jump_to_next_unread_item(bool start_with_first)
 	return false;
 }
 
+bool ItemListFormAction::jump_to_first_unread_item()
+{
+	return jump_to_unread_end(true);
+}
+
+bool ItemListFormAction::jump_to_last_unread_item()
+{
+	return jump_to_unread_end(false);
+}
+
+bool ItemListFormAction::jump_to_unread_end(bool first)
+{
+	// Articles whose state changed since the list was last drawn
+	for (const auto& itempos : invalidated_itempos) {
+		if (itempos < visible_items.size()) {
+			const auto& item = visible_items[itempos].first;
+			unread_index.update(item->guid(), item->unread());
+		}
+	}
+
+	while (true) {
+		const auto pos = first ? unread_index.first() : unread_index.last();
+		if (!pos.has_value() || *pos >= visible_items.size()) {
+			return false;
+		}
+		const auto& item = visible_items[*pos].first;
+		if (item->unread()) {
+			list.set_position(*pos);
+			return true;
+		}
+		// Read in some way that didn't redraw it, e.g. in the article view
+		unread_index.update(item->guid(), false);
+	}
+}
+
+bool ItemListFormAction::goto_percent(
+	const std::vector<std::string>& arguments)
+{
+	std::string error;
+	const auto percent = parse_percent(arguments, error);
+	if (!percent.has_value()) {
+		v->show_error(error);
+		return false;
+	}
+	const auto pos = percent_position(*percent, visible_items.size());
+	if (!pos.has_value()) {
+		v->show_error(_("No item selected!"));
+		return false;
+	}
+	list.set_position(*pos);
+	show_position();
+	return true;
+}
+
+void ItemListFormAction::show_position()
+{
+	v->set_status(strprintf::fmt(_("item %u/%u"),
+			list.get_position() + 1,
+			static_cast<unsigned int>(visible_items.size())));
+}
+
 bool ItemListFormAction::jump_to_previous_item(bool start_with_last)
 {
 	const unsigned int itempos = list.get_position();
@@ -1254,10 +1611,11 @@ void ItemListFormAction::handle_cmdline(const std::string& cmd)
 	if (1 == sscanf(cmd.c_str(), "%u", &idx)) {
 		handle_cmdline_num(idx);
 	} else {
-		std::vector<std::string> tokens = utils::tokenize_quoted(cmd);
-		if (tokens.empty()) {
+		const auto parsed = tokenize_cmdline(cmd);
+		if (!parsed.has_value() || parsed->empty()) {
 			return;
 		}
+		const std::vector<std::string>& tokens = parsed.value();
 		if (tokens[0] == "save" && tokens.size() >= 2) {
 			std::string filename = utils::resolve_tilde(tokens[1]);
 			const unsigned int itempos = list.get_position();
@@ -1336,8 +1694,9 @@ void ItemListFormAction::register_format_styles()
 	const std::string attrstr = rxman.get_attrs_stfl_string("articlelist", true);
 	const std::string textview = strprintf::fmt(
 			"{list[items] .expand:vh style_normal[listnormal]: "
-			"style_focus[listfocus]:fg=yellow,bg=blue,attr=bold "
+			"style_focus[listfocus]:%s "
 			"pos[items_pos]:0 offset[items_offset]:0 %s richtext:1}",
+			termcaps::degrade_style("fg=yellow,bg=blue,attr=bold"),
 			attrstr);
 	list.stfl_replace_list(0, textview);
 }
@@ -1360,6 +1719,86 @@ std::string ItemListFormAction::gen_flags(std::shared_ptr<RssItem> item)
 	return flags;
 }
 
+void ItemListFormAction::update_preview()
+{
+	const bool beside = (cfg->get_configvalue("preview-pane-position") == "right");
+	// TODO: change to std::clamp in C++17
+	const int percent = std::max(10, std::min(90,
+				cfg->get_configvalue_as_int("preview-pane-size")));
+
+	// The form's dimensions are only known after it was drawn for the first
+	// time; until then, keep the request pending. The dimensions are those
+	// of the whole area, so they stay the same whether the pane is shown or
+	// not.
+	const unsigned int total_width = utils::to_u(f.get("panes:w"));
+	const unsigned int total_height = utils::to_u(f.get("panes:h"));
+	const bool known_size = total_width != 0 && total_height != 0;
+
+	// On small terminals, the list is more important than the preview
+	const bool enabled = cfg->get_configvalue_as_bool("preview-pane")
+		&& (!known_size
+			|| termcaps::preview_pane_fits(total_width, total_height, percent,
+				beside));
+	f.set("show_preview_right", (enabled && beside) ? "1" : "0");
+	f.set("show_preview_below", (enabled && !beside) ? "1" : "0");
+	if (!enabled || !known_size) {
+		return;
+	}
+	unsigned int width = total_width;
+	unsigned int height = total_height;
+	if (beside) {
+		width = total_width * percent / 100;
+		f.set("preview_width", std::to_string(width));
+	} else {
+		height = total_height * percent / 100;
+		f.set("preview_height", std::to_string(height));
+	}
+
+	const auto size = std::make_pair(width, height);
+	if (size != old_preview_size) {
+		old_preview_size = size;
+		preview.reset();
+	}
+
+	const std::string pane = beside ? "preview_right" : "preview_below";
+	if (visible_items.empty()) {
+		f.modify(pane, "replace_inner", "{list}");
+		preview.reset();
+		return;
+	}
+
+	const auto item = visible_items[list.get_position()].first;
+	preview.request(item->guid());
+	const auto ready = preview.poll();
+	if (!ready || *ready != item->guid()) {
+		return;
+	}
+
+	LOG(Level::DEBUG, "ItemListFormAction::update_preview: rendering %s",
+		item->guid());
+	const auto render = [&]() {
+		return item_renderer::to_preview_text(*cfg, item);
+	};
+	// The limits can be changed with `set` at any time
+	preview.set_cache_limits(cfg->get_configvalue_as_int("article-cache-entries"),
+		cfg->get_configvalue_as_int("article-cache-size") * 1024);
+	ListFormatter previewfmt;
+	for (const auto& line : preview.lines(item->guid(), width, height, render)) {
+		previewfmt.add_line(line);
+	}
+	f.modify(pane, "replace_inner", previewfmt.format_list());
+}
+
+int ItemListFormAction::get_event_timeout()
+{
+	const int time_left = preview.time_left_ms();
+	if (time_left >= 0) {
+		// Wake up right after the preview becomes ready
+		return time_left + 1;
+	}
+	return FormAction::get_event_timeout();
+}
+
 void ItemListFormAction::prepare_set_filterpos()
 {
 	if (set_filterpos) {
@@ -1384,6 +1823,10 @@ void ItemListFormAction::set_feed(std::shared_ptr<RssFeed> fd)
 		fd->title());
 	feed = fd;
 	feed->load();
+	mark_read_undo.clear();
+	preview.clear_cache();
+	preview.reset();
+	render_cache.clear();
 	invalidate_everything();
 	do_update_visible_items();
 }
@@ -1406,6 +1849,219 @@ std::string ItemListFormAction::title()
 	}
 }
 
+void ItemListFormAction::add_to_memory_report(MemoryReport& report)
+{
+	report.add_dialog(title());
+	for (const auto& item : visible_items) {
+		report.add_item(item.first);
+	}
+}
+
+std::vector<std::shared_ptr<RssItem>> ItemListFormAction::selected_items()
+{
+	std::vector<std::shared_ptr<RssItem>> items;
+	for (const auto position : selection.selected_positions(list.get_position())) {
+		if (position < visible_items.size()) {
+			items.push_back(visible_items[position].first);
+		}
+	}
+	return items;
+}
+
+bool ItemListFormAction::store_item_states(
+	const std::vector<std::shared_ptr<RssItem>>& items,
+	bool read_changed,
+	bool flags_changed,
+	const std::string& message)
+{
+	bool stored = true;
+	try {
+		if (feed->is_cached()) {
+			rsscache->update_rssitem_states(items);
+		}
+		v->get_ctrl()->sync_article_states(
+			items, read_changed, flags_changed);
+		v->set_status(message);
+	} catch (const DbException& e) {
+		v->show_error(strprintf::fmt(
+				_("Error while updating the selected articles: %s"),
+				e.what()));
+		stored = false;
+	}
+	invalidate_everything();
+	return stored;
+}
+
+void ItemListFormAction::store_selection(
+	const std::vector<std::shared_ptr<RssItem>>& items,
+	bool read_changed,
+	bool flags_changed,
+	const std::string& message)
+{
+	store_item_states(items, read_changed, flags_changed, message);
+	selection.clear();
+}
+
+void ItemListFormAction::mark_beside_read(unsigned int itempos, bool below)
+{
+	if (itempos >= visible_items.size()) {
+		return;
+	}
+
+	// Articles whose state changed since the list was last drawn
+	for (const auto& pos : invalidated_itempos) {
+		if (pos < visible_items.size()) {
+			const auto& item = visible_items[pos].first;
+			unread_index.update(item->guid(), item->unread());
+		}
+	}
+
+	std::vector<std::shared_ptr<RssItem>> items;
+	std::vector<std::string> guids;
+	for (const auto pos : unread_index.unread_beside(itempos, below)) {
+		if (pos >= visible_items.size()) {
+			continue;
+		}
+		const auto& item = visible_items[pos].first;
+		unread_index.update(item->guid(), false);
+		// Read in some way that didn't redraw it, e.g. in the article view
+		if (!item->unread()) {
+			continue;
+		}
+		item->set_unread_nowrite_notify(false, true);
+		items.push_back(item);
+		guids.push_back(item->guid());
+	}
+
+	const bool stored = store_item_states(items, true, false,
+			strprintf::fmt(_("Marked %u article(s) read."),
+				static_cast<unsigned int>(items.size())));
+	if (stored) {
+		mark_read_undo.record(guids);
+	}
+
+	// Keeps the cursor on the same article if the ones that were marked
+	// disappear from the list
+	save_filterpos();
+}
+
+bool ItemListFormAction::undo_mark_read()
+{
+	const auto guids = mark_read_undo.undo();
+	if (!guids.has_value()) {
+		v->show_error(_("Nothing to undo."));
+		return false;
+	}
+
+	const std::unordered_set<std::string> marked(guids->begin(),
+		guids->end());
+	std::vector<std::shared_ptr<RssItem>> items;
+	{
+		std::lock_guard<std::mutex> lock(feed->item_mutex);
+		for (const auto& item : feed->items()) {
+			if (marked.count(item->guid()) != 0 && !item->unread()) {
+				item->set_unread_nowrite_notify(true, true);
+				unread_index.update(item->guid(), true);
+				items.push_back(item);
+			}
+		}
+	}
+
+	store_item_states(items, true, false,
+		strprintf::fmt(_("Marked %u article(s) unread."),
+			static_cast<unsigned int>(items.size())));
+	save_filterpos();
+	return true;
+}
+
+void ItemListFormAction::toggle_read_of_selection(const std::string& state)
+{
+	const auto items = selected_items();
+
+	// Like for a single article, "read" and "unread" set the state, and
+	// otherwise it's toggled: the selection becomes read unless all of it
+	// is read already
+	bool read = true;
+	if (state == "unread") {
+		read = false;
+	} else if (state != "read") {
+		read = std::any_of(items.begin(), items.end(),
+		[](const std::shared_ptr<RssItem>& item) {
+			return item->unread();
+		});
+		for (const auto& item : items) {
+			item->set_deleted(false);
+		}
+	}
+	for (const auto& item : items) {
+		item->set_unread_nowrite_notify(!read, true);
+	}
+
+	store_selection(items, true, false,
+		strprintf::fmt(read ? _("Marked %u article(s) read.") :
+			_("Marked %u article(s) unread."),
+			static_cast<unsigned int>(items.size())));
+}
+
+void ItemListFormAction::delete_selection()
+{
+	const auto items = selected_items();
+
+	// Deletes the selection unless all of it is deleted already, in which
+	// case it's restored
+	const bool deleted = std::any_of(items.begin(), items.end(),
+	[](const std::shared_ptr<RssItem>& item) {
+		return !item->deleted();
+	});
+	for (const auto& item : items) {
+		item->set_deleted(deleted);
+		if (deleted) {
+			item->set_unread_nowrite_notify(false, true);
+		}
+	}
+
+	store_selection(items, deleted, false,
+		strprintf::fmt(deleted ? _("Deleted %u article(s).") :
+			_("Restored %u article(s)."),
+			static_cast<unsigned int>(items.size())));
+}
+
+void ItemListFormAction::enqueue_selection(unsigned int itempos)
+{
+	std::vector<std::shared_ptr<RssItem>> items;
+	if (selection.is_active()) {
+		items = selected_items();
+	} else if (itempos < visible_items.size()) {
+		items.push_back(visible_items[itempos].first);
+	}
+	if (items.empty()) {
+		v->show_error(_("No item selected!"));
+		return;
+	}
+
+	unsigned int enqueued = 0;
+	for (const auto& item : items) {
+		if (item->enclosure_url().length() > 0 &&
+			utils::is_http_url(item->enclosure_url())) {
+			v->get_ctrl()->enqueue_url(item, feed);
+			enqueued++;
+		}
+	}
+
+	if (!selection.is_active()) {
+		const auto& url = items[0]->enclosure_url();
+		v->set_status(enqueued > 0 ?
+			strprintf::fmt(_("Added %s to download queue."), url) :
+			strprintf::fmt(_("Invalid URL: '%s'"), url));
+		return;
+	}
+	v->set_status(strprintf::fmt(
+			_("Added %u of %u article(s) to the download queue."),
+			enqueued,
+			static_cast<unsigned int>(items.size())));
+	selection.clear();
+}
+
 void ItemListFormAction::handle_op_saveall()
 {
 	LOG(Level::INFO,
@@ -5,8 +5,19 @@
 #include "configcontainer.h"
 #include "htmlrenderer.h"
 #include "rssfeed.h"
+#include "ruststring.h"
 #include "textformatter.h"
 
+extern "C" {
+	char* rs_piped_article_plain_text(const char* title, const char* author,
+		const char* date, const char* link, const char* text);
+
+	char* rs_piped_article_raw_html(const char* title, const char* author,
+		const char* date, const char* link, const char* html);
+
+	char* rs_piped_article_parse_command(const char* input, bool* raw_html);
+}
+
 namespace newsboat {
 
 std::string item_renderer::get_feedtitle(std::shared_ptr<RssItem> item)
@@ -65,6 +76,9 @@ void prepare_header(
 	add_line(item->pubDate(), _("Date: "));
 	add_line(item->link(), _("Link: "), LineType::softwrappable);
 	add_line(item->flags(), _("Flags: "));
+	add_line(stfl_quote_if_needed(utils::replace_all(
+				utils::utf8_to_locale(item->note()), "\n", " ")),
+		_("Note: "));
 
 	if (!item->enclosure_url().empty()) {
 		auto dlurl = strprintf::fmt(
@@ -102,7 +116,8 @@ void render_html(
 {
 	const std::string renderer = cfg.get_configvalue("html-renderer");
 	if (renderer == "internal") {
-		HtmlRenderer rnd(raw);
+		HtmlRenderer rnd(raw,
+			cfg.get_configvalue_as_int("html-max-nesting"));
 		rnd.render(source, lines, thelinks, url);
 	} else {
 		const char* argv[4];
@@ -130,6 +145,21 @@ void render_html(
 	}
 }
 
+std::string format_plain(ConfigContainer& cfg,
+	const std::vector<std::pair<LineType, std::string>>& lines)
+{
+	TextFormatter txtfmt;
+	txtfmt.set_hyphenation(cfg.get_configvalue_as_bool("hyphenation"));
+	txtfmt.add_lines(lines);
+
+	unsigned int width = cfg.get_configvalue_as_int("text-width");
+	if (width == 0) {
+		width = 80;
+	}
+
+	return txtfmt.format_text_plain(width);
+}
+
 std::string item_renderer::to_plain_text(
 	ConfigContainer& cfg,
 	std::shared_ptr<RssItem> item)
@@ -142,15 +172,64 @@ std::string item_renderer::to_plain_text(
 	render_html(cfg, utils::utf8_to_locale(item->description()), lines, links,
 		base, true);
 
-	TextFormatter txtfmt;
-	txtfmt.add_lines(lines);
+	return format_plain(cfg, lines);
+}
 
-	unsigned int width = cfg.get_configvalue_as_int("text-width");
-	if (width == 0) {
-		width = 80;
-	}
+std::string item_renderer::to_piped_text(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item)
+{
+	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
 
-	return txtfmt.format_text_plain(width);
+	const auto base = get_item_base_link(item);
+	render_html(cfg, utils::utf8_to_locale(item->description()), lines, links,
+		base, true);
+
+	const auto text = utils::locale_to_utf8(format_plain(cfg, lines));
+	return RustString(rs_piped_article_plain_text(item->title().c_str(),
+				item->author().c_str(),
+				utils::locale_to_utf8(item->pubDate()).c_str(),
+				item->link().c_str(),
+				text.c_str()));
+}
+
+std::string item_renderer::to_piped_html(std::shared_ptr<RssItem> item)
+{
+	return RustString(rs_piped_article_raw_html(item->title().c_str(),
+				item->author().c_str(),
+				utils::locale_to_utf8(item->pubDate()).c_str(),
+				item->link().c_str(),
+				item->description().c_str()));
+}
+
+std::string item_renderer::split_pipe_command(const std::string& input,
+	bool& raw_html)
+{
+	return RustString(rs_piped_article_parse_command(input.c_str(), &raw_html));
+}
+
+std::string item_renderer::to_preview_text(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item)
+{
+	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
+
+	prepare_header(item, lines, links, true);
+	const auto base = get_item_base_link(item);
+	render_html(cfg, utils::utf8_to_locale(item->description()), lines, links,
+		base, true);
+
+	std::string result;
+	for (const auto& line : lines) {
+		if (line.first == LineType::hr) {
+			continue;
+		}
+		result.append(line.second);
+		result.push_back('\n');
+	}
+	return result;
 }
 
 std::pair<std::string, size_t> item_renderer::to_stfl_list(
@@ -161,15 +240,50 @@ std::pair<std::string, size_t> item_renderer::to_stfl_list(
 	RegexManager* rxman,
 	const std::string& location,
 	std::vector<LinkPair>& links)
+{
+	std::vector<std::pair<LineType, std::string>> body;
+	const std::string baseurl = get_item_base_link(item);
+	const auto source = utils::utf8_to_locale(item->description());
+	render_html(cfg, source, body, links, baseurl, false);
+
+	return rendered_to_stfl_list(cfg, item, body, text_width, window_width,
+			rxman, location);
+}
+
+std::unique_ptr<RenderJob> item_renderer::start_rendering(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item,
+	std::vector<LinkPair> links,
+	unsigned int screen_height)
+{
+	if (cfg.get_configvalue("html-renderer") != "internal") {
+		return nullptr;
+	}
+	return std::unique_ptr<RenderJob>(new RenderJob(
+				utils::utf8_to_locale(item->description()),
+				get_item_base_link(item),
+				std::move(links),
+				screen_height,
+				cfg.get_configvalue_as_int("html-max-nesting")));
+}
+
+std::pair<std::string, size_t> item_renderer::rendered_to_stfl_list(
+	ConfigContainer& cfg,
+	std::shared_ptr<RssItem> item,
+	const std::vector<std::pair<LineType, std::string>>& body,
+	unsigned int text_width,
+	unsigned int window_width,
+	RegexManager* rxman,
+	const std::string& location)
 {
 	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
 
 	prepare_header(item, lines, links);
-	const std::string baseurl = get_item_base_link(item);
-	const auto body = utils::utf8_to_locale(item->description());
-	render_html(cfg, body, lines, links, baseurl, false);
+	lines.insert(lines.end(), body.begin(), body.end());
 
 	TextFormatter txtfmt;
+	txtfmt.set_hyphenation(cfg.get_configvalue_as_bool("hyphenation"));
 	txtfmt.add_lines(lines);
 
 	return txtfmt.format_text_to_list(rxman, location, text_width, window_width);
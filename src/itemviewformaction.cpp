@@ -8,12 +8,15 @@
 #include "confighandlerexception.h"
 #include "dbexception.h"
 #include "fmtstrformatter.h"
+#include "itemlistformaction.h"
 #include "itemrenderer.h"
 #include "htmlrenderer.h"
 #include "logger.h"
+#include "memoryreport.h"
 #include "rssfeed.h"
 #include "scopemeasure.h"
 #include "strprintf.h"
+#include "termcaps.h"
 #include "textformatter.h"
 #include "utils.h"
 #include "view.h"
@@ -48,6 +51,8 @@ void ItemViewFormAction::init()
 	f.set("msg", "");
 	do_redraw = true;
 	quit = false;
+	// Another article is about to be shown
+	cancel_rendering();
 	links.clear();
 	num_lines = 0;
 	if (!cfg->get_configvalue_as_bool("display-article-progress")) {
@@ -64,6 +69,17 @@ void ItemViewFormAction::init()
 	}
 	set_keymap_hints();
 	item = feed->get_item_by_guid(guid);
+
+	const std::string mark_read_on = cfg->get_configvalue("mark-read-on");
+	if (!mark_read_policy.set(mark_read_on)) {
+		// `set` doesn't validate values, so this one might be invalid
+		LOG(Level::WARN,
+			"ItemViewFormAction::init: ignoring invalid mark-read-on `%s'",
+			mark_read_on);
+	}
+	// Opening an article restarts the timer, even if it was running for
+	// the previous one
+	dwell_timer.start(mark_read_policy.dwell_ms());
 }
 
 void ItemViewFormAction::update_head(const std::shared_ptr<RssItem>& item)
@@ -98,50 +114,22 @@ void ItemViewFormAction::prepare()
 		}
 
 		update_head(item);
+		cancel_rendering();
 
-		const unsigned int window_width = textview.get_width();
-
-		unsigned int text_width =
-			cfg->get_configvalue_as_int("text-width");
-		if (text_width == 0 || text_width > window_width) {
-			text_width = window_width;
-			if (text_width > 5) {
-				text_width -= 5;
-			}
-		}
-
-		std::string formatted_text;
 		if (show_source) {
+			std::string formatted_text;
 			std::tie(formatted_text, num_lines) =
 				item_renderer::source_to_stfl_list(
 					item,
-					text_width,
-					window_width,
+					get_text_width(),
+					textview.get_width(),
 					&rxman,
 					"article");
+			textview.stfl_replace_lines(num_lines, formatted_text);
 		} else {
-			links.clear();
-			if (!item->enclosure_url().empty()) {
-				const auto link_type = utils::podcast_mime_to_link_type(item->enclosure_type());
-				if (link_type.has_value()) {
-					links.push_back(LinkPair(item->enclosure_url(), link_type.value()));
-				}
-			}
-
-			std::tie(formatted_text, num_lines) =
-				item_renderer::to_stfl_list(
-					// cfg can't be nullptr because that's a long-lived object
-					// created at the very start of the program.
-					*cfg,
-					item,
-					text_width,
-					window_width,
-					&rxman,
-					"article",
-					links);
+			render_article();
 		}
 
-		textview.stfl_replace_lines(num_lines, formatted_text);
 		f.set("article_offset", "0");
 
 		if (in_search) {
@@ -151,6 +139,132 @@ void ItemViewFormAction::prepare()
 
 		do_redraw = false;
 	}
+
+	if (render_job) {
+		continue_rendering();
+	}
+
+	// The main loop wakes us up when the dwell time is over, see
+	// get_event_timeout()
+	mark_read_on(MarkReadPolicy::Trigger::DWELL);
+	if (!render_job) {
+		// Until the article is rendered completely, its end isn't on the
+		// screen even if the rendered part is
+		mark_read_on(MarkReadPolicy::Trigger::SCROLL_END);
+	}
+}
+
+unsigned int ItemViewFormAction::get_text_width()
+{
+	const unsigned int window_width = textview.get_width();
+
+	unsigned int text_width = cfg->get_configvalue_as_int("text-width");
+	if (text_width == 0 || text_width > window_width) {
+		text_width = window_width;
+		if (text_width > 5) {
+			text_width -= 5;
+		}
+	}
+	return text_width;
+}
+
+void ItemViewFormAction::render_article()
+{
+	links.clear();
+	if (!item->enclosure_url().empty()) {
+		const auto link_type = utils::podcast_mime_to_link_type(item->enclosure_type());
+		if (link_type.has_value()) {
+			links.push_back(LinkPair(item->enclosure_url(), link_type.value()));
+		}
+	}
+
+	RenderCache& cache = itemlist->get_render_cache();
+	// The limits can be changed with `set` at any time
+	cache.set_limits(cfg->get_configvalue_as_int("article-cache-entries"),
+		cfg->get_configvalue_as_int("article-cache-size") * 1024);
+	std::vector<std::pair<LineType, std::string>> body;
+	if (cache.get(item->guid(), body, links)) {
+		show_article(body, false);
+		return;
+	}
+
+	render_job = item_renderer::start_rendering(*cfg, item, links,
+			textview.get_height());
+	if (render_job) {
+		// The header is there right away; the body follows in
+		// continue_rendering()
+		show_article(body, true);
+		return;
+	}
+
+	// An external `html-renderer` can't be interrupted
+	std::string formatted_text;
+	std::tie(formatted_text, num_lines) =
+		item_renderer::to_stfl_list(
+			// cfg can't be nullptr because that's a long-lived object
+			// created at the very start of the program.
+			*cfg,
+			item,
+			get_text_width(),
+			textview.get_width(),
+			&rxman,
+			"article",
+			links);
+	textview.stfl_replace_lines(num_lines, formatted_text);
+}
+
+void ItemViewFormAction::continue_rendering()
+{
+	ScopeMeasure m("ItemViewFormAction::continue_rendering");
+
+	switch (render_job->render_unit()) {
+	case RenderJob::Step::CONTINUE:
+		return;
+	case RenderJob::Step::SHOW_FIRST_SCREEN:
+		links = render_job->links();
+		show_article(render_job->lines(), true);
+		return;
+	case RenderJob::Step::FINISHED:
+		links = render_job->links();
+		itemlist->get_render_cache().store(item->guid(), *render_job);
+		show_article(render_job->lines(), false);
+		break;
+	case RenderJob::Step::CANCELLED:
+		break;
+	}
+	render_job.reset();
+}
+
+void ItemViewFormAction::cancel_rendering()
+{
+	if (render_job) {
+		LOG(Level::DEBUG,
+			"ItemViewFormAction::cancel_rendering: cancelling the rendering of %s",
+			item ? item->guid() : std::string());
+		render_job->cancel();
+		render_job.reset();
+	}
+}
+
+void ItemViewFormAction::show_article(
+	const std::vector<std::pair<LineType, std::string>>& body,
+	bool more_to_come)
+{
+	std::string formatted_text;
+	if (more_to_come) {
+		auto lines = body;
+		lines.push_back(std::make_pair(LineType::wrappable, std::string()));
+		lines.push_back(std::make_pair(LineType::wrappable,
+				std::string(_("Rendering the rest of the article..."))));
+		std::tie(formatted_text, num_lines) =
+			item_renderer::rendered_to_stfl_list(*cfg, item, lines,
+				get_text_width(), textview.get_width(), &rxman, "article");
+	} else {
+		std::tie(formatted_text, num_lines) =
+			item_renderer::rendered_to_stfl_list(*cfg, item, body,
+				get_text_width(), textview.get_width(), &rxman, "article");
+	}
+	textview.stfl_replace_lines(num_lines, formatted_text);
 }
 
 bool ItemViewFormAction::process_operation(Operation op,
@@ -160,22 +274,24 @@ bool ItemViewFormAction::process_operation(Operation op,
 	bool hardquit = false;
 
 	/*
-	 * whenever we process an operation, we mark the item
-	 * as read. Don't worry: when an item is already marked as
-	 * read, and then marked as read again, no database update
-	 * is done, since only _changes_ to the unread flag are
-	 * recorded in the database.
+	 * with `mark-read-on open`, whenever we process an operation, we
+	 * mark the item as read. Don't worry: when an item is already marked
+	 * as read, and then marked as read again, no database update is done,
+	 * since only _changes_ to the unread flag are recorded in the
+	 * database.
 	 */
-	try {
-		bool old_unread = item->unread();
-		item->set_unread(false);
-		if (old_unread) {
-			v->get_ctrl()->mark_article_read(item->guid(), true);
-		}
-	} catch (const DbException& e) {
-		v->show_error(strprintf::fmt(
-				_("Error while marking article as read: %s"),
-				e.what()));
+	mark_read_on(MarkReadPolicy::Trigger::OPEN);
+
+	switch (op) {
+	case OP_NEXTUNREAD:
+	case OP_PREVUNREAD:
+	case OP_NEXT:
+	case OP_PREV:
+	case OP_RANDOMUNREAD:
+		mark_read_on(MarkReadPolicy::Trigger::NEXT);
+		break;
+	default:
+		break;
 	}
 
 	switch (op) {
@@ -243,10 +359,12 @@ bool ItemViewFormAction::process_operation(Operation op,
 	}
 	break;
 	case OP_OPENINBROWSER:
-	case OP_OPENBROWSER_AND_MARK: {
+	case OP_OPENBROWSER_AND_MARK:
+	case OP_OPENINBROWSER_NONINTERACTIVELY: {
 		LOG(Level::INFO, "ItemViewFormAction::process_operation: starting browser");
 		v->set_status(_("Starting browser..."));
-		const auto exit_code = v->open_in_browser(item->link());
+		const auto exit_code = v->open_in_browser(item->link(),
+				op != OP_OPENINBROWSER_NONINTERACTIVELY);
 		if (!exit_code.has_value()) {
 			v->show_error(_("Failed to spawn browser"));
 			return false;
@@ -291,7 +409,8 @@ bool ItemViewFormAction::process_operation(Operation op,
 		if (automatic) {
 			if (args->size() > 0) {
 				qna_responses.clear();
-				qna_responses.push_back((*args)[0]);
+				// `--raw-html` is a separate argument
+				qna_responses.push_back(utils::join(*args, " "));
 				finished_qna(OP_PIPE_TO);
 			}
 		} else {
@@ -314,6 +433,28 @@ bool ItemViewFormAction::process_operation(Operation op,
 			this->start_qna(qna, OP_INT_EDITFLAGS_END);
 		}
 		break;
+	case OP_EDITNOTE:
+	case OP_EDITNOTE_IN_EDITOR:
+		if (automatic && op == OP_EDITNOTE) {
+			qna_responses.clear();
+			if (args->size() > 0) {
+				qna_responses.push_back((*args)[0]);
+				this->finished_qna(OP_INT_EDITNOTE_END);
+			}
+		} else if (op == OP_EDITNOTE_IN_EDITOR
+			|| item->note().find('\n') != std::string::npos) {
+			if (v->get_ctrl()->edit_note_in_editor(item)) {
+				v->set_status(_("Note updated."));
+				do_redraw = true;
+			} else {
+				v->show_error(_("Error: couldn't edit the note."));
+			}
+		} else {
+			std::vector<QnaPair> qna;
+			qna.push_back(QnaPair(_("Note: "), item->note()));
+			this->start_qna(qna, OP_INT_EDITNOTE_END);
+		}
+		break;
 	case OP_SHOWURLS: {
 		std::string urlviewer =
 			cfg->get_configvalue("external-url-viewer");
@@ -332,6 +473,10 @@ bool ItemViewFormAction::process_operation(Operation op,
 	}
 	break;
 	case OP_DELETE:
+		if (!feed->is_cached()) {
+			v->show_error(_("Error: articles of a previewed feed can't be deleted."));
+			break;
+		}
 		LOG(Level::INFO,
 			"ItemViewFormAction::process_operation: deleting current article");
 		item->set_deleted(true);
@@ -469,6 +614,8 @@ bool ItemViewFormAction::process_operation(Operation op,
 			v->pop_current_formaction();
 		}
 	} else if (quit) {
+		dwell_timer.cancel();
+		cancel_rendering();
 		v->pop_current_formaction();
 	}
 
@@ -498,6 +645,7 @@ void ItemViewFormAction::set_head(const std::string& s,
 	FmtStrFormatter fmt;
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 
 	auto itemtitle = s;
 	utils::remove_soft_hyphens(itemtitle);
@@ -513,13 +661,20 @@ void ItemViewFormAction::set_head(const std::string& s,
 	const unsigned int width = textview.get_width();
 
 	f.set("head",
-		fmt.do_format(
-			cfg->get_configvalue("itemview-title-format"), width));
+		fmt.do_format(termcaps::title_format(
+				"itemview-title-format",
+				cfg->get_configvalue("itemview-title-format"),
+				width),
+			width));
 }
 
 void ItemViewFormAction::handle_cmdline(const std::string& cmd)
 {
-	std::vector<std::string> tokens = utils::tokenize_quoted(cmd);
+	const auto parsed = tokenize_cmdline(cmd);
+	if (!parsed.has_value()) {
+		return;
+	}
+	const std::vector<std::string>& tokens = parsed.value();
 	if (!tokens.empty()) {
 		if (tokens[0] == "save" && tokens.size() >= 2) {
 			std::string filename = utils::resolve_tilde(tokens[1]);
@@ -558,18 +713,27 @@ void ItemViewFormAction::finished_qna(Operation op)
 		v->set_status(_("Flags updated."));
 		do_redraw = true;
 		break;
+	case OP_INT_EDITNOTE_END:
+		item->set_note(qna_responses[0]);
+		v->get_ctrl()->update_note(item);
+		v->set_status(_("Note updated."));
+		do_redraw = true;
+		break;
 	case OP_INT_START_SEARCH:
 		do_search();
 		break;
 	case OP_PIPE_TO: {
-		std::string cmd = qna_responses[0];
-		std::ostringstream ostr;
-		v->get_ctrl()->write_item(feed->get_item_by_guid(guid), ostr);
+		bool raw_html = false;
+		const std::string cmd =
+			item_renderer::split_pipe_command(qna_responses[0], raw_html);
+		const auto item = feed->get_item_by_guid(guid);
+		const std::string data = raw_html
+			? item_renderer::to_piped_html(item)
+			: item_renderer::to_piped_text(*cfg, item);
 		v->push_empty_formaction();
 		Stfl::reset();
 		FILE* f = popen(cmd.c_str(), "w");
 		if (f) {
-			std::string data = ostr.str();
 			fwrite(data.c_str(), data.length(), 1, f);
 			pclose(f);
 		}
@@ -599,12 +763,58 @@ void ItemViewFormAction::register_format_styles()
 		"@style_u_normal[color_underline]:attr=underline ");
 	std::string stfl_textview = strprintf::fmt(
 			"{textview[article] style_normal[article]: "
-			"style_end[end-of-text-marker]:fg=blue,attr=bold %s .expand:vh "
+			"style_end[end-of-text-marker]:%s %s .expand:vh "
 			"offset[article_offset]:0 richtext:1}",
+			termcaps::degrade_style("fg=blue,attr=bold"),
 			attrstr);
 	textview.stfl_replace_textview(0, stfl_textview);
 }
 
+int ItemViewFormAction::get_event_timeout()
+{
+	if (render_job) {
+		// Render the next unit unless a key is pressed in the meantime
+		return 1;
+	}
+
+	const std::int64_t time_left = dwell_timer.time_left_ms();
+	if (time_left >= 0 && item && item->unread()) {
+		// Wake up right after the article was shown long enough
+		return time_left + 1;
+	}
+	return FormAction::get_event_timeout();
+}
+
+void ItemViewFormAction::mark_read_on(MarkReadPolicy::Trigger trigger)
+{
+	if (!item->unread()) {
+		return;
+	}
+
+	MarkReadPolicy::Context context;
+	context.dwelled_ms = dwell_timer.elapsed_ms();
+	context.at_end = textview.get_scroll_offset() + textview.get_height() >=
+		num_lines;
+	if (mark_read_policy.should_mark_read(trigger, context)) {
+		mark_item_read();
+	}
+}
+
+void ItemViewFormAction::mark_item_read()
+{
+	try {
+		bool old_unread = item->unread();
+		item->set_unread(false);
+		if (old_unread) {
+			v->get_ctrl()->mark_article_read(item->guid(), true);
+		}
+	} catch (const DbException& e) {
+		v->show_error(strprintf::fmt(
+				_("Error while marking article as read: %s"),
+				e.what()));
+	}
+}
+
 void ItemViewFormAction::update_percent()
 {
 	if (cfg->get_configvalue_as_bool("display-article-progress")) {
@@ -641,6 +851,12 @@ std::string ItemViewFormAction::title()
 	return strprintf::fmt(_("Article - %s"), utils::utf8_to_locale(title));
 }
 
+void ItemViewFormAction::add_to_memory_report(MemoryReport& report)
+{
+	report.add_dialog(title());
+	report.add_item(item);
+}
+
 void ItemViewFormAction::set_highlightphrase(const std::string& text)
 {
 	highlight_text(text);
@@ -6,24 +6,219 @@
 #include <vector>
 
 #include "config.h"
+#include "configcontainer.h"
+#include "configexception.h"
 #include "confighandlerexception.h"
 #include "logger.h"
+#include "ruststring.h"
 #include "strprintf.h"
 #include "utils.h"
 
+extern "C" {
+	void* rs_tokenize_operation_sequence(const char* input,
+		bool strict,
+		std::size_t* column,
+		char** error);
+
+	void* rs_tokenize_macro_definition(const char* input,
+		bool strict,
+		char** description,
+		std::size_t* column,
+		char** error);
+
+	void* rs_tokenize_cmdline(const char* input, char** error);
+
+	void rs_operation_sequence_free(void* operations);
+
+	std::size_t rs_operation_sequence_count(void* operations);
+
+	std::size_t rs_operation_sequence_tokens_count(void* operations,
+		std::size_t operation);
+
+	char* rs_parse_key_name(const char* name, char** warning, char** error);
+
+	char* rs_operation_sequence_token(void* operations,
+		std::size_t operation,
+		std::size_t token);
+
+	void* rs_tokenize_run_cmdline(const char* input);
+
+	void* rs_parse_binding(const char* input,
+		char** key,
+		char** contexts,
+		char** error);
+
+	void* rs_operation_sequence_new();
+
+	void rs_operation_sequence_push_token(void* operations,
+		const char* token,
+		bool starts_operation);
+
+	char* rs_operation_sequence_to_run_cmdline(void* operations);
+	char* rs_operation_sequence_serialize(void* operations);
+	bool rs_operation_sequence_macro_parameter_count(void* operations,
+		std::size_t* count,
+		char** error);
+	bool rs_operation_sequence_check_arguments(void* operations,
+		char** error);
+
+	void* rs_operation_sequence_substitute_macro_arguments(void* operations,
+		const char* const* arguments,
+		std::size_t arguments_count);
+	char* rs_next_macro_argument_prompt(std::size_t count,
+		std::size_t supplied);
+
+	void* rs_key_origins_new();
+	void rs_key_origins_free(void* origins);
+	char* rs_key_origins_bind(void* origins,
+		const char* contexts,
+		const char* key,
+		const char* operation,
+		const char* file,
+		std::uint32_t line);
+	void rs_key_origins_unbind(void* origins,
+		const char* contexts,
+		const char* key);
+	void rs_key_origins_unbind_all(void* origins, const char* contexts);
+	char* rs_key_origins_binding(void* origins,
+		const char* context,
+		const char* key);
+	char* rs_key_origins_define_macro(void* origins,
+		const char* key,
+		const char* operations,
+		const char* file,
+		std::uint32_t line);
+	char* rs_key_origins_macro(void* origins, const char* key);
+	char* rs_key_origins_describe(void* origins,
+		const char* context,
+		const char* key,
+		const char* operation,
+		bool has_macro);
+	char* rs_describe_operation(const char* name,
+		const char* description,
+		const char* contexts,
+		const char* binding_contexts,
+		const char* binding_keys);
+	void rs_key_origins_register_operation(void* origins,
+		const char* operation,
+		const char* contexts);
+	void rs_key_origins_set_program_contexts(void* origins,
+		const char* contexts);
+	char* rs_key_origins_context_warnings(void* origins);
+
+	void* rs_default_actions_new();
+	void rs_default_actions_free(void* actions);
+	void rs_default_actions_register_operation(void* actions,
+		const char* operation,
+		const char* contexts);
+	void rs_default_actions_set_builtin(void* actions,
+		const char* context,
+		bool secondary,
+		const char* operation);
+	char* rs_default_actions_configure(void* actions,
+		const char* context,
+		bool secondary,
+		const char* operation);
+	char* rs_default_actions_resolve(void* actions,
+		const char* context,
+		bool secondary);
+	char* rs_default_actions_option_name(const char* context, bool secondary);
+	char* rs_default_actions_configurable_contexts();
+}
+
 namespace newsboat {
 
+namespace {
+
+/// Converts an operation sequence returned by Rust into a vector, and frees
+/// the Rust object.
+std::vector<std::vector<std::string>> take_operation_sequence(void* operations)
+{
+	std::vector<std::vector<std::string>> result;
+	const auto count = rs_operation_sequence_count(operations);
+	for (std::size_t i = 0; i < count; ++i) {
+		std::vector<std::string> tokens;
+		const auto tokens_count = rs_operation_sequence_tokens_count(
+				operations, i);
+		for (std::size_t j = 0; j < tokens_count; ++j) {
+			tokens.push_back(RustString(
+					rs_operation_sequence_token(operations, i, j)));
+		}
+		result.push_back(tokens);
+	}
+	rs_operation_sequence_free(operations);
+	return result;
+}
+
+/// Throws if an operation of the sequence was given the wrong arguments, in
+/// which case the sequence is freed.
+void check_arguments(void* operations)
+{
+	char* error = nullptr;
+	if (!rs_operation_sequence_check_arguments(operations, &error)) {
+		rs_operation_sequence_free(operations);
+		throw ConfigHandlerException(RustString(error));
+	}
+}
+
+/// Tokenizes an operation sequence. Throws if that fails, with a message
+/// made from \a error_format, the column at which it failed, and the reason.
+///
+/// If \a strict is set, quotes that aren't closed are an error; that's for
+/// config files, where an unbalanced quote is more likely a mistake than
+/// a shortcut.
+void* tokenize_operation_sequence(const std::string& input,
+	const std::string& error_format,
+	bool strict)
+{
+	std::size_t column = 0;
+	char* error = nullptr;
+	void* operations = rs_tokenize_operation_sequence(input.c_str(), strict,
+			&column, &error);
+	if (operations == nullptr) {
+		const std::string reason = RustString(error);
+		throw ConfigHandlerException(strprintf::fmt(error_format,
+				static_cast<unsigned int>(column), reason));
+	}
+	return operations;
+}
+
+/// Like tokenize_operation_sequence() in strict mode, but for the
+/// definition of a macro, whose description is stored into \a description
+/// (or an empty string, if it has none).
+void* tokenize_macro_definition(const std::string& input,
+	std::string& description)
+{
+	char* text = nullptr;
+	std::size_t column = 0;
+	char* error = nullptr;
+	void* operations = rs_tokenize_macro_definition(input.c_str(), true,
+			&text, &column, &error);
+	if (operations == nullptr) {
+		const std::string reason = RustString(error);
+		throw ConfigHandlerException(strprintf::fmt(
+				_("invalid macro at column %u: %s"),
+				static_cast<unsigned int>(column), reason));
+	}
+	description = RustString(text);
+	return operations;
+}
+
+} // namespace
+
 struct OpDesc {
 	const Operation op;
 	const std::string opstr;
 	const std::string default_key;
 	const std::string help_text;
 	const unsigned short flags;
+	const OpCategory category;
 };
 
 /*
  * This is the list of operations, defining operation, operation name (for
- * keybindings), default key, description, and where it's valid
+ * keybindings), default key, description, where it's valid, and the section
+ * of the help dialog it's listed in
  */
 static const std::vector<OpDesc> opdescs = {
 	{
@@ -32,102 +227,184 @@ static const std::vector<OpDesc> opdescs = {
 		"ENTER",
 		_("Open feed/article"),
 		KM_FEEDLIST | KM_FILEBROWSER | KM_ARTICLELIST | KM_TAGSELECT |
-		KM_FILTERSELECT | KM_URLVIEW | KM_DIALOGS | KM_DIRBROWSER
+		KM_FILTERSELECT | KM_URLVIEW | KM_DIALOGS | KM_DIRBROWSER,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_SWITCH_FOCUS,
 		"switch-focus",
 		"TAB",
 		_("Switch focus between widgets"),
-		KM_FILEBROWSER | KM_DIRBROWSER
+		KM_FILEBROWSER | KM_DIRBROWSER,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_QUIT,
+		"quit",
+		"q",
+		_("Return to previous dialog/Quit"),
+		KM_BOTH,
+		OpCategory::OTHER
 	},
-	{OP_QUIT, "quit", "q", _("Return to previous dialog/Quit"), KM_BOTH},
 	{
 		OP_HARDQUIT,
 		"hard-quit",
 		"Q",
 		_("Quit program, no confirmation"),
-		KM_BOTH
+		KM_BOTH,
+		OpCategory::OTHER
 	},
 	{
 		OP_RELOAD,
 		"reload",
 		"r",
 		_("Reload currently selected feed"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_RELOADALL,
+		"reload-all",
+		"R",
+		_("Reload all feeds"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_RELOADTAG,
+		"reload-tag",
+		"",
+		_("Reload feeds with a tag"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_CANCEL_RELOAD,
+		"cancel-reload",
+		"",
+		_("Cancel the running reload"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
 	},
-	{OP_RELOADALL, "reload-all", "R", _("Reload all feeds"), KM_FEEDLIST},
 	{
 		OP_MARKFEEDREAD,
 		"mark-feed-read",
 		"A",
 		_("Mark feed read"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_MARKALLFEEDSREAD,
 		"mark-all-feeds-read",
 		"C",
 		_("Mark all feeds read"),
-		KM_FEEDLIST
+		KM_FEEDLIST | KM_LONG,
+		OpCategory::FEEDS
 	},
 	{
 		OP_MARKALLABOVEASREAD,
 		"mark-all-above-as-read",
 		"",
 		_("Mark all above as read"),
-		KM_ARTICLELIST
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_MARKABOVEREAD,
+		"mark-above-read",
+		"",
+		_("Mark the unread articles above the cursor read"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_MARKBELOWREAD,
+		"mark-below-read",
+		"",
+		_("Mark the unread articles below the cursor read"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_UNDOMARKREAD,
+		"undo-mark-read",
+		"",
+		_("Undo the last mark-above-read or mark-below-read"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_SAVE,
+		"save",
+		"s",
+		_("Save article"),
+		KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_SAVEALL,
+		"save-all",
+		"",
+		_("Save articles"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
 	},
-	{OP_SAVE, "save", "s", _("Save article"), KM_ARTICLELIST | KM_ARTICLE},
-	{OP_SAVEALL, "save-all", "", _("Save articles"), KM_ARTICLELIST},
 	{
 		OP_NEXT,
 		"next",
 		"J",
 		_("Go to next entry"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_DIALOGS | KM_DIRBROWSER | KM_FILEBROWSER | KM_FILTERSELECT | KM_TAGSELECT | KM_URLVIEW | KM_PODBOAT
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_DIALOGS | KM_DIRBROWSER | KM_FILEBROWSER | KM_FILTERSELECT | KM_TAGSELECT | KM_URLVIEW | KM_PODBOAT,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_PREV,
 		"prev",
 		"K",
 		_("Go to previous entry"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_DIALOGS | KM_DIRBROWSER | KM_FILEBROWSER | KM_FILTERSELECT | KM_TAGSELECT | KM_URLVIEW | KM_PODBOAT
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_DIALOGS | KM_DIRBROWSER | KM_FILEBROWSER | KM_FILTERSELECT | KM_TAGSELECT | KM_URLVIEW | KM_PODBOAT,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_NEXTUNREAD,
 		"next-unread",
 		"n",
 		_("Go to next unread article"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_PREVUNREAD,
 		"prev-unread",
 		"p",
 		_("Go to previous unread article"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_RANDOMUNREAD,
 		"random-unread",
 		"^K",
 		_("Go to a random unread article"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_OPENBROWSER_AND_MARK,
 		"open-in-browser-and-mark-read",
 		"O",
 		_("Open URL of article, or entry in URL view. Mark read."),
-		KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW
+		KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_OPENALLUNREADINBROWSER,
 		"open-all-unread-in-browser",
 		"",
 		_("Open all unread items of selected feed in browser"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_OPENALLUNREADINBROWSER_AND_MARK,
@@ -135,294 +412,748 @@ static const std::vector<OpDesc> opdescs = {
 		"",
 		_("Open all unread items of selected feed in browser and mark "
 			"read"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_OPENINBROWSER,
 		"open-in-browser",
 		"o",
 		_("Open URL of article, feed, or entry in URL view"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_OPENINBROWSER_NONINTERACTIVELY,
+		"open-in-browser-noninteractively",
+		"",
+		_("Open URL of article, feed, or entry in URL view in the "
+			"background, without leaving Newsboat"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_HELP,
 		"help",
 		"?",
 		_("Open help dialog"),
-		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_PODBOAT
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_TOGGLESOURCEVIEW,
 		"toggle-source-view",
 		"^U",
 		_("Toggle source view"),
-		KM_ARTICLE
+		KM_ARTICLE,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_TOGGLEITEMREAD,
 		"toggle-article-read",
 		"N",
 		_("Toggle read status for article"),
-		KM_ARTICLELIST | KM_ARTICLE
+		KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_TOGGLESHOWREAD,
 		"toggle-show-read-feeds",
 		"l",
 		_("Toggle show read feeds/articles"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_TOGGLE_PREVIEW,
+		"toggle-preview",
+		"",
+		_("Toggle the article preview pane"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_SHOWURLS,
 		"show-urls",
 		"u",
 		_("Show URLs in current article"),
-		KM_ARTICLE | KM_ARTICLELIST
+		KM_ARTICLE | KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_CLEARTAG,
+		"clear-tag",
+		"^T",
+		_("Clear current tag"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_SETTAG,
+		"set-tag",
+		"t",
+		_("Select tag"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_SETTAG,
+		"select-tag",
+		"t",
+		_("Select tag"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_ADD_TAG,
+		"add-tag",
+		"",
+		_("Add a tag to the selected feed"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_REMOVE_TAG,
+		"remove-tag",
+		"",
+		_("Remove a tag from the selected feed"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_RENAME_TAG,
+		"rename-tag",
+		"",
+		_("Rename a tag on all feeds"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
 	},
-	{OP_CLEARTAG, "clear-tag", "^T", _("Clear current tag"), KM_FEEDLIST},
-	{OP_SETTAG, "set-tag", "t", _("Select tag"), KM_FEEDLIST},
-	{OP_SETTAG, "select-tag", "t", _("Select tag"), KM_FEEDLIST},
 	{
 		OP_SEARCH,
 		"open-search",
 		"/",
 		_("Open search dialog"),
-		KM_FEEDLIST | KM_HELP | KM_ARTICLELIST | KM_ARTICLE
+		KM_FEEDLIST | KM_HELP | KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::OTHER
+	},
+	{
+		OP_GOTO_URL,
+		"goto-url",
+		"#",
+		_("Goto URL #"),
+		KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_ENQUEUE,
+		"enqueue",
+		"e",
+		_("Add download to queue"),
+		KM_ARTICLE | KM_ARTICLELIST,
+		OpCategory::ARTICLES
 	},
-	{OP_GOTO_URL, "goto-url", "#", _("Goto URL #"), KM_ARTICLE},
-	{OP_ENQUEUE, "enqueue", "e", _("Add download to queue"), KM_ARTICLE},
 	{
 		OP_RELOADURLS,
 		"reload-urls",
 		"^R",
 		_("Reload the list of URLs from the configuration"),
-		KM_FEEDLIST
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_PB_DOWNLOAD,
+		"pb-download",
+		"d",
+		_("Download file"),
+		KM_PODBOAT,
+		OpCategory::OTHER
+	},
+	{
+		OP_PB_CANCEL,
+		"pb-cancel",
+		"c",
+		_("Cancel download"),
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
-	{OP_PB_DOWNLOAD, "pb-download", "d", _("Download file"), KM_PODBOAT},
-	{OP_PB_CANCEL, "pb-cancel", "c", _("Cancel download"), KM_PODBOAT},
 	{
 		OP_PB_DELETE,
 		"pb-delete",
 		"D",
 		_("Mark download as deleted"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_PB_PURGE,
 		"pb-purge",
 		"P",
 		_("Purge finished and deleted downloads from queue"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_PB_TOGGLE_DLALL,
 		"pb-toggle-download-all",
 		"a",
 		_("Toggle automatic download on/off"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_PB_PLAY,
 		"pb-play",
 		"p",
 		_("Start player with currently selected download"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_PB_MARK_FINISHED,
 		"pb-mark-as-finished",
 		"m",
 		_("Mark file as finished (not played)"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_PB_MOREDL,
 		"pb-increase-max-dls",
 		"+",
 		_("Increase the number of concurrent downloads"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
 	},
 	{
 		OP_PB_LESSDL,
 		"pb-decreate-max-dls",
 		"-",
 		_("Decrease the number of concurrent downloads"),
-		KM_PODBOAT
+		KM_PODBOAT,
+		OpCategory::OTHER
+	},
+	{
+		OP_REDRAW,
+		"redraw",
+		"^L",
+		_("Redraw screen"),
+		KM_SYSKEYS,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMDLINE,
+		"cmdline",
+		":",
+		_("Open the commandline"),
+		KM_NEWSBOAT,
+		OpCategory::OTHER
 	},
-	{OP_REDRAW, "redraw", "^L", _("Redraw screen"), KM_SYSKEYS},
-	{OP_CMDLINE, "cmdline", ":", _("Open the commandline"), KM_NEWSBOAT},
 	{
 		OP_SETFILTER,
 		"set-filter",
 		"F",
 		_("Set a filter"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_SELECTFILTER,
 		"select-filter",
 		"f",
 		_("Select a predefined filter"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_BUILDFILTER,
+		"build-filter",
+		"",
+		_("Build a filter from a list of attributes"),
+		KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_CLEARFILTER,
 		"clear-filter",
 		"^F",
 		_("Clear currently set filter"),
-		KM_FEEDLIST | KM_HELP | KM_ARTICLELIST
+		KM_FEEDLIST | KM_HELP | KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_BOOKMARK,
 		"bookmark",
 		"^B",
 		_("Bookmark current link/article"),
-		KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW
+		KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_EDITFLAGS,
 		"edit-flags",
 		"^E",
 		_("Edit flags"),
-		KM_ARTICLELIST | KM_ARTICLE
+		KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_EDITNOTE,
+		"edit-note",
+		"",
+		_("Edit the note on the article"),
+		KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_EDITNOTE_IN_EDITOR,
+		"edit-note-in-editor",
+		"",
+		_("Edit the note on the article in an external editor"),
+		KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_NEXTFEED,
+		"next-feed",
+		"j",
+		_("Go to next feed"),
+		KM_ARTICLELIST,
+		OpCategory::NAVIGATION
 	},
-	{OP_NEXTFEED, "next-feed", "j", _("Go to next feed"), KM_ARTICLELIST},
 	{
 		OP_PREVFEED,
 		"prev-feed",
 		"k",
 		_("Go to previous feed"),
-		KM_ARTICLELIST
+		KM_ARTICLELIST,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_NEXTUNREADFEED,
 		"next-unread-feed",
 		"^N",
 		_("Go to next unread feed"),
-		KM_ARTICLELIST
+		KM_ARTICLELIST,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_PREVUNREADFEED,
 		"prev-unread-feed",
 		"^P",
 		_("Go to previous unread feed"),
-		KM_ARTICLELIST
+		KM_ARTICLELIST,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_NEXTFEEDWITHUNREAD,
+		"next-feed-with-unread",
+		"",
+		_("Open the next feed with unread articles"),
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_PREVFEEDWITHUNREAD,
+		"prev-feed-with-unread",
+		"",
+		_("Open the previous feed with unread articles"),
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_MACROPREFIX,
+		"macro-prefix",
+		",",
+		_("Call a macro"),
+		KM_NEWSBOAT,
+		OpCategory::MACROS
+	},
+	{
+		OP_EDIT_MACRO,
+		"edit-macro",
+		"",
+		_("Copy a macro to the commandline to run it with changes"),
+		KM_NEWSBOAT,
+		OpCategory::MACROS
+	},
+	{
+		OP_DESCRIBE_KEY,
+		"describe-key",
+		"",
+		_("Show what a key does, and where it was bound"),
+		KM_NEWSBOAT,
+		OpCategory::OTHER
+	},
+	{
+		OP_DESCRIBE,
+		"describe",
+		"",
+		_("Show what an option or an operation is, and where it was set"),
+		KM_NEWSBOAT,
+		OpCategory::OTHER
 	},
-	{OP_MACROPREFIX, "macro-prefix", ",", _("Call a macro"), KM_NEWSBOAT},
 	{
 		OP_DELETE,
 		"delete-article",
 		"D",
 		_("Delete article"),
-		KM_ARTICLELIST | KM_ARTICLE
+		KM_ARTICLELIST | KM_ARTICLE,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_DELETE_ALL,
 		"delete-all-articles",
 		"^D",
 		_("Delete all articles"),
-		KM_ARTICLELIST
+		KM_ARTICLELIST | KM_LONG,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_PURGE_DELETED,
 		"purge-deleted",
 		"$",
 		_("Purge deleted articles"),
-		KM_ARTICLELIST
+		KM_ARTICLELIST | KM_LONG,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_EDIT_URLS,
 		"edit-urls",
 		"E",
 		_("Edit subscribed URLs"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_PREVIEW_FEED,
+		"preview-feed",
+		"",
+		_("Preview a feed before subscribing to it"),
+		KM_FEEDLIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_SUBSCRIBE,
+		"subscribe",
+		"",
+		_("Subscribe to the previewed feed"),
+		KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_CLOSEDIALOG,
 		"close-dialog",
 		"^X",
 		_("Close currently selected dialog"),
-		KM_DIALOGS
+		KM_DIALOGS,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_VIEWDIALOGS,
 		"view-dialogs",
 		"v",
 		_("View list of open dialogs"),
-		KM_NEWSBOAT
+		KM_NEWSBOAT,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_NEXTDIALOG,
 		"next-dialog",
 		"^V",
 		_("Go to next dialog"),
-		KM_NEWSBOAT
+		KM_NEWSBOAT,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_PREVDIALOG,
 		"prev-dialog",
 		"^G",
 		_("Go to previous dialog"),
-		KM_NEWSBOAT
+		KM_NEWSBOAT,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_POP_TO_FEEDLIST,
+		"pop-to-feedlist",
+		"",
+		_("Close dialogs until the feed list is reached"),
+		KM_NEWSBOAT,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_CLOSE_ALL_DIALOGS,
+		"close-all-dialogs",
+		"",
+		_("Close all dialogs except the feed list"),
+		KM_NEWSBOAT,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_PIPE_TO,
 		"pipe-to",
 		"|",
 		_("Pipe article to command"),
-		KM_ARTICLE | KM_ARTICLELIST
+		KM_ARTICLE | KM_ARTICLELIST,
+		OpCategory::ARTICLES
 	},
 	{
 		OP_SORT,
 		"sort",
 		"g",
 		_("Sort current list"),
-		KM_FEEDLIST | KM_ARTICLELIST
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
 	},
 	{
 		OP_REVSORT,
 		"rev-sort",
 		"G",
 		_("Sort current list (reverse)"),
-		KM_FEEDLIST | KM_ARTICLELIST
-	},
-
-	{OP_OPEN_URL_1, "one", "1", _("Open URL 1"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_2, "two", "2", _("Open URL 2"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_3, "three", "3", _("Open URL 3"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_4, "four", "4", _("Open URL 4"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_5, "five", "5", _("Open URL 5"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_6, "six", "6", _("Open URL 6"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_7, "seven", "7", _("Open URL 7"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_8, "eight", "8", _("Open URL 8"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_9, "nine", "9", _("Open URL 9"), KM_URLVIEW | KM_ARTICLE},
-	{OP_OPEN_URL_10, "zero", "0", _("Open URL 10"), KM_URLVIEW | KM_ARTICLE},
-
-	{OP_CMD_START_1, "cmd-one", "1", _("Start cmdline with 1"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_2, "cmd-two", "2", _("Start cmdline with 2"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_3, "cmd-three", "3", _("Start cmdline with 3"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_4, "cmd-four", "4", _("Start cmdline with 4"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_5, "cmd-five", "5", _("Start cmdline with 5"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_6, "cmd-six", "6", _("Start cmdline with 6"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_7, "cmd-seven", "7", _("Start cmdline with 7"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_8, "cmd-eight", "8", _("Start cmdline with 8"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-	{OP_CMD_START_9, "cmd-nine", "9", _("Start cmdline with 9"), KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT},
-
-	{OP_SK_UP, "up", "UP", _("Move to the previous entry"), KM_SYSKEYS},
-	{OP_SK_DOWN, "down", "DOWN", _("Move to the next entry"), KM_SYSKEYS},
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::FEEDS
+	},
+	{
+		OP_TOGGLE_SELECT,
+		"toggle-select",
+		"",
+		_("Select or unselect the current article"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_SELECT_RANGE,
+		"select-range",
+		"",
+		_("Start or finish selecting a range of articles"),
+		KM_ARTICLELIST,
+		OpCategory::ARTICLES
+	},
+	{
+		OP_FIRST_UNREAD,
+		"first-unread",
+		"",
+		_("Go to the first unread entry"),
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_LAST_UNREAD,
+		"last-unread",
+		"",
+		_("Go to the last unread entry"),
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_GOTO_PERCENT,
+		"goto-percent",
+		"",
+		_("Go to a position given in percent of the list"),
+		KM_FEEDLIST | KM_ARTICLELIST,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_DEFAULT_ACTION_PRIMARY,
+		"default-action-primary",
+		"",
+		_("Do what Enter does here"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_DEFAULT_ACTION_SECONDARY,
+		"default-action-secondary",
+		"",
+		_("Do what Space does here"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_ARTICLE | KM_URLVIEW,
+		OpCategory::NAVIGATION
+	},
+
+	{
+		OP_OPEN_URL_1,
+		"one",
+		"1",
+		_("Open URL 1"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_2,
+		"two",
+		"2",
+		_("Open URL 2"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_3,
+		"three",
+		"3",
+		_("Open URL 3"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_4,
+		"four",
+		"4",
+		_("Open URL 4"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_5,
+		"five",
+		"5",
+		_("Open URL 5"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_6,
+		"six",
+		"6",
+		_("Open URL 6"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_7,
+		"seven",
+		"7",
+		_("Open URL 7"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_8,
+		"eight",
+		"8",
+		_("Open URL 8"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_9,
+		"nine",
+		"9",
+		_("Open URL 9"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_OPEN_URL_10,
+		"zero",
+		"0",
+		_("Open URL 10"),
+		KM_URLVIEW | KM_ARTICLE,
+		OpCategory::NAVIGATION
+	},
+
+	{
+		OP_CMD_START_1,
+		"cmd-one",
+		"1",
+		_("Start cmdline with 1"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_2,
+		"cmd-two",
+		"2",
+		_("Start cmdline with 2"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_3,
+		"cmd-three",
+		"3",
+		_("Start cmdline with 3"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_4,
+		"cmd-four",
+		"4",
+		_("Start cmdline with 4"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_5,
+		"cmd-five",
+		"5",
+		_("Start cmdline with 5"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_6,
+		"cmd-six",
+		"6",
+		_("Start cmdline with 6"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_7,
+		"cmd-seven",
+		"7",
+		_("Start cmdline with 7"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_8,
+		"cmd-eight",
+		"8",
+		_("Start cmdline with 8"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+	{
+		OP_CMD_START_9,
+		"cmd-nine",
+		"9",
+		_("Start cmdline with 9"),
+		KM_FEEDLIST | KM_ARTICLELIST | KM_TAGSELECT | KM_FILTERSELECT,
+		OpCategory::OTHER
+	},
+
+	{
+		OP_SK_UP,
+		"up",
+		"UP",
+		_("Move to the previous entry"),
+		KM_SYSKEYS,
+		OpCategory::NAVIGATION
+	},
+	{
+		OP_SK_DOWN,
+		"down",
+		"DOWN",
+		_("Move to the next entry"),
+		KM_SYSKEYS,
+		OpCategory::NAVIGATION
+	},
 	{
 		OP_SK_PGUP,
 		"pageup",
 		"PPAGE",
 		_("Move to the previous page"),
-		KM_SYSKEYS
+		KM_SYSKEYS,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_SK_PGDOWN,
 		"pagedown",
 		"NPAGE",
 		_("Move to the next page"),
-		KM_SYSKEYS
+		KM_SYSKEYS,
+		OpCategory::NAVIGATION
 	},
 
 	{
@@ -430,14 +1161,16 @@ static const std::vector<OpDesc> opdescs = {
 		"home",
 		"HOME",
 		_("Move to the start of page/list"),
-		KM_SYSKEYS
+		KM_SYSKEYS,
+		OpCategory::NAVIGATION
 	},
 	{
 		OP_SK_END,
 		"end",
 		"END",
 		_("Move to the end of page/list"),
-		KM_SYSKEYS
+		KM_SYSKEYS,
+		OpCategory::NAVIGATION
 	},
 
 	{
@@ -445,34 +1178,52 @@ static const std::vector<OpDesc> opdescs = {
 		"XXXNOKEY-end-question",
 		"end-question",
 		"",
-		KM_INTERNAL
+		KM_INTERNAL,
+		OpCategory::OTHER
 	},
 	{
 		OP_INT_CANCEL_QNA,
 		"XXXNOKEY-cancel-qna",
 		"cancel-qna",
 		"",
-		KM_INTERNAL
+		KM_INTERNAL,
+		OpCategory::OTHER
 	},
 	{
 		OP_INT_QNA_NEXTHIST,
 		"XXXNOKEY-qna-next-history",
 		"qna-next-history",
 		"",
-		KM_INTERNAL
+		KM_INTERNAL,
+		OpCategory::OTHER
 	},
 	{
 		OP_INT_QNA_PREVHIST,
 		"XXXNOKEY-qna-prev-history",
 		"qna-prev-history",
 		"",
-		KM_INTERNAL
+		KM_INTERNAL,
+		OpCategory::OTHER
 	},
 
-	{OP_INT_RESIZE, "RESIZE", "internal-resize", "", KM_INTERNAL},
-	{OP_INT_SET, "set", "internal-set", "", KM_INTERNAL},
+	{
+		OP_INT_RESIZE,
+		"RESIZE",
+		"internal-resize",
+		"",
+		KM_INTERNAL,
+		OpCategory::OTHER
+	},
+	{OP_INT_SET, "set", "internal-set", "", KM_INTERNAL, OpCategory::OTHER},
 
-	{OP_INT_GOTO_URL, "gotourl", "internal-goto-url", "", KM_INTERNAL},
+	{
+		OP_INT_GOTO_URL,
+		"gotourl",
+		"internal-goto-url",
+		"",
+		KM_INTERNAL,
+		OpCategory::OTHER
+	},
 };
 
 static const std::map<std::string, std::uint32_t> contexts = {
@@ -489,7 +1240,24 @@ static const std::map<std::string, std::uint32_t> contexts = {
 	{"dirbrowser", KM_DIRBROWSER},
 };
 
+/// Newline-separated list of the contexts that \a context stands for.
+static std::string context_list(const std::string& context)
+{
+	if (context != "all") {
+		return context;
+	}
+
+	std::string result;
+	for (const auto& ctx : contexts) {
+		result.append(ctx.first);
+		result.append("\n");
+	}
+	return result;
+}
+
 KeyMap::KeyMap(unsigned flags)
+	: rs_origins(rs_key_origins_new())
+	, rs_default_actions(rs_default_actions_new())
 {
 	/*
 	 * At startup, initialize the keymap with the default settings from the
@@ -519,6 +1287,50 @@ KeyMap::KeyMap(unsigned flags)
 	keymap_["help"]["SPACE"] = OP_SK_PGDOWN;
 	keymap_["article"]["b"] = OP_SK_PGUP;
 	keymap_["article"]["SPACE"] = OP_SK_PGDOWN;
+
+	// Tell the origins where each operation works, so that they can warn
+	// about bindings that will never trigger
+	for (const auto& op_desc : opdescs) {
+		std::string valid_contexts;
+		for (const auto& ctx : contexts) {
+			if (op_desc.flags & (ctx.second | KM_INTERNAL | KM_SYSKEYS)) {
+				valid_contexts.append(ctx.first);
+				valid_contexts.append("\n");
+			}
+		}
+		rs_key_origins_register_operation(rs_origins, op_desc.opstr.c_str(),
+			valid_contexts.c_str());
+		if (!(op_desc.flags & KM_INTERNAL)) {
+			rs_default_actions_register_operation(rs_default_actions,
+				op_desc.opstr.c_str(), valid_contexts.c_str());
+		}
+	}
+
+	// Enter and Space do whatever they did before, but through the default
+	// actions, so that options can change that
+	const std::vector<std::string> configurable_contexts = utils::tokenize(
+			RustString(rs_default_actions_configurable_contexts()), "\n");
+	for (const auto& context : configurable_contexts) {
+		for (const bool secondary : {false, true}) {
+			const std::string key = secondary ? "SPACE" : "ENTER";
+			const Operation builtin = keymap_[context][key];
+			if (builtin != OP_NIL) {
+				rs_default_actions_set_builtin(rs_default_actions, context.c_str(),
+					secondary, getopname(builtin).c_str());
+			}
+			keymap_[context][key] = secondary
+				? OP_DEFAULT_ACTION_SECONDARY
+				: OP_DEFAULT_ACTION_PRIMARY;
+		}
+	}
+	std::string program_contexts;
+	for (const auto& ctx : contexts) {
+		if (ctx.second & flags) {
+			program_contexts.append(ctx.first);
+			program_contexts.append("\n");
+		}
+	}
+	rs_key_origins_set_program_contexts(rs_origins, program_contexts.c_str());
 }
 
 std::vector<KeyMapDesc> KeyMap::get_keymap_descriptions(std::string context)
@@ -530,12 +1342,18 @@ std::vector<KeyMapDesc> KeyMap::get_keymap_descriptions(std::string context)
 			continue;
 		}
 
+		if (opdesc.op == OP_DEFAULT_ACTION_PRIMARY
+			|| opdesc.op == OP_DEFAULT_ACTION_SECONDARY) {
+			// Keys bound to these are listed under what they stand for
+			continue;
+		}
+
 		bool bound_to_key = false;
 		for (const auto& keymap : keymap_[context]) {
 			const std::string& key = keymap.first;
-			const Operation op = keymap.second;
+			const Operation op = resolve_default_action(keymap.second, context);
 			if (opdesc.op == op) {
-				descs.push_back({key, opdesc.opstr, opdesc.help_text, context, opdesc.flags});
+				descs.push_back({key, opdesc.opstr, opdesc.help_text, context, opdesc.flags, opdesc.category});
 				bound_to_key = true;
 			}
 		}
@@ -544,13 +1362,35 @@ std::vector<KeyMapDesc> KeyMap::get_keymap_descriptions(std::string context)
 				"KeyMap::get_keymap_descriptions: found unbound function: %s context = %s",
 				opdesc.opstr,
 				context);
-			descs.push_back({"", opdesc.opstr, opdesc.help_text, context, opdesc.flags});
+			descs.push_back({"", opdesc.opstr, opdesc.help_text, context, opdesc.flags, opdesc.category});
+		}
+	}
+
+	// Macros are listed under the first key that calls them
+	const auto prefixes = get_keys(OP_MACROPREFIX, context);
+	if (!prefixes.empty()) {
+		for (const auto& macro : macros_) {
+			void* operations = to_operation_sequence(macro.second);
+			const std::string cmd =
+				RustString(rs_operation_sequence_serialize(operations));
+			rs_operation_sequence_free(operations);
+			const auto description = macro_descriptions_.find(macro.first);
+			descs.push_back({prefixes.front() + macro.first,
+				cmd,
+				description != macro_descriptions_.end() ? description->second : "",
+				context,
+				KM_NEWSBOAT,
+				OpCategory::MACROS});
 		}
 	}
 	return descs;
 }
 
-KeyMap::~KeyMap() {}
+KeyMap::~KeyMap()
+{
+	rs_key_origins_free(rs_origins);
+	rs_default_actions_free(rs_default_actions);
+}
 
 void KeyMap::set_key(Operation op,
 	const std::string& key,
@@ -629,7 +1469,46 @@ Operation KeyMap::get_operation(const std::string& keycode,
 	} else {
 		key = "NIL";
 	}
-	return keymap_[context][key];
+	return resolve_default_action(keymap_[context][key], context);
+}
+
+Operation KeyMap::resolve_default_action(Operation op,
+	const std::string& context)
+{
+	if (op != OP_DEFAULT_ACTION_PRIMARY && op != OP_DEFAULT_ACTION_SECONDARY) {
+		return op;
+	}
+	char* resolved = rs_default_actions_resolve(rs_default_actions,
+			context.c_str(), op == OP_DEFAULT_ACTION_SECONDARY);
+	if (resolved == nullptr) {
+		return OP_NIL;
+	}
+	const std::string opname = RustString(resolved);
+	return get_opcode(opname);
+}
+
+void KeyMap::apply_default_actions(const ConfigContainer& cfg)
+{
+	std::vector<std::string> errors;
+	const std::vector<std::string> configurable_contexts = utils::tokenize(
+			RustString(rs_default_actions_configurable_contexts()), "\n");
+	for (const auto& context : configurable_contexts) {
+		for (const bool secondary : {false, true}) {
+			const std::string option = RustString(
+					rs_default_actions_option_name(context.c_str(), secondary));
+			const std::string operation = cfg.get_configvalue(option);
+			char* error = rs_default_actions_configure(rs_default_actions,
+					context.c_str(), secondary, operation.c_str());
+			if (error != nullptr) {
+				errors.push_back(RustString(error));
+				LOG(Level::ERROR, "KeyMap::apply_default_actions: %s",
+					errors.back());
+			}
+		}
+	}
+	if (!errors.empty()) {
+		throw ConfigException(utils::join(errors, "\n"));
+	}
 }
 
 void KeyMap::dump_config(std::vector<std::string>& config_output) const
@@ -645,12 +1524,16 @@ void KeyMap::dump_config(std::vector<std::string>& config_output) const
 				configline.append(getopname(keymap.second));
 				configline.append(" ");
 				configline.append(context);
+				append_origin(configline,
+					binding_origin(context, keymap.first));
 				config_output.push_back(configline);
 			}
 		}
 	}
 	for (const auto& macro : macros_) {
-		std::string configline = "macro ";
+		std::string configline = macro_parameters_.count(macro.first) > 0
+			? "macro-with-args "
+			: "macro ";
 		configline.append(macro.first);
 		configline.append(" ");
 		for (unsigned int i = 0; i < macro.second.size(); ++i) {
@@ -664,6 +1547,13 @@ void KeyMap::dump_config(std::vector<std::string>& config_output) const
 				configline.append(" ; ");
 			}
 		}
+		const auto description = macro_descriptions_.find(macro.first);
+		if (description != macro_descriptions_.end()
+			&& !description->second.empty()) {
+			configline.append(" -- ");
+			configline.append(utils::quote(description->second));
+		}
+		append_origin(configline, macro_origin(macro.first));
 		config_output.push_back(configline);
 	}
 }
@@ -678,6 +1568,16 @@ std::string KeyMap::getopname(Operation op) const
 	return "<none>";
 }
 
+bool KeyMap::is_long_operation(Operation op) const
+{
+	for (const auto& opdesc : opdescs) {
+		if (opdesc.op == op) {
+			return opdesc.flags & KM_LONG;
+		}
+	}
+	return false;
+}
+
 void KeyMap::handle_action(const std::string& action, const std::string& params)
 {
 	/*
@@ -686,25 +1586,38 @@ void KeyMap::handle_action(const std::string& action, const std::string& params)
 	 */
 	LOG(Level::DEBUG, "KeyMap::handle_action(%s, ...) called", action);
 	if (action == "bind-key") {
-		const auto tokens = utils::tokenize_quoted(params);
-		if (tokens.size() < 2)
-			throw ConfigHandlerException(
-				ActionHandlerStatus::TOO_FEW_PARAMS);
-		std::string context = "all";
-		if (tokens.size() >= 3) {
-			context = tokens[2];
+		char* key_spec = nullptr;
+		char* context_names = nullptr;
+		char* error = nullptr;
+		void* operations = rs_parse_binding(params.c_str(), &key_spec,
+				&context_names, &error);
+		if (operations == nullptr) {
+			throw ConfigHandlerException(RustString(error));
 		}
-		if (!is_valid_context(context))
-			throw ConfigHandlerException(strprintf::fmt(
-					_("`%s' is not a valid context"), context));
-		const Operation op = get_opcode(tokens[1]);
+		const std::string text =
+			RustString(rs_operation_sequence_serialize(operations));
+		const auto sequence = take_operation_sequence(operations);
+		std::vector<std::string> binding_contexts = utils::tokenize(
+				RustString(context_names), "\n");
+		if (binding_contexts.empty()) {
+			binding_contexts.push_back("all");
+		}
+
+		// Sequences of operations are what macros are for
+		const Operation op = sequence.size() == 1 && sequence[0].size() == 1
+			? get_opcode(sequence[0][0])
+			: OP_NIL;
 		if (op == OP_NIL) {
 			throw ConfigHandlerException(
 				strprintf::fmt(_("`%s' is not a valid "
 						"key command"),
-					tokens[1]));
+					text));
+		}
+		const std::string key = canonicalize_key_name(RustString(key_spec));
+		for (const auto& context : binding_contexts) {
+			set_key(op, key, context);
+			record_binding(key, context, op);
 		}
-		set_key(op, tokens[0], context);
 	} else if (action == "unbind-key") {
 		const auto tokens = utils::tokenize_quoted(params);
 		if (tokens.size() < 1) {
@@ -717,45 +1630,285 @@ void KeyMap::handle_action(const std::string& action, const std::string& params)
 		}
 		if (tokens[0] == "-a") {
 			unset_all_keys(context);
+			rs_key_origins_unbind_all(rs_origins, context_list(context).c_str());
 		} else {
-			unset_key(tokens[0], context);
+			const std::string key = canonicalize_key_name(tokens[0]);
+			unset_key(key, context);
+			rs_key_origins_unbind(rs_origins, context_list(context).c_str(),
+				key.c_str());
 		}
 	} else if (action == "macro") {
 		std::string remaining_params = params;
 		const auto token = utils::extract_token_quoted(remaining_params);
-		const std::vector<MacroCmd> cmds = parse_operation_sequence(remaining_params);
+		std::string description;
+		void* operations = tokenize_macro_definition(remaining_params,
+				description);
+		check_arguments(operations);
+		const std::vector<MacroCmd> cmds =
+			to_macro_cmds(take_operation_sequence(operations));
+		if (!token.has_value() || cmds.empty()) {
+			throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+		}
+		const std::string macrokey = token.value();
+
+		macros_[macrokey] = cmds;
+		macro_parameters_.erase(macrokey);
+		macro_descriptions_[macrokey] = description;
+		record_macro(macrokey, cmds);
+	} else if (action == "macro-with-args") {
+		std::string remaining_params = params;
+		const auto token = utils::extract_token_quoted(remaining_params);
+		std::string description;
+		void* operations = tokenize_macro_definition(remaining_params,
+				description);
+		std::size_t parameter_count = 0;
+		char* error = nullptr;
+		if (!rs_operation_sequence_macro_parameter_count(
+				operations, &parameter_count, &error)) {
+			rs_operation_sequence_free(operations);
+			throw ConfigHandlerException(RustString(error));
+		}
+		check_arguments(operations);
+		const std::vector<MacroCmd> cmds =
+			to_macro_cmds(take_operation_sequence(operations));
 		if (!token.has_value() || cmds.empty()) {
 			throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
 		}
 		const std::string macrokey = token.value();
 
 		macros_[macrokey] = cmds;
+		macro_parameters_[macrokey] = parameter_count;
+		macro_descriptions_[macrokey] = description;
+		record_macro(macrokey, cmds);
 	} else if (action == "run-on-startup") {
-		startup_operations_sequence = parse_operation_sequence(params);
+		void* operations = tokenize_operation_sequence(params,
+				_("invalid operation sequence at column %u: %s"), true);
+		check_arguments(operations);
+		startup_operations_sequence =
+			to_macro_cmds(take_operation_sequence(operations));
 	} else {
 		throw ConfigHandlerException(ActionHandlerStatus::INVALID_PARAMS);
 	}
 }
 
+void KeyMap::set_origin(const std::string& file, unsigned int line)
+{
+	origin_file = file;
+	origin_line = line;
+}
+
+std::vector<std::string> KeyMap::get_warnings() const
+{
+	std::vector<std::string> result = warnings;
+	const std::string context_warnings =
+		RustString(rs_key_origins_context_warnings(rs_origins));
+	for (const auto& warning : utils::tokenize(context_warnings, "\n")) {
+		LOG(Level::WARN, "KeyMap::get_warnings: %s", warning);
+		result.push_back(warning);
+	}
+	return result;
+}
+
+std::string KeyMap::describe_key(const std::string& key,
+	const std::string& context) const
+{
+	char* warning = nullptr;
+	char* error = nullptr;
+	char* canonical = rs_parse_key_name(key.c_str(), &warning, &error);
+	if (canonical == nullptr) {
+		throw ConfigHandlerException(RustString(error));
+	}
+	// Describing a key isn't the place to nag about its spelling
+	RustString ignored_warning(warning);
+	const std::string name = RustString(canonical);
+
+	std::string operation;
+	const auto ctx = keymap_.find(context);
+	if (ctx != keymap_.end()) {
+		const auto binding = ctx->second.find(name);
+		if (binding != ctx->second.end() && binding->second != OP_NIL &&
+			binding->second < OP_INT_MIN) {
+			operation = getopname(binding->second);
+		}
+	}
+
+	return RustString(rs_key_origins_describe(rs_origins,
+				context.c_str(),
+				name.c_str(),
+				operation.c_str(),
+				macros_.count(name) > 0));
+}
+
+nonstd::optional<std::string> KeyMap::describe_operation(
+	const std::string& name)
+{
+	for (const auto& opdesc : opdescs) {
+		if (opdesc.opstr != name || (opdesc.flags & KM_INTERNAL)) {
+			continue;
+		}
+
+		std::string valid_contexts;
+		std::string binding_contexts;
+		std::string binding_keys;
+		for (const auto& ctx : contexts) {
+			if (!(opdesc.flags & (ctx.second | KM_SYSKEYS))) {
+				continue;
+			}
+			valid_contexts.append(ctx.first);
+			valid_contexts.append("\n");
+			for (const auto& key : get_keys(opdesc.op, ctx.first)) {
+				binding_contexts.append(ctx.first);
+				binding_contexts.append("\n");
+				binding_keys.append(key);
+				binding_keys.append("\n");
+			}
+		}
+		return std::string(RustString(rs_describe_operation(name.c_str(),
+						opdesc.help_text.c_str(),
+						valid_contexts.c_str(),
+						binding_contexts.c_str(),
+						binding_keys.c_str())));
+	}
+	return nonstd::nullopt;
+}
+
+std::vector<std::string> KeyMap::get_operation_names() const
+{
+	std::vector<std::string> names;
+	for (const auto& opdesc : opdescs) {
+		if (!(opdesc.flags & KM_INTERNAL)) {
+			names.push_back(opdesc.opstr);
+		}
+	}
+	return names;
+}
+
+void KeyMap::record_binding(const std::string& key,
+	const std::string& context,
+	Operation op)
+{
+	if (origin_file.empty()) {
+		return;
+	}
+
+	char* warning = rs_key_origins_bind(rs_origins,
+			context_list(context).c_str(),
+			key.c_str(),
+			getopname(op).c_str(),
+			origin_file.c_str(),
+			origin_line);
+	if (warning != nullptr) {
+		warnings.push_back(RustString(warning));
+		LOG(Level::WARN, "KeyMap::record_binding: %s", warnings.back());
+	}
+}
+
+void KeyMap::record_macro(const std::string& key,
+	const std::vector<MacroCmd>& cmds)
+{
+	if (origin_file.empty()) {
+		return;
+	}
+
+	std::string operations;
+	for (const auto& cmd : cmds) {
+		operations.append(getopname(cmd.op));
+		operations.append("\n");
+	}
+	char* warning = rs_key_origins_define_macro(rs_origins,
+			key.c_str(),
+			operations.c_str(),
+			origin_file.c_str(),
+			origin_line);
+	if (warning != nullptr) {
+		warnings.push_back(RustString(warning));
+		LOG(Level::WARN, "KeyMap::record_macro: %s", warnings.back());
+	}
+}
+
+void KeyMap::append_origin(std::string& configline,
+	const nonstd::optional<std::string>& origin) const
+{
+	if (origin.has_value()) {
+		configline.append(" # ");
+		configline.append(origin.value());
+	}
+}
+
+nonstd::optional<std::string> KeyMap::binding_origin(
+	const std::string& context,
+	const std::string& key) const
+{
+	char* origin = rs_key_origins_binding(rs_origins, context.c_str(),
+			key.c_str());
+	if (origin == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(origin));
+}
+
+nonstd::optional<std::string> KeyMap::macro_origin(const std::string& key) const
+{
+	char* origin = rs_key_origins_macro(rs_origins, key.c_str());
+	if (origin == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(origin));
+}
+
+std::string KeyMap::canonicalize_key_name(const std::string& key)
+{
+	char* warning = nullptr;
+	char* error = nullptr;
+	char* canonical = rs_parse_key_name(key.c_str(), &warning, &error);
+	if (canonical == nullptr) {
+		throw ConfigHandlerException(RustString(error));
+	}
+
+	if (warning != nullptr) {
+		const std::string message = RustString(warning);
+		if (warned_key_names.insert(key).second) {
+			LOG(Level::USERERROR, "%s", message);
+			std::cerr << message << std::endl;
+		}
+	}
+
+	return RustString(canonical);
+}
 
 std::vector<MacroCmd> KeyMap::parse_operation_sequence(const std::string& line)
 {
-	const auto operations = utils::tokenize_quoted(line, ";");
+	void* operations = tokenize_operation_sequence(line,
+			_("invalid operation sequence at column %u: %s"), false);
+	check_arguments(operations);
+
+	return to_macro_cmds(take_operation_sequence(operations));
+}
 
+std::vector<MacroCmd> KeyMap::to_macro_cmds(
+	const std::vector<std::vector<std::string>>& tokenized)
+{
 	std::vector<MacroCmd> cmds;
-	for (auto operation : operations) {
-		const auto command_name = utils::extract_token_quoted(operation);
-		if (!command_name.has_value()) {
+	MacroCondition condition = MacroCondition::ALWAYS;
+	for (const auto& tokens : tokenized) {
+		// The tokenizer puts connectors between operations, on their own
+		if (tokens[0] == "&&") {
+			condition = MacroCondition::IF_SUCCEEDED;
+			continue;
+		} else if (tokens[0] == "||") {
+			condition = MacroCondition::IF_FAILED;
 			continue;
 		}
-		const auto arguments = utils::tokenize_quoted(operation);
+
 		MacroCmd cmd;
-		cmd.op = get_opcode(command_name.value());
+		cmd.condition = condition;
+		condition = MacroCondition::ALWAYS;
+		cmd.op = get_opcode(tokens[0]);
 		if (cmd.op == OP_NIL) {
 			throw ConfigHandlerException(strprintf::fmt(_("`%s' is not a valid operation"),
-					command_name.value()));
+					tokens[0]));
 		}
-		cmd.args = arguments;
+		cmd.args.assign(tokens.begin() + 1, tokens.end());
 
 		cmds.push_back(cmd);
 	}
@@ -763,6 +1916,82 @@ std::vector<MacroCmd> KeyMap::parse_operation_sequence(const std::string& line)
 	return cmds;
 }
 
+nonstd::optional<std::vector<std::string>> KeyMap::tokenize_cmdline(
+	const std::string& line,
+	std::string& error)
+{
+	char* error_msg = nullptr;
+	void* operations = rs_tokenize_cmdline(line.c_str(), &error_msg);
+	if (operations == nullptr) {
+		error = RustString(error_msg);
+		return nonstd::nullopt;
+	}
+
+	const auto tokenized = take_operation_sequence(operations);
+	if (tokenized.empty()) {
+		return std::vector<std::string>();
+	}
+	return tokenized[0];
+}
+
+nonstd::optional<std::vector<MacroCmd>> KeyMap::parse_run_cmdline(
+	const std::string& line)
+{
+	void* operations = rs_tokenize_run_cmdline(line.c_str());
+	if (operations == nullptr) {
+		return nonstd::nullopt;
+	}
+	check_arguments(operations);
+	return to_macro_cmds(take_operation_sequence(operations));
+}
+
+nonstd::optional<std::string> KeyMap::get_macro_cmdline(
+	const std::string& key,
+	std::string& error) const
+{
+	const auto macro = macros_.find(key);
+	if (macro == macros_.end()) {
+		std::vector<std::string> keys;
+		for (const auto& m : macros_) {
+			keys.push_back(m.first);
+		}
+		if (keys.empty()) {
+			error = strprintf::fmt(_("No macro bound to `%s'; no macros are defined"),
+					key);
+		} else {
+			error = strprintf::fmt(_("No macro bound to `%s'; defined macros: %s"),
+					key,
+					utils::join(keys, ", "));
+		}
+		return nonstd::nullopt;
+	}
+
+	void* operations = to_operation_sequence(macro->second);
+	const std::string cmdline =
+		RustString(rs_operation_sequence_to_run_cmdline(operations));
+	rs_operation_sequence_free(operations);
+	return cmdline;
+}
+
+void* KeyMap::to_operation_sequence(const std::vector<MacroCmd>& cmds) const
+{
+	void* operations = rs_operation_sequence_new();
+	for (const auto& cmd : cmds) {
+		if (cmd.condition == MacroCondition::IF_SUCCEEDED) {
+			rs_operation_sequence_push_token(operations, "&&", true);
+		} else if (cmd.condition == MacroCondition::IF_FAILED) {
+			rs_operation_sequence_push_token(operations, "||", true);
+		}
+		rs_operation_sequence_push_token(operations,
+			getopname(cmd.op).c_str(),
+			true);
+		for (const auto& arg : cmd.args) {
+			rs_operation_sequence_push_token(operations, arg.c_str(), false);
+		}
+	}
+	return operations;
+}
+
 std::vector<MacroCmd> KeyMap::get_startup_operation_sequence()
 {
 	return startup_operations_sequence;
@@ -773,7 +2002,8 @@ std::vector<std::string> KeyMap::get_keys(Operation op,
 {
 	std::vector<std::string> keys;
 	for (const auto& keymap : keymap_[context]) {
-		if (keymap.second == op) {
+		if (keymap.second == op
+			|| resolve_default_action(keymap.second, context) == op) {
 			keys.push_back(keymap.first);
 		}
 	}
@@ -788,17 +2018,43 @@ std::vector<MacroCmd> KeyMap::get_macro(const std::string& key)
 	return {};
 }
 
-bool KeyMap::is_valid_context(const std::string& context)
+unsigned int KeyMap::get_macro_parameter_count(const std::string& key) const
 {
-	if (context == "all") {
-		return true;
+	const auto parameters = macro_parameters_.find(key);
+	if (parameters == macro_parameters_.end()) {
+		return 0;
 	}
+	return parameters->second;
+}
 
-	if (contexts.count(context) >= 1) {
-		return true;
+std::vector<MacroCmd> KeyMap::get_macro(const std::string& key,
+	const std::vector<std::string>& arguments)
+{
+	const auto macro = macros_.find(key);
+	if (macro == macros_.end()) {
+		return {};
 	}
 
-	return false;
+	std::vector<const char*> c_arguments;
+	for (const auto& argument : arguments) {
+		c_arguments.push_back(argument.c_str());
+	}
+	void* operations = to_operation_sequence(macro->second);
+	void* substituted = rs_operation_sequence_substitute_macro_arguments(
+			operations, c_arguments.data(), c_arguments.size());
+	rs_operation_sequence_free(operations);
+	return to_macro_cmds(take_operation_sequence(substituted));
+}
+
+nonstd::optional<std::string> KeyMap::next_macro_argument_prompt(
+	unsigned int count,
+	unsigned int supplied)
+{
+	char* prompt = rs_next_macro_argument_prompt(count, supplied);
+	if (prompt == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(prompt));
 }
 
 std::map<std::string, Operation> KeyMap::get_internal_operations() const
@@ -18,39 +18,73 @@ ListFormatter::~ListFormatter() {}
 
 void ListFormatter::add_line(const std::string& text)
 {
-	set_line(UINT_MAX, text);
+	add_lines({text});
 	LOG(Level::DEBUG, "ListFormatter::add_line: `%s'", text);
 }
 
 void ListFormatter::set_line(const unsigned int itempos,
 	const std::string& text)
 {
-	std::vector<std::string> formatted_text;
+	set_lines(itempos, {text});
+}
+
+void ListFormatter::add_lines(const std::vector<std::string>& rows)
+{
+	set_lines(UINT_MAX, rows);
+}
 
-	formatted_text.push_back(utils::wstr2str(utils::clean_nonprintable_characters(
-				utils::str2wstr(text))));
+void ListFormatter::set_lines(const unsigned int itempos,
+	const std::vector<std::string>& rows)
+{
+	std::vector<std::string> formatted_rows;
+	for (const auto& row : rows) {
+		formatted_rows.push_back(utils::wstr2str(
+				utils::clean_nonprintable_characters(utils::str2wstr(row))));
+	}
+	// Every item takes up at least one row
+	if (formatted_rows.empty()) {
+		formatted_rows.push_back("");
+	}
 
 	if (itempos == UINT_MAX) {
-		lines.insert(lines.cend(),
-			formatted_text.cbegin(),
-			formatted_text.cend());
+		items.push_back(formatted_rows);
 	} else {
-		lines[itempos] = formatted_text[0];
+		items[itempos] = formatted_rows;
 	}
 }
 
 std::string ListFormatter::format_list() const
 {
 	std::string format_cache = "{list";
-	for (auto str : lines) {
-		if (rxman) {
-			rxman->quote_and_highlight(str, location);
+	for (const auto& rows : items) {
+		for (auto str : rows) {
+			if (rxman) {
+				rxman->quote_and_highlight(str, location);
+			}
+			format_cache.append(strprintf::fmt(
+					"{listitem text:%s}", Stfl::quote(str)));
 		}
-		format_cache.append(strprintf::fmt(
-				"{listitem text:%s}", Stfl::quote(str)));
 	}
 	format_cache.push_back('}');
 	return format_cache;
 }
 
+unsigned int ListFormatter::get_lines_count() const
+{
+	unsigned int count = 0;
+	for (const auto& rows : items) {
+		count += rows.size();
+	}
+	return count;
+}
+
+std::vector<std::uint32_t> ListFormatter::get_row_counts() const
+{
+	std::vector<std::uint32_t> counts;
+	for (const auto& rows : items) {
+		counts.push_back(rows.size());
+	}
+	return counts;
+}
+
 } // namespace newsboat
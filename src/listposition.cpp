@@ -0,0 +1,134 @@
+#include "listposition.h"
+
+#include <cstdint>
+#include <memory>
+
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_unread_index_new();
+
+	void rs_unread_index_free(void* index);
+
+	void rs_unread_index_reset(void* index,
+		const char* const* keys,
+		const bool* unread,
+		std::size_t count);
+
+	void rs_unread_index_update(void* index, const char* key, bool unread);
+
+	bool rs_unread_index_first(void* index, std::size_t* position);
+
+	bool rs_unread_index_last(void* index, std::size_t* position);
+
+	std::size_t rs_unread_index_unread_beside(void* index,
+		std::size_t cursor,
+		bool below,
+		std::size_t* positions,
+		std::size_t capacity);
+
+	bool rs_percent_position(std::uint32_t percent,
+		std::size_t len,
+		std::size_t* position);
+
+	bool rs_parse_percent(const char* const* arguments,
+		std::size_t count,
+		std::uint32_t* percent,
+		char** error);
+}
+
+namespace newsboat {
+
+UnreadIndex::UnreadIndex()
+{
+	rs_index = rs_unread_index_new();
+}
+
+UnreadIndex::~UnreadIndex()
+{
+	rs_unread_index_free(rs_index);
+}
+
+void UnreadIndex::reset(const std::vector<std::string>& keys,
+	const std::vector<bool>& unread)
+{
+	std::vector<const char*> c_keys;
+	c_keys.reserve(keys.size());
+	for (const auto& key : keys) {
+		c_keys.push_back(key.c_str());
+	}
+	// std::vector<bool> doesn't store an array of bools
+	std::unique_ptr<bool[]> c_unread(new bool[unread.size()]);
+	for (std::size_t i = 0; i < unread.size(); ++i) {
+		c_unread[i] = unread[i];
+	}
+	rs_unread_index_reset(rs_index, c_keys.data(), c_unread.get(),
+		c_keys.size());
+}
+
+void UnreadIndex::update(const std::string& key, bool unread)
+{
+	rs_unread_index_update(rs_index, key.c_str(), unread);
+}
+
+nonstd::optional<unsigned int> UnreadIndex::first() const
+{
+	std::size_t position = 0;
+	if (rs_unread_index_first(rs_index, &position)) {
+		return position;
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<unsigned int> UnreadIndex::last() const
+{
+	std::size_t position = 0;
+	if (rs_unread_index_last(rs_index, &position)) {
+		return position;
+	}
+	return nonstd::nullopt;
+}
+
+std::vector<std::size_t> UnreadIndex::unread_beside(std::size_t cursor,
+	bool below) const
+{
+	const std::size_t count = rs_unread_index_unread_beside(
+			rs_index, cursor, below, nullptr, 0);
+	std::vector<std::size_t> positions(count);
+	if (count > 0) {
+		rs_unread_index_unread_beside(
+			rs_index, cursor, below, positions.data(), count);
+	}
+	return positions;
+}
+
+nonstd::optional<unsigned int> percent_position(unsigned int percent,
+	unsigned int size)
+{
+	std::size_t position = 0;
+	if (rs_percent_position(percent, size, &position)) {
+		return position;
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<unsigned int> parse_percent(
+	const std::vector<std::string>& arguments,
+	std::string& error)
+{
+	std::vector<const char*> c_arguments;
+	c_arguments.reserve(arguments.size());
+	for (const auto& argument : arguments) {
+		c_arguments.push_back(argument.c_str());
+	}
+	std::uint32_t percent = 0;
+	char* c_error = nullptr;
+	if (rs_parse_percent(c_arguments.data(), c_arguments.size(), &percent,
+			&c_error)) {
+		return percent;
+	}
+	error = RustString(c_error);
+	return nonstd::nullopt;
+}
+
+} // namespace newsboat
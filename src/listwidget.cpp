@@ -4,6 +4,25 @@
 
 #include "utils.h"
 
+extern "C" {
+	void* rs_row_groups_new();
+	void rs_row_groups_free(void* groups);
+	void rs_row_groups_set(void* groups,
+		const std::uint32_t* row_counts,
+		std::size_t count);
+	void rs_row_groups_set_uniform(void* groups, std::uint32_t items);
+	std::uint32_t rs_row_groups_item_count(void* groups);
+	std::uint32_t rs_row_groups_first_row(void* groups, std::uint32_t item);
+	std::uint32_t rs_row_groups_last_row(void* groups, std::uint32_t item);
+	std::uint32_t rs_row_groups_item_at_row(void* groups, std::uint32_t row);
+	std::uint32_t rs_row_groups_page_down(void* groups,
+		std::uint32_t item,
+		std::uint32_t height);
+	std::uint32_t rs_row_groups_page_up(void* groups,
+		std::uint32_t item,
+		std::uint32_t height);
+}
+
 namespace newsboat {
 
 ListWidget::ListWidget(const std::string& list_name, Stfl::Form& form,
@@ -12,19 +31,28 @@ ListWidget::ListWidget(const std::string& list_name, Stfl::Form& form,
 	, form(form)
 	, num_lines(0)
 	, num_context_lines(scrolloff)
+	, rs_row_groups(rs_row_groups_new())
+{
+}
+
+ListWidget::~ListWidget()
 {
+	rs_row_groups_free(rs_row_groups);
 }
 
 void ListWidget::stfl_replace_list(std::uint32_t number_of_lines,
 	std::string stfl)
 {
 	num_lines = number_of_lines;
+	rs_row_groups_set_uniform(rs_row_groups, number_of_lines);
 	form.modify(list_name, "replace", stfl);
 }
 
 void ListWidget::stfl_replace_lines(const ListFormatter& listfmt)
 {
 	num_lines = listfmt.get_lines_count();
+	const auto row_counts = listfmt.get_row_counts();
+	rs_row_groups_set(rs_row_groups, row_counts.data(), row_counts.size());
 	form.modify(list_name, "replace_inner", listfmt.format_list());
 }
 
@@ -43,11 +71,12 @@ bool ListWidget::move_up(bool wrap_scroll)
 
 bool ListWidget::move_down(bool wrap_scroll)
 {
-	if (num_lines == 0) {
+	const std::uint32_t num_items = get_item_count();
+	if (num_items == 0) {
 		// Ignore if list is empty
 		return false;
 	}
-	const std::uint32_t maxpos = num_lines - 1;
+	const std::uint32_t maxpos = num_items - 1;
 	const std::uint32_t curpos = get_position();
 	if (curpos + 1 <= maxpos) {
 		set_position(curpos + 1);
@@ -66,42 +95,38 @@ void ListWidget::move_to_first()
 
 void ListWidget::move_to_last()
 {
-	if (num_lines == 0) {
+	const std::uint32_t num_items = get_item_count();
+	if (num_items == 0) {
 		// Ignore if list is empty
 		return;
 	}
-	const std::uint32_t maxpos = num_lines - 1;
+	const std::uint32_t maxpos = num_items - 1;
 	set_position(maxpos);
 }
 
 void ListWidget::move_page_up(bool wrap_scroll)
 {
 	const std::uint32_t curpos = get_position();
-	const std::uint32_t list_height = get_height();
-	if (curpos > list_height) {
-		set_position(curpos - list_height);
-	} else if (wrap_scroll && curpos == 0) {
+	if (wrap_scroll && curpos == 0) {
 		move_to_last();
 	} else {
-		set_position(0);
+		set_position(rs_row_groups_page_up(rs_row_groups, curpos, get_height()));
 	}
 }
 
 void ListWidget::move_page_down(bool wrap_scroll)
 {
-	if (num_lines == 0) {
+	const std::uint32_t num_items = get_item_count();
+	if (num_items == 0) {
 		// Ignore if list is empty
 		return;
 	}
-	const std::uint32_t maxpos = num_lines - 1;
+	const std::uint32_t maxpos = num_items - 1;
 	const std::uint32_t curpos = get_position();
-	const std::uint32_t list_height = get_height();
-	if (curpos + list_height < maxpos) {
-		set_position(curpos + list_height);
-	} else if (wrap_scroll && curpos == maxpos) {
+	if (wrap_scroll && curpos == maxpos) {
 		move_to_first();
 	} else {
-		set_position(maxpos);
+		set_position(rs_row_groups_page_down(rs_row_groups, curpos, get_height()));
 	}
 }
 
@@ -109,15 +134,20 @@ std::uint32_t ListWidget::get_position()
 {
 	const std::string pos = form.get(list_name + "_pos");
 	if (!pos.empty()) {
-		return std::max(0, std::stoi(pos));
+		const std::uint32_t row = std::max(0, std::stoi(pos));
+		return rs_row_groups_item_at_row(rs_row_groups, row);
 	}
 	return 0;
 }
 
 void ListWidget::set_position(std::uint32_t pos)
 {
-	form.set(list_name + "_pos", std::to_string(pos));
-	update_scroll_offset(pos);
+	// The cursor sits on the first row of an item, but scrolling takes all
+	// of its rows into account
+	const std::uint32_t first_row = rs_row_groups_first_row(rs_row_groups, pos);
+	const std::uint32_t last_row = rs_row_groups_last_row(rs_row_groups, pos);
+	form.set(list_name + "_pos", std::to_string(first_row));
+	update_scroll_offset(first_row, last_row);
 }
 
 std::uint32_t ListWidget::get_width()
@@ -144,7 +174,13 @@ void ListWidget::set_scroll_offset(std::uint32_t offset)
 	form.set(list_name + "_offset", std::to_string(offset));
 }
 
-void ListWidget::update_scroll_offset(std::uint32_t pos)
+std::uint32_t ListWidget::get_item_count()
+{
+	return rs_row_groups_item_count(rs_row_groups);
+}
+
+void ListWidget::update_scroll_offset(std::uint32_t first_row,
+	std::uint32_t last_row)
 {
 	// In STFL, "offset" is how many items at the beginning of the list are
 	// hidden off-screen. That's how scrolling is implemented: to scroll down,
@@ -153,9 +189,9 @@ void ListWidget::update_scroll_offset(std::uint32_t pos)
 	// bounds we set.
 	//
 	// All the lines that are visible because of "scrolloff" setting are called
-	// "context" here. They include the current line under cursor (which has
-	// position "pos"), "cur_scroll_offset" lines above it, and
-	// "cur_scroll_offset" lines below it.
+	// "context" here. They include the current item under cursor (which
+	// spans rows "first_row" to "last_row"), "cur_scroll_offset" lines above
+	// it, and "cur_scroll_offset" lines below it.
 
 	const auto h = get_height();
 	const auto cur_scroll_offset = get_scroll_offset();
@@ -168,9 +204,9 @@ void ListWidget::update_scroll_offset(std::uint32_t pos)
 	if (2 * num_context_lines < h) {
 		// Check if items at the bottom of the "context" are visible. If not,
 		// we'll have to scroll down.
-		if (pos + num_context_lines >= cur_scroll_offset + h) {
-			if (pos + num_context_lines >= h) {
-				const std::uint32_t target_offset = pos + num_context_lines - h + 1;
+		if (last_row + num_context_lines >= cur_scroll_offset + h) {
+			if (last_row + num_context_lines >= h) {
+				const std::uint32_t target_offset = last_row + num_context_lines - h + 1;
 				set_scroll_offset(std::min(target_offset, max_offset));
 			} else { // "pos" is towards the beginning of the list; don't scroll
 				set_scroll_offset(0);
@@ -179,16 +215,16 @@ void ListWidget::update_scroll_offset(std::uint32_t pos)
 
 		// Check if items at the top of the "context" are visible. If not,
 		// we'll have to scroll up.
-		if (pos < cur_scroll_offset + num_context_lines) {
-			if (pos >= num_context_lines) {
-				set_scroll_offset(pos - num_context_lines);
+		if (first_row < cur_scroll_offset + num_context_lines) {
+			if (first_row >= num_context_lines) {
+				set_scroll_offset(first_row - num_context_lines);
 			} else { // "pos" is towards the beginning of the list; don't scroll
 				set_scroll_offset(0);
 			}
 		}
 	} else { // Keep selected item in the middle
-		if (pos > h / 2) {
-			const std::uint32_t target_offset = pos - h / 2;
+		if (first_row > h / 2) {
+			const std::uint32_t target_offset = first_row - h / 2;
 			set_scroll_offset(std::min(target_offset, max_offset));
 		} else { // "pos" is towards the beginning of the list; don't scroll
 			set_scroll_offset(0);
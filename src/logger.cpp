@@ -4,6 +4,11 @@ extern "C" {
 	void rs_set_logfile(const char* logfile);
 	void rs_set_user_error_logfile(const char* logfile);
 	void rs_set_loglevel(newsboat::Level level);
+	void rs_set_module_levels(const char* const* modules,
+		const newsboat::Level* levels,
+		std::size_t count);
+	void rs_set_log_max_size(uint64_t bytes);
+	void rs_set_log_keep_files(std::size_t n);
 }
 
 namespace newsboat {
@@ -22,4 +27,26 @@ void Logger::set_loglevel(Level l)
 	rs_set_loglevel(l);
 }
 
+void Logger::set_module_levels(
+	const std::vector<std::pair<std::string, Level>>& levels)
+{
+	std::vector<const char*> modules;
+	std::vector<Level> module_levels;
+	for (const auto& level : levels) {
+		modules.push_back(level.first.c_str());
+		module_levels.push_back(level.second);
+	}
+	rs_set_module_levels(modules.data(), module_levels.data(), levels.size());
+}
+
+void Logger::set_max_size(uint64_t bytes)
+{
+	rs_set_log_max_size(bytes);
+}
+
+void Logger::set_keep_files(std::size_t n)
+{
+	rs_set_log_keep_files(n);
+}
+
 } // namespace newsboat
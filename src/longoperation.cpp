@@ -0,0 +1,56 @@
+#include "longoperation.h"
+
+#include <cstdint>
+
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_long_operation_start(const char* name);
+
+	void rs_long_operation_free(void* operation);
+
+	char* rs_long_operation_status(void* operation);
+
+	char* rs_long_operation_finish(void* operation,
+		bool succeeded,
+		std::uint64_t notify_threshold_s);
+}
+
+namespace newsboat {
+
+LongOperation::LongOperation(const std::string& name)
+{
+	rs_object = rs_long_operation_start(name.c_str());
+}
+
+LongOperation::~LongOperation()
+{
+	rs_long_operation_free(rs_object);
+}
+
+std::string LongOperation::status() const
+{
+	if (rs_object == nullptr) {
+		return "";
+	}
+	return RustString(rs_long_operation_status(rs_object));
+}
+
+nonstd::optional<std::string> LongOperation::finish(bool succeeded,
+	unsigned int notify_threshold_s)
+{
+	if (rs_object == nullptr) {
+		return nonstd::nullopt;
+	}
+
+	// Finishing frees the Rust object
+	char* summary = rs_long_operation_finish(rs_object, succeeded,
+			notify_threshold_s);
+	rs_object = nullptr;
+	if (summary == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(summary));
+}
+
+} // namespace newsboat
@@ -0,0 +1,103 @@
+#include "markreadpolicy.h"
+
+#include "ruststring.h"
+
+extern "C" {
+	char* rs_mark_read_policy_validate(const char* value);
+
+	void* rs_mark_read_policy_new();
+
+	void rs_mark_read_policy_free(void* policy);
+
+	bool rs_mark_read_policy_set(void* policy, const char* value);
+
+	std::int64_t rs_mark_read_policy_dwell_ms(void* policy);
+
+	bool rs_mark_read_policy_should_mark_read(void* policy,
+		std::uint8_t trigger,
+		std::uint64_t dwelled_ms,
+		bool at_end);
+
+	void* rs_dwell_timer_new();
+
+	void rs_dwell_timer_free(void* timer);
+
+	void rs_dwell_timer_start(void* timer, std::int64_t threshold_ms);
+
+	void rs_dwell_timer_cancel(void* timer);
+
+	std::uint64_t rs_dwell_timer_elapsed_ms(void* timer);
+
+	std::int64_t rs_dwell_timer_time_left_ms(void* timer);
+}
+
+namespace newsboat {
+
+nonstd::optional<std::string> MarkReadPolicy::validate(const std::string& value)
+{
+	char* error = rs_mark_read_policy_validate(value.c_str());
+	if (error == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(error));
+}
+
+MarkReadPolicy::MarkReadPolicy()
+{
+	rs_policy = rs_mark_read_policy_new();
+}
+
+MarkReadPolicy::~MarkReadPolicy()
+{
+	rs_mark_read_policy_free(rs_policy);
+}
+
+bool MarkReadPolicy::set(const std::string& value)
+{
+	return rs_mark_read_policy_set(rs_policy, value.c_str());
+}
+
+std::int64_t MarkReadPolicy::dwell_ms()
+{
+	return rs_mark_read_policy_dwell_ms(rs_policy);
+}
+
+bool MarkReadPolicy::should_mark_read(Trigger trigger, const Context& context)
+{
+	return rs_mark_read_policy_should_mark_read(rs_policy,
+			static_cast<std::uint8_t>(trigger),
+			context.dwelled_ms,
+			context.at_end);
+}
+
+DwellTimer::DwellTimer()
+{
+	rs_timer = rs_dwell_timer_new();
+}
+
+DwellTimer::~DwellTimer()
+{
+	rs_dwell_timer_free(rs_timer);
+}
+
+void DwellTimer::start(std::int64_t threshold_ms)
+{
+	rs_dwell_timer_start(rs_timer, threshold_ms);
+}
+
+void DwellTimer::cancel()
+{
+	rs_dwell_timer_cancel(rs_timer);
+}
+
+std::uint64_t DwellTimer::elapsed_ms()
+{
+	return rs_dwell_timer_elapsed_ms(rs_timer);
+}
+
+std::int64_t DwellTimer::time_left_ms()
+{
+	return rs_dwell_timer_time_left_ms(rs_timer);
+}
+
+} // namespace newsboat
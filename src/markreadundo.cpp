@@ -0,0 +1,69 @@
+#include "markreadundo.h"
+
+#include <cstddef>
+
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_mark_read_undo_new();
+
+	void rs_mark_read_undo_free(void* undo);
+
+	void rs_mark_read_undo_record(void* undo,
+		const char* const* keys,
+		std::size_t count);
+
+	void* rs_mark_read_undo_undo(void* undo);
+
+	void rs_mark_read_undo_clear(void* undo);
+
+	void rs_mark_read_undo_keys_free(void* keys);
+
+	std::size_t rs_mark_read_undo_keys_count(void* keys);
+
+	char* rs_mark_read_undo_key(void* keys, std::size_t index);
+}
+
+namespace newsboat {
+
+MarkReadUndo::MarkReadUndo()
+{
+	rs_undo = rs_mark_read_undo_new();
+}
+
+MarkReadUndo::~MarkReadUndo()
+{
+	rs_mark_read_undo_free(rs_undo);
+}
+
+void MarkReadUndo::record(const std::vector<std::string>& guids)
+{
+	std::vector<const char*> c_guids;
+	c_guids.reserve(guids.size());
+	for (const auto& guid : guids) {
+		c_guids.push_back(guid.c_str());
+	}
+	rs_mark_read_undo_record(rs_undo, c_guids.data(), c_guids.size());
+}
+
+nonstd::optional<std::vector<std::string>> MarkReadUndo::undo()
+{
+	void* keys = rs_mark_read_undo_undo(rs_undo);
+	if (keys == nullptr) {
+		return nonstd::nullopt;
+	}
+	std::vector<std::string> guids;
+	const std::size_t count = rs_mark_read_undo_keys_count(keys);
+	for (std::size_t i = 0; i < count; ++i) {
+		guids.push_back(RustString(rs_mark_read_undo_key(keys, i)));
+	}
+	rs_mark_read_undo_keys_free(keys);
+	return guids;
+}
+
+void MarkReadUndo::clear()
+{
+	rs_mark_read_undo_clear(rs_undo);
+}
+
+} // namespace newsboat
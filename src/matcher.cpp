@@ -1,21 +1,82 @@
 #include "matcher.h"
 
-#include <cassert>
-#include <cinttypes>
-#include <ctime>
-#include <regex.h>
-#include <sstream>
-#include <vector>
+#include <cstdint>
+#include <exception>
 
 #include "logger.h"
 #include "matchable.h"
 #include "matcherexception.h"
+#include "ruststring.h"
 #include "scopemeasure.h"
-#include "utils.h"
 
-namespace newsboat {
+namespace {
+
+/// The item being matched, along with the storage that the callbacks below
+/// need.
+struct MatchContext {
+	newsboat::Matchable* item;
+
+	/// The last attribute value handed to Rust, which copies it before
+	/// asking for another one.
+	std::string value;
+
+	/// An exception thrown by the item, rethrown once we're back from Rust.
+	std::exception_ptr exception;
+};
+
+} // namespace
+
+extern "C" {
+	void* rs_matcher_parse(const char* expr, char** error);
+
+	void rs_matcher_free(void* matcher);
+
+	bool rs_matcher_matches(void* matcher,
+		void* item,
+		const char* (*attribute_value)(void* item, const char* attr),
+		bool (*date_timestamp)(void* item, const char* attr,
+			std::int64_t* timestamp),
+		bool* matched,
+		newsboat::MatcherErrorFfi* error);
+
+	// Exceptions can't unwind through Rust, so these stash them in the
+	// context and report the attribute as unavailable.
+
+	static const char* matchable_attribute_value(void* item, const char* attr)
+	{
+		auto context = static_cast<MatchContext*>(item);
+		try {
+			const auto value = context->item->attribute_value(attr);
+			if (!value.has_value()) {
+				return nullptr;
+			}
+			context->value = value.value();
+			return context->value.c_str();
+		} catch (...) {
+			context->exception = std::current_exception();
+			return nullptr;
+		}
+	}
 
-Matcher::Matcher() {}
+	static bool matchable_date_timestamp(void* item, const char* attr,
+		std::int64_t* timestamp)
+	{
+		auto context = static_cast<MatchContext*>(item);
+		try {
+			const auto value = context->item->date_timestamp(attr);
+			if (!value.has_value()) {
+				return false;
+			}
+			*timestamp = value.value();
+			return true;
+		} catch (...) {
+			context->exception = std::current_exception();
+			return false;
+		}
+	}
+}
+
+namespace newsboat {
 
 Matcher::Matcher(const std::string& expr)
 	: exp(expr)
@@ -23,6 +84,11 @@ Matcher::Matcher(const std::string& expr)
 	parse(expr);
 }
 
+Matcher::~Matcher()
+{
+	rs_matcher_free(rs_object);
+}
+
 std::string Matcher::get_expression()
 {
 	return exp;
@@ -34,12 +100,15 @@ bool Matcher::parse(const std::string& expr)
 
 	errmsg = "";
 
-	bool b = p.parse_string(expr);
+	rs_matcher_free(rs_object);
+	char* error = nullptr;
+	rs_object = rs_matcher_parse(expr.c_str(), &error);
 
+	const bool b = rs_object != nullptr;
 	if (b) {
 		exp = expr;
 	} else {
-		errmsg = utils::wstr2str(p.get_error());
+		errmsg = RustString(error);
 	}
 
 	LOG(Level::DEBUG,
@@ -67,171 +136,42 @@ bool Matcher::matches(Matchable* item)
 	 * measured (and felt by the user) on slow computers with a lot of items
 	 * to match.
 	 */
-	bool retval = false;
-	if (item) {
-		ScopeMeasure m1("Matcher::matches");
-		retval = matches_r(p.get_root(), item);
-	}
-	return retval;
-}
-
-std::string get_attr_or_throw(Matchable* item, const std::string& attr_name)
-{
-	const auto attr = item->attribute_value(attr_name);
-
-	if (!attr.has_value()) {
-		LOG(Level::WARN,
-			"Matcher::matches: attribute %s is not available",
-			attr_name);
-		throw MatcherException(MatcherException::Type::ATTRIB_UNAVAIL, attr_name);
-	}
-
-	return attr.value();
-}
-
-bool Matcher::matchop_lt(expression* e, Matchable* item)
-{
-	const int ilit = string_to_num(e->literal);
-
-	const auto attr = get_attr_or_throw(item, e->name);
-	const int iatt = string_to_num(attr);
-
-	return iatt < ilit;
-}
-
-bool Matcher::matchop_between(expression* e, Matchable* item)
-{
-	const auto attr = get_attr_or_throw(item, e->name);
-	const int att = string_to_num(attr);
-
-	const std::vector<std::string> lit = utils::tokenize(e->literal, ":");
-	if (lit.size() < 2) {
+	if (!item) {
 		return false;
 	}
-
-	int i1 = string_to_num(lit[0]);
-	int i2 = string_to_num(lit[1]);
-	if (i1 > i2) {
-		std::swap(i1, i2);
-	}
-
-	return (att >= i1 && att <= i2);
-}
-
-bool Matcher::matchop_gt(expression* e, Matchable* item)
-{
-	const auto attr = get_attr_or_throw(item, e->name);
-	const int iatt = string_to_num(attr);
-
-	const int ilit = string_to_num(e->literal);
-
-	return iatt > ilit;
-}
-
-bool Matcher::matchop_rxeq(expression* e, Matchable* item)
-{
-	const auto attr = get_attr_or_throw(item, e->name);
-
-	if (!e->regex) {
-		e->regex = new regex_t;
-		int err;
-		if ((err = regcomp(e->regex,
-						e->literal.c_str(),
-						REG_EXTENDED | REG_ICASE | REG_NOSUB)) != 0) {
-			delete e->regex;
-			e->regex = nullptr;
-			char buf[1024];
-			regerror(err, e->regex, buf, sizeof(buf));
-			throw MatcherException(
-				MatcherException::Type::INVALID_REGEX,
-				e->literal,
-				buf);
-		}
-	}
-	if (regexec(e->regex,
-			attr.c_str(),
-			0,
-			nullptr,
-			0) == 0) {
+	if (!rs_object) {
+		// Nothing was parsed, so there is nothing to filter out
 		return true;
 	}
-	return false;
-}
-
-bool Matcher::matchop_cont(expression* e, Matchable* item)
-{
-	const auto attr = get_attr_or_throw(item, e->name);
 
-	const std::vector<std::string> elements = utils::tokenize(attr, " ");
-	const std::string literal = e->literal;
-	for (const auto& elem : elements) {
-		if (literal == elem) {
-			return true;
+	ScopeMeasure m1("Matcher::matches", 50, Level::INFO);
+
+	MatchContext context{item, {}, nullptr};
+	bool matched = false;
+	MatcherErrorFfi error;
+	const bool ok = rs_matcher_matches(rs_object,
+			&context,
+			matchable_attribute_value,
+			matchable_date_timestamp,
+			&matched,
+			&error);
+	if (context.exception) {
+		if (!ok) {
+			// Free the strings of the error
+			MatcherException::from_rust_error(error);
 		}
+		std::rethrow_exception(context.exception);
 	}
-	return false;
-}
-
-bool Matcher::matchop_eq(expression* e, Matchable* item)
-{
-	const auto attr = get_attr_or_throw(item, e->name);
-
-	return (attr == e->literal);
-}
-
-bool Matcher::matches_r(expression* e, Matchable* item)
-{
-	if (e) {
-		switch (e->op) {
-		/* the operator "and" and "or" simply connect two different
-		 * subexpressions */
-		case LOGOP_AND:
-			// short-circuit evaluation in C -> short circuit evaluation in the filter language
-			return matches_r(e->l, item) &&
-				matches_r(e->r, item);
-
-		case LOGOP_OR:
-			return matches_r(e->l, item) ||
-				matches_r(e->r, item); // same here
-
-		/* while the other operator connect an attribute with a value */
-		case MATCHOP_EQ:
-			return matchop_eq(e, item);
-
-		case MATCHOP_NE:
-			return !matchop_eq(e, item);
-
-		case MATCHOP_LT:
-			return matchop_lt(e, item);
-
-		case MATCHOP_BETWEEN:
-			return matchop_between(e, item);
-
-		case MATCHOP_GT:
-			return matchop_gt(e, item);
-
-		case MATCHOP_LE:
-			return !matchop_gt(e, item);
-
-		case MATCHOP_GE:
-			return !matchop_lt(e, item);
-
-		case MATCHOP_RXEQ:
-			return matchop_rxeq(e, item);
-
-		case MATCHOP_RXNE:
-			return !matchop_rxeq(e, item);
-
-		case MATCHOP_CONTAINS:
-			return matchop_cont(e, item);
-
-		case MATCHOP_CONTAINSNOT:
-			return !matchop_cont(e, item);
+	if (!ok) {
+		const auto e = MatcherException::from_rust_error(error);
+		if (e.type() == MatcherException::Type::ATTRIB_UNAVAIL) {
+			LOG(Level::WARN,
+				"Matcher::matches: attribute %s is not available",
+				e.info());
 		}
-		return false;
-	} else {
-		return true; // shouldn't happen
+		throw e;
 	}
+	return matched;
 }
 
 std::string Matcher::get_parse_error()
@@ -239,11 +179,4 @@ std::string Matcher::get_parse_error()
 	return errmsg;
 }
 
-int Matcher::string_to_num(const std::string& number)
-{
-	int result = 0;
-	std::istringstream(number) >> result;
-	return result;
-}
-
 } // namespace newsboat
@@ -0,0 +1,63 @@
+#include "memoryreport.h"
+
+#include <cstdint>
+
+#include "rssitem.h"
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_memory_report_new();
+
+	void rs_memory_report_free(void* report);
+
+	void rs_memory_report_add_dialog(void* report, const char* title);
+
+	void rs_memory_report_add_item(void* report,
+		std::uintptr_t address,
+		std::uint64_t bytes);
+
+	std::uint64_t rs_memory_report_distinct_items(void* report);
+
+	char* rs_memory_report_lines(void* report);
+}
+
+namespace newsboat {
+
+MemoryReport::MemoryReport()
+{
+	rs_report = rs_memory_report_new();
+}
+
+MemoryReport::~MemoryReport()
+{
+	rs_memory_report_free(rs_report);
+}
+
+void MemoryReport::add_dialog(const std::string& title)
+{
+	rs_memory_report_add_dialog(rs_report, title.c_str());
+}
+
+void MemoryReport::add_item(const std::shared_ptr<RssItem>& item)
+{
+	if (!item) {
+		return;
+	}
+	rs_memory_report_add_item(rs_report,
+		reinterpret_cast<std::uintptr_t>(item.get()),
+		item->byte_size());
+}
+
+unsigned int MemoryReport::distinct_items() const
+{
+	return rs_memory_report_distinct_items(rs_report);
+}
+
+std::vector<std::string> MemoryReport::lines() const
+{
+	const std::string lines = RustString(rs_memory_report_lines(rs_report));
+	return utils::tokenize(lines, "\n");
+}
+
+} // namespace newsboat
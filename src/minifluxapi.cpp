@@ -154,6 +154,44 @@ rsspp::Feed MinifluxApi::fetch_feed(const std::string& id, CURL* cached_handle)
 	LOG(Level::DEBUG,
 		"MinifluxApi::fetch_feed: %" PRIu64 " items",
 		static_cast<uint64_t>(entries.size()));
+	fill_items(feed, entries);
+	return feed;
+}
+
+nonstd::optional<rsspp::Feed> MinifluxApi::fetch_feed_page(
+	const std::string& id,
+	unsigned int limit,
+	nonstd::optional<std::int64_t> before_id,
+	CURL* cached_handle)
+{
+	// Entry IDs only grow, so unlike publication dates, they make for a
+	// cursor that new entries can't shift
+	std::string query = strprintf::fmt(
+			"/v1/feeds/%s/entries?order=id&direction=desc&limit=%u",
+			id,
+			limit);
+	if (before_id.has_value()) {
+		query += strprintf::fmt("&before_entry_id=%" PRId64, before_id.value());
+	}
+
+	const json content = run_op(query, json(), HTTPMethod::GET, cached_handle);
+	if (content.is_null() || !content["entries"].is_array()) {
+		LOG(Level::ERROR,
+			"MinifluxApi::fetch_feed_page: no entries in the reply");
+		return nonstd::nullopt;
+	}
+
+	rsspp::Feed feed;
+	feed.rss_version = rsspp::Feed::MINIFLUX_JSON;
+	fill_items(feed, content["entries"]);
+	LOG(Level::DEBUG,
+		"MinifluxApi::fetch_feed_page: %" PRIu64 " items",
+		static_cast<uint64_t>(feed.items.size()));
+	return feed;
+}
+
+void MinifluxApi::fill_items(rsspp::Feed& feed, const json& entries)
+{
 	try {
 		for (const auto& entry : entries) {
 			rsspp::Item item;
@@ -199,8 +237,6 @@ rsspp::Feed MinifluxApi::fetch_feed(const std::string& id, CURL* cached_handle)
 	[](const rsspp::Item& a, const rsspp::Item& b) {
 		return a.pubDate_ts > b.pubDate_ts;
 	});
-
-	return feed;
 }
 
 void MinifluxApi::add_custom_headers(curl_slist** /* custom_headers */)
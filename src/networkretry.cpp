@@ -0,0 +1,97 @@
+#include "networkretry.h"
+
+#include <cstdint>
+
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_network_retry_new();
+
+	void rs_network_retry_free(void* retry);
+
+	void rs_network_retry_start_cycle(void* retry);
+
+	void rs_network_retry_feed_finished(void* retry,
+		const char* url,
+		bool failed,
+		std::uint32_t curl_code);
+
+	bool rs_network_retry_cycle_finished(void* retry,
+		bool enabled,
+		std::uint32_t threshold_percent);
+
+	bool rs_network_retry_seconds_until_probe(void* retry,
+		std::uint64_t* seconds);
+
+	char* rs_network_retry_probe_target(void* retry,
+		const char* configured_url);
+
+	char* rs_network_retry_probe_finished(void* retry, bool succeeded);
+}
+
+namespace newsboat {
+
+NetworkRetry::NetworkRetry()
+{
+	rs_retry = rs_network_retry_new();
+}
+
+NetworkRetry::~NetworkRetry()
+{
+	rs_network_retry_free(rs_retry);
+}
+
+void NetworkRetry::start_cycle()
+{
+	rs_network_retry_start_cycle(rs_retry);
+}
+
+void NetworkRetry::feed_succeeded(const std::string& url)
+{
+	rs_network_retry_feed_finished(rs_retry, url.c_str(), false, 0);
+}
+
+void NetworkRetry::feed_failed(const std::string& url,
+	unsigned int curl_code)
+{
+	rs_network_retry_feed_finished(rs_retry, url.c_str(), true, curl_code);
+}
+
+bool NetworkRetry::cycle_finished(bool enabled, unsigned int threshold_percent)
+{
+	return rs_network_retry_cycle_finished(rs_retry, enabled,
+			threshold_percent);
+}
+
+nonstd::optional<unsigned int> NetworkRetry::seconds_until_probe() const
+{
+	std::uint64_t seconds = 0;
+	if (!rs_network_retry_seconds_until_probe(rs_retry, &seconds)) {
+		return nonstd::nullopt;
+	}
+	return static_cast<unsigned int>(seconds);
+}
+
+nonstd::optional<std::string> NetworkRetry::probe_target(
+	const std::string& configured_url)
+{
+	const std::string url = RustString(rs_network_retry_probe_target(
+				rs_retry, configured_url.c_str()));
+	if (url.empty()) {
+		return nonstd::nullopt;
+	}
+	return url;
+}
+
+std::vector<std::string> NetworkRetry::probe_finished(bool succeeded)
+{
+	const std::string urls = RustString(rs_network_retry_probe_finished(
+				rs_retry, succeeded));
+	if (urls.empty()) {
+		return {};
+	}
+	return utils::tokenize(urls, "\n");
+}
+
+} // namespace newsboat
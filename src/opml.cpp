@@ -1,204 +1,123 @@
 #include "opml.h"
 
-#include <cassert>
+#include <algorithm>
 #include <cinttypes>
-#include <cstring>
 
+#include "logger.h"
 #include "rssfeed.h"
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_opml_parse(const char* content, char** error);
+	void* rs_opml_feeds_new();
+	void rs_opml_feeds_free(void* feeds);
+	size_t rs_opml_feeds_count(void* feeds);
+	void rs_opml_feeds_get(void* feeds, size_t index, char** url, char** tags);
+	void rs_opml_feeds_add(void* feeds,
+		const char* xml_url,
+		const char* html_url,
+		const char* title);
+	char* rs_opml_generate(void* feeds, bool version2);
+}
 
 namespace newsboat {
 
-xmlDocPtr opml::generate(const FeedContainer& feedcontainer)
+std::string opml::generate(const FeedContainer& feedcontainer, bool version2)
 {
-	xmlDocPtr root = xmlNewDoc((const xmlChar*)"1.0");
-	xmlNodePtr opml_node =
-		xmlNewDocNode(root, nullptr, (const xmlChar*)"opml", nullptr);
-	xmlSetProp(opml_node, (const xmlChar*)"version", (const xmlChar*)"1.0");
-	xmlDocSetRootElement(root, opml_node);
-
-	xmlNodePtr head = xmlNewTextChild(
-			opml_node, nullptr, (const xmlChar*)"head", nullptr);
-	xmlNewTextChild(head,
-		nullptr,
-		(const xmlChar*)"title",
-		(const xmlChar*)PROGRAM_NAME " - Exported Feeds");
-	xmlNodePtr body = xmlNewTextChild(
-			opml_node, nullptr, (const xmlChar*)"body", nullptr);
-
+	void* feeds = rs_opml_feeds_new();
 	for (const auto& feed : feedcontainer.get_all_feeds()) {
 		if (!utils::is_special_url(feed->rssurl())) {
-			std::string rssurl = feed->rssurl();
-			std::string link = feed->link();
-			std::string title = feed->title();
-
-			xmlNodePtr outline = xmlNewTextChild(body,
-					nullptr,
-					(const xmlChar*)"outline",
-					nullptr);
-			xmlSetProp(outline,
-				(const xmlChar*)"type",
-				(const xmlChar*)"rss");
-			xmlSetProp(outline,
-				(const xmlChar*)"xmlUrl",
-				(const xmlChar*)rssurl.c_str());
-			xmlSetProp(outline,
-				(const xmlChar*)"htmlUrl",
-				(const xmlChar*)link.c_str());
-			xmlSetProp(outline,
-				(const xmlChar*)"title",
-				(const xmlChar*)title.c_str());
+			rs_opml_feeds_add(feeds,
+				feed->rssurl().c_str(),
+				feed->link().c_str(),
+				feed->title().c_str());
 		}
 	}
 
-	return root;
-}
-
-void rec_find_rss_outlines(
-	FileUrlReader& urlcfg,
-	xmlNode* node,
-	std::string tag)
-{
-	while (node) {
-		std::string newtag = tag;
-
-		if (strcmp((const char*)node->name, "outline") == 0) {
-			char* url = (char*)xmlGetProp(
-					node, (const xmlChar*)"xmlUrl");
-			if (!url) {
-				url = (char*)xmlGetProp(
-						node, (const xmlChar*)"url");
-			}
-
-			if (url) {
-				LOG(Level::DEBUG,
-					"opml::import: found RSS outline with "
-					"url = "
-					"%s",
-					url);
-
-				std::string nurl = std::string(url);
-
-				// Liferea uses a pipe to signal feeds read from
-				// the output of a program in its OPMLs. Convert
-				// them to our syntax.
-				if (*url == '|') {
-					nurl = strprintf::fmt(
-							"exec:%s", url + 1);
-					LOG(Level::DEBUG,
-						"opml::import: liferea-style "
-						"url %s "
-						"converted to %s",
-						url,
-						nurl);
-				}
-
-				// Handle OPML filters.
-				char* filtercmd = (char*)xmlGetProp(
-						node, (const xmlChar*)"filtercmd");
-				if (filtercmd) {
-					LOG(Level::DEBUG,
-						"opml::import: adding filter "
-						"command %s to url %s",
-						filtercmd,
-						nurl);
-					nurl.insert(0,
-						strprintf::fmt("filter:%s:",
-							filtercmd));
-					xmlFree(filtercmd);
-				}
-
-				xmlFree(url);
-				// Filters and scripts may have arguments, so,
-				// quote them when needed.
-				// TODO: get rid of xmlStrdup, it's useless
-				url = (char*)xmlStrdup(
-						(const xmlChar*)
-						utils::quote_if_necessary(nurl)
-						.c_str());
-				assert(url);
-
-				bool found = false;
-
-				LOG(Level::DEBUG,
-					"opml::import: size = %" PRIu64,
-					static_cast<uint64_t>(urlcfg.get_urls().size()));
-				// TODO: replace with algorithm::any or something
-				if (urlcfg.get_urls().size() > 0) {
-					for (const auto& u :
-						urlcfg.get_urls()) {
-						if (u == url) {
-							found = true;
-						}
-					}
-				}
-
-				if (!found) {
-					LOG(Level::DEBUG,
-						"opml::import: added url = %s",
-						url);
-					urlcfg.get_urls().push_back(
-						std::string(url));
-					if (tag.length() > 0) {
-						LOG(Level::DEBUG,
-							"opml::import: "
-							"appending "
-							"tag %s to url %s",
-							tag,
-							url);
-						urlcfg.get_tags(url).push_back(
-							tag);
-					}
-				} else {
-					LOG(Level::DEBUG,
-						"opml::import: url = %s is "
-						"already "
-						"in list",
-						url);
-				}
-				xmlFree(url);
-			} else {
-				char* text = (char*)xmlGetProp(
-						node, (const xmlChar*)"text");
-				if (!text) {
-					text = (char*)xmlGetProp(
-							node, (const xmlChar*)"title");
-				}
-				if (text) {
-					if (newtag.length() > 0) {
-						newtag.append("/");
-					}
-					newtag.append(text);
-					xmlFree(text);
-				}
-			}
-		}
-		rec_find_rss_outlines(urlcfg, node->children, newtag);
-
-		node = node->next;
-	}
+	const std::string result = RustString(rs_opml_generate(feeds, version2));
+	rs_opml_feeds_free(feeds);
+	return result;
 }
 
 bool opml::import(
 	const std::string& filename,
 	FileUrlReader& urlcfg)
 {
-	xmlDoc* doc = xmlReadFile(filename.c_str(), nullptr, 0);
-	if (doc == nullptr) {
+	const std::string file_prefix = "file://";
+	std::string path = filename;
+	if (path.compare(0, file_prefix.length(), file_prefix) == 0) {
+		path.erase(0, file_prefix.length());
+	}
+
+	// The urls file is written anew below, which would drop the feeds that
+	// couldn't be read
+	const auto urls_error = urlcfg.get_error();
+	if (urls_error.has_value()) {
+		LOG(Level::USERERROR, "opml::import: %s", urls_error.value());
 		return false;
 	}
 
-	xmlNode* root = xmlDocGetRootElement(doc);
+	std::string encoding_warning;
+	const auto content = utils::read_text_file(path, encoding_warning);
+	if (!content.has_value()) {
+		LOG(Level::USERERROR, "opml::import: couldn't read %s", path);
+		return false;
+	}
+	if (!encoding_warning.empty()) {
+		LOG(Level::WARN, "opml::import: %s", encoding_warning);
+	}
+
+	char* error = nullptr;
+	void* feeds = rs_opml_parse(content.value().c_str(), &error);
+	if (feeds == nullptr) {
+		LOG(Level::USERERROR,
+			"opml::import: couldn't parse %s: %s",
+			path,
+			std::string(RustString(error)));
+		return false;
+	}
+
+	auto& urls = urlcfg.get_urls();
+	const size_t count = rs_opml_feeds_count(feeds);
+	LOG(Level::DEBUG,
+		"opml::import: found %" PRIu64 " feeds",
+		static_cast<uint64_t>(count));
+	for (size_t i = 0; i < count; i++) {
+		char* feed_url = nullptr;
+		char* feed_tags = nullptr;
+		rs_opml_feeds_get(feeds, i, &feed_url, &feed_tags);
+		// Filters and scripts may have arguments, so, quote them when
+		// needed.
+		const std::string url = utils::quote_if_necessary(RustString(feed_url));
+		const std::string tags = RustString(feed_tags);
+
+		if (std::find(urls.begin(), urls.end(), url) != urls.end()) {
+			LOG(Level::DEBUG,
+				"opml::import: url = %s is already in list",
+				url);
+			continue;
+		}
 
-	for (xmlNode* node = root->children; node != nullptr;
-		node = node->next) {
-		if (strcmp((const char*)node->name, "body") == 0) {
-			LOG(Level::DEBUG, "opml::import: found body");
-			rec_find_rss_outlines(urlcfg, node->children, "");
-			urlcfg.write_config();
+		LOG(Level::DEBUG, "opml::import: added url = %s", url);
+		urls.push_back(url);
+		for (const auto& tag : utils::tokenize(tags, "\n")) {
+			LOG(Level::DEBUG,
+				"opml::import: appending tag %s to url %s",
+				tag,
+				url);
+			urlcfg.get_tags(url).push_back(tag);
 		}
 	}
+	rs_opml_feeds_free(feeds);
 
-	xmlFreeDoc(doc);
+	const auto write_error = urlcfg.write_config();
+	if (write_error.has_value()) {
+		LOG(Level::USERERROR,
+			"opml::import: couldn't write the urls file: %s",
+			write_error.value());
+		return false;
+	}
 
 	return true;
 }
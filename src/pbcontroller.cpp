@@ -23,6 +23,7 @@
 #include "poddlthread.h"
 #include "queueloader.h"
 #include "strprintf.h"
+#include "termcaps.h"
 #include "utils.h"
 
 using namespace newsboat;
@@ -250,8 +251,7 @@ void PbController::initialize(int argc, char* argv[])
 	pid_t pid;
 	if (!fslock->try_lock(lock_file, pid)) {
 		std::cout << strprintf::fmt(
-				_("Error: an instance of %s is already "
-					"running (PID: %u)"),
+				_("Error: another instance of %s (PID %u) is running"),
 				"podboat",
 				pid)
 			<< std::endl;
@@ -271,12 +271,15 @@ void PbController::initialize(int argc, char* argv[])
 
 	NullConfigActionHandler null_cah;
 	cfgparser.register_handler("macro", null_cah);
+	cfgparser.register_handler("macro-with-args", null_cah);
 	cfgparser.register_handler("ignore-article", null_cah);
 	cfgparser.register_handler("always-download", null_cah);
 	cfgparser.register_handler("define-filter", null_cah);
 	cfgparser.register_handler("highlight", null_cah);
 	cfgparser.register_handler("highlight-article", null_cah);
 	cfgparser.register_handler("reset-unread-on-update", null_cah);
+	cfgparser.register_handler("accept-empty-feed", null_cah);
+	cfgparser.register_handler("ignore-feed-schedule", null_cah);
 
 	try {
 		cfgparser.parse_file("/etc/newsboat/config");
@@ -289,6 +292,7 @@ void PbController::initialize(int argc, char* argv[])
 
 int PbController::run()
 {
+	termcaps::set_color_support(termcaps::detect_color_support());
 	v->apply_colors_to_all_forms();
 
 	max_dls = cfg->get_configvalue_as_int("max-downloads");
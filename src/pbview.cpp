@@ -12,11 +12,11 @@
 #include "dllist.h"
 #include "download.h"
 #include "fmtstrformatter.h"
-#include "help.h"
 #include "listformatter.h"
 #include "logger.h"
 #include "pbcontroller.h"
 #include "poddlthread.h"
+#include "stflforms.h"
 #include "strprintf.h"
 #include "utils.h"
 
@@ -27,7 +27,7 @@ namespace podboat {
 PbView::PbView(PbController* c)
 	: ctrl(c)
 	, dllist_form(dllist_str)
-	, help_form(help_str)
+	, help_form(stflforms::help())
 	, keys(0)
 	, colorman(ctrl->get_colormanager())
 	, downloads_list("dls", dllist_form,
@@ -16,10 +16,18 @@ QueueManager::QueueManager(ConfigContainer* cfg_, ConfigPaths* paths_)
 	, paths(paths_)
 {}
 
+/// The URL to download the enclosure of \a item from: relative URLs are
+/// relative to the feed, and the fragment is of no use to the downloader.
+static std::string enclosure_download_url(const RssItem& item)
+{
+	return utils::absolute_url_without_fragment(item.feedurl(),
+			item.enclosure_url());
+}
+
 void QueueManager::enqueue_url(std::shared_ptr<RssItem> item,
 	std::shared_ptr<RssFeed> feed)
 {
-	const std::string& url = item->enclosure_url();
+	const std::string url = enclosure_download_url(*item);
 	bool url_found = false;
 	std::fstream f;
 	f.open(paths->queue_file(), std::fstream::in);
@@ -63,7 +71,7 @@ std::string QueueManager::generate_enqueue_filename(std::shared_ptr<RssItem>
 	item,
 	std::shared_ptr<RssFeed> feed)
 {
-	const std::string& url = item->enclosure_url();
+	const std::string url = enclosure_download_url(*item);
 	const std::string& title = utils::utf8_to_locale(item->title());
 	const time_t pubDate = item->pubDate_timestamp();
 
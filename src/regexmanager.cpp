@@ -8,6 +8,7 @@
 #include "confighandlerexception.h"
 #include "logger.h"
 #include "strprintf.h"
+#include "termcaps.h"
 #include "utils.h"
 
 namespace newsboat {
@@ -359,7 +360,8 @@ std::string RegexManager::get_attrs_stfl_string(const std::string& location,
 	const auto& attributes = locations[location];
 	std::string attrstr;
 	for (unsigned int i = 0; i < attributes.size(); ++i) {
-		const std::string& attribute = attributes[i].second;
+		const std::string attribute =
+			termcaps::degrade_style(attributes[i].second);
 		attrstr.append(strprintf::fmt("@style_%u_normal:%s ", i, attribute));
 		if (hasFocus) {
 			attrstr.append(strprintf::fmt("@style_%u_focus:%s ", i, attribute));
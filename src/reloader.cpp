@@ -3,6 +3,8 @@
 #include <algorithm>
 #include <cinttypes>
 #include <iostream>
+#include <memory>
+#include <set>
 #include <ncurses.h>
 #include <thread>
 
@@ -11,6 +13,12 @@
 #include "dbexception.h"
 #include "downloadthread.h"
 #include "fmtstrformatter.h"
+#include "emptyfeedguard.h"
+#include "feedschedule.h"
+#include "initialsync.h"
+#include "logger.h"
+#include "minifluxapi.h"
+#include "reloadhooks.h"
 #include "reloadrangethread.h"
 #include "reloadthread.h"
 #include "rss/exception.h"
@@ -22,10 +30,32 @@
 
 namespace newsboat {
 
+namespace {
+
+/// curl's progress callback. Returning non-zero aborts the transfer.
+int abort_if_cancelled(void* progress, curl_off_t, curl_off_t, curl_off_t,
+	curl_off_t)
+{
+	return static_cast<ReloadProgress*>(progress)->is_cancelled() ? 1 : 0;
+}
+
+/// Describes an exception that the parser wasn't supposed to throw. It
+/// probably points to a bug, so we ask the user to report it.
+std::string internal_error_message(const std::string& what)
+{
+	return strprintf::fmt(
+			_("internal error while parsing feed, please report: %s"),
+			what);
+}
+
+} // namespace
+
 Reloader::Reloader(Controller* c, Cache* cc, ConfigContainer* cfg)
 	: ctrl(c)
 	, rsscache(cc)
 	, cfg(cfg)
+	, in_reload_cycle(false)
+	, initial_syncs_stopped(false)
 {
 }
 
@@ -35,10 +65,11 @@ void Reloader::spawn_reloadthread()
 	t.detach();
 }
 
-void Reloader::start_reload_all_thread(const std::vector<int>& indexes)
+void Reloader::start_reload_all_thread(const std::vector<int>& indexes,
+	bool scheduled)
 {
 	LOG(Level::INFO, "starting reload all thread");
-	std::thread t(DownloadThread(*this, indexes));
+	std::thread t(DownloadThread(*this, indexes, scheduled));
 	t.detach();
 }
 
@@ -55,7 +86,8 @@ bool Reloader::trylock_reload_mutex()
 void Reloader::reload(unsigned int pos,
 	unsigned int max,
 	bool unattended,
-	CurlHandle* easyhandle)
+	CurlHandle* easyhandle,
+	bool scheduled)
 {
 	LOG(Level::DEBUG, "Reloader::reload: pos = %u max = %u", pos, max);
 	std::shared_ptr<RssFeed> oldfeed = ctrl->get_feedcontainer()->get_feed(pos);
@@ -64,10 +96,61 @@ void Reloader::reload(unsigned int pos,
 		// (e.g.  Reloader::reload_all() calling View::prepare_query_feed())
 		if (oldfeed->is_query_feed()) {
 			LOG(Level::DEBUG, "Reloader::reload: skipping query feed");
+			progress.feed_finished();
+			return;
+		}
+
+		if (progress.is_cancelled()) {
+			LOG(Level::DEBUG, "Reloader::reload: reload was cancelled");
+			return;
+		}
+
+		// Counts the feed as done however its reload ends, so that the
+		// progress always completes
+		ReloadProgress::FeedGuard feed_guard(progress);
+
+		FeedScheduler& scheduler = ctrl->get_feed_scheduler();
+		const auto postpone_reason = scheduler.postpone_reason(
+				oldfeed->rssurl(), oldfeed->schedule(), !scheduled);
+		if (postpone_reason.has_value()) {
+			LOG(Level::INFO, "Reloader::reload: not fetching %s: %s",
+				utils::censor_url(oldfeed->rssurl()),
+				postpone_reason.value());
 			return;
 		}
 
+		const std::string title = oldfeed->title().empty()
+			? utils::censor_url(oldfeed->rssurl())
+			: oldfeed->title();
+		progress.feed_started(title);
+
+		ReloadHooks& hooks = ctrl->get_reload_hooks();
+		const bool own_cycle = !in_reload_cycle;
+		if (own_cycle) {
+			hooks.start_cycle();
+		}
+
 		std::string errmsg;
+		const auto pre_reload_error = hooks.run_pre_reload(oldfeed->rssurl());
+		if (pre_reload_error.has_value()) {
+			errmsg = strprintf::fmt(
+					_("Error while retrieving %s: pre-reload-command %s"),
+					utils::censor_url(oldfeed->rssurl()),
+					pre_reload_error.value());
+			oldfeed->set_status(DlStatus::DL_ERROR);
+			ctrl->get_view()->set_status(errmsg);
+			LOG(Level::USERERROR, "%s", errmsg);
+			progress.feed_failed(oldfeed->rssurl(),
+				strprintf::fmt("pre-reload-command %s",
+					pre_reload_error.value()));
+			if (own_cycle) {
+				log_hook_report();
+				log_failure_report();
+				progress.finish();
+			}
+			return;
+		}
+
 		if (!unattended) {
 			ctrl->get_view()->set_status(
 				strprintf::fmt(_("%sLoading %s..."),
@@ -83,47 +166,277 @@ void Reloader::reload(unsigned int pos,
 			cfg,
 			ignore_dl ? ctrl->get_ignores() : nullptr,
 			ctrl->get_api());
+		parser.set_feed_options(&ctrl->get_feed_options());
+		// The parser resets the handle after each request, so the progress
+		// callback has to be installed anew for every feed.
+		std::unique_ptr<CurlHandle> local_handle;
+		if (easyhandle == nullptr) {
+			local_handle.reset(new CurlHandle());
+			easyhandle = local_handle.get();
+		}
+		curl_easy_setopt(easyhandle->ptr(), CURLOPT_NOPROGRESS, 0L);
+		curl_easy_setopt(easyhandle->ptr(), CURLOPT_XFERINFOFUNCTION,
+			abort_if_cancelled);
+		curl_easy_setopt(easyhandle->ptr(), CURLOPT_XFERINFODATA, &progress);
 		parser.set_easyhandle(easyhandle);
 		LOG(Level::DEBUG, "Reloader::reload: created parser");
+		const bool had_articles = oldfeed->total_item_count() > 0;
+		ReloadOutcome outcome = ReloadOutcome::NOT_MODIFIED;
+		unsigned int new_items = 0;
+		nonstd::optional<std::string> error;
+		unsigned int curl_code = 0;
 		try {
 			oldfeed->set_status(DlStatus::DURING_DOWNLOAD);
 			std::shared_ptr<RssFeed> newfeed = parser.parse();
+			std::string warning;
 			if (newfeed != nullptr) {
-				ctrl->replace_feed(
-					oldfeed, newfeed, pos, unattended);
-				if (newfeed->total_item_count() == 0) {
-					LOG(Level::DEBUG,
-						"Reloader::reload: feed is empty");
+				outcome = ReloadOutcome::FETCHED;
+				const auto suspicious = ctrl->get_empty_feed_guard().check(
+						oldfeed->rssurl(),
+						newfeed->total_item_count(),
+						oldfeed->total_item_count());
+				if (suspicious.has_value()) {
+					// Leave the cache alone, so that the articles don't
+					// look gone (or get expired) because of a hiccup
+					warning = strprintf::fmt(
+							_("Warning while retrieving %s: %s"),
+							utils::censor_url(oldfeed->rssurl()),
+							suspicious.value());
+					LOG(Level::USERERROR, "%s", warning);
+				} else {
+					new_items = count_new_items(*oldfeed, *newfeed);
+					ctrl->replace_feed(
+						oldfeed, newfeed, pos, unattended);
+					if (newfeed->total_item_count() == 0) {
+						LOG(Level::DEBUG,
+							"Reloader::reload: feed is empty");
+					}
 				}
 			}
 			oldfeed->set_status(DlStatus::SUCCESS);
-			ctrl->get_view()->set_status("");
+			ctrl->get_view()->set_status(warning);
 		} catch (const DbException& e) {
-			errmsg = strprintf::fmt(
-					_("Error while retrieving %s: %s"),
-					utils::censor_url(oldfeed->rssurl()),
-					e.what());
+			error = e.what();
 		} catch (const std::string& emsg) {
-			errmsg = strprintf::fmt(
-					_("Error while retrieving %s: %s"),
-					utils::censor_url(oldfeed->rssurl()),
-					emsg);
+			error = emsg;
 		} catch (rsspp::Exception& e) {
+			error = e.what();
+			curl_code = e.curl_code();
+		} catch (const std::exception& e) {
+			// This is the boundary of the reload task: nothing may escape,
+			// or the thread would take the whole program down
+			error = internal_error_message(e.what());
+		} catch (...) {
+			error = internal_error_message(_("unknown exception"));
+		}
+		if (error.has_value()) {
 			errmsg = strprintf::fmt(
 					_("Error while retrieving %s: %s"),
 					utils::censor_url(oldfeed->rssurl()),
-					e.what());
+					error.value());
+			if (progress.is_cancelled()) {
+				// The request was aborted by us; that's not an error
+				LOG(Level::DEBUG, "Reloader::reload: aborted: %s", errmsg);
+				oldfeed->set_status(DlStatus::TO_BE_DOWNLOADED);
+			} else {
+				oldfeed->set_status(DlStatus::DL_ERROR);
+				ctrl->get_view()->set_status(errmsg);
+				LOG(Level::USERERROR, "%s", errmsg);
+				progress.feed_failed(oldfeed->rssurl(), error.value());
+				network_retry.feed_failed(oldfeed->rssurl(), curl_code);
+			}
+			outcome = ReloadOutcome::FAILED;
+		} else {
+			scheduler.fetched(oldfeed->rssurl());
+			network_retry.feed_succeeded(oldfeed->rssurl());
+			if (outcome == ReloadOutcome::FETCHED &&
+				dynamic_cast<MinifluxApi*>(ctrl->get_api()) != nullptr) {
+				start_initial_sync(oldfeed->rssurl(), had_articles);
+			}
 		}
-		if (!errmsg.empty()) {
-			oldfeed->set_status(DlStatus::DL_ERROR);
-			ctrl->get_view()->set_status(errmsg);
-			LOG(Level::USERERROR, "%s", errmsg);
+
+		if (!progress.is_cancelled()) {
+			const auto post_reload_error = hooks.run_post_reload(
+					oldfeed->rssurl(), outcome, new_items);
+			if (post_reload_error.has_value()) {
+				LOG(Level::USERERROR,
+					"post-reload-command for %s %s",
+					utils::censor_url(oldfeed->rssurl()),
+					post_reload_error.value());
+			}
+		}
+		if (own_cycle) {
+			log_hook_report();
+			log_failure_report();
+			progress.finish();
 		}
 	} else {
 		ctrl->get_view()->show_error(_("Error: invalid feed!"));
 	}
 }
 
+void Reloader::start_initial_sync(const std::string& url, bool had_articles)
+{
+	nonstd::optional<std::string> progress;
+	try {
+		progress = rsscache->fetch_sync_progress(url);
+		if (!progress.has_value()) {
+			if (had_articles) {
+				// Cached before first syncs were a thing; the articles that
+				// are older than a regular fetch were dropped long ago
+				return;
+			}
+			// Record that the sync started, so that it resumes even if it's
+			// interrupted before the first page is stored
+			progress = InitialSync::initial_progress();
+			rsscache->set_sync_progress(url, progress.value());
+		}
+	} catch (const DbException& e) {
+		LOG(Level::ERROR,
+			"Reloader::start_initial_sync: can't get progress of %s: %s",
+			utils::censor_url(url),
+			e.what());
+		return;
+	}
+	if (InitialSync::is_complete(progress.value())) {
+		return;
+	}
+
+	std::lock_guard<std::mutex> guard(initial_syncs_mutex);
+	if (initial_syncs_stopped || initial_syncs.count(url) > 0) {
+		return;
+	}
+	initial_syncs.insert(url);
+	std::thread t([this, url]() {
+		run_initial_sync(url);
+		std::lock_guard<std::mutex> guard(initial_syncs_mutex);
+		initial_syncs.erase(url);
+		initial_sync_finished.notify_all();
+	});
+	t.detach();
+}
+
+void Reloader::run_initial_sync(const std::string& url)
+{
+	MinifluxApi* api = dynamic_cast<MinifluxApi*>(ctrl->get_api());
+	if (api == nullptr) {
+		return;
+	}
+
+	const bool ignore_dl = (cfg->get_configvalue("ignore-mode") == "download");
+	RssParser parser(url,
+		rsscache,
+		cfg,
+		ignore_dl ? ctrl->get_ignores() : nullptr,
+		api);
+	const bool reset_unread = ctrl->get_ignores()->matches_resetunread(url);
+	CurlHandle easyhandle;
+
+	try {
+		InitialSync sync(cfg->get_configvalue_as_int("miniflux-sync-page-size"),
+			cfg->get_configvalue_as_int("download-retries"),
+			std::chrono::seconds(
+				cfg->get_configvalue_as_int("miniflux-sync-page-delay")),
+			rsscache->fetch_sync_progress(url).value_or(""));
+		LOG(Level::INFO,
+			"Reloader::run_initial_sync: %s: %s",
+			utils::censor_url(url),
+			sync.status());
+
+		const bool complete = sync.run(
+		[&](unsigned int limit, nonstd::optional<std::int64_t> before_id) {
+			return api->fetch_feed_page(
+				url, limit, before_id, easyhandle.ptr());
+		},
+		[&](const rsspp::Feed& page, const std::string& progress) {
+			rsscache->store_sync_page(
+				parser.parse_page(page), reset_unread, progress);
+		},
+		[this]() {
+			return initial_syncs_stopped.load();
+		},
+		[&](const std::string& status) {
+			LOG(Level::DEBUG,
+				"Reloader::run_initial_sync: %s: %s",
+				utils::censor_url(url),
+				status);
+		});
+		LOG(Level::INFO,
+			"Reloader::run_initial_sync: %s: %s",
+			utils::censor_url(url),
+			sync.status());
+		if (!complete || initial_syncs_stopped) {
+			return;
+		}
+	} catch (const DbException& e) {
+		LOG(Level::USERERROR,
+			"First sync of %s stopped: %s",
+			utils::censor_url(url),
+			e.what());
+		return;
+	}
+
+	// Show the articles that the sync brought in
+	const auto feeds = ctrl->get_feedcontainer()->get_all_feeds();
+	for (unsigned int pos = 0; pos < feeds.size(); ++pos) {
+		if (feeds[pos]->rssurl() == url) {
+			reload(pos, 0, true);
+			break;
+		}
+	}
+}
+
+void Reloader::stop_initial_syncs()
+{
+	std::unique_lock<std::mutex> guard(initial_syncs_mutex);
+	initial_syncs_stopped = true;
+	initial_sync_finished.wait(guard, [this]() {
+		return initial_syncs.empty();
+	});
+}
+
+unsigned int Reloader::count_new_items(RssFeed& oldfeed, RssFeed& newfeed)
+{
+	std::set<std::string> old_guids;
+	for (const auto& item : oldfeed.items()) {
+		old_guids.insert(item->guid());
+	}
+
+	unsigned int result = 0;
+	for (const auto& item : newfeed.items()) {
+		if (old_guids.count(item->guid()) == 0) {
+			result++;
+		}
+	}
+	return result;
+}
+
+void Reloader::log_hook_report()
+{
+	for (const auto& line : ctrl->get_reload_hooks().report()) {
+		LOG(Level::INFO, "Reloader: %s", line);
+	}
+}
+
+void Reloader::log_failure_report()
+{
+	const auto failures = progress.failures();
+	for (const auto& failure : failures) {
+		LOG(Level::INFO,
+			"Reloader: failed to reload %s: %s",
+			utils::censor_url(failure.url),
+			failure.message);
+	}
+	std::lock_guard<std::mutex> guard(last_failures_mutex);
+	failures_of_last_reload = failures;
+}
+
+std::vector<ReloadProgress::Failure> Reloader::last_failures() const
+{
+	std::lock_guard<std::mutex> guard(last_failures_mutex);
+	return failures_of_last_reload;
+}
+
 std::string Reloader::prepare_message(unsigned int pos, unsigned int max)
 {
 	if (max > 0) {
@@ -132,7 +445,21 @@ std::string Reloader::prepare_message(unsigned int pos, unsigned int max)
 	return "";
 }
 
-void Reloader::reload_all(bool unattended)
+void Reloader::finish_network_retry_cycle()
+{
+	const bool enabled = cfg->get_configvalue_as_bool("network-retry");
+	const int threshold = std::max(0,
+			cfg->get_configvalue_as_int("network-retry-threshold"));
+	if (network_retry.cycle_finished(enabled, threshold)) {
+		const auto seconds = network_retry.seconds_until_probe();
+		LOG(Level::INFO,
+			"Reloader: too many feeds failed with network errors, "
+			"checking the network again in %u seconds",
+			seconds.value_or(0));
+	}
+}
+
+void Reloader::reload_all(bool unattended, bool scheduled)
 {
 	ScopeMeasure sm("Reloader::reload_all");
 
@@ -151,8 +478,12 @@ void Reloader::reload_all(bool unattended)
 	num_threads = std::max(min_threads, std::min(num_threads, max_threads));
 
 	LOG(Level::DEBUG, "Reloader::reload_all: starting with reload all...");
+	progress.start(num_feeds);
+	ctrl->get_reload_hooks().start_cycle();
+	network_retry.start_cycle();
+	in_reload_cycle = true;
 	if (num_threads == 1) {
-		reload_range(0, num_feeds - 1, num_feeds, unattended);
+		reload_range(0, num_feeds - 1, num_feeds, unattended, scheduled);
 	} else {
 		std::vector<std::pair<unsigned int, unsigned int>> partitions =
 				utils::partition_indexes(0, num_feeds - 1, num_threads);
@@ -164,14 +495,16 @@ void Reloader::reload_all(bool unattended)
 						partitions[i].first,
 						partitions[i].second,
 						num_feeds,
-						unattended)));
+						unattended,
+						scheduled)));
 		}
 		LOG(Level::DEBUG,
 			"Reloader::reload_all: starting my own reload...");
 		reload_range(partitions[num_threads - 1].first,
 			partitions[num_threads - 1].second,
 			num_feeds,
-			unattended);
+			unattended,
+			scheduled);
 		LOG(Level::DEBUG,
 			"Reloader::reload_all: joining other threads...");
 		for (size_t i = 0; i < threads.size(); i++) {
@@ -188,14 +521,26 @@ void Reloader::reload_all(bool unattended)
 		}
 	}
 
+	const bool cancelled = progress.is_cancelled();
+	log_failure_report();
+	finish_network_retry_cycle();
+	progress.finish();
+	in_reload_cycle = false;
+	log_hook_report();
+
 	ctrl->get_feedcontainer()->sort_feeds(cfg->get_feed_sort_strategy());
 	ctrl->update_feedlist();
 	ctrl->get_view()->force_redraw();
 
 	notify_reload_finished(unread_feeds, unread_articles);
+	if (cancelled && !unattended) {
+		notify_reload_cancelled();
+	}
 }
 
-void Reloader::reload_indexes(const std::vector<int>& indexes, bool unattended)
+void Reloader::reload_indexes(const std::vector<int>& indexes,
+	bool unattended,
+	bool scheduled)
 {
 	ScopeMeasure m1("Reloader::reload_indexes");
 	const auto unread_feeds =
@@ -204,21 +549,36 @@ void Reloader::reload_indexes(const std::vector<int>& indexes, bool unattended)
 		ctrl->get_feedcontainer()->unread_item_count();
 	const auto size = ctrl->get_feedcontainer()->feeds_size();
 
+	progress.start(indexes.size());
+	ctrl->get_reload_hooks().start_cycle();
+	network_retry.start_cycle();
+	in_reload_cycle = true;
 	for (const auto& idx : indexes) {
-		reload(idx, size, unattended);
+		reload(idx, size, unattended, nullptr, scheduled);
 	}
+	const bool cancelled = progress.is_cancelled();
+	log_failure_report();
+	finish_network_retry_cycle();
+	progress.finish();
+	in_reload_cycle = false;
+	log_hook_report();
 
 	notify_reload_finished(unread_feeds, unread_articles);
 
 	if (!unattended) {
-		ctrl->get_view()->set_status("");
+		if (cancelled) {
+			notify_reload_cancelled();
+		} else {
+			ctrl->get_view()->set_status("");
+		}
 	}
 }
 
 void Reloader::reload_range(unsigned int start,
 	unsigned int end,
 	unsigned int size,
-	bool unattended)
+	bool unattended,
+	bool scheduled)
 {
 	std::vector<unsigned int> v;
 	for (unsigned int i = start; i <= end; ++i) {
@@ -250,7 +610,7 @@ void Reloader::reload_range(unsigned int start,
 		LOG(Level::DEBUG,
 			"Reloader::reload_range: reloading feed #%u",
 			i);
-		reload(i, size, unattended, &easyhandle);
+		reload(i, size, unattended, &easyhandle, scheduled);
 	}
 }
 
@@ -279,6 +639,12 @@ void Reloader::notify(const std::string& msg)
 	}
 }
 
+void Reloader::notify_reload_cancelled()
+{
+	LOG(Level::INFO, "Reloader: reload cancelled by the user");
+	ctrl->get_view()->set_status(_("Reload cancelled"));
+}
+
 void Reloader::notify_reload_finished(unsigned int unread_feeds_before,
 	unsigned int unread_articles_before)
 {
@@ -0,0 +1,120 @@
+#include "reloadhooks.h"
+
+#include "confighandlerexception.h"
+#include "configparser.h"
+#include "ruststring.h"
+#include "utils.h"
+
+extern "C" {
+	void* rs_reload_hooks_new();
+
+	void rs_reload_hooks_free(void* hooks);
+
+	void rs_reload_hooks_set_command(void* hooks,
+		std::uint8_t kind,
+		const char* url,
+		const char* command);
+
+	void rs_reload_hooks_set_timeout(void* hooks, unsigned int seconds);
+
+	void rs_reload_hooks_start_cycle(void* hooks);
+
+	char* rs_reload_hooks_run_pre_reload(void* hooks, const char* url);
+
+	char* rs_reload_hooks_run_post_reload(void* hooks,
+		const char* url,
+		std::uint8_t outcome,
+		unsigned int new_items);
+
+	char* rs_reload_hooks_report(void* hooks);
+
+	char* rs_reload_hooks_dump_config(void* hooks);
+}
+
+namespace newsboat {
+
+namespace {
+
+const std::uint8_t PRE_RELOAD = 0;
+const std::uint8_t POST_RELOAD = 1;
+
+nonstd::optional<std::string> to_error(char* message)
+{
+	if (message == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(message));
+}
+
+} // namespace
+
+ReloadHooks::ReloadHooks()
+{
+	rs_hooks = rs_reload_hooks_new();
+}
+
+ReloadHooks::~ReloadHooks()
+{
+	rs_reload_hooks_free(rs_hooks);
+}
+
+void ReloadHooks::handle_action(const std::string& action,
+	const std::vector<std::string>& params)
+{
+	if (action != "pre-reload-command" && action != "post-reload-command") {
+		throw ConfigHandlerException(ActionHandlerStatus::INVALID_COMMAND);
+	}
+	if (params.size() < 2) {
+		throw ConfigHandlerException(ActionHandlerStatus::TOO_FEW_PARAMS);
+	}
+
+	const auto kind =
+		(action == "pre-reload-command") ? PRE_RELOAD : POST_RELOAD;
+	rs_reload_hooks_set_command(rs_hooks,
+		kind,
+		params[0].c_str(),
+		params[1].c_str());
+}
+
+void ReloadHooks::dump_config(std::vector<std::string>& config_output) const
+{
+	const std::string lines = RustString(rs_reload_hooks_dump_config(rs_hooks));
+	for (const auto& line : utils::tokenize(lines, "\n")) {
+		config_output.push_back(line);
+	}
+}
+
+void ReloadHooks::set_timeout(unsigned int seconds)
+{
+	rs_reload_hooks_set_timeout(rs_hooks, seconds);
+}
+
+void ReloadHooks::start_cycle()
+{
+	rs_reload_hooks_start_cycle(rs_hooks);
+}
+
+nonstd::optional<std::string> ReloadHooks::run_pre_reload(
+	const std::string& url)
+{
+	return to_error(rs_reload_hooks_run_pre_reload(rs_hooks, url.c_str()));
+}
+
+nonstd::optional<std::string> ReloadHooks::run_post_reload(
+	const std::string& url,
+	ReloadOutcome outcome,
+	unsigned int new_items)
+{
+	return to_error(rs_reload_hooks_run_post_reload(rs_hooks,
+				url.c_str(),
+				static_cast<std::uint8_t>(outcome),
+				new_items));
+}
+
+std::vector<std::string> ReloadHooks::report() const
+{
+	const std::string lines = RustString(rs_reload_hooks_report(rs_hooks));
+	return utils::tokenize(lines, "\n");
+}
+
+} // namespace newsboat
@@ -0,0 +1,138 @@
+#include "reloadprogress.h"
+
+#include <cstddef>
+
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_reload_progress_new();
+
+	void rs_reload_progress_free(void* progress);
+
+	void rs_reload_progress_start(void* progress, unsigned int total);
+
+	void rs_reload_progress_feed_started(void* progress, const char* title);
+
+	void rs_reload_progress_feed_finished(void* progress);
+
+	void rs_reload_progress_finish(void* progress);
+
+	void rs_reload_progress_cancel(void* progress);
+
+	bool rs_reload_progress_is_cancelled(void* progress);
+
+	void* rs_reload_progress_snapshot(void* progress);
+
+	void rs_reload_progress_snapshot_free(void* snapshot);
+
+	unsigned int rs_reload_progress_snapshot_done(void* snapshot);
+
+	unsigned int rs_reload_progress_snapshot_total(void* snapshot);
+
+	bool rs_reload_progress_snapshot_cancelled(void* snapshot);
+
+	char* rs_reload_progress_snapshot_percent_text(void* snapshot);
+
+	char* rs_reload_progress_snapshot_current_text(void* snapshot);
+
+	void rs_reload_progress_feed_failed(void* progress,
+		const char* url,
+		const char* message);
+
+	void* rs_reload_progress_failures(void* progress);
+
+	void rs_reload_progress_failures_free(void* failures);
+
+	std::size_t rs_reload_progress_failures_count(void* failures);
+
+	char* rs_reload_progress_failure_url(void* failures, std::size_t index);
+
+	char* rs_reload_progress_failure_message(void* failures,
+		std::size_t index);
+}
+
+namespace newsboat {
+
+ReloadProgress::ReloadProgress()
+{
+	rs_progress = rs_reload_progress_new();
+}
+
+ReloadProgress::~ReloadProgress()
+{
+	rs_reload_progress_free(rs_progress);
+}
+
+void ReloadProgress::start(unsigned int total)
+{
+	rs_reload_progress_start(rs_progress, total);
+}
+
+void ReloadProgress::feed_started(const std::string& title)
+{
+	rs_reload_progress_feed_started(rs_progress, title.c_str());
+}
+
+void ReloadProgress::feed_finished()
+{
+	rs_reload_progress_feed_finished(rs_progress);
+}
+
+void ReloadProgress::finish()
+{
+	rs_reload_progress_finish(rs_progress);
+}
+
+void ReloadProgress::cancel()
+{
+	rs_reload_progress_cancel(rs_progress);
+}
+
+bool ReloadProgress::is_cancelled() const
+{
+	return rs_reload_progress_is_cancelled(rs_progress);
+}
+
+ReloadProgress::Snapshot ReloadProgress::snapshot() const
+{
+	void* rs_snapshot = rs_reload_progress_snapshot(rs_progress);
+
+	Snapshot result;
+	result.done = rs_reload_progress_snapshot_done(rs_snapshot);
+	result.total = rs_reload_progress_snapshot_total(rs_snapshot);
+	result.cancelled = rs_reload_progress_snapshot_cancelled(rs_snapshot);
+	result.percent_text = RustString(
+			rs_reload_progress_snapshot_percent_text(rs_snapshot));
+	result.current_text = RustString(
+			rs_reload_progress_snapshot_current_text(rs_snapshot));
+
+	rs_reload_progress_snapshot_free(rs_snapshot);
+	return result;
+}
+
+void ReloadProgress::feed_failed(const std::string& url,
+	const std::string& message)
+{
+	rs_reload_progress_feed_failed(rs_progress, url.c_str(), message.c_str());
+}
+
+std::vector<ReloadProgress::Failure> ReloadProgress::failures() const
+{
+	void* rs_failures = rs_reload_progress_failures(rs_progress);
+
+	std::vector<Failure> result;
+	const auto count = rs_reload_progress_failures_count(rs_failures);
+	for (std::size_t i = 0; i < count; i++) {
+		Failure failure;
+		failure.url = RustString(
+				rs_reload_progress_failure_url(rs_failures, i));
+		failure.message = RustString(
+				rs_reload_progress_failure_message(rs_failures, i));
+		result.push_back(failure);
+	}
+
+	rs_reload_progress_failures_free(rs_failures);
+	return result;
+}
+
+} // namespace newsboat
@@ -6,18 +6,20 @@ ReloadRangeThread::ReloadRangeThread(Reloader& r,
 	unsigned int s,
 	unsigned int e,
 	unsigned int ss,
-	bool u)
+	bool u,
+	bool sch)
 	: reloader(r)
 	, start(s)
 	, end(e)
 	, size(ss)
 	, unattended(u)
+	, scheduled(sch)
 {
 }
 
 void ReloadRangeThread::operator()()
 {
-	reloader.reload_range(start, end, size, unattended);
+	reloader.reload_range(start, end, size, unattended, scheduled);
 }
 
 } // namespace newsboat
@@ -0,0 +1,66 @@
+#include "reloadtag.h"
+
+#include <cstdint>
+
+#include "rssfeed.h"
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_tag_scope_select(const char* tag,
+		const char* const* tags,
+		const std::size_t* tag_counts,
+		std::size_t feed_count,
+		char** error);
+
+	void rs_tag_scope_free(void* scope);
+
+	std::size_t rs_tag_scope_len(void* scope);
+
+	std::size_t rs_tag_scope_index(void* scope, std::size_t n);
+
+	char* rs_tag_scope_summary(void* scope);
+}
+
+namespace newsboat {
+
+nonstd::optional<TagScope> TagScope::select(const std::string& tag,
+	const std::vector<std::shared_ptr<RssFeed>>& feeds,
+	std::string& error)
+{
+	std::vector<const char*> tags;
+	std::vector<std::size_t> tag_counts;
+	for (const auto& feed : feeds) {
+		// Query feeds aren't fetched, so they don't count as carrying
+		// their tags
+		if (feed->is_query_feed()) {
+			tag_counts.push_back(0);
+			continue;
+		}
+		for (const auto& feed_tag : feed->get_tag_list()) {
+			tags.push_back(feed_tag.c_str());
+		}
+		tag_counts.push_back(feed->get_tag_list().size());
+	}
+
+	char* error_ptr = nullptr;
+	void* rs_scope = rs_tag_scope_select(tag.c_str(),
+			tags.data(),
+			tag_counts.data(),
+			tag_counts.size(),
+			&error_ptr);
+	if (rs_scope == nullptr) {
+		error = RustString(error_ptr);
+		return nonstd::nullopt;
+	}
+
+	TagScope scope;
+	const auto len = rs_tag_scope_len(rs_scope);
+	for (std::size_t n = 0; n < len; n++) {
+		scope.indexes.push_back(rs_tag_scope_index(rs_scope, n));
+	}
+	scope.summary = RustString(rs_tag_scope_summary(rs_scope));
+	rs_tag_scope_free(rs_scope);
+	return scope;
+}
+
+} // namespace newsboat
@@ -1,9 +1,13 @@
 #include "reloadthread.h"
 
+#include <algorithm>
 #include <cinttypes>
 #include <unistd.h>
 
+#include "curlhandle.h"
 #include "logger.h"
+#include "rssfeed.h"
+#include "utils.h"
 
 namespace newsboat {
 
@@ -37,13 +41,13 @@ void ReloadThread::operator()()
 
 		if (cfg->get_configvalue_as_bool("auto-reload")) {
 			if (suppressed_first) {
-				ctrl->get_reloader()->start_reload_all_thread();
+				ctrl->get_reloader()->start_reload_all_thread({}, true);
 			} else {
 				suppressed_first = true;
 				if (!cfg->get_configvalue_as_bool(
 						"suppress-first-reload")) {
 					ctrl->get_reloader()
-					->start_reload_all_thread();
+					->start_reload_all_thread({}, true);
 				}
 			}
 		} else {
@@ -51,15 +55,75 @@ void ReloadThread::operator()()
 			waittime_sec = 60;
 		}
 
-		time_t seconds_to_wait = 0;
-		if ((oldtime + waittime_sec) > time(nullptr))
-			seconds_to_wait =
-				oldtime + waittime_sec - time(nullptr);
+		wait_for_next_reload();
+	}
+}
+
+void ReloadThread::wait_for_next_reload()
+{
+	NetworkRetry& network_retry = ctrl->get_reloader()->get_network_retry();
+	for (;;) {
+		const time_t now = time(nullptr);
+		if (oldtime + waittime_sec <= now) {
+			return;
+		}
+		const time_t seconds_to_wait = oldtime + waittime_sec - now;
+
+		const auto until_probe = network_retry.seconds_until_probe();
+		if (until_probe.has_value() && until_probe.value() < seconds_to_wait) {
+			unsigned int left = until_probe.value();
+			while (left > 0) {
+				left = ::sleep(left);
+			}
+			probe_network();
+		} else {
+			::sleep(seconds_to_wait);
+		}
+	}
+}
+
+void ReloadThread::probe_network()
+{
+	Reloader* reloader = ctrl->get_reloader();
+	NetworkRetry& network_retry = reloader->get_network_retry();
+	const auto target = network_retry.probe_target(
+			cfg->get_configvalue("network-retry-url"));
+	if (!target.has_value()) {
+		return;
+	}
 
-		while (seconds_to_wait > 0) {
-			seconds_to_wait = ::sleep(seconds_to_wait);
+	// Any response, even an HTTP error, means that the network is back
+	CurlHandle handle;
+	utils::set_common_curl_options(handle.ptr(), cfg);
+	curl_easy_setopt(handle.ptr(), CURLOPT_URL, target.value().c_str());
+	curl_easy_setopt(handle.ptr(), CURLOPT_NOBODY, 1L);
+	const CURLcode result = curl_easy_perform(handle.ptr());
+	LOG(Level::INFO,
+		"ReloadThread: probed %s to see if the network is back: %s",
+		utils::censor_url(target.value()),
+		curl_easy_strerror(result));
+
+	const bool network_is_back = result == CURLE_OK
+		|| result == CURLE_HTTP_RETURNED_ERROR;
+	const auto urls = network_retry.probe_finished(network_is_back);
+	if (urls.empty()) {
+		return;
+	}
+	std::vector<int> indexes;
+	const auto feeds = ctrl->get_feedcontainer()->get_all_feeds();
+	for (unsigned int i = 0; i < feeds.size(); i++) {
+		const std::string& url = feeds[i]->rssurl();
+		if (std::find(urls.begin(), urls.end(), url) != urls.end()) {
+			indexes.push_back(i);
 		}
 	}
+	LOG(Level::INFO,
+		"ReloadThread: network is back, reloading %" PRIu64
+		" feeds that failed",
+		static_cast<uint64_t>(indexes.size()));
+	if (!indexes.empty()) {
+		reloader->start_reload_all_thread(indexes, true);
+	}
 }
 
 } // namespace newsboat
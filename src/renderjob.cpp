@@ -0,0 +1,201 @@
+#include "renderjob.h"
+
+#include <cstdint>
+
+#include "logger.h"
+#include "ruststring.h"
+
+extern "C" {
+	std::size_t rs_render_job_nodes_per_unit();
+
+	void* rs_render_job_new(std::size_t screen_height);
+
+	void rs_render_job_free(void* job);
+
+	void rs_render_job_cancel(void* job);
+
+	bool rs_render_job_should_continue(void* job);
+
+	std::uint8_t rs_render_job_unit_done(void* job,
+		std::size_t lines,
+		bool finished);
+
+	void* rs_rendered_article_new();
+
+	void rs_rendered_article_free(void* article);
+
+	void rs_rendered_article_add_line(void* article,
+		std::uint8_t line_type,
+		const char* line);
+
+	void rs_rendered_article_add_link(void* article,
+		const char* url,
+		std::uint8_t link_type);
+
+	std::size_t rs_rendered_article_line_count(void* article);
+
+	char* rs_rendered_article_line(void* article,
+		std::size_t index,
+		std::uint8_t* line_type);
+
+	std::size_t rs_rendered_article_link_count(void* article);
+
+	char* rs_rendered_article_link(void* article,
+		std::size_t index,
+		std::uint8_t* link_type);
+
+	void* rs_render_cache_new(std::size_t max_entries, std::size_t max_bytes);
+
+	void rs_render_cache_free(void* cache);
+
+	void rs_render_cache_set_limits(void* cache,
+		std::size_t max_entries,
+		std::size_t max_bytes);
+
+	void* rs_render_cache_get(void* cache, const char* guid);
+
+	bool rs_render_cache_store(void* cache,
+		const char* guid,
+		void* job,
+		void* article);
+
+	char* rs_render_cache_stats(void* cache);
+
+	void rs_render_cache_clear(void* cache);
+}
+
+namespace newsboat {
+
+RenderJob::RenderJob(const std::string& source,
+	const std::string& url,
+	std::vector<LinkPair> links,
+	unsigned int screen_height,
+	unsigned int max_nesting)
+	: renderer(false, max_nesting)
+	, state(source, url)
+{
+	state.links = std::move(links);
+	rs_job = rs_render_job_new(screen_height);
+}
+
+RenderJob::~RenderJob()
+{
+	rs_render_job_free(rs_job);
+}
+
+RenderJob::Step RenderJob::render_unit()
+{
+	bool finished = state.is_finished();
+	if (rs_render_job_should_continue(rs_job)) {
+		finished = renderer.render_some(state, rs_render_job_nodes_per_unit());
+	}
+
+	switch (rs_render_job_unit_done(rs_job, state.lines.size(), finished)) {
+	case 0:
+		return Step::CONTINUE;
+	case 1:
+		return Step::SHOW_FIRST_SCREEN;
+	case 2:
+		return Step::FINISHED;
+	default:
+		return Step::CANCELLED;
+	}
+}
+
+void RenderJob::cancel()
+{
+	rs_render_job_cancel(rs_job);
+}
+
+bool RenderJob::should_continue() const
+{
+	return rs_render_job_should_continue(rs_job);
+}
+
+const std::vector<std::pair<LineType, std::string>>& RenderJob::lines() const
+{
+	return state.lines;
+}
+
+const std::vector<LinkPair>& RenderJob::links() const
+{
+	return state.links;
+}
+
+RenderCache::RenderCache(std::size_t max_entries, std::size_t max_bytes)
+{
+	rs_cache = rs_render_cache_new(max_entries, max_bytes);
+}
+
+RenderCache::~RenderCache()
+{
+	rs_render_cache_free(rs_cache);
+}
+
+void RenderCache::set_limits(std::size_t max_entries, std::size_t max_bytes)
+{
+	rs_render_cache_set_limits(rs_cache, max_entries, max_bytes);
+}
+
+bool RenderCache::get(const std::string& guid,
+	std::vector<std::pair<LineType, std::string>>& lines,
+	std::vector<LinkPair>& links)
+{
+	void* article = rs_render_cache_get(rs_cache, guid.c_str());
+	if (article == nullptr) {
+		return false;
+	}
+
+	lines.clear();
+	const std::size_t line_count = rs_rendered_article_line_count(article);
+	for (std::size_t i = 0; i < line_count; ++i) {
+		std::uint8_t line_type = 0;
+		const std::string line = RustString(
+				rs_rendered_article_line(article, i, &line_type));
+		lines.push_back(std::make_pair(static_cast<LineType>(line_type), line));
+	}
+
+	links.clear();
+	const std::size_t link_count = rs_rendered_article_link_count(article);
+	for (std::size_t i = 0; i < link_count; ++i) {
+		std::uint8_t link_type = 0;
+		const std::string url = RustString(
+				rs_rendered_article_link(article, i, &link_type));
+		links.push_back(LinkPair(url, static_cast<LinkType>(link_type)));
+	}
+
+	rs_rendered_article_free(article);
+	return true;
+}
+
+bool RenderCache::store(const std::string& guid, const RenderJob& job)
+{
+	void* article = rs_rendered_article_new();
+	for (const auto& line : job.lines()) {
+		rs_rendered_article_add_line(article,
+			static_cast<std::uint8_t>(line.first),
+			line.second.c_str());
+	}
+	for (const auto& link : job.links()) {
+		rs_rendered_article_add_link(article,
+			link.first.c_str(),
+			static_cast<std::uint8_t>(link.second));
+	}
+
+	const bool stored = rs_render_cache_store(rs_cache, guid.c_str(), job.rs_job,
+			article);
+	rs_rendered_article_free(article);
+
+	LOG(Level::DEBUG, "RenderCache::store: %s %s; cache: %s",
+		stored ? "stored" : "didn't store",
+		guid,
+		std::string(RustString(rs_render_cache_stats(rs_cache))));
+	return stored;
+}
+
+void RenderCache::clear()
+{
+	rs_render_cache_clear(rs_cache);
+}
+
+} // namespace newsboat
@@ -35,6 +35,9 @@ RssFeed::RssFeed(Cache* c)
 	, idx(0)
 	, order(0)
 	, status_(DlStatus::SUCCESS)
+	, placeholder(false)
+	, placeholder_unread(0)
+	, placeholder_total(0)
 {
 }
 
@@ -44,6 +47,9 @@ RssFeed::~RssFeed()
 
 unsigned int RssFeed::unread_item_count() const
 {
+	if (placeholder) {
+		return placeholder_unread;
+	}
 	std::lock_guard<std::mutex> lock(item_mutex);
 	return std::count_if(items_.begin(),
 			items_.end(),
@@ -151,7 +157,7 @@ nonstd::optional<std::string> RssFeed::attribute_value(const std::string&
 	} else if (attribname == "unread_count") {
 		return std::to_string(unread_item_count());
 	} else if (attribname == "total_count") {
-		return std::to_string(items_.size());
+		return std::to_string(total_item_count());
 	} else if (attribname == "tags") {
 		return get_tags();
 	} else if (attribname == "feedindex") {
@@ -160,6 +166,15 @@ nonstd::optional<std::string> RssFeed::attribute_value(const std::string&
 	return nonstd::nullopt;
 }
 
+nonstd::optional<time_t> RssFeed::date_timestamp(const std::string&
+	attribname) const
+{
+	if (attribname == "feeddate") {
+		return pubDate_;
+	}
+	return nonstd::nullopt;
+}
+
 void RssFeed::update_items(std::vector<std::shared_ptr<RssFeed>> feeds)
 {
 	std::lock_guard<std::mutex> lock(item_mutex);
@@ -253,8 +268,7 @@ void RssFeed::sort_unlocked(const ArticleSortStrategy& sort_strategy)
 			items_.end(),
 			[&](const std::shared_ptr<RssItem>& a,
 		const std::shared_ptr<RssItem>& b) {
-			const auto cmp = utils::strnaturalcmp(utils::utf8_to_locale(a->title()),
-					utils::utf8_to_locale(b->title()));
+			const auto cmp = utils::strnaturalcmp(a->title(), b->title());
 			return sort_strategy.sd == SortDirection::DESC ? (cmp > 0) : (cmp < 0);
 		});
 		break;
@@ -321,6 +335,16 @@ void RssFeed::sort_unlocked(const ArticleSortStrategy& sort_strategy)
 					b->pubDate_timestamp());
 		});
 		break;
+	case ArtSortMethod::WORDS:
+		std::stable_sort(items_.begin(),
+			items_.end(),
+			[&](const std::shared_ptr<RssItem>& a,
+		const std::shared_ptr<RssItem>& b) {
+			return sort_strategy.sd == SortDirection::DESC
+				? (a->word_count() > b->word_count())
+				: (a->word_count() < b->word_count());
+		});
+		break;
 	case ArtSortMethod::RANDOM:
 		std::random_shuffle(items_.begin(), items_.end());
 		break;
@@ -377,6 +401,11 @@ std::string RssFeed::get_status()
 
 void RssFeed::unload()
 {
+	if (!is_cached()) {
+		// There is nowhere to load the descriptions back from
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(item_mutex);
 	for (const auto& item : items_) {
 		item->unload();
@@ -385,6 +414,10 @@ void RssFeed::unload()
 
 void RssFeed::load()
 {
+	if (!is_cached()) {
+		return;
+	}
+
 	std::lock_guard<std::mutex> lock(item_mutex);
 	ch->fetch_descriptions(this);
 }
@@ -17,10 +17,13 @@ RssItem::RssItem(Cache* c)
 	: ch(c)
 	, idx(0)
 	, size_(0)
+	, word_count_(0)
+	, reading_minutes_(0)
 	, pubDate_(0)
 	, unread_(true)
 	, enqueued_(false)
 	, deleted_(0)
+	, updated_(false)
 	, override_unread_(false)
 {
 }
@@ -57,6 +60,22 @@ void RssItem::set_size(unsigned int size)
 	size_ = size;
 }
 
+std::size_t RssItem::byte_size() const
+{
+	std::size_t bytes = sizeof(RssItem);
+	for (const auto* text : {
+			&title_, &link_, &author_, &guid_, &feedurl_, &enclosure_url_,
+			&enclosure_type_, &flags_, &oldflags_, &note_, &base
+		}) {
+		bytes += text->capacity();
+	}
+	std::lock_guard<std::mutex> guard(description_mutex);
+	if (description_.has_value()) {
+		bytes += description_->capacity();
+	}
+	return bytes;
+}
+
 std::string RssItem::length() const
 {
 	std::string::size_type l(size_);
@@ -91,6 +110,9 @@ void RssItem::set_unread_nowrite(bool u)
 void RssItem::set_unread_nowrite_notify(bool u, bool notify)
 {
 	unread_ = u;
+	if (!u) {
+		updated_ = false;
+	}
 	std::shared_ptr<RssFeed> feedptr = feedptr_.lock();
 	if (feedptr && notify) {
 		feedptr->get_item_by_guid(guid_)->set_unread_nowrite(
@@ -100,9 +122,15 @@ void RssItem::set_unread_nowrite_notify(bool u, bool notify)
 
 void RssItem::set_unread(bool u)
 {
-	if (unread_ != u) {
+	// Marking an edited article read also clears its "updated" mark, even if
+	// the article was read already
+	if (unread_ != u || (!u && updated_)) {
 		bool old_u = unread_;
+		const bool old_updated = updated_;
 		unread_ = u;
+		if (!u) {
+			updated_ = false;
+		}
 		std::shared_ptr<RssFeed> feedptr = feedptr_.lock();
 		if (feedptr)
 			feedptr->get_item_by_guid(guid_)->set_unread_nowrite(
@@ -116,6 +144,7 @@ void RssItem::set_unread(bool u)
 			// if the update failed, restore the old unread flag and
 			// rethrow the exception
 			unread_ = old_u;
+			updated_ = old_updated;
 			throw;
 		}
 	}
@@ -161,12 +190,20 @@ nonstd::optional<std::string> RssItem::attribute_value(const std::string&
 		return guid();
 	} else if (attribname == "unread") {
 		return unread_ ? "yes" : "no";
+	} else if (attribname == "updated") {
+		return updated_ ? "yes" : "no";
 	} else if (attribname == "enclosure_url") {
 		return enclosure_url();
 	} else if (attribname == "enclosure_type") {
 		return enclosure_type();
 	} else if (attribname == "flags") {
 		return flags();
+	} else if (attribname == "note") {
+		return utils::utf8_to_locale(note());
+	} else if (attribname == "words") {
+		return std::to_string(word_count_);
+	} else if (attribname == "reading_minutes") {
+		return std::to_string(reading_minutes_);
 	} else if (attribname == "age")
 		return std::to_string(
 				(time(nullptr) - pubDate_timestamp()) / 86400);
@@ -183,6 +220,21 @@ nonstd::optional<std::string> RssItem::attribute_value(const std::string&
 	return nonstd::nullopt;
 }
 
+nonstd::optional<time_t> RssItem::date_timestamp(const std::string&
+	attribname) const
+{
+	if (attribname == "date") {
+		return pubDate_;
+	}
+
+	std::shared_ptr<RssFeed> feedptr = feedptr_.lock();
+	if (feedptr) {
+		return feedptr->RssFeed::date_timestamp(attribname);
+	}
+
+	return nonstd::nullopt;
+}
+
 void RssItem::update_flags()
 {
 	if (ch) {
@@ -190,6 +242,13 @@ void RssItem::update_flags()
 	}
 }
 
+void RssItem::update_note()
+{
+	if (ch) {
+		ch->update_rssitem_note(this);
+	}
+}
+
 void RssItem::set_flags(const std::string& ff)
 {
 	oldflags_ = flags_;
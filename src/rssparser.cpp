@@ -11,6 +11,8 @@
 #include "config.h"
 #include "configcontainer.h"
 #include "curlhandle.h"
+#include "feedoptions.h"
+#include "feedschedule.h"
 #include "htmlrenderer.h"
 #include "logger.h"
 #include "minifluxapi.h"
@@ -38,6 +40,7 @@ RssParser::RssParser(const std::string& uri,
 	, ign(ii)
 	, api(a)
 	, easyhandle(0)
+	, feed_options(nullptr)
 {
 	is_ttrss = cfgcont->get_configvalue("urls-source") == "ttrss";
 	is_newsblur = cfgcont->get_configvalue("urls-source") == "newsblur";
@@ -55,6 +58,26 @@ std::shared_ptr<RssFeed> RssParser::parse()
 		return nullptr;
 	}
 
+	std::shared_ptr<RssFeed> feed = build_feed();
+
+	if (ch) {
+		ch->remove_old_deleted_items(feed.get());
+	}
+
+	return feed;
+}
+
+std::shared_ptr<RssFeed> RssParser::parse_page(const rsspp::Feed& page)
+{
+	f = page;
+	// Unlike parse(), this leaves deleted articles alone: they're missing
+	// from the page because they're on another one, not because the feed
+	// dropped them.
+	return build_feed();
+}
+
+std::shared_ptr<RssFeed> RssParser::build_feed()
+{
 	std::shared_ptr<RssFeed> feed(new RssFeed(ch));
 	feed->set_rssurl(my_uri);
 
@@ -71,8 +94,6 @@ std::shared_ptr<RssFeed> RssParser::parse()
 	fill_feed_fields(feed);
 	fill_feed_items(feed);
 
-	ch->remove_old_deleted_items(feed.get());
-
 	return feed;
 }
 
@@ -195,9 +216,22 @@ void RssParser::download_http(const std::string& uri)
 		proxy_type = cfgcont->get_configvalue("proxy-type");
 	}
 
+	std::vector<std::string> headers;
+	nonstd::optional<std::string> feed_useragent;
+	if (feed_options) {
+		headers = feed_options->http_headers(uri);
+		feed_useragent = feed_options->user_agent(uri);
+		// Headers tend to carry access tokens, so their values stay out of
+		// the log
+		for (const auto& header : feed_options->redacted_http_headers(uri)) {
+			LOG(Level::DEBUG, "RssParser::download_http: header %s", header);
+		}
+	}
+
 	for (unsigned int i = 0; i < retrycount
 		&& f.rss_version == rsspp::Feed::Version::UNKNOWN; i++) {
-		std::string useragent = utils::get_useragent(cfgcont);
+		std::string useragent =
+			feed_useragent.value_or(utils::get_useragent(cfgcont));
 		LOG(Level::DEBUG,
 			"RssParser::download_http: user-agent = %s",
 			useragent);
@@ -208,10 +242,15 @@ void RssParser::download_http(const std::string& uri)
 			proxy_auth.c_str(),
 			utils::get_proxy_type(proxy_type),
 			cfgcont->get_configvalue_as_bool(
-				"ssl-verifypeer"));
+				"ssl-verifypeer"),
+			cfgcont->get_configvalue_as_int(
+				"download-low-speed-limit"),
+			cfgcont->get_configvalue_as_int(
+				"download-low-speed-time"));
+		p.set_custom_headers(headers);
 		time_t lm = 0;
 		std::string etag;
-		if (!ign || !ign->matches_lastmodified(uri)) {
+		if (ch && (!ign || !ign->matches_lastmodified(uri))) {
 			ch->fetch_lastmodified(uri, lm, etag);
 		}
 		f = p.parse_url(uri,
@@ -228,8 +267,8 @@ void RssParser::download_http(const std::string& uri)
 			// up-cast which are always safe.
 			static_cast<int64_t>(p.get_last_modified()),
 			p.get_etag());
-		if (p.get_last_modified() != 0 ||
-			p.get_etag().length() > 0) {
+		if (ch && (p.get_last_modified() != 0 ||
+				p.get_etag().length() > 0)) {
 			LOG(Level::DEBUG,
 				"RssParser::download_http: "
 				"lastmodified "
@@ -328,6 +367,9 @@ void RssParser::fill_feed_fields(std::shared_ptr<RssFeed> feed)
 
 	set_rtl(feed, f.language);
 
+	feed->set_schedule(feedschedule::from_elements(
+			f.ttl, f.skip_hours, f.skip_days));
+
 	LOG(Level::DEBUG,
 		"RssParser::parse: feed title = `%s' link = `%s'",
 		feed->title(),
@@ -0,0 +1,63 @@
+#include "scopedcounts.h"
+
+#include <cstdint>
+
+extern "C" {
+	void* rs_scoped_counts_new();
+
+	void rs_scoped_counts_free(void* counts);
+
+	void rs_scoped_counts_clear(void* counts);
+
+	void rs_scoped_counts_update(void* counts,
+		const char* key,
+		bool in_scope,
+		std::uint32_t unread);
+
+	std::uint64_t rs_scoped_counts_total(void* counts);
+
+	std::uint64_t rs_scoped_counts_unread(void* counts);
+
+	std::uint64_t rs_scoped_counts_unread_members(void* counts);
+}
+
+namespace newsboat {
+
+ScopedCounts::ScopedCounts()
+{
+	rs_counts = rs_scoped_counts_new();
+}
+
+ScopedCounts::~ScopedCounts()
+{
+	rs_scoped_counts_free(rs_counts);
+}
+
+void ScopedCounts::clear()
+{
+	rs_scoped_counts_clear(rs_counts);
+}
+
+void ScopedCounts::update(const std::string& key,
+	bool in_scope,
+	unsigned int unread)
+{
+	rs_scoped_counts_update(rs_counts, key.c_str(), in_scope, unread);
+}
+
+unsigned int ScopedCounts::total() const
+{
+	return rs_scoped_counts_total(rs_counts);
+}
+
+unsigned int ScopedCounts::unread() const
+{
+	return rs_scoped_counts_unread(rs_counts);
+}
+
+unsigned int ScopedCounts::unread_members() const
+{
+	return rs_scoped_counts_unread_members(rs_counts);
+}
+
+} // namespace newsboat
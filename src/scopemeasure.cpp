@@ -1,9 +1,25 @@
 #include "scopemeasure.h"
 
+#include <cstdint>
+
+#include "ruststring.h"
+
 extern "C" {
 	void* create_rs_scopemeasure(const char* scope_name);
+	void* create_rs_scopemeasure_with_threshold(const char* scope_name,
+		std::uint64_t threshold_ms,
+		newsboat::Level level);
 	void destroy_rs_scopemeasure(void* object);
 	void rs_scopemeasure_stopover(void* object, const char* stopover_name);
+
+	void* rs_operation_timer_new(std::uint64_t threshold_ms);
+	void rs_operation_timer_free(void* timer);
+	void rs_operation_timer_start_step(void* timer);
+	void rs_operation_timer_finish_step(void* timer,
+		const char* name,
+		const char* const* arguments,
+		std::size_t arguments_count);
+	char* rs_operation_timer_slow_sequence_hint(void* timer);
 }
 
 namespace newsboat {
@@ -13,6 +29,15 @@ ScopeMeasure::ScopeMeasure(const std::string& func)
 	rs_object = create_rs_scopemeasure(func.c_str());
 }
 
+ScopeMeasure::ScopeMeasure(const std::string& func,
+	unsigned int threshold_ms,
+	Level level)
+{
+	rs_object = create_rs_scopemeasure_with_threshold(func.c_str(),
+			threshold_ms,
+			level);
+}
+
 void ScopeMeasure::stopover(const std::string& son)
 {
 	rs_scopemeasure_stopover(rs_object, son.c_str());
@@ -23,4 +48,53 @@ ScopeMeasure::~ScopeMeasure()
 	destroy_rs_scopemeasure(rs_object);
 }
 
+OperationTimer::OperationTimer(bool enabled, unsigned int threshold_ms)
+{
+	if (enabled) {
+		rs_object = rs_operation_timer_new(threshold_ms);
+	}
+}
+
+OperationTimer::~OperationTimer()
+{
+	rs_operation_timer_free(rs_object);
+}
+
+void OperationTimer::start_step()
+{
+	if (rs_object != nullptr) {
+		rs_operation_timer_start_step(rs_object);
+	}
+}
+
+void OperationTimer::finish_step(const std::string& name,
+	const std::vector<std::string>& args)
+{
+	if (rs_object == nullptr) {
+		return;
+	}
+
+	std::vector<const char*> c_args;
+	for (const auto& arg : args) {
+		c_args.push_back(arg.c_str());
+	}
+	rs_operation_timer_finish_step(rs_object,
+		name.c_str(),
+		c_args.data(),
+		c_args.size());
+}
+
+nonstd::optional<std::string> OperationTimer::slow_sequence_hint()
+{
+	if (rs_object == nullptr) {
+		return nonstd::nullopt;
+	}
+
+	char* hint = rs_operation_timer_slow_sequence_hint(rs_object);
+	if (hint == nullptr) {
+		return nonstd::nullopt;
+	}
+	return std::string(RustString(hint));
+}
+
 } // namespace newsboat
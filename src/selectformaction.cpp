@@ -198,6 +198,7 @@ void SelectFormAction::init()
 	FmtStrFormatter fmt;
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 
 	switch (type) {
 	case SelectionType::TAG:
@@ -0,0 +1,108 @@
+#include "selection.h"
+
+#include "ruststring.h"
+
+extern "C" {
+	void* rs_selection_new();
+
+	void rs_selection_free(void* selection);
+
+	void rs_selection_set_order(void* selection,
+		const char* const* keys,
+		std::size_t count);
+
+	bool rs_selection_toggle(void* selection, std::size_t cursor);
+
+	bool rs_selection_select_range(void* selection,
+		std::size_t cursor,
+		std::size_t* count);
+
+	bool rs_selection_is_anchored(void* selection);
+
+	bool rs_selection_is_active(void* selection);
+
+	std::size_t rs_selection_selected_positions(void* selection,
+		std::size_t cursor,
+		std::size_t* positions,
+		std::size_t capacity);
+
+	char* rs_selection_marker(void* selection,
+		std::size_t position,
+		std::size_t cursor);
+
+	bool rs_selection_needs_redraw(void* selection, std::size_t cursor);
+
+	void rs_selection_clear(void* selection);
+}
+
+namespace newsboat {
+
+Selection::Selection()
+{
+	rs_selection = rs_selection_new();
+}
+
+Selection::~Selection()
+{
+	rs_selection_free(rs_selection);
+}
+
+void Selection::set_order(const std::vector<std::string>& guids)
+{
+	std::vector<const char*> keys;
+	keys.reserve(guids.size());
+	for (const auto& guid : guids) {
+		keys.push_back(guid.c_str());
+	}
+	rs_selection_set_order(rs_selection, keys.data(), keys.size());
+}
+
+bool Selection::toggle(std::size_t cursor)
+{
+	return rs_selection_toggle(rs_selection, cursor);
+}
+
+bool Selection::select_range(std::size_t cursor, std::size_t& count)
+{
+	return rs_selection_select_range(rs_selection, cursor, &count);
+}
+
+bool Selection::is_anchored() const
+{
+	return rs_selection_is_anchored(rs_selection);
+}
+
+bool Selection::is_active() const
+{
+	return rs_selection_is_active(rs_selection);
+}
+
+std::vector<std::size_t> Selection::selected_positions(
+	std::size_t cursor) const
+{
+	const std::size_t count = rs_selection_selected_positions(
+			rs_selection, cursor, nullptr, 0);
+	std::vector<std::size_t> positions(count);
+	if (count > 0) {
+		rs_selection_selected_positions(
+			rs_selection, cursor, positions.data(), count);
+	}
+	return positions;
+}
+
+std::string Selection::marker(std::size_t position, std::size_t cursor) const
+{
+	return RustString(rs_selection_marker(rs_selection, position, cursor));
+}
+
+bool Selection::needs_redraw(std::size_t cursor)
+{
+	return rs_selection_needs_redraw(rs_selection, cursor);
+}
+
+void Selection::clear()
+{
+	rs_selection_clear(rs_selection);
+}
+
+} // namespace newsboat
@@ -0,0 +1,40 @@
+#include "stflforms.h"
+
+#include "config.h"
+#include "ruststring.h"
+
+extern "C" {
+	char* rs_stfl_help_form(const char* title, const char* hints);
+
+	char* rs_stfl_urlview_form(const char* title, const char* hints);
+
+	char* rs_stfl_filebrowser_form(const char* title,
+		const char* prompt,
+		const char* hints);
+}
+
+namespace newsboat {
+
+namespace stflforms {
+
+std::string help()
+{
+	return RustString(rs_stfl_help_form(_("Help"), _("q:Quit")));
+}
+
+std::string urlview()
+{
+	return RustString(rs_stfl_urlview_form(_("URLs"),
+				_("q:Quit ENTER:Open Feed")));
+}
+
+std::string filebrowser()
+{
+	return RustString(rs_stfl_filebrowser_form(_("File Browser"),
+				_("File: "),
+				_("q:Abort")));
+}
+
+} // namespace stflforms
+
+} // namespace newsboat
@@ -0,0 +1,97 @@
+#include "termcaps.h"
+
+#include <cstdlib>
+#include <ncurses.h>
+#include <term.h>
+#include <unistd.h>
+
+#include "logger.h"
+#include "ruststring.h"
+
+extern "C" {
+	std::uint8_t rs_detect_color_support(std::int32_t colors,
+		const char* colorterm);
+
+	char* rs_degrade_style(const char* style, std::uint8_t support);
+
+	char* rs_title_format(const char* option,
+		const char* configured,
+		std::uint32_t width);
+
+	bool rs_preview_pane_fits(std::uint32_t total_width,
+		std::uint32_t total_height,
+		std::uint32_t percent,
+		bool beside);
+}
+
+namespace newsboat {
+
+namespace termcaps {
+
+namespace {
+
+// Leave styles alone until the terminal is known; e.g. the tests never
+// look at the terminal at all.
+ColorSupport color_support = ColorSupport::TRUECOLOR;
+
+} // namespace
+
+ColorSupport detect_color_support()
+{
+	int colors = -1;
+	int error = 0;
+	// STFL sets up the terminal only once it draws the first form, and we
+	// need to know the colors before that. The terminfo entry is freed
+	// right away so that ncurses later starts from scratch.
+	if (setupterm(nullptr, STDOUT_FILENO, &error) == OK) {
+		colors = tigetnum(const_cast<char*>("colors"));
+		del_curterm(cur_term);
+	}
+	const char* colorterm = ::getenv("COLORTERM");
+	const auto support = static_cast<ColorSupport>(
+			rs_detect_color_support(colors, colorterm));
+	LOG(Level::DEBUG,
+		"termcaps::detect_color_support: colors = %i, COLORTERM = %s, "
+		"support = %u",
+		colors,
+		colorterm != nullptr ? colorterm : "(unset)",
+		static_cast<unsigned int>(support));
+	return support;
+}
+
+void set_color_support(ColorSupport support)
+{
+	color_support = support;
+}
+
+std::string degrade_style(const std::string& style)
+{
+	// Even truecolor terminals get RGB colors mapped to the palette, since
+	// STFL can't pass them on
+	if (color_support == ColorSupport::TRUECOLOR
+		&& style.find('#') == std::string::npos) {
+		return style;
+	}
+	return RustString(rs_degrade_style(style.c_str(),
+				static_cast<std::uint8_t>(color_support)));
+}
+
+std::string title_format(const std::string& option,
+	const std::string& configured,
+	unsigned int width)
+{
+	return RustString(rs_title_format(option.c_str(), configured.c_str(),
+				width));
+}
+
+bool preview_pane_fits(unsigned int total_width,
+	unsigned int total_height,
+	unsigned int percent,
+	bool beside)
+{
+	return rs_preview_pane_fits(total_width, total_height, percent, beside);
+}
+
+} // namespace termcaps
+
+} // namespace newsboat
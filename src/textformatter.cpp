@@ -8,11 +8,26 @@
 #include "htmlrenderer.h"
 #include "stflpp.h"
 #include "strprintf.h"
+#include "ruststring.h"
 #include "utils.h"
 
+extern "C" {
+	char* rs_hyphenate(const char* word,
+		size_t max_width,
+		bool use_patterns,
+		char** tail);
+}
+
 namespace newsboat {
 
-TextFormatter::TextFormatter() {}
+namespace {
+const std::string SOFT_HYPHEN = "\xC2\xAD";
+}
+
+TextFormatter::TextFormatter()
+	: hyphenation(false)
+{
+}
 
 TextFormatter::~TextFormatter() {}
 
@@ -37,8 +52,28 @@ void TextFormatter::add_lines(
 	}
 }
 
+/// Splits `word` so that the first part, with a hyphen appended, is at most
+/// `max_width` columns wide. Returns false if the word can't be split.
+bool hyphenate(std::string& word, std::string& tail, size_t max_width,
+	bool use_patterns, bool raw)
+{
+	if (!raw && word.find('<') != std::string::npos) {
+		// Don't break STFL tags and escaped angle brackets apart
+		return false;
+	}
+
+	char* rest = nullptr;
+	char* head = rs_hyphenate(word.c_str(), max_width, use_patterns, &rest);
+	if (head == nullptr) {
+		return false;
+	}
+	word = RustString(head);
+	tail = RustString(rest);
+	return true;
+}
+
 std::vector<std::string> wrap_line(const std::string& line, const size_t width,
-	bool raw)
+	bool raw, bool hyphenation)
 {
 	if (line.empty()) {
 		return {""};
@@ -71,6 +106,16 @@ std::vector<std::string> wrap_line(const std::string& line, const size_t width,
 		}
 	};
 
+	// Soft hyphens are invisible unless a word is broken at them
+	auto visible_width = [&strwidth](const std::string& word) {
+		if (word.find(SOFT_HYPHEN) == std::string::npos) {
+			return strwidth(word);
+		}
+		std::string visible = word;
+		utils::remove_soft_hyphens(visible);
+		return strwidth(visible);
+	};
+
 	if (iswhitespace(words[0])) {
 		prefix = substr_with_width(words[0], width);
 		prefix_width = strwidth(prefix);
@@ -79,9 +124,23 @@ std::vector<std::string> wrap_line(const std::string& line, const size_t width,
 
 	std::string curline = prefix;
 
-	for (auto& word : words) {
-		size_t word_width = strwidth(word);
-		size_t curline_width = strwidth(curline);
+	for (auto word : words) {
+		size_t word_width = visible_width(word);
+		size_t curline_width = visible_width(curline);
+
+		// Break the word at a soft hyphen (or, if enabled, wherever English
+		// hyphenation patterns allow) to fill up the current line
+		std::string tail;
+		while (curline_width + word_width > width && curline_width < width
+			&& hyphenate(word, tail, width - curline_width, hyphenation, raw)) {
+			curline.append(word);
+			result.push_back(curline);
+			curline = prefix;
+			word = tail;
+
+			word_width = visible_width(word);
+			curline_width = visible_width(curline);
+		}
 
 		// for languages (e.g., CJK) don't use a space as a word
 		// boundary
@@ -98,8 +157,8 @@ std::vector<std::string> wrap_line(const std::string& line, const size_t width,
 				word.clear();
 			}
 
-			word_width = strwidth(word);
-			curline_width = strwidth(curline);
+			word_width = visible_width(word);
+			curline_width = visible_width(curline);
 		}
 
 		if ((curline_width + word_width) > width) {
@@ -118,6 +177,12 @@ std::vector<std::string> wrap_line(const std::string& line, const size_t width,
 		result.push_back(curline);
 	}
 
+	for (auto& line : result) {
+		if (line.find(SOFT_HYPHEN) != std::string::npos) {
+			utils::remove_soft_hyphens(line);
+		}
+	}
+
 	return result;
 }
 
@@ -129,6 +194,7 @@ std::vector<std::string> format_text_plain_helper(
 	const size_t wrap_width,
 	// if non-zero, softwrappable lines are wrapped at this width
 	const size_t total_width,
+	bool hyphenation,
 	bool raw = false)
 {
 	LOG(Level::DEBUG,
@@ -173,7 +239,7 @@ std::vector<std::string> format_text_plain_helper(
 				continue;
 			}
 			text = utils::consolidate_whitespace(text);
-			for (const auto& line : wrap_line(text, wrap_width, raw)) {
+			for (const auto& line : wrap_line(text, wrap_width, raw, hyphenation)) {
 				store_line(line);
 			}
 			break;
@@ -187,7 +253,7 @@ std::vector<std::string> format_text_plain_helper(
 				store_line(text);
 			} else {
 				for (const auto& line :
-					wrap_line(text, total_width, raw)) {
+					wrap_line(text, total_width, raw, hyphenation)) {
 					store_line(line);
 				}
 			}
@@ -213,7 +279,7 @@ std::pair<std::string, std::size_t> TextFormatter::format_text_to_list(
 	const size_t total_width)
 {
 	auto formatted = format_text_plain_helper(
-			lines, rxman, location, wrap_width, total_width);
+			lines, rxman, location, wrap_width, total_width, hyphenation);
 
 	auto format_cache = std::string("{list");
 	for (auto& line : formatted) {
@@ -235,7 +301,7 @@ std::string TextFormatter::format_text_plain(const size_t width,
 {
 	std::string result;
 	auto formatted = format_text_plain_helper(
-			lines, nullptr, "", width, total_width, true);
+			lines, nullptr, "", width, total_width, hyphenation, true);
 	for (const auto& line : formatted) {
 		result += line + "\n";
 	}
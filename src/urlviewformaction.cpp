@@ -60,11 +60,20 @@ bool UrlViewFormAction::process_operation(Operation op,
 		break;
 	case OP_OPENINBROWSER:
 	case OP_OPENBROWSER_AND_MARK:
+	case OP_OPENINBROWSER_NONINTERACTIVELY:
 	case OP_OPEN: {
 		if (!links.empty()) {
 			const unsigned int pos = urls_list.get_position();
 			v->set_status(_("Starting browser..."));
-			v->open_in_browser(links[pos].first);
+			const auto exit_code = v->open_in_browser(links[pos].first,
+					op != OP_OPENINBROWSER_NONINTERACTIVELY);
+			if (!exit_code.has_value()) {
+				v->show_error(_("Failed to spawn browser"));
+				return false;
+			} else if (*exit_code != 0) {
+				v->show_error(strprintf::fmt(_("Browser returned error code %i"), *exit_code));
+				return false;
+			}
 			v->set_status("");
 		} else {
 			v->show_error(_("No links available!"));
@@ -121,11 +130,13 @@ bool UrlViewFormAction::process_operation(Operation op,
 void UrlViewFormAction::prepare()
 {
 	if (do_redraw) {
+		const bool strip_bidi =
+			cfg->get_configvalue("strip-bidi-controls") != "none";
 		ListFormatter listfmt;
 		unsigned int i = 0;
 		for (const auto& link : links) {
-			listfmt.add_line(utils::quote_for_stfl(strprintf::fmt("%2u  %s", i + 1,
-						link.first)));
+			listfmt.add_line(utils::quote_for_stfl(utils::format_urlview_line(i + 1,
+						link.first, strip_bidi)));
 			i++;
 		}
 		urls_list.stfl_replace_lines(listfmt);
@@ -143,6 +154,7 @@ void UrlViewFormAction::init()
 	FmtStrFormatter fmt;
 	fmt.register_fmt('N', PROGRAM_NAME);
 	fmt.register_fmt('V', utils::program_version());
+	fmt.register_fmt('S', v->dialog_breadcrumb(this));
 
 	f.set("head",
 		fmt.do_format(
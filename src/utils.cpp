@@ -11,7 +11,9 @@
 #include <cwchar>
 #include <errno.h>
 #include <fcntl.h>
+#include <fstream>
 #include <iconv.h>
+#include <iterator>
 #include <langinfo.h>
 #include <libxml/uri.h>
 #include <locale>
@@ -50,40 +52,19 @@ using HTTPMethod = newsboat::utils::HTTPMethod;
 
 namespace newsboat {
 
-namespace utils {
-void append_escapes(std::string& str, char c)
+std::string utils::strip_comments(const std::string& line)
 {
-	switch (c) {
-	case 'n':
-		str.append("\n");
-		break;
-	case 'r':
-		str.append("\r");
-		break;
-	case 't':
-		str.append("\t");
-		break;
-	case '"':
-		str.append("\"");
-		break;
-	// escaped backticks are passed through, still escaped. We un-escape
-	// them in ConfigParser::evaluate_backticks
-	case '`':
-		str.append("\\`");
-		break;
-	case '\\':
-		str.append("\\");
-		break;
-	default:
-		str.push_back(c);
-		break;
-	}
+	return RustString(rs_strip_comments(line.c_str()));
 }
+
+std::string utils::unescape_quoted(const std::string& input)
+{
+	return RustString(rs_unescape_quoted(input.c_str()));
 }
 
-std::string utils::strip_comments(const std::string& line)
+std::string utils::escape_quoted(const std::string& input)
 {
-	return RustString(rs_strip_comments(line.c_str()));
+	return RustString(rs_escape_quoted(input.c_str()));
 }
 
 std::vector<std::string> utils::tokenize_quoted(const std::string& str,
@@ -107,8 +88,8 @@ std::vector<std::string> utils::tokenize_quoted(const std::string& str,
 	 * 	[2]: x
 	 * 	y
 	 *
-	 * 	\", \r, \n, \t and \v are replaced with the literals that you
-	 * know from C/C++ strings.
+	 * 	Escapes inside quotes are handled by unescape_quoted(), the same
+	 * 	way the tokenizer of operation sequences does.
 	 *
 	 */
 	std::vector<std::string> tokens;
@@ -141,22 +122,22 @@ nonstd::optional<std::string> utils::extract_token_quoted(std::string& str,
 	std::string token;
 	if (str[0] == '"') {
 		std::string::size_type pos = 1;
+		std::string::size_type end = std::string::npos;
 		while (pos < str.length()) {
 			if (str[pos] == '"') {
 				// We've reached the end of this quoted token.
+				end = pos;
 				++pos;
 				break;
 			} else if (str[pos] == '\\') {
-				pos += 1;
-				if (pos < str.length()) {
-					append_escapes(token, str[pos]);
-					pos += 1;
-				}
+				// Skip the escaped character, which might be a quote
+				pos += 2;
 			} else {
-				token.push_back(str[pos]);
 				++pos;
 			}
 		}
+		pos = std::min(pos, str.length());
+		token = unescape_quoted(str.substr(1, std::min(end, pos) - 1));
 		str = str.substr(pos);
 	} else {
 		auto end_of_token = str.find_first_of(delimiters);
@@ -395,6 +376,15 @@ std::string utils::utf8_to_locale(const std::string& text)
 	return utils::convert_text(text, nl_langinfo(CODESET), "utf-8");
 }
 
+std::string utils::locale_to_utf8(const std::string& text)
+{
+	if (text.empty()) {
+		return {};
+	}
+
+	return utils::convert_text(text, "utf-8", nl_langinfo(CODESET));
+}
+
 std::string utils::get_command_output(const std::string& cmd)
 {
 	return RustString(rs_get_command_output(cmd.c_str()));
@@ -557,6 +547,13 @@ std::string utils::absolute_url(const std::string& url, const std::string& link)
 	return RustString(rs_absolute_url(url.c_str(), link.c_str()));
 }
 
+std::string utils::absolute_url_without_fragment(const std::string& url,
+	const std::string& link)
+{
+	return RustString(rs_absolute_url_without_fragment(url.c_str(),
+				link.c_str()));
+}
+
 std::string utils::get_useragent(ConfigContainer* cfgcont)
 {
 	std::string ua_pref = cfgcont->get_configvalue("user-agent");
@@ -878,6 +875,60 @@ int utils::mkdir_parents(const std::string& p, mode_t mode)
 	return rs_mkdir_parents(p.c_str(), static_cast<std::uint32_t>(mode));
 }
 
+nonstd::optional<std::string> utils::atomic_write(const std::string& path,
+	const std::string& contents,
+	mode_t mode)
+{
+	char* error = rs_atomic_write(path.c_str(), contents.data(),
+			contents.size(), static_cast<std::uint32_t>(mode));
+	if (error != nullptr) {
+		return std::string(RustString(error));
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<std::string> utils::atomic_write_urls_file(
+	const std::string& path,
+	const std::string& contents)
+{
+	char* error = rs_atomic_write_urls_file(path.c_str(), contents.data(),
+			contents.size());
+	if (error != nullptr) {
+		return std::string(RustString(error));
+	}
+	return nonstd::nullopt;
+}
+
+nonstd::optional<std::string> utils::read_text_file(const std::string& path,
+	std::string& warning)
+{
+	std::ifstream in(path, std::ios::binary);
+	if (!in.is_open()) {
+		return nonstd::nullopt;
+	}
+	const std::string contents((std::istreambuf_iterator<char>(in)),
+		std::istreambuf_iterator<char>());
+
+	char* warning_ptr = nullptr;
+	const std::string text = RustString(rs_file_encoding_decode(path.c_str(),
+				contents.data(), contents.size(), nl_langinfo(CODESET),
+				&warning_ptr));
+	warning = RustString(warning_ptr);
+	return text;
+}
+
+nonstd::optional<std::string> utils::convert_file_to_utf8(
+	const std::string& path,
+	bool& converted)
+{
+	char* error = rs_file_encoding_convert(path.c_str(), nl_langinfo(CODESET),
+			&converted);
+	if (error != nullptr) {
+		return std::string(RustString(error));
+	}
+	return nonstd::nullopt;
+}
+
 std::string utils::make_title(const std::string& const_url)
 {
 	return RustString(rs_make_title(const_url.c_str()));
@@ -915,6 +966,18 @@ void utils::remove_soft_hyphens(std::string& text)
 	text = RustString(rs_remove_soft_hyphens(text.c_str()));
 }
 
+std::string utils::strip_bidi_controls(const std::string& text)
+{
+	return RustString(rs_strip_bidi_controls(text.c_str()));
+}
+
+std::string utils::format_urlview_line(unsigned int number,
+	const std::string& url,
+	bool strip_bidi)
+{
+	return RustString(rs_format_urlview_line(number, url.c_str(), strip_bidi));
+}
+
 bool utils::is_valid_podcast_type(const std::string& mimetype)
 {
 	return rs_is_valid_podcast_type(mimetype.c_str());
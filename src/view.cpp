@@ -1,5 +1,6 @@
 #include "view.h"
 
+#include <algorithm>
 #include <assert.h>
 #include <cerrno>
 #include <cstdio>
@@ -29,10 +30,10 @@ extern "C" {
 #include "dialogsformaction.h"
 #include "exception.h"
 #include "feedlist.h"
-#include "filebrowser.h"
+#include "feedpreview.h"
+#include "filterbuilderformaction.h"
 #include "fmtstrformatter.h"
 #include "formaction.h"
-#include "help.h"
 #include "helpformaction.h"
 #include "htmlrenderer.h"
 #include "itemlist.h"
@@ -41,14 +42,18 @@ extern "C" {
 #include "itemviewformaction.h"
 #include "keymap.h"
 #include "logger.h"
+#include "longoperation.h"
+#include "memoryreport.h"
+#include "markreadpolicy.h"
 #include "matcherexception.h"
 #include "regexmanager.h"
 #include "reloadthread.h"
 #include "rssfeed.h"
 #include "selectformaction.h"
 #include "selecttag.h"
+#include "stflforms.h"
 #include "strprintf.h"
-#include "urlview.h"
+#include "termcaps.h"
 #include "urlviewformaction.h"
 #include "utils.h"
 
@@ -63,6 +68,8 @@ View::View(Controller* c)
 	, cfg(0)
 	, keys(0)
 	, current_formaction(0)
+	, modal_depth(0)
+	, ran_interactive_program(false)
 	, rxman(c->get_regexmanager())
 	, is_inside_qna(false)
 	, is_inside_cmdline(false)
@@ -120,30 +127,93 @@ void View::show_error(const std::string& msg)
 
 bool View::run_commands(const std::vector<MacroCmd>& commands)
 {
+	OperationTimer timer(cfg->get_configvalue_as_bool("operation-timing"),
+		cfg->get_configvalue_as_int("operation-timing-threshold"));
+	ran_interactive_program = false;
+	bool succeeded = true;
 	for (auto command : commands) {
 		if (formaction_stack_size() == 0) {
 			return true;
 		}
+		if (command.condition == MacroCondition::IF_SUCCEEDED && !succeeded) {
+			continue;
+		} else if (command.condition == MacroCondition::IF_FAILED
+			&& succeeded) {
+			continue;
+		} else if (command.condition == MacroCondition::ALWAYS
+			&& !succeeded) {
+			// Operation failed, and nothing handled that; abort
+			return false;
+		}
 		std::shared_ptr<FormAction> fa = get_current_formaction();
 		fa->prepare();
 		fa->get_form().run(-1);
-		if (!fa->process_op(command.op, true, &command.args)) {
-			// Operation failed, abort
-			return false;
-		}
+		timer.start_step();
+		command.op = keys->resolve_default_action(command.op, fa->id());
+		succeeded = process_op(fa, command.op, true, &command.args);
+		timer.finish_step(keys->getopname(command.op), command.args);
+	}
+	if (succeeded) {
+		show_slow_sequence_hint(timer);
+	}
+	return succeeded;
+}
+
+bool View::process_op(std::shared_ptr<FormAction> fa,
+	Operation op,
+	bool automatic,
+	std::vector<std::string>* args)
+{
+	if (!keys->is_long_operation(op)) {
+		return fa->process_op(op, automatic, args);
+	}
+
+	LongOperation operation(keys->getopname(op));
+	const std::string status = operation.status();
+	set_status(status);
+	const bool succeeded = fa->process_op(op, automatic, args);
+
+	const auto summary = operation.finish(succeeded,
+			cfg->get_configvalue_as_int("long-operation-notify-threshold"));
+	if (formaction_stack_size() > 0
+		&& get_current_formaction()->get_form().get("msg") == status) {
+		// The operation didn't put anything into the status line
+		set_status("");
+	}
+	if (summary) {
+		ctrl->get_reloader()->notify(summary.value());
+	}
+	return succeeded;
+}
+
+void View::show_detached_browser_failures()
+{
+	for (const auto& failure : detached_browsers.take_failures()) {
+		show_error(failure);
+	}
+}
+
+void View::show_slow_sequence_hint(OperationTimer& timer)
+{
+	if (ran_interactive_program) {
+		return;
+	}
+	const auto hint = timer.slow_sequence_hint();
+	if (hint && formaction_stack_size() > 0) {
+		set_status(hint.value());
 	}
-	return true;
 }
 
 int View::run()
 {
 	bool have_macroprefix = false;
 
+	termcaps::set_color_support(termcaps::detect_color_support());
+
 	feedlist_form = std::make_shared<FeedListFormAction>(
 			this, feedlist_str, rsscache, filters, cfg, rxman);
 	apply_colors(feedlist_form);
-	formaction_stack.push_back(feedlist_form);
-	current_formaction = formaction_stack_size() - 1;
+	push_formaction(feedlist_form);
 
 	get_current_formaction()->init();
 
@@ -169,7 +239,9 @@ int View::run()
 		fa->prepare();
 
 		// we then receive the event and ignore timeouts.
-		const char* event = fa->get_form().run(60000);
+		const char* event = fa->get_form().run(fa->get_event_timeout());
+
+		show_detached_browser_failures();
 
 		if (ctrl_c_hit) {
 			ctrl_c_hit = false;
@@ -199,8 +271,12 @@ int View::run()
 			LOG(Level::DEBUG,
 				"View::run: running macro `%s'",
 				event);
-			run_commands(keys->get_macro(event));
 			set_status("");
+			if (keys->get_macro_parameter_count(event) > 0) {
+				fa->start_macro_argument_qna(event);
+			} else {
+				run_commands(keys->get_macro(event));
+			}
 		} else {
 			const Operation op = keys->get_operation(event, fa->id());
 
@@ -216,7 +292,14 @@ int View::run()
 
 			// now we handle the operation to the
 			// formaction.
-			fa->process_op(op);
+			OperationTimer timer(
+				cfg->get_configvalue_as_bool("operation-timing"),
+				cfg->get_configvalue_as_int("operation-timing-threshold"));
+			ran_interactive_program = false;
+			timer.start_step();
+			process_op(fa, op);
+			timer.finish_step(keys->getopname(op), {});
+			show_slow_sequence_hint(timer);
 		}
 	}
 
@@ -232,8 +315,8 @@ std::string View::run_modal(std::shared_ptr<FormAction> f,
 	f->init();
 	unsigned int stacksize = formaction_stack.size();
 
-	formaction_stack.push_back(f);
-	current_formaction = formaction_stack_size() - 1;
+	push_formaction(f);
+	modal_depth++;
 
 	while (formaction_stack.size() > stacksize) {
 		std::shared_ptr<FormAction> fa = get_current_formaction();
@@ -259,6 +342,7 @@ std::string View::run_modal(std::shared_ptr<FormAction> f,
 
 		fa->process_op(op);
 	}
+	modal_depth--;
 
 	if (value == "") {
 		return "";
@@ -309,7 +393,8 @@ void View::open_in_pager(const std::string& filename)
 		cmdline = fmt.do_format(pager, 0);
 	} else {
 		const char* env_pager = nullptr;
-		if (pager != "") {
+		// The internal pager only shows articles
+		if (pager != "" && pager != "internal") {
 			cmdline.append(pager);
 		} else if ((env_pager = getenv("PAGER")) != nullptr) {
 			cmdline.append(env_pager);
@@ -320,28 +405,49 @@ void View::open_in_pager(const std::string& filename)
 		cmdline.append(filename);
 	}
 	Stfl::reset();
+	ran_interactive_program = true;
 	utils::run_interactively(cmdline, "View::open_in_pager");
 	pop_current_formaction();
 }
 
-nonstd::optional<std::uint8_t> View::open_in_browser(const std::string& url)
+void View::show_text(const std::string& text)
 {
-	formaction_stack.push_back(std::shared_ptr<FormAction>());
-	current_formaction = formaction_stack_size() - 1;
-	std::string cmdline;
-	const std::string browser = cfg->get_configvalue("browser");
-	const std::string escaped_url = "'" + utils::replace_all(url, "'", "%27") + "'";
-	if (browser.find("%u") != std::string::npos) {
-		cmdline = utils::replace_all(browser, "%u", escaped_url);
-	} else {
-		if (browser != "") {
-			cmdline.append(browser);
-		} else {
-			cmdline.append("lynx");
+	if (text.find('\n') == std::string::npos) {
+		set_status(text);
+		return;
+	}
+
+	const std::string filename = ctrl->create_temporary_file("newsboat-text");
+	if (filename.empty()) {
+		show_error(_("Error: couldn't create a temporary file."));
+		return;
+	}
+	{
+		std::ofstream out(filename, std::ios::binary);
+		out << text << '\n';
+	}
+	open_in_pager(filename);
+	::unlink(filename.c_str());
+}
+
+nonstd::optional<std::uint8_t> View::open_in_browser(const std::string& url,
+	bool interactive)
+{
+	const std::string cmdline =
+		browser::cmdline(cfg->get_configvalue("browser"), url);
+	if (!interactive) {
+		LOG(Level::DEBUG, "View::open_in_browser: running `%s' in background",
+			cmdline);
+		if (!detached_browsers.launch(url, cmdline)) {
+			return nonstd::nullopt;
 		}
-		cmdline.append(" " + escaped_url);
+		return 0;
 	}
+
+	formaction_stack.push_back(std::shared_ptr<FormAction>());
+	current_formaction = formaction_stack_size() - 1;
 	Stfl::reset();
+	ran_interactive_program = true;
 	const auto ret = utils::run_interactively(cmdline, "View::open_in_browser");
 	pop_current_formaction();
 	return ret;
@@ -404,8 +510,8 @@ void View::push_searchresult(std::shared_ptr<RssFeed> feed,
 		apply_colors(searchresult);
 		searchresult->set_parent_formaction(get_current_formaction());
 		searchresult->init();
-		formaction_stack.push_back(searchresult);
-		current_formaction = formaction_stack_size() - 1;
+		push_formaction(searchresult,
+			strprintf::fmt(_("Search for \"%s\""), phrase));
 	} else {
 		show_error(_("Error: feed contains no items!"));
 	}
@@ -429,8 +535,7 @@ std::shared_ptr<ItemListFormAction> View::push_itemlist(
 		apply_colors(itemlist);
 		itemlist->set_parent_formaction(get_current_formaction());
 		itemlist->init();
-		formaction_stack.push_back(itemlist);
-		current_formaction = formaction_stack_size() - 1;
+		push_formaction(itemlist, feed->title());
 		return itemlist;
 	} else {
 		show_error(_("Error: feed contains no items!"));
@@ -438,6 +543,24 @@ std::shared_ptr<ItemListFormAction> View::push_itemlist(
 	}
 }
 
+void View::push_feed_preview(const std::string& url)
+{
+	set_status(strprintf::fmt(_("Fetching %s..."), url));
+	std::string error;
+	const auto feed = feedpreview::fetch(url, cfg, error);
+	if (feed == nullptr) {
+		show_error(strprintf::fmt(_("Error: couldn't preview the feed: %s"),
+				error));
+		return;
+	}
+
+	feed->sort(cfg->get_article_sort_strategy());
+	if (push_itemlist(feed)) {
+		set_status(_("This is a preview. Use `subscribe' to add the feed to "
+				"the urls file."));
+	}
+}
+
 void View::push_itemlist(unsigned int pos)
 {
 	std::shared_ptr<RssFeed> feed =
@@ -473,16 +596,28 @@ void View::push_itemview(std::shared_ptr<RssFeed> f,
 		}
 		apply_colors(itemview);
 		itemview->init();
-		formaction_stack.push_back(itemview);
-		current_formaction = formaction_stack_size() - 1;
+		push_formaction(itemview);
 	} else {
 		std::shared_ptr<RssItem> item = f->get_item_by_guid(guid);
 		std::string filename = get_ctrl()->write_temporary_item(item);
+
+		// We can't tell how far the user scrolled in an external pager,
+		// so only `open` and `dwell` apply
+		MarkReadPolicy policy;
+		policy.set(cfg->get_configvalue("mark-read-on"));
+		DwellTimer timer;
+		timer.start(policy.dwell_ms());
 		open_in_pager(filename);
+		MarkReadPolicy::Context context;
+		context.dwelled_ms = timer.elapsed_ms();
+
+		const bool mark_read =
+			policy.should_mark_read(MarkReadPolicy::Trigger::OPEN, context) ||
+			policy.should_mark_read(MarkReadPolicy::Trigger::DWELL, context);
 		try {
 			bool old_unread = item->unread();
-			item->set_unread(false);
-			if (old_unread) {
+			if (mark_read && old_unread) {
+				item->set_unread(false);
 				get_ctrl()->mark_article_read(
 					item->guid(), true);
 			}
@@ -504,8 +639,7 @@ void View::view_dialogs()
 		dialogs->set_parent_formaction(fa);
 		apply_colors(dialogs);
 		dialogs->init();
-		formaction_stack.push_back(dialogs);
-		current_formaction = formaction_stack_size() - 1;
+		push_formaction(dialogs);
 	}
 }
 
@@ -514,33 +648,31 @@ void View::push_help()
 	auto fa = get_current_formaction();
 
 	std::shared_ptr<HelpFormAction> helpview(
-		new HelpFormAction(this, help_str, cfg));
+		new HelpFormAction(this, stflforms::help(), cfg));
 	apply_colors(helpview);
 	helpview->set_context(fa->id());
 	helpview->set_parent_formaction(fa);
 	helpview->init();
-	formaction_stack.push_back(helpview);
-	current_formaction = formaction_stack_size() - 1;
+	push_formaction(helpview);
 }
 
 void View::push_urlview(const std::vector<LinkPair>& links,
 	std::shared_ptr<RssFeed>& feed)
 {
 	std::shared_ptr<UrlViewFormAction> urlview(
-		new UrlViewFormAction(this, feed, urlview_str, cfg));
+		new UrlViewFormAction(this, feed, stflforms::urlview(), cfg));
 	apply_colors(urlview);
 	urlview->set_parent_formaction(get_current_formaction());
 	urlview->init();
 	urlview->set_links(links);
-	formaction_stack.push_back(urlview);
-	current_formaction = formaction_stack_size() - 1;
+	push_formaction(urlview);
 }
 
 std::string View::run_filebrowser(const std::string& default_filename,
 	const std::string& dir)
 {
 	std::shared_ptr<FileBrowserFormAction> filebrowser(
-		new FileBrowserFormAction(this, filebrowser_str, cfg));
+		new FileBrowserFormAction(this, stflforms::filebrowser(), cfg));
 	apply_colors(filebrowser);
 	filebrowser->set_dir(dir);
 	filebrowser->set_default_filename(default_filename);
@@ -551,7 +683,7 @@ std::string View::run_filebrowser(const std::string& default_filename,
 std::string View::run_dirbrowser(const std::string& dir)
 {
 	std::shared_ptr<DirBrowserFormAction> dirbrowser(
-		new DirBrowserFormAction(this, filebrowser_str, cfg));
+		new DirBrowserFormAction(this, stflforms::filebrowser(), cfg));
 	apply_colors(dirbrowser);
 	dirbrowser->set_dir(dir);
 	dirbrowser->set_parent_formaction(get_current_formaction());
@@ -586,6 +718,17 @@ std::string View::select_filter(const std::vector<FilterNameExprPair>& filters)
 	return selecttag->get_selected_value();
 }
 
+std::string View::build_filter(const std::string& context)
+{
+	std::shared_ptr<FilterBuilderFormAction> builder(
+		new FilterBuilderFormAction(this, selecttag_str, cfg));
+	apply_colors(builder);
+	builder->set_parent_formaction(get_current_formaction());
+	builder->set_context(context);
+	run_modal(builder, "");
+	return builder->get_expression();
+}
+
 char View::confirm(const std::string& prompt, const std::string& charset)
 {
 	LOG(Level::DEBUG, "View::confirm: charset = %s", charset);
@@ -739,6 +882,40 @@ bool View::get_prev_unread_feed(ItemListFormAction& itemlist)
 	return false;
 }
 
+nonstd::optional<std::string> View::open_feed_with_unread(bool forward,
+	ItemListFormAction* itemlist)
+{
+	unsigned int feedpos = 0;
+	switch (feedlist_form->jump_to_feed_with_unread(forward, feedpos)) {
+	case FeedJump::JUMPED:
+		break;
+	case FeedJump::ONLY_CURRENT:
+		return std::string(_("No other feeds with unread items."));
+	case FeedJump::NONE_IN_DIRECTION:
+		return std::string(forward
+				? _("No more feeds with unread items below.")
+				: _("No more feeds with unread items above."));
+	case FeedJump::NO_UNREAD:
+		return std::string(_("No feeds with unread items."));
+	}
+
+	const auto feed = feedlist_form->get_feed();
+	if (itemlist != nullptr) {
+		prepare_query_feed(feed);
+		itemlist->set_feed(feed);
+		itemlist->set_pos(feedpos);
+		itemlist->init();
+		itemlist->jump_to_next_unread_item(true);
+	} else {
+		const auto new_itemlist = push_itemlist(feed);
+		if (new_itemlist) {
+			new_itemlist->set_pos(feedpos);
+			new_itemlist->jump_to_next_unread_item(true);
+		}
+	}
+	return nonstd::nullopt;
+}
+
 bool View::get_next_unread(ItemListFormAction& itemlist,
 	ItemViewFormAction* itemview)
 {
@@ -902,6 +1079,7 @@ void View::pop_current_formaction()
 		++it;
 	}
 	formaction_stack.erase(it);
+	dialog_stack.remove(f.get());
 	if (f == nullptr) {
 		// XXX TODO this is not correct... we'd need to return to the previous one, but nullptr formactions have no parent
 		current_formaction = formaction_stack_size() -
@@ -948,6 +1126,7 @@ void View::remove_formaction(unsigned int pos)
 		++it;
 	}
 	formaction_stack.erase(it);
+	dialog_stack.remove(f.get());
 	current_formaction--;
 	if (f != nullptr && formaction_stack.size() > 0) {
 		// we set back the parent formactions of those who reference the
@@ -1006,6 +1185,21 @@ std::vector<std::pair<unsigned int, std::string>> View::get_formaction_names()
 	return formaction_names;
 }
 
+void View::show_memory_report()
+{
+	MemoryReport report;
+	for (const auto& form : formaction_stack) {
+		if (form) {
+			form->add_to_memory_report(report);
+		}
+	}
+	const auto lines = report.lines();
+	for (const auto& line : lines) {
+		LOG(Level::DEBUG, "View::show_memory_report: %s", line);
+	}
+	show_text(utils::join(lines, "\n"));
+}
+
 void View::goto_next_dialog()
 {
 	current_formaction++;
@@ -1023,6 +1217,77 @@ void View::goto_prev_dialog()
 	}
 }
 
+std::string View::dialog_breadcrumb(const FormAction* fa)
+{
+	return dialog_stack.breadcrumb(fa);
+}
+
+void View::pop_to_feedlist()
+{
+	close_dialogs(dialog_stack.unwind_to_feedlist(
+			get_current_formaction().get()));
+}
+
+void View::close_all_dialogs()
+{
+	close_dialogs(dialog_stack.close_all());
+}
+
+void View::push_formaction(std::shared_ptr<FormAction> fa,
+	const std::string& name)
+{
+	dialog_stack.push(fa.get(), fa->id(), name,
+		get_current_formaction().get());
+	formaction_stack.push_back(fa);
+	current_formaction = formaction_stack_size() - 1;
+}
+
+void View::close_dialogs(const std::vector<const void*>& dialogs)
+{
+	if (modal_depth > 0) {
+		// The code that opened the modal dialog is still running, and
+		// it might use the dialogs we'd close.
+		show_error(_("Error: close the current dialog first."));
+		return;
+	}
+
+	const auto find_dialog = [this](const void* dialog) {
+		return std::find_if(formaction_stack.begin(), formaction_stack.end(),
+		[dialog](const std::shared_ptr<FormAction>& fa) {
+			return fa.get() == dialog;
+		});
+	};
+
+	for (const auto dialog : dialogs) {
+		auto it = find_dialog(dialog);
+		if (it == formaction_stack.end()) {
+			// Already closed along with another dialog
+			continue;
+		}
+
+		// Keep the dialog alive until it's done processing
+		std::shared_ptr<FormAction> fa = *it;
+		current_formaction = it - formaction_stack.begin();
+		fa->process_op(OP_QUIT, true);
+
+		if (find_dialog(dialog) != formaction_stack.end()) {
+			LOG(Level::DEBUG,
+				"View::close_dialogs: %s didn't close, stopping",
+				fa->id());
+			return;
+		}
+	}
+
+	if (feedlist_form == nullptr) {
+		return;
+	}
+	auto it = find_dialog(feedlist_form.get());
+	if (it != formaction_stack.end()) {
+		current_formaction = it - formaction_stack.begin();
+		feedlist_form->set_redraw(true);
+	}
+}
+
 void View::inside_qna(bool f)
 {
 	curs_set(f ? 1 : 0);
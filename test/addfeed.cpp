@@ -0,0 +1,79 @@
+#include "addfeed.h"
+
+#include <fstream>
+
+#include "3rd-party/catch.hpp"
+#include "configcontainer.h"
+#include "fileurlreader.h"
+#include "test-helpers/tempfile.h"
+
+using namespace newsboat;
+
+TEST_CASE("add() appends the feed to the urls file", "[addfeed]")
+{
+	ConfigContainer cfg;
+	TestHelpers::TempFile urlsFile;
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "https://example.com/other.xml \"tag\"";
+	}
+
+	FileUrlReader urls(urlsFile.get_path());
+	const auto result = addfeed::add("file://data/rss.xml", urls, &cfg);
+	REQUIRE(result.outcome == addfeed::Outcome::ADDED);
+	REQUIRE(result.feed_url == "file://data/rss.xml");
+	REQUIRE(result.message == "Added file://data/rss.xml to " +
+		urlsFile.get_path() + ".");
+
+	FileUrlReader reread(urlsFile.get_path());
+	reread.reload();
+	REQUIRE(reread.get_urls() == std::vector<std::string>({
+		"https://example.com/other.xml",
+		"file://data/rss.xml"
+	}));
+	REQUIRE(reread.get_tags("https://example.com/other.xml") ==
+		std::vector<std::string>({"tag"}));
+}
+
+TEST_CASE("add() doesn't add a feed that's in the urls file already",
+	"[addfeed]")
+{
+	ConfigContainer cfg;
+	TestHelpers::TempFile urlsFile;
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "file://data/rss.xml\n";
+	}
+
+	FileUrlReader urls(urlsFile.get_path());
+	const auto result = addfeed::add("file://data/rss.xml", urls, &cfg);
+	REQUIRE(result.outcome == addfeed::Outcome::ALREADY_SUBSCRIBED);
+	REQUIRE(result.message ==
+		"You are already subscribed to file://data/rss.xml.");
+
+	urls.reload();
+	REQUIRE(urls.get_urls().size() == 1);
+}
+
+TEST_CASE("add() rejects things that aren't feeds", "[addfeed]")
+{
+	ConfigContainer cfg;
+	TestHelpers::TempFile urlsFile;
+	FileUrlReader urls(urlsFile.get_path());
+
+	SECTION("Not a URL") {
+		const auto result = addfeed::add("example.com/feed", urls, &cfg);
+		REQUIRE(result.outcome == addfeed::Outcome::FAILED);
+		REQUIRE(result.feed_url.empty());
+	}
+
+	SECTION("Not a feed") {
+		const auto result = addfeed::add("file://data/non-existent.xml", urls,
+				&cfg);
+		REQUIRE(result.outcome == addfeed::Outcome::FAILED);
+		REQUIRE(result.feed_url == "file://data/non-existent.xml");
+	}
+
+	std::ifstream in(urlsFile.get_path());
+	REQUIRE_FALSE(in.is_open());
+}
@@ -1,13 +1,22 @@
 #include "cache.h"
 
+#include <cinttypes>
 #include <sstream>
+#include <unistd.h>
 
 #include "3rd-party/catch.hpp"
 #include "configcontainer.h"
+#include "dbexception.h"
+#include "matcher.h"
 #include "rssfeed.h"
 #include "rssignores.h"
 #include "rssparser.h"
+#include "strprintf.h"
+#include "test-helpers/exceptionwithmsg.h"
+#include "test-helpers/misc.h"
+#include "test-helpers/tempdir.h"
 #include "test-helpers/tempfile.h"
+#include "utils.h"
 
 using namespace newsboat;
 
@@ -303,6 +312,38 @@ TEST_CASE(
 	}
 }
 
+TEST_CASE("cleanup_and_vacuum cleans up even if `cleanup-on-quit` is off, "
+	"and leaves the cache usable",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+
+	std::vector<std::string> feedurls = {
+		"file://data/rss.xml", "file://data/atom10_1.xml"
+	};
+
+	std::vector<std::shared_ptr<RssFeed>> feeds;
+	ConfigContainer cfg;
+	cfg.set_configvalue("cleanup-on-quit", "no");
+	Cache rsscache(dbfile.get_path(), &cfg);
+	for (const auto& url : feedurls) {
+		RssParser parser(url, &rsscache, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		feeds.push_back(feed);
+		rsscache.externalize_rssfeed(feed, false);
+	}
+
+	// Drop first feed; it should now be removed from the Cache, too
+	feeds.erase(feeds.cbegin(), feeds.cbegin() + 1);
+	REQUIRE_NOTHROW(rsscache.cleanup_and_vacuum(feeds));
+
+	std::shared_ptr<RssFeed> feed =
+		rsscache.internalize_rssfeed(feedurls[0], nullptr);
+	REQUIRE(feed->total_item_count() == 0);
+	feed = rsscache.internalize_rssfeed(feedurls[1], nullptr);
+	REQUIRE(feed->total_item_count() != 0);
+}
+
 TEST_CASE("fetch_descriptions fills out feed item's descriptions", "[Cache]")
 {
 	ConfigContainer cfg;
@@ -550,6 +591,79 @@ TEST_CASE("update_rssitem_flags dumps `rss_item` object's flags to DB",
 	REQUIRE(feed->items()[0]->flags() == "abc");
 }
 
+TEST_CASE("update_rssitem_states stores the states of all the given items",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	std::unique_ptr<Cache> rsscache(new Cache(dbfile.get_path(), &cfg));
+	const auto feedurl = "file://data/rss.xml";
+	RssParser parser(feedurl, rsscache.get(), &cfg, nullptr);
+	std::shared_ptr<RssFeed> feed = parser.parse();
+	rsscache->externalize_rssfeed(feed, false);
+
+	std::vector<std::shared_ptr<RssItem>> batch;
+	for (unsigned int i = 0; i < 3; ++i) {
+		auto item = feed->items()[i];
+		item->set_unread_nowrite(false);
+		item->set_flags("ab");
+		batch.push_back(item);
+	}
+	batch[2]->set_deleted(true);
+	const std::string untouched_guid = feed->items()[3]->guid();
+
+	REQUIRE_NOTHROW(rsscache->update_rssitem_states(batch));
+
+	rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+	feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	// The deleted item isn't loaded
+	REQUIRE(feed->total_item_count() == 7);
+	for (unsigned int i = 0; i < 2; ++i) {
+		const auto item = feed->get_item_by_guid(batch[i]->guid());
+		REQUIRE_FALSE(item->unread());
+		REQUIRE(item->flags() == "ab");
+	}
+	const auto untouched = feed->get_item_by_guid(untouched_guid);
+	REQUIRE(untouched->unread());
+	REQUIRE(untouched->flags().empty());
+}
+
+TEST_CASE("Notes stored with update_rssitem_note survive feed reloads",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	std::unique_ptr<Cache> rsscache(new Cache(dbfile.get_path(), &cfg));
+	const auto feedurl = "file://data/rss.xml";
+	RssParser parser(feedurl, rsscache.get(), &cfg, nullptr);
+	std::shared_ptr<RssFeed> feed = parser.parse();
+	rsscache->externalize_rssfeed(feed, false);
+
+	auto item = feed->items()[0];
+	item->set_note("for the weekly report");
+	REQUIRE_NOTHROW(rsscache->update_rssitem_note(item.get()));
+
+	// A reload parses the feed again, so the fresh items have no notes
+	feed = parser.parse();
+	REQUIRE(feed->items()[0]->note() == "");
+	rsscache->externalize_rssfeed(feed, false);
+
+	rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+	feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	REQUIRE(feed->items()[0]->note() == "for the weekly report");
+	REQUIRE(feed->items()[1]->note() == "");
+
+	SECTION("an empty note removes it") {
+		item = feed->items()[0];
+		item->set_note("");
+		REQUIRE_NOTHROW(rsscache->update_rssitem_note(item.get()));
+
+		rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		REQUIRE(feed->items()[0]->note() == "");
+	}
+}
+
 TEST_CASE(
 	"update_rssitem_unread_and_enqueued updates item's \"unread\" and "
 	"\"enqueued\" fields",
@@ -827,6 +941,151 @@ TEST_CASE(
 	}
 }
 
+TEST_CASE(
+	"externalize_rssfeed marks edited articles as updated and keeps their "
+	"\"unread\" field unless mark-updated-unread is set",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	std::unique_ptr<Cache> rsscache(new Cache(dbfile.get_path(), &cfg));
+	auto feedurl = "file://data/rss.xml";
+	RssParser parser(feedurl, rsscache.get(), &cfg, nullptr);
+	std::shared_ptr<RssFeed> feed = parser.parse();
+	feed->items()[0]->set_unread_nowrite(false);
+	rsscache->externalize_rssfeed(feed, false);
+
+	rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+	feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	feed->load();
+	REQUIRE_FALSE(feed->items()[0]->unread());
+	REQUIRE_FALSE(feed->items()[0]->updated());
+
+	SECTION("Edited content") {
+		feed->items()[0]->set_description("changed!");
+
+		SECTION("mark-updated-unread = no; item remains read") {
+			rsscache->externalize_rssfeed(feed, false);
+			rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+			feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+			REQUIRE_FALSE(feed->items()[0]->unread());
+			REQUIRE(feed->items()[0]->updated());
+		}
+
+		SECTION("mark-updated-unread = yes; item becomes unread") {
+			cfg.set_configvalue("mark-updated-unread", "yes");
+			rsscache->externalize_rssfeed(feed, false);
+			rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+			feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+			REQUIRE(feed->items()[0]->unread());
+			REQUIRE(feed->items()[0]->updated());
+		}
+	}
+
+	SECTION("Edited title") {
+		feed->items()[0]->set_title("Corrected title");
+		rsscache->externalize_rssfeed(feed, false);
+		rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		REQUIRE_FALSE(feed->items()[0]->unread());
+		REQUIRE(feed->items()[0]->updated());
+	}
+
+	SECTION("Whitespace-only changes are not edits") {
+		cfg.set_configvalue("mark-updated-unread", "yes");
+		const auto description = feed->items()[0]->description();
+		feed->items()[0]->set_description("\n  " + description + "  \n");
+		rsscache->externalize_rssfeed(feed, false);
+		rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		REQUIRE_FALSE(feed->items()[0]->unread());
+		REQUIRE_FALSE(feed->items()[0]->updated());
+	}
+}
+
+TEST_CASE("Cache stores the word count of articles, and computes reading "
+	"time from it",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	cfg.set_configvalue("reading-speed", "2");
+	std::unique_ptr<Cache> rsscache(new Cache(dbfile.get_path(), &cfg));
+	auto feedurl = "file://data/rss.xml";
+	RssParser parser(feedurl, rsscache.get(), &cfg, nullptr);
+	std::shared_ptr<RssFeed> feed = parser.parse();
+	feed->items()[0]->set_description(
+		"<p>Exactly five <a href=\"https://example.com\">words</a> here "
+		"https://example.org counted.</p><pre>code isn't</pre>");
+	rsscache->externalize_rssfeed(feed, false);
+	REQUIRE(feed->items()[0]->word_count() == 5);
+	REQUIRE(feed->items()[0]->reading_minutes() == 3);
+
+	SECTION("The count survives a restart") {
+		rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		REQUIRE(feed->items()[0]->word_count() == 5);
+		REQUIRE(feed->items()[0]->reading_minutes() == 3);
+	}
+
+	SECTION("Reading time follows the reading-speed setting") {
+		cfg.set_configvalue("reading-speed", "5");
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		REQUIRE(feed->items()[0]->word_count() == 5);
+		REQUIRE(feed->items()[0]->reading_minutes() == 1);
+	}
+
+	SECTION("Changed content is counted again") {
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		feed->load();
+		feed->items()[0]->set_description("Just three words");
+		rsscache->externalize_rssfeed(feed, false);
+
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		REQUIRE(feed->items()[0]->word_count() == 3);
+		REQUIRE(feed->items()[0]->reading_minutes() == 2);
+	}
+
+	SECTION("Filters can use the count") {
+		feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+		const auto item = feed->items()[0];
+
+		Matcher m;
+		REQUIRE(m.parse("words = 5 and reading_minutes > 2"));
+		REQUIRE(m.matches(item.get()));
+		REQUIRE(m.parse("words > 5"));
+		REQUIRE_FALSE(m.matches(item.get()));
+	}
+}
+
+TEST_CASE("Marking an updated article read clears its \"updated\" mark",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	std::unique_ptr<Cache> rsscache(new Cache(dbfile.get_path(), &cfg));
+	auto feedurl = "file://data/rss.xml";
+	RssParser parser(feedurl, rsscache.get(), &cfg, nullptr);
+	std::shared_ptr<RssFeed> feed = parser.parse();
+	feed->items()[0]->set_unread_nowrite(false);
+	rsscache->externalize_rssfeed(feed, false);
+
+	feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	feed->load();
+	feed->items()[0]->set_description("changed!");
+	rsscache->externalize_rssfeed(feed, false);
+
+	feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	REQUIRE(feed->items()[0]->updated());
+	feed->items()[0]->set_unread(false);
+	REQUIRE_FALSE(feed->items()[0]->updated());
+
+	rsscache.reset(new Cache(dbfile.get_path(), &cfg));
+	feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	REQUIRE_FALSE(feed->items()[0]->unread());
+	REQUIRE_FALSE(feed->items()[0]->updated());
+}
+
 TEST_CASE(
 	"externalize_rssfeed only updates \"unread\" field if override_unread "
 	"is set",
@@ -999,3 +1258,465 @@ TEST_CASE("search_in_items returns empty set if input set is empty", "[Cache]")
 	const guids result = rsscache.search_in_items("Botox", empty);
 	REQUIRE(result.empty());
 }
+
+TEST_CASE("merge_from copies missing articles and merges read states",
+	"[Cache]")
+{
+	const std::string guid =
+		"http://www.blogger.com/feeds/33750310/posts/full/"
+		"115822000722667899";
+
+	TestHelpers::TempFile otherfile;
+	ConfigContainer cfg;
+	{
+		Cache other(otherfile.get_path(), &cfg);
+		RssParser parser("file://data/rss.xml", &other, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		other.externalize_rssfeed(feed, false);
+		other.mark_items_read_by_guid({guid});
+	}
+
+	Cache rsscache(":memory:", &cfg);
+
+	SECTION("articles missing from the cache are added") {
+		const auto summary = rsscache.merge_from(otherfile.get_path(), true);
+		REQUIRE(summary.added == 8);
+		REQUIRE(summary.merged == 0);
+		REQUIRE(summary.conflicts == 0);
+		REQUIRE(rsscache.get_read_item_guids() ==
+			std::vector<std::string> {guid});
+
+		SECTION("merging again changes nothing") {
+			const auto again = rsscache.merge_from(otherfile.get_path(), true);
+			REQUIRE(again.added == 0);
+			REQUIRE(again.merged == 0);
+			REQUIRE(again.conflicts == 0);
+		}
+	}
+
+	SECTION("read states are merged according to the preference") {
+		RssParser parser("file://data/rss.xml", &rsscache, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		rsscache.externalize_rssfeed(feed, false);
+
+		SECTION("prefer read") {
+			const auto summary = rsscache.merge_from(otherfile.get_path(), true);
+			REQUIRE(summary.added == 0);
+			REQUIRE(summary.merged == 1);
+			REQUIRE(summary.conflicts == 1);
+			REQUIRE(rsscache.get_read_item_guids() ==
+				std::vector<std::string> {guid});
+		}
+
+		SECTION("prefer unread") {
+			const auto summary = rsscache.merge_from(otherfile.get_path(), false);
+			REQUIRE(summary.added == 0);
+			REQUIRE(summary.merged == 0);
+			REQUIRE(summary.conflicts == 1);
+			REQUIRE(rsscache.get_read_item_guids().empty());
+		}
+	}
+}
+
+TEST_CASE("merge_from carries notes over from the other cache", "[Cache]")
+{
+	const std::string feedurl = "file://data/rss.xml";
+
+	TestHelpers::TempFile otherfile;
+	ConfigContainer cfg;
+	{
+		Cache other(otherfile.get_path(), &cfg);
+		RssParser parser(feedurl, &other, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		other.externalize_rssfeed(feed, false);
+		feed->items()[0]->set_note("theirs");
+		other.update_rssitem_note(feed->items()[0].get());
+	}
+
+	Cache rsscache(":memory:", &cfg);
+
+	SECTION("notes of added articles are copied") {
+		rsscache.merge_from(otherfile.get_path(), true);
+
+		const auto feed = rsscache.internalize_rssfeed(feedurl, nullptr);
+		REQUIRE(feed->items()[0]->note() == "theirs");
+	}
+
+	SECTION("notes of existing articles are combined") {
+		RssParser parser(feedurl, &rsscache, &cfg, nullptr);
+		std::shared_ptr<RssFeed> feed = parser.parse();
+		rsscache.externalize_rssfeed(feed, false);
+		feed->items()[0]->set_note("ours");
+		rsscache.update_rssitem_note(feed->items()[0].get());
+
+		const auto summary = rsscache.merge_from(otherfile.get_path(), true);
+		REQUIRE(summary.merged == 1);
+
+		feed = rsscache.internalize_rssfeed(feedurl, nullptr);
+		REQUIRE(feed->items()[0]->note() == "ours; theirs");
+	}
+}
+
+TEST_CASE("merge_from throws if the other cache can't be opened", "[Cache]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	REQUIRE_THROWS_AS(rsscache.merge_from("non-existent.db", true), DbException);
+}
+
+TEST_CASE("recover() salvages the readable rows of a damaged cache file",
+	"[Cache]")
+{
+	// data/corrupted-cache.db has two feeds and 200 articles; one page in the
+	// middle of rss_item was overwritten with garbage.
+	TestHelpers::TempDir tmp;
+	const std::string cachefile = tmp.get_path() + "cache.db";
+	TestHelpers::copy_file("data/corrupted-cache.db", cachefile);
+
+	ConfigContainer cfg;
+	REQUIRE_FALSE(Cache::check_integrity(cachefile).empty());
+
+	std::vector<std::string> progress;
+	const auto summary = Cache::recover(cachefile, &cfg, false,
+	[&progress](const CacheRecoveryTable& table) {
+		progress.push_back(table.name);
+	});
+	REQUIRE(summary.has_value());
+	REQUIRE(progress == std::vector<std::string>({"rss_feed", "rss_item", "google_replay", "rss_item_note"}));
+
+	REQUIRE(summary->tables.size() == 4);
+	REQUIRE(summary->tables[0].recovered == 2);
+	REQUIRE(summary->tables[0].lost == 0u);
+	REQUIRE(summary->tables[1].recovered == 189);
+	REQUIRE(summary->tables[1].lost == 11u);
+	REQUIRE(summary->tables[2].recovered == 0);
+	REQUIRE(summary->tables[2].lost == 0u);
+	REQUIRE(summary->tables[3].recovered == 0);
+	REQUIRE(summary->tables[3].lost == 0u);
+
+	SECTION("the damaged file is kept as a backup") {
+		REQUIRE(summary->backup_file == cachefile + ".corrupt");
+		REQUIRE(0 == ::access(summary->backup_file.c_str(), R_OK));
+		REQUIRE_FALSE(Cache::check_integrity(summary->backup_file).empty());
+		REQUIRE(0 != ::access((cachefile + ".recovered").c_str(), F_OK));
+	}
+
+	SECTION("the recovered file is a healthy cache") {
+		REQUIRE(Cache::check_integrity(cachefile).empty());
+
+		Cache rsscache(cachefile, &cfg);
+		const auto feed = rsscache.internalize_rssfeed(
+				"https://example.com/feed.xml", nullptr);
+		REQUIRE(feed->total_item_count() == 189);
+		REQUIRE(feed->title() == "Example");
+	}
+}
+
+TEST_CASE("recover() refuses to touch a healthy cache file unless forced",
+	"[Cache]")
+{
+	TestHelpers::TempDir tmp;
+	const std::string cachefile = tmp.get_path() + "cache.db";
+
+	ConfigContainer cfg;
+	{
+		Cache rsscache(cachefile, &cfg);
+		RssParser parser("file://data/rss.xml", &rsscache, &cfg, nullptr);
+		rsscache.externalize_rssfeed(parser.parse(), false);
+	}
+	REQUIRE(Cache::check_integrity(cachefile).empty());
+
+	unsigned int calls = 0;
+	auto count_calls = [&calls](const CacheRecoveryTable&) {
+		calls++;
+	};
+
+	SECTION("without --force, nothing happens") {
+		REQUIRE_FALSE(Cache::recover(cachefile, &cfg, false,
+				count_calls).has_value());
+		REQUIRE(calls == 0);
+		REQUIRE(0 != ::access((cachefile + ".corrupt").c_str(), F_OK));
+	}
+
+	SECTION("with --force, every row is copied") {
+		const auto summary = Cache::recover(cachefile, &cfg, true, count_calls);
+		REQUIRE(summary.has_value());
+		REQUIRE(calls == 3);
+		REQUIRE(summary->tables[1].recovered == 8);
+		REQUIRE(summary->tables[1].lost == 0u);
+		REQUIRE(0 == ::access(summary->backup_file.c_str(), R_OK));
+
+		Cache rsscache(cachefile, &cfg);
+		REQUIRE(rsscache.internalize_rssfeed("file://data/rss.xml",
+				nullptr)->total_item_count() == 8);
+	}
+}
+
+TEST_CASE("check_integrity() throws if the cache file can't be opened",
+	"[Cache]")
+{
+	REQUIRE_THROWS_AS(Cache::check_integrity("non-existent.db"), DbException);
+}
+
+namespace {
+
+struct SqliteCloser {
+	void operator()(sqlite3* db)
+	{
+		sqlite3_close(db);
+	}
+};
+
+using SqliteHandle = std::unique_ptr<sqlite3, SqliteCloser>;
+
+SqliteHandle open_sqlite(const std::string& path)
+{
+	sqlite3* db = nullptr;
+	const int error = sqlite3_open(path.c_str(), &db);
+	SqliteHandle handle(db);
+	REQUIRE(error == SQLITE_OK);
+	return handle;
+}
+
+void exec_sql(sqlite3* db, const std::string& query)
+{
+	INFO("Query: " << query);
+	REQUIRE(sqlite3_exec(db, query.c_str(), nullptr, nullptr, nullptr)
+		== SQLITE_OK);
+}
+
+sqlite3_int64 query_integer(sqlite3* db, const std::string& query)
+{
+	INFO("Query: " << query);
+	sqlite3_stmt* stmt = nullptr;
+	REQUIRE(sqlite3_prepare_v2(db, query.c_str(), -1, &stmt, nullptr)
+		== SQLITE_OK);
+	const int rc = sqlite3_step(stmt);
+	const sqlite3_int64 result = sqlite3_column_int64(stmt, 0);
+	sqlite3_finalize(stmt);
+	REQUIRE(rc == SQLITE_ROW);
+	return result;
+}
+
+/// Turns the cache at `path` into one of schema 2.25 with `articles`
+/// articles whose flags are NULL, like a cache that predates version 2.26.
+void make_cache_of_version_2_25(const std::string& path, unsigned int articles)
+{
+	{
+		ConfigContainer cfg;
+		Cache rsscache(path, &cfg);
+	}
+
+	const auto db = open_sqlite(path);
+	exec_sql(db.get(), "DROP TABLE metadata;");
+	exec_sql(db.get(),
+		"CREATE TABLE metadata ( "
+		" db_schema_version_major INTEGER NOT NULL, "
+		" db_schema_version_minor INTEGER NOT NULL );");
+	exec_sql(db.get(), "INSERT INTO metadata VALUES (2, 25);");
+	exec_sql(db.get(), strprintf::fmt(
+			"WITH RECURSIVE n(i) AS "
+			"(SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < %u) "
+			"INSERT INTO rss_item (guid, title, author, url, feedurl, "
+			"pubDate, content, unread, flags) "
+			"SELECT 'guid' || i, 'title', 'author', 'url', 'feedurl', "
+			"0, 'content', 1, NULL FROM n;",
+			articles));
+}
+
+}
+
+TEST_CASE("Cache can't be opened by versions older than the one that "
+	"migrated it",
+	"[Cache]")
+{
+	using TestHelpers::ExceptionWithMsg;
+
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	make_cache_of_version_2_25(dbfile.get_path(), 10);
+	{
+		Cache rsscache(dbfile.get_path(), &cfg);
+	}
+
+	const auto db = open_sqlite(dbfile.get_path());
+	const auto major = query_integer(db.get(),
+			"SELECT min_reader_major FROM metadata;");
+	const auto minor = query_integer(db.get(),
+			"SELECT min_reader_minor FROM metadata;");
+	REQUIRE(major == utils::newsboat_version_major());
+
+	SECTION("the version that migrated it can open it") {
+		REQUIRE_NOTHROW(Cache(dbfile.get_path(), &cfg));
+	}
+
+	SECTION("older versions are refused with a message that explains why") {
+		exec_sql(db.get(), strprintf::fmt(
+				"UPDATE metadata SET min_reader_major = %" PRId64 ", "
+				"min_reader_minor = 0;",
+				static_cast<int64_t>(major + 1)));
+
+		const std::string expected = strprintf::fmt(
+				"The cache was migrated by newsboat %" PRId64 ".0, and can't "
+				"be opened by older versions. This is newsboat %" PRId64
+				".%" PRId64 "; please upgrade it, or point `cache-file' to "
+				"another file.",
+				static_cast<int64_t>(major + 1),
+				static_cast<int64_t>(major),
+				static_cast<int64_t>(minor));
+		REQUIRE_THROWS_MATCHES(Cache(dbfile.get_path(), &cfg),
+			std::runtime_error,
+			ExceptionWithMsg<std::runtime_error>(expected));
+	}
+}
+
+TEST_CASE("Large caches are migrated in chunks, reporting progress along the "
+	"way",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	make_cache_of_version_2_25(dbfile.get_path(), 12000);
+	{
+		const auto db = open_sqlite(dbfile.get_path());
+		exec_sql(db.get(), "UPDATE rss_item SET flags = 'ab' WHERE id = 42;");
+	}
+
+	std::vector<std::string> progress;
+	{
+		Cache rsscache(dbfile.get_path(), &cfg,
+		[&progress](const std::string& message) {
+			progress.push_back(message);
+		});
+	}
+
+	REQUIRE(progress.size() > 2);
+	REQUIRE(progress.front() == "migrating cache: 0%");
+	REQUIRE(progress.back() == "migrating cache: 100%");
+
+	const auto db = open_sqlite(dbfile.get_path());
+	REQUIRE(query_integer(db.get(),
+			"SELECT count(*) FROM rss_item WHERE flags IS NULL;") == 0);
+	REQUIRE(query_integer(db.get(),
+			"SELECT count(*) FROM rss_item WHERE flags = '';") == 11999);
+	REQUIRE(query_integer(db.get(),
+			"SELECT count(*) FROM rss_item WHERE flags = 'ab';") == 1);
+	REQUIRE(query_integer(db.get(),
+			"SELECT db_schema_version_minor FROM metadata;") == 26);
+
+	INFO("The checkpoints are gone once the migration is done");
+	REQUIRE(query_integer(db.get(),
+			"SELECT count(*) FROM sqlite_master "
+			"WHERE name = 'schema_migration_progress';") == 0);
+}
+
+TEST_CASE("An interrupted migration resumes after its last checkpoint",
+	"[Cache]")
+{
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	make_cache_of_version_2_25(dbfile.get_path(), 12000);
+	{
+		// This is what an interrupted migration leaves behind
+		const auto db = open_sqlite(dbfile.get_path());
+		exec_sql(db.get(),
+			"CREATE TABLE schema_migration_progress ( "
+			" major INTEGER NOT NULL, "
+			" minor INTEGER NOT NULL, "
+			" migration INTEGER NOT NULL, "
+			" done_up_to INTEGER NOT NULL, "
+			" PRIMARY KEY (major, minor, migration) );");
+		exec_sql(db.get(),
+			"INSERT INTO schema_migration_progress VALUES (2, 26, 0, 6000);");
+	}
+
+	std::vector<std::string> progress;
+	{
+		Cache rsscache(dbfile.get_path(), &cfg,
+		[&progress](const std::string& message) {
+			progress.push_back(message);
+		});
+	}
+
+	REQUIRE(progress.front() == "migrating cache: 50%");
+	REQUIRE(progress.back() == "migrating cache: 100%");
+
+	// The rows that the checkpoint covers weren't migrated for real, so
+	// they show that the migration didn't start over
+	const auto db = open_sqlite(dbfile.get_path());
+	REQUIRE(query_integer(db.get(),
+			"SELECT count(*) FROM rss_item WHERE flags IS NULL;") == 6000);
+	REQUIRE(query_integer(db.get(),
+			"SELECT min(id) FROM rss_item WHERE flags = '';") == 6001);
+	REQUIRE(query_integer(db.get(),
+			"SELECT db_schema_version_minor FROM metadata;") == 26);
+}
+
+TEST_CASE("open_read_only() reads a cache that another connection is writing "
+	"to",
+	"[Cache]")
+{
+	const std::string feedurl = "file://data/rss.xml";
+
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	{
+		Cache rsscache(dbfile.get_path(), &cfg);
+		RssParser parser(feedurl, &rsscache, &cfg, nullptr);
+		rsscache.externalize_rssfeed(parser.parse(), false);
+	}
+
+	const auto writer = open_sqlite(dbfile.get_path());
+	exec_sql(writer.get(), "BEGIN IMMEDIATE TRANSACTION;");
+
+	const auto rsscache = Cache::open_read_only(dbfile.get_path(), &cfg);
+	REQUIRE(rsscache->get_feed_urls() == std::vector<std::string> {feedurl});
+	const auto feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+	REQUIRE(feed->total_item_count() == 8);
+
+	exec_sql(writer.get(), "COMMIT;");
+}
+
+TEST_CASE("open_read_only() doesn't change the cache file", "[Cache]")
+{
+	const std::string feedurl = "file://data/rss.xml";
+
+	TestHelpers::TempFile dbfile;
+	ConfigContainer cfg;
+	{
+		Cache rsscache(dbfile.get_path(), &cfg);
+		RssParser parser(feedurl, &rsscache, &cfg, nullptr);
+		rsscache.externalize_rssfeed(parser.parse(), false);
+	}
+
+	SECTION("articles beyond max-items are left for the next writer") {
+		cfg.set_configvalue("max-items", "3");
+		{
+			const auto rsscache = Cache::open_read_only(dbfile.get_path(), &cfg);
+			const auto feed = rsscache->internalize_rssfeed(feedurl, nullptr);
+			REQUIRE(feed->total_item_count() == 3);
+		}
+
+		const auto db = open_sqlite(dbfile.get_path());
+		REQUIRE(query_integer(db.get(), "SELECT count(*) FROM rss_item;") == 8);
+	}
+
+	SECTION("caches in an old format are refused instead of migrated") {
+		make_cache_of_version_2_25(dbfile.get_path(), 10);
+
+		REQUIRE_THROWS_AS(Cache::open_read_only(dbfile.get_path(), &cfg),
+			std::runtime_error);
+
+		const auto db = open_sqlite(dbfile.get_path());
+		REQUIRE(query_integer(db.get(),
+				"SELECT db_schema_version_minor FROM metadata;") == 25);
+	}
+
+	SECTION("a missing file isn't created") {
+		TestHelpers::TempDir tmp;
+		const std::string missing = tmp.get_path() + "missing.db";
+
+		REQUIRE_THROWS_AS(Cache::open_read_only(missing, &cfg), DbException);
+		REQUIRE(0 != ::access(missing.c_str(), F_OK));
+	}
+}
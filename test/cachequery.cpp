@@ -0,0 +1,70 @@
+#include "cachequery.h"
+
+#include "3rd-party/catch.hpp"
+#include "cache.h"
+#include "configcontainer.h"
+#include "rssitem.h"
+
+using namespace newsboat;
+
+namespace {
+
+std::shared_ptr<RssItem> article(Cache* rsscache,
+	const std::string& title,
+	const std::string& link)
+{
+	const auto item = std::make_shared<RssItem>(rsscache);
+	item->set_title(title);
+	item->set_link(link);
+	item->set_unread(true);
+	return item;
+}
+
+} // anonymous namespace
+
+TEST_CASE("QueryResult prints the chosen fields as tab-separated values",
+	"[QueryResult]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+
+	QueryResult result({"title", "unread", "link"}, false);
+	result.add_article(*article(&rsscache, "First", "https://example.com/1"));
+	result.add_article(*article(&rsscache, "Tab\there", "https://example.com/2"));
+
+	REQUIRE(result.render() ==
+		"title\tunread\tlink\n"
+		"First\tyes\thttps://example.com/1\n"
+		"Tab\\there\tyes\thttps://example.com/2\n");
+}
+
+TEST_CASE("QueryResult prints the chosen fields as JSON", "[QueryResult]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+
+	SECTION("one object per article") {
+		QueryResult result({"title", "link"}, true);
+		result.add_article(*article(&rsscache, "A \"quote\"", "https://example.com"));
+
+		REQUIRE(result.render() ==
+			"[\n"
+			"  {\"title\":\"A \\\"quote\\\"\",\"link\":\"https://example.com\"}\n"
+			"]\n");
+	}
+
+	SECTION("attributes that the article doesn't have are null") {
+		QueryResult result({"title", "feedtitle"}, true);
+		result.add_article(*article(&rsscache, "Orphan", "https://example.com"));
+
+		REQUIRE(result.render() ==
+			"[\n"
+			"  {\"title\":\"Orphan\",\"feedtitle\":null}\n"
+			"]\n");
+	}
+
+	SECTION("no articles") {
+		QueryResult result({"title"}, true);
+		REQUIRE(result.render() == "[]\n");
+	}
+}
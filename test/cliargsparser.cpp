@@ -3,6 +3,7 @@
 #include <cstring>
 
 #include "cliargsparser.h"
+#include "clierror.h"
 #include "test-helpers/envvar.h"
 #include "test-helpers/opts.h"
 #include "test-helpers/stringmaker/optional.h"
@@ -19,7 +20,8 @@ TEST_CASE(
 		CliArgsParser args(opts.argc(), opts.argv());
 
 		REQUIRE(args.should_print_usage());
-		REQUIRE(args.return_code() == EXIT_FAILURE);
+		REQUIRE(args.return_code() ==
+			clierror::exit_code(ErrorCategory::USAGE));
 	};
 
 	SECTION("Example No.1") {
@@ -101,7 +103,8 @@ TEST_CASE(
 		CliArgsParser args(opts.argc(), opts.argv());
 
 		REQUIRE(args.should_print_usage());
-		REQUIRE(args.return_code() == EXIT_FAILURE);
+		REQUIRE(args.return_code() ==
+			clierror::exit_code(ErrorCategory::USAGE));
 	};
 
 	SECTION("-i first") {
@@ -570,7 +573,8 @@ TEST_CASE(
 		CliArgsParser args(opts.argc(), opts.argv());
 
 		REQUIRE(args.should_print_usage());
-		REQUIRE(args.return_code() == EXIT_FAILURE);
+		REQUIRE(args.return_code() ==
+			clierror::exit_code(ErrorCategory::USAGE));
 	};
 
 	SECTION("-I first") {
@@ -671,7 +675,8 @@ TEST_CASE(
 		CliArgsParser args(opts.argc(), opts.argv());
 
 		REQUIRE_FALSE(args.display_msg() == "");
-		REQUIRE(args.return_code() == EXIT_FAILURE);
+		REQUIRE(args.return_code() ==
+			clierror::exit_code(ErrorCategory::USAGE));
 	};
 
 	SECTION("-l0") {
@@ -703,3 +708,18 @@ TEST_CASE("Sets `program_name` to the first string of the options list",
 	check({"/usr/local/bin/app-with-a-path"},
 		"/usr/local/bin/app-with-a-path");
 }
+
+TEST_CASE("Reports usage errors as JSON if --error-format=json is provided",
+	"[CliArgsParser]")
+{
+	TestHelpers::Opts opts = {
+		"newsboat", "--error-format=json", "-I", "read.txt", "-E", "read.txt"
+	};
+	CliArgsParser args(opts.argc(), opts.argv());
+
+	REQUIRE(args.json_errors());
+	REQUIRE_FALSE(args.should_print_usage());
+	REQUIRE(args.return_code() == clierror::exit_code(ErrorCategory::USAGE));
+	REQUIRE(args.display_msg() ==
+		R"({"code":2,"category":"usage","message":"newsboat: -I and -E can't be used together"})");
+}
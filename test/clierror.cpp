@@ -0,0 +1,35 @@
+#include "clierror.h"
+
+#include "3rd-party/catch.hpp"
+
+using namespace newsboat;
+
+TEST_CASE("exit_code() returns a distinct code for each category", "[clierror]")
+{
+	REQUIRE(clierror::exit_code(ErrorCategory::OTHER) == 1);
+	REQUIRE(clierror::exit_code(ErrorCategory::USAGE) == 2);
+	REQUIRE(clierror::exit_code(ErrorCategory::CONFIG) == 3);
+	REQUIRE(clierror::exit_code(ErrorCategory::URLS_FILE) == 4);
+	REQUIRE(clierror::exit_code(ErrorCategory::CACHE) == 5);
+	REQUIRE(clierror::exit_code(ErrorCategory::NETWORK) == 6);
+}
+
+TEST_CASE("format() returns either the message or a JSON object",
+	"[clierror]")
+{
+	SECTION("Text") {
+		REQUIRE(clierror::format(ErrorCategory::CONFIG, "oops", false,
+				"/tmp/config", 3) == "oops");
+	}
+
+	SECTION("JSON without location") {
+		REQUIRE(clierror::format(ErrorCategory::CACHE, "locked", true) ==
+			R"({"code":5,"category":"cache","message":"locked"})");
+	}
+
+	SECTION("JSON with location") {
+		REQUIRE(clierror::format(ErrorCategory::CONFIG, "oops", true,
+				"/tmp/config", 3) ==
+			R"({"code":3,"category":"config","message":"oops","file":"/tmp/config","line":3})");
+	}
+}
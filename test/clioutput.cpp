@@ -0,0 +1,18 @@
+#include "clioutput.h"
+
+#include "3rd-party/catch.hpp"
+
+using namespace newsboat;
+
+TEST_CASE("CliReport prints what scripts expect if asked for plain output",
+	"[CliReport]")
+{
+	CliReport report(true);
+	report.add_feed("Feed", 3);
+	report.add_feed("Other feed", 4);
+	report.add_failure("https://example.com/feed", "Timeout was reached");
+
+	REQUIRE(report.unread_summary(7) ==
+		std::vector<std::string>({"7 unread articles"}));
+	REQUIRE(report.failure_report().empty());
+}
@@ -214,3 +214,83 @@ TEST_CASE("dump_config() returns everything we put into ColorManager",
 	REQUIRE(config.size() == 3);
 	REQUIRE(equivalent());
 }
+
+TEST_CASE("handle_action() points out the offending argument",
+	"[ColorManager]")
+{
+	ColorManager c;
+
+	const std::vector<std::pair<std::vector<std::string>, std::string>>
+	cases{
+		{
+			{"lisfocus", "red", "blue"},
+			"argument 1: `lisfocus' is not a valid configuration element. "
+			"Did you mean: listfocus?"
+		},
+		{
+			{"info", "redd", "blue"},
+			"argument 2: `redd' is not a valid color"
+		},
+		{
+			{"info", "red", "color256"},
+			"argument 3: `color256' is out of range (color0 to color255)"
+		},
+		{
+			{"info", "red", "blue", "bold", "unerline"},
+			"argument 5: `unerline' is not a valid attribute. "
+			"Did you mean: underline?"
+		},
+	};
+	for (const auto& testcase : cases) {
+		try {
+			c.handle_action("color", testcase.first);
+			FAIL("No exception thrown");
+		} catch (const ConfigHandlerException& e) {
+			REQUIRE(std::string(e.what()) == testcase.second);
+		}
+	}
+	REQUIRE(c.get_styles().empty());
+}
+
+TEST_CASE("handle_action() accepts hex colors, and apply_colors() maps them "
+	"to the palette",
+	"[ColorManager]")
+{
+	ColorManager c;
+
+	c.handle_action("color", {"listfocus", "#FF0000", "default", "bold"});
+	REQUIRE(c.get_styles()["listfocus"].fg_color == "#ff0000");
+	REQUIRE(c.get_styles()["listfocus"].bg_color == "default");
+	REQUIRE(c.get_styles()["listfocus"].attributes ==
+		std::vector<std::string>({"bold"}));
+
+	Stfl::Form form("vbox");
+	c.apply_colors(form);
+	REQUIRE(form.get("listfocus") == "fg=color196,attr=bold");
+
+	CHECK_THROWS_AS(c.handle_action("color", {"info", "#ff00", "default"}),
+		ConfigHandlerException);
+}
+
+TEST_CASE("dump_config() output can be parsed back into the same styles",
+	"[ColorManager]")
+{
+	ConfigParser cfg;
+	ColorManager c;
+	c.register_commands(cfg);
+
+	cfg.parse_line("color article color208 \"#00Ff80\" underline reverse",
+		"test");
+	std::vector<std::string> config;
+	c.dump_config(config);
+	REQUIRE(config == std::vector<std::string>({
+		"color article color208 \"#00ff80\" underline reverse"}));
+
+	ConfigParser other_cfg;
+	ColorManager other;
+	other.register_commands(other_cfg);
+	other_cfg.parse_line(config[0], "test");
+	std::vector<std::string> other_config;
+	other.dump_config(other_config);
+	REQUIRE(other_config == config);
+}
@@ -295,6 +295,8 @@ TEST_CASE(
 		"dirbrowser-title-format",
 		"download-full-page",
 		"download-filename-format",
+		"download-low-speed-limit",
+		"download-low-speed-time",
 		"download-path",
 		"download-retries",
 		"download-timeout",
@@ -344,6 +346,65 @@ TEST_CASE("get_suggestions() returns results in alphabetical order",
 	}
 }
 
+TEST_CASE("describe_option() tells the type and the values of the option, "
+	"and the config command that set it",
+	"[ConfigContainer]")
+{
+	ConfigContainer cfg;
+
+	SECTION("Option that still has its default value") {
+		REQUIRE(cfg.describe_option("reload-time") ==
+			"reload-time (number)\n"
+			"  default: 60\n"
+			"  current: 60\n"
+			"  not set, default value");
+	}
+
+	SECTION("Option set by a config command") {
+		cfg.set_origin("/home/user/.newsboat/config", 12);
+		cfg.handle_action("browser", {"lynx %u"});
+		REQUIRE(cfg.describe_option("browser") ==
+			"browser (text)\n"
+			"  default: \"\"\n"
+			"  current: \"lynx %u\"\n"
+			"  set at /home/user/.newsboat/config:12");
+	}
+
+	SECTION("The last config command that set the option is the one shown") {
+		cfg.set_origin("/home/user/.newsboat/config", 12);
+		cfg.handle_action("reload-time", {"15"});
+		cfg.set_origin("/home/user/.newsboat/conf.d/laptop", 3);
+		cfg.handle_action("reload-time", {"120"});
+		REQUIRE(cfg.describe_option("reload-time").value().find(
+				"  set at /home/user/.newsboat/conf.d/laptop:3") != std::string::npos);
+	}
+
+	SECTION("Option changed outside of config files") {
+		cfg.set_origin("/home/user/.newsboat/config", 12);
+		cfg.handle_action("reload-time", {"15"});
+		REQUIRE_FALSE(cfg.try_set_configvalue("reload-time", "30").has_value());
+		REQUIRE(cfg.describe_option("reload-time") ==
+			"reload-time (number)\n"
+			"  default: 60\n"
+			"  current: 30\n"
+			"  set outside of config files");
+
+		cfg.reset_to_default("reload-time");
+		REQUIRE(cfg.describe_option("reload-time").value().find(
+				"  not set, default value") != std::string::npos);
+	}
+
+	SECTION("Enum options list their values") {
+		const auto description = cfg.describe_option("proxy-type");
+		REQUIRE(description.has_value());
+		REQUIRE(description.value().find("proxy-type (one of http, ") == 0);
+	}
+
+	SECTION("Unknown option") {
+		REQUIRE_FALSE(cfg.describe_option("reload-tme").has_value());
+	}
+}
+
 TEST_CASE(
 	"get_feed_sort_strategy() returns correctly filled FeedSortStrategy "
 	"struct",
@@ -623,6 +684,18 @@ TEST_CASE(
 		REQUIRE(sort_strategy.sd == SortDirection::DESC);
 	}
 
+	SECTION("words") {
+		cfg.set_configvalue("article-sort-order", "words");
+		sort_strategy = cfg.get_article_sort_strategy();
+		REQUIRE(sort_strategy.sm == ArtSortMethod::WORDS);
+		REQUIRE(sort_strategy.sd == SortDirection::ASC);
+
+		cfg.set_configvalue("article-sort-order", "words-desc");
+		sort_strategy = cfg.get_article_sort_strategy();
+		REQUIRE(sort_strategy.sm == ArtSortMethod::WORDS);
+		REQUIRE(sort_strategy.sd == SortDirection::DESC);
+	}
+
 	SECTION("random") {
 		cfg.set_configvalue("article-sort-order", "random");
 		sort_strategy = cfg.get_article_sort_strategy();
@@ -694,3 +767,86 @@ TEST_CASE("get_article_sort_strategy() returns ascending direction "
 		REQUIRE(cfg.get_article_sort_strategy().sd == SortDirection::ASC);
 	}
 }
+
+TEST_CASE("try_set_configvalue() refuses values that the config file "
+	"wouldn't accept, and keeps the previous value",
+	"[ConfigContainer]")
+{
+	ConfigContainer cfg;
+
+	SECTION("format strings") {
+		const auto old_value = cfg.get_configvalue("feedlist-format");
+		const auto error = cfg.try_set_configvalue("feedlist-format", "%t %");
+		REQUIRE(error.has_value());
+		REQUIRE(error.value().find("position 4") != std::string::npos);
+		REQUIRE(cfg.get_configvalue("feedlist-format") == old_value);
+
+		REQUIRE_FALSE(cfg.try_set_configvalue("feedlist-format", "%t %u")
+			.has_value());
+		REQUIRE(cfg.get_configvalue("feedlist-format") == "%t %u");
+	}
+
+	SECTION("colors") {
+		const auto error = cfg.try_set_configvalue("search-highlight-colors",
+				"black yelow bold");
+		REQUIRE(error.has_value());
+		REQUIRE(cfg.get_configvalue("search-highlight-colors") ==
+			"black yellow bold");
+
+		REQUIRE_FALSE(cfg.try_set_configvalue("search-highlight-colors",
+				"white black").has_value());
+		REQUIRE(cfg.get_configvalue("search-highlight-colors") == "white black");
+	}
+
+	SECTION("mark-read-on") {
+		REQUIRE(cfg.try_set_configvalue("mark-read-on", "hover").has_value());
+		REQUIRE(cfg.get_configvalue("mark-read-on") == "open");
+	}
+
+	SECTION("booleans, integers and enums") {
+		REQUIRE(cfg.try_set_configvalue("show-read-feeds", "maybe").has_value());
+		REQUIRE(cfg.get_configvalue("show-read-feeds") == "yes");
+		REQUIRE(cfg.try_set_configvalue("scrolloff", "many").has_value());
+		REQUIRE(cfg.get_configvalue("scrolloff") == "0");
+		REQUIRE(cfg.try_set_configvalue("proxy-type", "carrier-pigeon")
+			.has_value());
+		REQUIRE(cfg.get_configvalue("proxy-type") == "http");
+	}
+
+	SECTION("unknown options") {
+		REQUIRE(cfg.try_set_configvalue("no-such-option", "42").has_value());
+	}
+
+	SECTION("unknown options that look like a known one") {
+		const auto error = cfg.try_set_configvalue("reload-tme", "42");
+		REQUIRE(error.has_value());
+		REQUIRE(error.value() == "unknown configuration option `reload-tme'. "
+			"Did you mean: reload-time?");
+	}
+}
+
+TEST_CASE("check_option_exists() only complains about unknown options",
+	"[ConfigContainer]")
+{
+	ConfigContainer cfg;
+
+	REQUIRE_FALSE(cfg.check_option_exists("reload-time").has_value());
+	REQUIRE(cfg.check_option_exists("reload-tim").value() ==
+		"unknown configuration option `reload-tim'. Did you mean: reload-time?");
+	REQUIRE(cfg.check_option_exists("frobnicate-widgets").value() ==
+		"unknown configuration option `frobnicate-widgets'");
+}
+
+TEST_CASE("Config file refuses malformed format strings and colors",
+	"[ConfigContainer]")
+{
+	ConfigContainer cfg;
+
+	REQUIRE_THROWS_AS(cfg.handle_action("articlelist-format", {"%4i %?T?x"}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(cfg.handle_action("search-highlight-colors",
+	{"black", "yellow", "blod"}),
+		ConfigHandlerException);
+	REQUIRE_NOTHROW(cfg.handle_action("articlelist-format", {"%4i %t"}));
+	REQUIRE(cfg.get_configvalue("articlelist-format") == "%4i %t");
+}
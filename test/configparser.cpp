@@ -5,6 +5,8 @@
 #include <vector>
 
 #include "3rd-party/catch.hpp"
+#include "configcontainer.h"
+#include "configexception.h"
 #include "keymap.h"
 #include "test-helpers/envvar.h"
 #include "test-helpers/tempfile.h"
@@ -286,3 +288,211 @@ TEST_CASE("include directive includes other config files", "[ConfigParser]")
 		REQUIRE(line_count == 1); // only 1 line from date command
 	}
 }
+
+TEST_CASE("parse_file() reports file and line at which an error occurred",
+	"[ConfigParser]")
+{
+	TestHelpers::TempFile config;
+	{
+		std::ofstream out(config.get_path());
+		out << "# a comment\n";
+		out << "frobnicate the widgets\n";
+	}
+
+	ConfigParser cfgparser;
+	try {
+		cfgparser.parse_file(config.get_path());
+		FAIL("parse_file() didn't throw");
+	} catch (const ConfigException& e) {
+		REQUIRE(std::string(e.what()) == "unknown command `frobnicate'");
+		REQUIRE(e.file() == config.get_path());
+		REQUIRE(e.line() == 2);
+	}
+}
+
+TEST_CASE("Unknown commands are errors unless ignore-unknown-directives is set",
+	"[ConfigParser]")
+{
+	ConfigParser cfgparser;
+	const std::string location = "dummy-location";
+
+	SECTION("by default, unknown commands are errors") {
+		REQUIRE_THROWS_AS(cfgparser.parse_line("frobnicate", location),
+			ConfigException);
+		REQUIRE(cfgparser.get_warnings().empty());
+	}
+
+	SECTION("with the option on, unknown commands produce a warning") {
+		cfgparser.parse_line("ignore-unknown-directives yes", location);
+		REQUIRE_NOTHROW(cfgparser.parse_line("frobnicate the widgets",
+				location));
+		REQUIRE(cfgparser.get_warnings() == std::vector<std::string>({
+			"ignored unknown command `frobnicate' (dummy-location)"}));
+	}
+
+	SECTION("the option can be turned off again") {
+		cfgparser.parse_line("ignore-unknown-directives yes", location);
+		cfgparser.parse_line("ignore-unknown-directives no", location);
+		REQUIRE_THROWS_AS(cfgparser.parse_line("frobnicate", location),
+			ConfigException);
+	}
+
+	SECTION("the option only accepts booleans") {
+		REQUIRE_THROWS_AS(
+			cfgparser.parse_line("ignore-unknown-directives maybe", location),
+			ConfigException);
+		REQUIRE_THROWS_AS(
+			cfgparser.parse_line("ignore-unknown-directives", location),
+			ConfigException);
+	}
+}
+
+TEST_CASE("Commands prefixed with `x-` are always ignored", "[ConfigParser]")
+{
+	ConfigParser cfgparser;
+	const std::string location = "dummy-location";
+
+	REQUIRE_NOTHROW(cfgparser.parse_line("x-my-tool-setting 42", location));
+	REQUIRE(cfgparser.get_warnings().empty());
+
+	cfgparser.parse_line("ignore-unknown-directives yes", location);
+	REQUIRE_NOTHROW(cfgparser.parse_line("x-my-tool-setting 42", location));
+	REQUIRE(cfgparser.get_warnings().empty());
+}
+
+TEST_CASE("Warnings about unknown commands are aggregated per command",
+	"[ConfigParser]")
+{
+	TestHelpers::TempFile config;
+	{
+		std::ofstream out(config.get_path());
+		out << "ignore-unknown-directives yes\n";
+		out << "frobnicate the widgets\n";
+		out << "defenestrate\n";
+		out << "frobnicate the gadgets\n";
+		out << "frobnicate everything\n";
+	}
+
+	ConfigParser cfgparser;
+	REQUIRE_NOTHROW(cfgparser.parse_file(config.get_path()));
+
+	const auto warnings = cfgparser.get_warnings();
+	REQUIRE(warnings.size() == 2);
+	REQUIRE(warnings[0] == "ignored unknown command `frobnicate' 3 times "
+		"(first at " + config.get_path() + " line 2)");
+	REQUIRE(warnings[1] == "ignored unknown command `defenestrate' (" +
+		config.get_path() + " line 3)");
+}
+
+TEST_CASE("tolerate-unknown-options makes config files skip unknown options, "
+	"even above it",
+	"[ConfigParser]")
+{
+	TestHelpers::TempFile config;
+	ConfigContainer cfg;
+	ConfigParser cfgparser;
+	cfg.register_commands(cfgparser);
+
+	SECTION("without it, an unknown option is an error") {
+		{
+			std::ofstream out(config.get_path());
+			out << "reload-time 42\n";
+			out << "scrollbar-style fancy\n";
+		}
+
+		REQUIRE_THROWS_AS(cfgparser.parse_file(config.get_path()),
+			ConfigException);
+	}
+
+	SECTION("with it, unknown options are listed in one warning") {
+		{
+			std::ofstream out(config.get_path());
+			out << "scrollbar-style fancy\n";
+			out << "reload-time 42\n";
+			out << "tolerate-unknown-options yes\n";
+			out << "feedlist-layout grid\n";
+			out << "scrollbar-style plain\n";
+		}
+
+		REQUIRE(cfgparser.parse_file(config.get_path()));
+		REQUIRE(cfg.get_configvalue("reload-time") == "42");
+		REQUIRE(cfgparser.get_warnings() == std::vector<std::string>({
+			"ignored unknown options because of `tolerate-unknown-options': "
+			"`scrollbar-style' (" + config.get_path() + " line 1), "
+			"`feedlist-layout' (" + config.get_path() + " line 4)"}));
+	}
+
+	SECTION("known options with bad values are still errors") {
+		{
+			std::ofstream out(config.get_path());
+			out << "tolerate-unknown-options yes\n";
+			out << "reload-time soon\n";
+		}
+
+		REQUIRE_THROWS_AS(cfgparser.parse_file(config.get_path()),
+			ConfigException);
+	}
+}
+
+TEST_CASE("Malformed lines with known commands are errors even if "
+	"ignore-unknown-directives is set",
+	"[ConfigParser]")
+{
+	TestHelpers::TempFile config;
+	{
+		std::ofstream out(config.get_path());
+		out << "ignore-unknown-directives yes\n";
+		out << "frobnicate the widgets\n";
+		out << "bind-key\n";
+	}
+
+	KeyMap keys(KM_NEWSBOAT);
+	ConfigParser cfgparser;
+	cfgparser.register_handler("bind-key", keys);
+
+	try {
+		cfgparser.parse_file(config.get_path());
+		FAIL("parse_file() didn't throw");
+	} catch (const ConfigException& e) {
+		REQUIRE(e.line() == 3);
+	}
+	REQUIRE(cfgparser.get_warnings().size() == 1);
+}
+
+TEST_CASE("parse_file() reads files that aren't UTF-8 as ISO-8859-1, and "
+	"warns about them",
+	"[ConfigParser]")
+{
+	ConfigParser cfgparser;
+	ConfigHandlerHistoryDummy handler;
+	cfgparser.register_handler("greeting", handler);
+
+	SECTION("ISO-8859-1 file") {
+		const std::string path("data/latin1-config");
+		REQUIRE(cfgparser.parse_file(path));
+
+		REQUIRE(handler.history.size() == 1);
+		REQUIRE(handler.history[0].second == std::vector<std::string>({
+			"Gr\u00fc\u00dfe aus K\u00f6ln"}));
+
+		REQUIRE(cfgparser.get_warnings() == std::vector<std::string>({
+			"Warning: " + path + " is not valid UTF-8; the lines that aren't "
+			"were read as ISO-8859-1. Run `newsboat --convert-to-utf8' to "
+			"convert the file."}));
+		REQUIRE(cfgparser.get_non_utf8_files() ==
+			std::vector<std::string>({path}));
+	}
+
+	SECTION("ASCII file") {
+		TestHelpers::TempFile config;
+		{
+			std::ofstream out(config.get_path());
+			out << "greeting \"Hello\"\n";
+		}
+
+		REQUIRE(cfgparser.parse_file(config.get_path()));
+		REQUIRE(handler.history.size() == 1);
+		REQUIRE(cfgparser.get_warnings().empty());
+		REQUIRE(cfgparser.get_non_utf8_files().empty());
+	}
+}
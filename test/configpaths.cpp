@@ -39,6 +39,8 @@ TEST_CASE("ConfigPaths returns paths to Newsboat dotdir if no Newsboat dirs "
 	REQUIRE(paths.queue_file() == newsboat_dir + "/queue");
 	REQUIRE(paths.search_file() == newsboat_dir + "/history.search");
 	REQUIRE(paths.cmdline_file() == newsboat_dir + "/history.cmdline");
+	REQUIRE(paths.feedlist_snapshot_file() == newsboat_dir +
+		"/feedlist.snapshot");
 }
 
 TEST_CASE("ConfigPaths returns paths to Newsboat XDG dirs if they exist and "
@@ -71,6 +73,8 @@ TEST_CASE("ConfigPaths returns paths to Newsboat XDG dirs if they exist and "
 		REQUIRE(paths.queue_file() == data_dir + "/queue");
 		REQUIRE(paths.search_file() == data_dir + "/history.search");
 		REQUIRE(paths.cmdline_file() == data_dir + "/history.cmdline");
+		REQUIRE(paths.feedlist_snapshot_file() == data_dir +
+			"/feedlist.snapshot");
 	};
 
 	SECTION("XDG_CONFIG_HOME is set") {
@@ -0,0 +1,72 @@
+#include "controlsocket.h"
+
+#include "3rd-party/catch.hpp"
+#include "test-helpers/tempfile.h"
+
+using namespace newsboat;
+
+TEST_CASE("send_add_feed() forwards the URL to the instance that listens",
+	"[ControlSocket]")
+{
+	TestHelpers::TempFile socketFile;
+	std::vector<std::string> received;
+
+	ControlSocket socket;
+	std::string error;
+	REQUIRE(socket.start(socketFile.get_path(),
+	[&received](const std::string& command, const std::string& argument) {
+		received.push_back(command + " " + argument);
+		return ControlSocket::Response{true, "Added " + argument + "."};
+	},
+	error));
+
+	ControlSocket::Response response;
+	const auto status = ControlSocket::send_add_feed(socketFile.get_path(),
+			"feed://example.com/rss", response);
+	REQUIRE(status == ControlSocket::SendStatus::RESPONDED);
+	REQUIRE(response.ok);
+	REQUIRE(response.message == "Added http://example.com/rss.");
+
+	socket.stop();
+	REQUIRE(received == std::vector<std::string>({"add-feed http://example.com/rss"}));
+}
+
+TEST_CASE("send_add_feed() reports an error response", "[ControlSocket]")
+{
+	TestHelpers::TempFile socketFile;
+
+	ControlSocket socket;
+	std::string error;
+	REQUIRE(socket.start(socketFile.get_path(),
+	[](const std::string&, const std::string& argument) {
+		return ControlSocket::Response{false, "You are already subscribed to " + argument + "."};
+	},
+	error));
+
+	ControlSocket::Response response;
+	const auto status = ControlSocket::send_add_feed(socketFile.get_path(),
+			"https://example.com/rss", response);
+	REQUIRE(status == ControlSocket::SendStatus::RESPONDED);
+	REQUIRE_FALSE(response.ok);
+	REQUIRE(response.message ==
+		"You are already subscribed to https://example.com/rss.");
+}
+
+TEST_CASE("send_add_feed() says if no instance listens", "[ControlSocket]")
+{
+	TestHelpers::TempFile socketFile;
+
+	ControlSocket::Response response;
+	const auto status = ControlSocket::send_add_feed(socketFile.get_path(),
+			"https://example.com/rss", response);
+	REQUIRE(status == ControlSocket::SendStatus::NO_INSTANCE);
+	REQUIRE_FALSE(response.ok);
+	REQUIRE_FALSE(response.message.empty());
+}
+
+TEST_CASE("path_for_lock_file() puts the socket next to the lock file",
+	"[ControlSocket]")
+{
+	REQUIRE(ControlSocket::path_for_lock_file("/home/user/.newsboat/cache.db.lock")
+		== "/home/user/.newsboat/cache.db.sock");
+}
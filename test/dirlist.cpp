@@ -0,0 +1,101 @@
+#include "dirlist.h"
+
+#include <fstream>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include "3rd-party/catch.hpp"
+
+#include "configcontainer.h"
+#include "test-helpers/tempdir.h"
+
+using namespace newsboat;
+
+namespace {
+
+void create_file(const std::string& path)
+{
+	std::ofstream out(path);
+	out << "x";
+}
+
+} // namespace
+
+TEST_CASE("DirList lists entries according to the filebrowser settings",
+	"[DirList]")
+{
+	TestHelpers::TempDir tmp;
+	const std::string base = tmp.get_path();
+	REQUIRE(::mkdir((base + "zdir").c_str(), 0700) == 0);
+	create_file(base + "file");
+	create_file(base + ".hidden");
+
+	ConfigContainer cfg;
+
+	SECTION("By default, everything is listed by name") {
+		const DirList list(base, cfg, false);
+		REQUIRE(list.size() == 4);
+		REQUIRE(list.name(0) == "..");
+		REQUIRE(list.name(1) == ".hidden");
+		REQUIRE(list.name(2) == "file");
+		REQUIRE(list.name(3) == "zdir");
+		REQUIRE(list.type(2) == '-');
+		REQUIRE(list.type(3) == 'd');
+		REQUIRE_FALSE(list.error().has_value());
+	}
+
+	SECTION("Hidden entries can be left out, and directories put first") {
+		cfg.set_configvalue("filebrowser-show-hidden", "no");
+		cfg.set_configvalue("filebrowser-dirs-first", "yes");
+		const DirList list(base, cfg, false);
+		REQUIRE(list.size() == 3);
+		REQUIRE(list.name(1) == "zdir");
+		REQUIRE(list.name(2) == "file");
+	}
+
+	SECTION("Directory browser only gets directories") {
+		const DirList list(base, cfg, true);
+		REQUIRE(list.size() == 2);
+		REQUIRE(list.name(1) == "zdir");
+	}
+}
+
+TEST_CASE("DirList shows symlinks as what they point to", "[DirList]")
+{
+	TestHelpers::TempDir tmp;
+	const std::string base = tmp.get_path();
+	REQUIRE(::mkdir((base + "dir").c_str(), 0700) == 0);
+	create_file(base + "file");
+	REQUIRE(::symlink("dir", (base + "link-to-dir").c_str()) == 0);
+	REQUIRE(::symlink("file", (base + "link-to-file").c_str()) == 0);
+	REQUIRE(::symlink("loop", (base + "loop").c_str()) == 0);
+
+	ConfigContainer cfg;
+	const DirList list(base, cfg, false);
+	REQUIRE(list.size() == 6);
+	REQUIRE(list.name(3) == "link-to-dir");
+	REQUIRE(list.type(3) == 'd');
+	REQUIRE(list.name(4) == "link-to-file");
+	REQUIRE(list.type(4) == '-');
+	REQUIRE(list.name(5) == "loop");
+	REQUIRE(list.type(5) == '-');
+	REQUIRE(list.row(3).find("link-to-dir@") != std::string::npos);
+}
+
+TEST_CASE("DirList::complete_path() completes the last component",
+	"[DirList]")
+{
+	TestHelpers::TempDir tmp;
+	const std::string base = tmp.get_path();
+	REQUIRE(::mkdir((base + "photos").c_str(), 0700) == 0);
+	create_file(base + "report-2020.txt");
+	create_file(base + "report-2021.txt");
+
+	ConfigContainer cfg;
+	REQUIRE(DirList::complete_path(base + "rep", cfg, false)
+		== base + "report-202");
+	REQUIRE(DirList::complete_path(base + "ph", cfg, false)
+		== base + "photos/");
+	REQUIRE(DirList::complete_path(base, cfg, true) == base + "photos/");
+	REQUIRE(DirList::complete_path(base + "x", cfg, false) == base + "x");
+}
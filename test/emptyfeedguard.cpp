@@ -0,0 +1,64 @@
+#include "emptyfeedguard.h"
+
+#include "3rd-party/catch.hpp"
+
+#include "confighandlerexception.h"
+
+using namespace newsboat;
+
+namespace {
+
+const std::string URL = "https://example.com/feed.xml";
+
+} // namespace
+
+TEST_CASE("accept-empty-feed requires at least one URL", "[EmptyFeedGuard]")
+{
+	EmptyFeedGuard guard;
+	REQUIRE_THROWS_AS(guard.handle_action("accept-empty-feed", {}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(guard.handle_action("reset-unread-on-update", {URL}),
+		ConfigHandlerException);
+	REQUIRE_NOTHROW(guard.handle_action("accept-empty-feed", {URL, "url2"}));
+}
+
+TEST_CASE("check() keeps cached articles until a feed was empty a few times "
+	"in a row",
+	"[EmptyFeedGuard]")
+{
+	EmptyFeedGuard guard;
+	guard.set_thresholds(0, 2);
+
+	const auto warning = guard.check(URL, 0, 134);
+	REQUIRE(warning.has_value());
+	REQUIRE(warning.value() ==
+		"feed returned empty document, keeping 134 cached items");
+
+	REQUIRE_FALSE(guard.check(URL, 0, 134).has_value());
+	REQUIRE_FALSE(guard.check(URL, 5, 134).has_value());
+}
+
+TEST_CASE("check() accepts empty fetches of feeds listed in accept-empty-feed",
+	"[EmptyFeedGuard]")
+{
+	EmptyFeedGuard guard;
+	guard.handle_action("accept-empty-feed", {URL});
+
+	REQUIRE_FALSE(guard.check(URL, 0, 134).has_value());
+	REQUIRE(guard.check("https://example.com/other.xml", 0, 134).has_value());
+}
+
+TEST_CASE("dump_config() writes an accept-empty-feed line per URL",
+	"[EmptyFeedGuard]")
+{
+	EmptyFeedGuard guard;
+	guard.handle_action("accept-empty-feed", {"url2"});
+	guard.handle_action("accept-empty-feed", {"url1"});
+
+	std::vector<std::string> config;
+	guard.dump_config(config);
+	REQUIRE(config == std::vector<std::string>({
+		R"#(accept-empty-feed "url1")#",
+		R"#(accept-empty-feed "url2")#",
+	}));
+}
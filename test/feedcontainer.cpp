@@ -1128,3 +1128,48 @@ TEST_CASE("replace_feed() puts given feed into the specified position",
 	REQUIRE(feed_before_replacement != feed_after_replacement);
 	REQUIRE(feed_after_replacement == first_feed);
 }
+
+TEST_CASE("replace_feed() can put given feed where another one is, if it's "
+	"still there",
+	"[FeedContainer]")
+{
+	FeedContainer feedcontainer;
+
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	const auto feeds = get_five_empty_feeds(&rsscache);
+	feedcontainer.set_feeds(feeds);
+
+	const auto placeholder = feeds[3];
+	const auto loaded = std::make_shared<RssFeed>(&rsscache);
+	REQUIRE(feedcontainer.replace_feed(placeholder, loaded));
+	REQUIRE(feedcontainer.get_feed(3) == loaded);
+
+	// The placeholder is gone, so there's nothing to replace anymore
+	const auto stale = std::make_shared<RssFeed>(&rsscache);
+	REQUIRE_FALSE(feedcontainer.replace_feed(placeholder, stale));
+	REQUIRE(feedcontainer.get_feed(3) == loaded);
+}
+
+TEST_CASE("unread_item_count() counts placeholder feeds by what they claim",
+	"[FeedContainer]")
+{
+	FeedContainer feedcontainer;
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	const auto feeds = get_five_empty_feeds(&rsscache);
+
+	for (int i = 0; i < 2; ++i) {
+		const auto item = std::make_shared<RssItem>(&rsscache);
+		item->set_guid(std::to_string(i));
+		item->set_unread_nowrite(true);
+		feeds[0]->add_item(item);
+	}
+	feeds[1]->set_placeholder_counts(3, 7);
+	feeds[2]->set_placeholder_counts(4, 4);
+	feeds[2]->set_tags({"!hidden"});
+	feedcontainer.set_feeds(feeds);
+
+	REQUIRE(feedcontainer.unread_item_count() == 5);
+	REQUIRE(feedcontainer.unread_feed_count() == 3);
+}
@@ -0,0 +1,93 @@
+#include "feedicons.h"
+
+#include "3rd-party/catch.hpp"
+
+#include "cache.h"
+#include "configcontainer.h"
+#include "confighandlerexception.h"
+#include "fmtstrformatter.h"
+#include "rssfeed.h"
+#include "utils.h"
+
+using namespace newsboat;
+
+namespace {
+
+std::shared_ptr<RssFeed> make_feed(Cache* rsscache,
+	unsigned int items,
+	unsigned int items_with_enclosures)
+{
+	auto feed = std::make_shared<RssFeed>(rsscache);
+	for (unsigned int i = 0; i < items; ++i) {
+		auto item = std::make_shared<RssItem>(rsscache);
+		item->set_guid(std::to_string(i));
+		if (i < items_with_enclosures) {
+			item->set_enclosure_url(
+				"https://example.com/" + std::to_string(i) + ".mp3");
+		}
+		feed->add_item(item);
+	}
+	return feed;
+}
+
+} // namespace
+
+TEST_CASE("feedlist-icon takes a known category and a narrow glyph",
+	"[FeedIcons]")
+{
+	FeedIcons icons;
+	REQUIRE_THROWS_AS(icons.handle_action("feedlist-icon", {"podcast"}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(icons.handle_action("feedlist-icon",
+			{"podcast", "P", "extra"}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(icons.handle_action("feedlist-icon", {"video", "V"}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(icons.handle_action("feedlist-icon", {"podcast", "♪♪♪"}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(icons.handle_action("feedlist-format", {"podcast", "P"}),
+		ConfigHandlerException);
+	REQUIRE_NOTHROW(icons.handle_action("feedlist-icon", {"podcast", "♪"}));
+
+	std::vector<std::string> config;
+	icons.dump_config(config);
+	REQUIRE(config == std::vector<std::string>({"feedlist-icon podcast \"♪\""}));
+}
+
+TEST_CASE("glyph() keeps the icon column aligned when a glyph is double-width",
+	"[FeedIcons]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FeedIcons icons;
+	icons.handle_action("feedlist-icon", {"podcast", "音"});
+
+	const auto podcast = make_feed(&rsscache, 4, 2);
+	const auto plain = make_feed(&rsscache, 4, 1);
+	REQUIRE(icons.glyph(podcast) == "音");
+	REQUIRE(icons.glyph(plain) == "  ");
+
+	FmtStrFormatter fmt;
+	fmt.register_fmt('I', icons.glyph(podcast));
+	const auto podcast_line = fmt.do_format("%I|%t");
+	fmt.register_fmt('I', icons.glyph(plain));
+	const auto plain_line = fmt.do_format("%I|%t");
+	REQUIRE(utils::strwidth(podcast_line) == utils::strwidth(plain_line));
+}
+
+TEST_CASE("glyph() shows errors before queries, and queries before podcasts",
+	"[FeedIcons]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FeedIcons icons;
+
+	const auto feed = make_feed(&rsscache, 2, 2);
+	REQUIRE(icons.glyph(feed) == "P");
+
+	feed->set_rssurl("query:Podcasts:unread = \"yes\"");
+	REQUIRE(icons.glyph(feed) == "Q");
+
+	feed->set_status(DlStatus::DL_ERROR);
+	REQUIRE(icons.glyph(feed) == "!");
+}
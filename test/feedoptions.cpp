@@ -0,0 +1,72 @@
+#include "feedoptions.h"
+
+#include "3rd-party/catch.hpp"
+
+#include "confighandlerexception.h"
+
+using namespace newsboat;
+
+namespace {
+
+const std::string URL = "https://example.com/feed.xml";
+
+} // namespace
+
+TEST_CASE("feedopts requires a URL and options with values", "[FeedOptions]")
+{
+	FeedOptions options;
+	REQUIRE_THROWS_AS(options.handle_action("feedopts", {URL}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(options.handle_action("feedopts", {URL, "user-agent"}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(options.handle_action("feedopts",
+	{URL, "user-agent", "curl", "http-header"}),
+	ConfigHandlerException);
+	REQUIRE_THROWS_AS(options.handle_action("feedopts", {URL, "cookie", "x"}),
+		ConfigHandlerException);
+	REQUIRE_NOTHROW(options.handle_action("feedopts",
+		{URL, "user-agent", "curl"}));
+}
+
+TEST_CASE("feedopts sets user agent and headers of a single feed",
+	"[FeedOptions]")
+{
+	FeedOptions options;
+	options.handle_action("feedopts",
+	{URL, "http-header", "X-Auth: secret", "user-agent", "Mozilla/5.0"});
+	options.handle_action("feedopts", {URL, "http-header", "X-Tenant: 42"});
+
+	REQUIRE(options.user_agent(URL) == std::string("Mozilla/5.0"));
+	REQUIRE(options.http_headers(URL)
+		== std::vector<std::string>({"X-Auth: secret", "X-Tenant: 42"}));
+	REQUIRE(options.redacted_http_headers(URL)
+		== std::vector<std::string>({"X-Auth: [redacted]", "X-Tenant: [redacted]"}));
+
+	const std::string other = "https://example.com/other.xml";
+	REQUIRE_FALSE(options.user_agent(other).has_value());
+	REQUIRE(options.http_headers(other).empty());
+}
+
+TEST_CASE("feedopts rejects headers that would inject other headers",
+	"[FeedOptions]")
+{
+	FeedOptions options;
+	REQUIRE_THROWS_WITH(options.handle_action("feedopts",
+	{URL, "http-header", "X-Auth: secret\r\nCookie: session=1"}),
+	"value of header `X-Auth' contains a line break or a null byte");
+	REQUIRE_THROWS_WITH(options.handle_action("feedopts",
+	{URL, "http-header", "If-None-Match: \"abc\""}),
+	"header `If-None-Match' can't be set: Newsboat sets it itself");
+	REQUIRE(options.http_headers(URL).empty());
+}
+
+TEST_CASE("FeedOptions::dump_config() recreates the options", "[FeedOptions]")
+{
+	FeedOptions options;
+	options.handle_action("feedopts", {URL, "user-agent", "curl"});
+
+	std::vector<std::string> config;
+	options.dump_config(config);
+	REQUIRE(config == std::vector<std::string>({
+		"feedopts \"https://example.com/feed.xml\" user-agent \"curl\""}));
+}
@@ -0,0 +1,56 @@
+#include "feedpreview.h"
+
+#include "3rd-party/catch.hpp"
+#include "cache.h"
+#include "configcontainer.h"
+#include "rssfeed.h"
+#include "rssparser.h"
+
+using namespace newsboat;
+
+TEST_CASE("fetch() returns a feed that isn't stored in the cache",
+	"[feedpreview]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+
+	std::string error;
+	auto feed = feedpreview::fetch("file://data/rss.xml", &cfg, error);
+	REQUIRE(feed != nullptr);
+	REQUIRE(error.empty());
+	REQUIRE_FALSE(feed->is_cached());
+	REQUIRE(feed->rssurl() == "file://data/rss.xml");
+	REQUIRE(feed->total_item_count() == 8);
+
+	SECTION("reading articles of the preview doesn't touch the cache") {
+		for (const auto& item : feed->items()) {
+			item->set_unread(false);
+		}
+		feed->mark_all_items_read();
+
+		REQUIRE(feed->unread_item_count() == 0);
+		REQUIRE(rsscache.get_read_item_guids().empty());
+		REQUIRE(rsscache.internalize_rssfeed("file://data/rss.xml",
+				nullptr)->total_item_count() == 0);
+	}
+
+	SECTION("the preview's articles are gone when the feed is") {
+		const std::weak_ptr<RssFeed> weak_feed = feed;
+		feed.reset();
+		REQUIRE(weak_feed.expired());
+		REQUIRE(rsscache.internalize_rssfeed("file://data/rss.xml",
+				nullptr)->total_item_count() == 0);
+	}
+}
+
+TEST_CASE("fetch() reports URLs that don't point to a feed", "[feedpreview]")
+{
+	ConfigContainer cfg;
+
+	std::string error;
+	const auto feed = feedpreview::fetch("file://data/non-existent.xml", &cfg,
+			error);
+	REQUIRE(feed == nullptr);
+	REQUIRE_FALSE(error.empty());
+	REQUIRE(error.find("file://data/non-existent.xml") == 0);
+}
@@ -5,6 +5,7 @@
 #include <unistd.h>
 
 #include "3rd-party/catch.hpp"
+#include "test-helpers/chmod.h"
 #include "test-helpers/misc.h"
 #include "test-helpers/tempfile.h"
 
@@ -95,3 +96,259 @@ TEST_CASE("Preserves URLs as-is", "[FileUrlReader][issue926]")
 	REQUIRE(u.get_urls()[0] ==
 		R"_(exec:curl --silent https://feeds.metaebene.me/raumzeit/m4a  | sed 's#\(</guid>\|</id>\)#-M4A&#')_");
 }
+
+TEST_CASE("reload() reports where the file can't be parsed, and keeps the "
+	"feeds it read before", "[FileUrlReader]")
+{
+	TestHelpers::TempFile urlsFile;
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "http://test1.url.cc/feed.xml \"tag1\"\n";
+	}
+
+	FileUrlReader u(urlsFile.get_path());
+	u.reload();
+	REQUIRE_FALSE(u.get_error().has_value());
+	REQUIRE(u.get_urls().size() == 1);
+
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "http://test1.url.cc/feed.xml \"tag1\"\n"
+			"http://anotherfeed.com/ \"local news\n";
+	}
+	u.reload();
+	REQUIRE(u.get_error() ==
+		std::string("urls file, line 2, column 25: the quote isn't closed"));
+	REQUIRE(u.get_error_line() == 2);
+	REQUIRE(u.get_urls() ==
+		std::vector<std::string>({"http://test1.url.cc/feed.xml"}));
+	REQUIRE(u.get_alltags() == std::vector<std::string>({"tag1"}));
+
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "http://anotherfeed.com/ \"local news\"\n";
+	}
+	u.reload();
+	REQUIRE_FALSE(u.get_error().has_value());
+	REQUIRE(u.get_urls() ==
+		std::vector<std::string>({"http://anotherfeed.com/"}));
+}
+
+TEST_CASE("append_url() adds exactly one line to the end of the file",
+	"[FileUrlReader]")
+{
+	TestHelpers::TempFile urlsFile;
+
+	const auto read_file = [&]() {
+		std::ifstream in(urlsFile.get_path());
+		return std::string(std::istreambuf_iterator<char>(in),
+				std::istreambuf_iterator<char>());
+	};
+
+	SECTION("file doesn't exist yet") {
+		FileUrlReader u(urlsFile.get_path());
+		REQUIRE_FALSE(u.append_url("https://example.com/feed.xml").has_value());
+		REQUIRE(read_file() == "https://example.com/feed.xml\n");
+		REQUIRE(u.get_urls() ==
+			std::vector<std::string> {"https://example.com/feed.xml"});
+	}
+
+	SECTION("existing lines are kept as is") {
+		{
+			std::ofstream out(urlsFile.get_path());
+			out << "# my feeds\nhttp://test1.url.cc/feed.xml \"tag1\"";
+		}
+
+		FileUrlReader u(urlsFile.get_path());
+		u.reload();
+		REQUIRE_FALSE(u.append_url("https://example.com/feed.xml").has_value());
+		REQUIRE(read_file() ==
+			"# my feeds\n"
+			"http://test1.url.cc/feed.xml \"tag1\"\n"
+			"https://example.com/feed.xml\n");
+
+		FileUrlReader u2(urlsFile.get_path());
+		u2.reload();
+		REQUIRE(u2.get_urls().size() == 2);
+		REQUIRE(u2.get_urls()[1] == "https://example.com/feed.xml");
+	}
+
+	SECTION("URL with spaces is quoted") {
+		FileUrlReader u(urlsFile.get_path());
+		REQUIRE_FALSE(u.append_url("exec:~/bin/feed --all").has_value());
+		REQUIRE(read_file() == "\"exec:~/bin/feed --all\"\n");
+
+		FileUrlReader u2(urlsFile.get_path());
+		u2.reload();
+		REQUIRE(u2.get_urls() ==
+			std::vector<std::string> {"exec:~/bin/feed --all"});
+	}
+}
+
+TEST_CASE("write_config() leaves the file alone if it would contain something "
+	"that isn't a URL", "[FileUrlReader]")
+{
+	TestHelpers::TempFile urlsFile;
+	const std::string original =
+		"http://test1.url.cc/feed.xml\n"
+		"not-a-url \"tag1\"\n";
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << original;
+	}
+
+	FileUrlReader u(urlsFile.get_path());
+	u.reload();
+	REQUIRE(u.write_config().has_value());
+
+	std::ifstream in(urlsFile.get_path());
+	const std::string contents((std::istreambuf_iterator<char>(in)),
+		std::istreambuf_iterator<char>());
+	REQUIRE(contents == original);
+}
+
+TEST_CASE("URL reader reads tags from files that aren't UTF-8 as ISO-8859-1",
+	"[FileUrlReader]")
+{
+	FileUrlReader u("data/latin1-urls");
+	u.reload();
+
+	REQUIRE(u.get_urls().size() == 2);
+	REQUIRE(u.get_tags("https://example.com/feed.xml") ==
+		std::vector<std::string>({"Nachrichten f\u00fcr K\u00f6ln", "Stra\u00dfe"}));
+	REQUIRE(u.get_tags("https://example.com/other.xml") ==
+		std::vector<std::string>({"ascii"}));
+}
+
+TEST_CASE("add_tag(), remove_tag() and rename_tag() only change the lines "
+	"of the tagged feeds, and re-read the tags", "[FileUrlReader]")
+{
+	TestHelpers::TempFile urlsFile;
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "# my feeds\n"
+			"http://test1.url.cc/feed.xml   news  # daily\n"
+			"\n"
+			"http://anotherfeed.com/ news \"local news\"\n";
+	}
+
+	const auto read_file = [&]() {
+		std::ifstream in(urlsFile.get_path());
+		return std::string(std::istreambuf_iterator<char>(in),
+				std::istreambuf_iterator<char>());
+	};
+
+	FileUrlReader u(urlsFile.get_path());
+	u.reload();
+
+	SECTION("tags with spaces are quoted") {
+		REQUIRE_FALSE(u.add_tag("http://test1.url.cc/feed.xml",
+				"open source").has_value());
+		REQUIRE(read_file() ==
+			"# my feeds\n"
+			"http://test1.url.cc/feed.xml   news \"open source\"  # daily\n"
+			"\n"
+			"http://anotherfeed.com/ news \"local news\"\n");
+		REQUIRE(u.get_tags("http://test1.url.cc/feed.xml") ==
+			std::vector<std::string>({"news", "open source"}));
+		REQUIRE(u.get_alltags().size() == 3);
+	}
+
+	SECTION("removing a tag") {
+		REQUIRE_FALSE(u.remove_tag("http://anotherfeed.com/",
+				"local news").has_value());
+		REQUIRE(read_file() ==
+			"# my feeds\n"
+			"http://test1.url.cc/feed.xml   news  # daily\n"
+			"\n"
+			"http://anotherfeed.com/ news\n");
+		REQUIRE(u.get_alltags() == std::vector<std::string>({"news"}));
+	}
+
+	SECTION("renaming a tag") {
+		REQUIRE(u.count_feeds_with_tag("news") == 2);
+
+		std::size_t renamed = 0;
+		REQUIRE_FALSE(u.rename_tag("news", "headlines", renamed).has_value());
+		REQUIRE(renamed == 2);
+		REQUIRE(read_file() ==
+			"# my feeds\n"
+			"http://test1.url.cc/feed.xml   headlines  # daily\n"
+			"\n"
+			"http://anotherfeed.com/ headlines \"local news\"\n");
+		REQUIRE(u.count_feeds_with_tag("news") == 0);
+		REQUIRE(u.get_tags("http://anotherfeed.com/") ==
+			std::vector<std::string>({"headlines", "local news"}));
+	}
+
+	SECTION("the file is left alone if the tags can't be changed") {
+		const std::string original = read_file();
+		std::size_t renamed = 0;
+		REQUIRE(u.add_tag("http://test1.url.cc/feed.xml", "news").has_value());
+		REQUIRE(u.add_tag("https://example.com/missing.xml",
+				"news").has_value());
+		REQUIRE(u.add_tag("http://test1.url.cc/feed.xml", "~Title").has_value());
+		REQUIRE(u.remove_tag("http://test1.url.cc/feed.xml",
+				"local news").has_value());
+		REQUIRE(u.rename_tag("sports", "football", renamed).has_value());
+		REQUIRE(renamed == 0);
+		REQUIRE(read_file() == original);
+	}
+}
+
+TEST_CASE("A read-only urls file is left alone, and the error says so",
+	"[FileUrlReader]")
+{
+	TestHelpers::TempFile urlsFile;
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << "http://test1.url.cc/feed.xml\n";
+	}
+	TestHelpers::Chmod read_only(urlsFile.get_path(), 0444);
+
+	FileUrlReader u(urlsFile.get_path());
+	u.reload();
+	const auto error = u.append_url("https://example.com/feed.xml");
+	REQUIRE(error.has_value());
+	REQUIRE(error.value().find("read-only") != std::string::npos);
+	REQUIRE(u.get_urls().size() == 1);
+
+	std::ifstream in(urlsFile.get_path());
+	const std::string contents((std::istreambuf_iterator<char>(in)),
+		std::istreambuf_iterator<char>());
+	REQUIRE(contents == "http://test1.url.cc/feed.xml\n");
+}
+
+TEST_CASE("With an overlay, changes go into it, and it's merged when the urls "
+	"file is read", "[FileUrlReader]")
+{
+	TestHelpers::TempFile urlsFile;
+	TestHelpers::TempFile overlayFile;
+	const std::string original =
+		"http://test1.url.cc/feed.xml\n"
+		"http://anotherfeed.com/ news\n";
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << original;
+	}
+	TestHelpers::Chmod read_only(urlsFile.get_path(), 0444);
+
+	FileUrlReader u(urlsFile.get_path(), overlayFile.get_path());
+	u.reload();
+	REQUIRE_FALSE(u.append_url("https://example.com/feed.xml").has_value());
+	REQUIRE_FALSE(u.add_tag("http://test1.url.cc/feed.xml", "tech").has_value());
+
+	FileUrlReader merged(urlsFile.get_path(), overlayFile.get_path());
+	merged.reload();
+	REQUIRE(merged.get_urls() == std::vector<std::string>({
+		"http://test1.url.cc/feed.xml",
+		"http://anotherfeed.com/",
+		"https://example.com/feed.xml"}));
+	REQUIRE(merged.get_tags("http://test1.url.cc/feed.xml")
+		== std::vector<std::string>({"tech"}));
+
+	FileUrlReader base(urlsFile.get_path());
+	base.reload();
+	REQUIRE(base.get_urls().size() == 2);
+	REQUIRE(base.get_tags("http://test1.url.cc/feed.xml").empty());
+}
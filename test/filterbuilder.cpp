@@ -0,0 +1,47 @@
+#include "filterbuilder.h"
+
+#include "3rd-party/catch.hpp"
+
+using namespace newsboat;
+
+TEST_CASE("FilterBuilder walks through attribute, operator and value",
+	"[FilterBuilder]")
+{
+	FilterBuilder builder("article");
+	REQUIRE(builder.stage() == FilterBuilder::Stage::ATTRIBUTE);
+	REQUIRE_FALSE(builder.expression().has_value());
+
+	const auto attributes = builder.items();
+	unsigned int unread = 0;
+	while (attributes.at(unread).find("unread ") != 0) {
+		unread++;
+	}
+	builder.select(unread);
+	REQUIRE(builder.stage() == FilterBuilder::Stage::OPERATOR);
+	REQUIRE(builder.items().size() == 2);
+
+	builder.select(0);
+	REQUIRE(builder.stage() == FilterBuilder::Stage::VALUE);
+	REQUIRE(builder.prompt() == "unread = (yes or no): ");
+
+	SECTION("invalid values are explained") {
+		const auto error = builder.set_value("maybe");
+		REQUIRE(error.has_value());
+		REQUIRE(error.value() == "Expected `yes' or `no'.");
+		REQUIRE(builder.stage() == FilterBuilder::Stage::VALUE);
+	}
+
+	SECTION("valid values complete the condition") {
+		REQUIRE_FALSE(builder.set_value("yes").has_value());
+		REQUIRE(builder.stage() == FilterBuilder::Stage::NEXT);
+		REQUIRE(builder.expression().value() == "unread = \"yes\"");
+		REQUIRE(builder.title() ==
+			"Add a condition, or apply the filter: unread = \"yes\"");
+	}
+
+	SECTION("cancelling the value goes back to the operators") {
+		builder.cancel_value();
+		REQUIRE(builder.stage() == FilterBuilder::Stage::OPERATOR);
+		REQUIRE(builder.prompt() == "");
+	}
+}
@@ -406,3 +406,74 @@ TEST_CASE("Center Text formatting",
 	REQUIRE(fmt.do_format("%=3T", 0) == "wha");
 	REQUIRE(fmt.do_format("%=0T", 20) == "      whatever      ");
 }
+
+TEST_CASE("do_format_wrapped() continues a value on indented rows",
+	"[FmtStrFormatter]")
+{
+	FmtStrFormatter fmt;
+
+	fmt.register_fmt('n', "N");
+	fmt.register_fmt('t', "The quick brown fox jumps over the lazy dog");
+
+	REQUIRE(fmt.do_format_wrapped("%n %t", 16, 't', 3) ==
+		std::vector<std::string>({
+		"N The quick",
+		"  brown fox",
+		"  jumps over the",
+	}));
+	REQUIRE(fmt.do_format_wrapped("%n %t", 16, 't', 1) ==
+		std::vector<std::string>({"N The quick brow"}));
+}
+
+TEST_CASE("Centering spacer fills both sides of the text that follows",
+	"[FmtStrFormatter]")
+{
+	FmtStrFormatter fmt;
+
+	fmt.register_fmt('t', "title");
+
+	REQUIRE(fmt.do_format("%=-%t", 11) == "---title---");
+	REQUIRE(fmt.do_format("%=-%t", 0) == "-title");
+}
+
+TEST_CASE("set_truncation_marker() ends values that were cut with a marker",
+	"[FmtStrFormatter]")
+{
+	FmtStrFormatter fmt;
+
+	fmt.register_fmt('t', "abcdef");
+	REQUIRE(fmt.do_format("%-5t") == "abcde");
+
+	fmt.set_truncation_marker("…");
+	REQUIRE(fmt.do_format("%-5t") == "abcd…");
+	REQUIRE(fmt.do_format("%-6t") == "abcdef");
+}
+
+TEST_CASE("Conditionals can be nested in either branch of another conditional",
+	"[FmtStrFormatter]")
+{
+	FmtStrFormatter fmt;
+	const std::string format = "%?F?[%F]&%?a?by %a&-??";
+
+	fmt.register_fmt('F', "!U");
+	fmt.register_fmt('a', "Jane");
+	REQUIRE(fmt.do_format(format) == "[!U]");
+
+	fmt.register_fmt('F', "");
+	REQUIRE(fmt.do_format(format) == "by Jane");
+
+	fmt.register_fmt('a', "");
+	REQUIRE(fmt.do_format(format) == "-");
+}
+
+TEST_CASE("\\? and \\& are a literal question mark and ampersand inside "
+	"conditionals",
+	"[FmtStrFormatter]")
+{
+	FmtStrFormatter fmt;
+
+	fmt.register_fmt('x', "x");
+
+	REQUIRE(fmt.do_format("%?x?why\\? R\\&D&none?") == "why? R&D");
+	REQUIRE(fmt.do_format("why\\? R\\&D") == "why\\? R\\&D");
+}
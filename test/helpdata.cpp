@@ -0,0 +1,62 @@
+#include "helpdata.h"
+
+#include <fstream>
+
+#include "3rd-party/catch.hpp"
+#include "keymap.h"
+
+using namespace newsboat;
+
+TEST_CASE("HelpData lists the default bindings of the article list",
+	"[HelpData]")
+{
+	KeyMap keys(KM_NEWSBOAT);
+	HelpData help(keys, "articlelist");
+
+	std::vector<std::string> expected;
+	std::ifstream snapshot("data/help-articlelist.txt");
+	std::string line;
+	while (std::getline(snapshot, line)) {
+		expected.push_back(line);
+	}
+	REQUIRE(expected.size() > 0);
+
+	REQUIRE(help.lines(80) == expected);
+}
+
+TEST_CASE("HelpData only lists operations of its context and generic ones",
+	"[HelpData]")
+{
+	KeyMap keys(KM_NEWSBOAT);
+	HelpData help(keys, "feedlist");
+
+	const auto lines = help.lines(0);
+	const auto has_line_with = [&lines](const std::string& text) {
+		for (const auto& line : lines) {
+			if (line.find(text) != std::string::npos) {
+				return true;
+			}
+		}
+		return false;
+	};
+
+	REQUIRE(has_line_with("reload-all"));
+	REQUIRE(has_line_with("pagedown"));
+	REQUIRE_FALSE(has_line_with("toggle-source-view"));
+	REQUIRE_FALSE(has_line_with("pb-download"));
+}
+
+TEST_CASE("HelpData::set_filter() narrows the rows down and highlights matches",
+	"[HelpData]")
+{
+	KeyMap keys(KM_NEWSBOAT);
+	HelpData help(keys, "articlelist");
+
+	help.set_filter("NEXT-UNREAD-FEED");
+
+	const std::vector<std::string> expected = {
+		"Navigation:",
+		"^N              <hl>next-unread-feed</>        Go to next unread feed",
+	};
+	REQUIRE(help.lines(80) == expected);
+}
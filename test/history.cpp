@@ -96,3 +96,25 @@ TEST_CASE("Only the most recent lines are saved when limiting history",
 		}
 	}
 }
+
+TEST_CASE("search_prefix() finds the first matching line going back from the "
+	"current position",
+	"[History]")
+{
+	History h;
+	h.add_line("save");
+	h.add_line("set bar");
+	h.add_line("open");
+	h.add_line("set foo");
+	// Repeating the newest line doesn't add it again
+	h.add_line("set foo");
+
+	REQUIRE(h.search_prefix("set") == "set foo");
+	REQUIRE_FALSE(h.search_prefix("quit").has_value());
+
+	REQUIRE(h.previous_line() == "set foo");
+	REQUIRE(h.search_prefix("set") == "set bar");
+	REQUIRE(h.previous_line() == "open");
+	REQUIRE(h.previous_line() == "set bar");
+	REQUIRE_FALSE(h.search_prefix("set").has_value());
+}
@@ -1,5 +1,6 @@
 #include "htmlrenderer.h"
 
+#include <algorithm>
 #include <sstream>
 
 #include "3rd-party/catch.hpp"
@@ -1465,3 +1466,190 @@ TEST_CASE("Ordered list can contain unordered list in its items",
 
 	REQUIRE(links.size() == 0);
 }
+
+TEST_CASE("Rendering a bit at a time produces the same output as rendering "
+	"in one go",
+	"[HtmlRenderer]")
+{
+	const std::vector<std::string> documents = {
+		"<p>Hello, <a href='/world'>world</a>!</p><img src='a.png'>",
+		"<ol type='i'><li>one<ul><li>nested</li><li>list</li></ul></li>"
+		"<li>two</li></ol><pre>  pre\n  formatted</pre>",
+		"<table border='1'><tr><td>unfinished table<td>cell",
+		"<h1>Title</h1><script>ignore();</script><blockquote>quoted"
+		"<br>text</blockquote><hr><video src='v.mp4'>fallback</video>",
+	};
+
+	for (const auto& document : documents) {
+		HtmlRenderer rnd;
+		std::vector<std::pair<LineType, std::string>> lines;
+		std::vector<LinkPair> links = {
+			LinkPair("https://example.com/podcast.mp3", LinkType::AUDIO)
+		};
+		const auto seeded_links = links;
+		rnd.render(document, lines, links, url);
+
+		const std::vector<std::size_t> units = {1, 2, 3};
+		for (const auto nodes : units) {
+			HtmlRenderer::State state(document, url);
+			state.links = seeded_links;
+			std::size_t calls = 1;
+			while (!rnd.render_some(state, nodes)) {
+				calls++;
+			}
+
+			INFO("Document: " << document << ", nodes per call: " << nodes);
+			REQUIRE(calls > 1);
+			REQUIRE(state.is_finished());
+			REQUIRE(state.lines == lines);
+			REQUIRE(state.links == links);
+			// Rendering past the end changes nothing
+			REQUIRE(rnd.render_some(state, nodes));
+			REQUIRE(state.lines == lines);
+		}
+	}
+}
+
+TEST_CASE("render_some() only adds complete lines, and the links at the end",
+	"[HtmlRenderer]")
+{
+	HtmlRenderer rnd;
+	HtmlRenderer::State state("<p>first <a href='/a'>link</a></p><p>second</p>",
+		url);
+
+	// <p>, "first ", <a>, "link", </a>, </p>
+	REQUIRE_FALSE(rnd.render_some(state, 6));
+	REQUIRE(state.lines == std::vector<std::pair<LineType, std::string>>({
+		p(LineType::wrappable, "first <u>link</>[1]"),
+	}));
+	REQUIRE(state.links.size() == 1);
+
+	REQUIRE(rnd.render_some(state, 100));
+	REQUIRE(state.lines.back() ==
+		p(LineType::softwrappable, "[1]: http://example.com/a (link)"));
+}
+
+namespace {
+
+std::string nested(const std::string& open, const std::string& close,
+	unsigned int depth, const std::string& text)
+{
+	std::string result;
+	for (unsigned int i = 0; i < depth; ++i) {
+		result.append(open);
+	}
+	result.append(text);
+	for (unsigned int i = 0; i < depth; ++i) {
+		result.append(close);
+	}
+	return result;
+}
+
+std::size_t leading_spaces(const std::string& line)
+{
+	const auto pos = line.find_first_not_of(' ');
+	return pos == std::string::npos ? line.length() : pos;
+}
+
+std::size_t count_markers(
+	const std::vector<std::pair<LineType, std::string>>& lines)
+{
+	return std::count_if(lines.begin(), lines.end(),
+	[](const std::pair<LineType, std::string>& line) {
+		return line.second.find("[nesting truncated]") != std::string::npos;
+	});
+}
+
+} // namespace
+
+TEST_CASE("Blockquotes nested too deep are rendered at the deepest allowed "
+	"indentation, after a single marker",
+	"[HtmlRenderer]")
+{
+	HtmlRenderer rnd;
+	const auto input = nested("<blockquote>", "</blockquote>", 1000, "deep");
+
+	HtmlRenderer::State state(input, url);
+	while (!rnd.render_some(state, 100)) {
+	}
+
+	REQUIRE(state.get_truncated_nestings() ==
+		1000 - HtmlRenderer::DEFAULT_MAX_NESTING);
+	REQUIRE(count_markers(state.lines) == 1);
+
+	const std::string indentation(HtmlRenderer::DEFAULT_MAX_NESTING * 2, ' ');
+	for (const auto& line : state.lines) {
+		REQUIRE(leading_spaces(line.second) <= indentation.length());
+	}
+	REQUIRE(std::find(state.lines.begin(), state.lines.end(),
+			p(LineType::wrappable, (indentation + "deep").c_str()))
+		!= state.lines.end());
+}
+
+TEST_CASE("Lists nested too deep stop being indented", "[HtmlRenderer]")
+{
+	HtmlRenderer rnd;
+	const auto input = nested("<ul><li>", "</li></ul>", 1000, "deep");
+	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
+
+	rnd.render(input, lines, links, url);
+
+	REQUIRE(count_markers(lines) == 1);
+	// Each list item indents by two levels of two spaces each
+	const auto max_indentation = HtmlRenderer::DEFAULT_MAX_NESTING * 4;
+	for (const auto& line : lines) {
+		REQUIRE(leading_spaces(line.second) <= max_indentation + 2);
+	}
+}
+
+TEST_CASE("The nesting limit can be changed", "[HtmlRenderer]")
+{
+	HtmlRenderer rnd(false, 2);
+	const auto input = nested("<blockquote>", "</blockquote>", 3, "deep");
+	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
+
+	rnd.render(input, lines, links, url);
+
+	REQUIRE(count_markers(lines) == 1);
+	REQUIRE(std::find(lines.begin(), lines.end(),
+			p(LineType::wrappable, "    [nesting truncated]")) != lines.end());
+	REQUIRE(std::find(lines.begin(), lines.end(),
+			p(LineType::wrappable, "    deep")) != lines.end());
+}
+
+TEST_CASE("Documents within the nesting limit are rendered as before",
+	"[HtmlRenderer]")
+{
+	const auto depth = HtmlRenderer::DEFAULT_MAX_NESTING;
+	const std::vector<std::string> documents = {
+		nested("<blockquote>", "</blockquote>", depth, "quoted"),
+		nested("<ul><li>", "</li></ul>", depth / 2,
+			nested("<blockquote>", "</blockquote>", depth / 2, "mixed")),
+		"<dl><dt>term</dt><dd>" +
+		nested("<ol><li>", "</li></ol>", depth - 1, "described") +
+		"</dd></dl>",
+		// Stray closing tags don't make room for deeper levels
+		"</blockquote></li></dd>" + nested("<blockquote>", "</blockquote>",
+			depth, "stray"),
+	};
+
+	for (const auto& document : documents) {
+		INFO("Document: " << document);
+
+		HtmlRenderer limited;
+		HtmlRenderer::State state(document, url);
+		while (!limited.render_some(state, 1000)) {
+		}
+
+		HtmlRenderer unlimited(false, 100000);
+		std::vector<std::pair<LineType, std::string>> lines;
+		std::vector<LinkPair> links;
+		unlimited.render(document, lines, links, url);
+
+		REQUIRE(state.get_truncated_nestings() == 0);
+		REQUIRE(count_markers(state.lines) == 0);
+		REQUIRE(state.lines == lines);
+	}
+}
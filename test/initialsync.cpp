@@ -0,0 +1,208 @@
+#include "initialsync.h"
+
+#include <cstdint>
+#include <vector>
+
+#include "3rd-party/catch.hpp"
+
+#include "cache.h"
+#include "configcontainer.h"
+#include "rssfeed.h"
+#include "rssparser.h"
+
+using namespace newsboat;
+
+namespace {
+
+const std::string FEED_ID = "42";
+
+/// A Miniflux feed with articles that have IDs from 1 to `articles`.
+class FakeApi {
+public:
+	explicit FakeApi(std::int64_t articles)
+		: articles(articles)
+	{
+	}
+
+	nonstd::optional<rsspp::Feed> fetch(unsigned int limit,
+		nonstd::optional<std::int64_t> before_id)
+	{
+		requests++;
+		if (failures_left > 0) {
+			failures_left--;
+			return nonstd::nullopt;
+		}
+
+		rsspp::Feed page;
+		page.rss_version = rsspp::Feed::MINIFLUX_JSON;
+		const std::int64_t newest = before_id.value_or(articles + 1) - 1;
+		for (std::int64_t id = newest; id > 0 && page.items.size() < limit; --id) {
+			rsspp::Item item;
+			item.guid = std::to_string(id);
+			item.title = "Article " + item.guid;
+			item.pubDate = "Sat, 01 Aug 2020 12:00:00 +0000";
+			page.items.push_back(item);
+		}
+		return page;
+	}
+
+	std::int64_t articles;
+	unsigned int requests = 0;
+	unsigned int failures_left = 0;
+};
+
+class SyncFixture {
+public:
+	SyncFixture()
+		: rsscache(":memory:", &cfg)
+		, parser(FEED_ID, &rsscache, &cfg, nullptr)
+	{
+	}
+
+	InitialSync::FetchPage fetch(FakeApi& api)
+	{
+		return [&api](unsigned int limit, nonstd::optional<std::int64_t> before) {
+			return api.fetch(limit, before);
+		};
+	}
+
+	InitialSync::StorePage store()
+	{
+		return [this](const rsspp::Feed& page, const std::string& progress) {
+			rsscache.store_sync_page(parser.parse_page(page), false, progress);
+		};
+	}
+
+	unsigned int cached_articles()
+	{
+		return rsscache.internalize_rssfeed(FEED_ID, nullptr)->total_item_count();
+	}
+
+	ConfigContainer cfg;
+	Cache rsscache;
+	RssParser parser;
+};
+
+void ignore_status(const std::string&) {}
+
+void no_sleep(std::chrono::milliseconds) {}
+
+} // namespace
+
+TEST_CASE("run() stores the articles page by page, and they can be read "
+	"as soon as the first page is in",
+	"[InitialSync]")
+{
+	SyncFixture fixture;
+	FakeApi api(25);
+	InitialSync sync(10, 1, std::chrono::milliseconds(0), "");
+
+	std::vector<unsigned int> cached_before_request;
+	const auto fetch = [&](unsigned int limit,
+	nonstd::optional<std::int64_t> before) {
+		cached_before_request.push_back(fixture.cached_articles());
+		return api.fetch(limit, before);
+	};
+	std::vector<std::string> statuses;
+	const auto report = [&](const std::string& status) {
+		statuses.push_back(status);
+	};
+
+	REQUIRE(sync.run(fetch, fixture.store(), []() {
+		return false;
+	}, report, no_sleep));
+
+	REQUIRE(sync.is_complete());
+	REQUIRE(cached_before_request == std::vector<unsigned int>({0, 10, 20}));
+	REQUIRE(fixture.cached_articles() == 25);
+	REQUIRE(statuses.size() == 3);
+	REQUIRE(statuses.back() == "first sync complete: 25 articles in 3 pages");
+
+	const auto progress = fixture.rsscache.fetch_sync_progress(FEED_ID);
+	REQUIRE(progress.has_value());
+	REQUIRE(InitialSync::is_complete(progress.value()));
+}
+
+TEST_CASE("An interrupted sync resumes after the last stored page",
+	"[InitialSync]")
+{
+	SyncFixture fixture;
+	FakeApi api(45);
+
+	{
+		InitialSync sync(10, 1, std::chrono::milliseconds(0), "");
+		unsigned int pages = 0;
+		const auto count_pages = [&](const std::string&) {
+			pages++;
+		};
+		REQUIRE_FALSE(sync.run(fixture.fetch(api), fixture.store(), [&]() {
+			return pages == 2;
+		}, count_pages, no_sleep));
+		REQUIRE(fixture.cached_articles() == 20);
+	}
+
+	// Newsboat was restarted
+	const auto progress = fixture.rsscache.fetch_sync_progress(FEED_ID);
+	REQUIRE(progress.has_value());
+	REQUIRE_FALSE(InitialSync::is_complete(progress.value()));
+
+	InitialSync sync(10, 1, std::chrono::milliseconds(0), progress.value());
+	REQUIRE(sync.run(fixture.fetch(api), fixture.store(), []() {
+		return false;
+	}, ignore_status, no_sleep));
+	REQUIRE(fixture.cached_articles() == 45);
+	// No page was fetched twice
+	REQUIRE(api.requests == 5);
+	REQUIRE(sync.status() == "first sync complete: 45 articles in 5 pages");
+}
+
+TEST_CASE("run() retries a failed page with growing pauses, then gives up",
+	"[InitialSync]")
+{
+	SyncFixture fixture;
+	FakeApi api(15);
+	std::chrono::milliseconds slept(0);
+	const auto sleep = [&](std::chrono::milliseconds duration) {
+		slept += duration;
+	};
+
+	SECTION("a page that fails fewer times than the attempts is retried") {
+		api.failures_left = 2;
+		InitialSync sync(10, 3, std::chrono::milliseconds(0), "");
+		REQUIRE(sync.run(fixture.fetch(api), fixture.store(), []() {
+			return false;
+		}, ignore_status, sleep));
+		REQUIRE(fixture.cached_articles() == 15);
+		// Backoff starts at a second, and doubles with each failure
+		REQUIRE(slept == std::chrono::milliseconds(3000));
+	}
+
+	SECTION("a page that keeps failing stops the sync, with progress intact") {
+		api.failures_left = 3;
+		InitialSync sync(10, 3, std::chrono::milliseconds(0), "");
+		REQUIRE_FALSE(sync.run(fixture.fetch(api), fixture.store(), []() {
+			return false;
+		}, ignore_status, sleep));
+		REQUIRE(fixture.cached_articles() == 0);
+		REQUIRE_FALSE(fixture.rsscache.fetch_sync_progress(FEED_ID).has_value());
+	}
+}
+
+TEST_CASE("run() pauses between pages", "[InitialSync]")
+{
+	SyncFixture fixture;
+	FakeApi api(25);
+	std::vector<std::chrono::milliseconds> pauses;
+	const auto sleep = [&](std::chrono::milliseconds duration) {
+		pauses.push_back(duration);
+	};
+
+	InitialSync sync(10, 1, std::chrono::milliseconds(300), "");
+	REQUIRE(sync.run(fixture.fetch(api), fixture.store(), []() {
+		return false;
+	}, ignore_status, sleep));
+	REQUIRE(pauses == std::vector<std::chrono::milliseconds>({
+		std::chrono::milliseconds(300),
+		std::chrono::milliseconds(300)
+	}));
+}
@@ -486,6 +486,48 @@ TEST_CASE("OP_BOOKMARK pipes articles url and title to bookmark-command",
 		separator + feed_title);
 }
 
+TEST_CASE("OP_EDITNOTE arguments replace an item's note",
+	"[ItemListFormAction]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FilterContainer filters;
+	RegexManager rxman;
+
+	std::vector<std::string> op_args;
+
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	std::shared_ptr<RssFeed> feed = std::make_shared<RssFeed>(&rsscache);
+	std::shared_ptr<RssItem> item = std::make_shared<RssItem>(&rsscache);
+	item->set_note("old note");
+
+	ItemListFormAction itemlist(&v, itemlist_str, &rsscache, filters, &cfg, rxman);
+
+	feed->add_item(item);
+	itemlist.set_feed(feed);
+
+	SECTION("A new note") {
+		op_args.push_back("for the weekly report");
+
+		REQUIRE_NOTHROW(
+			itemlist.process_op(OP_EDITNOTE, true, &op_args));
+		REQUIRE(item->note() == "for the weekly report");
+	}
+
+	SECTION("An empty argument removes the note") {
+		op_args.push_back("");
+
+		REQUIRE_NOTHROW(
+			itemlist.process_op(OP_EDITNOTE, true, &op_args));
+		REQUIRE(item->note() == "");
+	}
+}
+
 TEST_CASE("OP_EDITFLAGS arguments are added to an item's flags",
 	"[ItemListFormAction]")
 {
@@ -575,6 +617,68 @@ TEST_CASE("OP_EDITFLAGS arguments are added to an item's flags",
 	}
 }
 
+TEST_CASE("Batch operations apply to all selected articles and then clear "
+	"the selection", "[ItemListFormAction]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FilterContainer filters;
+	RegexManager rxman;
+
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	std::shared_ptr<RssFeed> feed = std::make_shared<RssFeed>(&rsscache);
+	std::vector<std::shared_ptr<RssItem>> items;
+	for (const auto& guid : {
+			"guid-1", "guid-2", "guid-3"
+		}) {
+		auto item = std::make_shared<RssItem>(&rsscache);
+		item->set_guid(guid);
+		item->set_unread_nowrite(true);
+		feed->add_item(item);
+		items.push_back(item);
+	}
+
+	ItemListFormAction itemlist(&v, itemlist_str, &rsscache, filters, &cfg, rxman);
+	itemlist.set_feed(feed);
+
+	// Selecting an article moves the cursor to the next one
+	REQUIRE_NOTHROW(itemlist.process_op(OP_TOGGLE_SELECT));
+	REQUIRE_NOTHROW(itemlist.process_op(OP_TOGGLE_SELECT));
+
+	SECTION("toggle-article-read marks the selection read") {
+		REQUIRE_NOTHROW(itemlist.process_op(OP_TOGGLEITEMREAD));
+		REQUIRE_FALSE(items[0]->unread());
+		REQUIRE_FALSE(items[1]->unread());
+		REQUIRE(items[2]->unread());
+
+		// Afterwards, it's back to the article under the cursor
+		std::vector<std::string> op_args = {"read"};
+		REQUIRE_NOTHROW(itemlist.process_op(OP_TOGGLEITEMREAD, true, &op_args));
+		REQUIRE_FALSE(items[2]->unread());
+	}
+
+	SECTION("edit-flags sets the flags of the selection") {
+		std::vector<std::string> op_args = {"ab"};
+		REQUIRE_NOTHROW(itemlist.process_op(OP_EDITFLAGS, true, &op_args));
+		REQUIRE(items[0]->flags() == "ab");
+		REQUIRE(items[1]->flags() == "ab");
+		REQUIRE(items[2]->flags().empty());
+	}
+
+	SECTION("delete-article deletes the selection") {
+		REQUIRE_NOTHROW(itemlist.process_op(OP_DELETE));
+		REQUIRE(items[0]->deleted());
+		REQUIRE(items[1]->deleted());
+		REQUIRE_FALSE(items[0]->unread());
+		REQUIRE_FALSE(items[2]->deleted());
+	}
+}
+
 TEST_CASE("OP_SAVE writes an article's attributes to the specified file",
 	"[ItemListFormAction]")
 {
@@ -817,3 +921,274 @@ TEST_CASE("OP_PIPE_TO pipes an article's content to an external command",
 		test_url,
 		test_description);
 }
+
+TEST_CASE("OP_INT_SET fails, and keeps the old value, if the value is invalid",
+	"[ItemListFormAction]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FilterContainer filters;
+	RegexManager rxman;
+
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	ItemListFormAction itemlist(&v, itemlist_str, &rsscache, filters, &cfg, rxman);
+	const auto old_format = cfg.get_configvalue("articlelist-format");
+
+	SECTION("An invalid format string") {
+		std::vector<std::string> op_args = {"articlelist-format", "%i %"};
+
+		REQUIRE_FALSE(itemlist.process_op(OP_INT_SET, true, &op_args));
+		REQUIRE(cfg.get_configvalue("articlelist-format") == old_format);
+	}
+
+	SECTION("A valid format string") {
+		std::vector<std::string> op_args = {"articlelist-format", "%i %t"};
+
+		REQUIRE(itemlist.process_op(OP_INT_SET, true, &op_args));
+		REQUIRE(cfg.get_configvalue("articlelist-format") == "%i %t");
+	}
+}
+
+TEST_CASE("Title counts articles that match the filter, and follows mark-read",
+	"[ItemListFormAction]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FilterContainer filters;
+	RegexManager rxman;
+
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	cfg.set_configvalue("articlelist-title-format", "%m/%r of %u/%t");
+
+	std::shared_ptr<RssFeed> feed = std::make_shared<RssFeed>(&rsscache);
+	const std::vector<std::string> titles = {
+		"Rust 1.44 released", "Rust in Newsboat", "C++ news", "Weather"
+	};
+	for (const auto& title : titles) {
+		std::shared_ptr<RssItem> item = std::make_shared<RssItem>(&rsscache);
+		item->set_guid("guid-" + title);
+		item->set_title(title);
+		feed->add_item(item);
+	}
+	feed->items()[3]->set_unread_nowrite(false);
+
+	ItemListFormAction itemlist(&v, itemlist_str, &rsscache, filters, &cfg, rxman);
+	itemlist.set_feed(feed);
+
+	SECTION("Without a filter, all articles are counted") {
+		itemlist.prepare();
+		REQUIRE(itemlist.get_value("head") == "4/3 of 3/4");
+	}
+
+	SECTION("With a filter, only the matching articles are counted") {
+		std::vector<std::string> filter_args = {"title =~ \"Rust\""};
+		REQUIRE(itemlist.process_op(OP_SETFILTER, true, &filter_args));
+		itemlist.prepare();
+		REQUIRE(itemlist.get_value("head") == "2/2 of 3/4");
+
+		std::vector<std::string> read_args = {"read"};
+		REQUIRE(itemlist.process_op(OP_TOGGLEITEMREAD, true, &read_args));
+		itemlist.prepare();
+		REQUIRE(itemlist.get_value("head") == "2/1 of 2/4");
+	}
+
+	SECTION("Articles leave the scope when the filter stops matching them") {
+		std::vector<std::string> filter_args = {"unread = \"yes\""};
+		REQUIRE(itemlist.process_op(OP_SETFILTER, true, &filter_args));
+		itemlist.prepare();
+		REQUIRE(itemlist.get_value("head") == "3/3 of 3/4");
+
+		std::vector<std::string> read_args = {"read"};
+		REQUIRE(itemlist.process_op(OP_TOGGLEITEMREAD, true, &read_args));
+		itemlist.prepare();
+		// The article stays in the list until it's redrawn, but isn't
+		// counted anymore
+		REQUIRE(itemlist.get_value("head") == "2/2 of 2/4");
+	}
+}
+
+TEST_CASE("first-unread, last-unread and goto-percent move the cursor",
+	"[ItemListFormAction]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FilterContainer filters;
+	RegexManager rxman;
+
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	std::shared_ptr<RssFeed> feed = std::make_shared<RssFeed>(&rsscache);
+	std::vector<std::shared_ptr<RssItem>> items;
+	for (unsigned int i = 0; i < 11; ++i) {
+		auto item = std::make_shared<RssItem>(&rsscache);
+		item->set_guid(strprintf::fmt("guid-%u", i));
+		// Articles 3, 4 and 7 are unread
+		item->set_unread_nowrite(i == 3 || i == 4 || i == 7);
+		feed->add_item(item);
+		items.push_back(item);
+	}
+
+	ItemListFormAction itemlist(&v, itemlist_str, &rsscache, filters, &cfg, rxman);
+	itemlist.set_feed(feed);
+
+	SECTION("first-unread and last-unread go to the ends of the unread articles") {
+		REQUIRE(itemlist.process_op(OP_LAST_UNREAD));
+		REQUIRE(itemlist.get_guid() == "guid-7");
+		REQUIRE(itemlist.process_op(OP_FIRST_UNREAD));
+		REQUIRE(itemlist.get_guid() == "guid-3");
+
+		// Articles that were read elsewhere are skipped
+		items[7]->set_unread_nowrite(false);
+		REQUIRE(itemlist.process_op(OP_LAST_UNREAD));
+		REQUIRE(itemlist.get_guid() == "guid-4");
+
+		items[3]->set_unread_nowrite(false);
+		items[4]->set_unread_nowrite(false);
+		REQUIRE_FALSE(itemlist.process_op(OP_FIRST_UNREAD));
+	}
+
+	SECTION("goto-percent maps 0 to 100 onto the list") {
+		std::vector<std::string> op_args = {"50"};
+		REQUIRE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-5");
+
+		op_args = {"100"};
+		REQUIRE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-10");
+
+		op_args = {"0"};
+		REQUIRE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-0");
+	}
+
+	SECTION("goto-percent fails, and stays put, if its argument is wrong") {
+		std::vector<std::string> op_args = {"30"};
+		REQUIRE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-3");
+
+		op_args = {"101"};
+		REQUIRE_FALSE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		op_args = {};
+		REQUIRE_FALSE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-3");
+	}
+}
+
+TEST_CASE("mark-above-read and mark-below-read mark the shown articles beside "
+	"the cursor, and can be undone", "[ItemListFormAction]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	FilterContainer filters;
+	RegexManager rxman;
+
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	std::shared_ptr<RssFeed> feed = std::make_shared<RssFeed>(&rsscache);
+	std::vector<std::shared_ptr<RssItem>> items;
+	for (unsigned int i = 0; i < 11; ++i) {
+		auto item = std::make_shared<RssItem>(&rsscache);
+		item->set_guid(strprintf::fmt("guid-%u", i));
+		item->set_title(std::string(1, 'a' + i));
+		// Articles 2 and 8 are read
+		item->set_unread_nowrite(i != 2 && i != 8);
+		feed->add_item(item);
+		items.push_back(item);
+	}
+
+	const auto unread_guids = [&]() {
+		std::vector<std::string> result;
+		for (const auto& item : items) {
+			if (item->unread()) {
+				result.push_back(item->guid());
+			}
+		}
+		return result;
+	};
+
+	ItemListFormAction itemlist(&v, itemlist_str, &rsscache, filters, &cfg, rxman);
+
+	SECTION("Only the articles beside the cursor are marked, and undo "
+		"restores exactly those") {
+		itemlist.set_feed(feed);
+		std::vector<std::string> op_args = {"50"};
+		REQUIRE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-5");
+
+		REQUIRE(itemlist.process_op(OP_MARKBELOWREAD));
+		REQUIRE(itemlist.get_guid() == "guid-5");
+		REQUIRE(unread_guids() == std::vector<std::string>({
+			"guid-0", "guid-1", "guid-3", "guid-4", "guid-5"}));
+
+		REQUIRE(itemlist.process_op(OP_MARKABOVEREAD));
+		REQUIRE(unread_guids() == std::vector<std::string>({"guid-5"}));
+
+		REQUIRE(itemlist.process_op(OP_UNDOMARKREAD));
+		REQUIRE(unread_guids() == std::vector<std::string>({
+			"guid-0", "guid-1", "guid-3", "guid-4", "guid-5"}));
+		REQUIRE(itemlist.process_op(OP_UNDOMARKREAD));
+		REQUIRE(unread_guids() == std::vector<std::string>({
+			"guid-0", "guid-1", "guid-3", "guid-4", "guid-5", "guid-6",
+			"guid-7", "guid-9", "guid-10"}));
+		REQUIRE_FALSE(itemlist.process_op(OP_UNDOMARKREAD));
+	}
+
+	SECTION("Nothing is marked beyond the ends of the list") {
+		itemlist.set_feed(feed);
+		REQUIRE(itemlist.get_guid() == "guid-0");
+		REQUIRE(itemlist.process_op(OP_MARKABOVEREAD));
+		REQUIRE(unread_guids().size() == 9);
+		// Empty operations aren't recorded
+		REQUIRE_FALSE(itemlist.process_op(OP_UNDOMARKREAD));
+
+		REQUIRE(itemlist.process_op(OP_LAST_UNREAD));
+		REQUIRE(itemlist.get_guid() == "guid-10");
+		REQUIRE(itemlist.process_op(OP_MARKBELOWREAD));
+		REQUIRE(unread_guids().size() == 9);
+	}
+
+	SECTION("The order in which the articles are shown is followed") {
+		cfg.set_configvalue("article-sort-order", "title-desc");
+		feed->sort(cfg.get_article_sort_strategy());
+		itemlist.set_feed(feed);
+		std::vector<std::string> op_args = {"30"};
+		REQUIRE(itemlist.process_op(OP_GOTO_PERCENT, true, &op_args));
+		REQUIRE(itemlist.get_guid() == "guid-7");
+
+		REQUIRE(itemlist.process_op(OP_MARKABOVEREAD));
+		REQUIRE(unread_guids() == std::vector<std::string>({
+			"guid-0", "guid-1", "guid-3", "guid-4", "guid-5", "guid-6",
+			"guid-7"}));
+	}
+
+	SECTION("Articles hidden by the filter aren't marked") {
+		itemlist.set_feed(feed);
+		std::vector<std::string> filter_args = {"title != \"b\""};
+		REQUIRE(itemlist.process_op(OP_SETFILTER, true, &filter_args));
+		itemlist.prepare();
+		REQUIRE(itemlist.process_op(OP_LAST_UNREAD));
+		REQUIRE(itemlist.get_guid() == "guid-10");
+
+		REQUIRE(itemlist.process_op(OP_MARKABOVEREAD));
+		REQUIRE(unread_guids() == std::vector<std::string>({
+			"guid-1", "guid-10"}));
+	}
+}
@@ -298,6 +298,158 @@ TEST_CASE("to_plain_text() does not escape '<' and '>' in header and body",
 	REQUIRE(result == expected);
 }
 
+TEST_CASE("to_plain_text() shows the article's note on a single line",
+	"[item_renderer]")
+{
+	TestHelpers::EnvVar tzEnv("TZ");
+	tzEnv.set("UTC");
+
+	ConfigContainer cfg;
+
+	Cache rsscache(":memory:", &cfg);
+
+	std::shared_ptr<RssItem> item;
+	std::shared_ptr<RssFeed> feed;
+	std::tie(item, feed) = create_test_item(&rsscache);
+
+	item->set_note("Quote this\nin the weekly report");
+
+	const auto result = item_renderer::to_plain_text(cfg, item);
+	REQUIRE(result.find("Flags: " + ITEM_FLAGS_RENDERED + "\n"
+			"Note: Quote this in the weekly report\n") != std::string::npos);
+}
+
+TEST_CASE("to_preview_text() renders the article without wrapping it",
+	"[item_renderer]")
+{
+	ConfigContainer cfg;
+	cfg.set_configvalue("text-width", "20");
+
+	Cache rsscache(":memory:", &cfg);
+
+	std::shared_ptr<RssItem> item;
+	std::shared_ptr<RssFeed> feed;
+	std::tie(item, feed) = create_test_item(&rsscache);
+
+	const auto paragraph = std::string(
+			"This paragraph is much longer than twenty characters, "
+			"but the preview pane wraps it by itself.");
+	item->set_description("<p>" + paragraph + "</p>");
+
+	const auto result = item_renderer::to_preview_text(cfg, item);
+
+	REQUIRE(result.find("Title: " + ITEM_TITLE + "\n") != std::string::npos);
+	REQUIRE(result.find(paragraph + "\n") != std::string::npos);
+	REQUIRE(result.find("<>") == std::string::npos);
+}
+
+TEST_CASE("to_piped_text() and to_piped_html() start with a fixed header block",
+	"[item_renderer]")
+{
+	TestHelpers::EnvVar tzEnv("TZ");
+	tzEnv.set("UTC");
+
+	ConfigContainer cfg;
+	cfg.set_configvalue("text-width", "80");
+
+	Cache rsscache(":memory:", &cfg);
+
+	std::shared_ptr<RssItem> item;
+	std::shared_ptr<RssFeed> feed;
+	std::tie(item, feed) = create_test_item(&rsscache);
+
+	const auto description = ITEM_DESCRIPTON +
+		"<p>See also <a href='https://example.com'>this site</a>.</p>";
+	item->set_description(description);
+
+	const auto header = std::string() +
+		"Title: " + ITEM_TITLE + '\n' +
+		"Author: " + ITEM_AUTHOR + '\n' +
+		"Date: Sun, 30 Sep 2018 19:34:25 +0000\n" +
+		"Link: " + ITEM_LINK + '\n' +
+		"\n";
+
+	SECTION("Rendered text") {
+		const auto expected = header +
+			ITEM_DESCRIPTON_RENDERED + '\n' +
+			"\n" +
+			"See also this site[1].\n" +
+			"\n" +
+			"Links:\n" +
+			"[1]: https://example.com/ (link)\n";
+
+		REQUIRE(item_renderer::to_piped_text(cfg, item) == expected);
+	}
+
+	SECTION("Raw HTML") {
+		REQUIRE(item_renderer::to_piped_html(item) == header + description + '\n');
+	}
+
+	SECTION("Empty fields keep their lines") {
+		item->set_author("");
+		item->set_link("");
+		item->set_description("");
+
+		const auto expected = std::string() +
+			"Title: " + ITEM_TITLE + '\n' +
+			"Author:\n" +
+			"Date: Sun, 30 Sep 2018 19:34:25 +0000\n" +
+			"Link:\n" +
+			"\n";
+
+		REQUIRE(item_renderer::to_piped_text(cfg, item) == expected);
+		REQUIRE(item_renderer::to_piped_html(item) == expected);
+	}
+
+	SECTION("Feed, flags, note and enclosure aren't part of the header") {
+		item->set_enclosure_url(ITEM_ENCLOSURE_URL);
+		item->set_note("A note");
+
+		const auto result = item_renderer::to_piped_text(cfg, item);
+		REQUIRE(result.substr(0, header.size()) == header);
+		REQUIRE(result.find(FEED_TITLE) == std::string::npos);
+		REQUIRE(result.find(ITEM_ENCLOSURE_URL) == std::string::npos);
+		REQUIRE(result.find("A note") == std::string::npos);
+	}
+}
+
+TEST_CASE("to_piped_text() doesn't contain style tags if highlights are "
+	"configured",
+	"[item_renderer]")
+{
+	ConfigContainer cfg;
+	RegexManager rxman;
+	rxman.handle_action("highlight", {"article", "world", "red"});
+
+	Cache rsscache(":memory:", &cfg);
+
+	std::shared_ptr<RssItem> item;
+	std::shared_ptr<RssFeed> feed;
+	std::tie(item, feed) = create_test_item(&rsscache);
+	item->set_description("<p>Hello, <b>world</b>!</p>");
+
+	std::vector<LinkPair> links;
+	const auto list = item_renderer::to_stfl_list(cfg, item, 80, 80, &rxman,
+			"article", links);
+	REQUIRE(list.first.find("</>") != std::string::npos);
+
+	const auto result = item_renderer::to_piped_text(cfg, item);
+	REQUIRE(result.find("Hello, world!\n") != std::string::npos);
+	REQUIRE(result.find('<') == std::string::npos);
+}
+
+TEST_CASE("split_pipe_command() splits off the `--raw-html` flag",
+	"[item_renderer]")
+{
+	bool raw_html = true;
+	REQUIRE(item_renderer::split_pipe_command("less", raw_html) == "less");
+	REQUIRE_FALSE(raw_html);
+
+	REQUIRE(item_renderer::split_pipe_command("--raw-html w3m -T text/html",
+			raw_html) == "w3m -T text/html");
+	REQUIRE(raw_html);
+}
+
 TEST_CASE("Empty fields are not rendered", "[item_renderer]")
 {
 	TestHelpers::EnvVar tzEnv("TZ");
@@ -5,7 +5,12 @@
 
 #include "3rd-party/catch.hpp"
 
+#include "configcontainer.h"
+#include "configexception.h"
 #include "confighandlerexception.h"
+#include "strprintf.h"
+#include "test-helpers/exceptionwithmsg.h"
+#include "utils.h"
 
 using namespace newsboat;
 
@@ -207,6 +212,27 @@ TEST_CASE("handle_action()", "[KeyMap]")
 			ConfigHandlerException);
 	}
 
+	SECTION("binds in each of a comma-separated list of dialogs") {
+		REQUIRE_NOTHROW(k.handle_action("bind-key",
+				"X reload feedlist,articlelist"));
+		REQUIRE(k.get_operation("X", "feedlist") == OP_RELOAD);
+		REQUIRE(k.get_operation("X", "articlelist") == OP_RELOAD);
+		REQUIRE(k.get_operation("X", "article") == OP_NIL);
+	}
+
+	SECTION("rejects unknown dialogs, sequences and extra parameters") {
+		REQUIRE_THROWS_AS(k.handle_action("bind-key",
+				"X reload feedlist,artcilelist"),
+			ConfigHandlerException);
+		REQUIRE_THROWS_AS(k.handle_action("bind-key",
+				"X \"reload; open\" feedlist"),
+			ConfigHandlerException);
+		REQUIRE_THROWS_AS(k.handle_action("bind-key",
+				"X reload feedlist garbage"),
+			ConfigHandlerException);
+		REQUIRE(k.get_operation("X", "feedlist") == OP_NIL);
+	}
+
 	SECTION("allows binding multiple keys to OP_SK_xxx operations") {
 		REQUIRE_NOTHROW(k.handle_action("bind-key", "u pageup"));
 		REQUIRE_NOTHROW(k.handle_action("bind-key", "p pageup"));
@@ -222,6 +248,59 @@ TEST_CASE("handle_action()", "[KeyMap]")
 	}
 }
 
+TEST_CASE("bind-key and unbind-key accept foreign spellings of key names",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("Ctrl combinations") {
+		REQUIRE_NOTHROW(k.handle_action("bind-key", "C-r reload-all"));
+		REQUIRE(k.get_operation("^R", "feedlist") == OP_RELOADALL);
+
+		REQUIRE_NOTHROW(k.handle_action("bind-key", "^e open feedlist"));
+		REQUIRE(k.get_operation("^E", "feedlist") == OP_OPEN);
+	}
+
+	SECTION("named keys in a different case or in angle brackets") {
+		REQUIRE_NOTHROW(k.handle_action("bind-key", "Enter quit"));
+		REQUIRE(k.get_operation("ENTER", "feedlist") == OP_QUIT);
+
+		REQUIRE_NOTHROW(k.handle_action("bind-key", "<Space> open"));
+		REQUIRE(k.get_operation("SPACE", "feedlist") == OP_OPEN);
+	}
+
+	SECTION("unbind-key canonicalizes, too") {
+		REQUIRE_NOTHROW(k.handle_action("unbind-key", "ppage"));
+		REQUIRE(k.get_operation("PPAGE", "feedlist") == OP_NIL);
+	}
+
+	SECTION("unknown names result in an error with suggestions") {
+		try {
+			k.handle_action("bind-key", "ENTR open");
+			FAIL("no exception thrown");
+		} catch (const ConfigHandlerException& e) {
+			REQUIRE(std::string(e.what()) ==
+				"`ENTR' is not a valid key name. Did you mean: ENTER, END?");
+		}
+
+		REQUIRE_THROWS_AS(k.handle_action("unbind-key", "xyzzyplugh"),
+			ConfigHandlerException);
+	}
+
+	SECTION("dump_config() writes canonical names") {
+		k.unset_all_keys("all");
+		k.handle_action("bind-key", "C-r reload-all feedlist");
+		k.handle_action("bind-key", "<PageDown> pagedown feedlist");
+
+		std::vector<std::string> dumpOutput;
+		k.dump_config(dumpOutput);
+
+		REQUIRE(dumpOutput.size() == 2);
+		REQUIRE(dumpOutput[0] == R"(bind-key "NPAGE" pagedown feedlist)");
+		REQUIRE(dumpOutput[1] == R"(bind-key "^R" reload-all feedlist)");
+	}
+}
+
 TEST_CASE("verify get_keymap_descriptions() behavior",
 	"[KeyMap]")
 {
@@ -519,3 +598,680 @@ TEST_CASE("Regression test for macro configuration semicolon handling",
 		REQUIRE(macros[1].args == std::vector<std::string>({}));
 	}
 }
+
+TEST_CASE("Commandline and macros are tokenized the same way", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	const std::vector<std::string> inputs = {
+		R"(set browser lynx)",
+		R"(set browser "firefox --new-tab %u")",
+		R"(set browser "a;b")",
+		R"(set browser "echo \"%u\"")",
+		R"(set datetime-format "%d\t%b")",
+		R"(set browser "unterminated)",
+		R"(set browser "")",
+		R"(  open-in-browser  )",
+	};
+
+	for (const auto& input : inputs) {
+		DYNAMIC_SECTION(input) {
+			const auto macro = k.parse_operation_sequence(input);
+			REQUIRE(macro.size() == 1);
+
+			std::string error;
+			const auto cmdline = KeyMap::tokenize_cmdline(input, error);
+			REQUIRE(cmdline.has_value());
+			REQUIRE(error.empty());
+
+			REQUIRE(k.get_opcode(cmdline.value()[0]) == macro[0].op);
+			const std::vector<std::string> cmdline_args(
+				cmdline.value().begin() + 1, cmdline.value().end());
+			REQUIRE(cmdline_args == macro[0].args);
+		}
+	}
+}
+
+TEST_CASE("tokenize_cmdline() reports the column at which parsing failed",
+	"[KeyMap]")
+{
+	SECTION("Empty line produces no tokens") {
+		std::string error;
+		const auto tokens = KeyMap::tokenize_cmdline("   ", error);
+		REQUIRE(tokens.has_value());
+		REQUIRE(tokens.value().empty());
+	}
+
+	SECTION("Equals sign separates name and value in `set'") {
+		std::string error;
+		const auto tokens = KeyMap::tokenize_cmdline(
+				R"(set browser="firefox --new-tab")", error);
+		REQUIRE(tokens.has_value());
+		REQUIRE(tokens.value() == std::vector<std::string>({"set", "browser", "firefox --new-tab"}));
+	}
+
+	SECTION("Only one command is allowed") {
+		std::string error;
+		const auto tokens = KeyMap::tokenize_cmdline("open; quit", error);
+		REQUIRE_FALSE(tokens.has_value());
+		REQUIRE(error.find("column 5") != std::string::npos);
+		REQUIRE(error.find("open>>;<< quit") != std::string::npos);
+	}
+}
+
+TEST_CASE("get_macro_cmdline() returns a `run' command that runs the macro",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.handle_action("macro",
+		R"(b set browser "firefox --new-tab %u; echo \"hi\"" ; open-in-browser ; set browser "")");
+
+	std::string error;
+	const auto cmdline = k.get_macro_cmdline("b", error);
+	REQUIRE(cmdline.has_value());
+	REQUIRE(cmdline.value() == R"(run set browser "firefox --new-tab %u; echo \"hi\""; open-in-browser; set browser "")");
+
+	SECTION("Parsing the commandline gives back the macro") {
+		const auto commands = k.parse_run_cmdline(cmdline.value());
+		REQUIRE(commands.has_value());
+
+		const auto macro = k.get_macro("b");
+		REQUIRE(commands->size() == macro.size());
+		for (std::size_t i = 0; i < macro.size(); ++i) {
+			REQUIRE(commands->at(i).op == macro[i].op);
+			REQUIRE(commands->at(i).args == macro[i].args);
+		}
+	}
+}
+
+TEST_CASE("get_macro_cmdline() lists defined macros if there's no macro "
+	"bound to the key",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	std::string error;
+
+	SECTION("No macros defined") {
+		REQUIRE_FALSE(k.get_macro_cmdline("x", error).has_value());
+		REQUIRE(error == "No macro bound to `x'; no macros are defined");
+	}
+
+	SECTION("Some macros defined") {
+		k.handle_action("macro", "b open-in-browser");
+		k.handle_action("macro", "a open ; quit");
+		REQUIRE_FALSE(k.get_macro_cmdline("x", error).has_value());
+		REQUIRE(error == "No macro bound to `x'; defined macros: a, b");
+	}
+}
+
+TEST_CASE("parse_run_cmdline() parses an edited operation sequence",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("Operations and their arguments are returned") {
+		const auto commands = k.parse_run_cmdline(
+				R"(run set browser "lynx %u"; open-in-browser ; reload)");
+		REQUIRE(commands.has_value());
+		REQUIRE(commands->size() == 3);
+		REQUIRE(commands->at(0).op == OP_INT_SET);
+		REQUIRE(commands->at(0).args == std::vector<std::string>({"browser", "lynx %u"}));
+		REQUIRE(commands->at(1).op == OP_OPENINBROWSER);
+		REQUIRE(commands->at(1).args.empty());
+		REQUIRE(commands->at(2).op == OP_RELOAD);
+	}
+
+	SECTION("Quoted arguments can contain spaces") {
+		const auto commands = k.parse_run_cmdline(
+				R"(run rename-tag "local news" news)");
+		REQUIRE(commands.has_value());
+		REQUIRE(commands->size() == 1);
+		REQUIRE(commands->at(0).op == OP_RENAME_TAG);
+		REQUIRE(commands->at(0).args == std::vector<std::string>({"local news", "news"}));
+	}
+
+	SECTION("Other commands are left alone") {
+		REQUIRE_FALSE(k.parse_run_cmdline("set browser lynx").has_value());
+		REQUIRE_FALSE(k.parse_run_cmdline("runner").has_value());
+	}
+
+	SECTION("Invalid operations result in exception") {
+		REQUIRE_THROWS_AS(k.parse_run_cmdline("run open ; not-an-operation"),
+			ConfigHandlerException);
+	}
+
+	SECTION("Nothing is remembered") {
+		REQUIRE(k.parse_run_cmdline("run open").has_value());
+		std::vector<std::string> dump;
+		k.dump_config(dump);
+		for (const auto& line : dump) {
+			REQUIRE(line.find("macro") != 0);
+		}
+	}
+}
+
+TEST_CASE("macro-with-args records the number of arguments a macro takes",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	k.handle_action("macro", "a save %1");
+	k.handle_action("macro-with-args", R"(b set browser "%2 %1"; open-in-browser)");
+	k.handle_action("macro-with-args", "c save %0");
+
+	REQUIRE(k.get_macro_parameter_count("a") == 0);
+	REQUIRE(k.get_macro_parameter_count("b") == 2);
+	REQUIRE(k.get_macro_parameter_count("c") == 1);
+	REQUIRE(k.get_macro_parameter_count("x") == 0);
+
+	SECTION("Plain macros keep placeholders as is") {
+		const auto macro = k.get_macro("a");
+		REQUIRE(macro.size() == 1);
+		REQUIRE(macro[0].args == std::vector<std::string>({"%1"}));
+	}
+
+	SECTION("redefining a macro with `macro' drops its arguments") {
+		k.handle_action("macro", "b open");
+		REQUIRE(k.get_macro_parameter_count("b") == 0);
+	}
+
+	SECTION("dump_config() preserves the kind of macro") {
+		std::vector<std::string> dumpOutput;
+		k.dump_config(dumpOutput);
+		REQUIRE(dumpOutput.size() == 3);
+		REQUIRE(dumpOutput[0] == R"(macro a save "%1")");
+		REQUIRE(dumpOutput[1] ==
+			R"(macro-with-args b set "browser" "%2 %1" ; open-in-browser)");
+		REQUIRE(dumpOutput[2] == R"(macro-with-args c save "%0")");
+	}
+}
+
+TEST_CASE("Macros can be described after `--'", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.handle_action("macro",
+		R"(p set browser "foo -- bar"; open-in-browser -- "open; then \"quit\"")");
+	k.handle_action("macro-with-args", R"(s save "%1" -- "save as")");
+	k.handle_action("macro", "q quit");
+
+	const auto macro = k.get_macro("p");
+	REQUIRE(macro.size() == 2);
+	REQUIRE(macro[0].args == std::vector<std::string>({"browser", "foo -- bar"}));
+	REQUIRE(macro[1].op == OP_OPENINBROWSER);
+	REQUIRE(macro[1].args.empty());
+
+	SECTION("the help dialog lists macros with their descriptions") {
+		const auto descriptions = k.get_keymap_descriptions("articlelist");
+		std::map<std::string, KeyMapDesc> macros;
+		for (const auto& description : descriptions) {
+			if (description.category == OpCategory::MACROS
+				&& description.cmd != "macro-prefix") {
+				macros[description.key] = description;
+			}
+		}
+		REQUIRE(macros.size() == 3);
+		REQUIRE(macros[",p"].cmd ==
+			R"(set browser "foo -- bar"; open-in-browser)");
+		REQUIRE(macros[",p"].desc == R"(open; then "quit")");
+		REQUIRE(macros[",s"].desc == "save as");
+		REQUIRE(macros[",q"].cmd == "quit");
+		REQUIRE(macros[",q"].desc == "");
+	}
+
+	SECTION("dump_config() keeps the descriptions") {
+		std::vector<std::string> dumpOutput;
+		k.unset_all_keys("all");
+		k.dump_config(dumpOutput);
+		REQUIRE(dumpOutput.size() == 3);
+		REQUIRE(dumpOutput[0] ==
+			R"(macro p set "browser" "foo -- bar" ; open-in-browser -- "open; then \"quit\"")");
+		REQUIRE(dumpOutput[1] == R"(macro q quit)");
+		REQUIRE(dumpOutput[2] == R"(macro-with-args s save "%1" -- "save as")");
+	}
+
+	SECTION("`--' without a quoted description is an error") {
+		using TestHelpers::ExceptionWithMsg;
+
+		REQUIRE_THROWS_MATCHES(k.handle_action("macro", "x open --"),
+			ConfigHandlerException,
+			ExceptionWithMsg<ConfigHandlerException>(
+				"invalid macro at column 7: "
+				"`--' must be followed by a quoted description"));
+	}
+}
+
+TEST_CASE("macro-with-args rejects gaps between parameters", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	REQUIRE_THROWS_AS(k.handle_action("macro-with-args", "a set a %1; set b %3"),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(k.handle_action("macro-with-args", "a invalid-op %1"),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(k.handle_action("macro-with-args", "a"),
+		ConfigHandlerException);
+	REQUIRE(k.get_macro("a").empty());
+}
+
+TEST_CASE("get_macro() substitutes arguments into a parameterized macro",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.handle_action("macro-with-args", R"(b set browser "%2 %1"; save %1; set a %0)");
+
+	const std::vector<std::string> arguments = {
+		R"(file with "quotes"; spaces)",
+		"lynx",
+	};
+	const auto macro = k.get_macro("b", arguments);
+	REQUIRE(macro.size() == 3);
+	REQUIRE(macro[0].op == OP_INT_SET);
+	REQUIRE(macro[0].args == std::vector<std::string>({"browser", R"(lynx file with "quotes"; spaces)"}));
+	REQUIRE(macro[1].op == OP_SAVE);
+	REQUIRE(macro[1].args == std::vector<std::string>({arguments[0]}));
+	REQUIRE(macro[2].op == OP_INT_SET);
+	REQUIRE(macro[2].args == std::vector<std::string>({"a", R"(file with "quotes"; spaces lynx)"}));
+}
+
+TEST_CASE("next_macro_argument_prompt() asks for missing arguments in order",
+	"[KeyMap]")
+{
+	REQUIRE(KeyMap::next_macro_argument_prompt(2, 0) == std::string("Argument %1 of 2: "));
+	REQUIRE(KeyMap::next_macro_argument_prompt(2, 1) == std::string("Argument %2 of 2: "));
+	REQUIRE_FALSE(KeyMap::next_macro_argument_prompt(2, 2).has_value());
+}
+
+TEST_CASE("Operations connected with `&&` and `||` run depending on the "
+	"previous one", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.handle_action("macro",
+		R"(o open-in-browser && toggle-article-read "read" || quit; reload)");
+
+	const auto macro = k.get_macro("o");
+	REQUIRE(macro.size() == 4);
+	REQUIRE(macro[0].op == OP_OPENINBROWSER);
+	REQUIRE(macro[0].condition == MacroCondition::ALWAYS);
+	REQUIRE(macro[1].op == OP_TOGGLEITEMREAD);
+	REQUIRE(macro[1].args == std::vector<std::string>({"read"}));
+	REQUIRE(macro[1].condition == MacroCondition::IF_SUCCEEDED);
+	REQUIRE(macro[2].op == OP_QUIT);
+	REQUIRE(macro[2].condition == MacroCondition::IF_FAILED);
+	REQUIRE(macro[3].op == OP_RELOAD);
+	REQUIRE(macro[3].condition == MacroCondition::ALWAYS);
+
+	std::string error;
+	REQUIRE(k.get_macro_cmdline("o", error) ==
+		std::string("run open-in-browser && toggle-article-read read || quit; reload"));
+
+	const auto substituted = k.get_macro("o", {});
+	REQUIRE(substituted.size() == 4);
+	REQUIRE(substituted[2].condition == MacroCondition::IF_FAILED);
+}
+
+TEST_CASE("goto-percent in macros must have a number from 0 to 100",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("A valid number is accepted") {
+		k.handle_action("macro", "h goto-percent 50 ; first-unread");
+		const auto macro = k.get_macro("h");
+		REQUIRE(macro.size() == 2);
+		REQUIRE(macro[0].op == OP_GOTO_PERCENT);
+		REQUIRE(macro[0].args == std::vector<std::string>({"50"}));
+		REQUIRE(macro[1].op == OP_FIRST_UNREAD);
+	}
+
+	SECTION("A missing or wrong argument is an error") {
+		REQUIRE_THROWS_AS(k.handle_action("macro", "h goto-percent"),
+			ConfigHandlerException);
+		REQUIRE_THROWS_AS(k.handle_action("macro", "h goto-percent 150"),
+			ConfigHandlerException);
+		REQUIRE_THROWS_AS(k.handle_action("macro", "h goto-percent 1 2"),
+			ConfigHandlerException);
+		REQUIRE_THROWS_AS(k.handle_action("run-on-startup", "goto-percent x"),
+			ConfigHandlerException);
+		REQUIRE_THROWS_AS(k.parse_run_cmdline("run goto-percent 101"),
+			ConfigHandlerException);
+	}
+
+	SECTION("The argument of a parameterized macro is checked when it runs") {
+		k.handle_action("macro-with-args", "h goto-percent %1");
+		const auto macro = k.get_macro("h", {"75"});
+		REQUIRE(macro.size() == 1);
+		REQUIRE(macro[0].args == std::vector<std::string>({"75"}));
+	}
+}
+
+TEST_CASE("A connector must be followed by an operation", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	REQUIRE_THROWS_AS(k.handle_action("macro", "o open-in-browser &&"),
+		ConfigHandlerException);
+}
+
+TEST_CASE("Broken operation sequences are reported with the column at which "
+	"parsing failed",
+	"[KeyMap]")
+{
+	using TestHelpers::ExceptionWithMsg;
+
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("macro") {
+		REQUIRE_THROWS_MATCHES(
+			k.handle_action("macro", R"(x set browser "ÄÖÜ"; set x "a\)"),
+			ConfigHandlerException,
+			ExceptionWithMsg<ConfigHandlerException>(
+				"invalid macro at column 27: unterminated quoted string"));
+	}
+
+	SECTION("macro-with-args") {
+		REQUIRE_THROWS_MATCHES(
+			k.handle_action("macro-with-args", "x open %1 ||"),
+			ConfigHandlerException,
+			ExceptionWithMsg<ConfigHandlerException>(
+				"invalid macro at column 10: `||' must be followed by an "
+				"operation"));
+	}
+
+	SECTION("run-on-startup") {
+		REQUIRE_THROWS_MATCHES(
+			k.handle_action("run-on-startup", R"(open fire"fox")"),
+			ConfigHandlerException,
+			ExceptionWithMsg<ConfigHandlerException>(
+				"invalid operation sequence at column 10: unexpected "
+				"`\"fox\"'"));
+	}
+}
+
+TEST_CASE("Quotes in macros and run-on-startup have to be closed, unlike in "
+	"parse_operation_sequence()",
+	"[KeyMap]")
+{
+	using TestHelpers::ExceptionWithMsg;
+
+	KeyMap k(KM_NEWSBOAT);
+
+	REQUIRE_THROWS_MATCHES(
+		k.handle_action("macro", R"(x set browser "lynx; open-in-browser)"),
+		ConfigHandlerException,
+		ExceptionWithMsg<ConfigHandlerException>(
+			"invalid macro at column 14: quote isn't closed: "
+			"\"lynx; open-in-browser"));
+	REQUIRE_THROWS_MATCHES(
+		k.handle_action("run-on-startup", "set browser 'lynx"),
+		ConfigHandlerException,
+		ExceptionWithMsg<ConfigHandlerException>(
+			"invalid operation sequence at column 13: quote isn't closed: "
+			"'lynx"));
+
+	const auto lenient = k.parse_operation_sequence(R"(set browser "lynx)");
+	REQUIRE(lenient.size() == 1);
+	REQUIRE(lenient[0].args == std::vector<std::string>({"browser", "lynx"}));
+}
+
+TEST_CASE("Bindings and macros remember the file and line that defined them, "
+	"through nested includes",
+	"[KeyMap]")
+{
+	const std::string dir = utils::getcwd() + "/data/key-origins/";
+	ConfigParser cfgparser;
+	KeyMap k(KM_NEWSBOAT);
+	cfgparser.register_handler("bind-key", k);
+	cfgparser.register_handler("macro", k);
+	cfgparser.parse_file("data/key-origins/config");
+
+	REQUIRE(k.describe_key("r", "feedlist") ==
+		"`r' in feedlist: reload-all, bound at " + dir + "conf.d/10-keys.conf:1");
+	REQUIRE(k.describe_key("o", "articlelist") ==
+		"`o' in articlelist: open, bound at " + dir + "config:2; "
+		"macro defined at " + dir + "config:3");
+
+	std::vector<std::string> dump;
+	k.dump_config(dump);
+	REQUIRE(std::count(dump.begin(), dump.end(),
+			R"(bind-key "r" reload-all feedlist # )" + dir + "conf.d/10-keys.conf:1") == 1);
+	REQUIRE(std::count(dump.begin(), dump.end(),
+			"macro x reload # " + dir + "config:3") == 1);
+}
+
+TEST_CASE("A binding that replaces one from another file is reported with "
+	"both locations",
+	"[KeyMap]")
+{
+	const std::string dir = utils::getcwd() + "/data/key-origins/";
+	ConfigParser cfgparser;
+	KeyMap k(KM_NEWSBOAT);
+	cfgparser.register_handler("bind-key", k);
+	cfgparser.register_handler("macro", k);
+	cfgparser.parse_file("data/key-origins/config");
+
+	REQUIRE(k.get_warnings() == std::vector<std::string>({
+		dir + "config:2: binding `o' to open overrides binding from " +
+		dir + "conf.d/20-more.conf:1 (open-in-browser in articlelist)",
+		dir + "config:3: macro `x' overrides macro from " +
+		dir + "conf.d/20-more.conf:2",
+	}));
+
+	SECTION("Reading the same config again is not a conflict") {
+		cfgparser.parse_file("data/key-origins/config");
+		REQUIRE(k.get_warnings().size() == 2);
+	}
+}
+
+TEST_CASE("Bindings and macros that will never trigger are reported with "
+	"their location",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.set_origin("config", 12);
+
+	SECTION("Operation that doesn't work in the given context") {
+		k.handle_action("bind-key", "o open-in-browser filebrowser");
+		REQUIRE(k.get_warnings() == std::vector<std::string>({
+			"config:12: `open-in-browser' bound to `o' in context "
+			"`filebrowser' will never trigger",
+		}));
+	}
+
+	SECTION("Key bound everywhere only has to work somewhere") {
+		k.handle_action("bind-key", "o open-in-browser");
+		k.handle_action("bind-key", "d pb-download");
+		REQUIRE(k.get_warnings().empty());
+	}
+
+	SECTION("Macro step that works in no context of newsboat") {
+		k.handle_action("macro", "x open ; pb-download");
+		REQUIRE(k.get_warnings() == std::vector<std::string>({
+			"config:12: `pb-download' in macro `x' will never run: it works "
+			"in no context",
+		}));
+		// The macro is kept
+		REQUIRE(k.get_macro("x").size() == 2);
+	}
+}
+
+TEST_CASE("Default bindings never trigger context warnings", "[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	k.set_origin("config", 1);
+
+	const std::vector<std::string> contexts = {"feedlist", "filebrowser",
+		"help", "articlelist", "article", "tagselection", "filterselection",
+		"urlview", "dialogs", "dirbrowser"
+	};
+	for (const auto& context : contexts) {
+		for (const auto& desc : k.get_keymap_descriptions(context)) {
+			if (desc.key.empty()) {
+				continue;
+			}
+			k.handle_action("bind-key", strprintf::fmt("\"%s\" %s %s",
+					desc.key, desc.cmd, context));
+		}
+	}
+
+	REQUIRE(k.get_warnings().empty());
+}
+
+TEST_CASE("describe_key() tells the operation, the context and where the key "
+	"was bound",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("Default binding") {
+		REQUIRE(k.describe_key("r", "feedlist") ==
+			"`r' in feedlist: reload, default binding");
+	}
+
+	SECTION("Binding from a config file") {
+		k.set_origin("/home/user/.newsboat/config", 7);
+		k.handle_action("bind-key", "^R reload-all articlelist");
+		REQUIRE(k.describe_key("^R", "articlelist") ==
+			"`^R' in articlelist: reload-all, bound at /home/user/.newsboat/config:7");
+	}
+
+	SECTION("Unbound key") {
+		k.handle_action("unbind-key", "r feedlist");
+		REQUIRE(k.describe_key("r", "feedlist") == "`r' in feedlist: not bound");
+	}
+
+	SECTION("Invalid key name") {
+		REQUIRE_THROWS_AS(k.describe_key("", "feedlist"), ConfigHandlerException);
+	}
+}
+
+TEST_CASE("describe_operation() tells what the operation does, where it "
+	"works and which keys it's bound to",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+
+	SECTION("Operation with default bindings") {
+		REQUIRE(k.describe_operation("reload") ==
+			"reload: Reload currently selected feed\n"
+			"  works in: articlelist, feedlist\n"
+			"  bound to: r in articlelist, feedlist");
+	}
+
+	SECTION("Bindings from the config are shown, and arguments too") {
+		k.handle_action("bind-key", "% goto-percent feedlist");
+		REQUIRE(k.describe_operation("goto-percent") ==
+			"goto-percent <percent>: Go to a position given in percent of the list\n"
+			"  works in: articlelist, feedlist\n"
+			"  bound to: % in feedlist");
+	}
+
+	SECTION("Unbound operation") {
+		k.handle_action("unbind-key", "r");
+		REQUIRE(k.describe_operation("reload").value().find(
+				"  not bound to any key") != std::string::npos);
+	}
+
+	SECTION("Unknown and internal operations") {
+		REQUIRE_FALSE(k.describe_operation("reload-everything").has_value());
+		REQUIRE_FALSE(k.describe_operation("internal-goto-url").has_value());
+	}
+}
+
+TEST_CASE("get_operation_names() lists the operations that can be bound",
+	"[KeyMap]")
+{
+	KeyMap k(KM_NEWSBOAT);
+	const auto names = k.get_operation_names();
+
+	REQUIRE(std::find(names.begin(), names.end(), "describe") != names.end());
+	REQUIRE(std::find(names.begin(), names.end(), "reload") != names.end());
+	REQUIRE(std::find(names.begin(), names.end(),
+			"internal-goto-url") == names.end());
+}
+
+TEST_CASE("Enter and Space do what the default-action options say",
+	"[KeyMap]")
+{
+	using TestHelpers::ExceptionWithMsg;
+
+	KeyMap k(KM_NEWSBOAT);
+	ConfigContainer cfg;
+
+	SECTION("By default, they do what they did before") {
+		k.apply_default_actions(cfg);
+		REQUIRE(k.get_operation("ENTER", "feedlist") == OP_OPEN);
+		REQUIRE(k.get_operation("ENTER", "articlelist") == OP_OPEN);
+		REQUIRE(k.get_operation("ENTER", "urlview") == OP_OPEN);
+		REQUIRE(k.get_operation("SPACE", "article") == OP_SK_PGDOWN);
+		REQUIRE(k.get_operation("SPACE", "feedlist") == OP_NIL);
+		REQUIRE(k.get_keys(OP_OPEN, "feedlist") ==
+			std::vector<std::string>({"ENTER"}));
+	}
+
+	SECTION("Each context has its own default actions") {
+		cfg.set_configvalue("articlelist-primary-action", "open-in-browser");
+		cfg.set_configvalue("articlelist-secondary-action",
+			"toggle-article-read");
+		k.apply_default_actions(cfg);
+
+		REQUIRE(k.get_operation("ENTER", "articlelist") == OP_OPENINBROWSER);
+		REQUIRE(k.get_operation("SPACE", "articlelist") == OP_TOGGLEITEMREAD);
+		REQUIRE(k.get_operation("ENTER", "feedlist") == OP_OPEN);
+		REQUIRE(k.get_operation("SPACE", "article") == OP_SK_PGDOWN);
+		REQUIRE(k.get_keys(OP_OPENINBROWSER, "articlelist") ==
+			std::vector<std::string>({"ENTER", "o"}));
+
+		SECTION("An empty value goes back to the built-in operation") {
+			cfg.set_configvalue("articlelist-primary-action", "");
+			k.apply_default_actions(cfg);
+			REQUIRE(k.get_operation("ENTER", "articlelist") == OP_OPEN);
+		}
+	}
+
+	SECTION("Operations that don't work in the context are rejected") {
+		cfg.set_configvalue("feedlist-secondary-action", "toggle-article-read");
+		cfg.set_configvalue("articlelist-primary-action", "open-in-browser");
+		REQUIRE_THROWS_MATCHES(k.apply_default_actions(cfg),
+			ConfigException,
+			ExceptionWithMsg<ConfigException>(
+				"`feedlist-secondary-action' can't be set to "
+				"`toggle-article-read': it doesn't work in context `feedlist'"));
+
+		// The valid option still applies
+		REQUIRE(k.get_operation("SPACE", "feedlist") == OP_NIL);
+		REQUIRE(k.get_operation("ENTER", "articlelist") == OP_OPENINBROWSER);
+	}
+
+	SECTION("Macros can invoke the default actions") {
+		cfg.set_configvalue("articlelist-primary-action", "open-in-browser");
+		k.apply_default_actions(cfg);
+		k.handle_action("macro",
+			R"(m default-action-primary; toggle-article-read "read")");
+
+		const auto macro = k.get_macro("m");
+		REQUIRE(macro.size() == 2);
+		REQUIRE(macro[0].op == OP_DEFAULT_ACTION_PRIMARY);
+		REQUIRE(k.resolve_default_action(macro[0].op, "articlelist") ==
+			OP_OPENINBROWSER);
+		REQUIRE(k.resolve_default_action(macro[0].op, "feedlist") == OP_OPEN);
+		REQUIRE(k.resolve_default_action(macro[1].op, "articlelist") ==
+			OP_TOGGLEITEMREAD);
+	}
+
+	SECTION("The help dialog lists what the keys stand for") {
+		cfg.set_configvalue("articlelist-primary-action", "open-in-browser");
+		k.apply_default_actions(cfg);
+
+		const auto descriptions = k.get_keymap_descriptions("articlelist");
+		const auto enter = std::find_if(descriptions.begin(), descriptions.end(),
+		[](const KeyMapDesc& x) {
+			return x.key == "ENTER";
+		});
+		REQUIRE(enter != descriptions.end());
+		REQUIRE(enter->cmd == "open-in-browser");
+		REQUIRE(std::none_of(descriptions.begin(), descriptions.end(),
+		[](const KeyMapDesc& x) {
+			return x.cmd == "default-action-primary"
+				|| x.cmd == "default-action-secondary";
+		}));
+	}
+}
@@ -72,3 +72,29 @@ TEST_CASE("format_list() uses regex manager if one is passed",
 
 	REQUIRE(fmt.format_list() == expected);
 }
+
+TEST_CASE("add_lines() adds an item that takes up several rows",
+	"[ListFormatter]")
+{
+	ListFormatter fmt;
+
+	fmt.add_line("one");
+	fmt.add_lines({"two", "  continued"});
+	fmt.add_line("three");
+
+	REQUIRE(fmt.get_lines_count() == 4);
+	REQUIRE(fmt.get_row_counts() == std::vector<std::uint32_t>({1, 2, 1}));
+
+	fmt.set_lines(2, {"three", "  and more"});
+	fmt.set_line(1, "two");
+	REQUIRE(fmt.get_row_counts() == std::vector<std::uint32_t>({1, 1, 2}));
+
+	const std::string expected =
+		"{list"
+		"{listitem text:\"one\"}"
+		"{listitem text:\"two\"}"
+		"{listitem text:\"three\"}"
+		"{listitem text:\"  and more\"}"
+		"}";
+	REQUIRE(fmt.format_list() == expected);
+}
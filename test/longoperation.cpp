@@ -0,0 +1,30 @@
+#include "longoperation.h"
+
+#include "3rd-party/catch.hpp"
+
+using namespace newsboat;
+
+TEST_CASE("status() names the operation that's running", "[LongOperation]")
+{
+	LongOperation op("mark-all-feeds-read");
+
+	const auto status = op.status();
+	REQUIRE(status.find("Running `mark-all-feeds-read'...") !=
+		std::string::npos);
+}
+
+TEST_CASE("finish() doesn't notify about operations that were fast",
+	"[LongOperation]")
+{
+	LongOperation op("purge-deleted");
+
+	REQUIRE_FALSE(op.finish(true, 1));
+}
+
+TEST_CASE("finish() does nothing when called again", "[LongOperation]")
+{
+	LongOperation op("purge-deleted");
+
+	REQUIRE_FALSE(op.finish(true, 0));
+	REQUIRE_FALSE(op.finish(false, 0));
+}
@@ -0,0 +1,94 @@
+#include "markreadpolicy.h"
+
+#include "3rd-party/catch.hpp"
+
+#include "configcontainer.h"
+#include "confighandlerexception.h"
+
+using namespace newsboat;
+
+TEST_CASE("validate() explains what's wrong with a mark-read-on value",
+	"[MarkReadPolicy]")
+{
+	REQUIRE_FALSE(MarkReadPolicy::validate("open").has_value());
+	REQUIRE_FALSE(MarkReadPolicy::validate("dwell:5,scroll-end,next")
+		.has_value());
+	REQUIRE_FALSE(MarkReadPolicy::validate("").has_value());
+
+	const auto error = MarkReadPolicy::validate("open,hover");
+	REQUIRE(error.has_value());
+	REQUIRE(error.value() == "unknown trigger `hover'");
+}
+
+TEST_CASE("mark-read-on is validated when the config is loaded",
+	"[MarkReadPolicy]")
+{
+	ConfigContainer cfg;
+	REQUIRE(cfg.get_configvalue("mark-read-on") == "open");
+
+	REQUIRE_THROWS_AS(cfg.handle_action("mark-read-on", {"dwell"}),
+		ConfigHandlerException);
+	REQUIRE(cfg.get_configvalue("mark-read-on") == "open");
+
+	REQUIRE_NOTHROW(cfg.handle_action("mark-read-on", {"dwell:5,next"}));
+	REQUIRE(cfg.get_configvalue("mark-read-on") == "dwell:5,next");
+}
+
+TEST_CASE("should_mark_read() follows the configured triggers",
+	"[MarkReadPolicy]")
+{
+	MarkReadPolicy policy;
+	MarkReadPolicy::Context context;
+
+	SECTION("by default, articles become read when they're opened") {
+		REQUIRE(policy.should_mark_read(MarkReadPolicy::Trigger::OPEN,
+				context));
+		REQUIRE_FALSE(policy.should_mark_read(MarkReadPolicy::Trigger::NEXT,
+				context));
+		REQUIRE(policy.dwell_ms() == -1);
+	}
+
+	SECTION("invalid values leave the policy as it was") {
+		REQUIRE_FALSE(policy.set("dwell:soon"));
+		REQUIRE(policy.should_mark_read(MarkReadPolicy::Trigger::OPEN,
+				context));
+	}
+
+	SECTION("dwell and scroll-end depend on the context") {
+		REQUIRE(policy.set("dwell:2,scroll-end"));
+		REQUIRE(policy.dwell_ms() == 2000);
+		REQUIRE_FALSE(policy.should_mark_read(MarkReadPolicy::Trigger::OPEN,
+				context));
+
+		context.dwelled_ms = 1999;
+		REQUIRE_FALSE(policy.should_mark_read(
+				MarkReadPolicy::Trigger::DWELL, context));
+		context.dwelled_ms = 2000;
+		REQUIRE(policy.should_mark_read(MarkReadPolicy::Trigger::DWELL,
+				context));
+
+		REQUIRE_FALSE(policy.should_mark_read(
+				MarkReadPolicy::Trigger::SCROLL_END, context));
+		context.at_end = true;
+		REQUIRE(policy.should_mark_read(
+				MarkReadPolicy::Trigger::SCROLL_END, context));
+	}
+}
+
+TEST_CASE("DwellTimer only counts while it's running", "[MarkReadPolicy]")
+{
+	DwellTimer timer;
+	REQUIRE(timer.elapsed_ms() == 0);
+	REQUIRE(timer.time_left_ms() == -1);
+
+	timer.start(60000);
+	REQUIRE(timer.time_left_ms() > 0);
+	REQUIRE(timer.time_left_ms() <= 60000);
+
+	timer.cancel();
+	REQUIRE(timer.elapsed_ms() == 0);
+	REQUIRE(timer.time_left_ms() == -1);
+
+	timer.start(-1);
+	REQUIRE(timer.time_left_ms() == -1);
+}
@@ -2,6 +2,7 @@
 
 #include "3rd-party/catch.hpp"
 
+#include <ctime>
 #include <map>
 
 #include "matchable.h"
@@ -20,6 +21,16 @@ public:
 		: m_data(data)
 	{}
 
+	MatcherMockMatchable(
+		std::initializer_list<std::pair<const std::string, time_t>>
+		timestamps)
+		: m_timestamps(timestamps)
+	{
+		for (const auto& timestamp : m_timestamps) {
+			m_data[timestamp.first] = std::to_string(timestamp.second);
+		}
+	}
+
 	nonstd::optional<std::string> attribute_value(const std::string& attribname)
 	const override
 	{
@@ -31,8 +42,20 @@ public:
 		return nonstd::nullopt;
 	}
 
+	nonstd::optional<time_t> date_timestamp(const std::string& attribname)
+	const override
+	{
+		const auto it = m_timestamps.find(attribname);
+		if (it != m_timestamps.cend()) {
+			return it->second;
+		}
+
+		return nonstd::nullopt;
+	}
+
 private:
 	std::map<std::string, std::string> m_data;
+	std::map<std::string, time_t> m_timestamps;
 };
 
 TEST_CASE("Operator `=` checks if field has given value", "[Matcher]")
@@ -226,6 +249,32 @@ TEST_CASE("Matcher throws if expression contains undefined fields", "[Matcher]")
 	REQUIRE_THROWS_AS(m.matches(&mock), MatcherException);
 }
 
+TEST_CASE("`!` and `not` negate comparisons and groups", "[Matcher]")
+{
+	MatcherMockMatchable mock({{"unread", "yes"}, {"tags", "news podcast"}});
+	Matcher m;
+
+	REQUIRE(m.parse("!unread = \"no\""));
+	REQUIRE(m.matches(&mock));
+
+	REQUIRE(m.parse("not unread = \"yes\""));
+	REQUIRE_FALSE(m.matches(&mock));
+
+	REQUIRE(m.parse("!(tags # \"podcast\" or unread = \"no\")"));
+	REQUIRE_FALSE(m.matches(&mock));
+
+	REQUIRE(m.parse("not(tags # \"sports\") and !!unread = \"yes\""));
+	REQUIRE(m.matches(&mock));
+
+	SECTION("Negation doesn't hide undefined fields") {
+		REQUIRE(m.parse("!BBBB = \"foo\""));
+		REQUIRE_THROWS_AS(m.matches(&mock), MatcherException);
+
+		REQUIRE(m.parse("not (BBBB = \"foo\" and unread = \"yes\")"));
+		REQUIRE_THROWS_AS(m.matches(&mock), MatcherException);
+	}
+}
+
 TEST_CASE("Matcher throws if regex passed to `=~` or `!~` is invalid",
 	"[Matcher]")
 {
@@ -637,7 +686,7 @@ TEST_CASE("Operator `between` checks if field's value is in given range",
 			REQUIRE_FALSE(m.matches(&mock));
 
 			REQUIRE(m.parse("practically_zero between 0:1"));
-			REQUIRE(m.matches(&mock));
+			REQUIRE_FALSE(m.matches(&mock));
 
 			REQUIRE(m.parse("practically_zero between 1:100"));
 			REQUIRE_FALSE(m.matches(&mock));
@@ -805,26 +854,41 @@ TEST_CASE("Whitespace before and/or is not required", "[Matcher]")
 	REQUIRE(m.matches(&mock));
 }
 
-TEST_CASE("string_to_num() converts numeric prefix of the string to int",
-	"[Matcher]")
+TEST_CASE("Dates can be compared against times relative to now", "[Matcher]")
 {
-	REQUIRE(Matcher::string_to_num("7654") == 7654);
-	REQUIRE(Matcher::string_to_num("123foo") == 123);
-	REQUIRE(Matcher::string_to_num("-999999bar") == -999999);
-
-	REQUIRE(Matcher::string_to_num("-2147483648min") == -2147483648);
-	REQUIRE(Matcher::string_to_num("2147483647 is ok") == 2147483647);
-
-	// On under-/over-flow, returns min/max representable value
-	REQUIRE(Matcher::string_to_num("-2147483649 is too small for i32") ==
-		-2147483648);
-	REQUIRE(Matcher::string_to_num("2147483648 is too large for i32") ==
-		2147483647);
-}
+	const time_t now = time(nullptr);
+	const time_t three_days_ago = now - 3 * 24 * 60 * 60;
+	MatcherMockMatchable mock({{"date", three_days_ago}, {"feeddate", now + 60 * 60}});
+	Matcher m;
 
-TEST_CASE("string_to_num() returns 0 if there is no numeric prefix",
-	"[Matcher]")
-{
-	REQUIRE(Matcher::string_to_num("hello") == 0);
-	REQUIRE(Matcher::string_to_num("") == 0);
+	REQUIRE(m.parse("date > \"-7 days\""));
+	REQUIRE(m.matches(&mock));
+
+	REQUIRE(m.parse("date > \"-2 days\""));
+	REQUIRE_FALSE(m.matches(&mock));
+
+	REQUIRE(m.parse("date < \"-48 hours\" and feeddate > now"));
+	REQUIRE(m.matches(&mock));
+
+	REQUIRE(m.parse("feeddate <= \"+30 minutes\""));
+	REQUIRE_FALSE(m.matches(&mock));
+
+	SECTION("Matchables without timestamps can't be compared") {
+		MatcherMockMatchable undated({{"date", "Tue, 01 Jul 2003 10:52:37 +0200"}});
+
+		REQUIRE(m.parse("date > \"-7 days\""));
+		REQUIRE_THROWS_AS(m.matches(&undated), MatcherException);
+	}
+
+	SECTION("Only operators that order dates accept relative dates") {
+		REQUIRE_FALSE(m.parse("date =~ \"-7 days\""));
+		REQUIRE(m.get_parse_error().find("position 5") != std::string::npos);
+
+		REQUIRE_FALSE(m.parse("date # now"));
+		REQUIRE_FALSE(m.parse("date between \"-7 days\""));
+	}
+
+	SECTION("Unknown units are parse errors") {
+		REQUIRE_FALSE(m.parse("date > \"-7 fortnights\""));
+	}
 }
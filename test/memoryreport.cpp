@@ -0,0 +1,67 @@
+#include "memoryreport.h"
+
+#include "3rd-party/catch.hpp"
+#include "cache.h"
+#include "configcontainer.h"
+#include "rssitem.h"
+
+using namespace newsboat;
+
+TEST_CASE("RssItem::byte_size() counts the text the item holds",
+	"[MemoryReport]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	RssItem item(&rsscache);
+
+	const auto empty = item.byte_size();
+	REQUIRE(empty >= sizeof(RssItem));
+
+	item.set_title(std::string(1000, 't'));
+	item.set_description(std::string(5000, 'd'));
+	REQUIRE(item.byte_size() >= empty + 6000);
+}
+
+TEST_CASE("MemoryReport counts items shared between dialogs once",
+	"[MemoryReport]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	auto first = std::make_shared<RssItem>(&rsscache);
+	auto second = std::make_shared<RssItem>(&rsscache);
+
+	const std::vector<std::shared_ptr<RssItem>> feed = {first, second};
+	const std::vector<std::shared_ptr<RssItem>> search = {second};
+	REQUIRE(feed[1].get() == search[0].get());
+
+	MemoryReport report;
+	report.add_dialog("Feed");
+	for (const auto& item : feed) {
+		report.add_item(item);
+	}
+	report.add_dialog("Search");
+	for (const auto& item : search) {
+		report.add_item(item);
+	}
+
+	REQUIRE(report.distinct_items() == 2);
+
+	const auto lines = report.lines();
+	REQUIRE(lines.size() == 3);
+	REQUIRE(lines[0].find("Feed: 2 items (1 shared), ") == 0);
+	REQUIRE(lines[1].find("Search: 1 item (1 shared), ") == 0);
+	REQUIRE(lines[2].find("Total: 2 distinct items, ") == 0);
+}
+
+TEST_CASE("MemoryReport ignores missing items", "[MemoryReport]")
+{
+	MemoryReport report;
+	report.add_dialog("Article");
+	report.add_item(nullptr);
+
+	REQUIRE(report.distinct_items() == 0);
+	REQUIRE(report.lines() == std::vector<std::string>({
+		"Article: 0 items, 0 B",
+		"Total: 0 distinct items, 0 B",
+	}));
+}
@@ -0,0 +1,55 @@
+#include "networkretry.h"
+
+#include "3rd-party/catch.hpp"
+#include "rss/exception.h"
+
+using namespace newsboat;
+
+TEST_CASE("rsspp::Exception carries the curl error that caused it",
+	"[NetworkRetry]")
+{
+	REQUIRE(rsspp::Exception("could not parse buffer").curl_code() == 0);
+	// CURLE_COULDNT_RESOLVE_HOST
+	REQUIRE(rsspp::Exception("Couldn't resolve host name", 6).curl_code()
+		== 6);
+}
+
+TEST_CASE("NetworkRetry schedules a probe if enough feeds fail with network "
+	"errors", "[NetworkRetry]")
+{
+	NetworkRetry retry;
+	retry.start_cycle();
+	// CURLE_COULDNT_RESOLVE_HOST and CURLE_COULDNT_CONNECT
+	retry.feed_failed("https://a.example/feed", 6);
+	retry.feed_failed("https://b.example/feed", 7);
+	retry.feed_succeeded("https://c.example/feed");
+
+	SECTION("unless it's disabled") {
+		REQUIRE_FALSE(retry.cycle_finished(false, 50));
+		REQUIRE_FALSE(retry.seconds_until_probe().has_value());
+	}
+
+	SECTION("unless too few feeds failed") {
+		REQUIRE_FALSE(retry.cycle_finished(true, 90));
+		REQUIRE_FALSE(retry.seconds_until_probe().has_value());
+	}
+
+	SECTION("and waits before sending it") {
+		REQUIRE(retry.cycle_finished(true, 50));
+		REQUIRE(retry.seconds_until_probe().has_value());
+		REQUIRE(retry.seconds_until_probe().value() <= 30);
+		REQUIRE(retry.seconds_until_probe().value() > 0);
+		REQUIRE_FALSE(retry.probe_target("").has_value());
+	}
+}
+
+TEST_CASE("NetworkRetry doesn't count parse errors as network errors",
+	"[NetworkRetry]")
+{
+	NetworkRetry retry;
+	retry.start_cycle();
+	retry.feed_failed("https://a.example/feed", 0);
+	// CURLE_HTTP_RETURNED_ERROR
+	retry.feed_failed("https://b.example/feed", 22);
+	REQUIRE_FALSE(retry.cycle_finished(true, 0));
+}
@@ -1,6 +1,7 @@
 #include "opml.h"
 
-#include <libxml/xmlsave.h>
+#include <fstream>
+#include <unistd.h>
 
 #include "3rd-party/catch.hpp"
 #include "cache.h"
@@ -14,40 +15,20 @@ using namespace newsboat;
 TEST_CASE("opml::generate creates an XML document with feed URLs in OPML format",
 	"[Opml]")
 {
-	const auto check =
-		[]
-		(const FeedContainer& feedcontainer)
-	-> std::string {
-		xmlDocPtr opml = opml::generate(feedcontainer);
-
-		xmlBufferPtr buffer = xmlBufferCreate();
-
-		xmlSaveCtxtPtr context = xmlSaveToBuffer(buffer, nullptr, 0);
-		xmlSaveDoc(context, opml);
-		xmlSaveClose(context);
-
-		xmlFreeDoc(opml);
-
-		const std::string opmlText(
-			reinterpret_cast<char*>(buffer->content),
-			buffer->use);
-
-		xmlBufferFree(buffer);
-		buffer = nullptr;
-
-		return opmlText;
-	};
-
 	SECTION("No feeds") {
 		FeedContainer feeds;
 
 		const std::string expectedOpmlText(
-			"<?xml version=\"1.0\"?>\n"
-			"<opml version=\"1.0\">"
-			"<head><title>newsboat - Exported Feeds</title></head>"
-			"<body/></opml>\n");
+			"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"
+			"<opml version=\"1.0\">\n"
+			"  <head>\n"
+			"    <title>newsboat - Exported Feeds</title>\n"
+			"  </head>\n"
+			"  <body>\n"
+			"  </body>\n"
+			"</opml>\n");
 
-		REQUIRE(check(feeds) == expectedOpmlText);
+		REQUIRE(opml::generate(feeds) == expectedOpmlText);
 	}
 
 	SECTION("A few feeds") {
@@ -63,28 +44,56 @@ TEST_CASE("opml::generate creates an XML document with feed URLs in OPML format"
 		feeds.add_feed(std::move(feed));
 
 		feed = std::make_shared<RssFeed>(&rsscache);
-		feed->set_title("Feed 2");
+		feed->set_title("Tom & Jerry's \"Feed 2\"");
 		feed->set_link("https://example.com/feed2/");
 		feed->set_rssurl("https://example.com/feed2.xml");
 		feeds.add_feed(std::move(feed));
 
-		const std::string expectedOpmlText(
-			"<?xml version=\"1.0\"?>\n"
-			"<opml version=\"1.0\">"
-			"<head><title>newsboat - Exported Feeds</title></head>"
-			"<body>"
-			"<outline type=\"rss\" "
-			"xmlUrl=\"https://example.com/feed1.xml\" "
-			"htmlUrl=\"https://example.com/feed1/\" "
-			"title=\"Feed 1\"/>"
-			"<outline type=\"rss\" "
-			"xmlUrl=\"https://example.com/feed2.xml\" "
-			"htmlUrl=\"https://example.com/feed2/\" "
-			"title=\"Feed 2\"/>"
-			"</body>"
-			"</opml>\n");
-
-		REQUIRE(check(feeds) == expectedOpmlText);
+		SECTION("OPML 1.0") {
+			const std::string expectedOpmlText(
+				"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"
+				"<opml version=\"1.0\">\n"
+				"  <head>\n"
+				"    <title>newsboat - Exported Feeds</title>\n"
+				"  </head>\n"
+				"  <body>\n"
+				"    <outline type=\"rss\" "
+				"xmlUrl=\"https://example.com/feed1.xml\" "
+				"htmlUrl=\"https://example.com/feed1/\" "
+				"title=\"Feed 1\"/>\n"
+				"    <outline type=\"rss\" "
+				"xmlUrl=\"https://example.com/feed2.xml\" "
+				"htmlUrl=\"https://example.com/feed2/\" "
+				"title=\"Tom &amp; Jerry's &quot;Feed 2&quot;\"/>\n"
+				"  </body>\n"
+				"</opml>\n");
+
+			REQUIRE(opml::generate(feeds) == expectedOpmlText);
+		}
+
+		SECTION("OPML 2.0") {
+			const std::string expectedOpmlText(
+				"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"
+				"<opml version=\"2.0\">\n"
+				"  <head>\n"
+				"    <title>newsboat - Exported Feeds</title>\n"
+				"  </head>\n"
+				"  <body>\n"
+				"    <outline type=\"rss\" "
+				"text=\"Feed 1\" "
+				"xmlUrl=\"https://example.com/feed1.xml\" "
+				"htmlUrl=\"https://example.com/feed1/\" "
+				"title=\"Feed 1\"/>\n"
+				"    <outline type=\"rss\" "
+				"text=\"Tom &amp; Jerry's &quot;Feed 2&quot;\" "
+				"xmlUrl=\"https://example.com/feed2.xml\" "
+				"htmlUrl=\"https://example.com/feed2/\" "
+				"title=\"Tom &amp; Jerry's &quot;Feed 2&quot;\"/>\n"
+				"  </body>\n"
+				"</opml>\n");
+
+			REQUIRE(opml::generate(feeds, true) == expectedOpmlText);
+		}
 	}
 }
 
@@ -129,7 +138,7 @@ TEST_CASE("import() populates UrlReader with URLs from the OPML file", "[Opml]")
 		{"https://example.com/feed.atom", {}},
 		{"https://example.com/feed.rss09", {}},
 		{"https://blogs.example.com/~john/posts.rss", {"Blogs"}},
-		{"https://blogs.example.com/~mike/.rss", {"Blogs/friends"}},
+		{"https://blogs.example.com/~mike/.rss", {"Blogs", "friends"}},
 		{"https://fred.example.com/writing/index.php?type=rss", {"eloquent"}},
 	};
 
@@ -276,6 +285,46 @@ TEST_CASE("import() skips URLs that are already present in UrlReader",
 	}
 }
 
+TEST_CASE("import() returns false and leaves the urls file alone "
+	"if the file isn't OPML",
+	"[Opml]")
+{
+	TestHelpers::TempFile urlsFile;
+
+	FileUrlReader urlcfg(urlsFile.get_path());
+	urlcfg.reload();
+
+	REQUIRE_FALSE(opml::import("data/test-urls.txt", urlcfg));
+	REQUIRE(urlcfg.get_urls().empty());
+	REQUIRE(0 != ::access(urlsFile.get_path().c_str(), F_OK));
+}
+
+TEST_CASE("import() leaves the urls file alone if it can't be parsed",
+	"[Opml]")
+{
+	TestHelpers::TempFile urlsFile;
+	const std::string original =
+		"http://test1.url.cc/feed.xml\n"
+		"http://anotherfeed.com/ \"local news\n";
+	{
+		std::ofstream out(urlsFile.get_path());
+		out << original;
+	}
+
+	FileUrlReader urlcfg(urlsFile.get_path());
+	urlcfg.reload();
+	REQUIRE(urlcfg.get_error().has_value());
+
+	REQUIRE_FALSE(opml::import(
+			"file://" + utils::getcwd() + "/data/test-urls+.opml",
+			urlcfg));
+
+	std::ifstream in(urlsFile.get_path());
+	const std::string contents((std::istreambuf_iterator<char>(in)),
+		std::istreambuf_iterator<char>());
+	REQUIRE(contents == original);
+}
+
 // falls back to "url" if "xmlUrl" is absent
 
 // skips an entry if xmlUrl/url is absent
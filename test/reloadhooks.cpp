@@ -0,0 +1,70 @@
+#include "reloadhooks.h"
+
+#include "3rd-party/catch.hpp"
+
+#include "confighandlerexception.h"
+
+using namespace newsboat;
+
+namespace {
+
+const std::string URL = "https://example.com/feed.xml";
+
+} // namespace
+
+TEST_CASE("pre-reload-command and post-reload-command require a URL and "
+	"a command",
+	"[ReloadHooks]")
+{
+	ReloadHooks hooks;
+	REQUIRE_THROWS_AS(hooks.handle_action("pre-reload-command", {URL}),
+		ConfigHandlerException);
+	REQUIRE_THROWS_AS(hooks.handle_action("post-reload-command", {}),
+		ConfigHandlerException);
+	REQUIRE_NOTHROW(hooks.handle_action("pre-reload-command", {URL, "true"}));
+}
+
+TEST_CASE("Failed pre-reload hook returns an error message", "[ReloadHooks]")
+{
+	ReloadHooks hooks;
+	hooks.handle_action("pre-reload-command", {URL, "echo 'token expired' >&2; exit 2"});
+	hooks.start_cycle();
+
+	const auto error = hooks.run_pre_reload(URL);
+	REQUIRE(error.has_value());
+	REQUIRE(error.value() == "exited with status 2: token expired");
+
+	REQUIRE_FALSE(hooks.run_pre_reload("https://example.com/other.xml"));
+}
+
+TEST_CASE("Post-reload hook gets outcome and number of new items as arguments",
+	"[ReloadHooks]")
+{
+	ReloadHooks hooks;
+	hooks.handle_action("post-reload-command",
+		{URL, "test \"$1\" = ok && test \"$2\" = 3"});
+	hooks.start_cycle();
+
+	REQUIRE_FALSE(hooks.run_post_reload(URL, ReloadOutcome::FETCHED, 3));
+	REQUIRE(hooks.report() == std::vector<std::string>({
+		"post-reload-command for " + URL + ": ok"}));
+
+	hooks.start_cycle();
+	const auto error = hooks.run_post_reload(URL, ReloadOutcome::FAILED, 0);
+	REQUIRE(error.has_value());
+	REQUIRE(error.value() == "exited with status 1");
+}
+
+TEST_CASE("ReloadHooks::dump_config() writes one line per hook",
+	"[ReloadHooks]")
+{
+	ReloadHooks hooks;
+	hooks.handle_action("pre-reload-command", {URL, "refresh-token"});
+	hooks.handle_action("post-reload-command", {URL, "notify"});
+
+	std::vector<std::string> config;
+	hooks.dump_config(config);
+	REQUIRE(config == std::vector<std::string>({
+		"post-reload-command \"" + URL + "\" \"notify\"",
+		"pre-reload-command \"" + URL + "\" \"refresh-token\""}));
+}
@@ -0,0 +1,61 @@
+#include "reloadtag.h"
+
+#include <memory>
+
+#include "3rd-party/catch.hpp"
+#include "cache.h"
+#include "configcontainer.h"
+#include "rssfeed.h"
+
+using namespace newsboat;
+
+namespace {
+
+std::shared_ptr<RssFeed> feed_with_tags(Cache* rsscache,
+	const std::string& url,
+	const std::vector<std::string>& tags)
+{
+	const auto feed = std::make_shared<RssFeed>(rsscache);
+	feed->set_rssurl(url);
+	feed->set_tags(tags);
+	return feed;
+}
+
+} // anonymous namespace
+
+TEST_CASE("select() picks the feeds that carry the tag", "[TagScope]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	const std::vector<std::shared_ptr<RssFeed>> feeds = {
+		feed_with_tags(&rsscache, "https://example.com/a", {"news", "daily"}),
+		feed_with_tags(&rsscache, "https://example.com/b", {"tech"}),
+		feed_with_tags(&rsscache, "query:News:tags # \"news\"", {"news"}),
+		feed_with_tags(&rsscache, "https://example.com/c", {"tech", "news"}),
+	};
+
+	std::string error;
+	const auto scope = TagScope::select("news", feeds, error);
+
+	REQUIRE(scope.has_value());
+	REQUIRE(scope->indexes == std::vector<int>({0, 3}));
+	REQUIRE(scope->summary == "Reloading 2 feeds tagged `news' (out of 4)");
+	REQUIRE(error.empty());
+}
+
+TEST_CASE("select() suggests similar tags if no feed carries the tag",
+	"[TagScope]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	const std::vector<std::shared_ptr<RssFeed>> feeds = {
+		feed_with_tags(&rsscache, "https://example.com/a", {"news"}),
+		feed_with_tags(&rsscache, "https://example.com/b", {"tech"}),
+	};
+
+	std::string error;
+	const auto scope = TagScope::select("nwes", feeds, error);
+
+	REQUIRE_FALSE(scope.has_value());
+	REQUIRE(error == "No feed is tagged `nwes'. Did you mean: news?");
+}
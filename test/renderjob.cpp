@@ -0,0 +1,106 @@
+#include "renderjob.h"
+
+#include <algorithm>
+
+#include "3rd-party/catch.hpp"
+
+using namespace newsboat;
+
+namespace {
+
+// Big enough to take several units of work
+std::string long_article()
+{
+	std::string result;
+	for (int i = 0; i < 1000; ++i) {
+		result += "<p>Paragraph " + std::to_string(i) + "</p>";
+	}
+	result += "<a href='/more'>more</a>";
+	return result;
+}
+
+const std::string url = "https://example.com/article";
+
+} // namespace
+
+TEST_CASE("RenderJob shows the first screen before rendering the rest",
+	"[RenderJob]")
+{
+	RenderJob job(long_article(), url, {}, 10);
+
+	std::vector<RenderJob::Step> steps;
+	std::size_t lines_on_first_screen = 0;
+	while (job.should_continue()) {
+		const auto step = job.render_unit();
+		steps.push_back(step);
+		if (step == RenderJob::Step::SHOW_FIRST_SCREEN) {
+			lines_on_first_screen = job.lines().size();
+		}
+	}
+
+	REQUIRE(steps.size() > 2);
+	REQUIRE(steps.front() == RenderJob::Step::SHOW_FIRST_SCREEN);
+	REQUIRE(steps.back() == RenderJob::Step::FINISHED);
+	REQUIRE(std::count(steps.begin(), steps.end(),
+			RenderJob::Step::SHOW_FIRST_SCREEN) == 1);
+
+	REQUIRE(lines_on_first_screen >= 10);
+	REQUIRE(lines_on_first_screen < job.lines().size());
+	REQUIRE(job.links().size() == 1);
+}
+
+TEST_CASE("RenderJob for a short article is shown once it's finished",
+	"[RenderJob]")
+{
+	RenderJob job("<p>Hello, world!</p>", url, {}, 10);
+	REQUIRE(job.render_unit() == RenderJob::Step::FINISHED);
+	REQUIRE_FALSE(job.should_continue());
+}
+
+TEST_CASE("Cancelled RenderJob leaves no cache entry", "[RenderJob]")
+{
+	RenderCache cache(10, 1024 * 1024);
+	RenderJob job(long_article(), url, {}, 10);
+
+	REQUIRE(job.render_unit() == RenderJob::Step::SHOW_FIRST_SCREEN);
+	job.cancel();
+	REQUIRE_FALSE(job.should_continue());
+	REQUIRE(job.render_unit() == RenderJob::Step::CANCELLED);
+
+	REQUIRE_FALSE(cache.store("guid", job));
+
+	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
+	REQUIRE_FALSE(cache.get("guid", lines, links));
+}
+
+TEST_CASE("RenderCache keeps articles that were rendered completely",
+	"[RenderCache]")
+{
+	RenderCache cache(10, 1024 * 1024);
+	const std::vector<LinkPair> enclosure = {
+		LinkPair("https://example.com/podcast.mp3", LinkType::AUDIO)
+	};
+	RenderJob job(long_article(), url, enclosure, 10);
+
+	REQUIRE(job.render_unit() == RenderJob::Step::SHOW_FIRST_SCREEN);
+	// Not finished yet
+	REQUIRE_FALSE(cache.store("guid", job));
+
+	while (job.should_continue()) {
+		job.render_unit();
+	}
+	REQUIRE(cache.store("guid", job));
+
+	std::vector<std::pair<LineType, std::string>> lines;
+	std::vector<LinkPair> links;
+	REQUIRE(cache.get("guid", lines, links));
+	REQUIRE(lines == job.lines());
+	REQUIRE(links == job.links());
+	REQUIRE(links.size() == 2);
+	REQUIRE(links[0].second == LinkType::AUDIO);
+	REQUIRE(links[1] == LinkPair("https://example.com/more", LinkType::HREF));
+
+	cache.clear();
+	REQUIRE_FALSE(cache.get("guid", lines, links));
+}
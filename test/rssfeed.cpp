@@ -208,6 +208,32 @@ TEST_CASE("RssFeed::sort() correctly sorts articles", "[RssFeed]")
 		REQUIRE(articles[3]->pubDate_timestamp() == 23);
 		REQUIRE(articles[4]->pubDate_timestamp() == 7);
 	}
+
+	SECTION("words") {
+		auto articles = f.items();
+		articles[0]->set_word_count(800, 4);
+		articles[1]->set_word_count(150, 1);
+		articles[2]->set_word_count(2400, 12);
+		articles[3]->set_word_count(0, 0);
+		articles[4]->set_word_count(450, 3);
+
+		ArticleSortStrategy ss;
+		ss.sm = ArtSortMethod::WORDS;
+		ss.sd = SortDirection::ASC;
+		f.sort(ss);
+		articles = f.items();
+		REQUIRE(articles[0]->word_count() == 0);
+		REQUIRE(articles[1]->word_count() == 150);
+		REQUIRE(articles[2]->word_count() == 450);
+		REQUIRE(articles[3]->word_count() == 800);
+		REQUIRE(articles[4]->word_count() == 2400);
+
+		ss.sd = SortDirection::DESC;
+		f.sort(ss);
+		articles = f.items();
+		REQUIRE(articles[0]->word_count() == 2400);
+		REQUIRE(articles[4]->word_count() == 0);
+	}
 }
 
 TEST_CASE("RssFeed::unread_item_count() returns number of unread articles",
@@ -240,6 +266,25 @@ TEST_CASE("RssFeed::unread_item_count() returns number of unread articles",
 	REQUIRE(f.unread_item_count() == 0);
 }
 
+TEST_CASE("Placeholder feeds report the counts they were given instead of "
+	"counting their articles",
+	"[RssFeed]")
+{
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	RssFeed f(&rsscache);
+	f.set_rssurl("https://example.com/feed.xml");
+	REQUIRE_FALSE(f.is_placeholder());
+
+	f.set_placeholder_counts(3, 10);
+	REQUIRE(f.is_placeholder());
+	REQUIRE(f.items().empty());
+	REQUIRE(f.unread_item_count() == 3);
+	REQUIRE(f.total_item_count() == 10);
+	REQUIRE(f.attribute_value("unread_count") == "3");
+	REQUIRE(f.attribute_value("total_count") == "10");
+}
+
 TEST_CASE("RssFeed::matches_tag() returns true if article has a specified tag",
 	"[RssFeed]")
 {
@@ -179,6 +179,27 @@ TEST_CASE("RssItem contains a number of matchable attributes", "[RssItem]")
 		}
 	}
 
+	SECTION("updated") {
+		const auto attr = "updated";
+
+		SECTION("for articles that weren't edited, attribute equals \"no\"") {
+			REQUIRE(item.attribute_value(attr) == "no");
+		}
+
+		SECTION("for edited articles, attribute equals \"yes\"") {
+			item.set_updated(true);
+
+			REQUIRE(item.attribute_value(attr) == "yes");
+		}
+
+		SECTION("marking the article read resets the attribute") {
+			item.set_updated(true);
+			item.set_unread(false);
+
+			REQUIRE(item.attribute_value(attr) == "no");
+		}
+	}
+
 	SECTION("enclosure_url") {
 		const auto attr = "enclosure_url";
 
@@ -206,6 +227,27 @@ TEST_CASE("RssItem contains a number of matchable attributes", "[RssItem]")
 		REQUIRE(item.attribute_value(attr) == flags);
 	}
 
+	SECTION("note") {
+		const auto attr = "note";
+
+		REQUIRE(item.attribute_value(attr) == "");
+
+		const auto note = "Use this in the weekly report";
+		item.set_note(note);
+
+		REQUIRE(item.attribute_value(attr) == note);
+	}
+
+	SECTION("words and reading_minutes") {
+		REQUIRE(item.attribute_value("words") == "0");
+		REQUIRE(item.attribute_value("reading_minutes") == "0");
+
+		item.set_word_count(1234, 7);
+
+		REQUIRE(item.attribute_value("words") == "1234");
+		REQUIRE(item.attribute_value("reading_minutes") == "7");
+	}
+
 	SECTION("age, the number of days since publication") {
 		const auto attr = "age";
 
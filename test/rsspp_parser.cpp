@@ -1,9 +1,111 @@
 #include "rss/parser.h"
 
+#include <arpa/inet.h>
+#include <chrono>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <thread>
+#include <unistd.h>
+
 #include "3rd-party/catch.hpp"
 #include "rss/exception.h"
+#include "strprintf.h"
 #include "test-helpers/exceptionwithmsg.h"
 
+using namespace newsboat;
+
+namespace {
+
+const std::string fake_feed =
+	"<?xml version=\"1.0\"?>"
+	"<rss version=\"2.0\"><channel><title>Fake</title></channel></rss>";
+
+enum class Serving {
+	/// The whole response at once.
+	NORMALLY,
+	/// The headers, then five bytes a second.
+	SLOWLY,
+	/// A Content-Length twice the size of the body.
+	TRUNCATED,
+};
+
+/// Serves a single request on a local port, in the way it's told to.
+class FakeServer {
+public:
+	explicit FakeServer(Serving serving)
+	{
+		listener = socket(AF_INET, SOCK_STREAM, 0);
+		sockaddr_in address{};
+		address.sin_family = AF_INET;
+		address.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+		address.sin_port = 0;
+		REQUIRE(bind(listener, reinterpret_cast<sockaddr*>(&address),
+				sizeof(address)) == 0);
+		REQUIRE(listen(listener, 1) == 0);
+		socklen_t length = sizeof(address);
+		getsockname(listener, reinterpret_cast<sockaddr*>(&address), &length);
+		port = ntohs(address.sin_port);
+		server = std::thread([this, serving]() {
+			serve(serving);
+		});
+	}
+
+	~FakeServer()
+	{
+		server.join();
+		close(listener);
+	}
+
+	std::string url() const
+	{
+		return strprintf::fmt("http://127.0.0.1:%u/feed.xml", port);
+	}
+
+private:
+	void serve(Serving serving)
+	{
+		const int client = accept(listener, nullptr, nullptr);
+		if (client < 0) {
+			return;
+		}
+		char request[4096];
+		recv(client, request, sizeof(request), 0);
+
+		const auto length = (serving == Serving::TRUNCATED)
+			? 2 * fake_feed.length()
+			: fake_feed.length();
+		send_all(client, strprintf::fmt("HTTP/1.1 200 OK\r\n"
+				"Content-Type: application/rss+xml\r\n"
+				"Content-Length: %u\r\n\r\n",
+				static_cast<unsigned int>(length)));
+		if (serving == Serving::SLOWLY) {
+			for (const char c : fake_feed) {
+				if (!send_all(client, std::string(1, c))) {
+					break;
+				}
+				std::this_thread::sleep_for(std::chrono::milliseconds(200));
+			}
+		} else {
+			send_all(client, fake_feed);
+		}
+		close(client);
+	}
+
+	/// Returns false once the client went away.
+	static bool send_all(int client, const std::string& data)
+	{
+		// MSG_NOSIGNAL, because the client hanging up mustn't kill the tests
+		return send(client, data.c_str(), data.length(), MSG_NOSIGNAL)
+			== static_cast<ssize_t>(data.length());
+	}
+
+	int listener;
+	unsigned int port;
+	std::thread server;
+};
+
+} // namespace
+
 TEST_CASE("Throws exception if file doesn't exist", "[rsspp::Parser]")
 {
 	using TestHelpers::ExceptionWithMsg;
@@ -153,6 +255,21 @@ TEST_CASE("Extracts data fro RSS 2.0", "[rsspp::Parser]")
 	REQUIRE_FALSE(f.items[0].guid_isPermaLink);
 }
 
+TEST_CASE("Extracts TTL, skipHours and skipDays from RSS 2.0",
+	"[rsspp::Parser]")
+{
+	rsspp::Parser p;
+	rsspp::Feed f;
+
+	REQUIRE_NOTHROW(f = p.parse_file("data/rss20_schedule.xml"));
+
+	REQUIRE(f.ttl == "60");
+	REQUIRE(f.skip_hours == std::vector<std::string>({"0", "1", "23"}));
+	REQUIRE(f.skip_days == std::vector<std::string>({"Saturday", "Sunday"}));
+
+	REQUIRE(f.items.size() == 1u);
+}
+
 TEST_CASE("Extracts data from RSS 1.0", "[rsspp::Parser]")
 {
 	rsspp::Parser p;
@@ -285,3 +402,52 @@ TEST_CASE("Extracts data from media:... tags in  RSS 2.0 feeds",
 	REQUIRE(f.items[1].description_type == "html");
 	REQUIRE(f.items[1].link == "http://example.com/player.html");
 }
+
+TEST_CASE("parse_url() enforces the download limits", "[rsspp::Parser]")
+{
+	using TestHelpers::ExceptionWithMsg;
+
+	SECTION("A normal download is unaffected") {
+		FakeServer server(Serving::NORMALLY);
+		rsspp::Parser p(5, "", "", "", CURLPROXY_HTTP, true, 10, 1);
+		rsspp::Feed f;
+
+		REQUIRE_NOTHROW(f = p.parse_url(server.url()));
+		REQUIRE(f.title == "Fake");
+	}
+
+	SECTION("A download that takes too long is aborted") {
+		FakeServer server(Serving::SLOWLY);
+		// No low-speed limit, so only the deadline can stop this
+		rsspp::Parser p(1, "", "", "", CURLPROXY_HTTP, true, 0, 0);
+
+		REQUIRE_THROWS_MATCHES(p.parse_url(server.url()),
+			rsspp::Exception,
+			ExceptionWithMsg<rsspp::Exception>(
+				"Download didn't finish within 1 second (download-timeout)"));
+	}
+
+	SECTION("A download that stays too slow is aborted") {
+		FakeServer server(Serving::SLOWLY);
+		rsspp::Parser p(60, "", "", "", CURLPROXY_HTTP, true, 100, 1);
+
+		REQUIRE_THROWS_MATCHES(p.parse_url(server.url()),
+			rsspp::Exception,
+			ExceptionWithMsg<rsspp::Exception>(
+				"Download was slower than 100 bytes per second for 1 "
+				"second (download-low-speed-limit)"));
+	}
+
+	SECTION("A truncated download is an error rather than a short feed") {
+		FakeServer server(Serving::TRUNCATED);
+		rsspp::Parser p(5, "", "", "", CURLPROXY_HTTP, true, 10, 1);
+
+		const auto expected = strprintf::fmt(
+				"Download was cut short: got %u of %u bytes",
+				static_cast<unsigned int>(fake_feed.length()),
+				static_cast<unsigned int>(2 * fake_feed.length()));
+		REQUIRE_THROWS_MATCHES(p.parse_url(server.url()),
+			rsspp::Exception,
+			ExceptionWithMsg<rsspp::Exception>(expected));
+	}
+}
@@ -0,0 +1,55 @@
+#include "termcaps.h"
+
+#include "3rd-party/catch.hpp"
+
+using namespace newsboat;
+
+TEST_CASE("degrade_style() maps colors down to what the terminal supports",
+	"[termcaps]")
+{
+	const std::string style = "fg=color196,bg=color21,attr=underline";
+
+	SECTION("Styles are unchanged until the terminal is known") {
+		REQUIRE(termcaps::degrade_style(style) == style);
+	}
+
+	SECTION("8 colors") {
+		termcaps::set_color_support(ColorSupport::COLORS_8);
+		REQUIRE(termcaps::degrade_style(style) ==
+			"fg=red,bg=blue,attr=underline,attr=bold");
+	}
+
+	SECTION("No colors") {
+		termcaps::set_color_support(ColorSupport::NONE);
+		REQUIRE(termcaps::degrade_style(style) == "attr=underline");
+	}
+
+	SECTION("256 colors") {
+		termcaps::set_color_support(ColorSupport::COLORS_256);
+		REQUIRE(termcaps::degrade_style(style) == style);
+	}
+
+	termcaps::set_color_support(ColorSupport::TRUECOLOR);
+}
+
+TEST_CASE("title_format() falls back to a short template on narrow titles",
+	"[termcaps]")
+{
+	const std::string configured = "%N %V - Search results (%u unread, %t total)";
+	const std::string option = "searchresult-title-format";
+
+	REQUIRE(termcaps::title_format(option, configured, 80) == configured);
+	REQUIRE(termcaps::title_format(option, configured, 30) ==
+		"Search results (%u/%t)");
+	REQUIRE(termcaps::title_format("help-title-format", "%N %V - Help", 30) ==
+		"%N %V - Help");
+}
+
+TEST_CASE("preview_pane_fits() requires room for both the list and the preview",
+	"[termcaps]")
+{
+	REQUIRE(termcaps::preview_pane_fits(80, 24, 50, false));
+	REQUIRE_FALSE(termcaps::preview_pane_fits(80, 9, 50, false));
+	REQUIRE(termcaps::preview_pane_fits(80, 24, 50, true));
+	REQUIRE_FALSE(termcaps::preview_pane_fits(50, 24, 50, true));
+}
@@ -164,6 +164,42 @@ TEST_CASE("wrappable sequences longer then format width are forced-wrapped",
 	REQUIRE(fmt.format_text_plain(5, 10) == expected);
 }
 
+TEST_CASE("Words are broken at soft hyphens, which are otherwise invisible",
+	"[TextFormatter]")
+{
+	TextFormatter fmt;
+	fmt.add_line(LineType::wrappable, "a hyphen\xC2\xAD" "ation test");
+	fmt.add_line(LineType::wrappable, "soft\xC2\xAD" "ware");
+
+	const std::string expected =
+		"a hyphen-\n"
+		"ation test\n"
+		"software\n";
+	REQUIRE(fmt.format_text_plain(10) == expected);
+}
+
+TEST_CASE("Words are hyphenated with patterns if hyphenation is enabled",
+	"[TextFormatter]")
+{
+	TextFormatter fmt;
+	fmt.add_line(LineType::wrappable, "some information");
+
+	SECTION("hyphenation disabled") {
+		const std::string expected =
+			"some \n"
+			"information\n";
+		REQUIRE(fmt.format_text_plain(12) == expected);
+	}
+
+	SECTION("hyphenation enabled") {
+		fmt.set_hyphenation(true);
+		const std::string expected =
+			"some infor-\n"
+			"mation\n";
+		REQUIRE(fmt.format_text_plain(12) == expected);
+	}
+}
+
 TEST_CASE("Lines marked as non-wrappable are always returned verbatim",
 	"[TextFormatter]")
 {
@@ -0,0 +1,221 @@
+#include "view.h"
+
+#include <chrono>
+#include <fstream>
+#include <thread>
+
+#include "3rd-party/catch.hpp"
+#include "cache.h"
+#include "configpaths.h"
+#include "itemlistformaction.h"
+#include "keymap.h"
+#include "rssfeed.h"
+#include "test-helpers/tempfile.h"
+
+using namespace newsboat;
+
+namespace {
+
+std::shared_ptr<RssFeed> make_feed(Cache* rsscache)
+{
+	auto feed = std::make_shared<RssFeed>(rsscache);
+	feed->set_title("Planet Rust");
+	auto item = std::make_shared<RssItem>(rsscache);
+	item->set_guid("guid");
+	item->set_title("Article Title");
+	feed->add_item(item);
+	return feed;
+}
+
+void run_macro(newsboat::View& v, KeyMap& keys, const std::string& macro)
+{
+	for (auto command : keys.parse_operation_sequence(macro)) {
+		REQUIRE(v.get_current_formaction() != nullptr);
+		v.get_current_formaction()->process_op(command.op, true,
+			&command.args);
+	}
+}
+
+std::shared_ptr<RssFeed> make_feed_with_unread_items(Cache* rsscache)
+{
+	auto feed = std::make_shared<RssFeed>(rsscache);
+	for (const char* name : {
+			"first", "second", "third"
+		}) {
+		auto item = std::make_shared<RssItem>(rsscache);
+		item->set_guid(name);
+		item->set_link(std::string("https://example.com/") + name);
+		item->set_unread_nowrite(true);
+		feed->add_item(item);
+	}
+	return feed;
+}
+
+/// Browser that writes the URLs it's asked to open into \a path, one per
+/// line, and exits with \a exit_code.
+std::string recording_browser(const std::string& path, int exit_code)
+{
+	return "echo %u >> '" + path + "'; exit " + std::to_string(exit_code);
+}
+
+std::vector<std::string> opened_urls(const std::string& path)
+{
+	std::vector<std::string> urls;
+	// The browser might still be running in the background
+	for (int i = 0; i < 50 && urls.empty(); ++i) {
+		std::this_thread::sleep_for(std::chrono::milliseconds(100));
+		std::ifstream file(path);
+		std::string url;
+		while (std::getline(file, url)) {
+			urls.push_back(url);
+		}
+	}
+	return urls;
+}
+
+} // namespace
+
+TEST_CASE("pop-to-feedlist in a macro closes the dialogs from the newest one "
+	"back", "[View]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	KeyMap keys(KM_NEWSBOAT);
+	v.set_keymap(&keys);
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	auto feed = make_feed(&rsscache);
+
+	// Three levels deep: article list, article, URLs
+	auto itemlist = v.push_itemlist(feed);
+	REQUIRE(itemlist != nullptr);
+	v.push_itemview(feed, "guid");
+	auto itemview = v.get_current_formaction();
+	REQUIRE(itemview->id() == "article");
+	std::vector<LinkPair> links;
+	v.push_urlview(links, feed);
+	auto urlview = v.get_current_formaction();
+	REQUIRE(urlview->id() == "urlview");
+	REQUIRE(v.formaction_stack_size() == 3);
+
+	REQUIRE(v.dialog_breadcrumb(itemlist.get()) == "Planet Rust");
+	REQUIRE(v.dialog_breadcrumb(itemview.get()) == "Planet Rust > Article");
+	REQUIRE(v.dialog_breadcrumb(urlview.get()) ==
+		"Planet Rust > Article > URLs");
+
+	run_macro(v, keys, "pop-to-feedlist");
+
+	// There's no feed list outside of View::run(), so everything got closed
+	REQUIRE(v.formaction_stack_size() == 0);
+	REQUIRE(v.dialog_breadcrumb(urlview.get()) == "");
+}
+
+TEST_CASE("pop-to-feedlist leaves dialogs opened from elsewhere open", "[View]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	KeyMap keys(KM_NEWSBOAT);
+	v.set_keymap(&keys);
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+
+	auto feed = make_feed(&rsscache);
+
+	auto first = v.push_itemlist(feed);
+	v.push_help();
+	// Open the article from the article list, not from the help dialog
+	v.goto_prev_dialog();
+	REQUIRE(v.get_current_formaction() == first);
+	v.push_itemview(feed, "guid");
+	REQUIRE(v.formaction_stack_size() == 3);
+
+	SECTION("pop-to-feedlist") {
+		run_macro(v, keys, "pop-to-feedlist");
+
+		REQUIRE(v.formaction_stack_size() == 1);
+		REQUIRE(v.get_current_formaction()->id() == "help");
+	}
+
+	SECTION("close-all-dialogs") {
+		run_macro(v, keys, "close-all-dialogs");
+
+		REQUIRE(v.formaction_stack_size() == 0);
+	}
+}
+
+TEST_CASE("open-in-browser fails if the browser exits with an error, so that "
+	"`&&` and `||` can branch on it", "[View]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	KeyMap keys(KM_NEWSBOAT);
+	v.set_keymap(&keys);
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+	TestHelpers::TempFile urls;
+
+	auto itemlist = v.push_itemlist(make_feed_with_unread_items(&rsscache));
+	REQUIRE(itemlist != nullptr);
+
+	SECTION("a failing browser fails the operation") {
+		cfg.set_configvalue("browser", recording_browser(urls.get_path(), 3));
+		REQUIRE_FALSE(itemlist->process_op(OP_OPENINBROWSER, true));
+		REQUIRE(opened_urls(urls.get_path()) ==
+			std::vector<std::string>({"https://example.com/first"}));
+	}
+
+	SECTION("a successful browser doesn't") {
+		cfg.set_configvalue("browser", recording_browser(urls.get_path(), 0));
+		REQUIRE(itemlist->process_op(OP_OPENINBROWSER, true));
+	}
+
+	SECTION("open-all-unread-in-browser stops at the first failure") {
+		cfg.set_configvalue("browser", recording_browser(urls.get_path(), 1));
+		REQUIRE_FALSE(itemlist->process_op(OP_OPENALLUNREADINBROWSER, true));
+		REQUIRE(opened_urls(urls.get_path()) ==
+			std::vector<std::string>({"https://example.com/first"}));
+	}
+
+	SECTION("open-all-unread-in-browser opens every unread item otherwise") {
+		cfg.set_configvalue("browser", recording_browser(urls.get_path(), 0));
+		REQUIRE(itemlist->process_op(OP_OPENALLUNREADINBROWSER, true));
+		REQUIRE(opened_urls(urls.get_path()).size() == 3);
+	}
+}
+
+TEST_CASE("open-in-browser-noninteractively leaves the dialogs alone and "
+	"only fails if the browser can't be started", "[View]")
+{
+	ConfigPaths paths;
+	Controller c(paths);
+	newsboat::View v(&c);
+	ConfigContainer cfg;
+	Cache rsscache(":memory:", &cfg);
+	KeyMap keys(KM_NEWSBOAT);
+	v.set_keymap(&keys);
+	v.set_config_container(&cfg);
+	c.set_view(&v);
+	TestHelpers::TempFile urls;
+
+	auto itemlist = v.push_itemlist(make_feed_with_unread_items(&rsscache));
+	REQUIRE(itemlist != nullptr);
+	cfg.set_configvalue("browser", recording_browser(urls.get_path(), 5));
+
+	REQUIRE(itemlist->process_op(OP_OPENINBROWSER_NONINTERACTIVELY, true));
+	// The interactive browser puts a placeholder on top of the dialogs while
+	// it runs; this one didn't need to
+	REQUIRE(v.formaction_stack_size() == 1);
+	REQUIRE(v.get_current_formaction() == itemlist);
+	REQUIRE(opened_urls(urls.get_path()) ==
+		std::vector<std::string>({"https://example.com/first"}));
+}